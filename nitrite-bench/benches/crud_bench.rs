@@ -2,8 +2,12 @@
 
 use std::hint::black_box;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use nitrite::filter::field;
+use nitrite::common::{from_value, Convertible, Value};
+use nitrite::doc;
+use nitrite::errors::NitriteResult;
+use nitrite::filter::{all, field};
 use nitrite::index::non_unique_index;
+use nitrite_derive::Convertible;
 use nitrite_bench::data_gen::{generate_simple_docs, generate_single_doc};
 use nitrite_bench::stores::{create_fjall_db, create_inmemory_db};
 
@@ -270,6 +274,67 @@ fn bench_delete(c: &mut Criterion) {
     group.finish();
 }
 
+#[derive(Convertible, Default)]
+struct Order {
+    id: i64,
+    items: Vec<String>,
+    quantities: Vec<i64>,
+    history: Vec<Vec<i64>>,
+}
+
+/// The conversion before array buffers were pooled: every field is a fresh copy.
+fn order_from_copies(value: &Value) -> NitriteResult<Order> {
+    let doc = value.as_document().unwrap();
+    Ok(Order {
+        id: from_value::<i64>(&doc.get("id")?)?,
+        items: from_value::<Vec<String>>(&doc.get("items")?)?,
+        quantities: from_value::<Vec<i64>>(&doc.get("quantities")?)?,
+        history: from_value::<Vec<Vec<i64>>>(&doc.get("history")?)?,
+    })
+}
+
+/// Converting a cursor of documents with array fields into objects: the derived conversion
+/// reads arrays into buffers pooled across the documents of the cursor, the copying one
+/// allocates every buffer anew.
+fn bench_convert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("CRUD/Convert");
+
+    for size in [1_000, 10_000].iter() {
+        let ctx = create_inmemory_db().unwrap();
+        let collection = ctx.db().collection("orders").unwrap();
+        let docs = (0..*size as i64)
+            .map(|i| {
+                doc! {
+                    id: (i),
+                    items: ["book", "pen", (format!("item{}", i))],
+                    quantities: [(1i64), (2i64), (i % 7)],
+                    history: [[(1i64), (2i64)], [(3i64), (i % 5)]]
+                }
+            })
+            .collect();
+        collection.insert_many(docs).unwrap();
+        group.throughput(Throughput::Elements(*size as u64));
+
+        group.bench_function(BenchmarkId::new("pooled", size), |b| {
+            b.iter(|| {
+                let cursor = collection.find(all()).unwrap();
+                let orders = cursor.map(|doc| Order::from_value(&Value::Document(doc.unwrap())).unwrap());
+                black_box(orders.map(|order| order.items.len()).sum::<usize>())
+            });
+        });
+
+        group.bench_function(BenchmarkId::new("copied", size), |b| {
+            b.iter(|| {
+                let cursor = collection.find(all()).unwrap();
+                let orders = cursor.map(|doc| order_from_copies(&Value::Document(doc.unwrap())).unwrap());
+                black_box(orders.map(|order| order.items.len()).sum::<usize>())
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_insert_single,
@@ -277,6 +342,7 @@ criterion_group!(
     bench_read,
     bench_update,
     bench_delete,
-    bench_count
+    bench_count,
+    bench_convert
 );
 criterion_main!(benches);
@@ -1,17 +1,43 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::quote;
-use syn::{DataEnum, DataStruct, DeriveInput, Field, LitStr, Result, Type};
+use syn::{DataEnum, DataStruct, DeriveInput, Field, LitInt, LitStr, Path, Result, Type};
 
 pub(crate) fn generate_convertible_for_struct(
     ast: &DeriveInput,
     data: &DataStruct,
 ) -> Result<TokenStream> {
     let mut ignored_fields: Vec<String> = vec![];
+    let mut version: Option<u32> = None;
+    let mut upgrade: Option<Path> = None;
 
     // Check if the struct has a `converter` attribute - use if-let for clarity
     for attr in &ast.attrs {
-        if attr.path().is_ident("converter") {
+        if attr.path().is_ident("convertible") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("version") {
+                    let value: LitInt = meta.value()?.parse()?;
+                    let number = value.base10_parse::<u32>()?;
+                    if number == 0 {
+                        return Err(meta.error("version must be at least 1"));
+                    }
+                    version = Some(number);
+                    Ok(())
+                } else if meta.path.is_ident("upgrade") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    upgrade = Some(value.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `version` or `upgrade`"))
+                }
+            })?;
+            if upgrade.is_some() && version.is_none() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "an upgrade hook requires a version",
+                ));
+            }
+        } else if attr.path().is_ident("converter") {
             attr.parse_nested_meta(|meta| {
                 if meta.path.is_ident("ignored") {
                     let value = meta.value()?;
@@ -70,11 +96,41 @@ pub(crate) fn generate_convertible_for_struct(
             if ignored_fields.contains(name) {
                 quote! { #ident: Default::default() }
             } else {
-                quote! { #ident: nitrite::common::from_value::<#ty>(&doc.get(#name)?)? }
+                // the field is read into pooled buffers, handed back once converted
+                quote! {
+                    #ident: {
+                        let value = nitrite::common::ValuePool::field(&doc, #name)?;
+                        let result = nitrite::common::from_value::<#ty>(&value);
+                        nitrite::common::ValuePool::release(value);
+                        result?
+                    }
+                }
             }
         })
         .collect();
 
+    // Versioned types stamp their version on write and upgrade older documents on read
+    let (write_version, read_version) = match version {
+        Some(version) => {
+            let upgrade = match &upgrade {
+                Some(path) => quote! { Some(#path as nitrite::common::UpgradeFn) },
+                None => quote! { None },
+            };
+            (
+                quote! {
+                    doc.put(
+                        nitrite::common::DOC_SCHEMA_VERSION,
+                        nitrite::common::Value::U32(#version),
+                    )?;
+                },
+                quote! {
+                    let doc = nitrite::common::upgrade_document(doc, #version, #upgrade)?;
+                },
+            )
+        }
+        None => (quote! {}, quote! {}),
+    };
+
     // Get the name identifier of the struct
     let name = &ast.ident;
     let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
@@ -87,12 +143,14 @@ pub(crate) fn generate_convertible_for_struct(
             fn to_value(&self) -> nitrite::errors::NitriteResult<nitrite::common::Value> {
                 let mut doc = nitrite::collection::Document::new();
                 #(doc.put(#filtered_field_names, self.#filtered_idents.to_value()?)?;)*
+                #write_version
                 Ok(nitrite::common::Value::Document(doc))
             }
 
             fn from_value(value: &nitrite::common::Value) -> nitrite::errors::NitriteResult<Self::Output> {
                 match value {
                     nitrite::common::Value::Document(doc) => {
+                        #read_version
                         Ok(#name {
                             #(#default_initializers,)*
                         })
@@ -122,7 +180,12 @@ pub(crate) fn generate_convertible_for_enum(
 
     // Check if the enum has a `converter` attribute with ignored fields
     for attr in &ast.attrs {
-        if attr.path().is_ident("converter") {
+        if attr.path().is_ident("convertible") {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "versioning is only supported for structs",
+            ));
+        } else if attr.path().is_ident("converter") {
             attr.parse_nested_meta(|meta| {
                 if meta.path.is_ident("ignored") {
                     let value = meta.value()?;
@@ -207,7 +270,14 @@ pub(crate) fn generate_convertible_for_enum(
                         if ignored_fields.contains(name) {
                             quote! { #ident: Default::default() }
                         } else {
-                            quote! { #ident: nitrite::common::from_value::<#ty>(&data.get(#name)?)? }
+                            quote! {
+                                #ident: {
+                                    let value = nitrite::common::ValuePool::field(data, #name)?;
+                                    let result = nitrite::common::from_value::<#ty>(&value);
+                                    nitrite::common::ValuePool::release(value);
+                                    result?
+                                }
+                            }
                         }
                     })
                     .collect();
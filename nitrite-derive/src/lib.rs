@@ -12,6 +12,7 @@
 //!
 //! - **Supported for**: Structs with named fields and enums with data
 //! - **Field attribute**: `#[converter(serialize = "...", deserialize = "...")]`
+//! - **Type attribute**: `#[convertible(version = N, upgrade = "...")]` versions a struct
 //!
 //! # Examples
 //!
@@ -81,12 +82,41 @@ use syn::{Data, DeriveInput};
 /// # Attributes
 ///
 /// - `#[converter(serialize = "fn_name", deserialize = "fn_name")]` - Custom conversion functions
+/// - `#[convertible(version = N, upgrade = "fn_path")]` - Versions a struct, see below
+///
+/// # Versioning
+///
+/// A struct with `#[convertible(version = N)]` writes `N` in the `_schema_version` field of
+/// its documents. Reading a document of an older version calls the upgrade hook, a
+/// `fn(old_version: u32, doc: Document) -> NitriteResult<Document>`, which returns the
+/// document in the current shape before the fields are read. Documents written before the
+/// struct was versioned are at version 1. Without an upgrade hook, reading an older
+/// document fails with `ErrorKind::DocumentTooOld`.
+///
+/// ```rust,ignore
+/// #[derive(Convertible)]
+/// #[convertible(version = 2, upgrade = "User::upgrade")]
+/// pub struct User {
+///     pub full_name: String,
+/// }
+///
+/// impl User {
+///     fn upgrade(old_version: u32, mut doc: Document) -> NitriteResult<Document> {
+///         if old_version < 2 {
+///             let name = doc.get("name")?;
+///             doc.put("full_name", name)?;
+///         }
+///         Ok(doc)
+///     }
+/// }
+/// ```
 ///
 /// # Errors
 ///
 /// Returns a compile error if:
 /// - Any field doesn't implement `Convertible`
 /// - The type is a union (unions are not supported)
+/// - `#[convertible(...)]` is used on an enum
 ///
 /// # Examples
 ///
@@ -97,7 +127,7 @@ use syn::{Data, DeriveInput};
 ///     pub age: u32,
 /// }
 /// ```
-#[proc_macro_derive(Convertible, attributes(converter))]
+#[proc_macro_derive(Convertible, attributes(converter, convertible))]
 pub fn derive_convert(input: TokenStream) -> TokenStream {
     let ast = syn::parse_macro_input!(input as DeriveInput);
 
@@ -160,6 +190,13 @@ pub fn derive_convert(input: TokenStream) -> TokenStream {
 ///
 /// - `#[entity(id)]` - Marks a field as the primary key (optional)
 ///
+/// # Schema
+///
+/// The names and declared types of the persisted fields (all fields except those in
+/// `#[converter(ignored = "...")]`) make up the entity schema. Opening a repository whose
+/// stored schema differs fails with `ErrorKind::SchemaChanged` until it is migrated, or
+/// until the version in `#[convertible(version = N)]` is raised above the stored one.
+///
 /// # Errors
 ///
 /// Returns a compile error if:
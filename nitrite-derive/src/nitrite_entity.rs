@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{DataStruct, DeriveInput, LitStr, Result};
+use syn::{DataStruct, DeriveInput, LitInt, LitStr, Result};
 
 pub(crate) fn generate_entity_for_struct(
     ast: &DeriveInput,
@@ -16,9 +16,34 @@ pub(crate) fn generate_entity_for_struct(
     let mut id_found = false;
     let mut is_nitrite_id = false;
     let mut id_type: Option<proc_macro2::TokenStream> = None;
+    let mut ignored_fields: Vec<String> = vec![];
+    let mut schema_version: Option<u32> = None;
 
     for attr in &ast.attrs {
-        if attr.path().is_ident("entity") {
+        if attr.path().is_ident("convertible") {
+            // A versioned entity may change its fields along with its version
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("version") {
+                    let value: LitInt = meta.value()?.parse()?;
+                    schema_version = Some(value.base10_parse::<u32>()?);
+                } else if meta.path.is_ident("upgrade") {
+                    let _: LitStr = meta.value()?.parse()?;
+                }
+                Ok(())
+            })?
+        } else if attr.path().is_ident("converter") {
+            // Fields ignored by the converter are not persisted, so not part of the schema
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("ignored") {
+                    let value = meta.value()?;
+                    let s: LitStr = value.parse()?;
+                    for field in s.value().split(',') {
+                        ignored_fields.push(field.trim().to_string());
+                    }
+                }
+                Ok(())
+            })?
+        } else if attr.path().is_ident("entity") {
             attr.parse_nested_meta(|meta| {
                 if meta.path.is_ident("name") {
                     let value = meta.value()?;
@@ -197,12 +222,29 @@ pub(crate) fn generate_entity_for_struct(
         }
     };
 
+    // Generate entity schema code from the persisted fields and their declared types
+    let schema_fields_code = data.fields.iter().filter_map(|field| {
+        let field_name = field.ident.as_ref()?.to_string();
+        if ignored_fields.contains(&field_name) {
+            return None;
+        }
+        let type_name = field.ty.to_token_stream().to_string().replace(' ', "");
+        Some(quote!((#field_name, #type_name)))
+    });
+    let with_version = schema_version.map(|version| quote!(.with_version(#version)));
+    let entity_schema_code = quote! {
+        fn entity_schema(&self) -> Option<nitrite::repository::EntitySchema> {
+            Some(nitrite::repository::EntitySchema::new(vec![#(#schema_fields_code),*])#with_version)
+        }
+    };
+
     let gen = quote! {
         impl #impl_generics nitrite::repository::NitriteEntity for #name #ty_generics #where_clause {
             #id_type_code
             #entity_name_code
             #entity_id_code
             #entity_indexes_code
+            #entity_schema_code
         }
     };
 
@@ -0,0 +1,237 @@
+use crate::error::{FfiResult, NitriteError};
+use crate::filter::filter_from_json;
+use crate::json::{document_from_json, document_to_json, format_id, parse_id};
+use nitrite::collection::{FindOptions, NitriteCollection, NitriteId};
+use nitrite::common::SortOrder;
+use nitrite::index::IndexOptions;
+use nitrite::nitrite::Nitrite;
+use nitrite_fjall_adapter::FjallModule;
+use std::sync::{Arc, Mutex};
+
+/// An open database.
+#[derive(uniffi::Object)]
+pub struct Database {
+    db: Nitrite,
+}
+
+#[uniffi::export]
+impl Database {
+    /// Opens or creates a database.
+    ///
+    /// With a `path` the database is stored in files under it, otherwise it lives in
+    /// memory. The credentials are required if the database was created with them.
+    #[uniffi::constructor]
+    pub fn open(
+        path: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> FfiResult<Arc<Database>> {
+        let mut builder = Nitrite::builder();
+        if let Some(path) = path {
+            builder = builder.load_module(FjallModule::with_config().db_path(&path).build());
+        }
+        let db = builder.open_or_create(username.as_deref(), password.as_deref())?;
+        Ok(Arc::new(Database { db }))
+    }
+
+    /// Opens a collection, creating it if needed.
+    pub fn collection(&self, name: String) -> FfiResult<Arc<Collection>> {
+        let collection = self.db.collection(&name)?;
+        Ok(Arc::new(Collection { collection }))
+    }
+
+    /// Returns `true` if a collection with this name exists.
+    pub fn has_collection(&self, name: String) -> FfiResult<bool> {
+        Ok(self.db.has_collection(&name)?)
+    }
+
+    /// Returns the names of the collections, sorted.
+    pub fn collection_names(&self) -> FfiResult<Vec<String>> {
+        let mut names: Vec<String> = self.db.list_collection_names()?.into_iter().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Drops a collection and its documents.
+    pub fn destroy_collection(&self, name: String) -> FfiResult<()> {
+        Ok(self.db.destroy_collection(&name)?)
+    }
+
+    /// Writes the pending changes to disk.
+    pub fn commit(&self) -> FfiResult<()> {
+        Ok(self.db.commit()?)
+    }
+
+    /// Closes the database. The collections and cursors can no longer be used.
+    pub fn close(&self) -> FfiResult<()> {
+        Ok(self.db.close()?)
+    }
+
+    /// Returns `true` once the database is closed.
+    pub fn is_closed(&self) -> FfiResult<bool> {
+        Ok(self.db.is_closed()?)
+    }
+}
+
+/// Options of [`Collection::find`].
+#[derive(uniffi::Record, Default)]
+pub struct FindParams {
+    /// Field to sort the documents by.
+    pub sort_by: Option<String>,
+    /// Sorts in descending order instead of ascending.
+    pub descending: bool,
+    /// Number of documents to skip.
+    pub skip: Option<u64>,
+    /// Maximum number of documents to return.
+    pub limit: Option<u64>,
+}
+
+/// A collection of JSON documents.
+///
+/// Documents are passed as JSON objects and returned with their `_id` as a string.
+/// Filters use the syntax described on [`filter_from_json`].
+#[derive(uniffi::Object)]
+pub struct Collection {
+    collection: NitriteCollection,
+}
+
+#[uniffi::export]
+impl Collection {
+    /// Returns the name of the collection.
+    pub fn name(&self) -> String {
+        self.collection.name()
+    }
+
+    /// Inserts a document and returns its id.
+    pub fn insert(&self, document: String) -> FfiResult<String> {
+        let result = self.collection.insert(document_from_json(&document)?)?;
+        result
+            .affected_nitrite_ids()
+            .first()
+            .map(format_id)
+            .ok_or_else(|| NitriteError::Database {
+                kind: "Internal error".to_string(),
+                message: "The document was not inserted".to_string(),
+            })
+    }
+
+    /// Inserts documents atomically and returns their ids, in order.
+    pub fn insert_many(&self, documents: Vec<String>) -> FfiResult<Vec<String>> {
+        let documents = documents
+            .iter()
+            .map(|document| document_from_json(document))
+            .collect::<FfiResult<Vec<_>>>()?;
+        let result = self.collection.insert_many(documents)?;
+        Ok(result.affected_nitrite_ids().iter().map(format_id).collect())
+    }
+
+    /// Sets the fields of `update` on the matching documents and returns their number.
+    pub fn update(&self, filter: String, update: String) -> FfiResult<u64> {
+        let result = self
+            .collection
+            .update(filter_from_json(&filter)?, &document_from_json(&update)?)?;
+        Ok(result.affected_nitrite_ids().len() as u64)
+    }
+
+    /// Removes the matching documents and returns their number.
+    pub fn remove(&self, filter: String) -> FfiResult<u64> {
+        let result = self.collection.remove(filter_from_json(&filter)?, false)?;
+        Ok(result.affected_nitrite_ids().len() as u64)
+    }
+
+    /// Returns the document with this id.
+    pub fn get_by_id(&self, id: String) -> FfiResult<Option<String>> {
+        let document = self.collection.get_by_id(&parse_id(&id)?)?;
+        Ok(document.as_ref().map(document_to_json))
+    }
+
+    /// Finds the matching documents.
+    pub fn find(&self, filter: String, params: Option<FindParams>) -> FfiResult<Arc<Cursor>> {
+        let params = params.unwrap_or_default();
+        let mut options = FindOptions::new();
+        if let Some(sort_by) = params.sort_by {
+            let order = if params.descending {
+                SortOrder::Descending
+            } else {
+                SortOrder::Ascending
+            };
+            options = options.sort_by(sort_by, order);
+        }
+        if let Some(skip) = params.skip {
+            options = options.skip(skip);
+        }
+        if let Some(limit) = params.limit {
+            options = options.limit(limit);
+        }
+
+        // the ids are resolved up front, so that the cursor can be shared between threads
+        let cursor = self
+            .collection
+            .find_with_options(filter_from_json(&filter)?, &options)?;
+        let mut ids = Vec::new();
+        for document in cursor {
+            ids.push(document?.id()?);
+        }
+        Ok(Arc::new(Cursor {
+            collection: self.collection.clone(),
+            ids,
+            position: Mutex::new(0),
+        }))
+    }
+
+    /// Returns the number of documents.
+    pub fn size(&self) -> FfiResult<u64> {
+        Ok(self.collection.size()?)
+    }
+
+    /// Creates an index of type `unique`, `non-unique` or `full-text` on the fields.
+    pub fn create_index(&self, fields: Vec<String>, index_type: String) -> FfiResult<()> {
+        let fields = fields.iter().map(|field| field.as_str()).collect();
+        Ok(self
+            .collection
+            .create_index(fields, &IndexOptions::new(&index_type))?)
+    }
+}
+
+/// The result of a [`Collection::find`], read one document or one batch at a time.
+///
+/// The matching documents are selected when the cursor is created; a document removed
+/// before it is read is skipped.
+#[derive(uniffi::Object)]
+pub struct Cursor {
+    collection: NitriteCollection,
+    ids: Vec<NitriteId>,
+    position: Mutex<usize>,
+}
+
+#[uniffi::export]
+impl Cursor {
+    /// Returns the number of matching documents.
+    pub fn count(&self) -> u64 {
+        self.ids.len() as u64
+    }
+
+    /// Returns the next document, or `None` at the end.
+    pub fn next(&self) -> FfiResult<Option<String>> {
+        Ok(self.next_batch(1)?.pop())
+    }
+
+    /// Returns up to `max` next documents; an empty list at the end.
+    pub fn next_batch(&self, max: u32) -> FfiResult<Vec<String>> {
+        let mut position = self.position.lock().unwrap_or_else(|err| err.into_inner());
+        let mut documents = Vec::new();
+        while documents.len() < max as usize && *position < self.ids.len() {
+            let id = self.ids[*position];
+            *position += 1;
+            if let Some(document) = self.collection.get_by_id(&id)? {
+                documents.push(document_to_json(&document));
+            }
+        }
+        Ok(documents)
+    }
+
+    /// Starts reading from the first document again.
+    pub fn reset(&self) {
+        *self.position.lock().unwrap_or_else(|err| err.into_inner()) = 0;
+    }
+}
@@ -0,0 +1,31 @@
+use nitrite::errors::NitriteError as CoreError;
+
+/// Error returned to the foreign code.
+///
+/// Surfaces as `NitriteException` in Kotlin and `NitriteError` in Swift.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum NitriteError {
+    /// An operation of the database failed; `kind` names the nitrite error kind.
+    #[error("{kind}: {message}")]
+    Database { kind: String, message: String },
+    /// A document or filter is not valid JSON, or has an unsupported shape.
+    #[error("invalid JSON: {message}")]
+    InvalidJson { message: String },
+}
+
+impl From<CoreError> for NitriteError {
+    fn from(err: CoreError) -> Self {
+        NitriteError::Database {
+            kind: err.kind().to_string(),
+            message: err.message().to_string(),
+        }
+    }
+}
+
+pub(crate) fn invalid_json(message: impl Into<String>) -> NitriteError {
+    let message = message.into();
+    log::error!("Invalid JSON: {}", message);
+    NitriteError::InvalidJson { message }
+}
+
+pub(crate) type FfiResult<T> = Result<T, NitriteError>;
@@ -0,0 +1,149 @@
+use crate::error::{invalid_json, FfiResult};
+use crate::json::{json_to_value, parse_id};
+use nitrite::common::{Value, DOC_ID};
+use nitrite::filter::{all, and, by_id, field, not, or, Filter};
+use serde_json::{Map, Value as Json};
+
+/// Parses a filter in the MongoDB style query syntax.
+///
+/// - `{}` (or an empty string) matches all documents
+/// - `{"name": "Ada", "age": {"$gte": 18}}` combines the field conditions with `and`
+/// - `$and`, `$or` take an array of filters and `$not` a filter
+/// - field operators: `$eq`, `$ne`, `$gt`, `$gte`, `$lt`, `$lte`, `$in`, `$nin`,
+///   `$text` (full-text index) and `$regex`
+/// - `{"_id": "<id>"}` selects a document by its id
+pub(crate) fn filter_from_json(json: &str) -> FfiResult<Filter> {
+    if json.trim().is_empty() {
+        return Ok(all());
+    }
+    match serde_json::from_str(json).map_err(|err| invalid_json(err.to_string()))? {
+        Json::Object(conditions) => object_to_filter(conditions),
+        _ => Err(invalid_json("a filter must be a JSON object")),
+    }
+}
+
+fn object_to_filter(conditions: Map<String, Json>) -> FfiResult<Filter> {
+    let mut filters = Vec::with_capacity(conditions.len());
+    for (name, condition) in conditions {
+        let filter = match name.as_str() {
+            "$and" => and(filter_list(condition, "$and")?),
+            "$or" => or(filter_list(condition, "$or")?),
+            "$not" => not(nested_filter(condition, "$not")?),
+            _ if name.starts_with('$') => {
+                return Err(invalid_json(format!("unknown filter operator {}", name)))
+            }
+            _ => field_filter(&name, condition)?,
+        };
+        filters.push(filter);
+    }
+
+    Ok(match filters.len() {
+        0 => all(),
+        1 => filters.remove(0),
+        _ => and(filters),
+    })
+}
+
+fn nested_filter(condition: Json, operator: &str) -> FfiResult<Filter> {
+    match condition {
+        Json::Object(conditions) => object_to_filter(conditions),
+        _ => Err(invalid_json(format!("{} requires a filter object", operator))),
+    }
+}
+
+fn filter_list(condition: Json, operator: &str) -> FfiResult<Vec<Filter>> {
+    match condition {
+        Json::Array(filters) if filters.len() >= 2 => filters
+            .into_iter()
+            .map(|filter| nested_filter(filter, operator))
+            .collect(),
+        _ => Err(invalid_json(format!("{} requires an array of at least two filters", operator))),
+    }
+}
+
+fn field_filter(name: &str, condition: Json) -> FfiResult<Filter> {
+    match condition {
+        Json::String(id) if name == DOC_ID => Ok(by_id(parse_id(&id)?)),
+        Json::Object(operators) if operators.keys().any(|key| key.starts_with('$')) => {
+            let mut filters = Vec::with_capacity(operators.len());
+            for (operator, operand) in operators {
+                filters.push(operator_filter(name, &operator, operand)?);
+            }
+            Ok(match filters.len() {
+                1 => filters.remove(0),
+                _ => and(filters),
+            })
+        }
+        value => Ok(field(name).eq(json_to_value(value)?)),
+    }
+}
+
+fn operator_filter(name: &str, operator: &str, operand: Json) -> FfiResult<Filter> {
+    Ok(match operator {
+        "$eq" => field(name).eq(json_to_value(operand)?),
+        "$ne" => field(name).ne(json_to_value(operand)?),
+        "$gt" => field(name).gt(json_to_value(operand)?),
+        "$gte" => field(name).gte(json_to_value(operand)?),
+        "$lt" => field(name).lt(json_to_value(operand)?),
+        "$lte" => field(name).lte(json_to_value(operand)?),
+        "$in" => field(name).in_array(value_list(operand, operator)?),
+        "$nin" => field(name).not_in_array(value_list(operand, operator)?),
+        "$text" => field(name).text(&string_operand(operand, operator)?),
+        "$regex" => field(name).text_regex(&string_operand(operand, operator)?),
+        _ => return Err(invalid_json(format!("unknown filter operator {}", operator))),
+    })
+}
+
+fn value_list(operand: Json, operator: &str) -> FfiResult<Vec<Value>> {
+    match operand {
+        Json::Array(values) => values.into_iter().map(json_to_value).collect(),
+        _ => Err(invalid_json(format!("{} requires an array", operator))),
+    }
+}
+
+fn string_operand(operand: Json, operator: &str) -> FfiResult<String> {
+    match operand {
+        Json::String(value) => Ok(value),
+        _ => Err(invalid_json(format!("{} requires a string", operator))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nitrite::collection::Document;
+    use nitrite::doc;
+
+    fn matches(filter: &str, document: &Document) -> bool {
+        filter_from_json(filter).unwrap().apply(document).unwrap()
+    }
+
+    #[test]
+    fn test_field_conditions() {
+        let document = doc! { "name": "Ada", "age": 36, "tags": ["math", "code"] };
+        assert!(matches("", &document));
+        assert!(matches("{}", &document));
+        assert!(matches(r#"{"name": "Ada"}"#, &document));
+        assert!(matches(r#"{"name": "Ada", "age": {"$gte": 30, "$lt": 40}}"#, &document));
+        assert!(!matches(r#"{"name": "Ada", "age": {"$gt": 36}}"#, &document));
+        assert!(matches(r#"{"name": {"$in": ["Ada", "Grace"]}}"#, &document));
+        assert!(matches(r#"{"name": {"$regex": "^A"}}"#, &document));
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let document = doc! { "name": "Ada", "age": 36 };
+        assert!(matches(r#"{"$or": [{"name": "Grace"}, {"age": 36}]}"#, &document));
+        assert!(!matches(r#"{"$and": [{"name": "Grace"}, {"age": 36}]}"#, &document));
+        assert!(matches(r#"{"$not": {"name": "Grace"}}"#, &document));
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        assert!(filter_from_json("[]").is_err());
+        assert!(filter_from_json(r#"{"$nor": []}"#).is_err());
+        assert!(filter_from_json(r#"{"age": {"$between": 1}}"#).is_err());
+        assert!(filter_from_json(r#"{"$or": [{"age": 1}]}"#).is_err());
+        assert!(filter_from_json(r#"{"_id": "x"}"#).is_err());
+    }
+}
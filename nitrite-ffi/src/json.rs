@@ -0,0 +1,156 @@
+use crate::error::{invalid_json, FfiResult};
+use nitrite::collection::{Document, NitriteId};
+use nitrite::common::{Value, DOC_ID};
+use serde_json::{Map, Number, Value as Json};
+
+/// Parses a JSON object into a document.
+///
+/// Integers become `I64` (or `U64` above `i64::MAX`) and other numbers `F64`. A top-level
+/// `_id` must be the id string returned by the bindings.
+pub(crate) fn document_from_json(json: &str) -> FfiResult<Document> {
+    match serde_json::from_str(json).map_err(|err| invalid_json(err.to_string()))? {
+        Json::Object(fields) => object_to_document(fields, true),
+        _ => Err(invalid_json("a document must be a JSON object")),
+    }
+}
+
+/// Formats a document as a JSON object; ids are written as strings.
+pub(crate) fn document_to_json(document: &Document) -> String {
+    Json::Object(document_to_object(document)).to_string()
+}
+
+pub(crate) fn parse_id(id: &str) -> FfiResult<NitriteId> {
+    let id_value = id
+        .parse()
+        .map_err(|_| invalid_json(format!("'{}' is not a document id", id)))?;
+    Ok(NitriteId::create_id(id_value)?)
+}
+
+pub(crate) fn format_id(id: &NitriteId) -> String {
+    id.id_value().to_string()
+}
+
+fn object_to_document(fields: Map<String, Json>, top_level: bool) -> FfiResult<Document> {
+    let mut document = Document::new();
+    for (name, value) in fields {
+        let value = match value {
+            Json::String(id) if top_level && name == DOC_ID => Value::NitriteId(parse_id(&id)?),
+            value => json_to_value(value)?,
+        };
+        document.put(name, value)?;
+    }
+    Ok(document)
+}
+
+pub(crate) fn json_to_value(json: Json) -> FfiResult<Value> {
+    Ok(match json {
+        Json::Null => Value::Null,
+        Json::Bool(value) => Value::Bool(value),
+        Json::Number(number) => {
+            if let Some(value) = number.as_i64() {
+                Value::I64(value)
+            } else if let Some(value) = number.as_u64() {
+                Value::U64(value)
+            } else {
+                Value::F64(number.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Json::String(value) => Value::String(value),
+        Json::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(json_to_value)
+                .collect::<FfiResult<Vec<Value>>>()?,
+        ),
+        Json::Object(fields) => Value::Document(object_to_document(fields, false)?),
+    })
+}
+
+fn document_to_object(document: &Document) -> Map<String, Json> {
+    document
+        .iter()
+        .map(|(name, value)| (name, value_to_json(&value)))
+        .collect()
+}
+
+fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Null | Value::Unknown => Json::Null,
+        Value::Bool(value) => Json::Bool(*value),
+        Value::I8(value) => Json::from(*value),
+        Value::U8(value) => Json::from(*value),
+        Value::I16(value) => Json::from(*value),
+        Value::U16(value) => Json::from(*value),
+        Value::I32(value) => Json::from(*value),
+        Value::U32(value) => Json::from(*value),
+        Value::I64(value) => Json::from(*value),
+        Value::U64(value) => Json::from(*value),
+        Value::ISize(value) => Json::from(*value),
+        Value::USize(value) => Json::from(*value),
+        // outside the 64-bit range JSON numbers lose precision, so keep the digits
+        Value::I128(value) => i64::try_from(*value)
+            .map(Json::from)
+            .unwrap_or_else(|_| Json::String(value.to_string())),
+        Value::U128(value) => u64::try_from(*value)
+            .map(Json::from)
+            .unwrap_or_else(|_| Json::String(value.to_string())),
+        Value::F32(value) => float_to_json(*value as f64),
+        Value::F64(value) => float_to_json(*value),
+        Value::Char(value) => Json::String(value.to_string()),
+        Value::String(value) => Json::String(value.clone()),
+        Value::Document(document) => Json::Object(document_to_object(document)),
+        Value::Array(values) => Json::Array(values.iter().map(value_to_json).collect()),
+        Value::Map(entries) => Json::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (map_key(key), value_to_json(value)))
+                .collect(),
+        ),
+        Value::NitriteId(id) => Json::String(format_id(id)),
+        Value::Bytes(bytes) => Json::Array(bytes.iter().map(|byte| Json::from(*byte)).collect()),
+        // validated when the value was made, so it parses back
+        Value::RawJson(json) => serde_json::from_str(json).unwrap_or(Json::Null),
+    }
+}
+
+fn float_to_json(value: f64) -> Json {
+    Number::from_f64(value).map(Json::Number).unwrap_or(Json::Null)
+}
+
+fn map_key(key: &Value) -> String {
+    match value_to_json(key) {
+        Json::String(key) => key,
+        key => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nitrite::doc;
+
+    #[test]
+    fn test_document_round_trip() {
+        let json = r#"{"name":"Ada","age":36,"ratio":0.5,"tags":["a","b"],"address":{"city":"London"},"missing":null}"#;
+        let document = document_from_json(json).unwrap();
+        assert_eq!(document.get("age").unwrap(), Value::I64(36));
+        assert_eq!(document.get("address.city").unwrap(), Value::String("London".to_string()));
+
+        let round_trip: Json = serde_json::from_str(&document_to_json(&document)).unwrap();
+        assert_eq!(round_trip, serde_json::from_str::<Json>(json).unwrap());
+    }
+
+    #[test]
+    fn test_document_id() {
+        let mut document = doc! { "name": "Ada" };
+        let id = document.id().unwrap();
+        let json = document_to_json(&document);
+        assert!(json.contains(&format!("\"_id\":\"{}\"", id.id_value())));
+
+        let mut parsed = document_from_json(&json).unwrap();
+        assert_eq!(parsed.id().unwrap(), id);
+
+        assert!(document_from_json(r#"{"_id":"abc"}"#).is_err());
+        assert!(document_from_json("[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! # Nitrite FFI — Kotlin and Swift bindings for Nitrite
+//!
+//! This crate exposes the Nitrite engine to Android and iOS apps through
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/). It is built as a `cdylib` (Android) or
+//! `staticlib` (iOS), and the foreign bindings are generated from the library:
+//!
+//! ```text
+//! cargo build --release -p nitrite_ffi
+//! uniffi-bindgen generate --library target/release/libnitrite_ffi.so --language kotlin --out-dir out
+//! uniffi-bindgen generate --library target/release/libnitrite_ffi.a --language swift --out-dir out
+//! ```
+//!
+//! Documents cross the boundary as JSON objects, and filters use a MongoDB style query
+//! syntax:
+//!
+//! ```kotlin
+//! val db = Database.open(context.filesDir.path + "/app.db", null, null)
+//! val users = db.collection("users")
+//! val id = users.insert("""{"name": "Ada", "age": 36}""")
+//!
+//! val cursor = users.find("""{"age": {"${'$'}gte": 18}}""", FindParams(sortBy = "name"))
+//! while (true) {
+//!     val user = cursor.next() ?: break
+//!     println(user)
+//! }
+//! db.close()
+//! ```
+//!
+//! A database opened with a path is stored with the fjall adapter; without a path it lives
+//! in memory.
+
+uniffi::setup_scaffolding!();
+
+mod database;
+mod error;
+mod filter;
+mod json;
+
+pub use database::*;
+pub use error::NitriteError;
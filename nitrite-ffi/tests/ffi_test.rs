@@ -0,0 +1,122 @@
+use nitrite_ffi::{Database, FindParams, NitriteError};
+use serde_json::{json, Value};
+use std::fs;
+
+fn parse(document: &str) -> Value {
+    serde_json::from_str(document).unwrap()
+}
+
+#[test]
+fn test_collection_crud() {
+    let db = Database::open(None, None, None).unwrap();
+    let users = db.collection("users".to_string()).unwrap();
+
+    let ada = users.insert(r#"{"name": "Ada", "age": 36}"#.to_string()).unwrap();
+    let ids = users
+        .insert_many(vec![
+            r#"{"name": "Grace", "age": 45}"#.to_string(),
+            r#"{"name": "Linus", "age": 17}"#.to_string(),
+        ])
+        .unwrap();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(users.size().unwrap(), 3);
+
+    let document = parse(&users.get_by_id(ada.clone()).unwrap().unwrap());
+    assert_eq!(document["name"], json!("Ada"));
+    assert_eq!(document["_id"], json!(ada));
+
+    let updated = users
+        .update(r#"{"name": "Ada"}"#.to_string(), r#"{"age": 37}"#.to_string())
+        .unwrap();
+    assert_eq!(updated, 1);
+    let document = parse(&users.get_by_id(ada).unwrap().unwrap());
+    assert_eq!(document["age"], json!(37));
+
+    let removed = users.remove(r#"{"age": {"$lt": 18}}"#.to_string()).unwrap();
+    assert_eq!(removed, 1);
+    assert_eq!(users.size().unwrap(), 2);
+    assert_eq!(db.collection_names().unwrap(), vec!["users".to_string()]);
+
+    db.close().unwrap();
+    assert!(db.is_closed().unwrap());
+}
+
+#[test]
+fn test_find_with_cursor() {
+    let db = Database::open(None, None, None).unwrap();
+    let users = db.collection("users".to_string()).unwrap();
+    for (name, age) in [("Ada", 36), ("Grace", 45), ("Linus", 17), ("Alan", 41)] {
+        users
+            .insert(json!({ "name": name, "age": age }).to_string())
+            .unwrap();
+    }
+
+    let params = FindParams {
+        sort_by: Some("age".to_string()),
+        descending: true,
+        skip: None,
+        limit: Some(2),
+    };
+    let cursor = users
+        .find(r#"{"age": {"$gte": 18}}"#.to_string(), Some(params))
+        .unwrap();
+    assert_eq!(cursor.count(), 2);
+    assert_eq!(parse(&cursor.next().unwrap().unwrap())["name"], json!("Grace"));
+    assert_eq!(parse(&cursor.next().unwrap().unwrap())["name"], json!("Alan"));
+    assert!(cursor.next().unwrap().is_none());
+
+    cursor.reset();
+    assert_eq!(cursor.next_batch(10).unwrap().len(), 2);
+
+    // documents removed after the find are skipped
+    let cursor = users.find(String::new(), None).unwrap();
+    users.remove(r#"{"name": "Linus"}"#.to_string()).unwrap();
+    assert_eq!(cursor.count(), 4);
+    assert_eq!(cursor.next_batch(10).unwrap().len(), 3);
+
+    db.close().unwrap();
+}
+
+#[test]
+fn test_invalid_input() {
+    let db = Database::open(None, None, None).unwrap();
+    let users = db.collection("users".to_string()).unwrap();
+
+    let err = users.insert("[1, 2]".to_string()).unwrap_err();
+    assert!(matches!(err, NitriteError::InvalidJson { .. }));
+    let err = users.find(r#"{"age": {"$near": 1}}"#.to_string(), None).err().unwrap();
+    assert!(matches!(err, NitriteError::InvalidJson { .. }));
+
+    users
+        .create_index(vec!["name".to_string()], "unique".to_string())
+        .unwrap();
+    users.insert(r#"{"name": "Ada"}"#.to_string()).unwrap();
+    let err = users.insert(r#"{"name": "Ada"}"#.to_string()).unwrap_err();
+    assert!(matches!(err, NitriteError::Database { .. }));
+
+    db.close().unwrap();
+}
+
+#[test]
+fn test_file_database() {
+    let path = std::env::temp_dir().join(format!("nitrite-ffi-{}", std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+
+    let db = Database::open(Some(path.clone()), None, None).unwrap();
+    db.collection("notes".to_string())
+        .unwrap()
+        .insert(r#"{"text": "hello"}"#.to_string())
+        .unwrap();
+    db.close().unwrap();
+
+    let db = Database::open(Some(path.clone()), None, None).unwrap();
+    let cursor = db
+        .collection("notes".to_string())
+        .unwrap()
+        .find(r#"{"text": "hello"}"#.to_string(), None)
+        .unwrap();
+    assert_eq!(cursor.count(), 1);
+    db.close().unwrap();
+
+    fs::remove_dir_all(path).unwrap();
+}
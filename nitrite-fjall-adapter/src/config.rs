@@ -7,6 +7,7 @@ use std::sync::atomic::{
     AtomicBool, AtomicI8, AtomicU16, AtomicU32, AtomicU64, AtomicUsize, Ordering,
 };
 use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 /// Controls when a committed write is made durable (fsynced) to stable storage.
 ///
@@ -45,6 +46,132 @@ impl Durability {
     }
 }
 
+/// Partition settings for the maps whose names match a pattern.
+///
+/// Registered on [`FjallModuleBuilder::partition_options`](crate::FjallModuleBuilder::partition_options)
+/// with a name pattern, where `*` matches any run of characters and `?` a single one. A
+/// setting left unset keeps the store-wide value. Map names are nitrite's names, so the
+/// collection `events` is the map `events` and its indexes are maps named
+/// `$nitrite_index|events|<fields>|<type>`.
+///
+/// Fjall applies the settings when a partition is created, an existing partition keeps the
+/// settings it was created with.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PartitionOptions {
+    block_size: Option<u32>,
+    compression_type: Option<CompressionType>,
+    bloom_filter_bits: Option<i8>,
+    max_memtable_size: Option<u32>,
+    kv_separated: Option<bool>,
+}
+
+impl PartitionOptions {
+    /// Creates partition options that keep every store-wide setting.
+    pub fn new() -> PartitionOptions {
+        PartitionOptions::default()
+    }
+
+    /// Sets the block size of the partition's segments.
+    pub fn block_size(mut self, block_size: u32) -> Self {
+        self.block_size = Some(block_size);
+        self
+    }
+
+    /// Sets the compression of the partition's blocks.
+    pub fn compression_type(mut self, compression_type: CompressionType) -> Self {
+        self.compression_type = Some(compression_type);
+        self
+    }
+
+    /// Sets the bloom filter bits per key.
+    pub fn bloom_filter_bits(mut self, bloom_filter_bits: u8) -> Self {
+        self.bloom_filter_bits = Some(bloom_filter_bits as i8);
+        self
+    }
+
+    /// Disables the bloom filters of the partition.
+    pub fn disable_bloom_filter(mut self) -> Self {
+        self.bloom_filter_bits = Some(-1);
+        self
+    }
+
+    /// Sets the size a memtable grows to before it is flushed.
+    pub fn max_memtable_size(mut self, max_memtable_size: u32) -> Self {
+        self.max_memtable_size = Some(max_memtable_size);
+        self
+    }
+
+    /// Sets whether large values are stored apart from the keys.
+    pub fn kv_separated(mut self, kv_separated: bool) -> Self {
+        self.kv_separated = Some(kv_separated);
+        self
+    }
+
+    /// Returns the block size, if set.
+    pub fn get_block_size(&self) -> Option<u32> {
+        self.block_size
+    }
+
+    /// Returns the compression type, if set.
+    pub fn get_compression_type(&self) -> Option<CompressionType> {
+        self.compression_type
+    }
+
+    /// Returns the bloom filter bits per key if set, `-1` when bloom filters are disabled.
+    pub fn get_bloom_filter_bits(&self) -> Option<i8> {
+        self.bloom_filter_bits
+    }
+
+    /// Returns the max memtable size, if set.
+    pub fn get_max_memtable_size(&self) -> Option<u32> {
+        self.max_memtable_size
+    }
+
+    /// Returns the key-value separation, if set.
+    pub fn get_kv_separated(&self) -> Option<bool> {
+        self.kv_separated
+    }
+
+    /// Fills the settings left unset from `defaults`.
+    fn or(self, defaults: PartitionOptions) -> PartitionOptions {
+        PartitionOptions {
+            block_size: self.block_size.or(defaults.block_size),
+            compression_type: self.compression_type.or(defaults.compression_type),
+            bloom_filter_bits: self.bloom_filter_bits.or(defaults.bloom_filter_bits),
+            max_memtable_size: self.max_memtable_size.or(defaults.max_memtable_size),
+            kv_separated: self.kv_separated.or(defaults.kv_separated),
+        }
+    }
+}
+
+/// Matches a map name against a pattern where `*` matches any run of characters and `?`
+/// exactly one.
+pub(crate) fn matches_name_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, tried)) = backtrack {
+            // let the last `*` swallow one more character
+            p = star + 1;
+            n = tried + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 #[derive(Clone)]
 /// Fjall database configuration wrapper.
 ///
@@ -119,28 +246,81 @@ impl FjallConfig {
         config
     }
 
-    /// Builds a Fjall Partition configuration from this config.
+    /// Builds the Fjall Partition configuration of the map named `map_name`.
     ///
     /// Creates partition-level settings including bloom filters, compression strategy,
     /// memtable sizing, block size, and optional key-value separation for handling
-    /// large values efficiently.
+    /// large values efficiently. The first registered [`PartitionOptions`] whose pattern
+    /// matches the map name overrides the store-wide settings.
     ///
     /// Returns: A configured `PartitionCreateOptions` for partition creation
     #[inline]
-    pub(crate) fn partition_config(&self) -> PartitionCreateOptions {
+    pub(crate) fn partition_config_for(&self, map_name: &str) -> PartitionCreateOptions {
+        self.create_options(self.partition_options_for(map_name))
+    }
+
+    /// Returns the partition settings used for the map named `map_name`, with every
+    /// setting resolved.
+    pub fn partition_options_for(&self, map_name: &str) -> PartitionOptions {
+        let defaults = self.default_partition_options();
+        self.inner
+            .partition_overrides()
+            .into_iter()
+            .find(|(pattern, _)| matches_name_pattern(pattern, map_name))
+            .map(|(_, options)| options.or(defaults))
+            .unwrap_or(defaults)
+    }
+
+    /// Returns the registered partition overrides with their name patterns, in the order
+    /// they are tried.
+    pub fn partition_overrides(&self) -> Vec<(String, PartitionOptions)> {
+        self.inner.partition_overrides()
+    }
+
+    /// Registers partition settings for the maps matching `pattern`.
+    #[inline]
+    pub(crate) fn add_partition_override(&self, pattern: &str, options: PartitionOptions) {
+        self.inner.add_partition_override(pattern, options)
+    }
+
+    fn default_partition_options(&self) -> PartitionOptions {
+        PartitionOptions {
+            block_size: Some(self.inner.block_size()),
+            compression_type: Some(self.inner.compression_type()),
+            bloom_filter_bits: Some(self.inner.bloom_filter_bits()),
+            max_memtable_size: Some(self.inner.max_memtable_size()),
+            kv_separated: Some(self.inner.kv_separated()),
+        }
+    }
+
+    fn create_options(&self, options: PartitionOptions) -> PartitionCreateOptions {
+        let bloom_filter_bits = options
+            .bloom_filter_bits
+            .unwrap_or_else(|| self.inner.bloom_filter_bits());
         let mut config = PartitionCreateOptions::default();
         config = config
-            .bloom_filter_bits(if self.inner.bloom_filter_bits() == -1 {
+            .bloom_filter_bits(if bloom_filter_bits == -1 {
                 None
             } else {
-                Some(self.inner.bloom_filter_bits() as u8)
+                Some(bloom_filter_bits as u8)
             })
-            .compression(self.inner.compression_type())
+            .compression(
+                options
+                    .compression_type
+                    .unwrap_or_else(|| self.inner.compression_type()),
+            )
             .compaction_strategy(self.inner.compaction_strategy())
-            .max_memtable_size(self.inner.max_memtable_size())
-            .block_size(self.inner.block_size());
-
-        if self.inner.kv_separated() {
+            .max_memtable_size(
+                options
+                    .max_memtable_size
+                    .unwrap_or_else(|| self.inner.max_memtable_size()),
+            )
+            .block_size(options.block_size.unwrap_or_else(|| self.inner.block_size()));
+
+        if options
+            .kv_separated
+            .unwrap_or_else(|| self.inner.kv_separated())
+        {
             config = config.with_kv_separation(KvSeparationOptions::default());
         }
         config
@@ -405,6 +585,21 @@ impl FjallConfig {
     pub(crate) fn set_staleness_threshold(&self, t: f32) {
         self.inner.set_staleness_threshold(t)
     }
+
+    /// Returns how long opening the store waits for another process to release the
+    /// database lock.
+    ///
+    /// `None` (the default) fails the open right away with a "database is locked" error.
+    #[inline]
+    pub fn lock_timeout(&self) -> Option<Duration> {
+        self.inner.lock_timeout()
+    }
+
+    /// Sets how long opening the store waits for the database lock.
+    #[inline]
+    pub(crate) fn set_lock_timeout(&self, timeout: Option<Duration>) {
+        self.inner.set_lock_timeout(timeout)
+    }
 }
 
 impl StoreConfigProvider for FjallConfig {
@@ -450,6 +645,7 @@ impl StoreConfigProvider for FjallConfig {
 /// - Compression and compaction strategies
 /// - Event listener registration
 /// - Bloom filter and memtable tuning
+/// - Partition settings overridden per map name pattern
 ///
 /// Thread-safe: All fields use atomic types (AtomicBool, AtomicU64, etc.) or Atomic<T>
 /// for efficient concurrent access without mutex overhead.
@@ -475,6 +671,9 @@ struct FjallConfigInner {
     kv_separated: AtomicBool,
     space_amp_factor: Atomic<f32>,
     staleness_threshold: Atomic<f32>,
+    partition_overrides: Atomic<Vec<(String, PartitionOptions)>>,
+    // milliseconds, 0 means do not wait
+    lock_timeout_ms: AtomicU64,
 }
 
 impl FjallConfigInner {
@@ -539,6 +738,8 @@ impl FjallConfigInner {
             kv_separated: AtomicBool::new(false),
             space_amp_factor: atomic(1.5),
             staleness_threshold: atomic(0.8),
+            partition_overrides: atomic(Vec::new()),
+            lock_timeout_ms: AtomicU64::new(0),
         }
     }
 
@@ -759,6 +960,31 @@ impl FjallConfigInner {
         self.staleness_threshold
             .write_with(|it| *it = staleness_threshold)
     }
+
+    #[inline]
+    pub fn partition_overrides(&self) -> Vec<(String, PartitionOptions)> {
+        self.partition_overrides.read_with(|it| it.clone())
+    }
+
+    #[inline]
+    pub(crate) fn add_partition_override(&self, pattern: &str, options: PartitionOptions) {
+        self.partition_overrides
+            .write_with(|it| it.push((pattern.to_string(), options)))
+    }
+
+    #[inline]
+    pub fn lock_timeout(&self) -> Option<Duration> {
+        match self.lock_timeout_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_lock_timeout(&self, timeout: Option<Duration>) {
+        let ms = timeout.map_or(0, |timeout| timeout.as_millis().min(u64::MAX as u128) as u64);
+        self.lock_timeout_ms.store(ms, Ordering::Relaxed)
+    }
 }
 
 #[cfg(test)]
@@ -810,6 +1036,7 @@ mod tests {
         assert!(!config.kv_separated());
         assert_eq!(config.space_amp_factor(), 1.5);
         assert_eq!(config.staleness_threshold(), 0.8);
+        assert_eq!(config.lock_timeout(), None);
     }
 
     #[test]
@@ -869,6 +1096,71 @@ mod tests {
 
         config.set_staleness_threshold(0.9);
         assert_eq!(config.staleness_threshold(), 0.9);
+
+        config.set_lock_timeout(Some(Duration::from_secs(2)));
+        assert_eq!(config.lock_timeout(), Some(Duration::from_secs(2)));
+        config.set_lock_timeout(None);
+        assert_eq!(config.lock_timeout(), None);
+    }
+
+    #[test]
+    fn test_matches_name_pattern() {
+        assert!(matches_name_pattern("events", "events"));
+        assert!(!matches_name_pattern("events", "events_archive"));
+        assert!(matches_name_pattern("events*", "events_archive"));
+        assert!(matches_name_pattern("*", ""));
+        assert!(matches_name_pattern("$nitrite_index|events|*", "$nitrite_index|events|ts|non-unique"));
+        assert!(!matches_name_pattern("$nitrite_index|events|*", "$nitrite_index|users|ts|unique"));
+        assert!(matches_name_pattern("log_?", "log_1"));
+        assert!(!matches_name_pattern("log_?", "log_12"));
+        assert!(matches_name_pattern("*a*b*", "xxaxxbxx"));
+        assert!(!matches_name_pattern("*a*b", "xxbxxa"));
+    }
+
+    #[test]
+    fn test_partition_options_for() {
+        let config = FjallConfig::new();
+        config.add_partition_override(
+            "events",
+            PartitionOptions::new()
+                .block_size(32 * 1_024)
+                .disable_bloom_filter(),
+        );
+        config.add_partition_override("event*", PartitionOptions::new().block_size(8 * 1_024));
+        config.add_partition_override(
+            "*",
+            PartitionOptions::new().compression_type(CompressionType::None),
+        );
+        assert_eq!(config.partition_overrides().len(), 3);
+
+        // the first matching pattern wins, unset settings keep the store-wide value
+        let events = config.partition_options_for("events");
+        assert_eq!(events.get_block_size(), Some(32 * 1_024));
+        assert_eq!(events.get_bloom_filter_bits(), Some(-1));
+        assert_eq!(events.get_compression_type(), Some(CompressionType::Lz4));
+        assert_eq!(events.get_max_memtable_size(), Some(config.max_memtable_size()));
+        assert_eq!(events.get_kv_separated(), Some(false));
+
+        let archive = config.partition_options_for("events_archive");
+        assert_eq!(archive.get_block_size(), Some(8 * 1_024));
+        assert_eq!(archive.get_bloom_filter_bits(), Some(10));
+
+        let settings = config.partition_options_for("settings");
+        assert_eq!(settings.get_block_size(), Some(4 * 1_024));
+        assert_eq!(settings.get_compression_type(), Some(CompressionType::None));
+    }
+
+    #[test]
+    fn test_partition_options_without_overrides() {
+        let config = FjallConfig::new();
+        config.set_block_size(16 * 1_024);
+        config.set_kv_separated(true);
+
+        let options = config.partition_options_for("any");
+        assert_eq!(options.get_block_size(), Some(16 * 1_024));
+        assert_eq!(options.get_kv_separated(), Some(true));
+        assert_eq!(options.get_bloom_filter_bits(), Some(10));
+        assert_eq!(PartitionOptions::new().get_block_size(), None);
     }
 
     #[test]
@@ -0,0 +1,206 @@
+use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Name of the lock file kept in the database directory.
+pub(crate) const LOCK_FILE_NAME: &str = "nitrite.lock";
+
+/// Interval between two attempts to take a lock held by another process.
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lock files held by this process, with the number of open stores sharing each.
+///
+/// An advisory lock guards against *other* processes. Stores of this process opened on the
+/// same path share one lock, so a store that was dropped without being closed does not lock
+/// the process out of its own database.
+static HELD_LOCKS: LazyLock<Mutex<HashMap<PathBuf, (File, usize)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// An advisory lock on a database directory, released when dropped.
+///
+/// The lock file records the id of the process holding the lock, so a second process
+/// opening the database can report who holds it.
+pub(crate) struct StoreLock {
+    path: PathBuf,
+}
+
+impl StoreLock {
+    /// Locks the database directory `db_path`, creating it if needed.
+    ///
+    /// If another process holds the lock, retries until `timeout` has passed. Without a
+    /// timeout it fails right away.
+    pub(crate) fn acquire(db_path: &str, timeout: Option<Duration>) -> NitriteResult<StoreLock> {
+        fs::create_dir_all(db_path)?;
+        let path = lock_path(db_path);
+
+        let mut held = HELD_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, count)) = held.get_mut(&path) {
+            *count += 1;
+            return Ok(StoreLock { path });
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {
+                    if deadline.is_some_and(|deadline| Instant::now() < deadline) {
+                        thread::sleep(LOCK_POLL_INTERVAL);
+                        continue;
+                    }
+                    return Err(locked_error(db_path, &path, timeout));
+                }
+                Err(TryLockError::Error(err)) => {
+                    log::error!("Failed to lock database {}: {}", db_path, err);
+                    return Err(err.into());
+                }
+            }
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+        file.sync_data()?;
+
+        held.insert(path.clone(), (file, 1));
+        Ok(StoreLock { path })
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let mut held = HELD_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, count)) = held.get_mut(&self.path) {
+            *count -= 1;
+            if *count == 0 {
+                // closing the file releases the lock
+                held.remove(&self.path);
+            }
+        }
+    }
+}
+
+fn lock_path(db_path: &str) -> PathBuf {
+    let dir = Path::new(db_path);
+    // one registry entry per directory, however the path was spelled
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    dir.join(LOCK_FILE_NAME)
+}
+
+fn locked_error(db_path: &str, lock_path: &Path, timeout: Option<Duration>) -> NitriteError {
+    let holder = read_holder(lock_path)
+        .map(|pid| format!("PID {}", pid))
+        .unwrap_or_else(|| "another process".to_string());
+    let message = match timeout {
+        Some(timeout) => format!(
+            "database {} is locked by {} (waited {} ms)",
+            db_path,
+            holder,
+            timeout.as_millis()
+        ),
+        None => format!("database {} is locked by {}", db_path, holder),
+    };
+    log::error!("{}", message);
+    NitriteError::new(&message, ErrorKind::FileAccessError)
+}
+
+/// Reads the process id recorded in the lock file, if it can be read.
+fn read_holder(lock_path: &Path) -> Option<u32> {
+    let mut content = String::new();
+    File::open(lock_path)
+        .and_then(|mut file| file.read_to_string(&mut content))
+        .ok()?;
+    content.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn random_path() -> String {
+        let id = uuid::Uuid::new_v4();
+        PathBuf::from("../test-data").join(id.to_string()).to_str().unwrap().to_string()
+    }
+
+    /// Holds the lock file the way another process would: through its own file handle.
+    fn lock_as_other_process(db_path: &str, pid: u32) -> File {
+        fs::create_dir_all(db_path).unwrap();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(lock_path(db_path))
+            .unwrap();
+        file.try_lock().unwrap();
+        write!(file, "{}", pid).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_lock_records_pid_and_is_shared_in_process() {
+        let db_path = random_path();
+        let first = StoreLock::acquire(&db_path, None).unwrap();
+        assert_eq!(read_holder(&lock_path(&db_path)), Some(std::process::id()));
+
+        // a second store of this process shares the lock
+        let second = StoreLock::acquire(&db_path, None).unwrap();
+        drop(first);
+        assert!(HELD_LOCKS.lock().unwrap().contains_key(&lock_path(&db_path)));
+        drop(second);
+        assert!(!HELD_LOCKS.lock().unwrap().contains_key(&lock_path(&db_path)));
+
+        let _ = fs::remove_dir_all(&db_path);
+    }
+
+    #[test]
+    fn test_lock_held_by_other_process_fails() {
+        let db_path = random_path();
+        let other = lock_as_other_process(&db_path, 4242);
+
+        let err = StoreLock::acquire(&db_path, None).err().unwrap();
+        assert_eq!(err.kind(), &ErrorKind::FileAccessError);
+        assert!(err.message().contains("is locked by PID 4242"));
+
+        drop(other);
+        let lock = StoreLock::acquire(&db_path, None).unwrap();
+        assert_eq!(read_holder(&lock_path(&db_path)), Some(std::process::id()));
+        drop(lock);
+
+        let _ = fs::remove_dir_all(&db_path);
+    }
+
+    #[test]
+    fn test_lock_waits_until_released() {
+        let db_path = random_path();
+        let other = lock_as_other_process(&db_path, 4242);
+
+        let err = StoreLock::acquire(&db_path, Some(Duration::from_millis(120)))
+            .err()
+            .unwrap();
+        assert!(err.message().contains("waited 120 ms"));
+
+        let release = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            drop(other);
+        });
+        let lock = StoreLock::acquire(&db_path, Some(Duration::from_secs(10))).unwrap();
+        release.join().unwrap();
+        drop(lock);
+
+        let _ = fs::remove_dir_all(&db_path);
+    }
+}
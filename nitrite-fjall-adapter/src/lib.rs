@@ -1,9 +1,12 @@
 extern crate core;
 
 mod config;
+mod file_lock;
 mod map;
 mod module;
 mod ordered_key;
+mod snapshot;
+mod split;
 mod store;
 mod tx_scope;
 mod version;
@@ -1,9 +1,12 @@
 use crate::config::FjallConfig;
+use crate::split::{configured_fields, is_splittable, split_map_name, SplitStorage};
 use crate::store::FjallStore;
 use crate::wrapper::FjallValue;
 use fjall::{GarbageCollection, TxPartitionHandle};
+use parking_lot::RwLock;
 use nitrite::common::{async_task, AttributeAware, Attributes, Key, Value, META_MAP_NAME};
 use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
+use nitrite::profiler::{timed, Phase};
 use nitrite::store::{
     EntryIterator, KeyIterator, NitriteMap, NitriteMapProvider, NitriteStore,
     SingleMapEntryProvider, SingleMapKeyProvider, SingleMapValueProvider, ValueIterator,
@@ -111,7 +114,7 @@ impl NitriteMapProvider for FjallMap {
     fn clear(&self) -> NitriteResult<()> {
         self.inner.clear()?;
         let clone = self.clone();
-        async_task(move || {
+        let cleanup = move || {
             // Safe cleanup: handle all errors gracefully without panicking in async task
             match clone.get_store() {
                 Ok(store) => {
@@ -142,7 +145,13 @@ impl NitriteMapProvider for FjallMap {
                     // Non-fatal - garbage collection is best-effort
                 }
             }
-        });
+        };
+
+        // on the database scheduler, closing the database waits for the cleanup
+        match self.inner.store.scheduler() {
+            Some(scheduler) => scheduler.execute(cleanup),
+            None => async_task(cleanup),
+        }
         Ok(())
     }
 
@@ -209,6 +218,17 @@ impl NitriteMapProvider for FjallMap {
         self.inner.put_all(entries)
     }
 
+    /// Removes the entries whose keys lie in `start..end` in one write transaction.
+    ///
+    /// Arguments:
+    /// - `start`: Lowest key to remove
+    /// - `end`: Key the removal stops at, not removed
+    ///
+    /// Returns: Number of entries removed
+    fn remove_range(&self, start: &Key, end: &Key) -> NitriteResult<u64> {
+        self.inner.remove_range(start, end)
+    }
+
     /// Returns the number of entries in this map.
     ///
     /// Returns: Number of key-value pairs
@@ -379,12 +399,16 @@ impl FjallMap {
 /// Thread-safe: Uses AtomicBool for state flags with Relaxed ordering for performance.
 struct FjallMapInner {
     name: String,
+    /// Decoded name of the map.
+    plain_name: String,
     overlay_key: Arc<str>,
     partition: TxPartitionHandle,
     closed: AtomicBool,
     dropped: AtomicBool,
     store: FjallStore,
     fjall_config: FjallConfig,
+    /// Split storage of the map, loaded from its attributes on first use.
+    split: RwLock<Option<Arc<SplitStorage>>>,
 }
 
 #[derive(Clone, Copy)]
@@ -410,14 +434,17 @@ impl FjallMapInner {
         fjall_config: FjallConfig,
     ) -> FjallMapInner {
         let overlay_key = Arc::<str>::from(name.as_str());
+        let plain_name = FjallStore::decode_name(&name);
         FjallMapInner {
             name,
+            plain_name,
             overlay_key,
             partition,
             store,
             closed: AtomicBool::new(false),
             dropped: AtomicBool::new(false),
             fjall_config,
+            split: RwLock::new(None),
         }
     }
 
@@ -465,18 +492,20 @@ impl FjallMapInner {
     /// instead of crashing the process.
     #[inline]
     fn decode_value(raw: FjallValue) -> NitriteResult<Value> {
-        raw.try_into_key().map_err(NitriteError::from)
+        timed(Phase::Deserialization, || raw.try_into_key()).map_err(NitriteError::from)
     }
 
     #[inline]
     fn decode_bytes(raw: &[u8]) -> NitriteResult<Value> {
-        bincode::serde::decode_from_slice(raw, bincode::config::legacy())
-            .map(|(value, _)| value)
-            .map_err(|err| {
-                NitriteError::from(crate::wrapper::FjallValueError::DeserializationError(
-                    err.to_string(),
-                ))
-            })
+        timed(Phase::Deserialization, || {
+            bincode::serde::decode_from_slice(raw, bincode::config::legacy())
+        })
+        .map(|(value, _)| value)
+        .map_err(|err| {
+            NitriteError::from(crate::wrapper::FjallValueError::DeserializationError(
+                err.to_string(),
+            ))
+        })
     }
 
     fn insert_in_tx(&self, key: FjallValue, value: FjallValue) -> NitriteResult<()> {
@@ -731,6 +760,36 @@ impl FjallMapInner {
         })
     }
 
+    /// Returns the split storage of the map, loading it from the split fields of the
+    /// collection options the first time.
+    fn split_storage(&self) -> NitriteResult<Arc<SplitStorage>> {
+        if let Some(split) = self.split.read().as_ref() {
+            return Ok(split.clone());
+        }
+
+        let split = Arc::new(self.load_split_storage()?);
+        *self.split.write() = Some(split.clone());
+        Ok(split)
+    }
+
+    fn load_split_storage(&self) -> NitriteResult<SplitStorage> {
+        if !is_splittable(&self.plain_name) {
+            return Ok(SplitStorage::inactive(&self.plain_name));
+        }
+
+        let fields = configured_fields(self.get_attributes()?.as_ref());
+        let store = self.get_store()?;
+        let split_name = split_map_name(&self.plain_name);
+        // the split map is kept once created, so documents split before the fields were
+        // unset are still put back together
+        let parts = if !fields.is_empty() || store.has_map(&split_name)? {
+            Some(store.open_map(&split_name)?)
+        } else {
+            None
+        };
+        Ok(SplitStorage::new(&self.plain_name, fields, parts))
+    }
+
     fn get_attributes(&self) -> NitriteResult<Option<Attributes>> {
         if !self.is_dropped()? {
             let store = self.get_store()?;
@@ -766,6 +825,8 @@ impl FjallMapInner {
             if name.ne(META_MAP_NAME) {
                 meta_map.put(Value::from(name), Value::from(attributes.to_document()))?;
             }
+            // the split fields may have changed
+            *self.split.write() = None;
         }
         Ok(())
     }
@@ -791,7 +852,18 @@ impl FjallMapInner {
 
         // Use normalized numeric types for keys to ensure consistent index behavior
         let normalized_key = FjallValue::try_from_key(key)?;
-        self.visible_value("get value from", &normalized_key)
+        let value = self.visible_value("get value from", &normalized_key)?;
+        match value {
+            Some(value) => {
+                let split = self.split_storage()?;
+                if split.is_active() {
+                    Ok(Some(split.join(key, value)?))
+                } else {
+                    Ok(Some(value))
+                }
+            }
+            None => Ok(None),
+        }
     }
 
     fn clear(&self) -> NitriteResult<()> {
@@ -810,7 +882,8 @@ impl FjallMapInner {
                 self.remove_in_tx(key)?;
             }
             Ok(())
-        })
+        })?;
+        self.split_storage()?.clear()
     }
 
     fn is_closed(&self) -> NitriteResult<bool> {
@@ -827,11 +900,26 @@ impl FjallMapInner {
         self.check_opened()?;
         // Read the current value first (through the active transaction if any), then delete it
         // within an atomic write transaction.
-        let value = self.get(key)?;
+        let split = self.split_storage()?;
+        if !split.is_active() {
+            let value = self.get(key)?;
+            let normalized_key = FjallValue::try_from_key(key)?;
+            self.store
+                .write_in_tx(|| self.remove_in_tx(normalized_key.as_ref().to_vec()))?;
+            return Ok(value);
+        }
+
         let normalized_key = FjallValue::try_from_key(key)?;
-        self.store
-            .write_in_tx(|| self.remove_in_tx(normalized_key.as_ref().to_vec()))?;
-        Ok(value)
+        self.store.write_in_tx(|| {
+            let stored = self.visible_value("remove value from", &normalized_key)?;
+            let value = stored
+                .clone()
+                .map(|stored| split.join(key, stored))
+                .transpose()?;
+            split.remove(key, stored.as_ref())?;
+            self.remove_in_tx(normalized_key.as_ref().to_vec())?;
+            Ok(value)
+        })
     }
 
     fn put(&self, key: Key, value: Value) -> NitriteResult<()> {
@@ -839,12 +927,29 @@ impl FjallMapInner {
         // Use normalized numeric types for keys to ensure consistent index behavior
         // across different numeric types (e.g., I64 vs U64)
         let normalized_key = FjallValue::try_from_key(&key)?;
+        let split = self.split_storage()?;
         self.store.write_in_tx(|| {
+            let value = self.split_value(&split, &key, &normalized_key, value)?;
             let fjall_value = FjallValue::try_from_value(&value)?;
             self.insert_in_tx(normalized_key, fjall_value)
         })
     }
 
+    /// Moves the split fields of a value about to be stored under `key` out of it.
+    fn split_value(
+        &self,
+        split: &SplitStorage,
+        key: &Key,
+        normalized_key: &FjallValue,
+        value: Value,
+    ) -> NitriteResult<Value> {
+        if !split.is_active() {
+            return Ok(value);
+        }
+        let previous = self.visible_value("put value in", normalized_key)?;
+        split.split(key, value, previous.as_ref())
+    }
+
     /// Inserts multiple key-value pairs as part of one atomic write transaction.
     ///
     /// Every entry lands in the same transaction as the rest of the enclosing atomic scope, so
@@ -857,9 +962,11 @@ impl FjallMapInner {
             return Ok(());
         }
 
+        let split = self.split_storage()?;
         self.store.write_in_tx(|| {
             for (key, value) in entries {
                 let normalized_key = FjallValue::try_from_key(&key)?;
+                let value = self.split_value(&split, &key, &normalized_key, value)?;
                 let fjall_value = FjallValue::try_from_value(&value)?;
                 self.insert_in_tx(normalized_key, fjall_value)?;
             }
@@ -867,6 +974,47 @@ impl FjallMapInner {
         })
     }
 
+    /// Removes the entries of a key range as part of one atomic write transaction.
+    ///
+    /// Fjall has no range tombstones, so the visible keys of the range are collected with
+    /// ordered seeks and removed together; stored values are only decoded when their split
+    /// fields must be removed with them.
+    fn remove_range(&self, start: &Key, end: &Key) -> NitriteResult<u64> {
+        self.check_opened()?;
+        let start_key = FjallValue::try_from_key(start)?;
+        let end_key = FjallValue::try_from_key(end)?;
+        let split = self.split_storage()?;
+        self.store.write_in_tx(|| {
+            let mut entries = Vec::new();
+            let mut next = self.visible_entry_raw(
+                "remove range from",
+                Some(start_key.as_ref()),
+                true,
+                SeekDirection::Forward,
+            )?;
+            while let Some((key, value)) = next.filter(|(key, _)| key.as_slice() < end_key.as_ref()) {
+                next = self.visible_entry_raw(
+                    "remove range from",
+                    Some(&key),
+                    false,
+                    SeekDirection::Forward,
+                )?;
+                entries.push((key, value));
+            }
+
+            let removed = entries.len() as u64;
+            for (raw_key, raw_value) in entries {
+                if split.is_active() {
+                    let key = Self::decode_value(FjallValue::from(raw_key.clone()))?;
+                    let stored = Self::decode_bytes(&raw_value)?;
+                    split.remove(&key, Some(&stored))?;
+                }
+                self.remove_in_tx(raw_key)?;
+            }
+            Ok(removed)
+        })
+    }
+
     fn size(&self) -> NitriteResult<u64> {
         self.check_opened()?;
         if !crate::tx_scope::in_scope() {
@@ -882,13 +1030,19 @@ impl FjallMapInner {
         let normalized_key = FjallValue::try_from_key(&key)?;
         // The read and the conditional insert run in one transaction so the check and the
         // write are atomic and read-your-writes consistent.
+        let split = self.split_storage()?;
         self.store.write_in_tx(|| {
             let existing = self.visible_value("get item from", &normalized_key)?;
-            if existing.is_none() {
-                let fjall_value = FjallValue::try_from_value(&value)?;
-                self.insert_in_tx(normalized_key.clone(), fjall_value)?;
+            match existing {
+                Some(existing) if split.is_active() => Ok(Some(split.join(&key, existing)?)),
+                Some(existing) => Ok(Some(existing)),
+                None => {
+                    let value = split.split(&key, value, None)?;
+                    let fjall_value = FjallValue::try_from_value(&value)?;
+                    self.insert_in_tx(normalized_key.clone(), fjall_value)?;
+                    Ok(None)
+                }
             }
-            Ok(existing)
         })
     }
 
@@ -991,6 +1145,13 @@ impl FjallMapInner {
         let name = self.get_name()?; // Get decoded name since remove_map will encode it
         store.remove_map(&name)?;
 
+        // the split fields go with the map; release the handle on them first
+        *self.split.write() = None;
+        let split_name = split_map_name(&name);
+        if is_splittable(&name) && store.has_map(&split_name)? {
+            store.remove_map(&split_name)?;
+        }
+
         Ok(())
     }
 
@@ -1074,7 +1235,7 @@ mod tests {
             .expect("Store keyspace should be initialized");
         let partition = keyspace
             .clone()
-            .open_partition("test_partition", fjall_config.partition_config())
+            .open_partition("test_partition", fjall_config.partition_config_for("test_partition"))
             .expect("Failed to open partition");
 
         let fjall_map = FjallMap::new(
@@ -2013,6 +2174,33 @@ mod tests {
 
     // =================== put_all batch write tests ===================
 
+    #[test]
+    fn test_remove_range() {
+        run_test(
+            create_context,
+            |ctx| {
+                let map = ctx.fjall_map_unsafe();
+                let entries = (0..10i64).map(|i| (Key::from(i), Value::from(i))).collect();
+                map.put_all(entries).unwrap();
+
+                let removed = map.remove_range(&Key::from(2i64), &Key::from(5i64)).unwrap();
+                assert_eq!(removed, 3);
+                assert_eq!(map.size().unwrap(), 7);
+                assert!(map.get(&Key::from(4i64)).unwrap().is_none());
+                assert_eq!(map.get(&Key::from(5i64)).unwrap(), Some(Value::from(5i64)));
+                assert_eq!(map.first_key().unwrap(), Some(Key::from(0i64)));
+                assert_eq!(map.higher_key(&Key::from(1i64)).unwrap(), Some(Key::from(5i64)));
+
+                // an empty or reversed range removes nothing
+                assert_eq!(map.remove_range(&Key::from(2i64), &Key::from(5i64)).unwrap(), 0);
+                assert_eq!(map.remove_range(&Key::from(9i64), &Key::from(0i64)).unwrap(), 0);
+                assert_eq!(map.remove_range(&Key::from(0i64), &Key::from(100i64)).unwrap(), 7);
+                assert!(map.is_empty().unwrap());
+            },
+            cleanup,
+        );
+    }
+
     #[test]
     fn test_put_all_empty_batch() {
         run_test(
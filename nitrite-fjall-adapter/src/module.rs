@@ -1,10 +1,15 @@
-use crate::config::{Durability, FjallConfig};
+use crate::config::{Durability, FjallConfig, PartitionOptions};
 use crate::store::FjallStore;
 use fjall::compaction::Strategy;
 use fjall::CompressionType;
-use nitrite::common::{NitriteModule, NitritePlugin, PluginRegistrar};
+use nitrite::common::{ModuleInfo, NitriteModule, NitritePlugin, PluginRegistrar};
+#[cfg(feature = "config")]
+use nitrite::config_file::StoreSettings;
+#[cfg(feature = "config")]
+use nitrite::errors::{ErrorKind, NitriteError};
 use nitrite::errors::NitriteResult;
 use nitrite::store::{NitriteStore, StoreEventListener, StoreModule};
+use std::time::Duration;
 
 /// Nitrite storage module using the Fjall key-value store.
 ///
@@ -59,6 +64,110 @@ impl FjallModule {
     pub fn with_config() -> FjallModuleBuilder {
         FjallModuleBuilder::new()
     }
+
+    /// Creates a module from the store settings of a nitrite configuration file.
+    ///
+    /// `path` is required. `cache_size` sets the block cache capacity and `durability` is
+    /// `on_commit` or `periodic`. A `preset` (`production`, `high_throughput` or
+    /// `low_memory`) is applied first; the other builder settings can be given by name,
+    /// e.g. `fsync_frequency = 500` or `kv_separated = true`.
+    ///
+    /// Requires the `config` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is missing, a setting is unknown or has a value of
+    /// the wrong type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let db = NitriteBuilder::from_config_file("nitrite.toml")
+    ///     .module_factory("fjall", |config| FjallModule::from_settings(config.store()))
+    ///     .open_or_create(None, None)?;
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_settings(settings: &StoreSettings) -> NitriteResult<FjallModule> {
+        let mut builder = FjallModule::with_config();
+
+        match settings.option("preset").map(|value| value.as_str()) {
+            None => {}
+            Some(Some("production")) => builder = builder.production_preset(),
+            Some(Some("high_throughput")) => builder = builder.high_throughput_preset(),
+            Some(Some("low_memory")) => builder = builder.low_memory_preset(),
+            Some(_) => return Err(invalid_setting("preset")),
+        }
+
+        match settings.path() {
+            Some(path) => builder = builder.db_path(path),
+            None => {
+                log::error!("The fjall store requires a path");
+                return Err(NitriteError::new(
+                    "The fjall store requires a path",
+                    ErrorKind::ValidationError,
+                ));
+            }
+        }
+        if let Some(cache_size) = settings.cache_size() {
+            builder = builder.block_cache_capacity(cache_size);
+        }
+        match settings.durability() {
+            None => {}
+            Some("on_commit") => builder = builder.durability(Durability::OnCommit),
+            Some("periodic") => builder = builder.durability(Durability::Periodic),
+            Some(_) => return Err(invalid_setting("durability")),
+        }
+
+        for (name, value) in settings.options() {
+            let invalid = || invalid_setting(name);
+            let as_u64 = || value.as_u64().ok_or_else(invalid);
+            let as_f32 = || value.as_f64().map(|value| value as f32).ok_or_else(invalid);
+            builder = match name.as_str() {
+                "preset" => builder,
+                "manual_journal_persist" => {
+                    builder.manual_journal_persist(value.as_bool().ok_or_else(invalid)?)
+                }
+                "kv_separated" => builder.kv_separated(value.as_bool().ok_or_else(invalid)?),
+                "flush_workers" => builder.flush_workers(as_u64()? as usize),
+                "compaction_workers" => builder.compaction_workers(as_u64()? as usize),
+                "blob_cache_capacity" => builder.blob_cache_capacity(as_u64()?),
+                "max_journaling_size" => builder.max_journaling_size(as_u64()?),
+                "max_write_buffer_size" => builder.max_write_buffer_size(as_u64()?),
+                "fsync_frequency" => {
+                    builder.fsync_frequency(u16::try_from(as_u64()?).map_err(|_| invalid())?)
+                }
+                "bloom_filter_bits" => {
+                    builder.bloom_filter_bits(u8::try_from(as_u64()?).map_err(|_| invalid())?)
+                }
+                "max_memtable_size" => {
+                    builder.max_memtable_size(u32::try_from(as_u64()?).map_err(|_| invalid())?)
+                }
+                "block_size" => {
+                    builder.block_size(u32::try_from(as_u64()?).map_err(|_| invalid())?)
+                }
+                "space_amp_factor" => builder.space_amp_factor(as_f32()?),
+                "staleness_threshold" => builder.staleness_threshold(as_f32()?),
+                "lock_timeout_ms" => builder.lock_timeout(Duration::from_millis(as_u64()?)),
+                _ => {
+                    log::error!("Unknown fjall store setting {}", name);
+                    return Err(NitriteError::new(
+                        &format!("Unknown fjall store setting {}", name),
+                        ErrorKind::ValidationError,
+                    ));
+                }
+            };
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(feature = "config")]
+fn invalid_setting(name: &str) -> NitriteError {
+    log::error!("Invalid value of fjall store setting {}", name);
+    NitriteError::new(
+        &format!("Invalid value of fjall store setting {}", name),
+        ErrorKind::ValidationError,
+    )
 }
 
 impl NitriteModule for FjallModule {
@@ -72,6 +181,13 @@ impl NitriteModule for FjallModule {
         let store = self.get_store()?;
         plugin_registrar.register_store_plugin(store)
     }
+
+    fn info(&self) -> ModuleInfo {
+        // released in lockstep with nitrite, so it needs the matching core version
+        ModuleInfo::new("nitrite-fjall-adapter", env!("CARGO_PKG_VERSION"))
+            .min_core_version(env!("CARGO_PKG_VERSION"))
+            .store()
+    }
 }
 
 impl StoreModule for FjallModule {
@@ -334,7 +450,47 @@ impl FjallModuleBuilder {
         self.store_config.set_staleness_threshold(staleness_threshold);
         self
     }
+
+    /// Waits up to `timeout` for another process to release the database lock when
+    /// opening the store.
+    ///
+    /// Only one process can open a database at a time. By default opening a database
+    /// held by another process fails right away with a "database is locked by PID ..."
+    /// error; a zero timeout restores that behaviour.
+    #[inline]
+    pub fn lock_timeout(self, timeout: Duration) -> Self {
+        self.store_config
+            .set_lock_timeout(Some(timeout).filter(|timeout| !timeout.is_zero()));
+        self
+    }
     
+    /// Overrides partition settings for the maps whose names match `pattern`.
+    ///
+    /// `*` matches any run of characters and `?` a single one. When several patterns
+    /// match a map, the one registered first wins, so register specific patterns before
+    /// general ones.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let module = FjallModule::with_config()
+    ///     .db_path("/data/app")
+    ///     .partition_options(
+    ///         "events",
+    ///         PartitionOptions::new()
+    ///             .block_size(32 * 1024)
+    ///             .compression_type(CompressionType::Lz4)
+    ///             .max_memtable_size(64 * 1024 * 1024),
+    ///     )
+    ///     .partition_options("$nitrite_index|events|*", PartitionOptions::new().block_size(16 * 1024))
+    ///     .build();
+    /// ```
+    #[inline]
+    pub fn partition_options(self, pattern: &str, options: PartitionOptions) -> Self {
+        self.store_config.add_partition_override(pattern, options);
+        self
+    }
+
     #[inline]
     pub fn build(self) -> FjallModule {
         FjallModule {
@@ -349,6 +505,8 @@ mod tests {
     use fjall::compaction::Strategy;
     use fjall::CompressionType;
     use nitrite::common::PluginRegistrar;
+    use nitrite::nitrite::Nitrite;
+    use nitrite::store::conformance::StoreConformance;
 
     #[inline(never)]
     fn black_box<T>(x: T) -> T {
@@ -361,6 +519,36 @@ mod tests {
         assert!(builder.store_config.db_path().is_empty());
     }
 
+    #[test]
+    fn test_fjall_module_builder_partition_options() {
+        let module = FjallModule::with_config()
+            .block_size(8 * 1_024)
+            .partition_options("events", PartitionOptions::new().block_size(64 * 1_024))
+            .build();
+        let overrides = module.store_config.partition_overrides();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].0, "events");
+        assert_eq!(
+            module.store_config.partition_options_for("events").get_block_size(),
+            Some(64 * 1_024)
+        );
+        assert_eq!(
+            module.store_config.partition_options_for("users").get_block_size(),
+            Some(8 * 1_024)
+        );
+    }
+
+    #[test]
+    fn test_fjall_module_builder_lock_timeout() {
+        let module = FjallModule::with_config()
+            .lock_timeout(Duration::from_millis(1_500))
+            .build();
+        assert_eq!(module.store_config.lock_timeout(), Some(Duration::from_millis(1_500)));
+
+        let module = FjallModule::with_config().lock_timeout(Duration::ZERO).build();
+        assert_eq!(module.store_config.lock_timeout(), None);
+    }
+
     #[test]
     fn test_fjall_module_plugins() {
         let module = FjallModule {
@@ -637,4 +825,26 @@ mod tests {
             black_box(module);
         }
     }
+
+    #[test]
+    fn test_fjall_store_conforms() {
+        let path = std::path::PathBuf::from("../test-data")
+            .join(uuid::Uuid::new_v4().to_string())
+            .to_str()
+            .unwrap()
+            .to_string();
+        let db_path = path.clone();
+        let report = StoreConformance::new(move || {
+            let module = FjallModule::with_config()
+                .db_path(&db_path)
+                .low_memory_preset()
+                .build();
+            Nitrite::builder().load_module(module).open_or_create(None, None)
+        })
+        .persistent(true)
+        .run();
+
+        let _ = std::fs::remove_dir_all(&path);
+        assert!(report.is_success(), "{}", report);
+    }
 }
\ No newline at end of file
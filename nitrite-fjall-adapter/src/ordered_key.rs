@@ -57,7 +57,7 @@ const ELEM_CONTINUE: u8 = 0x01;
 #[inline]
 fn ordered_f64_bits(f: f64) -> u64 {
     let bits = f.to_bits();
-    let mask = ((bits >> 63) as u64).wrapping_neg() | (1u64 << 63);
+    let mask = (bits >> 63).wrapping_neg() | (1u64 << 63);
     bits ^ mask
 }
 
@@ -0,0 +1,133 @@
+use crate::split::{join_parts, split_map_name};
+use crate::wrapper::{to_nitrite_error, FjallValue};
+use fjall::{ReadTransaction, TxPartitionHandle};
+use nitrite::common::{Key, Value};
+use nitrite::errors::{NitriteError, NitriteResult};
+use nitrite::store::{EntryIterator, EntryIteratorProvider, StoreSnapshotProvider};
+use std::collections::HashMap;
+use std::ops::Bound::{Excluded, Unbounded};
+use std::sync::Arc;
+
+/// Fjall-backed store snapshot.
+///
+/// Purpose: Provides a point-in-time view across all captured partitions by reading
+/// them through a single Fjall read transaction. Every partition is read at the same
+/// sequence number, so the view is consistent across collections and indexes.
+///
+/// Characteristics:
+/// - Read-only (writes go to the live keyspace and are never visible here)
+/// - Cloneable iteration state (each `entries` call starts a fresh cursor)
+/// - Keeps the snapshot's versions alive until the last iterator is dropped
+///
+/// Usage: Created by `FjallStore::open_snapshot`, wrapped in a `StoreSnapshot`.
+pub(crate) struct FjallStoreSnapshot {
+    read_tx: Arc<ReadTransaction>,
+    partitions: HashMap<String, TxPartitionHandle>,
+}
+
+impl FjallStoreSnapshot {
+    /// Creates a snapshot over the given partitions.
+    ///
+    /// Arguments:
+    /// - `read_tx`: Read transaction pinning the snapshot's sequence number
+    /// - `partitions`: Partition handles keyed by (decoded) map name, with the split
+    ///   fields of a map under the name of its split map
+    pub(crate) fn new(
+        read_tx: ReadTransaction,
+        partitions: HashMap<String, TxPartitionHandle>,
+    ) -> FjallStoreSnapshot {
+        FjallStoreSnapshot {
+            read_tx: Arc::new(read_tx),
+            partitions,
+        }
+    }
+}
+
+impl StoreSnapshotProvider for FjallStoreSnapshot {
+    fn entries(&self, map_name: &str) -> NitriteResult<EntryIterator> {
+        Ok(EntryIterator::new(SnapshotEntryProvider {
+            read_tx: self.read_tx.clone(),
+            map_name: map_name.to_string(),
+            partition: self.partitions.get(map_name).cloned(),
+            parts: self.partitions.get(&split_map_name(map_name)).cloned(),
+            current: None,
+        }))
+    }
+}
+
+/// Bidirectional cursor over one partition of a `FjallStoreSnapshot`.
+///
+/// Each step seeks from the current raw key, mirroring `SingleMapEntryProvider`, so the
+/// provider holds no borrowed Fjall iterator and stays `Send + Sync`.
+struct SnapshotEntryProvider {
+    read_tx: Arc<ReadTransaction>,
+    map_name: String,
+    partition: Option<TxPartitionHandle>,
+    /// Split fields of the documents of the partition.
+    parts: Option<TxPartitionHandle>,
+    current: Option<Vec<u8>>,
+}
+
+impl SnapshotEntryProvider {
+    fn step(&mut self, forward: bool) -> Option<NitriteResult<(Key, Value)>> {
+        let partition = self.partition.as_ref()?;
+        let bound = match &self.current {
+            Some(key) => Excluded(key.clone()),
+            None => Unbounded,
+        };
+
+        let next = if forward {
+            self.read_tx.range(partition, (bound, Unbounded)).next()
+        } else {
+            self.read_tx.range(partition, (Unbounded, bound)).next_back()
+        };
+
+        match next? {
+            Ok((key, value)) => {
+                self.current = Some(key.to_vec());
+                Some(decode_entry(&key, &value).and_then(|entry| self.join(entry)))
+            }
+            Err(err) => Some(Err(to_nitrite_error(err))),
+        }
+    }
+
+    /// Puts back the split fields of a document, as of the snapshot.
+    fn join(&self, (key, value): (Key, Value)) -> NitriteResult<(Key, Value)> {
+        let Some(parts) = &self.parts else {
+            return Ok((key, value));
+        };
+        let value = join_parts(&self.map_name, &key, value, |part_key| {
+            let raw_key = FjallValue::try_from_key(part_key)?;
+            self.read_tx
+                .get(parts, raw_key)
+                .map_err(to_nitrite_error)?
+                .map(|raw| {
+                    FjallValue::from(raw.to_vec())
+                        .try_into_value()
+                        .map_err(NitriteError::from)
+                })
+                .transpose()
+        })?;
+        Ok((key, value))
+    }
+}
+
+impl EntryIteratorProvider for SnapshotEntryProvider {
+    fn next_entry(&mut self) -> Option<NitriteResult<(Key, Value)>> {
+        self.step(true)
+    }
+
+    fn prev_entry(&mut self) -> Option<NitriteResult<(Key, Value)>> {
+        self.step(false)
+    }
+}
+
+fn decode_entry(key: &[u8], value: &[u8]) -> NitriteResult<(Key, Value)> {
+    let key = FjallValue::from(key.to_vec())
+        .try_into_key()
+        .map_err(NitriteError::from)?;
+    let value = FjallValue::from(value.to_vec())
+        .try_into_value()
+        .map_err(NitriteError::from)?;
+    Ok((key, value))
+}
@@ -0,0 +1,318 @@
+use nitrite::collection::Document;
+use nitrite::common::{Key, Value, COLLECTION_SPLIT_FIELDS};
+use nitrite::errors::NitriteResult;
+use nitrite::store::{is_field_requested, NitriteMap};
+
+/// Prefix of the maps holding the split fields of a collection.
+const SPLIT_MAP_PREFIX: &str = "$nitrite_split|";
+
+/// Field of a stored document listing its fields kept in the split map. It only exists
+/// in storage: documents are read with it replaced by the split fields.
+const SPLIT_MARKER: &str = "$nitrite_split";
+
+/// Returns the name of the map holding the split fields of the map `map_name`.
+pub(crate) fn split_map_name(map_name: &str) -> String {
+    format!("{}{}", SPLIT_MAP_PREFIX, map_name)
+}
+
+/// Returns `true` if the map `map_name` holds the split fields of another map.
+pub(crate) fn is_split_map(map_name: &str) -> bool {
+    map_name.starts_with(SPLIT_MAP_PREFIX)
+}
+
+/// Returns `true` if the map `map_name` may have split fields.
+pub(crate) fn is_splittable(map_name: &str) -> bool {
+    map_name != nitrite::common::META_MAP_NAME && !is_split_map(map_name)
+}
+
+/// Returns the fields the collection options of a map want split.
+pub(crate) fn configured_fields(attributes: Option<&nitrite::common::Attributes>) -> Vec<String> {
+    match attributes.and_then(|attributes| attributes.get(COLLECTION_SPLIT_FIELDS)) {
+        Some(Value::Array(fields)) => fields
+            .iter()
+            .filter_map(|field| field.as_string().cloned())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Key of a split field of a document in the split map. The key of the document comes
+/// first, so the split fields of a document are stored next to each other.
+pub(crate) fn part_key(key: &Key, field: &str) -> Key {
+    Value::Array(vec![key.clone(), Value::String(field.to_string())])
+}
+
+/// The split storage of a map: the fields to store apart from their documents and the map
+/// holding them.
+///
+/// Split fields are moved out of a document when it is written, each under
+/// `[document key, field]`, and the document is stored with a marker listing them. Reading
+/// the document puts back the split fields it lists, except those a projected query does
+/// not need, see [`is_field_requested`].
+pub(crate) struct SplitStorage {
+    map_name: String,
+    fields: Vec<String>,
+    parts: Option<NitriteMap>,
+}
+
+impl SplitStorage {
+    /// Split storage of a map that never split a field.
+    pub(crate) fn inactive(map_name: &str) -> Self {
+        SplitStorage {
+            map_name: map_name.to_string(),
+            fields: Vec::new(),
+            parts: None,
+        }
+    }
+
+    pub(crate) fn new(map_name: &str, fields: Vec<String>, parts: Option<NitriteMap>) -> Self {
+        SplitStorage {
+            map_name: map_name.to_string(),
+            fields,
+            parts,
+        }
+    }
+
+    /// Returns `true` if the documents of the map may have split fields, either because
+    /// fields are split or because some were before.
+    pub(crate) fn is_active(&self) -> bool {
+        self.parts.is_some()
+    }
+
+    /// Moves the split fields of a document about to be stored into the split map and
+    /// returns the rest of the document, replacing the split fields of the document it
+    /// overwrites.
+    pub(crate) fn split(&self, key: &Key, value: Value, previous: Option<&Value>) -> NitriteResult<Value> {
+        let Some(parts) = &self.parts else {
+            return Ok(value);
+        };
+
+        let previous_fields = previous.map(marker_fields).unwrap_or_default();
+        let Value::Document(mut document) = value else {
+            for field in &previous_fields {
+                parts.remove(&part_key(key, field))?;
+            }
+            return Ok(value);
+        };
+
+        let mut split = Vec::new();
+        for field in &self.fields {
+            let part = document.get(field)?;
+            if part == Value::Null {
+                continue;
+            }
+            document.remove(field)?;
+            parts.put(part_key(key, field), part)?;
+            split.push(Value::String(field.clone()));
+        }
+
+        for field in &previous_fields {
+            if !split.contains(&Value::String(field.clone())) {
+                parts.remove(&part_key(key, field))?;
+            }
+        }
+
+        if !split.is_empty() {
+            document.put(SPLIT_MARKER, Value::Array(split))?;
+        }
+        Ok(Value::Document(document))
+    }
+
+    /// Puts the split fields of a stored document back.
+    pub(crate) fn join(&self, key: &Key, value: Value) -> NitriteResult<Value> {
+        match &self.parts {
+            Some(parts) => join_parts(&self.map_name, key, value, |part_key| parts.get(part_key)),
+            None => Ok(value),
+        }
+    }
+
+    /// Removes the split fields of a removed document.
+    pub(crate) fn remove(&self, key: &Key, previous: Option<&Value>) -> NitriteResult<()> {
+        if let (Some(parts), Some(previous)) = (&self.parts, previous) {
+            for field in marker_fields(previous) {
+                parts.remove(&part_key(key, &field))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the split fields of every document.
+    pub(crate) fn clear(&self) -> NitriteResult<()> {
+        match &self.parts {
+            Some(parts) => parts.clear(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Puts back the split fields listed in a stored document of the map `map_name`, reading
+/// each with `get_part`.
+pub(crate) fn join_parts(
+    map_name: &str,
+    key: &Key,
+    value: Value,
+    mut get_part: impl FnMut(&Key) -> NitriteResult<Option<Value>>,
+) -> NitriteResult<Value> {
+    let Value::Document(mut document) = value else {
+        return Ok(value);
+    };
+    let fields = document_marker_fields(&document);
+    if fields.is_empty() {
+        return Ok(Value::Document(document));
+    }
+
+    document.remove(SPLIT_MARKER)?;
+    for field in fields {
+        if is_field_requested(map_name, &field) {
+            if let Some(part) = get_part(&part_key(key, &field))? {
+                document.put(field, part)?;
+            }
+        }
+    }
+    Ok(Value::Document(document))
+}
+
+/// Returns the split fields listed in a stored document.
+fn marker_fields(value: &Value) -> Vec<String> {
+    value
+        .as_document()
+        .map(document_marker_fields)
+        .unwrap_or_default()
+}
+
+fn document_marker_fields(document: &Document) -> Vec<String> {
+    match document.get(SPLIT_MARKER) {
+        Ok(Value::Array(fields)) => fields
+            .iter()
+            .filter_map(|field| field.as_string().cloned())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FjallModule;
+    use nitrite::collection::{CollectionOptions, NitriteId};
+    use nitrite::doc;
+    use nitrite::nitrite::Nitrite;
+    use nitrite::store::with_projection;
+    use std::sync::Arc;
+
+    fn open_db(path: &str) -> Nitrite {
+        let module = FjallModule::with_config()
+            .db_path(path)
+            .low_memory_preset()
+            .build();
+        Nitrite::builder()
+            .load_module(module)
+            .open_or_create(None, None)
+            .unwrap()
+    }
+
+    fn random_path() -> String {
+        std::path::PathBuf::from("../test-data")
+            .join(uuid::Uuid::new_v4().to_string())
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_split_fields_are_stored_apart() {
+        let path = random_path();
+        let db = open_db(&path);
+        let options = CollectionOptions::new()
+            .split_field("content")
+            .split_field("meta.thumbnail");
+        let files = db.collection_with_options("files", options).unwrap();
+        let id = files
+            .insert(doc! {
+                name: "report.pdf",
+                content: "0123456789",
+                meta: { thumbnail: "abc", pages: 3 },
+            })
+            .unwrap()
+            .affected_nitrite_ids()[0];
+
+        let store = db.store();
+        let map = store.open_map("files").unwrap();
+        let parts = store.open_map(&split_map_name("files")).unwrap();
+        let key = Value::NitriteId(id);
+        assert_eq!(parts.size().unwrap(), 2);
+        let map_names = store.map_names().unwrap();
+        assert!(map_names.contains("files"));
+        assert!(!map_names.contains(&split_map_name("files")));
+        assert_eq!(
+            parts.get(&part_key(&key, "content")).unwrap(),
+            Some(Value::from("0123456789"))
+        );
+
+        let document = map.get(&key).unwrap().unwrap();
+        let document = document.as_document().unwrap();
+        assert_eq!(document.get("content").unwrap(), Value::from("0123456789"));
+        assert_eq!(document.get("meta.thumbnail").unwrap(), Value::from("abc"));
+        assert_eq!(document.get("meta.pages").unwrap(), Value::from(3));
+        assert!(!document.contains_key(SPLIT_MARKER));
+
+        let fields: Arc<[String]> = Arc::from(vec!["name".to_string(), "meta.pages".to_string()]);
+        let projected = with_projection("files", &fields, || map.get(&key)).unwrap().unwrap();
+        let projected = projected.as_document().unwrap();
+        assert_eq!(projected.get("name").unwrap(), Value::from("report.pdf"));
+        assert_eq!(projected.get("content").unwrap(), Value::Null);
+        assert_eq!(projected.get("meta.thumbnail").unwrap(), Value::Null);
+        assert!(!projected.contains_key(SPLIT_MARKER));
+
+        // a field no longer present loses its part
+        map.put(key.clone(), Value::Document(doc! { name: "report.pdf", content: "new" }))
+            .unwrap();
+        assert_eq!(parts.size().unwrap(), 1);
+
+        map.remove(&key).unwrap();
+        assert!(parts.is_empty().unwrap());
+
+        // a range removal takes the parts along
+        map.put(key.clone(), Value::Document(doc! { name: "report.pdf", content: "again" }))
+            .unwrap();
+        assert_eq!(parts.size().unwrap(), 1);
+        let end = Value::NitriteId(NitriteId::create_id(id.id_value() + 1).unwrap());
+        assert_eq!(map.remove_range(&key, &end).unwrap(), 1);
+        assert!(parts.is_empty().unwrap());
+
+        db.close().unwrap();
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_split_fields_survive_unsetting_the_option() {
+        let path = random_path();
+        let id = NitriteId::new();
+        {
+            let db = open_db(&path);
+            let files = db
+                .collection_with_options("files", CollectionOptions::new().split_field("content"))
+                .unwrap();
+            let mut document = doc! { name: "a.txt", content: "hello" };
+            document.put("_id", id).unwrap();
+            files.insert(document).unwrap();
+            files.set_options(CollectionOptions::new()).unwrap();
+            db.close().unwrap();
+        }
+        {
+            let db = open_db(&path);
+            let files = db.collection("files").unwrap();
+            let document = files.get_by_id(&id).unwrap().unwrap();
+            assert_eq!(document.get("content").unwrap(), Value::from("hello"));
+
+            // rewriting the document stores it whole again
+            files.update_by_id(&id, &doc! { name: "b.txt" }, false).unwrap();
+            let parts = db.store().open_map(&split_map_name("files")).unwrap();
+            assert!(parts.is_empty().unwrap());
+            let document = files.get_by_id(&id).unwrap().unwrap();
+            assert_eq!(document.get("content").unwrap(), Value::from("hello"));
+            db.close().unwrap();
+        }
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
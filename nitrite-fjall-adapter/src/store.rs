@@ -1,23 +1,26 @@
 use crate::config::FjallConfig;
+use crate::file_lock::StoreLock;
 use crate::map::FjallMap;
+use crate::snapshot::FjallStoreSnapshot;
+use crate::split::{is_split_map, split_map_name};
 use crate::version::fjall_version;
 use crate::wrapper::to_nitrite_error;
 use crossbeam::sync::WaitGroup;
 use dashmap::DashMap;
 use fjall::{GarbageCollection, PersistMode, TxKeyspace, WriteTransaction};
 use nitrite::common::{
-    async_task, NitriteEventBus, NitritePlugin, NitritePluginProvider, SubscriberRef,
+    async_task, NitriteEventBus, NitritePlugin, NitritePluginProvider, Scheduler, SubscriberRef,
     COLLECTION_CATALOG,
 };
 use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
 use nitrite::nitrite_config::NitriteConfig;
 use nitrite::store::{
     NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider, StoreCatalog, StoreConfig,
-    StoreEventInfo, StoreEventListener, StoreEvents,
+    StoreEventInfo, StoreEventListener, StoreEvents, StoreSnapshot,
 };
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, OnceLock, RwLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
 #[derive(Clone)]
 /// Fjall-based store implementation.
@@ -58,6 +61,11 @@ impl FjallStore {
         }
     }
 
+    /// Returns the scheduler of the database, once the store is initialized.
+    pub(crate) fn scheduler(&self) -> Option<Scheduler> {
+        self.inner.nitrite_config.get().map(|config| config.scheduler())
+    }
+
     /// Encodes a map name to make it safe for Fjall partition names.
     /// 
     /// Fjall does not support certain characters in partition names (e.g., pipe `|`).
@@ -227,6 +235,10 @@ impl NitriteStoreProvider for FjallStore {
         self.inner.remove_map(&name)
     }
 
+    fn map_names(&self) -> NitriteResult<HashSet<String>> {
+        self.inner.map_names()
+    }
+
     fn subscribe(&self, listener: StoreEventListener) -> NitriteResult<Option<SubscriberRef>> {
         self.inner.subscribe(listener)
     }
@@ -246,6 +258,14 @@ impl NitriteStoreProvider for FjallStore {
     fn store_catalog(&self) -> NitriteResult<StoreCatalog> {
         self.inner.store_catalog(self.clone())
     }
+
+    fn open_snapshot(&self, map_names: &HashSet<String>) -> NitriteResult<StoreSnapshot> {
+        self.inner.open_snapshot(map_names)
+    }
+
+    fn alert(&self, event: StoreEvents) -> NitriteResult<()> {
+        self.inner.alert(event)
+    }
 }
 
 struct FjallStoreInner {
@@ -260,6 +280,8 @@ struct FjallStoreInner {
     store_config: FjallConfig,
     nitrite_config: OnceLock<NitriteConfig>,
     map_registry: DashMap<String, FjallMap>,
+    // Advisory lock keeping other processes out of the database while it is open.
+    file_lock: Mutex<Option<StoreLock>>,
 }
 
 impl FjallStoreInner {
@@ -271,6 +293,7 @@ impl FjallStoreInner {
             store_config: config,
             nitrite_config: OnceLock::new(),
             map_registry: DashMap::new(),
+            file_lock: Mutex::new(None),
         }
     }
 
@@ -387,6 +410,8 @@ impl FjallStoreInner {
         // databases (e.g. the test suite) exhausts the OS thread limit and hangs.
         let keyspace = self.keyspace.write().unwrap_or_else(|e| e.into_inner()).take();
         drop(keyspace);
+        // only now that the keyspace files are released may another process open them
+        self.file_lock.lock().unwrap_or_else(|e| e.into_inner()).take();
 
         self.closed
             .store(true, std::sync::atomic::Ordering::Relaxed);
@@ -427,6 +452,17 @@ impl FjallStoreInner {
     }
 
     fn open_or_create(&self) -> NitriteResult<()> {
+        // Fjall itself does not guard against a second process opening the same keyspace,
+        // which corrupts it, so take the database lock before touching any file.
+        let mut file_lock = self.file_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let acquired = file_lock.is_none();
+        if acquired {
+            *file_lock = Some(StoreLock::acquire(
+                self.store_config.db_path(),
+                self.store_config.lock_timeout(),
+            )?);
+        }
+
         let config = self.store_config.keyspace_config();
         // Open a transactional keyspace so a logical write can span the data partition and all
         // of its index partitions inside one atomic, cross-partition Fjall transaction.
@@ -443,6 +479,9 @@ impl FjallStoreInner {
             }
             Err(err) => {
                 log::error!("Failed to open or create keyspace: {}", err);
+                if acquired {
+                    file_lock.take();
+                }
                 Err(to_nitrite_error(err))
             }
         }
@@ -581,7 +620,9 @@ impl FjallStoreInner {
             let wait_group = WaitGroup::new();
             for map in &maps {
                 let cloned_keyspace = ks.clone();
-                let cloned_options = self.store_config.partition_config().clone();
+                let cloned_options = self
+                    .store_config
+                    .partition_config_for(&FjallStore::decode_name(map));
                 let space_amp_factor = self.store_config.space_amp_factor();
                 let stale_threshold = self.store_config.staleness_threshold();
                 let cloned_map = map.clone();
@@ -642,9 +683,11 @@ impl FjallStoreInner {
             // maintenance pass observes every partition persisted and reclaims the
             // entire sealed-journal backlog. `rotate_memtable_and_wait` is a no-op
             // for a partition whose memtable is already empty.
-            let options = self.store_config.partition_config();
             for map in &maps {
-                match ks.open_partition(map, options.clone()) {
+                let options = self
+                    .store_config
+                    .partition_config_for(&FjallStore::decode_name(map));
+                match ks.open_partition(map, options) {
                     Ok(partition) => {
                         if let Err(err) = partition.inner().rotate_memtable_and_wait() {
                             log::error!(
@@ -679,6 +722,56 @@ impl FjallStoreInner {
         }
     }
 
+    fn map_names(&self) -> NitriteResult<HashSet<String>> {
+        let Some(ks) = self.keyspace() else {
+            log::error!("Keyspace is not initialized");
+            return Err(NitriteError::new(
+                "Keyspace is not initialized",
+                ErrorKind::PluginError,
+            ));
+        };
+
+        // the split maps are part of the maps whose fields they hold
+        Ok(ks
+            .list_partitions()
+            .iter()
+            .map(|partition| FjallStore::decode_name(partition.trim()))
+            .filter(|name| !is_split_map(name))
+            .collect())
+    }
+
+    fn open_snapshot(&self, map_names: &HashSet<String>) -> NitriteResult<StoreSnapshot> {
+        let Some(ks) = self.keyspace() else {
+            return Err(NitriteError::new(
+                "Keyspace is not initialized",
+                ErrorKind::PluginError,
+            ));
+        };
+
+        // pin the sequence number first; partitions are only handles, their contents
+        // are read through the transaction at this instant
+        let read_tx = ks.read_tx();
+        let mut partitions = HashMap::with_capacity(map_names.len());
+        for map_name in map_names {
+            let name = FjallStore::encode_name(map_name);
+            if ks.partition_exists(&name) {
+                let config = self.store_config.partition_config_for(map_name);
+                let partition = self.open_partition_with_retry(&ks, &name, &config)?;
+                partitions.insert(map_name.clone(), partition);
+            }
+
+            // the split fields of the documents of the map, to put them back
+            let split_name = split_map_name(map_name);
+            let name = FjallStore::encode_name(&split_name);
+            if ks.partition_exists(&name) {
+                let config = self.store_config.partition_config_for(&split_name);
+                let partition = self.open_partition_with_retry(&ks, &name, &config)?;
+                partitions.insert(split_name, partition);
+            }
+        }
+        Ok(StoreSnapshot::new(FjallStoreSnapshot::new(read_tx, partitions)))
+    }
+
     fn open_map(&self, name: &str, fjall_store: FjallStore) -> NitriteResult<NitriteMap> {
         let mut closed = false;
         if let Some(map) = self.map_registry.get(name) {
@@ -696,7 +789,9 @@ impl FjallStoreInner {
         }
 
         if let Some(ks) = self.keyspace() {
-            let config = self.store_config.partition_config();
+            let config = self
+                .store_config
+                .partition_config_for(&FjallStore::decode_name(name));
 
             // Try to open the partition - with retry logic for deleted partitions
             let partition = self.open_partition_with_retry(&ks, name, &config)?;
@@ -733,7 +828,9 @@ impl FjallStoreInner {
         self.close_map(name)?;
 
         if let Some(ks) = self.keyspace() {
-            let options = self.store_config.partition_config();
+            let options = self
+                .store_config
+                .partition_config_for(&FjallStore::decode_name(name));
             match ks.open_partition(name, options) {
                 Ok(partition) => {
                     match ks.delete_partition(partition.clone()) {
@@ -830,6 +927,7 @@ impl Drop for FjallStoreInner {
 #[allow(clippy::assertions_on_constants)] // tests use assert!(true) as "reached without panic" markers
 mod tests {
     use super::*;
+    use crate::config::PartitionOptions;
     use crate::tests::{run_test, Context};
     use nitrite::store::StoreEventListener;
     use std::path::PathBuf;
@@ -1074,6 +1172,75 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_fjall_store_partition_options_per_map() {
+        run_test(|| {
+            let path = random_path();
+            let fjall_config = FjallConfig::new();
+            fjall_config.set_db_path(&path);
+            fjall_config.add_partition_override(
+                "events",
+                PartitionOptions::new().block_size(32 * 1_024),
+            );
+            fjall_config.add_partition_override(
+                "$nitrite_index|events|*",
+                PartitionOptions::new().block_size(16 * 1_024),
+            );
+            Context::new(path, None, None, Some(FjallStore::new(fjall_config)), None)
+        }, |ctx| {
+            let store = ctx.fjall_store_unsafe();
+            store.open_or_create().unwrap();
+            store.open_map("events").unwrap();
+            store.open_map("$nitrite_index|events|ts|non-unique").unwrap();
+            store.open_map("settings").unwrap();
+
+            let keyspace = store.keyspace().unwrap();
+            let block_size = |name: &str| {
+                let partition = keyspace
+                    .open_partition(&FjallStore::encode_name(name), Default::default())
+                    .unwrap();
+                let block_size = partition.inner().config.data_block_size;
+                block_size
+            };
+            assert_eq!(block_size("events"), 32 * 1_024);
+            assert_eq!(block_size("$nitrite_index|events|ts|non-unique"), 16 * 1_024);
+            assert_eq!(block_size("settings"), 4 * 1_024);
+        }, |ctx| {
+            cleanup(ctx);
+        });
+    }
+
+    #[test]
+    fn test_fjall_store_locked_by_other_process() {
+        run_test(|| {
+            create_context()
+        }, |ctx| {
+            let lock_path = PathBuf::from(ctx.path()).join(crate::file_lock::LOCK_FILE_NAME);
+            fs::create_dir_all(ctx.path()).unwrap();
+            // a separate handle locks the file the way another process would
+            let other = fs::File::create(&lock_path).unwrap();
+            other.try_lock().unwrap();
+            fs::write(&lock_path, "4242").unwrap();
+
+            let store = ctx.fjall_store_unsafe();
+            let err = store.open_or_create().err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::FileAccessError);
+            assert!(err.message().contains("is locked by PID 4242"));
+            assert!(store.inner.keyspace().is_none());
+
+            drop(other);
+            store.open_or_create().unwrap();
+            assert_eq!(fs::read_to_string(&lock_path).unwrap(), std::process::id().to_string());
+
+            let other = fs::File::open(&lock_path).unwrap();
+            assert!(other.try_lock().is_err());
+            store.close().unwrap();
+            assert!(other.try_lock().is_ok());
+        }, |ctx| {
+            cleanup(ctx);
+        });
+    }
+
     #[test]
     fn test_fjall_store_close_map() {
         run_test(|| {
@@ -2,6 +2,7 @@ use nitrite::collection::order_by;
 use nitrite::common::SortOrder;
 use nitrite::doc;
 use nitrite::filter::{all, and, field};
+use nitrite::fmt::Table;
 use nitrite::nitrite::Nitrite;
 use nitrite::repository::ObjectRepository;
 use nitrite_fjall_adapter::FjallModule;
@@ -123,6 +124,13 @@ fn main() -> ExampleResult<()> {
     let nearby = activity.find(spatial_field("location").within(seattle))?;
     println!("Spatial matches inside Seattle bounding box: {}", nearby.count());
 
+    println!("Activity log:");
+    let mut log = activity.find(all())?;
+    println!(
+        "{}",
+        Table::new().columns(&["task_slug", "summary"]).render_cursor(&mut log)?
+    );
+
     drop(tasks);
     drop(activity);
     db.close()?;
@@ -5,6 +5,8 @@ use nitrite::doc;
 use nitrite::errors::NitriteResult;
 use nitrite::nitrite::Nitrite;
 #[cfg(feature = "fjall")]
+use nitrite::nitrite_builder::NitriteBuilder;
+#[cfg(feature = "fjall")]
 use nitrite_fjall_adapter::FjallModule;
 use std::backtrace::Backtrace;
 use std::time::{Duration, Instant, SystemTime};
@@ -132,6 +134,29 @@ impl TestContext {
     }
 }
 
+/// Opens the Fjall-backed database at `path` with the low memory preset, for tests that
+/// close and reopen the same database.
+#[cfg(feature = "fjall")]
+pub fn open_fjall_db(path: &str) -> Nitrite {
+    open_fjall_db_with(path, |builder| builder)
+}
+
+/// Like [`open_fjall_db`], with `configure` applied to the builder before opening.
+#[cfg(feature = "fjall")]
+pub fn open_fjall_db_with(
+    path: &str,
+    configure: impl FnOnce(NitriteBuilder) -> NitriteBuilder,
+) -> Nitrite {
+    let storage_module = FjallModule::with_config()
+        .db_path(path)
+        .low_memory_preset()
+        .build();
+
+    configure(Nitrite::builder().load_module(storage_module))
+        .open_or_create(None, None)
+        .expect("failed to open Fjall-backed Nitrite database")
+}
+
 pub fn random_path() -> String {
     let id = uuid::Uuid::new_v4();
     let temp_dir = env::temp_dir();
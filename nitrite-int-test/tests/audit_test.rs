@@ -0,0 +1,107 @@
+//! The audit log on the Fjall store: entries are written with the operations and kept
+//! across a restart.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::audit::{AuditOptions, AuditQuery, REDACTED};
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+#[test]
+fn test_audit_log_records_writes() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let patients = db.collection("patients").unwrap();
+        patients.insert(doc! { name: "before" }).unwrap();
+
+        db.enable_audit(AuditOptions::new().redact("ssn")).unwrap();
+        let inserted = patients
+            .insert_many(vec![
+                doc! { name: "a", ssn: "078-05-1120" },
+                doc! { name: "b", ssn: "219-09-9999" },
+            ])
+            .unwrap();
+        patients
+            .update(field("ssn").eq("078-05-1120"), &doc! { name: "A" })
+            .unwrap();
+        // reads are not recorded by default
+        assert_eq!(patients.find(field("name").eq("b")).unwrap().count(), 1);
+        db.disable_audit();
+        patients.insert(doc! { name: "after" }).unwrap();
+
+        let log = db.audit_log().unwrap();
+        let entries = log.entries(&AuditQuery::new()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "insert_many");
+        assert_eq!(entries[0].collection, "patients");
+        assert_eq!(&entries[0].affected_ids, inserted.affected_nitrite_ids());
+        assert_eq!(entries[1].operation, "update");
+        assert_eq!(entries[1].filter.as_deref(), Some(format!("(ssn == {})", REDACTED).as_str()));
+        assert!(entries[0].timestamp <= entries[1].timestamp);
+
+        let first = inserted.affected_nitrite_ids()[0];
+        let updates = log
+            .entries(&AuditQuery::new().operation("update").affecting(first))
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        // the log is not a collection of the database
+        assert!(!db.list_collection_names().unwrap().iter().any(|name| name.contains("audit")));
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        assert!(db.audit_options().is_none());
+        let log = db.audit_log().unwrap();
+        assert_eq!(log.len().unwrap(), 2);
+        log.clear().unwrap();
+        assert!(log.is_empty().unwrap());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_audit_log_records_sessions_and_reads() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        db.enable_audit(AuditOptions::new().include_reads(true).max_entries(3))
+            .unwrap();
+
+        let session_id = db
+            .with_session(|session| {
+                let transaction = session.begin_transaction()?;
+                transaction.collection("orders")?.insert(doc! { item: "pen" })?;
+                transaction.commit()?;
+                Ok(session.id().to_string())
+            })
+            .unwrap();
+        let found = orders.find(field("item").eq("pen")).unwrap().count();
+        assert_eq!(found, 1);
+
+        let log = db.audit_log().unwrap();
+        let committed = log.entries(&AuditQuery::new().session(&session_id)).unwrap();
+        assert_eq!(committed.len(), 1);
+        assert_eq!(committed[0].operation, "insert");
+        assert!(committed[0].transaction_id.is_some());
+
+        let reads = log.entries(&AuditQuery::new().operation("find")).unwrap();
+        assert_eq!(reads.len(), 1);
+        assert_eq!(reads[0].filter.as_deref(), Some(r#"(item == "pen")"#));
+        assert!(reads[0].session_id.is_none());
+
+        // the log is capped, the oldest entries are evicted
+        for n in 0..5 {
+            orders.insert(doc! { n: n }).unwrap();
+        }
+        let entries = log.entries(&AuditQuery::new()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|entry| entry.operation == "insert" && entry.session_id.is_none()));
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,74 @@
+//! Bulk writes on the Fjall store: a failed batch must leave no partial write behind, in
+//! the documents or in the indexes, also after a reopen.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::{BulkOperation, BulkWriteOptions};
+use nitrite::doc;
+use nitrite::filter::{all, field};
+use nitrite::index::unique_index;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+#[test]
+fn test_failed_bulk_write_is_discarded() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let items = db.collection("items").unwrap();
+        items.create_index(vec!["sku"], &unique_index()).unwrap();
+        items.insert_many(vec![doc! { sku: "A", qty: 1 }, doc! { sku: "B", qty: 2 }]).unwrap();
+
+        let result = items
+            .bulk_write_with_options(
+                vec![
+                    BulkOperation::InsertOne(doc! { sku: "C", qty: 3 }),
+                    BulkOperation::UpdateMany { filter: all(), update: doc! { qty: 0 }, upsert: false },
+                    BulkOperation::DeleteOne(field("sku").eq("B")),
+                    BulkOperation::InsertOne(doc! { sku: "A" }),
+                ],
+                &BulkWriteOptions::new().ordered(false),
+            )
+            .unwrap();
+        assert!(!result.is_applied());
+        assert_eq!(result.errors().len(), 1);
+        assert_eq!(result.errors()[0].index(), 3);
+
+        // the unique index no longer holds the discarded `C`
+        items.insert(doc! { sku: "C", qty: 4 }).unwrap();
+        db.close().unwrap();
+    }
+
+    let db = open_fjall_db(&path);
+    let items = db.collection("items").unwrap();
+    assert_eq!(items.size().unwrap(), 3);
+    assert_eq!(items.find(field("qty").eq(0)).unwrap().count(), 0);
+    assert_eq!(items.find(field("sku").eq("B")).unwrap().count(), 1);
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_bulk_write_is_applied() {
+    let path = random_path();
+    let db = open_fjall_db(&path);
+    let items = db.collection("items").unwrap();
+    items.insert(doc! { sku: "A", qty: 1 }).unwrap();
+
+    let result = items
+        .bulk_write(vec![
+            BulkOperation::InsertOne(doc! { sku: "B", qty: 2 }),
+            BulkOperation::ReplaceOne {
+                filter: field("sku").eq("A"),
+                replacement: doc! { sku: "A", qty: 10 },
+                upsert: false,
+            },
+        ])
+        .unwrap();
+    assert!(result.is_applied());
+    assert_eq!(result.modified_count(), 1);
+    assert_eq!(items.find(field("qty").eq(10)).unwrap().count(), 1);
+    assert_eq!(items.size().unwrap(), 2);
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
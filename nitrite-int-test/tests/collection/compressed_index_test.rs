@@ -0,0 +1,123 @@
+//! Non-unique indexes stored as compressed posting lists.
+
+use nitrite::collection::NitriteCollection;
+use nitrite::doc;
+use nitrite::errors::{ErrorKind, NitriteResult};
+use nitrite::filter::{and, field};
+use nitrite::index::{non_unique_index, unique_index};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+const STATUSES: [&str; 3] = ["open", "pending", "closed"];
+
+fn insert_tickets(coll: &NitriteCollection, count: i64) -> NitriteResult<()> {
+    let docs: Vec<_> = (0..count)
+        .map(|i| {
+            doc! {
+                "status": (STATUSES[(i % 3) as usize]),
+                "priority": (i % 10),
+                "seq": i,
+            }
+        })
+        .collect();
+    coll.insert_many(docs)?;
+    Ok(())
+}
+
+#[test]
+fn test_compressed_index_queries() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("tickets")?;
+            coll.create_index(vec!["status"], &non_unique_index().compressed(true))?;
+            coll.create_index(vec!["priority"], &non_unique_index().compressed(true))?;
+            insert_tickets(&coll, 3_000)?;
+
+            assert_eq!(coll.find(field("status").eq("open"))?.count(), 1_000);
+            assert_eq!(coll.find(field("status").eq("missing"))?.count(), 0);
+            assert_eq!(
+                coll.find(field("status").in_array(vec!["open", "closed"]))?.count(),
+                2_000
+            );
+            assert_eq!(coll.find(field("status").not_in_array(vec!["open"]))?.count(), 2_000);
+
+            // bounded ranges and multi-bound intersections on one field
+            assert_eq!(
+                coll.find(and(vec![field("priority").gte(2), field("priority").lt(5)]))?
+                    .count(),
+                900
+            );
+            assert_eq!(
+                coll.find(and(vec![
+                    field("priority").gt(1),
+                    field("priority").gt(3),
+                    field("priority").lte(8),
+                ]))?
+                .count(),
+                1_500
+            );
+
+            let cursor = coll.find(field("status").eq("pending"))?;
+            let plan = cursor.find_plan().unwrap();
+            assert!(plan.index_descriptor().is_some_and(|it| it.is_compressed()));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_compressed_index_follows_writes() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("tickets")?;
+            coll.create_index(vec!["status"], &non_unique_index().compressed(true))?;
+            insert_tickets(&coll, 1_500)?;
+
+            coll.update(
+                and(vec![field("status").eq("open"), field("seq").lt(300)]),
+                &doc! { "status": "closed" },
+            )?;
+            assert_eq!(coll.find(field("status").eq("open"))?.count(), 400);
+            assert_eq!(coll.find(field("status").eq("closed"))?.count(), 600);
+
+            coll.remove(field("status").eq("pending"), false)?;
+            assert_eq!(coll.find(field("status").eq("pending"))?.count(), 0);
+            assert_eq!(coll.size()?, 1_000);
+
+            coll.rebuild_index(vec!["status"])?;
+            assert_eq!(coll.find(field("status").eq("closed"))?.count(), 600);
+            assert!(ctx.db().check_indexes()?.is_consistent());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_compressed_index_options_are_validated() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("tickets")?;
+            let error = coll
+                .create_index(vec!["status"], &unique_index().compressed(true))
+                .unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::IndexingError);
+
+            let error = coll
+                .create_index(vec!["status", "seq"], &non_unique_index().compressed(true))
+                .unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::IndexingError);
+
+            coll.create_index(vec!["status"], &non_unique_index())?;
+            let error = coll
+                .create_index(vec!["status"], &non_unique_index().compressed(true))
+                .unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::IndexingError);
+            Ok(())
+        },
+        cleanup,
+    )
+}
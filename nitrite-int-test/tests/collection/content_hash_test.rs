@@ -0,0 +1,151 @@
+use nitrite::collection::{CollectionEventListener, CollectionOptions, Document};
+use nitrite::common::{content_hash_field, Value};
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite::index::non_unique_index;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn with_id(mut document: Document, id: nitrite::collection::NitriteId) -> Document {
+    document.put("_id", id).unwrap();
+    document
+}
+
+#[test]
+fn test_insert_if_changed_skips_unchanged_documents() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("products")?;
+            collection.create_index(vec!["sku"], &non_unique_index())?;
+            let events = Arc::new(AtomicUsize::new(0));
+            let counter = events.clone();
+            collection.subscribe(CollectionEventListener::new(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }))?;
+
+            let result = collection.insert_if_changed(doc! { sku: "a-1", price: 10 })?;
+            let id = result.affected_nitrite_ids()[0];
+            let product = doc! { sku: "a-1", price: 10 };
+            assert_eq!(events.load(Ordering::SeqCst), 1);
+
+            // the same content, with another integer type, is not written again
+            let result = collection.insert_if_changed(with_id(doc! { sku: "a-1", price: 10i64 }, id))?;
+            assert!(result.affected_nitrite_ids().is_empty());
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.revision()?, 1);
+            assert_eq!(stored.content_hash(), product.content_hash());
+            assert_eq!(events.load(Ordering::SeqCst), 1);
+
+            let result = collection.insert_if_changed(with_id(doc! { price: 12 }, id))?;
+            assert_eq!(result.affected_nitrite_ids(), &vec![id]);
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.revision()?, 2);
+            assert_eq!(stored.get("sku")?, Value::from("a-1"));
+            assert_eq!(stored.get("price")?, Value::I32(12));
+            assert_eq!(collection.find(field("sku").eq("a-1"))?.count(), 1);
+            assert_eq!(events.load(Ordering::SeqCst), 2);
+
+            // an id that is not stored is inserted under that id
+            let other = nitrite::collection::NitriteId::new();
+            collection.insert_if_changed(with_id(doc! { sku: "b-2" }, other))?;
+            assert_eq!(collection.get_by_id(&other)?.unwrap().get("sku")?, Value::from("b-2"));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_stored_content_hash() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx
+                .db()
+                .collection_with_options("products", CollectionOptions::new().content_hash(true))?;
+            let result = collection.insert(doc! { sku: "a-1", price: 10 })?;
+            let id = result.affected_nitrite_ids()[0];
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.get(&content_hash_field())?, Value::U128(stored.content_hash()));
+            let first = stored.content_hash();
+
+            collection.update_by_id(&id, &doc! { price: 11 }, false)?;
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_ne!(stored.content_hash(), first);
+            assert_eq!(stored.get(&content_hash_field())?, Value::U128(stored.content_hash()));
+
+            // duplicates can be found by their stored hash
+            collection.insert(doc! { price: 11, sku: "a-1" })?;
+            let hash = Value::U128(stored.content_hash());
+            assert_eq!(collection.find(field(&content_hash_field()).eq(hash))?.count(), 2);
+
+            collection.set_options(CollectionOptions::new())?;
+            collection.update_by_id(&id, &doc! { price: 12 }, false)?;
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.get(&content_hash_field())?, Value::Null);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_insert_if_changed_in_transaction() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let collection = db.collection("products")?;
+            let id = collection.insert(doc! { sku: "a-1", price: 10 })?.affected_nitrite_ids()[0];
+
+            db.with_session(|session| {
+                let transaction = session.begin_transaction()?;
+                let products = transaction.collection("products")?;
+                let result = products.insert_if_changed(with_id(doc! { price: 10 }, id))?;
+                assert!(result.affected_nitrite_ids().is_empty());
+                let result = products.insert_if_changed(with_id(doc! { price: 15 }, id))?;
+                assert_eq!(result.affected_nitrite_ids(), &vec![id]);
+                transaction.commit()
+            })?;
+
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.get("price")?, Value::I32(15));
+            assert_eq!(stored.revision()?, 2);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_insert_if_changed_uses_stored_hash() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let collection =
+                db.collection_with_options("products", CollectionOptions::new().content_hash(true))?;
+            let id = collection.insert(doc! { sku: "a-1", price: 10 })?.affected_nitrite_ids()[0];
+
+            let result = collection.insert_if_changed(with_id(doc! { price: 10 }, id))?;
+            assert!(result.affected_nitrite_ids().is_empty());
+
+            // the comparison trusts the stored hash, so a stale one lets the same content through
+            let map = db.store().open_map("products")?;
+            let mut stored = map.get(&Value::NitriteId(id))?.unwrap().as_document().unwrap().clone();
+            stored.put(content_hash_field(), Value::U128(0))?;
+            map.put(Value::NitriteId(id), Value::Document(stored))?;
+
+            let result = collection.insert_if_changed(with_id(doc! { price: 10 }, id))?;
+            assert_eq!(result.affected_nitrite_ids(), &vec![id]);
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.revision()?, 2);
+            assert_eq!(stored.get(&content_hash_field())?, Value::U128(stored.content_hash()));
+            Ok(())
+        },
+        cleanup,
+    )
+}
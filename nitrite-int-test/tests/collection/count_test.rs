@@ -0,0 +1,73 @@
+use nitrite::collection::NitriteCollection;
+use nitrite::doc;
+use nitrite::errors::NitriteResult;
+use nitrite::filter::{all, and, field, or};
+use nitrite::index::{non_unique_index, unique_index};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+fn insert_messages(coll: &NitriteCollection) -> NitriteResult<()> {
+    let docs = (0..60)
+        .map(|i| {
+            doc! {
+                "seq": i,
+                "folder": (if i % 3 == 0 { "inbox" } else { "archive" }),
+                "read": (i % 2 == 0),
+            }
+        })
+        .collect();
+    coll.insert_many(docs)?;
+    Ok(())
+}
+
+#[test]
+fn test_count_matches_find() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("messages")?;
+            coll.create_index(vec!["folder"], &non_unique_index())?;
+            coll.create_index(vec!["seq"], &unique_index())?;
+            insert_messages(&coll)?;
+
+            let filters = vec![
+                all(),
+                field("folder").eq("inbox"),
+                field("folder").eq("missing"),
+                field("seq").eq(7),
+                field("seq").between(10, 19, true, true),
+                and(vec![field("folder").eq("inbox"), field("read").eq(false)]),
+                or(vec![field("folder").eq("inbox"), field("seq").lt(5)]),
+                field("read").eq(true),
+            ];
+            for filter in filters {
+                let expected = coll.find(filter.clone())?.count() as u64;
+                assert_eq!(coll.count(filter)?, expected);
+            }
+            assert_eq!(coll.count(field("folder").eq("inbox"))?, 20);
+            assert_eq!(coll.count(all())?, 60);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_count_follows_writes() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("messages")?;
+            coll.create_index(vec!["folder"], &non_unique_index())?;
+            insert_messages(&coll)?;
+
+            coll.update(field("seq").lt(6), &doc! { folder: "trash" })?;
+            coll.remove(field("folder").eq("archive"), false)?;
+            assert_eq!(coll.count(field("folder").eq("trash"))?, 6);
+            assert_eq!(coll.count(field("folder").eq("inbox"))?, 18);
+            assert_eq!(coll.count(field("folder").eq("archive"))?, 0);
+            assert_eq!(coll.count(all())?, coll.size()?);
+            Ok(())
+        },
+        cleanup,
+    )
+}
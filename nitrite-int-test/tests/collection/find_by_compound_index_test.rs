@@ -1,6 +1,9 @@
-use nitrite::common::Value;
-use nitrite::filter::{and, field, or};
-use nitrite::index::unique_index;
+use nitrite::collection::{Document, FindOptions};
+use nitrite::common::{SortOrder, Value};
+use nitrite::doc;
+use nitrite::filter::{and, field, or, Filter};
+use nitrite::fmt::render_plan;
+use nitrite::index::{non_unique_index, unique_index};
 use nitrite_int_test::test_util::{cleanup, create_test_context, insert_test_documents, run_test};
 
 #[test]
@@ -99,4 +102,119 @@ fn test_find_by_or_filter_and_filter() {
         },
         cleanup,
     )
-}
\ No newline at end of file
+}
+
+fn projected(
+    coll: &nitrite::collection::NitriteCollection,
+    filter: Filter,
+    options: FindOptions,
+    projection: &Document,
+) -> nitrite::errors::NitriteResult<(bool, Vec<Document>)> {
+    let mut cursor = coll.find_with_options(filter, &options.project(projection.clone()))?;
+    let covered = cursor.find_plan().unwrap().is_covered();
+    if covered {
+        assert!(render_plan(cursor.find_plan().unwrap()).contains("covered: true"));
+    }
+    let documents = cursor.by_ref().collect::<Result<Vec<_>, _>>()?;
+    Ok((covered, documents))
+}
+
+#[test]
+fn test_find_covered_by_compound_index() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            coll.create_index(vec!["city", "age"], &non_unique_index())?;
+            for i in 0..40 {
+                coll.insert(doc! {
+                    "city": (format!("city{}", i % 4)),
+                    "age": (20 + i % 10),
+                    "name": (format!("name{}", i)),
+                })?;
+            }
+
+            let projection = doc! { "city": (Value::Null), "age": (Value::Null) };
+            let sorted = || FindOptions::new().sort_by("age".to_string(), SortOrder::Descending);
+            let unsorted = || FindOptions::new();
+            let paged = || sorted().skip(3).limit(5);
+            // ties of a sort make a page of results vary, only its size is compared
+            let cases: Vec<(Filter, &dyn Fn() -> FindOptions, bool)> = vec![
+                (and(vec![field("city").eq("city1"), field("age").gte(25)]), &sorted, false),
+                (field("city").in_array(vec!["city0", "city3"]), &unsorted, false),
+                (field("city").gt("city1"), &sorted, false),
+                (field("city").lte("city1"), &paged, true),
+            ];
+            for (filter, options, paged) in cases {
+                let (covered, documents) = projected(&coll, filter.clone(), options(), &projection)?;
+                assert!(covered, "{}", filter);
+
+                let expected = coll
+                    .find_with_options(filter, &options())?
+                    .project(projection.clone())?
+                    .collect::<Result<Vec<_>, _>>()?;
+                assert!(!documents.is_empty());
+                if paged {
+                    assert_eq!(documents.len(), expected.len());
+                } else {
+                    let mut documents = documents;
+                    let mut expected = expected;
+                    documents.sort_by_key(|document| format!("{:?}", document));
+                    expected.sort_by_key(|document| format!("{:?}", document));
+                    assert_eq!(documents, expected);
+                }
+            }
+
+            // a field outside the index is read from the documents
+            let with_name = doc! { "city": (Value::Null), "name": (Value::Null) };
+            let (covered, documents) =
+                projected(&coll, field("city").eq("city2"), FindOptions::new(), &with_name)?;
+            assert!(!covered);
+            assert_eq!(documents.len(), 10);
+            assert!(documents.iter().all(|document| document.get("name").unwrap().is_string()));
+
+            // removed documents leave the index and the covered results
+            coll.remove(field("city").eq("city1"), false)?;
+            let (covered, documents) =
+                projected(&coll, field("city").eq("city1"), FindOptions::new(), &projection)?;
+            assert!(covered);
+            assert!(documents.is_empty());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_not_covered_by_inexact_compound_index() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            coll.create_index(vec!["tags", "rank"], &non_unique_index())?;
+            coll.insert(doc! { "tags": ["a", "b"], "rank": 1 })?;
+            coll.insert(doc! { "tags": "a", "rank": 2 })?;
+
+            // a multikey key holds one element of the array, not the array
+            let projection = doc! { "tags": (Value::Null), "rank": (Value::Null) };
+            let (covered, documents) =
+                projected(&coll, field("tags").eq("a"), FindOptions::new(), &projection)?;
+            assert!(!covered);
+            assert_eq!(documents.len(), 2);
+            assert!(documents
+                .iter()
+                .any(|document| document.get("tags").unwrap().is_array()));
+
+            let other = ctx.db().collection("other")?;
+            other.create_index(vec!["code", "rank"], &unique_index())?;
+            other.insert(doc! { "code": (Value::U32(7)), "rank": 1 })?;
+            let projection = doc! { "code": (Value::Null), "rank": (Value::Null) };
+            let (covered, documents) =
+                projected(&other, field("code").eq(Value::U32(7)), FindOptions::new(), &projection)?;
+            assert!(!covered);
+            assert_eq!(documents[0].get("code")?, Value::U32(7));
+            Ok(())
+        },
+        cleanup,
+    )
+}
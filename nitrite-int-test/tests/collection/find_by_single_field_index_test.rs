@@ -1,6 +1,7 @@
 use nitrite::collection::order_by;
 use nitrite::common::{SortOrder, Value};
-use nitrite::filter::{all, and, field, or};
+use nitrite::doc;
+use nitrite::filter::{all, and, field, not, or, param, Params, PreparedFilter};
 use nitrite::index::{full_text_index, non_unique_index, unique_index};
 use nitrite_int_test::test_util::{
     cleanup, create_test_context, insert_test_documents, is_sorted, now, run_test
@@ -438,4 +439,102 @@ fn test_find_text_with_only_wild_card() {
         },
         cleanup,
     )
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_find_with_prepared_filter() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            for age in 0..20 {
+                coll.insert(doc! { age: age, group: (age % 2) })?;
+            }
+            coll.create_index(vec!["age"], &non_unique_index())?;
+
+            let prepared = PreparedFilter::new(
+                field("age").gte(param("min")).and(field("group").eq(param("group"))),
+            )?;
+            for (min, group, expected) in [(10, 0, 5), (15, 1, 3), (0, 1, 10)] {
+                let cursor = coll.find(prepared.bind(&Params::new().set("min", min).set("group", group))?)?;
+                assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+                assert_eq!(cursor.count(), expected);
+            }
+
+            // the index choice of the shape is forgotten with the index
+            coll.drop_index(vec!["age"])?;
+            let cursor = coll.find(prepared.bind(&Params::new().set("min", 10).set("group", 0))?)?;
+            assert!(cursor.find_plan().unwrap().index_descriptor().is_none());
+            assert_eq!(cursor.count(), 5);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_aggregate_indexed_field() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            for (item, price) in [("pen", 2), ("book", 12), ("lamp", 25)] {
+                coll.insert(doc!{ "item": item, "price": price })?;
+            }
+            coll.insert(doc!{ "item": "gift", "price": "free" })?;
+            let mut bag = doc!{ "item": "bag", "price": 30 };
+            bag.set_field_expiry("price", 1)?;
+            coll.insert(bag)?;
+            coll.create_index(vec!["price"], &non_unique_index())?;
+            coll.create_index(vec!["item"], &unique_index())?;
+
+            assert_eq!(coll.find(all())?.min("price")?, Some(Value::I32(2)));
+            // the index still holds the expired price, which is not taken for the bound
+            assert_eq!(coll.find(all())?.max("price")?, Some(Value::I32(25)));
+            assert_eq!(coll.find(all())?.sum("price")?, 39.0);
+            assert_eq!(coll.find(all())?.avg("price")?, Some(13.0));
+            assert_eq!(coll.find(all())?.percentile("price", 50.0)?, Some(12.0));
+            assert_eq!(coll.find(field("price").gt(2))?.min("price")?, Some(Value::I32(12)));
+            assert_eq!(coll.find(all())?.max("item")?, None);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_with_normalized_negation() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            for age in 0..20 {
+                coll.insert(doc! { age: age, group: (age % 4) })?;
+            }
+            coll.insert(doc! { age: [3, 30], group: 3 })?;
+            coll.insert(doc! { group: 1 })?;
+
+            let filters = || {
+                vec![
+                    (not(or(vec![field("age").ne(3), field("group").eq(0)])), 1),
+                    (not(not(field("age").eq(15))), 1),
+                    (not(field("age").not_in_array(vec![1, 2, 30])), 2),
+                    (and(vec![field("age").gt(2), field("age").gt(5), field("age").lte(8)]), 3),
+                ]
+            };
+            for (filter, expected) in filters() {
+                assert_eq!(coll.find(filter)?.count(), expected);
+            }
+
+            // with an index, the same documents are found through it
+            coll.create_index(vec!["age"], &non_unique_index())?;
+            for (filter, expected) in filters() {
+                let cursor = coll.find(filter)?;
+                assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+                assert_eq!(cursor.count(), expected);
+            }
+            Ok(())
+        },
+        cleanup,
+    )
+}
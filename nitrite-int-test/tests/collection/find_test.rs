@@ -1,12 +1,17 @@
 use chrono::{DateTime, Utc};
 use icu::locale::locale;
 use icu_collator::options::CollatorOptions;
-use nitrite::collection::{limit_to, order_by, skip_by, NitriteId};
+use nitrite::collection::{
+    limit_to, order_by, skip_by, CancellationToken, FindOptions, NitriteId, RedactionPolicy,
+};
 use nitrite::common::{SortOrder, Value};
 use nitrite::doc;
-use nitrite::filter::{all, and, by_id, field, or};
+use nitrite::errors::ErrorKind;
+use nitrite::filter::{all, and, by_id, by_id_range, field, or, text_any, text_any_indexed};
+use nitrite::fmt::{render_plan, Table};
 use nitrite::index::{non_unique_index, unique_index};
 use nitrite_int_test::test_util::{cleanup, create_test_context, create_test_docs, insert_test_documents, is_sorted, now, run_test, NitriteDateTime};
+use std::time::Duration;
 
 #[test]
 fn test_find_all() {
@@ -775,6 +780,78 @@ fn test_elem_match_filter() {
     )
 }
 
+#[test]
+fn test_elem_match_filter_with_multikey_index() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            coll.create_index(vec!["product_scores.score"], &non_unique_index())?;
+            coll.create_index(vec!["str_array"], &non_unique_index())?;
+
+            coll.insert(doc!{
+                "product_scores": [
+                    { "product": "abc", "score": 10 },
+                    { "product": "xyz", "score": 5 }
+                ],
+                "str_array": ["a", "b"]
+            })?;
+            coll.insert(doc!{
+                "product_scores": [
+                    { "product": "abc", "score": 8 },
+                    { "product": "xyz", "score": 7 }
+                ],
+                "str_array": ["d", "e"]
+            })?;
+            coll.insert(doc!{
+                "product_scores": [
+                    { "product": "abc", "score": 7 },
+                    { "product": "xyz", "score": 8 }
+                ],
+                "str_array": ["a", "f"]
+            })?;
+
+            // the index narrows to documents with any score >= 8, the element match
+            // keeps only those where the same element is a "xyz" product
+            let cursor = coll.find(field("product_scores").elem_match(
+                field("score").gte(8).and(field("product").eq("xyz"))
+            ))?;
+            let find_plan = cursor.find_plan().unwrap();
+            assert!(find_plan.index_descriptor().is_some());
+            assert!(find_plan.full_scan_filter().is_some());
+            assert_eq!(cursor.count(), 1);
+
+            let cursor = coll.find(field("product_scores").elem_match(
+                field("score").gt(9).and(field("product").eq("xyz"))
+            ))?;
+            assert_eq!(cursor.count(), 0);
+
+            let cursor = coll.find(field("str_array").elem_match(field("$").eq("a")))?;
+            assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+            assert_eq!(cursor.count(), 2);
+
+            // nested arrays inside the elements
+            coll.insert(doc!{
+                "orders": [
+                    { "qty": 2, "items": [{ "sku": "a" }, { "sku": "b" }] },
+                    { "qty": 1, "items": [{ "sku": "c" }] }
+                ]
+            })?;
+            let cursor = coll.find(field("orders").elem_match(
+                field("qty").gt(1).and(field("items").elem_match(field("sku").eq("b")))
+            ))?;
+            assert_eq!(cursor.count(), 1);
+            let cursor = coll.find(field("orders").elem_match(
+                field("qty").gt(1).and(field("items").elem_match(field("sku").eq("c")))
+            ))?;
+            assert_eq!(cursor.count(), 0);
+
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
 #[test]
 fn test_not_equal_filter() {
     run_test(
@@ -1263,6 +1340,40 @@ fn test_by_id_filter() {
     )
 }
 
+#[test]
+fn test_by_id_range_filter() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("tag")?;
+
+            let mut ids = Vec::new();
+            for age in 30..40 {
+                let result = collection.insert(doc!{ "age": age })?;
+                ids.extend(result.affected_nitrite_ids());
+            }
+
+            let cursor = collection.find(by_id_range(ids[3], ids[7]))?;
+            let ages = cursor
+                .map(|doc| doc.and_then(|doc| doc.get("age")))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(ages, (33..=37).map(Value::from).collect::<Vec<_>>());
+
+            let result = collection.find(and(vec![
+                by_id_range(ids[3], ids[7]),
+                field("age").gt(35),
+            ]))?;
+            assert_eq!(result.count(), 2);
+
+            let (start, end) = NitriteId::from_time_range(ids[0].timestamp(), ids[9].timestamp())?;
+            assert_eq!(collection.find(by_id_range(start, end))?.count(), 10);
+
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
 #[test]
 fn test_by_non_existing_id() {
     run_test(
@@ -2163,3 +2274,282 @@ fn test_iter_with_id_verify_same_id_as_get_by_id() {
     );
 }
 
+
+#[test]
+fn test_find_or_with_overlapping_branches() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            insert_test_documents(&coll)?;
+
+            // both branches match the same document, which must be returned once
+            let filter = || or(vec![field("first_name").eq("fn1"), field("last_name").eq("ln1")]);
+            let documents: Vec<_> = coll.find(filter())?.collect();
+            assert_eq!(documents.len(), 1);
+
+            coll.create_index(vec!["first_name"], &non_unique_index())?;
+            let documents: Vec<_> = coll.find(filter())?.collect();
+            assert_eq!(documents.len(), 1);
+
+            coll.create_index(vec!["last_name"], &non_unique_index())?;
+            let cursor = coll.find(filter())?;
+            assert!(cursor.find_plan().unwrap().sub_plans().is_some());
+            let documents: Vec<_> = cursor.collect();
+            assert_eq!(documents.len(), 1);
+
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_or_unions_index_results() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            for i in 0..10 {
+                coll.insert(doc! { "a": i, "b": (i * 10), "c": (i % 2) })?;
+            }
+            coll.create_index(vec!["a"], &non_unique_index())?;
+            coll.create_index(vec!["b"], &non_unique_index())?;
+            let mut first = coll.find(field("a").eq(0))?.next().unwrap()?;
+            let first_id = first.id()?;
+
+            // both branches are answered by their index; the union is counted from the ids
+            let cursor = coll.find(or(vec![field("a").eq(1), field("b").gte(70)]))?;
+            let sub_plans = cursor.find_plan().unwrap().sub_plans().unwrap();
+            assert!(sub_plans.iter().all(|plan| plan.index_descriptor().is_some()));
+            assert_eq!(cursor.count(), 4);
+
+            // an id lookup is a branch like an index
+            let cursor = coll.find(or(vec![by_id(first_id), field("b").eq(90), field("a").eq(9)]))?;
+            assert!(cursor.find_plan().unwrap().sub_plans().is_some());
+            assert_eq!(cursor.count(), 2);
+
+            // the rest of a branch is checked on the documents its index found
+            let filter = or(vec![
+                and(vec![field("a").lt(4), field("c").eq(1)]),
+                field("b").eq(20),
+            ]);
+            let cursor = coll.find(filter)?;
+            assert!(cursor.find_plan().unwrap().sub_plans().is_some());
+            let mut numbers: Vec<_> = cursor.map(|doc| doc.unwrap().get("a").unwrap()).collect();
+            numbers.sort();
+            assert_eq!(numbers, vec![Value::I32(1), Value::I32(2), Value::I32(3)]);
+
+            // an unindexed branch scans the collection once for the whole filter
+            let cursor = coll.find(or(vec![field("a").eq(1), field("c").eq(0)]))?;
+            assert!(cursor.find_plan().unwrap().sub_plans().is_none());
+            assert_eq!(cursor.count(), 6);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_text_any() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            coll.insert_many(vec![
+                doc!{ "name": "Ada Lovelace", "city": "London", "notes": ["first programmer"] },
+                doc!{ "name": "Alan Turing", "city": "Wilmslow", "notes": ["codebreaker"] },
+                doc!{ "name": "Grace Hopper", "city": "New York", "notes": ["compiler", "Navy"] },
+            ])?;
+            coll.create_index(vec!["name"], &unique_index())?;
+
+            let names = |filter| -> Vec<Value> {
+                coll.find(filter)
+                    .unwrap()
+                    .map(|doc| doc.unwrap().get("name").unwrap())
+                    .collect()
+            };
+            assert_eq!(names(text_any("london ADA")), vec![Value::from("Ada Lovelace")]);
+            assert_eq!(names(text_any("navy hopper")), vec![Value::from("Grace Hopper")]);
+            assert!(names(text_any("ada turing")).is_empty());
+            assert_eq!(
+                names(and(vec![text_any("programmer"), field("city").eq("London")])),
+                vec![Value::from("Ada Lovelace")]
+            );
+
+            // only the indexed name is searched
+            assert!(names(text_any_indexed("codebreaker")).is_empty());
+            assert_eq!(names(text_any_indexed("turing")), vec![Value::from("Alan Turing")]);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_with_redaction_policy() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("payments")?;
+            let result = coll.insert(doc!{ "holder": "Ada", "card": "4111111111114242", "cvv": 123 })?;
+            let id = result.affected_nitrite_ids()[0];
+            coll.set_redaction_policy(Some(
+                RedactionPolicy::new().mask("card", 4).hide("cvv").exempt("billing"),
+            ))?;
+
+            // filters still see the stored value
+            let doc = coll.find(field("card").eq("4111111111114242"))?.first().unwrap()?;
+            assert_eq!(doc.get("card")?, Value::from("************4242"));
+            assert_eq!(doc.get("cvv")?, Value::Null);
+            assert_eq!(coll.get_by_id(&id)?.unwrap().get("card")?, Value::from("************4242"));
+
+            let options = FindOptions::new().principal("billing");
+            let doc = coll.find_with_options(all(), &options)?.first().unwrap()?;
+            assert_eq!(doc.get("card")?, Value::from("4111111111114242"));
+            assert_eq!(doc.get("cvv")?, Value::I32(123));
+
+            ctx.db().with_session(|session| {
+                let txn = session.begin_transaction()?;
+                let doc = txn.collection("payments")?.find(all())?.first().unwrap()?;
+                assert_eq!(doc.get("cvv")?, Value::Null);
+                txn.rollback()?;
+                Ok(())
+            })?;
+
+            coll.set_redaction_policy(None)?;
+            assert_eq!(coll.get_by_id(&id)?.unwrap().get("cvv")?, Value::I32(123));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_with_cancellation_token() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            for i in 0..20 {
+                coll.insert(doc!{ "value": i })?;
+            }
+
+            let token = CancellationToken::new();
+            let options = FindOptions::new().cancellation_token(token.clone());
+            let mut cursor = coll.find_with_options(all(), &options)?;
+            assert!(cursor.next().unwrap().is_ok());
+            assert!(cursor.next().unwrap().is_ok());
+
+            token.cancel();
+            let err = cursor.next().unwrap().unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::Cancelled);
+            assert!(cursor.next().is_none());
+
+            // a filter skipping every document stops as well
+            let cursor = coll.find_with_options(field("value").eq(-1), &options)?;
+            let results: Vec<_> = cursor.collect();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].as_ref().unwrap_err().kind(), &ErrorKind::Cancelled);
+
+            // queries without the token are not affected
+            assert_eq!(coll.find(all())?.count(), 20);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_with_timeout() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            coll.create_index(vec!["value"], &non_unique_index())?;
+            for i in 0..20 {
+                coll.insert(doc!{ "value": i, "name": (format!("name{}", i)) })?;
+            }
+
+            // a scan, an indexed read and a blocking sort all give up once the time is up
+            let expired = FindOptions::new().timeout(Duration::ZERO);
+            let mut cursor = coll.find_with_options(all(), &expired)?;
+            assert_eq!(cursor.next().unwrap().unwrap_err().kind(), &ErrorKind::QueryTimedOut);
+            let mut cursor = coll.find_with_options(field("value").gte(10), &expired)?;
+            assert_eq!(cursor.next().unwrap().unwrap_err().kind(), &ErrorKind::QueryTimedOut);
+            let sorted = FindOptions::new()
+                .sort_by("name".to_string(), SortOrder::Descending)
+                .timeout(Duration::ZERO);
+            let mut cursor = coll.find_with_options(all(), &sorted)?;
+            assert_eq!(cursor.next().unwrap().unwrap_err().kind(), &ErrorKind::QueryTimedOut);
+
+            let options = FindOptions::new().timeout(Duration::from_secs(60));
+            let cursor = coll.find_with_options(field("value").gte(10), &options)?;
+            assert_eq!(cursor.filter(|doc| doc.is_ok()).count(), 10);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_find_sorted_with_max_memory() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            for i in 0..50 {
+                coll.insert(doc!{ "value": (i * 7 % 50), "name": (format!("name{}", i)) })?;
+            }
+
+            // a cap far below the sort budget spills the sort to the store
+            let options = FindOptions::new()
+                .sort_by("value".to_string(), SortOrder::Descending)
+                .max_memory(256);
+            let values: Vec<i32> = coll
+                .find_with_options(all(), &options)?
+                .map(|doc| doc.and_then(|doc| doc.get("value")).map(|value| *value.as_i32().unwrap()))
+                .collect::<Result<_, _>>()?;
+            let expected: Vec<i32> = (0..50).rev().collect();
+            assert_eq!(values, expected);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_render_find_plan_and_table() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("test")?;
+            coll.create_index(vec!["value"], &non_unique_index())?;
+            for i in 0..5 {
+                coll.insert(doc!{ "value": i, "name": (format!("name{}", i)) })?;
+            }
+
+            let options = order_by("name", SortOrder::Descending).limit(3);
+            let mut cursor = coll.find_with_options(field("value").gte(1), &options)?;
+            let plan = render_plan(cursor.find_plan().unwrap());
+            assert!(plan.starts_with("index scan: non-unique index on [value]"));
+            assert!(plan.contains("sort: name descending"));
+            assert!(plan.ends_with("limit: 3"));
+
+            let table = Table::new().columns(&["name", "value"]).render_cursor(&mut cursor)?;
+            assert_eq!(
+                table,
+                "+-------+-------+\n\
+                 | name  | value |\n\
+                 +-------+-------+\n\
+                 | name4 |     4 |\n\
+                 | name3 |     3 |\n\
+                 | name2 |     2 |\n\
+                 +-------+-------+\n\
+                 3 rows"
+            );
+            Ok(())
+        },
+        cleanup,
+    )
+}
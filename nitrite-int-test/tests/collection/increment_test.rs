@@ -0,0 +1,84 @@
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite::index::non_unique_index;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+use std::thread;
+
+#[test]
+fn test_concurrent_increments_are_not_lost() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("counters")?;
+            let result = collection.insert(doc! { page: "home", views: 0 })?;
+            let id = result.affected_nitrite_ids()[0];
+
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let collection = collection.clone();
+                    thread::spawn(move || {
+                        for _ in 0..50 {
+                            collection.increment(&id, "views", Value::from(1)).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+
+            let document = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(document.get("views")?, Value::I32(400));
+            assert_eq!(document.get("page")?, Value::from("home"));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_increment_indexed_counter() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("counters")?;
+            collection.create_index(vec!["stats.views"], &non_unique_index())?;
+            let result = collection.insert(doc! { page: "home", stats: { views: 10 } })?;
+            let id = result.affected_nitrite_ids()[0];
+
+            collection.increment(&id, "stats.views", Value::from(5))?;
+            assert_eq!(collection.find(field("stats.views").eq(10))?.count(), 0);
+            assert_eq!(collection.find(field("stats.views").eq(15))?.count(), 1);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_increment_in_transaction() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let collection = db.collection("counters")?;
+            let result = collection.insert(doc! { page: "home", views: 1 })?;
+            let id = result.affected_nitrite_ids()[0];
+
+            db.with_session(|session| {
+                let transaction = session.begin_transaction()?;
+                let counters = transaction.collection("counters")?;
+                assert_eq!(counters.increment(&id, "views", Value::from(2))?, Value::I32(3));
+                // a write made outside the transaction before it commits still counts
+                collection.increment(&id, "views", Value::from(10))?;
+                transaction.commit()
+            })?;
+
+            let document = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(document.get("views")?, Value::I32(13));
+            Ok(())
+        },
+        cleanup,
+    )
+}
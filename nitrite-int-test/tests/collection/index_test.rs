@@ -746,3 +746,35 @@ fn test_rebuild_multiple_indexes() {
         cleanup,
     );
 }
+
+#[test]
+fn test_with_indexes_disabled() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            collection.create_index(vec!["email"], &unique_index())?;
+            collection.create_index(vec!["age"], &non_unique_index())?;
+
+            let loaded = collection.with_indexes_disabled(|collection| {
+                let documents = (0..100)
+                    .map(|i| doc!{ "email": (format!("user{}@example.com", i)), "age": (i % 10) })
+                    .collect();
+                collection.insert_many(documents)?;
+                collection.size()
+            })?;
+            assert_eq!(loaded, 100);
+
+            let cursor = collection.find(field("age").eq(3))?;
+            assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+            assert_eq!(cursor.count(), 10);
+            assert_eq!(collection.find(field("email").eq("user42@example.com"))?.count(), 1);
+
+            // the rebuilt unique index is enforced again
+            assert!(collection.insert(doc!{ "email": "user42@example.com" }).is_err());
+
+            Ok(())
+        },
+        cleanup,
+    );
+}
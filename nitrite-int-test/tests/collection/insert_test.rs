@@ -1,3 +1,4 @@
+use nitrite::collection::{CollectionOptions, InsertManyOptions};
 use nitrite::common::Value;
 use nitrite::doc;
 use nitrite::filter::{all, field};
@@ -295,4 +296,37 @@ fn test_insert_with_custom_id() {
         },
         cleanup,
     )
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_insert_many_skipping_invalid_documents() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection_with_options(
+                "validated",
+                CollectionOptions::new().required_field("email"),
+            )?;
+
+            let documents = (0..10)
+                .map(|i| {
+                    if i % 3 == 0 {
+                        doc!{ "name": (format!("user{}", i)) }
+                    } else {
+                        doc!{ "name": (format!("user{}", i)), "email": (format!("user{}@example.com", i)) }
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let options = InsertManyOptions::new().skip_invalid(true);
+            let result = collection.insert_many_with_options(documents, &options)?;
+            let invalid: Vec<usize> = result.errors().iter().map(|e| e.index()).collect();
+            assert_eq!(invalid, vec![0, 3, 6, 9]);
+            assert_eq!(result.inserted_ids().len(), 6);
+            assert_eq!(collection.find(all())?.count(), 6);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
@@ -1,4 +1,4 @@
-use nitrite::collection::NitriteCollection;
+use nitrite::collection::{FindOptions, NitriteCollection};
 use nitrite::common::{Lookup, Value};
 use nitrite::doc;
 use nitrite::filter::all;
@@ -59,6 +59,37 @@ fn test_join_all() {
 }
 
 // Helper function to insert foreign documents
+#[test]
+fn test_join_with_foreign_memory_limit() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            let foreign_collection = ctx.db().collection("foreign")?;
+            insert_test_documents(&collection)?;
+            insert_foreign_documents(&foreign_collection)?;
+            let lookup = Lookup::new("first_name", "f_name", "personal_details");
+
+            let mut cursor = collection.find(all())?;
+            let mut foreign_cursor = foreign_collection.find(all())?;
+            let expected = cursor
+                .join(&mut foreign_cursor, &lookup)?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // the foreign side does not fit its cap, so it is read again for each row
+            let mut cursor = collection.find(all())?;
+            let options = FindOptions::new().max_memory(64);
+            let mut foreign_cursor = foreign_collection.find_with_options(all(), &options)?;
+            let result = cursor
+                .join(&mut foreign_cursor, &lookup)?
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(result, expected);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
 fn insert_foreign_documents(collection: &NitriteCollection) -> nitrite::errors::NitriteResult<()> {
     let doc1 = doc!{
         "f_name": "fn1",
@@ -16,4 +16,14 @@ mod collection_find_negative_test;
 mod collection_insert_negative_test;
 mod collection_delete_negative_test;
 mod non_unique_index_scale_test;
+mod reference_test;
+mod write_details_test;
+mod count_test;
+mod increment_test;
+mod content_hash_test;
+mod remove_id_range_test;
+mod sort_stability_test;
 
+mod compressed_index_test;
+mod raw_json_test;
+mod resume_scan_test;
@@ -102,7 +102,6 @@ fn test_non_unique_integer_range_and_sorted_scan_exact() {
             // Ascending sorted scan — negatives first, strictly increasing.
             let asc: Vec<i64> = coll
                 .find_with_options(field("seq").gte(-50), &order_by("seq", SortOrder::Ascending))?
-                .into_iter()
                 .map(|d| d.unwrap().get("seq").unwrap().as_i64().copied().unwrap())
                 .collect();
             assert_eq!(asc.len(), 500);
@@ -112,7 +111,6 @@ fn test_non_unique_integer_range_and_sorted_scan_exact() {
             // Descending sorted scan — newest-first style, strictly decreasing.
             let desc: Vec<i64> = coll
                 .find_with_options(field("seq").gte(-50), &order_by("seq", SortOrder::Descending))?
-                .into_iter()
                 .map(|d| d.unwrap().get("seq").unwrap().as_i64().copied().unwrap())
                 .collect();
             assert_eq!(desc.len(), 500);
@@ -166,7 +164,6 @@ fn test_non_unique_composite_matches_unique_array_layout() {
             let collect_seq = |coll: &nitrite::collection::NitriteCollection| -> NitriteResult<Vec<i64>> {
                 Ok(coll
                     .find_with_options(field("seq").gte(0), &order_by("seq", SortOrder::Descending))?
-                    .into_iter()
                     .map(|d| d.unwrap().get("seq").unwrap().as_i64().copied().unwrap())
                     .collect())
             };
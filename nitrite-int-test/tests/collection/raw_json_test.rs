@@ -0,0 +1,80 @@
+//! Raw JSON fields: stored verbatim, left out of indexes until materialized.
+
+use nitrite::collection::Document;
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
+use nitrite::index::non_unique_index;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+const PAYLOAD: &str = r#"{"sensor": "t1", "readings": [21.5, 21.7], "ok": true}"#;
+
+#[test]
+fn test_raw_json_is_stored_verbatim() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("events")?;
+            let mut event = doc! { "kind": "reading" };
+            event.put("payload", Value::raw_json(PAYLOAD)?)?;
+            let id = coll.insert(event)?.affected_nitrite_ids()[0];
+
+            let stored = coll.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.get("payload")?.as_raw_json(), Some(PAYLOAD));
+            // the text is not looked into, so its fields are not found
+            assert_eq!(stored.get("payload.sensor")?, Value::Null);
+            assert_eq!(coll.find(field("payload.sensor").eq("t1"))?.count(), 0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_materialized_raw_json_is_indexed() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("events")?;
+            coll.create_index(vec!["level"], &non_unique_index())?;
+            coll.create_index(vec!["payload.sensor"], &non_unique_index())?;
+
+            let mut event = doc! { "kind": "reading" };
+            event.put("level", Value::raw_json("3")?)?;
+            event.put("payload", Value::raw_json(PAYLOAD)?)?;
+            let id = coll.insert(event)?.affected_nitrite_ids()[0];
+            // a raw JSON field is indexed as missing
+            assert_eq!(coll.find(field("level").eq(Value::Null))?.count(), 1);
+
+            let mut event = coll.get_by_id(&id)?.unwrap();
+            event.materialize("level")?;
+            event.materialize("payload")?;
+            assert_eq!(event.get("payload.readings")?, Value::Array(vec![Value::F64(21.5), Value::F64(21.7)]));
+            coll.update_one(&event, false)?;
+
+            let found: Vec<Document> = coll
+                .find(field("payload.sensor").eq("t1"))?
+                .collect::<Result<_, _>>()?;
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].get("payload.ok")?, Value::Bool(true));
+            assert_eq!(coll.find(field("level").eq(3i64))?.count(), 1);
+            assert_eq!(coll.find(field("level").eq(Value::Null))?.count(), 0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_invalid_raw_json_is_rejected() {
+    run_test(
+        create_test_context,
+        |_ctx| {
+            let error = Value::raw_json(r#"{"sensor": "t1""#).unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::ValidationError);
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,120 @@
+use nitrite::collection::{NitriteId, OnDelete, Reference};
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::{all, field};
+use nitrite::index::non_unique_index;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[test]
+fn test_reference_checked_on_insert_and_update() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            ctx.db()
+                .add_reference(Reference::new("orders", "customer_id", "customers"))?;
+            let customers = ctx.db().collection("customers")?;
+            let orders = ctx.db().collection("orders")?;
+            let ada = customers.insert(doc! { name: "Ada" })?.affected_nitrite_ids()[0];
+
+            orders.insert(doc! { customer_id: ada, item: "anvil" })?;
+            orders.insert(doc! { item: "rocket" })?;
+
+            let missing = NitriteId::new();
+            let err = orders
+                .insert(doc! { customer_id: missing, item: "magnet" })
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), &ErrorKind::ReferenceViolation);
+
+            let err = orders
+                .update(field("item").eq("anvil"), &doc! { customer_id: missing })
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), &ErrorKind::ReferenceViolation);
+
+            let err = orders
+                .insert(doc! { customer_id: "Ada", item: "magnet" })
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), &ErrorKind::ReferenceViolation);
+            assert_eq!(orders.size()?, 2);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_reference_on_delete() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            db.add_reference(Reference::new("orders", "customer_id", "customers"))?;
+            db.add_reference(
+                Reference::new("lines", "order_id", "orders").on_delete(OnDelete::Cascade),
+            )?;
+            db.add_reference(
+                Reference::new("tickets", "order_id", "orders").on_delete(OnDelete::SetNull),
+            )?;
+
+            let customers = db.collection("customers")?;
+            let orders = db.collection("orders")?;
+            let lines = db.collection("lines")?;
+            let tickets = db.collection("tickets")?;
+            lines.create_index(vec!["order_id"], &non_unique_index())?;
+
+            let ada = customers.insert(doc! { name: "Ada" })?.affected_nitrite_ids()[0];
+            let order = orders
+                .insert(doc! { customer_id: ada, item: "anvil" })?
+                .affected_nitrite_ids()[0];
+            lines.insert(doc! { order_id: order, quantity: 1 })?;
+            lines.insert(doc! { order_id: order, quantity: 2 })?;
+            tickets.insert(doc! { order_id: order, subject: "late" })?;
+
+            // restricted while the order references the customer
+            let err = customers.remove(all(), false).err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::ReferenceViolation);
+            assert_eq!(customers.size()?, 1);
+
+            orders.remove(field("_id").eq(order), false)?;
+            assert_eq!(lines.size()?, 0);
+            let ticket = tickets.find(all())?.first().unwrap()?;
+            assert_eq!(ticket.get("order_id")?, Value::Null);
+
+            customers.remove(all(), false)?;
+            assert_eq!(customers.size()?, 0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_reference_registry() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            db.add_reference(Reference::new("orders", "customer_id", "customers"))?;
+
+            let err = db
+                .add_reference(Reference::new("customers", "last_order", "orders"))
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), &ErrorKind::InvalidOperation);
+            assert!(db
+                .add_reference(Reference::new("employees", "manager", "employees"))
+                .is_err());
+            assert_eq!(db.references().len(), 1);
+
+            assert!(db.remove_reference("orders", "customer_id"));
+            assert!(db.references().is_empty());
+            db.collection("orders")?
+                .insert(doc! { customer_id: (NitriteId::new()) })?;
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,142 @@
+use nitrite::collection::{CollectionEventListener, CollectionOptions, Document, NitriteId};
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::filter::{all, by_id_range, field};
+use nitrite::index::non_unique_index;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn with_id(mut document: Document, id: NitriteId) -> Document {
+    document.put("_id", id).unwrap();
+    document
+}
+
+fn ids(count: u64) -> Vec<NitriteId> {
+    let first = NitriteId::new().id_value();
+    (0..count)
+        .map(|i| NitriteId::create_id(first + i * 10).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_remove_id_range() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("events")?;
+            let ids = ids(10);
+            for (i, id) in ids.iter().enumerate() {
+                collection.insert(with_id(doc! { seq: (i as i32) }, *id))?;
+            }
+            let events = Arc::new(AtomicUsize::new(0));
+            let counter = events.clone();
+            collection.subscribe(CollectionEventListener::new(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }))?;
+
+            // both ends are removed, ids between two documents match nothing
+            assert_eq!(collection.remove_id_range(&ids[2], &ids[5])?, 4);
+            assert_eq!(collection.size()?, 6);
+            assert!(collection.get_by_id(&ids[2])?.is_none());
+            assert!(collection.get_by_id(&ids[5])?.is_none());
+            assert!(collection.get_by_id(&ids[6])?.is_some());
+            let between = NitriteId::create_id(ids[6].id_value() + 1)?;
+            assert_eq!(collection.remove_id_range(&between, &between)?, 0);
+            assert_eq!(collection.remove_id_range(&ids[9], &ids[0])?, 0);
+            assert_eq!(collection.find(by_id_range(ids[0], ids[9]))?.count(), 6);
+
+            // a purge publishes no events
+            assert_eq!(events.load(Ordering::SeqCst), 0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_remove_id_range_keeps_indexes_and_soft_deletes() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx
+                .db()
+                .collection_with_options("events", CollectionOptions::new().soft_delete(true))?;
+            collection.create_index(vec!["kind"], &non_unique_index())?;
+            let ids = ids(6);
+            for (i, id) in ids.iter().enumerate() {
+                let kind = if i % 2 == 0 { "even" } else { "odd" };
+                collection.insert(with_id(doc! { kind: kind }, *id))?;
+            }
+
+            assert_eq!(collection.remove_id_range(&ids[0], &ids[3])?, 4);
+            let odd: Vec<_> = collection
+                .find(field("kind").eq("odd"))?
+                .map(|document| document.unwrap().id().unwrap())
+                .collect();
+            assert_eq!(odd, vec![ids[5]]);
+            assert_eq!(collection.find(field("kind").eq("even"))?.count(), 1);
+            assert_eq!(collection.deleted_documents()?.len(), 4);
+
+            collection.restore(&ids[1])?;
+            assert_eq!(collection.find(field("kind").eq("odd"))?.count(), 2);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_remove_id_range_by_time() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("events")?;
+            let old = collection.insert(doc! { at: "old" })?.affected_nitrite_ids()[0];
+            let cutoff = old.timestamp();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            collection.insert(doc! { at: "new" })?;
+
+            let (start, end) = NitriteId::from_time_range(0, cutoff)?;
+            assert_eq!(collection.remove_id_range(&start, &end)?, 1);
+            let left: Vec<_> = collection
+                .find(all())?
+                .map(|document| document.unwrap().get("at").unwrap())
+                .collect();
+            assert_eq!(left, vec![Value::from("new")]);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_remove_id_range_in_transaction() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let collection = db.collection("events")?;
+            let ids = ids(4);
+            for id in &ids {
+                collection.insert(with_id(doc! { kind: "a" }, *id))?;
+            }
+
+            db.with_session(|session| {
+                let transaction = session.begin_transaction()?;
+                let events = transaction.collection("events")?;
+                assert_eq!(events.remove_id_range(&ids[1], &ids[2])?, 2);
+                assert_eq!(events.size()?, 2);
+                assert_eq!(collection.size()?, 4);
+                transaction.commit()
+            })?;
+
+            assert_eq!(collection.size()?, 2);
+            assert!(collection.get_by_id(&ids[1])?.is_none());
+            assert!(collection.get_by_id(&ids[3])?.is_some());
+            Ok(())
+        },
+        cleanup,
+    )
+}
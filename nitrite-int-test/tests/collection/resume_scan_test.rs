@@ -0,0 +1,121 @@
+//! Checkpointed cursors and resumed collection scans.
+
+use nitrite::collection::{Document, FindOptions, NitriteCollection, ScanToken};
+use nitrite::common::SortOrder;
+use nitrite::doc;
+use nitrite::errors::{ErrorKind, NitriteResult};
+use nitrite::filter::{all, field};
+use nitrite::index::{non_unique_index, IndexHint};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+fn insert_events(coll: &NitriteCollection, count: i64) -> NitriteResult<()> {
+    let docs: Vec<_> = (0..count)
+        .map(|i| doc! { "seq": i, "kind": (if i % 2 == 0 { "even" } else { "odd" }) })
+        .collect();
+    coll.insert_many(docs)?;
+    Ok(())
+}
+
+fn seqs(documents: impl Iterator<Item = NitriteResult<Document>>) -> NitriteResult<Vec<i64>> {
+    documents
+        .map(|doc| Ok(doc?.get("seq")?.as_i64().copied().unwrap_or_default()))
+        .collect()
+}
+
+#[test]
+fn test_resume_scan_continues_after_checkpoint() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("events")?;
+            insert_events(&coll, 500)?;
+
+            let mut cursor = coll.find(all())?;
+            let mut exported = seqs(cursor.by_ref().take(200))?;
+            // the token is saved as text, as a job would store it with its progress
+            let saved = cursor.checkpoint()?.to_string();
+            drop(cursor);
+
+            let token = ScanToken::parse(&saved)?;
+            assert_eq!(token.collection_name(), "events");
+            assert_eq!(token.position(), 200);
+
+            let mut resumed = coll.resume_scan(&token)?;
+            exported.extend(seqs(resumed.by_ref())?);
+            assert_eq!(resumed.checkpoint()?.position(), 500);
+
+            exported.sort();
+            assert_eq!(exported, (0..500).collect::<Vec<_>>());
+
+            // a scan resumed at its end returns nothing
+            let mut finished = coll.resume_scan(&resumed.checkpoint()?)?;
+            assert!(finished.next().is_none());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_resume_filtered_scan() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("events")?;
+            // the index is not used, the resumed query scans the collection
+            coll.create_index(vec!["kind"], &non_unique_index())?;
+            insert_events(&coll, 300)?;
+
+            let options = FindOptions::new().limit(50);
+            let mut cursor = coll.find_with_options(field("kind").eq("odd"), &options)?;
+            let first = seqs(cursor.by_ref())?;
+            assert_eq!(first.len(), 50);
+            let token = cursor.checkpoint();
+            assert_eq!(token.unwrap_err().kind(), &ErrorKind::InvalidOperation);
+
+            let mut cursor = coll.find(field("kind").eq("odd").with_hint(IndexHint::CollectionScan))?;
+            let first = seqs(cursor.by_ref().take(50))?;
+            let token = cursor.checkpoint()?;
+
+            let options = FindOptions::new().resume_after(token);
+            let rest = seqs(coll.find_with_options(field("kind").eq("odd"), &options)?)?;
+            assert_eq!(first.len() + rest.len(), 150);
+            assert!(rest.iter().all(|seq| seq % 2 == 1 && !first.contains(seq)));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_resume_scan_is_validated() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("events")?;
+            let other = ctx.db().collection("other")?;
+            insert_events(&coll, 10)?;
+
+            let sorted = coll.find_with_options(
+                all(),
+                &FindOptions::new().sort_by("seq".to_string(), SortOrder::Descending),
+            )?;
+            assert_eq!(sorted.checkpoint().unwrap_err().kind(), &ErrorKind::InvalidOperation);
+
+            let token = coll.find(all())?.checkpoint()?;
+            let error = other.resume_scan(&token).err().unwrap();
+            assert_eq!(error.kind(), &ErrorKind::InvalidOperation);
+
+            let options = FindOptions::new()
+                .resume_after(token)
+                .sort_by("seq".to_string(), SortOrder::Ascending);
+            let error = coll.find_with_options(all(), &options).err().unwrap();
+            assert_eq!(error.kind(), &ErrorKind::InvalidOperation);
+
+            let error = ScanToken::parse("not a token").unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::ValidationError);
+            Ok(())
+        },
+        cleanup,
+    )
+}
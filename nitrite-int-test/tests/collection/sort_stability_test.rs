@@ -0,0 +1,132 @@
+use nitrite::collection::{order_by, Document, FindOptions, NitriteCollection, NitriteId};
+use nitrite::common::SortOrder;
+use nitrite::doc;
+use nitrite::errors::NitriteResult;
+use nitrite::filter::{all, field};
+use nitrite::index::non_unique_index;
+use nitrite::nitrite::Nitrite;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+/// Inserts 30 documents in three groups, with ids out of insertion order.
+fn insert_groups(collection: &NitriteCollection) -> NitriteResult<Vec<NitriteId>> {
+    let first = NitriteId::new().id_value();
+    let mut ids = Vec::new();
+    for i in 0..30u64 {
+        // 0, 7, 14, ... modulo 30 visits every id once
+        let id = NitriteId::create_id(first + (i * 7) % 30)?;
+        let mut document = doc! { group: ((i % 3) as i32), seq: (i as i32) };
+        document.put("_id", id)?;
+        collection.insert(document)?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+fn read_pages(collection: &NitriteCollection, page_size: u64) -> NitriteResult<Vec<NitriteId>> {
+    let mut ids = Vec::new();
+    for page in 0..30u64.div_ceil(page_size) {
+        let options = order_by("group", SortOrder::Descending)
+            .skip(page * page_size)
+            .limit(page_size);
+        for document in collection.find_with_options(all(), &options)? {
+            ids.push(document?.id()?);
+        }
+    }
+    Ok(ids)
+}
+
+fn read_ids(cursor: impl Iterator<Item = NitriteResult<Document>>) -> NitriteResult<Vec<NitriteId>> {
+    cursor.map(|document| document?.id()).collect()
+}
+
+#[test]
+fn test_pages_of_equal_sort_keys_are_stable() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("items")?;
+            let ids = insert_groups(&collection)?;
+
+            let paged = read_pages(&collection, 4)?;
+            let mut unique = paged.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), 30);
+
+            // each group is returned by ascending id
+            let mut expected = Vec::new();
+            for group in [2, 1, 0] {
+                let mut group_ids: Vec<NitriteId> =
+                    ids.iter().skip(group).step_by(3).copied().collect();
+                group_ids.sort();
+                expected.extend(group_ids);
+            }
+            assert_eq!(paged, expected);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_sort_order_is_the_same_on_every_store() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let persistent = ctx.db().collection("items")?;
+            let memory_db = Nitrite::builder().open_or_create(None, None)?;
+            let memory = memory_db.collection("items")?;
+
+            let first = NitriteId::new().id_value();
+            for i in 0..20u64 {
+                let id = NitriteId::create_id(first + (i * 13) % 20)?;
+                for collection in [&persistent, &memory] {
+                    let mut document = doc! { group: ((i % 2) as i32) };
+                    document.put("_id", id)?;
+                    collection.insert(document)?;
+                }
+            }
+
+            for collection in [&persistent, &memory] {
+                collection.create_index(vec!["group"], &non_unique_index())?;
+            }
+            let sorted = order_by("group", SortOrder::Ascending).limit(7);
+            assert_eq!(
+                read_ids(persistent.find_with_options(all(), &sorted)?)?,
+                read_ids(memory.find_with_options(all(), &sorted)?)?
+            );
+
+            // an unsorted query answered by an index reads its documents by id
+            let indexed = read_ids(persistent.find(field("group").eq(1))?)?;
+            assert!(indexed.windows(2).all(|pair| pair[0] < pair[1]));
+            assert_eq!(indexed, read_ids(memory.find(field("group").eq(1))?)?);
+
+            memory_db.close()?;
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_sort_without_tie_break() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("items")?;
+            insert_groups(&collection)?;
+
+            let options = FindOptions::new()
+                .sort_by("group".to_string(), SortOrder::Ascending)
+                .tie_break_by_id(false);
+            let groups: Vec<i32> = collection
+                .find_with_options(all(), &options)?
+                .map(|document| *document.unwrap().get("group").unwrap().as_i32().unwrap())
+                .collect();
+            assert_eq!(groups.len(), 30);
+            assert!(groups.windows(2).all(|pair| pair[0] <= pair[1]));
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -1,7 +1,11 @@
-use nitrite::collection::{insert_if_absent, just_once, CollectionEventListener, UpdateOptions};
+use nitrite::collection::{
+    insert_if_absent, just_once, CollectionEventListener, UpdateEachOptions, UpdateOptions,
+};
 use nitrite::common::Value;
+use std::time::Duration;
 use nitrite::doc;
 use nitrite::filter::{all, field};
+use nitrite::index::non_unique_index;
 use nitrite_int_test::test_util::{cleanup, create_test_context, insert_test_documents, run_test};
 
 #[test]
@@ -426,4 +430,88 @@ fn test_issue151() {
         },
         cleanup,
     )
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_update_each() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            for i in 0..25 {
+                collection.insert(doc!{ "seq": i, "processed": false })?;
+            }
+
+            let options = UpdateEachOptions::new().batch_size(10);
+            let result = collection.update_each_with_options(
+                field("processed").eq(false),
+                |mut doc| {
+                    doc.put("processed", true)?;
+                    Ok(Some(doc))
+                },
+                &options,
+            )?;
+            assert_eq!(result.matched_count(), 25);
+            assert_eq!(result.modified_count(), 25);
+            assert_eq!(result.batch_count(), 3);
+
+            // documents the closure leaves alone are not written
+            let result = collection.update_each(all(), |_| Ok(None))?;
+            assert_eq!(result.matched_count(), 25);
+            assert_eq!(result.modified_count(), 0);
+            assert_eq!(result.batch_count(), 0);
+
+            assert_eq!(collection.find(field("processed").eq(true))?.count(), 25);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+
+#[test]
+fn test_field_expiry() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            collection.create_index(vec!["city"], &non_unique_index())?;
+
+            let mut depot = doc!{ "name": "Depot 7", "city": "London", "geocode": "52.1,4.3" };
+            depot.set_field_expiry("city", 1)?;
+            depot.set_field_expiry("geocode", 1)?;
+            let id = collection.insert(depot)?.affected_nitrite_ids()[0];
+            let mut fresh = doc!{ "name": "Depot 8" };
+            fresh.put_with_ttl("city", "London", Duration::from_secs(3600))?;
+            collection.insert(fresh)?;
+
+            let stored = collection.get_by_id(&id)?.unwrap();
+            assert_eq!(stored.get("name")?, Value::from("Depot 7"));
+            assert_eq!(stored.get("city")?, Value::Null);
+            assert_eq!(stored.field_expiry("city")?, None);
+
+            // the index still holds the expired value until the sweep
+            let names = |cursor: nitrite::common::DocumentCursor| -> Vec<Value> {
+                cursor.map(|doc| doc.unwrap().get("name").unwrap()).collect()
+            };
+            assert_eq!(names(collection.find(field("city").eq("London"))?), vec![Value::from("Depot 8")]);
+            assert_eq!(names(collection.find(field("geocode").eq(Value::Null))?).len(), 2);
+
+            assert_eq!(collection.sweep_expired_fields()?, 1);
+            assert_eq!(collection.sweep_expired_fields()?, 0);
+            assert_eq!(names(collection.find(field("city").eq("London"))?), vec![Value::from("Depot 8")]);
+            assert_eq!(collection.get_by_id(&id)?.unwrap().get("name")?, Value::from("Depot 7"));
+
+            // setting the field again makes it visible for good
+            let mut depot = doc!{ "name": "Depot 9" };
+            depot.set_field_expiry("city", 1)?;
+            depot.put("city", "Paris")?;
+            let id = collection.insert(depot)?.affected_nitrite_ids()[0];
+            assert!(names(collection.find(field("city").eq("Paris"))?).is_empty());
+            collection.update_by_id(&id, &doc!{ "city": "Paris" }, false)?;
+            assert_eq!(names(collection.find(field("city").eq("Paris"))?), vec![Value::from("Depot 9")]);
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,58 @@
+use nitrite::collection::CollectionOptions;
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[test]
+fn test_write_details_of_detailed_collection() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection_with_options(
+                "detailed",
+                CollectionOptions::new().detailed_results(true),
+            )?;
+
+            // a batch large enough for the optimized insert path
+            let documents = (0..20).map(|i| doc! { seq: i, even: (i % 2 == 0) }).collect();
+            let result = coll.insert_many(documents)?;
+            let details = result.details().unwrap();
+            assert_eq!(details.outcomes().len(), 20);
+            assert!(details.outcomes().iter().all(|outcome| outcome.is_inserted()
+                && !outcome.is_matched()
+                && outcome.error().is_none()));
+            assert!(details.total_time() >= details.index_time());
+            let ids: Vec<_> = details.outcomes().iter().map(|outcome| outcome.nitrite_id()).collect();
+            assert_eq!(&ids, result.affected_nitrite_ids());
+
+            let result = coll.update(field("even").eq(true), &doc! { tagged: true })?;
+            let details = result.details().unwrap();
+            assert_eq!(details.outcomes().len(), 10);
+            assert!(details.outcomes().iter().all(|outcome| outcome.is_matched()
+                && outcome.is_modified()
+                && !outcome.is_inserted()));
+
+            let result = coll.remove(field("seq").eq(3), false)?;
+            let outcomes = result.details().unwrap().outcomes();
+            assert_eq!(outcomes.len(), 1);
+            assert!(outcomes[0].is_matched() && outcomes[0].is_modified());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_write_details_not_recorded_by_default() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let coll = ctx.db().collection("plain")?;
+            let result = coll.insert(doc! { seq: 1 })?;
+            assert!(result.details().is_none());
+            assert_eq!(result.affected_nitrite_ids().len(), 1);
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,131 @@
+//! Dropping a collection removes the directories and files of its full-text and
+//! spatial indexes, while clearing it keeps the indexes and only removes entries.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::doc;
+use nitrite::index::unique_index;
+use nitrite::nitrite::Nitrite;
+use nitrite_fjall_adapter::FjallModule;
+use nitrite_int_test::test_util::random_path;
+use nitrite_spatial::{spatial_field, spatial_index, Geometry, SpatialModule};
+use nitrite_tantivy_fts::{fts_field, fts_index, TantivyFtsModule};
+use std::fs;
+
+fn open(path: &str) -> Nitrite {
+    let storage_module = FjallModule::with_config()
+        .db_path(path)
+        .low_memory_preset()
+        .build();
+    Nitrite::builder()
+        .load_module(storage_module)
+        .load_module(TantivyFtsModule::default())
+        .load_module(SpatialModule)
+        .open_or_create(None, None)
+        .unwrap()
+}
+
+/// Lists the entries the module indexes of a collection keep next to the store.
+fn module_index_files(path: &str, collection: &str) -> Vec<String> {
+    let prefix = format!("{}_", collection);
+    let mut names: Vec<String> = fs::read_dir(path)
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(&prefix))
+        .collect();
+    names.sort();
+    names
+}
+
+fn populate(db: &Nitrite, name: &str) {
+    let collection = db.collection(name).unwrap();
+    collection.create_index(vec!["content"], &fts_index()).unwrap();
+    collection
+        .create_index(vec!["location"], &spatial_index())
+        .unwrap();
+    collection.create_index(vec!["code"], &unique_index()).unwrap();
+    collection
+        .insert(doc! {
+            code: 1,
+            content: "the quick brown fox",
+            location: { x: 1.0, y: 1.0 }
+        })
+        .unwrap();
+    db.commit().unwrap();
+}
+
+#[test]
+fn test_destroy_collection_removes_module_index_files() {
+    let path = random_path();
+    let db = open(&path);
+    populate(&db, "notes");
+    populate(&db, "kept");
+    assert_eq!(module_index_files(&path, "notes").len(), 2);
+
+    db.destroy_collection("notes").unwrap();
+    assert!(module_index_files(&path, "notes").is_empty());
+    assert!(!db.has_collection("notes").unwrap());
+    assert_eq!(module_index_files(&path, "kept").len(), 2);
+
+    // a collection with the same name starts without indexes
+    let notes = db.collection("notes").unwrap();
+    assert!(notes.list_indexes().unwrap().is_empty());
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_destroy_collection_after_reopen_removes_module_index_files() {
+    let path = random_path();
+    let db = open(&path);
+    populate(&db, "notes");
+    db.close().unwrap();
+
+    // the indexes have not been used since the database was opened
+    let db = open(&path);
+    assert_eq!(module_index_files(&path, "notes").len(), 2);
+    db.destroy_collection("notes").unwrap();
+    assert!(module_index_files(&path, "notes").is_empty());
+    db.close().unwrap();
+
+    let db = open(&path);
+    assert!(!db.has_collection("notes").unwrap());
+    assert!(module_index_files(&path, "notes").is_empty());
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_clear_keeps_indexes_and_removes_entries() {
+    let path = random_path();
+    let db = open(&path);
+    populate(&db, "notes");
+
+    let notes = db.collection("notes").unwrap();
+    notes.clear().unwrap();
+    assert_eq!(notes.size().unwrap(), 0);
+    assert_eq!(notes.list_indexes().unwrap().len(), 3);
+    assert_eq!(module_index_files(&path, "notes").len(), 2);
+
+    let found = notes.find(fts_field("content").matches("fox")).unwrap();
+    assert_eq!(found.count(), 0);
+    let search_box = Geometry::envelope(0.0, 0.0, 2.0, 2.0);
+    let found = notes
+        .find(spatial_field("location").within(search_box))
+        .unwrap();
+    assert_eq!(found.count(), 0);
+
+    // the unique index no longer holds the removed value
+    notes
+        .insert(doc! {
+            code: 1,
+            content: "a lazy dog",
+            location: { x: 5.0, y: 5.0 }
+        })
+        .unwrap();
+    let found = notes.find(fts_field("content").matches("lazy")).unwrap();
+    assert_eq!(found.count(), 1);
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,156 @@
+//! Per-collection options on the Fjall store: the options and the soft-deleted documents
+//! must survive a reopen, a capped collection keeps evicting its oldest documents, a
+//! collection with a quota keeps rejecting writes and split fields are read back with
+//! their documents.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::{CollectionOptions, WriteDurability};
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::{all, field};
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+#[test]
+fn test_collection_options_survive_reopen() {
+    let path = random_path();
+    let options = CollectionOptions::new()
+        .durability(WriteDurability::Sync)
+        .required_field("level")
+        .soft_delete(true);
+    let id;
+    {
+        let db = open_fjall_db(&path);
+        let logs = db.collection_with_options("logs", options.clone()).unwrap();
+        id = logs.insert(doc! { level: "info", message: "started" }).unwrap()
+            .affected_nitrite_ids()[0];
+        logs.remove(field("level").eq("info"), false).unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let logs = db.collection("logs").unwrap();
+        assert_eq!(logs.options().unwrap(), options);
+
+        let err = logs.insert(doc! { message: "no level" }).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ValidationError);
+
+        assert_eq!(logs.deleted_documents().unwrap().len(), 1);
+        logs.restore(&id).unwrap();
+        assert_eq!(logs.find(all()).unwrap().count(), 1);
+        assert!(logs.deleted_documents().unwrap().is_empty());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_capped_collection_evicts_oldest_after_reopen() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let events = db
+            .collection_with_options("events", CollectionOptions::new().max_documents(5))
+            .unwrap();
+        for seq in 0..8 {
+            events.insert(doc! { seq: seq }).unwrap();
+        }
+        assert_eq!(events.size().unwrap(), 5);
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let events = db.collection("events").unwrap();
+        events.insert(doc! { seq: 8 }).unwrap();
+        assert_eq!(events.size().unwrap(), 5);
+
+        let mut remaining: Vec<i32> = events
+            .find(all())
+            .unwrap()
+            .map(|doc| *doc.unwrap().get("seq").unwrap().as_i32().unwrap())
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec![4, 5, 6, 7, 8]);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_quota_rejects_writes_after_reopen() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let logs = db
+            .collection_with_options("logs", CollectionOptions::new().document_quota(3))
+            .unwrap();
+        logs.insert_many(vec![doc! { seq: 0 }, doc! { seq: 1 }]).unwrap();
+
+        // a batch that does not fit is stored not at all
+        let err = logs
+            .insert_many(vec![doc! { seq: 2 }, doc! { seq: 3 }])
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::QuotaExceeded);
+        assert_eq!(logs.size().unwrap(), 2);
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let logs = db.collection("logs").unwrap();
+        logs.insert(doc! { seq: 2 }).unwrap();
+        let err = logs.insert(doc! { seq: 3 }).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::QuotaExceeded);
+        assert_eq!(logs.size().unwrap(), 3);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_split_fields_read_back_whole() {
+    let path = random_path();
+    let options = CollectionOptions::new().split_field("content");
+    {
+        let db = open_fjall_db(&path);
+        let files = db.collection_with_options("files", options.clone()).unwrap();
+        for i in 0..20 {
+            files
+                .insert(doc! { name: (format!("file-{}", i)), size: i, content: (("x").repeat(1000)) })
+                .unwrap();
+        }
+        files.update(field("size").lt(5), &doc! { content: "small" }).unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let files = db.collection("files").unwrap();
+        assert_eq!(files.options().unwrap(), options);
+
+        let small = files.find(field("content").eq("small")).unwrap();
+        assert_eq!(small.count(), 5);
+        for document in files.find(field("size").gte(5)).unwrap() {
+            assert_eq!(document.unwrap().get("content").unwrap().as_string().unwrap().len(), 1000);
+        }
+
+        let mut cursor = files.find(all()).unwrap();
+        let names: Vec<_> = cursor
+            .project(doc! { name: (Value::Null) })
+            .unwrap()
+            .map(|document| document.unwrap())
+            .collect();
+        assert_eq!(names.len(), 20);
+        assert!(names.iter().all(|document| document.size() == 1));
+
+        let snapshot = db.snapshot().unwrap();
+        for document in snapshot.documents("files").unwrap() {
+            assert!(document.unwrap().get("content").unwrap().as_string().is_some());
+        }
+
+        files.dispose().unwrap();
+        assert!(!db.store().has_map("$nitrite_split|files").unwrap());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
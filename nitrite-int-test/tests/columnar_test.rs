@@ -0,0 +1,135 @@
+use arrow_array::{Array, Float64Array, Int64Array, ListArray, RecordBatch, StringArray};
+use nitrite::collection::{FindOptions, NitriteCollection};
+use nitrite::columnar::{ColumnType, SchemaMapping};
+use nitrite::doc;
+use nitrite::errors::{ErrorKind, NitriteResult};
+use nitrite::filter::{all, field};
+use nitrite_int_test::test_util::{cleanup, create_test_context, random_path, run_test};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::{self, File};
+
+fn insert_orders(orders: &NitriteCollection) -> NitriteResult<()> {
+    orders.insert_many(vec![
+        doc! { customer: { name: "Ada", city: "London" }, total: 42.5, items: 3, tags: ["new", "gift"] },
+        doc! { customer: { name: "Grace" }, total: 7, items: 1 },
+        doc! { customer: { name: "Linus", city: "Helsinki" }, total: 120.0, items: 8, tags: [] },
+    ])?;
+    Ok(())
+}
+
+fn read_parquet(path: &str) -> Vec<RecordBatch> {
+    let file = File::open(path).unwrap();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .unwrap();
+    reader.map(|batch| batch.unwrap()).collect()
+}
+
+#[test]
+fn test_cursor_to_arrow() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let orders = ctx.db().collection("orders")?;
+            insert_orders(&orders)?;
+
+            let mut cursor = orders.find(field("total").gt(10))?;
+            let batch = cursor.to_arrow(None)?;
+            assert_eq!(batch.num_rows(), 2);
+            let schema = batch.schema();
+            let names: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+            assert_eq!(names, vec!["_id", "customer", "items", "tags", "total"]);
+            assert_eq!(schema.field(4).data_type(), &ColumnType::Float64.data_type());
+
+            // the cursor is reset, so it can be read again
+            assert_eq!(cursor.size(), 2);
+
+            let mapping = SchemaMapping::new()
+                .column_as("customer.name", "customer", ColumnType::Utf8)
+                .column("total", ColumnType::Float64)
+                .column("tags", ColumnType::List(Box::new(ColumnType::Utf8)));
+            let batch = orders.find(all())?.to_arrow(Some(&mapping))?;
+            assert_eq!(batch.num_rows(), 3);
+            let totals = batch.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+            assert_eq!(totals.iter().flatten().sum::<f64>(), 169.5);
+            let tags = batch.column(2).as_any().downcast_ref::<ListArray>().unwrap();
+            assert_eq!(tags.null_count(), 1);
+
+            let mapping = SchemaMapping::new().column("customer.city", ColumnType::Int64);
+            let err = orders.find(all())?.to_arrow(Some(&mapping)).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::InvalidDataType);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_cursor_to_arrow_with_max_memory() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let orders = ctx.db().collection("orders")?;
+            insert_orders(&orders)?;
+
+            let options = FindOptions::new().max_memory(64);
+            let err = orders.find_with_options(all(), &options)?.to_arrow(None).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::MemoryLimitExceeded);
+
+            let options = FindOptions::new().max_memory(64 * 1024);
+            let batch = orders.find_with_options(all(), &options)?.to_arrow(None)?;
+            assert_eq!(batch.num_rows(), 3);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_export_parquet() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let orders = ctx.db().collection("orders")?;
+            insert_orders(&orders)?;
+            let path = format!("{}.parquet", random_path());
+
+            let rows = orders.export_parquet(
+                &path,
+                field("items").gte(3),
+                Some(&["customer.name", "items"]),
+            )?;
+            assert_eq!(rows, 2);
+
+            let batches = read_parquet(&path);
+            assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 2);
+            let batch = &batches[0];
+            assert_eq!(batch.schema().field(0).name(), "customer.name");
+            let names = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+            let mut names: Vec<&str> = names.iter().flatten().collect();
+            names.sort();
+            assert_eq!(names, vec!["Ada", "Linus"]);
+            let items = batch.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+            assert_eq!(items.iter().flatten().sum::<i64>(), 11);
+
+            let mapping = SchemaMapping::new()
+                .column("_id", ColumnType::Utf8)
+                .column("total", ColumnType::Float64);
+            let rows = orders.export_parquet_with_schema(&path, all(), &mapping)?;
+            assert_eq!(rows, 3);
+            let batches = read_parquet(&path);
+            assert_eq!(batches[0].num_columns(), 2);
+            assert_eq!(batches[0].num_rows(), 3);
+
+            // an export without matching documents writes an empty file with the schema
+            let rows = orders.export_parquet(&path, field("items").gt(100), None)?;
+            assert_eq!(rows, 0);
+            assert!(read_parquet(&path).iter().all(|batch| batch.num_rows() == 0));
+
+            fs::remove_file(&path)?;
+            Ok(())
+        },
+        cleanup,
+    )
+}
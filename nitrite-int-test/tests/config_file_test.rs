@@ -0,0 +1,130 @@
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::Document;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::all;
+use nitrite::nitrite::Nitrite;
+use nitrite::nitrite_builder::NitriteBuilder;
+use nitrite_fjall_adapter::FjallModule;
+use nitrite_int_test::test_util::random_path;
+use nitrite_spatial::SpatialModule;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes a configuration file next to the database directory.
+fn write_config(db_path: &str, extension: &str, content: &str) -> PathBuf {
+    let config_path = PathBuf::from(format!("{}.{}", db_path, extension));
+    fs::write(&config_path, content).expect("failed to write configuration file");
+    config_path
+}
+
+fn open_from_config(config_path: &PathBuf) -> Nitrite {
+    NitriteBuilder::from_config_file(config_path)
+        .module_factory("fjall", |config| FjallModule::from_settings(config.store()))
+        .module_factory("spatial", |_| Ok(SpatialModule))
+        .open_or_create(None, None)
+        .expect("failed to open database from configuration file")
+}
+
+#[test]
+fn test_open_from_toml_and_yaml_config() {
+    let db_path = random_path();
+    let toml_path = write_config(
+        &db_path,
+        "toml",
+        &format!(
+            "field_separator = \".\"\n\
+             [store]\n\
+             backend = \"fjall\"\n\
+             path = \"{}\"\n\
+             cache_size = 8388608\n\
+             durability = \"on_commit\"\n\
+             preset = \"low_memory\"\n\
+             [modules]\n\
+             spatial = true\n",
+            db_path.replace('\\', "\\\\")
+        ),
+    );
+
+    let db = open_from_config(&toml_path);
+    let modules: Vec<String> = db
+        .config()
+        .plugins()
+        .iter()
+        .map(|info| info.name().to_string())
+        .collect();
+    assert!(modules.contains(&"nitrite-fjall-adapter".to_string()));
+    assert!(modules.contains(&"nitrite-spatial".to_string()));
+
+    let collection = db.collection("orders").unwrap();
+    collection.insert(doc! { "order": 1 }).unwrap();
+    db.close().unwrap();
+
+    // the same store, configured in YAML without the spatial module
+    let yaml_path = write_config(
+        &db_path,
+        "yaml",
+        &format!("store:\n  backend: fjall\n  path: '{}'\nmodules:\n  spatial: false\n", db_path),
+    );
+    let db = open_from_config(&yaml_path);
+    let modules: Vec<String> = db
+        .config()
+        .plugins()
+        .iter()
+        .map(|info| info.name().to_string())
+        .collect();
+    assert!(!modules.contains(&"nitrite-spatial".to_string()));
+
+    let documents: Vec<Document> = db
+        .collection("orders")
+        .unwrap()
+        .find(all())
+        .unwrap()
+        .map(|document| document.unwrap())
+        .collect();
+    assert_eq!(documents.len(), 1);
+    db.close().unwrap();
+
+    fs::remove_file(toml_path).unwrap();
+    fs::remove_file(yaml_path).unwrap();
+    fs::remove_dir_all(db_path).unwrap();
+}
+
+#[test]
+fn test_config_without_module_factory() {
+    let db_path = random_path();
+    let config_path = write_config(&db_path, "toml", "[modules]\nfts = true\n");
+
+    let result = NitriteBuilder::from_config_file(&config_path).open_or_create(None, None);
+    let err = result.err().expect("unregistered module should be rejected");
+    assert_eq!(err.kind(), &ErrorKind::PluginLoadFailed);
+
+    fs::remove_file(config_path).unwrap();
+}
+
+#[test]
+fn test_invalid_store_setting() {
+    let db_path = random_path();
+    let config_path = write_config(
+        &db_path,
+        "toml",
+        &format!("[store]\nbackend = \"fjall\"\npath = \"{}\"\nfsync_frequency = \"often\"\n", db_path),
+    );
+
+    let result = NitriteBuilder::from_config_file(&config_path)
+        .module_factory("fjall", |config| FjallModule::from_settings(config.store()))
+        .open_or_create(None, None);
+    let err = result.err().expect("invalid setting should be rejected");
+    assert_eq!(err.kind(), &ErrorKind::ValidationError);
+
+    fs::remove_file(config_path).unwrap();
+}
+
+#[test]
+fn test_missing_config_file() {
+    let result = NitriteBuilder::from_config_file(format!("{}.toml", random_path()))
+        .open_or_create(None, None);
+    let err = result.err().expect("missing file should be rejected");
+    assert_eq!(err.kind(), &ErrorKind::FileNotFound);
+}
@@ -1,8 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use nitrite::common::{Convertible, Value};
+    use nitrite::common::{Convertible, Value, ValuePool, DOC_SCHEMA_VERSION};
     use nitrite::doc;
-    use nitrite::errors::NitriteResult;
+    use nitrite::errors::{ErrorKind, NitriteResult};
     use nitrite_derive::Convertible;
 
     #[test]
@@ -343,4 +343,99 @@ mod tests {
             panic!("Expected Book::Horror variant");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_versioned_convertible_struct() {
+        #[derive(Convertible, Debug, Default, PartialEq)]
+        #[convertible(version = 2, upgrade = "Book::upgrade")]
+        pub struct Book {
+            id: i32,
+            title: String,
+        }
+
+        impl Book {
+            fn upgrade(version: u32, mut doc: nitrite::collection::Document) -> NitriteResult<nitrite::collection::Document> {
+                if version < 2 {
+                    let name = doc.get("name")?;
+                    doc.put("title", name)?;
+                    doc.remove("name")?;
+                }
+                Ok(doc)
+            }
+        }
+
+        let book = Book {
+            id: 1,
+            title: "Book1".to_string(),
+        };
+        let value = book.to_value().unwrap();
+        let document = value.as_document().unwrap();
+        assert_eq!(document.get(DOC_SCHEMA_VERSION).unwrap(), Value::U32(2));
+        assert_eq!(Book::from_value(&value).unwrap(), book);
+
+        // a document written before the type was versioned is upgraded on read
+        let old = doc!{
+            "id": 1,
+            "name": "Book1",
+        };
+        assert_eq!(Book::from_value(&Value::from(old)).unwrap(), book);
+    }
+
+    #[test]
+    fn test_versioned_convertible_struct_without_upgrade() {
+        #[derive(Convertible, Debug, Default)]
+        #[convertible(version = 3)]
+        pub struct Book {
+            id: i32,
+        }
+
+        let old = doc!{
+            "id": 1,
+            "_schema_version": 2,
+        };
+        let error = Book::from_value(&Value::from(old)).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::DocumentTooOld {
+                version: 2,
+                current: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_derived_convertible_reuses_array_buffers() {
+        #[derive(Convertible, Debug, Default, PartialEq)]
+        pub struct Order {
+            id: i32,
+            items: Vec<String>,
+            quantities: Vec<Vec<i32>>,
+        }
+
+        let documents: Vec<Value> = (0..10)
+            .map(|i| {
+                Order {
+                    id: i,
+                    items: vec![format!("item{}", i), "extra".to_string()],
+                    quantities: vec![vec![i, 1], vec![2]],
+                }
+                .to_value()
+                .unwrap()
+            })
+            .collect();
+
+        ValuePool::clear();
+        for (i, document) in documents.iter().enumerate() {
+            let order = Order::from_value(document).unwrap();
+            assert_eq!(order.id, i as i32);
+            assert_eq!(order.items, vec![format!("item{}", i), "extra".to_string()]);
+            assert_eq!(order.quantities, vec![vec![i as i32, 1], vec![2]]);
+        }
+
+        // 4 arrays per order; only the first order allocates, and only 3 buffers, as the
+        // buffer of its items is released before its quantities are read
+        let stats = ValuePool::stats();
+        assert_eq!(stats.allocated, 3);
+        assert_eq!(stats.reused, 37);
+    }
+}
@@ -0,0 +1,98 @@
+//! Disk watchdog on the Fjall store: a volume running out of space suspends the writes
+//! and the data written before stays readable.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::all;
+use nitrite::store::disk::{DiskUsage, DiskWatchdog};
+use nitrite::store::{StoreEventListener, StoreEvents};
+use nitrite_int_test::test_util::{open_fjall_db_with, random_path};
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const MIB: u64 = 1024 * 1024;
+
+#[test]
+fn test_watchdog_reads_the_store_volume() {
+    let path = random_path();
+    {
+        let db = open_fjall_db_with(&path, |builder| builder.disk_watchdog(DiskWatchdog::new()));
+        let monitor = db.disk_monitor().unwrap().unwrap();
+        assert_eq!(monitor.path().to_str(), Some(path.as_str()));
+
+        let usage = monitor.last_usage().unwrap();
+        assert!(usage.total() > 0);
+        assert!(usage.available() <= usage.total());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_low_disk_space_suspends_writes() {
+    let path = random_path();
+    let free = Arc::new(AtomicU64::new(800 * MIB));
+    let simulated = free.clone();
+    let watchdog = DiskWatchdog::new()
+        .interval(Duration::from_millis(20))
+        .warn_below(200 * MIB)
+        .read_only_below(50 * MIB)
+        .resume_above(100 * MIB)
+        .probe(move |_| Ok(DiskUsage::new(simulated.load(Ordering::SeqCst), 1024 * MIB)));
+    {
+        let db = open_fjall_db_with(&path, |builder| builder.disk_watchdog(watchdog.clone()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        db.store()
+            .subscribe(StoreEventListener::new(move |info| {
+                recorded.lock().unwrap().push(info.event());
+                Ok(())
+            }))
+            .unwrap();
+
+        let orders = db.collection("orders").unwrap();
+        orders.insert(doc! { number: 1 }).unwrap();
+
+        // the scheduled checks notice the volume filling up
+        free.store(20 * MIB, Ordering::SeqCst);
+        let monitor = db.disk_monitor().unwrap().unwrap();
+        let mut waited = 0;
+        while !monitor.writes_suspended() && waited < 250 {
+            std::thread::sleep(Duration::from_millis(20));
+            waited += 1;
+        }
+        assert!(monitor.writes_suspended());
+        assert_eq!(
+            events.lock().unwrap()[..2],
+            [
+                StoreEvents::DiskSpaceLow { available: 20 * MIB, threshold: 200 * MIB },
+                StoreEvents::WritesSuspended { available: 20 * MIB },
+            ]
+        );
+
+        let error = orders.insert(doc! { number: 2 }).err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::DiskFull);
+        assert_eq!(orders.find(all()).unwrap().count(), 1);
+
+        // closing does not need to write
+        db.close().unwrap();
+    }
+    {
+        // still low when reopened, the writes are suspended from the start
+        let db = open_fjall_db_with(&path, |builder| builder.disk_watchdog(watchdog));
+        assert!(db.disk_monitor().unwrap().unwrap().writes_suspended());
+        let orders = db.collection("orders").unwrap();
+        assert_eq!(orders.size().unwrap(), 1);
+
+        free.store(150 * MIB, Ordering::SeqCst);
+        db.disk_monitor().unwrap().unwrap().check().unwrap();
+        orders.insert(doc! { number: 2 }).unwrap();
+        assert_eq!(orders.size().unwrap(), 2);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
@@ -1,4 +1,8 @@
-use nitrite::collection::{insert_if_absent, CollectionEventInfo, CollectionEventListener, CollectionEvents};
+use nitrite::collection::{
+    insert_if_absent, CollectionEventInfo, CollectionEventListener, CollectionEvents, CollectionOptions,
+    OverflowPolicy,
+};
+use nitrite::errors::ErrorKind;
 use nitrite::doc;
 use nitrite::filter::field;
 use nitrite_derive::{Convertible, NitriteEntity};
@@ -282,4 +286,237 @@ fn test_single_event_listener() {
         },
         cleanup,
     )
-}
\ No newline at end of file
+}
+
+/// Subscribes to `collection` and returns the events it receives, in sequence order.
+fn record_events(
+    collection: &nitrite::collection::NitriteCollection,
+) -> nitrite::errors::NitriteResult<Arc<Mutex<Vec<CollectionEventInfo>>>> {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    collection.subscribe(CollectionEventListener::new(move |event: CollectionEventInfo| {
+        let mut events = events_clone.lock().unwrap();
+        events.push(event);
+        events.sort_by_key(|event| event.sequence());
+        Ok(())
+    }))?;
+    Ok(events)
+}
+
+#[test]
+fn test_event_images() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx
+                .db()
+                .collection_with_options("images", CollectionOptions::new().event_images(true))?;
+            let events = record_events(&collection)?;
+
+            let id = collection.insert(doc!{ "name": "a", "count": 1 })?.affected_nitrite_ids()[0];
+            collection.update(field("name").eq("a"), &doc!{ "count": 2 })?;
+            collection.remove(field("name").eq("a"), false)?;
+            wait_for_event(1000, || events.lock().unwrap().len() == 3);
+
+            let events = events.lock().unwrap();
+            assert!(events.windows(2).all(|pair| pair[0].sequence() < pair[1].sequence()));
+            assert!(events.iter().all(|event| event.document_id() == Some(id)));
+            assert!(events.iter().all(|event| event.transaction_id().is_none()));
+
+            let (insert, update, remove) = (&events[0], &events[1], &events[2]);
+            assert_eq!(insert.event_type(), CollectionEvents::Insert);
+            assert!(insert.before().is_none());
+            assert_eq!(insert.after().unwrap().get("count")?, 1.into());
+
+            assert_eq!(update.event_type(), CollectionEvents::Update);
+            assert_eq!(update.before().unwrap().get("count")?, 1.into());
+            assert_eq!(update.after().unwrap().get("count")?, 2.into());
+
+            assert_eq!(remove.event_type(), CollectionEvents::Remove);
+            assert_eq!(remove.before().unwrap().get("count")?, 2.into());
+            assert!(remove.after().is_none());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_event_images_off_by_default() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("plain")?;
+            let events = record_events(&collection)?;
+
+            let id = collection.insert(doc!{ "name": "a" })?.affected_nitrite_ids()[0];
+            collection.update(field("name").eq("a"), &doc!{ "name": "b" })?;
+            wait_for_event(1000, || events.lock().unwrap().len() == 2);
+
+            let events = events.lock().unwrap();
+            for event in events.iter() {
+                assert_eq!(event.document_id(), Some(id));
+                assert!(event.item().is_some());
+                assert!(event.before().is_none());
+                assert!(event.after().is_none());
+            }
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_transaction_commit_events_carry_origin() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let collection = db.collection("origin")?;
+            let events = record_events(&collection)?;
+
+            let (session_id, transaction_id) = db.with_session(|session| {
+                let transaction = session.begin_transaction()?;
+                let tx_col = transaction.collection("origin")?;
+                tx_col.insert(doc!{ "name": "a" })?;
+                tx_col.insert(doc!{ "name": "b" })?;
+                let ids = (session.id().to_string(), transaction.id().to_string());
+                transaction.commit()?;
+                Ok(ids)
+            })?;
+            collection.insert(doc!{ "name": "c" })?;
+            wait_for_event(1000, || events.lock().unwrap().len() == 3);
+
+            let events = events.lock().unwrap();
+            for event in &events[..2] {
+                assert_eq!(event.transaction_id(), Some(transaction_id.as_str()));
+                assert_eq!(event.session_id(), Some(session_id.as_str()));
+            }
+            assert!(events[2].transaction_id().is_none());
+            assert!(events[2].session_id().is_none());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_queued_listener_does_not_block_writes() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            let (permits, gate) = std::sync::mpsc::channel::<()>();
+            let gate = Mutex::new(gate);
+            let seen = Arc::new(Mutex::new(Vec::new()));
+            let seen_clone = seen.clone();
+            let subscriber = collection.subscribe(
+                CollectionEventListener::new(move |event: CollectionEventInfo| {
+                    let _ = gate.lock().unwrap().recv();
+                    seen_clone.lock().unwrap().push(event.sequence());
+                    Ok(())
+                })
+                .named("slow")
+                .queued(3, OverflowPolicy::DropOldest)?,
+            )?;
+
+            // the listener holds the first event while the others fill its queue
+            for i in 0..6 {
+                collection.insert(doc! { n: i })?;
+            }
+            let listener = ctx.db().describe()?.listeners.remove(0);
+            assert_eq!(listener.collection, "test");
+            assert_eq!(listener.name.as_deref(), Some("slow"));
+            assert_eq!(listener.capacity, Some(3));
+            assert_eq!(listener.overflow, Some(OverflowPolicy::DropOldest));
+            assert!(listener.dropped >= 2);
+            assert!(!listener.is_healthy());
+
+            for _ in 0..6 {
+                let _ = permits.send(());
+            }
+            wait_for_event(2000, || {
+                let description = ctx.db().describe().unwrap();
+                let listener = &description.listeners[0];
+                listener.queued == 0 && listener.delivered + listener.dropped == 6
+            });
+            let seen = seen.lock().unwrap().clone();
+            assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+
+            collection.unsubscribe(subscriber.unwrap())?;
+            assert!(ctx.db().describe()?.listeners.is_empty());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_queued_listener_rejects_when_full() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            let (permits, gate) = std::sync::mpsc::channel::<()>();
+            let gate = Mutex::new(gate);
+            collection.subscribe(
+                CollectionEventListener::new(move |_event: CollectionEventInfo| {
+                    let _ = gate.lock().unwrap().recv();
+                    Ok(())
+                })
+                .queued(1, OverflowPolicy::Error)?,
+            )?;
+
+            let id = collection.insert(doc! { n: 0 })?.affected_nitrite_ids()[0];
+            wait_for_event(1000, || ctx.db().describe().unwrap().listeners[0].queued == 0);
+            collection.update_by_id(&id, &doc! { n: 1 }, false)?;
+
+            // the queue is full, the event of the update is refused and the update undone
+            let error = collection.update_by_id(&id, &doc! { n: 2 }, false).unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::EventError);
+            assert_eq!(collection.get_by_id(&id)?.unwrap().get("n")?, nitrite::common::Value::I32(1));
+            assert_eq!(ctx.db().describe()?.listeners[0].rejected, 1);
+
+            for _ in 0..2 {
+                let _ = permits.send(());
+            }
+            wait_for_event(1000, || ctx.db().describe().unwrap().listeners[0].delivered == 2);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_describe_reports_direct_listeners() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("test")?;
+            collection.subscribe(CollectionEventListener::new(|event: CollectionEventInfo| {
+                match event.event_type() {
+                    CollectionEvents::Remove => Err(nitrite::errors::NitriteError::new(
+                        "cannot handle removals",
+                        ErrorKind::EventError,
+                    )),
+                    _ => Ok(()),
+                }
+            }))?;
+            collection.insert(doc! { n: 1 })?;
+            let _ = collection.remove(field("n").eq(1), false);
+
+            let description = ctx.db().describe()?;
+            assert!(description.collections.contains(&"test".to_string()));
+            let listener = &description.listeners[0];
+            assert_eq!(listener.capacity, None);
+            assert_eq!(listener.delivered, 1);
+            assert_eq!(listener.failed, 1);
+            assert_eq!(description.unhealthy_listeners().count(), 1);
+
+            collection.close()?;
+            assert!(ctx.db().describe()?.listeners.is_empty());
+            Ok(())
+        },
+        cleanup,
+    )
+}
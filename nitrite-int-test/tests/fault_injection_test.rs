@@ -0,0 +1,87 @@
+//! Fault injection on the Fjall store: failed writes surface as errors and can be
+//! retried.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::Document;
+use nitrite::doc;
+use nitrite::errors::{ErrorKind, NitriteResult};
+use nitrite::filter::field;
+use nitrite::index::unique_index;
+use nitrite::nitrite::Nitrite;
+use nitrite::store::fault::{Fault, MapOperation};
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+use std::time::{Duration, Instant};
+
+fn insert_with_retry(db: &Nitrite, document: Document, attempts: u32) -> NitriteResult<u32> {
+    let orders = db.collection("orders")?;
+    let mut attempt = 1;
+    loop {
+        match orders.insert(document.clone()) {
+            Ok(_) => return Ok(attempt),
+            Err(error) if error.kind() == &ErrorKind::IOError && attempt < attempts => attempt += 1,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[test]
+fn test_injected_write_failures_are_retried() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        orders.create_index(vec!["number"], &unique_index()).unwrap();
+        let faults = db.fault_injector().unwrap();
+
+        // the first two writes of a document fail, the third attempt goes through
+        let fault = faults.inject(Fault::fail().on_map("orders").on_writes().times(2));
+        assert_eq!(insert_with_retry(&db, doc! { number: 1 }, 5).unwrap(), 3);
+        assert_eq!(faults.hits(fault), 2);
+        faults.clear();
+
+        // a failed index write fails the insert, and the document is not kept
+        faults.inject(Fault::fail().on_map_prefix("$nitrite_index|orders|").on_writes().nth(1));
+        let error = orders.insert(doc! { number: 2 }).err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::IOError);
+        assert_eq!(orders.size().unwrap(), 1);
+        faults.clear();
+        orders.insert(doc! { number: 3 }).unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        assert_eq!(orders.find(field("number").eq(1)).unwrap().count(), 1);
+        assert_eq!(orders.find(field("number").eq(3)).unwrap().count(), 1);
+        assert_eq!(orders.size().unwrap(), 2);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_injected_delay_slows_reads() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        let mut order = doc! { number: 1 };
+        let id = order.id().unwrap();
+        orders.insert(order).unwrap();
+
+        let faults = db.fault_injector().unwrap();
+        faults.inject(
+            Fault::delay(Duration::from_millis(100))
+                .on_map("orders")
+                .on(MapOperation::Get)
+                .matching_key(id),
+        );
+        let start = Instant::now();
+        assert!(orders.get_by_id(&id).unwrap().is_some());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,69 @@
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
+use nitrite::repository::ObjectRepository;
+use nitrite::testing::Fixtures;
+use nitrite_derive::{Convertible, NitriteEntity};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[derive(Clone, Debug, Default, PartialEq, Convertible, NitriteEntity)]
+#[entity(name = "fixture_customers", id(field = "code"), index(type = "unique", fields = "email"))]
+pub struct Customer {
+    code: String,
+    name: String,
+    email: String,
+}
+
+const SHOP: &str = r#"
+[[repositories.fixture_customers]]
+"$label" = "alice"
+code = "C-1"
+name = "Alice"
+email = "alice@example.com"
+
+[[repositories.fixture_customers]]
+code = "C-2"
+name = "Bob"
+email = "bob@example.com"
+
+[[collections.orders]]
+customer = { "$ref" = "alice.code" }
+placed_by = { "$ref" = "alice" }
+total = 42
+"#;
+
+#[test]
+fn test_fixtures_seed_repositories() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            // the repository does not exist yet, it is opened for the registered type
+            let error = Fixtures::from_toml(SHOP)?.load(&db).err().unwrap();
+            assert_eq!(error.kind(), &ErrorKind::RepositoryNotFound);
+
+            let fixtures = Fixtures::from_toml(SHOP)?.repository::<Customer>();
+            let seeded = fixtures.load(&db)?;
+
+            let customers: ObjectRepository<Customer> = db.repository()?;
+            let alice = customers.get_by_id(&"C-1".to_string())?.unwrap();
+            assert_eq!(alice.name, "Alice");
+            assert!(customers.has_index(vec!["email"])?);
+
+            let orders = db.collection("orders")?;
+            let order = orders.find(field("customer").eq("C-1"))?.next().unwrap()?;
+            assert_eq!(
+                order.get("placed_by")?.as_nitrite_id(),
+                seeded.id("alice").as_ref()
+            );
+
+            // a test changes the data, the next one starts from the fixtures again
+            customers.remove(field("code").eq("C-2"), false)?;
+            orders.insert(nitrite::doc! { customer: "C-3" })?;
+            fixtures.reset(&db)?;
+            assert_eq!(customers.size()?, 2);
+            assert_eq!(orders.size()?, 1);
+            Ok(())
+        },
+        cleanup,
+    )
+}
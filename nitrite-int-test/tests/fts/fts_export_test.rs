@@ -0,0 +1,158 @@
+//! Integration tests for exporting and importing full-text index data.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::archive::{ArchiveExporter, ArchiveImporter};
+use nitrite::collection::NitriteCollection;
+use nitrite::doc;
+use nitrite::errors::{ErrorKind, NitriteResult};
+use nitrite::filter::all;
+use nitrite::index::IndexExport;
+use nitrite_int_test::test_util::{cleanup, create_fts_test_context, run_test};
+use nitrite_tantivy_fts::{fts_field, fts_index};
+
+fn insert_articles(collection: &NitriteCollection) -> NitriteResult<()> {
+    collection.insert(doc! { title: "Fox", content: "A quick brown fox jumps over the lazy dog" })?;
+    collection.insert(doc! { title: "Rust", content: "Rust is a fast and safe systems language" })?;
+    collection.insert(doc! { title: "Dogs", content: "The lazy dog sleeps all day" })?;
+    Ok(())
+}
+
+/// Copies the documents of `source` into `target`, under the same ids.
+fn copy_documents(source: &NitriteCollection, target: &NitriteCollection) -> NitriteResult<()> {
+    for document in source.find(all())? {
+        target.insert(document?)?;
+    }
+    Ok(())
+}
+
+fn search_count(collection: &NitriteCollection, term: &str) -> NitriteResult<usize> {
+    Ok(collection.find(fts_field("content").matches(term))?.count())
+}
+
+#[test]
+fn test_import_fts_index_into_copy_of_collection() {
+    run_test(
+        create_fts_test_context,
+        |ctx| {
+            let source = ctx.db().collection("articles")?;
+            source.create_index(vec!["content"], &fts_index())?;
+            insert_articles(&source)?;
+
+            let path = format!("{}/content.idx", ctx.path());
+            source.export_index(vec!["content"])?.write_to_file(&path)?;
+
+            let target_ctx = create_fts_test_context()?;
+            let target = target_ctx.db().collection("articles")?;
+            copy_documents(&source, &target)?;
+            assert_eq!(target.content_hash()?, source.content_hash()?);
+
+            let export = IndexExport::read_from_file(&path)?;
+            assert_eq!(export.collection_name(), "articles");
+            target.import_index(&export)?;
+
+            assert!(target.has_index(vec!["content"])?);
+            assert_eq!(search_count(&target, "lazy")?, 2);
+            assert_eq!(search_count(&target, "rust")?, 1);
+
+            // the imported index is maintained like a built one
+            target.insert(doc! { title: "Cats", content: "A lazy cat" })?;
+            assert_eq!(search_count(&target, "lazy")?, 3);
+
+            cleanup(target_ctx)
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_import_fts_index_rejects_other_documents() {
+    run_test(
+        create_fts_test_context,
+        |ctx| {
+            let source = ctx.db().collection("articles")?;
+            source.create_index(vec!["content"], &fts_index())?;
+            insert_articles(&source)?;
+            let export = source.export_index(vec!["content"])?;
+
+            let target_ctx = create_fts_test_context()?;
+            let target = target_ctx.db().collection("articles")?;
+            copy_documents(&source, &target)?;
+            target.insert(doc! { title: "Extra", content: "Not in the source" })?;
+
+            let err = target.import_index(&export).unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::ValidationError);
+            assert!(!target.has_index(vec!["content"])?);
+
+            cleanup(target_ctx)
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_archive_restores_fts_index() {
+    run_test(
+        create_fts_test_context,
+        |ctx| {
+            let source = ctx.db().collection("articles")?;
+            source.create_index(vec!["content"], &fts_index())?;
+            insert_articles(&source)?;
+
+            let mut archive = Vec::new();
+            let manifest = ArchiveExporter::new(&ctx.db()).export_to(&mut archive)?;
+            let indexes = &manifest.collection("articles").unwrap().indexes;
+            assert!(indexes.iter().all(|index| index.has_data));
+
+            let target_ctx = create_fts_test_context()?;
+            let summary = ArchiveImporter::new(&target_ctx.db()).import_from(archive.as_slice())?;
+            assert_eq!(summary.inserted, 3);
+            assert_eq!(summary.restored_indexes, 1);
+
+            let target = target_ctx.db().collection("articles")?;
+            assert_eq!(search_count(&target, "lazy")?, 2);
+
+            cleanup(target_ctx)
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_archive_rebuilds_fts_index_when_data_does_not_match() {
+    run_test(
+        create_fts_test_context,
+        |ctx| {
+            let source = ctx.db().collection("articles")?;
+            source.create_index(vec!["content"], &fts_index())?;
+            insert_articles(&source)?;
+
+            let mut archive = Vec::new();
+            ArchiveExporter::new(&ctx.db()).export_to(&mut archive)?;
+
+            // the target already holds a document the archived index does not know
+            let target_ctx = create_fts_test_context()?;
+            let target = target_ctx.db().collection("articles")?;
+            target.insert(doc! { title: "Local", content: "A lazy local article" })?;
+
+            let summary = ArchiveImporter::new(&target_ctx.db()).import_from(archive.as_slice())?;
+            assert_eq!(summary.restored_indexes, 0);
+            assert!(target.has_index(vec!["content"])?);
+            assert_eq!(search_count(&target, "lazy")?, 3);
+
+            // without index data the index is built from the documents as well
+            let mut archive = Vec::new();
+            ArchiveExporter::new(&ctx.db())
+                .include_index_data(false)
+                .export_to(&mut archive)?;
+            let plain_ctx = create_fts_test_context()?;
+            let summary = ArchiveImporter::new(&plain_ctx.db()).import_from(archive.as_slice())?;
+            assert_eq!(summary.restored_indexes, 0);
+            assert_eq!(search_count(&plain_ctx.db().collection("articles")?, "lazy")?, 2);
+
+            cleanup(plain_ctx)?;
+            cleanup(target_ctx)
+        },
+        cleanup,
+    )
+}
@@ -1 +1,2 @@
+pub mod fts_export_test;
 pub mod fts_index_test;
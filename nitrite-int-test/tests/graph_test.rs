@@ -0,0 +1,73 @@
+//! Graphs on the Fjall store: edges and the vertex collection survive a restart.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::NitriteId;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::graph::{Direction, Traversal};
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+#[test]
+fn test_graph_survives_reopen() {
+    let path = random_path();
+    let ids: Vec<NitriteId>;
+    {
+        let db = open_fjall_db(&path);
+        let people = db.collection("people").unwrap();
+        ids = (0..10)
+            .map(|n| {
+                let mut person = doc! { name: (format!("p{}", n)) };
+                let id = person.id().unwrap();
+                people.insert(person).unwrap();
+                id
+            })
+            .collect();
+
+        // a chain of friends p0 -> p1 -> ... -> p9, with a shortcut p0 -> p5
+        let social = db.graph("social", "people").unwrap();
+        for pair in ids.windows(2) {
+            social.add_edge(&pair[0], &pair[1], "friend").unwrap();
+        }
+        social.add_edge(&ids[0], &ids[5], "friend").unwrap();
+        social.add_edge(&ids[9], &ids[0], "follows").unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let error = db.graph("social", "companies").err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+
+        let social = db.graph("social", "people").unwrap();
+        let friends = Traversal::new().label("friend");
+        let path = social.shortest_path(&ids[0], &ids[9], &friends).unwrap().unwrap();
+        let names: Vec<String> = path
+            .iter()
+            .map(|person| person.get("name").unwrap().as_string().unwrap().clone())
+            .collect();
+        assert_eq!(names, vec!["p0", "p5", "p6", "p7", "p8", "p9"]);
+
+        let visits = social.bfs(&ids[0], &friends.clone().max_depth(2)).unwrap();
+        assert_eq!(visits.len(), 5);
+        assert_eq!(visits.iter().filter(|visit| visit.depth == 2).count(), 2);
+        assert_eq!(social.dfs(&ids[0], &friends).unwrap().len(), 10);
+
+        let followers = social.neighbors(&ids[0], Direction::Incoming, Some("follows")).unwrap();
+        assert_eq!(followers.len(), 1);
+        assert_eq!(followers[0].get("name").unwrap().as_string().unwrap(), "p9");
+
+        db.drop_graph("social").unwrap();
+        assert_eq!(db.collection("people").unwrap().size().unwrap(), 10);
+        db.close().unwrap();
+    }
+    {
+        // dropped with its vertex collection, it can be created again over another one
+        let db = open_fjall_db(&path);
+        db.collection("companies").unwrap();
+        let social = db.graph("social", "companies").unwrap();
+        assert!(social.edges(&ids[0], Direction::Both).unwrap().is_empty());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,60 @@
+//! Revision history on the Fjall store: recorded versions and the history setting must
+//! survive a reopen.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::{HistoryOptions, NitriteId};
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn test_history_survives_reopen() {
+    let path = random_path();
+    let id: NitriteId;
+    let before_update;
+    {
+        let db = open_fjall_db(&path);
+        let items = db.collection("items").unwrap();
+        items
+            .enable_history(HistoryOptions::new().max_versions(10))
+            .unwrap();
+        id = items.insert(doc! { name: "a", qty: 1 }).unwrap().affected_nitrite_ids()[0];
+        thread::sleep(Duration::from_millis(5));
+        before_update = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        thread::sleep(Duration::from_millis(5));
+        items.update(field("name").eq("a"), &doc! { qty: 2 }).unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let items = db.collection("items").unwrap();
+        assert_eq!(
+            items.history_options().unwrap().unwrap().get_max_versions(),
+            Some(10)
+        );
+
+        // writes after the reopen are still recorded
+        items.update(field("name").eq("a"), &doc! { qty: 3 }).unwrap();
+        let revisions: Vec<i32> = items
+            .document_history(&id)
+            .unwrap()
+            .iter()
+            .map(|version| version.revision)
+            .collect();
+        assert_eq!(revisions, vec![1, 2, 3]);
+
+        let as_of: Vec<Value> = items
+            .find_as_of(before_update, field("name").eq("a"))
+            .unwrap()
+            .map(|doc| doc.unwrap().get("qty").unwrap())
+            .collect();
+        assert_eq!(as_of, vec![Value::I32(1)]);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
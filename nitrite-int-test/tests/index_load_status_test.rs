@@ -0,0 +1,53 @@
+//! Indexes of a reopened persistent database are opened on first use, and
+//! `Nitrite::index_load_status` reports the ones still to be loaded.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite::index::{non_unique_index, unique_index};
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+#[test]
+fn test_index_load_status_after_reopen() {
+    let path = random_path();
+
+    {
+        let db = open_fjall_db(&path);
+        let users = db.collection("users").unwrap();
+        users.create_index(vec!["email"], &unique_index()).unwrap();
+        users.create_index(vec!["age"], &non_unique_index()).unwrap();
+        for i in 0..10 {
+            users
+                .insert(doc! { "email": (format!("u{}@x.io", i)), "age": (i % 3) })
+                .unwrap();
+        }
+        db.collection("events").unwrap().insert(doc! { "n": 1 }).unwrap();
+        assert!(db.index_load_status().unwrap().is_complete());
+        db.commit().unwrap();
+        db.close().unwrap();
+    }
+
+    let db = open_fjall_db(&path);
+    let status = db.index_load_status().unwrap();
+    assert_eq!(status.indexes.len(), 2);
+    assert_eq!(status.loaded_count(), 0);
+
+    // a query opens the index it uses, and only that one
+    let users = db.collection("users").unwrap();
+    assert_eq!(users.find(field("age").eq(1)).unwrap().count(), 3);
+    let status = db.index_load_status().unwrap();
+    assert_eq!(status.loaded_count(), 1);
+    let pending: Vec<_> = status
+        .pending()
+        .map(|index| index.index_fields().field_names())
+        .collect();
+    assert_eq!(pending, vec![vec!["email".to_string()]]);
+
+    db.warm_up(["users"]).unwrap().wait().unwrap();
+    assert!(db.index_load_status().unwrap().is_complete());
+
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,168 @@
+//! Indexes left inconsistent with their documents, as after a crash between a
+//! document write and its index writes, are detected and handled when the database
+//! is opened with an `IndexRecoveryPolicy`.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::{Document, NitriteId};
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
+use nitrite::index::{non_unique_index, unique_index};
+use nitrite::index_check::{IndexIssue, IndexRecoveryPolicy};
+use nitrite::nitrite::Nitrite;
+use nitrite::nitrite_builder::NitriteBuilder;
+use nitrite_fjall_adapter::FjallModule;
+use nitrite_int_test::test_util::random_path;
+use std::fs;
+
+fn builder(path: &str) -> NitriteBuilder {
+    let storage_module = FjallModule::with_config()
+        .db_path(path)
+        .low_memory_preset()
+        .build();
+    Nitrite::builder().load_module(storage_module)
+}
+
+/// Creates a collection whose indexes miss the entries of one document.
+fn create_inconsistent_db(path: &str) {
+    let db = builder(path).open_or_create(None, None).unwrap();
+    let users = db.collection("users").unwrap();
+    users.create_index(vec!["email"], &unique_index()).unwrap();
+    users
+        .create_index(vec!["age"], &non_unique_index())
+        .unwrap();
+    for i in 0..5 {
+        users
+            .insert(doc! { "email": (format!("u{}@x.io", i)), "age": (i % 2) })
+            .unwrap();
+    }
+
+    // written to the collection map only, bypassing the indexes
+    let id = NitriteId::new();
+    let mut ghost = doc! { "email": "ghost@x.io", "age": 7 };
+    ghost.put("_id", Value::NitriteId(id)).unwrap();
+    db.store()
+        .open_map("users")
+        .unwrap()
+        .put(Value::NitriteId(id), Value::Document(ghost))
+        .unwrap();
+
+    db.commit().unwrap();
+    db.close().unwrap();
+}
+
+fn find_email(db: &Nitrite, email: &str) -> Vec<Document> {
+    db.collection("users")
+        .unwrap()
+        .find(field("email").eq(email))
+        .unwrap()
+        .map(|doc| doc.unwrap())
+        .collect()
+}
+
+#[test]
+fn test_check_without_policy_only_reports() {
+    let path = random_path();
+    create_inconsistent_db(&path);
+
+    let db = builder(&path).open_or_create(None, None).unwrap();
+    let report = db.check_indexes().unwrap();
+    assert_eq!(report.checked, 2);
+    assert_eq!(report.inconsistent.len(), 2);
+    for index in &report.inconsistent {
+        assert_eq!(
+            index.issues,
+            vec![IndexIssue::MissingEntries { documents: 1 }]
+        );
+    }
+    // the index is still used, so the document is not found
+    assert!(find_email(&db, "ghost@x.io").is_empty());
+
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_fail_policy_refuses_to_open() {
+    let path = random_path();
+    create_inconsistent_db(&path);
+
+    let err = builder(&path)
+        .index_recovery_policy(IndexRecoveryPolicy::Fail)
+        .open_or_create(None, None)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), &ErrorKind::IndexingError);
+
+    // the store was released, so the database opens again
+    let db = builder(&path).open_or_create(None, None).unwrap();
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_rebuild_policy_repairs_indexes() {
+    let path = random_path();
+    create_inconsistent_db(&path);
+
+    let db = builder(&path)
+        .index_recovery_policy(IndexRecoveryPolicy::Rebuild)
+        .open_or_create(None, None)
+        .unwrap();
+    assert!(db.check_indexes().unwrap().is_consistent());
+    assert_eq!(find_email(&db, "ghost@x.io").len(), 1);
+    let users = db.collection("users").unwrap();
+    assert_eq!(users.find(field("age").eq(7)).unwrap().count(), 1);
+
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_disable_policy_disables_until_rebuild() {
+    let path = random_path();
+    create_inconsistent_db(&path);
+
+    {
+        let db = builder(&path)
+            .index_recovery_policy(IndexRecoveryPolicy::Disable)
+            .open_or_create(None, None)
+            .unwrap();
+        let report = db.check_indexes().unwrap();
+        assert_eq!(report.checked, 0);
+        assert_eq!(report.disabled.len(), 2);
+
+        // queries scan the collection instead of the disabled indexes
+        assert_eq!(find_email(&db, "ghost@x.io").len(), 1);
+        let users = db.collection("users").unwrap();
+        users
+            .insert(doc! { "email": "new@x.io", "age": 7 })
+            .unwrap();
+        assert_eq!(users.find(field("age").eq(7)).unwrap().count(), 2);
+
+        users.rebuild_index(vec!["email"]).unwrap();
+        let report = db.check_indexes().unwrap();
+        assert_eq!(report.checked, 1);
+        assert!(report.is_consistent());
+        db.commit().unwrap();
+        db.close().unwrap();
+    }
+
+    // the other index stays disabled across a reopen until it is rebuilt
+    let db = builder(&path)
+        .index_recovery_policy(IndexRecoveryPolicy::Disable)
+        .open_or_create(None, None)
+        .unwrap();
+    let report = db.check_indexes().unwrap();
+    assert_eq!(report.disabled.len(), 1);
+    assert_eq!(
+        report.disabled[0].index_fields().field_names(),
+        vec!["age".to_string()]
+    );
+    assert_eq!(find_email(&db, "new@x.io").len(), 1);
+
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
@@ -1,10 +1,25 @@
 use std::fs;
 
-use nitrite::{common::{Value, NON_UNIQUE_INDEX}, doc, migration::Migration, nitrite::Nitrite};
+use nitrite::{common::{Value, NON_UNIQUE_INDEX}, doc, errors::ErrorKind, migration::Migration, nitrite::Nitrite};
+use nitrite_derive::{Convertible, NitriteEntity};
 use nitrite_fjall_adapter::FjallModule;
 use nitrite_int_test::test_util::random_path;
 
-use crate::{generate_book, Book, MyBook};
+use crate::{generate_book, Book, BookId, MyBook};
+
+/// `Book` without its `tags` field, stored in the same repository.
+#[derive(Debug, Convertible, NitriteEntity, Default, Clone)]
+#[entity(
+    name = "books",
+    id(field = "book_id", embedded_fields = "isbn, name"),
+    index(type = "unique", fields = "price, publisher")
+)]
+pub struct BookWithoutTags {
+    pub book_id: BookId,
+    pub publisher: Option<String>,
+    pub price: Option<f64>,
+    pub description: Option<String>,
+}
 
 // ==================== Basic Migration Tests ====================
 
@@ -674,6 +689,64 @@ fn test_migration_repository_delete_field() {
     fs::remove_dir_all(path).expect("Failed to remove database directory");
 }
 
+#[test]
+fn test_migration_repository_schema_change() {
+    let path = random_path();
+
+    let storage_module = FjallModule::with_config()
+        .db_path(&path)
+        .build();
+
+    let db = Nitrite::builder()
+        .load_module(storage_module)
+        .open_or_create(None, None).expect("Failed to create initial database");
+
+    let books = db.repository::<Book>().expect("Failed to open Book repository");
+    books.insert_many(vec![generate_book(), generate_book()]).expect("Failed to insert books");
+
+    db.close().expect("Failed to close initial database");
+
+    // Opening the repository with a changed entity requires a migration
+    let storage_module = FjallModule::with_config()
+        .db_path(&path)
+        .build();
+
+    let db = Nitrite::builder()
+        .load_module(storage_module)
+        .open_or_create(None, None).expect("Failed to reopen database");
+
+    let err = db.repository::<BookWithoutTags>().err().expect("Schema change should be detected");
+    assert_eq!(err.kind(), &ErrorKind::SchemaChanged(vec!["tags".to_string()]));
+
+    db.close().expect("Failed to close database");
+
+    let storage_module = FjallModule::with_config()
+        .db_path(&path)
+        .build();
+
+    let migration = Migration::new(1, 2, |instruction| {
+        instruction.for_repository("books", None)
+            .delete_field("tags");
+        Ok(())
+    });
+
+    let db = Nitrite::builder()
+        .load_module(storage_module)
+        .schema_version(2)
+        .add_migration(migration)
+        .open_or_create(None, None).expect("Failed to apply migration");
+
+    let books = db.repository::<BookWithoutTags>().expect("Failed to open migrated repository");
+    assert_eq!(books.size().expect("Failed to count books"), 2);
+    // The new schema is recorded, so the old entity is now rejected
+    let err = db.repository::<Book>().err().expect("Schema change should be detected");
+    assert_eq!(err.kind(), &ErrorKind::SchemaChanged(vec!["tags".to_string()]));
+
+    db.close().expect("Failed to close database");
+
+    fs::remove_dir_all(path).expect("Failed to remove database directory");
+}
+
 #[test]
 fn test_migration_repository_add_field() {
     let path = random_path();
@@ -1,4 +1,5 @@
 // Based on Java NitriteBuilderTest.java
+use nitrite::collection::NitriteId;
 use nitrite::nitrite::Nitrite;
 use nitrite::doc;
 use nitrite::filter::all;
@@ -98,3 +99,20 @@ fn test_builder_close() {
 
     assert!(db.is_closed().unwrap());
 }
+
+#[test]
+fn test_builder_node_id() {
+    let db = Nitrite::builder()
+        .node_id(42)
+        .open_or_create(None, None)
+        .expect("Failed to create database");
+
+    let collection = db.collection("test").unwrap();
+    let result = collection.insert(doc!{"key": "value"}).unwrap();
+    let id = result.affected_nitrite_ids()[0];
+    // the node id sits above the 12 sequence bits
+    assert_eq!((id.id_value() >> 12) & 1023, 42);
+    assert_eq!(NitriteId::generator_metrics().node_id(), 42);
+
+    db.close().unwrap();
+}
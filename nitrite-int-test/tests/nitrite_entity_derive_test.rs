@@ -28,6 +28,25 @@ mod tests {
         assert_eq!(entity_indexes, None);
     }
 
+    #[test]
+    fn test_nitrite_entity_schema() {
+        #[derive(NitriteEntity, Convertible, Default)]
+        #[converter(ignored = "cache")]
+        pub struct Book {
+            id: i32,
+            tags: Vec<String>,
+            cache: Option<String>,
+        }
+
+        let schema = Book::default().entity_schema().unwrap();
+        let fields: Vec<(&str, &str)> = schema
+            .fields()
+            .iter()
+            .map(|(name, type_name)| (name.as_str(), type_name.as_str()))
+            .collect();
+        assert_eq!(fields, vec![("id", "i32"), ("tags", "Vec<String>")]);
+    }
+
     #[test]
     fn test_nitrite_entity_with_name() {
         #[derive(NitriteEntity, Default)]
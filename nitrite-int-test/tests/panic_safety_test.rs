@@ -0,0 +1,122 @@
+// Malformed input given to the public API must come back as an error, never as a panic
+// unwinding into the application.
+use nitrite::archive::ArchiveImporter;
+use nitrite::collection::{order_by, Document, NitriteId};
+use nitrite::common::{SortOrder, Value};
+use nitrite::csv::CsvImporter;
+use nitrite::filter::{and, field, not, or};
+use nitrite::index::{full_text_index, non_unique_index, unique_index, IndexOptions};
+use nitrite::nitrite::Nitrite;
+use nitrite::try_doc;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Runs `f` and fails the test if it panics; its result, Ok or Err, is not checked.
+fn assert_no_panic<T>(what: &str, f: impl FnOnce() -> T) {
+    if catch_unwind(AssertUnwindSafe(f)).is_err() {
+        panic!("{} panicked", what);
+    }
+}
+
+const BAD_KEYS: [&str; 10] = ["", ".", "..", "a..b", ".a", "a.", "a.-1", "a.0.", "_id", "/~2"];
+
+#[test]
+fn test_malformed_documents_do_not_panic() {
+    for key in BAD_KEYS {
+        assert_no_panic(key, || {
+            let mut doc = Document::new();
+            let _ = doc.put(key, 1);
+            let _ = doc.put(key, Value::Null);
+            let _ = doc.get(key);
+            let _ = doc.get_path(key);
+            let _ = doc.query_path(key);
+            let _ = doc.remove(key);
+        });
+    }
+
+    assert!(try_doc!{ "a..b": 1 }.is_err());
+    assert!(try_doc!{ nested: { "": 1 } }.is_err());
+}
+
+#[test]
+fn test_malformed_queries_do_not_panic() {
+    let db = Nitrite::builder().open_or_create(None, None).unwrap();
+    let collection = db.collection("test").unwrap();
+    collection
+        .insert_many(vec![
+            try_doc!{ a: 1, b: "x", c: { d: [1, 2] } }.unwrap(),
+            try_doc!{ a: "text", b: (Value::Null) }.unwrap(),
+            Document::new(),
+        ])
+        .unwrap();
+    collection.create_index(vec!["a"], &non_unique_index()).unwrap();
+    collection.create_index(vec!["b"], &full_text_index()).unwrap();
+
+    for key in BAD_KEYS {
+        assert_no_panic(key, || {
+            let filters = vec![
+                field(key).eq(1),
+                field(key).gt("x"),
+                field(key).between(5, 1, true, false),
+                field(key).in_array(Vec::<i32>::new()),
+                field(key).text_regex("(["),
+                field(key).text(""),
+                field(key).elem_match(field(key).lt(Value::Null)),
+                and(vec![field("a").gte(1), field("a").lt(1), not(field(key).eq(1))]),
+                or(vec![field("b").text("*"), field(key).ne(Value::Null)]),
+            ];
+            for filter in filters {
+                if let Ok(cursor) = collection.find(filter.clone()) {
+                    cursor.for_each(drop);
+                }
+                let options = order_by(key, SortOrder::Descending).skip(u64::MAX).limit(0);
+                if let Ok(cursor) = collection.find_with_options(filter.clone(), &options) {
+                    cursor.for_each(drop);
+                }
+                let _ = collection.update(filter.clone(), &Document::new());
+                let _ = collection.remove(filter, true);
+            }
+            let _ = collection.create_index(vec![key], &unique_index());
+            let _ = collection.create_index(vec![key, key], &non_unique_index());
+            let _ = collection.create_index(vec![key], &IndexOptions::new("missing"));
+            let _ = collection.drop_index(vec![key]);
+        });
+    }
+
+    assert_no_panic("get_by_id", || {
+        let _ = collection.get_by_id(&NitriteId::new());
+        let _ = NitriteId::create_id(0);
+        let _ = NitriteId::create_id(u64::MAX);
+    });
+    db.close().unwrap();
+}
+
+#[test]
+fn test_malformed_sql_and_imports_do_not_panic() {
+    let db = Nitrite::builder().open_or_create(None, None).unwrap();
+    let collection = db.collection("test").unwrap();
+
+    for query in [
+        "",
+        "SELECT",
+        "SELECT * FROM",
+        "SELECT * FROM test WHERE",
+        "SELECT * FROM test WHERE a = ",
+        "SELECT * FROM test ORDER BY",
+        "SELECT * FROM test LIMIT -1",
+        "SELECT * FROM test WHERE a IN ()",
+        "SELECT * FROM test WHERE ((a = 1)",
+        "SELECT * FROM 'unterminated",
+    ] {
+        assert_no_panic(query, || db.sql(query));
+    }
+
+    assert_no_panic("csv import", || {
+        let _ = CsvImporter::new(&collection).import_from("a,b\n1\n\"x,2,3\n".as_bytes());
+        let _ = CsvImporter::new(&collection).import_from(&[0xff, 0xfe, 0x00][..]);
+    });
+    assert_no_panic("archive import", || {
+        let _ = ArchiveImporter::new(&db).import_from(&b"not an archive"[..]);
+        let _ = ArchiveImporter::new(&db).import_from(&[][..]);
+    });
+    db.close().unwrap();
+}
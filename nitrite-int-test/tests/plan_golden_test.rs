@@ -0,0 +1,80 @@
+//! Golden plans of indexed queries: each query must keep the plan recorded under
+//! `tests/plans`. Run with `NITRITE_UPDATE_PLANS=1` to record them again.
+
+use nitrite::assert_plan_uses_index;
+use nitrite::collection::{order_by, NitriteCollection};
+use nitrite::common::SortOrder;
+use nitrite::doc;
+use nitrite::filter::{and, field, or};
+use nitrite::index::{non_unique_index, unique_index};
+use nitrite::nitrite::Nitrite;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+fn golden(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("plans")
+        .join(name)
+}
+
+fn people(db: &Nitrite) -> NitriteCollection {
+    let people = db.collection("people").unwrap();
+    people.create_index(vec!["email"], &unique_index()).unwrap();
+    people.create_index(vec!["last", "first"], &non_unique_index()).unwrap();
+    people.create_index(vec!["age"], &non_unique_index()).unwrap();
+    people
+        .insert_many(vec![
+            doc! { email: "ada@example.com", first: "Ada", last: "Lovelace", age: 36 },
+            doc! { email: "grace@example.com", first: "Grace", last: "Hopper", age: 85 },
+        ])
+        .unwrap();
+    people
+}
+
+#[test]
+fn test_plans_match_golden_files() {
+    let db = Nitrite::builder().open_or_create(None, None).unwrap();
+    let people = people(&db);
+
+    let cursor = people.find(field("email").eq("ada@example.com")).unwrap();
+    let plan = cursor.find_plan().unwrap();
+    assert_plan_uses_index!(plan, "email");
+    plan.snapshot().check_golden(golden("by_email.plan")).unwrap();
+
+    let cursor = people
+        .find_with_options(
+            and(vec![field("last").eq("Lovelace"), field("first").eq("Ada")]),
+            &order_by("age", SortOrder::Descending).limit(10),
+        )
+        .unwrap();
+    let plan = cursor.find_plan().unwrap();
+    assert_plan_uses_index!(plan, "last", "first");
+    plan.snapshot().check_golden(golden("by_name.plan")).unwrap();
+
+    let cursor = people
+        .find(or(vec![field("email").eq("ada@example.com"), field("age").gt(80)]))
+        .unwrap();
+    let plan = cursor.find_plan().unwrap();
+    assert_plan_uses_index!(plan, "email");
+    assert_plan_uses_index!(plan, "age");
+    plan.snapshot().check_golden(golden("email_or_age.plan")).unwrap();
+    db.close().unwrap();
+}
+
+#[test]
+fn test_unindexed_query_is_reported() {
+    let db = Nitrite::builder().open_or_create(None, None).unwrap();
+    let people = people(&db);
+
+    let cursor = people.find(field("first").eq("Ada")).unwrap();
+    let snapshot = cursor.find_plan().unwrap().snapshot();
+    assert!(!snapshot.uses_any_index());
+    let indexed = people.find(field("email").eq("ada@example.com")).unwrap();
+    assert_ne!(snapshot, indexed.find_plan().unwrap().snapshot());
+
+    let plan = cursor.find_plan().unwrap();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| assert_plan_uses_index!(plan, "first")));
+    assert!(result.is_err());
+    db.close().unwrap();
+}
@@ -0,0 +1,44 @@
+//! The profiler on the Fjall store, where reading documents decodes them from disk.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::Document;
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite::index::non_unique_index;
+use nitrite::profiler::Phase;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+use std::time::Duration;
+
+#[test]
+fn test_profile_of_an_indexed_find() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        orders.create_index(vec!["customer"], &non_unique_index()).unwrap();
+        let documents: Vec<Document> = (0..500)
+            .map(|n| doc! { customer: (format!("c{}", n % 10)), total: n })
+            .collect();
+        orders.insert_many(documents).unwrap();
+
+        db.profiler().enable();
+        let found = orders.find(field("customer").eq("c3")).unwrap().count();
+        assert_eq!(found, 50);
+
+        let profile = &db.profiler().recent(1)[0];
+        assert_eq!(profile.operation(), "find");
+        assert_eq!(profile.collection(), "orders");
+        assert!(profile.phase(Phase::Planning) > Duration::ZERO);
+        assert!(profile.phase(Phase::IndexScan) > Duration::ZERO);
+        assert!(profile.phase(Phase::Deserialization) > Duration::ZERO);
+        assert_eq!(profile.phase(Phase::IndexMaintenance), Duration::ZERO);
+
+        let folded = profile.to_folded();
+        assert!(folded.contains("orders;find;deserialization "));
+        assert!(folded.lines().all(|line| line.starts_with("orders;find")));
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,321 @@
+// Property-based tests for document paths and filter evaluation.
+//
+// Random documents, filter trees and index sets are generated with proptest; a failing case
+// is shrunk to a minimal reproduction. Set PROPTEST_CASES to run more cases locally.
+use nitrite::collection::{Document, NitriteCollection};
+use nitrite::common::Value;
+use nitrite::filter::{and, field, not, or, Filter};
+use nitrite::index::non_unique_index;
+use nitrite::nitrite::Nitrite;
+use proptest::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Runs `cases` cases unless PROPTEST_CASES asks for a different number.
+fn config(cases: u32) -> ProptestConfig {
+    let mut config = ProptestConfig::default();
+    if std::env::var_os("PROPTEST_CASES").is_none() {
+        config.cases = cases;
+    }
+    config
+}
+
+/// A generated document: each field is absent when `None`.
+#[derive(Debug, Clone)]
+struct Row {
+    a: Option<i32>,
+    b: Option<String>,
+    d: Option<i32>,
+}
+
+impl Row {
+    fn to_document(&self, seq: usize) -> Document {
+        let mut doc = Document::new();
+        doc.put("seq", seq as i64).unwrap();
+        if let Some(a) = self.a {
+            doc.put("a", a).unwrap();
+        }
+        if let Some(b) = &self.b {
+            doc.put("b", b.as_str()).unwrap();
+        }
+        if let Some(d) = self.d {
+            doc.put("c.d", d).unwrap();
+        }
+        doc
+    }
+}
+
+fn row() -> impl Strategy<Value = Row> {
+    (
+        proptest::option::of(0..6i32),
+        proptest::option::of(prop::sample::select(vec!["x", "y", "z"]).prop_map(String::from)),
+        proptest::option::of(-3..3i32),
+    )
+        .prop_map(|(a, b, d)| Row { a, b, d })
+}
+
+/// A leaf comparison, kept as data so failing cases print readably.
+#[derive(Debug, Clone)]
+enum Leaf {
+    Eq(&'static str, Value),
+    Ne(&'static str, Value),
+    Gt(&'static str, Value),
+    Gte(&'static str, Value),
+    Lt(&'static str, Value),
+    Lte(&'static str, Value),
+    In(&'static str, Vec<Value>),
+    NotIn(&'static str, Vec<Value>),
+}
+
+#[derive(Debug, Clone)]
+enum FilterTree {
+    Leaf(Leaf),
+    And(Vec<FilterTree>),
+    Or(Vec<FilterTree>),
+    Not(Box<FilterTree>),
+}
+
+impl FilterTree {
+    fn to_filter(&self) -> Filter {
+        match self {
+            FilterTree::Leaf(leaf) => match leaf.clone() {
+                Leaf::Eq(name, value) => field(name).eq(value),
+                Leaf::Ne(name, value) => field(name).ne(value),
+                Leaf::Gt(name, value) => field(name).gt(value),
+                Leaf::Gte(name, value) => field(name).gte(value),
+                Leaf::Lt(name, value) => field(name).lt(value),
+                Leaf::Lte(name, value) => field(name).lte(value),
+                Leaf::In(name, values) => field(name).in_array(values),
+                Leaf::NotIn(name, values) => field(name).not_in_array(values),
+            },
+            FilterTree::And(children) => and(children.iter().map(Self::to_filter).collect()),
+            FilterTree::Or(children) => or(children.iter().map(Self::to_filter).collect()),
+            FilterTree::Not(child) => not(child.to_filter()),
+        }
+    }
+}
+
+/// A field name together with a value of that field's type.
+fn field_value() -> impl Strategy<Value = (&'static str, Value)> {
+    prop_oneof![
+        (0..6i32).prop_map(|v| ("a", Value::from(v))),
+        prop::sample::select(vec!["x", "y", "z", "w"]).prop_map(|v| ("b", Value::from(v))),
+        (-3..3i32).prop_map(|v| ("c.d", Value::from(v))),
+    ]
+}
+
+fn field_values() -> impl Strategy<Value = (&'static str, Vec<Value>)> {
+    prop_oneof![
+        prop::collection::vec((0..6i32).prop_map(Value::from), 1..4).prop_map(|v| ("a", v)),
+        prop::collection::vec(
+            prop::sample::select(vec!["x", "y", "z", "w"]).prop_map(Value::from),
+            1..4
+        )
+        .prop_map(|v| ("b", v)),
+        prop::collection::vec((-3..3i32).prop_map(Value::from), 1..4).prop_map(|v| ("c.d", v)),
+    ]
+}
+
+fn leaf() -> impl Strategy<Value = FilterTree> {
+    prop_oneof![
+        field_value().prop_map(|(f, v)| Leaf::Eq(f, v)),
+        field_value().prop_map(|(f, v)| Leaf::Ne(f, v)),
+        field_value().prop_map(|(f, v)| Leaf::Gt(f, v)),
+        field_value().prop_map(|(f, v)| Leaf::Gte(f, v)),
+        field_value().prop_map(|(f, v)| Leaf::Lt(f, v)),
+        field_value().prop_map(|(f, v)| Leaf::Lte(f, v)),
+        field_values().prop_map(|(f, v)| Leaf::In(f, v)),
+        field_values().prop_map(|(f, v)| Leaf::NotIn(f, v)),
+    ]
+    .prop_map(FilterTree::Leaf)
+}
+
+fn filter_tree() -> impl Strategy<Value = FilterTree> {
+    leaf().prop_recursive(3, 12, 3, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 2..4).prop_map(FilterTree::And),
+            prop::collection::vec(inner.clone(), 2..4).prop_map(FilterTree::Or),
+            inner.prop_map(|child| FilterTree::Not(Box::new(child))),
+        ]
+    })
+}
+
+/// The indexes a collection may be given; a generated set picks any subset of them.
+const INDEXES: [&[&str]; 5] = [&["a"], &["b"], &["c.d"], &["a", "b"], &["b", "c.d"]];
+
+fn index_set() -> impl Strategy<Value = Vec<usize>> {
+    prop::sample::subsequence((0..INDEXES.len()).collect::<Vec<_>>(), 1..=INDEXES.len())
+}
+
+fn open_collection(db: &Nitrite, name: &str, rows: &[Row]) -> NitriteCollection {
+    let collection = db.collection(name).unwrap();
+    let docs: Vec<Document> = rows
+        .iter()
+        .enumerate()
+        .map(|(seq, row)| row.to_document(seq))
+        .collect();
+    if !docs.is_empty() {
+        collection.insert_many(docs).unwrap();
+    }
+    collection
+}
+
+fn matching_seqs(collection: &NitriteCollection, filter: Filter) -> BTreeSet<i64> {
+    collection
+        .find(filter)
+        .unwrap()
+        .map(|doc| doc.unwrap().get("seq").unwrap().as_i64().copied().unwrap())
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(config(64))]
+
+    #[test]
+    fn prop_indexed_find_matches_full_scan(
+        rows in prop::collection::vec(row(), 0..24),
+        tree in filter_tree(),
+        indexes in index_set(),
+    ) {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let scanned = open_collection(&db, "scanned", &rows);
+        let indexed = open_collection(&db, "indexed", &rows);
+        for index in &indexes {
+            indexed.create_index(INDEXES[*index].to_vec(), &non_unique_index()).unwrap();
+        }
+
+        let filter = tree.to_filter();
+        let expected = matching_seqs(&scanned, filter.clone());
+        let actual = matching_seqs(&indexed, filter);
+        db.close().unwrap();
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn prop_index_created_before_insert_matches_full_scan(
+        rows in prop::collection::vec(row(), 0..24),
+        tree in filter_tree(),
+        indexes in index_set(),
+    ) {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let scanned = open_collection(&db, "scanned", &rows);
+        let indexed = db.collection("indexed").unwrap();
+        for index in &indexes {
+            indexed.create_index(INDEXES[*index].to_vec(), &non_unique_index()).unwrap();
+        }
+        for (seq, row) in rows.iter().enumerate() {
+            indexed.insert(row.to_document(seq)).unwrap();
+        }
+
+        let filter = tree.to_filter();
+        let expected = matching_seqs(&scanned, filter.clone());
+        let actual = matching_seqs(&indexed, filter);
+        db.close().unwrap();
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn prop_not_is_complement(
+        rows in prop::collection::vec(row(), 0..24),
+        tree in filter_tree(),
+    ) {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let collection = open_collection(&db, "test", &rows);
+
+        let filter = tree.to_filter();
+        let matched = matching_seqs(&collection, filter.clone());
+        let unmatched = matching_seqs(&collection, not(filter));
+        db.close().unwrap();
+
+        prop_assert!(matched.is_disjoint(&unmatched));
+        prop_assert_eq!(matched.len() + unmatched.len(), rows.len());
+    }
+}
+
+/// A path segment that is never an array index and never contains the separator.
+fn segment() -> impl Strategy<Value = String> {
+    "[a-c]{1,2}"
+}
+
+fn path() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec(segment(), 1..4)
+}
+
+#[derive(Debug, Clone)]
+enum PathOp {
+    Put(Vec<String>, i32),
+    Remove(Vec<String>),
+}
+
+fn path_op() -> impl Strategy<Value = PathOp> {
+    prop_oneof![
+        3 => (path(), any::<i32>()).prop_map(|(path, value)| PathOp::Put(path, value)),
+        1 => path().prop_map(PathOp::Remove),
+    ]
+}
+
+fn starts_with(path: &[String], prefix: &[String]) -> bool {
+    path.len() >= prefix.len() && path[..prefix.len()] == *prefix
+}
+
+proptest! {
+    #![proptest_config(config(256))]
+
+    /// Replays random puts and removes of embedded keys against a model holding the leaf
+    /// values by path.
+    #[test]
+    fn prop_embedded_put_get_remove_matches_model(ops in prop::collection::vec(path_op(), 1..24)) {
+        let mut doc = Document::new();
+        let mut model: BTreeMap<Vec<String>, i32> = BTreeMap::new();
+
+        for op in ops {
+            match op {
+                PathOp::Put(path, value) => {
+                    doc.put(path.join("."), value).unwrap();
+                    // the value replaces anything below it and any leaf on the way to it
+                    model.retain(|leaf, _| !starts_with(leaf, &path) && !starts_with(&path, leaf));
+                    model.insert(path, value);
+                }
+                PathOp::Remove(path) => {
+                    doc.remove(&path.join(".")).unwrap();
+                    model.retain(|leaf, _| !starts_with(leaf, &path));
+                }
+            }
+        }
+
+        for (path, value) in &model {
+            prop_assert_eq!(doc.get(&path.join(".")).unwrap(), Value::from(*value));
+            let pointer = format!("/{}", path.join("/"));
+            prop_assert_eq!(doc.get_path(&pointer).unwrap(), Value::from(*value));
+        }
+        let fields: BTreeSet<String> = doc.fields().into_iter().collect();
+        let expected: BTreeSet<String> = model.keys().map(|path| path.join(".")).collect();
+        prop_assert_eq!(fields, expected);
+    }
+
+    /// Arbitrary keys, including empty segments, separators at the edges and array-index
+    /// look-alikes, must produce errors rather than panics, and a rejected put must leave
+    /// the document untouched.
+    #[test]
+    fn prop_arbitrary_keys_never_panic(
+        keys in prop::collection::vec("[ab0.\\-/~]{0,6}", 1..12),
+        pointer in "[ab0/~1]{0,8}",
+    ) {
+        let mut doc = Document::new();
+        doc.put("arr", vec![Value::from(1), Value::from(2)]).unwrap();
+
+        for key in &keys {
+            let before = doc.clone();
+            if doc.put(key.as_str(), 7).is_err() {
+                prop_assert_eq!(&doc, &before);
+            }
+            let _ = doc.get(key);
+            let _ = doc.contains_field(key);
+            let _ = doc.get_path(&pointer);
+            let _ = doc.query_path(key);
+        }
+        for key in &keys {
+            let _ = doc.remove(key);
+            let _ = doc.fields();
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// Repositories of entities described at runtime, sharing their collection with typed ones.
+
+use nitrite::collection::{Document, NitriteId};
+use nitrite::common::{PersistentCollection, Value, NON_UNIQUE_INDEX};
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite::repository::{EntityDescriptor, EntityId, EntityIndex};
+use nitrite_derive::{Convertible, NitriteEntity};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[derive(Debug, Convertible, NitriteEntity, Default, Clone, PartialEq)]
+#[entity(name = "plugin_books", id(field = "isbn"), index(type = "non-unique", fields = "author"))]
+pub struct PluginBook {
+    pub isbn: String,
+    pub title: String,
+    pub author: String,
+}
+
+fn plugin_books() -> EntityDescriptor {
+    EntityDescriptor::new("plugin_books")
+        .id(EntityId::new("isbn", None, None))
+        .index(EntityIndex::new(vec!["author"], Some(NON_UNIQUE_INDEX)))
+}
+
+#[test]
+fn test_dynamic_and_typed_repositories_share_entities() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let dynamic = db.dynamic_repository(plugin_books())?;
+            dynamic.insert(doc! { isbn: "1", title: "Dune", author: "Herbert" })?;
+
+            let typed = db.repository::<PluginBook>()?;
+            let book = typed.get_by_id(&"1".to_string())?;
+            assert_eq!(book.map(|book| book.title), Some("Dune".to_string()));
+
+            typed.insert(PluginBook {
+                isbn: "2".to_string(),
+                title: "Emma".to_string(),
+                author: "Austen".to_string(),
+            })?;
+            let book = dynamic.get_by_id(&Value::from("2"))?.unwrap();
+            assert_eq!(book.get("author")?, Value::from("Austen"));
+
+            // the typed repository rejects the duplicate id written through the dynamic one
+            assert!(dynamic.insert(doc! { isbn: "2", title: "Copy" }).is_err());
+            assert_eq!(dynamic.find(field("author").eq("Herbert"))?.count(), 1);
+            assert_eq!(typed.size()?, 2);
+
+            // the id declared by the typed repository wins
+            assert!(db.dynamic_repository(EntityDescriptor::new("plugin_books")).is_err());
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_dynamic_repository_with_nitrite_id() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let descriptor = EntityDescriptor::new("plugin_notes")
+                .id(EntityId::new("note_id", Some(true), None));
+            let notes = db.keyed_dynamic_repository(descriptor, "tenant")?;
+
+            notes.insert(doc! { text: "first" })?;
+            let note: Document = notes.find(field("text").eq("first"))?.next().unwrap()?;
+            let id = note.get("note_id")?;
+            assert!(id.as_nitrite_id().is_some());
+
+            // a NitriteId cannot be chosen on insert
+            let chosen = NitriteId::new();
+            let mut document = doc! { text: "second" };
+            document.put("note_id", chosen)?;
+            assert!(notes.insert(document).is_err());
+
+            let mut update = note.clone();
+            update.put("text", "edited")?;
+            notes.update_one(update, false)?;
+            let note = notes.get_by_id(&id)?.unwrap();
+            assert_eq!(note.get("text")?, Value::from("edited"));
+
+            assert!(db.list_keyed_repositories()?.contains_key("tenant"));
+            notes.remove_one(&note)?;
+            assert_eq!(notes.size()?, 0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_dynamic_repository_with_embedded_id() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let descriptor = EntityDescriptor::new("plugin_lines")
+                .id(EntityId::new("line_id", None, Some(vec!["order", "number"])));
+            let lines = db.dynamic_repository(descriptor)?;
+            assert!(lines.has_index(vec!["line_id.order", "line_id.number"])?);
+
+            lines.update_one(doc! { line_id: { order: "A", number: 1 }, quantity: 3 }, true)?;
+            lines.update_one(doc! { line_id: { order: "A", number: 2 }, quantity: 1 }, true)?;
+            lines.update_one(doc! { line_id: { order: "A", number: 1 }, quantity: 5 }, true)?;
+            assert_eq!(lines.size()?, 2);
+
+            let id = Value::Document(doc! { order: "A", number: 1 });
+            let line = lines.get_by_id(&id)?.unwrap();
+            assert_eq!(line.get("quantity")?, Value::from(5));
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,85 @@
+// Repositories whose entities are identified by a closure.
+
+use nitrite::filter::field;
+use nitrite_derive::{Convertible, NitriteEntity};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[derive(Debug, Convertible, NitriteEntity, Default, Clone, PartialEq)]
+#[entity(name = "order_lines", index(type = "non-unique", fields = "sku"))]
+pub struct OrderLine {
+    pub order_no: String,
+    pub line_no: i64,
+    pub sku: String,
+    pub quantity: i64,
+}
+
+fn order_line(order_no: &str, line_no: i64, sku: &str, quantity: i64) -> OrderLine {
+    OrderLine {
+        order_no: order_no.to_string(),
+        line_no,
+        sku: sku.to_string(),
+        quantity,
+    }
+}
+
+#[test]
+fn test_upsert_by_composite_id() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo = ctx
+                .db()
+                .repository_with_id_fn::<OrderLine, (String, i64)>(|line| {
+                    (line.order_no.clone(), line.line_no)
+                })?;
+
+            repo.update_one(order_line("A-100", 1, "apple", 3), true)?;
+            repo.update_one(order_line("A-100", 2, "pear", 1), true)?;
+            repo.update_one(order_line("A-101", 1, "apple", 2), true)?;
+            repo.update_one(order_line("A-100", 1, "apple", 10), true)?;
+            assert_eq!(repo.size()?, 3);
+
+            let line = repo.get_by_id(&("A-100".to_string(), 1))?;
+            assert_eq!(line, Some(order_line("A-100", 1, "apple", 10)));
+            assert_eq!(repo.find(field("sku").eq("apple"))?.count(), 2);
+
+            // an update of a missing id without upsert changes nothing
+            let result = repo.update_one(order_line("B-1", 1, "plum", 1), false)?;
+            assert!(result.affected_nitrite_ids().is_empty());
+
+            repo.remove_one(order_line("A-100", 2, "", 0))?;
+            assert!(repo.get_by_id(&("A-100".to_string(), 2))?.is_none());
+            assert_eq!(repo.size()?, 2);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_id_fn_ids_survive_reopen() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let db = ctx.db();
+            let id_fn = |line: &OrderLine| format!("{}/{}", line.order_no, line.line_no);
+
+            let repo = db.repository_with_id_fn::<OrderLine, String>(id_fn)?;
+            repo.insert_many(vec![
+                order_line("A-100", 1, "apple", 3),
+                order_line("A-100", 2, "pear", 1),
+            ])?;
+            assert!(repo.insert(order_line("A-100", 1, "plum", 5)).is_err());
+            db.commit()?;
+
+            // a second handle sees the same ids
+            let repo = db.repository_with_id_fn::<OrderLine, String>(id_fn)?;
+            let line = repo.get_by_id(&"A-100/2".to_string())?;
+            assert_eq!(line, Some(order_line("A-100", 2, "pear", 1)));
+            repo.remove_by_id(&"A-100/2".to_string())?;
+            assert_eq!(repo.size()?, 1);
+            Ok(())
+        },
+        cleanup,
+    )
+}
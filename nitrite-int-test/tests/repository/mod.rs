@@ -7,6 +7,9 @@ mod repository_modification_test;
 mod repository_search_test;
 mod repository_factory_test;
 mod object_cursor_test;
+mod id_fn_repository_test;
+mod dynamic_repository_test;
+mod upsert_many_test;
 
 use fake::faker::address::en::{CityName, CountryCode, StreetName, ZipCode};
 use fake::faker::barcode::en::Isbn;
@@ -1,4 +1,8 @@
 // Based on Java RepositoryFactoryTest.java
+use nitrite::collection::Document;
+use nitrite::errors::{ErrorKind, NitriteResult};
+use nitrite::common::Value;
+use nitrite::filter::field;
 use nitrite::repository::ObjectRepository;
 use nitrite_derive::{Convertible, NitriteEntity};
 use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
@@ -10,6 +14,35 @@ pub struct TestEntity {
     value: Option<i32>,
 }
 
+#[derive(Clone, Debug, Default, Convertible, NitriteEntity)]
+#[entity(name = "TestEntity")]
+pub struct ChangedTestEntity {
+    id: Option<String>,
+    name: Option<String>,
+    value: Option<i64>,
+    created: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Convertible, NitriteEntity)]
+#[entity(name = "TestEntity")]
+#[convertible(version = 2, upgrade = "VersionedTestEntity::upgrade")]
+pub struct VersionedTestEntity {
+    id: Option<String>,
+    label: Option<String>,
+    value: Option<i32>,
+}
+
+impl VersionedTestEntity {
+    fn upgrade(version: u32, mut doc: Document) -> NitriteResult<Document> {
+        if version < 2 {
+            let name = doc.get("name")?;
+            doc.put("label", name)?;
+            doc.remove("name")?;
+        }
+        Ok(doc)
+    }
+}
+
 #[test]
 fn test_repository_creation() {
     run_test(
@@ -82,3 +115,82 @@ fn test_repository_name() {
         cleanup,
     )
 }
+
+#[test]
+fn test_repository_schema_changed() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let _repo: ObjectRepository<TestEntity> = ctx.db().repository()?;
+            // reopening with the same entity keeps working
+            let _repo: ObjectRepository<TestEntity> = ctx.db().repository()?;
+
+            let result = ctx.db().repository::<ChangedTestEntity>();
+            let err = result.err().expect("schema change must be detected");
+            assert_eq!(
+                err.kind(),
+                &ErrorKind::SchemaChanged(vec!["created".to_string(), "value".to_string()])
+            );
+
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_repository_schema_changed_with_version() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo: ObjectRepository<TestEntity> = ctx.db().repository()?;
+            repo.insert(TestEntity {
+                id: Some("1".to_string()),
+                name: Some("first".to_string()),
+                value: Some(10),
+            })?;
+
+            // a higher version accepts the new fields and upgrades the old entities
+            let repo: ObjectRepository<VersionedTestEntity> = ctx.db().repository()?;
+            let entity = repo.find(field("value").eq(10))?.first().unwrap()?;
+            assert_eq!(entity.label, Some("first".to_string()));
+            repo.insert(VersionedTestEntity {
+                id: Some("2".to_string()),
+                label: Some("second".to_string()),
+                value: Some(20),
+            })?;
+            assert_eq!(repo.size()?, 2);
+
+            // the new schema is recorded, so going back without a version is a change
+            let err = ctx.db().repository::<TestEntity>().err().unwrap();
+            assert!(matches!(err.kind(), ErrorKind::SchemaChanged(_)));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_repository_collection() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo: ObjectRepository<TestEntity> = ctx.db().keyed_repository("test")?;
+            repo.insert(TestEntity {
+                id: Some("1".to_string()),
+                name: Some("first".to_string()),
+                value: Some(10),
+            })?;
+
+            let collection = ctx.db().repository_collection("TestEntity", Some("test"))?;
+            assert_eq!(collection.name(), repo.document_collection().name());
+            let document = collection.find(field("name").eq("first"))?.first().unwrap()?;
+            assert_eq!(document.get("value")?, Value::I32(10));
+
+            let err = ctx.db().repository_collection("TestEntity", None).err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::RepositoryNotFound);
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,139 @@
+// Bulk upserts matched on an external identifier or on the entity id.
+
+use nitrite::collection::{ConflictTarget, UpsertManyOptions};
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
+use nitrite_derive::{Convertible, NitriteEntity};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[derive(Debug, Convertible, NitriteEntity, Default, Clone, PartialEq)]
+#[entity(name = "feed_books", index(type = "unique", fields = "isbn"))]
+pub struct FeedBook {
+    pub isbn: String,
+    pub title: String,
+    pub stock: i64,
+}
+
+#[derive(Debug, Convertible, NitriteEntity, Default, Clone, PartialEq)]
+#[entity(name = "feed_authors", id(field = "code"))]
+pub struct FeedAuthor {
+    pub code: String,
+    pub name: String,
+}
+
+fn book(isbn: &str, title: &str, stock: i64) -> FeedBook {
+    FeedBook {
+        isbn: isbn.to_string(),
+        title: title.to_string(),
+        stock,
+    }
+}
+
+#[test]
+fn test_upsert_many_on_unique_index() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo = ctx.db().repository::<FeedBook>()?;
+            repo.insert_many(vec![book("111", "Dune", 1), book("222", "Emma", 2)])?;
+
+            let feed: Vec<FeedBook> = (0..50)
+                .map(|i| book(&format!("{:03}", 200 + i), &format!("Title {}", i), i))
+                .collect();
+            let result = repo.upsert_many_with_options(
+                feed,
+                ConflictTarget::index("isbn"),
+                &UpsertManyOptions::new().batch_size(7),
+            )?;
+            assert_eq!(result.matched_count(), 1);
+            assert_eq!(result.inserted_ids().len(), 49);
+            assert_eq!(repo.size()?, 51);
+
+            let emma = repo.find(field("isbn").eq("222"))?.first().unwrap()?;
+            assert_eq!(emma, book("222", "Title 22", 22));
+            let dune = repo.find(field("isbn").eq("111"))?.first().unwrap()?;
+            assert_eq!(dune, book("111", "Dune", 1));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_upsert_many_is_atomic() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo = ctx.db().repository::<FeedBook>()?;
+            repo.insert(book("111", "Dune", 1))?;
+
+            // a feed record without an isbn fails the whole upsert
+            let error = repo
+                .document_collection()
+                .upsert_many(
+                    vec![doc! { isbn: "333", title: "Ulysses" }, doc! { title: "Unknown" }],
+                    ConflictTarget::index("isbn"),
+                )
+                .unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::MissingRequiredField);
+            assert_eq!(repo.size()?, 1);
+
+            let error = repo
+                .upsert_many(vec![book("444", "Walden", 1)], ConflictTarget::index("title"))
+                .unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::IndexNotFound);
+            assert_eq!(repo.size()?, 1);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_upsert_many_on_entity_id() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo = ctx.db().repository::<FeedAuthor>()?;
+            let author = |code: &str, name: &str| FeedAuthor {
+                code: code.to_string(),
+                name: name.to_string(),
+            };
+            repo.insert(author("A1", "Herbert"))?;
+
+            let result = repo.upsert_many(
+                vec![author("A1", "Frank Herbert"), author("A2", "Austen"), author("A2", "Jane Austen")],
+                ConflictTarget::Id,
+            )?;
+            assert_eq!(result.matched_count(), 1);
+            assert_eq!(result.inserted_ids().len(), 1);
+            assert_eq!(repo.get_by_id(&"A1".to_string())?, Some(author("A1", "Frank Herbert")));
+            assert_eq!(repo.get_by_id(&"A2".to_string())?, Some(author("A2", "Jane Austen")));
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_upsert_many_with_id_fn() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo = ctx
+                .db()
+                .repository_with_id_fn::<FeedBook, String>(|book| book.isbn.clone())?;
+            repo.upsert_many(vec![book("111", "Dune", 1)], ConflictTarget::Id)?;
+            let result = repo.upsert_many(
+                vec![book("111", "Dune", 4), book("222", "Emma", 2)],
+                ConflictTarget::Id,
+            )?;
+            assert_eq!(result.matched_count(), 1);
+            assert_eq!(repo.size()?, 2);
+            assert_eq!(repo.get_by_id(&"111".to_string())?, Some(book("111", "Dune", 4)));
+            Ok(())
+        },
+        cleanup,
+    )
+}
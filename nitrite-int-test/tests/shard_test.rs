@@ -0,0 +1,107 @@
+//! Sharded collections on the Fjall store: the layout and documents must survive a reopen,
+//! and each shard must be maintainable on its own.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::{order_by, CollectionEventListener, CollectionEvents};
+use nitrite::common::{SortOrder, Value};
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::{all, field};
+use nitrite::index::non_unique_index;
+use nitrite::shard::ShardOptions;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn by_year() -> ShardOptions {
+    ShardOptions::range("year", vec![Value::from(2020), Value::from(2024)])
+}
+
+#[test]
+fn test_sharded_collection_survives_reopen() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let reports = db.sharded_collection("reports", by_year()).unwrap();
+        for year in [2018, 2021, 2022, 2025] {
+            reports.insert(doc! { year: year, title: (format!("report {}", year)) }).unwrap();
+        }
+        assert_eq!(reports.shard(1).unwrap().size().unwrap(), 2);
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let other = db.sharded_collection("reports", ShardOptions::hash("year", 3));
+        assert!(matches!(other.err().map(|e| e.kind().clone()), Some(ErrorKind::ValidationError)));
+
+        let reports = db.sharded_collection("reports", by_year()).unwrap();
+        let years: Vec<Value> = reports
+            .find_with_options(all(), &order_by("year", SortOrder::Ascending))
+            .unwrap()
+            .map(|document| document.unwrap().get("year").unwrap())
+            .collect();
+        assert_eq!(
+            years,
+            vec![Value::from(2018), Value::from(2021), Value::from(2022), Value::from(2025)]
+        );
+        assert_eq!(reports.count(field("year").eq(2021)).unwrap(), 1);
+        assert!(db.list_collection_names().unwrap().is_empty());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_shards_are_maintained_independently() {
+    let path = random_path();
+    let db = open_fjall_db(&path);
+    let reports = db.sharded_collection("reports", by_year()).unwrap();
+    reports.create_index(vec!["title"], &non_unique_index()).unwrap();
+    for year in [2018, 2021, 2025] {
+        reports.insert(doc! { year: year, title: "annual" }).unwrap();
+    }
+    for shard in reports.shards() {
+        assert!(shard.has_index(vec!["title"]).unwrap());
+    }
+
+    reports.shard(2).unwrap().rebuild_index(vec!["title"]).unwrap();
+    assert_eq!(reports.count(field("title").eq("annual")).unwrap(), 3);
+
+    // a handle keeps the maps of its shards open
+    drop(reports);
+    db.drop_sharded_collection("reports").unwrap();
+    let reports = db.sharded_collection("reports", ShardOptions::hash("year", 2)).unwrap();
+    assert_eq!(reports.size().unwrap(), 0);
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_listener_hears_every_shard() {
+    let path = random_path();
+    let db = open_fjall_db(&path);
+    let reports = db.sharded_collection("reports", by_year()).unwrap();
+    let inserts = Arc::new(AtomicUsize::new(0));
+    let counter = inserts.clone();
+    let subscriber = reports
+        .subscribe(CollectionEventListener::new(move |event| {
+            if event.event_type() == CollectionEvents::Insert {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(())
+        }))
+        .unwrap()
+        .unwrap();
+
+    reports.insert(doc! { year: 2018 }).unwrap();
+    reports.insert(doc! { year: 2025 }).unwrap();
+    assert_eq!(inserts.load(Ordering::SeqCst), 2);
+
+    reports.unsubscribe(subscriber).unwrap();
+    reports.insert(doc! { year: 2021 }).unwrap();
+    assert_eq!(inserts.load(Ordering::SeqCst), 2);
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,94 @@
+//! Database-wide snapshots on the Fjall store: the view must be pinned at the instant it was
+//! opened, across every collection, while writers keep going.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::doc;
+use nitrite::filter::field;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::collections::HashSet;
+use std::fs;
+use std::thread;
+
+#[test]
+fn test_snapshot_is_consistent_across_collections() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        let lines = db.collection("order_lines").unwrap();
+        for i in 0..50 {
+            orders.insert(doc! { order: i }).unwrap();
+            lines.insert(doc! { order: i, qty: 1 }).unwrap();
+        }
+
+        let snapshot = db.snapshot().unwrap();
+
+        let writer = {
+            let db = db.clone();
+            thread::spawn(move || {
+                let orders = db.collection("orders").unwrap();
+                let lines = db.collection("order_lines").unwrap();
+                for i in 50..150 {
+                    orders.insert(doc! { order: i }).unwrap();
+                    lines.insert(doc! { order: i, qty: 1 }).unwrap();
+                }
+                orders.remove(field("order").lt(10), false).unwrap();
+            })
+        };
+
+        let readers: Vec<_> = ["orders", "order_lines"]
+            .into_iter()
+            .map(|name| {
+                let snapshot = snapshot.clone();
+                thread::spawn(move || snapshot.documents(name).unwrap().count())
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), 50);
+        }
+        writer.join().unwrap();
+
+        assert_eq!(snapshot.documents("orders").unwrap().count(), 50);
+        assert_eq!(
+            snapshot
+                .find("orders", field("order").lt(10))
+                .unwrap()
+                .count(),
+            10
+        );
+        assert_eq!(orders.size().unwrap(), 140);
+        assert_eq!(db.snapshot().unwrap().documents("orders").unwrap().count(), 140);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_store_snapshot_reverse_iteration() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let store = db.store();
+        let map = store.open_map("snapshot_map").unwrap();
+        for i in 0..5 {
+            map.put(i.into(), format!("v{}", i).into()).unwrap();
+        }
+
+        let names = HashSet::from(["snapshot_map".to_string(), "missing".to_string()]);
+        let snapshot = store.open_snapshot(&names).unwrap();
+        map.put(10.into(), "late".into()).unwrap();
+
+        let keys: Vec<_> = snapshot
+            .entries("snapshot_map")
+            .unwrap()
+            .rev()
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec![4.into(), 3.into(), 2.into(), 1.into(), 0.into()]);
+        assert_eq!(snapshot.entries("missing").unwrap().count(), 0);
+        assert!(!store.has_map("missing").unwrap());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
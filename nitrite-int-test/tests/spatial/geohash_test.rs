@@ -0,0 +1,98 @@
+//! Integration tests for geohash bucketing over regular string indexes.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::collection::NitriteCollection;
+use nitrite::doc;
+use nitrite::errors::NitriteResult;
+use nitrite::index::non_unique_index;
+use nitrite_int_test::test_util::{cleanup, create_spatial_test_context, run_test};
+use nitrite_spatial::{geohash_bounds, geohash_decode, geohash_encode, geohash_prefix, GeoPoint};
+
+const PLACES: [(&str, f64, f64); 5] = [
+    ("harbour", 57.64911, 10.40744),
+    ("lighthouse", 57.64930, 10.40800),
+    ("church", 57.65200, 10.41500),
+    ("airport", 57.09275, 9.84919),
+    ("copenhagen", 55.67594, 12.56553),
+];
+
+fn insert_places(collection: &NitriteCollection) -> NitriteResult<()> {
+    for (name, latitude, longitude) in PLACES {
+        let location = GeoPoint::new(latitude, longitude)?;
+        collection.insert(doc! {
+            name: name,
+            latitude: latitude,
+            longitude: longitude,
+            geohash: (geohash_encode(&location, 9)?),
+        })?;
+    }
+    Ok(())
+}
+
+fn find_names(collection: &NitriteCollection, prefix: &str) -> NitriteResult<Vec<String>> {
+    let mut names = Vec::new();
+    for document in collection.find(geohash_prefix("geohash", prefix))? {
+        names.push(document?.get("name")?.as_string().cloned().unwrap_or_default());
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[test]
+fn test_geohash_prefix_uses_string_index() {
+    run_test(
+        create_spatial_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("places")?;
+            collection.create_index(vec!["geohash"], &non_unique_index())?;
+            insert_places(&collection)?;
+
+            let cursor = collection.find(geohash_prefix("geohash", "u4pruy"))?;
+            assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+
+            assert_eq!(find_names(&collection, "u4pruy")?, vec!["harbour", "lighthouse"]);
+            assert_eq!(find_names(&collection, "U4PR")?.len(), 3);
+            assert_eq!(find_names(&collection, "u4")?.len(), 4);
+            assert_eq!(find_names(&collection, "u")?.len(), 5);
+            assert!(find_names(&collection, "s")?.is_empty());
+
+            // every match lies in the cell the prefix names
+            let bounds = geohash_bounds("u4pr")?;
+            for document in collection.find(geohash_prefix("geohash", "u4pr"))? {
+                let document = document?;
+                let latitude = *document.get("latitude")?.as_f64().unwrap();
+                let longitude = *document.get("longitude")?.as_f64().unwrap();
+                assert!(bounds.contains_point(longitude, latitude));
+            }
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_geohash_prefix_without_index() {
+    run_test(
+        create_spatial_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("places")?;
+            insert_places(&collection)?;
+
+            let cursor = collection.find(geohash_prefix("geohash", "u4pruy"))?;
+            assert!(cursor.find_plan().unwrap().index_descriptor().is_none());
+            assert_eq!(find_names(&collection, "u4pruy")?, vec!["harbour", "lighthouse"]);
+
+            // the stored hash decodes to within a few meters of the place
+            let harbour = collection
+                .find(geohash_prefix("geohash", "u4pruydqq"))?
+                .next()
+                .unwrap()?;
+            let center = geohash_decode(harbour.get("geohash")?.as_string().unwrap())?;
+            let place = GeoPoint::new(PLACES[0].1, PLACES[0].2)?;
+            assert!(center.distance_meters(&place) < 5.0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -5,3 +5,4 @@
 
 mod spatial_index_test;
 mod geometry_enhancements_test;
+mod geohash_test;
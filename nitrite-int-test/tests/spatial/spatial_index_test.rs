@@ -5,7 +5,10 @@
 
 #![cfg(feature = "fjall")]
 
+use nitrite::collection::FindOptions;
 use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
 use nitrite_int_test::test_util::{cleanup, create_spatial_test_context, run_test};
 use nitrite_spatial::{spatial_field, spatial_index, Geometry, Point};
 
@@ -389,3 +392,63 @@ fn test_knearest_query() {
         cleanup,
     )
 }
+
+#[test]
+fn test_warm_up_spatial_index() {
+    run_test(
+        create_spatial_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("warm_places")?;
+            collection.create_index(vec!["location"], &spatial_index())?;
+            collection.insert(doc! { name: "a", location: { x: 1.0, y: 1.0 } })?;
+            collection.insert(doc! { name: "b", location: { x: 5.0, y: 5.0 } })?;
+
+            let report = ctx.db().warm_up(["warm_places"])?.wait()?;
+            assert_eq!(report.documents, 2);
+            assert_eq!(report.indexes, 1);
+
+            let filter = spatial_field("location").within(Geometry::envelope(0.0, 0.0, 2.0, 2.0));
+            assert_eq!(collection.find(filter)?.count(), 1);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_sort_by_distance() {
+    run_test(
+        create_spatial_test_context,
+        |ctx| {
+            let collection = ctx.db().collection("sorted_places")?;
+            collection.create_index(vec!["location"], &spatial_index())?;
+            for x in [7.0, 2.0, 8.5, 4.0, 1.0, 30.0] {
+                collection.insert(doc! { x: (x), location: { x: (x), y: 0.0 } })?;
+            }
+
+            let within = || spatial_field("location").within(Geometry::envelope(0.0, -1.0, 10.0, 1.0));
+            let options = FindOptions::new().sort_by_distance("location", Point::new(5.0, 0.0));
+            let order: Vec<f64> = collection
+                .find_with_options(within(), &options)?
+                .map(|doc| doc.unwrap().get("x").unwrap().as_f64().copied().unwrap())
+                .collect();
+            assert_eq!(order, vec![4.0, 7.0, 2.0, 8.5, 1.0]);
+
+            let options = FindOptions::new()
+                .sort_by_distance("location", Point::new(0.0, 0.0))
+                .limit(2);
+            let order: Vec<f64> = collection
+                .find_with_options(within(), &options)?
+                .map(|doc| doc.unwrap().get("x").unwrap().as_f64().copied().unwrap())
+                .collect();
+            assert_eq!(order, vec![1.0, 2.0]);
+
+            // the filter must be answered by the spatial index on the sorted field
+            let options = FindOptions::new().sort_by_distance("location", Point::new(0.0, 0.0));
+            let err = collection.find_with_options(field("x").gt(1.0), &options).err().unwrap();
+            assert_eq!(*err.kind(), ErrorKind::FilterError);
+            Ok(())
+        },
+        cleanup,
+    )
+}
@@ -0,0 +1,70 @@
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::repository::ObjectRepository;
+use nitrite_derive::{Convertible, NitriteEntity};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[derive(Clone, Debug, Default, Convertible, NitriteEntity)]
+pub struct Employee {
+    id: Option<String>,
+    name: Option<String>,
+    salary: Option<i32>,
+}
+
+#[test]
+fn test_sql_on_collection() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let orders = ctx.db().collection("orders")?;
+            orders.insert_many(vec![
+                doc! { customer: { name: "Ada" }, total: 42.5, status: "paid" },
+                doc! { customer: { name: "Grace" }, total: 7, status: "new" },
+                doc! { customer: { name: "Linus" }, total: 120.0, status: "paid" },
+            ])?;
+
+            let rows = ctx.db().sql(
+                "SELECT customer.name, total FROM orders \
+                 WHERE status = 'paid' AND total > 10 ORDER BY total DESC",
+            )?;
+            assert_eq!(rows.len(), 2);
+            assert_eq!(rows[0].get("customer.name")?, Value::from("Linus"));
+            assert_eq!(rows[1].get("total")?, Value::F64(42.5));
+            assert!(!rows[0].contains_key("status"));
+
+            let err = ctx.db().sql("SELECT * FROM orders WHERE").err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::FilterError);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_sql_on_repository() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let repo: ObjectRepository<Employee> = ctx.db().repository()?;
+            for (id, name, salary) in [("1", "Ada", 5000), ("2", "Alan", 4000), ("3", "Grace", 6000)] {
+                repo.insert(Employee {
+                    id: Some(id.to_string()),
+                    name: Some(name.to_string()),
+                    salary: Some(salary),
+                })?;
+            }
+
+            let rows = ctx
+                .db()
+                .sql("SELECT name FROM Employee WHERE name LIKE 'A%' ORDER BY salary")?;
+            let names: Vec<Value> = rows.iter().map(|row| row.get("name").unwrap()).collect();
+            assert_eq!(names, vec![Value::from("Alan"), Value::from("Ada")]);
+
+            let err = ctx.db().sql("SELECT * FROM Manager").err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::CollectionNotFound);
+            Ok(())
+        },
+        cleanup,
+    )
+}
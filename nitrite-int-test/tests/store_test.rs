@@ -702,6 +702,52 @@ mod tests {
         let _ = std::fs::remove_dir_all(&db_path);
     }
 
+    #[test]
+    fn test_in_memory_store_dump_and_load() {
+        use nitrite::index::unique_index;
+        use nitrite::store::memory::{InMemoryStore, InMemoryStoreConfig};
+
+        let dump_path = std::env::temp_dir().join(format!("nitrite_dump_{}", uuid::Uuid::new_v4()));
+
+        {
+            let store = InMemoryStore::new(InMemoryStoreConfig::new());
+            let db = Nitrite::builder()
+                .load_module(InMemoryStoreModule::from_store(store.clone()))
+                .open_or_create(None, None)
+                .unwrap();
+            let coll = db.collection("users").unwrap();
+            coll.create_index(vec!["email"], &unique_index()).unwrap();
+            for i in 0..10 {
+                coll.insert(doc!{"email": (format!("user{}@example.com", i)), "age": i})
+                    .unwrap();
+            }
+            db.commit().unwrap();
+            store.dump_to(&dump_path).unwrap();
+            db.close().unwrap();
+        }
+
+        let store = InMemoryStore::new(InMemoryStoreConfig::new());
+        store.load_from(&dump_path).unwrap();
+        let db = Nitrite::builder()
+            .load_module(InMemoryStoreModule::from_store(store))
+            .open_or_create(None, None)
+            .unwrap();
+        assert!(db.has_collection("users").unwrap());
+
+        let coll = db.collection("users").unwrap();
+        assert_eq!(coll.size().unwrap(), 10);
+        assert!(coll.has_index(vec!["email"]).unwrap());
+        let cursor = coll.find(field("email").eq("user3@example.com")).unwrap();
+        assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+        assert_eq!(cursor.count(), 1);
+
+        // the unique index was restored along with the documents
+        assert!(coll.insert(doc!{"email": "user3@example.com"}).is_err());
+        db.close().unwrap();
+
+        let _ = std::fs::remove_file(&dump_path);
+    }
+
     #[test]
     fn test_collection_names_with_special_characters() {
         let temp_dir = std::env::temp_dir();
@@ -740,4 +786,108 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&db_path);
     }
+
+    #[test]
+    fn test_migrate_memory_store_to_fjall() {
+        let temp_dir = std::env::temp_dir();
+        let db_path = temp_dir.join(format!("nitrite_migrate_{}", uuid::Uuid::new_v4()));
+        let db_path_str = db_path.to_str().unwrap().to_string();
+
+        {
+            let db = Nitrite::builder().open_or_create(None, None).unwrap();
+            let users = db.collection("users").unwrap();
+            users.create_index(vec!["email"], &nitrite::index::unique_index()).unwrap();
+            users.insert(doc!{"email": "a@example.com", "age": 30}).unwrap();
+            users.insert(doc!{"email": "b@example.com", "age": 40}).unwrap();
+            let memory_version = db.store().store_version().unwrap();
+
+            db.migrate_store(FjallModule::with_config().db_path(&db_path_str).build())
+                .unwrap();
+            assert_ne!(db.store().store_version().unwrap(), memory_version);
+
+            // handles taken before the migration write to the new store
+            users.insert(doc!{"email": "c@example.com", "age": 50}).unwrap();
+            assert!(users.insert(doc!{"email": "a@example.com"}).is_err());
+            assert_eq!(users.find(field("email").eq("b@example.com")).unwrap().count(), 1);
+            assert_eq!(users.size().unwrap(), 3);
+            db.close().unwrap();
+        }
+
+        {
+            let db = Nitrite::builder()
+                .load_module(FjallModule::with_config().db_path(&db_path_str).build())
+                .open_or_create(None, None)
+                .unwrap();
+            assert!(db.has_collection("users").unwrap());
+            let users = db.collection("users").unwrap();
+            assert!(users.has_index(vec!["email"]).unwrap());
+            assert_eq!(users.size().unwrap(), 3);
+            assert_eq!(users.find(field("age").gt(35)).unwrap().count(), 2);
+            db.close().unwrap();
+        }
+
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+
+    #[test]
+    fn test_migrate_store_while_writing() {
+        let temp_dir = std::env::temp_dir();
+        let db_path = temp_dir.join(format!("nitrite_migrate_{}", uuid::Uuid::new_v4()));
+        let db_path_str = db_path.to_str().unwrap().to_string();
+
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let events = db.collection("events").unwrap();
+        events.create_index(vec!["seq"], &nitrite::index::unique_index()).unwrap();
+        for seq in 0..500 {
+            events.insert(doc!{"seq": seq}).unwrap();
+        }
+
+        let writer = {
+            let events = events.clone();
+            std::thread::spawn(move || {
+                for seq in 500..1500 {
+                    events.insert(doc!{"seq": seq}).unwrap();
+                }
+            })
+        };
+        db.migrate_store(FjallModule::with_config().db_path(&db_path_str).build())
+            .unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(events.size().unwrap(), 1500);
+        assert_eq!(events.find(field("seq").gte(0)).unwrap().count(), 1500);
+        db.close().unwrap();
+
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
+
+    #[test]
+    fn test_migrate_store_refuses_a_store_holding_the_maps() {
+        let temp_dir = std::env::temp_dir();
+        let db_path = temp_dir.join(format!("nitrite_migrate_{}", uuid::Uuid::new_v4()));
+        let db_path_str = db_path.to_str().unwrap().to_string();
+
+        {
+            let db = Nitrite::builder()
+                .load_module(FjallModule::with_config().db_path(&db_path_str).build())
+                .open_or_create(None, None)
+                .unwrap();
+            db.collection("users").unwrap().insert(doc!{"name": "old"}).unwrap();
+            db.close().unwrap();
+        }
+
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let users = db.collection("users").unwrap();
+        users.insert(doc!{"name": "new"}).unwrap();
+        assert!(db
+            .migrate_store(FjallModule::with_config().db_path(&db_path_str).build())
+            .is_err());
+
+        // the database stays on the memory store
+        users.insert(doc!{"name": "newer"}).unwrap();
+        assert_eq!(users.size().unwrap(), 2);
+        db.close().unwrap();
+
+        let _ = std::fs::remove_dir_all(&db_path);
+    }
 }
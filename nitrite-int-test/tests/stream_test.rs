@@ -1,6 +1,9 @@
 // Based on Java DocumentCursorTest.java
 use nitrite::doc;
-use nitrite::filter::field;
+use nitrite::collection::order_by;
+use nitrite::common::SortOrder;
+use nitrite::filter::{all, field};
+use nitrite::nitrite::Nitrite;
 use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
 
 #[test]
@@ -175,3 +178,58 @@ fn test_cursor_size_and_count() {
         cleanup,
     )
 }
+
+#[test]
+fn test_sort_beyond_memory_budget() {
+    // A budget of a few documents makes the blocking sort spill several runs to the store;
+    // the merged cursor must still return every document in order.
+    let db = Nitrite::builder()
+        .sort_memory_budget(256)
+        .open_or_create(None, None)
+        .unwrap();
+    let collection = db.collection("spilled_sort").unwrap();
+    for i in 0i64..200 {
+        collection.insert(doc! {"id": i, "rank": ((i * 37) % 101)}).unwrap();
+    }
+
+    let ranks: Vec<i64> = collection
+        .find_with_options(all(), &order_by("rank", SortOrder::Descending))
+        .unwrap()
+        .map(|doc| *doc.unwrap().get("rank").unwrap().as_i64().unwrap())
+        .collect();
+    assert_eq!(ranks.len(), 200);
+    assert!(ranks.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    let page: Vec<i64> = collection
+        .find_with_options(
+            all(),
+            &order_by("rank", SortOrder::Ascending).skip(10).limit(5),
+        )
+        .unwrap()
+        .map(|doc| *doc.unwrap().get("rank").unwrap().as_i64().unwrap())
+        .collect();
+    assert_eq!(page, vec![5, 5, 6, 6, 7]);
+    db.close().unwrap();
+}
+
+
+#[cfg(feature = "fjall")]
+#[test]
+fn test_open_removes_orphaned_sort_runs() {
+    use nitrite_int_test::test_util::{open_fjall_db, random_path};
+
+    let path = random_path();
+    let run = "$nitrite_sort|0b7e4a9c-orphan";
+    {
+        // a run a crashed cursor never removed
+        let db = open_fjall_db(&path);
+        db.store().open_map(run).unwrap().put("0".into(), "x".into()).unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        assert!(!db.store().has_map(run).unwrap());
+        db.close().unwrap();
+    }
+    let _ = std::fs::remove_dir_all(&path);
+}
@@ -0,0 +1,86 @@
+use nitrite::collection::CollectionOptions;
+use nitrite::doc;
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
+use nitrite::index::unique_index;
+use nitrite::repository::ObjectRepository;
+use nitrite_derive::{Convertible, NitriteEntity};
+use nitrite_int_test::test_util::{cleanup, create_test_context, run_test};
+
+#[derive(Clone, Debug, Default, Convertible, NitriteEntity)]
+pub struct Invoice {
+    id: Option<String>,
+    total: Option<i32>,
+}
+
+#[test]
+fn test_tenant_collections_are_isolated() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let acme = ctx.db().tenant("acme")?;
+            let globex = ctx.db().tenant("globex")?;
+
+            let acme_orders = acme.collection("orders")?;
+            acme_orders.create_index(vec!["number"], &unique_index())?;
+            acme_orders.insert(doc! { number: 1, item: "anvil" })?;
+
+            // same name, different collection, with its own indexes
+            let globex_orders =
+                globex.collection_with_options("orders", CollectionOptions::new().document_quota(10))?;
+            assert_eq!(globex_orders.size()?, 0);
+            assert!(!globex_orders.has_index(vec!["number"])?);
+            globex_orders.insert(doc! { number: 1, item: "rocket" })?;
+            assert_eq!(acme_orders.find(field("number").eq(1))?.count(), 1);
+            assert!(acme_orders.options()?.get_document_quota().is_none());
+
+            ctx.db().collection("orders")?;
+            assert!(acme.has_collection("orders")?);
+            assert_eq!(acme.list_collection_names()?.len(), 1);
+            assert_eq!(ctx.db().list_tenants()?.len(), 2);
+
+            let err = ctx.db().tenant("ac|me").err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::ValidationError);
+            Ok(())
+        },
+        cleanup,
+    )
+}
+
+#[test]
+fn test_drop_tenant() {
+    run_test(
+        create_test_context,
+        |ctx| {
+            let acme = ctx.db().tenant("acme")?;
+            acme.collection("orders")?.insert(doc! { item: "anvil" })?;
+            let invoices: ObjectRepository<Invoice> = acme.repository()?;
+            invoices.insert(Invoice {
+                id: Some("1".to_string()),
+                total: Some(100),
+            })?;
+            let archived: ObjectRepository<Invoice> = acme.keyed_repository("2024")?;
+            archived.insert(Invoice {
+                id: Some("0".to_string()),
+                total: Some(10),
+            })?;
+            ctx.db().tenant("globex")?.collection("orders")?.insert(doc! { item: "rocket" })?;
+
+            assert!(acme.has_repository::<Invoice>()?);
+            assert!(acme.list_repositories()?.contains("Invoice"));
+            assert!(acme.list_keyed_repositories()?.contains_key("2024"));
+            assert!(!ctx.db().tenant("globex")?.has_repository::<Invoice>()?);
+
+            ctx.db().drop_tenant("acme")?;
+            assert!(acme.list_collection_names()?.is_empty());
+            assert!(acme.list_repositories()?.is_empty());
+            assert!(acme.list_keyed_repositories()?.is_empty());
+            assert_eq!(ctx.db().list_tenants()?.into_iter().collect::<Vec<_>>(), vec!["globex"]);
+
+            // the tenant can start over
+            assert_eq!(acme.collection("orders")?.size()?, 0);
+            Ok(())
+        },
+        cleanup,
+    )
+}
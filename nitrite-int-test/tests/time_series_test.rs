@@ -0,0 +1,62 @@
+//! Time series on the Fjall store: buckets and options survive a restart.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::errors::ErrorKind;
+use nitrite::time_series::{DataPoint, TimeSeriesOptions};
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+use std::time::Duration;
+
+fn options() -> TimeSeriesOptions {
+    TimeSeriesOptions::new().bucket_width(Duration::from_secs(60))
+}
+
+#[test]
+fn test_time_series_survives_reopen() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let readings = db.time_series("readings", options()).unwrap();
+        // one point a second for ten minutes, in two series
+        for sensor in ["s1", "s2"] {
+            let points = (0..600)
+                .map(|second| DataPoint::new(second * 1000).with("t", (second % 60) as f64))
+                .collect();
+            readings.insert_many(sensor, points).unwrap();
+        }
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let error = db
+            .time_series("readings", TimeSeriesOptions::new())
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+
+        let readings = db.time_series("readings", options()).unwrap();
+        assert_eq!(readings.size("s1").unwrap(), 600);
+        assert_eq!(readings.range("s2", 30_000, 90_000).unwrap().len(), 60);
+
+        let minutes = readings
+            .downsample("s1", "t", 0, 600_000, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(minutes.len(), 10);
+        assert!(minutes.iter().all(|minute| minute.count == 60
+            && minute.min == 0.0
+            && minute.max == 59.0
+            && minute.avg() == 29.5));
+
+        db.drop_time_series("readings").unwrap();
+        db.close().unwrap();
+    }
+    {
+        // dropped with its options, it can be created again with other buckets
+        let db = open_fjall_db(&path);
+        let readings = db.time_series("readings", TimeSeriesOptions::new()).unwrap();
+        assert!(readings.series().unwrap().is_empty());
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,46 @@
+//! Topics on the Fjall store: messages and the progress of consumer groups must survive a
+//! reopen.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::doc;
+use nitrite::common::Value;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+#[test]
+fn test_topic_progress_survives_reopen() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let jobs = db.topic("jobs").unwrap();
+        for seq in 0..3 {
+            jobs.publish(doc! { seq: seq }).unwrap();
+        }
+
+        let workers = jobs.subscribe("workers").unwrap();
+        let first = workers.receive().unwrap().unwrap();
+        workers.ack(&first).unwrap();
+        // received but never acknowledged before the crash
+        workers.receive().unwrap().unwrap();
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        let jobs = db.topic("jobs").unwrap();
+        assert_eq!(jobs.size().unwrap(), 3);
+        assert!(db.list_collection_names().unwrap().is_empty());
+
+        let workers = jobs.subscribe("workers").unwrap();
+        let third = workers.receive().unwrap().unwrap();
+        assert_eq!(third.payload().get("seq").unwrap(), Value::I32(2));
+        workers.ack(&third).unwrap();
+        assert!(workers.receive().unwrap().is_none());
+
+        let audit = jobs.subscribe("audit").unwrap();
+        let first = audit.receive().unwrap().unwrap();
+        assert_eq!(first.payload().get("seq").unwrap(), Value::I32(0));
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
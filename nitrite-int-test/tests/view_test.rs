@@ -0,0 +1,71 @@
+//! Views on the Fjall store: their documents must survive a reopen, and reopening a view
+//! must pick up the writes made to the source while it was not open.
+
+#![cfg(feature = "fjall")]
+
+use nitrite::common::Value;
+use nitrite::doc;
+use nitrite::filter::{all, field};
+use nitrite::view::ViewOptions;
+use nitrite_int_test::test_util::{open_fjall_db, random_path};
+use std::fs;
+
+fn open_orders() -> ViewOptions {
+    ViewOptions::new("orders")
+        .filter(field("status").eq("open"))
+        .projection(vec!["customer"])
+}
+
+#[test]
+fn test_view_survives_reopen() {
+    let path = random_path();
+    {
+        let db = open_fjall_db(&path);
+        let orders = db.collection("orders").unwrap();
+        let view = db.view("open_orders", open_orders()).unwrap();
+        orders.insert(doc! { customer: "acme", status: "open" }).unwrap();
+        orders.insert(doc! { customer: "globex", status: "closed" }).unwrap();
+        assert_eq!(view.size().unwrap(), 1);
+        db.close().unwrap();
+    }
+    {
+        let db = open_fjall_db(&path);
+        // written while the view is not open
+        let orders = db.collection("orders").unwrap();
+        orders.insert(doc! { customer: "initech", status: "open" }).unwrap();
+        orders.remove(field("customer").eq("acme"), false).unwrap();
+
+        let view = db.view("open_orders", open_orders()).unwrap();
+        let customers: Vec<Value> = view
+            .find(all())
+            .unwrap()
+            .map(|document| document.unwrap().get("customer").unwrap())
+            .collect();
+        assert_eq!(customers, vec![Value::from("initech")]);
+        assert_eq!(db.list_collection_names().unwrap().len(), 1);
+        db.close().unwrap();
+    }
+    let _ = fs::remove_dir_all(&path);
+}
+
+#[test]
+fn test_view_follows_transaction_commit() {
+    let path = random_path();
+    let db = open_fjall_db(&path);
+    db.collection("orders").unwrap();
+    let view = db.view("open_orders", open_orders()).unwrap();
+
+    db.with_session(|session| {
+        let transaction = session.begin_transaction()?;
+        let orders = transaction.collection("orders")?;
+        orders.insert(doc! { customer: "acme", status: "open" })?;
+        orders.insert(doc! { customer: "globex", status: "open" })?;
+        assert_eq!(view.size()?, 0);
+        transaction.commit()
+    })
+    .unwrap();
+    assert_eq!(view.size().unwrap(), 2);
+
+    db.close().unwrap();
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,588 @@
+//! Conversion of decoded Java objects and plain JSON into Nitrite values.
+
+use crate::stream::{read_utf, JavaObject, JavaValue};
+use nitrite::collection::{Document, NitriteId};
+use nitrite::common::{Value, DOC_ID, FULL_TEXT_INDEX, NON_UNIQUE_INDEX, UNIQUE_INDEX};
+use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
+
+/// Map classes whose `writeObject` writes the entries as alternating keys and values.
+const MAP_CLASSES: [&str; 6] = [
+    "java.util.HashMap",
+    "java.util.Hashtable",
+    "java.util.TreeMap",
+    "java.util.IdentityHashMap",
+    "java.util.WeakHashMap",
+    "java.util.concurrent.ConcurrentHashMap",
+];
+
+/// An index definition found in an export.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IndexDefinition {
+    pub(crate) index_type: String,
+    pub(crate) fields: Vec<String>,
+}
+
+/// Converts a decoded Java value into a Nitrite value.
+pub(crate) fn to_value(java: &JavaValue) -> NitriteResult<Value> {
+    Ok(match java {
+        JavaValue::Null => Value::Null,
+        JavaValue::Bool(value) => Value::Bool(*value),
+        JavaValue::Byte(value) => Value::I8(*value),
+        JavaValue::Char(value) => Value::Char(*value),
+        JavaValue::Short(value) => Value::I16(*value),
+        JavaValue::Int(value) => Value::I32(*value),
+        JavaValue::Long(value) => Value::I64(*value),
+        JavaValue::Float(value) => Value::F32(*value),
+        JavaValue::Double(value) => Value::F64(*value),
+        JavaValue::String(value) => Value::String(value.clone()),
+        JavaValue::Bytes(bytes) => Value::Bytes(bytes.clone()),
+        JavaValue::Array { elements, .. } => Value::Array(to_values(elements.iter())?),
+        JavaValue::Enum { constant, .. } => Value::String(constant.clone()),
+        JavaValue::Class(name) => Value::String(name.clone()),
+        JavaValue::Object(object) => object_to_value(object)?,
+    })
+}
+
+/// Converts a decoded Java document into a Nitrite document.
+pub(crate) fn to_document(java: &JavaValue) -> NitriteResult<Document> {
+    match to_value(java)? {
+        Value::Document(document) => Ok(document),
+        other => Err(conversion_error(&format!(
+            "Expected a document but found {:?}",
+            other
+        ))),
+    }
+}
+
+/// Reads the numeric value of a Java `NitriteId`, or of an id exported as a number or
+/// string.
+pub(crate) fn java_id(java: &JavaValue) -> Option<u64> {
+    match java {
+        JavaValue::Long(id) => u64::try_from(*id).ok(),
+        JavaValue::String(id) => id.trim().parse().ok(),
+        JavaValue::Object(object) if is_nitrite_id(object) => {
+            if let Some(id) = object.field("idValue") {
+                return java_id(id);
+            }
+            // Nitrite 4 writes the id with writeUTF, older versions as a long
+            let block = object.class_data.last()?.blocks().next()?;
+            match read_utf(block) {
+                Some((id, _)) => id.trim().parse().ok(),
+                None => block
+                    .get(..8)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .and_then(|bytes| u64::try_from(i64::from_be_bytes(bytes)).ok()),
+            }
+        }
+        JavaValue::Object(object) => object.field("value").and_then(java_id),
+        _ => None,
+    }
+}
+
+/// Reads a Java `IndexDescriptor` (Nitrite 4) or `IndexEntry` (Nitrite 3).
+pub(crate) fn java_index(java: &JavaValue) -> NitriteResult<IndexDefinition> {
+    let JavaValue::Object(object) = java else {
+        return Err(conversion_error("Index descriptor is not an object"));
+    };
+
+    let index_type = match object.field("indexType") {
+        Some(JavaValue::String(index_type)) => Some(index_type.clone()),
+        Some(JavaValue::Enum { constant, .. }) => Some(constant.clone()),
+        // written by hand with writeUTF, ahead of the fields
+        _ => object
+            .class_data
+            .last()
+            .and_then(|data| data.blocks().next())
+            .and_then(read_utf)
+            .map(|(index_type, _)| index_type),
+    };
+
+    let fields = match (object.field("fields"), object.field("field")) {
+        (Some(fields), _) => string_list(&to_value(fields)?),
+        (None, Some(field)) => string_list(&to_value(field)?),
+        (None, None) => match object.class_data.last() {
+            Some(data) => match data.annotation_objects().next() {
+                Some(fields) => string_list(&to_value(fields)?),
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        },
+    };
+
+    match index_type {
+        Some(index_type) if !fields.is_empty() => Ok(IndexDefinition {
+            index_type: nitrite_index_type(&index_type),
+            fields,
+        }),
+        _ => Err(conversion_error(&format!(
+            "Cannot read the index descriptor {}",
+            object.class_name
+        ))),
+    }
+}
+
+/// Converts a plain JSON value into a Nitrite value.
+pub(crate) fn json_to_value(json: &serde_json::Value) -> NitriteResult<Value> {
+    Ok(match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(value) => Value::Bool(*value),
+        serde_json::Value::Number(number) => {
+            if let Some(value) = number.as_i64() {
+                Value::I64(value)
+            } else if let Some(value) = number.as_u64() {
+                Value::U64(value)
+            } else {
+                Value::F64(number.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        serde_json::Value::String(value) => Value::String(value.clone()),
+        serde_json::Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(json_to_value)
+                .collect::<NitriteResult<Vec<_>>>()?,
+        ),
+        serde_json::Value::Object(map) => {
+            let mut document = Document::new();
+            for (key, value) in map {
+                put_field(&mut document, key, json_to_value(value)?)?;
+            }
+            Value::Document(document)
+        }
+    })
+}
+
+/// Reads an id exported as a plain JSON number or string.
+pub(crate) fn json_id(json: &serde_json::Value) -> Option<u64> {
+    match json {
+        serde_json::Value::Number(number) => number.as_u64(),
+        serde_json::Value::String(id) => id.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Reads an index exported as plain JSON, `{"type": "unique", "fields": ["a", "b"]}`.
+pub(crate) fn json_index(json: &serde_json::Value) -> NitriteResult<IndexDefinition> {
+    let index_type = json
+        .get("type")
+        .or_else(|| json.get("indexType"))
+        .and_then(serde_json::Value::as_str);
+    let fields = match json.get("fields").or_else(|| json.get("field")) {
+        Some(fields) => string_list(&json_to_value(fields)?),
+        None => Vec::new(),
+    };
+    match index_type {
+        Some(index_type) if !fields.is_empty() => Ok(IndexDefinition {
+            index_type: nitrite_index_type(index_type),
+            fields,
+        }),
+        _ => Err(conversion_error(&format!("Cannot read the index {}", json))),
+    }
+}
+
+/// Maps Java index type names, such as `NonUnique`, to the names used here.
+fn nitrite_index_type(java_type: &str) -> String {
+    let normalized: String = java_type
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .collect::<String>()
+        .to_lowercase();
+    match normalized.as_str() {
+        "unique" => UNIQUE_INDEX.to_string(),
+        "nonunique" => NON_UNIQUE_INDEX.to_string(),
+        "fulltext" => FULL_TEXT_INDEX.to_string(),
+        _ => java_type.to_lowercase(),
+    }
+}
+
+fn object_to_value(object: &JavaObject) -> NitriteResult<Value> {
+    if is_nitrite_id(object) {
+        return Ok(nitrite_id_value(java_id(&JavaValue::Object(object.clone()))));
+    }
+
+    match object.class_name.as_str() {
+        "java.lang.Boolean" | "java.lang.Byte" | "java.lang.Character" | "java.lang.Short"
+        | "java.lang.Integer" | "java.lang.Long" | "java.lang.Float" | "java.lang.Double" => {
+            return match object.field("value") {
+                Some(value) => to_value(value),
+                None => Ok(Value::Null),
+            };
+        }
+        "java.util.UUID" => return Ok(uuid(object)),
+        "java.math.BigInteger" => return big_integer(object).map(Value::from),
+        "java.math.BigDecimal" => return big_decimal(object).map(Value::String),
+        _ => {}
+    }
+
+    if object.is_a("java.util.Date") {
+        // Date writes its time in milliseconds after the (empty) default fields
+        let millis = object
+            .class("java.util.Date")
+            .and_then(|data| data.blocks().next())
+            .and_then(|block| block.get(..8))
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(i64::from_be_bytes);
+        return Ok(millis.map(Value::I64).unwrap_or(Value::Null));
+    }
+
+    if let Some(entries) = map_entries(object) {
+        let mut document = Document::new();
+        for (key, value) in entries {
+            let key = match to_value(key)? {
+                Value::String(key) => key,
+                other => other.to_string(),
+            };
+            put_field(&mut document, &key, to_value(value)?)?;
+        }
+        return Ok(Value::Document(document));
+    }
+
+    if object.class_name.starts_with("java.util.") {
+        return Ok(Value::Array(to_values(collection_elements(object).into_iter())?));
+    }
+
+    // a plain serializable object, written with its fields
+    let fields: Vec<_> = object
+        .class_data
+        .iter()
+        .flat_map(|data| data.fields.iter())
+        .collect();
+    if fields.is_empty() {
+        // its state is whatever its writeObject wrote
+        let written: Vec<_> = object
+            .class_data
+            .iter()
+            .flat_map(|data| data.annotation_objects())
+            .collect();
+        return match written.as_slice() {
+            [single] => to_value(single),
+            _ => Ok(Value::Array(to_values(written.into_iter())?)),
+        };
+    }
+    let mut document = Document::new();
+    for (name, value) in fields {
+        put_field(&mut document, name, to_value(value)?)?;
+    }
+    Ok(Value::Document(document))
+}
+
+fn to_values<'a>(values: impl Iterator<Item = &'a JavaValue>) -> NitriteResult<Vec<Value>> {
+    values.map(to_value).collect()
+}
+
+fn put_field(document: &mut Document, key: &str, value: Value) -> NitriteResult<()> {
+    if key.is_empty() {
+        return Ok(());
+    }
+    if key == DOC_ID {
+        // only a valid NitriteId can be stored as the id, the importer sets it anyway
+        return match value {
+            Value::NitriteId(_) => document.put(key, value),
+            _ => Ok(()),
+        };
+    }
+    document.put(key, value)
+}
+
+fn is_nitrite_id(object: &JavaObject) -> bool {
+    object.class_name.starts_with("org.dizitart.") && object.class_name.ends_with(".NitriteId")
+}
+
+fn nitrite_id_value(id: Option<u64>) -> Value {
+    match id {
+        Some(id) => match NitriteId::create_id(id) {
+            Ok(id) => Value::NitriteId(id),
+            // ids of old Java versions can be outside the range used here
+            Err(_) => Value::U64(id),
+        },
+        None => Value::Null,
+    }
+}
+
+/// The entries of a map or a Nitrite document, or `None` if `object` is not a map.
+fn map_entries(object: &JavaObject) -> Option<Vec<(&JavaValue, &JavaValue)>> {
+    // Nitrite 4 documents write their entries as Pair objects
+    let pairs: Vec<_> = object
+        .class_data
+        .iter()
+        .filter(|data| !MAP_CLASSES.contains(&data.class_name.as_str()))
+        .flat_map(|data| data.annotation_objects())
+        .filter_map(|value| match value {
+            JavaValue::Object(pair) if pair.class_name.ends_with(".Pair") => pair_parts(pair),
+            _ => None,
+        })
+        .collect();
+    if !pairs.is_empty() {
+        return Some(pairs);
+    }
+
+    let map = object
+        .class_data
+        .iter()
+        .find(|data| MAP_CLASSES.contains(&data.class_name.as_str()))?;
+    let written: Vec<_> = map.annotation_objects().collect();
+    Some(
+        written
+            .chunks_exact(2)
+            .map(|entry| (entry[0], entry[1]))
+            // ConcurrentHashMap ends its entries with a null pair
+            .take_while(|(key, _)| **key != JavaValue::Null)
+            .collect(),
+    )
+}
+
+fn pair_parts(pair: &JavaObject) -> Option<(&JavaValue, &JavaValue)> {
+    if let (Some(first), Some(second)) = (pair.field("first"), pair.field("second")) {
+        return Some((first, second));
+    }
+    let mut written = pair.class_data.last()?.annotation_objects();
+    Some((written.next()?, written.next()?))
+}
+
+/// The elements of a `java.util` collection.
+fn collection_elements(object: &JavaObject) -> Vec<&JavaValue> {
+    let mut written: Vec<_> = object
+        .class_data
+        .iter()
+        .flat_map(|data| data.annotation_objects())
+        .collect();
+    if object.is_a("java.util.TreeSet") && !written.is_empty() {
+        // TreeSet writes its comparator ahead of the elements
+        written.remove(0);
+    }
+    if !written.is_empty() {
+        return written;
+    }
+
+    // Vector and the wrappers in Arrays and Collections keep their elements in fields
+    let count = match object.field("elementCount") {
+        Some(JavaValue::Int(count)) => usize::try_from(*count).unwrap_or(0),
+        _ => usize::MAX,
+    };
+    for data in object.class_data.iter().rev() {
+        for (name, value) in &data.fields {
+            match value {
+                JavaValue::Array { elements, .. } => return elements.iter().take(count).collect(),
+                JavaValue::Object(inner) if name == "c" || name == "list" => {
+                    return collection_elements(inner)
+                }
+                _ if name == "element" => return vec![value],
+                _ => {}
+            }
+        }
+    }
+    Vec::new()
+}
+
+fn uuid(object: &JavaObject) -> Value {
+    match (object.field("mostSigBits"), object.field("leastSigBits")) {
+        (Some(JavaValue::Long(most)), Some(JavaValue::Long(least))) => {
+            let hex = format!("{:016x}{:016x}", *most as u64, *least as u64);
+            Value::String(format!(
+                "{}-{}-{}-{}-{}",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            ))
+        }
+        _ => Value::Null,
+    }
+}
+
+fn big_integer(object: &JavaObject) -> NitriteResult<i128> {
+    let (Some(JavaValue::Int(signum)), Some(JavaValue::Bytes(magnitude))) =
+        (object.field("signum"), object.field("magnitude"))
+    else {
+        return Err(conversion_error("Cannot read a java.math.BigInteger"));
+    };
+    let significant = magnitude.iter().skip_while(|byte| **byte == 0);
+    if significant.clone().count() > 15 {
+        return Err(conversion_error(
+            "java.math.BigInteger value does not fit in 120 bits",
+        ));
+    }
+    let magnitude = significant.fold(0i128, |value, byte| (value << 8) | *byte as i128);
+    Ok(magnitude * *signum as i128)
+}
+
+/// Formats a `BigDecimal` as a decimal string, keeping its exact value.
+fn big_decimal(object: &JavaObject) -> NitriteResult<String> {
+    let (Some(JavaValue::Int(scale)), Some(JavaValue::Object(unscaled))) =
+        (object.field("scale"), object.field("intVal"))
+    else {
+        return Err(conversion_error("Cannot read a java.math.BigDecimal"));
+    };
+    let unscaled = big_integer(unscaled)?;
+    let digits = unscaled.unsigned_abs().to_string();
+    let sign = if unscaled < 0 { "-" } else { "" };
+    let scale = *scale;
+    Ok(if scale <= 0 {
+        format!("{}{}{}", sign, digits, "0".repeat(scale.unsigned_abs() as usize))
+    } else {
+        let scale = scale as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        format!("{}{}.{}", sign, whole, fraction)
+    })
+}
+
+/// Flattens the strings in a value, looking into arrays and documents.
+fn string_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => vec![text.clone()],
+        Value::Array(values) => values.iter().flat_map(string_list).collect(),
+        Value::Document(document) => document
+            .iter()
+            .map(|(_, value)| string_list(&value))
+            .find(|strings| !strings.is_empty())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+fn conversion_error(message: &str) -> NitriteError {
+    log::error!("{}", message);
+    NitriteError::new(message, ErrorKind::EncodingError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::decode_encoded;
+
+    fn java(encoded: &str) -> JavaValue {
+        decode_encoded(encoded).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_convert_linked_hash_map() {
+        let map = java(concat!(
+            "aced0005737200176a6176612e7574696c2e4c696e6b6564486173684d617034c04e5c106cc0fb02",
+            "00015a000b6163636573734f72646572787200116a6176612e7574696c2e486173684d61700507da",
+            "c1c31660d103000246000a6c6f6164466163746f724900097468726573686f6c6478703f40000000",
+            "00000c7708000000100000000274000161737200116a6176612e6c616e672e496e746567657212e2",
+            "a0a4f781873802000149000576616c7565787200106a6176612e6c616e672e4e756d62657286ac95",
+            "1d0b94e08b020000787000000001740001627372001a6a6176612e7574696c2e4172726179732441",
+            "727261794c697374d9a43cbecd8806d20200015b0001617400135b4c6a6176612f6c616e672f4f62",
+            "6a6563743b7870757200135b4c6a6176612e6c616e672e537472696e673badd256e7e91d7b470200",
+            "007870000000027400017871007e000d7800"
+        ));
+        let document = to_document(&map).unwrap();
+        assert_eq!(document.get("a").unwrap(), Value::I32(1));
+        assert_eq!(
+            document.get("b").unwrap(),
+            Value::Array(vec![Value::from("x"), Value::from("x")])
+        );
+    }
+
+    #[test]
+    fn test_convert_nitrite_document_and_id() {
+        let document = to_document(&java(concat!(
+            "aced00057372002b6f72672e64697a69746172742e6e6f322e636f6c6c656374696f6e2e4e697472",
+            "697465446f63756d656e740000000058104966030000787200176a6176612e7574696c2e4c696e6b",
+            "6564486173684d617034c04e5c106cc0fb0200015a000b6163636573734f72646572787200116a61",
+            "76612e7574696c2e486173684d61700507dac1c31660d103000246000a6c6f6164466163746f7249",
+            "00097468726573686f6c6478703f4000000000000c770800000010000000027400046e616d657400",
+            "016e7400066e65737465647371007e00003f4000000000000c770800000010000000017400016b73",
+            "72000e6a6176612e6c616e672e4c6f6e673b8be490cc8f23df0200014a000576616c756578720010",
+            "6a6176612e6c616e672e4e756d62657286ac951d0b94e08b02000078700000000000000002780077",
+            "0400000001737200236f72672e64697a69746172742e6e6f322e636f6d6d6f6e2e7475706c65732e",
+            "50616972000000005f4b13470300024c000566697273747400124c6a6176612f6c616e672f4f626a",
+            "6563743b4c00067365636f6e6471007e000d787071007e000871007e000b78787800770400000002",
+            "7371007e000c71007e000471007e0005787371007e000c71007e000671007e00077878"
+        )))
+        .unwrap();
+        assert_eq!(document.get("name").unwrap(), Value::from("n"));
+        assert_eq!(document.get("nested.k").unwrap(), Value::I64(2));
+
+        let id = java(concat!(
+            "aced0005737200256f72672e64697a69746172742e6e6f322e636f6c6c656374696f6e2e4e6974",
+            "72697465496400000000581049670300014c0007696456616c75657400124c6a6176612f6c616e",
+            "672f537472696e673b7870771500133132333435363738393031323334353637383978"
+        ));
+        assert_eq!(java_id(&id), Some(1234567890123456789));
+        assert_eq!(
+            to_value(&id).unwrap(),
+            Value::NitriteId(NitriteId::create_id(1234567890123456789).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_convert_date_uuid_and_big_decimal() {
+        let date = java(concat!(
+            "aced00057372000e6a6176612e7574696c2e44617465686a81014b5974190300007870770800",
+            "00018bcfe5687b78"
+        ));
+        assert_eq!(to_value(&date).unwrap(), Value::I64(1700000000123));
+
+        let uuid = java(concat!(
+            "aced00057372000e6a6176612e7574696c2e55554944bc9903f7986d852f0200024a000c6c6561",
+            "7374536967426974734a000b6d6f7374536967426974737870a456426614174000123e4567e89b",
+            "12d3"
+        ));
+        assert_eq!(
+            to_value(&uuid).unwrap(),
+            Value::from("123e4567-e89b-12d3-a456-426614174000")
+        );
+
+        let decimal = java(concat!(
+            "aced0005737200146a6176612e6d6174682e426967446563696d616c54c71557f981284f030002",
+            "4900057363616c654c0006696e7456616c7400164c6a6176612f6d6174682f426967496e746567",
+            "65723b787200106a6176612e6c616e672e4e756d62657286ac951d0b94e08b0200007870000000",
+            "03737200146a6176612e6d6174682e426967496e74656765728cfc9f1fa93bfb1d030006490008",
+            "626974436f756e744900096269744c656e67746849001366697273744e6f6e7a65726f42797465",
+            "4e756d49000c6c6f776573745365744269744900067369676e756d5b00096d61676e6974756465",
+            "7400025b427871007e0002fffffffffffffffffffffffefffffffe00000001757200025b42acf3",
+            "17f8060854e0020000787000000003bc614e7878"
+        ));
+        assert_eq!(to_value(&decimal).unwrap(), Value::from("12345.678"));
+    }
+
+    #[test]
+    fn test_convert_index_descriptor() {
+        let index = java(concat!(
+            "aced0005737200266f72672e64697a69746172742e6e6f322e696e6465782e496e64657844657363",
+            "726970746f72000000005dfa648d0300034c000e636f6c6c656374696f6e4e616d657400124c6a61",
+            "76612f6c616e672f537472696e673b4c00066669656c64737400204c6f72672f64697a6974617274",
+            "2f6e6f322f636f6d6d6f6e2f4669656c64733b4c0009696e6465785479706571007e000178707708",
+            "0006556e697175657372001e6f72672e64697a69746172742e6e6f322e636f6d6d6f6e2e4669656c",
+            "6473000000005f772f440300014c000a6669656c644e616d65737400104c6a6176612f7574696c2f",
+            "4c6973743b7870737200136a6176612e7574696c2e41727261794c6973747881d21d99c7619d0300",
+            "0149000473697a6578700000000277040000000274000161740001627878770300016378"
+        ));
+        assert_eq!(
+            java_index(&index).unwrap(),
+            IndexDefinition {
+                index_type: UNIQUE_INDEX.to_string(),
+                fields: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_convert_plain_json() {
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{"name": "n", "count": 3, "ratio": 0.5, "tags": ["a"], "_id": "1"}"#,
+        )
+        .unwrap();
+        let Value::Document(document) = json_to_value(&json).unwrap() else {
+            panic!("expected a document");
+        };
+        assert_eq!(document.get("count").unwrap(), Value::I64(3));
+        assert_eq!(document.get("ratio").unwrap(), Value::F64(0.5));
+        assert!(!document.has_id());
+
+        let index: serde_json::Value =
+            serde_json::from_str(r#"{"type": "NonUnique", "fields": ["a", "b"]}"#).unwrap();
+        assert_eq!(
+            json_index(&index).unwrap(),
+            IndexDefinition {
+                index_type: NON_UNIQUE_INDEX.to_string(),
+                fields: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+        assert_eq!(json_id(&serde_json::json!("1700000000000000001")), Some(1700000000000000001));
+    }
+}
@@ -0,0 +1,182 @@
+//! The JSON export read by the importer.
+
+use crate::convert::{
+    java_id, java_index, json_id, json_index, json_to_value, to_document, IndexDefinition,
+};
+use crate::stream::{decode, JavaValue};
+use base64::Engine;
+use nitrite::collection::Document;
+use nitrite::common::Value;
+use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
+use serde::Deserialize;
+
+/// Start of a Java serialization stream, hex encoded.
+const HEX_STREAM_PREFIX: &str = "aced0005";
+/// Start of a Java serialization stream, base64 encoded.
+const BASE64_STREAM_PREFIX: &str = "rO0AB";
+
+/// The top level of an export written by Java Nitrite's exporter.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct JavaExport {
+    #[serde(default)]
+    pub(crate) collections: Vec<ExportedMap>,
+    #[serde(default)]
+    pub(crate) repositories: Vec<ExportedMap>,
+    #[serde(default, rename = "keyedRepositories")]
+    pub(crate) keyed_repositories: Vec<ExportedMap>,
+}
+
+/// A collection or repository with its indexes and documents.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExportedMap {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) indices: Vec<ExportedIndex>,
+    #[serde(default)]
+    pub(crate) data: Vec<ExportedEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExportedIndex {
+    pub(crate) index: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExportedEntry {
+    pub(crate) key: serde_json::Value,
+    pub(crate) value: serde_json::Value,
+}
+
+impl ExportedIndex {
+    pub(crate) fn definition(&self) -> NitriteResult<IndexDefinition> {
+        match decode_json(&self.index)? {
+            Some(java) => java_index(&java),
+            None => json_index(&self.index),
+        }
+    }
+}
+
+impl ExportedEntry {
+    /// The numeric value of the document's `NitriteId`.
+    pub(crate) fn id(&self) -> NitriteResult<u64> {
+        let id = match decode_json(&self.key)? {
+            Some(java) => java_id(&java),
+            None => json_id(&self.key),
+        };
+        id.ok_or_else(|| export_error(&format!("Cannot read the document id {}", self.key)))
+    }
+
+    pub(crate) fn document(&self) -> NitriteResult<Document> {
+        match decode_json(&self.value)? {
+            Some(java) => to_document(&java),
+            None => match json_to_value(&self.value)? {
+                Value::Document(document) => Ok(document),
+                _ => Err(export_error(&format!(
+                    "Document is not an object: {}",
+                    self.value
+                ))),
+            },
+        }
+    }
+}
+
+/// Decodes a JSON string that holds a hex or base64 encoded Java serialization stream,
+/// and returns `None` for any other value.
+fn decode_json(json: &serde_json::Value) -> NitriteResult<Option<JavaValue>> {
+    match json {
+        serde_json::Value::String(text) => decode_encoded(text),
+        _ => Ok(None),
+    }
+}
+
+/// Decodes a hex or base64 encoded Java serialization stream, and returns `None` if
+/// `text` is not one.
+pub(crate) fn decode_encoded(text: &str) -> NitriteResult<Option<JavaValue>> {
+    let text = text.trim();
+    let bytes = if text
+        .get(..HEX_STREAM_PREFIX.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(HEX_STREAM_PREFIX))
+    {
+        decode_hex(text)?
+    } else if text.starts_with(BASE64_STREAM_PREFIX) {
+        // accept both alphabets, with or without padding
+        let normalized: String = text
+            .chars()
+            .filter(|c| *c != '=')
+            .map(|c| match c {
+                '+' => '-',
+                '/' => '_',
+                c => c,
+            })
+            .collect();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(normalized)
+            .map_err(|err| export_error(&format!("Invalid base64 in export: {}", err)))?
+    } else {
+        return Ok(None);
+    };
+    decode(&bytes).map(Some)
+}
+
+fn decode_hex(text: &str) -> NitriteResult<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return Err(export_error("Hex encoded value has an odd length"));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            text.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| export_error("Invalid hex in export"))
+        })
+        .collect()
+}
+
+pub(crate) fn export_error(message: &str) -> NitriteError {
+    log::error!("{}", message);
+    NitriteError::new(message, ErrorKind::EncodingError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG_HEX: &str = concat!(
+        "aced00057372000e6a6176612e6c616e672e4c6f6e673b8be490cc8f23df0200014a000576616c75",
+        "65787200106a6176612e6c616e672e4e756d62657286ac951d0b94e08b0200007870000000000000002a"
+    );
+
+    #[test]
+    fn test_decode_encoded_hex_and_base64() {
+        let from_hex = decode_encoded(LONG_HEX).unwrap().unwrap();
+        let bytes = decode_hex(LONG_HEX).unwrap();
+        let standard = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        let url_safe = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes);
+        assert_eq!(decode_encoded(&standard).unwrap().unwrap(), from_hex);
+        assert_eq!(decode_encoded(&url_safe).unwrap().unwrap(), from_hex);
+        assert_eq!(decode_encoded(&LONG_HEX.to_uppercase()).unwrap().unwrap(), from_hex);
+    }
+
+    #[test]
+    fn test_decode_encoded_ignores_plain_strings() {
+        assert!(decode_encoded("1700000000000000001").unwrap().is_none());
+        assert!(decode_encoded("hello").unwrap().is_none());
+        assert!(decode_encoded("aced0005zz").is_err());
+    }
+
+    #[test]
+    fn test_exported_entry_with_plain_json() {
+        let entry: ExportedEntry = serde_json::from_str(
+            r#"{"key": 1700000000000000001, "value": {"name": "a", "tags": [1, 2]}}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.id().unwrap(), 1700000000000000001);
+        let document = entry.document().unwrap();
+        assert_eq!(document.get("name").unwrap(), Value::from("a"));
+
+        let entry: ExportedEntry =
+            serde_json::from_str(r#"{"key": "not an id", "value": [1]}"#).unwrap();
+        assert!(entry.id().is_err());
+        assert!(entry.document().is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::export::{export_error, ExportedMap, JavaExport};
+use nitrite::collection::{NitriteCollection, NitriteId};
+use nitrite::common::{
+    DOC_ID, DOC_MODIFIED, DOC_REVISION, DOC_SOURCE, KEY_OBJ_SEPARATOR,
+};
+use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
+use nitrite::index::IndexOptions;
+use nitrite::nitrite::Nitrite;
+use nitrite::repository::EntityDescriptor;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Counts of what a migration did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MigrationSummary {
+    /// Collections migrated.
+    pub collections: u64,
+    /// Repositories migrated, keyed ones included.
+    pub repositories: u64,
+    /// Indexes created in the target.
+    pub indexes: u64,
+    /// Documents inserted.
+    pub inserted: u64,
+    /// Documents ignored because the target already had a document with the same id.
+    pub skipped: u64,
+    /// Documents inserted with a new id, see [`JavaImporter::reassign_invalid_ids`].
+    pub reassigned: u64,
+}
+
+/// Migrates the collections, repositories and indexes of a Java Nitrite database into
+/// a Nitrite database.
+///
+/// Documents keep their `NitriteId`s. Their revision and modification time start over,
+/// as they do for any inserted document. A document whose id already exists in the
+/// target is left alone, so an interrupted migration can be run again.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite_java_import::JavaImporter;
+///
+/// let summary = JavaImporter::new(&db).import_from_file("export.json")?;
+/// println!("migrated {} documents", summary.inserted);
+/// ```
+pub struct JavaImporter {
+    nitrite: Nitrite,
+    reassign_invalid_ids: bool,
+}
+
+impl JavaImporter {
+    /// Creates an importer that migrates into `nitrite`.
+    pub fn new(nitrite: &Nitrite) -> Self {
+        JavaImporter {
+            nitrite: nitrite.clone(),
+            reassign_invalid_ids: false,
+        }
+    }
+
+    /// Gives a new id to documents whose Java id is outside the range of [`NitriteId`],
+    /// instead of failing.
+    ///
+    /// Java Nitrite 3 generated small sequential ids, which this crate does not accept.
+    pub fn reassign_invalid_ids(mut self, reassign: bool) -> Self {
+        self.reassign_invalid_ids = reassign;
+        self
+    }
+
+    /// Imports from the export file at `path`.
+    pub fn import_from_file(&self, path: impl AsRef<Path>) -> NitriteResult<MigrationSummary> {
+        let file = File::open(path)?;
+        self.import_from(BufReader::new(file))
+    }
+
+    /// Imports from an export read from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EncodingError` if the export is malformed, and an `InvalidId` error
+    /// for a Java id outside the range of [`NitriteId`] unless
+    /// [`reassign_invalid_ids`](Self::reassign_invalid_ids) is set.
+    pub fn import_from<R: Read>(&self, reader: R) -> NitriteResult<MigrationSummary> {
+        let export: JavaExport = serde_json::from_reader(reader)
+            .map_err(|err| export_error(&format!("Invalid Java Nitrite export: {}", err)))?;
+
+        let mut summary = MigrationSummary::default();
+        for map in &export.collections {
+            let collection = self.nitrite.collection(&map.name)?;
+            self.migrate(map, &collection, &mut summary)?;
+            summary.collections += 1;
+        }
+        for map in &export.repositories {
+            let repository = self
+                .nitrite
+                .dynamic_repository(EntityDescriptor::new(&map.name))?;
+            self.migrate(map, &repository.document_collection(), &mut summary)?;
+            summary.repositories += 1;
+        }
+        for map in &export.keyed_repositories {
+            let Some((entity, key)) = map.name.split_once(KEY_OBJ_SEPARATOR) else {
+                return Err(export_error(&format!(
+                    "Keyed repository {} has no key",
+                    map.name
+                )));
+            };
+            let repository = self
+                .nitrite
+                .keyed_dynamic_repository(EntityDescriptor::new(entity), key)?;
+            self.migrate(map, &repository.document_collection(), &mut summary)?;
+            summary.repositories += 1;
+        }
+
+        log::info!("Migrated Java Nitrite export: {:?}", summary);
+        Ok(summary)
+    }
+
+    fn migrate(
+        &self,
+        map: &ExportedMap,
+        collection: &NitriteCollection,
+        summary: &mut MigrationSummary,
+    ) -> NitriteResult<()> {
+        for index in &map.indices {
+            let definition = index.definition()?;
+            let fields: Vec<&str> = definition.fields.iter().map(String::as_str).collect();
+            if !collection.has_index(fields.clone())? {
+                collection.create_index(fields, &IndexOptions::new(&definition.index_type))?;
+                summary.indexes += 1;
+            }
+        }
+
+        for entry in &map.data {
+            let java_id = entry.id()?;
+            let mut document = entry.document()?;
+            for field in [DOC_ID, DOC_REVISION, DOC_MODIFIED, DOC_SOURCE] {
+                document.remove(field)?;
+            }
+
+            match NitriteId::create_id(java_id) {
+                Ok(id) => {
+                    if collection.get_by_id(&id)?.is_some() {
+                        summary.skipped += 1;
+                        continue;
+                    }
+                    document.put(DOC_ID, id)?;
+                    collection.insert(document)?;
+                    summary.inserted += 1;
+                }
+                Err(_) if self.reassign_invalid_ids => {
+                    collection.insert(document)?;
+                    summary.reassigned += 1;
+                    summary.inserted += 1;
+                }
+                Err(err) => {
+                    log::error!("Document id {} in {} is not a valid NitriteId", java_id, map.name);
+                    return Err(NitriteError::new_with_cause(
+                        &format!(
+                            "Document id {} in {} is not a valid NitriteId, enable \
+                             reassign_invalid_ids to give such documents new ids",
+                            java_id, map.name
+                        ),
+                        ErrorKind::InvalidId,
+                        err,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! # Nitrite Java Import - Migration from Java Nitrite
+//!
+//! This crate migrates databases created by [Java Nitrite](https://github.com/nitrite/nitrite-java)
+//! into a Nitrite database, typically one backed by the Fjall store. Collections,
+//! repositories (keyed ones included), index definitions and documents are carried over,
+//! and every document keeps its `NitriteId`.
+//!
+//! The MVStore file layout is an implementation detail of H2, so the migration reads
+//! the export that Java Nitrite writes instead: export the database with the `Exporter`
+//! of the `nitrite-support` module, which works for MVStore and RocksDB stores alike,
+//! then import the file here.
+//!
+//! ## Export format
+//!
+//! The export is a JSON object:
+//!
+//! ```json
+//! {
+//!   "collections": [
+//!     {
+//!       "name": "books",
+//!       "indices": [{ "index": "rO0ABXNy..." }],
+//!       "data": [{ "key": "rO0ABXNy...", "value": "rO0ABXNy..." }]
+//!     }
+//!   ],
+//!   "repositories": [{ "name": "com.example.Employee", "indices": [], "data": [] }],
+//!   "keyedRepositories": [{ "name": "com.example.Employee+managers", "indices": [], "data": [] }]
+//! }
+//! ```
+//!
+//! - `index` is a serialized `IndexDescriptor` (Nitrite 4) or `IndexEntry` (Nitrite 3).
+//! - `key` is the serialized `NitriteId` of the document and `value` the serialized
+//!   document.
+//! - Serialized objects are Java serialization streams, base64 or hex encoded.
+//!
+//! Plain JSON is accepted in their place, which helps when the export is produced by
+//! other means: an index as `{"type": "unique", "fields": ["isbn"]}`, a key as a number
+//! or numeric string, and a document as a JSON object.
+//!
+//! Java values become the closest Nitrite value. Boxed numbers keep their width,
+//! `Date`s become milliseconds since the epoch, `BigDecimal`s and `UUID`s become
+//! strings, enums become their constant name and `byte[]` becomes bytes. Maps and
+//! serializable objects become embedded documents, and collections become arrays.
+//!
+//! Repositories are restored as document repositories named after the Java entity
+//! class, so they can be opened with
+//! [`Nitrite::dynamic_repository`](nitrite::nitrite::Nitrite::dynamic_repository), or
+//! with a typed repository whose entity name is the Java class name.
+//!
+//! ## Usage
+//!
+//! ```rust,ignore
+//! use nitrite::nitrite::Nitrite;
+//! use nitrite_fjall_adapter::FjallModule;
+//! use nitrite_java_import::JavaImporter;
+//!
+//! let db = Nitrite::builder()
+//!     .load_module(FjallModule::with_config().db_path("/data/migrated").build())
+//!     .open_or_create(None, None)?;
+//!
+//! let summary = JavaImporter::new(&db).import_from_file("export.json")?;
+//! db.commit()?;
+//! ```
+//!
+//! The `nitrite-java-import` binary does the same from the command line:
+//!
+//! ```text
+//! nitrite-java-import export.json /data/migrated
+//! ```
+
+mod convert;
+mod export;
+mod importer;
+mod stream;
+
+pub use importer::{JavaImporter, MigrationSummary};
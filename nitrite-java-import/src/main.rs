@@ -0,0 +1,58 @@
+//! Command line tool migrating a Java Nitrite export into a Fjall backed database.
+
+use nitrite::nitrite::Nitrite;
+use nitrite_fjall_adapter::FjallModule;
+use nitrite_java_import::JavaImporter;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: nitrite-java-import [--reassign-invalid-ids] <export.json> <db-path>";
+
+fn main() -> ExitCode {
+    let mut reassign_invalid_ids = false;
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--reassign-invalid-ids" => reassign_invalid_ids = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [export, db_path] = paths.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let result = Nitrite::builder()
+        .load_module(FjallModule::with_config().db_path(db_path).build())
+        .open_or_create(None, None)
+        .and_then(|db| {
+            let summary = JavaImporter::new(&db)
+                .reassign_invalid_ids(reassign_invalid_ids)
+                .import_from_file(export)?;
+            db.close()?;
+            Ok(summary)
+        });
+
+    match result {
+        Ok(summary) => {
+            println!(
+                "Migrated {} collection(s) and {} repository(ies): {} document(s) inserted, \
+                 {} skipped, {} with new ids, {} index(es) created",
+                summary.collections,
+                summary.repositories,
+                summary.inserted,
+                summary.skipped,
+                summary.reassigned,
+                summary.indexes
+            );
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Migration failed: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,704 @@
+//! Decoder for the Java object serialization stream protocol.
+//!
+//! Java Nitrite stores keys, documents and index descriptors as serialized Java objects,
+//! and its exporter writes them in this form. The decoder turns a stream into a tree of
+//! [`JavaValue`]s without knowing the classes involved; interpreting the tree is left
+//! to the `convert` module.
+
+use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
+use std::rc::Rc;
+
+const STREAM_MAGIC: u16 = 0xACED;
+const STREAM_VERSION: u16 = 5;
+
+const TC_NULL: u8 = 0x70;
+const TC_REFERENCE: u8 = 0x71;
+const TC_CLASSDESC: u8 = 0x72;
+const TC_OBJECT: u8 = 0x73;
+const TC_STRING: u8 = 0x74;
+const TC_ARRAY: u8 = 0x75;
+const TC_CLASS: u8 = 0x76;
+const TC_BLOCKDATA: u8 = 0x77;
+const TC_ENDBLOCKDATA: u8 = 0x78;
+const TC_RESET: u8 = 0x79;
+const TC_BLOCKDATALONG: u8 = 0x7A;
+const TC_EXCEPTION: u8 = 0x7B;
+const TC_LONGSTRING: u8 = 0x7C;
+const TC_PROXYCLASSDESC: u8 = 0x7D;
+const TC_ENUM: u8 = 0x7E;
+
+const BASE_WIRE_HANDLE: usize = 0x7E0000;
+
+const SC_WRITE_METHOD: u8 = 0x01;
+const SC_SERIALIZABLE: u8 = 0x02;
+const SC_EXTERNALIZABLE: u8 = 0x04;
+const SC_BLOCK_DATA: u8 = 0x08;
+
+const MAX_DEPTH: usize = 512;
+
+/// A value read from a Java serialization stream.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JavaValue {
+    Null,
+    Bool(bool),
+    Byte(i8),
+    Char(char),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// A `byte[]`.
+    Bytes(Vec<u8>),
+    /// Any other array, with its JVM class name such as `[Ljava.lang.Object;`.
+    Array {
+        class_name: String,
+        elements: Vec<JavaValue>,
+    },
+    Enum {
+        class_name: String,
+        constant: String,
+    },
+    Class(String),
+    Object(JavaObject),
+}
+
+/// A serialized object, with the data written for each class of its hierarchy.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JavaObject {
+    pub(crate) class_name: String,
+    /// Superclass first, the object's own class last.
+    pub(crate) class_data: Vec<ClassData>,
+}
+
+/// The data one class of an object's hierarchy wrote.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ClassData {
+    pub(crate) class_name: String,
+    /// Serializable fields, present when the class used the default serialization.
+    pub(crate) fields: Vec<(String, JavaValue)>,
+    /// What a custom `writeObject` wrote after the fields.
+    pub(crate) annotations: Vec<Annotation>,
+}
+
+/// An item written by a custom `writeObject` method.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Annotation {
+    /// Primitive data, with consecutive blocks joined.
+    Block(Vec<u8>),
+    Object(JavaValue),
+}
+
+impl JavaObject {
+    /// Whether `class_name` is the object's class or one of its superclasses.
+    pub(crate) fn is_a(&self, class_name: &str) -> bool {
+        self.class_data.iter().any(|data| data.class_name == class_name)
+    }
+
+    /// The data written for `class_name` in the object's hierarchy.
+    pub(crate) fn class(&self, class_name: &str) -> Option<&ClassData> {
+        self.class_data.iter().find(|data| data.class_name == class_name)
+    }
+
+    /// The value of the field `name`, searching the whole hierarchy.
+    pub(crate) fn field(&self, name: &str) -> Option<&JavaValue> {
+        self.class_data
+            .iter()
+            .rev()
+            .flat_map(|data| data.fields.iter())
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl ClassData {
+    /// The objects written by the class, skipping primitive data.
+    pub(crate) fn annotation_objects(&self) -> impl Iterator<Item = &JavaValue> {
+        self.annotations.iter().filter_map(|annotation| match annotation {
+            Annotation::Object(value) => Some(value),
+            Annotation::Block(_) => None,
+        })
+    }
+
+    /// The primitive data written by the class, in order.
+    pub(crate) fn blocks(&self) -> impl Iterator<Item = &[u8]> {
+        self.annotations.iter().filter_map(|annotation| match annotation {
+            Annotation::Block(bytes) => Some(bytes.as_slice()),
+            Annotation::Object(_) => None,
+        })
+    }
+}
+
+/// Decodes a single object from a Java serialization stream.
+pub(crate) fn decode(bytes: &[u8]) -> NitriteResult<JavaValue> {
+    let mut reader = StreamReader {
+        bytes,
+        pos: 0,
+        handles: Vec::new(),
+        depth: 0,
+    };
+    let magic = reader.read_u16()?;
+    let version = reader.read_u16()?;
+    if magic != STREAM_MAGIC || version != STREAM_VERSION {
+        return Err(stream_error(&format!(
+            "Not a Java serialization stream: header {:04x} {:04x}",
+            magic, version
+        )));
+    }
+    reader.read_object()
+}
+
+/// Reads a string written with `DataOutput.writeUTF` from the start of `bytes`, and
+/// returns it with the number of bytes it took.
+pub(crate) fn read_utf(bytes: &[u8]) -> Option<(String, usize)> {
+    let length = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]) as usize;
+    let data = bytes.get(2..2 + length)?;
+    Some((decode_modified_utf8(data), 2 + length))
+}
+
+#[derive(Debug)]
+struct ClassDesc {
+    name: String,
+    flags: u8,
+    fields: Vec<FieldDesc>,
+    super_desc: Option<Rc<ClassDesc>>,
+}
+
+#[derive(Debug)]
+struct FieldDesc {
+    type_code: u8,
+    name: String,
+}
+
+/// Field values and annotations written by a class with a custom `writeObject`.
+type CustomData = (Vec<(String, JavaValue)>, Vec<Annotation>);
+
+#[derive(Debug, Clone)]
+enum Handle {
+    Pending,
+    ClassDesc(Rc<ClassDesc>),
+    Value(JavaValue),
+}
+
+struct StreamReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    handles: Vec<Handle>,
+    depth: usize,
+}
+
+impl StreamReader<'_> {
+    fn read_object(&mut self) -> NitriteResult<JavaValue> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(stream_error("Java object graph is nested too deeply"));
+        }
+        let result = self.read_object_content();
+        self.depth -= 1;
+        result
+    }
+
+    fn read_object_content(&mut self) -> NitriteResult<JavaValue> {
+        match self.read_u8()? {
+            TC_NULL => Ok(JavaValue::Null),
+            TC_REFERENCE => match self.read_handle()? {
+                Handle::Value(value) => Ok(value),
+                // a reference to an object still being read, only cycles produce these
+                Handle::Pending => Ok(JavaValue::Null),
+                Handle::ClassDesc(_) => {
+                    Err(stream_error("Expected an object but found a class descriptor"))
+                }
+            },
+            TC_STRING => {
+                let length = self.read_u16()? as usize;
+                let value = JavaValue::String(decode_modified_utf8(self.read_bytes(length)?));
+                self.handles.push(Handle::Value(value.clone()));
+                Ok(value)
+            }
+            TC_LONGSTRING => {
+                let length = self.read_i64()?;
+                let length = self.read_length(length)?;
+                let value = JavaValue::String(decode_modified_utf8(self.read_bytes(length)?));
+                self.handles.push(Handle::Value(value.clone()));
+                Ok(value)
+            }
+            TC_OBJECT => self.read_new_object(),
+            TC_ARRAY => self.read_array(),
+            TC_ENUM => self.read_enum(),
+            TC_CLASS => {
+                let desc = self.read_required_class_desc()?;
+                let value = JavaValue::Class(desc.name.clone());
+                self.handles.push(Handle::Value(value.clone()));
+                Ok(value)
+            }
+            TC_RESET => {
+                self.handles.clear();
+                self.read_object_content()
+            }
+            TC_EXCEPTION => Err(stream_error("Java stream contains a serialization exception")),
+            tag => Err(stream_error(&format!(
+                "Unexpected tag {:#04x} at offset {}",
+                tag,
+                self.pos - 1
+            ))),
+        }
+    }
+
+    fn read_class_desc(&mut self) -> NitriteResult<Option<Rc<ClassDesc>>> {
+        match self.read_u8()? {
+            TC_NULL => Ok(None),
+            TC_REFERENCE => match self.read_handle()? {
+                Handle::ClassDesc(desc) => Ok(Some(desc)),
+                _ => Err(stream_error("Expected a class descriptor reference")),
+            },
+            TC_CLASSDESC => {
+                let name = self.read_utf()?;
+                let _serial_version_uid = self.read_i64()?;
+                let handle = self.reserve_handle();
+                let flags = self.read_u8()?;
+                let count = self.read_u16()?;
+                let mut fields = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let type_code = self.read_u8()?;
+                    let name = self.read_utf()?;
+                    if type_code == b'L' || type_code == b'[' {
+                        // the field's JVM type signature
+                        self.read_object()?;
+                    }
+                    fields.push(FieldDesc { type_code, name });
+                }
+                self.read_annotations()?;
+                let super_desc = self.read_class_desc()?;
+                let desc = Rc::new(ClassDesc {
+                    name,
+                    flags,
+                    fields,
+                    super_desc,
+                });
+                self.handles[handle] = Handle::ClassDesc(desc.clone());
+                Ok(Some(desc))
+            }
+            TC_PROXYCLASSDESC => {
+                let handle = self.reserve_handle();
+                let count = self.read_i32()?;
+                for _ in 0..count {
+                    self.read_utf()?;
+                }
+                self.read_annotations()?;
+                let super_desc = self.read_class_desc()?;
+                let desc = Rc::new(ClassDesc {
+                    name: "$Proxy".to_string(),
+                    flags: SC_SERIALIZABLE,
+                    fields: Vec::new(),
+                    super_desc,
+                });
+                self.handles[handle] = Handle::ClassDesc(desc.clone());
+                Ok(Some(desc))
+            }
+            tag => Err(stream_error(&format!(
+                "Expected a class descriptor but found tag {:#04x}",
+                tag
+            ))),
+        }
+    }
+
+    fn read_required_class_desc(&mut self) -> NitriteResult<Rc<ClassDesc>> {
+        self.read_class_desc()?
+            .ok_or_else(|| stream_error("Missing class descriptor"))
+    }
+
+    fn read_new_object(&mut self) -> NitriteResult<JavaValue> {
+        let desc = self.read_required_class_desc()?;
+        let handle = self.reserve_handle();
+
+        let mut hierarchy = Vec::new();
+        let mut current = Some(desc.clone());
+        while let Some(class) = current {
+            current = class.super_desc.clone();
+            hierarchy.push(class);
+        }
+
+        let mut class_data = Vec::with_capacity(hierarchy.len());
+        for class in hierarchy.iter().rev() {
+            class_data.push(self.read_class_data(class)?);
+        }
+
+        let value = JavaValue::Object(JavaObject {
+            class_name: desc.name.clone(),
+            class_data,
+        });
+        self.handles[handle] = Handle::Value(value.clone());
+        Ok(value)
+    }
+
+    fn read_class_data(&mut self, class: &ClassDesc) -> NitriteResult<ClassData> {
+        let mut data = ClassData {
+            class_name: class.name.clone(),
+            fields: Vec::new(),
+            annotations: Vec::new(),
+        };
+
+        if class.flags & SC_EXTERNALIZABLE != 0 {
+            if class.flags & SC_BLOCK_DATA == 0 {
+                return Err(stream_error(&format!(
+                    "Class {} uses the pre-1.2 externalizable format",
+                    class.name
+                )));
+            }
+            data.annotations = self.read_annotations()?;
+        } else if class.flags & SC_SERIALIZABLE != 0 {
+            if class.flags & SC_WRITE_METHOD == 0 {
+                data.fields = self.read_field_values(class)?;
+            } else if class.fields.is_empty() {
+                data.annotations = self.read_annotations()?;
+            } else {
+                // The stream does not say whether a custom writeObject wrote the default
+                // fields first. JDK classes do, Nitrite's classes write their state by
+                // hand, so start with the likely layout and fall back to the other.
+                let defaults_first = !class.name.starts_with("org.dizitart.");
+                let (pos, handles) = (self.pos, self.handles.len());
+                match self.read_custom_data(class, defaults_first) {
+                    Ok((fields, annotations)) => {
+                        data.fields = fields;
+                        data.annotations = annotations;
+                    }
+                    Err(_) => {
+                        self.pos = pos;
+                        self.handles.truncate(handles);
+                        let (fields, annotations) = self.read_custom_data(class, !defaults_first)?;
+                        data.fields = fields;
+                        data.annotations = annotations;
+                    }
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    fn read_custom_data(
+        &mut self,
+        class: &ClassDesc,
+        with_fields: bool,
+    ) -> NitriteResult<CustomData> {
+        let fields = if with_fields {
+            self.read_field_values(class)?
+        } else {
+            Vec::new()
+        };
+        Ok((fields, self.read_annotations()?))
+    }
+
+    fn read_field_values(&mut self, class: &ClassDesc) -> NitriteResult<Vec<(String, JavaValue)>> {
+        let mut values = Vec::with_capacity(class.fields.len());
+        for field in &class.fields {
+            let value = self.read_typed_value(field.type_code)?;
+            values.push((field.name.clone(), value));
+        }
+        Ok(values)
+    }
+
+    fn read_typed_value(&mut self, type_code: u8) -> NitriteResult<JavaValue> {
+        Ok(match type_code {
+            b'B' => JavaValue::Byte(self.read_u8()? as i8),
+            b'C' => JavaValue::Char(
+                char::from_u32(self.read_u16()? as u32).unwrap_or(char::REPLACEMENT_CHARACTER),
+            ),
+            b'D' => JavaValue::Double(f64::from_bits(self.read_i64()? as u64)),
+            b'F' => JavaValue::Float(f32::from_bits(self.read_i32()? as u32)),
+            b'I' => JavaValue::Int(self.read_i32()?),
+            b'J' => JavaValue::Long(self.read_i64()?),
+            b'S' => JavaValue::Short(self.read_u16()? as i16),
+            b'Z' => JavaValue::Bool(self.read_u8()? != 0),
+            b'L' | b'[' => self.read_object()?,
+            code => {
+                return Err(stream_error(&format!(
+                    "Unknown field type code {:?}",
+                    code as char
+                )))
+            }
+        })
+    }
+
+    fn read_annotations(&mut self) -> NitriteResult<Vec<Annotation>> {
+        let mut annotations: Vec<Annotation> = Vec::new();
+        loop {
+            let block = match self.peek_u8()? {
+                TC_ENDBLOCKDATA => {
+                    self.pos += 1;
+                    return Ok(annotations);
+                }
+                TC_BLOCKDATA => {
+                    self.pos += 1;
+                    let length = self.read_u8()? as usize;
+                    self.read_bytes(length)?
+                }
+                TC_BLOCKDATALONG => {
+                    self.pos += 1;
+                    let length = self.read_i32()?;
+                    let length = self.read_length(length as i64)?;
+                    self.read_bytes(length)?
+                }
+                _ => {
+                    annotations.push(Annotation::Object(self.read_object()?));
+                    continue;
+                }
+            };
+            // writers split primitive data at arbitrary points, so join the pieces
+            match annotations.last_mut() {
+                Some(Annotation::Block(bytes)) => bytes.extend_from_slice(block),
+                _ => annotations.push(Annotation::Block(block.to_vec())),
+            }
+        }
+    }
+
+    fn read_array(&mut self) -> NitriteResult<JavaValue> {
+        let desc = self.read_required_class_desc()?;
+        let handle = self.reserve_handle();
+        let length = self.read_i32()?;
+        let length = self.read_length(length as i64)?;
+        let element_type = desc.name.as_bytes().get(1).copied().unwrap_or(b'L');
+
+        let value = if element_type == b'B' {
+            JavaValue::Bytes(self.read_bytes(length)?.to_vec())
+        } else {
+            // do not trust the length for the allocation, the elements may be missing
+            let mut elements = Vec::with_capacity(length.min(1024));
+            for _ in 0..length {
+                elements.push(self.read_typed_value(element_type)?);
+            }
+            JavaValue::Array {
+                class_name: desc.name.clone(),
+                elements,
+            }
+        };
+        self.handles[handle] = Handle::Value(value.clone());
+        Ok(value)
+    }
+
+    fn read_enum(&mut self) -> NitriteResult<JavaValue> {
+        let desc = self.read_required_class_desc()?;
+        let handle = self.reserve_handle();
+        let constant = match self.read_object()? {
+            JavaValue::String(constant) => constant,
+            _ => return Err(stream_error("Enum constant name is not a string")),
+        };
+        let value = JavaValue::Enum {
+            class_name: desc.name.clone(),
+            constant,
+        };
+        self.handles[handle] = Handle::Value(value.clone());
+        Ok(value)
+    }
+
+    fn reserve_handle(&mut self) -> usize {
+        self.handles.push(Handle::Pending);
+        self.handles.len() - 1
+    }
+
+    fn read_handle(&mut self) -> NitriteResult<Handle> {
+        let wire = self.read_i32()? as u32 as usize;
+        wire.checked_sub(BASE_WIRE_HANDLE)
+            .and_then(|index| self.handles.get(index))
+            .cloned()
+            .ok_or_else(|| stream_error(&format!("Unknown object handle {:#x}", wire)))
+    }
+
+    fn read_utf(&mut self) -> NitriteResult<String> {
+        let length = self.read_u16()? as usize;
+        Ok(decode_modified_utf8(self.read_bytes(length)?))
+    }
+
+    fn read_length(&self, length: i64) -> NitriteResult<usize> {
+        usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= self.bytes.len() - self.pos)
+            .ok_or_else(|| stream_error(&format!("Invalid length {}", length)))
+    }
+
+    fn peek_u8(&self) -> NitriteResult<u8> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| stream_error("Unexpected end of Java stream"))
+    }
+
+    fn read_bytes(&mut self, length: usize) -> NitriteResult<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + length)
+            .ok_or_else(|| stream_error("Unexpected end of Java stream"))?;
+        self.pos += length;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> NitriteResult<u8> {
+        let byte = self.peek_u8()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn read_u16(&mut self) -> NitriteResult<u16> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_i32(&mut self) -> NitriteResult<i32> {
+        let mut buffer = [0u8; 4];
+        buffer.copy_from_slice(self.read_bytes(4)?);
+        Ok(i32::from_be_bytes(buffer))
+    }
+
+    fn read_i64(&mut self) -> NitriteResult<i64> {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(self.read_bytes(8)?);
+        Ok(i64::from_be_bytes(buffer))
+    }
+}
+
+/// Decodes Java's modified UTF-8, which encodes NUL in two bytes and characters outside
+/// the basic plane as surrogate pairs.
+fn decode_modified_utf8(bytes: &[u8]) -> String {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let first = bytes[i] as u16;
+        let continuation = |offset: usize| bytes.get(i + offset).map(|b| (*b as u16) & 0x3F);
+        if first & 0x80 == 0 {
+            units.push(first);
+            i += 1;
+        } else if first & 0xE0 == 0xC0 {
+            units.push(((first & 0x1F) << 6) | continuation(1).unwrap_or(0));
+            i += 2;
+        } else if first & 0xF0 == 0xE0 {
+            units.push(
+                ((first & 0x0F) << 12)
+                    | (continuation(1).unwrap_or(0) << 6)
+                    | continuation(2).unwrap_or(0),
+            );
+            i += 3;
+        } else {
+            units.push(0xFFFD);
+            i += 1;
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+fn stream_error(message: &str) -> NitriteError {
+    log::error!("{}", message);
+    NitriteError::new(message, ErrorKind::EncodingError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_string_with_modified_utf8() {
+        let value = decode(&hex("aced000574000e68c3a96c6c6fc080eda0b4edb49e")).unwrap();
+        assert_eq!(value, JavaValue::String("héllo\u{0}𝄞".to_string()));
+    }
+
+    #[test]
+    fn test_decode_boxed_long() {
+        let value = decode(&hex(concat!(
+            "aced00057372000e6a6176612e6c616e672e4c6f6e673b8be490cc8f23df0200014a000576616c75",
+            "65787200106a6176612e6c616e672e4e756d62657286ac951d0b94e08b0200007870000000000000002a"
+        )))
+        .unwrap();
+        let JavaValue::Object(object) = value else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.class_name, "java.lang.Long");
+        assert!(object.is_a("java.lang.Number"));
+        assert_eq!(object.field("value"), Some(&JavaValue::Long(42)));
+    }
+
+    #[test]
+    fn test_decode_array_list_with_references() {
+        let value = decode(&hex(concat!(
+            "aced0005737200136a6176612e7574696c2e41727261794c6973747881d21d99c7619d0300014900",
+            "0473697a65787000000005770400000005737200116a6176612e6c616e672e496e746567657212e2",
+            "a0a4f781873802000149000576616c7565787200106a6176612e6c616e672e4e756d62657286ac95",
+            "1d0b94e08b02000078700000000174000374776f737200106a6176612e6c616e672e446f75626c65",
+            "80b3c24a296bfb0402000144000576616c75657871007e0003400c000000000000737200116a6176",
+            "612e6c616e672e426f6f6c65616ecd207280d59cfaee0200015a000576616c75657870017078"
+        )))
+        .unwrap();
+        let JavaValue::Object(object) = value else {
+            panic!("expected an object");
+        };
+        let list = object.class("java.util.ArrayList").unwrap();
+        assert_eq!(list.fields, vec![("size".to_string(), JavaValue::Int(5))]);
+        assert_eq!(list.blocks().next(), Some(&[0, 0, 0, 5][..]));
+        let elements: Vec<_> = list.annotation_objects().collect();
+        assert_eq!(elements.len(), 5);
+        assert_eq!(elements[1], &JavaValue::String("two".to_string()));
+        assert_eq!(elements[4], &JavaValue::Null);
+    }
+
+    #[test]
+    fn test_decode_primitive_arrays_and_enum() {
+        assert_eq!(
+            decode(&hex("aced0005757200025b42acf317f8060854e0020000787000000003010203")).unwrap(),
+            JavaValue::Bytes(vec![1, 2, 3])
+        );
+        assert_eq!(
+            decode(&hex(
+                "aced0005757200025b494dba602676eab2a50200007870000000020000000700000008"
+            ))
+            .unwrap(),
+            JavaValue::Array {
+                class_name: "[I".to_string(),
+                elements: vec![JavaValue::Int(7), JavaValue::Int(8)],
+            }
+        );
+        assert_eq!(
+            decode(&hex(concat!(
+                "aced00057e72000a47656e2453746174757300000000000000001200007872000e6a6176612e6c",
+                "616e672e456e756d0000000000000000120000787074000752455449524544"
+            )))
+            .unwrap(),
+            JavaValue::Enum {
+                class_name: "Gen$Status".to_string(),
+                constant: "RETIRED".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_custom_write_object_without_default_fields() {
+        // org.dizitart.no2.collection.NitriteId writes its id with writeUTF only
+        let value = decode(&hex(concat!(
+            "aced0005737200256f72672e64697a69746172742e6e6f322e636f6c6c656374696f6e2e4e6974",
+            "72697465496400000000581049670300014c0007696456616c75657400124c6a6176612f6c616e",
+            "672f537472696e673b7870771500133132333435363738393031323334353637383978"
+        )))
+        .unwrap();
+        let JavaValue::Object(object) = value else {
+            panic!("expected an object");
+        };
+        let data = &object.class_data[0];
+        assert!(data.fields.is_empty());
+        let block = data.blocks().next().unwrap();
+        assert_eq!(read_utf(block).unwrap().0, "1234567890123456789");
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        assert!(decode(&hex("cafebabe")).is_err());
+        assert!(decode(&hex("aced0005")).is_err());
+        // truncated object
+        assert!(decode(&hex("aced00057372000e6a6176612e6c616e67")).is_err());
+        // reference to an unknown handle
+        assert!(decode(&hex("aced000571007e0009")).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Migration of an export written by Java Nitrite into a Fjall backed database.
+//!
+//! `fixtures/java_export.json` holds Java serialization streams of Nitrite 4 documents,
+//! ids and index descriptors, as written by the Java exporter.
+
+use nitrite::collection::{NitriteCollection, NitriteId};
+use nitrite::common::{PersistentCollection, Value, NON_UNIQUE_INDEX, UNIQUE_INDEX};
+use nitrite::errors::ErrorKind;
+use nitrite::filter::field;
+use nitrite::nitrite::Nitrite;
+use nitrite::repository::EntityDescriptor;
+use nitrite_fjall_adapter::FjallModule;
+use nitrite_java_import::{JavaImporter, MigrationSummary};
+use std::path::Path;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/java_export.json");
+
+fn open_db(path: &Path) -> Nitrite {
+    let storage_module = FjallModule::with_config()
+        .db_path(path.to_str().expect("utf8 path"))
+        .low_memory_preset()
+        .build();
+    Nitrite::builder()
+        .load_module(storage_module)
+        .open_or_create(None, None)
+        .expect("failed to open Nitrite database")
+}
+
+fn id(value: u64) -> NitriteId {
+    NitriteId::create_id(value).unwrap()
+}
+
+fn index_types(collection: &NitriteCollection) -> Vec<(Vec<String>, String)> {
+    let mut indexes: Vec<_> = collection
+        .list_indexes()
+        .unwrap()
+        .iter()
+        .map(|index| {
+            (
+                index.index_fields().field_names(),
+                index.index_type().to_string(),
+            )
+        })
+        .collect();
+    indexes.sort();
+    indexes
+}
+
+#[test]
+fn test_import_collections_and_repositories() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(dir.path());
+
+    let summary = JavaImporter::new(&db).import_from_file(FIXTURE).unwrap();
+    assert_eq!(
+        summary,
+        MigrationSummary {
+            collections: 1,
+            repositories: 2,
+            indexes: 3,
+            inserted: 5,
+            skipped: 0,
+            reassigned: 0,
+        }
+    );
+
+    let books = db.collection("books").unwrap();
+    assert_eq!(books.size().unwrap(), 2);
+    assert_eq!(
+        index_types(&books),
+        vec![
+            (
+                vec!["author".to_string(), "year".to_string()],
+                NON_UNIQUE_INDEX.to_string()
+            ),
+            (vec!["isbn".to_string()], UNIQUE_INDEX.to_string()),
+        ]
+    );
+
+    let dune = books.get_by_id(&id(1700000000000000001)).unwrap().unwrap();
+    assert_eq!(dune.get("title").unwrap(), Value::from("Dune"));
+    assert_eq!(dune.get("year").unwrap(), Value::I32(1965));
+    assert_eq!(dune.get("price").unwrap(), Value::F64(9.99));
+    assert_eq!(dune.get("available").unwrap(), Value::Bool(true));
+    assert_eq!(
+        dune.get("tags").unwrap(),
+        Value::Array(vec![Value::from("sf"), Value::from("classic")])
+    );
+    assert_eq!(dune.get("published").unwrap(), Value::I64(-157766400000));
+    assert_eq!(dune.get("publisher.city").unwrap(), Value::from("Philadelphia"));
+    assert_eq!(dune.get("cover").unwrap(), Value::Bytes(vec![1, 2, 3]));
+    assert_eq!(dune.get("notes").unwrap(), Value::Null);
+    assert_eq!(dune.revision().unwrap(), 1);
+
+    let emma = books
+        .find(field("isbn").eq("978-1"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(emma.get("price").unwrap(), Value::F32(4.5));
+    assert_eq!(emma.get("rating").unwrap(), Value::I64(4));
+    assert_eq!(emma.get("tags").unwrap(), Value::Array(vec![]));
+
+    let employees = db
+        .dynamic_repository(EntityDescriptor::new("com.example.Employee"))
+        .unwrap();
+    assert_eq!(employees.size().unwrap(), 2);
+    assert!(employees.has_index(vec!["empId"]).unwrap());
+    let retired = employees
+        .find(field("status").eq("RETIRED"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(retired.get("name").unwrap(), Value::from("Linus"));
+
+    let managers = db
+        .keyed_dynamic_repository(EntityDescriptor::new("com.example.Employee"), "managers")
+        .unwrap();
+    let grace = managers
+        .document_collection()
+        .get_by_id(&id(1700000000000000021))
+        .unwrap()
+        .unwrap();
+    assert_eq!(grace.get("empId").unwrap(), Value::I64(3));
+
+    db.close().unwrap();
+}
+
+#[test]
+fn test_import_is_idempotent_and_persistent() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(dir.path());
+    JavaImporter::new(&db).import_from_file(FIXTURE).unwrap();
+
+    let summary = JavaImporter::new(&db).import_from_file(FIXTURE).unwrap();
+    assert_eq!(summary.inserted, 0);
+    assert_eq!(summary.skipped, 5);
+    assert_eq!(summary.indexes, 0);
+    db.close().unwrap();
+
+    let db = open_db(dir.path());
+    let books = db.collection("books").unwrap();
+    assert_eq!(books.size().unwrap(), 2);
+    assert!(books.get_by_id(&id(1700000000000000002)).unwrap().is_some());
+    db.close().unwrap();
+}
+
+#[test]
+fn test_import_plain_json_with_invalid_ids() {
+    let export = r#"{
+        "collections": [{
+            "name": "legacy",
+            "indices": [{"index": {"type": "Unique", "fields": ["code"]}}],
+            "data": [
+                {"key": 7, "value": {"code": "a", "_id": 7, "_revision": 3}},
+                {"key": "1700000000000000031", "value": {"code": "b"}}
+            ]
+        }]
+    }"#;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(dir.path());
+
+    let err = JavaImporter::new(&db)
+        .import_from(export.as_bytes())
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::InvalidId);
+
+    let summary = JavaImporter::new(&db)
+        .reassign_invalid_ids(true)
+        .import_from(export.as_bytes())
+        .unwrap();
+    assert_eq!(summary.inserted, 2);
+    assert_eq!(summary.reassigned, 1);
+
+    let legacy = db.collection("legacy").unwrap();
+    let reassigned = legacy
+        .find(field("code").eq("a"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(reassigned.revision().unwrap(), 1);
+    assert!(legacy.get_by_id(&id(1700000000000000031)).unwrap().is_some());
+    db.close().unwrap();
+}
+
+#[test]
+fn test_import_rejects_malformed_export() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = open_db(dir.path());
+
+    let err = JavaImporter::new(&db)
+        .import_from("not json".as_bytes())
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::EncodingError);
+
+    let export = r#"{"collections": [{"name": "c", "data": [{"key": "rO0ABXQAAWE", "value": "rO0ABQ"}]}]}"#;
+    let err = JavaImporter::new(&db)
+        .import_from(export.as_bytes())
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::EncodingError);
+    db.close().unwrap();
+}
@@ -0,0 +1,132 @@
+use crate::error::OrRaise;
+use nitrite::collection::{Document, NitriteId};
+use nitrite::common::{Value, DOC_ID};
+use pyo3::exceptions::{PyOverflowError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use pyo3::IntoPyObjectExt;
+
+/// Converts a dict into a document. A top-level `_id` must be the id string returned by
+/// the bindings.
+pub(crate) fn dict_to_document(dict: &Bound<'_, PyDict>) -> PyResult<Document> {
+    let mut document = Document::new();
+    for (name, value) in dict.iter() {
+        let name: String = name
+            .extract()
+            .map_err(|_| PyTypeError::new_err("document keys must be strings"))?;
+        let value = if name == DOC_ID {
+            Value::NitriteId(parse_id(&value.extract::<String>()?)?)
+        } else {
+            py_to_value(&value)?
+        };
+        document.put(name, value).or_raise()?;
+    }
+    Ok(document)
+}
+
+/// Converts a document into a dict; ids are written as strings.
+pub(crate) fn document_to_dict<'py>(
+    py: Python<'py>,
+    document: &Document,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for (name, value) in document.iter() {
+        dict.set_item(name, value_to_py(py, &value)?)?;
+    }
+    Ok(dict)
+}
+
+pub(crate) fn parse_id(id: &str) -> PyResult<NitriteId> {
+    let id_value = id
+        .parse()
+        .map_err(|_| PyValueError::new_err(format!("'{}' is not a document id", id)))?;
+    NitriteId::create_id(id_value).or_raise()
+}
+
+pub(crate) fn format_id(id: &NitriteId) -> String {
+    id.id_value().to_string()
+}
+
+pub(crate) fn py_to_value(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    // bool is checked before int, as it is a subclass of it
+    if object.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(value) = object.downcast::<PyBool>() {
+        Ok(Value::Bool(value.is_true()))
+    } else if object.is_instance_of::<PyInt>() {
+        if let Ok(value) = object.extract::<i64>() {
+            Ok(Value::I64(value))
+        } else if let Ok(value) = object.extract::<u64>() {
+            Ok(Value::U64(value))
+        } else {
+            Err(PyOverflowError::new_err("integers are limited to 64 bits"))
+        }
+    } else if let Ok(value) = object.downcast::<PyFloat>() {
+        Ok(Value::F64(value.value()))
+    } else if let Ok(value) = object.downcast::<PyString>() {
+        Ok(Value::String(value.to_str()?.to_string()))
+    } else if let Ok(value) = object.downcast::<PyBytes>() {
+        Ok(Value::Bytes(value.as_bytes().to_vec()))
+    } else if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut document = Document::new();
+        for (name, value) in dict.iter() {
+            let name: String = name
+                .extract()
+                .map_err(|_| PyTypeError::new_err("document keys must be strings"))?;
+            document.put(name, py_to_value(&value)?).or_raise()?;
+        }
+        Ok(Value::Document(document))
+    } else if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        let values = object
+            .try_iter()?
+            .map(|item| py_to_value(&item?))
+            .collect::<PyResult<Vec<Value>>>()?;
+        Ok(Value::Array(values))
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "values of type {} cannot be stored",
+            object.get_type().name()?
+        )))
+    }
+}
+
+pub(crate) fn value_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    match value {
+        Value::Null | Value::Unknown => Ok(py.None()),
+        Value::Bool(value) => value.into_py_any(py),
+        Value::I8(value) => value.into_py_any(py),
+        Value::U8(value) => value.into_py_any(py),
+        Value::I16(value) => value.into_py_any(py),
+        Value::U16(value) => value.into_py_any(py),
+        Value::I32(value) => value.into_py_any(py),
+        Value::U32(value) => value.into_py_any(py),
+        Value::I64(value) => value.into_py_any(py),
+        Value::U64(value) => value.into_py_any(py),
+        Value::I128(value) => value.into_py_any(py),
+        Value::U128(value) => value.into_py_any(py),
+        Value::ISize(value) => value.into_py_any(py),
+        Value::USize(value) => value.into_py_any(py),
+        Value::F32(value) => value.into_py_any(py),
+        Value::F64(value) => value.into_py_any(py),
+        Value::Char(value) => value.into_py_any(py),
+        Value::String(value) => value.into_py_any(py),
+        Value::Document(document) => document_to_dict(py, document)?.into_py_any(py),
+        Value::Array(values) => {
+            let values = values
+                .iter()
+                .map(|value| value_to_py(py, value))
+                .collect::<PyResult<Vec<PyObject>>>()?;
+            PyList::new(py, values)?.into_py_any(py)
+        }
+        Value::Map(entries) => {
+            let dict = PyDict::new(py);
+            for (key, value) in entries.iter() {
+                dict.set_item(value_to_py(py, key)?, value_to_py(py, value)?)?;
+            }
+            dict.into_py_any(py)
+        }
+        Value::NitriteId(id) => format_id(id).into_py_any(py),
+        Value::Bytes(bytes) => PyBytes::new(py, bytes).into_py_any(py),
+        Value::RawJson(_) => value_to_py(py, &value.materialize().or_raise()?),
+    }
+}
@@ -0,0 +1,350 @@
+use crate::convert::{dict_to_document, document_to_dict, format_id, parse_id};
+use crate::error::OrRaise;
+use crate::filter::PyFilter;
+use arrow::pyarrow::ToPyArrow;
+use nitrite::collection::{FindOptions, NitriteCollection, NitriteId, UpdateOptions};
+use nitrite::columnar::{to_record_batch, SchemaMapping};
+use nitrite::common::SortOrder;
+use nitrite::filter::{all, Filter};
+use nitrite::index::IndexOptions;
+use nitrite::nitrite::Nitrite;
+use nitrite_fjall_adapter::FjallModule;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// An open database, usable as a context manager that closes it.
+#[pyclass(name = "Database", module = "nitrite", frozen)]
+pub struct PyDatabase {
+    db: Nitrite,
+}
+
+#[pymethods]
+impl PyDatabase {
+    /// Opens or creates a database.
+    ///
+    /// With a `path` the database is stored with the fjall adapter, otherwise it lives
+    /// in memory.
+    #[new]
+    #[pyo3(signature = (path=None, username=None, password=None))]
+    fn new(path: Option<&str>, username: Option<&str>, password: Option<&str>) -> PyResult<Self> {
+        let mut builder = Nitrite::builder();
+        if let Some(path) = path {
+            builder = builder.load_module(FjallModule::with_config().db_path(path).build());
+        }
+        let db = builder.open_or_create(username, password).or_raise()?;
+        Ok(PyDatabase { db })
+    }
+
+    /// Opens a collection, creating it if needed.
+    fn collection(&self, name: &str) -> PyResult<PyCollection> {
+        let collection = self.db.collection(name).or_raise()?;
+        Ok(PyCollection { collection })
+    }
+
+    /// Opens the documents of an existing repository; the entities are read as dicts.
+    #[pyo3(signature = (entity_name, key=None))]
+    fn repository(&self, entity_name: &str, key: Option<&str>) -> PyResult<PyCollection> {
+        let collection = self.db.repository_collection(entity_name, key).or_raise()?;
+        Ok(PyCollection { collection })
+    }
+
+    fn has_collection(&self, name: &str) -> PyResult<bool> {
+        self.db.has_collection(name).or_raise()
+    }
+
+    /// Returns the names of the collections, sorted.
+    fn collection_names(&self) -> PyResult<Vec<String>> {
+        let mut names: Vec<String> = self.db.list_collection_names().or_raise()?.into_iter().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the entity names of the repositories, sorted.
+    fn repository_names(&self) -> PyResult<Vec<String>> {
+        let mut names: Vec<String> = self.db.list_repositories().or_raise()?.into_iter().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the entity names of the keyed repositories, by key.
+    fn keyed_repository_names(&self) -> PyResult<BTreeMap<String, Vec<String>>> {
+        let repositories = self.db.list_keyed_repositories().or_raise()?;
+        Ok(repositories
+            .into_iter()
+            .map(|(key, names)| {
+                let mut names: Vec<String> = names.into_iter().collect();
+                names.sort();
+                (key, names)
+            })
+            .collect())
+    }
+
+    fn destroy_collection(&self, name: &str) -> PyResult<()> {
+        self.db.destroy_collection(name).or_raise()
+    }
+
+    fn commit(&self) -> PyResult<()> {
+        self.db.commit().or_raise()
+    }
+
+    fn close(&self) -> PyResult<()> {
+        self.db.close().or_raise()
+    }
+
+    #[getter]
+    fn closed(&self) -> PyResult<bool> {
+        self.db.is_closed().or_raise()
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<bool> {
+        if !self.db.is_closed().or_raise()? {
+            self.db.close().or_raise()?;
+        }
+        Ok(false)
+    }
+}
+
+/// A collection of documents, read and written as dicts.
+///
+/// Ids are returned as strings in the `_id` key.
+#[pyclass(name = "Collection", module = "nitrite", frozen)]
+pub struct PyCollection {
+    collection: NitriteCollection,
+}
+
+#[pymethods]
+impl PyCollection {
+    #[getter]
+    fn name(&self) -> String {
+        self.collection.name()
+    }
+
+    /// Inserts a document and returns its id.
+    fn insert(&self, document: &Bound<'_, PyDict>) -> PyResult<String> {
+        let result = self.collection.insert(dict_to_document(document)?).or_raise()?;
+        Ok(result
+            .affected_nitrite_ids()
+            .first()
+            .map(format_id)
+            .unwrap_or_default())
+    }
+
+    /// Inserts documents atomically and returns their ids, in order.
+    fn insert_many(&self, documents: Vec<Bound<'_, PyDict>>) -> PyResult<Vec<String>> {
+        let documents = documents
+            .iter()
+            .map(dict_to_document)
+            .collect::<PyResult<Vec<_>>>()?;
+        let result = self.collection.insert_many(documents).or_raise()?;
+        Ok(result.affected_nitrite_ids().iter().map(format_id).collect())
+    }
+
+    /// Sets the keys of `update` on the matching documents and returns their number.
+    #[pyo3(signature = (filter, update, insert_if_absent=false))]
+    fn update(
+        &self,
+        filter: Option<PyFilter>,
+        update: &Bound<'_, PyDict>,
+        insert_if_absent: bool,
+    ) -> PyResult<usize> {
+        let result = self
+            .collection
+            .update_with_options(
+                filter_or_all(filter),
+                &dict_to_document(update)?,
+                &UpdateOptions::new(insert_if_absent, false),
+            )
+            .or_raise()?;
+        Ok(result.affected_nitrite_ids().len())
+    }
+
+    /// Removes the matching documents and returns their number; `None` removes all.
+    #[pyo3(signature = (filter))]
+    fn remove(&self, filter: Option<PyFilter>) -> PyResult<usize> {
+        let result = self.collection.remove(filter_or_all(filter), false).or_raise()?;
+        Ok(result.affected_nitrite_ids().len())
+    }
+
+    fn get_by_id<'py>(&self, py: Python<'py>, id: &str) -> PyResult<Option<Bound<'py, PyDict>>> {
+        match self.collection.get_by_id(&parse_id(id)?).or_raise()? {
+            Some(document) => Ok(Some(document_to_dict(py, &document)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the matching documents, all of them without a filter.
+    #[pyo3(signature = (filter=None, *, sort_by=None, descending=false, skip=None, limit=None))]
+    fn find(
+        &self,
+        filter: Option<PyFilter>,
+        sort_by: Option<String>,
+        descending: bool,
+        skip: Option<u64>,
+        limit: Option<u64>,
+    ) -> PyResult<PyCursor> {
+        let mut options = FindOptions::new();
+        if let Some(sort_by) = sort_by {
+            let order = if descending {
+                SortOrder::Descending
+            } else {
+                SortOrder::Ascending
+            };
+            options = options.sort_by(sort_by, order);
+        }
+        if let Some(skip) = skip {
+            options = options.skip(skip);
+        }
+        if let Some(limit) = limit {
+            options = options.limit(limit);
+        }
+
+        // the ids are resolved up front, as the cursors of the database are bound to
+        // the thread that created them
+        let cursor = self
+            .collection
+            .find_with_options(filter_or_all(filter), &options)
+            .or_raise()?;
+        let mut ids = Vec::new();
+        for document in cursor {
+            ids.push(document.or_raise()?.id().or_raise()?);
+        }
+        Ok(PyCursor {
+            collection: self.collection.clone(),
+            ids,
+            position: 0,
+        })
+    }
+
+    /// Creates an index of type `unique`, `non-unique` or `full-text` on the fields.
+    #[pyo3(signature = (fields, index_type="non-unique"))]
+    fn create_index(&self, fields: Vec<String>, index_type: &str) -> PyResult<()> {
+        let fields = fields.iter().map(|field| field.as_str()).collect();
+        self.collection
+            .create_index(fields, &IndexOptions::new(index_type))
+            .or_raise()
+    }
+
+    /// Writes the matching documents to a zstd-compressed Parquet file and returns their
+    /// number.
+    ///
+    /// The columns are the `projection` fields, or `_id` and the top-level fields.
+    #[pyo3(signature = (path, filter=None, projection=None))]
+    fn export_parquet(
+        &self,
+        path: PathBuf,
+        filter: Option<PyFilter>,
+        projection: Option<Vec<String>>,
+    ) -> PyResult<u64> {
+        let projection: Option<Vec<&str>> = projection
+            .as_ref()
+            .map(|fields| fields.iter().map(|field| field.as_str()).collect());
+        self.collection
+            .export_parquet(path, filter_or_all(filter), projection.as_deref())
+            .or_raise()
+    }
+
+    fn size(&self) -> PyResult<u64> {
+        self.collection.size().or_raise()
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.collection.size().or_raise()? as usize)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Collection({})", self.collection.name())
+    }
+}
+
+/// The result of [`PyCollection::find`], iterated as dicts or converted at once.
+///
+/// The matching documents are selected when the cursor is created; a document removed
+/// before it is read is skipped.
+#[pyclass(name = "Cursor", module = "nitrite")]
+pub struct PyCursor {
+    collection: NitriteCollection,
+    ids: Vec<NitriteId>,
+    position: usize,
+}
+
+#[pymethods]
+impl PyCursor {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        while self.position < self.ids.len() {
+            let id = self.ids[self.position];
+            self.position += 1;
+            if let Some(document) = self.collection.get_by_id(&id).or_raise()? {
+                return Ok(Some(document_to_dict(py, &document)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the number of matching documents.
+    fn __len__(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns all the matching documents as a list of dicts, ready for
+    /// `pandas.DataFrame`. The position of the iteration is not changed.
+    fn to_list<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let list = PyList::empty(py);
+        for id in &self.ids {
+            if let Some(document) = self.collection.get_by_id(id).or_raise()? {
+                list.append(document_to_dict(py, &document)?)?;
+            }
+        }
+        Ok(list)
+    }
+
+    /// Returns all the matching documents as a `pyarrow.RecordBatch`, which needs pyarrow.
+    ///
+    /// The columns are the `fields`, which may be embedded fields, or `_id` and the
+    /// top-level fields; their types are inferred as described on
+    /// `nitrite::columnar::ColumnType`.
+    #[pyo3(signature = (fields=None))]
+    fn to_arrow(&self, py: Python<'_>, fields: Option<Vec<String>>) -> PyResult<PyObject> {
+        let mut documents = Vec::with_capacity(self.ids.len());
+        for id in &self.ids {
+            if let Some(document) = self.collection.get_by_id(id).or_raise()? {
+                documents.push(document);
+            }
+        }
+
+        let mapping = match &fields {
+            Some(fields) => {
+                let fields: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+                SchemaMapping::infer_fields(&documents, &fields)
+            }
+            None => SchemaMapping::infer(&documents),
+        }
+        .or_raise()?;
+        to_record_batch(&documents, &mapping)
+            .or_raise()?
+            .to_pyarrow(py)
+    }
+
+    /// Starts reading from the first document again.
+    fn rewind(&mut self) {
+        self.position = 0;
+    }
+}
+
+fn filter_or_all(filter: Option<PyFilter>) -> Filter {
+    filter.map(|filter| filter.filter).unwrap_or_else(all)
+}
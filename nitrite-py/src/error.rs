@@ -0,0 +1,24 @@
+use nitrite::errors::NitriteResult;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::PyResult;
+
+create_exception!(
+    nitrite,
+    NitriteError,
+    PyException,
+    "Raised when a database operation fails; the arguments are the message and the error kind."
+);
+
+/// Raises the errors of the database as [`NitriteError`].
+pub(crate) trait OrRaise<T> {
+    fn or_raise(self) -> PyResult<T>;
+}
+
+impl<T> OrRaise<T> for NitriteResult<T> {
+    fn or_raise(self) -> PyResult<T> {
+        self.map_err(|err| {
+            NitriteError::new_err((err.message().to_string(), err.kind().to_string()))
+        })
+    }
+}
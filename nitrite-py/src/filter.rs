@@ -0,0 +1,110 @@
+use crate::convert::{parse_id, py_to_value};
+use nitrite::common::Value;
+use nitrite::filter::{and, field as field_filter, not, or, Filter};
+use pyo3::prelude::*;
+use pyo3::pyclass::CompareOp;
+
+/// A condition on the documents, built with [`field`] and combined with `&`, `|` and `~`.
+#[pyclass(name = "Filter", module = "nitrite", frozen)]
+#[derive(Clone)]
+pub struct PyFilter {
+    pub(crate) filter: Filter,
+}
+
+#[pymethods]
+impl PyFilter {
+    fn __and__(&self, other: PyFilter) -> PyFilter {
+        PyFilter {
+            filter: and(vec![self.filter.clone(), other.filter]),
+        }
+    }
+
+    fn __or__(&self, other: PyFilter) -> PyFilter {
+        PyFilter {
+            filter: or(vec![self.filter.clone(), other.filter]),
+        }
+    }
+
+    fn __invert__(&self) -> PyFilter {
+        PyFilter {
+            filter: not(self.filter.clone()),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Filter({})", self.filter)
+    }
+}
+
+/// A field of the documents; comparing it with a value gives a [`PyFilter`].
+#[pyclass(name = "Field", module = "nitrite", frozen)]
+pub struct PyField {
+    name: String,
+}
+
+#[pymethods]
+impl PyField {
+    fn __richcmp__(&self, other: &Bound<'_, PyAny>, op: CompareOp) -> PyResult<PyFilter> {
+        let value = py_to_value(other)?;
+        let field = field_filter(&self.name);
+        let filter = match op {
+            CompareOp::Eq => field.eq(value),
+            CompareOp::Ne => field.ne(value),
+            CompareOp::Gt => field.gt(value),
+            CompareOp::Ge => field.gte(value),
+            CompareOp::Lt => field.lt(value),
+            CompareOp::Le => field.lte(value),
+        };
+        Ok(PyFilter { filter })
+    }
+
+    /// Matches the documents whose field equals one of the values.
+    fn in_(&self, values: Vec<Bound<'_, PyAny>>) -> PyResult<PyFilter> {
+        Ok(PyFilter {
+            filter: field_filter(&self.name).in_array(values_of(values)?),
+        })
+    }
+
+    /// Matches the documents whose field equals none of the values.
+    fn not_in(&self, values: Vec<Bound<'_, PyAny>>) -> PyResult<PyFilter> {
+        Ok(PyFilter {
+            filter: field_filter(&self.name).not_in_array(values_of(values)?),
+        })
+    }
+
+    /// Full-text search on a field with a full-text index.
+    fn text(&self, query: &str) -> PyFilter {
+        PyFilter {
+            filter: field_filter(&self.name).text(query),
+        }
+    }
+
+    /// Matches the documents whose field matches the regular expression.
+    fn regex(&self, pattern: &str) -> PyFilter {
+        PyFilter {
+            filter: field_filter(&self.name).text_regex(pattern),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Field({})", self.name)
+    }
+}
+
+/// Returns a field to build a filter on, e.g. `field("age") >= 18`.
+#[pyfunction]
+pub fn field(name: String) -> PyField {
+    PyField { name }
+}
+
+/// Returns a filter selecting the document with this id.
+#[pyfunction]
+pub fn by_id(id: &str) -> PyResult<PyFilter> {
+    Ok(PyFilter {
+        filter: nitrite::filter::by_id(parse_id(id)?),
+    })
+}
+
+fn values_of(values: Vec<Bound<'_, PyAny>>) -> PyResult<Vec<Value>> {
+    values.iter().map(py_to_value).collect()
+}
@@ -0,0 +1,56 @@
+//! # Nitrite Python — Python bindings for Nitrite
+//!
+//! This crate builds the `nitrite` Python module with [PyO3](https://pyo3.rs), so that
+//! Nitrite databases, including the files written by the fjall adapter, can be read
+//! and written from Python. The wheel is built with [maturin](https://www.maturin.rs):
+//!
+//! ```text
+//! cd nitrite-py && maturin develop --release
+//! ```
+//!
+//! Documents are dicts, the entities of repositories are read as dicts too, and
+//! filters are built from fields:
+//!
+//! ```python
+//! import pandas as pd
+//! from nitrite import Database, field
+//!
+//! with Database("/data/app.db") as db:
+//!     orders = db.collection("orders")
+//!     orders.insert({"customer": "Ada", "total": 42.5})
+//!
+//!     recent = orders.find((field("total") > 10) & ~(field("customer") == "Bob"),
+//!                          sort_by="total", descending=True)
+//!     frame = pd.DataFrame(recent.to_list())
+//!
+//!     users = db.repository("User").find().to_arrow()  # pyarrow.RecordBatch
+//! ```
+//!
+//! Errors of the database are raised as `nitrite.NitriteError`, with the message and
+//! the error kind as arguments.
+
+mod convert;
+mod database;
+mod error;
+mod filter;
+
+pub use database::{PyCollection, PyCursor, PyDatabase};
+pub use error::NitriteError;
+pub use filter::{PyField, PyFilter};
+
+use pyo3::prelude::*;
+
+/// The `nitrite` Python module.
+#[pymodule]
+#[pyo3(name = "nitrite")]
+pub fn nitrite_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDatabase>()?;
+    module.add_class::<PyCollection>()?;
+    module.add_class::<PyCursor>()?;
+    module.add_class::<PyFilter>()?;
+    module.add_class::<PyField>()?;
+    module.add_function(wrap_pyfunction!(filter::field, module)?)?;
+    module.add_function(wrap_pyfunction!(filter::by_id, module)?)?;
+    module.add("NitriteError", module.py().get_type::<NitriteError>())?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+use std::ffi::CStr;
+
+fn run_python(code: &CStr) {
+    run_python_with(code, &[])
+}
+
+fn run_python_with(code: &CStr, variables: &[(&str, &str)]) {
+    Python::with_gil(|py| {
+        let module = wrap_pymodule!(nitrite_py::nitrite_py)(py);
+        let modules = py.import("sys").unwrap().getattr("modules").unwrap();
+        modules.set_item("nitrite", module).unwrap();
+
+        let globals = PyDict::new(py);
+        for (name, value) in variables {
+            globals.set_item(name, value).unwrap();
+        }
+        if let Err(err) = py.run(code, Some(&globals), None) {
+            err.display(py);
+            panic!("python code failed: {}", err);
+        }
+    })
+}
+
+#[test]
+fn test_collection() {
+    run_python(c_str!(
+        r#"
+from nitrite import Database, NitriteError, by_id, field
+
+with Database() as db:
+    users = db.collection("users")
+    ada = users.insert({"name": "Ada", "age": 36, "tags": ["math"], "address": {"city": "London"}})
+    ids = users.insert_many([{"name": "Grace", "age": 45}, {"name": "Linus", "age": 17}])
+    assert len(ids) == 2
+    assert len(users) == 3
+
+    document = users.get_by_id(ada)
+    assert document["_id"] == ada
+    assert document["address"] == {"city": "London"}
+    assert document["tags"] == ["math"]
+
+    assert users.update(field("name") == "Ada", {"age": 37}) == 1
+    assert users.get_by_id(ada)["age"] == 37
+    assert users.remove(field("age") < 18) == 1
+
+    names = [user["name"] for user in users.find(field("age") >= 18, sort_by="age", descending=True)]
+    assert names == ["Grace", "Ada"], names
+    assert [user["name"] for user in users.find(by_id(ada))] == ["Ada"]
+    assert len(users.find((field("name") == "Ada") | ~(field("age") > 40))) == 1
+    assert len(users.find(field("name").in_(["Ada", "Grace"]), limit=1)) == 1
+
+    try:
+        users.insert({"name": object()})
+        raise AssertionError("unsupported values must be rejected")
+    except TypeError:
+        pass
+
+    users.create_index(["name"], "unique")
+    try:
+        users.insert({"name": "Ada"})
+        raise AssertionError("the unique index must be enforced")
+    except NitriteError as err:
+        assert err.args[1] == "Unique constraint violation", err.args
+
+assert db.closed
+"#
+    ));
+}
+
+#[test]
+fn test_cursor_conversion() {
+    run_python(c_str!(
+        r#"
+from nitrite import Database
+
+db = Database()
+orders = db.collection("orders")
+orders.insert_many([{"customer": "Ada", "total": 42.5}, {"customer": "Bob", "total": 7.0}])
+
+cursor = orders.find(sort_by="total")
+rows = cursor.to_list()
+assert [row["customer"] for row in rows] == ["Bob", "Ada"]
+assert [row["customer"] for row in cursor] == ["Bob", "Ada"]
+assert list(cursor) == []
+
+cursor.rewind()
+assert next(cursor)["customer"] == "Bob"
+assert len(cursor.to_list()) == 2
+
+orders.remove(None)
+assert cursor.to_list() == []
+db.close()
+"#
+    ));
+}
+
+#[test]
+fn test_export_parquet() {
+    let dir = std::env::temp_dir().join(format!("nitrite-py-parquet-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("orders.parquet");
+    run_python_with(
+        c_str!(
+            r#"
+from nitrite import Database, field
+
+with Database() as db:
+    orders = db.collection("orders")
+    orders.insert_many([{"customer": "Ada", "total": 42.5}, {"customer": "Bob", "total": 7.0}])
+    assert orders.export_parquet(path, field("total") > 10, ["customer", "total"]) == 1
+"#
+        ),
+        &[("path", path.to_str().unwrap())],
+    );
+    assert!(std::fs::metadata(&path).unwrap().len() > 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -13,6 +13,7 @@
 pub mod rtree_types;
 pub mod rtree_constants;
 pub mod rtree_cache;
+pub mod rtree_flusher;
 pub mod rtree_storage;
 pub mod persistence;
 mod rtree_impl;
@@ -22,7 +23,8 @@ pub use rtree_types::{
     InternalBBox, Node, LeafEntry, ChildRef, FileHeader, PageId, PageWithChecksum, FreePage,
 };
 pub use rtree_constants::DEFAULT_CACHE_PAGES;
-pub use rtree_impl::DiskRTree;
+pub use rtree_flusher::BackgroundFlushOptions;
+pub use rtree_impl::{DiskRTree, NearestEntries};
 pub use persistence::{
     IntegrityReport, RepairOptions, RepairReport, FreeListManager, MigrationManager,
     VersionMigration, V1ToV2Migration, V2ToV3Migration,
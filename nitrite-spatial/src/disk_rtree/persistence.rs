@@ -5,6 +5,8 @@
 //! - Free list management for page reuse
 //! - File format migration support
 
+use std::collections::HashSet;
+
 use super::rtree_storage::Storage;
 use super::rtree_types::{FileHeader, FreePage, PageId, SpatialError, SpatialResult};
 
@@ -146,6 +148,28 @@ impl FreeListManager {
         Ok(())
     }
 
+    /// Walk the free list chain and collect its pages
+    ///
+    /// Stops after `free_page_count` links, so a damaged chain cannot loop.
+    pub fn collect_free_pages(storage: &Storage, header: &FileHeader) -> SpatialResult<HashSet<PageId>> {
+        let mut pages = HashSet::new();
+        let mut page_id = header.free_list_head;
+
+        while page_id != 0 && (pages.len() as u64) < header.free_page_count {
+            if !pages.insert(page_id) {
+                break;
+            }
+            let free_page_bytes = storage.read_free_page(page_id)?;
+            let free_page: FreePage =
+                bincode::serde::decode_from_slice(&free_page_bytes, bincode::config::legacy())
+                    .map(|(page, _)| page)
+                    .map_err(|e| SpatialError::Serialization(e.to_string()))?;
+            page_id = free_page.next_free;
+        }
+
+        Ok(pages)
+    }
+
     /// Get the number of free pages available
     pub fn free_page_count(header: &FileHeader) -> u64 {
         header.free_page_count
@@ -475,6 +499,29 @@ mod tests {
         assert_eq!(page_id, 1);
     }
 
+    #[test]
+    fn test_free_list_manager_chain_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::create(&dir.path().join("test.rtree")).unwrap();
+        let mut header = FileHeader::new();
+        header.next_page_id = 4;
+
+        FreeListManager::free_page(&storage, &mut header, 1).unwrap();
+        FreeListManager::free_page(&storage, &mut header, 3).unwrap();
+        assert_eq!(header.free_page_count, 2);
+        assert_eq!(
+            FreeListManager::collect_free_pages(&storage, &header).unwrap(),
+            HashSet::from([1, 3])
+        );
+
+        // last freed, first reused; then the chain continues
+        assert_eq!(FreeListManager::allocate_page(&storage, &mut header).unwrap(), 3);
+        assert_eq!(FreeListManager::allocate_page(&storage, &mut header).unwrap(), 1);
+        assert_eq!(FreeListManager::allocate_page(&storage, &mut header).unwrap(), 4);
+        assert_eq!(header.free_page_count, 0);
+        assert_eq!(header.free_list_head, 0);
+    }
+
     #[test]
     fn test_free_list_manager_free_page_count_zero() {
         let header = FileHeader::new();
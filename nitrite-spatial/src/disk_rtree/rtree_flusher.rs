@@ -0,0 +1,161 @@
+//! Background write-back of evicted R-Tree pages.
+//!
+//! Without a flusher, a dirty page evicted from the cache is written to disk
+//! inside the insert that evicted it. With one, evicted pages go to a
+//! write-back queue instead and a background thread writes them in batches.
+//! A page evicted again before it was written replaces its queued version, so
+//! each page is written once per batch no matter how often it was evicted.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use parking_lot::{Condvar, Mutex};
+
+use super::rtree_storage::Storage;
+use super::rtree_types::{Node, PageId, SpatialResult};
+
+/// Options for the background flusher of a [`DiskRTree`](super::DiskRTree)
+#[derive(Debug, Clone)]
+pub struct BackgroundFlushOptions {
+    /// How long the flusher waits for a full batch before writing what is queued
+    pub interval: Duration,
+    /// Maximum pages written per batch; a full batch wakes the flusher early
+    pub batch_size: usize,
+    /// Queued pages at which the evicting insert writes the queue itself
+    pub max_dirty_pages: usize,
+}
+
+impl Default for BackgroundFlushOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(100),
+            batch_size: 64,
+            max_dirty_pages: 1024,
+        }
+    }
+}
+
+/// Evicted dirty pages waiting to be written, at most one version per page
+pub(crate) struct WriteBackQueue {
+    pages: Mutex<HashMap<PageId, Node>>,
+    wakeup: Condvar,
+    options: BackgroundFlushOptions,
+}
+
+impl WriteBackQueue {
+    pub(crate) fn new(options: BackgroundFlushOptions) -> Self {
+        Self {
+            pages: Mutex::new(HashMap::new()),
+            wakeup: Condvar::new(),
+            options,
+        }
+    }
+
+    pub(crate) fn options(&self) -> &BackgroundFlushOptions {
+        &self.options
+    }
+
+    /// Queue an evicted page, replacing a queued older version of it.
+    ///
+    /// Returns true if the queue reached `max_dirty_pages`, in which case the
+    /// caller must write the queue synchronously.
+    pub(crate) fn push(&self, page_id: PageId, node: Node) -> bool {
+        let mut pages = self.pages.lock();
+        pages.insert(page_id, node);
+        if pages.len() >= self.options.batch_size.max(1) {
+            self.wakeup.notify_one();
+        }
+        pages.len() >= self.options.max_dirty_pages
+    }
+
+    /// The queued version of a page, newer than the one on disk
+    pub(crate) fn get(&self, page_id: PageId) -> Option<Node> {
+        self.pages.lock().get(&page_id).cloned()
+    }
+
+    /// Drop a queued page without writing it, e.g. because it was freed
+    pub(crate) fn discard(&self, page_id: PageId) {
+        self.pages.lock().remove(&page_id);
+    }
+
+    /// Drop all queued pages without writing them
+    pub(crate) fn clear(&self) {
+        self.pages.lock().clear();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pages.lock().len()
+    }
+
+    /// Block until a batch is full, the interval elapsed or `wake` was called
+    pub(crate) fn wait(&self) {
+        let mut pages = self.pages.lock();
+        if pages.len() < self.options.batch_size.max(1) {
+            self.wakeup.wait_for(&mut pages, self.options.interval);
+        }
+    }
+
+    /// Wake the flusher, e.g. to let it see it was stopped
+    pub(crate) fn wake(&self) {
+        self.wakeup.notify_all();
+    }
+
+    /// Write up to `limit` queued pages in page order.
+    ///
+    /// A page leaves the queue only once it is on disk, and is written under the
+    /// queue lock, so readers never miss its latest version and a discarded page
+    /// is never written after it was discarded.
+    ///
+    /// Returns the number of pages written.
+    pub(crate) fn write_batch(&self, storage: &Storage, limit: usize) -> SpatialResult<usize> {
+        let mut page_ids: Vec<PageId> = self.pages.lock().keys().copied().collect();
+        page_ids.sort_unstable();
+        page_ids.truncate(limit);
+
+        let mut written = 0;
+        for page_id in page_ids {
+            let mut pages = self.pages.lock();
+            if let Some(node) = pages.get(&page_id) {
+                storage.write_page(page_id, node)?;
+                pages.remove(&page_id);
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_back_queue_coalesces_and_writes() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::create(&dir.path().join("test.rtree")).unwrap();
+        let queue = WriteBackQueue::new(BackgroundFlushOptions {
+            max_dirty_pages: 3,
+            ..Default::default()
+        });
+
+        assert!(!queue.push(2, Node::Leaf { entries: vec![] }));
+        assert!(!queue.push(2, Node::Internal { children: vec![], level: 1 }));
+        assert!(!queue.push(1, Node::Leaf { entries: vec![] }));
+        assert_eq!(queue.len(), 2);
+        assert!(matches!(queue.get(2), Some(Node::Internal { .. })));
+
+        assert_eq!(queue.write_batch(&storage, 1).unwrap(), 1);
+        assert_eq!(queue.len(), 1);
+        assert!(queue.get(1).is_none());
+        assert!(storage.read_page(1).is_ok());
+
+        queue.discard(2);
+        assert_eq!(queue.write_batch(&storage, usize::MAX).unwrap(), 0);
+
+        // the third queued page reaches max_dirty_pages
+        assert!(!queue.push(3, Node::Leaf { entries: vec![] }));
+        assert!(!queue.push(4, Node::Leaf { entries: vec![] }));
+        assert!(queue.push(5, Node::Leaf { entries: vec![] }));
+    }
+}
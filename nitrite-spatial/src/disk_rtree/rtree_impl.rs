@@ -1,22 +1,27 @@
 //! DiskRTree implementation.
 
 use parking_lot::RwLock;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
 use crate::bounding_box::BoundingBox;
-use crate::nitrite_rtree::NitriteRTree;
+use crate::nitrite_rtree::{NearestIter, NitriteRTree};
 
 use super::rtree_types::{
     SpatialError, SpatialResult, NitriteIdValue, RTreeStats, RebuildStats, FragmentationMetrics,
     InternalBBox, Node, LeafEntry, ChildRef, FileHeader, PageId,
 };
 use super::rtree_cache::PageCache;
+use super::rtree_flusher::{BackgroundFlushOptions, WriteBackQueue};
 use super::rtree_storage::Storage;
 use super::rtree_constants::{DEFAULT_CACHE_PAGES, MAX_LEAF_ENTRIES, MAX_INTERNAL_CHILDREN};
-use super::persistence::{IntegrityReport, RepairOptions, RepairReport, MigrationManager};
+use super::persistence::{FreeListManager, IntegrityReport, RepairOptions, RepairReport, MigrationManager};
 
 pub struct DiskRTree {
-    inner: std::sync::Arc<DiskRTreeInner>,
+    inner: Arc<DiskRTreeInner>,
 }
 
 struct DiskRTreeInner {
@@ -27,8 +32,10 @@ struct DiskRTreeInner {
     stats: RTreeStatistics,
     /// Is the tree closed?
     closed: RwLock<bool>,
-    /// Free page list for memory-based reuse
-    free_pages: RwLock<Vec<PageId>>,
+    /// Write-back queue of evicted dirty pages, if the background flusher runs
+    write_back: RwLock<Option<Arc<WriteBackQueue>>>,
+    /// Stop flag and thread of the background flusher
+    flusher: parking_lot::Mutex<Option<(Arc<AtomicBool>, JoinHandle<()>)>>,
 }
 
 /// Internal statistics tracking
@@ -69,13 +76,14 @@ impl DiskRTree {
         storage.sync()?;
 
         Ok(Self {
-            inner: std::sync::Arc::new(DiskRTreeInner {
+            inner: Arc::new(DiskRTreeInner {
                 storage,
                 cache: RwLock::new(PageCache::new(cache_pages)),
                 header: RwLock::new(header),
                 stats: RTreeStatistics::new(),
                 closed: RwLock::new(false),
-                free_pages: RwLock::new(Vec::new()),
+                write_back: RwLock::new(None),
+                flusher: parking_lot::Mutex::new(None),
             }),
         })
     }
@@ -96,17 +104,24 @@ impl DiskRTree {
     ) -> SpatialResult<Self> {
         let storage = Storage::open(path.as_ref())?;
         // Only read header - no tree data loaded yet!
-        let header = storage.read_header()?;
+        let mut header = storage.read_header()?;
         header.validate()?;
 
+        // Older files kept only the first freed page in the header, which may
+        // have been reused since; such a head is not a free list to follow.
+        if header.free_page_count == 0 {
+            header.free_list_head = 0;
+        }
+
         Ok(Self {
-            inner: std::sync::Arc::new(DiskRTreeInner {
+            inner: Arc::new(DiskRTreeInner {
                 storage,
                 cache: RwLock::new(PageCache::new(cache_pages)),
                 header: RwLock::new(header),
                 stats: RTreeStatistics::new(),
                 closed: RwLock::new(false),
-                free_pages: RwLock::new(Vec::new()),
+                write_back: RwLock::new(None),
+                flusher: parking_lot::Mutex::new(None),
             }),
         })
     }
@@ -210,6 +225,7 @@ impl DiskRTree {
             cache_misses: self.inner.stats.cache_misses.load(Ordering::Relaxed),
             disk_reads: self.inner.stats.disk_reads.load(Ordering::Relaxed),
             disk_writes: self.inner.stats.disk_writes.load(Ordering::Relaxed),
+            pending_writes: self.write_back().map_or(0, |queue| queue.len() as u64),
             tree_height: header.height,
         }
     }
@@ -352,8 +368,156 @@ impl DiskRTree {
         }
     }
 
+    /// Shrink the file by rewriting the live pages contiguously
+    ///
+    /// Pages emptied by deletes go on the free list, where later inserts reuse
+    /// them, but the file itself never shrinks. Compaction moves the live pages
+    /// from the end of the file into the free slots before them, drops the free
+    /// list and truncates the file after the last live page. Pages are moved
+    /// one by one, so the memory needed is a page id per live page.
+    ///
+    /// # Returns
+    /// The number of pages the file shrank by
+    pub fn compact(&self) -> SpatialResult<u64> {
+        self.check_closed()?;
+        self.flush()?;
+
+        // Everything is on disk now; hold both locks so no operation sees the
+        // tree while pages move, and work on the storage directly.
+        let mut header = self.inner.header.write();
+        let mut cache = self.inner.cache.write();
+        let storage = &self.inner.storage;
+
+        // Collect the live pages, parents before children
+        let mut live = Vec::new();
+        if header.root_page != 0 {
+            let mut stack = vec![header.root_page];
+            while let Some(page_id) = stack.pop() {
+                live.push(page_id);
+                if let Node::Internal { children, .. } = storage.read_page(page_id)? {
+                    stack.extend(children.iter().map(|child| child.page_id));
+                }
+            }
+        }
+        let live_count = live.len() as u64;
+
+        // Each live page beyond the compacted length moves into a slot within
+        // it that holds no live page; there are exactly as many of both.
+        let live_set: HashSet<PageId> = live.iter().copied().collect();
+        let mut holes = (1..=live_count).filter(|page_id| !live_set.contains(page_id));
+        let moves: HashMap<PageId, PageId> = live
+            .iter()
+            .filter(|&&page_id| page_id > live_count)
+            .filter_map(|&page_id| holes.next().map(|hole| (page_id, hole)))
+            .collect();
+
+        // Holes never hold live pages and moved pages leave their old slots
+        // untouched, so no page is overwritten before it is read.
+        for &page_id in &live {
+            let mut node = storage.read_page(page_id)?;
+            let mut relinked = false;
+            if let Node::Internal { children, .. } = &mut node {
+                for child in children.iter_mut() {
+                    if let Some(&new_id) = moves.get(&child.page_id) {
+                        child.page_id = new_id;
+                        relinked = true;
+                    }
+                }
+            }
+
+            let target = moves.get(&page_id).copied().unwrap_or(page_id);
+            if target != page_id || relinked {
+                storage.write_page(target, &node)?;
+                self.inner.stats.disk_writes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let old_page_count = header.next_page_id;
+        if let Some(&new_root) = moves.get(&header.root_page) {
+            header.root_page = new_root;
+        }
+        header.next_page_id = live_count + 1;
+        header.free_list_head = 0;
+        header.free_page_count = 0;
+
+        // cached pages may sit under their old ids
+        cache.clear();
+
+        storage.write_header(&header)?;
+        storage.truncate(header.next_page_id)?;
+        storage.sync()?;
+
+        Ok(old_page_count.saturating_sub(header.next_page_id))
+    }
+
+    /// Start a background thread that writes evicted dirty pages
+    ///
+    /// Without it, a dirty page evicted from the cache is written inside the
+    /// insert that evicted it. Once started, evicted pages are queued instead
+    /// and the thread writes them in batches of `batch_size` pages in page
+    /// order, at least every `interval`. A page evicted again while queued is
+    /// written once. When `max_dirty_pages` pages are queued, the evicting
+    /// insert writes the whole queue itself, which bounds the queue's memory.
+    /// [`flush`](Self::flush) and closing the tree write the queue as well.
+    ///
+    /// # Errors
+    /// Returns `InvalidOperation` if the flusher already runs.
+    pub fn start_background_flush(&self, options: BackgroundFlushOptions) -> SpatialResult<()> {
+        self.check_closed()?;
+
+        let mut flusher = self.inner.flusher.lock();
+        if flusher.is_some() {
+            return Err(SpatialError::InvalidOperation(
+                "Background flush is already running".into(),
+            ));
+        }
+
+        let queue = Arc::new(WriteBackQueue::new(options));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::Builder::new()
+            .name("rtree-flusher".into())
+            .spawn({
+                let tree = Arc::downgrade(&self.inner);
+                let queue = queue.clone();
+                let stop = stop.clone();
+                move || run_flusher(tree, queue, stop)
+            })?;
+
+        *self.inner.write_back.write() = Some(queue);
+        *flusher = Some((stop, handle));
+        Ok(())
+    }
+
+    /// Stop the background flusher, if it runs; writing the queue is up to the caller
+    fn stop_background_flush(&self) {
+        let flusher = self.inner.flusher.lock().take();
+        if let Some((stop, handle)) = flusher {
+            stop.store(true, Ordering::Release);
+            if let Some(queue) = self.write_back() {
+                queue.wake();
+            }
+            let _ = handle.join();
+        }
+    }
+
+    fn write_back(&self) -> Option<Arc<WriteBackQueue>> {
+        self.inner.write_back.read().clone()
+    }
+
+    /// Write the queued pages of the background flusher, if it runs
+    fn write_queued_pages(&self) -> SpatialResult<()> {
+        if let Some(queue) = self.write_back() {
+            let written = queue.write_batch(&self.inner.storage, usize::MAX)?;
+            self.inner.stats.disk_writes.fetch_add(written as u64, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
     /// Flush all dirty pages to disk
     pub fn flush(&self) -> SpatialResult<()> {
+        // queued pages first: a dirty cached copy of the same page is newer
+        self.write_queued_pages()?;
+
         let dirty_pages = self.inner.cache.read().get_dirty_pages();
 
         for page_id in dirty_pages {
@@ -408,11 +572,17 @@ impl DiskRTree {
             }
         }
 
-        // Scan all allocated pages for corruption
+        // Scan all allocated pages for corruption; pages on the free list hold
+        // a free list link instead of a node
+        let free_pages = FreeListManager::collect_free_pages(&self.inner.storage, &header)?;
         let mut current_page_id = 1;
         let next_page_id = header.next_page_id;
 
         while current_page_id < next_page_id {
+            if free_pages.contains(&current_page_id) {
+                current_page_id += 1;
+                continue;
+            }
             match self.inner.storage.read_page(current_page_id) {
                 Ok(_node) => {
                     report.pages_checked += 1;
@@ -503,57 +673,26 @@ impl DiskRTree {
 
     /// Allocate a new page ID
     ///
-    /// Implements page reuse through a free list. This method tracks freed pages
-    /// in memory and reuses them when available, reducing file fragmentation.
-    ///
-    /// Algorithm:
-    /// 1. Check in-memory free list first
-    /// 2. If empty, check header's free_list_head for persisted free pages
-    /// 3. If available, pop from free list and return
-    /// 4. Otherwise, allocate a new page ID from next_page_id
-    fn allocate_page(&self) -> PageId {
-        // Try in-memory free list first
-        {
-            let mut free_pages = self.inner.free_pages.write();
-            if let Some(page_id) = free_pages.pop() {
-                return page_id;
-            }
-        }
-
-        // Check header's persisted free list
+    /// Reuses the head of the on-disk free list if there is one, so pages freed
+    /// by deletes are reused, also after a reopen. Otherwise the file grows by a
+    /// page.
+    fn allocate_page(&self) -> SpatialResult<PageId> {
         let mut header = self.inner.header.write();
-        if header.free_list_head != 0 {
-            let page_id = header.free_list_head;
-            // For simplicity, clear the persisted free list
-            // In a production system, we'd chain the free list
-            header.free_list_head = 0;
-            return page_id;
-        }
-
-        // Allocate a new page
-        let page_id = header.next_page_id;
-        header.next_page_id += 1;
-        page_id
+        FreeListManager::allocate_page(&self.inner.storage, &mut header)
     }
 
-    /// Free a page (add to in-memory free list for reuse)
+    /// Free a page, pushing it on the on-disk free list for reuse
     ///
-    /// When a page is freed, it's added to the in-memory free list for reuse.
-    /// This reduces fragmentation by allowing reuse of deleted page slots.
-    /// The freed pages are tracked in memory for fast allocation.
-    ///
-    /// Algorithm:
-    /// 1. Add the page ID to the in-memory free list
-    /// 2. Optionally persist to header if list grows large
-    fn free_page(&self, page_id: PageId) {
-        let mut free_pages = self.inner.free_pages.write();
-        free_pages.push(page_id);
-        
-        // Persist first freed page to header for recovery
-        if free_pages.len() == 1 && page_id > 0 {
-            let mut header = self.inner.header.write();
-            header.free_list_head = page_id;
-        }
+    /// The page is dropped from the cache and the write-back queue first, so a
+    /// dirty copy of its old node can never be written over the free list link.
+    fn free_page(&self, header: &mut FileHeader, page_id: PageId) -> SpatialResult<()> {
+        self.inner.cache.write().remove(page_id);
+        if let Some(queue) = self.write_back() {
+            queue.discard(page_id);
+        }
+        FreeListManager::free_page(&self.inner.storage, header, page_id)?;
+        self.inner.stats.disk_writes.fetch_add(1, Ordering::Relaxed);
+        Ok(())
     }
 
     /// Read a node - first checks cache, then loads from disk.
@@ -570,6 +709,13 @@ impl DiskRTree {
 
         // Cache miss - must load from disk
         self.inner.stats.cache_misses.fetch_add(1, Ordering::Relaxed);
+
+        // unless the page was evicted and still waits to be written
+        if let Some(node) = self.write_back().and_then(|queue| queue.get(page_id)) {
+            self.cache_node(page_id, node.clone(), false)?;
+            return Ok(node);
+        }
+
         self.inner.stats.disk_reads.fetch_add(1, Ordering::Relaxed);
 
         // Read SINGLE page from disk
@@ -587,17 +733,30 @@ impl DiskRTree {
     }
 
     /// Add a node to cache, handling eviction if necessary.
-    /// Evicted dirty pages are written to disk.
+    /// Evicted dirty pages are written to disk, or queued for the background flusher.
     fn cache_node(&self, page_id: PageId, node: Node, dirty: bool) -> SpatialResult<()> {
+        let write_back = self.write_back();
         let mut cache = self.inner.cache.write();
 
         // Evict old pages if cache is full
         while cache.needs_eviction() {
             if let Some((evict_id, evict_node, evict_dirty)) = cache.evict_oldest() {
-                if evict_dirty {
-                    // Write evicted dirty page to disk
-                    self.inner.storage.write_page(evict_id, &evict_node)?;
-                    self.inner.stats.disk_writes.fetch_add(1, Ordering::Relaxed);
+                if !evict_dirty {
+                    continue;
+                }
+                match &write_back {
+                    Some(queue) => {
+                        // the flusher falls behind; write the queue here instead of growing it
+                        if queue.push(evict_id, evict_node) {
+                            let written = queue.write_batch(&self.inner.storage, usize::MAX)?;
+                            self.inner.stats.disk_writes.fetch_add(written as u64, Ordering::Relaxed);
+                        }
+                    }
+                    None => {
+                        // Write evicted dirty page to disk
+                        self.inner.storage.write_page(evict_id, &evict_node)?;
+                        self.inner.stats.disk_writes.fetch_add(1, Ordering::Relaxed);
+                    }
                 }
             } else {
                 break;
@@ -662,7 +821,7 @@ impl DiskRTree {
                 let (remaining, new_entries) = self.split_leaf(entries);
                 *entries = remaining;
 
-                let new_page_id = self.allocate_page();
+                let new_page_id = self.allocate_page()?;
                 let new_bbox = compute_entries_bbox(&new_entries);
                 let new_node = Node::Leaf {
                     entries: new_entries,
@@ -728,7 +887,7 @@ impl DiskRTree {
                     let (remaining, new_children) = self.split_internal(children);
                     *children = remaining;
 
-                    new_page = self.allocate_page();
+                    new_page = self.allocate_page()?;
                     new_bbox = compute_children_bbox(&new_children);
                     let new_node = Node::Internal {
                         children: new_children,
@@ -748,7 +907,7 @@ impl DiskRTree {
         let old_root = self.inner.header.read().root_page;
         let old_root_bbox = self.read_node(old_root)?.compute_bbox();
 
-        let new_root_id = self.allocate_page();
+        let new_root_id = self.allocate_page()?;
         let new_root = Node::Internal {
             children: vec![
                 ChildRef {
@@ -897,7 +1056,7 @@ impl DiskRTree {
 
                             // Check for underflow in child
                             if child_node.is_underfull() && child_node.is_empty() {
-                                self.free_page(children[i].page_id);
+                                self.free_page(&mut self.inner.header.write(), children[i].page_id)?;
                                 children.remove(i);
                             } else {
                                 children[i].bbox = new_bbox;
@@ -965,6 +1124,38 @@ impl DiskRTree {
         Ok(results)
     }
 
+    /// Iterates all entries in increasing distance from a point.
+    ///
+    /// Unlike [`find_nearest`](Self::find_nearest), which needs `k` up front, this is an
+    /// incremental best-first traversal: pages are read only as far as the caller
+    /// consumes the iterator.
+    ///
+    /// # Arguments
+    /// * `center_x` - X coordinate of the query point
+    /// * `center_y` - Y coordinate of the query point
+    ///
+    /// # Returns
+    /// An iterator of (NitriteId, distance) pairs, nearest first
+    pub fn nearest_entries(&self, center_x: f64, center_y: f64) -> SpatialResult<NearestEntries<'_>> {
+        self.check_closed()?;
+
+        let mut queue = BinaryHeap::new();
+        let root_page = self.inner.header.read().root_page;
+        if root_page != 0 {
+            queue.push(NearestCandidate {
+                distance: 0.0,
+                item: NearestItem::Page(root_page),
+            });
+        }
+
+        Ok(NearestEntries {
+            tree: self,
+            center_x,
+            center_y,
+            queue,
+        })
+    }
+
     /// Recursive helper for KNN search
     fn find_nearest_recursive(
         &self,
@@ -1055,6 +1246,99 @@ impl DiskRTree {
     }
 }
 
+// ============================================================================
+// Incremental Nearest-Neighbor Traversal
+// ============================================================================
+
+/// Iterator over the entries of a [`DiskRTree`] in increasing distance from a point.
+///
+/// Created by [`DiskRTree::nearest_entries`].
+pub struct NearestEntries<'a> {
+    tree: &'a DiskRTree,
+    center_x: f64,
+    center_y: f64,
+    queue: BinaryHeap<NearestCandidate>,
+}
+
+enum NearestItem {
+    Page(PageId),
+    Entry(NitriteIdValue),
+}
+
+/// A queued page or entry with its minimum distance to the query point.
+struct NearestCandidate {
+    distance: f64,
+    item: NearestItem,
+}
+
+impl PartialEq for NearestCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for NearestCandidate {}
+
+impl PartialOrd for NearestCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NearestCandidate {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        // Reversed, so the max-heap pops the nearest candidate first
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+impl Iterator for NearestEntries<'_> {
+    type Item = SpatialResult<(NitriteIdValue, f64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A page is never nearer than its bounding box, so an entry popped from the
+        // queue is nearer than everything not yet returned.
+        while let Some(candidate) = self.queue.pop() {
+            let page_id = match candidate.item {
+                NearestItem::Entry(id) => return Some(Ok((id, candidate.distance))),
+                NearestItem::Page(page_id) => page_id,
+            };
+
+            let node = match self.tree.read_node(page_id) {
+                Ok(node) => node,
+                Err(e) => {
+                    self.queue.clear();
+                    return Some(Err(e));
+                }
+            };
+
+            match node {
+                Node::Leaf { entries } => {
+                    for entry in entries {
+                        let distance =
+                            self.tree.point_to_bbox_distance(self.center_x, self.center_y, &entry.bbox);
+                        self.queue.push(NearestCandidate {
+                            distance,
+                            item: NearestItem::Entry(entry.id),
+                        });
+                    }
+                }
+                Node::Internal { children, .. } => {
+                    for child in children {
+                        let distance =
+                            self.tree.point_to_bbox_distance(self.center_x, self.center_y, &child.bbox);
+                        self.queue.push(NearestCandidate {
+                            distance,
+                            item: NearestItem::Page(child.page_id),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
 // ============================================================================
 // NitriteRTree Trait Implementation
 // ============================================================================
@@ -1070,7 +1354,7 @@ impl NitriteRTree for DiskRTree {
 
         if root_page == 0 {
             // Empty tree - create root leaf
-            let page_id = self.allocate_page();
+            let page_id = self.allocate_page()?;
             let node = Node::Leaf {
                 entries: vec![entry],
             };
@@ -1129,14 +1413,14 @@ impl NitriteRTree for DiskRTree {
                     let old_root = header.root_page;
                     header.root_page = children[0].page_id;
                     header.height = header.height.saturating_sub(1);
-                    self.free_page(old_root);
+                    self.free_page(&mut header, old_root)?;
                 }
             } else if let Node::Leaf { entries } = &root_node {
                 if entries.is_empty() {
                     let old_root = header.root_page;
                     header.root_page = 0;
                     header.height = 0;
-                    self.free_page(old_root);
+                    self.free_page(&mut header, old_root)?;
                 }
             }
 
@@ -1180,6 +1464,14 @@ impl NitriteRTree for DiskRTree {
         self.inner.header.read().entry_count
     }
 
+    fn flush(&self) -> SpatialResult<()> {
+        // a closed tree has already been flushed
+        if *self.inner.closed.read() {
+            return Ok(());
+        }
+        DiskRTree::flush(self)
+    }
+
     fn close(&self) -> SpatialResult<()> {
         let mut closed = self.inner.closed.write();
         if *closed {
@@ -1187,6 +1479,7 @@ impl NitriteRTree for DiskRTree {
         }
         
         self.flush()?;
+        self.stop_background_flush();
         *closed = true;
         Ok(())
     }
@@ -1194,7 +1487,10 @@ impl NitriteRTree for DiskRTree {
     fn clear(&self) -> SpatialResult<()> {
         self.check_closed()?;
         
-        // Clear cache
+        // Clear cache and queued pages
+        if let Some(queue) = self.write_back() {
+            queue.clear();
+        }
         let dirty_pages = self.inner.cache.write().clear();
         
         // Write any dirty pages first (optional - we're clearing anyway)
@@ -1215,7 +1511,11 @@ impl NitriteRTree for DiskRTree {
     fn drop_tree(&self) -> SpatialResult<()> {
         let mut closed = self.inner.closed.write();
         
-        // Clear cache without writing
+        // Clear cache and queued pages without writing
+        self.stop_background_flush();
+        if let Some(queue) = self.write_back() {
+            queue.clear();
+        }
         self.inner.cache.write().clear();
         
         // Delete backing file content
@@ -1235,12 +1535,40 @@ impl NitriteRTree for DiskRTree {
         // Delegate to the extended impl method
         Self::find_nearest(self, center_x, center_y, k, max_distance)
     }
+
+    fn iter_nearest(
+        &self,
+        center_x: f64,
+        center_y: f64,
+    ) -> SpatialResult<NearestIter<'_>> {
+        Ok(Box::new(self.nearest_entries(center_x, center_y)?))
+    }
 }
 
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
+/// Body of the background flusher thread; ends when stopped or the tree is gone
+fn run_flusher(tree: Weak<DiskRTreeInner>, queue: Arc<WriteBackQueue>, stop: Arc<AtomicBool>) {
+    let batch_size = queue.options().batch_size.max(1);
+    while !stop.load(Ordering::Acquire) {
+        queue.wait();
+        if stop.load(Ordering::Acquire) {
+            break;
+        }
+        let Some(inner) = tree.upgrade() else {
+            break;
+        };
+        match queue.write_batch(&inner.storage, batch_size) {
+            Ok(written) => {
+                inner.stats.disk_writes.fetch_add(written as u64, Ordering::Relaxed);
+            }
+            Err(e) => log::error!("Background flush of R-tree pages failed: {}", e),
+        }
+    }
+}
+
 fn compute_entries_bbox(entries: &[LeafEntry]) -> InternalBBox {
     let mut bbox = InternalBBox::empty();
     for e in entries {
@@ -1267,6 +1595,7 @@ impl Drop for DiskRTree {
         if !*self.inner.closed.read() {
             let _ = self.flush();
         }
+        self.stop_background_flush();
     }
 }
 
@@ -1405,9 +1734,8 @@ mod tests {
             tree.drop_tree().unwrap();
         }
 
-        // File should be empty/deleted
-        let metadata = std::fs::metadata(&path).unwrap();
-        assert_eq!(metadata.len(), 0);
+        // File should be deleted
+        assert!(!path.exists());
     }
 
     #[test]
@@ -1659,6 +1987,183 @@ mod tests {
         tree.close().unwrap();
     }
 
+    #[test]
+    fn test_nearest_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_nearest_entries.rtree");
+
+        let tree = DiskRTree::create(&path).unwrap();
+        assert!(tree.nearest_entries(0.0, 0.0).unwrap().next().is_none());
+
+        // Enough points to split the root, inserted out of distance order
+        for i in (0..500u64).rev() {
+            let x = ((i * 7919) % 500) as f64;
+            tree.add(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+        }
+
+        let results: Vec<_> = tree
+            .nearest_entries(250.0, 0.0)
+            .unwrap()
+            .collect::<SpatialResult<_>>()
+            .unwrap();
+        assert_eq!(results.len(), 500);
+        assert!(results.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert_eq!(results[0].1, 0.0);
+
+        let knn = tree.find_nearest(250.0, 0.0, 5, None).unwrap();
+        let distances: Vec<f64> = results.iter().take(5).map(|r| r.1).collect();
+        assert_eq!(distances, knn.iter().map(|r| r.1).collect::<Vec<_>>());
+
+        tree.close().unwrap();
+    }
+
+    #[test]
+    fn test_free_pages_reused_after_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_free_list.rtree");
+
+        let tree = DiskRTree::create(&path).unwrap();
+        for i in 0..2000u64 {
+            let x = i as f64;
+            tree.add(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+        }
+        // empty the leaves of the left half
+        for i in 0..1000u64 {
+            let x = i as f64;
+            assert!(tree.remove(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap());
+        }
+        let freed = tree.inner.header.read().free_page_count;
+        assert!(freed > 0);
+        tree.flush().unwrap();
+        assert!(tree.check_integrity().unwrap().is_valid);
+        tree.close().unwrap();
+
+        let tree = DiskRTree::open(&path).unwrap();
+        let next_page_id = tree.inner.header.read().next_page_id;
+        assert_eq!(tree.inner.header.read().free_page_count, freed);
+
+        for i in 0..1000u64 {
+            let x = i as f64;
+            tree.add(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+        }
+        // the freed pages were reused before the file grew
+        let header = tree.inner.header.read().clone();
+        assert!(header.free_page_count < freed);
+        assert!(header.next_page_id - next_page_id <= freed);
+        assert_eq!(tree.size(), 2000);
+        assert_eq!(
+            tree.find_intersecting_keys(&BoundingBox::new(0.0, -1.0, 1999.0, 1.0))
+                .unwrap()
+                .len(),
+            2000
+        );
+        tree.close().unwrap();
+    }
+
+    #[test]
+    fn test_compact() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_compact.rtree");
+
+        let tree = DiskRTree::create(&path).unwrap();
+        for i in 0..3000u64 {
+            let x = i as f64;
+            tree.add(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+        }
+        // remove all but the right end, whose pages were allocated last
+        for i in 0..2800u64 {
+            let x = i as f64;
+            tree.remove(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+        }
+        tree.flush().unwrap();
+        let len_before = std::fs::metadata(&path).unwrap().len();
+
+        let shrunk = tree.compact().unwrap();
+        assert!(shrunk > 0);
+        let len_after = std::fs::metadata(&path).unwrap().len();
+        assert_eq!(len_before - len_after, shrunk * 16384);
+        assert_eq!(tree.inner.header.read().free_page_count, 0);
+        assert!(tree.check_integrity().unwrap().is_valid);
+
+        let all = BoundingBox::new(0.0, -1.0, 3000.0, 1.0);
+        let mut found = tree.find_intersecting_keys(&all).unwrap();
+        found.sort();
+        assert_eq!(found, (2800..3000).collect::<Vec<_>>());
+
+        // still usable, and intact after a reopen
+        tree.add(&BoundingBox::new(5.0, 0.0, 5.0, 0.0), 5).unwrap();
+        tree.close().unwrap();
+        let tree = DiskRTree::open(&path).unwrap();
+        assert_eq!(tree.find_intersecting_keys(&all).unwrap().len(), 201);
+        assert_eq!(tree.compact().unwrap(), 0);
+        tree.close().unwrap();
+    }
+
+    #[test]
+    fn test_background_flush() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_background_flush.rtree");
+
+        let tree = DiskRTree::create_with_cache_size(&path, 4).unwrap();
+        tree.start_background_flush(BackgroundFlushOptions {
+            interval: std::time::Duration::from_millis(5),
+            batch_size: 8,
+            max_dirty_pages: 10_000,
+        })
+        .unwrap();
+        assert!(tree.start_background_flush(BackgroundFlushOptions::default()).is_err());
+
+        for i in 0..3000u64 {
+            let x = i as f64;
+            tree.add(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+        }
+        // queued pages are read back before they reach the disk
+        let all = BoundingBox::new(0.0, -1.0, 3000.0, 1.0);
+        assert_eq!(tree.find_intersecting_keys(&all).unwrap().len(), 3000);
+
+        tree.flush().unwrap();
+        assert_eq!(tree.stats().pending_writes, 0);
+        tree.close().unwrap();
+
+        let tree = DiskRTree::open(&path).unwrap();
+        assert_eq!(tree.find_intersecting_keys(&all).unwrap().len(), 3000);
+        tree.close().unwrap();
+    }
+
+    #[test]
+    fn test_background_flush_max_dirty_fallback() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_background_fallback.rtree");
+
+        let tree = DiskRTree::create_with_cache_size(&path, 4).unwrap();
+        // a flusher that never gets to run leaves it all to the fallback
+        tree.start_background_flush(BackgroundFlushOptions {
+            interval: std::time::Duration::from_secs(3600),
+            batch_size: usize::MAX,
+            max_dirty_pages: 3,
+        })
+        .unwrap();
+
+        for i in 0..2000u64 {
+            let x = i as f64;
+            tree.add(&BoundingBox::new(x, 0.0, x, 0.0), i).unwrap();
+            assert!(tree.stats().pending_writes < 3);
+        }
+        assert!(tree.stats().disk_writes > 0);
+
+        // dropping without close flushes and stops the flusher
+        drop(tree);
+        let tree = DiskRTree::open(&path).unwrap();
+        assert_eq!(tree.size(), 2000);
+        assert_eq!(
+            tree.find_intersecting_keys(&BoundingBox::new(0.0, -1.0, 2000.0, 1.0))
+                .unwrap()
+                .len(),
+            2000
+        );
+        tree.close().unwrap();
+    }
+
     #[test]
     fn test_detect_fragmentation_empty_tree() {
         let dir = tempdir().unwrap();
@@ -185,12 +185,22 @@ impl Storage {
         Ok(())
     }
 
+    /// Truncate the file to the given number of pages, header included
+    pub fn truncate(&self, page_count: u64) -> SpatialResult<()> {
+        let file = self.file.write();
+        file.set_len(page_count * self.page_size as u64)?;
+        Ok(())
+    }
+
     /// Delete the backing file
     pub fn delete(&self) -> SpatialResult<()> {
-        // File will be deleted when dropped after truncating
         let file = self.file.write();
         file.set_len(0)?;
-        Ok(())
+        // an empty file left behind would fail to open as an R-tree
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
     }
 }
 
@@ -297,6 +307,20 @@ mod tests {
         let storage = Storage::create(&path).unwrap();
         let result = storage.delete();
         assert!(result.is_ok());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_storage_truncate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.rtree");
+        let storage = Storage::create(&path).unwrap();
+        let node = Node::Leaf { entries: vec![] };
+        storage.write_page(3, &node).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 4 * 16384);
+
+        storage.truncate(2).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 2 * 16384);
     }
 
     #[test]
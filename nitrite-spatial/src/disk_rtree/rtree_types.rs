@@ -96,6 +96,8 @@ pub struct RTreeStats {
     pub cache_misses: u64,
     pub disk_reads: u64,
     pub disk_writes: u64,
+    /// Evicted dirty pages queued for the background flusher
+    pub pending_writes: u64,
     pub tree_height: u32,
 }
 
@@ -6,6 +6,9 @@
 //! - `NearFilter` - finds geometries within a distance of a point
 //! - `GeoNearFilter` - finds geometries within a geodesic distance of a geographic point
 //!
+//! [`geohash_prefix`] builds a plain string range filter instead, answered by a regular
+//! index on a field holding geohashes.
+//!
 //! ## Two-Phase Query Execution
 //!
 //! Spatial queries use a two-phase approach for accuracy and performance:
@@ -22,7 +25,7 @@ use std::sync::{Arc, OnceLock};
 use nitrite::collection::Document;
 use nitrite::common::Value;
 use nitrite::errors::{ErrorKind, NitriteError, NitriteResult};
-use nitrite::filter::{Filter, FilterProvider};
+use nitrite::filter::{field, Filter, FilterProvider};
 
 use crate::geometry::{create_geodesic_circle, GeoPoint, Geometry, Point};
 use crate::SpatialError;
@@ -920,11 +923,89 @@ pub fn as_spatial_filter(filter: &Filter) -> Option<&dyn SpatialFilterOps> {
     }
 }
 
+/// Creates a filter matching documents whose `field_name` holds a geohash inside the cell
+/// `prefix`, that is a geohash starting with `prefix`.
+///
+/// The filter is the string range `prefix <= value < next(prefix)`, so a regular unique or
+/// non-unique index on the field answers it with one range scan and no spatial index is
+/// needed. It is coarser than the spatial filters: a cell is a rectangle, and a point near
+/// its edge may be closer to points of the neighbouring cell than to points of its own.
+/// `prefix` is matched in lower case, as [`geohash_encode`](crate::geohash_encode) writes
+/// geohashes.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use nitrite_spatial::{geohash_encode, geohash_prefix, GeoPoint};
+///
+/// let location = GeoPoint::new(57.64911, 10.40744)?;
+/// places.insert(doc! { name: "harbour", geohash: (geohash_encode(&location, 9)?) })?;
+/// places.create_index(vec!["geohash"], &non_unique_index())?;
+///
+/// let nearby = places.find(geohash_prefix("geohash", "u4pruy"))?;
+/// ```
+pub fn geohash_prefix(field_name: &str, prefix: &str) -> Filter {
+    let lower = prefix.to_ascii_lowercase();
+    match prefix_successor(&lower) {
+        Some(upper) => field(field_name).between(lower, upper, true, false),
+        // the empty prefix matches every geohash
+        None => field(field_name).gte(lower),
+    }
+}
+
+/// Returns the least string greater than every string starting with `prefix`, or `None`
+/// if there is no such string.
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = match last {
+            '\u{d7ff}' => Some('\u{e000}'),
+            _ => char::from_u32(u32::from(last) + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::BTreeMap;
 
+    #[test]
+    fn test_geohash_prefix_filter() {
+        let filter = geohash_prefix("geohash", "U4PRUY");
+        let matches = |geohash: &str| {
+            let mut document = Document::new();
+            document.put("geohash", geohash).unwrap();
+            filter.apply(&document).unwrap()
+        };
+
+        assert!(matches("u4pruy"));
+        assert!(matches("u4pruydqqvj"));
+        assert!(!matches("u4pruz"));
+        assert!(!matches("u4prux0"));
+        assert!(!matches("u4pru"));
+        assert!(!matches("s0000"));
+
+        let every = geohash_prefix("geohash", "");
+        let mut document = Document::new();
+        document.put("geohash", "zzzz").unwrap();
+        assert!(every.apply(&document).unwrap());
+    }
+
+    #[test]
+    fn test_prefix_successor() {
+        assert_eq!(prefix_successor("u4pruy").as_deref(), Some("u4pruz"));
+        assert_eq!(prefix_successor("z").as_deref(), Some("{"));
+        assert_eq!(prefix_successor("a\u{d7ff}").as_deref(), Some("a\u{e000}"));
+        assert_eq!(prefix_successor("a\u{10ffff}").as_deref(), Some("b"));
+        assert_eq!(prefix_successor(""), None);
+    }
+
     #[test]
     fn test_intersects_filter_display() {
         let filter = IntersectsFilter::new("location", Geometry::point(10.0, 20.0));
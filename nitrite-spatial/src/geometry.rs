@@ -5,6 +5,7 @@
 //! - Points (2D coordinates)
 //! - GeoPoints (geographic coordinates with validation)
 //! - Polygons and other shapes via WKT parsing
+//! - Geohash encoding and decoding of geographic points
 //!
 //! ## Design Philosophy
 //!
@@ -87,6 +88,13 @@ impl Display for Point {
     }
 }
 
+/// Converts to `(x, y)`, e.g. for `FindOptions::sort_by_distance`.
+impl From<Point> for (f64, f64) {
+    fn from(point: Point) -> Self {
+        (point.x(), point.y())
+    }
+}
+
 /// A geographic point with validated latitude and longitude coordinates.
 ///
 /// This type provides explicit type safety for geographic coordinates,
@@ -229,6 +237,13 @@ impl Display for GeoPoint {
     }
 }
 
+/// Converts to `(longitude, latitude)`, the `(x, y)` of the point in a spatial index.
+impl From<GeoPoint> for (f64, f64) {
+    fn from(point: GeoPoint) -> Self {
+        (point.longitude(), point.latitude())
+    }
+}
+
 /// Earth's mean radius in meters (WGS84)
 const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
 
@@ -495,6 +510,141 @@ pub fn create_geodesic_circle(center: &GeoPoint, radius_meters: f64, _num_points
     }
 }
 
+/// The longest geohash [`geohash_encode`] produces, about 3.7 cm by 1.9 cm per cell.
+pub const MAX_GEOHASH_PRECISION: usize = 12;
+
+/// The geohash alphabet, indexed by the 5-bit value of a character.
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Encodes a geographic point as a geohash of `precision` characters.
+///
+/// A geohash names a cell of a grid over the globe; each character narrows the cell by
+/// 5 bits, alternating between longitude and latitude. Points in the same cell share the
+/// hash and nearby cells usually share a prefix, so a geohash stored in a regular string
+/// field buckets locations coarsely and can be queried with
+/// [`geohash_prefix`](crate::geohash_prefix) or used as a shard key.
+///
+/// # Errors
+/// Returns an error if `precision` is not between 1 and [`MAX_GEOHASH_PRECISION`].
+///
+/// # Example
+///
+/// ```rust
+/// use nitrite_spatial::{geohash_encode, GeoPoint};
+///
+/// let point = GeoPoint::new(57.64911, 10.40744).unwrap();
+/// assert_eq!(geohash_encode(&point, 11).unwrap(), "u4pruydqqvj");
+/// ```
+pub fn geohash_encode(point: &GeoPoint, precision: usize) -> Result<String, SpatialError> {
+    if !(1..=MAX_GEOHASH_PRECISION).contains(&precision) {
+        return Err(SpatialError::InvalidOperation(format!(
+            "Geohash precision must be between 1 and {}, got: {}",
+            MAX_GEOHASH_PRECISION, precision
+        )));
+    }
+
+    let (mut lat_min, mut lat_max) = (-90.0, 90.0);
+    let (mut lon_min, mut lon_max) = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut even_bit = true;
+    for _ in 0..precision {
+        let mut index = 0usize;
+        for _ in 0..5 {
+            // the bits alternate between longitude and latitude, longitude first
+            let (value, min, max) = if even_bit {
+                (point.longitude(), &mut lon_min, &mut lon_max)
+            } else {
+                (point.latitude(), &mut lat_min, &mut lat_max)
+            };
+            let mid = (*min + *max) / 2.0;
+            index <<= 1;
+            if value >= mid {
+                index |= 1;
+                *min = mid;
+            } else {
+                *max = mid;
+            }
+            even_bit = !even_bit;
+        }
+        hash.push(GEOHASH_ALPHABET[index] as char);
+    }
+    Ok(hash)
+}
+
+/// Returns the cell a geohash names, with x as longitude and y as latitude.
+///
+/// Upper case characters are accepted.
+///
+/// # Errors
+/// Returns an error if `geohash` is empty, longer than [`MAX_GEOHASH_PRECISION`] or has a
+/// character outside of the geohash alphabet (`0-9` and `b-z` without `i`, `l` and `o`).
+pub fn geohash_bounds(geohash: &str) -> Result<BoundingBox, SpatialError> {
+    if geohash.is_empty() || geohash.len() > MAX_GEOHASH_PRECISION {
+        return Err(SpatialError::InvalidOperation(format!(
+            "Geohash must have 1 to {} characters, got: {:?}",
+            MAX_GEOHASH_PRECISION, geohash
+        )));
+    }
+
+    let (mut lat_min, mut lat_max) = (-90.0, 90.0);
+    let (mut lon_min, mut lon_max) = (-180.0, 180.0);
+    let mut even_bit = true;
+    for c in geohash.chars() {
+        let index = geohash_index(c).ok_or_else(|| {
+            SpatialError::InvalidOperation(format!(
+                "Invalid geohash character {:?} in {:?}",
+                c, geohash
+            ))
+        })?;
+        for bit in (0..5).rev() {
+            let (min, max) = if even_bit {
+                (&mut lon_min, &mut lon_max)
+            } else {
+                (&mut lat_min, &mut lat_max)
+            };
+            let mid = (*min + *max) / 2.0;
+            if index >> bit & 1 == 1 {
+                *min = mid;
+            } else {
+                *max = mid;
+            }
+            even_bit = !even_bit;
+        }
+    }
+    Ok(BoundingBox::new(lon_min, lat_min, lon_max, lat_max))
+}
+
+/// Decodes a geohash to the center of the cell it names.
+///
+/// The center is within half the cell size of every point encoded to `geohash`; use
+/// [`geohash_bounds`] for the cell itself.
+///
+/// # Errors
+/// Returns an error if `geohash` is not a valid geohash, see [`geohash_bounds`].
+///
+/// # Example
+///
+/// ```rust
+/// use nitrite_spatial::geohash_decode;
+///
+/// let center = geohash_decode("u4pruydqqvj").unwrap();
+/// assert!((center.latitude() - 57.64911).abs() < 1e-4);
+/// assert!((center.longitude() - 10.40744).abs() < 1e-4);
+/// ```
+pub fn geohash_decode(geohash: &str) -> Result<GeoPoint, SpatialError> {
+    let bounds = geohash_bounds(geohash)?;
+    GeoPoint::new(
+        (bounds.min_y + bounds.max_y) / 2.0,
+        (bounds.min_x + bounds.max_x) / 2.0,
+    )
+}
+
+/// Returns the 5-bit value of a geohash character.
+fn geohash_index(c: char) -> Option<usize> {
+    let c = c.to_ascii_lowercase();
+    GEOHASH_ALPHABET.iter().position(|&b| b as char == c)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -599,6 +749,59 @@ mod tests {
         let deg = meters_to_degrees(111_320.0, 0.0);
         assert!((deg - 1.0).abs() < 0.1);
     }
+
+    #[test]
+    fn test_geohash_encode() {
+        let point = GeoPoint::new(57.64911, 10.40744).unwrap();
+        assert_eq!(geohash_encode(&point, 11).unwrap(), "u4pruydqqvj");
+        assert_eq!(geohash_encode(&point, 1).unwrap(), "u");
+
+        // a longer hash refines the shorter one
+        let long = geohash_encode(&point, MAX_GEOHASH_PRECISION).unwrap();
+        assert_eq!(long.len(), MAX_GEOHASH_PRECISION);
+        assert!(long.starts_with("u4pruy"));
+
+        let origin = GeoPoint::new(0.0, 0.0).unwrap();
+        assert_eq!(geohash_encode(&origin, 5).unwrap(), "s0000");
+        let corner = GeoPoint::new(-90.0, -180.0).unwrap();
+        assert_eq!(geohash_encode(&corner, 4).unwrap(), "0000");
+        let opposite = GeoPoint::new(90.0, 180.0).unwrap();
+        assert_eq!(geohash_encode(&opposite, 4).unwrap(), "zzzz");
+
+        assert!(geohash_encode(&point, 0).is_err());
+        assert!(geohash_encode(&point, MAX_GEOHASH_PRECISION + 1).is_err());
+    }
+
+    #[test]
+    fn test_geohash_decode() {
+        let center = geohash_decode("u4pruydqqvj").unwrap();
+        assert!((center.latitude() - 57.64911).abs() < 1e-4);
+        assert!((center.longitude() - 10.40744).abs() < 1e-4);
+        assert_eq!(geohash_decode("U4PRUYDQQVJ").unwrap(), center);
+
+        let bounds = geohash_bounds("s").unwrap();
+        assert_eq!(bounds, BoundingBox::new(0.0, 0.0, 45.0, 45.0));
+
+        assert!(geohash_decode("").is_err());
+        assert!(geohash_decode("u4pa").is_err());
+        assert!(geohash_decode("u4pruydqqvjuu").is_err());
+    }
+
+    #[test]
+    fn test_geohash_roundtrip() {
+        for (latitude, longitude) in [(45.0, -93.265), (-33.8688, 151.2093), (0.1, -0.1), (89.9, 179.9)] {
+            let point = GeoPoint::new(latitude, longitude).unwrap();
+            for precision in 1..=MAX_GEOHASH_PRECISION {
+                let hash = geohash_encode(&point, precision).unwrap();
+                let bounds = geohash_bounds(&hash).unwrap();
+                assert!(bounds.min_x <= longitude && longitude <= bounds.max_x);
+                assert!(bounds.min_y <= latitude && latitude <= bounds.max_y);
+                // the center lies in the same cell
+                let center = geohash_decode(&hash).unwrap();
+                assert_eq!(geohash_encode(&center, precision).unwrap(), hash);
+            }
+        }
+    }
 }
 
 // ADDITIONAL TESTS FOR COVERAGE
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
 use nitrite::{
     collection::{FindPlan, NitriteId},
@@ -10,7 +10,8 @@ use nitrite::{
 
 use crate::{
     filter::{as_spatial_filter, is_spatial_filter, value_to_geometry, KNearestFilter},
-    BoundingBox, DiskRTree, Geometry, IntersectsFilter, NitriteRTree, WithinFilter,
+    disk_rtree::rtree_types::NitriteIdValue,
+    BoundingBox, DiskRTree, Geometry, IntersectsFilter, NitriteRTree, SpatialError, WithinFilter,
     NearFilter, GeoNearFilter,
 };
 
@@ -169,7 +170,17 @@ impl SpatialIndex {
 
         // Handle KNearestFilter separately as it uses find_nearest, not find_intersecting_keys
         if let Some(knearest_filter) = filter.as_any().downcast_ref::<KNearestFilter>() {
-            return self.find_knearest_nitrite_ids(knearest_filter, config);
+            let results = self.find_knearest_nitrite_ids(knearest_filter, config)?;
+            return match find_plan.distance_sort() {
+                Some(sort) => {
+                    let ids = results.iter().map(NitriteId::id_value).collect();
+                    self.order_by_distance(ids, sort.x(), sort.y())?
+                        .into_iter()
+                        .map(NitriteId::create_id)
+                        .collect()
+                }
+                None => Ok(results),
+            };
         }
 
         let search_geometry = spatial_filter.geometry();
@@ -198,6 +209,12 @@ impl SpatialIndex {
             )
         })?;
 
+        // Candidates are refined in distance order, so the results keep it
+        let candidate_ids = match find_plan.distance_sort() {
+            Some(sort) => self.order_by_distance(candidate_ids, sort.x(), sort.y())?,
+            None => candidate_ids,
+        };
+
         // Phase 2: Geometry refinement
         // For precise results, we need to retrieve the actual geometry from each
         // candidate document and apply the exact spatial predicate.
@@ -217,6 +234,39 @@ impl SpatialIndex {
         Ok(results)
     }
 
+    /// Orders ids by the distance of their entries to a point, nearest first.
+    /// Walks the R-tree incrementally and stops as soon as every id has been seen.
+    fn order_by_distance(
+        &self,
+        ids: Vec<NitriteIdValue>,
+        x: f64,
+        y: f64,
+    ) -> NitriteResult<Vec<NitriteIdValue>> {
+        let map_err = |e: SpatialError| {
+            NitriteError::new(
+                &format!("Failed to sort by distance on spatial index: {}", e),
+                ErrorKind::Extension("spatial".to_string()),
+            )
+        };
+
+        let mut pending: HashSet<NitriteIdValue> = ids.into_iter().collect();
+        let mut ordered = Vec::with_capacity(pending.len());
+        if pending.is_empty() {
+            return Ok(ordered);
+        }
+
+        for entry in self.inner.rtree.iter_nearest(x, y).map_err(map_err)? {
+            let (id, _distance) = entry.map_err(map_err)?;
+            if pending.remove(&id) {
+                ordered.push(id);
+                if pending.is_empty() {
+                    break;
+                }
+            }
+        }
+        Ok(ordered)
+    }
+
     /// Finds K nearest entries to a point using the spatial index.
     /// This method uses find_nearest from DiskRTree which performs KNN search directly.
     fn find_knearest_nitrite_ids(
@@ -291,6 +341,26 @@ impl SpatialIndex {
         Ok(None)
     }
 
+    /// Writes the pending changes of the R-tree to disk, keeping the index open.
+    pub fn flush(&self) -> NitriteResult<()> {
+        self.inner.rtree.flush().map_err(|e| {
+            NitriteError::new(
+                &format!("Failed to flush spatial index: {}", e),
+                ErrorKind::Extension("spatial".to_string()),
+            )
+        })
+    }
+
+    /// Removes every entry from the R-tree, keeping the index open.
+    pub fn clear(&self) -> NitriteResult<()> {
+        self.inner.rtree.clear().map_err(|e| {
+            NitriteError::new(
+                &format!("Failed to clear spatial index: {}", e),
+                ErrorKind::Extension("spatial".to_string()),
+            )
+        })
+    }
+
    pub fn close(&self) -> NitriteResult<()> {
         self.inner.rtree.close().map_err(|e| {
             NitriteError::new(
@@ -884,5 +954,19 @@ mod tests {
         assert_eq!(results[0].id_value(), TEST_ID_1);
         index.drop().expect("Failed to drop index");
     }
-}
 
+    #[test]
+    fn test_clear_keeps_index_usable() {
+        let descriptor = create_test_index_descriptor();
+        let index = SpatialIndex::new(descriptor, None).expect("Failed to create index");
+
+        let bbox = BoundingBox::new(0.0, 0.0, 1.0, 1.0);
+        index.inner.rtree.add(&bbox, TEST_ID_1).expect("Failed to add entry");
+        index.clear().expect("Failed to clear index");
+        assert_eq!(index.inner.rtree.size(), 0);
+
+        index.inner.rtree.add(&bbox, TEST_ID_2).expect("Failed to add entry");
+        assert_eq!(index.inner.rtree.size(), 1);
+        index.drop().expect("Failed to drop index");
+    }
+}
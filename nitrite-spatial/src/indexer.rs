@@ -157,6 +157,25 @@ impl NitriteIndexerProvider for SpatialIndexer {
         Ok(())
     }
 
+    fn clear_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.get_or_create_index(index_descriptor)?.clear()
+    }
+
+    fn destroy_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        // open the index so that its file is removed even when it has
+        // not been used since the database was opened
+        self.get_or_create_index(index_descriptor)?;
+        self.drop_index(index_descriptor, nitrite_config)
+    }
+
     fn write_index_entry(
         &self,
         field_values: &FieldValues,
@@ -189,6 +208,33 @@ impl NitriteIndexerProvider for SpatialIndexer {
         let index = self.get_or_create_index(&index_descriptor)?;
         index.find_nitrite_ids(find_plan, nitrite_config)
     }
+
+    fn supports_distance_sort(&self) -> bool {
+        // results are ordered by an incremental nearest-neighbor walk of the R-tree
+        true
+    }
+
+    fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        // opening the index loads the R-tree file and registers it for later queries
+        self.get_or_create_index(index_descriptor)?;
+        Ok(())
+    }
+
+    fn is_loaded(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        // the R-tree lives in its own file, opened when the index is first used
+        let registry = self.inner.index_registry.read().map_err(|_| {
+            NitriteError::new("Lock poisoned", ErrorKind::InternalError)
+        })?;
+        Ok(registry.contains_key(&derive_index_map_name(index_descriptor)))
+    }
 }
 
 impl NitritePluginProvider for SpatialIndexer {
@@ -202,6 +248,19 @@ impl NitritePluginProvider for SpatialIndexer {
         Ok(())
     }
 
+    fn on_flush(&self) -> NitriteResult<()> {
+        // Write the dirty R-tree pages while the store is still open
+        let registry = self.inner.index_registry.read().map_err(|_| {
+            NitriteError::new("Lock poisoned", ErrorKind::InternalError)
+        })?;
+
+        for index in registry.values() {
+            index.flush()?;
+        }
+
+        Ok(())
+    }
+
     fn close(&self) -> NitriteResult<()> {
         // Close all indexes
         let registry = self.inner.index_registry.read().map_err(|_| {
@@ -255,6 +314,28 @@ mod tests {
         assert!(indexer.validate_index(&fields).is_err());
     }
 
+    #[test]
+    fn test_spatial_indexer_destroy_index_removes_unopened_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fields = Fields::with_names(vec!["location"]).unwrap();
+        let descriptor = IndexDescriptor::new(SPATIAL_INDEX, fields, "places");
+        let config = NitriteConfig::default();
+
+        let indexer = SpatialIndexer::new();
+        indexer.set_base_path(temp_dir.path().to_path_buf());
+        indexer.get_or_create_index(&descriptor).unwrap().close().unwrap();
+        let rtree_file = temp_dir
+            .path()
+            .join(format!("{}.rtree", derive_index_map_name(&descriptor)));
+        assert!(rtree_file.exists());
+
+        // a fresh indexer has not opened the index, as after reopening the database
+        let indexer = SpatialIndexer::new();
+        indexer.set_base_path(temp_dir.path().to_path_buf());
+        indexer.destroy_index(&descriptor, &config).unwrap();
+        assert!(!rtree_file.exists());
+    }
+
     #[test]
     fn test_spatial_module_plugins() {
         let module = SpatialModule;
@@ -14,6 +14,7 @@
 //! - **Two-Phase Search**: Fast R-tree bbox search followed by precise geometry refinement
 //! - **Spatial Filters**: Intersects, Within, Near, and GeoNear filters
 //! - **Fluent API**: Builder pattern for spatial queries
+//! - **Geohashes**: Encoding, decoding and prefix filters over regular string indexes
 //!
 //! ## Quick Start
 //!
@@ -79,19 +80,22 @@ pub mod spatial_module;
 
 // Re-export R-Tree types
 pub use bounding_box::BoundingBox;
-pub use disk_rtree::{DiskRTree, RTreeStats, SpatialError, SpatialResult};
+pub use disk_rtree::{BackgroundFlushOptions, DiskRTree, RTreeStats, SpatialError, SpatialResult};
 pub use nitrite_rtree::NitriteRTree;
 
 // Re-export geometry types
 pub use geometry::{
-    create_geodesic_circle, meters_to_degrees, Coordinate, GeoPoint, Geometry, Point,
+    create_geodesic_circle, geohash_bounds, geohash_decode, geohash_encode, meters_to_degrees,
+    Coordinate, GeoPoint, Geometry, Point, MAX_GEOHASH_PRECISION,
 };
 pub use geometry_extended::{
     parse_geojson, parse_wkt, GeometryValue, LineString, MultiGeometry, PolygonWithHoles,
 };
 
 // Re-export filter types
-pub use filter::{GeoNearFilter, IntersectsFilter, NearFilter, SpatialFilterOps, WithinFilter};
+pub use filter::{
+    geohash_prefix, GeoNearFilter, IntersectsFilter, NearFilter, SpatialFilterOps, WithinFilter,
+};
 
 // Re-export fluent API
 pub use fluent::SpatialFluentFilter;
@@ -3,6 +3,9 @@
 use crate::bounding_box::BoundingBox;
 use crate::disk_rtree::rtree_types::{SpatialResult, NitriteIdValue};
 
+/// Iterator over rtree entries as (NitriteId, distance) pairs, nearest first.
+pub type NearestIter<'a> = Box<dyn Iterator<Item = SpatialResult<(NitriteIdValue, f64)>> + 'a>;
+
 /// Represents an R-Tree in the nitrite database.
 /// 
 /// This trait defines the interface for spatial indexing operations,
@@ -29,6 +32,15 @@ pub trait NitriteRTree: Send + Sync {
         max_distance: Option<f64>,
     ) -> SpatialResult<Vec<(NitriteIdValue, f64)>>;
 
+    /// Iterates all entries in increasing distance from a point, nearest first.
+    ///
+    /// The tree is traversed incrementally, only as far as the iterator is consumed.
+    fn iter_nearest(
+        &self,
+        center_x: f64,
+        center_y: f64,
+    ) -> SpatialResult<NearestIter<'_>>;
+
     /// Finds entries within a specific distance of a point (range query).
     fn find_within_distance(
         &self,
@@ -43,6 +55,11 @@ pub trait NitriteRTree: Send + Sync {
     /// Gets the size of the rtree.
     fn size(&self) -> u64;
 
+    /// Writes all pending changes to storage, keeping the rtree open.
+    fn flush(&self) -> SpatialResult<()> {
+        Ok(())
+    }
+
     /// Closes this RTree instance, flushing all pending changes.
     fn close(&self) -> SpatialResult<()>;
 
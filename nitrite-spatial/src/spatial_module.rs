@@ -1,6 +1,6 @@
-use nitrite::{common::{NitriteModule, NitritePlugin, PluginRegistrar}, errors::NitriteResult, index::NitriteIndexer};
+use nitrite::{common::{ModuleInfo, NitriteModule, NitritePlugin, PluginRegistrar}, errors::NitriteResult, index::NitriteIndexer};
 
-use crate::SpatialIndexer;
+use crate::{SpatialIndexer, SPATIAL_INDEX};
 
 /// Nitrite module for loading the spatial indexer.
 ///
@@ -35,4 +35,16 @@ impl NitriteModule for SpatialModule {
     fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()> {
         plugin_registrar.register_indexer_plugin(NitriteIndexer::new(SpatialIndexer::new()))
     }
-}
\ No newline at end of file
+
+    fn info(&self) -> ModuleInfo {
+        // released in lockstep with nitrite, so it needs the matching core version
+        ModuleInfo::new("nitrite-spatial", env!("CARGO_PKG_VERSION"))
+            .min_core_version(env!("CARGO_PKG_VERSION"))
+            .indexer_type(SPATIAL_INDEX)
+            .filter_op("intersects")
+            .filter_op("within")
+            .filter_op("near")
+            .filter_op("geo_near")
+            .filter_op("knearest")
+    }
+}
@@ -5,6 +5,10 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use parking_lot::RwLock;
+
+use crate::language::LanguageDetector;
+
 /// Default index writer heap size: 50 MB
 pub const DEFAULT_INDEX_WRITER_HEAP_MB: usize = 50;
 
@@ -43,6 +47,9 @@ struct FtsConfigInner {
 
     /// Maximum results returned per search.
     search_result_limit: AtomicUsize,
+
+    /// Optional detector routing documents to language-specific analyzers.
+    language_detector: RwLock<Option<Arc<dyn LanguageDetector>>>,
 }
 
 impl FtsConfig {
@@ -122,6 +129,28 @@ impl FtsConfig {
         self.set_search_result_limit(limit);
         self
     }
+
+    /// Returns the language detector, if one is configured.
+    #[inline]
+    pub fn language_detector(&self) -> Option<Arc<dyn LanguageDetector>> {
+        self.inner.language_detector.read().clone()
+    }
+
+    /// Sets the language detector used to select a per-document analyzer.
+    ///
+    /// Only affects indexes created or opened after the call.
+    #[inline]
+    pub fn set_language_detector(&self, detector: Arc<dyn LanguageDetector>) {
+        *self.inner.language_detector.write() = Some(detector);
+    }
+
+    /// Sets the language detector used to select a per-document analyzer.
+    /// Builder-style method for chaining.
+    #[inline]
+    pub fn with_language_detector(self, detector: Arc<dyn LanguageDetector>) -> Self {
+        self.set_language_detector(detector);
+        self
+    }
 }
 
 impl Default for FtsConfig {
@@ -136,6 +165,7 @@ impl FtsConfigInner {
             index_writer_heap_size: AtomicUsize::new(DEFAULT_INDEX_WRITER_HEAP_MB * 1024 * 1024),
             num_threads: AtomicUsize::new(DEFAULT_NUM_THREADS),
             search_result_limit: AtomicUsize::new(DEFAULT_SEARCH_RESULT_LIMIT),
+            language_detector: RwLock::new(None),
         }
     }
 }
@@ -150,6 +180,7 @@ mod tests {
         assert_eq!(config.index_writer_heap_size(), 50 * 1024 * 1024);
         assert_eq!(config.num_threads(), 0);
         assert_eq!(config.search_result_limit(), 10_000);
+        assert!(config.language_detector().is_none());
     }
 
     #[test]
@@ -182,6 +213,20 @@ mod tests {
         assert_eq!(config.search_result_limit(), 20000);
     }
 
+    #[test]
+    fn test_fts_config_language_detector() {
+        struct English;
+        impl LanguageDetector for English {
+            fn detect(&self, _text: &str) -> Option<String> {
+                Some("en".to_string())
+            }
+        }
+
+        let config = FtsConfig::new().with_language_detector(Arc::new(English));
+        let detector = config.language_detector().unwrap();
+        assert_eq!(detector.detect("hello"), Some("en".to_string()));
+    }
+
     #[test]
     fn test_fts_config_clone() {
         let config1 = FtsConfig::new().with_num_threads(4);
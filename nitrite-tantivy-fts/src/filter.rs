@@ -25,6 +25,11 @@ pub trait FtsFilter: Send + Sync {
 
     /// Returns the field name this filter applies to.
     fn field_name(&self) -> String;
+
+    /// Returns the language facet this filter is restricted to, if any.
+    fn language(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Filter that finds documents matching a text query.
@@ -39,6 +44,7 @@ pub struct TextSearchFilter {
 struct TextSearchFilterInner {
     field: RwLock<String>,
     query: String,
+    language: Option<String>,
 }
 
 impl TextSearchFilter {
@@ -48,6 +54,23 @@ impl TextSearchFilter {
             inner: Arc::new(TextSearchFilterInner {
                 field: RwLock::new(field.into()),
                 query: query.into(),
+                language: None,
+            }),
+        }
+    }
+
+    /// Creates a new text search filter restricted to documents
+    /// whose detected language matches the given ISO 639-1 code.
+    pub fn with_language(
+        field: impl Into<String>,
+        query: impl Into<String>,
+        language: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(TextSearchFilterInner {
+                field: RwLock::new(field.into()),
+                query: query.into(),
+                language: Some(crate::language::normalize_language(&language.into())),
             }),
         }
     }
@@ -66,6 +89,10 @@ impl FtsFilter for TextSearchFilter {
     fn field_name(&self) -> String {
         self.inner.field.read().clone()
     }
+
+    fn language(&self) -> Option<String> {
+        self.inner.language.clone()
+    }
 }
 
 impl FilterProvider for TextSearchFilter {
@@ -150,6 +177,7 @@ pub struct PhraseFilter {
 struct PhraseFilterInner {
     field: RwLock<String>,
     phrase: String,
+    language: Option<String>,
 }
 
 impl PhraseFilter {
@@ -159,6 +187,23 @@ impl PhraseFilter {
             inner: Arc::new(PhraseFilterInner {
                 field: RwLock::new(field.into()),
                 phrase: phrase.into(),
+                language: None,
+            }),
+        }
+    }
+
+    /// Creates a new phrase filter restricted to documents
+    /// whose detected language matches the given ISO 639-1 code.
+    pub fn with_language(
+        field: impl Into<String>,
+        phrase: impl Into<String>,
+        language: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(PhraseFilterInner {
+                field: RwLock::new(field.into()),
+                phrase: phrase.into(),
+                language: Some(crate::language::normalize_language(&language.into())),
             }),
         }
     }
@@ -178,6 +223,10 @@ impl FtsFilter for PhraseFilter {
     fn field_name(&self) -> String {
         self.inner.field.read().clone()
     }
+
+    fn language(&self) -> Option<String> {
+        self.inner.language.clone()
+    }
 }
 
 impl FilterProvider for PhraseFilter {
@@ -35,6 +35,7 @@ pub fn fts_field(field: &str) -> FtsFluentFilter {
 /// Fluent builder for FTS filters.
 pub struct FtsFluentFilter {
     field: String,
+    language: Option<String>,
 }
 
 impl FtsFluentFilter {
@@ -42,9 +43,28 @@ impl FtsFluentFilter {
     pub fn new(field: impl Into<String>) -> Self {
         Self {
             field: field.into(),
+            language: None,
         }
     }
 
+    /// Restricts the filter to documents whose detected language matches the
+    /// given ISO 639-1 code.
+    ///
+    /// Requires the FTS module to be configured with a
+    /// [`LanguageDetector`](crate::LanguageDetector).
+    ///
+    /// ## Example
+    ///
+    /// ```rust,ignore
+    /// use nitrite_tantivy_fts::fts_field;
+    ///
+    /// let filter = fts_field("content").language("fr").matches("bonjour");
+    /// ```
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
     /// Creates a text search filter that matches documents containing
     /// any of the specified search terms.
     ///
@@ -66,7 +86,10 @@ impl FtsFluentFilter {
     /// let filter = fts_field("content").matches("+hello +world");
     /// ```
     pub fn matches(self, query: impl Into<String>) -> Filter {
-        Filter::new(TextSearchFilter::new(self.field, query))
+        match self.language {
+            Some(language) => Filter::new(TextSearchFilter::with_language(self.field, query, language)),
+            None => Filter::new(TextSearchFilter::new(self.field, query)),
+        }
     }
 
     /// Creates a phrase filter that matches documents containing
@@ -81,7 +104,10 @@ impl FtsFluentFilter {
     /// let filter = fts_field("content").phrase("hello world");
     /// ```
     pub fn phrase(self, phrase: impl Into<String>) -> Filter {
-        Filter::new(PhraseFilter::new(self.field, phrase))
+        match self.language {
+            Some(language) => Filter::new(PhraseFilter::with_language(self.field, phrase, language)),
+            None => Filter::new(PhraseFilter::new(self.field, phrase)),
+        }
     }
 
     /// Alias for `matches` - creates a text search filter.
@@ -314,4 +340,15 @@ mod tests {
         let phrase_filter = filter.as_any().downcast_ref::<PhraseFilter>().unwrap();
         assert!(phrase_filter.query_string().contains("こんにちは世界"));
     }
+
+    #[test]
+    fn test_language_restricted_filters() {
+        let filter = fts_field("content").language("FR").matches("bonjour");
+        let text_filter = filter.as_any().downcast_ref::<TextSearchFilter>().unwrap();
+        assert_eq!(text_filter.language(), Some("fr".to_string()));
+
+        let filter = fts_field("content").phrase("bonjour monde");
+        let phrase_filter = filter.as_any().downcast_ref::<PhraseFilter>().unwrap();
+        assert_eq!(phrase_filter.language(), None);
+    }
 }
@@ -3,14 +3,18 @@
 //! This module provides the `TantivyFtsModule` that integrates the FTS indexer
 //! with Nitrite's plugin system.
 
+use std::sync::Arc;
+
 use nitrite::{
-    common::{NitriteModule, NitritePlugin, PluginRegistrar},
+    common::{ModuleInfo, NitriteModule, NitritePlugin, PluginRegistrar},
     errors::NitriteResult,
     index::NitriteIndexer,
 };
 
 use crate::config::FtsConfig;
+use crate::filter::FTS_INDEX;
 use crate::indexer::FtsIndexer;
+use crate::language::LanguageDetector;
 
 /// Nitrite module for loading the FTS indexer.
 ///
@@ -84,6 +88,15 @@ impl NitriteModule for TantivyFtsModule {
             self.config.clone(),
         )))
     }
+
+    fn info(&self) -> ModuleInfo {
+        // released in lockstep with nitrite, so it needs the matching core version
+        ModuleInfo::new("nitrite-tantivy-fts", env!("CARGO_PKG_VERSION"))
+            .min_core_version(env!("CARGO_PKG_VERSION"))
+            .indexer_type(FTS_INDEX)
+            .filter_op("matches")
+            .filter_op("phrase")
+    }
 }
 
 /// Builder for configuring a TantivyFtsModule.
@@ -129,6 +142,16 @@ impl TantivyFtsModuleBuilder {
         self
     }
 
+    /// Sets a language detector so each document is indexed with the analyzer
+    /// of its detected language and tagged with a `lang` facet.
+    ///
+    /// Default: none (all documents use the default analyzer)
+    #[inline]
+    pub fn language_detector(self, detector: impl LanguageDetector + 'static) -> Self {
+        self.config.set_language_detector(Arc::new(detector));
+        self
+    }
+
     /// Builds the TantivyFtsModule with the configured settings.
     #[inline]
     pub fn build(self) -> TantivyFtsModule {
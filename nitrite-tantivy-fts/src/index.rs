@@ -3,15 +3,21 @@
 //! This module provides the `FtsIndex` that wraps Tantivy's Index
 //! for integration with Nitrite's indexing system.
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use parking_lot::RwLock;
-use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
-use tantivy::schema::{Field, Schema, Value as TantivyValue, STORED, STRING, TEXT};
-use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument};
+use tantivy::collector::{FacetCollector, TopDocs};
+use tantivy::directory::error::OpenReadError;
+use tantivy::directory::RamDirectory;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{
+    FacetOptions, Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions,
+    Value as TantivyValue, STORED, STRING, TEXT,
+};
+use tantivy::{Directory, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument};
 
 use nitrite::collection::{FindPlan, NitriteId};
 use nitrite::common::{FieldValues, Value};
@@ -20,6 +26,21 @@ use nitrite::index::IndexDescriptor;
 
 use crate::config::FtsConfig;
 use crate::filter::{as_fts_filter, is_fts_filter};
+use crate::language::{
+    language_analyzer, language_facet, language_text_field, normalize_language,
+    register_language_analyzers, LanguageDetector, LANGUAGE_FIELD, SUPPORTED_LANGUAGES,
+};
+
+const META_FILE: &str = "meta.json";
+const MANAGED_FILE: &str = ".managed.json";
+
+const EXPORT_MAGIC: &[u8; 8] = b"NITFTS\0\0";
+const EXPORT_VERSION: u32 = 1;
+/// How often an export is retried when merges replace the segments being read.
+const EXPORT_ATTEMPTS: usize = 5;
+
+/// The name and content of one file of an exported index.
+type IndexFile = (String, Vec<u8>);
 
 /// A full-text search index instance for a specific field.
 #[derive(Clone)]
@@ -40,6 +61,11 @@ struct FtsIndexInner {
     dirty: AtomicBool,
     id_field: Field,
     text_field: Field,
+    /// Facet field recording each document's detected language (language-aware indexes only).
+    lang_field: Option<Field>,
+    /// Language-specific text fields keyed by ISO 639-1 code.
+    language_fields: HashMap<String, Field>,
+    language_detector: Option<Arc<dyn LanguageDetector>>,
     index_path: Option<PathBuf>,
     search_result_limit: usize,
 }
@@ -56,10 +82,25 @@ impl FtsIndex {
     ) -> NitriteResult<Self> {
         let index_name = derive_index_map_name(&index_descriptor);
 
-        // Build schema with id and text fields
+        let language_detector = config.language_detector();
+
+        // Build schema with id and text fields, plus a language facet and one
+        // stemmed text field per supported language when a detector is configured
         let mut schema_builder = Schema::builder();
-        let id_field = schema_builder.add_text_field("_id", STRING | STORED);
-        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        schema_builder.add_text_field("_id", STRING | STORED);
+        schema_builder.add_text_field("text", TEXT | STORED);
+        if language_detector.is_some() {
+            schema_builder.add_facet_field(LANGUAGE_FIELD, FacetOptions::default().set_stored());
+            for (code, _) in SUPPORTED_LANGUAGES {
+                let indexing = TextFieldIndexing::default()
+                    .set_tokenizer(&language_analyzer(code))
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+                schema_builder.add_text_field(
+                    &language_text_field(code),
+                    TextOptions::default().set_indexing_options(indexing),
+                );
+            }
+        }
         let schema = schema_builder.build();
 
         // Create or open the index
@@ -99,6 +140,38 @@ impl FtsIndex {
             (index, None)
         };
 
+        Self::from_index(index, index_path, config)
+    }
+
+    /// Wraps an opened tantivy index, with its writer and reader.
+    fn from_index(index: Index, index_path: Option<PathBuf>, config: &FtsConfig) -> NitriteResult<Self> {
+        let language_detector = config.language_detector();
+        register_language_analyzers(&index);
+
+        // Resolve fields from the index's own schema: an existing index may have been
+        // created with or without the language fields.
+        let schema = index.schema();
+        let field = |name: &str| {
+            schema.get_field(name).map_err(|e| {
+                NitriteError::new(
+                    &format!("Invalid FTS index schema: {}", e),
+                    ErrorKind::Extension("FTS".to_string()),
+                )
+            })
+        };
+        let id_field = field("_id")?;
+        let text_field = field("text")?;
+        let lang_field = schema.get_field(LANGUAGE_FIELD).ok();
+        let language_fields = SUPPORTED_LANGUAGES
+            .iter()
+            .filter_map(|(code, _)| {
+                schema
+                    .get_field(&language_text_field(code))
+                    .ok()
+                    .map(|f| (code.to_string(), f))
+            })
+            .collect::<HashMap<_, _>>();
+
         // Create index writer with configured heap size and thread count
         let heap_size = config.index_writer_heap_size();
         let num_threads = config.num_threads();
@@ -136,6 +209,9 @@ impl FtsIndex {
                 dirty: AtomicBool::new(false),
                 id_field,
                 text_field,
+                lang_field,
+                language_fields,
+                language_detector,
                 index_path,
                 search_result_limit: config.search_result_limit(),
             }),
@@ -196,10 +272,26 @@ impl FtsIndex {
             return Ok(());
         }
 
+        // Route the text to the analyzer of its detected language, if any
+        let language = self
+            .inner
+            .language_detector
+            .as_ref()
+            .and_then(|detector| detector.detect(&text))
+            .map(|code| normalize_language(&code));
+        let target_field = language
+            .as_ref()
+            .and_then(|code| self.inner.language_fields.get(code))
+            .copied()
+            .unwrap_or(self.inner.text_field);
+
         // Create document
         let mut doc = TantivyDocument::new();
         doc.add_text(self.inner.id_field, nitrite_id.to_string());
-        doc.add_text(self.inner.text_field, &text);
+        doc.add_text(target_field, &text);
+        if let (Some(lang_field), Some(code)) = (self.inner.lang_field, &language) {
+            doc.add_facet(lang_field, language_facet(code));
+        }
 
         let id_term = tantivy::Term::from_field_text(self.inner.id_field, &nitrite_id.to_string());
 
@@ -265,25 +357,54 @@ impl FtsIndex {
         })?;
 
         let query_str = fts_filter.query_string();
-        self.search(&query_str)
+        self.search(&query_str, fts_filter.language().as_deref())
     }
 
-    /// Performs a full-text search and returns matching NitriteIds.
-    fn search(&self, query_str: &str) -> NitriteResult<Vec<NitriteId>> {
-        // Flush any buffered writes once so this search observes them, then reuse the cached
-        // reader instead of reopening the index segments per query.
+    /// Counts the documents matching a full-text query per detected language, as pairs
+    /// of an ISO 639-1 code and a count ordered by code. Documents without a detected
+    /// language are not counted.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the index has no language facet, because no [`LanguageDetector`] was
+    /// configured when it was created.
+    pub fn language_counts(&self, query_str: &str) -> NitriteResult<Vec<(String, u64)>> {
+        self.language_field()?;
         self.commit_if_dirty()?;
 
         let searcher = self.inner.reader.searcher();
-        let query_parser = QueryParser::for_index(&self.inner.index, vec![self.inner.text_field]);
-
-        let query = query_parser.parse_query(query_str).map_err(|e| {
+        let query = self.parse_query(query_str, None)?;
+        let mut collector = FacetCollector::for_field(LANGUAGE_FIELD);
+        collector.add_facet("/");
+        let counts = searcher.search(&query, &collector).map_err(|e| {
             NitriteError::new(
-                &format!("Failed to parse FTS query '{}': {}", query_str, e),
+                &format!("FTS search failed: {}", e),
                 ErrorKind::Extension("FTS".to_string()),
             )
         })?;
 
+        Ok(counts
+            .get("/")
+            .filter_map(|(facet, count)| {
+                facet.to_path().last().map(|code| (code.to_string(), count))
+            })
+            .collect())
+    }
+
+    /// Performs a full-text search and returns matching NitriteIds, optionally
+    /// restricted to documents of one language.
+    fn search(
+        &self,
+        query_str: &str,
+        language: Option<&str>,
+    ) -> NitriteResult<Vec<NitriteId>> {
+        // Flush any buffered writes once so this search observes them, then reuse the cached
+        // reader instead of reopening the index segments per query.
+        self.commit_if_dirty()?;
+
+        let searcher = self.inner.reader.searcher();
+        let query = self.parse_query(query_str, language)?;
+
         // Search with configured limit
         let top_docs = searcher
             .search(&query, &TopDocs::with_limit(self.inner.search_result_limit))
@@ -318,6 +439,72 @@ impl FtsIndex {
         Ok(results)
     }
 
+    /// Parses a full-text query over the text fields, restricted to the documents of
+    /// `language` if given.
+    fn parse_query(&self, query_str: &str, language: Option<&str>) -> NitriteResult<Box<dyn Query>> {
+        // Each language field analyzes the query with its own stemmer
+        let mut default_fields = vec![self.inner.text_field];
+        default_fields.extend(self.inner.language_fields.values().copied());
+        let query_parser = QueryParser::for_index(&self.inner.index, default_fields);
+
+        let parsed = query_parser.parse_query(query_str).map_err(|e| {
+            NitriteError::new(
+                &format!("Failed to parse FTS query '{}': {}", query_str, e),
+                ErrorKind::Extension("FTS".to_string()),
+            )
+        })?;
+
+        Ok(match language {
+            Some(code) => {
+                let lang_term = tantivy::Term::from_facet(self.language_field()?, &language_facet(code));
+                Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, parsed),
+                    (
+                        Occur::Must,
+                        Box::new(TermQuery::new(lang_term, IndexRecordOption::Basic)),
+                    ),
+                ]))
+            }
+            None => parsed,
+        })
+    }
+
+    /// Returns the language facet field, which only indexes created with a language
+    /// detector have.
+    fn language_field(&self) -> NitriteResult<Field> {
+        self.inner.lang_field.ok_or_else(|| {
+            NitriteError::new(
+                "FTS index has no language facet; configure a LanguageDetector",
+                ErrorKind::Extension("FTS".to_string()),
+            )
+        })
+    }
+
+    /// Commits the buffered writes and deletes, keeping the writer open.
+    pub fn flush(&self) -> NitriteResult<()> {
+        self.commit_if_dirty()
+    }
+
+    /// Removes every document from the FTS index, keeping the index open.
+    pub fn clear(&self) -> NitriteResult<()> {
+        {
+            let mut writer_guard = self.inner.index_writer.write();
+            if let Some(ref mut writer) = *writer_guard {
+                // unlike delete_all_documents, a delete query also removes the
+                // documents added since the last commit
+                writer.delete_query(Box::new(AllQuery)).map_err(|e| {
+                    NitriteError::new(
+                        &format!("Failed to clear FTS index: {}", e),
+                        ErrorKind::Extension("FTS".to_string()),
+                    )
+                })?;
+            }
+        }
+        // Commit right away so searches stop seeing the removed documents.
+        self.inner.dirty.store(true, Ordering::Release);
+        self.commit_if_dirty()
+    }
+
     /// Closes the FTS index, committing any pending changes.
     pub fn close(&self) -> NitriteResult<()> {
         let mut writer_guard = self.inner.index_writer.write();
@@ -353,6 +540,129 @@ impl FtsIndex {
 
         Ok(())
     }
+
+    /// Exports the committed files of the index, committing the buffered writes first.
+    ///
+    /// A merge may replace segments while the files are read; the export is then
+    /// taken again from the new `meta.json`.
+    pub fn export(&self) -> NitriteResult<Vec<u8>> {
+        self.commit_if_dirty()?;
+
+        for _ in 0..EXPORT_ATTEMPTS {
+            if let Some(files) = self.read_committed_files()? {
+                return Ok(write_index_files(&files));
+            }
+        }
+        Err(NitriteError::new(
+            "Failed to export FTS index: the index kept changing while it was read",
+            ErrorKind::Extension("FTS".to_string()),
+        ))
+    }
+
+    /// Reads `meta.json` with the files of the segments it lists, or returns `None` if
+    /// the index changed in the meantime.
+    fn read_committed_files(&self) -> NitriteResult<Option<Vec<IndexFile>>> {
+        let directory = self.inner.index.directory();
+        let read_meta = || {
+            directory.atomic_read(Path::new(META_FILE)).map_err(|e| {
+                NitriteError::new(
+                    &format!("Failed to read FTS index metadata: {}", e),
+                    ErrorKind::Extension("FTS".to_string()),
+                )
+            })
+        };
+
+        let meta = read_meta()?;
+        let segments = self.inner.index.searchable_segment_metas().map_err(|e| {
+            NitriteError::new(
+                &format!("Failed to read FTS index segments: {}", e),
+                ErrorKind::Extension("FTS".to_string()),
+            )
+        })?;
+
+        let mut paths: Vec<PathBuf> = segments
+            .iter()
+            .flat_map(|segment| segment.list_files())
+            .collect();
+        paths.sort();
+
+        let mut files = Vec::with_capacity(paths.len() + 2);
+        for path in paths {
+            // read whole, as `open_read` strips the footer tantivy checks on open
+            let bytes = match directory.atomic_read(&path) {
+                Ok(bytes) => bytes,
+                // not every component exists in every segment; files removed by a
+                // merge meanwhile are caught by the meta.json check below
+                Err(OpenReadError::FileDoesNotExist(_)) => continue,
+                Err(e) => {
+                    return Err(NitriteError::new(
+                        &format!("Failed to read FTS index file {:?}: {}", path, e),
+                        ErrorKind::Extension("FTS".to_string()),
+                    ))
+                }
+            };
+            files.push((path.to_string_lossy().into_owned(), bytes));
+        }
+
+        // the files read belong to this meta.json only if it did not change meanwhile
+        if read_meta()? != meta {
+            return Ok(None);
+        }
+        // the list of files tantivy manages, so that it cleans up the imported files
+        // once they are merged away
+        if let Ok(managed) = directory.atomic_read(Path::new(MANAGED_FILE)) {
+            files.push((MANAGED_FILE.to_string(), managed));
+        }
+        files.push((META_FILE.to_string(), meta));
+        Ok(Some(files))
+    }
+
+    /// Creates the index of `index_descriptor` from the files exported by
+    /// [`FtsIndex::export`], replacing what is stored at its location.
+    pub fn import(
+        index_descriptor: IndexDescriptor,
+        base_path: Option<PathBuf>,
+        config: &FtsConfig,
+        data: &[u8],
+    ) -> NitriteResult<Self> {
+        let files = read_index_files(data)?;
+        if !files.iter().any(|(name, _)| name == META_FILE) {
+            return Err(NitriteError::new(
+                "Invalid FTS index export: no meta.json",
+                ErrorKind::Extension("FTS".to_string()),
+            ));
+        }
+
+        let Some(base) = base_path else {
+            let directory = RamDirectory::create();
+            for (name, bytes) in &files {
+                directory.atomic_write(Path::new(name), bytes).map_err(|e| {
+                    NitriteError::new(
+                        &format!("Failed to write FTS index file {}: {}", name, e),
+                        ErrorKind::Extension("FTS".to_string()),
+                    )
+                })?;
+            }
+            let index = Index::open(directory).map_err(|e| {
+                NitriteError::new(
+                    &format!("Failed to open imported FTS index: {}", e),
+                    ErrorKind::Extension("FTS".to_string()),
+                )
+            })?;
+            return Self::from_index(index, None, config);
+        };
+
+        let path = base.join(format!("{}_fts", derive_index_map_name(&index_descriptor)));
+        let result = write_index_dir(&path, &files)
+            .and_then(|_| FtsIndex::new(index_descriptor, Some(base), config));
+        if result.is_err() && path.exists() {
+            // leave no half-written index to be opened later
+            if let Err(e) = std::fs::remove_dir_all(&path) {
+                log::warn!("Failed to remove FTS index directory {:?}: {}", path, e);
+            }
+        }
+        result
+    }
 }
 
 /// Derives the index map name from an index descriptor.
@@ -363,6 +673,96 @@ pub(crate) fn derive_index_map_name(descriptor: &IndexDescriptor) -> String {
     format!("{}_{}_{}_idx", collection, fields, index_type)
 }
 
+/// Writes the exported files into a new index directory at `path`.
+fn write_index_dir(path: &Path, files: &[IndexFile]) -> NitriteResult<()> {
+    let io_error = |e: std::io::Error| {
+        NitriteError::new(
+            &format!("Failed to write FTS index directory {:?}: {}", path, e),
+            ErrorKind::Extension("FTS".to_string()),
+        )
+    };
+
+    if path.exists() {
+        std::fs::remove_dir_all(path).map_err(io_error)?;
+    }
+    std::fs::create_dir_all(path).map_err(io_error)?;
+    for (name, bytes) in files {
+        std::fs::write(path.join(name), bytes).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Packs the files of an index: a header naming the format and its version, then the
+/// name and content of every file.
+fn write_index_files(files: &[IndexFile]) -> Vec<u8> {
+    let size = files
+        .iter()
+        .map(|(name, bytes)| 12 + name.len() + bytes.len())
+        .sum::<usize>();
+    let mut data = Vec::with_capacity(16 + size);
+    data.extend_from_slice(EXPORT_MAGIC);
+    data.extend_from_slice(&EXPORT_VERSION.to_le_bytes());
+    data.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    for (name, bytes) in files {
+        data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        data.extend_from_slice(bytes);
+    }
+    data
+}
+
+/// Unpacks the files packed by [`write_index_files`], checking that every name is a
+/// plain file name.
+fn read_index_files(mut data: &[u8]) -> NitriteResult<Vec<IndexFile>> {
+    fn invalid(message: &str) -> NitriteError {
+        NitriteError::new(
+            &format!("Invalid FTS index export: {}", message),
+            ErrorKind::Extension("FTS".to_string()),
+        )
+    }
+
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> NitriteResult<&'a [u8]> {
+        if data.len() < len {
+            return Err(invalid("truncated data"));
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
+
+    fn take_u32(data: &mut &[u8]) -> NitriteResult<u32> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(take(data, 4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    if take(&mut data, EXPORT_MAGIC.len())? != EXPORT_MAGIC {
+        return Err(invalid("not an FTS index"));
+    }
+    let version = take_u32(&mut data)?;
+    if version > EXPORT_VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+
+    let count = take_u32(&mut data)?;
+    let mut files = Vec::new();
+    for _ in 0..count {
+        let name_len = take_u32(&mut data)? as usize;
+        let name = std::str::from_utf8(take(&mut data, name_len)?)
+            .map_err(|_| invalid("invalid file name"))?;
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(invalid(&format!("invalid file name {}", name)));
+        }
+
+        let mut len = [0u8; 8];
+        len.copy_from_slice(take(&mut data, 8)?);
+        let len = usize::try_from(u64::from_le_bytes(len)).map_err(|_| invalid("truncated data"))?;
+        files.push((name.to_string(), take(&mut data, len)?.to_vec()));
+    }
+    Ok(files)
+}
+
 /// Converts a Value to text for indexing.
 fn value_to_text(value: &Value) -> String {
     match value {
@@ -412,6 +812,17 @@ mod tests {
         )
     }
 
+    struct PrefixDetector;
+
+    impl LanguageDetector for PrefixDetector {
+        fn detect(&self, text: &str) -> Option<String> {
+            // Test corpus marks its language with a leading tag, e.g. "[fr] ..."
+            text.strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .map(|(code, _)| code.to_string())
+        }
+    }
+
     fn create_test_field_values(_id: u64, text: &str) -> FieldValues {
         let fields = Fields::with_names(vec!["content"]).unwrap();
         let nitrite_id = NitriteId::new();
@@ -525,7 +936,7 @@ mod tests {
         let index = FtsIndex::new(descriptor, None, &config).unwrap();
         let cloned = index.clone();
         // Both should work independently
-        assert!(cloned.search("test").is_ok());
+        assert!(cloned.search("test", None).is_ok());
     }
 
     #[test]
@@ -546,7 +957,7 @@ mod tests {
         let config = create_test_config();
         let index = FtsIndex::new(descriptor, None, &config).unwrap();
 
-        let results = index.search("nonexistent");
+        let results = index.search("nonexistent", None);
         assert!(results.is_ok());
         assert_eq!(results.unwrap().len(), 0);
     }
@@ -562,7 +973,7 @@ mod tests {
         index.write(&field_values).unwrap();
 
         // Search for it
-        let results = index.search("hello").unwrap();
+        let results = index.search("hello", None).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -584,15 +995,15 @@ mod tests {
             .unwrap();
 
         // Search for "hello" - should find 2 documents
-        let results = index.search("hello").unwrap();
+        let results = index.search("hello", None).unwrap();
         assert_eq!(results.len(), 2);
 
         // Search for "world" - should find 2 documents
-        let results = index.search("world").unwrap();
+        let results = index.search("world", None).unwrap();
         assert_eq!(results.len(), 2);
 
         // Search for "universe" - should find 1 document
-        let results = index.search("universe").unwrap();
+        let results = index.search("universe", None).unwrap();
         assert_eq!(results.len(), 1);
     }
 
@@ -607,13 +1018,13 @@ mod tests {
         index.write(&field_values).unwrap();
 
         // Verify it exists
-        assert_eq!(index.search("hello").unwrap().len(), 1);
+        assert_eq!(index.search("hello", None).unwrap().len(), 1);
 
         // Remove it
         index.remove(&field_values).unwrap();
 
         // Verify it's gone
-        assert_eq!(index.search("hello").unwrap().len(), 0);
+        assert_eq!(index.search("hello", None).unwrap().len(), 0);
     }
 
     #[test]
@@ -630,10 +1041,52 @@ mod tests {
             .unwrap();
 
         // Phrase search
-        let results = index.search("\"quick brown\"").unwrap();
+        let results = index.search("\"quick brown\"", None).unwrap();
         assert_eq!(results.len(), 1);
     }
 
+    #[test]
+    fn test_fts_index_language_routing_and_facet() {
+        let descriptor = create_test_index_descriptor();
+        let config = FtsConfig::new().with_language_detector(Arc::new(PrefixDetector));
+        let index = FtsIndex::new(descriptor, None, &config).unwrap();
+
+        index
+            .write(&create_test_field_values(1001, "[en] the runners were running"))
+            .unwrap();
+        index
+            .write(&create_test_field_values(1002, "[fr] les coureurs couraient"))
+            .unwrap();
+        index
+            .write(&create_test_field_values(1003, "untagged running text"))
+            .unwrap();
+
+        // English stemming matches "run" against "running"/"runners"
+        assert_eq!(index.search("run", Some("en")).unwrap().len(), 1);
+        assert_eq!(index.search("coureurs", Some("fr")).unwrap().len(), 1);
+        assert_eq!(index.search("coureurs", Some("en")).unwrap().len(), 0);
+        // Without a facet restriction all languages and the default analyzer are searched
+        assert_eq!(index.search("running", None).unwrap().len(), 2);
+
+        // Matches are counted per language facet, untagged documents are left out
+        index
+            .write(&create_test_field_values(1004, "[en] running late"))
+            .unwrap();
+        assert_eq!(
+            index.language_counts("running OR coureurs").unwrap(),
+            vec![("en".to_string(), 2), ("fr".to_string(), 1)]
+        );
+        assert_eq!(index.language_counts("late").unwrap(), vec![("en".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_fts_index_language_filter_without_detector() {
+        let descriptor = create_test_index_descriptor();
+        let index = FtsIndex::new(descriptor, None, &create_test_config()).unwrap();
+        assert!(index.search("hello", Some("en")).is_err());
+        assert!(index.language_counts("hello").is_err());
+    }
+
     // ===== FtsIndex Lifecycle Tests =====
 
     #[test]
@@ -650,6 +1103,47 @@ mod tests {
         assert!(index.close().is_ok());
     }
 
+    #[test]
+    fn test_fts_index_flush() {
+        let descriptor = create_test_index_descriptor();
+        let config = create_test_config();
+        let index = FtsIndex::new(descriptor, None, &config).unwrap();
+
+        index
+            .write(&create_test_field_values(1001, "test"))
+            .unwrap();
+        assert!(index.inner.dirty.load(Ordering::Acquire));
+
+        index.flush().unwrap();
+        assert!(!index.inner.dirty.load(Ordering::Acquire));
+        // the writer stays open for further writes
+        index
+            .write(&create_test_field_values(1002, "more"))
+            .unwrap();
+        assert!(index.close().is_ok());
+    }
+
+    #[test]
+    fn test_fts_index_clear() {
+        let descriptor = create_test_index_descriptor();
+        let config = create_test_config();
+        let index = FtsIndex::new(descriptor, None, &config).unwrap();
+
+        index
+            .write(&create_test_field_values(1001, "hello world"))
+            .unwrap();
+        assert_eq!(index.search("hello", None).unwrap().len(), 1);
+
+        index.clear().unwrap();
+        assert!(index.search("hello", None).unwrap().is_empty());
+
+        // the index stays usable
+        index
+            .write(&create_test_field_values(1002, "hello again"))
+            .unwrap();
+        assert_eq!(index.search("hello", None).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_fts_index_drop() {
         let descriptor = create_test_index_descriptor();
@@ -685,7 +1179,7 @@ mod tests {
             .write(&create_test_field_values(1001, "日本語テスト"))
             .unwrap();
 
-        let results = index.search("日本語").unwrap();
+        let results = index.search("日本語", None).unwrap();
         // Note: tantivy's default tokenizer may not split CJK well
         // but the index operation should succeed
         let _ = results.len();
@@ -702,6 +1196,93 @@ mod tests {
             .unwrap();
 
         // Should be able to search (may be tokenized)
-        assert!(index.search("hello").is_ok());
+        assert!(index.search("hello", None).is_ok());
+    }
+
+    #[test]
+    fn test_fts_index_export_import_in_memory() {
+        let descriptor = create_test_index_descriptor();
+        let config = create_test_config();
+        let index = FtsIndex::new(descriptor.clone(), None, &config).unwrap();
+        index
+            .write(&create_test_field_values(1001, "hello world"))
+            .unwrap();
+        index
+            .write(&create_test_field_values(1002, "goodbye world"))
+            .unwrap();
+
+        // the buffered writes are committed by the export
+        let data = index.export().unwrap();
+        let imported = FtsIndex::import(descriptor, None, &config, &data).unwrap();
+        assert_eq!(imported.search("world", None).unwrap().len(), 2);
+        assert_eq!(imported.search("hello", None).unwrap().len(), 1);
+
+        // the imported index takes further writes
+        imported
+            .write(&create_test_field_values(1003, "hello again"))
+            .unwrap();
+        assert_eq!(imported.search("hello", None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_fts_index_export_import_on_disk() {
+        let descriptor = create_test_index_descriptor();
+        let config = create_test_config();
+        let source_dir = tempfile::tempdir().unwrap();
+        let index =
+            FtsIndex::new(descriptor.clone(), Some(source_dir.path().to_path_buf()), &config)
+                .unwrap();
+        for i in 0..20 {
+            index
+                .write(&create_test_field_values(i, &format!("document number {}", i)))
+                .unwrap();
+            // several commits leave several segments
+            if i % 5 == 4 {
+                index.flush().unwrap();
+            }
+        }
+        let data = index.export().unwrap();
+        index.close().unwrap();
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let imported =
+            FtsIndex::import(descriptor.clone(), Some(target_dir.path().to_path_buf()), &config, &data)
+                .unwrap();
+        assert_eq!(imported.search("document", None).unwrap().len(), 20);
+        imported.close().unwrap();
+
+        // the imported files are opened again like any other index
+        let reopened =
+            FtsIndex::new(descriptor, Some(target_dir.path().to_path_buf()), &config).unwrap();
+        assert_eq!(reopened.search("number", None).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn test_fts_index_import_rejects_invalid_data() {
+        let descriptor = create_test_index_descriptor();
+        let config = create_test_config();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = Some(temp_dir.path().to_path_buf());
+
+        assert!(FtsIndex::import(descriptor.clone(), None, &config, b"not an index").is_err());
+
+        let escaping = write_index_files(&[
+            ("meta.json".to_string(), b"{}".to_vec()),
+            ("../outside".to_string(), b"data".to_vec()),
+        ]);
+        assert!(FtsIndex::import(descriptor.clone(), base.clone(), &config, &escaping).is_err());
+        assert!(!temp_dir.path().join("outside").exists());
+
+        let without_meta = write_index_files(&[("segment.idx".to_string(), b"data".to_vec())]);
+        assert!(FtsIndex::import(descriptor.clone(), base.clone(), &config, &without_meta).is_err());
+
+        let data = write_index_files(&[("meta.json".to_string(), b"{}".to_vec())]);
+        assert!(FtsIndex::import(descriptor.clone(), base, &config, &data[..data.len() - 1]).is_err());
+        // a failed import leaves no index directory behind
+        let index_dir = temp_dir
+            .path()
+            .join(format!("{}_fts", derive_index_map_name(&descriptor)));
+        assert!(FtsIndex::import(descriptor, Some(temp_dir.path().to_path_buf()), &config, &data).is_err());
+        assert!(!index_dir.exists());
     }
 }
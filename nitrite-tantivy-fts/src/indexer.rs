@@ -84,37 +84,41 @@ impl FtsIndexer {
         }
 
         // Create new index
-        let base_path = self
-            .inner
-            .base_path
-            .read()
-            .map_err(|_| NitriteError::new("Lock poisoned", ErrorKind::InternalError))?;
+        let index = FtsIndex::new(
+            index_descriptor.clone(),
+            self.index_base_path()?,
+            &self.inner.config,
+        )?;
+        self.register_index(index_name, &index)?;
+        Ok(index)
+    }
 
-        let index = if self
+    /// Returns the directory the indexes are stored under, or `None` for in-memory indexes.
+    fn index_base_path(&self) -> NitriteResult<Option<PathBuf>> {
+        if self
             .inner
             .in_memory
             .load(std::sync::atomic::Ordering::Relaxed)
         {
-            FtsIndex::new(index_descriptor.clone(), None, &self.inner.config)?
-        } else {
-            FtsIndex::new(
-                index_descriptor.clone(),
-                base_path.clone(),
-                &self.inner.config,
-            )?
-        };
-
-        // Store in registry
-        {
-            let mut registry = self
-                .inner
-                .index_registry
-                .write()
-                .map_err(|_| NitriteError::new("Lock poisoned", ErrorKind::InternalError))?;
-            registry.insert(index_name, index.clone());
+            return Ok(None);
         }
 
-        Ok(index)
+        let base_path = self
+            .inner
+            .base_path
+            .read()
+            .map_err(|_| NitriteError::new("Lock poisoned", ErrorKind::InternalError))?;
+        Ok(base_path.clone())
+    }
+
+    fn register_index(&self, index_name: String, index: &FtsIndex) -> NitriteResult<()> {
+        let mut registry = self
+            .inner
+            .index_registry
+            .write()
+            .map_err(|_| NitriteError::new("Lock poisoned", ErrorKind::InternalError))?;
+        registry.insert(index_name, index.clone());
+        Ok(())
     }
 }
 
@@ -168,6 +172,25 @@ impl NitriteIndexerProvider for FtsIndexer {
         Ok(())
     }
 
+    fn clear_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.get_or_create_index(index_descriptor)?.clear()
+    }
+
+    fn destroy_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        // open the index so that its directory is removed even when it has
+        // not been used since the database was opened
+        self.get_or_create_index(index_descriptor)?;
+        self.drop_index(index_descriptor, nitrite_config)
+    }
+
     fn write_index_entry(
         &self,
         field_values: &FieldValues,
@@ -224,6 +247,61 @@ impl NitriteIndexerProvider for FtsIndexer {
 
         index.find_nitrite_ids(find_plan)
     }
+
+    fn export_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<u8>>> {
+        let index = self.get_or_create_index(index_descriptor)?;
+        index.export().map(Some)
+    }
+
+    fn import_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+        data: &[u8],
+    ) -> NitriteResult<()> {
+        // the tantivy files replace the index, so close it and remove its directory first
+        self.drop_index(index_descriptor, nitrite_config)?;
+        let index = FtsIndex::import(
+            index_descriptor.clone(),
+            self.index_base_path()?,
+            &self.inner.config,
+            data,
+        )?;
+        self.register_index(derive_index_map_name(index_descriptor), &index)
+    }
+
+    fn ranks_results(&self) -> bool {
+        // hits are returned best match first
+        true
+    }
+
+    fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        // opening the index loads the tantivy segments and starts its reader
+        self.get_or_create_index(index_descriptor)?;
+        Ok(())
+    }
+
+    fn is_loaded(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        // the tantivy index lives in its own directory, opened when first used
+        let registry = self
+            .inner
+            .index_registry
+            .read()
+            .map_err(|_| NitriteError::new("Lock poisoned", ErrorKind::InternalError))?;
+        Ok(registry.contains_key(&derive_index_map_name(index_descriptor)))
+    }
 }
 
 impl NitritePluginProvider for FtsIndexer {
@@ -238,6 +316,21 @@ impl NitritePluginProvider for FtsIndexer {
         Ok(())
     }
 
+    fn on_flush(&self) -> NitriteResult<()> {
+        // Commit the buffered batches while the store is still open
+        let registry = self
+            .inner
+            .index_registry
+            .read()
+            .map_err(|_| NitriteError::new("Lock poisoned", ErrorKind::InternalError))?;
+
+        for index in registry.values() {
+            index.flush()?;
+        }
+
+        Ok(())
+    }
+
     fn close(&self) -> NitriteResult<()> {
         // Close all indexes
         let registry = self
@@ -489,4 +582,50 @@ mod tests {
         let result = indexer.drop_index(&descriptor, &config);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_fts_indexer_destroy_index_removes_unopened_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fields = Fields::with_names(vec!["content"]).unwrap();
+        let descriptor = IndexDescriptor::new("tantivy-fts", fields, "notes");
+        let config = NitriteConfig::default();
+
+        let indexer = FtsIndexer::new();
+        indexer.inner.in_memory.store(false, std::sync::atomic::Ordering::Relaxed);
+        indexer.set_base_path(temp_dir.path().to_path_buf());
+        indexer.get_or_create_index(&descriptor).unwrap().close().unwrap();
+        let index_dir = temp_dir.path().join(format!("{}_fts", derive_index_map_name(&descriptor)));
+        assert!(index_dir.exists());
+
+        // a fresh indexer has not opened the index, as after reopening the database
+        let indexer = FtsIndexer::new();
+        indexer.inner.in_memory.store(false, std::sync::atomic::Ordering::Relaxed);
+        indexer.set_base_path(temp_dir.path().to_path_buf());
+        indexer.destroy_index(&descriptor, &config).unwrap();
+        assert!(!index_dir.exists());
+    }
+
+    #[test]
+    fn test_fts_indexer_clear_index() {
+        let indexer = FtsIndexer::new();
+        let fields = Fields::with_names(vec!["content"]).unwrap();
+        let descriptor = IndexDescriptor::new("tantivy-fts", fields.clone(), "notes");
+        let config = NitriteConfig::default();
+
+        let field_values = FieldValues::new(
+            vec![("content".to_string(), Value::String("hello world".to_string()))],
+            NitriteId::new(),
+            fields,
+        );
+        indexer
+            .write_index_entry(&field_values, &descriptor, &config)
+            .unwrap();
+        assert!(indexer.clear_index(&descriptor, &config).is_ok());
+
+        // the index stays registered for further writes
+        assert_eq!(indexer.inner.index_registry.read().unwrap().len(), 1);
+        assert!(indexer
+            .write_index_entry(&field_values, &descriptor, &config)
+            .is_ok());
+    }
 }
@@ -0,0 +1,119 @@
+//! Language detection support for multilingual FTS indexes.
+//!
+//! When a [`LanguageDetector`] is configured, every document is routed at index time to
+//! a language-specific analyzer (lowercasing + stemming for that language) and its detected
+//! language is recorded in a `lang` facet field, as `/<code>`, so searches can be restricted
+//! to one language with [`FtsFluentFilter::language`](crate::FtsFluentFilter::language) and
+//! their matches counted per language with [`FtsIndex::language_counts`](crate::index::FtsIndex::language_counts).
+
+use tantivy::schema::Facet;
+use tantivy::tokenizer::{Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer, TextAnalyzer};
+use tantivy::Index;
+
+/// Name of the facet field holding the detected language of a document.
+pub const LANGUAGE_FIELD: &str = "lang";
+
+/// Returns the facet of a language, `/<code>`.
+pub(crate) fn language_facet(code: &str) -> Facet {
+    Facet::from_path([code])
+}
+
+/// Detects the language of a piece of text at index time.
+///
+/// Implementations wrap an actual detection library (e.g. `whatlang` or `lingua`) and
+/// return an ISO 639-1 code such as `"en"` or `"fr"`. Returning `None` indexes the text
+/// with the default analyzer and without a language facet.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use nitrite_tantivy_fts::LanguageDetector;
+///
+/// struct WhatlangDetector;
+///
+/// impl LanguageDetector for WhatlangDetector {
+///     fn detect(&self, text: &str) -> Option<String> {
+///         whatlang::detect_lang(text).map(|lang| lang.code().to_string())
+///     }
+/// }
+/// ```
+pub trait LanguageDetector: Send + Sync {
+    /// Returns the ISO 639-1 code of the language of `text`, if it can be determined.
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+/// ISO 639-1 codes which have a dedicated stemming analyzer.
+pub(crate) const SUPPORTED_LANGUAGES: &[(&str, Language)] = &[
+    ("ar", Language::Arabic),
+    ("da", Language::Danish),
+    ("nl", Language::Dutch),
+    ("en", Language::English),
+    ("fi", Language::Finnish),
+    ("fr", Language::French),
+    ("de", Language::German),
+    ("el", Language::Greek),
+    ("hu", Language::Hungarian),
+    ("it", Language::Italian),
+    ("no", Language::Norwegian),
+    ("pt", Language::Portuguese),
+    ("ro", Language::Romanian),
+    ("ru", Language::Russian),
+    ("es", Language::Spanish),
+    ("sv", Language::Swedish),
+    ("ta", Language::Tamil),
+    ("tr", Language::Turkish),
+];
+
+/// Normalizes a language code as returned by a detector (`"EN"`, `" fr "`).
+pub(crate) fn normalize_language(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+/// Returns the name of the text field holding documents of the given language.
+pub(crate) fn language_text_field(code: &str) -> String {
+    format!("text_{}", code)
+}
+
+/// Returns the name of the analyzer registered for the given language.
+pub(crate) fn language_analyzer(code: &str) -> String {
+    format!("fts_{}", code)
+}
+
+/// Registers one stemming analyzer per supported language on the index.
+///
+/// Tokenizers are not persisted by tantivy, so this must run every time an index is opened.
+pub(crate) fn register_language_analyzers(index: &Index) {
+    for (code, language) in SUPPORTED_LANGUAGES {
+        let analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(RemoveLongFilter::limit(40))
+            .filter(LowerCaser)
+            .filter(Stemmer::new(*language))
+            .build();
+        index.tokenizers().register(&language_analyzer(code), analyzer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_language() {
+        assert_eq!(normalize_language(" EN "), "en");
+        assert_eq!(normalize_language("fr"), "fr");
+    }
+
+    #[test]
+    fn test_language_names() {
+        assert_eq!(language_text_field("de"), "text_de");
+        assert_eq!(language_analyzer("de"), "fts_de");
+    }
+
+    #[test]
+    fn test_supported_languages_are_unique() {
+        let mut codes: Vec<&str> = SUPPORTED_LANGUAGES.iter().map(|(c, _)| *c).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), SUPPORTED_LANGUAGES.len());
+    }
+}
@@ -12,6 +12,7 @@
 //! - **Persistent**: Index data survives process restarts
 //! - **Thread Safe**: Concurrent read/write support
 //! - **Configurable**: Tune memory usage and performance
+//! - **Multilingual**: Optional per-document analyzer selection via a pluggable language detector
 //!
 //! ## Quick Start
 //!
@@ -54,6 +55,7 @@ pub mod fluent;
 pub mod fts_module;
 pub mod index;
 pub mod indexer;
+pub mod language;
 
 // Re-export config types
 pub use config::FtsConfig;
@@ -67,6 +69,9 @@ pub use fluent::{fts_field, FtsFluentFilter};
 // Re-export indexer types
 pub use indexer::FtsIndexer;
 
+// Re-export language detection types
+pub use language::LanguageDetector;
+
 // Re-export module
 pub use fts_module::{TantivyFtsModule, TantivyFtsModuleBuilder};
 
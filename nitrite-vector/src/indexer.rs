@@ -142,6 +142,16 @@ impl NitriteIndexerProvider for VectorIndexer {
         index.destroy(&base, nitrite_config)
     }
 
+    fn clear_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        // The graph has no cheap truncate; destroy it and let the next write
+        // open a fresh one.
+        self.drop_index(index_descriptor, nitrite_config)
+    }
+
     fn write_index_entry(
         &self,
         field_values: &FieldValues,
@@ -213,6 +223,30 @@ impl NitriteIndexerProvider for VectorIndexer {
             .collect();
         Ok(ids)
     }
+
+    fn ranks_results(&self) -> bool {
+        // hits are returned nearest first
+        true
+    }
+
+    fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        // opening the index loads the graph (or maps the DiskANN file) and registers it
+        self.get_or_open(index_descriptor, nitrite_config)?;
+        Ok(())
+    }
+
+    fn is_loaded(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        let name = derive_vector_map_name(index_descriptor);
+        Ok(self.inner.registry.read().contains_key(&name))
+    }
 }
 
 impl NitritePluginProvider for VectorIndexer {
@@ -221,6 +255,15 @@ impl NitritePluginProvider for VectorIndexer {
         Ok(())
     }
 
+    fn on_flush(&self) -> NitriteResult<()> {
+        // Write the DiskANN sidecars while the store is still open
+        let registry = self.inner.registry.read();
+        for index in registry.values() {
+            index.flush()?;
+        }
+        Ok(())
+    }
+
     fn close(&self) -> NitriteResult<()> {
         // Flush any disk-resident indexes (writes the DiskANN sidecar) before
         // dropping the in-memory registry.
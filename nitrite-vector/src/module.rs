@@ -27,11 +27,12 @@
 
 use std::collections::HashMap;
 
-use nitrite::common::{NitriteModule, NitritePlugin, PluginRegistrar};
+use nitrite::common::{ModuleInfo, NitriteModule, NitritePlugin, PluginRegistrar};
 use nitrite::errors::NitriteResult;
 use nitrite::index::NitriteIndexer;
 
 use crate::distance::Metric;
+use crate::filter::VECTOR_INDEX;
 use crate::indexer::VectorIndexer;
 use crate::precision::Precision;
 use crate::vector_index::{IndexBackend, VectorIndexConfig};
@@ -67,6 +68,14 @@ impl NitriteModule for VectorModule {
     fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()> {
         plugin_registrar.register_indexer_plugin(NitriteIndexer::new(self.indexer.clone()))
     }
+
+    fn info(&self) -> ModuleInfo {
+        // released in lockstep with nitrite, so it needs the matching core version
+        ModuleInfo::new("nitrite-vector", env!("CARGO_PKG_VERSION"))
+            .min_core_version(env!("CARGO_PKG_VERSION"))
+            .indexer_type(VECTOR_INDEX)
+            .filter_op("nearest")
+    }
 }
 
 /// Fluent builder for [`VectorModule`]. Every setter maps to a
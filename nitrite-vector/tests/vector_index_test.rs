@@ -73,3 +73,21 @@ fn remove_drops_document_from_results() {
     assert!(!got.contains(&"a".to_string()));
     assert_eq!(got[0], "b");
 }
+
+#[test]
+fn clear_keeps_the_index_and_forgets_the_vectors() {
+    let (_dir, db) = temp_db(config());
+    let collection = db.collection("docs").unwrap();
+    collection
+        .create_index(vec!["embedding"], &vector_index_options())
+        .unwrap();
+
+    collection.insert(doc_with_vector("a", &[1.0, 0.0, 0.0])).unwrap();
+    collection.insert(doc_with_vector("b", &[0.0, 1.0, 0.0])).unwrap();
+    collection.clear().unwrap();
+    assert!(collection.has_index(vec!["embedding"]).unwrap());
+
+    collection.insert(doc_with_vector("c", &[0.9, 0.1, 0.0])).unwrap();
+    let filter = vector_field("embedding").nearest(vec![1.0, 0.0, 0.0], 3).build();
+    assert_eq!(names(&collection, filter), vec!["c"]);
+}
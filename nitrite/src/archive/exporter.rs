@@ -0,0 +1,217 @@
+use super::manifest::{
+    ArchiveManifest, ArchivedCollection, ArchivedIndex, ARCHIVE_FORMAT, ARCHIVE_VERSION,
+};
+use super::{encode_hex, encoding_error, ArchiveRecord};
+use crate::common::AttributeAware;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::all;
+use crate::index::IndexExport;
+use crate::nitrite::Nitrite;
+use crate::{get_current_time_or_zero, PersistentCollection, NITRITE_VERSION};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The zstd compression level used when none is configured.
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// Writes collections of a database to a zstd-compressed archive.
+///
+/// The archive starts with an [`ArchiveManifest`] describing the exported collections,
+/// their indexes and attributes, followed by every document of those collections.
+/// Documents are read through the collection, so collection processors are applied just
+/// as they would be for any other read.
+///
+/// The data of every index that can be exported, like a full-text index, follows the
+/// documents of its collection, so an import does not have to rebuild it; see
+/// [`include_index_data`](ArchiveExporter::include_index_data).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::archive::ArchiveExporter;
+///
+/// let manifest = ArchiveExporter::new(&db)
+///     .collections(["orders", "customers"])
+///     .export_to_file("backup.nitrite.zst")?;
+/// ```
+pub struct ArchiveExporter {
+    nitrite: Nitrite,
+    collections: Option<Vec<String>>,
+    compression_level: i32,
+    include_index_data: bool,
+}
+
+impl ArchiveExporter {
+    /// Creates an exporter for all collections of `nitrite`.
+    pub fn new(nitrite: &Nitrite) -> Self {
+        ArchiveExporter {
+            nitrite: nitrite.clone(),
+            collections: None,
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+            include_index_data: true,
+        }
+    }
+
+    /// Restricts the export to the given collections.
+    pub fn collections<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.collections = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the zstd compression level (1-22, default [`DEFAULT_COMPRESSION_LEVEL`]).
+    pub fn compression_level(mut self, level: i32) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// Sets whether the data of exportable indexes is written to the archive (default
+    /// `true`).
+    ///
+    /// Index data makes the archive larger but spares the import rebuilding those
+    /// indexes from the documents, which can take long for a full-text index. An index
+    /// that is being built or is stale is left out and rebuilt on import.
+    pub fn include_index_data(mut self, include: bool) -> Self {
+        self.include_index_data = include;
+        self
+    }
+
+    /// Exports to a new file at `path`, replacing any existing file.
+    pub fn export_to_file(&self, path: impl AsRef<Path>) -> NitriteResult<ArchiveManifest> {
+        let file = File::create(path)?;
+        self.export_to(BufWriter::new(file))
+    }
+
+    /// Exports to `writer` and returns the manifest that was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if a selected collection does not exist.
+    pub fn export_to<W: Write>(&self, writer: W) -> NitriteResult<ArchiveManifest> {
+        let names = self.collection_names()?;
+
+        let mut collections = Vec::with_capacity(names.len());
+        let mut index_exports = Vec::with_capacity(names.len());
+        for name in &names {
+            let collection = self.nitrite.collection(name)?;
+            let mut indexes = Vec::new();
+            let mut exports = Vec::new();
+            for descriptor in collection.list_indexes()? {
+                let fields = descriptor.index_fields().field_names();
+                let export = self.export_index_data(name, &fields)?;
+                indexes.push(ArchivedIndex {
+                    fields,
+                    index_type: descriptor.index_type(),
+                    has_data: export.is_some(),
+                    sparse: descriptor.is_sparse(),
+                    case_insensitive: descriptor.is_case_insensitive(),
+                    compressed: descriptor.is_compressed(),
+                });
+                exports.extend(export);
+            }
+            index_exports.push(exports);
+            collections.push(ArchivedCollection {
+                name: name.clone(),
+                indexes,
+                attributes: collection.get_attributes()?.to_document(),
+            });
+        }
+
+        let manifest = ArchiveManifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            nitrite_version: NITRITE_VERSION.to_string(),
+            created_at: get_current_time_or_zero(),
+            collections,
+        };
+
+        let mut encoder =
+            zstd::stream::write::Encoder::new(writer, self.compression_level)?;
+        serde_json::to_writer(&mut encoder, &manifest).map_err(encoding_error)?;
+        encoder.write_all(b"\n")?;
+
+        for (name, exports) in names.iter().zip(index_exports) {
+            let collection = self.nitrite.collection(name)?;
+            for document in collection.find(all())? {
+                let record = ArchiveRecord {
+                    collection: name.clone(),
+                    document: Some(document?),
+                    index: None,
+                };
+                serde_json::to_writer(&mut encoder, &record).map_err(encoding_error)?;
+                encoder.write_all(b"\n")?;
+            }
+
+            for export in exports {
+                let mut bytes = Vec::new();
+                export.write_to(&mut bytes)?;
+                let record = ArchiveRecord {
+                    collection: name.clone(),
+                    document: None,
+                    index: Some(encode_hex(&bytes)),
+                };
+                serde_json::to_writer(&mut encoder, &record).map_err(encoding_error)?;
+                encoder.write_all(b"\n")?;
+            }
+        }
+
+        encoder.finish()?.flush()?;
+        log::info!(
+            "Exported {} collection(s) to archive",
+            manifest.collections.len()
+        );
+        Ok(manifest)
+    }
+
+    /// Exports the data of an index, or returns `None` if its indexer cannot export it
+    /// or it is not up to date.
+    fn export_index_data(&self, name: &str, fields: &[String]) -> NitriteResult<Option<IndexExport>> {
+        if !self.include_index_data {
+            return Ok(None);
+        }
+
+        let collection = self.nitrite.collection(name)?;
+        let field_names = fields.iter().map(String::as_str).collect();
+        match collection.export_index(field_names) {
+            Ok(export) => Ok(Some(export)),
+            Err(err) if err.kind() == &ErrorKind::InvalidOperation => Ok(None),
+            Err(err) if err.kind() == &ErrorKind::IndexingError => {
+                log::warn!(
+                    "Index on {:?} of collection {} is not archived and will be rebuilt on import: {}",
+                    fields,
+                    name,
+                    err
+                );
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn collection_names(&self) -> NitriteResult<Vec<String>> {
+        let existing = self.nitrite.list_collection_names()?;
+        match &self.collections {
+            Some(selected) => {
+                for name in selected {
+                    if !existing.contains(name) {
+                        log::error!("Collection {} does not exist", name);
+                        return Err(NitriteError::new(
+                            &format!("Collection {} does not exist", name),
+                            ErrorKind::NotFound,
+                        ));
+                    }
+                }
+                Ok(selected.clone())
+            }
+            None => {
+                let mut names: Vec<String> = existing.into_iter().collect();
+                names.sort();
+                Ok(names)
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use super::manifest::{
+    ArchiveManifest, ArchivedCollection, ArchivedIndex, ARCHIVE_FORMAT, ARCHIVE_VERSION,
+};
+use super::{decode_hex, encoding_error, ArchiveRecord};
+use crate::collection::{Document, NitriteCollection};
+use crate::common::{modified_field, revision_field, source_field, AttributeAware, DOC_ID};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::by_id;
+use crate::index::IndexExport;
+use crate::nitrite::Nitrite;
+use crate::PersistentCollection;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// What to do when an archived document has the same `_id` as a document that already
+/// exists in the target collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the existing document and ignore the archived one.
+    #[default]
+    Skip,
+    /// Replace the existing document with the archived one.
+    Overwrite,
+    /// Copy the fields of the archived document into the existing document.
+    Merge,
+}
+
+/// Counts of what an import did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportSummary {
+    /// Documents that did not exist in the target and were inserted.
+    pub inserted: u64,
+    /// Existing documents replaced under [`ConflictPolicy::Overwrite`].
+    pub overwritten: u64,
+    /// Existing documents updated under [`ConflictPolicy::Merge`].
+    pub merged: u64,
+    /// Archived documents ignored under [`ConflictPolicy::Skip`].
+    pub skipped: u64,
+    /// Indexes restored from their archived data instead of being rebuilt.
+    pub restored_indexes: u64,
+}
+
+/// Restores collections from an archive written by [`super::ArchiveExporter`].
+///
+/// By default every collection in the archive is restored under its original name and
+/// documents that already exist in the target are left untouched. Missing indexes are
+/// created before documents are inserted, and archived attributes are copied for keys
+/// the target collection does not have yet.
+///
+/// A missing index whose data is in the archive is instead imported from that data once
+/// the documents are restored. The data is only used if the restored collection holds
+/// exactly the archived documents (see
+/// [`NitriteCollectionProvider::import_index`](crate::collection::NitriteCollectionProvider::import_index));
+/// otherwise, or if it cannot be imported, the index is built from the documents.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::archive::{ArchiveImporter, ConflictPolicy};
+///
+/// let summary = ArchiveImporter::new(&db)
+///     .collections(["orders"])
+///     .rename("orders", "orders_restored")
+///     .conflict_policy(ConflictPolicy::Overwrite)
+///     .import_from_file("backup.nitrite.zst")?;
+/// ```
+pub struct ArchiveImporter {
+    nitrite: Nitrite,
+    collections: Option<HashSet<String>>,
+    renames: HashMap<String, String>,
+    conflict_policy: ConflictPolicy,
+}
+
+impl ArchiveImporter {
+    /// Creates an importer that restores into `nitrite`.
+    pub fn new(nitrite: &Nitrite) -> Self {
+        ArchiveImporter {
+            nitrite: nitrite.clone(),
+            collections: None,
+            renames: HashMap::new(),
+            conflict_policy: ConflictPolicy::default(),
+        }
+    }
+
+    /// Restricts the import to the given archived collections.
+    pub fn collections<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.collections = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Restores the archived collection `from` into the collection `to`.
+    pub fn rename(mut self, from: &str, to: &str) -> Self {
+        self.renames.insert(from.to_string(), to.to_string());
+        self
+    }
+
+    /// Sets how documents that already exist in the target are handled.
+    pub fn conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Imports from the archive file at `path`.
+    pub fn import_from_file(&self, path: impl AsRef<Path>) -> NitriteResult<ImportSummary> {
+        let file = File::open(path)?;
+        self.import_from(file)
+    }
+
+    /// Imports from an archive read from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if a selected collection is not in the archive, and an
+    /// `EncodingError` if the archive is malformed or was written by a newer format
+    /// version.
+    pub fn import_from<R: Read>(&self, reader: R) -> NitriteResult<ImportSummary> {
+        let mut lines = BufReader::new(zstd::stream::read::Decoder::new(reader)?).lines();
+
+        let manifest = match lines.next() {
+            Some(line) => {
+                serde_json::from_str::<ArchiveManifest>(&line?).map_err(encoding_error)?
+            }
+            None => {
+                log::error!("Archive is empty");
+                return Err(NitriteError::new("Archive is empty", ErrorKind::EncodingError));
+            }
+        };
+        validate_manifest(&manifest)?;
+
+        let mut targets = HashMap::new();
+        let mut deferred = HashMap::new();
+        for archived in &manifest.collections {
+            if self.is_selected(&archived.name) {
+                let (collection, indexes) = self.prepare_collection(archived)?;
+                targets.insert(archived.name.clone(), collection);
+                deferred.insert(archived.name.clone(), indexes);
+            }
+        }
+        if let Some(selected) = &self.collections {
+            if let Some(missing) = selected.iter().find(|name| !targets.contains_key(*name)) {
+                log::error!("Collection {} is not in the archive", missing);
+                return Err(NitriteError::new(
+                    &format!("Collection {} is not in the archive", missing),
+                    ErrorKind::NotFound,
+                ));
+            }
+        }
+
+        let mut summary = ImportSummary::default();
+        for line in lines {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let record: ArchiveRecord = serde_json::from_str(&line).map_err(encoding_error)?;
+            let Some(collection) = targets.get(&record.collection) else {
+                continue;
+            };
+            if let Some(document) = record.document {
+                self.restore_document(collection, document, &mut summary)?;
+            }
+            if let (Some(data), Some(indexes)) = (record.index, deferred.get_mut(&record.collection)) {
+                if restore_index(collection, &data, indexes) {
+                    summary.restored_indexes += 1;
+                }
+            }
+        }
+
+        // the indexes whose data was missing or unusable are built from the documents
+        for (name, indexes) in deferred {
+            if let Some(collection) = targets.get(&name) {
+                for index in indexes {
+                    let fields: Vec<&str> = index.fields.iter().map(String::as_str).collect();
+                    collection.create_index(fields, &index.index_options())?;
+                }
+            }
+        }
+
+        log::info!(
+            "Imported {} collection(s) from archive: {:?}",
+            targets.len(),
+            summary
+        );
+        Ok(summary)
+    }
+
+    fn is_selected(&self, name: &str) -> bool {
+        self.collections
+            .as_ref()
+            .is_none_or(|selected| selected.contains(name))
+    }
+
+    /// Opens the target collection of `archived` and creates its missing indexes, but
+    /// for those with archived data, which are returned to be restored after the
+    /// documents.
+    fn prepare_collection(
+        &self,
+        archived: &ArchivedCollection,
+    ) -> NitriteResult<(NitriteCollection, Vec<ArchivedIndex>)> {
+        let target = self
+            .renames
+            .get(&archived.name)
+            .map(String::as_str)
+            .unwrap_or(&archived.name);
+        let collection = self.nitrite.collection(target)?;
+
+        let mut deferred = Vec::new();
+        for index in &archived.indexes {
+            let fields: Vec<&str> = index.fields.iter().map(String::as_str).collect();
+            if collection.has_index(fields.clone())? {
+                continue;
+            }
+            if index.has_data {
+                deferred.push(index.clone());
+            } else {
+                collection.create_index(fields, &index.index_options())?;
+            }
+        }
+
+        let mut attributes = collection.get_attributes()?;
+        let mut changed = false;
+        for (key, value) in archived.attributes.iter() {
+            if !attributes.has_key(&key) {
+                attributes.put(&key, value);
+                changed = true;
+            }
+        }
+        if changed {
+            collection.set_attributes(attributes)?;
+        }
+        Ok((collection, deferred))
+    }
+
+    fn restore_document(
+        &self,
+        collection: &NitriteCollection,
+        mut document: Document,
+        summary: &mut ImportSummary,
+    ) -> NitriteResult<()> {
+        let id = document.id()?;
+        if collection.get_by_id(&id)?.is_none() {
+            collection.insert(document)?;
+            summary.inserted += 1;
+            return Ok(());
+        }
+
+        match self.conflict_policy {
+            ConflictPolicy::Skip => summary.skipped += 1,
+            ConflictPolicy::Overwrite => {
+                collection.remove(by_id(id), true)?;
+                collection.insert(document)?;
+                summary.overwritten += 1;
+            }
+            ConflictPolicy::Merge => {
+                for field in [DOC_ID.to_string(), revision_field(), modified_field(), source_field()] {
+                    document.remove(&field)?;
+                }
+                collection.update_by_id(&id, &document, false)?;
+                summary.merged += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Imports archived index data into `collection` if it belongs to one of the deferred
+/// `indexes`, and returns whether it was imported. An index whose data cannot be used
+/// stays deferred, to be built from the documents.
+fn restore_index(collection: &NitriteCollection, data: &str, indexes: &mut Vec<ArchivedIndex>) -> bool {
+    let export = match decode_hex(data).and_then(|bytes| IndexExport::read_from(bytes.as_slice())) {
+        Ok(export) => export,
+        Err(err) => {
+            log::warn!("Ignoring invalid index data of collection {}: {}", collection.name(), err);
+            return false;
+        }
+    };
+
+    let Some(position) = indexes.iter().position(|index| {
+        index.fields == export.field_names() && index.index_type == export.index_type()
+    }) else {
+        return false;
+    };
+
+    match collection.import_index(&export) {
+        Ok(()) => {
+            indexes.remove(position);
+            true
+        }
+        Err(err) => {
+            log::warn!(
+                "Rebuilding index on {:?} of collection {} instead of importing its data: {}",
+                export.field_names(),
+                collection.name(),
+                err
+            );
+            false
+        }
+    }
+}
+
+fn validate_manifest(manifest: &ArchiveManifest) -> NitriteResult<()> {
+    if manifest.format != ARCHIVE_FORMAT {
+        log::error!("Not a Nitrite archive: format {}", manifest.format);
+        return Err(NitriteError::new(
+            &format!("Not a Nitrite archive: format {}", manifest.format),
+            ErrorKind::EncodingError,
+        ));
+    }
+    if manifest.version > ARCHIVE_VERSION {
+        log::error!("Unsupported archive version {}", manifest.version);
+        return Err(NitriteError::new(
+            &format!(
+                "Unsupported archive version {} (supported up to {})",
+                manifest.version, ARCHIVE_VERSION
+            ),
+            ErrorKind::EncodingError,
+        ));
+    }
+    Ok(())
+}
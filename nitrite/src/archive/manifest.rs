@@ -0,0 +1,80 @@
+use crate::collection::Document;
+use crate::index::IndexOptions;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a Nitrite archive in its manifest.
+pub const ARCHIVE_FORMAT: &str = "nitrite-archive";
+
+/// The archive format version written by this release.
+pub const ARCHIVE_VERSION: u32 = 2;
+
+/// The manifest at the head of a Nitrite archive.
+///
+/// The manifest describes what the archive contains so an import can select and
+/// prepare collections before reading any documents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Always [`ARCHIVE_FORMAT`].
+    pub format: String,
+    /// The archive format version, see [`ARCHIVE_VERSION`].
+    pub version: u32,
+    /// The Nitrite version that wrote the archive.
+    pub nitrite_version: String,
+    /// Creation time of the archive in milliseconds since the Unix epoch.
+    pub created_at: u128,
+    /// The collections contained in the archive, in the order they are stored.
+    pub collections: Vec<ArchivedCollection>,
+}
+
+impl ArchiveManifest {
+    /// Returns the archived collection with the given name, if present.
+    pub fn collection(&self, name: &str) -> Option<&ArchivedCollection> {
+        self.collections.iter().find(|collection| collection.name == name)
+    }
+}
+
+/// Description of one collection in an archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedCollection {
+    /// The collection name at export time.
+    pub name: String,
+    /// The indexes defined on the collection.
+    pub indexes: Vec<ArchivedIndex>,
+    /// The collection attributes (see [`crate::common::AttributeAware`]).
+    pub attributes: Document,
+}
+
+/// Description of one index in an archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedIndex {
+    /// The indexed fields, in index order.
+    pub fields: Vec<String>,
+    /// The index type, as given by [`crate::index::IndexOptions::index_type`].
+    pub index_type: String,
+    /// Whether the archive holds the data of the index, see
+    /// [`crate::index::IndexExport`]. Archives of version 1 never do.
+    #[serde(default)]
+    pub has_data: bool,
+    /// Whether the index leaves out the documents without the indexed fields, see
+    /// [`IndexOptions::sparse`].
+    #[serde(default)]
+    pub sparse: bool,
+    /// Whether the index compares string keys ignoring case, see
+    /// [`IndexOptions::case_insensitive`].
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Whether the index stores its ids as compressed posting lists, see
+    /// [`IndexOptions::compressed`].
+    #[serde(default)]
+    pub compressed: bool,
+}
+
+impl ArchivedIndex {
+    /// Returns the options to create the index with.
+    pub fn index_options(&self) -> IndexOptions {
+        IndexOptions::new(&self.index_type)
+            .sparse(self.sparse)
+            .case_insensitive(self.case_insensitive)
+            .compressed(self.compressed)
+    }
+}
@@ -0,0 +1,358 @@
+//! Compressed export and import archives.
+//!
+//! An archive is a zstd-compressed stream of newline-delimited JSON. The first line is an
+//! [`ArchiveManifest`] listing the exported collections with their indexes and
+//! attributes; every following line holds one document and the name of the collection it
+//! belongs to. The documents of a collection may be followed by the data of its
+//! exportable indexes, one line per index (see [`crate::index::IndexExport`]).
+//!
+//! - [`ArchiveExporter`] writes all or selected collections of a database.
+//! - [`ArchiveImporter`] restores all or selected collections, optionally under a
+//!   different name, resolving `_id` conflicts with a [`ConflictPolicy`].
+//!
+//! This module requires the `archive` feature.
+
+mod exporter;
+mod importer;
+mod manifest;
+
+pub use exporter::*;
+pub use importer::*;
+pub use manifest::*;
+
+use crate::collection::Document;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use serde::{Deserialize, Serialize};
+
+/// One archived document, or the hex-encoded data of one archived index.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArchiveRecord {
+    pub(crate) collection: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) document: Option<Document>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) index: Option<String>,
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push(DIGITS[usize::from(byte >> 4)] as char);
+        hex.push(DIGITS[usize::from(byte & 0xf)] as char);
+    }
+    hex
+}
+
+pub(crate) fn decode_hex(hex: &str) -> NitriteResult<Vec<u8>> {
+    fn digit(c: u8) -> Option<u8> {
+        match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    let hex = hex.as_bytes();
+    if !hex.len().is_multiple_of(2) {
+        log::error!("Invalid archive data: odd length of hex data");
+        return Err(NitriteError::new(
+            "Invalid archive data: odd length of hex data",
+            ErrorKind::EncodingError,
+        ));
+    }
+    hex.chunks(2)
+        .map(|pair| match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => {
+                log::error!("Invalid archive data: invalid hex digit");
+                Err(NitriteError::new(
+                    "Invalid archive data: invalid hex digit",
+                    ErrorKind::EncodingError,
+                ))
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn encoding_error(err: serde_json::Error) -> NitriteError {
+    log::error!("Invalid archive data: {}", err);
+    NitriteError::new(
+        &format!("Invalid archive data: {}", err),
+        ErrorKind::EncodingError,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{AttributeAware, Value};
+    use crate::doc;
+    use crate::filter::field;
+    use crate::index::{non_unique_index, unique_index};
+    use crate::nitrite::Nitrite;
+    use crate::PersistentCollection;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    fn export(db: &Nitrite, collections: &[&str]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let exporter = ArchiveExporter::new(db);
+        let exporter = if collections.is_empty() {
+            exporter
+        } else {
+            exporter.collections(collections.iter().copied())
+        };
+        exporter.export_to(&mut buffer).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let source = setup_nitrite();
+        let users = source.collection("users").unwrap();
+        users.create_index(vec!["email"], &unique_index()).unwrap();
+        users
+            .set_attribute("schema_hash", Value::from("abc123"))
+            .unwrap();
+        users.insert(doc! { email: "a@x.io", age: 30 }).unwrap();
+        users.insert(doc! { email: "b@x.io", tags: ["x", "y"] }).unwrap();
+
+        let archive = export(&source, &[]);
+
+        let target = setup_nitrite();
+        let summary = ArchiveImporter::new(&target)
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert_eq!(summary.inserted, 2);
+
+        let restored = target.collection("users").unwrap();
+        assert_eq!(restored.size().unwrap(), 2);
+        assert!(restored.has_index(vec!["email"]).unwrap());
+        assert_eq!(
+            restored.get_attribute("schema_hash").unwrap(),
+            Some(Value::from("abc123"))
+        );
+        let doc = restored
+            .find(field("email").eq("b@x.io"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(doc.get("tags").unwrap(), Value::from(vec!["x", "y"]));
+    }
+
+    #[test]
+    fn test_sparse_index_roundtrip() {
+        let source = setup_nitrite();
+        let users = source.collection("users").unwrap();
+        users
+            .create_index(vec!["email"], &unique_index().sparse(true))
+            .unwrap();
+        users.insert(doc! { email: "a@x.io" }).unwrap();
+        users.insert(doc! { name: "no email" }).unwrap();
+        users.insert(doc! { name: "no email either" }).unwrap();
+
+        let archive = export(&source, &[]);
+        let target = setup_nitrite();
+        let summary = ArchiveImporter::new(&target)
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert_eq!(summary.inserted, 3);
+
+        let restored = target.collection("users").unwrap();
+        let index = restored.list_indexes().unwrap().pop().unwrap();
+        assert!(index.is_sparse());
+        assert_eq!(restored.size().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_case_insensitive_index_roundtrip() {
+        let source = setup_nitrite();
+        let users = source.collection("users").unwrap();
+        users
+            .create_index(vec!["email"], &unique_index().case_insensitive(true))
+            .unwrap();
+        users.insert(doc! { email: "Foo@x.io" }).unwrap();
+
+        let archive = export(&source, &[]);
+        let target = setup_nitrite();
+        ArchiveImporter::new(&target)
+            .import_from(archive.as_slice())
+            .unwrap();
+
+        let restored = target.collection("users").unwrap();
+        let index = restored.list_indexes().unwrap().pop().unwrap();
+        assert!(index.is_case_insensitive());
+        let duplicate = restored.insert(doc! { email: "FOO@X.IO" });
+        assert!(matches!(
+            duplicate.err().map(|e| e.kind().clone()),
+            Some(ErrorKind::UniqueConstraintViolation)
+        ));
+    }
+
+    #[test]
+    fn test_compressed_index_roundtrip() {
+        let source = setup_nitrite();
+        let orders = source.collection("orders").unwrap();
+        orders
+            .create_index(vec!["status"], &non_unique_index().compressed(true))
+            .unwrap();
+        for n in 0..10 {
+            orders.insert(doc! { n: n, status: "open" }).unwrap();
+        }
+
+        let archive = export(&source, &[]);
+        let target = setup_nitrite();
+        ArchiveImporter::new(&target)
+            .import_from(archive.as_slice())
+            .unwrap();
+
+        let restored = target.collection("orders").unwrap();
+        let index = restored.list_indexes().unwrap().pop().unwrap();
+        assert!(index.is_compressed());
+        assert_eq!(restored.find(field("status").eq("open")).unwrap().count(), 10);
+    }
+
+    #[test]
+    fn test_export_selected_collections_and_rename() {
+        let source = setup_nitrite();
+        source.collection("a").unwrap().insert(doc! { n: 1 }).unwrap();
+        source.collection("b").unwrap().insert(doc! { n: 2 }).unwrap();
+
+        let archive = export(&source, &["a"]);
+        let manifest_names: Vec<String> = ArchiveExporter::new(&source)
+            .collections(["a"])
+            .export_to(Vec::new())
+            .unwrap()
+            .collections
+            .into_iter()
+            .map(|collection| collection.name)
+            .collect();
+        assert_eq!(manifest_names, vec!["a".to_string()]);
+
+        let target = setup_nitrite();
+        ArchiveImporter::new(&target)
+            .rename("a", "a_restored")
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert!(target.has_collection("a_restored").unwrap());
+        assert!(!target.has_collection("a").unwrap());
+        assert!(!target.has_collection("b").unwrap());
+        assert_eq!(target.collection("a_restored").unwrap().size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_export_unknown_collection() {
+        let source = setup_nitrite();
+        let result = ArchiveExporter::new(&source)
+            .collections(["missing"])
+            .export_to(Vec::new());
+        assert!(matches!(result.err().map(|e| e.kind().clone()), Some(ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn test_import_unknown_collection() {
+        let source = setup_nitrite();
+        source.collection("a").unwrap().insert(doc! { n: 1 }).unwrap();
+        let archive = export(&source, &[]);
+
+        let target = setup_nitrite();
+        let result = ArchiveImporter::new(&target)
+            .collections(["b"])
+            .import_from(archive.as_slice());
+        assert!(matches!(result.err().map(|e| e.kind().clone()), Some(ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn test_import_conflict_policies() {
+        let db = setup_nitrite();
+        let col = db.collection("items").unwrap();
+        col.insert(doc! { name: "archived", qty: 1 }).unwrap();
+        let archive = export(&db, &[]);
+
+        let mut doc = col.find(field("name").eq("archived")).unwrap().next().unwrap().unwrap();
+        let id = doc.id().unwrap();
+        col.update_by_id(&id, &doc! { name: "local", extra: true }, false)
+            .unwrap();
+
+        let summary = ArchiveImporter::new(&db)
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(col.get_by_id(&id).unwrap().unwrap().get("name").unwrap(), Value::from("local"));
+
+        let summary = ArchiveImporter::new(&db)
+            .conflict_policy(ConflictPolicy::Merge)
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert_eq!(summary.merged, 1);
+        let merged = col.get_by_id(&id).unwrap().unwrap();
+        assert_eq!(merged.get("name").unwrap(), Value::from("archived"));
+        assert_eq!(merged.get("extra").unwrap(), Value::from(true));
+
+        let summary = ArchiveImporter::new(&db)
+            .conflict_policy(ConflictPolicy::Overwrite)
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert_eq!(summary.overwritten, 1);
+        let overwritten = col.get_by_id(&id).unwrap().unwrap();
+        assert_eq!(overwritten.get("name").unwrap(), Value::from("archived"));
+        assert!(overwritten.get("extra").unwrap().is_null());
+        assert_eq!(col.size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let hex = encode_hex(&bytes);
+        assert_eq!(&hex[..8], "00010203");
+        assert_eq!(decode_hex(&hex).unwrap(), bytes);
+        assert_eq!(decode_hex("0A").unwrap(), vec![10]);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_index_data_only_for_exportable_indexes() {
+        let source = setup_nitrite();
+        let users = source.collection("users").unwrap();
+        users.create_index(vec!["email"], &unique_index()).unwrap();
+        users.insert(doc! { email: "a@x.io" }).unwrap();
+
+        let mut archive = Vec::new();
+        let manifest = ArchiveExporter::new(&source).export_to(&mut archive).unwrap();
+        assert_eq!(manifest.version, ARCHIVE_VERSION);
+        assert!(!manifest.collection("users").unwrap().indexes[0].has_data);
+
+        let target = setup_nitrite();
+        let summary = ArchiveImporter::new(&target)
+            .import_from(archive.as_slice())
+            .unwrap();
+        assert_eq!(summary.restored_indexes, 0);
+        assert!(target.collection("users").unwrap().has_index(vec!["email"]).unwrap());
+    }
+
+    #[test]
+    fn test_index_of_version_1_manifest_has_no_data() {
+        let index: ArchivedIndex =
+            serde_json::from_str(r#"{"fields":["email"],"index_type":"Unique"}"#).unwrap();
+        assert!(!index.has_data);
+        assert!(!index.sparse);
+        assert!(!index.case_insensitive);
+        assert!(!index.compressed);
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_archive() {
+        let db = setup_nitrite();
+        let compressed = zstd::stream::encode_all(&b"{\"format\":\"other\"}\n"[..], 3).unwrap();
+        assert!(ArchiveImporter::new(&db).import_from(compressed.as_slice()).is_err());
+
+        let empty = zstd::stream::encode_all(&b""[..], 3).unwrap();
+        assert!(ArchiveImporter::new(&db).import_from(empty.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,485 @@
+use crate::{
+    collection::{CollectionOptions, Document, EventOrigin, NitriteCollection, NitriteId},
+    common::{get_current_time_or_zero, Value, AUDIT_COLLECTION},
+    errors::NitriteResult,
+    filter::{all, field, is_and_filter, is_not_filter, is_or_filter, Filter},
+};
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const TIMESTAMP: &str = "timestamp";
+const OPERATION: &str = "operation";
+const COLLECTION: &str = "collection";
+const SESSION: &str = "session";
+const TRANSACTION: &str = "transaction";
+const FILTER: &str = "filter";
+const IDS: &str = "ids";
+
+/// Text written in place of a redacted value in a filter summary.
+pub const REDACTED: &str = "<redacted>";
+
+/// Options of the audit log, set with
+/// [`Nitrite::enable_audit`](crate::nitrite::Nitrite::enable_audit).
+///
+/// Redaction rules apply to the filter summaries: the value a filter compares a redacted
+/// field with is replaced by [`REDACTED`], so that a query such as `ssn == "078-05-1120"`
+/// is logged as `(ssn == <redacted>)`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// db.enable_audit(
+///     AuditOptions::new()
+///         .max_entries(1_000_000)
+///         .include_reads(true)
+///         .redact("ssn")
+///         .redact("card.number"),
+/// )?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditOptions {
+    max_entries: u64,
+    include_reads: bool,
+    redacted_fields: HashSet<String>,
+    redact_all_values: bool,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        AuditOptions {
+            max_entries: 100_000,
+            include_reads: false,
+            redacted_fields: HashSet::new(),
+            redact_all_values: false,
+        }
+    }
+}
+
+impl AuditOptions {
+    /// Creates the default options: the last 100 000 writes are kept, reads are not
+    /// recorded and filter summaries are not redacted.
+    pub fn new() -> Self {
+        AuditOptions::default()
+    }
+
+    /// Sets how many entries the audit log keeps (at least 1). Once it is full, every
+    /// new entry evicts the oldest one.
+    pub fn max_entries(mut self, max_entries: u64) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Sets whether `find`, `count` and `get_by_id` are recorded too.
+    pub fn include_reads(mut self, include_reads: bool) -> Self {
+        self.include_reads = include_reads;
+        self
+    }
+
+    /// Redacts the values compared with `field` in the filter summaries.
+    pub fn redact(mut self, field: &str) -> Self {
+        self.redacted_fields.insert(field.to_string());
+        self
+    }
+
+    /// Redacts the values of every field in the filter summaries, keeping only the
+    /// shape of the filters.
+    pub fn redact_all_values(mut self, redact_all_values: bool) -> Self {
+        self.redact_all_values = redact_all_values;
+        self
+    }
+
+    /// Returns how many entries the audit log keeps.
+    pub fn get_max_entries(&self) -> u64 {
+        self.max_entries
+    }
+
+    /// Returns whether reads are recorded.
+    pub fn get_include_reads(&self) -> bool {
+        self.include_reads
+    }
+
+    /// Checks whether the values compared with `field` are redacted.
+    pub fn is_redacted(&self, field: &str) -> bool {
+        self.redact_all_values || self.redacted_fields.contains(field)
+    }
+}
+
+/// One operation recorded in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// When the operation completed, in milliseconds since the Unix epoch.
+    pub timestamp: u128,
+    /// The operation, such as `insert`, `update`, `remove` or `find`.
+    pub operation: String,
+    /// The collection or repository the operation ran on.
+    pub collection: String,
+    /// The session of the transaction the write was committed by, if any.
+    pub session_id: Option<String>,
+    /// The transaction the write was committed by, if any.
+    pub transaction_id: Option<String>,
+    /// The filter of the operation as text, after redaction, if it took one.
+    pub filter: Option<String>,
+    /// The ids of the documents written, or read by id.
+    pub affected_ids: Vec<NitriteId>,
+}
+
+impl AuditEntry {
+    fn to_document(&self) -> NitriteResult<Document> {
+        let mut document = Document::new();
+        document.put(TIMESTAMP, Value::U128(self.timestamp))?;
+        document.put(OPERATION, self.operation.as_str())?;
+        document.put(COLLECTION, self.collection.as_str())?;
+        document.put(SESSION, optional_text(&self.session_id))?;
+        document.put(TRANSACTION, optional_text(&self.transaction_id))?;
+        document.put(FILTER, optional_text(&self.filter))?;
+        let ids = self.affected_ids.iter().copied().map(Value::NitriteId).collect();
+        document.put(IDS, Value::Array(ids))?;
+        Ok(document)
+    }
+
+    fn from_document(document: &Document) -> NitriteResult<Self> {
+        let text = |key: &str| -> NitriteResult<Option<String>> {
+            Ok(document.get(key)?.as_string().cloned())
+        };
+        let affected_ids = match document.get(IDS)? {
+            Value::Array(ids) => ids.iter().filter_map(|id| id.as_nitrite_id().copied()).collect(),
+            _ => Vec::new(),
+        };
+        Ok(AuditEntry {
+            timestamp: document.get(TIMESTAMP)?.as_u128().copied().unwrap_or_default(),
+            operation: text(OPERATION)?.unwrap_or_default(),
+            collection: text(COLLECTION)?.unwrap_or_default(),
+            session_id: text(SESSION)?,
+            transaction_id: text(TRANSACTION)?,
+            filter: text(FILTER)?,
+            affected_ids,
+        })
+    }
+}
+
+fn optional_text(text: &Option<String>) -> Value {
+    text.as_deref().map(Value::from).unwrap_or(Value::Null)
+}
+
+/// Criteria selecting entries of the audit log. Every criterion set must match.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let removals = db.audit_log()?.entries(
+///     &AuditQuery::new().collection("patients").operation("remove").since(yesterday),
+/// )?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    collection: Option<String>,
+    operation: Option<String>,
+    session_id: Option<String>,
+    affected_id: Option<NitriteId>,
+    since: Option<u128>,
+    until: Option<u128>,
+    limit: Option<u64>,
+}
+
+impl AuditQuery {
+    /// Creates a query matching every entry.
+    pub fn new() -> Self {
+        AuditQuery::default()
+    }
+
+    /// Matches the operations on a collection.
+    pub fn collection(mut self, collection: &str) -> Self {
+        self.collection = Some(collection.to_string());
+        self
+    }
+
+    /// Matches one kind of operation, such as `insert`.
+    pub fn operation(mut self, operation: &str) -> Self {
+        self.operation = Some(operation.to_string());
+        self
+    }
+
+    /// Matches the writes committed by the transactions of a session.
+    pub fn session(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Matches the operations that wrote or read the document with `id`.
+    pub fn affecting(mut self, id: NitriteId) -> Self {
+        self.affected_id = Some(id);
+        self
+    }
+
+    /// Matches the operations completed at or after `timestamp`, in milliseconds since
+    /// the Unix epoch.
+    pub fn since(mut self, timestamp: u128) -> Self {
+        self.since = Some(timestamp);
+        self
+    }
+
+    /// Matches the operations completed before `timestamp`, in milliseconds since the
+    /// Unix epoch.
+    pub fn until(mut self, timestamp: u128) -> Self {
+        self.until = Some(timestamp);
+        self
+    }
+
+    /// Returns at most `limit` entries, the oldest ones.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn to_filter(&self) -> Filter {
+        let mut filters = Vec::new();
+        if let Some(collection) = &self.collection {
+            filters.push(field(COLLECTION).eq(collection.as_str()));
+        }
+        if let Some(operation) = &self.operation {
+            filters.push(field(OPERATION).eq(operation.as_str()));
+        }
+        if let Some(session_id) = &self.session_id {
+            filters.push(field(SESSION).eq(session_id.as_str()));
+        }
+        if let Some(id) = self.affected_id {
+            filters.push(field(IDS).eq(Value::NitriteId(id)));
+        }
+        if let Some(since) = self.since {
+            filters.push(field(TIMESTAMP).gte(Value::U128(since)));
+        }
+        if let Some(until) = self.until {
+            filters.push(field(TIMESTAMP).lt(Value::U128(until)));
+        }
+
+        match filters.len() {
+            0 => all(),
+            1 => filters.remove(0),
+            _ => crate::filter::and(filters),
+        }
+    }
+}
+
+/// Read access to the audit log of a database, opened with
+/// [`Nitrite::audit_log`](crate::nitrite::Nitrite::audit_log).
+///
+/// The log is a capped collection: it keeps the last
+/// [`max_entries`](AuditOptions::max_entries) entries, oldest first. It stays readable
+/// after auditing is disabled, and across restarts on a persistent store.
+#[derive(Clone)]
+pub struct AuditLog {
+    entries: NitriteCollection,
+}
+
+impl AuditLog {
+    pub(crate) fn new(entries: NitriteCollection) -> Self {
+        AuditLog { entries }
+    }
+
+    /// Returns the entries matching `query`, oldest first.
+    pub fn entries(&self, query: &AuditQuery) -> NitriteResult<Vec<AuditEntry>> {
+        let cursor = self.entries.find(query.to_filter())?;
+        let limit = query.limit.map_or(usize::MAX, |limit| limit as usize);
+        cursor
+            .take(limit)
+            .map(|document| AuditEntry::from_document(&document?))
+            .collect()
+    }
+
+    /// Returns the number of entries in the log.
+    pub fn len(&self) -> NitriteResult<u64> {
+        self.entries.size()
+    }
+
+    /// Returns `true` if the log has no entries.
+    pub fn is_empty(&self) -> NitriteResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Removes every entry of the log.
+    pub fn clear(&self) -> NitriteResult<()> {
+        self.entries.clear()
+    }
+}
+
+/// Records the operations of the collections of a database while auditing is enabled.
+///
+/// Shared by every collection through the configuration of the database.
+#[derive(Clone, Default)]
+pub(crate) struct Auditor {
+    active: Arc<RwLock<Option<ActiveAudit>>>,
+}
+
+#[derive(Clone)]
+struct ActiveAudit {
+    options: AuditOptions,
+    entries: NitriteCollection,
+}
+
+impl Auditor {
+    pub(crate) fn new() -> Self {
+        Auditor::default()
+    }
+
+    pub(crate) fn enable(&self, options: AuditOptions, entries: NitriteCollection) -> NitriteResult<()> {
+        entries.set_options(CollectionOptions::new().max_documents(options.max_entries))?;
+        *self.active.write() = Some(ActiveAudit { options, entries });
+        Ok(())
+    }
+
+    pub(crate) fn disable(&self) {
+        self.active.write().take();
+    }
+
+    pub(crate) fn options(&self) -> Option<AuditOptions> {
+        self.active.read().as_ref().map(|active| active.options.clone())
+    }
+
+    /// Records a completed write.
+    pub(crate) fn record_write(
+        &self,
+        operation: &str,
+        collection: &str,
+        filter: Option<&Filter>,
+        affected_ids: &[NitriteId],
+    ) {
+        self.record(operation, collection, filter, affected_ids, false);
+    }
+
+    /// Records a completed read, if reads are audited.
+    pub(crate) fn record_read(
+        &self,
+        operation: &str,
+        collection: &str,
+        filter: Option<&Filter>,
+        affected_ids: &[NitriteId],
+    ) {
+        self.record(operation, collection, filter, affected_ids, true);
+    }
+
+    fn record(
+        &self,
+        operation: &str,
+        collection: &str,
+        filter: Option<&Filter>,
+        affected_ids: &[NitriteId],
+        read: bool,
+    ) {
+        // the log does not audit itself
+        if collection == AUDIT_COLLECTION {
+            return;
+        }
+        // released before writing, the write comes back here for the log itself
+        let Some(active) = self.active.read().clone() else {
+            return;
+        };
+        if read && !active.options.include_reads {
+            return;
+        }
+
+        let origin = EventOrigin::current();
+        let entry = AuditEntry {
+            timestamp: get_current_time_or_zero(),
+            operation: operation.to_string(),
+            collection: collection.to_string(),
+            session_id: origin.as_ref().and_then(|origin| origin.session_id().map(str::to_string)),
+            transaction_id: origin.map(|origin| origin.transaction_id().to_string()),
+            filter: filter.map(|filter| summarize(filter, &active.options)),
+            affected_ids: affected_ids.to_vec(),
+        };
+        // the operation itself is done, a failure to log it must not undo it
+        let written = entry
+            .to_document()
+            .and_then(|document| active.entries.insert(document));
+        if let Err(err) = written {
+            log::error!("Failed to write the audit entry of {} on {}: {}", operation, collection, err);
+        }
+    }
+}
+
+/// Writes `filter` as text, with the values of redacted fields replaced.
+fn summarize(filter: &Filter, options: &AuditOptions) -> String {
+    let operands = |separator: &str| -> String {
+        let operands = filter.logical_filters().unwrap_or_default();
+        let parts: Vec<String> = operands.iter().map(|operand| summarize(operand, options)).collect();
+        format!("({})", parts.join(separator))
+    };
+
+    if is_and_filter(filter) {
+        operands(" && ")
+    } else if is_or_filter(filter) {
+        operands(" || ")
+    } else if is_not_filter(filter) {
+        let negated = filter.logical_filters().unwrap_or_default();
+        match negated.first() {
+            Some(negated) => format!("(not {})", summarize(negated, options)),
+            None => filter.to_string(),
+        }
+    } else if filter.has_field() {
+        match filter.get_field_name() {
+            Ok(field_name) if options.is_redacted(&field_name) => {
+                redact_value(filter, &field_name)
+            }
+            _ => filter.to_string(),
+        }
+    } else {
+        filter.to_string()
+    }
+}
+
+/// Writes a field filter with its value replaced, keeping the operator when the value
+/// can be found in the text of the filter.
+fn redact_value(filter: &Filter, field_name: &str) -> String {
+    let text = filter.to_string();
+    if let Ok(Some(value)) = filter.get_field_value() {
+        let value = value.to_string();
+        // the field name is written first, the value is looked for after it
+        if let Some(start) = text.find(field_name) {
+            let (head, tail) = text.split_at(start + field_name.len());
+            if !value.is_empty() && tail.contains(&value) {
+                return format!("{}{}", head, tail.replace(&value, REDACTED));
+            }
+        }
+    }
+    format!("({} {})", field_name, REDACTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{and, field, not, or};
+
+    #[test]
+    fn test_summarize_redacts_values() {
+        let options = AuditOptions::new().redact("ssn");
+        let filter = and(vec![
+            field("ssn").eq("078-05-1120"),
+            or(vec![field("age").gt(30), not(field("ssn").in_array(vec!["1", "2"]))]),
+        ]);
+        let summary = summarize(&filter, &options);
+        assert!(!summary.contains("078-05-1120"), "{}", summary);
+        assert!(summary.contains("(ssn == <redacted>)"), "{}", summary);
+        assert!(summary.contains("30"), "{}", summary);
+        assert!(!summary.contains("\"2\""), "{}", summary);
+
+        let options = AuditOptions::new().redact_all_values(true);
+        let summary = summarize(&field("age").gt(30), &options);
+        assert_eq!(summary, format!("(age > {})", REDACTED));
+    }
+
+    #[test]
+    fn test_entry_document_round_trip() {
+        let entry = AuditEntry {
+            timestamp: 42,
+            operation: "insert".to_string(),
+            collection: "orders".to_string(),
+            session_id: None,
+            transaction_id: Some("tx".to_string()),
+            filter: None,
+            affected_ids: vec![NitriteId::new()],
+        };
+        let document = entry.to_document().unwrap();
+        assert_eq!(AuditEntry::from_document(&document).unwrap(), entry);
+    }
+}
@@ -0,0 +1,190 @@
+use super::{Document, NitriteId, WriteToken, WriteTokenHolder};
+use crate::errors::NitriteError;
+use crate::filter::Filter;
+
+/// One write of a [`bulk_write`](super::NitriteCollectionProvider::bulk_write).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::collection::{BulkOperation, BulkWriteOptions};
+/// use nitrite::doc;
+/// use nitrite::filter::field;
+///
+/// let result = collection.bulk_write(vec![
+///     BulkOperation::InsertOne(doc! { sku: "A1", stock: 10 }),
+///     BulkOperation::UpdateMany {
+///         filter: field("stock").eq(0),
+///         update: doc! { status: "sold out" },
+///         upsert: false,
+///     },
+///     BulkOperation::DeleteOne(field("sku").eq("Z9")),
+/// ])?;
+/// assert!(result.is_applied());
+/// ```
+pub enum BulkOperation {
+    /// Inserts a document.
+    InsertOne(Document),
+    /// Updates the first document matching `filter`, or inserts `update` if none matches
+    /// and `upsert` is set.
+    UpdateOne {
+        filter: Filter,
+        update: Document,
+        upsert: bool,
+    },
+    /// Updates all documents matching `filter`, or inserts `update` if none matches and
+    /// `upsert` is set.
+    UpdateMany {
+        filter: Filter,
+        update: Document,
+        upsert: bool,
+    },
+    /// Replaces the first document matching `filter` with `replacement`, keeping its id,
+    /// or inserts `replacement` if none matches and `upsert` is set.
+    ReplaceOne {
+        filter: Filter,
+        replacement: Document,
+        upsert: bool,
+    },
+    /// Removes the first document matching the filter.
+    DeleteOne(Filter),
+    /// Removes all documents matching the filter.
+    DeleteMany(Filter),
+}
+
+impl BulkOperation {
+    /// Returns the name of the operation, as used in error messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BulkOperation::InsertOne(_) => "InsertOne",
+            BulkOperation::UpdateOne { .. } => "UpdateOne",
+            BulkOperation::UpdateMany { .. } => "UpdateMany",
+            BulkOperation::ReplaceOne { .. } => "ReplaceOne",
+            BulkOperation::DeleteOne(_) => "DeleteOne",
+            BulkOperation::DeleteMany(_) => "DeleteMany",
+        }
+    }
+}
+
+/// Options of a [`bulk_write`](super::NitriteCollectionProvider::bulk_write).
+///
+/// An ordered bulk write (the default) stops at the first failing operation. An unordered
+/// one runs every operation so that all failures are reported at once. Either way the
+/// batch is atomic: if any operation fails, none of them is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkWriteOptions {
+    ordered: bool,
+}
+
+impl Default for BulkWriteOptions {
+    fn default() -> Self {
+        BulkWriteOptions { ordered: true }
+    }
+}
+
+impl BulkWriteOptions {
+    /// Creates the default options of an ordered bulk write.
+    pub fn new() -> Self {
+        BulkWriteOptions::default()
+    }
+
+    /// Sets whether the bulk write stops at the first failing operation.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Returns `true` if the bulk write stops at the first failing operation.
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+}
+
+/// The failure of one operation of a bulk write.
+#[derive(Debug, Clone)]
+pub struct BulkWriteError {
+    index: usize,
+    error: NitriteError,
+}
+
+impl BulkWriteError {
+    pub(crate) fn new(index: usize, error: NitriteError) -> Self {
+        BulkWriteError { index, error }
+    }
+
+    /// Returns the position of the failed operation in the batch.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns why the operation failed.
+    pub fn error(&self) -> &NitriteError {
+        &self.error
+    }
+}
+
+/// The outcome of a bulk write.
+///
+/// If any operation failed, [`errors`](BulkWriteResult::errors) lists the failures, the
+/// whole batch has been rolled back and all counts are zero.
+#[derive(Debug, Clone, Default)]
+pub struct BulkWriteResult {
+    pub(crate) inserted_ids: Vec<NitriteId>,
+    pub(crate) upserted_ids: Vec<NitriteId>,
+    pub(crate) modified_count: usize,
+    pub(crate) deleted_count: usize,
+    pub(crate) errors: Vec<BulkWriteError>,
+    pub(crate) write_token: WriteToken,
+}
+
+impl BulkWriteResult {
+    /// Returns `true` if every operation succeeded and the batch was applied.
+    pub fn is_applied(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the ids of the documents inserted by `InsertOne` operations.
+    pub fn inserted_ids(&self) -> &[NitriteId] {
+        &self.inserted_ids
+    }
+
+    /// Returns the ids of the documents inserted by upserts.
+    pub fn upserted_ids(&self) -> &[NitriteId] {
+        &self.upserted_ids
+    }
+
+    /// Returns the number of documents updated or replaced.
+    pub fn modified_count(&self) -> usize {
+        self.modified_count
+    }
+
+    /// Returns the number of documents removed.
+    pub fn deleted_count(&self) -> usize {
+        self.deleted_count
+    }
+
+    /// Returns the failed operations, in the order they ran.
+    pub fn errors(&self) -> &[BulkWriteError] {
+        &self.errors
+    }
+
+    /// Returns the token of the batch, to be passed to
+    /// [`FindOptions::after_write`](super::FindOptions::after_write).
+    pub fn write_token(&self) -> WriteToken {
+        self.write_token
+    }
+
+    /// Clears the counts after the batch has been rolled back.
+    pub(crate) fn discard(&mut self) {
+        self.inserted_ids.clear();
+        self.upserted_ids.clear();
+        self.modified_count = 0;
+        self.deleted_count = 0;
+    }
+}
+
+impl WriteTokenHolder for BulkWriteResult {
+    fn set_write_token(&mut self, token: WriteToken) {
+        self.write_token = token;
+    }
+}
@@ -0,0 +1,131 @@
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Cancels the queries it was given to, from any thread.
+///
+/// Passing a token to [`FindOptions::cancellation_token`](super::FindOptions::cancellation_token)
+/// makes the query check it while it reads documents. Once [`cancel`](Self::cancel) is
+/// called, the cursor of the query returns a `Cancelled` error and ends, including a
+/// blocking sort that is still reading its input. Clones of a token share its state, so
+/// one token can cancel several queries, for example all queries of a web request whose
+/// client has gone away.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let token = CancellationToken::new();
+/// let cursor = orders.find_with_options(
+///     field("status").eq("paid"),
+///     &FindOptions::new().cancellation_token(token.clone()),
+/// )?;
+///
+/// // on another thread
+/// token.cancel();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancels the queries holding this token. Cancelling twice has no further effect.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` once the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// The deadline and cancellation token of a running query, checked by its streams.
+#[derive(Debug, Clone)]
+pub(crate) struct QueryInterrupt {
+    deadline: Option<Instant>,
+    token: Option<CancellationToken>,
+}
+
+impl QueryInterrupt {
+    /// Starts the clock of a query, or returns `None` if it can be neither timed out
+    /// nor cancelled.
+    pub(crate) fn start(
+        timeout: Option<Duration>,
+        token: Option<&CancellationToken>,
+    ) -> Option<QueryInterrupt> {
+        if timeout.is_none() && token.is_none() {
+            return None;
+        }
+
+        Some(QueryInterrupt {
+            // a timeout too large for the clock never expires
+            deadline: timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
+            token: token.cloned(),
+        })
+    }
+
+    /// Fails with `Cancelled` if the token was cancelled, or with `QueryTimedOut` if the
+    /// deadline has passed.
+    pub(crate) fn check(&self) -> NitriteResult<()> {
+        if self.token.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            log::error!("Query cancelled");
+            return Err(NitriteError::new("Query cancelled", ErrorKind::Cancelled));
+        }
+
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            log::error!("Query timed out");
+            return Err(NitriteError::new("Query timed out", ErrorKind::QueryTimedOut));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn test_query_interrupt_start() {
+        assert!(QueryInterrupt::start(None, None).is_none());
+        let interrupt = QueryInterrupt::start(Some(Duration::MAX), None).unwrap();
+        assert!(interrupt.check().is_ok());
+    }
+
+    #[test]
+    fn test_query_interrupt_cancelled() {
+        let token = CancellationToken::new();
+        let interrupt = QueryInterrupt::start(None, Some(&token)).unwrap();
+        assert!(interrupt.check().is_ok());
+
+        token.cancel();
+        let error = interrupt.check().unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn test_query_interrupt_timed_out() {
+        let interrupt = QueryInterrupt::start(Some(Duration::ZERO), None).unwrap();
+        let error = interrupt.check().unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::QueryTimedOut);
+        assert!(!error.is_retryable());
+    }
+}
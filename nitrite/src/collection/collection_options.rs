@@ -0,0 +1,479 @@
+use super::Document;
+use crate::{
+    common::{is_reserved_field, Attributes, Value},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    COLLECTION_BYTE_QUOTA, COLLECTION_CONTENT_HASH, COLLECTION_DETAILED_RESULTS, COLLECTION_DOCUMENT_QUOTA, COLLECTION_DURABILITY, COLLECTION_EVENT_IMAGES, COLLECTION_MAX_BYTES,
+    COLLECTION_MAX_DOCUMENTS, COLLECTION_REQUIRED_FIELDS, COLLECTION_SOFT_DELETE, COLLECTION_SPLIT_FIELDS,
+};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// When the writes of a collection reach durable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteDurability {
+    /// Writes are persisted with the next commit of the store: an explicit
+    /// `db.commit()`, an auto-commit or the close of the database.
+    #[default]
+    Buffered,
+    /// Every write operation commits the store before it returns.
+    Sync,
+}
+
+impl WriteDurability {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WriteDurability::Buffered => "buffered",
+            WriteDurability::Sync => "sync",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "buffered" => Some(WriteDurability::Buffered),
+            "sync" => Some(WriteDurability::Sync),
+            _ => None,
+        }
+    }
+}
+
+/// Settings of a single collection.
+///
+/// Options are applied with `db.collection_with_options()` or
+/// [`set_options`](super::NitriteCollectionProvider::set_options) and persisted with the
+/// collection, so they are restored when the database is reopened. A collection that never
+/// had options set uses the defaults: buffered writes, no required fields, hard deletes,
+/// no cap and no quota.
+///
+/// # Capped collections
+///
+/// A collection capped by a number of documents or a number of bytes evicts its oldest
+/// documents, in `NitriteId` order, whenever a write takes it over the cap. Each evicted
+/// document is removed from the indexes and announced with a `CollectionEvents::Remove`
+/// event whose reason is `EventReason::Eviction`; it is not kept by soft delete. The newest document is never evicted, even if it
+/// alone is over the byte cap. The byte size of a document is an estimate of its encoded
+/// size.
+///
+/// # Quotas
+///
+/// A quota limits a collection the same way without evicting anything: a write that would
+/// take the collection over its quota fails with `ErrorKind::QuotaExceeded` and stores
+/// nothing. The document that did not fit is handed to the
+/// [`on_quota_exceeded`](CollectionOptions::on_quota_exceeded) hook, if one is set. Writes
+/// that do not grow the collection, like removals and shrinking updates, are always
+/// accepted, so a collection over a lowered quota can still be cleaned up.
+///
+/// # Detailed results
+///
+/// With [`detailed_results`](CollectionOptions::detailed_results) the `WriteResult` of every
+/// insert, update and removal of the collection carries the outcome of each document
+/// written and the time spent on the write and on its indexes, to find the slow writes of
+/// a batch.
+///
+/// # Event images
+///
+/// With [`event_images`](CollectionOptions::event_images) the events of the collection
+/// carry the documents written as they were before and after each write, see
+/// [`CollectionEventInfo`](super::CollectionEventInfo).
+///
+/// # Split storage
+///
+/// Fields set with [`split_field`](CollectionOptions::split_field) are stored apart from
+/// the rest of their document by the stores that support it, like the fjall store, and put
+/// back transparently when the document is read. A projected query, like
+/// `find(...)?.project(...)`, then reads only the split fields it projects, which saves
+/// reading large values, like file contents or embeddings, when listing the other fields.
+/// Documents written before a field was split are stored whole until they are written
+/// again. Stores without split storage keep their documents whole.
+///
+/// # Content hashes
+///
+/// With [`content_hash`](CollectionOptions::content_hash) every document written stores
+/// its [`content_hash`](super::Document::content_hash) in the `_hash` metadata field, as a
+/// `u128`, so readers and indexes can find duplicate or changed documents without hashing
+/// them. Documents written before the option was set have no hash until they are written
+/// again, and writes after it is unset remove the hash.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::collection::{CollectionOptions, WriteDurability};
+///
+/// let options = CollectionOptions::new()
+///     .durability(WriteDurability::Sync)
+///     .required_field("level")
+///     .required_field("message")
+///     .soft_delete(true);
+/// let logs = db.collection_with_options("logs", options)?;
+///
+/// // keep the latest 10 000 events, in at most 4 MB
+/// let telemetry = db.collection_with_options(
+///     "telemetry",
+///     CollectionOptions::new().max_documents(10_000).max_bytes(4 * 1024 * 1024),
+/// )?;
+///
+/// // refuse writes past 64 MB instead of dropping old entries
+/// let audit = db.collection_with_options(
+///     "audit",
+///     CollectionOptions::new()
+///         .byte_quota(64 * 1024 * 1024)
+///         .on_quota_exceeded(|document| log::warn!("audit is full, dropped {}", document)),
+/// )?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CollectionOptions {
+    durability: WriteDurability,
+    required_fields: Vec<String>,
+    soft_delete: bool,
+    max_documents: Option<u64>,
+    max_bytes: Option<u64>,
+    document_quota: Option<u64>,
+    byte_quota: Option<u64>,
+    detailed_results: bool,
+    event_images: bool,
+    split_fields: Vec<String>,
+    content_hash: bool,
+    quota_hook: Option<QuotaHook>,
+}
+
+type QuotaCallback = Arc<dyn Fn(&Document) + Send + Sync>;
+
+/// The hook called with the documents rejected by the quota of a collection.
+#[derive(Clone)]
+struct QuotaHook(QuotaCallback);
+
+impl Debug for QuotaHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("QuotaHook")
+    }
+}
+
+impl PartialEq for QuotaHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for QuotaHook {}
+
+impl CollectionOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        CollectionOptions::default()
+    }
+
+    /// Sets when the writes of the collection reach durable storage.
+    pub fn durability(mut self, durability: WriteDurability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Requires every inserted or updated document to have a non-null value for `field`.
+    ///
+    /// Embedded fields are addressed with the field separator, like `address.city`.
+    pub fn required_field(mut self, field: &str) -> Self {
+        if !self.required_fields.iter().any(|name| name == field) {
+            self.required_fields.push(field.to_string());
+        }
+        self
+    }
+
+    /// Sets whether removed documents are kept aside so they can be restored.
+    ///
+    /// Soft-deleted documents are no longer found by queries. They are listed by
+    /// `deleted_documents()`, brought back by `restore()` and discarded by
+    /// `purge_deleted()`.
+    pub fn soft_delete(mut self, soft_delete: bool) -> Self {
+        self.soft_delete = soft_delete;
+        self
+    }
+
+    /// Caps the collection at `max_documents` documents (at least 1).
+    pub fn max_documents(mut self, max_documents: u64) -> Self {
+        self.max_documents = Some(max_documents.max(1));
+        self
+    }
+
+    /// Caps the collection at about `max_bytes` bytes of documents.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Rejects writes that would take the collection over `document_quota` documents.
+    pub fn document_quota(mut self, document_quota: u64) -> Self {
+        self.document_quota = Some(document_quota);
+        self
+    }
+
+    /// Rejects writes that would take the collection over about `byte_quota` bytes of
+    /// documents.
+    pub fn byte_quota(mut self, byte_quota: u64) -> Self {
+        self.byte_quota = Some(byte_quota);
+        self
+    }
+
+    /// Calls `hook` with the stored form of the document a write could not store because
+    /// of the quota, before the write fails.
+    ///
+    /// The hook runs on the writing thread while the collection is locked, so it must not
+    /// write to the collection. Unlike the other options it is not persisted: a reopened
+    /// collection has no hook until its options are set again.
+    pub fn on_quota_exceeded(mut self, hook: impl Fn(&Document) + Send + Sync + 'static) -> Self {
+        self.quota_hook = Some(QuotaHook(Arc::new(hook)));
+        self
+    }
+
+    /// Sets whether the write results of the collection carry per-document outcomes and
+    /// timings.
+    pub fn detailed_results(mut self, detailed_results: bool) -> Self {
+        self.detailed_results = detailed_results;
+        self
+    }
+
+    /// Sets whether the events of the collection carry the documents written before and
+    /// after each write.
+    pub fn event_images(mut self, event_images: bool) -> Self {
+        self.event_images = event_images;
+        self
+    }
+
+    /// Stores `field` apart from the rest of its document, so projections not reading it
+    /// skip it. The id and the metadata fields are never split.
+    ///
+    /// Embedded fields are addressed with the field separator, like `attachment.data`.
+    pub fn split_field(mut self, field: &str) -> Self {
+        if !field.is_empty()
+            && !is_reserved_field(field)
+            && !self.split_fields.iter().any(|name| name == field)
+        {
+            self.split_fields.push(field.to_string());
+        }
+        self
+    }
+
+    /// Sets whether the documents of the collection store their content hash.
+    pub fn content_hash(mut self, content_hash: bool) -> Self {
+        self.content_hash = content_hash;
+        self
+    }
+
+    /// Returns when the writes of the collection reach durable storage.
+    pub fn get_durability(&self) -> WriteDurability {
+        self.durability
+    }
+
+    /// Returns the fields every document must have.
+    pub fn get_required_fields(&self) -> &[String] {
+        &self.required_fields
+    }
+
+    /// Returns `true` if removed documents are kept aside.
+    pub fn is_soft_delete(&self) -> bool {
+        self.soft_delete
+    }
+
+    /// Returns the maximum number of documents, if capped.
+    pub fn get_max_documents(&self) -> Option<u64> {
+        self.max_documents
+    }
+
+    /// Returns the maximum size of the documents in bytes, if capped.
+    pub fn get_max_bytes(&self) -> Option<u64> {
+        self.max_bytes
+    }
+
+    /// Returns `true` if the collection evicts its oldest documents.
+    pub fn is_capped(&self) -> bool {
+        self.max_documents.is_some() || self.max_bytes.is_some()
+    }
+
+    /// Returns the maximum number of documents writes may reach, if limited.
+    pub fn get_document_quota(&self) -> Option<u64> {
+        self.document_quota
+    }
+
+    /// Returns the maximum size of the documents in bytes writes may reach, if limited.
+    pub fn get_byte_quota(&self) -> Option<u64> {
+        self.byte_quota
+    }
+
+    /// Returns `true` if the collection rejects writes over a quota.
+    pub fn has_quota(&self) -> bool {
+        self.document_quota.is_some() || self.byte_quota.is_some()
+    }
+
+    /// Returns `true` if the write results of the collection carry per-document outcomes
+    /// and timings.
+    pub fn is_detailed_results(&self) -> bool {
+        self.detailed_results
+    }
+
+    /// Returns `true` if the events of the collection carry the documents written before
+    /// and after each write.
+    pub fn is_event_images(&self) -> bool {
+        self.event_images
+    }
+
+    /// Returns the fields stored apart from the rest of their document.
+    pub fn get_split_fields(&self) -> &[String] {
+        &self.split_fields
+    }
+
+    /// Returns `true` if the documents of the collection store their content hash.
+    pub fn is_content_hash(&self) -> bool {
+        self.content_hash
+    }
+
+    /// Returns the hook called with the documents rejected by the quota, if set.
+    pub(crate) fn quota_hook(&self) -> Option<QuotaCallback> {
+        self.quota_hook.as_ref().map(|hook| hook.0.clone())
+    }
+
+    /// Checks a document about to be written against the required fields.
+    pub(crate) fn validate(&self, document: &Document) -> NitriteResult<()> {
+        for field in &self.required_fields {
+            if document.get(field)? == Value::Null {
+                log::error!("Document is missing the required field {}", field);
+                return Err(NitriteError::new(
+                    &format!("Document is missing the required field {}", field),
+                    ErrorKind::ValidationError,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the options into the collection attributes.
+    pub(crate) fn write_attributes(&self, attributes: &mut Attributes) {
+        attributes.put(
+            COLLECTION_DURABILITY,
+            Value::String(self.durability.as_str().to_string()),
+        );
+        attributes.put(
+            COLLECTION_REQUIRED_FIELDS,
+            Value::Array(
+                self.required_fields
+                    .iter()
+                    .map(|field| Value::String(field.clone()))
+                    .collect(),
+            ),
+        );
+        attributes.put(COLLECTION_SOFT_DELETE, Value::Bool(self.soft_delete));
+        attributes.put(
+            COLLECTION_MAX_DOCUMENTS,
+            self.max_documents.map(Value::U64).unwrap_or(Value::Null),
+        );
+        attributes.put(
+            COLLECTION_MAX_BYTES,
+            self.max_bytes.map(Value::U64).unwrap_or(Value::Null),
+        );
+        attributes.put(
+            COLLECTION_DOCUMENT_QUOTA,
+            self.document_quota.map(Value::U64).unwrap_or(Value::Null),
+        );
+        attributes.put(
+            COLLECTION_BYTE_QUOTA,
+            self.byte_quota.map(Value::U64).unwrap_or(Value::Null),
+        );
+        attributes.put(COLLECTION_DETAILED_RESULTS, Value::Bool(self.detailed_results));
+        attributes.put(COLLECTION_EVENT_IMAGES, Value::Bool(self.event_images));
+        attributes.put(
+            COLLECTION_SPLIT_FIELDS,
+            Value::Array(
+                self.split_fields
+                    .iter()
+                    .map(|field| Value::String(field.clone()))
+                    .collect(),
+            ),
+        );
+        attributes.put(COLLECTION_CONTENT_HASH, Value::Bool(self.content_hash));
+    }
+
+    /// Reads the options from the collection attributes, using the defaults for the
+    /// settings that were never stored.
+    pub(crate) fn from_attributes(attributes: &Attributes) -> Self {
+        let mut options = CollectionOptions::new();
+        if let Some(Value::String(durability)) = attributes.get(COLLECTION_DURABILITY) {
+            options.durability = WriteDurability::from_name(durability).unwrap_or_default();
+        }
+        if let Some(Value::Array(fields)) = attributes.get(COLLECTION_REQUIRED_FIELDS) {
+            for field in fields {
+                if let Value::String(field) = field {
+                    options = options.required_field(field);
+                }
+            }
+        }
+        if let Some(Value::Bool(soft_delete)) = attributes.get(COLLECTION_SOFT_DELETE) {
+            options.soft_delete = *soft_delete;
+        }
+        if let Some(Value::U64(max_documents)) = attributes.get(COLLECTION_MAX_DOCUMENTS) {
+            options.max_documents = Some(*max_documents);
+        }
+        if let Some(Value::U64(max_bytes)) = attributes.get(COLLECTION_MAX_BYTES) {
+            options.max_bytes = Some(*max_bytes);
+        }
+        if let Some(Value::U64(document_quota)) = attributes.get(COLLECTION_DOCUMENT_QUOTA) {
+            options.document_quota = Some(*document_quota);
+        }
+        if let Some(Value::U64(byte_quota)) = attributes.get(COLLECTION_BYTE_QUOTA) {
+            options.byte_quota = Some(*byte_quota);
+        }
+        if let Some(Value::Bool(detailed_results)) = attributes.get(COLLECTION_DETAILED_RESULTS) {
+            options.detailed_results = *detailed_results;
+        }
+        if let Some(Value::Bool(event_images)) = attributes.get(COLLECTION_EVENT_IMAGES) {
+            options.event_images = *event_images;
+        }
+        if let Some(Value::Array(fields)) = attributes.get(COLLECTION_SPLIT_FIELDS) {
+            for field in fields {
+                if let Value::String(field) = field {
+                    options = options.split_field(field);
+                }
+            }
+        }
+        if let Some(Value::Bool(content_hash)) = attributes.get(COLLECTION_CONTENT_HASH) {
+            options.content_hash = *content_hash;
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options_round_trip_through_attributes() {
+        let options = CollectionOptions::new()
+            .durability(WriteDurability::Sync)
+            .required_field("level")
+            .required_field("level")
+            .soft_delete(true)
+            .max_documents(0)
+            .max_bytes(1024)
+            .document_quota(500)
+            .byte_quota(1 << 20)
+            .detailed_results(true)
+            .event_images(true)
+            .split_field("content")
+            .split_field("content")
+            .split_field("_id")
+            .content_hash(true);
+        assert_eq!(options.get_required_fields(), ["level".to_string()]);
+        assert_eq!(options.get_split_fields(), ["content".to_string()]);
+        assert_eq!(options.get_max_documents(), Some(1));
+        assert!(options.is_capped());
+        assert!(options.has_quota());
+        assert!(options.is_detailed_results());
+        assert!(options.is_event_images());
+        assert!(options.is_content_hash());
+
+        let mut attributes = Attributes::new();
+        options.write_attributes(&mut attributes);
+        assert_eq!(CollectionOptions::from_attributes(&attributes), options);
+        assert_eq!(
+            CollectionOptions::from_attributes(&Attributes::new()),
+            CollectionOptions::default()
+        );
+    }
+}
@@ -1,17 +1,25 @@
 use crate::{
-    common::{LockHandle, LockRegistry}, create_unique_filter, errors::{ErrorKind, NitriteError, NitriteResult}, filter::{is_all_filter, Filter}, nitrite_config::NitriteConfig, store::{NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider}, AttributeAware, EventAware, Fields, NitriteEventBus, PersistentCollection, Processor
+    common::{LockHandle, LockRegistry}, create_unique_filter, errors::{ErrorKind, NitriteError, NitriteResult}, filter::{is_all_filter, Filter}, nitrite_config::NitriteConfig, store::{NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider}, AttributeAware, EventAware, Fields, NitriteEventBus, PersistentCollection, Processor, Value
 };
+use crate::audit::Auditor;
+use crate::profiler::Profiler;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use super::{operation::CollectionOperations, NitriteCollectionProvider, UpdateOptions};
+use super::{
+    operation::{CollectionOperations, WriteResult}, NitriteCollectionProvider, NitriteId, RedactionPolicy, UpdateOptions, WriteTokenHolder,
+    WriteTracker,
+};
 
 pub(crate) struct DefaultNitriteCollection {
     collection_name: String,
     nitrite_map: NitriteMap,
     store: NitriteStore,
     operations: CollectionOperations,
+    write_tracker: WriteTracker,
     dropped: AtomicBool,
     lock_handle: LockHandle,
+    profiler: Profiler,
+    auditor: Auditor,
 }
 
 impl DefaultNitriteCollection {
@@ -23,6 +31,9 @@ impl DefaultNitriteCollection {
     ) -> NitriteResult<Self> {
         let store = nitrite_config.nitrite_store()?;
         let event_bus = NitriteEventBus::new();
+        let write_tracker = nitrite_config.write_tracker();
+        let profiler = nitrite_config.profiler();
+        let auditor = nitrite_config.auditor();
 
         let operations = CollectionOperations::new(
             collection_name,
@@ -36,8 +47,11 @@ impl DefaultNitriteCollection {
             nitrite_map: nitrite_map.clone(),
             store: store.clone(),
             operations,
+            write_tracker,
             dropped: AtomicBool::from(false),
             lock_handle,
+            profiler,
+            auditor,
         })
     }
 
@@ -81,6 +95,25 @@ impl DefaultNitriteCollection {
 
         Ok(())
     }
+
+    /// Completes a successful write: commits it right away if the collection uses sync
+    /// durability and stamps the result with the token of the write.
+    fn synced<T: WriteTokenHolder>(&self, result: NitriteResult<T>) -> NitriteResult<T> {
+        let mut value = result?;
+        self.operations.sync_if_required()?;
+        value.set_write_token(self.write_tracker.issue());
+        Ok(value)
+    }
+
+    /// Records a completed write of this collection in the audit log, if enabled.
+    fn audit_write(&self, operation: &str, filter: Option<&Filter>, ids: &[NitriteId]) {
+        self.auditor.record_write(operation, &self.collection_name, filter, ids);
+    }
+
+    /// Records a completed read of this collection in the audit log, if reads are audited.
+    fn audit_read(&self, operation: &str, filter: Option<&Filter>, ids: &[NitriteId]) {
+        self.auditor.record_read(operation, &self.collection_name, filter, ids);
+    }
 }
 
 impl EventAware for DefaultNitriteCollection {
@@ -112,6 +145,24 @@ impl AttributeAware for DefaultNitriteCollection {
         self.ensure_opened()?;
         self.operations.set_attributes(attributes)
     }
+
+    fn set_attribute(&self, key: &str, value: crate::common::Value) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let mut attributes = self.operations.attributes()?.unwrap_or_default();
+        attributes.put(key, value);
+        self.operations.set_attributes(attributes)
+    }
+
+    fn remove_attribute(&self, key: &str) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let mut attributes = self.operations.attributes()?.unwrap_or_default();
+        if attributes.remove(key).is_some() {
+            self.operations.set_attributes(attributes)?;
+        }
+        Ok(())
+    }
 }
 
 impl PersistentCollection for DefaultNitriteCollection {
@@ -131,7 +182,7 @@ impl PersistentCollection for DefaultNitriteCollection {
         self.ensure_opened()?;
         let fields = Fields::with_names(field_names)?;
         self.operations
-            .create_index(&fields, &index_options.index_type())
+            .create_index(&fields, index_options)
     }
 
     fn rebuild_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
@@ -198,7 +249,9 @@ impl PersistentCollection for DefaultNitriteCollection {
     fn clear(&self) -> NitriteResult<()> {
         let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.clear()
+        self.operations.clear()?;
+        self.audit_write("clear", None, &[]);
+        Ok(())
     }
 
     fn dispose(&self) -> NitriteResult<()> {
@@ -249,7 +302,10 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
     ) -> NitriteResult<super::operation::WriteResult> {
         let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.insert(document)
+        let _profile = self.profiler.start("insert", &self.collection_name);
+        let result = self.synced(self.operations.insert(document))?;
+        self.audit_write("insert", None, result.affected_nitrite_ids());
+        Ok(result)
     }
 
     fn insert_many(
@@ -258,7 +314,10 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
     ) -> NitriteResult<super::operation::WriteResult> {
         let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.insert_batch(documents)
+        let _profile = self.profiler.start("insert_many", &self.collection_name);
+        let result = self.synced(self.operations.insert_batch(documents))?;
+        self.audit_write("insert_many", None, result.affected_nitrite_ids());
+        Ok(result)
     }
 
     fn update_with_options(
@@ -269,7 +328,10 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
     ) -> NitriteResult<super::operation::WriteResult> {
         let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.update(filter, update, update_options)
+        let _profile = self.profiler.start("update", &self.collection_name);
+        let result = self.synced(self.operations.update(filter.clone(), update, update_options))?;
+        self.audit_write("update", Some(&filter), result.affected_nitrite_ids());
+        Ok(result)
     }
 
     fn update_one(
@@ -305,7 +367,39 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
     ) -> NitriteResult<super::operation::WriteResult> {
         let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.update_by_id(id, update, insert_if_absent)
+        let _profile = self.profiler.start("update_by_id", &self.collection_name);
+        let result = self.synced(self.operations.update_by_id(id, update, insert_if_absent))?;
+        self.audit_write("update_by_id", None, result.affected_nitrite_ids());
+        Ok(result)
+    }
+
+    fn insert_if_changed(&self, document: super::Document) -> NitriteResult<super::operation::WriteResult> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let _profile = self.profiler.start("insert_if_changed", &self.collection_name);
+        let result = self.synced(self.operations.insert_if_changed(document))?;
+        self.audit_write("insert_if_changed", None, result.affected_nitrite_ids());
+        Ok(result)
+    }
+
+    fn remove_id_range(&self, start: &super::NitriteId, end: &super::NitriteId) -> NitriteResult<u64> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let _profile = self.profiler.start("remove_id_range", &self.collection_name);
+        let removed = self.operations.remove_id_range(start, end)?;
+        self.operations.sync_if_required()?;
+        self.audit_write("remove_id_range", None, &[*start, *end]);
+        Ok(removed)
+    }
+
+    fn increment(&self, id: &super::NitriteId, field: &str, delta: Value) -> NitriteResult<Value> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let _profile = self.profiler.start("increment", &self.collection_name);
+        let counter = self.operations.increment(id, field, &delta)?;
+        self.operations.sync_if_required()?;
+        self.audit_write("increment", None, &[*id]);
+        Ok(counter)
     }
 
     fn remove(
@@ -323,7 +417,10 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
         
         let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.remove(filter, just_once)
+        let _profile = self.profiler.start("remove", &self.collection_name);
+        let result = self.synced(self.operations.remove(filter.clone(), just_once))?;
+        self.audit_write("remove", Some(&filter), result.affected_nitrite_ids());
+        Ok(result)
     }
 
     fn remove_one(
@@ -333,7 +430,10 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
         let _guard = self.lock_handle.write();
         if document.has_id() {
             self.ensure_opened()?;
-            self.operations.remove_document(document)
+            let _profile = self.profiler.start("remove_one", &self.collection_name);
+            let result: WriteResult = self.synced(self.operations.remove_document(document))?;
+            self.audit_write("remove_one", None, result.affected_nitrite_ids());
+            Ok(result)
         } else {
             log::error!("Document does not have id");
             Err(NitriteError::new(
@@ -343,10 +443,23 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
         }
     }
 
-    fn find(&self, filter: Filter) -> NitriteResult<crate::DocumentCursor> {
-        let _guard = self.lock_handle.read();
+    fn bulk_write_with_options(
+        &self,
+        operations: Vec<super::BulkOperation>,
+        options: &super::BulkWriteOptions,
+    ) -> NitriteResult<super::BulkWriteResult> {
+        let _guard = self.lock_handle.write();
         self.ensure_opened()?;
-        self.operations.find(filter, &super::FindOptions::new())
+        let _profile = self.profiler.start("bulk_write", &self.collection_name);
+        let result = self.synced(self.operations.bulk_write(operations, options))?;
+        let mut ids = result.inserted_ids().to_vec();
+        ids.extend_from_slice(result.upserted_ids());
+        self.audit_write("bulk_write", None, &ids);
+        Ok(result)
+    }
+
+    fn find(&self, filter: Filter) -> NitriteResult<crate::DocumentCursor> {
+        self.find_with_options(filter, &super::FindOptions::new())
     }
 
     fn find_with_options(
@@ -354,15 +467,179 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
         filter: Filter,
         find_options: &super::FindOptions,
     ) -> NitriteResult<crate::DocumentCursor> {
+        // wait before locking, a committing transaction needs the write lock
+        if let Some(token) = find_options.after_write {
+            self.write_tracker.wait_for(token)?;
+        }
         let _guard = self.lock_handle.read();
         self.ensure_opened()?;
-        self.operations.find(filter, find_options)
+        let profile = self.profiler.start("find", &self.collection_name);
+        let cursor = self.operations.find(filter.clone(), find_options)?;
+        self.audit_read("find", Some(&filter), &[]);
+        // the iteration of the cursor belongs to the find
+        Ok(match profile {
+            Some(profile) => cursor.with_profile(profile.profile()),
+            None => cursor,
+        })
+    }
+
+    fn count(&self, filter: Filter) -> NitriteResult<u64> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        let _profile = self.profiler.start("count", &self.collection_name);
+        let count = self.operations.count(filter.clone())?;
+        self.audit_read("count", Some(&filter), &[]);
+        Ok(count)
     }
 
     fn get_by_id(&self, id: &super::NitriteId) -> NitriteResult<Option<super::Document>> {
         let _guard = self.lock_handle.read();
         self.ensure_opened()?;
-        self.operations.get_by_id(id)
+        let _profile = self.profiler.start("get_by_id", &self.collection_name);
+        let document = self.operations.get_by_id(id)?;
+        self.audit_read("get_by_id", None, &[*id]);
+        Ok(document)
+    }
+
+    fn enable_history(&self, options: super::HistoryOptions) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.enable_history(options)
+    }
+
+    fn disable_history(&self) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.disable_history()
+    }
+
+    fn history_options(&self) -> NitriteResult<Option<super::HistoryOptions>> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        Ok(self.operations.history_options())
+    }
+
+    fn document_history(
+        &self,
+        id: &super::NitriteId,
+    ) -> NitriteResult<Vec<super::DocumentVersion>> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        self.operations.document_history(id)
+    }
+
+    fn find_as_of(&self, timestamp: u128, filter: Filter) -> NitriteResult<crate::DocumentCursor> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        self.operations.find_as_of(timestamp, filter)
+    }
+
+    fn analyze(&self) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.analyze()
+    }
+
+    fn index_statistics(
+        &self,
+        field_names: Vec<&str>,
+    ) -> NitriteResult<Option<crate::index::IndexStatistics>> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        let fields = Fields::with_names(field_names)?;
+        self.operations.index_statistics(&fields)
+    }
+
+    fn content_hash(&self) -> NitriteResult<u128> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        self.operations.content_hash()
+    }
+
+    fn export_index(&self, field_names: Vec<&str>) -> NitriteResult<crate::index::IndexExport> {
+        // the read lock keeps writers out between hashing the documents and exporting
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        let _profile = self.profiler.start("export_index", &self.collection_name);
+        let fields = Fields::with_names(field_names)?;
+        self.operations.export_index(&fields)
+    }
+
+    fn import_index(&self, export: &crate::index::IndexExport) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let _profile = self.profiler.start("import_index", &self.collection_name);
+        self.operations.import_index(export)
+    }
+
+    fn defer_index_maintenance(
+        &self,
+        bulk_load: &mut dyn FnMut() -> NitriteResult<()>,
+    ) -> NitriteResult<()> {
+        {
+            let _guard = self.lock_handle.write();
+            self.ensure_opened()?;
+            self.operations.defer_index_maintenance()?;
+        }
+
+        // the writes of the closure take the lock themselves
+        let loaded = bulk_load();
+
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let rebuilt = self.operations.resume_index_maintenance();
+        loaded.and(rebuilt)
+    }
+
+    fn options(&self) -> NitriteResult<super::CollectionOptions> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        Ok(self.operations.options())
+    }
+
+    fn set_options(&self, options: super::CollectionOptions) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.set_options(options)
+    }
+
+    fn deleted_documents(&self) -> NitriteResult<Vec<super::Document>> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        self.operations.deleted_documents()
+    }
+
+    fn restore(&self, id: &super::NitriteId) -> NitriteResult<super::operation::WriteResult> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        let result = self.synced(self.operations.restore(id))?;
+        self.audit_write("restore", None, result.affected_nitrite_ids());
+        Ok(result)
+    }
+
+    fn purge_deleted(&self) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.purge_deleted()
+    }
+
+    fn sweep_expired_fields(&self) -> NitriteResult<u64> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.sweep_expired_fields()
+    }
+
+    fn set_redaction_policy(&self, policy: Option<RedactionPolicy>) -> NitriteResult<()> {
+        let _guard = self.lock_handle.write();
+        self.ensure_opened()?;
+        self.operations.set_redaction_policy(policy);
+        Ok(())
+    }
+
+    fn redaction_policy(&self) -> NitriteResult<Option<RedactionPolicy>> {
+        let _guard = self.lock_handle.read();
+        self.ensure_opened()?;
+        Ok(self.operations.redaction_policy())
     }
 
     fn name(&self) -> String {
@@ -373,11 +650,21 @@ impl NitriteCollectionProvider for DefaultNitriteCollection {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::collection::{CollectionEventListener, Document, FindOptions, NitriteId};
+    use crate::collection::{
+        BulkOperation, BulkWriteOptions, CollectionEventInfo, CollectionEventListener,
+        CollectionEvents, CollectionOptions, Document, EventReason, FindOptions, InsertManyOptions, NitriteId,
+        UpdateEachOptions,
+        WriteDurability,
+    };
     use crate::common::ProcessorProvider;
-    use crate::filter::field;
+    use crate::filter::{all, by_id_range, field};
     use crate::index::{unique_index, IndexOptions};
     use crate::nitrite_config::NitriteConfig;
+    use crate::collection::HistoryOptions;
+    use crate::common::Value;
+    use crate::doc;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
 
     fn setup_collection() -> DefaultNitriteCollection {
         let nitrite_config = NitriteConfig::default();
@@ -436,6 +723,18 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_set_and_remove_attribute() {
+        let collection = setup_collection();
+        let value = crate::common::Value::String("v1".to_string());
+        collection.set_attribute("schema_hash", value.clone()).unwrap();
+        assert_eq!(collection.get_attribute("schema_hash").unwrap(), Some(value));
+        assert!(collection.get_attributes().unwrap().has_key("schema_hash"));
+
+        collection.remove_attribute("schema_hash").unwrap();
+        assert!(collection.get_attribute("schema_hash").unwrap().is_none());
+    }
+
     #[test]
     fn test_add_processor() {
         let collection = setup_collection();
@@ -793,5 +1092,724 @@ mod tests {
         let between = field("created_at").between_optional_inclusive(1000i64, 3000i64);
         assert_eq!(c.find(and(vec![field("tags").in_array(vec!["todo"]), between])).unwrap().count(), 1);
     }
-}
 
+    fn pause() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_document_history_records_writes() {
+        let c = setup_collection();
+        c.enable_history(HistoryOptions::new()).unwrap();
+
+        let id = c.insert(doc! { name: "a", qty: 1 }).unwrap().affected_nitrite_ids()[0];
+        c.update_by_id(&id, &doc! { qty: 2 }, false).unwrap();
+        c.remove(field("name").eq("a"), false).unwrap();
+
+        let history = c.document_history(&id).unwrap();
+        let revisions: Vec<i32> = history.iter().map(|v| v.revision).collect();
+        assert_eq!(revisions, vec![1, 2, 3]);
+        assert_eq!(history[1].document.as_ref().unwrap().get("qty").unwrap(), Value::I32(2));
+        assert!(history[2].is_removed());
+    }
+
+    #[test]
+    fn test_find_as_of() {
+        let c = setup_collection();
+        c.insert(doc! { name: "untracked", qty: 0 }).unwrap();
+        c.enable_history(HistoryOptions::new()).unwrap();
+        let id = c.insert(doc! { name: "a", qty: 1 }).unwrap().affected_nitrite_ids()[0];
+        pause();
+        let before_update = crate::get_current_time_or_zero();
+        pause();
+        c.update_by_id(&id, &doc! { qty: 2 }, false).unwrap();
+        pause();
+        let before_remove = crate::get_current_time_or_zero();
+        pause();
+        c.remove(field("name").eq("a"), false).unwrap();
+
+        let qty_as_of = |time| -> Vec<Value> {
+            c.find_as_of(time, field("name").eq("a"))
+                .unwrap()
+                .map(|doc| doc.unwrap().get("qty").unwrap())
+                .collect()
+        };
+        assert_eq!(qty_as_of(before_update), vec![Value::I32(1)]);
+        assert_eq!(qty_as_of(before_remove), vec![Value::I32(2)]);
+        assert!(qty_as_of(crate::get_current_time_or_zero()).is_empty());
+        assert!(qty_as_of(0).is_empty());
+
+        // documents not written since history was enabled resolve to their current state
+        assert_eq!(c.find_as_of(before_update, crate::filter::all()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_history_of_restored_document() {
+        let c = setup_collection();
+        c.set_options(CollectionOptions::new().soft_delete(true)).unwrap();
+        c.enable_history(HistoryOptions::new()).unwrap();
+        let id = c.insert(doc! { name: "a", qty: 1 }).unwrap().affected_nitrite_ids()[0];
+        pause();
+        let before_remove = crate::get_current_time_or_zero();
+        pause();
+        c.remove(field("name").eq("a"), false).unwrap();
+        pause();
+        let before_restore = crate::get_current_time_or_zero();
+        pause();
+        c.restore(&id).unwrap();
+
+        // the restore starts the revision over but does not replace the first version
+        let history = c.document_history(&id).unwrap();
+        let revisions: Vec<i32> = history.iter().map(|v| v.revision).collect();
+        assert_eq!(revisions, vec![1, 2, 1]);
+        assert!(history[1].is_removed());
+        assert_eq!(history[2].document.as_ref().unwrap().get("qty").unwrap(), Value::I32(1));
+
+        let found_as_of = |time| c.find_as_of(time, field("name").eq("a")).unwrap().count();
+        assert_eq!(found_as_of(before_remove), 1);
+        assert_eq!(found_as_of(before_restore), 0);
+        assert_eq!(found_as_of(crate::get_current_time_or_zero()), 1);
+    }
+
+    #[test]
+    fn test_history_keeps_state_before_enabling() {
+        let c = setup_collection();
+        let id = c.insert(doc! { name: "a", qty: 1 }).unwrap().affected_nitrite_ids()[0];
+        c.enable_history(HistoryOptions::new()).unwrap();
+        c.update_by_id(&id, &doc! { qty: 2 }, false).unwrap();
+
+        let history = c.document_history(&id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].document.as_ref().unwrap().get("qty").unwrap(), Value::I32(1));
+    }
+
+    #[test]
+    fn test_history_bounded_by_versions() {
+        let c = setup_collection();
+        c.enable_history(HistoryOptions::new().max_versions(2)).unwrap();
+        let id = c.insert(doc! { qty: 0 }).unwrap().affected_nitrite_ids()[0];
+        for qty in 1..5 {
+            c.update_by_id(&id, &doc! { qty: qty }, false).unwrap();
+        }
+
+        let revisions: Vec<i32> = c.document_history(&id).unwrap().iter().map(|v| v.revision).collect();
+        assert_eq!(revisions, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_disable_history() {
+        let c = setup_collection();
+        assert!(c.history_options().unwrap().is_none());
+        c.enable_history(HistoryOptions::new().max_versions(3)).unwrap();
+        assert_eq!(c.history_options().unwrap().unwrap().get_max_versions(), Some(3));
+        let id = c.insert(doc! { qty: 0 }).unwrap().affected_nitrite_ids()[0];
+        assert_eq!(c.document_history(&id).unwrap().len(), 1);
+
+        c.disable_history().unwrap();
+        assert!(c.history_options().unwrap().is_none());
+        assert!(c.document_history(&id).unwrap().is_empty());
+        c.update_by_id(&id, &doc! { qty: 1 }, false).unwrap();
+        assert!(c.document_history(&id).unwrap().is_empty());
+    }
+
+    fn planned_index(c: &DefaultNitriteCollection, filter: Filter) -> Vec<String> {
+        let cursor = c.find(filter).unwrap();
+        let descriptor = cursor.find_plan().unwrap().index_descriptor().unwrap();
+        descriptor.index_fields().field_names()
+    }
+
+    #[test]
+    fn test_analyze_picks_most_selective_index() {
+        let c = setup_collection();
+        c.create_index(vec!["status"], &IndexOptions::new(crate::NON_UNIQUE_INDEX)).unwrap();
+        c.create_index(vec!["region"], &IndexOptions::new(crate::NON_UNIQUE_INDEX)).unwrap();
+        for i in 0..100 {
+            let status = if i == 0 { "inactive" } else { "active" };
+            c.insert(doc! { status: status, region: (format!("r{}", i % 20)) }).unwrap();
+        }
+        assert!(c.index_statistics(vec!["status"]).unwrap().is_none());
+
+        c.analyze().unwrap();
+        let statistics = c.index_statistics(vec!["status"]).unwrap().unwrap();
+        assert_eq!(statistics.total_entries(), 100);
+        assert_eq!(statistics.distinct_keys(), 2);
+        assert_eq!(statistics.most_common()[0], (Value::from("active"), 99));
+
+        let common = field("status").eq("active").and(field("region").eq("r1"));
+        assert_eq!(planned_index(&c, common.clone()), vec!["region"]);
+        assert_eq!(c.find(common).unwrap().count(), 5);
+
+        let rare = field("status").eq("inactive").and(field("region").eq("r0"));
+        assert_eq!(planned_index(&c, rare.clone()), vec!["status"]);
+        assert_eq!(c.find(rare).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_rebuild_index_discards_statistics() {
+        let c = setup_collection();
+        c.create_index(vec!["status"], &IndexOptions::new(crate::NON_UNIQUE_INDEX)).unwrap();
+        c.insert(doc! { status: "active" }).unwrap();
+        c.analyze().unwrap();
+        assert!(c.index_statistics(vec!["status"]).unwrap().is_some());
+
+        c.rebuild_index(vec!["status"]).unwrap();
+        assert!(c.index_statistics(vec!["status"]).unwrap().is_none());
+    }
+
+    fn has_index_plan(c: &DefaultNitriteCollection, filter: Filter) -> bool {
+        let cursor = c.find(filter).unwrap();
+        cursor.find_plan().unwrap().index_descriptor().is_some()
+    }
+
+    #[test]
+    fn test_defer_index_maintenance_rebuilds_indexes() {
+        let c = setup_collection();
+        c.create_index(vec!["code"], &IndexOptions::new(crate::UNIQUE_INDEX)).unwrap();
+        c.insert(doc! { code: 100 }).unwrap();
+
+        c.defer_index_maintenance(&mut || {
+            for i in (0..50).rev() {
+                c.insert(doc! { code: i })?;
+            }
+            // the stale index is not used meanwhile
+            assert!(!has_index_plan(&c, field("code").eq(7)));
+            assert_eq!(c.find(field("code").eq(7))?.count(), 1);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(planned_index(&c, field("code").eq(7)), vec!["code"]);
+        assert_eq!(c.find(field("code").eq(7)).unwrap().count(), 1);
+        assert_eq!(c.find(field("code").gte(40)).unwrap().count(), 11);
+        assert!(c.insert(doc! { code: 7 }).is_err());
+    }
+
+    #[test]
+    fn test_defer_index_maintenance_failed_rebuild_keeps_index_dirty() {
+        let c = setup_collection();
+        c.create_index(vec!["code"], &IndexOptions::new(crate::UNIQUE_INDEX)).unwrap();
+
+        let result = c.defer_index_maintenance(&mut || {
+            c.insert(doc! { code: 1 })?;
+            c.insert(doc! { code: 1 })?;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert!(!has_index_plan(&c, field("code").eq(1)));
+        assert_eq!(c.find(field("code").eq(1)).unwrap().count(), 2);
+
+        let duplicate = c.find(field("code").eq(1)).unwrap().next().unwrap().unwrap();
+        c.operations.remove_document(&duplicate).unwrap();
+        c.rebuild_index(vec!["code"]).unwrap();
+        assert!(has_index_plan(&c, field("code").eq(1)));
+        assert_eq!(c.find(field("code").eq(1)).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_defer_index_maintenance_does_not_nest() {
+        let c = setup_collection();
+        c.create_index(vec!["code"], &IndexOptions::new(crate::UNIQUE_INDEX)).unwrap();
+
+        let result = c.defer_index_maintenance(&mut || {
+            let nested = c.defer_index_maintenance(&mut || Ok(()));
+            assert_eq!(nested.unwrap_err().kind(), &ErrorKind::InvalidOperation);
+            c.insert(doc! { code: 1 })?;
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert!(has_index_plan(&c, field("code").eq(1)));
+    }
+
+    fn hinted_collection() -> DefaultNitriteCollection {
+        let c = setup_collection();
+        c.create_index(vec!["status"], &IndexOptions::new(crate::NON_UNIQUE_INDEX)).unwrap();
+        c.create_index(vec!["region"], &IndexOptions::new(crate::NON_UNIQUE_INDEX)).unwrap();
+        for i in 0..10 {
+            c.insert(doc! { status: (if i % 2 == 0 { "on" } else { "off" }), region: (i % 3) }).unwrap();
+        }
+        c
+    }
+
+    #[test]
+    fn test_find_options_hint_forces_index() {
+        let c = hinted_collection();
+        let filter = field("status").eq("on").and(field("region").eq(0));
+        for index in ["status", "region"] {
+            let cursor = c.find_with_options(filter.clone(), &FindOptions::new().hint(index)).unwrap();
+            let plan = cursor.find_plan().unwrap();
+            assert_eq!(plan.index_descriptor().unwrap().index_fields().field_names(), vec![index]);
+            assert_eq!(cursor.count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_hint_forces_collection_scan() {
+        let c = hinted_collection();
+        let options = FindOptions::new().hint(crate::index::IndexHint::CollectionScan);
+        let cursor = c.find_with_options(field("status").eq("on"), &options).unwrap();
+        let plan = cursor.find_plan().unwrap();
+        assert!(plan.index_descriptor().is_none());
+        assert!(plan.full_scan_filter().is_some());
+        assert_eq!(cursor.count(), 5);
+    }
+
+    #[test]
+    fn test_filter_hint() {
+        let c = hinted_collection();
+        let filter = field("status").eq("off").and(field("region").with_hint("region").eq(1));
+        let cursor = c.find(filter).unwrap();
+        let plan = cursor.find_plan().unwrap();
+        assert_eq!(plan.index_descriptor().unwrap().index_fields().field_names(), vec!["region"]);
+        assert_eq!(cursor.count(), 2);
+    }
+
+    #[test]
+    fn test_invalid_hints() {
+        let c = hinted_collection();
+        let kind = |result: NitriteResult<crate::DocumentCursor>| result.err().map(|e| e.kind().clone());
+
+        // no index on the hinted field
+        let missing = c.find_with_options(field("status").eq("on"), &FindOptions::new().hint("name"));
+        assert_eq!(kind(missing), Some(ErrorKind::IndexNotFound));
+
+        // the hinted index cannot serve the filter
+        let unusable = c.find_with_options(field("status").eq("on"), &FindOptions::new().hint("region"));
+        assert_eq!(kind(unusable), Some(ErrorKind::FilterError));
+
+        // hints of the options and the filter disagree
+        let conflicting = c.find_with_options(
+            field("status").eq("on").with_hint("status"),
+            &FindOptions::new().hint("region"),
+        );
+        assert_eq!(kind(conflicting), Some(ErrorKind::FilterError));
+    }
+
+    #[test]
+    fn test_case_insensitive_unique_index() {
+        let c = setup_collection();
+        let options = IndexOptions::new(crate::UNIQUE_INDEX).case_insensitive(true);
+        c.create_index(vec!["email"], &options).unwrap();
+        c.insert(doc! { email: "Foo@x.com" }).unwrap();
+
+        let duplicate = c.insert(doc! { email: "foo@x.com" });
+        assert_eq!(
+            duplicate.err().map(|e| e.kind().clone()),
+            Some(ErrorKind::UniqueConstraintViolation)
+        );
+
+        // the index folds case, the documents and equality filters do not
+        let mut cursor = c.find(field("email").eq("Foo@x.com")).unwrap();
+        assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+        let doc = cursor.next().unwrap().unwrap();
+        assert_eq!(doc.get("email").unwrap(), Value::from("Foo@x.com"));
+        assert_eq!(c.find(field("email").eq("FOO@X.COM")).unwrap().count(), 0);
+        assert_eq!(c.find(field("email").in_array(vec!["foo@x.com", "Foo@x.com"])).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_case_insensitive_index_options() {
+        let c = setup_collection();
+        c.create_index(vec!["email"], &IndexOptions::new(crate::UNIQUE_INDEX)).unwrap();
+        let options = IndexOptions::new(crate::UNIQUE_INDEX).case_insensitive(true);
+        assert!(c.create_index(vec!["email"], &options).is_err());
+
+        let options = IndexOptions::new(crate::FULL_TEXT_INDEX).case_insensitive(true);
+        assert!(c.create_index(vec!["bio"], &options).is_err());
+    }
+
+    #[test]
+    fn test_sparse_index() {
+        let c = setup_collection();
+        let options = IndexOptions::new(crate::UNIQUE_INDEX).sparse(true);
+        c.create_index(vec!["referral_code"], &options).unwrap();
+        c.insert(doc! { name: "a", referral_code: "R1" }).unwrap();
+        // a sparse unique index lets any number of documents omit the field
+        c.insert(doc! { name: "b" }).unwrap();
+        c.insert(doc! { name: "c" }).unwrap();
+
+        let cursor = c.find(field("referral_code").eq("R1")).unwrap();
+        assert!(cursor.find_plan().unwrap().index_descriptor().is_some());
+        assert_eq!(cursor.count(), 1);
+
+        // null queries cannot be answered by a sparse index
+        let cursor = c.find(field("referral_code").eq(Value::Null)).unwrap();
+        assert!(cursor.find_plan().unwrap().index_descriptor().is_none());
+        assert_eq!(cursor.count(), 2);
+
+        let cursor = c.find(field("referral_code").in_array(vec![Value::from("R1"), Value::Null])).unwrap();
+        assert!(cursor.find_plan().unwrap().index_descriptor().is_none());
+        assert_eq!(cursor.count(), 3);
+
+        // updating a document into and out of the index keeps it consistent
+        c.update(field("name").eq("b"), &doc! { referral_code: "R2" }).unwrap();
+        assert_eq!(c.find(field("referral_code").eq("R2")).unwrap().count(), 1);
+        assert!(c.insert(doc! { name: "d", referral_code: "R2" }).is_err());
+    }
+
+    #[test]
+    fn test_bulk_write() {
+        let c = setup_collection();
+        c.insert_many(vec![doc! { sku: "A", stock: 0 }, doc! { sku: "B", stock: 0 }, doc! { sku: "C", stock: 5 }])
+            .unwrap();
+
+        let result = c
+            .bulk_write(vec![
+                BulkOperation::InsertOne(doc! { sku: "D", stock: 1 }),
+                BulkOperation::UpdateMany {
+                    filter: field("stock").eq(0),
+                    update: doc! { status: "sold out" },
+                    upsert: false,
+                },
+                BulkOperation::ReplaceOne {
+                    filter: field("sku").eq("C"),
+                    replacement: doc! { sku: "C", stock: 4 },
+                    upsert: false,
+                },
+                BulkOperation::UpdateOne {
+                    filter: field("sku").eq("E"),
+                    update: doc! { sku: "E", stock: 9 },
+                    upsert: true,
+                },
+                BulkOperation::DeleteOne(field("sku").eq("A")),
+            ])
+            .unwrap();
+
+        assert!(result.is_applied());
+        assert_eq!(result.inserted_ids().len(), 1);
+        assert_eq!(result.upserted_ids().len(), 1);
+        assert_eq!(result.modified_count(), 3);
+        assert_eq!(result.deleted_count(), 1);
+        assert_eq!(c.size().unwrap(), 4);
+        assert_eq!(c.find(field("status").eq("sold out")).unwrap().count(), 1);
+
+        let replaced = c.find(field("sku").eq("C")).unwrap().next().unwrap().unwrap();
+        assert_eq!(replaced.get("stock").unwrap(), Value::from(4));
+    }
+
+    #[test]
+    fn test_bulk_write_rolls_back_on_failure() {
+        let c = setup_collection();
+        c.create_index(vec!["sku"], &unique_index()).unwrap();
+        c.insert_many(vec![doc! { sku: "A", stock: 1 }, doc! { sku: "B", stock: 2 }]).unwrap();
+
+        let operations = || {
+            vec![
+                BulkOperation::InsertOne(doc! { sku: "C", stock: 3 }),
+                BulkOperation::UpdateMany { filter: all(), update: doc! { stock: 0 }, upsert: false },
+                BulkOperation::InsertOne(doc! { sku: "A" }),
+                BulkOperation::DeleteMany(field("sku").eq("B")),
+                BulkOperation::InsertOne(doc! { sku: "B" }),
+            ]
+        };
+
+        // an ordered batch stops at the first failure
+        let result = c.bulk_write(operations()).unwrap();
+        assert!(!result.is_applied());
+        assert_eq!(result.errors().len(), 1);
+        assert_eq!(result.errors()[0].index(), 2);
+        assert_eq!(result.errors()[0].error().kind(), &ErrorKind::UniqueConstraintViolation);
+        assert!(result.inserted_ids().is_empty());
+        assert_eq!(result.modified_count(), 0);
+
+        // an unordered batch reports every failure
+        let options = BulkWriteOptions::new().ordered(false);
+        let result = c.bulk_write_with_options(operations(), &options).unwrap();
+        assert_eq!(result.errors().iter().map(|e| e.index()).collect::<Vec<_>>(), vec![2]);
+
+        // either way the collection is unchanged
+        assert_eq!(c.size().unwrap(), 2);
+        assert_eq!(c.find(field("stock").eq(0)).unwrap().count(), 0);
+        assert_eq!(c.find(field("sku").eq("C")).unwrap().count(), 0);
+        assert_eq!(c.find(field("sku").eq("B")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_insert_many_with_options_reports_invalid_documents() {
+        let c = setup_collection();
+        c.set_options(CollectionOptions::new().required_field("sku")).unwrap();
+        let id = c.insert(doc! { sku: "A" }).unwrap().affected_nitrite_ids()[0];
+        let existing = c.get_by_id(&id).unwrap().unwrap();
+
+        let documents = || {
+            vec![
+                doc! { sku: "B" },
+                doc! { name: "no sku" },
+                existing.clone(),
+                doc! { sku: "C" },
+            ]
+        };
+
+        // by default nothing is inserted
+        let result = c
+            .insert_many_with_options(documents(), &InsertManyOptions::new())
+            .unwrap();
+        assert!(!result.is_valid());
+        assert!(result.inserted_ids().is_empty());
+        let errors: Vec<(usize, ErrorKind)> = result
+            .errors()
+            .iter()
+            .map(|invalid| (invalid.index(), invalid.error().kind().clone()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (1, ErrorKind::ValidationError),
+                (2, ErrorKind::UniqueConstraintViolation)
+            ]
+        );
+        assert_eq!(c.size().unwrap(), 1);
+
+        // the valid documents are inserted when invalid ones are skipped
+        let options = InsertManyOptions::new().skip_invalid(true);
+        let result = c.insert_many_with_options(documents(), &options).unwrap();
+        assert_eq!(result.errors().len(), 2);
+        assert_eq!(result.inserted_ids().len(), 2);
+        assert_eq!(c.size().unwrap(), 3);
+        assert_eq!(c.find(field("sku").eq("C")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_insert_many_with_options_rejects_duplicate_ids_in_batch() {
+        let c = setup_collection();
+        let mut document = doc! { sku: "A" };
+        document.id().unwrap();
+
+        let options = InsertManyOptions::new().skip_invalid(true);
+        let result = c
+            .insert_many_with_options(vec![document.clone(), document], &options)
+            .unwrap();
+        assert_eq!(result.inserted_ids().len(), 1);
+        assert_eq!(result.errors().len(), 1);
+        assert_eq!(result.errors()[0].index(), 1);
+        assert_eq!(c.size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_update_each() {
+        let c = setup_collection();
+        for n in 0..10 {
+            c.insert(doc! { n: n, processed: false }).unwrap();
+        }
+
+        let options = UpdateEachOptions::new().batch_size(3);
+        let result = c
+            .update_each_with_options(
+                field("n").gte(2),
+                &mut |mut doc| {
+                    if doc.get("n")?.as_i32().is_some_and(|n| n % 2 == 0) {
+                        doc.put("processed", true)?;
+                        return Ok(Some(doc));
+                    }
+                    Ok(None)
+                },
+                &options,
+            )
+            .unwrap();
+        assert_eq!(result.matched_count(), 8);
+        assert_eq!(result.modified_count(), 4);
+        assert!(result.batch_count() >= 2);
+        assert_eq!(c.find(field("processed").eq(true)).unwrap().count(), 4);
+        assert_eq!(c.size().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_update_each_stops_at_error() {
+        let c = setup_collection();
+        for n in 0..6 {
+            c.insert(doc! { n: n }).unwrap();
+        }
+
+        let mut seen = 0;
+        let options = UpdateEachOptions::new().batch_size(2);
+        let err = c
+            .update_each_with_options(
+                all(),
+                &mut |mut doc| {
+                    seen += 1;
+                    if seen == 4 {
+                        return Err(NitriteError::new("stop", ErrorKind::InvalidOperation));
+                    }
+                    doc.put("touched", true)?;
+                    Ok(Some(doc))
+                },
+                &options,
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidOperation);
+        // the first batch is written, the failing one is not
+        assert_eq!(c.find(field("touched").eq(true)).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_find_by_id_range() {
+        let c = setup_collection();
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            let result = c.insert(doc! { n: i, even: (i % 2 == 0) }).unwrap();
+            ids.extend(result.affected_nitrite_ids());
+        }
+
+        let cursor = c.find(by_id_range(ids[2], ids[6])).unwrap();
+        assert!(cursor.find_plan().unwrap().by_id_filter().is_some());
+        let values: Vec<i32> = cursor
+            .map(|doc| *doc.unwrap().get("n").unwrap().as_i32().unwrap())
+            .collect();
+        assert_eq!(values, vec![2, 3, 4, 5, 6]);
+
+        // other filters are checked on the documents in the range
+        let cursor = c.find(by_id_range(ids[2], ids[6]).and(field("even").eq(true))).unwrap();
+        assert!(cursor.find_plan().unwrap().by_id_filter().is_some());
+        assert_eq!(cursor.count(), 3);
+
+        // ids created in a time range
+        let (start, end) = NitriteId::from_time_range(0, ids[9].timestamp()).unwrap();
+        assert_eq!(c.find(by_id_range(start, end)).unwrap().count(), 10);
+        let (start, end) = NitriteId::from_time_range(ids[9].timestamp() + 1, u64::MAX as u128).unwrap();
+        assert_eq!(c.find(by_id_range(start, end)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_required_fields() {
+        let c = setup_collection();
+        c.set_options(CollectionOptions::new().required_field("level").required_field("meta.host"))
+            .unwrap();
+
+        assert!(c.insert(doc! { level: "info", meta: { host: "a" } }).is_ok());
+        let err = c.insert(doc! { level: "info" }).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ValidationError);
+        let mut update = Document::new();
+        update.put("level", Value::Null).unwrap();
+        assert!(c.update(field("level").eq("info"), &update).is_err());
+        assert_eq!(c.find(field("level").eq("info")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_soft_delete() {
+        let c = setup_collection();
+        c.set_options(CollectionOptions::new().soft_delete(true)).unwrap();
+        let id = c.insert(doc! { name: "a" }).unwrap().affected_nitrite_ids()[0];
+        c.insert(doc! { name: "b" }).unwrap();
+
+        c.remove(field("name").eq("a"), false).unwrap();
+        assert_eq!(c.size().unwrap(), 1);
+        let deleted = c.deleted_documents().unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].get("name").unwrap(), Value::from("a"));
+
+        assert_eq!(c.restore(&id).unwrap().affected_nitrite_ids(), &vec![id]);
+        assert_eq!(c.find(field("name").eq("a")).unwrap().count(), 1);
+        assert!(c.deleted_documents().unwrap().is_empty());
+        assert!(c.restore(&id).unwrap().affected_nitrite_ids().is_empty());
+
+        c.remove(all(), false).unwrap();
+        assert_eq!(c.deleted_documents().unwrap().len(), 2);
+        c.purge_deleted().unwrap();
+        assert!(c.deleted_documents().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sync_durability() {
+        let c = setup_collection();
+        c.set_options(CollectionOptions::new().durability(WriteDurability::Sync)).unwrap();
+        assert_eq!(c.options().unwrap().get_durability(), WriteDurability::Sync);
+        c.insert(doc! { name: "a" }).unwrap();
+        assert!(!c.store().unwrap().has_unsaved_changes().unwrap());
+    }
+
+    #[test]
+    fn test_capped_by_documents() {
+        let c = setup_collection();
+        c.set_options(CollectionOptions::new().max_documents(3)).unwrap();
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let evicted_clone = evicted.clone();
+        c.subscribe(CollectionEventListener::new(move |event: CollectionEventInfo| {
+            if event.event_type() == CollectionEvents::Remove && event.reason() == EventReason::Eviction {
+                if let Some(Value::Document(doc)) = event.item() {
+                    evicted_clone.lock().push(doc.get("seq")?);
+                }
+            }
+            Ok(())
+        }))
+        .unwrap();
+
+        for seq in 0..5 {
+            c.insert(doc! { seq: seq }).unwrap();
+        }
+        assert_eq!(c.size().unwrap(), 3);
+        let remaining: Vec<Value> = c
+            .find(all())
+            .unwrap()
+            .map(|doc| doc.unwrap().get("seq").unwrap())
+            .collect();
+        assert!(!remaining.contains(&Value::I32(0)));
+        assert!(!remaining.contains(&Value::I32(1)));
+
+        for _ in 0..100 {
+            if evicted.lock().len() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(*evicted.lock(), vec![Value::I32(0), Value::I32(1)]);
+    }
+
+    #[test]
+    fn test_capped_by_bytes() {
+        let c = setup_collection();
+        let payload = "x".repeat(100);
+        c.insert_many(vec![doc! { payload: (payload.clone()) }, doc! { payload: (payload.clone()) }])
+            .unwrap();
+        c.set_options(CollectionOptions::new().max_bytes(250)).unwrap();
+        assert_eq!(c.size().unwrap(), 2);
+
+        c.insert(doc! { payload: (payload.clone()) }).unwrap();
+        assert_eq!(c.size().unwrap(), 1);
+
+        // the newest document is kept even when it alone is over the cap
+        c.insert(doc! { payload: ("y".repeat(500)) }).unwrap();
+        assert_eq!(c.size().unwrap(), 1);
+        assert_eq!(c.find(field("payload").eq(payload)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_document_quota() {
+        let c = setup_collection();
+        let rejected = Arc::new(Mutex::new(Vec::new()));
+        let rejected_clone = rejected.clone();
+        let options = CollectionOptions::new()
+            .document_quota(2)
+            .on_quota_exceeded(move |doc| rejected_clone.lock().push(doc.get("seq").unwrap()));
+        c.set_options(options).unwrap();
+
+        c.insert_many(vec![doc! { seq: 0 }, doc! { seq: 1 }]).unwrap();
+        let err = c.insert(doc! { seq: 2 }).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::QuotaExceeded);
+        assert_eq!(c.size().unwrap(), 2);
+
+        // updates do not add documents
+        c.update(field("seq").eq(0), &doc! { note: "kept" }).unwrap();
+        c.remove(field("seq").eq(1), false).unwrap();
+        c.insert(doc! { seq: 3 }).unwrap();
+        assert_eq!(c.size().unwrap(), 2);
+        assert_eq!(*rejected.lock(), vec![Value::I32(2)]);
+    }
+
+    #[test]
+    fn test_byte_quota() {
+        let c = setup_collection();
+        let payload = "x".repeat(100);
+        c.insert(doc! { payload: (payload.clone()) }).unwrap();
+        c.set_options(CollectionOptions::new().byte_quota(250)).unwrap();
+
+        let err = c
+            .update(field("payload").eq(payload.clone()), &doc! { payload: ("x".repeat(300)) })
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::QuotaExceeded);
+        assert_eq!(c.find(field("payload").eq(payload.clone())).unwrap().count(), 1);
+
+        // a write that shrinks the collection is accepted over a lowered quota
+        c.set_options(CollectionOptions::new().byte_quota(10)).unwrap();
+        c.update(field("payload").eq(payload), &doc! { payload: "y" }).unwrap();
+        assert_eq!(c.find(field("payload").eq("y")).unwrap().count(), 1);
+    }
+}
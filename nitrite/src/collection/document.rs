@@ -2,13 +2,18 @@ use smallvec::SmallVec;
 use im::OrdMap;
 
 use crate::collection::nitrite_id::NitriteId;
-use crate::common::{ReadExecutor, Value, DOC_ID, DOC_MODIFIED, DOC_REVISION, DOC_SOURCE, RESERVED_FIELDS};
+use crate::collection::DocumentBuilder;
+use crate::common::{
+    content_hash_128, expiry_field, get_current_time_or_zero, is_reserved_field, modified_field,
+    revision_field, source_field, ReadExecutor, Value, DOC_ID,
+};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::FIELD_SEPARATOR;
 use itertools::Itertools;
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Display};
+use std::time::Duration;
 
 type FieldVec = SmallVec<[String; 8]>;
 
@@ -31,6 +36,10 @@ type FieldVec = SmallVec<[String; 8]>;
 /// * `_revision` - The revision number of the document.
 /// * `_source` - The source of the document.
 /// * `_modified` - The last modified time of the document.
+/// * `_hash` - The content hash of the document, for collections that store it.
+///
+/// The `_` prefix of the metadata fields can be changed with
+/// [`crate::nitrite_builder::NitriteBuilder::metadata_prefix`].
 ///
 /// ## Lock-Free Design
 ///
@@ -61,6 +70,22 @@ impl Document {
         }
     }
 
+    /// Creates a [`DocumentBuilder`], which sets fields without `Result` plumbing and
+    /// validates them all at once when the document is built.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let doc = Document::builder()
+    ///     .field("a", 1)
+    ///     .nested("loc", |b| b.field("city", "NY"))
+    ///     .build()?;
+    /// assert_eq!(doc.get("loc.city")?, Value::from("NY"));
+    /// ```
+    pub fn builder() -> DocumentBuilder {
+        DocumentBuilder::new()
+    }
+
     /// Checks if the document is empty.
     ///
     /// # Examples
@@ -144,6 +169,14 @@ impl Document {
         // accordingly associated with the embedded field.
         if FIELD_SEPARATOR.read_with(|sep| key.contains(sep)) {
             let splits: Vec<&str> = FIELD_SEPARATOR.read_with(|it| key.split(it).collect());
+            // reject the key before deep_put creates any of the embedded documents
+            if splits.iter().any(|split| split.is_empty()) {
+                log::error!("Document does not support empty key");
+                return Err(NitriteError::new(
+                    "Document does not support empty key",
+                    ErrorKind::InvalidOperation,
+                ));
+            }
             self.deep_put(&splits, value)
         } else {
             self.data = self.data.update(key.to_string(), value);
@@ -213,6 +246,108 @@ impl Document {
         }
     }
 
+    /// Returns a reference to a top-level field, without looking up embedded fields.
+    pub(crate) fn field_ref(&self, key: &str) -> Option<&Value> {
+        self.data.get(key)
+    }
+
+    /// Returns the [Value] referenced by an RFC 6901 JSON Pointer, or [Value::Null]
+    /// if the pointer does not resolve to a value.
+    ///
+    /// Unlike [Document::get], every pointer token is taken literally, so keys containing
+    /// the field separator can be addressed. `~1` and `~0` escape `/` and `~` respectively,
+    /// and numeric tokens index into arrays. The empty pointer refers to the whole document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pointer is not empty and does not start with `/`, or
+    /// contains an invalid `~` escape.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let doc = doc!{
+    ///     "location": { "address": { "zip": 10001 } },
+    ///     "tags": ["a", "b"],
+    ///     "a/b": 1
+    /// };
+    /// assert_eq!(doc.get_path("/location/address/zip")?, Value::I32(10001));
+    /// assert_eq!(doc.get_path("/tags/1")?, Value::String("b".to_string()));
+    /// assert_eq!(doc.get_path("/a~1b")?, Value::I32(1));
+    /// ```
+    pub fn get_path(&self, pointer: &str) -> NitriteResult<Value> {
+        if pointer.is_empty() {
+            return Ok(Value::Document(self.clone()));
+        }
+
+        let Some(tokens) = pointer.strip_prefix('/') else {
+            log::error!("Invalid JSON pointer {}", pointer);
+            return Err(NitriteError::new(
+                &format!("Invalid JSON pointer {}, it must start with '/'", pointer),
+                ErrorKind::ValidationError,
+            ));
+        };
+
+        let mut current = Value::Document(self.clone());
+        for token in tokens.split('/') {
+            let token = unescape_pointer_token(token)?;
+            let next = match &current {
+                Value::Document(obj) => obj.data.get(&token).cloned(),
+                Value::Array(arr) => parse_pointer_index(&token).and_then(|i| arr.get(i).cloned()),
+                _ => None,
+            };
+
+            match next {
+                Some(value) => current = value,
+                None => return Ok(Value::Null),
+            }
+        }
+        Ok(current)
+    }
+
+    /// Returns all values matching a path which may contain `*` wildcards.
+    ///
+    /// The path uses the field separator like [Document::get]. A `*` segment matches
+    /// every field of a nested document or every element of an array, a numeric segment
+    /// indexes into an array, and any other segment on an array is applied to each of
+    /// its elements. Paths that do not resolve contribute no values.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let doc = doc!{
+    ///     "items": [
+    ///         { "name": "pen", "price": 2 },
+    ///         { "name": "book", "price": 10 }
+    ///     ]
+    /// };
+    /// let names = doc.query_path("items.*.name")?;
+    /// assert_eq!(names, vec![Value::from("pen"), Value::from("book")]);
+    /// ```
+    pub fn query_path(&self, path: &str) -> NitriteResult<Vec<Value>> {
+        if path.is_empty() {
+            log::error!("Document does not support empty key");
+            return Err(NitriteError::new(
+                "Document does not support empty key",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        let separator = FIELD_SEPARATOR.read_with(|s| s.clone());
+        let splits: Vec<&str> = path.split(&separator).collect();
+        if splits.iter().any(|s| s.is_empty()) {
+            log::error!("Document does not support empty key");
+            return Err(NitriteError::new(
+                "Document does not support empty key",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        let mut results = Vec::new();
+        collect_path_matches(&Value::Document(self.clone()), &splits, &mut results);
+        Ok(results)
+    }
+
     /// Return the [NitriteId] associated with this document.
     ///
     /// If the document does not have an `_id` field, this method automatically generates
@@ -251,7 +386,7 @@ impl Document {
         } else {
             // if _id field is not populated already, create a new id
             // and set it in the document
-            let nitrite_id = NitriteId::new();
+            let nitrite_id = NitriteId::try_new()?;
             self.data = self.data.update(
                 DOC_ID.to_string(),
                 Value::NitriteId(nitrite_id),
@@ -564,7 +699,7 @@ impl Document {
     /// // revision will be > 0 depending on update history
     /// ```
     pub fn revision(&self) -> NitriteResult<i32> {
-        if let Ok(Value::I32(revision)) = self.get(DOC_REVISION) {
+        if let Ok(Value::I32(revision)) = self.get(&revision_field()) {
             Ok(revision)
         } else {
             Ok(0)
@@ -589,13 +724,35 @@ impl Document {
     /// // Documents from collections may have source set by the database
     /// ```
     pub fn source(&self) -> NitriteResult<String> {
-        if let Ok(Value::String(source)) = self.get(DOC_SOURCE) {
+        if let Ok(Value::String(source)) = self.get(&source_field()) {
             Ok(source.clone())
         } else {
             Ok("".to_string())
         }
     }
 
+    /// Returns a 128-bit hash of the content of this document, to tell whether two
+    /// documents hold the same data.
+    ///
+    /// The hash covers every field but `_id` and the metadata fields, so the same content
+    /// stored under another id or at another revision hashes alike. It is computed over a
+    /// normalized form of the content: integers hash by value whatever their type, as do
+    /// floats without a fraction, and null fields hash like missing ones. Field order does
+    /// not matter, array order does. The hash of a given content never changes between
+    /// versions, so it can be stored, see
+    /// [`CollectionOptions::content_hash`](crate::collection::CollectionOptions::content_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let a = doc!{ "name": "Ada", "age": 36 };
+    /// let b = doc!{ "age": 36i64, "name": "Ada" };
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u128 {
+        content_hash_128(self)
+    }
+
     /// Gets last modified time of this document since epoch.
     ///
     /// Returns the timestamp (in milliseconds since Unix epoch) when the document
@@ -617,13 +774,146 @@ impl Document {
     /// assert!(timestamp > 0);
     /// ```
     pub fn last_modified_since_epoch(&self) -> NitriteResult<i64> {
-        if let Ok(Value::I64(modified)) = self.get(DOC_MODIFIED) {
+        if let Ok(Value::I64(modified)) = self.get(&modified_field()) {
             Ok(modified)
         } else {
             Ok(0)
         }
     }
 
+    /// Associates a value with a key that expires after `ttl`.
+    ///
+    /// This is [`put`](Document::put) followed by
+    /// [`set_field_expiry`](Document::set_field_expiry) with a deadline of now plus `ttl`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut place = doc!{ "name": "Depot 7" };
+    /// place.put_with_ttl("geocode", doc!{ "lat": 52.1, "lon": 4.3 }, Duration::from_secs(3600))?;
+    /// ```
+    pub fn put_with_ttl<T: Into<Value>>(&mut self, key: &str, value: T, ttl: Duration) -> NitriteResult<()> {
+        self.put(key, value)?;
+        let deadline = get_current_time_or_zero().saturating_add(ttl.as_millis());
+        self.set_field_expiry(key, i64::try_from(deadline).unwrap_or(i64::MAX))
+    }
+
+    /// Sets the time, in milliseconds since epoch, after which a field expires.
+    ///
+    /// Once expired, the field is left out of the documents read from a collection and
+    /// filters see it as null, while the rest of the document stays intact. Expired fields
+    /// are removed from storage, and from the indexes, by
+    /// [`sweep_expired_fields`](crate::collection::NitriteCollectionProvider::sweep_expired_fields).
+    ///
+    /// The deadlines are kept in the `_expiry` metadata field. An update that sets a field
+    /// without a deadline of its own for it clears the stored deadline of that field.
+    pub fn set_field_expiry(&mut self, field: &str, deadline: i64) -> NitriteResult<()> {
+        let expiry_field = expiry_field();
+        let mut expiry = match self.get(&expiry_field)? {
+            Value::Document(expiry) => expiry,
+            _ => Document::new(),
+        };
+        expiry.put(field, Value::I64(deadline))?;
+        self.put(expiry_field, Value::Document(expiry))
+    }
+
+    /// Gets the expiry deadline of a field in milliseconds since epoch, if it has one.
+    pub fn field_expiry(&self, field: &str) -> NitriteResult<Option<i64>> {
+        match self.get(&expiry_field())? {
+            Value::Document(expiry) => match expiry.get(field)? {
+                Value::I64(deadline) => Ok(Some(deadline)),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Removes the expiry deadline of a field, keeping its value.
+    pub fn clear_field_expiry(&mut self, field: &str) -> NitriteResult<()> {
+        self.remove_expiry_entries(&expiry_field(), &[field.to_string()])
+    }
+
+    /// Parses the [raw JSON](Value::RawJson) of a field into documents, arrays and scalar
+    /// values, in place. A field holding any other value is left as it is.
+    ///
+    /// Once materialized, the field can be filtered on and indexed like any other; the
+    /// parsed form is stored when the document is written back.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidationError` if the JSON text of the field is not valid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let mut event = events.get_by_id(&id)?.unwrap();
+    /// event.materialize("payload")?;
+    /// assert_eq!(event.get("payload.sensor")?, Value::from("t1"));
+    /// ```
+    pub fn materialize(&mut self, key: &str) -> NitriteResult<()> {
+        let value = self.get(key)?;
+        if value.is_raw_json() {
+            self.put(key, value.materialize()?)?;
+        }
+        Ok(())
+    }
+
+    /// Associates `value` with `key` as given: the key is neither split on the field
+    /// separator nor validated, so this cannot fail.
+    pub(crate) fn put_literal(&mut self, key: String, value: Value) {
+        self.data = self.data.update(key, value);
+    }
+
+    /// Returns the fields whose expiry deadline is at or before `now`.
+    pub(crate) fn expired_fields(&self, expiry_field: &str, now: i64) -> NitriteResult<Vec<String>> {
+        let Value::Document(expiry) = self.get(expiry_field)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut expired = Vec::new();
+        for field in expiry.fields() {
+            if matches!(expiry.get(&field)?, Value::I64(deadline) if deadline <= now) {
+                expired.push(field);
+            }
+        }
+        Ok(expired)
+    }
+
+    /// Removes the given fields together with their expiry deadlines.
+    pub(crate) fn remove_expired_fields(&mut self, expiry_field: &str, fields: &[String]) -> NitriteResult<()> {
+        for field in fields {
+            self.remove(field)?;
+        }
+        self.remove_expiry_entries(expiry_field, fields)
+    }
+
+    /// Returns a copy of this document without the fields that expired at `now`, or
+    /// `None` if no field has expired.
+    pub(crate) fn without_expired_fields(&self, expiry_field: &str, now: i64) -> NitriteResult<Option<Document>> {
+        let expired = self.expired_fields(expiry_field, now)?;
+        if expired.is_empty() {
+            return Ok(None);
+        }
+
+        let mut document = self.clone();
+        document.remove_expired_fields(expiry_field, &expired)?;
+        Ok(Some(document))
+    }
+
+    fn remove_expiry_entries(&mut self, expiry_field: &str, fields: &[String]) -> NitriteResult<()> {
+        let Value::Document(mut expiry) = self.get(expiry_field)? else {
+            return Ok(());
+        };
+        for field in fields {
+            expiry.remove(field)?;
+        }
+        if expiry.is_empty() {
+            self.remove(expiry_field)
+        } else {
+            self.put(expiry_field, Value::Document(expiry))
+        }
+    }
+
     /// Converts this document to a [BTreeMap].
     ///
     /// Creates a new [BTreeMap] containing all the key-value pairs from this document.
@@ -741,7 +1031,7 @@ impl Document {
         // iterate top level keys
         for key in self.data.keys() {
             // ignore the reserved fields
-            if RESERVED_FIELDS.contains(&key.as_str()) {
+            if is_reserved_field(key) {
                 continue;
             }
 
@@ -920,8 +1210,7 @@ impl Document {
                     }
                 }
                 _ => {
-                    // if current level value is null, remove the key
-                    self.data = self.data.without(key);
+                    // a missing or scalar value has no embedded field to remove
                     Ok(())
                 }
             }
@@ -1085,8 +1374,84 @@ pub fn normalize(value: &str) -> String {
     value.trim_matches('"').to_string()
 }
 
+fn unescape_pointer_token(token: &str) -> NitriteResult<String> {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => result.push('~'),
+            Some('1') => result.push('/'),
+            _ => {
+                log::error!("Invalid escape sequence in JSON pointer token {}", token);
+                return Err(NitriteError::new(
+                    &format!("Invalid escape sequence in JSON pointer token {}", token),
+                    ErrorKind::ValidationError,
+                ));
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn parse_pointer_index(token: &str) -> Option<usize> {
+    // RFC 6901 array indexes are plain decimals without leading zeros
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn collect_path_matches(value: &Value, splits: &[&str], results: &mut Vec<Value>) {
+    let Some((&key, rest)) = splits.split_first() else {
+        results.push(value.clone());
+        return;
+    };
+
+    match value {
+        Value::Document(obj) => {
+            if key == "*" {
+                for (_, item) in obj.data.iter() {
+                    collect_path_matches(item, rest, results);
+                }
+            } else if let Some(item) = obj.data.get(key) {
+                collect_path_matches(item, rest, results);
+            }
+        }
+        Value::Array(arr) => {
+            if key == "*" {
+                for item in arr {
+                    collect_path_matches(item, rest, results);
+                }
+            } else if let Ok(index) = key.parse::<usize>() {
+                if let Some(item) = arr.get(index) {
+                    collect_path_matches(item, rest, results);
+                }
+            } else {
+                // a field name on an array applies to each element, like `get`
+                for item in arr {
+                    collect_path_matches(item, splits, results);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 /// Creates a Nitrite Document with JSON-like syntax.
 ///
+/// Besides plain `key: value` pairs, two forms help build partial documents:
+///
+/// * `..other` merges an existing [Document] (recursively, like [Document::merge]).
+///   Entries are applied in order, so later keys override spread fields.
+/// * `key?: value` takes an `Option` and omits the key entirely when it is `None`.
+///
 /// # Examples
 ///
 /// ```rust
@@ -1117,9 +1482,45 @@ pub fn normalize(value: &str) -> String {
 ///     },
 ///     values: [1, 2, 3]
 /// };
+///
+/// // Spread and optional fields
+/// let nickname: Option<&str> = None;
+/// let update = doc!{
+///     ..simple,
+///     age: 31,
+///     nickname?: nickname
+/// };
+/// assert_eq!(update.size(), 2);
 /// ```
 #[macro_export]
 macro_rules! doc {
+    // internal: all entries consumed
+    (@entries $doc:ident;) => {};
+
+    // internal: `..other` merges an existing document
+    (@entries $doc:ident; .. $other:expr $(, $($rest:tt)*)?) => {
+        $doc.merge(&$other)
+            .expect(&format!("Failed to merge {} in document", stringify!($other)));
+        $crate::doc!(@entries $doc; $($($rest)*)?);
+    };
+
+    // internal: `key?: option` puts the value only when it is `Some`
+    (@entries $doc:ident; $key:tt ? : $value:tt $(, $($rest:tt)*)?) => {
+        // `identity` keeps parenthesized values like `(opt.clone())` free of `unused_parens`
+        if let Some(value) = ::core::convert::identity::<Option<_>>($value) {
+            $doc.put(&$crate::collection::normalize(stringify!($key)), $crate::common::Value::from(value))
+                .expect(&format!("Failed to put value {} in document", stringify!($value)));
+        }
+        $crate::doc!(@entries $doc; $($($rest)*)?);
+    };
+
+    // internal: plain `key: value`
+    (@entries $doc:ident; $key:tt : $value:tt $(, $($rest:tt)*)?) => {
+        $doc.put(&$crate::collection::normalize(stringify!($key)), $crate::doc_value!($value))
+            .expect(&format!("Failed to put value {} in document", stringify!($value)));
+        $crate::doc!(@entries $doc; $($($rest)*)?);
+    };
+
     // match an empty document (with braces for backward compat)
     ({}) => {
         $crate::collection::Document::new()
@@ -1131,8 +1532,8 @@ macro_rules! doc {
     };
 
     // match a document with key value pairs (old syntax with outer braces - for backward compat)
-    ({ $($key:tt : $value:tt),* $(,)? }) => {
-        $crate::doc!($($key : $value),*)
+    ({ $($body:tt)* }) => {
+        $crate::doc!($($body)*)
     };
 
     // match a document with key value pairs (new syntax without outer braces)
@@ -1149,22 +1550,130 @@ macro_rules! doc {
             doc
         }
     };
+
+    // match a document using spread (`..other`) or optional (`key?: value`) entries
+    ($($body:tt)+) => {
+        {
+            #[allow(unused_imports)]
+            use $crate::doc_value;
+
+            let mut doc = $crate::collection::Document::new();
+            $crate::doc!(@entries doc; $($body)+);
+            doc
+        }
+    };
 }
 
 /// Helper macro to convert values for the doc! macro.
 /// Handles nested documents, arrays, and expressions.
 #[macro_export]
 macro_rules! doc_value {
+    // match an empty nested document
+    ({}) => {
+        $crate::common::Value::Document($crate::collection::Document::new())
+    };
+
+    // match a nested document starting with a spread
+    ({ .. $($body:tt)* }) => {
+        $crate::common::Value::Document($crate::doc!(.. $($body)*))
+    };
+
+    // match a nested document starting with an optional field
+    ({ $key:tt ? : $($body:tt)* }) => {
+        $crate::common::Value::Document($crate::doc!($key ? : $($body)*))
+    };
+
     // match a nested document
-    ({ $($key:tt : $value:tt),* $(,)? }) => {
+    ({ $key:tt : $($body:tt)* }) => {
+        $crate::common::Value::Document($crate::doc!($key : $($body)*))
+    };
+
+    // match an array of values
+    ([ $($value:tt),* $(,)? ]) => {
+        $crate::common::Value::Array(vec![$($crate::doc_value!($value)),*])
+    };
+
+    // match an expression (variable, function call, arithmetic in parens, literals, etc.)
+    ($value:expr) => {
+        $crate::common::Value::from($value)
+    };
+}
+
+/// Creates a Nitrite Document like [doc!], returning an error instead of panicking.
+///
+/// [doc!] panics when an entry cannot be put in the document, for example a key with
+/// an empty embedded segment like `"a..b"` or a `..other` spread that fails to merge.
+/// `try_doc!` accepts the same syntax, including nested documents, and evaluates to a
+/// `NitriteResult<Document>` holding the first such error.
+///
+/// # Examples
+///
+/// ```rust
+/// use nitrite::try_doc;
+///
+/// let doc = try_doc!{ name: "Alice", address: { city: "Paris" } }.unwrap();
+/// assert_eq!(doc.get("address.city").unwrap(), "Paris".into());
+///
+/// assert!(try_doc!{ "a..b": 1 }.is_err());
+/// ```
+#[macro_export]
+macro_rules! try_doc {
+    // internal: all entries consumed
+    (@entries $doc:ident;) => {};
+
+    // internal: `..other` merges an existing document
+    (@entries $doc:ident; .. $other:expr $(, $($rest:tt)*)?) => {
+        $doc.merge(&$other)?;
+        $crate::try_doc!(@entries $doc; $($($rest)*)?);
+    };
+
+    // internal: `key?: option` puts the value only when it is `Some`
+    (@entries $doc:ident; $key:tt ? : $value:tt $(, $($rest:tt)*)?) => {
+        if let Some(value) = ::core::convert::identity::<Option<_>>($value) {
+            $doc.put(&$crate::collection::normalize(stringify!($key)), $crate::common::Value::from(value))?;
+        }
+        $crate::try_doc!(@entries $doc; $($($rest)*)?);
+    };
+
+    // internal: plain `key: value`
+    (@entries $doc:ident; $key:tt : $value:tt $(, $($rest:tt)*)?) => {
+        $doc.put(&$crate::collection::normalize(stringify!($key)), $crate::try_doc_value!($value))?;
+        $crate::try_doc!(@entries $doc; $($($rest)*)?);
+    };
+
+    // match a document with outer braces
+    ({ $($body:tt)* }) => {
+        $crate::try_doc!($($body)*)
+    };
+
+    // match any entries, or none
+    ($($body:tt)*) => {
         {
-            $crate::common::Value::Document($crate::doc!{ $($key : $value),* })
+            // the closure gives `?` in the entries a scope to return the error from
+            #[allow(clippy::redundant_closure_call)]
+            let result = (|| -> $crate::errors::NitriteResult<$crate::collection::Document> {
+                #[allow(unused_mut)]
+                let mut doc = $crate::collection::Document::new();
+                $crate::try_doc!(@entries doc; $($body)*);
+                Ok(doc)
+            })();
+            result
         }
     };
+}
+
+/// Helper macro to convert values for the try_doc! macro.
+/// Nested documents are built with try_doc! and their errors propagated.
+#[macro_export]
+macro_rules! try_doc_value {
+    // match a nested document
+    ({ $($body:tt)* }) => {
+        $crate::common::Value::Document($crate::try_doc!($($body)*)?)
+    };
 
     // match an array of values
     ([ $($value:tt),* $(,)? ]) => {
-        $crate::common::Value::Array(vec![$($crate::doc_value!($value)),*])
+        $crate::common::Value::Array(vec![$($crate::try_doc_value!($value)),*])
     };
 
     // match an expression (variable, function call, arithmetic in parens, literals, etc.)
@@ -1180,6 +1689,7 @@ mod tests {
     use super::*;
     use crate::collection::Document;
     use crate::common::Value::Null;
+    use crate::common::{DOC_MODIFIED, DOC_REVISION, DOC_SOURCE};
     use crate::{create_document, document_from_map, empty_document};
 
     fn set_up() -> Document {
@@ -1365,6 +1875,43 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_put_rejected_embedded_key_leaves_document_unchanged() {
+        let mut doc = doc!{ "key": 1 };
+        assert!(doc.put("a.b.", Value::I32(1)).is_err());
+        assert!(doc.put("a..b", Value::I32(1)).is_err());
+        assert_eq!(doc, doc!{ "key": 1 });
+    }
+
+    #[test]
+    fn test_try_doc_matches_doc() {
+        let base = doc!{ "name": "base", "age": 20 };
+        let nickname: Option<&str> = None;
+        let built = try_doc!{
+            ..base,
+            age: 21,
+            nickname?: nickname,
+            address: { city: "Paris", codes: [1, { zip: "75001" }] }
+        }
+        .unwrap();
+        let expected = doc!{
+            ..base,
+            age: 21,
+            nickname?: nickname,
+            address: { city: "Paris", codes: [1, { zip: "75001" }] }
+        };
+        assert_eq!(built, expected);
+        assert_eq!(try_doc!{}.unwrap(), Document::new());
+    }
+
+    #[test]
+    fn test_try_doc_returns_error_instead_of_panicking() {
+        assert!(try_doc!{ "a..b": 1 }.is_err());
+        assert!(try_doc!{ ok: 1, nested: { "x.": 2 } }.is_err());
+        assert!(try_doc!{ list: [{ ".x": 1 }] }.is_err());
+        assert!(try_doc!{ "_id": "not an id" }.is_err());
+    }
+
     #[test]
     fn test_put_reserved_id() {
         let mut doc = Document::new();
@@ -1495,6 +2042,47 @@ mod tests {
         assert_eq!(doc.last_modified_since_epoch().unwrap(), 123456789);
     }
 
+    #[test]
+    fn test_put_with_ttl() {
+        let mut doc = Document::new();
+        doc.put_with_ttl("geocode", "52.1,4.3", Duration::from_secs(3600)).unwrap();
+        assert_eq!(doc.get("geocode").unwrap(), Value::from("52.1,4.3"));
+        let deadline = doc.field_expiry("geocode").unwrap().unwrap();
+        assert!(deadline > get_current_time_or_zero() as i64);
+        assert!(doc.expired_fields(&expiry_field(), deadline - 1).unwrap().is_empty());
+        assert_eq!(doc.expired_fields(&expiry_field(), deadline).unwrap(), vec!["geocode"]);
+        // the deadlines are metadata, not fields of the document
+        assert_eq!(doc.fields().len(), 1);
+    }
+
+    #[test]
+    fn test_without_expired_fields() {
+        let mut doc = doc! { name: "Ada", address: { city: "London", zip: "N1" } };
+        doc.set_field_expiry("address.city", 10).unwrap();
+        doc.set_field_expiry("name", 20).unwrap();
+
+        assert!(doc.without_expired_fields(&expiry_field(), 5).unwrap().is_none());
+        let visible = doc.without_expired_fields(&expiry_field(), 15).unwrap().unwrap();
+        assert_eq!(visible.get("address.city").unwrap(), Null);
+        assert_eq!(visible.get("address.zip").unwrap(), Value::from("N1"));
+        assert_eq!(visible.field_expiry("address.city").unwrap(), None);
+        assert_eq!(visible.field_expiry("name").unwrap(), Some(20));
+
+        let visible = doc.without_expired_fields(&expiry_field(), 20).unwrap().unwrap();
+        assert_eq!(visible.get("name").unwrap(), Null);
+        assert!(!visible.contains_key(&expiry_field()));
+    }
+
+    #[test]
+    fn test_clear_field_expiry() {
+        let mut doc = doc! { name: "Ada" };
+        doc.set_field_expiry("name", 10).unwrap();
+        doc.clear_field_expiry("name").unwrap();
+        assert_eq!(doc.field_expiry("name").unwrap(), None);
+        assert!(!doc.contains_key(&expiry_field()));
+        assert_eq!(doc.get("name").unwrap(), Value::from("Ada"));
+    }
+
     #[test]
     fn test_to_map() {
         let doc = set_up();
@@ -1692,6 +2280,17 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_deep_remove_below_scalar_keeps_scalar() {
+        let mut doc = doc!{ "a": 1 };
+        doc.put("b", Value::Null).unwrap();
+        doc.remove("a.b").unwrap();
+        doc.remove("b.c").unwrap();
+        doc.remove("c.d").unwrap();
+        assert_eq!(doc.get("a").unwrap(), Value::I32(1));
+        assert!(doc.contains_key("b"));
+    }
+
     #[test]
     fn test_deep_remove_invalid_field() {
         let mut doc = empty_document();
@@ -2404,6 +3003,123 @@ mod tests {
         assert!(doc.is_empty());
     }
 
+    #[test]
+    fn test_get_path() {
+        let mut doc = set_up();
+        doc.put("a/b", 1).unwrap();
+        doc.put("m~n", 2).unwrap();
+
+        assert_eq!(doc.get_path("/location/address/zip").unwrap(), Value::I32(10001));
+        assert_eq!(doc.get_path("/location/address/house/2").unwrap(), Value::String("3".to_string()));
+        assert_eq!(doc.get_path("/obj_array/1/value").unwrap(), Value::I32(2));
+        assert_eq!(doc.get_path("/a~1b").unwrap(), Value::I32(1));
+        assert_eq!(doc.get_path("/m~0n").unwrap(), Value::I32(2));
+        assert_eq!(doc.get_path("").unwrap(), Value::Document(doc.clone()));
+
+        // unresolvable pointers yield null
+        assert_eq!(doc.get_path("/location/missing").unwrap(), Null);
+        assert_eq!(doc.get_path("/category/3").unwrap(), Null);
+        assert_eq!(doc.get_path("/category/01").unwrap(), Null);
+        assert_eq!(doc.get_path("/category/-").unwrap(), Null);
+
+        assert!(doc.get_path("location/address").is_err());
+        assert!(doc.get_path("/a~2b").is_err());
+    }
+
+    #[test]
+    fn test_query_path() {
+        let doc = doc!{
+            items: [
+                { name: "pen", price: 2 },
+                { name: "book", price: 10 },
+                { price: 5 }
+            ],
+            prices: {
+                usd: 1,
+                eur: 2
+            }
+        };
+
+        assert_eq!(
+            doc.query_path("items.*.name").unwrap(),
+            vec![Value::from("pen"), Value::from("book")]
+        );
+        assert_eq!(doc.query_path("items.1.price").unwrap(), vec![Value::I32(10)]);
+        assert_eq!(doc.query_path("items.price").unwrap().len(), 3);
+        assert_eq!(doc.query_path("prices.*").unwrap(), vec![Value::I32(2), Value::I32(1)]);
+        assert!(doc.query_path("items.*.missing").unwrap().is_empty());
+        assert!(doc.query_path("items..name").is_err());
+    }
+
+    #[test]
+    fn test_doc_macro_spread() {
+        let base = doc!{
+            name: "Alice",
+            age: 30,
+            address: {
+                city: "NYC"
+            }
+        };
+
+        let doc = doc!{
+            ..base,
+            age: 31,
+            address: {
+                zip: 10001
+            }
+        };
+
+        assert_eq!(doc.get("name").unwrap(), Value::String("Alice".to_string()));
+        assert_eq!(doc.get("age").unwrap(), Value::I32(31));
+        // plain keys after a spread replace the value, they do not merge
+        assert_eq!(doc.get("address.city").unwrap(), Null);
+        assert_eq!(doc.get("address.zip").unwrap(), Value::I32(10001));
+
+        // later spreads override earlier keys
+        let doc = doc!{ age: 20, ..base };
+        assert_eq!(doc.get("age").unwrap(), Value::I32(30));
+    }
+
+    #[test]
+    fn test_doc_macro_optional_fields() {
+        let nickname: Option<&str> = None;
+        let email = Some("alice@example.com");
+
+        let doc = doc!{
+            name: "Alice",
+            nickname?: nickname,
+            email?: email,
+            score?: (Some(10).map(|s| s * 2))
+        };
+
+        assert_eq!(doc.size(), 3);
+        assert!(!doc.contains_key("nickname"));
+        assert_eq!(doc.get("email").unwrap(), Value::String("alice@example.com".to_string()));
+        assert_eq!(doc.get("score").unwrap(), Value::I32(20));
+    }
+
+    #[test]
+    fn test_doc_macro_spread_and_optional_nested() {
+        let extra = doc!{ b: 2 };
+        let missing: Option<i32> = None;
+
+        let doc = doc!{
+            outer: {
+                ..extra,
+                c?: missing,
+                d?: (Some(4))
+            },
+            inner: {
+                a?: (Some(1))
+            }
+        };
+
+        assert_eq!(doc.get("outer.b").unwrap(), Value::I32(2));
+        assert!(!doc.contains_field("outer.c"));
+        assert_eq!(doc.get("outer.d").unwrap(), Value::I32(4));
+        assert_eq!(doc.get("inner.a").unwrap(), Value::I32(1));
+    }
+
     // Additional tests for coverage improvement
     
     #[test]
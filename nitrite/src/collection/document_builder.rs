@@ -0,0 +1,179 @@
+use super::Document;
+use crate::common::{ReadExecutor, Value};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::FIELD_SEPARATOR;
+
+/// A value set on a [`DocumentBuilder`], kept unvalidated until `build()`.
+#[derive(Debug, Clone)]
+enum BuilderValue {
+    Value(Value),
+    Nested(DocumentBuilder),
+}
+
+/// Builds a [`Document`] with infallible chaining.
+///
+/// Unlike [`Document::put`], setting a field never fails: the keys are validated
+/// once by [`build`](DocumentBuilder::build), which reports every invalid key
+/// together. Fields are applied in the order they were set, so a later field
+/// replaces an earlier one with the same key, and keys may be embedded
+/// (`"location.city"`) as with `put`.
+///
+/// A builder owns its fields and can be sent to or shared with other threads.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let document = Document::builder()
+///     .field("name", "Alice")
+///     .field("age", 30)
+///     .nested("location", |b| b.field("city", "New York").field("zip", 10001))
+///     .build()?;
+/// assert_eq!(document.get("location.city")?, Value::from("New York"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DocumentBuilder {
+    fields: Vec<(String, BuilderValue)>,
+}
+
+impl DocumentBuilder {
+    /// Creates a builder without fields.
+    pub fn new() -> Self {
+        DocumentBuilder::default()
+    }
+
+    /// Sets a field to a value.
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.fields.push((key.into(), BuilderValue::Value(value.into())));
+        self
+    }
+
+    /// Sets a field to a nested document built by `build` from an empty builder.
+    pub fn nested<F>(mut self, key: impl Into<String>, build: F) -> Self
+    where
+        F: FnOnce(DocumentBuilder) -> DocumentBuilder,
+    {
+        let nested = build(DocumentBuilder::new());
+        self.fields.push((key.into(), BuilderValue::Nested(nested)));
+        self
+    }
+
+    /// Returns `true` if no field has been set.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Builds the document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidOperation` error listing every field that `put` would
+    /// reject, for example an empty key or a manually set `_id`. Keys of nested
+    /// documents are reported with their full path.
+    pub fn build(self) -> NitriteResult<Document> {
+        let mut errors = Vec::new();
+        let document = self.build_into(None, &mut errors);
+        if errors.is_empty() {
+            return Ok(document);
+        }
+
+        log::error!("Invalid document fields: {}", errors.join("; "));
+        Err(NitriteError::new(
+            &format!("Invalid document fields: {}", errors.join("; ")),
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    fn build_into(self, parent: Option<&str>, errors: &mut Vec<String>) -> Document {
+        let mut document = Document::new();
+        for (key, value) in self.fields {
+            let path = match parent {
+                Some(parent) => FIELD_SEPARATOR.read_with(|sep| format!("{}{}{}", parent, sep, key)),
+                None => key.clone(),
+            };
+
+            let value = match value {
+                BuilderValue::Value(value) => value,
+                BuilderValue::Nested(nested) => Value::from(nested.build_into(Some(&path), errors)),
+            };
+            if let Err(e) = document.put(key, value) {
+                errors.push(format!("'{}': {}", path, e.message()));
+            }
+        }
+        document
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::NitriteId;
+    use crate::common::DOC_ID;
+
+    #[test]
+    fn test_build_document() {
+        let document = Document::builder()
+            .field("name", "Alice")
+            .field("age", 30)
+            .field("address.zip", 10001)
+            .nested("location", |b| {
+                b.field("city", "NY").nested("geo", |b| b.field("lat", 40.7))
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(document.get("name").unwrap(), Value::from("Alice"));
+        assert_eq!(document.get("age").unwrap(), Value::from(30));
+        assert_eq!(document.get("address.zip").unwrap(), Value::from(10001));
+        assert_eq!(document.get("location.city").unwrap(), Value::from("NY"));
+        assert_eq!(document.get("location.geo.lat").unwrap(), Value::from(40.7));
+    }
+
+    #[test]
+    fn test_later_field_replaces_earlier() {
+        let document = Document::builder()
+            .field("status", "draft")
+            .field("status", "published")
+            .build()
+            .unwrap();
+        assert_eq!(document.get("status").unwrap(), Value::from("published"));
+        assert_eq!(document.size(), 1);
+    }
+
+    #[test]
+    fn test_build_reports_all_key_errors() {
+        let error = Document::builder()
+            .field("", 1)
+            .field("ok", 2)
+            .field(DOC_ID, "not an id")
+            .nested("location", |b| b.field("", "NY"))
+            .build()
+            .unwrap_err();
+
+        assert_eq!(error.kind(), &ErrorKind::InvalidOperation);
+        let message = error.message();
+        assert!(message.contains("'': Document does not support empty key"));
+        assert!(message.contains(&format!("'{}'", DOC_ID)));
+        assert!(message.contains("'location.': Document does not support empty key"));
+        assert!(!message.contains("'ok'"));
+    }
+
+    #[test]
+    fn test_build_accepts_nitrite_id() {
+        let id = NitriteId::new();
+        let mut document = Document::builder().field(DOC_ID, id).build().unwrap();
+        assert_eq!(document.id().unwrap(), id);
+    }
+
+    #[test]
+    fn test_builder_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DocumentBuilder>();
+
+        let builder = Document::builder().field("a", 1);
+        let document = std::thread::spawn(move || builder.field("b", 2).build())
+            .join()
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.size(), 2);
+    }
+}
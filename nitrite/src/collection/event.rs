@@ -1,3 +1,5 @@
+use crate::collection::listener_queue::{listener_health, EventQueue, ListenerMetrics};
+use crate::collection::{Document, ListenerHealth, NitriteId, OverflowPolicy};
 use crate::common::{ReadExecutor, WriteExecutor};
 use crate::errors::NitriteResult;
 use crate::{atomic, get_current_time_or_zero, Atomic, Value};
@@ -5,9 +7,19 @@ use anyhow::Error;
 use basu::error::BasuError;
 use basu::event::Event;
 use basu::Handle;
+use std::cell::RefCell;
 use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+/// Sequence number of the last collection event created in this process.
+static EVENT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The transaction whose writes the current thread is applying, if any.
+    static EVENT_ORIGIN: RefCell<Option<EventOrigin>> = const { RefCell::new(None) };
+}
+
 /// Event types that can occur on a collection.
 ///
 /// CollectionEvents enumerates all collection-level operations that can trigger
@@ -17,7 +29,8 @@ use std::sync::Arc;
 /// # Variants
 /// - `Insert`: A new document was added to the collection
 /// - `Update`: An existing document was modified in the collection
-/// - `Remove`: A document was deleted from the collection
+/// - `Remove`: A document was deleted from the collection, or evicted from a capped
+///   collection, see [`CollectionEventInfo::reason`]
 /// - `IndexStart`: Index creation/rebuild has begun
 /// - `IndexEnd`: Index creation/rebuild has completed
 ///
@@ -34,6 +47,16 @@ pub enum CollectionEvents {
     IndexEnd,
 }
 
+/// Why the document of a collection event was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EventReason {
+    /// The document was written by an operation on the collection.
+    #[default]
+    Write,
+    /// The oldest document of a capped collection was removed to stay within the cap.
+    Eviction,
+}
+
 /// Information about a collection event that occurred.
 ///
 /// CollectionEventInfo contains details about an operation that happened on a collection,
@@ -45,6 +68,23 @@ pub enum CollectionEvents {
 /// - **Immutable item**: The document/value is captured at event time
 /// - **Atomic originator**: The originator can be updated via lock-free atomic operations
 /// - **Timestamped**: Each event records its creation time automatically
+/// - **Sequenced**: Each event gets a sequence number, increasing across all the
+///   collections of the process. Listeners run on a thread pool and may see events out of
+///   order; the sequence number gives them the order in which the events were raised.
+///
+/// # Document events
+///
+/// The events of a document write carry the id of the document. If the collection was
+/// opened with [`event_images`](crate::collection::CollectionOptions::event_images), they
+/// also carry the document as it was before the write (updates, removals and evictions)
+/// and as it is after it (inserts and updates). The images are off by default to keep
+/// events of large documents cheap.
+///
+/// The documents evicted from a capped collection are announced with `Remove` events whose
+/// [`reason`](Self::reason) is [`EventReason::Eviction`].
+///
+/// Writes applied by the commit of a transaction carry the id of the transaction, and the
+/// id of its session if it was begun from one.
 ///
 /// # Usage
 ///
@@ -81,7 +121,37 @@ impl CollectionEventInfo {
     /// For index events, the item is typically None.
     pub fn new(item: Option<Value>, event_type: CollectionEvents, originator: String) -> Self {
         CollectionEventInfo {
-            inner: Arc::new(CollectionEventInner::new(item, event_type, originator)),
+            inner: Arc::new(CollectionEventInner::new(item, event_type, originator, None)),
+        }
+    }
+
+    /// Creates the event of a write to the document `id`, with its images before and
+    /// after the write if the collection keeps them.
+    pub(crate) fn for_document(
+        item: Option<Value>,
+        event_type: CollectionEvents,
+        originator: String,
+        id: NitriteId,
+        before: Option<Document>,
+        after: Option<Document>,
+    ) -> Self {
+        let images = DocumentImages { id, before, after, reason: EventReason::Write };
+        CollectionEventInfo {
+            inner: Arc::new(CollectionEventInner::new(item, event_type, originator, Some(images))),
+        }
+    }
+
+    /// Creates the `Remove` event of the document `id` evicted from a capped collection,
+    /// with its image before the eviction if the collection keeps them.
+    pub(crate) fn for_eviction(
+        item: Option<Value>,
+        originator: String,
+        id: NitriteId,
+        before: Option<Document>,
+    ) -> Self {
+        let images = DocumentImages { id, before, after: None, reason: EventReason::Eviction };
+        CollectionEventInfo {
+            inner: Arc::new(CollectionEventInner::new(item, CollectionEvents::Remove, originator, Some(images))),
         }
     }
 
@@ -123,6 +193,52 @@ impl CollectionEventInfo {
         self.inner.timestamp
     }
 
+    /// Returns the sequence number of this event.
+    ///
+    /// Sequence numbers start at 1 and increase with every event created in the process,
+    /// whatever its collection.
+    pub fn sequence(&self) -> u64 {
+        self.inner.sequence
+    }
+
+    /// Returns the id of the document written, `None` for events that are not about a
+    /// single stored document.
+    pub fn document_id(&self) -> Option<NitriteId> {
+        self.inner.images.as_ref().map(|images| images.id)
+    }
+
+    /// Returns the document as it was before the write, if the collection keeps event
+    /// images and the document existed.
+    pub fn before(&self) -> Option<&Document> {
+        self.inner.images.as_ref().and_then(|images| images.before.as_ref())
+    }
+
+    /// Returns the document as it is after the write, if the collection keeps event
+    /// images and the document still exists.
+    pub fn after(&self) -> Option<&Document> {
+        self.inner.images.as_ref().and_then(|images| images.after.as_ref())
+    }
+
+    /// Returns why the document of this event was written, [`EventReason::Write`] for
+    /// events that are not about a single stored document.
+    pub fn reason(&self) -> EventReason {
+        self.inner.images.as_ref().map_or(EventReason::Write, |images| images.reason)
+    }
+
+    /// Returns the id of the transaction whose commit raised this event, if any.
+    pub fn transaction_id(&self) -> Option<&str> {
+        self.inner.origin.as_ref().map(|origin| origin.transaction_id.as_str())
+    }
+
+    /// Returns the id of the session of the transaction whose commit raised this event,
+    /// if the transaction was begun from a session.
+    pub fn session_id(&self) -> Option<&str> {
+        self.inner
+            .origin
+            .as_ref()
+            .and_then(|origin| origin.session_id.as_deref())
+    }
+
     /// Updates the originator of this event.
     ///
     /// # Arguments
@@ -145,10 +261,70 @@ impl Debug for CollectionEventInfo {
             .field("event_type", &self.event_type())
             .field("timestamp", &self.timestamp())
             .field("originator", &self.originator())
+            .field("sequence", &self.sequence())
+            .field("document_id", &self.document_id())
+            .field("before", &self.before())
+            .field("after", &self.after())
+            .field("reason", &self.reason())
+            .field("transaction_id", &self.transaction_id())
+            .field("session_id", &self.session_id())
             .finish()
     }
 }
 
+/// The transaction, and its session, of the events raised on the current thread.
+///
+/// The commit of a transaction applies its writes to the collections on the committing
+/// thread, so the origin is set for the duration of the commit with [`EventOrigin::enter`]
+/// and picked up by every event created meanwhile.
+#[derive(Debug, Clone)]
+pub(crate) struct EventOrigin {
+    session_id: Option<String>,
+    transaction_id: String,
+}
+
+impl EventOrigin {
+    /// Marks the events created on this thread as raised by `transaction_id` until the
+    /// returned guard is dropped.
+    pub(crate) fn enter(session_id: Option<String>, transaction_id: String) -> EventOriginGuard {
+        let origin = EventOrigin { session_id, transaction_id };
+        let previous = EVENT_ORIGIN.with(|current| current.borrow_mut().replace(origin));
+        EventOriginGuard { previous }
+    }
+
+    pub(crate) fn current() -> Option<EventOrigin> {
+        EVENT_ORIGIN.with(|current| current.borrow().clone())
+    }
+
+    pub(crate) fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    pub(crate) fn transaction_id(&self) -> &str {
+        &self.transaction_id
+    }
+}
+
+/// Restores the previous event origin of the thread when dropped.
+pub(crate) struct EventOriginGuard {
+    previous: Option<EventOrigin>,
+}
+
+impl Drop for EventOriginGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        EVENT_ORIGIN.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// The document a write event is about.
+struct DocumentImages {
+    id: NitriteId,
+    before: Option<Document>,
+    after: Option<Document>,
+    reason: EventReason,
+}
+
 /// Opaque implementation details of CollectionEventInfo.
 /// This struct is part of the PIMPL pattern and should not be accessed directly.
 pub(crate) struct CollectionEventInner {
@@ -156,15 +332,26 @@ pub(crate) struct CollectionEventInner {
     event_type: CollectionEvents,
     timestamp: u128,
     originator: Atomic<String>,
+    sequence: u64,
+    images: Option<DocumentImages>,
+    origin: Option<EventOrigin>,
 }
 
 impl CollectionEventInner {
-    fn new(item: Option<Value>, event_type: CollectionEvents, originator: String) -> Self {
+    fn new(
+        item: Option<Value>,
+        event_type: CollectionEvents,
+        originator: String,
+        images: Option<DocumentImages>,
+    ) -> Self {
         CollectionEventInner {
             item,
             event_type,
             timestamp: get_current_time_or_zero(),
             originator: atomic(originator),
+            sequence: EVENT_SEQUENCE.fetch_add(1, Ordering::Relaxed) + 1,
+            images,
+            origin: EventOrigin::current(),
         }
     }
 
@@ -197,10 +384,28 @@ where
 /// CollectionEventListener wraps an event handler callback and can be registered with
 /// a collection to receive notifications when collection operations occur.
 ///
+/// # Delivery
+///
+/// By default the writes call the listener: a write returns once its listeners, run in
+/// Rayon's thread pool, have handled its events, so a slow listener slows the writes
+/// down. The error of a listener is reported to the write.
+///
+/// A listener made [`queued`](Self::queued) has a bounded queue and a thread of its own
+/// instead. Writes only queue their events, which the listener handles one at a time in
+/// the order they were queued. When the queue is full, its [`OverflowPolicy`] decides
+/// whether the write waits, the oldest event is dropped, or the event is rejected.
+/// Errors and panics of a queued listener are logged and counted, the writes do not see
+/// them. Events still queued when the listener is unsubscribed or its collection closed
+/// are delivered before its thread ends.
+///
+/// Both kinds report their counters in the
+/// [`ListenerHealth`](crate::collection::ListenerHealth) of
+/// [`Nitrite::describe`](crate::nitrite::Nitrite::describe).
+///
 /// # Characteristics
-/// - **Cloneable**: Thread-safe sharing via Arc for use across threads
+/// - **Cloneable**: Thread-safe sharing via Arc for use across threads; clones share the
+///   queue and the counters
 /// - **Closure-based**: Accepts any callable matching CollectionEventCallback signature
-/// - **Asynchronous**: Events are processed in Rayon's parallel thread pool
 ///
 /// # Usage
 ///
@@ -210,10 +415,20 @@ where
 ///     println!("Event: {:?}", event.event_type());
 ///     Ok(())
 /// }))?;
+///
+/// // a search indexer that must not slow the writes down, nor miss an event
+/// collection.subscribe(
+///     CollectionEventListener::new(reindex)
+///         .named("search")
+///         .queued(10_000, OverflowPolicy::Block)?,
+/// )?;
 /// ```
 #[derive(Clone)]
 pub struct CollectionEventListener {
     on_event: Arc<dyn CollectionEventCallback>,
+    name: Option<String>,
+    queue: Option<Arc<EventQueue>>,
+    metrics: Arc<ListenerMetrics>,
 }
 
 impl CollectionEventListener {
@@ -230,21 +445,64 @@ impl CollectionEventListener {
     /// # Behavior
     ///
     /// Wraps the callback in Arc<dyn CollectionEventCallback> to enable polymorphic
-    /// listener usage and thread-safe sharing.
+    /// listener usage and thread-safe sharing. The writes call the listener until it is
+    /// made [`queued`](Self::queued).
     pub fn new(on_event: impl CollectionEventCallback + 'static) -> Self {
         CollectionEventListener {
             on_event: Arc::new(on_event),
+            name: None,
+            queue: None,
+            metrics: Arc::new(ListenerMetrics::default()),
         }
     }
+
+    /// Names the listener in its [`ListenerHealth`](crate::collection::ListenerHealth).
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Gives the listener a queue of `capacity` events and a thread handling them, so
+    /// writes no longer wait for it unless the queue is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of events the queue holds
+    /// * `overflow` - What to do with an event arriving while the queue is full
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if `capacity` is 0, and an error if the thread cannot
+    /// be started.
+    ///
+    /// # Behavior
+    ///
+    /// With [`OverflowPolicy::Block`], a write waits while holding its collection, so the
+    /// listener must not write to that collection.
+    pub fn queued(mut self, capacity: usize, overflow: OverflowPolicy) -> NitriteResult<Self> {
+        let queue = EventQueue::start(capacity, overflow, self.on_event.clone(), self.metrics.clone())?;
+        self.queue = Some(Arc::new(queue));
+        Ok(self)
+    }
+
+    /// Returns the health of the listener, subscribed to `collection`.
+    pub(crate) fn health(&self, collection: &str) -> ListenerHealth {
+        listener_health(collection, self.name.as_deref(), self.queue.as_deref(), &self.metrics)
+    }
 }
 
 impl Handle<CollectionEventInfo> for CollectionEventListener {
     fn handle(&self, event: &Event<CollectionEventInfo>) -> Result<(), BasuError> {
-        // below code will run in rayon's thread pool using parallel iterator
-        match (self.on_event)(event.data.clone()) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(BasuError::HandlerError(Error::from(e))),
-        }
+        let result = match &self.queue {
+            Some(queue) => queue.offer(event.data.clone()),
+            None => {
+                // below code will run in rayon's thread pool using parallel iterator
+                let result = (self.on_event)(event.data.clone());
+                self.metrics.record(&result);
+                result
+            }
+        };
+        result.map_err(|e| BasuError::HandlerError(Error::from(e)))
     }
 }
 
@@ -286,6 +544,72 @@ mod tests {
         assert_eq!(event.originator(), new_originator);
     }
 
+    #[test]
+    fn test_collection_event_sequence_increases() {
+        let first = CollectionEventInfo::new(None, CollectionEvents::IndexStart, String::new());
+        let second = CollectionEventInfo::new(None, CollectionEvents::IndexEnd, String::new());
+        assert!(first.sequence() > 0);
+        assert!(second.sequence() > first.sequence());
+    }
+
+    #[test]
+    fn test_collection_event_for_document() {
+        let id = NitriteId::new();
+        let mut before = Document::new();
+        before.put("a", 1).unwrap();
+        let mut after = Document::new();
+        after.put("a", 2).unwrap();
+
+        let event = CollectionEventInfo::for_document(
+            Some(Value::Document(after.clone())),
+            CollectionEvents::Update,
+            String::new(),
+            id,
+            Some(before.clone()),
+            Some(after.clone()),
+        );
+        assert_eq!(event.document_id(), Some(id));
+        assert_eq!(event.before(), Some(&before));
+        assert_eq!(event.after(), Some(&after));
+
+        let plain = CollectionEventInfo::new(None, CollectionEvents::IndexStart, String::new());
+        assert_eq!(plain.document_id(), None);
+        assert!(plain.before().is_none());
+        assert!(plain.after().is_none());
+    }
+
+    #[test]
+    fn test_event_origin_is_scoped() {
+        let outside = CollectionEventInfo::new(None, CollectionEvents::Insert, String::new());
+        assert!(outside.transaction_id().is_none());
+
+        {
+            let _outer = EventOrigin::enter(Some("session".to_string()), "tx1".to_string());
+            {
+                let _inner = EventOrigin::enter(None, "tx2".to_string());
+                let event = CollectionEventInfo::new(None, CollectionEvents::Insert, String::new());
+                assert_eq!(event.transaction_id(), Some("tx2"));
+                assert_eq!(event.session_id(), None);
+            }
+            let event = CollectionEventInfo::new(None, CollectionEvents::Insert, String::new());
+            assert_eq!(event.transaction_id(), Some("tx1"));
+            assert_eq!(event.session_id(), Some("session"));
+
+            // the origin belongs to the committing thread only
+            let other = std::thread::spawn(|| {
+                CollectionEventInfo::new(None, CollectionEvents::Insert, String::new())
+                    .transaction_id()
+                    .map(str::to_string)
+            })
+            .join()
+            .unwrap();
+            assert_eq!(other, None);
+        }
+
+        let after = CollectionEventInfo::new(None, CollectionEvents::Insert, String::new());
+        assert!(after.transaction_id().is_none());
+    }
+
     #[test]
     fn test_collection_event_listener_new() {
         let callback = |_event| Ok(());
@@ -1,6 +1,11 @@
-use crate::{SortOrder, SortableFields};
+use crate::{
+    collection::{CancellationToken, Document, ScanToken, WriteToken},
+    index::IndexHint,
+    SortOrder, SortableFields,
+};
 use icu_collator::options::CollatorOptions;
 use icu_collator::CollatorPreferences;
+use std::time::Duration;
 
 /// Options for controlling find operations on documents.
 ///
@@ -25,13 +30,33 @@ use icu_collator::CollatorPreferences;
 /// let options = limit_to(100);
 /// let options = distinct();
 /// ```
+///
+/// # Result order
+///
+/// Documents that sort alike are returned by ascending `_id`, so a sorted query returns
+/// the same order on every store and pages read with `skip` and `limit` neither repeat
+/// nor miss a document. Without a sort, a query answered by an index returns its
+/// documents by ascending `_id` as well, unless the index ranks them, like the nearest
+/// first of a vector search or the best match first of a full-text search. A collection
+/// scan returns the documents in the order of the store. See
+/// [`tie_break_by_id`](FindOptions::tie_break_by_id) to turn the tie-break off.
 pub struct FindOptions {
     pub(crate) sort_by: Option<SortableFields>,
+    pub(crate) distance_sort: Option<DistanceSort>,
     pub(crate) skip: Option<u64>,
     pub(crate) limit: Option<u64>,
     pub(crate) distinct: bool,
     pub(crate) collator_options: Option<CollatorOptions>,
     pub(crate) collator_preferences: Option<CollatorPreferences>,
+    pub(crate) hint: Option<IndexHint>,
+    pub(crate) after_write: Option<WriteToken>,
+    pub(crate) principal: Option<String>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    pub(crate) max_memory: Option<u64>,
+    pub(crate) projection: Option<Document>,
+    pub(crate) tie_break_by_id: bool,
+    pub(crate) resume_after: Option<ScanToken>,
 }
 
 /// Creates `FindOptions` with sorting by a field.
@@ -55,6 +80,16 @@ pub fn order_by(field_name: &str, sort_order: SortOrder) -> FindOptions {
         distinct: false,
         collator_options: None,
         collator_preferences: None,
+        hint: None,
+        after_write: None,
+        principal: None,
+        distance_sort: None,
+        timeout: None,
+        cancellation_token: None,
+        max_memory: None,
+        projection: None,
+        tie_break_by_id: true,
+        resume_after: None,
     }
 }
 
@@ -77,6 +112,16 @@ pub fn skip_by(skip: u64) -> FindOptions {
         distinct: false,
         collator_options: None,
         collator_preferences: None,
+        hint: None,
+        after_write: None,
+        principal: None,
+        distance_sort: None,
+        timeout: None,
+        cancellation_token: None,
+        max_memory: None,
+        projection: None,
+        tie_break_by_id: true,
+        resume_after: None,
     }
 }
 
@@ -99,6 +144,16 @@ pub fn limit_to(limit: u64) -> FindOptions {
         distinct: false,
         collator_options: None,
         collator_preferences: None,
+        hint: None,
+        after_write: None,
+        principal: None,
+        distance_sort: None,
+        timeout: None,
+        cancellation_token: None,
+        max_memory: None,
+        projection: None,
+        tie_break_by_id: true,
+        resume_after: None,
     }
 }
 
@@ -113,6 +168,16 @@ pub fn distinct() -> FindOptions {
         distinct: true,
         collator_options: None,
         collator_preferences: None,
+        hint: None,
+        after_write: None,
+        principal: None,
+        distance_sort: None,
+        timeout: None,
+        cancellation_token: None,
+        max_memory: None,
+        projection: None,
+        tie_break_by_id: true,
+        resume_after: None,
     }
 }
 
@@ -126,6 +191,16 @@ impl FindOptions {
             distinct: false,
             collator_options: Some(CollatorOptions::default()),
             collator_preferences: Some(CollatorPreferences::default()),
+            hint: None,
+            after_write: None,
+            principal: None,
+            distance_sort: None,
+            timeout: None,
+            cancellation_token: None,
+            max_memory: None,
+            projection: None,
+            tie_break_by_id: true,
+            resume_after: None,
         }
     }
 
@@ -157,6 +232,28 @@ impl FindOptions {
         self
     }
 
+    /// Sorts the results by their distance to a point, nearest first.
+    ///
+    /// The field must have a spatial index and the filter of the query must be answered
+    /// by that index, for example `find(within_filter)` on the same field. The results
+    /// are then read in distance order from the index, without a sort of the whole result
+    /// set. `find()` fails with `FilterError` if no such index serves the filter, and with
+    /// `InvalidOperation` if `sort_by` is set as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `field_name` - The field of the spatial index
+    /// * `point` - The point to measure the distance from, as `(x, y)`
+    pub fn sort_by_distance(mut self, field_name: &str, point: impl Into<(f64, f64)>) -> FindOptions {
+        let (x, y) = point.into();
+        self.distance_sort = Some(DistanceSort {
+            field_name: field_name.to_string(),
+            x,
+            y,
+        });
+        self
+    }
+
     pub fn distinct(mut self) -> FindOptions {
         self.distinct = true;
         self
@@ -171,6 +268,168 @@ impl FindOptions {
         self.collator_preferences = Some(collator);
         self
     }
+
+    /// Forces the index used by the query, overriding the optimizer's choice.
+    ///
+    /// The hint names the index by its field name(s), or is
+    /// [`IndexHint::CollectionScan`] to ignore all indexes. `find()` fails with
+    /// `IndexNotFound` if no such index exists, and with `FilterError` if the index
+    /// cannot serve the filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `hint` - The field name(s) of the index to use, or an [`IndexHint`]
+    pub fn hint(mut self, hint: impl Into<IndexHint>) -> FindOptions {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Makes the query wait until the write identified by `token` is visible.
+    ///
+    /// A query can run on a different thread than the write it depends on, for example in
+    /// a follow-up request of a web application. With this option the query waits until
+    /// the write has completed, or until the transaction that made it has committed or
+    /// rolled back, and fails with `Timeout` after 30 seconds. Inside a transaction the
+    /// writes of that same transaction never wait.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token returned with the result of the write
+    pub fn after_write(mut self, token: WriteToken) -> FindOptions {
+        self.after_write = Some(token);
+        self
+    }
+
+    /// Names the reader of the query, for the redaction policy of the collection.
+    ///
+    /// A principal exempted by the [`RedactionPolicy`](crate::collection::RedactionPolicy)
+    /// gets the documents as stored; any other reader, like one without a principal, gets
+    /// them redacted.
+    ///
+    /// # Arguments
+    ///
+    /// * `principal` - The name of the reader
+    pub fn principal(mut self, principal: &str) -> FindOptions {
+        self.principal = Some(principal.to_string());
+        self
+    }
+
+    /// Sets how long the query may run.
+    ///
+    /// The clock starts when `find()` is called and covers reading the results from the
+    /// cursor. Once the time is up, the cursor returns a `QueryTimedOut` error and ends. The
+    /// check runs for each document read from the collection, so a scan or a blocking sort
+    /// stops soon after the deadline; a single index lookup is not interrupted.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The time the query may take
+    pub fn timeout(mut self, timeout: Duration) -> FindOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Makes the query stop with a `Cancelled` error once `token` is cancelled.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token to cancel the query with, see [`CancellationToken`]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> FindOptions {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Caps the memory the query may hold for its documents, in bytes.
+    ///
+    /// Documents are counted by their estimated encoded size. A blocking sort spills to
+    /// the store once its buffer reaches the cap, if that is below the sort memory budget
+    /// of the database. A cursor cached for replay, like the foreign side of a join, stops
+    /// caching at the cap and re-runs its query on each reset instead. Reading all results
+    /// at once, as [`DocumentCursor::to_arrow`](crate::DocumentCursor) does, fails with
+    /// `MemoryLimitExceeded`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The number of bytes the query may hold
+    pub fn max_memory(mut self, bytes: u64) -> FindOptions {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Returns only the fields of `projection` from each matching document.
+    ///
+    /// The documents found hold the fields of `projection`, with a null value for those
+    /// a document does not have, like [`DocumentCursor::project`](crate::DocumentCursor).
+    /// Knowing the fields ahead lets the query skip reading the documents when a compound
+    /// index holds them all: if the index answers the whole filter and holds every
+    /// projected field, and the sort if any, the results are built from the index keys.
+    /// The plan of such a query is [covered](crate::collection::FindPlan::is_covered).
+    /// Like a count answered by an index, it sees expired fields until they are swept.
+    ///
+    /// # Arguments
+    ///
+    /// * `projection` - A document whose field names are the fields to return
+    pub fn project(mut self, projection: Document) -> FindOptions {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Sets whether documents that sort alike are ordered by their `_id`, on by default.
+    ///
+    /// With the tie-break off, documents with equal sort keys come in the order they
+    /// were read, which can differ between stores and between runs, and an unsorted
+    /// query answered by an index returns its ids in the order of the index scan. It
+    /// saves a comparison per tie and the reordering of the ids, for queries whose
+    /// order of equal documents does not matter.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `false` to leave the order of equal documents unspecified
+    pub fn tie_break_by_id(mut self, enabled: bool) -> FindOptions {
+        self.tie_break_by_id = enabled;
+        self
+    }
+
+    /// Resumes the scan a [`ScanToken`] was taken from, right after its last document.
+    ///
+    /// The query reads the collection in the order of the ids, without an index, and
+    /// returns the matching documents stored after the position of the token; the
+    /// cursor can be [checkpointed](crate::DocumentCursor::checkpoint) again. `find()`
+    /// fails with `InvalidOperation` if the token is of another collection, or if the
+    /// query sorts its results or hints an index, as neither keeps the order of the ids.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token of the scan to resume
+    pub fn resume_after(mut self, token: ScanToken) -> FindOptions {
+        self.resume_after = Some(token);
+        self
+    }
+}
+
+/// The point a query is sorted by distance to, see [`FindOptions::sort_by_distance`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceSort {
+    field_name: String,
+    x: f64,
+    y: f64,
+}
+
+impl DistanceSort {
+    /// Returns the field of the spatial index.
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+
+    /// Returns the x coordinate of the point.
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    /// Returns the y coordinate of the point.
+    pub fn y(&self) -> f64 {
+        self.y
+    }
 }
 
 impl Default for FindOptions {
@@ -243,6 +502,39 @@ mod tests {
         assert!(options.collator_options.is_some());
     }
 
+    #[test]
+    fn test_find_options_sort_by_distance() {
+        let options = FindOptions::new().sort_by_distance("location", (1.5, -2.0));
+
+        let sort = options.distance_sort.unwrap();
+        assert_eq!(sort.field_name(), "location");
+        assert_eq!(sort.x(), 1.5);
+        assert_eq!(sort.y(), -2.0);
+        assert!(options.sort_by.is_none());
+    }
+
+    #[test]
+    fn test_find_options_timeout_and_cancellation_token() {
+        let options = FindOptions::new();
+        assert!(options.timeout.is_none());
+        assert!(options.cancellation_token.is_none());
+
+        let token = CancellationToken::new();
+        let options = FindOptions::new()
+            .timeout(Duration::from_millis(250))
+            .cancellation_token(token.clone());
+        assert_eq!(options.timeout, Some(Duration::from_millis(250)));
+        token.cancel();
+        assert!(options.cancellation_token.unwrap().is_cancelled());
+    }
+
+    #[test]
+    fn test_find_options_max_memory() {
+        assert!(FindOptions::new().max_memory.is_none());
+        let options = FindOptions::new().max_memory(1024);
+        assert_eq!(options.max_memory, Some(1024));
+    }
+
     #[test]
     fn test_find_options_skip() {
         let skip = 10;
@@ -305,5 +597,14 @@ mod tests {
         assert!(!options.distinct);
         assert!(options.collator_options.is_some());
         assert!(options.collator_preferences.is_some());
+        assert!(options.tie_break_by_id);
+    }
+
+    #[test]
+    fn test_find_options_tie_break_by_id() {
+        assert!(order_by("name", SortOrder::Ascending).tie_break_by_id);
+        let options = FindOptions::new().tie_break_by_id(false);
+
+        assert!(!options.tie_break_by_id);
     }
 }
\ No newline at end of file
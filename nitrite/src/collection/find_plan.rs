@@ -1,4 +1,5 @@
 use crate::{
+    collection::{DistanceSort, NitriteId, PlanSnapshot, PLAN_FORMAT_VERSION},
     filter::{Filter, IndexScanFilter},
     index::IndexDescriptor,
     SortOrder,
@@ -168,6 +169,17 @@ impl FindPlan {
         self.inner.blocking_sort_order.clone()
     }
 
+    /// Returns the point the results are ordered by distance to, if any.
+    ///
+    /// When set, the index of the plan must return the matching ids nearest first.
+    ///
+    /// # Returns
+    ///
+    /// `Some(DistanceSort)` if the query sorts by distance, `None` otherwise.
+    pub fn distance_sort(&self) -> Option<DistanceSort> {
+        self.inner.distance_sort.clone()
+    }
+
     /// Returns the number of results to skip.
     ///
     /// Used for pagination. Results are skipped before the limit is applied.
@@ -222,6 +234,49 @@ impl FindPlan {
         self.inner.distinct
     }
 
+    /// Returns whether documents that sort alike are ordered by their `_id`.
+    ///
+    /// See [`FindOptions::tie_break_by_id`](crate::collection::FindOptions::tie_break_by_id).
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let plan = FindPlan::new();
+    /// assert!(plan.tie_break_by_id());
+    /// ```
+    pub fn tie_break_by_id(&self) -> bool {
+        self.inner.tie_break_by_id
+    }
+
+    /// Returns `true` if the query is answered from the keys of its index alone.
+    ///
+    /// A covered plan never reads the matching documents: it builds them from the
+    /// entries of its compound index, which hold every field the query filters, sorts
+    /// and projects on, see [`FindOptions::project`](crate::collection::FindOptions::project).
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let plan = FindPlan::new();
+    /// assert!(!plan.is_covered());
+    /// ```
+    pub fn is_covered(&self) -> bool {
+        self.inner.covered
+    }
+
+    /// Returns the id the scan of a resumed query starts after, see
+    /// [`FindOptions::resume_after`](crate::collection::FindOptions::resume_after).
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let plan = FindPlan::new();
+    /// assert!(plan.resume_after().is_none());
+    /// ```
+    pub fn resume_after(&self) -> Option<NitriteId> {
+        self.inner.resume_after
+    }
+
     /// Returns the collator options for text comparison if specified.
     ///
     /// ICU Collator options control how strings are compared during sorting,
@@ -278,6 +333,31 @@ impl FindPlan {
         self.inner.sub_plans.clone()
     }
 
+    /// Returns a stable, serializable description of this plan, for golden tests.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let plan = FindPlan::new();
+    /// assert!(!plan.snapshot().uses_any_index());
+    /// ```
+    pub fn snapshot(&self) -> PlanSnapshot {
+        PlanSnapshot::of(self)
+    }
+
+    /// Renders this plan as text that stays the same for the same plan across releases
+    /// sharing a [`PLAN_FORMAT_VERSION`], see [`PlanSnapshot::to_canonical_string`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let plan = FindPlan::new();
+    /// assert_eq!(plan.to_canonical_string(), "plan format: 1\ncollection scan\n");
+    /// ```
+    pub fn to_canonical_string(&self) -> String {
+        self.snapshot().to_canonical_string()
+    }
+
     /// Adds a sub-plan to this plan.
     ///
     /// Sub-plans are executed as part of a composite query strategy.
@@ -311,6 +391,12 @@ impl FindPlan {
     // Internal setter methods for query optimizer use
     // These use Arc::get_mut to safely mutate only when the Arc is not shared
 
+    pub(crate) fn clear_sub_plans(&mut self) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.sub_plans = None;
+        }
+    }
+
     pub(crate) fn set_by_id_filter(&mut self, filter: Filter) {
         if let Some(inner) = Arc::get_mut(&mut self.inner) {
             inner.by_id_filter = Some(filter);
@@ -341,6 +427,12 @@ impl FindPlan {
         }
     }
 
+    pub(crate) fn set_distance_sort(&mut self, sort: DistanceSort) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.distance_sort = Some(sort);
+        }
+    }
+
     pub(crate) fn set_skip(&mut self, skip: u64) {
         if let Some(inner) = Arc::get_mut(&mut self.inner) {
             inner.skip = Some(skip);
@@ -359,6 +451,12 @@ impl FindPlan {
         }
     }
 
+    pub(crate) fn set_tie_break_by_id(&mut self, tie_break_by_id: bool) {
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.tie_break_by_id = tie_break_by_id;
+        }
+    }
+
     pub(crate) fn set_collator_options(&mut self, options: CollatorOptions) {
         if let Some(inner) = Arc::get_mut(&mut self.inner) {
             inner.collator_options = Some(options);
@@ -370,10 +468,31 @@ impl FindPlan {
             inner.collator_preferences = Some(preferences);
         }
     }
+
+    /// Returns a copy of the plan answered from the keys of its index. The copy is not
+    /// shared, so a plan kept in the plan cache is left as it is.
+    pub(crate) fn to_covered(&self) -> FindPlan {
+        let mut inner = (*self.inner).clone();
+        inner.covered = true;
+        FindPlan {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Returns a copy of the plan whose scan of the collection starts after `id`. Like
+    /// [`to_covered`](Self::to_covered), it leaves a cached plan as it is.
+    pub(crate) fn resuming_after(&self, id: NitriteId) -> FindPlan {
+        let mut inner = (*self.inner).clone();
+        inner.resume_after = Some(id);
+        FindPlan {
+            inner: Arc::new(inner),
+        }
+    }
 }
 
 /// Opaque implementation details of FindPlan.
 /// This struct is part of the PIMPL pattern and should not be accessed directly.
+#[derive(Clone)]
 pub(crate) struct FindPlanInner {
     pub(crate) by_id_filter: Option<Filter>,
     pub(crate) index_scan_filter: Option<IndexScanFilter>,
@@ -381,12 +500,16 @@ pub(crate) struct FindPlanInner {
     pub(crate) index_descriptor: Option<IndexDescriptor>,
     pub(crate) index_scan_order: Option<HashMap<String, bool>>,
     pub(crate) blocking_sort_order: Option<Vec<(String, SortOrder)>>,
+    pub(crate) distance_sort: Option<DistanceSort>,
     pub(crate) skip: Option<u64>,
     pub(crate) limit: Option<u64>,
     pub(crate) distinct: bool,
+    pub(crate) tie_break_by_id: bool,
     pub(crate) collator_options: Option<CollatorOptions>,
     pub(crate) collator_preferences: Option<CollatorPreferences>,
     pub(crate) sub_plans: Option<Vec<FindPlan>>,
+    pub(crate) covered: bool,
+    pub(crate) resume_after: Option<NitriteId>,
 }
 
 impl FindPlanInner {
@@ -398,12 +521,16 @@ impl FindPlanInner {
             index_descriptor: None,
             index_scan_order: None,
             blocking_sort_order: None,
+            distance_sort: None,
             skip: None,
             limit: None,
             distinct: false,
+            tie_break_by_id: true,
             collator_options: None,
             collator_preferences: None,
             sub_plans: None,
+            covered: false,
+            resume_after: None,
         }
     }
 }
@@ -0,0 +1,75 @@
+use super::Document;
+use std::time::Duration;
+
+/// Options for the revision history of a collection.
+///
+/// When revision history is enabled on a collection, every write records the resulting
+/// version of the document in a side map, so previous states can be read back with
+/// [`super::NitriteCollectionProvider::document_history`] and
+/// [`super::NitriteCollectionProvider::find_as_of`]. History is bounded per document by
+/// a number of versions, an age, or both; the newest version of a document is always kept.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::collection::HistoryOptions;
+/// use std::time::Duration;
+///
+/// // keep up to 50 versions per document, none older than 90 days
+/// let options = HistoryOptions::new()
+///     .max_versions(50)
+///     .max_age(Duration::from_secs(90 * 24 * 60 * 60));
+/// collection.enable_history(options)?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HistoryOptions {
+    pub(crate) max_versions: Option<usize>,
+    pub(crate) max_age: Option<Duration>,
+}
+
+impl HistoryOptions {
+    /// Creates options that keep every version.
+    pub fn new() -> Self {
+        HistoryOptions::default()
+    }
+
+    /// Keeps at most `max_versions` versions per document (at least 1).
+    pub fn max_versions(mut self, max_versions: usize) -> Self {
+        self.max_versions = Some(max_versions.max(1));
+        self
+    }
+
+    /// Discards versions older than `max_age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Returns the maximum number of versions kept per document, if bounded.
+    pub fn get_max_versions(&self) -> Option<usize> {
+        self.max_versions
+    }
+
+    /// Returns the maximum age of kept versions, if bounded.
+    pub fn get_max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+}
+
+/// One recorded version of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentVersion {
+    /// The document as written, or `None` if this version records a removal.
+    pub document: Option<Document>,
+    /// The revision of the document at this version.
+    pub revision: i32,
+    /// The time of the write in milliseconds since the Unix epoch.
+    pub modified: u128,
+}
+
+impl DocumentVersion {
+    /// Returns `true` if this version records the removal of the document.
+    pub fn is_removed(&self) -> bool {
+        self.document.is_none()
+    }
+}
@@ -0,0 +1,104 @@
+use super::{NitriteId, WriteToken};
+use crate::errors::NitriteError;
+
+/// Options of an
+/// [`insert_many_with_options`](super::NitriteCollectionProvider::insert_many_with_options).
+///
+/// All documents are validated before any of them is written. By default nothing is
+/// inserted if a document is invalid; with `skip_invalid` the valid documents are inserted
+/// and the invalid ones are only reported.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::collection::InsertManyOptions;
+///
+/// let result = collection.insert_many_with_options(
+///     documents,
+///     &InsertManyOptions::new().skip_invalid(true),
+/// )?;
+/// for invalid in result.errors() {
+///     println!("document {} was not inserted: {}", invalid.index(), invalid.error());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InsertManyOptions {
+    skip_invalid: bool,
+}
+
+impl InsertManyOptions {
+    /// Creates the default options, inserting nothing if a document is invalid.
+    pub fn new() -> Self {
+        InsertManyOptions::default()
+    }
+
+    /// Sets whether the valid documents are inserted when some documents are invalid.
+    pub fn skip_invalid(mut self, skip_invalid: bool) -> Self {
+        self.skip_invalid = skip_invalid;
+        self
+    }
+
+    /// Returns `true` if the valid documents are inserted when some documents are invalid.
+    pub fn is_skip_invalid(&self) -> bool {
+        self.skip_invalid
+    }
+}
+
+/// A document rejected by the validation of an
+/// [`insert_many_with_options`](super::NitriteCollectionProvider::insert_many_with_options).
+#[derive(Debug, Clone)]
+pub struct InvalidDocument {
+    index: usize,
+    error: NitriteError,
+}
+
+impl InvalidDocument {
+    pub(crate) fn new(index: usize, error: NitriteError) -> Self {
+        InvalidDocument { index, error }
+    }
+
+    /// Returns the position of the document in the batch.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns why the document is invalid.
+    pub fn error(&self) -> &NitriteError {
+        &self.error
+    }
+}
+
+/// The outcome of an
+/// [`insert_many_with_options`](super::NitriteCollectionProvider::insert_many_with_options).
+///
+/// [`errors`](InsertManyResult::errors) lists every invalid document. Unless invalid
+/// documents are skipped, nothing has been inserted if the list is not empty.
+#[derive(Debug, Clone, Default)]
+pub struct InsertManyResult {
+    pub(crate) inserted_ids: Vec<NitriteId>,
+    pub(crate) errors: Vec<InvalidDocument>,
+    pub(crate) write_token: WriteToken,
+}
+
+impl InsertManyResult {
+    /// Returns `true` if every document was valid.
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the ids of the inserted documents, in the order of the batch.
+    pub fn inserted_ids(&self) -> &[NitriteId] {
+        &self.inserted_ids
+    }
+
+    /// Returns the invalid documents, in the order of the batch.
+    pub fn errors(&self) -> &[InvalidDocument] {
+        &self.errors
+    }
+
+    /// Returns the token of the insert, to be passed to
+    /// [`FindOptions::after_write`](super::FindOptions::after_write).
+    pub fn write_token(&self) -> WriteToken {
+        self.write_token
+    }
+}
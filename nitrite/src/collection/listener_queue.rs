@@ -0,0 +1,433 @@
+use crate::collection::{CollectionEventCallback, CollectionEventInfo, CollectionEventListener};
+use crate::common::catch_panics_with;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use basu::HandlerId;
+use dashmap::DashMap;
+use parking_lot::{Condvar, Mutex};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// What a queued listener does with an event arriving while its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The write publishing the event waits until the listener makes room.
+    Block,
+    /// The oldest queued event is dropped to make room.
+    DropOldest,
+    /// The event is rejected, and its write reports an `EventError` like it reports the
+    /// error of a listener called by the writes.
+    Error,
+}
+
+/// The health of a listener subscribed to a collection, from
+/// [`Nitrite::describe`](crate::nitrite::Nitrite::describe).
+///
+/// The counters start when the listener is created and are shared by its clones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenerHealth {
+    /// The collection the listener is subscribed to.
+    pub collection: String,
+    /// The name given with [`CollectionEventListener::named`], if any.
+    pub name: Option<String>,
+    /// The capacity of the queue of the listener, `None` if the writes call it.
+    pub capacity: Option<usize>,
+    /// What the listener does when its queue is full, `None` if the writes call it.
+    pub overflow: Option<OverflowPolicy>,
+    /// Events waiting in the queue.
+    pub queued: usize,
+    /// Events the listener handled.
+    pub delivered: u64,
+    /// Events the listener failed to handle, by returning an error or panicking.
+    pub failed: u64,
+    /// Events dropped from a full queue by [`OverflowPolicy::DropOldest`].
+    pub dropped: u64,
+    /// Events refused by a full queue with [`OverflowPolicy::Error`].
+    pub rejected: u64,
+}
+
+impl ListenerHealth {
+    /// Returns `true` if the listener handled every event it was given so far.
+    pub fn is_healthy(&self) -> bool {
+        self.failed == 0 && self.dropped == 0 && self.rejected == 0
+    }
+}
+
+/// Counters of the events given to a listener.
+#[derive(Default)]
+pub(crate) struct ListenerMetrics {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl ListenerMetrics {
+    /// Counts an event handled with `result`.
+    pub(crate) fn record<T>(&self, result: &NitriteResult<T>) {
+        match result {
+            Ok(_) => self.delivered.fetch_add(1, Ordering::Relaxed),
+            Err(_) => self.failed.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+}
+
+/// The bounded queue of a listener, emptied by a thread of its own.
+///
+/// Dropping the queue closes it: the thread delivers the events still queued, then ends.
+pub(crate) struct EventQueue {
+    state: Arc<QueueState>,
+}
+
+struct QueueState {
+    capacity: usize,
+    overflow: OverflowPolicy,
+    queue: Mutex<PendingEvents>,
+    /// Signalled when an event is queued or the queue is closed.
+    available: Condvar,
+    /// Signalled when an event leaves the queue.
+    space: Condvar,
+    metrics: Arc<ListenerMetrics>,
+}
+
+#[derive(Default)]
+struct PendingEvents {
+    events: VecDeque<CollectionEventInfo>,
+    closed: bool,
+}
+
+impl EventQueue {
+    /// Starts the thread delivering the queued events to `on_event`.
+    pub(crate) fn start(
+        capacity: usize,
+        overflow: OverflowPolicy,
+        on_event: Arc<dyn CollectionEventCallback>,
+        metrics: Arc<ListenerMetrics>,
+    ) -> NitriteResult<Self> {
+        if capacity == 0 {
+            log::error!("Capacity of a listener queue must be at least 1");
+            return Err(NitriteError::new(
+                "Capacity of a listener queue must be at least 1",
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        let state = Arc::new(QueueState {
+            capacity,
+            overflow,
+            queue: Mutex::new(PendingEvents::default()),
+            available: Condvar::new(),
+            space: Condvar::new(),
+            metrics,
+        });
+        let worker = state.clone();
+        thread::Builder::new()
+            .name("nitrite-listener".to_string())
+            .spawn(move || deliver(&worker, on_event.as_ref()))?;
+        Ok(EventQueue { state })
+    }
+
+    /// Queues an event, applying the overflow policy if the queue is full.
+    pub(crate) fn offer(&self, event: CollectionEventInfo) -> NitriteResult<()> {
+        let state = &self.state;
+        let mut queue = state.queue.lock();
+        while queue.events.len() >= state.capacity && !queue.closed {
+            match state.overflow {
+                OverflowPolicy::Block => state.space.wait(&mut queue),
+                OverflowPolicy::DropOldest => {
+                    queue.events.pop_front();
+                    state.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::Error => {
+                    state.metrics.rejected.fetch_add(1, Ordering::Relaxed);
+                    log::error!(
+                        "Queue of a collection event listener is full ({} events)",
+                        state.capacity
+                    );
+                    return Err(NitriteError::new(
+                        &format!(
+                            "Queue of a collection event listener is full ({} events)",
+                            state.capacity
+                        ),
+                        ErrorKind::EventError,
+                    ));
+                }
+            }
+        }
+        queue.events.push_back(event);
+        state.available.notify_one();
+        Ok(())
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.state.capacity
+    }
+
+    pub(crate) fn overflow(&self) -> OverflowPolicy {
+        self.state.overflow
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.state.queue.lock().events.len()
+    }
+}
+
+impl Drop for EventQueue {
+    fn drop(&mut self) {
+        self.state.queue.lock().closed = true;
+        self.state.available.notify_all();
+        self.state.space.notify_all();
+    }
+}
+
+/// Delivers the queued events one at a time, in the order they were queued, until the
+/// queue is closed and empty.
+fn deliver(state: &QueueState, on_event: &dyn CollectionEventCallback) {
+    loop {
+        let event = {
+            let mut queue = state.queue.lock();
+            loop {
+                if let Some(event) = queue.events.pop_front() {
+                    break event;
+                }
+                if queue.closed {
+                    return;
+                }
+                state.available.wait(&mut queue);
+            }
+        };
+        state.space.notify_one();
+
+        // a panicking listener must not stop the thread, writes could wait for it forever
+        let result = catch_panics_with(
+            || "Collection event listener".to_string(),
+            || on_event(event),
+        );
+        if let Err(e) = &result {
+            log::error!("Collection event listener failed: {}", e);
+        }
+        state.metrics.record(&result);
+    }
+}
+
+/// The listeners subscribed to the collections of a database, for
+/// [`Nitrite::describe`](crate::nitrite::Nitrite::describe).
+#[derive(Clone, Default)]
+pub(crate) struct ListenerRegistry {
+    listeners: Arc<DashMap<HandlerId, (String, CollectionEventListener)>>,
+}
+
+impl ListenerRegistry {
+    pub fn new() -> Self {
+        ListenerRegistry::default()
+    }
+
+    /// Records a listener subscribed to `collection` under `handler_id`.
+    pub fn add(&self, handler_id: HandlerId, collection: &str, listener: CollectionEventListener) {
+        self.listeners
+            .insert(handler_id, (collection.to_string(), listener));
+    }
+
+    /// Forgets an unsubscribed listener.
+    pub fn remove(&self, handler_id: &HandlerId) {
+        self.listeners.remove(handler_id);
+    }
+
+    /// Forgets the listeners of a closed collection.
+    pub fn remove_collection(&self, collection: &str) {
+        self.listeners.retain(|_, (name, _)| name != collection);
+    }
+
+    /// Returns the health of the listeners, by collection and name.
+    pub fn health(&self) -> Vec<ListenerHealth> {
+        let mut health: Vec<ListenerHealth> = self
+            .listeners
+            .iter()
+            .map(|entry| {
+                let (collection, listener) = entry.value();
+                listener.health(collection)
+            })
+            .collect();
+        health.sort_by(|a, b| (&a.collection, &a.name).cmp(&(&b.collection, &b.name)));
+        health
+    }
+}
+
+/// Returns the health of a listener subscribed to `collection`.
+pub(crate) fn listener_health(
+    collection: &str,
+    name: Option<&str>,
+    queue: Option<&EventQueue>,
+    metrics: &ListenerMetrics,
+) -> ListenerHealth {
+    ListenerHealth {
+        collection: collection.to_string(),
+        name: name.map(str::to_string),
+        capacity: queue.map(EventQueue::capacity),
+        overflow: queue.map(EventQueue::overflow),
+        queued: queue.map_or(0, EventQueue::len),
+        delivered: metrics.delivered.load(Ordering::Relaxed),
+        failed: metrics.failed.load(Ordering::Relaxed),
+        dropped: metrics.dropped.load(Ordering::Relaxed),
+        rejected: metrics.rejected.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::CollectionEvents;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn event(sequence: i32) -> CollectionEventInfo {
+        CollectionEventInfo::new(
+            Some(crate::Value::I32(sequence)),
+            CollectionEvents::Insert,
+            String::new(),
+        )
+    }
+
+    /// A queue whose listener waits for a permit before handling each event.
+    fn gated_queue(
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> (EventQueue, mpsc::Sender<()>, mpsc::Receiver<i32>, Arc<ListenerMetrics>) {
+        let (permits, gate) = mpsc::channel::<()>();
+        let (seen, received) = mpsc::channel();
+        let gate = Mutex::new(gate);
+        let seen = Mutex::new(seen);
+        let metrics = Arc::new(ListenerMetrics::default());
+        let queue = EventQueue::start(
+            capacity,
+            overflow,
+            Arc::new(move |event: CollectionEventInfo| {
+                let _ = gate.lock().recv();
+                let sequence = *event.item().unwrap().as_i32().unwrap();
+                let _ = seen.lock().send(sequence);
+                Ok(())
+            }),
+            metrics.clone(),
+        )
+        .unwrap();
+        (queue, permits, received, metrics)
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        for _ in 0..500 {
+            if condition() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("condition not reached");
+    }
+
+    #[test]
+    fn test_zero_capacity_is_rejected() {
+        let result = EventQueue::start(
+            0,
+            OverflowPolicy::Block,
+            Arc::new(|_| Ok(())),
+            Arc::new(ListenerMetrics::default()),
+        );
+        assert_eq!(result.err().unwrap().kind(), &ErrorKind::ValidationError);
+    }
+
+    #[test]
+    fn test_drop_oldest_keeps_newest_events() {
+        let (queue, permits, received, metrics) = gated_queue(2, OverflowPolicy::DropOldest);
+        queue.offer(event(1)).unwrap();
+        // the listener holds event 1 while 2 and 3 fill the queue
+        wait_until(|| queue.len() == 0);
+        for sequence in 2..=5 {
+            queue.offer(event(sequence)).unwrap();
+        }
+        assert_eq!(queue.len(), 2);
+        assert_eq!(metrics.dropped.load(Ordering::Relaxed), 2);
+
+        for _ in 0..3 {
+            permits.send(()).unwrap();
+        }
+        let delivered: Vec<i32> = (0..3).map(|_| received.recv().unwrap()).collect();
+        assert_eq!(delivered, vec![1, 4, 5]);
+        wait_until(|| metrics.delivered.load(Ordering::Relaxed) == 3);
+    }
+
+    #[test]
+    fn test_error_policy_rejects_when_full() {
+        let (queue, permits, received, metrics) = gated_queue(1, OverflowPolicy::Error);
+        queue.offer(event(1)).unwrap();
+        wait_until(|| queue.len() == 0);
+        queue.offer(event(2)).unwrap();
+        let error = queue.offer(event(3)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::EventError);
+        assert_eq!(metrics.rejected.load(Ordering::Relaxed), 1);
+
+        permits.send(()).unwrap();
+        permits.send(()).unwrap();
+        assert_eq!(received.recv().unwrap(), 1);
+        assert_eq!(received.recv().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_block_policy_waits_for_room() {
+        let (queue, permits, received, _) = gated_queue(1, OverflowPolicy::Block);
+        let queue = Arc::new(queue);
+        queue.offer(event(1)).unwrap();
+        wait_until(|| queue.len() == 0);
+        queue.offer(event(2)).unwrap();
+
+        let publisher = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.offer(event(3)))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!publisher.is_finished());
+
+        permits.send(()).unwrap();
+        publisher.join().unwrap().unwrap();
+        permits.send(()).unwrap();
+        permits.send(()).unwrap();
+        let delivered: Vec<i32> = (0..3).map(|_| received.recv().unwrap()).collect();
+        assert_eq!(delivered, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_closed_queue_delivers_pending_events() {
+        let (queue, permits, received, _) = gated_queue(4, OverflowPolicy::Block);
+        for sequence in 1..=3 {
+            queue.offer(event(sequence)).unwrap();
+        }
+        drop(queue);
+        for _ in 0..3 {
+            permits.send(()).unwrap();
+        }
+        let delivered: Vec<i32> = (0..3).map(|_| received.recv().unwrap()).collect();
+        assert_eq!(delivered, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_failing_listener_keeps_delivering() {
+        let metrics = Arc::new(ListenerMetrics::default());
+        let queue = EventQueue::start(
+            8,
+            OverflowPolicy::Block,
+            Arc::new(|event: CollectionEventInfo| {
+                match *event.item().unwrap().as_i32().unwrap() {
+                    1 => panic!("listener bug"),
+                    2 => Err(NitriteError::new("failed", ErrorKind::EventError)),
+                    _ => Ok(()),
+                }
+            }),
+            metrics.clone(),
+        )
+        .unwrap();
+        for sequence in 1..=3 {
+            queue.offer(event(sequence)).unwrap();
+        }
+        wait_until(|| metrics.delivered.load(Ordering::Relaxed) == 1);
+        assert_eq!(metrics.failed.load(Ordering::Relaxed), 2);
+    }
+}
@@ -55,22 +55,56 @@
 //! - `_modified` - Last modification timestamp
 
 mod document;
+mod document_builder;
 mod event;
+mod listener_queue;
 mod nitrite_id;
 mod find_plan;
+mod plan_snapshot;
 pub(crate) mod snowflake;
 pub(crate) mod operation;
 mod find_options;
 mod update_options;
+mod bulk_write;
+mod insert_many;
+mod update_each;
+mod upsert_many;
+mod collection_options;
+mod history_options;
 mod nitrite_collection;
 mod default_nitrite_collection;
 mod collection_factory;
+mod write_token;
+mod cancellation;
+mod redaction;
+mod reference;
+mod scan_token;
 
 pub(crate) use collection_factory::*;
+pub use bulk_write::*;
+pub use cancellation::CancellationToken;
+pub(crate) use cancellation::QueryInterrupt;
+pub use collection_options::*;
 pub use document::*;
+pub use document_builder::DocumentBuilder;
 pub use event::*;
 pub use find_options::*;
 pub use find_plan::*;
+pub use plan_snapshot::*;
+pub use history_options::*;
+pub use insert_many::*;
+pub use listener_queue::{ListenerHealth, OverflowPolicy};
+pub(crate) use listener_queue::ListenerRegistry;
 pub use nitrite_collection::*;
 pub use nitrite_id::NitriteId;
-pub use update_options::*;
\ No newline at end of file
+pub use redaction::*;
+pub use snowflake::{ClockSkewPolicy, IdGeneratorMetrics, MAX_NODE_ID};
+pub use reference::{OnDelete, Reference};
+pub use scan_token::ScanToken;
+pub use update_options::*;
+pub use update_each::*;
+pub use upsert_many::{ConflictTarget, UpsertManyOptions, UpsertManyResult};
+pub(crate) use upsert_many::{check_unique_index, conflict_fields, upsert_documents};
+pub use write_token::WriteToken;
+pub(crate) use write_token::{WriteTokenHolder, WriteTracker};
+pub(crate) use reference::ReferenceRegistry;
\ No newline at end of file
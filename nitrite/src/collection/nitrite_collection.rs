@@ -1,8 +1,21 @@
-use super::{operation::WriteResult, Document, FindOptions, NitriteId, UpdateOptions};
+use super::{
+    operation::WriteResult, BulkOperation, BulkWriteOptions, BulkWriteResult, CollectionOptions,
+    ConflictTarget, Document,
+    DocumentVersion, FindOptions, HistoryOptions, InsertManyOptions, InsertManyResult,
+    InvalidDocument, NitriteId, RedactionPolicy, ScanToken, UpdateEachOptions, UpdateEachResult, UpdateOptions,
+    UpsertManyOptions, UpsertManyResult,
+};
+use super::upsert_many::{conflict_fields, upsert_documents};
 use crate::{
-    errors::NitriteResult, filter::Filter, DocumentCursor
-    , PersistentCollection,
+    common::{collection_hash_entry, revision_field, Value, DOC_ID, REPLICATOR},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::{all, by_id, by_id_range, Filter},
+    index::{IndexExport, IndexStatistics},
+    DocumentCursor, PersistentCollection,
 };
+#[cfg(feature = "arrow")]
+use crate::columnar::SchemaMapping;
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -22,6 +35,54 @@ pub trait NitriteCollectionProvider: PersistentCollection {
     ///
     /// This is more efficient than calling `insert()` multiple times for batch operations.
     fn insert_many(&self, documents: Vec<Document>) -> NitriteResult<WriteResult>;
+
+    /// Validates all documents before inserting any of them and reports every invalid
+    /// document with its position in the batch.
+    ///
+    /// A document is invalid if it lacks a required field of the collection or if its id
+    /// is already taken, by a stored document or an earlier document of the batch. By
+    /// default nothing is inserted when a document is invalid; with
+    /// [`skip_invalid`](InsertManyOptions::skip_invalid) the valid documents are inserted.
+    /// The insert itself is atomic like `insert_many()`: an `Err`, for example for a unique
+    /// index violation, means that no document was inserted.
+    fn insert_many_with_options(
+        &self,
+        documents: Vec<Document>,
+        options: &InsertManyOptions,
+    ) -> NitriteResult<InsertManyResult> {
+        let collection_options = self.options()?;
+        let mut result = InsertManyResult::default();
+        let mut ids = HashSet::with_capacity(documents.len());
+        let mut valid = Vec::with_capacity(documents.len());
+
+        for (index, mut document) in documents.into_iter().enumerate() {
+            let checked = document.id().and_then(|id| {
+                collection_options.validate(&document)?;
+                if !ids.insert(id) || self.get_by_id(&id)?.is_some() {
+                    log::error!("Document already exists with id {}", id);
+                    return Err(NitriteError::new(
+                        &format!("Document already exists with id {}", id),
+                        ErrorKind::UniqueConstraintViolation,
+                    ));
+                }
+                Ok(())
+            });
+
+            match checked {
+                Ok(()) => valid.push(document),
+                Err(error) => result.errors.push(InvalidDocument::new(index, error)),
+            }
+        }
+
+        if valid.is_empty() || (!result.errors.is_empty() && !options.is_skip_invalid()) {
+            return Ok(result);
+        }
+
+        let written = self.insert_many(valid)?;
+        result.inserted_ids = written.affected_nitrite_ids().clone();
+        result.write_token = written.write_token();
+        Ok(result)
+    }
     
     /// Updates documents matching a filter with the specified update document.
     ///
@@ -62,6 +123,63 @@ pub trait NitriteCollectionProvider: PersistentCollection {
         insert_if_absent: bool,
     ) -> NitriteResult<WriteResult>;
 
+    /// Inserts a document, or updates the stored document with its id, unless that would
+    /// not change the stored document.
+    ///
+    /// A document without an id, or whose id is not stored, is inserted. Otherwise the
+    /// document is written like `update_one(document, true)`: its fields are set on the
+    /// stored document and the fields it does not have are kept. When the stored document
+    /// already holds these values, as told by [`Document::content_hash`], nothing is
+    /// written: the revision stays, no index is touched, no event is raised and the result
+    /// has no affected id. This spares the indexes of a collection a sync keeps rewriting
+    /// with the same documents.
+    ///
+    /// The check and the write are atomic on a collection of the database; they are two
+    /// steps on other implementations.
+    fn insert_if_changed(&self, document: Document) -> NitriteResult<WriteResult> {
+        let mut document = document;
+        if !document.has_id() {
+            return self.insert(document);
+        }
+
+        let id = document.id()?;
+        let Some(stored) = self.get_by_id(&id)? else {
+            return self.insert(document);
+        };
+        if REPLICATOR.ne(&document.source()?) {
+            document.remove(&revision_field())?;
+        }
+        let mut merged = stored.clone();
+        merged.merge(&document)?;
+        if merged.content_hash() == stored.content_hash() {
+            return Ok(WriteResult::new(Vec::new()));
+        }
+        self.update_by_id(&id, &document, false)
+    }
+
+    /// Adds `delta` to the numeric field `field` of the document `id` and returns the new
+    /// value of the field.
+    ///
+    /// The counter is changed in place under the write lock of the collection, so
+    /// concurrent increments are never lost and the caller does not read and write back
+    /// the document. A missing field counts as zero; the field keeps its numeric type.
+    /// Indexes are only updated if one of them covers the field. The write bumps the
+    /// revision of the document and raises an `Update` event like any update.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the document does not exist, an `InvalidDataType`
+    /// error if the field or `delta` is not a number, or an integer field would be
+    /// incremented by a decimal, and an `InvalidOperation` error if the result overflows
+    /// the type of the field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let views = collection.increment(&id, "views", Value::from(1))?;
+    /// ```
+    fn increment(&self, id: &NitriteId, field: &str, delta: Value) -> NitriteResult<Value>;
+
     /// Removes documents matching a filter.
     ///
     /// # Arguments
@@ -73,6 +191,151 @@ pub trait NitriteCollectionProvider: PersistentCollection {
     /// Removes a single document by its identity (using its `_id` field).
     fn remove_one(&self, document: &Document) -> NitriteResult<WriteResult>;
 
+    /// Removes the documents whose id lies between `start` and `end`, both inclusive, and
+    /// returns the number of documents removed.
+    ///
+    /// Ids grow with their creation time, so with `NitriteId::from_time_range()` this
+    /// purges the documents created in a time range. On a collection of the database the
+    /// documents are dropped with one range removal of the store in a single atomic
+    /// scope; they are only read when indexes, revision history, soft delete, a cap or
+    /// quota, or references to the collection need them, and no events are published,
+    /// as with `clear()`. Other implementations remove the matching documents with
+    /// `remove()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// // drop everything created before the last 30 days
+    /// let cutoff = get_current_time_or_zero() - 30 * 24 * 3_600_000;
+    /// let (start, end) = NitriteId::from_time_range(0, cutoff)?;
+    /// let removed = collection.remove_id_range(&start, &end)?;
+    /// ```
+    fn remove_id_range(&self, start: &NitriteId, end: &NitriteId) -> NitriteResult<u64> {
+        let result = self.remove(by_id_range(*start, *end), false)?;
+        Ok(result.affected_nitrite_ids().len() as u64)
+    }
+
+    /// Runs a batch of inserts, updates, replacements and removals atomically.
+    ///
+    /// The operations run in order as one unit: if any of them fails, the whole batch is
+    /// rolled back and the result lists the failures. This uses the default, ordered
+    /// options; use `bulk_write_with_options()` to report every failure of the batch.
+    fn bulk_write(&self, operations: Vec<BulkOperation>) -> NitriteResult<BulkWriteResult> {
+        self.bulk_write_with_options(operations, &BulkWriteOptions::default())
+    }
+
+    /// Runs a batch of writes atomically with the specified options.
+    ///
+    /// An ordered batch stops at the first failing operation, an unordered one runs every
+    /// operation before rolling back. An `Err` is only returned when the batch cannot be
+    /// run or rolled back at all, for example because the collection is closed.
+    fn bulk_write_with_options(
+        &self,
+        operations: Vec<BulkOperation>,
+        options: &BulkWriteOptions,
+    ) -> NitriteResult<BulkWriteResult>;
+
+    /// Passes each document matching a filter to `transform` and writes back the documents
+    /// it returns, in atomic batches.
+    ///
+    /// The matching documents are determined when the call starts and are read one batch
+    /// at a time; documents removed in the meantime are skipped. `transform` returns
+    /// `Ok(Some(update))` to change a document, with the same merge semantics as
+    /// `update()`, or `Ok(None)` to leave it as it is. If `transform` or a batch fails the
+    /// error is returned: the failing batch is not applied, the batches before it are.
+    ///
+    /// [`NitriteCollection::update_each`] accepts the closure by value.
+    fn update_each_with_options(
+        &self,
+        filter: Filter,
+        transform: &mut dyn FnMut(Document) -> NitriteResult<Option<Document>>,
+        options: &UpdateEachOptions,
+    ) -> NitriteResult<UpdateEachResult> {
+        let mut ids = Vec::new();
+        for document in self.find(filter)? {
+            let mut document = document?;
+            ids.push(document.id()?);
+        }
+
+        let mut result = UpdateEachResult::default();
+        for batch in ids.chunks(options.get_batch_size()) {
+            let mut operations = Vec::with_capacity(batch.len());
+            for id in batch {
+                let Some(document) = self.get_by_id(id)? else {
+                    continue;
+                };
+                result.matched_count += 1;
+                if let Some(mut update) = transform(document)? {
+                    update.put(DOC_ID, Value::NitriteId(*id))?;
+                    operations.push(BulkOperation::UpdateOne {
+                        filter: by_id(*id),
+                        update,
+                        upsert: false,
+                    });
+                }
+            }
+            if operations.is_empty() {
+                continue;
+            }
+
+            let written = self.bulk_write(operations)?;
+            if let Some(failure) = written.errors().first() {
+                log::error!("Failed to write back a batch of update_each: {}", failure.error());
+                return Err(failure.error().clone());
+            }
+            result.modified_count += written.modified_count();
+            result.batch_count += 1;
+            result.write_token = written.write_token();
+        }
+        Ok(result)
+    }
+
+    /// Inserts the documents no stored document conflicts with and updates the others,
+    /// matching them on `target`, in one atomic write.
+    ///
+    /// This uses the default options; see
+    /// [`upsert_many_with_options`](NitriteCollectionProvider::upsert_many_with_options).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// books.create_index(vec!["isbn"], &unique_index())?;
+    /// let result = books.upsert_many(feed, ConflictTarget::index("isbn"))?;
+    /// println!("{} new, {} updated", result.inserted_ids().len(), result.matched_count());
+    /// ```
+    fn upsert_many(
+        &self,
+        documents: Vec<Document>,
+        target: ConflictTarget,
+    ) -> NitriteResult<UpsertManyResult> {
+        self.upsert_many_with_options(documents, target, &UpsertManyOptions::default())
+    }
+
+    /// Inserts the documents no stored document conflicts with and updates the others,
+    /// with the specified options.
+    ///
+    /// A document conflicts with the stored document that has the same value for the
+    /// field of `target`, which must have a unique index, or the same `_id` for
+    /// [`ConflictTarget::Id`]. The stored documents are looked up a batch of keys per
+    /// query. Conflicting documents are updated with the same merge semantics as
+    /// `update()`, documents of the batch sharing a key are merged in order first, and all
+    /// writes are applied as one [`bulk_write`](NitriteCollectionProvider::bulk_write):
+    /// an `Err` means that no document was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the field of `target` has no unique index, or if a document
+    /// has no value or an array for it.
+    fn upsert_many_with_options(
+        &self,
+        documents: Vec<Document>,
+        target: ConflictTarget,
+        options: &UpsertManyOptions,
+    ) -> NitriteResult<UpsertManyResult> {
+        let key_fields = conflict_fields(self, &target)?;
+        upsert_documents(self, documents, &key_fields, options)
+    }
+
     /// Finds documents matching a filter.
     ///
     /// Returns a `DocumentCursor` for iterating over results.
@@ -87,11 +350,202 @@ pub trait NitriteCollectionProvider: PersistentCollection {
         find_options: &FindOptions,
     ) -> NitriteResult<DocumentCursor>;
 
+    /// Resumes a scan of the whole collection from a token of
+    /// [`DocumentCursor::checkpoint`].
+    ///
+    /// The cursor returns the documents stored after the last document returned before
+    /// the checkpoint, in the order of the ids, and can be checkpointed again. A job
+    /// exporting a large collection can save the token with its progress and, after a
+    /// crash or a restart, continue where it stopped instead of starting over. To resume
+    /// a filtered scan, pass the token to
+    /// [`FindOptions::resume_after`](super::FindOptions::resume_after) with the filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidOperation` if the token is of another collection.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let token = ScanToken::parse(&load_progress()?)?;
+    /// let mut cursor = events.resume_scan(&token)?;
+    /// ```
+    fn resume_scan(&self, token: &ScanToken) -> NitriteResult<DocumentCursor> {
+        self.find_with_options(all(), &FindOptions::new().resume_after(token.clone()))
+    }
+
+    /// Counts the documents matching a filter.
+    ///
+    /// The count is planned on its own rather than through a cursor: a filter fully
+    /// covered by a unique or non-unique index is answered from the index entries, and
+    /// `all()` from the size of the collection, without reading any document. Other
+    /// filters are counted by streaming the matching documents.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let unread = collection.count(field("folder").eq("inbox").and(field("read").eq(false)))?;
+    /// ```
+    fn count(&self, filter: Filter) -> NitriteResult<u64>;
+
     /// Retrieves a document by its NitriteId.
     ///
     /// This is an O(1) operation.
     fn get_by_id(&self, id: &NitriteId) -> NitriteResult<Option<Document>>;
 
+    /// Enables revision history for this collection, or changes its bounds.
+    ///
+    /// From now on every insert, update and remove records the resulting version of the
+    /// document, so that earlier states can be read with `document_history()` and
+    /// `find_as_of()`. The setting is persisted with the collection.
+    fn enable_history(&self, options: HistoryOptions) -> NitriteResult<()>;
+
+    /// Disables revision history and discards all recorded versions.
+    fn disable_history(&self) -> NitriteResult<()>;
+
+    /// Returns the history options if revision history is enabled.
+    fn history_options(&self) -> NitriteResult<Option<HistoryOptions>>;
+
+    /// Returns the recorded versions of a document, oldest first.
+    ///
+    /// The list is empty if the document has not been written since history was enabled.
+    fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>>;
+
+    /// Finds documents matching a filter as they were at `timestamp` (milliseconds since
+    /// the Unix epoch).
+    ///
+    /// Documents written since history was enabled are resolved from their recorded
+    /// versions; other documents are included with their current state if they were last
+    /// modified at or before `timestamp`. The filter is evaluated without indexes.
+    fn find_as_of(&self, timestamp: u128, filter: Filter) -> NitriteResult<DocumentCursor>;
+
+    /// Gathers cardinality statistics of the collection's unique and non-unique indexes.
+    ///
+    /// The statistics (entry count, distinct keys and most common keys of each index) are
+    /// persisted with the index metadata. When several indexes match a query and all of
+    /// them have been analyzed, the query planner uses them to pick the most selective
+    /// index instead of the one covering the most filters. Statistics are not maintained
+    /// on writes; run `analyze()` again after the data distribution changes.
+    fn analyze(&self) -> NitriteResult<()>;
+
+    /// Returns the statistics gathered by the last `analyze()` for the index on the
+    /// given fields, or `None` if there is no such index or it has not been analyzed.
+    fn index_statistics(&self, field_names: Vec<&str>) -> NitriteResult<Option<IndexStatistics>>;
+
+    /// Returns a hash of the documents of this collection: of their ids and their fields
+    /// but the metadata fields, independent of the order the documents are stored in.
+    ///
+    /// Two copies of a collection holding the same documents have the same hash, whatever
+    /// their revisions or the store they live in. It is what an [`IndexExport`] is checked
+    /// against on import. This reads every document.
+    fn content_hash(&self) -> NitriteResult<u128> {
+        let mut hash = 0u128;
+        for document in self.find(all())? {
+            let mut document = document?;
+            let id = document.id()?;
+            hash = hash.wrapping_add(collection_hash_entry(&id, &document));
+        }
+        Ok(hash)
+    }
+
+    /// Exports the data of the index on the given fields, to be imported into another
+    /// copy of this collection instead of rebuilding the index there.
+    ///
+    /// Only indexes whose indexer keeps its entries outside of the collection can be
+    /// exported, like the full-text indexes of `nitrite_tantivy_fts`; for other indexes,
+    /// or an index that is not up to date, this returns an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// collection.export_index(vec!["body"])?.write_to_file("body.idx")?;
+    /// ```
+    fn export_index(&self, field_names: Vec<&str>) -> NitriteResult<IndexExport> {
+        log::error!(
+            "Collection {} cannot export the index on fields {:?}",
+            self.name(),
+            field_names
+        );
+        Err(NitriteError::new(
+            &format!("Collection {} cannot export indexes", self.name()),
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    /// Imports the data of an index exported by [`export_index`](Self::export_index),
+    /// creating the index if this collection does not have it yet.
+    ///
+    /// The export must come from a collection holding the same documents, as checked by
+    /// [`content_hash`](Self::content_hash); otherwise this returns a `ValidationError`
+    /// and the index is left as it was, so it can be built from the documents instead.
+    /// An existing index of the same fields and type is replaced.
+    fn import_index(&self, export: &IndexExport) -> NitriteResult<()> {
+        log::error!(
+            "Collection {} cannot import the index on fields {:?}",
+            self.name(),
+            export.field_names()
+        );
+        Err(NitriteError::new(
+            &format!("Collection {} cannot import indexes", self.name()),
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    /// Runs `bulk_load` without maintaining the existing indexes on writes, then rebuilds
+    /// them from the documents in key order.
+    ///
+    /// Before `bulk_load` runs, every index is marked dirty in its metadata and queries stop
+    /// using it. An index is marked clean again only once its rebuild succeeds: if the
+    /// rebuild fails, for example because the loaded documents violate a unique index, or
+    /// the database stops before it runs, the index stays unused by queries and is rebuilt
+    /// on a later write. The rebuild runs even if `bulk_load` fails; its error is returned
+    /// first. Unique constraints are only checked by the rebuild.
+    ///
+    /// [`NitriteCollection::with_indexes_disabled`] passes the collection to the closure.
+    fn defer_index_maintenance(
+        &self,
+        bulk_load: &mut dyn FnMut() -> NitriteResult<()>,
+    ) -> NitriteResult<()>;
+
+    /// Returns the options of this collection.
+    fn options(&self) -> NitriteResult<CollectionOptions>;
+
+    /// Replaces the options of this collection.
+    ///
+    /// The options are persisted with the collection and apply to every later write.
+    /// Turning soft delete off discards the soft-deleted documents.
+    fn set_options(&self, options: CollectionOptions) -> NitriteResult<()>;
+
+    /// Returns the documents removed while soft delete was enabled.
+    fn deleted_documents(&self) -> NitriteResult<Vec<Document>>;
+
+    /// Inserts a soft-deleted document back under its original id.
+    ///
+    /// The document is inserted like a new one and starts again at revision 1. The
+    /// result is empty if no soft-deleted document has this id.
+    fn restore(&self, id: &NitriteId) -> NitriteResult<WriteResult>;
+
+    /// Discards all soft-deleted documents.
+    fn purge_deleted(&self) -> NitriteResult<()>;
+
+    /// Removes the fields whose expiry deadline has passed from storage and from the
+    /// indexes, and returns the number of documents changed.
+    ///
+    /// Expired fields are hidden from reads as soon as they expire; until they are swept
+    /// their values stay in the indexes, so a count answered from an index alone may still
+    /// include them. See [`Document::set_field_expiry`].
+    fn sweep_expired_fields(&self) -> NitriteResult<u64>;
+
+    /// Sets the policy redacting the documents returned by `find` and `get_by_id`, or
+    /// removes it with `None`.
+    ///
+    /// The policy lives with this collection instance and is not persisted. Transactions
+    /// started afterwards redact their reads the same way. See [`RedactionPolicy`].
+    fn set_redaction_policy(&self, policy: Option<RedactionPolicy>) -> NitriteResult<()>;
+
+    /// Returns the redaction policy of this collection, if any.
+    fn redaction_policy(&self) -> NitriteResult<Option<RedactionPolicy>>;
+
     /// Returns the name of this collection.
     fn name(&self) -> String;
 }
@@ -139,6 +593,119 @@ impl NitriteCollection {
     pub fn new<T: NitriteCollectionProvider + 'static>(inner: T) -> Self {
         NitriteCollection { inner: Arc::new(inner) }
     }
+
+    /// Passes each document matching `filter` to `transform` and writes back the
+    /// documents it returns, in atomic batches of the default size.
+    ///
+    /// See [`NitriteCollectionProvider::update_each_with_options`] for the details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let result = orders.update_each(field("status").eq("pending"), |mut order| {
+    ///     let total = order.get("total")?;
+    ///     if total.as_i64().is_some_and(|total| *total > 1000) {
+    ///         order.put("status", "review")?;
+    ///         return Ok(Some(order));
+    ///     }
+    ///     Ok(None)
+    /// })?;
+    /// println!("{} of {} orders need a review", result.modified_count(), result.matched_count());
+    /// ```
+    pub fn update_each<F>(&self, filter: Filter, transform: F) -> NitriteResult<UpdateEachResult>
+    where
+        F: FnMut(Document) -> NitriteResult<Option<Document>>,
+    {
+        self.update_each_with_options(filter, transform, &UpdateEachOptions::default())
+    }
+
+    /// Like [`update_each`](NitriteCollection::update_each), with the specified options.
+    pub fn update_each_with_options<F>(
+        &self,
+        filter: Filter,
+        mut transform: F,
+        options: &UpdateEachOptions,
+    ) -> NitriteResult<UpdateEachResult>
+    where
+        F: FnMut(Document) -> NitriteResult<Option<Document>>,
+    {
+        self.inner
+            .update_each_with_options(filter, &mut transform, options)
+    }
+
+    /// Runs `bulk_load` with index maintenance deferred and rebuilds the indexes once it
+    /// returns, which is faster than maintaining them on every insert of a large load.
+    ///
+    /// See [`NitriteCollectionProvider::defer_index_maintenance`] for the details.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let imported = users.with_indexes_disabled(|users| {
+    ///     for chunk in records.chunks(1000) {
+    ///         users.insert_many(chunk.to_vec())?;
+    ///     }
+    ///     Ok(records.len())
+    /// })?;
+    /// ```
+    pub fn with_indexes_disabled<F, R>(&self, bulk_load: F) -> NitriteResult<R>
+    where
+        F: FnOnce(&NitriteCollection) -> NitriteResult<R>,
+    {
+        let mut bulk_load = Some(bulk_load);
+        let mut output = None;
+        self.inner.defer_index_maintenance(&mut || {
+            if let Some(bulk_load) = bulk_load.take() {
+                output = Some(bulk_load(self)?);
+            }
+            Ok(())
+        })?;
+
+        output.ok_or_else(|| {
+            log::error!("Bulk load of collection {} did not run", self.name());
+            NitriteError::new("Bulk load did not run", ErrorKind::InvalidOperation)
+        })
+    }
+
+    /// Writes the documents matching `filter` to a Parquet file and returns their number.
+    ///
+    /// The columns are the `projection` fields, which may be embedded fields, or `_id`
+    /// and all the top-level fields; their types are inferred from the documents as
+    /// described on [`SchemaMapping::infer`]. An existing file at `path` is replaced.
+    ///
+    /// Requires the `arrow` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let rows = orders.export_parquet(
+    ///     "/exports/orders.parquet",
+    ///     field("status").eq("shipped"),
+    ///     Some(&["_id", "customer.name", "total"]),
+    /// )?;
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn export_parquet(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        filter: Filter,
+        projection: Option<&[&str]>,
+    ) -> NitriteResult<u64> {
+        crate::columnar::export_parquet(self, path.as_ref(), filter, None, projection)
+    }
+
+    /// Like [`export_parquet`](NitriteCollection::export_parquet), with the columns of
+    /// `mapping`. A value that does not fit the type of its column fails the export with
+    /// an `InvalidDataType` error.
+    #[cfg(feature = "arrow")]
+    pub fn export_parquet_with_schema(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        filter: Filter,
+        mapping: &SchemaMapping,
+    ) -> NitriteResult<u64> {
+        crate::columnar::export_parquet(self, path.as_ref(), filter, Some(mapping), None)
+    }
 }
 
 impl Deref for NitriteCollection {
@@ -1,3 +1,4 @@
+use super::snowflake::{IdGeneratorMetrics, SNOWFLAKE_EPOCH, TIMESTAMP_LEFT_SHIFT};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::{ID_GENERATOR, NO2};
 use once_cell::sync::Lazy;
@@ -79,6 +80,27 @@ impl NitriteId {
         }
     }
 
+    /// Generates a new unique `NitriteId`, honouring the configured
+    /// [`ClockSkewPolicy`](crate::collection::ClockSkewPolicy).
+    ///
+    /// [`NitriteId::new`] never fails: if the clock moved backwards further than the
+    /// policy allows, it continues from the last timestamp. Nitrite generates the ids
+    /// of inserted documents with this method instead, so that such a jump fails the
+    /// insert.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the clock moved backwards and the policy refuses the jump.
+    pub fn try_new() -> NitriteResult<Self> {
+        let id_value = ID_GENERATOR.try_get_id()?;
+        Ok(NitriteId { id_value })
+    }
+
+    /// Returns the counters of the id generator shared by all databases of the process.
+    pub fn generator_metrics() -> IdGeneratorMetrics {
+        ID_GENERATOR.metrics()
+    }
+
     /// Creates a `NitriteId` from a specific value.
     ///
     /// The value must be within the valid range [10^18, 10^19).
@@ -113,6 +135,74 @@ impl NitriteId {
         self.id_value
     }
 
+    /// Returns when this id was generated, in milliseconds since the Unix epoch.
+    ///
+    /// Generated ids embed their creation time, so documents can be ordered or selected
+    /// by age without a timestamp field. The result is meaningless for ids created from
+    /// arbitrary values with [`NitriteId::create_id`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let id = NitriteId::new();
+    /// let created_at = id.timestamp();
+    /// ```
+    pub fn timestamp(&self) -> u128 {
+        ((self.id_value >> TIMESTAMP_LEFT_SHIFT) + SNOWFLAKE_EPOCH) as u128
+    }
+
+    /// Returns the smallest and the largest id that can be generated between `start` and
+    /// `end` (inclusive, in milliseconds since the Unix epoch).
+    ///
+    /// The bounds are meant for [`by_id_range`](crate::filter::by_id_range), which selects
+    /// the documents created in a time range with a range scan of the collection. Bounds
+    /// outside of the valid id range are clamped to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidId` error if `start` is after `end`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use nitrite::filter::by_id_range;
+    ///
+    /// // documents created in the last hour
+    /// let now = get_current_time_or_zero();
+    /// let (start, end) = NitriteId::from_time_range(now - 3_600_000, now)?;
+    /// let cursor = collection.find(by_id_range(start, end))?;
+    /// ```
+    pub fn from_time_range(start: u128, end: u128) -> NitriteResult<(NitriteId, NitriteId)> {
+        if start > end {
+            log::error!("Time range start {} is after its end {}", start, end);
+            return Err(NitriteError::new(
+                &format!("Time range start {} is after its end {}", start, end),
+                ErrorKind::InvalidId,
+            ));
+        }
+
+        let epoch = SNOWFLAKE_EPOCH as u128;
+        let scale = 1_u128 << TIMESTAMP_LEFT_SHIFT;
+        let lowest = start.saturating_sub(epoch).saturating_mul(scale);
+        let highest = end
+            .saturating_sub(epoch)
+            .saturating_add(1)
+            .saturating_mul(scale)
+            .saturating_sub(1);
+        let clamp = |value: u128| value.clamp(*MIN_VALUE as u128, *MAX_VALUE as u128 - 1) as u64;
+
+        Ok((
+            NitriteId { id_value: clamp(lowest) },
+            NitriteId { id_value: clamp(highest) },
+        ))
+    }
+
+    /// Rebuilds an id from the value of an id that was already validated, such as one
+    /// read back from an [`IdSet`](crate::common::IdSet).
+    pub(crate) fn from_id_value(id_value: u64) -> NitriteId {
+        NitriteId { id_value }
+    }
+
     /// Returns a sentinel `NitriteId` that sorts strictly below every real id.
     ///
     /// Real ids are always in `[10^18, 10^19)`, so `0` is guaranteed to be smaller
@@ -135,6 +225,12 @@ impl NitriteId {
         NitriteId { id_value: u64::MAX }
     }
 
+    /// Returns the id right after this one, the exclusive end of a key range ending with
+    /// this id. The max sentinel is its own successor.
+    pub(crate) fn next_id(&self) -> NitriteId {
+        NitriteId { id_value: self.id_value.saturating_add(1) }
+    }
+
     pub(crate) fn valid_id(id_value: u64) -> NitriteResult<bool> {
         if id_value >= *MAX_VALUE {
             log::error!("Id value is too large");
@@ -219,6 +315,34 @@ mod tests {
         assert_eq!(id.err().unwrap().kind(), &ErrorKind::InvalidId);
     }
 
+    #[test]
+    fn test_timestamp() {
+        let before = crate::common::get_current_time_or_zero();
+        let id = NitriteId::new();
+        let after = crate::common::get_current_time_or_zero();
+        assert!(id.timestamp() >= before && id.timestamp() <= after + 1);
+    }
+
+    #[test]
+    fn test_from_time_range() {
+        let id = NitriteId::new();
+        let (start, end) = NitriteId::from_time_range(id.timestamp(), id.timestamp()).unwrap();
+        assert!(start <= id && id <= end);
+        assert_eq!(start.timestamp(), id.timestamp());
+        assert_eq!(end.timestamp(), id.timestamp());
+
+        let (later, _) = NitriteId::from_time_range(id.timestamp() + 1, id.timestamp() + 1).unwrap();
+        assert!(later > id);
+
+        // bounds are clamped to the valid range
+        let (start, end) = NitriteId::from_time_range(0, u128::MAX / 2).unwrap();
+        assert!(NitriteId::valid_id(start.id_value()).is_ok());
+        assert!(NitriteId::valid_id(end.id_value()).is_ok());
+
+        let result = NitriteId::from_time_range(2, 1);
+        assert_eq!(result.err().unwrap().kind(), &ErrorKind::InvalidId);
+    }
+
     #[test]
     fn test_display() {
         let id = NitriteId::create_id(1234567890123456789).unwrap();
@@ -1,31 +1,45 @@
 use super::{
-    find_optimizer::FindOptimizer, index_operations::IndexOperations,
+    field_expiry::hide_expired_fields, find_optimizer::FindOptimizer, history_operations::HistoryOperations,
+    index_operations::IndexOperations, options_operations::OptionsOperations,
     index_writer::DocumentIndexWriter, read_operations::ReadOperations,
     write_operations::WriteOperations, write_result::WriteResult,
 };
 use crate::{
     collection::{
-        CollectionEventInfo, CollectionEventListener, Document, FindOptions, NitriteId, UpdateOptions,
+        BulkOperation, BulkWriteError, BulkWriteOptions, BulkWriteResult, CollectionEventInfo,
+        CollectionEventListener, CollectionOptions, Document, DocumentVersion, FindOptions, HistoryOptions,
+        ListenerRegistry, NitriteId, RedactionPolicy, UpdateOptions,
     },
-    errors::NitriteResult,
-    filter::Filter,
-    index::IndexDescriptor,
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::{field, Filter},
+    index::{IndexDescriptor, IndexExport, IndexOptions, IndexStatistics},
     nitrite_config::NitriteConfig,
     store::{NitriteMap, NitriteMapProvider, NitriteStoreProvider},
-    AttributeAware, Attributes, DocumentCursor, Fields, NitriteEventBus, Processor, ProcessorChain,
-    SubscriberRef,
+    atomic, collection_hash_entry, expiry_field, Atomic, AttributeAware, Attributes, DocumentCursor, Fields, NitriteEventBus, Processor,
+    ProcessorChain, ReadExecutor, SubscriberRef, Value, WriteExecutor, DOC_ID,
 };
 use std::sync::Arc;
 use std::{borrow::Cow, ops::Deref};
 
+/// What a bulk write has to undo for one operation: the documents to remove and the
+/// original documents to put back.
+struct BulkUndo {
+    ids: Vec<NitriteId>,
+    originals: Vec<Document>,
+}
 
 pub(crate) struct CollectionOperations {
+    collection_name: String,
     nitrite_map: NitriteMap,
     event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
+    listeners: ListenerRegistry,
     processor_chain: ProcessorChain,
     index_operations: IndexOperations,
     write_operations: WriteOperations,
     read_operations: ReadOperations,
+    history_operations: HistoryOperations,
+    options_operations: OptionsOperations,
+    redaction_policy: Atomic<Option<RedactionPolicy>>,
 }
 
 impl CollectionOperations {
@@ -61,29 +75,51 @@ impl CollectionOperations {
         let index_writer =
             DocumentIndexWriter::new(nitrite_config.clone(), index_operations.clone());
 
+        let history_operations =
+            HistoryOperations::new(collection_name, nitrite_map.clone(), processor_chain.clone())?;
+
+        let options_operations =
+            OptionsOperations::new(collection_name, nitrite_map.clone(), processor_chain.clone())?;
+
         let write_operations = WriteOperations::new(
             index_writer.clone(),
             read_operations.clone(),
             event_bus.clone(),
             nitrite_map.clone(),
             processor_chain.clone(),
+            history_operations.clone(),
+            options_operations.clone(),
+            collection_name,
+            nitrite_config.references(),
         );
 
         Ok(Self {
+            collection_name: collection_name.to_string(),
             nitrite_map,
             event_bus,
+            listeners: nitrite_config.listeners(),
             processor_chain,
             index_operations,
             write_operations,
             read_operations,
+            history_operations,
+            options_operations,
+            redaction_policy: atomic(None),
         })
     }
 
     pub fn subscribe(&self, handler: CollectionEventListener) -> NitriteResult<Option<SubscriberRef>> {
-        self.event_bus.register(handler)
+        let listener = handler.clone();
+        let subscriber = self.event_bus.register(handler)?;
+        if let Some(subscriber) = &subscriber {
+            self.listeners
+                .add(subscriber.inner.clone(), &self.collection_name, listener);
+        }
+        Ok(subscriber)
     }
 
     pub fn unsubscribe(&self, subscriber: SubscriberRef) -> NitriteResult<()> {
+        self.listeners.remove(&subscriber.inner);
         self.event_bus.deregister(subscriber)
     }
 
@@ -91,8 +127,8 @@ impl CollectionOperations {
         self.processor_chain.add_processor(processor);
     }
 
-    pub fn create_index(&self, fields: &Fields, index_type: &str) -> NitriteResult<()> {
-        self.index_operations.create_index(fields, index_type)
+    pub fn create_index(&self, fields: &Fields, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.index_operations.create_index(fields, index_options)
     }
 
     pub fn find_index(&self, fields: &Fields) -> NitriteResult<Option<IndexDescriptor>> {
@@ -123,6 +159,45 @@ impl CollectionOperations {
         self.index_operations.drop_all_indexes()
     }
 
+    pub fn analyze(&self) -> NitriteResult<()> {
+        self.index_operations.analyze()
+    }
+
+    pub fn index_statistics(&self, fields: &Fields) -> NitriteResult<Option<IndexStatistics>> {
+        self.index_operations.index_statistics(fields)
+    }
+
+    pub fn defer_index_maintenance(&self) -> NitriteResult<()> {
+        self.index_operations.defer_maintenance()
+    }
+
+    pub fn resume_index_maintenance(&self) -> NitriteResult<()> {
+        self.index_operations.resume_maintenance()
+    }
+
+    /// Returns the content hash of the stored documents, which does not depend on the
+    /// order they are stored in or on their metadata fields.
+    pub fn content_hash(&self) -> NitriteResult<u128> {
+        let mut hash = 0u128;
+        for entry in self.nitrite_map.entries()? {
+            let (key, value) = entry?;
+            if let (Value::NitriteId(id), Value::Document(document)) = (key, value) {
+                hash = hash.wrapping_add(collection_hash_entry(&id, &document));
+            }
+        }
+        Ok(hash)
+    }
+
+    pub fn export_index(&self, fields: &Fields) -> NitriteResult<IndexExport> {
+        let content_hash = self.content_hash()?;
+        self.index_operations.export_index(fields, content_hash)
+    }
+
+    pub fn import_index(&self, export: &IndexExport) -> NitriteResult<()> {
+        let content_hash = self.content_hash()?;
+        self.index_operations.import_index(export, content_hash)
+    }
+
     pub fn insert(&self, document: Document) -> NitriteResult<WriteResult> {
         self.write_operations.insert(document)
     }
@@ -140,6 +215,12 @@ impl CollectionOperations {
         self.write_operations.update(filter, update, update_options)
     }
 
+    /// Inserts a document, or updates the stored document with its id unless that would
+    /// not change its content.
+    pub fn insert_if_changed(&self, document: Document) -> NitriteResult<WriteResult> {
+        self.write_operations.insert_if_changed(document)
+    }
+
     /// Updates a document directly by its NitriteId without filter-based lookup.
     /// This is an O(1) operation as it directly accesses the document by its key.
     pub fn update_by_id(
@@ -151,6 +232,11 @@ impl CollectionOperations {
         self.write_operations.update_by_id(id, update, insert_if_absent)
     }
 
+    /// Adds `delta` to a counter field of a document and returns its new value.
+    pub fn increment(&self, id: &NitriteId, field: &str, delta: &Value) -> NitriteResult<Value> {
+        self.write_operations.increment(id, field, delta)
+    }
+
     pub fn remove(&self, filter: Filter, just_once: bool) -> NitriteResult<WriteResult> {
         self.write_operations.remove(filter, just_once)
     }
@@ -159,21 +245,170 @@ impl CollectionOperations {
         self.write_operations.remove_document(document)
     }
 
+    pub fn remove_id_range(&self, start: &NitriteId, end: &NitriteId) -> NitriteResult<u64> {
+        self.write_operations.remove_id_range(start, end)
+    }
+
     pub fn find(
         &self,
         filter: Filter,
         find_options: &FindOptions,
     ) -> NitriteResult<DocumentCursor> {
-        self.read_operations.find(filter, find_options)
+        let cursor = self
+            .read_operations
+            .find(filter, find_options)?
+            .hide_expired_fields();
+        match self.redaction_for(find_options.principal.as_deref()) {
+            Some(policy) => Ok(cursor.redact_with(policy)),
+            None => Ok(cursor),
+        }
+    }
+
+    pub fn count(&self, filter: Filter) -> NitriteResult<u64> {
+        self.read_operations.count(filter)
     }
 
     pub fn get_by_id(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
-        self.read_operations.get_by_id(id)
+        let document = match self.read_operations.get_by_id(id)? {
+            Some(document) => hide_expired_fields(document, &expiry_field())?,
+            None => return Ok(None),
+        };
+        match self.redaction_for(None) {
+            Some(policy) => Ok(Some(policy.redact(document)?)),
+            None => Ok(Some(document)),
+        }
+    }
+
+    pub fn set_redaction_policy(&self, policy: Option<RedactionPolicy>) {
+        self.redaction_policy.write_with(|it| *it = policy);
+    }
+
+    pub fn redaction_policy(&self) -> Option<RedactionPolicy> {
+        self.redaction_policy.read_with(|it| it.clone())
+    }
+
+    fn redaction_for(&self, principal: Option<&str>) -> Option<RedactionPolicy> {
+        self.redaction_policy.read_with(|it| {
+            it.as_ref()
+                .filter(|policy| policy.applies_to(principal))
+                .cloned()
+        })
+    }
+
+    pub fn enable_history(&self, options: HistoryOptions) -> NitriteResult<()> {
+        self.history_operations.enable(options)
+    }
+
+    pub fn disable_history(&self) -> NitriteResult<()> {
+        self.history_operations.disable()
+    }
+
+    pub fn history_options(&self) -> Option<HistoryOptions> {
+        self.history_operations.options()
+    }
+
+    pub fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        self.history_operations.document_history(id)
+    }
+
+    pub fn find_as_of(&self, timestamp: u128, filter: Filter) -> NitriteResult<DocumentCursor> {
+        self.history_operations.find_as_of(timestamp, filter)
+    }
+
+    pub fn options(&self) -> CollectionOptions {
+        self.options_operations.options()
+    }
+
+    pub fn set_options(&self, options: CollectionOptions) -> NitriteResult<()> {
+        self.options_operations.set_options(options)
+    }
+
+    /// Commits the store if the collection requires every write to be durable.
+    pub fn sync_if_required(&self) -> NitriteResult<()> {
+        if self.options_operations.is_sync() {
+            self.nitrite_map.get_store()?.commit()?;
+        }
+        Ok(())
+    }
+
+    pub fn deleted_documents(&self) -> NitriteResult<Vec<Document>> {
+        self.options_operations.deleted_documents()
+    }
+
+    /// Inserts a soft-deleted document back, under its original id.
+    pub fn restore(&self, id: &NitriteId) -> NitriteResult<WriteResult> {
+        match self.options_operations.deleted_document(id)? {
+            Some(document) => {
+                let result = self.write_operations.insert(document)?;
+                self.options_operations.forget_deleted(id)?;
+                Ok(result)
+            }
+            None => Ok(WriteResult::new(vec![])),
+        }
+    }
+
+    pub fn purge_deleted(&self) -> NitriteResult<()> {
+        self.options_operations.purge_deleted()
+    }
+
+    pub fn sweep_expired_fields(&self) -> NitriteResult<u64> {
+        self.write_operations.sweep_expired_fields()
+    }
+
+    /// Runs a batch of writes as one unit.
+    ///
+    /// On a store with atomic write scopes a failed batch is discarded by the store;
+    /// otherwise the operations applied so far are undone, last first, by removing the
+    /// documents they wrote and re-inserting the documents they changed or removed.
+    pub fn bulk_write(
+        &self,
+        operations: Vec<BulkOperation>,
+        options: &BulkWriteOptions,
+    ) -> NitriteResult<BulkWriteResult> {
+        let store = self.nitrite_map.get_store()?;
+        let mut result = BulkWriteResult::default();
+        let mut undo_log = Vec::new();
+
+        let outcome = store.with_atomic(|| {
+            for (index, operation) in operations.into_iter().enumerate() {
+                let name = operation.name();
+                if let Err(e) = self.apply_bulk_operation(operation, &mut result, &mut undo_log) {
+                    log::debug!("Bulk write operation {} ({}) failed: {}", index, name, e);
+                    result.errors.push(BulkWriteError::new(index, e));
+                    if options.is_ordered() {
+                        break;
+                    }
+                }
+            }
+
+            if result.errors.is_empty() {
+                Ok(())
+            } else {
+                Err(NitriteError::new("Bulk write failed", ErrorKind::InvalidOperation))
+            }
+        });
+
+        if let Err(e) = outcome {
+            if !store.supports_atomic() {
+                for undo in undo_log.into_iter().rev() {
+                    self.undo_bulk_operation(undo)?;
+                }
+            }
+            if result.errors.is_empty() {
+                // every operation succeeded, but the store could not commit them
+                return Err(e);
+            }
+            result.discard();
+        }
+        Ok(result)
     }
 
     pub fn dispose(&self) -> NitriteResult<()> {
         self.index_operations.dispose_all_indexes()?;
+        self.history_operations.dispose()?;
+        self.options_operations.dispose()?;
         self.dispose_nitrite_map()?;
+        self.listeners.remove_collection(&self.collection_name);
         self.event_bus.close()?;
         Ok(())
     }
@@ -193,15 +428,139 @@ impl CollectionOperations {
     pub fn close(&self) -> NitriteResult<()> {
         self.index_operations.close()?;
         self.nitrite_map.close()?;
+        self.listeners.remove_collection(&self.collection_name);
         self.event_bus.close()?;
         Ok(())
     }
 
     pub fn clear(&self) -> NitriteResult<()> {
         self.index_operations.clear()?;
+        self.history_operations.clear()?;
+        self.options_operations.clear()?;
         self.nitrite_map.clear()
     }
 
+    fn apply_bulk_operation(
+        &self,
+        operation: BulkOperation,
+        result: &mut BulkWriteResult,
+        undo_log: &mut Vec<BulkUndo>,
+    ) -> NitriteResult<()> {
+        match operation {
+            BulkOperation::InsertOne(document) => {
+                let ids = self.insert(document)?.affected_nitrite_ids().clone();
+                result.inserted_ids.extend(&ids);
+                undo_log.push(BulkUndo { ids, originals: Vec::new() });
+            }
+            BulkOperation::UpdateOne { filter, update, upsert } => {
+                let update_options = UpdateOptions::new(upsert, true);
+                self.bulk_update(filter, &update, &update_options, result, undo_log)?;
+            }
+            BulkOperation::UpdateMany { filter, update, upsert } => {
+                let update_options = UpdateOptions::new(upsert, false);
+                self.bulk_update(filter, &update, &update_options, result, undo_log)?;
+            }
+            BulkOperation::ReplaceOne { filter, mut replacement, upsert } => {
+                let original = self.find(filter, &FindOptions::new())?.next().transpose()?;
+                match original {
+                    Some(mut original) => {
+                        let id = original.id()?;
+                        replacement.put(DOC_ID, Value::NitriteId(id))?;
+                        undo_log.push(BulkUndo { ids: vec![id], originals: vec![original.clone()] });
+                        self.remove_document(&original)?;
+                        self.insert(replacement)?;
+                        result.modified_count += 1;
+                    }
+                    None if upsert => {
+                        let ids = self.insert(replacement)?.affected_nitrite_ids().clone();
+                        result.upserted_ids.extend(&ids);
+                        undo_log.push(BulkUndo { ids, originals: Vec::new() });
+                    }
+                    None => {}
+                }
+            }
+            BulkOperation::DeleteOne(filter) => {
+                let originals = self.find_originals(&filter)?;
+                let written = self.remove(filter, true);
+                result.deleted_count += self.record_bulk_undo(written, originals, undo_log)?.len();
+            }
+            BulkOperation::DeleteMany(filter) => {
+                let originals = self.find_originals(&filter)?;
+                let written = self.remove(filter, false);
+                result.deleted_count += self.record_bulk_undo(written, originals, undo_log)?.len();
+            }
+        }
+        Ok(())
+    }
+
+    fn bulk_update(
+        &self,
+        filter: Filter,
+        update: &Document,
+        update_options: &UpdateOptions,
+        result: &mut BulkWriteResult,
+        undo_log: &mut Vec<BulkUndo>,
+    ) -> NitriteResult<()> {
+        let originals = self.find_originals(&filter)?;
+        let original_ids: Vec<NitriteId> = originals.iter().map(|(id, _)| *id).collect();
+        let written = self.update(filter, update, update_options);
+        for id in self.record_bulk_undo(written, originals, undo_log)? {
+            if original_ids.contains(&id) {
+                result.modified_count += 1;
+            } else {
+                result.upserted_ids.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the documents matching `filter` before a bulk operation changes them.
+    fn find_originals(&self, filter: &Filter) -> NitriteResult<Vec<(NitriteId, Document)>> {
+        let mut originals = Vec::new();
+        for document in self.find(filter.clone(), &FindOptions::new())? {
+            let mut document = document?;
+            originals.push((document.id()?, document));
+        }
+        Ok(originals)
+    }
+
+    /// Records how to undo an update or removal and returns the ids it wrote. A failed
+    /// operation may have written any of the matched documents, so all of them are restored.
+    fn record_bulk_undo(
+        &self,
+        written: NitriteResult<WriteResult>,
+        originals: Vec<(NitriteId, Document)>,
+        undo_log: &mut Vec<BulkUndo>,
+    ) -> NitriteResult<Vec<NitriteId>> {
+        match written {
+            Ok(write_result) => {
+                let ids = write_result.affected_nitrite_ids().clone();
+                let originals = originals
+                    .into_iter()
+                    .filter(|(id, _)| ids.contains(id))
+                    .map(|(_, document)| document)
+                    .collect();
+                undo_log.push(BulkUndo { ids: ids.clone(), originals });
+                Ok(ids)
+            }
+            Err(e) => {
+                let (ids, originals) = originals.into_iter().unzip();
+                undo_log.push(BulkUndo { ids, originals });
+                Err(e)
+            }
+        }
+    }
+
+    fn undo_bulk_operation(&self, undo: BulkUndo) -> NitriteResult<()> {
+        if !undo.ids.is_empty() {
+            self.remove(field(DOC_ID).in_array(undo.ids), false)?;
+        }
+        if !undo.originals.is_empty() {
+            self.insert_batch(undo.originals)?;
+        }
+        Ok(())
+    }
+
     fn dispose_nitrite_map(&self) -> NitriteResult<()> {
         let store = self.nitrite_map.get_store()?;
         let catalog = store.store_catalog()?;
@@ -327,7 +686,7 @@ mod tests {
         let fields = Fields::with_names(vec!["field"]).expect("Fields creation failed");
         let has_index = collection.has_index(&fields).expect("Has index failed");
         assert!(!has_index);
-        let result = collection.create_index(&fields, UNIQUE_INDEX);
+        let result = collection.create_index(&fields, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
         let has_index = collection.has_index(&fields).expect("Has index failed");
         assert!(has_index);
@@ -341,7 +700,7 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap().is_none());
 
-        let result = collection.create_index(&fields, UNIQUE_INDEX);
+        let result = collection.create_index(&fields, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
         assert!(collection.find_index(&fields).unwrap().is_some());
     }
@@ -366,7 +725,7 @@ mod tests {
         assert!(result.unwrap().is_empty());
         
         let fields = Fields::with_names(vec!["field"]).expect("Fields creation failed");
-        let result = collection.create_index(&fields, UNIQUE_INDEX);
+        let result = collection.create_index(&fields, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
         let indexes = collection.list_indexes().expect("List indexes failed");
         assert_eq!(indexes.len(), 1);
@@ -379,7 +738,7 @@ mod tests {
         let result = collection.drop_index(&fields);
         assert!(result.is_ok());
         
-        let result = collection.create_index(&fields, UNIQUE_INDEX);
+        let result = collection.create_index(&fields, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
 
         let has_index = collection.has_index(&fields).expect("Has index failed");
@@ -399,10 +758,10 @@ mod tests {
         let fields1 = Fields::with_names(vec!["field1"]).expect("Fields creation failed");
         let fields2 = Fields::with_names(vec!["field2"]).expect("Fields creation failed");
         
-        let result = collection.create_index(&fields1, UNIQUE_INDEX);
+        let result = collection.create_index(&fields1, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
         
-        let result = collection.create_index(&fields2, NON_UNIQUE_INDEX);
+        let result = collection.create_index(&fields2, &IndexOptions::new(NON_UNIQUE_INDEX));
         assert!(result.is_ok());
         
         let indexes = collection.list_indexes().expect("List indexes failed");
@@ -0,0 +1,111 @@
+use std::any::Any;
+use std::fmt::Display;
+
+use crate::collection::Document;
+use crate::common::{expiry_field, get_current_time_or_zero};
+use crate::errors::NitriteResult;
+use crate::filter::{and, Filter, FilterProvider};
+
+/// Applies a filter to documents as if their expired fields were unset.
+///
+/// Expired fields stay in storage until they are swept, so a document read from the map
+/// may still hold them. Wrapping the full scan filter of a plan makes such fields match as
+/// null; wrapping the index scan filter in `expired_only` mode re-checks the documents an
+/// index returned, which might have been found by the value of an expired field.
+pub(crate) struct FieldExpiryFilter {
+    filter: Filter,
+    expiry_field: String,
+    expired_only: bool,
+}
+
+impl FieldExpiryFilter {
+    /// Wraps the full scan filter of a plan.
+    pub(crate) fn full_scan(filter: Filter) -> Filter {
+        Filter::new(FieldExpiryFilter {
+            filter,
+            expiry_field: expiry_field(),
+            expired_only: false,
+        })
+    }
+
+    /// Wraps the filters of an index scan; documents without expired fields are accepted
+    /// as the index found them.
+    pub(crate) fn index_recheck(mut filters: Vec<Filter>) -> Filter {
+        let filter = if filters.len() == 1 {
+            filters.remove(0)
+        } else {
+            and(filters)
+        };
+        Filter::new(FieldExpiryFilter {
+            filter,
+            expiry_field: expiry_field(),
+            expired_only: true,
+        })
+    }
+}
+
+impl Display for FieldExpiryFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.filter)
+    }
+}
+
+impl FilterProvider for FieldExpiryFilter {
+    fn apply(&self, entry: &Document) -> NitriteResult<bool> {
+        let now = get_current_time_or_zero() as i64;
+        match entry.without_expired_fields(&self.expiry_field, now)? {
+            Some(visible) => self.filter.apply(&visible),
+            None if self.expired_only => Ok(true),
+            None => self.filter.apply(entry),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Removes the expired fields from a document read by a user.
+pub(crate) fn hide_expired_fields(document: Document, expiry_field: &str) -> NitriteResult<Document> {
+    let now = get_current_time_or_zero() as i64;
+    Ok(document
+        .without_expired_fields(expiry_field, now)?
+        .unwrap_or(document))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use crate::filter::field;
+
+    fn expired_city() -> Document {
+        let mut document = doc! { name: "Ada", city: "London" };
+        document.set_field_expiry("city", 1).unwrap();
+        document
+    }
+
+    #[test]
+    fn test_full_scan_sees_expired_field_as_null() {
+        let document = expired_city();
+        assert!(!FieldExpiryFilter::full_scan(field("city").eq("London")).apply(&document).unwrap());
+        assert!(FieldExpiryFilter::full_scan(field("city").eq(())).apply(&document).unwrap());
+        assert!(FieldExpiryFilter::full_scan(field("name").eq("Ada")).apply(&document).unwrap());
+    }
+
+    #[test]
+    fn test_index_recheck() {
+        let recheck = FieldExpiryFilter::index_recheck(vec![field("city").eq("London")]);
+        assert!(!recheck.apply(&expired_city()).unwrap());
+        // without expired fields the index result is trusted as it is
+        assert!(recheck.apply(&doc! { city: "Paris" }).unwrap());
+    }
+
+    #[test]
+    fn test_hide_expired_fields() {
+        let document = hide_expired_fields(expired_city(), &expiry_field()).unwrap();
+        assert_eq!(document.get("city").unwrap(), crate::Value::Null);
+        assert!(!document.contains_key(&expiry_field()));
+        assert_eq!(document.get("name").unwrap(), crate::Value::from("Ada"));
+    }
+}
@@ -1,16 +1,19 @@
 use dashmap::DashMap;
 use smallvec::{SmallVec, ToSmallVec};
 
-use crate::common::{SortableFields, Value};
-use crate::filter::{AndFilter, EqualsFilter, OrFilter};
+use crate::common::{SortableFields, Value, NON_UNIQUE_INDEX, UNIQUE_INDEX};
+use crate::filter::{
+    AndFilter, ComparisonMode, ElementMatchFilter, EqualsFilter, OrFilter, SortingAwareFilter,
+};
 use crate::{
-    collection::{FindOptions, FindPlan},
+    collection::{Document, FindOptions, FindPlan},
     errors::{ErrorKind, NitriteError, NitriteResult},
     filter::{
-        is_all_filter, is_and_filter, is_between_filter, is_equals_filter, is_or_filter,
-        is_text_filter, Filter, FilterProvider, IndexScanFilter,
+        field, is_all_filter, is_and_filter, is_between_filter, is_element_match_filter,
+        is_equals_filter, is_id_range_filter, is_in_filter, is_or_filter, is_text_filter, normalize,
+        Filter, FilterProvider, IndexScanFilter,
     },
-    index::IndexDescriptor,
+    index::{fold_case, IndexDescriptor, IndexHint, IndexStatistics},
     SortOrder, DOC_ID,
 };
 use std::collections::{BTreeMap, HashMap};
@@ -48,12 +51,28 @@ impl FindOptimizer {
     pub fn invalidate_index_entries(&self, affected_index: &IndexDescriptor) {
         self.inner.invalidate_index_entries(affected_index);
     }
+
+    pub fn set_statistics(&self, index_descriptor: IndexDescriptor, statistics: IndexStatistics) {
+        self.inner.set_statistics(index_descriptor, statistics);
+    }
+
+    pub fn remove_statistics(&self, index_descriptor: &IndexDescriptor) {
+        self.inner.remove_statistics(index_descriptor);
+    }
+
+    pub fn statistics(&self, index_descriptor: &IndexDescriptor) -> Option<IndexStatistics> {
+        self.inner.statistics(index_descriptor)
+    }
 }
 
 pub(crate) struct FindOptimizerInner {
     query_cache: DashMap<u64, CachedPlan>,
+    // Indexes chosen for the shapes of prepared filters, whatever values they are bound to
+    shape_cache: DashMap<u64, Vec<IndexDescriptor>>,
     cache_limit: usize,
     last_index_version: AtomicU64,
+    // Cardinality statistics of analyzed indexes, used to rank candidate indexes
+    statistics: DashMap<IndexDescriptor, IndexStatistics>,
 }
 
 struct CachedPlan {
@@ -61,12 +80,24 @@ struct CachedPlan {
     used_index_descriptors: Vec<IndexDescriptor>,
 }
 
+/// Verifies all indexes used by a cached plan still exist and are the same.
+fn indexes_exist(used_indexes: &[IndexDescriptor], index_descriptors: &[IndexDescriptor]) -> bool {
+    used_indexes.iter().all(|used_idx| {
+        index_descriptors.iter().any(|current_idx| {
+            current_idx.index_fields() == used_idx.index_fields()
+                && current_idx.index_type() == used_idx.index_type()
+        })
+    })
+}
+
 impl FindOptimizerInner {
     pub fn new() -> Self {
         FindOptimizerInner {
             query_cache: DashMap::new(),
+            shape_cache: DashMap::new(),
             cache_limit: 100,
             last_index_version: AtomicU64::new(0),
+            statistics: DashMap::new(),
         }
     }
 
@@ -76,29 +107,61 @@ impl FindOptimizerInner {
         find_options: &FindOptions,
         index_descriptors: &[IndexDescriptor],
     ) -> NitriteResult<FindPlan> {
-        let cache_key = self.compute_cache_key(filter, find_options);
+        let hint = self.resolve_hint(filter, find_options, index_descriptors)?;
+
+        // A filter bound from a prepared filter is planned by its shape rather than its
+        // values: the first binding ranks all indexes, later ones only consider the
+        // indexes chosen then, and no plan is cached per value.
+        let shape_key = filter
+            .plan_shape()
+            .map(|shape| self.compute_cache_key(shape, find_options, hint.as_ref()));
+        let cache_key = match shape_key {
+            Some(_) => None,
+            None => Some(self.compute_cache_key(filter.to_string(), find_options, hint.as_ref())),
+        };
 
         // Check if cached plan exists and is valid
-        if let Some(cached) = self.query_cache.get(&cache_key) {
-            let cached_plan = &cached.plan;
-            
-            // Verify all used indexes still exist and are the same
-            let all_indexes_valid = cached.used_index_descriptors.iter().all(|used_idx| {
-                index_descriptors.iter().any(|current_idx| {
-                    current_idx.index_fields() == used_idx.index_fields() &&
-                    current_idx.index_type() == used_idx.index_type()
-                })
-            });
-            
-            if all_indexes_valid {
-                return Ok(cached_plan.clone());
+        if let Some(cache_key) = cache_key {
+            if let Some(cached) = self.query_cache.get(&cache_key) {
+                if indexes_exist(&cached.used_index_descriptors, index_descriptors) {
+                    return Ok(cached.plan.clone());
+                }
             }
             // If invalid, remove from cache and continue to generate new plan
             self.query_cache.remove(&cache_key);
         }
-        
-        // Create new plan
-        let mut find_plan = self.create_find_plan_internal(index_descriptors, filter)?;
+
+        let mut shape_indexes = None;
+        if let Some(shape_key) = shape_key {
+            if let Some(cached) = self.shape_cache.get(&shape_key) {
+                if indexes_exist(&cached, index_descriptors) {
+                    shape_indexes = Some(cached.clone());
+                }
+            }
+            if shape_indexes.is_none() {
+                self.shape_cache.remove(&shape_key);
+            }
+        }
+        let index_descriptors = shape_indexes.as_deref().unwrap_or(index_descriptors);
+
+        // Plan the normalized filter (negations pushed down, bounds merged), which can use
+        // more indexes than the filter as written
+        let normalized = normalize(filter)?;
+
+        // Create new plan, considering only the hinted index if there is a hint
+        let mut find_plan = match &hint {
+            Some(hint) => {
+                let hinted: Vec<IndexDescriptor> = index_descriptors
+                    .iter()
+                    .filter(|descriptor| hint.is_index(descriptor))
+                    .cloned()
+                    .collect();
+                let find_plan = self.create_find_plan_internal(&hinted, &normalized)?;
+                self.check_hint_used(hint, &find_plan, filter)?;
+                find_plan
+            }
+            None => self.create_find_plan_internal(index_descriptors, &normalized)?,
+        };
         self.read_sort_options(find_options, &mut find_plan)?;
         self.read_limit_options(find_options, &mut find_plan)?;
 
@@ -106,6 +169,7 @@ impl FindOptimizerInner {
             find_plan.set_collator_options(options);
         }
         find_plan.set_distinct(find_options.distinct);
+        find_plan.set_tie_break_by_id(find_options.tie_break_by_id);
         
         // Extract all indexes used by this plan
         let mut used_indexes = Vec::new();
@@ -123,12 +187,20 @@ impl FindOptimizerInner {
         }
         
         // Cache the new plan if we have capacity
-        if self.query_cache.len() < self.cache_limit {
-            let cached_plan = CachedPlan {
-                plan: find_plan.clone(),
-                used_index_descriptors: used_indexes,
-            };
-            self.query_cache.insert(cache_key, cached_plan);
+        match (cache_key, shape_key) {
+            (Some(cache_key), _) if self.query_cache.len() < self.cache_limit => {
+                let cached_plan = CachedPlan {
+                    plan: find_plan.clone(),
+                    used_index_descriptors: used_indexes,
+                };
+                self.query_cache.insert(cache_key, cached_plan);
+            }
+            (_, Some(shape_key))
+                if shape_indexes.is_none() && self.shape_cache.len() < self.cache_limit =>
+            {
+                self.shape_cache.insert(shape_key, used_indexes);
+            }
+            _ => {}
         }
         
         Ok(find_plan)
@@ -137,6 +209,7 @@ impl FindOptimizerInner {
     // Call this method whenever indexes change (creation or deletion)
     pub fn invalidate_cache(&self) {
         self.query_cache.clear();
+        self.shape_cache.clear();
         self.last_index_version.fetch_add(1, Ordering::Relaxed);
     }
     
@@ -149,16 +222,128 @@ impl FindOptimizerInner {
                 idx.index_type() == affected_index.index_type()
             )
         });
+        self.shape_cache.retain(|_, used_indexes| {
+            !used_indexes.iter().any(|idx| {
+                idx.index_fields() == affected_index.index_fields()
+                    && idx.index_type() == affected_index.index_type()
+            })
+        });
+    }
+
+    pub fn set_statistics(&self, index_descriptor: IndexDescriptor, statistics: IndexStatistics) {
+        self.statistics.insert(index_descriptor, statistics);
+        // cached plans may have been chosen without (or with outdated) statistics
+        self.invalidate_cache();
+    }
+
+    pub fn remove_statistics(&self, index_descriptor: &IndexDescriptor) {
+        if self.statistics.remove(index_descriptor).is_some() {
+            self.invalidate_cache();
+        }
+    }
+
+    pub fn statistics(&self, index_descriptor: &IndexDescriptor) -> Option<IndexStatistics> {
+        self.statistics.get(index_descriptor).map(|it| it.clone())
+    }
+
+    /// Collects the index hint of a query from the find options and the filter (including
+    /// the operands of a top-level `and`), and validates it against the existing indexes.
+    fn resolve_hint(
+        &self,
+        filter: &Filter,
+        find_options: &FindOptions,
+        index_descriptors: &[IndexDescriptor],
+    ) -> NitriteResult<Option<IndexHint>> {
+        let mut hints = Vec::new();
+        if let Some(hint) = &find_options.hint {
+            hints.push(hint.clone());
+        }
+        // a resumed scan reads the collection in the order of the ids
+        if find_options.resume_after.is_some() {
+            hints.push(IndexHint::CollectionScan);
+        }
+        self.collect_filter_hints(filter, &mut hints)?;
+
+        let hint = match hints.split_first() {
+            None => return Ok(None),
+            Some((first, rest)) => {
+                if let Some(other) = rest.iter().find(|hint| *hint != first) {
+                    log::error!("Conflicting index hints: {} and {}", first, other);
+                    return Err(NitriteError::new(
+                        &format!("Conflicting index hints: {} and {}", first, other),
+                        ErrorKind::FilterError,
+                    ));
+                }
+                first.clone()
+            }
+        };
+
+        if let IndexHint::Index(field_names) = &hint {
+            if !index_descriptors.iter().any(|descriptor| hint.is_index(descriptor)) {
+                log::error!("Index hint refers to a missing index on {:?}", field_names);
+                return Err(NitriteError::new(
+                    &format!("No index found on fields {:?} for the index hint", field_names),
+                    ErrorKind::IndexNotFound,
+                ));
+            }
+        }
+        Ok(Some(hint))
     }
 
-    fn compute_cache_key(&self, filter: &Filter, find_options: &FindOptions) -> u64 {
+    fn collect_filter_hints(&self, filter: &Filter, hints: &mut Vec<IndexHint>) -> NitriteResult<()> {
+        if let Some(hint) = filter.hint() {
+            hints.push(hint.clone());
+        }
+        if is_and_filter(filter) {
+            for operand in filter.logical_filters()? {
+                self.collect_filter_hints(&operand, hints)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if a plan built for an index hint does not use the hinted index, i.e. the
+    /// index cannot serve the filter.
+    fn check_hint_used(&self, hint: &IndexHint, find_plan: &FindPlan, filter: &Filter) -> NitriteResult<()> {
+        fn uses_index(hint: &IndexHint, plan: &FindPlan) -> bool {
+            if plan.by_id_filter().is_some() {
+                return true;
+            }
+            if let Some(descriptor) = plan.index_descriptor() {
+                return hint.is_index(&descriptor);
+            }
+            match plan.sub_plans() {
+                Some(sub_plans) if plan.full_scan_filter().is_none() => {
+                    sub_plans.iter().all(|sub_plan| uses_index(hint, sub_plan))
+                }
+                _ => false,
+            }
+        }
+
+        if matches!(hint, IndexHint::Index(_)) && !uses_index(hint, find_plan) {
+            log::error!("Hinted {} cannot be used for filter {}", hint, filter);
+            return Err(NitriteError::new(
+                &format!("Hinted {} cannot be used for filter {}", hint, filter),
+                ErrorKind::FilterError,
+            ));
+        }
+        Ok(())
+    }
+
+    fn compute_cache_key(
+        &self,
+        filter_key: impl Hash,
+        find_options: &FindOptions,
+        hint: Option<&IndexHint>,
+    ) -> u64 {
         let mut hasher = DefaultHasher::new();
+        hint.hash(&mut hasher);
         
         // Include index version in the key to invalidate all cache when indexes change
         self.last_index_version.load(Ordering::Relaxed).hash(&mut hasher);
         
-        // Hash the filter string representation
-        filter.to_string().hash(&mut hasher);
+        // Hash the filter string representation, or the shape of a prepared filter
+        filter_key.hash(&mut hasher);
         
         // Hash the find options
         if let Some(ref sort_by) = find_options.sort_by {
@@ -168,9 +353,16 @@ impl FindOptimizerInner {
             }
         }
         
+        if let Some(ref distance_sort) = find_options.distance_sort {
+            distance_sort.field_name().hash(&mut hasher);
+            distance_sort.x().to_bits().hash(&mut hasher);
+            distance_sort.y().to_bits().hash(&mut hasher);
+        }
+
         find_options.skip.hash(&mut hasher);
         find_options.limit.hash(&mut hasher);
         find_options.distinct.hash(&mut hasher);
+        find_options.tie_break_by_id.hash(&mut hasher);
         
         hasher.finish()
     }
@@ -235,26 +427,43 @@ impl FindOptimizerInner {
 
         self.plan_id_filter(&mut find_plan, &filters)?;
         // If we have an ID filter, we don't need to do anything else
-        if find_plan.by_id_filter().is_some() {
+        if find_plan
+            .by_id_filter()
+            .is_some_and(|filter| !is_id_range_filter(&filter))
+        {
             return Ok(find_plan);
         }
 
-        // Then process index-only filters
-        self.plan_index_only_filter(
-            &mut find_plan,
-            &mut index_scan_filters,
-            index_descriptors,
-            &filters,
-        )?;
-
-        // If no index-only filters, try regular indexed fields
-        if index_scan_filters.is_empty() {
-            self.plan_index_scan_filter(
+        // An id range scans the collection map, the other filters are checked on its documents
+        if find_plan.by_id_filter().is_none() {
+            // Then process index-only filters
+            self.plan_index_only_filter(
                 &mut find_plan,
                 &mut index_scan_filters,
                 index_descriptors,
                 &filters,
             )?;
+
+            // If no index-only filters, try regular indexed fields
+            if index_scan_filters.is_empty() {
+                // element matches can narrow the scan through an index on the array path,
+                // they stay behind as full scan filters
+                let mut candidates = filters.clone();
+                for filter in &filters {
+                    if let Some(element_match) =
+                        filter.as_any().downcast_ref::<ElementMatchFilter>()
+                    {
+                        candidates.extend(element_match.index_filters()?);
+                    }
+                }
+
+                self.plan_index_scan_filter(
+                    &mut find_plan,
+                    &mut index_scan_filters,
+                    index_descriptors,
+                    &candidates,
+                )?;
+            }
         }
 
         // Finally, handle full scan filters
@@ -265,6 +474,18 @@ impl FindOptimizerInner {
             &filters,
         )?;
 
+        // A case-insensitive index stores folded keys: look up the folded values and
+        // re-check the original values on the documents found.
+        if find_plan
+            .index_descriptor()
+            .is_some_and(|descriptor| descriptor.is_case_insensitive())
+        {
+            for filter in index_scan_filters.iter_mut() {
+                full_scan_filters.push(filter.clone());
+                *filter = self.fold_filter(filter)?;
+            }
+        }
+
         // Set up filter plan with minimal allocations
         if index_scan_filters.len() == 1 {
             // Use iterator to create vec without cloning
@@ -298,11 +519,16 @@ impl FindOptimizerInner {
 
                 if equals_filter.get_field_name()? == DOC_ID {
                     find_plan.set_by_id_filter(filter.clone());
-                    break;
+                    return Ok(());
                 }
             }
         }
 
+        // without an id lookup, an id range is the next best thing
+        if let Some(filter) = filters.iter().find(|filter| is_id_range_filter(filter)) {
+            find_plan.set_by_id_filter(filter.clone());
+        }
+
         Ok(())
     }
 
@@ -392,14 +618,25 @@ impl FindOptimizerInner {
                 let is_terminal = field_idx == last_field_idx;
                 let mut matched = false;
                 for filter in filters {
-                    if !filter.has_field() {
-                        // filter has no field, skip
+                    if !filter.has_field() || is_element_match_filter(filter) {
+                        // filter has no field or is not answered by an index, skip
                         continue;
                     }
 
                     // Using ? operator for error propagation
                     let name = filter.get_field_name()?;
                     if field_name == &name {
+                        if index_descriptor.is_case_insensitive()
+                            && !is_equals_filter(filter)
+                            && !is_in_filter(filter)
+                        {
+                            // a case-insensitive index only answers exact lookups
+                            continue;
+                        }
+                        if index_descriptor.is_sparse() && self.matches_null(filter)? {
+                            // documents without the field are not in a sparse index
+                            continue;
+                        }
                         index_filters.push(filter.clone());
                         matched = true;
                         if !is_terminal {
@@ -420,11 +657,32 @@ impl FindOptimizerInner {
             }
         }
 
+        // With statistics for every candidate, pick the index expected to return the fewest
+        // entries; otherwise fall back to the index that covers the most filters.
+        let analyzed = index_filter_map.len() > 1
+            && index_filter_map
+                .keys()
+                .all(|descriptor| self.statistics.contains_key(descriptor));
+        let best = if analyzed {
+            let mut ranked = Vec::with_capacity(index_filter_map.len());
+            for (descriptor, filters) in index_filter_map {
+                let estimate = self.estimate_index_scan(&descriptor, &filters)?;
+                ranked.push((estimate, descriptor, filters));
+            }
+            ranked
+                .into_iter()
+                .min_by(|(a, _, a_filters), (b, _, b_filters)| {
+                    a.total_cmp(b).then(b_filters.len().cmp(&a_filters.len()))
+                })
+                .map(|(_, descriptor, filters)| (descriptor, filters))
+        } else {
+            index_filter_map
+                .into_iter()
+                .max_by_key(|(_, filters)| filters.len())
+        };
+
         // Find the best matching index descriptor and its filters without extra cloning
-        if let Some((best_descriptor, best_filters)) = index_filter_map
-            .into_iter()
-            .max_by_key(|(_, filters)| filters.len())
-        {
+        if let Some((best_descriptor, best_filters)) = best {
             // Cache the filters by moving them directly instead of cloning again
             index_scan_filters.extend(best_filters);
             find_plan.set_index_descriptor(best_descriptor);
@@ -433,6 +691,74 @@ impl FindOptimizerInner {
         Ok(())
     }
 
+    /// Returns `true` if an equals or in filter looks for null values.
+    fn matches_null(&self, filter: &Filter) -> NitriteResult<bool> {
+        if !is_equals_filter(filter) && !is_in_filter(filter) {
+            return Ok(false);
+        }
+        match filter.get_field_value()? {
+            None | Some(Value::Null) => Ok(true),
+            Some(Value::Array(values)) if is_in_filter(filter) => {
+                Ok(values.iter().any(|value| value.is_null()))
+            }
+            Some(_) => Ok(false),
+        }
+    }
+
+    /// Returns the lookup filter of a case-insensitive index for an equals or in filter.
+    fn fold_filter(&self, filter: &Filter) -> NitriteResult<Filter> {
+        let field_name = filter.get_field_name()?;
+        let value = filter.get_field_value()?.unwrap_or(Value::Null);
+        match fold_case(&value) {
+            Value::Array(values) if is_in_filter(filter) => Ok(field(&field_name).in_array(values)),
+            value => Ok(field(&field_name).eq(value)),
+        }
+    }
+
+    /// Estimates the number of index entries a scan with `filters` visits, from the
+    /// statistics of the index's first field. Each filter on a further field of a compound
+    /// index is assumed to keep a tenth of the entries.
+    fn estimate_index_scan(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        filters: &[Filter],
+    ) -> NitriteResult<f64> {
+        let statistics = match self.statistics.get(index_descriptor) {
+            Some(statistics) => statistics.clone(),
+            None => return Ok(f64::MAX),
+        };
+
+        let field_names = index_descriptor.index_fields().field_names();
+        let first_field = field_names.first().cloned().unwrap_or_default();
+        let mut estimate = statistics.total_entries() as f64;
+        let mut narrowing = 1.0;
+
+        for filter in filters {
+            if filter.get_field_name()? != first_field {
+                narrowing *= 0.1;
+                continue;
+            }
+
+            let value = filter.get_field_value()?.unwrap_or(Value::Null);
+            let matched = if is_equals_filter(filter) {
+                statistics.estimate_equals(&value)
+            } else if is_in_filter(filter) {
+                match &value {
+                    Value::Array(values) => values
+                        .iter()
+                        .map(|value| statistics.estimate_equals(value))
+                        .sum(),
+                    _ => statistics.estimate_range(),
+                }
+            } else {
+                statistics.estimate_range()
+            };
+            estimate = estimate.min(matched);
+        }
+
+        Ok(estimate * narrowing)
+    }
+
     fn plan_full_scan_filter(
         &self,
         find_plan: &mut FindPlan,
@@ -530,20 +856,15 @@ impl FindOptimizerInner {
             find_plan.add_sub_plan(sub_plan);
         }
 
-        let mut clear = false;
-        for plan in find_plan.sub_plans().unwrap() {
-            if plan.index_descriptor().is_none() {
-                clear = true;
-                break;
-            }
-        }
+        // Each branch answered by an index or an id lookup is executed on its own and the
+        // id sets are unioned
+        let clear = find_plan.sub_plans().unwrap_or_default().iter().any(|plan| {
+            plan.index_descriptor().is_none() && plan.by_id_filter().is_none()
+        });
 
         if clear {
-            if let Some(_sub_plans) = find_plan.sub_plans() {
-                // We cannot easily clear sub_plans through Arc, so we use set_full_scan_filter
-                // to establish the fallback search strategy
-                drop(_sub_plans);
-            }
+            // one branch needs a full scan anyway, so scan once with the whole `or`
+            find_plan.clear_sub_plans();
             find_plan.set_full_scan_filter(Filter::new(OrFilter::new(filters.to_vec())));
         }
 
@@ -567,6 +888,37 @@ impl FindOptimizerInner {
             find_plan.set_blocking_sort_order(sort_by.sorting_order());
         }
 
+        if let Some(distance_sort) = &find_options.distance_sort {
+            if find_options.sort_by.is_some() {
+                log::error!("sort_by_distance cannot be combined with sort_by");
+                return Err(NitriteError::new(
+                    "sort_by_distance cannot be combined with sort_by",
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+
+            // The distance order comes from the index itself, so the whole filter must be
+            // answered by the index on the sorted field.
+            let field_name = distance_sort.field_name();
+            let served = find_plan
+                .index_descriptor()
+                .is_some_and(|descriptor| descriptor.index_fields().field_names() == [field_name]);
+            if !served {
+                log::error!(
+                    "sort_by_distance on '{}' requires a filter answered by the index on that field",
+                    field_name
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "sort_by_distance on '{}' requires a filter answered by the index on that field",
+                        field_name
+                    ),
+                    ErrorKind::FilterError,
+                ));
+            }
+            find_plan.set_distance_sort(distance_sort.clone());
+        }
+
         Ok(())
     }
 
@@ -587,10 +939,54 @@ impl FindOptimizerInner {
     }
 }
 
+/// Returns `true` if a find plan and a projection can be answered from the keys of a
+/// compound index alone, without reading a document.
+///
+/// The plan must scan a compound unique or non-unique index that does not fold case, with
+/// nothing left to check on the documents: no post-filter, OR-union, distance sort or
+/// distinct. The projection and the sort may only ask for `_id` and indexed fields.
+pub(crate) fn is_covered_by_index(find_plan: &FindPlan, projection: &Document) -> bool {
+    let Some(index_descriptor) = find_plan.index_descriptor() else {
+        return false;
+    };
+    let index_type = index_descriptor.index_type();
+    if !index_descriptor.is_compound_index()
+        || (index_type != UNIQUE_INDEX && index_type != NON_UNIQUE_INDEX)
+        || index_descriptor.is_case_insensitive()
+    {
+        return false;
+    }
+
+    if find_plan.by_id_filter().is_some()
+        || find_plan.full_scan_filter().is_some()
+        || find_plan.sub_plans().is_some_and(|sub_plans| !sub_plans.is_empty())
+        || find_plan.distance_sort().is_some()
+        || find_plan.distinct()
+        || find_plan
+            .index_scan_filter()
+            .is_none_or(|index_scan_filter| index_scan_filter.filters().is_empty())
+    {
+        return false;
+    }
+
+    let field_names = index_descriptor.index_fields().field_names();
+    let is_covered_field = |field: &str| field == DOC_ID || field_names.iter().any(|name| name == field);
+    let projected = projection.fields();
+    !projected.is_empty()
+        && projected.iter().all(|field| is_covered_field(field))
+        && find_plan
+            .blocking_sort_order()
+            .unwrap_or_default()
+            .iter()
+            .all(|(field, _)| is_covered_field(field))
+}
+
 struct FilterMatcher {
     field_name: Option<String>,
     filter_type_id: std::any::TypeId,
     filter_value: Option<Value>,
+    // `a < 3` and `a >= 3` share type, field and value
+    comparison_mode: Option<ComparisonMode>,
 }
 
 impl FilterMatcher {
@@ -605,11 +1001,16 @@ impl FilterMatcher {
         // In those cases, get_field_value() returns an error, which we convert to None.
         // This allows such filters to still be matched by type_id and field_name.
         let filter_value = filter.get_field_value().ok().flatten();
+        let comparison_mode = filter
+            .as_any()
+            .downcast_ref::<SortingAwareFilter>()
+            .map(|comparison| comparison.comparison_mode());
 
         Ok(Self {
             field_name,
             filter_type_id: filter.as_any().type_id(),
             filter_value,
+            comparison_mode,
         })
     }
 
@@ -632,7 +1033,7 @@ impl FilterMatcher {
             _ => {}
         }
 
-        true
+        self.comparison_mode == other.comparison_mode
     }
 }
 
@@ -640,8 +1041,8 @@ impl FilterMatcher {
 mod tests {
     use super::*;
     use crate::collection::{FindOptions, FindPlan};
-    use crate::common::{Fields, UNIQUE_INDEX};
-    use crate::filter::{and, field, or, Filter};
+    use crate::common::{Fields, NON_UNIQUE_INDEX, UNIQUE_INDEX};
+    use crate::filter::{and, field, not, or, param, Filter, Params, PreparedFilter};
     use crate::index::IndexDescriptor;
 
     fn setup_find_optimizer() -> FindOptimizer {
@@ -815,6 +1216,28 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_read_sort_options_distance_sort_without_index() {
+        let optimizer = setup_find_optimizer();
+        let find_options = FindOptions::new().sort_by_distance("location", (0.0, 0.0));
+        let mut find_plan = FindPlan::new();
+
+        let result = optimizer.inner.read_sort_options(&find_options, &mut find_plan);
+        assert_eq!(*result.unwrap_err().kind(), ErrorKind::FilterError);
+    }
+
+    #[test]
+    fn test_read_sort_options_distance_sort_with_sort_by() {
+        let optimizer = setup_find_optimizer();
+        let find_options = FindOptions::new()
+            .sort_by("name".to_string(), SortOrder::Ascending)
+            .sort_by_distance("location", (0.0, 0.0));
+        let mut find_plan = FindPlan::new();
+
+        let result = optimizer.inner.read_sort_options(&find_options, &mut find_plan);
+        assert_eq!(*result.unwrap_err().kind(), ErrorKind::InvalidOperation);
+    }
+
     #[test]
     fn test_read_limit_options() {
         let optimizer = setup_find_optimizer();
@@ -1046,4 +1469,168 @@ mod tests {
         // The filter should be in full_scan_filter since there's no matching index
         assert!(find_plan.full_scan_filter().is_some(), "full_scan_filter should be set for EqualsFilter without index");
     }
+
+    #[test]
+    fn test_prepared_filter_plans_by_shape() {
+        let optimizer = setup_find_optimizer();
+        let prepared = PreparedFilter::new(field("field").eq(param("value"))).unwrap();
+        let find_options = FindOptions::default();
+        let index_descriptors = vec![create_index_descriptor()];
+
+        for value in ["a", "b", "c"] {
+            let filter = prepared.bind(&Params::new().set("value", value)).unwrap();
+            let find_plan = optimizer
+                .create_find_plan(&filter, &find_options, &index_descriptors)
+                .unwrap();
+            assert!(find_plan.index_descriptor().is_some());
+
+            // the plan scans the index for the bound value, not a cached one
+            let scan_filters = find_plan.index_scan_filter().unwrap().filters();
+            assert_eq!(scan_filters[0].get_field_value().unwrap(), Some(value.into()));
+        }
+        assert!(optimizer.inner.query_cache.is_empty());
+        assert_eq!(optimizer.inner.shape_cache.len(), 1);
+
+        optimizer.invalidate_index_entries(&create_index_descriptor());
+        assert!(optimizer.inner.shape_cache.is_empty());
+    }
+
+    #[test]
+    fn test_normalized_filter_uses_index() {
+        let optimizer = setup_find_optimizer();
+        let find_options = FindOptions::default();
+        let index_descriptors = vec![create_index_descriptor()];
+
+        // not(not(..)) and not(a || b) used to reach the planner as a single opaque filter
+        let filter = not(not(field("field").eq("value")));
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert!(find_plan.index_descriptor().is_some());
+        assert!(find_plan.full_scan_filter().is_none());
+
+        let filter = not(or(vec![field("field").ne("value"), field("other").eq(1)]));
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert!(find_plan.index_descriptor().is_some());
+        let scan_filters = find_plan.index_scan_filter().unwrap().filters();
+        assert_eq!(scan_filters[0].to_string(), "(field == \"value\")");
+        assert!(find_plan.full_scan_filter().is_some());
+
+        let filter = not(field("field").eq("value"));
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert!(find_plan.index_descriptor().is_none());
+    }
+
+    #[test]
+    fn test_element_match_uses_multikey_index() {
+        let optimizer = setup_find_optimizer();
+        let find_options = FindOptions::default();
+        let index_descriptors = vec![IndexDescriptor::new(
+            NON_UNIQUE_INDEX,
+            Fields::with_names(vec!["scores.score"]).unwrap(),
+            "test_collection",
+        )];
+
+        let filter = field("scores").elem_match(
+            field("score").gt(0.9).and(field("product").eq("x")),
+        );
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert!(find_plan.index_descriptor().is_some());
+        let scan_filters = find_plan.index_scan_filter().unwrap().filters();
+        assert_eq!(scan_filters.len(), 1);
+        assert_eq!(scan_filters[0].get_field_name().unwrap(), "scores.score");
+        // the element match is still checked on the documents found
+        assert!(is_element_match_filter(&find_plan.full_scan_filter().unwrap()));
+
+        // no index on the array path
+        let filter = field("scores").elem_match(field("product").eq("x"));
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert!(find_plan.index_descriptor().is_none());
+    }
+
+    #[test]
+    fn test_element_match_is_never_an_index_scan_filter() {
+        let optimizer = setup_find_optimizer();
+        let find_options = FindOptions::default();
+        let index_descriptors = vec![create_index_descriptor()];
+
+        // an index on the array field itself cannot answer an element condition on a sub-field
+        let filter = field("field").elem_match(field("score").gt(1));
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert!(find_plan.index_descriptor().is_none());
+        assert!(find_plan.full_scan_filter().is_some());
+    }
+
+    #[test]
+    fn test_range_bound_on_compound_prefix_is_rechecked() {
+        let optimizer = setup_find_optimizer();
+        let find_options = FindOptions::default();
+        let index_descriptors = vec![IndexDescriptor::new(
+            NON_UNIQUE_INDEX,
+            Fields::with_names(vec!["a", "b"]).unwrap(),
+            "test_collection",
+        )];
+
+        // a compound prefix field takes one bound, the other one must stay a full scan
+        // filter even though both bounds have the same value
+        let filter = field("a").gte(3).and(field("a").lt(3));
+        let find_plan = optimizer
+            .create_find_plan(&filter, &find_options, &index_descriptors)
+            .unwrap();
+        assert_eq!(find_plan.index_scan_filter().unwrap().filters().len(), 1);
+        assert!(find_plan.full_scan_filter().is_some());
+    }
+
+    #[test]
+    fn test_is_covered_by_index() {
+        let optimizer = setup_find_optimizer();
+        let index_descriptors = vec![IndexDescriptor::new(
+            NON_UNIQUE_INDEX,
+            Fields::with_names(vec!["a", "b"]).unwrap(),
+            "test_collection",
+        )];
+        let plan = |filter: Filter, find_options: FindOptions| {
+            optimizer
+                .create_find_plan(&filter, &find_options, &index_descriptors)
+                .unwrap()
+        };
+        let projection = crate::doc! { a: (Value::Null), b: (Value::Null) };
+
+        let find_plan = plan(field("a").eq(1).and(field("b").gt(2)), FindOptions::default());
+        assert!(is_covered_by_index(&find_plan, &projection));
+
+        let sorted = FindOptions::default().sort_by("b".to_string(), SortOrder::Descending);
+        let find_plan = plan(field("a").eq(1), sorted);
+        assert!(is_covered_by_index(&find_plan, &projection));
+
+        // a field outside the index needs the documents
+        let find_plan = plan(field("a").eq(1), FindOptions::default());
+        assert!(!is_covered_by_index(&find_plan, &crate::doc! { a: (Value::Null), c: (Value::Null) }));
+        let sorted = FindOptions::default().sort_by("c".to_string(), SortOrder::Ascending);
+        assert!(!is_covered_by_index(&plan(field("a").eq(1), sorted), &projection));
+        let find_plan = plan(field("a").eq(1).and(field("c").eq(2)), FindOptions::default());
+        assert!(!is_covered_by_index(&find_plan, &projection));
+
+        let find_plan = plan(field("a").eq(1), FindOptions::default().distinct());
+        assert!(!is_covered_by_index(&find_plan, &projection));
+        let find_plan = plan(field("a").eq(1).or(field("a").eq(2)), FindOptions::default());
+        assert!(!is_covered_by_index(&find_plan, &projection));
+
+        // single field indexes are not covering
+        let index_descriptors = vec![create_index_descriptor()];
+        let find_plan = optimizer
+            .create_find_plan(&create_filter(), &FindOptions::default(), &index_descriptors)
+            .unwrap();
+        assert!(!is_covered_by_index(&find_plan, &crate::doc! { field: (Value::Null) }));
+    }
 }
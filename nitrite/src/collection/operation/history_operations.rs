@@ -0,0 +1,552 @@
+use crate::{
+    collection::{Document, DocumentVersion, HistoryOptions, NitriteId},
+    common::{modified_field, DocumentCursor, ProcessorChain, ProcessorProvider},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::Filter,
+    get_current_time_or_zero,
+    store::{NitriteMap, NitriteMapProvider, NitriteStoreProvider},
+    AttributeAware, Value, HISTORY_MAX_AGE, HISTORY_MAX_VERSIONS, HISTORY_PREFIX,
+    INTERNAL_NAME_SEPARATOR,
+};
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+const VERSION_DOCUMENT: &str = "document";
+const VERSION_REVISION: &str = "revision";
+const VERSION_MODIFIED: &str = "modified";
+
+/// Records and queries the revision history of a collection.
+///
+/// The history lives in a side map (`$nitrite_history|<collection>`) with one entry per
+/// recorded version, keyed `[id, sequence]`. The sequence of a document only grows, even
+/// when a re-insert, like a restore, starts its revision over, so the versions of a
+/// document sort oldest first and old ones are pruned with a range removal instead of
+/// rewriting the whole history. Versions are stored in their written
+/// (processed) form and run through the processor chain when read.
+/// The history options are persisted as collection attributes, so history stays enabled
+/// across restarts.
+#[derive(Clone)]
+pub(crate) struct HistoryOperations {
+    inner: Arc<HistoryOperationsInner>,
+}
+
+impl HistoryOperations {
+    pub fn new(
+        collection_name: &str,
+        nitrite_map: NitriteMap,
+        processor_chain: ProcessorChain,
+    ) -> NitriteResult<Self> {
+        let options = match nitrite_map.attributes()? {
+            Some(attributes) => options_from_attributes(
+                attributes.get(HISTORY_MAX_VERSIONS),
+                attributes.get(HISTORY_MAX_AGE),
+            ),
+            None => None,
+        };
+
+        Ok(HistoryOperations {
+            inner: Arc::new(HistoryOperationsInner {
+                history_map_name: format!(
+                    "{}{}{}",
+                    HISTORY_PREFIX, INTERNAL_NAME_SEPARATOR, collection_name
+                ),
+                nitrite_map,
+                processor_chain,
+                options: RwLock::new(options),
+            }),
+        })
+    }
+
+    /// Returns `true` if writes are currently recorded.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.inner.options.read().is_some()
+    }
+
+    pub fn options(&self) -> Option<HistoryOptions> {
+        *self.inner.options.read()
+    }
+
+    pub fn enable(&self, options: HistoryOptions) -> NitriteResult<()> {
+        self.inner.enable(options)
+    }
+
+    pub fn disable(&self) -> NitriteResult<()> {
+        self.inner.disable()
+    }
+
+    /// Records a write. `previous` is the stored form of the document before the write
+    /// (`None` for inserts) and `current` the stored form after it.
+    pub fn record_write(
+        &self,
+        id: &NitriteId,
+        previous: Option<&Document>,
+        current: &Document,
+    ) -> NitriteResult<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let revision = current.revision()?;
+        let version = version_entry(Some(current), revision, modified_time(current));
+        self.inner.append(id, previous, version)
+    }
+
+    /// Records a removal. `previous` is the stored form of the removed document.
+    pub fn record_removal(
+        &self,
+        id: &NitriteId,
+        previous: &Document,
+        revision: i32,
+        removed_at: u128,
+    ) -> NitriteResult<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        self.inner.append(id, Some(previous), version_entry(None, revision, removed_at))
+    }
+
+    pub fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        self.inner.document_history(id)
+    }
+
+    pub fn find_as_of(&self, timestamp: u128, filter: Filter) -> NitriteResult<DocumentCursor> {
+        self.inner.find_as_of(timestamp, filter)
+    }
+
+    pub fn clear(&self) -> NitriteResult<()> {
+        self.inner.clear()
+    }
+
+    pub fn dispose(&self) -> NitriteResult<()> {
+        self.inner.dispose()
+    }
+}
+
+struct HistoryOperationsInner {
+    history_map_name: String,
+    nitrite_map: NitriteMap,
+    processor_chain: ProcessorChain,
+    options: RwLock<Option<HistoryOptions>>,
+}
+
+impl HistoryOperationsInner {
+    fn enable(&self, options: HistoryOptions) -> NitriteResult<()> {
+        let mut attributes = self.nitrite_map.attributes()?.unwrap_or_default();
+        match options.max_versions {
+            Some(max_versions) => attributes.put(HISTORY_MAX_VERSIONS, Value::U64(max_versions as u64)),
+            None => attributes.put(HISTORY_MAX_VERSIONS, Value::Null),
+        }
+        match options.max_age {
+            Some(max_age) => attributes.put(HISTORY_MAX_AGE, Value::U128(max_age.as_millis())),
+            None => attributes.put(HISTORY_MAX_AGE, Value::Null),
+        }
+        self.nitrite_map.set_attributes(attributes)?;
+        *self.options.write() = Some(options);
+        Ok(())
+    }
+
+    fn disable(&self) -> NitriteResult<()> {
+        if let Some(mut attributes) = self.nitrite_map.attributes()? {
+            let removed_versions = attributes.remove(HISTORY_MAX_VERSIONS).is_some();
+            let removed_age = attributes.remove(HISTORY_MAX_AGE).is_some();
+            if removed_versions || removed_age {
+                self.nitrite_map.set_attributes(attributes)?;
+            }
+        }
+        *self.options.write() = None;
+        self.dispose()
+    }
+
+    fn history_map(&self) -> NitriteResult<NitriteMap> {
+        self.nitrite_map.get_store()?.open_map(&self.history_map_name)
+    }
+
+    fn has_history_map(&self) -> NitriteResult<bool> {
+        self.nitrite_map.get_store()?.has_map(&self.history_map_name)
+    }
+
+    fn append(
+        &self,
+        id: &NitriteId,
+        previous: Option<&Document>,
+        version: Value,
+    ) -> NitriteResult<()> {
+        let Some(options) = *self.options.read() else {
+            return Ok(());
+        };
+
+        let history_map = self.history_map()?;
+        let mut sequence = match last_version_key(&history_map, id)? {
+            Some(key) => version_sequence(&key) + 1,
+            None => 1,
+        };
+        // the first recorded write also keeps the state it replaced, so the document can
+        // be read as of any time after history was enabled
+        if sequence == 1 {
+            if let Some(previous) = previous {
+                let previous_revision = previous.revision()?;
+                history_map.put(
+                    version_key(id, sequence),
+                    version_entry(Some(previous), previous_revision, modified_time(previous)),
+                )?;
+                sequence += 1;
+            }
+        }
+        history_map.put(version_key(id, sequence), version)?;
+        prune(&history_map, id, &options, get_current_time_or_zero())
+    }
+
+    fn versions(&self, history_map: &NitriteMap, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        let end = end_key(id);
+        let mut versions = Vec::new();
+        let mut next = first_version_key(history_map, id)?;
+        while let Some(key) = next {
+            if let Some(version) = history_map.get(&key)? {
+                versions.push(self.to_document_version(version)?);
+            }
+            next = history_map.higher_key(&key)?.filter(|key| *key < end);
+        }
+        Ok(versions)
+    }
+
+    fn to_document_version(&self, version: Value) -> NitriteResult<DocumentVersion> {
+        let Value::Document(version) = version else {
+            log::error!("Invalid history version, expected a document");
+            return Err(NitriteError::new(
+                "Invalid history version, expected a document",
+                ErrorKind::InvalidOperation,
+            ));
+        };
+
+        let document = match version.get(VERSION_DOCUMENT)? {
+            Value::Document(doc) => Some(self.processor_chain.process_after_read(doc)?),
+            _ => None,
+        };
+        let revision = match version.get(VERSION_REVISION)? {
+            Value::I32(revision) => revision,
+            _ => 0,
+        };
+        Ok(DocumentVersion {
+            document,
+            revision,
+            modified: time_value(&version.get(VERSION_MODIFIED)?),
+        })
+    }
+
+    fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        if !self.has_history_map()? {
+            return Ok(Vec::new());
+        }
+        self.versions(&self.history_map()?, id)
+    }
+
+    fn find_as_of(&self, timestamp: u128, filter: Filter) -> NitriteResult<DocumentCursor> {
+        let mut results = Vec::new();
+        let mut tracked = HashSet::new();
+
+        if self.has_history_map()? {
+            // the versions of a document are adjacent and oldest first, the last one not
+            // newer than `timestamp` is the state of the document at that time
+            let mut as_of: Option<Value> = None;
+            for entry in self.history_map()?.entries()? {
+                let (key, version) = entry?;
+                let Some(id) = version_id(&key) else {
+                    continue;
+                };
+                if tracked.insert(id) {
+                    if let Some(version) = as_of.take() {
+                        self.collect_as_of(version, &filter, &mut results)?;
+                    }
+                }
+                if version_time(&version) <= timestamp {
+                    as_of = Some(version);
+                }
+            }
+            if let Some(version) = as_of {
+                self.collect_as_of(version, &filter, &mut results)?;
+            }
+        }
+
+        // documents without history have not been written since history was enabled,
+        // so their current state is also their state at `timestamp` if it already existed
+        for entry in self.nitrite_map.entries()? {
+            let (key, value) = entry?;
+            let (Value::NitriteId(id), Value::Document(doc)) = (key, value) else {
+                continue;
+            };
+            if tracked.contains(&id) || modified_time(&doc) > timestamp {
+                continue;
+            }
+            let doc = self.processor_chain.process_after_read(doc)?;
+            if filter.apply(&doc)? {
+                results.push(Ok(doc));
+            }
+        }
+
+        Ok(DocumentCursor::new(
+            Box::new(results.into_iter()),
+            ProcessorChain::new(),
+        ))
+    }
+
+    fn collect_as_of(
+        &self,
+        version: Value,
+        filter: &Filter,
+        results: &mut Vec<NitriteResult<Document>>,
+    ) -> NitriteResult<()> {
+        if let Some(doc) = self.to_document_version(version)?.document {
+            if filter.apply(&doc)? {
+                results.push(Ok(doc));
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&self) -> NitriteResult<()> {
+        if self.has_history_map()? {
+            self.history_map()?.clear()?;
+        }
+        Ok(())
+    }
+
+    fn dispose(&self) -> NitriteResult<()> {
+        if self.has_history_map()? {
+            self.history_map()?.dispose()?;
+        }
+        Ok(())
+    }
+}
+
+fn options_from_attributes(
+    max_versions: Option<&Value>,
+    max_age: Option<&Value>,
+) -> Option<HistoryOptions> {
+    if max_versions.is_none() && max_age.is_none() {
+        return None;
+    }
+
+    let mut options = HistoryOptions::new();
+    if let Some(Value::U64(max_versions)) = max_versions {
+        options = options.max_versions(*max_versions as usize);
+    }
+    if let Some(Value::U128(max_age)) = max_age {
+        options = options.max_age(Duration::from_millis(*max_age as u64));
+    }
+    Some(options)
+}
+
+fn version_entry(document: Option<&Document>, revision: i32, modified: u128) -> Value {
+    let mut version = Document::new();
+    // the keys are fixed and valid, so these puts cannot fail
+    let _ = version.put(
+        VERSION_DOCUMENT,
+        document.cloned().map(Value::Document).unwrap_or(Value::Null),
+    );
+    let _ = version.put(VERSION_REVISION, Value::I32(revision));
+    let _ = version.put(VERSION_MODIFIED, Value::U128(modified));
+    Value::Document(version)
+}
+
+fn modified_time(document: &Document) -> u128 {
+    document
+        .get(&modified_field())
+        .map(|value| time_value(&value))
+        .unwrap_or(0)
+}
+
+fn time_value(value: &Value) -> u128 {
+    match value {
+        Value::U128(time) => *time,
+        Value::I64(time) => (*time).max(0) as u128,
+        Value::U64(time) => *time as u128,
+        _ => 0,
+    }
+}
+
+fn version_time(version: &Value) -> u128 {
+    match version {
+        Value::Document(version) => version
+            .get(VERSION_MODIFIED)
+            .map(|value| time_value(&value))
+            .unwrap_or(0),
+        _ => 0,
+    }
+}
+
+fn version_key(id: &NitriteId, sequence: u64) -> Value {
+    Value::Array(vec![Value::NitriteId(*id), Value::U64(sequence)])
+}
+
+fn version_sequence(key: &Value) -> u64 {
+    // stores may normalize unsigned keys, the Fjall store reads the sequence back as `I64`
+    match key {
+        Value::Array(parts) => match parts.get(1) {
+            Some(Value::U64(sequence)) => *sequence,
+            Some(Value::I64(sequence)) => (*sequence).max(0) as u64,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// The lowest key of the versions of `id`, every version key sorts after it.
+fn start_key(id: &NitriteId) -> Value {
+    Value::Array(vec![Value::NitriteId(*id)])
+}
+
+/// The key the versions of `id` end before.
+fn end_key(id: &NitriteId) -> Value {
+    start_key(&id.next_id())
+}
+
+fn version_id(key: &Value) -> Option<NitriteId> {
+    match key {
+        Value::Array(parts) => match parts.first() {
+            Some(Value::NitriteId(id)) => Some(*id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn first_version_key(history_map: &NitriteMap, id: &NitriteId) -> NitriteResult<Option<Value>> {
+    let end = end_key(id);
+    Ok(history_map
+        .ceiling_key(&start_key(id))?
+        .filter(|key| *key < end))
+}
+
+fn last_version_key(history_map: &NitriteMap, id: &NitriteId) -> NitriteResult<Option<Value>> {
+    let start = start_key(id);
+    Ok(history_map
+        .lower_key(&end_key(id))?
+        .filter(|key| *key >= start))
+}
+
+/// Drops the oldest versions of `id` beyond the configured bounds, always keeping the
+/// newest one.
+fn prune(
+    history_map: &NitriteMap,
+    id: &NitriteId,
+    options: &HistoryOptions,
+    now: u128,
+) -> NitriteResult<()> {
+    let start = start_key(id);
+    if let Some(max_versions) = options.max_versions {
+        // walk back from the newest version to the oldest one that is kept
+        let mut kept = last_version_key(history_map, id)?;
+        for _ in 1..max_versions {
+            kept = match kept {
+                Some(key) => history_map.lower_key(&key)?.filter(|key| *key >= start),
+                None => break,
+            };
+        }
+        if let Some(oldest_kept) = kept {
+            history_map.remove_range(&start, &oldest_kept)?;
+        }
+    }
+    if let Some(max_age) = options.max_age {
+        let cutoff = now.saturating_sub(max_age.as_millis());
+        let end = end_key(id);
+        let mut first_kept = None;
+        let mut next = first_version_key(history_map, id)?;
+        while let Some(key) = next {
+            next = history_map.higher_key(&key)?.filter(|key| *key < end);
+            let expired = match (&next, history_map.get(&key)?) {
+                (Some(_), Some(version)) => version_time(&version) < cutoff,
+                _ => false,
+            };
+            if !expired {
+                first_kept = Some(key);
+                break;
+            }
+        }
+        if let Some(first_kept) = first_kept {
+            history_map.remove_range(&start, &first_kept)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory::{InMemoryMap, InMemoryStore, InMemoryStoreConfig};
+    use crate::store::NitriteStore;
+
+    fn history_at(id: &NitriteId, times: &[u128]) -> NitriteMap {
+        let store = NitriteStore::new(InMemoryStore::new(InMemoryStoreConfig::new()));
+        let history_map = NitriteMap::new(InMemoryMap::new("history", store));
+        for (sequence, time) in times.iter().enumerate() {
+            let sequence = sequence as u64 + 1;
+            history_map
+                .put(
+                    version_key(id, sequence),
+                    version_entry(Some(&Document::new()), sequence as i32, *time),
+                )
+                .unwrap();
+        }
+        history_map
+    }
+
+    fn times(history_map: &NitriteMap) -> Vec<u128> {
+        history_map
+            .values()
+            .unwrap()
+            .map(|version| version_time(&version.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_prune_by_count() {
+        let id = NitriteId::new();
+        let history_map = history_at(&id, &[1, 2, 3, 4]);
+        prune(&history_map, &id, &HistoryOptions::new().max_versions(2), 10).unwrap();
+        assert_eq!(times(&history_map), vec![3, 4]);
+
+        prune(&history_map, &id, &HistoryOptions::new().max_versions(5), 10).unwrap();
+        assert_eq!(times(&history_map), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_prune_by_age_keeps_newest() {
+        let id = NitriteId::new();
+        let history_map = history_at(&id, &[1_000, 2_000, 9_000]);
+        let options = HistoryOptions::new().max_age(Duration::from_millis(5_000));
+        prune(&history_map, &id, &options, 10_000).unwrap();
+        assert_eq!(times(&history_map), vec![9_000]);
+
+        let history_map = history_at(&id, &[1_000]);
+        let options = HistoryOptions::new().max_age(Duration::from_millis(1));
+        prune(&history_map, &id, &options, 10_000).unwrap();
+        assert_eq!(times(&history_map), vec![1_000]);
+    }
+
+    #[test]
+    fn test_prune_keeps_other_documents() {
+        let id = NitriteId::new();
+        let other = id.next_id();
+        let history_map = history_at(&id, &[1, 2, 3]);
+        history_map
+            .put(version_key(&other, 1), version_entry(None, 1, 1))
+            .unwrap();
+
+        prune(&history_map, &id, &HistoryOptions::new().max_versions(1), 10).unwrap();
+        assert_eq!(times(&history_map), vec![3, 1]);
+        assert_eq!(
+            first_version_key(&history_map, &other).unwrap(),
+            Some(version_key(&other, 1))
+        );
+    }
+
+    #[test]
+    fn test_options_from_attributes() {
+        assert!(options_from_attributes(None, None).is_none());
+        let options = options_from_attributes(Some(&Value::U64(5)), Some(&Value::Null)).unwrap();
+        assert_eq!(options.get_max_versions(), Some(5));
+        assert!(options.get_max_age().is_none());
+    }
+}
@@ -1,11 +1,11 @@
 use crate::common::{ReadExecutor, WriteExecutor};
 use crate::{
     atomic, derive_index_map_name, derive_index_meta_map_name,
-    errors::{NitriteError, NitriteResult},
-    index::{index_meta::IndexMeta, IndexDescriptor, NitriteIndexerProvider},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    index::{index_meta::IndexMeta, IndexDescriptor, IndexOptions, IndexStatistics, NitriteIndexerProvider},
     nitrite_config::NitriteConfig,
     store::{NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider},
-    Atomic, Convertible, Fields,
+    Atomic, Convertible, Fields, NON_UNIQUE_INDEX, UNIQUE_INDEX,
 };
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -139,16 +139,16 @@ impl IndexManager {
     ///
     /// # Arguments
     /// * `fields` - The field(s) to index
-    /// * `index_type` - The type of index (e.g., "UNIQUE", "NON_UNIQUE")
+    /// * `index_options` - The type (e.g., "UNIQUE", "NON_UNIQUE") and options of the index
     ///
     /// # Returns
     /// The created IndexDescriptor
     pub fn create_index_descriptor(
         &self,
         fields: &Fields,
-        index_type: &str,
+        index_options: &IndexOptions,
     ) -> NitriteResult<IndexDescriptor> {
-        self.inner.create_index_descriptor(fields, index_type)
+        self.inner.create_index_descriptor(fields, index_options)
     }
 
     /// Drops an index descriptor (removes metadata but not the actual index).
@@ -172,13 +172,58 @@ impl IndexManager {
         self.inner.begin_indexing(fields)
     }
 
-    /// Ends an indexing operation, marking the index as clean.
+    /// Ends an indexing operation, marking the index as clean and enabled.
     ///
     /// # Arguments
     /// * `fields` - The field(s) that were indexed
     pub fn end_indexing(&self, fields: &Fields) -> NitriteResult<()> {
         self.inner.end_indexing(fields)
     }
+
+    /// Stores (or with `None`, discards) the statistics of an index.
+    ///
+    /// # Arguments
+    /// * `fields` - The field(s) of the index
+    /// * `statistics` - The statistics gathered by `analyze()`
+    pub fn set_index_statistics(
+        &self,
+        fields: &Fields,
+        statistics: Option<IndexStatistics>,
+    ) -> NitriteResult<()> {
+        self.inner.set_index_statistics(fields, statistics)
+    }
+
+    /// Lists the persisted statistics of all analyzed indexes.
+    pub fn list_index_statistics(&self) -> NitriteResult<Vec<(IndexDescriptor, IndexStatistics)>> {
+        self.inner.list_index_statistics()
+    }
+
+    /// Lists the indexes marked as dirty, whose build or rebuild has not completed.
+    pub fn list_dirty_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.inner.list_dirty_indexes()
+    }
+
+    /// Lists the indexes disabled until their next rebuild.
+    pub fn list_disabled_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.inner.list_disabled_indexes()
+    }
+}
+
+/// Reads the index descriptors of a collection from its index metadata without
+/// opening the collection.
+///
+/// # Arguments
+/// * `collection_name` - The name of the collection
+/// * `store` - The store holding the index metadata
+pub(crate) fn read_index_descriptors(
+    collection_name: &str,
+    store: &NitriteStore,
+) -> NitriteResult<Vec<IndexDescriptor>> {
+    let index_meta_map_name = derive_index_meta_map_name(collection_name);
+    if !store.has_map(&index_meta_map_name)? {
+        return Ok(Vec::new());
+    }
+    IndexManagerInner::list_index_descriptors(store.open_map(&index_meta_map_name)?)
 }
 
 /// The internal implementation of IndexManager.
@@ -366,8 +411,42 @@ impl IndexManagerInner {
     pub fn create_index_descriptor(
         &self,
         fields: &Fields,
-        index_type: &str,
+        index_options: &IndexOptions,
     ) -> NitriteResult<IndexDescriptor> {
+        let index_type = &index_options.index_type();
+        if index_type != UNIQUE_INDEX && index_type != NON_UNIQUE_INDEX {
+            let option = if index_options.is_case_insensitive() {
+                Some("Case-insensitive")
+            } else if index_options.is_sparse() {
+                Some("Sparse")
+            } else {
+                None
+            };
+            if let Some(option) = option {
+                log::error!("{} option is not supported by {} indexes", option, index_type);
+                return Err(NitriteError::new(
+                    &format!("{} option is not supported by {} indexes", option, index_type),
+                    ErrorKind::IndexingError,
+                ));
+            }
+        }
+
+        if index_options.is_compressed()
+            && (index_type != NON_UNIQUE_INDEX || fields.field_names().len() > 1)
+        {
+            log::error!(
+                "Compressed option is only supported by single-field {} indexes",
+                NON_UNIQUE_INDEX
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Compressed option is only supported by single-field {} indexes",
+                    NON_UNIQUE_INDEX
+                ),
+                ErrorKind::IndexingError,
+            ));
+        }
+
         // validate index
         let indexer = self.nitrite_config.find_indexer(index_type)
             .map_err(|e| NitriteError::new(&format!("Failed to find indexer for type '{}': {}", index_type, e), e.kind().clone()))?;
@@ -375,7 +454,10 @@ impl IndexManagerInner {
             .map_err(|e| NitriteError::new(&format!("Index validation failed for fields '{}': {}", fields, e), e.kind().clone()))?;
 
         let index_descriptor =
-            IndexDescriptor::new(index_type, fields.clone(), &self.collection_name);
+            IndexDescriptor::new(index_type, fields.clone(), &self.collection_name)
+                .with_case_insensitive(index_options.is_case_insensitive())
+                .with_sparse(index_options.is_sparse())
+                .with_compressed(index_options.is_compressed());
         let index_map_name = derive_index_map_name(&index_descriptor);
         let index_meta = IndexMeta::new(index_descriptor.clone(), index_map_name);
         self.index_meta_map
@@ -412,7 +494,61 @@ impl IndexManagerInner {
     }
 
     pub fn end_indexing(&self, fields: &Fields) -> NitriteResult<()> {
-        self.mark_dirty(fields, false)
+        let fields = fields.to_value()?;
+        if let Some(value) = self.index_meta_map.get(&fields)? {
+            // a completed build also re-enables an index disabled by a consistency check
+            let mut index_meta = IndexMeta::from_value(&value)?;
+            index_meta.set_dirty(false);
+            index_meta.set_disabled(false);
+            self.index_meta_map.put(fields, index_meta.to_value()?)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_index_statistics(
+        &self,
+        fields: &Fields,
+        statistics: Option<IndexStatistics>,
+    ) -> NitriteResult<()> {
+        let fields = fields.to_value()?;
+        if let Some(value) = self.index_meta_map.get(&fields)? {
+            let mut index_meta = IndexMeta::from_value(&value)?;
+            index_meta.set_statistics(statistics);
+            self.index_meta_map.put(fields, index_meta.to_value()?)?;
+        }
+        Ok(())
+    }
+
+    pub fn list_index_statistics(&self) -> NitriteResult<Vec<(IndexDescriptor, IndexStatistics)>> {
+        let mut statistics = Vec::new();
+        for entry in self.index_meta_map.entries()? {
+            let (_, value) = entry?;
+            let index_meta = IndexMeta::from_value(&value)?;
+            if let Some(stats) = index_meta.statistics() {
+                statistics.push((index_meta.index_descriptor(), stats));
+            }
+        }
+        Ok(statistics)
+    }
+
+    pub fn list_dirty_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.list_indexes_where(IndexMeta::is_dirty)
+    }
+
+    pub fn list_disabled_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.list_indexes_where(IndexMeta::is_disabled)
+    }
+
+    fn list_indexes_where(&self, predicate: fn(&IndexMeta) -> bool) -> NitriteResult<Vec<IndexDescriptor>> {
+        let mut indexes = Vec::new();
+        for entry in self.index_meta_map.entries()? {
+            let (_, value) = entry?;
+            let index_meta = IndexMeta::from_value(&value)?;
+            if predicate(&index_meta) {
+                indexes.push(index_meta.index_descriptor());
+            }
+        }
+        Ok(indexes)
     }
 
     fn ensure_index_descriptor_cache(&self) -> NitriteResult<()> {
@@ -573,7 +709,7 @@ mod tests {
     fn test_create_index_descriptor() {
         let manager = setup_index_manager();
         let fields = create_fields();
-        let result = manager.create_index_descriptor(&fields, UNIQUE_INDEX);
+        let result = manager.create_index_descriptor(&fields, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
     }
 
@@ -7,12 +7,15 @@ use crate::{
     collection::{CollectionEventInfo, CollectionEventListener, CollectionEvents},
     errors::{ErrorKind, NitriteError, NitriteResult},
     get_document_values,
-    index::{IndexDescriptor, NitriteIndexerProvider},
+    derive_index_map_name,
+    index::{IndexDescriptor, IndexExport, IndexOptions, IndexStatistics, NitriteIndexerProvider},
     nitrite_config::NitriteConfig,
-    store::{NitriteMap, NitriteMapProvider},
-    Atomic, Convertible, Fields, NitriteEventBus, Value,
+    store::{NitriteMap, NitriteMapProvider, NitriteStoreProvider},
+    Atomic, Convertible, Fields, NitriteEventBus, Value, NON_UNIQUE_INDEX, UNIQUE_INDEX,
 };
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
+use once_cell::sync::OnceCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 
@@ -82,13 +85,13 @@ impl IndexOperations {
     ///
     /// # Arguments
     /// * `fields` - The field(s) to create the index on
-    /// * `index_type` - The type of index (e.g., "UNIQUE", "NON_UNIQUE")
+    /// * `index_options` - The type (e.g., "UNIQUE", "NON_UNIQUE") and options of the index
     ///
     /// # Errors
-    /// Returns an error if the index already exists with a different type,
+    /// Returns an error if the index already exists with a different type or options,
     /// or if index creation fails.
-    pub fn create_index(&self, fields: &Fields, index_type: &str) -> NitriteResult<()> {
-        self.inner.create_index(fields, index_type)
+    pub fn create_index(&self, fields: &Fields, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.inner.create_index(fields, index_options)
     }
 
     /// Builds an index by processing all existing documents.
@@ -198,6 +201,92 @@ impl IndexOperations {
     pub fn should_rebuild_index(&self, fields: &Fields) -> NitriteResult<bool> {
         self.inner.should_rebuild_index(fields)
     }
+
+    /// Gathers cardinality statistics of the unique and non-unique indexes.
+    ///
+    /// The statistics are persisted with the index metadata and used by the find
+    /// optimizer to choose between several indexes matching a query.
+    pub fn analyze(&self) -> NitriteResult<()> {
+        self.inner.analyze()
+    }
+
+    /// Gets the statistics gathered by the last `analyze()` for an index.
+    ///
+    /// # Arguments
+    /// * `fields` - The field(s) of the index
+    ///
+    /// # Returns
+    /// The statistics, or None if the index does not exist or has not been analyzed
+    pub fn index_statistics(&self, fields: &Fields) -> NitriteResult<Option<IndexStatistics>> {
+        self.inner.index_statistics(fields)
+    }
+
+    /// Lists the indexes queries may use.
+    ///
+    /// Stale indexes are left out: those whose maintenance is deferred and those
+    /// whose rebuild has not succeeded yet, including after a restart.
+    pub fn queryable_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.inner.queryable_indexes()
+    }
+
+    /// Checks if an index is stale and must not be used by queries.
+    pub fn is_stale(&self, fields: &Fields) -> bool {
+        self.inner.stale_indexes.contains(fields)
+    }
+
+    /// Stops maintaining the existing indexes on writes until
+    /// `resume_maintenance()` is called.
+    ///
+    /// The indexes are marked dirty in their metadata first, so an index stays
+    /// dirty and is rebuilt on a later write if its rebuild fails or never runs.
+    ///
+    /// # Errors
+    /// Returns an error if maintenance is already deferred or an index is being built.
+    pub fn defer_maintenance(&self) -> NitriteResult<()> {
+        self.inner.defer_maintenance()
+    }
+
+    /// Checks if writes skip the index on the specified fields.
+    pub fn is_maintenance_deferred(&self, fields: &Fields) -> bool {
+        self.inner.maintenance_deferred.load(Ordering::Acquire) && self.is_stale(fields)
+    }
+
+    /// Checks if the index on the specified fields was disabled by a consistency
+    /// check. A disabled index is neither queried nor maintained until it is rebuilt.
+    pub fn is_disabled(&self, fields: &Fields) -> NitriteResult<bool> {
+        self.inner.load_metadata()?;
+        Ok(self.inner.disabled_indexes.contains(fields))
+    }
+
+    /// Maintains the indexes on writes again and rebuilds every stale index from
+    /// the documents in sorted order.
+    ///
+    /// # Errors
+    /// Returns the first rebuild error; the indexes that failed stay dirty.
+    pub fn resume_maintenance(&self) -> NitriteResult<()> {
+        self.inner.resume_maintenance()
+    }
+
+    /// Exports the data of the index on the specified fields, stamped with the
+    /// content hash of the collection.
+    ///
+    /// # Errors
+    /// Returns an error if there is no such index, it is not up to date or its
+    /// indexer cannot export it.
+    pub fn export_index(&self, fields: &Fields, content_hash: u128) -> NitriteResult<IndexExport> {
+        self.inner.export_index(fields, content_hash)
+    }
+
+    /// Replaces the data of an index with exported data, creating the index if it
+    /// does not exist yet.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the export was taken from other documents than
+    /// `content_hash` describes; an index created by the import is removed again on
+    /// any error.
+    pub fn import_index(&self, export: &IndexExport, content_hash: u128) -> NitriteResult<()> {
+        self.inner.import_index(export, content_hash)
+    }
 }
 
 /// The internal implementation of IndexOperations.
@@ -211,6 +300,11 @@ struct IndexOperationInner {
     nitrite_map: NitriteMap,
     event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
     index_build_tracker: DashMap<Fields, bool>,
+    stale_indexes: DashSet<Fields>,
+    disabled_indexes: DashSet<Fields>,
+    /// Set once the index metadata was read, on the first use of an index
+    metadata: OnceCell<()>,
+    maintenance_deferred: AtomicBool,
     index_manager: Atomic<IndexManager>,
     find_optimizer: FindOptimizer,
     indexer_cache: DashMap<String, NitriteIndexer>,
@@ -234,6 +328,10 @@ impl IndexOperationInner {
             nitrite_map,
             event_bus,
             index_build_tracker,
+            stale_indexes: DashSet::new(),
+            disabled_indexes: DashSet::new(),
+            metadata: OnceCell::new(),
+            maintenance_deferred: AtomicBool::new(false),
             index_manager: atomic(index_manager),
             find_optimizer,
             indexer_cache,
@@ -244,7 +342,38 @@ impl IndexOperationInner {
         self.index_manager.write_with(|manager| manager.close())
     }
 
-    pub fn create_index(&self, fields: &Fields, index_type: &str) -> NitriteResult<()> {
+    /// Reads the statistics and dirty marks of the indexes the first time an index
+    /// is used rather than when the collection is opened, so that opening a
+    /// collection with many indexes stays cheap.
+    fn load_metadata(&self) -> NitriteResult<()> {
+        self.metadata
+            .get_or_try_init(|| {
+                self.index_manager.read_with(|manager| {
+                    for (index_descriptor, statistics) in manager.list_index_statistics()? {
+                        self.find_optimizer.set_statistics(index_descriptor, statistics);
+                    }
+
+                    // an index left dirty by an interrupted build or a failed rebuild
+                    // is incomplete until it is rebuilt
+                    for index_descriptor in manager.list_dirty_indexes()? {
+                        self.stale_indexes.insert(index_descriptor.index_fields());
+                    }
+
+                    // a disabled index stays out of queries and writes until rebuilt
+                    for index_descriptor in manager.list_disabled_indexes()? {
+                        let fields = index_descriptor.index_fields();
+                        self.stale_indexes.insert(fields.clone());
+                        self.disabled_indexes.insert(fields);
+                    }
+                    Ok(())
+                })
+            })
+            .map(|_| ())
+    }
+
+    pub fn create_index(&self, fields: &Fields, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.load_metadata()?;
+        let index_type = index_options.index_type();
         let index_descriptor = self
             .index_manager
             .read_with(|manager| manager.find_exact_index(fields))?;
@@ -264,6 +393,33 @@ impl IndexOperationInner {
                     ),
                     ErrorKind::IndexingError,
                 ))
+            } else if index_descriptor.is_case_insensitive() != index_options.is_case_insensitive() {
+                log::error!(
+                    "Index already exists on fields {:?} with different case sensitivity",
+                    fields.field_names()
+                );
+                Err(NitriteError::new(
+                    "Index already exists with different case sensitivity",
+                    ErrorKind::IndexingError,
+                ))
+            } else if index_descriptor.is_sparse() != index_options.is_sparse() {
+                log::error!(
+                    "Index already exists on fields {:?} with different sparse option",
+                    fields.field_names()
+                );
+                Err(NitriteError::new(
+                    "Index already exists with different sparse option",
+                    ErrorKind::IndexingError,
+                ))
+            } else if index_descriptor.is_compressed() != index_options.is_compressed() {
+                log::error!(
+                    "Index already exists on fields {:?} with different compressed option",
+                    fields.field_names()
+                );
+                Err(NitriteError::new(
+                    "Index already exists with different compressed option",
+                    ErrorKind::IndexingError,
+                ))
             } else {
                 // if index is of same type, return
                 Ok(())
@@ -272,7 +428,7 @@ impl IndexOperationInner {
             // if index not there, create new index
             let index_descriptor = self
                 .index_manager
-                .read_with(|manager| manager.create_index_descriptor(fields, index_type))?;
+                .read_with(|manager| manager.create_index_descriptor(fields, index_options))?;
             self.build_index(&index_descriptor, false)?;
 
             self.find_optimizer.invalidate_cache();
@@ -290,7 +446,7 @@ impl IndexOperationInner {
 
         if !build_flag {
             self.set_build_flag(&fields, true);
-            self.build_index_internal(index_descriptor, rebuild)?;
+            self.build_index_internal(index_descriptor, rebuild, false)?;
             Ok(())
         } else {
             log::error!(
@@ -308,6 +464,12 @@ impl IndexOperationInner {
     }
 
     pub fn drop_index(&self, fields: &Fields) -> NitriteResult<()> {
+        self.remove_index(fields, false)
+    }
+
+    /// Removes an index with its entries and metadata. A destroyed index also
+    /// loses the resources its indexer keeps outside the store.
+    fn remove_index(&self, fields: &Fields, destroy: bool) -> NitriteResult<()> {
         let build_flag = self.get_build_flag(fields);
         if build_flag {
             log::error!("Index is building for fields: {:?}", fields.field_names());
@@ -321,14 +483,21 @@ impl IndexOperationInner {
         if let Some(index_descriptor) = index_descriptor {
             self.find_optimizer
                 .invalidate_index_entries(&index_descriptor);
+            self.find_optimizer.remove_statistics(&index_descriptor);
 
             let index_type = index_descriptor.index_type();
             let indexer = self.get_indexer(&index_type)?;
-            indexer.drop_index(&index_descriptor, &self.nitrite_config)?;
+            if destroy {
+                indexer.destroy_index(&index_descriptor, &self.nitrite_config)?;
+            } else {
+                indexer.drop_index(&index_descriptor, &self.nitrite_config)?;
+            }
 
             self.index_manager
                 .read_with(|manager| manager.drop_index_descriptor(fields))?;
             self.index_build_tracker.remove(fields);
+            self.stale_indexes.remove(fields);
+            self.disabled_indexes.remove(fields);
             Ok(())
         } else {
             Ok(())
@@ -356,6 +525,8 @@ impl IndexOperationInner {
         // the index maps, so we just need to clear our tracking state and invalidate caches.
         // We do NOT call clear_all() here because the indexes are already dropped.
         self.index_build_tracker.clear();
+        self.stale_indexes.clear();
+        self.disabled_indexes.clear();
 
         // Invalidate all cache entries
         self.find_optimizer.invalidate_cache();
@@ -380,12 +551,14 @@ impl IndexOperationInner {
         let indexes = self.list_indexes()?;
         for x in &indexes {
             let fields = x.index_fields();
-            self.drop_index(&fields)?;
+            self.remove_index(&fields, true)?;
         }
 
         self.index_manager
             .read_with(|manager| manager.dispose_index_meta())?;
         self.index_build_tracker.clear();
+        self.stale_indexes.clear();
+        self.disabled_indexes.clear();
         self.index_manager.read_with(|manager| manager.close())?;
 
         // Invalidate all cache entries
@@ -395,6 +568,7 @@ impl IndexOperationInner {
     }
 
     pub fn clear(&self) -> NitriteResult<()> {
+        self.load_metadata()?;
         for val in self.index_build_tracker.iter() {
             if *val.value() {
                 log::error!("Index is building, cannot clear indexes");
@@ -405,13 +579,18 @@ impl IndexOperationInner {
             }
         }
 
-        self.index_manager
-            .read_with(|manager| manager.clear_all())?;
+        for index_descriptor in self.list_indexes()? {
+            let indexer = self.get_indexer(&index_descriptor.index_type())?;
+            indexer.clear_index(&index_descriptor, &self.nitrite_config)?;
+            self.discard_statistics(&index_descriptor)?;
+        }
         self.index_build_tracker.clear();
+        self.find_optimizer.invalidate_cache();
         Ok(())
     }
 
     pub fn is_indexing(&self, fields: &Fields) -> NitriteResult<bool> {
+        self.load_metadata()?;
         let has_index = self
             .index_manager
             .read_with(|manager| manager.has_index_descriptor(fields))?;
@@ -419,30 +598,290 @@ impl IndexOperationInner {
     }
 
     pub fn has_index_entry(&self, fields: &Fields) -> NitriteResult<bool> {
+        self.load_metadata()?;
         self
             .index_manager
             .read_with(|manager| manager.has_index_descriptor(fields))
     }
 
     pub fn find_index_descriptor(&self, fields: &Fields) -> NitriteResult<Option<IndexDescriptor>> {
+        self.load_metadata()?;
         self
             .index_manager
             .read_with(|manager| manager.find_exact_index(fields))
     }
 
     pub fn list_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.load_metadata()?;
         self
             .index_manager
             .read_with(|manager| manager.get_index_descriptors())
     }
 
     pub fn should_rebuild_index(&self, fields: &Fields) -> NitriteResult<bool> {
+        self.load_metadata()?;
         Ok(self
             .index_manager
             .read_with(|manager| manager.is_dirty_index(fields))?
             && !self.get_build_flag(fields))
     }
 
+    pub fn analyze(&self) -> NitriteResult<()> {
+        let store = self.nitrite_config.nitrite_store()?;
+        for index_descriptor in self.list_indexes()? {
+            // plugin indexes keep their own layout the statistics cannot describe
+            let index_type = index_descriptor.index_type();
+            if index_type != UNIQUE_INDEX && index_type != NON_UNIQUE_INDEX {
+                continue;
+            }
+
+            let fields = index_descriptor.index_fields();
+            if self.get_build_flag(&fields) {
+                continue;
+            }
+
+            let index_map = store.open_map(&derive_index_map_name(&index_descriptor))?;
+            let statistics = IndexStatistics::analyze(&index_map)?;
+            self.index_manager.read_with(|manager| {
+                manager.set_index_statistics(&fields, Some(statistics.clone()))
+            })?;
+            self.find_optimizer
+                .set_statistics(index_descriptor, statistics);
+        }
+        Ok(())
+    }
+
+    pub fn index_statistics(&self, fields: &Fields) -> NitriteResult<Option<IndexStatistics>> {
+        Ok(self
+            .find_index_descriptor(fields)?
+            .and_then(|index_descriptor| self.find_optimizer.statistics(&index_descriptor)))
+    }
+
+    pub fn queryable_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        let mut indexes = self.list_indexes()?;
+        if !self.stale_indexes.is_empty() {
+            indexes.retain(|index_descriptor| {
+                !self.stale_indexes.contains(&index_descriptor.index_fields())
+            });
+        }
+        Ok(indexes)
+    }
+
+    pub fn defer_maintenance(&self) -> NitriteResult<()> {
+        if self.index_build_tracker.iter().any(|val| *val.value()) {
+            log::error!("Index is building, cannot defer index maintenance");
+            return Err(NitriteError::new(
+                "Index is building, cannot defer index maintenance",
+                ErrorKind::IndexingError,
+            ));
+        }
+
+        if self.maintenance_deferred.swap(true, Ordering::AcqRel) {
+            log::error!(
+                "Index maintenance is already deferred on collection {}",
+                self.collection_name
+            );
+            return Err(NitriteError::new(
+                "Index maintenance is already deferred",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        let result = self.list_indexes().and_then(|indexes| {
+            for index_descriptor in indexes {
+                self.index_manager
+                    .read_with(|manager| manager.mark_index_dirty(&index_descriptor))?;
+                self.stale_indexes.insert(index_descriptor.index_fields());
+            }
+            Ok(())
+        });
+        self.find_optimizer.invalidate_cache();
+
+        if result.is_err() {
+            // indexes already marked dirty are rebuilt on their next write
+            self.maintenance_deferred.store(false, Ordering::Release);
+        }
+        result
+    }
+
+    pub fn resume_maintenance(&self) -> NitriteResult<()> {
+        self.maintenance_deferred.store(false, Ordering::Release);
+
+        let mut result = Ok(());
+        for index_descriptor in self.list_indexes()? {
+            let fields = index_descriptor.index_fields();
+            if !self.stale_indexes.contains(&fields) || self.get_build_flag(&fields) {
+                continue;
+            }
+
+            self.set_build_flag(&fields, true);
+            if let Err(e) = self.build_index_internal(&index_descriptor, true, true) {
+                log::error!(
+                    "Failed to rebuild index on fields {:?}: {}",
+                    fields.field_names(),
+                    e
+                );
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn export_index(&self, fields: &Fields, content_hash: u128) -> NitriteResult<IndexExport> {
+        let index_descriptor = match self.find_index_descriptor(fields)? {
+            Some(index_descriptor) => index_descriptor,
+            None => {
+                log::error!("No index found on fields {:?}", fields.field_names());
+                return Err(NitriteError::new(
+                    &format!("No index found on fields {:?}", fields.field_names()),
+                    ErrorKind::IndexNotFound,
+                ));
+            }
+        };
+
+        // a stale index would not answer for the documents the hash describes
+        if self.get_build_flag(fields) || self.stale_indexes.contains(fields) {
+            log::error!(
+                "Index on fields {:?} is not up to date and cannot be exported",
+                fields.field_names()
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Index on fields {:?} is not up to date and cannot be exported",
+                    fields.field_names()
+                ),
+                ErrorKind::IndexingError,
+            ));
+        }
+
+        let index_type = index_descriptor.index_type();
+        let indexer = self.get_indexer(&index_type)?;
+        match indexer.export_index(&index_descriptor, &self.nitrite_config)? {
+            Some(data) => {
+                Ok(IndexExport::new(
+                    self.collection_name.clone(),
+                    fields.field_names(),
+                    &index_descriptor.index_options(),
+                    content_hash,
+                    data,
+                ))
+            }
+            None => {
+                log::error!("Index type {} cannot be exported", index_type);
+                Err(NitriteError::new(
+                    &format!("Index type {} cannot be exported", index_type),
+                    ErrorKind::InvalidOperation,
+                ))
+            }
+        }
+    }
+
+    pub fn import_index(&self, export: &IndexExport, content_hash: u128) -> NitriteResult<()> {
+        if export.content_hash() != content_hash {
+            log::error!(
+                "Index data of collection {} does not match the documents of collection {}",
+                export.collection_name(),
+                self.collection_name
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Index data of collection {} does not match the documents of collection {}",
+                    export.collection_name(),
+                    self.collection_name
+                ),
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        self.load_metadata()?;
+        let field_names = export.field_names().iter().map(String::as_str).collect();
+        let fields = Fields::with_names(field_names)?;
+        if self.get_build_flag(&fields) {
+            log::error!("Index is building for fields: {:?}", fields.field_names());
+            return Err(NitriteError::new(
+                &format!("Index is building for fields: {:?}", fields.field_names()),
+                ErrorKind::IndexingError,
+            ));
+        }
+
+        let existing = self
+            .index_manager
+            .read_with(|manager| manager.find_exact_index(&fields))?;
+        let index_descriptor = match &existing {
+            Some(index_descriptor) if index_descriptor.index_type() != export.index_type() => {
+                log::error!(
+                    "Index already exists on fields {:?} with different type: {}",
+                    fields.field_names(),
+                    index_descriptor.index_type()
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "Index already exists with different type: {}",
+                        index_descriptor.index_type()
+                    ),
+                    ErrorKind::IndexingError,
+                ));
+            }
+            Some(index_descriptor) => index_descriptor.clone(),
+            None => self.index_manager.read_with(|manager| {
+                manager.create_index_descriptor(&fields, &export.index_options())
+            })?,
+        };
+
+        self.set_build_flag(&fields, true);
+        let result = (|| {
+            self.index_manager
+                .read_with(|manager| manager.begin_indexing(&fields))?;
+            self.find_optimizer
+                .invalidate_index_entries(&index_descriptor);
+            self.discard_statistics(&index_descriptor)?;
+
+            let indexer = self.get_indexer(&index_descriptor.index_type())?;
+            indexer.import_index(&index_descriptor, &self.nitrite_config, export.data())?;
+
+            self.index_manager
+                .read_with(|manager| manager.end_indexing(&fields))
+        })();
+        self.set_build_flag(&fields, false);
+
+        match result {
+            Ok(()) => {
+                self.disabled_indexes.remove(&fields);
+                self.stale_indexes.remove(&fields);
+                self.find_optimizer.invalidate_cache();
+                self.alert(CollectionEvents::IndexEnd, &fields)
+            }
+            Err(e) => {
+                if existing.is_none() {
+                    if let Err(remove_error) = self.remove_index(&fields, false) {
+                        log::warn!(
+                            "Failed to remove index on fields {:?} after a failed import: {}",
+                            fields.field_names(),
+                            remove_error
+                        );
+                    }
+                } else {
+                    // the old entries are gone, so the index stays dirty until rebuilt
+                    self.stale_indexes.insert(fields);
+                    self.find_optimizer.invalidate_cache();
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn discard_statistics(&self, index_descriptor: &IndexDescriptor) -> NitriteResult<()> {
+        if self.find_optimizer.statistics(index_descriptor).is_some() {
+            self.find_optimizer.remove_statistics(index_descriptor);
+            let fields = index_descriptor.index_fields();
+            self.index_manager
+                .read_with(|manager| manager.set_index_statistics(&fields, None))?;
+        }
+        Ok(())
+    }
+
     fn get_indexer(&self, index_type: &str) -> NitriteResult<NitriteIndexer> {
         // Use entry API for single-lookup caching pattern
         use dashmap::mapref::entry::Entry;
@@ -472,10 +911,14 @@ impl IndexOperationInner {
         self.index_build_tracker.insert(fields.clone(), flag);
     }
 
+    /// Writes the entries of every document into the index. A `sorted` build
+    /// writes them in key order, which suits bulk loads at the cost of holding
+    /// all entries in memory.
     fn build_index_internal(
         &self,
         index_descriptor: &IndexDescriptor,
         rebuild: bool,
+        sorted: bool,
     ) -> NitriteResult<()> {
         let fields = index_descriptor.index_fields();
 
@@ -520,21 +963,32 @@ impl IndexOperationInner {
 
             if rebuild {
                 indexer.drop_index(index_descriptor, &self.nitrite_config)?;
+                self.discard_statistics(index_descriptor)?;
             }
 
             // Process documents
+            let mut sorted_entries = Vec::new();
             for entry in self.nitrite_map.entries()? {
                 let (_, value) = entry?;
                 if let Value::Document(mut doc) = value {
                     let field_values = get_document_values(&mut doc, &fields)?;
-                    indexer.write_index_entry(
-                        &field_values,
-                        index_descriptor,
-                        &self.nitrite_config,
-                    )?;
+                    if sorted {
+                        sorted_entries.push(field_values);
+                    } else {
+                        indexer.write_index_entry(
+                            &field_values,
+                            index_descriptor,
+                            &self.nitrite_config,
+                        )?;
+                    }
                 }
             }
 
+            sorted_entries.sort_by_cached_key(|field_values| field_values.values());
+            for field_values in &sorted_entries {
+                indexer.write_index_entry(field_values, index_descriptor, &self.nitrite_config)?;
+            }
+
             self.index_manager
                 .read_with(|manager| manager.end_indexing(&fields))?;
 
@@ -545,6 +999,10 @@ impl IndexOperationInner {
             Ok(_) => {
                 guard.complete(); // Mark as complete so flag is not cleared in Drop
                 self.set_build_flag(&fields, false);
+                self.disabled_indexes.remove(&fields);
+                if self.stale_indexes.remove(&fields).is_some() {
+                    self.find_optimizer.invalidate_cache();
+                }
                 self.alert(CollectionEvents::IndexEnd, &fields)?;
                 Ok(())
             }
@@ -655,7 +1113,7 @@ mod tests {
     fn test_create_index() {
         let index_operations = setup_index_operations();
         let fields = create_fields();
-        let result = index_operations.create_index(&fields, UNIQUE_INDEX);
+        let result = index_operations.create_index(&fields, &IndexOptions::new(UNIQUE_INDEX));
         assert!(result.is_ok());
     }
 
@@ -675,7 +1133,7 @@ mod tests {
         assert!(result.is_ok());
 
         index_operations
-            .create_index(&fields, UNIQUE_INDEX)
+            .create_index(&fields, &IndexOptions::new(UNIQUE_INDEX))
             .expect("Failed to create index");
         let result = index_operations.drop_index(&fields);
         assert!(result.is_ok());
@@ -691,10 +1149,10 @@ mod tests {
         let fields2 = Fields::with_names(vec!["field1", "field2"]).unwrap();
 
         index_operations
-            .create_index(&fields1, UNIQUE_INDEX)
+            .create_index(&fields1, &IndexOptions::new(UNIQUE_INDEX))
             .expect("Failed to create index");
         index_operations
-            .create_index(&fields2, UNIQUE_INDEX)
+            .create_index(&fields2, &IndexOptions::new(UNIQUE_INDEX))
             .expect("Failed to create index");
         let result = index_operations.drop_all_indexes();
         assert!(result.is_ok());
@@ -751,6 +1209,80 @@ mod tests {
         assert!(!result.unwrap());
     }
 
+    #[test]
+    fn test_defer_and_resume_maintenance() {
+        let index_operations = setup_index_operations();
+        let fields = create_fields();
+        index_operations
+            .create_index(&fields, &IndexOptions::new(UNIQUE_INDEX))
+            .unwrap();
+
+        index_operations.defer_maintenance().unwrap();
+        assert!(index_operations.is_maintenance_deferred(&fields));
+        assert!(index_operations.should_rebuild_index(&fields).unwrap());
+        assert!(index_operations.queryable_indexes().unwrap().is_empty());
+        assert!(index_operations.defer_maintenance().is_err());
+
+        index_operations.resume_maintenance().unwrap();
+        assert!(!index_operations.is_maintenance_deferred(&fields));
+        assert!(!index_operations.should_rebuild_index(&fields).unwrap());
+        assert_eq!(index_operations.queryable_indexes().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_export_and_import_index() {
+        let index_operations = setup_index_operations();
+        let fields = create_fields();
+        let err = index_operations.export_index(&fields, 0).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::IndexNotFound);
+
+        // the unique indexer keeps its entries in the store and cannot export them
+        index_operations
+            .create_index(&fields, &IndexOptions::new(UNIQUE_INDEX))
+            .unwrap();
+        let err = index_operations.export_index(&fields, 0).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidOperation);
+
+        let other = Fields::with_names(vec!["other"]).unwrap();
+        let export = IndexExport::new(
+            "test_collection".to_string(),
+            other.field_names(),
+            &IndexOptions::new(NON_UNIQUE_INDEX),
+            7,
+            vec![],
+        );
+        let err = index_operations.import_index(&export, 8).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ValidationError);
+
+        // an index created by a failed import is removed again
+        let err = index_operations.import_index(&export, 7).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidOperation);
+        assert!(index_operations.find_index_descriptor(&other).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dirty_index_loaded_as_stale_on_first_use() {
+        let index_operations = setup_index_operations();
+        let fields = create_fields();
+        index_operations
+            .create_index(&fields, &IndexOptions::new(UNIQUE_INDEX))
+            .unwrap();
+        index_operations.defer_maintenance().unwrap();
+
+        // a second instance over the same store sees the index left dirty
+        let reopened = IndexOperations::new(
+            index_operations.collection_name(),
+            index_operations.nitrite_config(),
+            index_operations.nitrite_map(),
+            FindOptimizer::new(),
+            NitriteEventBus::new(),
+        )
+        .unwrap();
+        assert!(!reopened.is_stale(&fields));
+        assert!(reopened.queryable_indexes().unwrap().is_empty());
+        assert!(reopened.is_stale(&fields));
+    }
+
     // Performance optimization tests for indexer caching
 
     #[test]
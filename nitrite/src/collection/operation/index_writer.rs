@@ -6,6 +6,7 @@ use crate::{
     index::{IndexDescriptor, NitriteIndexer, NitriteIndexerProvider},
     is_affected_by_update,
     nitrite_config::NitriteConfig,
+    profiler::{timed, Phase},
 };
 use std::sync::Arc;
 use std::collections::HashMap;
@@ -51,7 +52,7 @@ impl DocumentIndexWriter {
     /// # Errors
     /// Returns an error if any index update fails
     pub fn write_index_entry(&self, document: &mut Document) -> NitriteResult<()> {
-        self.inner.write_index_entry(document)
+        timed(Phase::IndexMaintenance, || self.inner.write_index_entry(document))
     }
 
     /// Removes index entries for the given document.
@@ -65,18 +66,18 @@ impl DocumentIndexWriter {
     /// # Errors
     /// Returns an error if any index update fails
     pub fn remove_index_entry(&self, document: &mut Document) -> NitriteResult<()> {
-        self.inner.remove_index_entry(document)
+        timed(Phase::IndexMaintenance, || self.inner.remove_index_entry(document))
     }
 
     /// Updates index entries for a modified document.
     ///
-    /// This method is called when a document is updated. It only updates indexes affected
-    /// by the changed fields, improving performance for partial updates.
+    /// This method is called when a document is updated. It compares the indexed fields
+    /// of both versions and only rewrites the entries of indexes whose values changed,
+    /// so updating a non-indexed field touches no index at all.
     ///
     /// # Arguments
     /// * `old_document` - The document before the update
     /// * `new_document` - The document after the update
-    /// * `updated_fields` - The fields that were modified
     ///
     /// # Errors
     /// Returns an error if any index update fails
@@ -84,9 +85,10 @@ impl DocumentIndexWriter {
         &self,
         old_document: &mut Document,
         new_document: &mut Document,
-        updated_fields: &Document,
     ) -> NitriteResult<()> {
-        self.inner.update_index_entry(old_document, new_document, updated_fields)
+        timed(Phase::IndexMaintenance, || {
+            self.inner.update_index_entry(old_document, new_document)
+        })
     }
 }
 
@@ -150,13 +152,12 @@ impl DocumentIndexWriterInner {
         &self,
         old_document: &mut Document,
         new_document: &mut Document,
-        updated_fields: &Document,
     ) -> NitriteResult<()> {
         let index_entries = self.index_operation.list_indexes()?;
         for index_descriptor in index_entries {
             let fields = index_descriptor.index_fields();
 
-            if is_affected_by_update(&fields, updated_fields) {
+            if is_affected_by_update(&fields, old_document, new_document)? {
                 let index_type = index_descriptor.index_type();
                 let mut indexer = self.nitrite_config.find_indexer(&index_type)?;
 
@@ -174,6 +175,11 @@ impl DocumentIndexWriterInner {
         indexer: &mut NitriteIndexer,
     ) -> NitriteResult<()> {
         let fields = index_descriptor.index_fields();
+        if self.index_operation.is_maintenance_deferred(&fields)
+            || self.index_operation.is_disabled(&fields)?
+        {
+            return Ok(());
+        }
         let field_values = get_document_values(document, &fields)?;
 
         if self.index_operation.should_rebuild_index(&fields)? {
@@ -191,6 +197,11 @@ impl DocumentIndexWriterInner {
         indexer: &mut NitriteIndexer,
     ) -> NitriteResult<()> {
         let fields = index_descriptor.index_fields();
+        if self.index_operation.is_maintenance_deferred(&fields)
+            || self.index_operation.is_disabled(&fields)?
+        {
+            return Ok(());
+        }
         let field_values = get_document_values(document, &fields)?;
 
         if self.index_operation.should_rebuild_index(&fields)? {
@@ -275,12 +286,40 @@ mod tests {
         let writer = setup_document_index_writer();
         let mut old_document = create_document();
         let mut new_document = create_document();
-        let updated_fields = create_document();
-        let result =
-            writer.update_index_entry(&mut old_document, &mut new_document, &updated_fields);
+        let result = writer.update_index_entry(&mut old_document, &mut new_document);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_update_index_entry_skips_unchanged_indexes() {
+        let writer = setup_document_index_writer();
+        writer
+            .index_operation()
+            .create_index(&create_fields(), &crate::index::IndexOptions::new(UNIQUE_INDEX))
+            .unwrap();
+
+        let mut first = create_document();
+        writer.write_index_entry(&mut first).unwrap();
+
+        // a document sharing the unique value, as if it was never indexed
+        let mut old_document = create_document();
+        let mut new_document = old_document.clone();
+        new_document.put("other", 1).unwrap();
+
+        // the indexed field did not change, so the unique index is not rewritten
+        writer
+            .update_index_entry(&mut old_document, &mut new_document)
+            .unwrap();
+
+        let mut old_document = create_document();
+        old_document.put("field", "other value").unwrap();
+        let mut new_document = create_document();
+        new_document.put("field", "value").unwrap();
+        assert!(writer
+            .update_index_entry(&mut old_document, &mut new_document)
+            .is_err());
+    }
+
     #[test]
     fn test_write_index_entry_internal() {
         let writer = setup_document_index_writer();
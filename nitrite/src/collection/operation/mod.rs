@@ -1,13 +1,19 @@
 mod collection_operations;
 mod read_operations;
 mod write_operations;
+mod history_operations;
+mod options_operations;
 mod index_operations;
 mod index_manager;
 mod find_optimizer;
 mod write_result;
 mod index_writer;
+mod field_expiry;
 
 
 pub(crate) use collection_operations::*;
+pub(crate) use history_operations::*;
+pub(crate) use options_operations::*;
 pub(crate) use index_manager::*;
+pub(crate) use field_expiry::*;
 pub use write_result::*;
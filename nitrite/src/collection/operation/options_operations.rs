@@ -0,0 +1,405 @@
+use crate::{
+    collection::{CollectionOptions, Document, NitriteId, WriteDurability},
+    common::{content_hash_field, ProcessorChain, ProcessorProvider},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    store::{NitriteMap, NitriteMapProvider, NitriteStoreProvider},
+    AttributeAware, Value, DELETED_PREFIX, INTERNAL_NAME_SEPARATOR,
+};
+use parking_lot::{Mutex, RwLock};
+use std::sync::Arc;
+
+/// Applies the options of a collection.
+///
+/// The options are persisted as collection attributes. Soft-deleted documents live in a
+/// side map (`$nitrite_deleted|<collection>`) keyed by document id, in their stored
+/// (processed) form, and are run through the processor chain when read back.
+///
+/// For a capped collection or one with a quota the number of documents and their estimated
+/// size are counted once, on the first write after the collection is opened or its options
+/// change, and then kept up to date by the write operations.
+#[derive(Clone)]
+pub(crate) struct OptionsOperations {
+    inner: Arc<OptionsOperationsInner>,
+}
+
+impl OptionsOperations {
+    pub fn new(
+        collection_name: &str,
+        nitrite_map: NitriteMap,
+        processor_chain: ProcessorChain,
+    ) -> NitriteResult<Self> {
+        let options = match nitrite_map.attributes()? {
+            Some(attributes) => CollectionOptions::from_attributes(&attributes),
+            None => CollectionOptions::new(),
+        };
+
+        Ok(OptionsOperations {
+            inner: Arc::new(OptionsOperationsInner {
+                collection_name: collection_name.to_string(),
+                deleted_map_name: format!(
+                    "{}{}{}",
+                    DELETED_PREFIX, INTERNAL_NAME_SEPARATOR, collection_name
+                ),
+                nitrite_map,
+                processor_chain,
+                options: RwLock::new(options),
+                usage: Mutex::new(None),
+            }),
+        })
+    }
+
+    pub fn options(&self) -> CollectionOptions {
+        self.inner.options.read().clone()
+    }
+
+    pub fn set_options(&self, options: CollectionOptions) -> NitriteResult<()> {
+        self.inner.set_options(options)
+    }
+
+    /// Returns `true` if every write has to commit the store.
+    #[inline]
+    pub fn is_sync(&self) -> bool {
+        self.inner.options.read().get_durability() == WriteDurability::Sync
+    }
+
+    /// Returns `true` if the write results carry per-document outcomes and timings.
+    #[inline]
+    pub fn is_detailed(&self) -> bool {
+        self.inner.options.read().is_detailed_results()
+    }
+
+    /// Returns `true` if the events carry the documents written before and after each
+    /// write.
+    #[inline]
+    pub fn has_event_images(&self) -> bool {
+        self.inner.options.read().is_event_images()
+    }
+
+    /// Checks a document about to be written against the required fields.
+    pub fn validate(&self, document: &Document) -> NitriteResult<()> {
+        self.inner.options.read().validate(document)
+    }
+
+    /// Stores the content hash of a document about to be written if the collection keeps
+    /// it, and removes a hash it no longer keeps up to date otherwise. `document` is the
+    /// document before processing.
+    pub fn stamp_content_hash(&self, document: &mut Document) -> NitriteResult<()> {
+        if self.inner.options.read().is_content_hash() {
+            let hash = document.content_hash();
+            document.put(content_hash_field(), Value::U128(hash))
+        } else {
+            document.remove(&content_hash_field())
+        }
+    }
+
+    /// Keeps a removed document aside if soft delete is enabled. `document` is the
+    /// stored form of the removed document.
+    pub fn record_removal(&self, id: &NitriteId, document: &Document) -> NitriteResult<()> {
+        if !self.inner.options.read().is_soft_delete() {
+            return Ok(());
+        }
+        self.inner
+            .deleted_map()?
+            .put(Value::NitriteId(*id), Value::Document(document.clone()))
+    }
+
+    /// Returns `true` if removed documents are kept aside.
+    #[inline]
+    pub fn is_soft_delete(&self) -> bool {
+        self.inner.options.read().is_soft_delete()
+    }
+
+    /// Returns `true` if the collection evicts its oldest documents.
+    #[inline]
+    pub fn is_capped(&self) -> bool {
+        self.inner.options.read().is_capped()
+    }
+
+    /// Returns `true` if the writes of the collection are counted, for a cap or a quota.
+    #[inline]
+    pub fn tracks_usage(&self) -> bool {
+        let options = self.inner.options.read();
+        options.is_capped() || options.has_quota()
+    }
+
+    /// Checks writes about to be stored against the quota of the collection. Each write is
+    /// the stored form of the document it replaces, if any, and its new stored form.
+    ///
+    /// Returns the position of the first write that does not fit with the reason. A write
+    /// that does not grow the collection always fits.
+    pub fn quota_breach(
+        &self,
+        writes: &[(Option<&Document>, &Document)],
+    ) -> NitriteResult<Option<(usize, String)>> {
+        let options = self.inner.options.read();
+        if !options.has_quota() {
+            return Ok(None);
+        }
+
+        let mut usage = self.inner.usage.lock();
+        let usage = match usage.as_mut() {
+            Some(usage) => usage,
+            None => usage.insert(self.inner.measure_usage()?),
+        };
+        let (mut count, mut bytes) = (usage.count, usage.bytes);
+        for (position, (previous, current)) in writes.iter().enumerate() {
+            let previous_size = previous.map(estimated_size).unwrap_or(0);
+            let size = estimated_size(current);
+            if previous.is_none() {
+                count += 1;
+                if let Some(quota) = options.get_document_quota().filter(|quota| count > *quota) {
+                    return Ok(Some((position, format!(
+                        "Collection {} would exceed its quota of {} documents",
+                        self.inner.collection_name, quota
+                    ))));
+                }
+            }
+            bytes = (bytes + size).saturating_sub(previous_size);
+            if size > previous_size {
+                if let Some(quota) = options.get_byte_quota().filter(|quota| bytes > *quota) {
+                    return Ok(Some((position, format!(
+                        "Collection {} would exceed its quota of {} bytes",
+                        self.inner.collection_name, quota
+                    ))));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Hands a document rejected by the quota to the quota hook of the collection, if set.
+    pub fn quota_exceeded(&self, document: &Document) {
+        // the hook may read the options, so it is not called under their lock
+        let hook = self.inner.options.read().quota_hook();
+        if let Some(hook) = hook {
+            hook(document);
+        }
+    }
+
+    /// Accounts for a document written to a collection whose usage is counted. `previous`
+    /// is the stored form of the document it replaced, if any.
+    pub fn track_write(&self, previous: Option<&Document>, current: &Document) {
+        if let Some(usage) = self.inner.usage.lock().as_mut() {
+            if let Some(previous) = previous {
+                usage.remove(previous);
+            }
+            usage.add(current);
+        }
+    }
+
+    /// Accounts for a document removed from a collection whose usage is counted.
+    pub fn track_removal(&self, document: &Document) {
+        if let Some(usage) = self.inner.usage.lock().as_mut() {
+            usage.remove(document);
+        }
+    }
+
+    /// Returns `true` if a capped collection holds more than its cap. The newest document is
+    /// always kept, so a collection with a single document is never over the cap.
+    pub fn is_over_cap(&self) -> NitriteResult<bool> {
+        let options = self.inner.options.read();
+        if !options.is_capped() {
+            return Ok(false);
+        }
+
+        let mut usage = self.inner.usage.lock();
+        let usage = match usage.as_mut() {
+            Some(usage) => usage,
+            None => usage.insert(self.inner.measure_usage()?),
+        };
+        if usage.count <= 1 {
+            return Ok(false);
+        }
+        Ok(options.get_max_documents().is_some_and(|max| usage.count > max)
+            || options.get_max_bytes().is_some_and(|max| usage.bytes > max))
+    }
+
+    /// Forgets the counted usage so it is measured again on the next write.
+    pub fn reset_usage(&self) {
+        *self.inner.usage.lock() = None;
+    }
+
+    pub fn deleted_documents(&self) -> NitriteResult<Vec<Document>> {
+        self.inner.deleted_documents()
+    }
+
+    /// Returns a soft-deleted document in its read form.
+    pub fn deleted_document(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
+        self.inner.deleted_document(id)
+    }
+
+    /// Discards a soft-deleted document once it has been restored.
+    pub fn forget_deleted(&self, id: &NitriteId) -> NitriteResult<()> {
+        if self.inner.has_deleted_map()? {
+            self.inner.deleted_map()?.remove(&Value::NitriteId(*id))?;
+        }
+        Ok(())
+    }
+
+    /// Discards all soft-deleted documents.
+    pub fn purge_deleted(&self) -> NitriteResult<()> {
+        self.inner.clear()
+    }
+
+    pub fn clear(&self) -> NitriteResult<()> {
+        self.reset_usage();
+        self.inner.clear()
+    }
+
+    pub fn dispose(&self) -> NitriteResult<()> {
+        self.inner.dispose()
+    }
+}
+
+struct OptionsOperationsInner {
+    collection_name: String,
+    deleted_map_name: String,
+    nitrite_map: NitriteMap,
+    processor_chain: ProcessorChain,
+    options: RwLock<CollectionOptions>,
+    usage: Mutex<Option<Usage>>,
+}
+
+impl OptionsOperationsInner {
+    fn set_options(&self, options: CollectionOptions) -> NitriteResult<()> {
+        let mut attributes = self.nitrite_map.attributes()?.unwrap_or_default();
+        options.write_attributes(&mut attributes);
+        self.nitrite_map.set_attributes(attributes)?;
+
+        let discard_deleted = !options.is_soft_delete();
+        *self.options.write() = options;
+        *self.usage.lock() = None;
+        if discard_deleted {
+            self.dispose()?;
+        }
+        Ok(())
+    }
+
+    fn measure_usage(&self) -> NitriteResult<Usage> {
+        let mut usage = Usage::default();
+        for entry in self.nitrite_map.values()? {
+            if let Value::Document(document) = entry? {
+                usage.add(&document);
+            }
+        }
+        Ok(usage)
+    }
+
+    fn deleted_map(&self) -> NitriteResult<NitriteMap> {
+        self.nitrite_map.get_store()?.open_map(&self.deleted_map_name)
+    }
+
+    fn has_deleted_map(&self) -> NitriteResult<bool> {
+        self.nitrite_map.get_store()?.has_map(&self.deleted_map_name)
+    }
+
+    fn deleted_documents(&self) -> NitriteResult<Vec<Document>> {
+        let mut documents = Vec::new();
+        if !self.has_deleted_map()? {
+            return Ok(documents);
+        }
+        for entry in self.deleted_map()?.values()? {
+            if let Value::Document(document) = entry? {
+                documents.push(self.processor_chain.process_after_read(document)?);
+            }
+        }
+        Ok(documents)
+    }
+
+    fn deleted_document(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
+        if !self.has_deleted_map()? {
+            return Ok(None);
+        }
+        match self.deleted_map()?.get(&Value::NitriteId(*id))? {
+            Some(Value::Document(document)) => {
+                Ok(Some(self.processor_chain.process_after_read(document)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn clear(&self) -> NitriteResult<()> {
+        if self.has_deleted_map()? {
+            self.deleted_map()?.clear()?;
+        }
+        Ok(())
+    }
+
+    fn dispose(&self) -> NitriteResult<()> {
+        if self.has_deleted_map()? {
+            self.deleted_map()?.dispose()?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of documents of a capped collection, or one with a quota, and their estimated size
+/// in bytes.
+#[derive(Debug, Default)]
+struct Usage {
+    count: u64,
+    bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, document: &Document) {
+        self.count += 1;
+        self.bytes += estimated_size(document);
+    }
+
+    fn remove(&mut self, document: &Document) {
+        self.count = self.count.saturating_sub(1);
+        self.bytes = self.bytes.saturating_sub(estimated_size(document));
+    }
+}
+
+/// Estimates the encoded size of a document: the length of its keys, strings and byte
+/// arrays plus the width of its scalar values.
+pub(crate) fn estimated_size(document: &Document) -> u64 {
+    document
+        .iter()
+        .map(|(key, value)| key.len() as u64 + estimated_value_size(&value))
+        .sum()
+}
+
+fn estimated_value_size(value: &Value) -> u64 {
+    match value {
+        Value::Null | Value::Unknown => 1,
+        Value::Bool(_) | Value::I8(_) | Value::U8(_) => 1,
+        Value::I16(_) | Value::U16(_) => 2,
+        Value::I32(_) | Value::U32(_) | Value::F32(_) | Value::Char(_) => 4,
+        Value::I64(_) | Value::U64(_) | Value::ISize(_) | Value::USize(_) | Value::F64(_) => 8,
+        Value::I128(_) | Value::U128(_) => 16,
+        Value::NitriteId(_) => 8,
+        Value::String(value) => value.len() as u64,
+        Value::Bytes(value) => value.len() as u64,
+        Value::RawJson(value) => value.len() as u64,
+        Value::Document(document) => estimated_size(document),
+        Value::Array(values) => values.iter().map(estimated_value_size).sum(),
+        Value::Map(entries) => entries
+            .iter()
+            .map(|(key, value)| estimated_value_size(key) + estimated_value_size(value))
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    #[test]
+    fn test_estimated_size() {
+        let small = doc! { level: "info" };
+        let large = doc! { level: "info", message: "disk usage is above the threshold" };
+        assert_eq!(estimated_size(&small), 9);
+        assert!(estimated_size(&large) > estimated_size(&small));
+
+        let mut usage = Usage::default();
+        usage.add(&small);
+        usage.add(&large);
+        usage.remove(&small);
+        assert_eq!(usage.count, 1);
+        assert_eq!(usage.bytes, estimated_size(&large));
+    }
+}
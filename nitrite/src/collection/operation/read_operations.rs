@@ -1,20 +1,28 @@
-use super::{find_optimizer::FindOptimizer, index_operations::IndexOperations};
+use super::{
+    field_expiry::{hide_expired_fields, FieldExpiryFilter},
+    find_optimizer::{is_covered_by_index, FindOptimizer},
+    index_operations::IndexOperations,
+};
 use crate::filter::is_all_filter;
 use crate::{
-    collection::{Document, FindOptions, FindPlan, NitriteId},
+    collection::{Document, FindOptions, FindPlan, NitriteId, QueryInterrupt, ScanToken},
     errors::{ErrorKind, NitriteError, NitriteResult},
-    filter::{Filter, FilterProvider},
+    filter::{and, Filter, FilterProvider, IdRangeFilter, TextAnyFilter},
     filtered_stream::FilteredStream,
-    index::NitriteIndexerProvider,
-    indexed_stream::IndexedStream,
+    id_range_stream::IdRangeStream,
+    index::{IndexHint, NitriteIndexer, NitriteIndexerProvider},
+    indexed_stream::{CoveredStream, IndexedStream},
+    interruptible_stream::InterruptibleStream,
     map_values::MapValues,
     nitrite_config::NitriteConfig,
+    profiler::{timed, Phase},
     single_stream::SingleStream,
     sorted_stream::SortedStream,
-    store::{NitriteMap, NitriteMapProvider},
-    union_stream::UnionStream,
+    store::{NitriteMap, NitriteMapProvider, NitriteStoreProvider},
+    union_stream::{IdUnionStream, UnionStream},
     unique_stream::UniqueStream,
-    DocumentCursor, ProcessorChain, ProcessorProvider, Value,
+    derive_index_map_name, expiry_field, DocumentCursor, Fields, ProcessorChain,
+    ProcessorProvider, SortOrder, Value, NON_UNIQUE_INDEX, UNIQUE_INDEX,
 };
 use icu_collator::options::CollatorOptions;
 use icu_collator::{Collator, CollatorPreferences};
@@ -94,14 +102,128 @@ impl ReadOperationsInner {
         filter: Filter,
         find_options: &FindOptions,
     ) -> NitriteResult<DocumentCursor> {
-        self.prepare_filter(&filter)?;
-        let index_descriptors = self.index_operations.list_indexes()?;
-        let find_plan =
-            self.find_optimizer
+        if let Some(token) = &find_options.resume_after {
+            self.check_resume(token, find_options)?;
+        }
+        let interrupt = QueryInterrupt::start(
+            find_options.timeout,
+            find_options.cancellation_token.as_ref(),
+        );
+        let find_plan = timed(Phase::Planning, || {
+            self.prepare_filter(&filter)?;
+            let index_descriptors = self.index_operations.queryable_indexes()?;
+            let find_plan = self.find_optimizer
                 .create_find_plan(&filter, find_options, &index_descriptors)?;
+            let find_plan = self.resume(find_plan, find_options)?;
+            self.cover(find_plan, find_options)
+        })?;
+
+        let mut cursor = self.create_cursor(&find_plan, interrupt, find_options.max_memory)?;
+        if is_map_scan(&find_plan) {
+            cursor = cursor.checkpointed(
+                self.collection_name.clone(),
+                find_options.resume_after.as_ref(),
+            );
+        }
+        Ok(cursor.projected_to(find_options.projection.clone()))
+    }
 
-        let cursor = self.create_cursor(&find_plan)?;
-        Ok(cursor)
+    /// Fails if a scan cannot resume from `token` with these options: the token must be
+    /// of this collection, and the documents must come in the order of the ids.
+    fn check_resume(&self, token: &ScanToken, find_options: &FindOptions) -> NitriteResult<()> {
+        if token.collection_name() != self.collection_name {
+            log::error!(
+                "Scan token of collection {} cannot resume a scan of {}",
+                token.collection_name(),
+                self.collection_name
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Scan token of collection {} cannot resume a scan of {}",
+                    token.collection_name(),
+                    self.collection_name
+                ),
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        if find_options.sort_by.is_some()
+            || find_options.distance_sort.is_some()
+            || matches!(find_options.hint, Some(IndexHint::Index(_)))
+        {
+            log::error!("A resumed scan cannot be sorted or use an index");
+            return Err(NitriteError::new(
+                "A resumed scan cannot be sorted or use an index",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Starts the scan of a resumed query after the last document of its token.
+    fn resume(&self, find_plan: FindPlan, find_options: &FindOptions) -> NitriteResult<FindPlan> {
+        let Some(token) = &find_options.resume_after else {
+            return Ok(find_plan);
+        };
+        if !is_map_scan(&find_plan) {
+            log::error!("The filter of a resumed scan must not be answered by id");
+            return Err(NitriteError::new(
+                "The filter of a resumed scan must not be answered by id",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        match token.last_id() {
+            Some(id) => Ok(find_plan.resuming_after(id)),
+            None => Ok(find_plan),
+        }
+    }
+
+    /// Marks a plan covered when its index can build the projected documents from its
+    /// keys. Processors may need any field of the stored documents, so they prevent it.
+    fn cover(&self, find_plan: FindPlan, find_options: &FindOptions) -> NitriteResult<FindPlan> {
+        let (Some(projection), Some(index_descriptor)) =
+            (&find_options.projection, find_plan.index_descriptor())
+        else {
+            return Ok(find_plan);
+        };
+        if !self.processor_chain.is_empty() || !is_covered_by_index(&find_plan, projection) {
+            return Ok(find_plan);
+        }
+
+        let indexer = self
+            .nitrite_config
+            .find_indexer(&index_descriptor.index_type())?;
+        if indexer.can_cover(&index_descriptor, &self.nitrite_config)? {
+            Ok(find_plan.to_covered())
+        } else {
+            Ok(find_plan)
+        }
+    }
+
+    /// Counts the documents matching a filter, planned on its own without sorting.
+    ///
+    /// A filter fully covered by an index is counted from the index entries and a filter
+    /// over the whole collection from the size of the collection, without reading any
+    /// document. Anything else is counted by streaming the matching documents, which are
+    /// never run through the processors.
+    pub fn count(&self, filter: Filter) -> NitriteResult<u64> {
+        let find_plan = timed(Phase::Planning, || {
+            self.prepare_filter(&filter)?;
+            let index_descriptors = self.index_operations.queryable_indexes()?;
+            self.find_optimizer
+                .create_find_plan(&filter, &FindOptions::new(), &index_descriptors)
+        })?;
+
+        let (stream, covered_count) = self.build_raw_stream(&find_plan, None, None)?;
+        if let Some(count) = covered_count {
+            return Ok(count as u64);
+        }
+
+        let mut count = 0;
+        for document in stream {
+            document?;
+            count += 1;
+        }
+        Ok(count)
     }
 
     pub fn get_by_id(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
@@ -141,17 +263,38 @@ impl ReadOperationsInner {
             }
         }
 
+        if let Some(text_any) = filter.as_any().downcast_ref::<TextAnyFilter>() {
+            if text_any.is_indexed_only() {
+                let mut fields = Vec::new();
+                for index_descriptor in self.index_operations.list_indexes()? {
+                    for field in index_descriptor.index_fields().field_names() {
+                        if !fields.contains(&field) {
+                            fields.push(field);
+                        }
+                    }
+                }
+                text_any.set_indexed_fields(fields);
+            }
+        }
+
         // Set collection name using reference instead of cloning entire string
         filter.set_collection_name(self.collection_name.clone())?;
         Ok(())
     }
 
-    fn create_cursor(&self, find_plan: &FindPlan) -> NitriteResult<DocumentCursor> {
-        let (iter, covered_count) = self.build_raw_stream(find_plan)?;
+    fn create_cursor(
+        &self,
+        find_plan: &FindPlan,
+        interrupt: Option<QueryInterrupt>,
+        max_memory: Option<u64>,
+    ) -> NitriteResult<DocumentCursor> {
+        let (iter, covered_count) =
+            self.build_raw_stream(find_plan, interrupt.as_ref(), max_memory)?;
 
         // Build a factory that rebuilds the stream on demand, so the (streaming) cursor can be
         // reset and replayed without retaining every yielded document in memory. The captured
-        // `ReadOperations` is a cheap, Arc-backed clone of this read context.
+        // `ReadOperations` is a cheap, Arc-backed clone of this read context. A replay keeps
+        // the deadline, cancellation token and memory cap of the query.
         let ops = ReadOperations::new(
             self.collection_name.clone(),
             self.index_operations.clone(),
@@ -161,13 +304,85 @@ impl ReadOperationsInner {
             self.processor_chain.clone(),
         );
         let plan = find_plan.clone();
-        let factory = Box::new(move || ops.build_raw_stream(&plan).map(|(stream, _)| stream));
+        let bound_ops = ops.clone();
+        let factory = Box::new(move || {
+            ops.build_raw_stream(&plan, interrupt.as_ref(), max_memory)
+                .map(|(stream, _)| stream)
+        });
+
+        let cursor = DocumentCursor::streaming(iter, factory, self.processor_chain.clone())
+            .set_find_plan(find_plan.clone())
+            .with_covered_count(covered_count)
+            .with_memory_limit(max_memory)
+            .with_source_map(self.nitrite_map.get_name()?);
+        if is_whole_collection(find_plan) {
+            let field_bound =
+                Box::new(move |field: &str, order| bound_ops.indexed_field_bound(field, order));
+            Ok(cursor.with_field_bound(field_bound))
+        } else {
+            Ok(cursor)
+        }
+    }
 
-        Ok(
-            DocumentCursor::streaming(iter, factory, self.processor_chain.clone())
-                .set_find_plan(find_plan.clone())
-                .with_covered_count(covered_count),
-        )
+    /// Returns the smallest or largest numeric value of a field from a unique or
+    /// non-unique index on it alone, or `None` if there is no such index or it cannot
+    /// answer. The value found is checked against its document, as the index may still
+    /// hold values of expired fields until they are swept.
+    fn indexed_field_bound(&self, field: &str, order: SortOrder) -> NitriteResult<Option<Value>> {
+        let fields = Fields::with_names(vec![field])?;
+        let index_descriptor = match self.index_operations.find_index_descriptor(&fields)? {
+            Some(index_descriptor) => index_descriptor,
+            None => return Ok(None),
+        };
+        let index_type = index_descriptor.index_type();
+        if (index_type != UNIQUE_INDEX && index_type != NON_UNIQUE_INDEX)
+            || self.index_operations.is_indexing(&fields)?
+            || self.index_operations.is_stale(&fields)
+        {
+            return Ok(None);
+        }
+
+        let index_map = self
+            .nitrite_config
+            .nitrite_store()?
+            .open_map(&derive_index_map_name(&index_descriptor))?;
+        let entries: Box<dyn Iterator<Item = NitriteResult<(Value, Value)>>> = match order {
+            SortOrder::Ascending => Box::new(index_map.entries()?),
+            SortOrder::Descending => Box::new(index_map.reverse_entries()?),
+        };
+
+        for entry in entries {
+            let (key, value) = entry?;
+            // `value -> [ids]` rows of unique indexes, `[value, id] -> null` rows otherwise
+            let (indexed, nitrite_id) = match (&key, &value) {
+                (Value::Array(parts), Value::Null) => match (parts.first(), parts.last()) {
+                    (Some(indexed), Some(Value::NitriteId(id))) if parts.len() == 2 => (indexed, *id),
+                    _ => continue,
+                },
+                (_, Value::Array(ids)) => match ids.first() {
+                    Some(Value::NitriteId(id)) => (&key, *id),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if !indexed.is_number() {
+                continue;
+            }
+
+            // keys are sorted, so the first number is the bound if its document agrees
+            let document = match self.get_by_id(&nitrite_id)? {
+                Some(document) => hide_expired_fields(document, &expiry_field())?,
+                None => return Ok(None),
+            };
+            return Ok(document
+                .get(field)
+                .ok()
+                .and_then(|value| match value {
+                    Value::Array(values) => values.into_iter().find(|v| v.cmp(indexed).is_eq()),
+                    value => value.cmp(indexed).is_eq().then_some(value),
+                }));
+        }
+        Ok(None)
     }
 
     /// Builds the raw (pre-processor) document stream for a plan, plus the index-covered match
@@ -176,6 +391,8 @@ impl ReadOperationsInner {
     pub(crate) fn build_raw_stream(
         &self,
         find_plan: &FindPlan,
+        interrupt: Option<&QueryInterrupt>,
+        max_memory: Option<u64>,
     ) -> NitriteResult<(DocumentStream, Option<usize>)> {
         // Fast path for simple all-documents query with no filtering or sorting
         if find_plan.by_id_filter().is_none()
@@ -185,11 +402,14 @@ impl ReadOperationsInner {
             && find_plan.sub_plans().is_none_or(|p| p.is_empty())
         {
             // Direct map iteration with no filters
-            let iter = Box::new(MapValues::new(self.nitrite_map.clone()));
+            let iter = interruptible(Box::new(self.scan_values(find_plan)), interrupt);
 
             // Apply limit/skip if needed. With neither, the whole collection matches, so the
             // count is the map size — answerable without iterating any document.
-            let covered_count = if find_plan.skip().is_some() || find_plan.limit().is_some() {
+            let covered_count = if find_plan.skip().is_some()
+                || find_plan.limit().is_some()
+                || find_plan.resume_after().is_some()
+            {
                 None
             } else {
                 Some(self.nitrite_map.size()? as usize)
@@ -208,7 +428,8 @@ impl ReadOperationsInner {
 
         // Standard path for complex queries
         let mut indexed_id_count = None;
-        let iter = self.find_suitable_iter(find_plan, &mut indexed_id_count)?;
+        let iter =
+            self.find_suitable_iter(find_plan, interrupt, max_memory, &mut indexed_id_count)?;
         // The index id count is the exact match count only when nothing downstream drops or
         // changes cardinality (a post-filter, skip, or limit). Sort does not change the count.
         let covered_count = if find_plan.full_scan_filter().is_none()
@@ -225,12 +446,30 @@ impl ReadOperationsInner {
     fn find_suitable_iter(
         &self,
         find_plan: &FindPlan,
+        interrupt: Option<&QueryInterrupt>,
+        max_memory: Option<u64>,
         indexed_id_count: &mut Option<usize>,
     ) -> NitriteResult<Box<dyn Iterator<Item = NitriteResult<Document>>>> {
         let mut raw_stream: Box<dyn Iterator<Item = NitriteResult<Document>>>;
 
         if let Some(sub_plans) = find_plan.sub_plans() {
-            if !sub_plans.is_empty() {
+            if !sub_plans.is_empty() && sub_plans.iter().all(is_id_lookup) {
+                // Every branch of the `or` is answered by an index or an id lookup: union
+                // their id sets so each matching document is read once.
+                let mut id_sets = Vec::with_capacity(sub_plans.len());
+                let mut checks = Vec::with_capacity(sub_plans.len());
+                for sub_plan in &sub_plans {
+                    id_sets.push(self.find_branch_ids(sub_plan)?);
+                    checks.push(branch_check(sub_plan));
+                }
+
+                let stream = IdUnionStream::new(self.nitrite_map.clone(), id_sets, checks);
+                // Without a check on the documents, the union is the exact match count.
+                if sub_plans.iter().all(|sub_plan| sub_plan.full_scan_filter().is_none()) {
+                    *indexed_id_count = Some(stream.id_count());
+                }
+                raw_stream = interruptible(Box::new(stream), interrupt);
+            } else if !sub_plans.is_empty() {
                 let mut sub_iters: SmallVec<
                     [Box<dyn Iterator<Item = NitriteResult<Document>>>; 4],
                 > = SmallVec::with_capacity(sub_plans.len());
@@ -238,138 +477,95 @@ impl ReadOperationsInner {
                 for sub_plan in sub_plans {
                     // A sub-plan's own covered count cannot answer the union's count (dedup),
                     // so discard it here.
-                    let iter = self.find_suitable_iter(&sub_plan, &mut None)?;
+                    let iter =
+                        self.find_suitable_iter(&sub_plan, interrupt, max_memory, &mut None)?;
                     sub_iters.push(iter);
                 }
 
-                raw_stream = Box::new(UnionStream::new(sub_iters.into_vec()));
-
-                if find_plan.distinct() {
-                    raw_stream = Box::new(UniqueStream::new(raw_stream));
-                }
+                // a document matching several branches of an `or` is returned once
+                raw_stream = Box::new(UniqueStream::new(UnionStream::new(sub_iters.into_vec())));
             } else {
-                if find_plan.by_id_filter().is_some() {
-                    let nitrite_id = find_plan.by_id_filter().unwrap();
-                    let nitrite_id = nitrite_id.get_field_value()?;
-                    match nitrite_id {
-                        Some(Value::NitriteId(id)) => {
-                            let document = self.nitrite_map.get(&Value::from(id))?;
-                            if let Some(doc) = document {
-                                match doc.as_document() {
-                                    Some(d) => {
-                                        raw_stream = Box::new(SingleStream::new(Some(d.clone())));
-                                    }
-                                    None => {
-                                        log::error!(
-                                            "Expected Document value in collection store for ID {:?}, found non-Document type",
-                                            nitrite_id
-                                        );
-                                        return Err(NitriteError::new(
-                                            "Invalid value type in collection store",
-                                            ErrorKind::ValidationError,
-                                        ));
-                                    }
-                                }
-                            } else {
-                                raw_stream = Box::new(SingleStream::new(None));
-                            }
-                        }
-                        _ => {
-                            log::error!("Invalid NitriteId {:?}", nitrite_id);
-                            return Err(NitriteError::new(
-                                "Invalid NitriteId",
-                                ErrorKind::FilterError,
-                            ));
-                        }
-                    }
+                if let Some(by_id_filter) = find_plan.by_id_filter() {
+                    raw_stream = self.find_by_id_filter(&by_id_filter)?;
                 } else {
                     if let Some(index_descriptor) = find_plan.index_descriptor() {
                         let indexer = self
                             .nitrite_config
                             .find_indexer(&index_descriptor.index_type())?;
 
-                        let nitrite_ids =
-                            indexer.find_by_filter(find_plan, &self.nitrite_config)?;
-
-                        raw_stream =
-                            Box::new(IndexedStream::new(self.nitrite_map.clone(), nitrite_ids));
+                        check_distance_sort(find_plan, &indexer)?;
+                        raw_stream = match self.find_covered(find_plan, &indexer)? {
+                            Some(documents) => Box::new(CoveredStream::new(documents)),
+                            None => {
+                                let nitrite_ids = timed(Phase::IndexScan, || {
+                                    indexer.find_by_filter(find_plan, &self.nitrite_config)
+                                })?;
+                                recheck_expired_fields(
+                                    find_plan,
+                                    Box::new(self.indexed_stream(find_plan, &indexer, nitrite_ids)),
+                                )
+                            }
+                        };
                     } else {
-                        raw_stream = Box::new(MapValues::new(self.nitrite_map.clone()));
+                        raw_stream = Box::new(self.scan_values(find_plan));
                     }
                 }
 
-                if find_plan.full_scan_filter().is_some() {
+                raw_stream = interruptible(raw_stream, interrupt);
+                if let Some(full_scan_filter) = find_plan.full_scan_filter() {
                     raw_stream = Box::new(FilteredStream::new(
                         raw_stream,
-                        find_plan.full_scan_filter().unwrap(),
+                        FieldExpiryFilter::full_scan(full_scan_filter),
                     ));
                 }
             }
         } else {
-            if find_plan.by_id_filter().is_some() {
-                let nitrite_id = find_plan.by_id_filter().unwrap();
-                let nitrite_id = nitrite_id.get_field_value()?;
-                match nitrite_id {
-                    Some(Value::NitriteId(id)) => {
-                        let document = self.nitrite_map.get(&Value::from(id))?;
-                        if let Some(doc) = document {
-                            match doc.as_document() {
-                                Some(d) => {
-                                    raw_stream = Box::new(SingleStream::new(Some(d.clone())));
-                                }
-                                None => {
-                                    log::error!(
-                                        "Expected Document value in collection store for ID {:?}, found non-Document type",
-                                        nitrite_id
-                                    );
-                                    return Err(NitriteError::new(
-                                        "Invalid value type in collection store",
-                                        ErrorKind::ValidationError,
-                                    ));
-                                }
-                            }
-                        } else {
-                            raw_stream = Box::new(SingleStream::new(None));
-                        }
-                    }
-                    _ => {
-                        log::error!("Invalid NitriteId {:?}", nitrite_id);
-                        return Err(NitriteError::new(
-                            "Invalid NitriteId",
-                            ErrorKind::FilterError,
-                        ));
-                    }
-                }
+            if let Some(by_id_filter) = find_plan.by_id_filter() {
+                raw_stream = self.find_by_id_filter(&by_id_filter)?;
             } else {
                 if let Some(index_descriptor) = find_plan.index_descriptor() {
                     let indexer = self
                         .nitrite_config
                         .find_indexer(&index_descriptor.index_type())?;
 
-                    let nitrite_ids = indexer.find_by_filter(find_plan, &self.nitrite_config)?;
-
-                    // The index supplied the exact matching id set; record its size so a
+                    check_distance_sort(find_plan, &indexer)?;
+                    // The index supplies the exact matching id set; record its size so a
                     // count()/size() with no row-dropping step downstream can answer from it.
-                    *indexed_id_count = Some(nitrite_ids.len());
-                    raw_stream =
-                        Box::new(IndexedStream::new(self.nitrite_map.clone(), nitrite_ids));
+                    raw_stream = match self.find_covered(find_plan, &indexer)? {
+                        Some(documents) => {
+                            *indexed_id_count = Some(documents.len());
+                            Box::new(CoveredStream::new(documents))
+                        }
+                        None => {
+                            let nitrite_ids = timed(Phase::IndexScan, || {
+                                indexer.find_by_filter(find_plan, &self.nitrite_config)
+                            })?;
+                            *indexed_id_count = Some(nitrite_ids.len());
+                            recheck_expired_fields(
+                                find_plan,
+                                Box::new(self.indexed_stream(find_plan, &indexer, nitrite_ids)),
+                            )
+                        }
+                    };
                 } else {
-                    raw_stream = Box::new(MapValues::new(self.nitrite_map.clone()));
+                    raw_stream = Box::new(self.scan_values(find_plan));
                 }
             }
 
-            if find_plan.full_scan_filter().is_some() {
+            // the source is checked, so a filter or sort pulling from it stops as well
+            raw_stream = interruptible(raw_stream, interrupt);
+            if let Some(full_scan_filter) = find_plan.full_scan_filter() {
                 raw_stream = Box::new(FilteredStream::new(
                     raw_stream,
-                    find_plan.full_scan_filter().unwrap(),
+                    FieldExpiryFilter::full_scan(full_scan_filter),
                 ));
             }
         }
 
-        if find_plan.blocking_sort_order().is_some()
-            && !find_plan.blocking_sort_order().unwrap().is_empty()
+        if let Some(sort_order) = find_plan
+            .blocking_sort_order()
+            .filter(|sort_order| !sort_order.is_empty())
         {
-            let sort_order = find_plan.blocking_sort_order().unwrap();
             let collator_preference = find_plan
                 .collator_preferences()
                 .unwrap_or_default();
@@ -383,7 +579,17 @@ impl ReadOperationsInner {
                         ErrorKind::BackendError
                     )
                 })?;
-            raw_stream = Box::new(SortedStream::new(raw_stream, sort_order, Some(collator)));
+            // the memory cap of the query lowers the budget before the sort spills
+            let memory_budget = self.nitrite_config.sort_memory_budget();
+            let memory_budget = max_memory.map_or(memory_budget, |max| max.min(memory_budget));
+            raw_stream = Box::new(SortedStream::with_memory_budget(
+                raw_stream,
+                sort_order,
+                Some(collator),
+                find_plan.tie_break_by_id(),
+                self.nitrite_map.get_store()?,
+                memory_budget,
+            ));
         }
 
         if find_plan.skip().is_some() || find_plan.limit().is_some() {
@@ -394,6 +600,190 @@ impl ReadOperationsInner {
 
         Ok(raw_stream)
     }
+
+    /// Reads the documents of the collection in the order of the map, after the resume
+    /// position of the plan if it has one.
+    fn scan_values(&self, find_plan: &FindPlan) -> MapValues {
+        match find_plan.resume_after() {
+            Some(id) => MapValues::after(self.nitrite_map.clone(), Value::from(id)),
+            None => MapValues::new(self.nitrite_map.clone()),
+        }
+    }
+
+    /// Reads the documents of the ids an index found. They are read by ascending id
+    /// unless the index ranks them, the plan sorts them by distance or the tie-break is
+    /// off, in which case the order of the index is kept.
+    fn indexed_stream(
+        &self,
+        find_plan: &FindPlan,
+        indexer: &NitriteIndexer,
+        nitrite_ids: Vec<NitriteId>,
+    ) -> IndexedStream {
+        if find_plan.tie_break_by_id()
+            && find_plan.distance_sort().is_none()
+            && !indexer.ranks_results()
+        {
+            IndexedStream::ordered_by_id(self.nitrite_map.clone(), nitrite_ids)
+        } else {
+            IndexedStream::new(self.nitrite_map.clone(), nitrite_ids)
+        }
+    }
+
+    /// Builds the documents of a covered plan from the keys of its index, or returns
+    /// `None` if the plan is not covered or the index can no longer answer it.
+    fn find_covered(
+        &self,
+        find_plan: &FindPlan,
+        indexer: &NitriteIndexer,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        if !find_plan.is_covered() {
+            return Ok(None);
+        }
+        timed(Phase::IndexScan, || indexer.find_covered(find_plan, &self.nitrite_config))
+    }
+
+    /// Finds the ids of the documents an index or id lookup returns for a branch of an `or`.
+    fn find_branch_ids(&self, sub_plan: &FindPlan) -> NitriteResult<Vec<NitriteId>> {
+        if let Some(by_id_filter) = sub_plan.by_id_filter() {
+            let mut nitrite_ids = Vec::new();
+            for document in self.find_by_id_filter(&by_id_filter)? {
+                nitrite_ids.push(document?.id()?);
+            }
+            return Ok(nitrite_ids);
+        }
+
+        let index_descriptor = sub_plan.index_descriptor().ok_or_else(|| {
+            NitriteError::new(
+                "Branch of an or filter has no index to answer it",
+                ErrorKind::FilterError,
+            )
+        })?;
+        let indexer = self
+            .nitrite_config
+            .find_indexer(&index_descriptor.index_type())?;
+        timed(Phase::IndexScan, || indexer.find_by_filter(sub_plan, &self.nitrite_config))
+    }
+
+    fn find_by_id_filter(&self, by_id_filter: &Filter) -> NitriteResult<DocumentStream> {
+        if let Some(id_range_filter) = by_id_filter.as_any().downcast_ref::<IdRangeFilter>() {
+            let (start, end) = id_range_filter.bounds();
+            return Ok(Box::new(IdRangeStream::new(
+                self.nitrite_map.clone(),
+                start,
+                end,
+            )));
+        }
+
+        let nitrite_id = by_id_filter.get_field_value()?;
+        match nitrite_id {
+            Some(Value::NitriteId(id)) => {
+                let document = self.nitrite_map.get(&Value::from(id))?;
+                match document {
+                    Some(doc) => match doc.as_document() {
+                        Some(d) => Ok(Box::new(SingleStream::new(Some(d.clone())))),
+                        None => {
+                            log::error!(
+                                "Expected Document value in collection store for ID {:?}, found non-Document type",
+                                nitrite_id
+                            );
+                            Err(NitriteError::new(
+                                "Invalid value type in collection store",
+                                ErrorKind::ValidationError,
+                            ))
+                        }
+                    },
+                    None => Ok(Box::new(SingleStream::new(None))),
+                }
+            }
+            _ => {
+                log::error!("Invalid NitriteId {:?}", nitrite_id);
+                Err(NitriteError::new(
+                    "Invalid NitriteId",
+                    ErrorKind::FilterError,
+                ))
+            }
+        }
+    }
+}
+
+/// Checks a stream for the timeout and cancellation of its query, if it has any.
+fn interruptible(stream: DocumentStream, interrupt: Option<&QueryInterrupt>) -> DocumentStream {
+    match interrupt {
+        Some(interrupt) => Box::new(InterruptibleStream::new(stream, interrupt.clone())),
+        None => stream,
+    }
+}
+
+/// Checks whether a plan reads every document of the collection.
+fn is_whole_collection(find_plan: &FindPlan) -> bool {
+    find_plan.by_id_filter().is_none()
+        && find_plan.index_descriptor().is_none()
+        && find_plan.full_scan_filter().is_none()
+        && find_plan.sub_plans().is_none_or(|p| p.is_empty())
+        && find_plan.skip().is_none()
+        && find_plan.limit().is_none()
+        && find_plan.resume_after().is_none()
+}
+
+/// Checks whether a plan returns the documents it reads from the collection in the order
+/// of the map, so that a cursor over it can be checkpointed.
+fn is_map_scan(find_plan: &FindPlan) -> bool {
+    find_plan.by_id_filter().is_none()
+        && find_plan.index_descriptor().is_none()
+        && find_plan.sub_plans().is_none_or(|p| p.is_empty())
+        && find_plan.blocking_sort_order().is_none_or(|order| order.is_empty())
+        && find_plan.distance_sort().is_none()
+}
+
+/// Fails if a plan sorted by distance is answered by an index that cannot order by distance.
+fn check_distance_sort(find_plan: &FindPlan, indexer: &NitriteIndexer) -> NitriteResult<()> {
+    if find_plan.distance_sort().is_some() && !indexer.supports_distance_sort() {
+        log::error!("Index type {} cannot sort by distance", indexer.index_type());
+        return Err(NitriteError::new(
+            &format!("Index type {} cannot sort by distance", indexer.index_type()),
+            ErrorKind::InvalidOperation,
+        ));
+    }
+    Ok(())
+}
+
+/// Drops indexed documents that only matched through an expired, not yet swept field.
+/// Checks whether a plan finds its documents through an index or by their ids.
+fn is_id_lookup(find_plan: &FindPlan) -> bool {
+    find_plan.sub_plans().is_none_or(|sub_plans| sub_plans.is_empty())
+        && (find_plan.by_id_filter().is_some() || find_plan.index_descriptor().is_some())
+}
+
+/// Returns the check the documents found for a branch of an `or` must pass: its
+/// residual filter and the re-check of its index filters against expired fields.
+fn branch_check(sub_plan: &FindPlan) -> Option<Filter> {
+    let mut checks = Vec::new();
+    if let Some(index_scan_filter) = sub_plan.index_scan_filter() {
+        if !index_scan_filter.filters().is_empty() {
+            checks.push(FieldExpiryFilter::index_recheck(index_scan_filter.filters()));
+        }
+    }
+    if let Some(full_scan_filter) = sub_plan.full_scan_filter() {
+        checks.push(FieldExpiryFilter::full_scan(full_scan_filter));
+    }
+
+    match checks.len() {
+        0 => None,
+        1 => checks.pop(),
+        _ => Some(and(checks)),
+    }
+}
+
+fn recheck_expired_fields(
+    find_plan: &FindPlan,
+    stream: Box<dyn Iterator<Item = NitriteResult<Document>>>,
+) -> Box<dyn Iterator<Item = NitriteResult<Document>>> {
+    match find_plan.index_scan_filter() {
+        Some(index_scan_filter) if !index_scan_filter.filters().is_empty() => Box::new(
+            FilteredStream::new(stream, FieldExpiryFilter::index_recheck(index_scan_filter.filters())),
+        ),
+        _ => stream,
+    }
 }
 
 #[cfg(test)]
@@ -455,6 +845,24 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_count() {
+        let read_operations = setup_read_operations();
+        for i in 0..5 {
+            let mut document = Document::new();
+            document.put("seq", Value::from(i)).unwrap();
+            let id = document.id().unwrap();
+            read_operations
+                .nitrite_map
+                .put(Value::NitriteId(id), Value::Document(document))
+                .unwrap();
+        }
+
+        assert_eq!(read_operations.count(all()).unwrap(), 5);
+        assert_eq!(read_operations.count(field("seq").gte(3)).unwrap(), 2);
+        assert_eq!(read_operations.count(field("seq").eq(9)).unwrap(), 0);
+    }
+
     #[test]
     fn test_get_by_id_not_found() {
         let read_operations = setup_read_operations();
@@ -569,7 +977,7 @@ mod tests {
             .find_optimizer
             .create_find_plan(&filter, &find_options, &[index_descriptor])
             .unwrap();
-        let result = inner.create_cursor(&find_plan);
+        let result = inner.create_cursor(&find_plan, None, None);
         assert!(result.is_ok());
     }
 
@@ -588,7 +996,7 @@ mod tests {
             .find_optimizer
             .create_find_plan(&filter, &find_options, &[index_descriptor])
             .unwrap();
-        let result = inner.find_suitable_iter(&find_plan, &mut None);
+        let result = inner.find_suitable_iter(&find_plan, None, None, &mut None);
         assert!(result.is_ok());
     }
 
@@ -605,7 +1013,7 @@ mod tests {
         }
         // Actually, for empty we just don't add any
 
-        let result = inner.find_suitable_iter(&find_plan, &mut None);
+        let result = inner.find_suitable_iter(&find_plan, None, None, &mut None);
         assert!(result.is_ok());
     }
 
@@ -621,7 +1029,7 @@ mod tests {
         find_plan.add_sub_plan(sub_plan1);
         find_plan.add_sub_plan(sub_plan2);
 
-        let result = inner.find_suitable_iter(&find_plan, &mut None);
+        let result = inner.find_suitable_iter(&find_plan, None, None, &mut None);
         assert!(result.is_ok());
     }
 
@@ -637,7 +1045,7 @@ mod tests {
         }
 
         // This should not panic with the fix (using if-let instead of multiple unwraps)
-        let result = inner.find_suitable_iter(&find_plan, &mut None);
+        let result = inner.find_suitable_iter(&find_plan, None, None, &mut None);
         assert!(result.is_ok());
     }
 
@@ -651,7 +1059,7 @@ mod tests {
         // Create a minimal find plan for fast path
         let find_plan = FindPlan::new();
 
-        let result = inner.create_cursor(&find_plan);
+        let result = inner.create_cursor(&find_plan, None, None);
         assert!(result.is_ok());
 
         let cursor = result.unwrap();
@@ -668,7 +1076,7 @@ mod tests {
         find_plan.set_skip(10);
         find_plan.set_limit(5);
 
-        let result = inner.create_cursor(&find_plan);
+        let result = inner.create_cursor(&find_plan, None, None);
         assert!(result.is_ok());
     }
 
@@ -710,10 +1118,10 @@ mod tests {
         // Test that cursor creation doesn't create redundant intermediate objects
         let find_plan = FindPlan::new();
 
-        let cursor1 = inner.create_cursor(&find_plan);
+        let cursor1 = inner.create_cursor(&find_plan, None, None);
         assert!(cursor1.is_ok());
 
-        let cursor2 = inner.create_cursor(&find_plan);
+        let cursor2 = inner.create_cursor(&find_plan, None, None);
         assert!(cursor2.is_ok());
 
         // Both should be valid
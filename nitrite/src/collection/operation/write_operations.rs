@@ -1,10 +1,12 @@
 use super::{
-    index_writer::DocumentIndexWriter, read_operations::ReadOperations, write_result::WriteResult,
+    history_operations::HistoryOperations, index_writer::DocumentIndexWriter,
+    options_operations::OptionsOperations, read_operations::ReadOperations,
+    write_result::{WriteRecorder, WriteResult},
 };
 use crate::{
     collection::{
-        CollectionEventInfo, CollectionEventListener, CollectionEvents, Document, FindOptions, NitriteId, UpdateOptions
-    }, common::get_current_time_or_zero, errors::{ErrorKind, NitriteError, NitriteResult}, filter::Filter, get_current_time, store::{NitriteMap, NitriteMapProvider}, Key, NitriteEventBus, ProcessorChain, ProcessorProvider, Value, DOC_ID, DOC_MODIFIED, DOC_REVISION, DOC_SOURCE, REPLICATOR
+        CollectionEventInfo, CollectionEventListener, CollectionEvents, Document, FindOptions, NitriteId, ReferenceRegistry, UpdateOptions
+    }, common::{content_hash_field, expiry_field, get_current_time_or_zero, is_reserved_field, modified_field, revision_field, source_field}, errors::{ErrorKind, NitriteError, NitriteResult}, filter::Filter, get_current_time, store::{NitriteMap, NitriteMapProvider}, Key, NitriteEventBus, ProcessorChain, ProcessorProvider, ReadExecutor, Value, DOC_ID, DOC_MODIFIED, DOC_REVISION, DOC_SOURCE, FIELD_SEPARATOR, REPLICATOR
 };
 use std::sync::Arc;
 
@@ -15,12 +17,17 @@ pub(crate) struct WriteOperations {
 
 impl WriteOperations {
     /// Creates a new WriteOperations instance with the required components.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         document_index_writer: DocumentIndexWriter,
         read_operations: ReadOperations,
         event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
         nitrite_map: NitriteMap,
         processor_chain: ProcessorChain,
+        history: HistoryOperations,
+        options: OptionsOperations,
+        collection_name: &str,
+        references: ReferenceRegistry,
     ) -> Self {
         let inner = WriteOperationsInner::new(
             document_index_writer,
@@ -28,6 +35,10 @@ impl WriteOperations {
             event_bus,
             nitrite_map,
             processor_chain,
+            history,
+            options,
+            collection_name,
+            references,
         );
 
         Self {
@@ -76,13 +87,21 @@ impl WriteOperations {
     /// Inserts a single document into the collection.
     pub fn insert(&self, document: Document) -> NitriteResult<WriteResult> {
         // `move` lets the document be handed to the atomic scope without an extra clone.
-        self.with_atomic(move || self.inner.insert(document))
+        self.with_atomic(move || {
+            let result = self.inner.insert(document)?;
+            self.inner.evict_if_capped()?;
+            Ok(result)
+        })
     }
 
     /// Inserts multiple documents into the collection using optimized batch operations.
     pub fn insert_batch(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
         // `move` hands the whole batch to the atomic scope without cloning every document.
-        self.with_atomic(move || self.inner.insert_batch(documents))
+        self.with_atomic(move || {
+            let result = self.inner.insert_batch(documents)?;
+            self.inner.evict_if_capped()?;
+            Ok(result)
+        })
     }
 
     /// Updates documents matching a filter with the provided update fields.
@@ -92,7 +111,11 @@ impl WriteOperations {
         update: &Document,
         update_options: &UpdateOptions,
     ) -> NitriteResult<WriteResult> {
-        self.with_atomic(move || self.inner.update(filter, update, update_options))
+        self.with_atomic(move || {
+            let result = self.inner.update(filter, update, update_options)?;
+            self.inner.evict_if_capped()?;
+            Ok(result)
+        })
     }
 
     /// Updates a document directly by its NitriteId without filter-based lookup.
@@ -102,7 +125,21 @@ impl WriteOperations {
         update: &Document,
         insert_if_absent: bool,
     ) -> NitriteResult<WriteResult> {
-        self.with_atomic(move || self.inner.update_by_id(id, update, insert_if_absent))
+        self.with_atomic(move || {
+            let result = self.inner.update_by_id(id, update, insert_if_absent)?;
+            self.inner.evict_if_capped()?;
+            Ok(result)
+        })
+    }
+
+    /// Inserts a document, or updates the stored document with its id unless that would
+    /// not change its content.
+    pub fn insert_if_changed(&self, document: Document) -> NitriteResult<WriteResult> {
+        self.with_atomic(move || {
+            let result = self.inner.insert_if_changed(document)?;
+            self.inner.evict_if_capped()?;
+            Ok(result)
+        })
     }
 
     /// Removes documents matching a filter.
@@ -110,10 +147,47 @@ impl WriteOperations {
         self.with_atomic(move || self.inner.remove(filter, just_once))
     }
 
+    /// Adds `delta` to the numeric field `field` of the document `id` and returns the new
+    /// value of the field.
+    pub fn increment(&self, id: &NitriteId, field: &str, delta: &Value) -> NitriteResult<Value> {
+        self.with_atomic(|| self.inner.increment(id, field, delta))
+    }
+
     /// Removes a specific document from the collection.
     pub fn remove_document(&self, document: &Document) -> NitriteResult<WriteResult> {
         self.with_atomic(|| self.inner.remove_document(document))
     }
+
+    /// Removes expired fields from storage and from the indexes, one document per atomic
+    /// scope, and returns the number of documents changed.
+    pub fn sweep_expired_fields(&self) -> NitriteResult<u64> {
+        let expiry_field = expiry_field();
+        let now = get_current_time_or_zero() as i64;
+
+        let mut nitrite_ids = Vec::new();
+        for entry in self.inner.nitrite_map.entries()? {
+            let (key, value) = entry?;
+            if let (Value::NitriteId(nitrite_id), Value::Document(document)) = (key, value) {
+                if !document.expired_fields(&expiry_field, now)?.is_empty() {
+                    nitrite_ids.push(nitrite_id);
+                }
+            }
+        }
+
+        let mut swept = 0;
+        for nitrite_id in nitrite_ids {
+            if self.with_atomic(|| self.inner.sweep_document(&nitrite_id, &expiry_field, now))? {
+                swept += 1;
+            }
+        }
+        Ok(swept)
+    }
+
+    /// Removes the documents whose id lies between `start` and `end`, both inclusive, in
+    /// one atomic scope and returns the number of documents removed.
+    pub fn remove_id_range(&self, start: &NitriteId, end: &NitriteId) -> NitriteResult<u64> {
+        self.with_atomic(|| self.inner.remove_id_range(start, end))
+    }
 }
 
 /// Inner implementation of write operations containing the actual business logic.
@@ -123,15 +197,24 @@ struct WriteOperationsInner {
     event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
     nitrite_map: NitriteMap,
     processor_chain: ProcessorChain,
+    history: HistoryOperations,
+    options: OptionsOperations,
+    collection_name: String,
+    references: ReferenceRegistry,
 }
 
 impl WriteOperationsInner {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         document_index_writer: DocumentIndexWriter,
         read_operations: ReadOperations,
         event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
         nitrite_map: NitriteMap,
         processor_chain: ProcessorChain,
+        history: HistoryOperations,
+        options: OptionsOperations,
+        collection_name: &str,
+        references: ReferenceRegistry,
     ) -> Self {
         Self {
             document_index_writer,
@@ -139,6 +222,28 @@ impl WriteOperationsInner {
             event_bus,
             nitrite_map,
             processor_chain,
+            history,
+            options,
+            collection_name: collection_name.to_string(),
+            references,
+        }
+    }
+
+    /// Checks that a document about to be written references existing documents.
+    fn check_references(&self, document: &Document) -> NitriteResult<()> {
+        self.references
+            .check_write(&self.collection_name, document, &self.nitrite_map.get_store()?)
+    }
+
+    /// Returns the stored form of a document before it is overwritten, when revision
+    /// history or the cap or quota of the collection needs it.
+    fn previous_stored(&self, nitrite_id: &NitriteId) -> NitriteResult<Option<Document>> {
+        if !self.history.is_enabled() && !self.options.tracks_usage() {
+            return Ok(None);
+        }
+        match self.nitrite_map.get(&Value::NitriteId(*nitrite_id))? {
+            Some(Value::Document(doc)) => Ok(Some(doc)),
+            _ => Ok(None),
         }
     }
 
@@ -147,28 +252,32 @@ impl WriteOperationsInner {
     }
 
     pub fn insert_batch(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
+        let mut recorder = WriteRecorder::new(self.options.is_detailed());
+        self.insert_documents(documents, &mut recorder)?;
+        Ok(recorder.finish())
+    }
+
+    fn insert_documents(&self, documents: Vec<Document>, recorder: &mut WriteRecorder) -> NitriteResult<()> {
         if documents.is_empty() {
-            return Ok(WriteResult::new(vec![]));
+            return Ok(());
         }
         
         // For small batches, use simple sequential processing
         // (overhead of batch coordination exceeds benefits)
         if documents.len() <= 10 {
-            return self.insert_batch_sequential(documents);
+            return self.insert_batch_sequential(documents, recorder);
         }
         
         // For larger batches, use optimized batch insert with put_all
-        self.insert_batch_optimized(documents)
+        self.insert_batch_optimized(documents, recorder)
     }
     
     /// Sequential insert for small batches - simple and efficient for few documents
-    fn insert_batch_sequential(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
-        let mut nitrite_ids = Vec::with_capacity(documents.len());
+    fn insert_batch_sequential(&self, documents: Vec<Document>, recorder: &mut WriteRecorder) -> NitriteResult<()> {
         for document in documents {
-            let id = self.process_insert(document)?;
-            nitrite_ids.push(id);
+            self.process_insert(document, recorder)?;
         }
-        Ok(WriteResult::new(nitrite_ids))
+        Ok(())
     }
     
     /// Optimized batch insert using put_all for larger batches.
@@ -181,7 +290,7 @@ impl WriteOperationsInner {
     /// Note: Unique index constraint violations are detected during index writing (Phase 4).
     /// If a unique index violation occurs, all successfully indexed documents and stored
     /// documents are rolled back to maintain consistency.
-    fn insert_batch_optimized(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
+    fn insert_batch_optimized(&self, documents: Vec<Document>, recorder: &mut WriteRecorder) -> NitriteResult<()> {
         let batch_size = documents.len();
         
         // Phase 1: Prepare all documents and collect metadata
@@ -196,6 +305,9 @@ impl WriteOperationsInner {
             .collect();
         
         self.validate_no_duplicates(&keys)?;
+
+        let writes: Vec<_> = prepared.iter().map(|(_, processed, _, _)| (None, processed)).collect();
+        self.check_quota(&writes)?;
         
         // Collect all IDs upfront for potential rollback (put_all stores all at once)
         let all_ids: Vec<NitriteId> = prepared.iter()
@@ -218,12 +330,12 @@ impl WriteOperationsInner {
         // Phase 4: Write index entries and publish events
         // Track successfully indexed documents for potential rollback
         // Unique index constraint violations are detected here
-        let mut nitrite_ids = Vec::with_capacity(batch_size);
         let mut indexed_docs: Vec<Document> = Vec::with_capacity(batch_size);
         
         for (id, mut processed_doc, original_doc, source) in prepared {
             // Write index entries - this is where unique index violations are detected
-            if let Err(e) = self.document_index_writer.write_index_entry(&mut processed_doc) {
+            let indexed = recorder.time_index(|| self.document_index_writer.write_index_entry(&mut processed_doc));
+            if let Err(e) = indexed {
                 // Rollback: remove index entries for successfully indexed documents
                 self.rollback_batch_indexes(&indexed_docs);
                 // Rollback: remove ALL stored documents (put_all stored them all at once)
@@ -235,22 +347,25 @@ impl WriteOperationsInner {
                 ));
             }
             
+            self.history.record_write(&id, None, &processed_doc)?;
+            self.options.track_write(None, &processed_doc);
+
             // Track the indexed document for potential rollback. `processed_doc` is not used
             // afterwards, so move it in rather than cloning.
             indexed_docs.push(processed_doc);
             
+            recorder.inserted(id);
+
             // Publish event
-            let value = Value::Document(original_doc);
-            let event = CollectionEventInfo::new(Some(value), CollectionEvents::Insert, source);
+            let event = self.document_event(CollectionEvents::Insert, original_doc, source, id, None);
             if let Err(e) = self.event_bus.publish(event) {
                 log::warn!("Failed to publish insert event for {}: {}", id, e);
                 // Don't fail the operation for event publishing errors
+                recorder.failed(e);
             }
-            
-            nitrite_ids.push(id);
         }
         
-        Ok(WriteResult::new(nitrite_ids))
+        Ok(())
     }
     
     /// Prepares a document for insertion by setting metadata and processing.
@@ -270,29 +385,33 @@ impl WriteOperationsInner {
         let time = get_current_time_or_zero();
 
         if REPLICATOR.ne(&source) {
-            new_doc.remove(DOC_SOURCE)
+            new_doc.remove(&source_field())
                 .map_err(|e| NitriteError::new(
                     &format!("Failed to remove document source field during insert: {}", e),
                     e.kind().clone(),
                 ))?;
-            new_doc.put(DOC_REVISION, Value::I32(1))
+            new_doc.put(revision_field(), Value::I32(1))
                 .map_err(|e| NitriteError::new(
                     &format!("Failed to set document revision during insert: {}", e),
                     e.kind().clone(),
                 ))?;
-            new_doc.put(DOC_MODIFIED, Value::U128(time))
+            new_doc.put(modified_field(), Value::U128(time))
                 .map_err(|e| NitriteError::new(
                     &format!("Failed to set document modification time during insert: {}", e),
                     e.kind().clone(),
                 ))?;
         } else {
-            new_doc.remove(DOC_SOURCE)
+            new_doc.remove(&source_field())
                 .map_err(|e| NitriteError::new(
                     &format!("Failed to remove document source field during replication insert: {}", e),
                     e.kind().clone(),
                 ))?;
         }
 
+        self.options.stamp_content_hash(&mut new_doc)?;
+
+        self.options.validate(&new_doc)?;
+        self.check_references(&new_doc)?;
         let processed = self.processor_chain.process_before_write(new_doc.clone())
             .map_err(|e| NitriteError::new(
                 &format!("Failed to process document before write during insert: {}", e),
@@ -333,7 +452,15 @@ impl WriteOperationsInner {
                     .collect();
                 
                 handles.into_iter()
-                    .map(|h| h.join().unwrap())
+                    .map(|h| {
+                        h.join().unwrap_or_else(|_| {
+                            log::error!("Duplicate id check panicked");
+                            Err(NitriteError::new(
+                                "Duplicate id check panicked",
+                                ErrorKind::InternalError,
+                            ))
+                        })
+                    })
                     .collect()
             });
             
@@ -384,7 +511,7 @@ impl WriteOperationsInner {
         }
     }
     
-    fn process_insert(&self, document: Document) -> NitriteResult<NitriteId> {
+    fn process_insert(&self, document: Document, recorder: &mut WriteRecorder) -> NitriteResult<()> {
         let mut new_doc = document;
         let nitrite_id = new_doc.id()
             .map_err(|e| NitriteError::new(&format!("Failed to retrieve document ID during insert: {}", e), e.kind().clone()))?;
@@ -393,19 +520,24 @@ impl WriteOperationsInner {
         let time = get_current_time_or_zero();
 
         if REPLICATOR.ne(&source) {
-            new_doc.remove(DOC_SOURCE)
+            new_doc.remove(&source_field())
                 .map_err(|e| NitriteError::new(&format!("Failed to remove document source field during insert: {}", e), e.kind().clone()))?;
-            new_doc.put(DOC_REVISION, Value::I32(1))
+            new_doc.put(revision_field(), Value::I32(1))
                 .map_err(|e| NitriteError::new(&format!("Failed to set document revision during insert: {}", e), e.kind().clone()))?;
-            new_doc.put(DOC_MODIFIED, Value::U128(time))
+            new_doc.put(modified_field(), Value::U128(time))
                 .map_err(|e| NitriteError::new(&format!("Failed to set document modification time during insert: {}", e), e.kind().clone()))?;
         } else {
-            new_doc.remove(DOC_SOURCE)
+            new_doc.remove(&source_field())
                 .map_err(|e| NitriteError::new(&format!("Failed to remove document source field during replication insert: {}", e), e.kind().clone()))?;
         }
 
+        self.options.stamp_content_hash(&mut new_doc)?;
+
+        self.options.validate(&new_doc)?;
+        self.check_references(&new_doc)?;
         let mut processed = self.processor_chain.process_before_write(new_doc.clone())
             .map_err(|e| NitriteError::new(&format!("Failed to process document before write during insert: {}", e), e.kind().clone()))?;
+        self.check_quota(&[(None, &processed)])?;
         let existing = self.nitrite_map.put_if_absent(
             Value::NitriteId(nitrite_id),
             Value::Document(processed.clone()),
@@ -418,20 +550,22 @@ impl WriteOperationsInner {
                 ErrorKind::UniqueConstraintViolation,
             ));
         } else {
-            let result = self.document_index_writer.write_index_entry(&mut processed);
+            let result = recorder.time_index(|| self.document_index_writer.write_index_entry(&mut processed));
             if let Err(e) = result {
                 self.nitrite_map.remove(&Value::NitriteId(nitrite_id))
                     .map_err(|remove_err| NitriteError::new(&format!("Failed to rollback document storage after index write failure: {}", remove_err), remove_err.kind().clone()))?;
                 return Err(NitriteError::new(&format!("Failed to write index entries during insert: {}", e), e.kind().clone()));
             }
+            self.history.record_write(&nitrite_id, None, &processed)?;
+            self.options.track_write(None, &processed);
         }
 
-        let value = Value::Document(new_doc);
-        let event = CollectionEventInfo::new(Some(value), CollectionEvents::Insert, source);
+        let event = self.document_event(CollectionEvents::Insert, new_doc, source, nitrite_id, None);
         self.event_bus.publish(event)
             .map_err(|e| NitriteError::new(&format!("Failed to publish insert event: {}", e), e.kind().clone()))?;
         
-        Ok(nitrite_id)
+        recorder.inserted(nitrite_id);
+        Ok(())
     }
 
     pub fn update(
@@ -441,17 +575,17 @@ impl WriteOperationsInner {
         update_options: &UpdateOptions,
     ) -> NitriteResult<WriteResult> {
         let cursor = self.read_operations.find(filter, &FindOptions::new())?;
-        let mut nitrite_ids = Vec::new();
+        let mut recorder = WriteRecorder::new(self.options.is_detailed());
 
         let mut document = update.clone();
         document.remove(DOC_ID)?;
 
         if REPLICATOR.ne(&document.source()?) {
-            document.remove(DOC_REVISION)?;
+            document.remove(&revision_field())?;
         }
 
         if document.is_empty() {
-            return Ok(WriteResult::new(nitrite_ids));
+            return Ok(recorder.finish());
         }
 
         let mut count = 0usize;
@@ -469,7 +603,7 @@ impl WriteOperationsInner {
 
             // Process in batches for better performance
             if docs.len() >= batch_size {
-                self.process_update_batch(&document, &mut nitrite_ids, docs)?;
+                self.process_update_batch(&document, &mut recorder, docs)?;
                 docs = Vec::with_capacity(batch_size);
                 
                 // Dynamically adjust batch size based on performance
@@ -483,34 +617,32 @@ impl WriteOperationsInner {
 
         // Process remaining docs
         if !docs.is_empty() {
-            self.process_update_batch(&document, &mut nitrite_ids, docs)?;
+            self.process_update_batch(&document, &mut recorder, docs)?;
         }
 
         if count == 0 && update_options.is_insert_if_absent() {
-            return self.insert(update.clone());
+            self.insert_documents(vec![update.clone()], &mut recorder)?;
         }
 
-        Ok(WriteResult::new(nitrite_ids))
+        Ok(recorder.finish())
     }
     
     fn process_update_batch(
         &self, 
         update_doc: &Document, 
-        nitrite_ids: &mut Vec<NitriteId>, 
+        recorder: &mut WriteRecorder, 
         docs: Vec<Document>
     ) -> NitriteResult<()> {
         // For small batches, use simple sequential processing
         if docs.len() <= 10 {
             for doc in docs {
-                if let Some(id) = self.process_single_update(doc, update_doc)? {
-                    nitrite_ids.push(id);
-                }
+                self.process_single_update(doc, update_doc, recorder)?;
             }
             return Ok(());
         }
         
         // For larger batches, use optimized batch update with put_all
-        self.process_update_batch_optimized(update_doc, nitrite_ids, docs)
+        self.process_update_batch_optimized(update_doc, recorder, docs)
     }
     
     /// Optimized batch update using put_all for larger batches.
@@ -522,7 +654,7 @@ impl WriteOperationsInner {
     fn process_update_batch_optimized(
         &self,
         update_doc: &Document,
-        nitrite_ids: &mut Vec<NitriteId>,
+        recorder: &mut WriteRecorder,
         docs: Vec<Document>,
     ) -> NitriteResult<()> {
         let source = update_doc.source()?;
@@ -540,16 +672,28 @@ impl WriteOperationsInner {
             if REPLICATOR.ne(&source) {
                 new_doc.merge(update_doc)?;
                 let revision = new_doc.revision()?;
-                new_doc.put(DOC_REVISION, Value::I32(revision + 1))?;
-                new_doc.put(DOC_MODIFIED, Value::U128(time))?;
+                new_doc.put(revision_field(), Value::I32(revision + 1))?;
+                new_doc.put(modified_field(), Value::U128(time))?;
             } else {
                 new_doc.merge(update_doc)?;
             }
             
+            self.options.stamp_content_hash(&mut new_doc)?;
+            
+            self.options.validate(&new_doc)?;
+            self.check_references(&new_doc)?;
             let processed = self.processor_chain.process_before_write(new_doc.clone())?;
             prepared.push((nitrite_id, old_doc, new_doc, processed));
         }
         
+        let previous: Vec<Option<Document>> = prepared.iter()
+            .map(|(id, _, _, _)| self.previous_stored(id))
+            .collect::<NitriteResult<Vec<_>>>()?;
+        let writes: Vec<_> = prepared.iter().zip(&previous)
+            .map(|((_, _, _, processed), previous)| (previous.as_ref(), processed))
+            .collect();
+        self.check_quota(&writes)?;
+
         // Phase 2: Batch write using put_all
         let entries: Vec<(Key, Value)> = prepared.iter()
             .map(|(id, _, _, processed)| {
@@ -563,32 +707,30 @@ impl WriteOperationsInner {
         // Track successfully updated documents for rollback
         let mut updated_indexes: Vec<(NitriteId, Document, Document)> = Vec::with_capacity(prepared.len());
         
-        for (id, mut old_doc, new_doc, mut processed) in prepared {
+        for ((id, mut old_doc, new_doc, mut processed), previous) in prepared.into_iter().zip(previous) {
             // Update index entries
-            let result = self.document_index_writer.update_index_entry(
-                &mut old_doc,
-                &mut processed,
-                update_doc,
-            );
+            let result = recorder.time_index(|| self.document_index_writer.update_index_entry(&mut old_doc, &mut processed));
             
             if let Err(e) = result {
                 // Rollback: restore old documents for all updates
-                self.rollback_batch_update(&updated_indexes, &id, &old_doc, update_doc)?;
+                self.rollback_batch_update(&updated_indexes, &id, &old_doc)?;
                 return Err(e);
             }
             
+            self.history.record_write(&id, previous.as_ref(), &processed)?;
+            self.options.track_write(previous.as_ref(), &processed);
+
+            let event = self.document_event(CollectionEvents::Update, new_doc, source.clone(), id, Some(&old_doc));
+
             // Track for potential rollback
             updated_indexes.push((id, old_doc, processed.clone()));
             
+            recorder.updated(id, update_doc.size() > 0);
+
             // Publish event
-            let value = Value::Document(new_doc);
-            let event = CollectionEventInfo::new(Some(value), CollectionEvents::Update, source.clone());
             if let Err(e) = self.event_bus.publish(event) {
                 log::warn!("Failed to publish update event for {}: {}", id, e);
-            }
-            
-            if update_doc.size() > 0 {
-                nitrite_ids.push(id);
+                recorder.failed(e);
             }
         }
         
@@ -601,7 +743,6 @@ impl WriteOperationsInner {
         updated_indexes: &[(NitriteId, Document, Document)],
         failed_id: &NitriteId,
         failed_old_doc: &Document,
-        update_doc: &Document,
     ) -> NitriteResult<()> {
         // Restore the failed document's old state
         self.nitrite_map.put(
@@ -621,11 +762,7 @@ impl WriteOperationsInner {
             // Restore index entries
             let mut old_doc_clone = old_doc.clone();
             let mut processed_clone = processed.clone();
-            if let Err(e) = self.document_index_writer.update_index_entry(
-                &mut processed_clone,
-                &mut old_doc_clone,
-                update_doc,
-            ) {
+            if let Err(e) = self.document_index_writer.update_index_entry(&mut processed_clone, &mut old_doc_clone) {
                 log::error!("Failed to rollback index entry for {}: {}", id, e);
             }
         }
@@ -640,7 +777,7 @@ impl WriteOperationsInner {
         Ok(())
     }
     
-    fn process_single_update(&self, doc: Document, update_doc: &Document) -> NitriteResult<Option<NitriteId>> {
+    fn process_single_update(&self, doc: Document, update_doc: &Document, recorder: &mut WriteRecorder) -> NitriteResult<()> {
         let mut new_doc = doc.clone();
         let mut old_doc = doc;
         let source = update_doc.source()?;
@@ -648,50 +785,136 @@ impl WriteOperationsInner {
 
         let nitrite_id = new_doc.id()?;
 
+        // a field set again without a deadline of its own no longer expires
+        for field in update_doc.fields() {
+            if update_doc.field_expiry(&field)?.is_none() && new_doc.field_expiry(&field)?.is_some() {
+                new_doc.clear_field_expiry(&field)?;
+            }
+        }
+
         if REPLICATOR.ne(&source) {
             new_doc.merge(update_doc)?;
 
             let revision = new_doc.revision()?;
-            new_doc.put(DOC_REVISION, Value::I32(revision + 1))?;
-            new_doc.put(DOC_MODIFIED, Value::U128(time))?;
+            new_doc.put(revision_field(), Value::I32(revision + 1))?;
+            new_doc.put(modified_field(), Value::U128(time))?;
         } else {
             new_doc.merge(update_doc)?;
         }
 
+        self.options.stamp_content_hash(&mut new_doc)?;
+
+        self.options.validate(&new_doc)?;
+        self.check_references(&new_doc)?;
         let mut processed = self.processor_chain.process_before_write(new_doc.clone())?;
+        let previous = self.previous_stored(&nitrite_id)?;
+        self.check_quota(&[(previous.as_ref(), &processed)])?;
         self.nitrite_map.put(
             Value::NitriteId(nitrite_id),
             Value::Document(processed.clone()),
         )?;
 
-        let result = self.document_index_writer.update_index_entry(
-            &mut old_doc,
-            &mut processed,
-            update_doc,
-        );
+        let result = recorder.time_index(|| self.document_index_writer.update_index_entry(&mut old_doc, &mut processed));
         
         if let Err(e) = result {
             self.nitrite_map.put(
                 Value::NitriteId(nitrite_id),
                 Value::Document(old_doc.clone()),
             )?;
-            self.document_index_writer.update_index_entry(
-                &mut processed,
-                &mut old_doc,
-                update_doc,
-            )?;
+            self.document_index_writer.update_index_entry(&mut processed, &mut old_doc)?;
             return Err(e);
         }
+        self.history.record_write(&nitrite_id, previous.as_ref(), &processed)?;
+        self.options.track_write(previous.as_ref(), &processed);
 
-        let value = Value::Document(new_doc.clone());
-        let event = CollectionEventInfo::new(Some(value), CollectionEvents::Update, source);
+        let event = self.document_event(CollectionEvents::Update, new_doc, source, nitrite_id, Some(&old_doc));
         self.event_bus.publish(event)?;
 
-        if update_doc.size() > 0 {
-            Ok(Some(nitrite_id))
-        } else {
-            Ok(None)
+        recorder.updated(nitrite_id, update_doc.size() > 0);
+        Ok(())
+    }
+
+    /// Adds `delta` to a counter field in one step: the stored document is changed in place
+    /// and the indexes are only touched if one of them covers the field. A missing field
+    /// counts as zero.
+    pub fn increment(&self, id: &NitriteId, field: &str, delta: &Value) -> NitriteResult<Value> {
+        // with a metadata prefix like `_meta.`, the metadata sits under one top-level field
+        let root = FIELD_SEPARATOR.read_with(|separator| {
+            field.split(separator.as_str()).next().unwrap_or(field).to_string()
+        });
+        if is_reserved_field(field) || is_reserved_field(&root) {
+            log::error!("Cannot increment the reserved field {}", field);
+            return Err(NitriteError::new(
+                &format!("Cannot increment the reserved field {}", field),
+                ErrorKind::InvalidFieldName,
+            ));
+        }
+
+        let stored = match self.nitrite_map.get(&Value::NitriteId(*id))? {
+            Some(Value::Document(document)) => document,
+            Some(_) => {
+                log::error!("Expected Document value in collection store for ID {:?}", id);
+                return Err(NitriteError::new(
+                    "Invalid value type in collection store",
+                    ErrorKind::ValidationError,
+                ));
+            }
+            None => {
+                log::error!("Document {} not found in collection {}", id, self.collection_name);
+                return Err(NitriteError::new(
+                    &format!("Document {} not found in collection {}", id, self.collection_name),
+                    ErrorKind::NotFound,
+                ));
+            }
+        };
+
+        let old_doc = self.processor_chain.process_after_read(stored.clone())?;
+        let counter = add_numbers(&old_doc.get(field)?, delta).inspect_err(|e| {
+            log::error!("Cannot increment field {} of document {}: {}", field, id, e);
+        })?;
+
+        let mut new_doc = old_doc.clone();
+        new_doc.put(field, counter.clone())?;
+        let revision = new_doc.revision()?;
+        new_doc.put(revision_field(), Value::I32(revision + 1))?;
+        new_doc.put(modified_field(), Value::U128(get_current_time_or_zero()))?;
+        self.options.stamp_content_hash(&mut new_doc)?;
+
+        let mut processed = self.processor_chain.process_before_write(new_doc.clone())?;
+        let previous = (self.history.is_enabled() || self.options.tracks_usage()).then(|| stored.clone());
+        self.check_quota(&[(previous.as_ref(), &processed)])?;
+        self.nitrite_map.put(Value::NitriteId(*id), Value::Document(processed.clone()))?;
+
+        if self.is_indexed(field)? {
+            let mut old_stored = stored;
+            if let Err(e) = self.document_index_writer.update_index_entry(&mut old_stored, &mut processed) {
+                self.nitrite_map.put(Value::NitriteId(*id), Value::Document(old_stored))?;
+                return Err(e);
+            }
         }
+        self.history.record_write(id, previous.as_ref(), &processed)?;
+        self.options.track_write(previous.as_ref(), &processed);
+
+        let event = self.document_event(CollectionEvents::Update, new_doc, String::new(), *id, Some(&old_doc));
+        self.event_bus.publish(event)?;
+        Ok(counter)
+    }
+
+    /// Returns `true` if an index of the collection covers `field`, an embedded field of
+    /// it or a field it is embedded in.
+    fn is_indexed(&self, field: &str) -> NitriteResult<bool> {
+        let separator = FIELD_SEPARATOR.read_with(|separator| separator.clone());
+        let covers = |indexed: &str| {
+            indexed == field
+                || field.starts_with(&format!("{}{}", indexed, separator))
+                || indexed.starts_with(&format!("{}{}", field, separator))
+        };
+        Ok(self
+            .document_index_writer
+            .index_operation()
+            .list_indexes()?
+            .iter()
+            .any(|descriptor| descriptor.index_fields().field_names().iter().any(|indexed| covers(indexed))))
     }
 
     /// Updates a document directly by its NitriteId without filter-based lookup.
@@ -711,6 +934,8 @@ impl WriteOperationsInner {
         update: &Document,
         insert_if_absent: bool,
     ) -> NitriteResult<WriteResult> {
+        let mut recorder = WriteRecorder::new(self.options.is_detailed());
+
         // Get the existing document directly by ID (O(1) lookup)
         let existing = self.nitrite_map.get(&Value::NitriteId(*id))?;
         
@@ -728,35 +953,75 @@ impl WriteOperationsInner {
                 };
                 
                 // Process through the existing update logic
-                let mut nitrite_ids = Vec::new();
-                if let Some(updated_id) = self.process_single_update(doc, update)? {
-                    nitrite_ids.push(updated_id);
-                }
-                Ok(WriteResult::new(nitrite_ids))
+                self.process_single_update(doc, update, &mut recorder)?;
+                Ok(recorder.finish())
             }
             None => {
                 if insert_if_absent {
                     // Insert the document with the specified ID
                     let mut new_doc = update.clone();
                     new_doc.put(DOC_ID, Value::NitriteId(*id))?;
-                    self.insert(new_doc)
-                } else {
-                    // Document not found and insert_if_absent is false
-                    Ok(WriteResult::new(vec![]))
+                    self.insert_documents(vec![new_doc], &mut recorder)?;
                 }
+                // Document not found and insert_if_absent is false
+                Ok(recorder.finish())
+            }
+        }
+    }
+
+    /// Writes a document unless the stored document with its id already holds its values:
+    /// the document is merged into the stored one, as by an update, and nothing is written
+    /// if the content hash of the stored document stays the same.
+    pub fn insert_if_changed(&self, document: Document) -> NitriteResult<WriteResult> {
+        let mut recorder = WriteRecorder::new(self.options.is_detailed());
+        let mut document = document;
+        if !document.has_id() {
+            self.insert_documents(vec![document], &mut recorder)?;
+            return Ok(recorder.finish());
+        }
+
+        let nitrite_id = document.id()?;
+        let stored = match self.nitrite_map.get(&Value::NitriteId(nitrite_id))? {
+            Some(Value::Document(stored)) => stored,
+            Some(_) => {
+                log::error!("Expected Document value in collection store for ID {:?}", nitrite_id);
+                return Err(NitriteError::new(
+                    "Invalid value type in collection store",
+                    ErrorKind::ValidationError,
+                ));
             }
+            None => {
+                self.insert_documents(vec![document], &mut recorder)?;
+                return Ok(recorder.finish());
+            }
+        };
+
+        if REPLICATOR.ne(&document.source()?) {
+            document.remove(&revision_field())?;
+        }
+        let current = self.processor_chain.process_after_read(stored.clone())?;
+        // a collection storing the hash has it already, only older documents need hashing
+        let current_hash = match current.get(&content_hash_field())? {
+            Value::U128(hash) => hash,
+            _ => current.content_hash(),
+        };
+        let mut merged = current.clone();
+        merged.merge(&document)?;
+        if merged.content_hash() != current_hash {
+            self.process_single_update(stored, &document, &mut recorder)?;
         }
+        Ok(recorder.finish())
     }
 
     pub fn remove(&self, filter: Filter, just_once: bool) -> NitriteResult<WriteResult> {
         let cursor = self.read_operations.find(filter, &FindOptions::new())?;
-        let mut nitrite_ids = Vec::new();
+        let mut recorder = WriteRecorder::new(self.options.is_detailed());
 
         for doc_result in cursor {
             let doc = doc_result?;
 
             let processed = self.processor_chain.process_before_write(doc.clone())?;
-            let event = self.remove_internal(processed.clone(), &mut nitrite_ids)?;
+            let event = self.remove_internal(processed.clone(), &mut recorder)?;
 
             if let Some(event) = event {
                 self.event_bus.publish(event)?;
@@ -767,27 +1032,29 @@ impl WriteOperationsInner {
             }
         }
 
-        Ok(WriteResult::new(nitrite_ids))
+        Ok(recorder.finish())
     }
 
     pub fn remove_document(&self, document: &Document) -> NitriteResult<WriteResult> {
-        let mut nitrite_ids = Vec::new();
-        let event = self.remove_internal(document.clone(), &mut nitrite_ids)?;
+        let mut recorder = WriteRecorder::new(self.options.is_detailed());
+        let event = self.remove_internal(document.clone(), &mut recorder)?;
 
         if let Some(event) = event {
             event.set_originator(document.source()?);
             self.event_bus.publish(event)?;
         }
 
-        Ok(WriteResult::new(nitrite_ids))
+        Ok(recorder.finish())
     }
 
     fn remove_internal(
         &self,
         mut document: Document,
-        nitrite_ids: &mut Vec<NitriteId>,
+        recorder: &mut WriteRecorder,
     ) -> NitriteResult<Option<CollectionEventInfo>> {
         let nitrite_id = document.id()?;
+        let store = self.nitrite_map.get_store()?;
+        self.references.check_remove(&self.collection_name, &nitrite_id, &store)?;
         let document = self
             .nitrite_map
             .remove(&Key::NitriteId(nitrite_id))?;
@@ -806,18 +1073,267 @@ impl WriteOperationsInner {
         };
 
         let remove_at = get_current_time_or_zero();
-        self.document_index_writer
-            .remove_index_entry(&mut document)?;
-        nitrite_ids.push(nitrite_id);
+        recorder.time_index(|| self.document_index_writer.remove_index_entry(&mut document))?;
+        recorder.removed(nitrite_id);
+        self.references.cascade_remove(&self.collection_name, &nitrite_id, &store)?;
 
         let revision = document.revision()? + 1;
-        document.put(DOC_REVISION, Value::I32(revision))?;
-        document.put(DOC_MODIFIED, Value::U128(remove_at))?;
-
-        let value = Value::Document(document.clone());
-        let event = CollectionEventInfo::new(Some(value), CollectionEvents::Remove, document.source()?);
+        self.history.record_removal(&nitrite_id, &document, revision, remove_at)?;
+        self.options.record_removal(&nitrite_id, &document)?;
+        self.options.track_removal(&document);
+        let before = self.options.has_event_images().then(|| document.clone());
+        document.put(revision_field(), Value::I32(revision))?;
+        document.put(modified_field(), Value::U128(remove_at))?;
+
+        let source = document.source()?;
+        let event = self.document_event(CollectionEvents::Remove, document, source, nitrite_id, before.as_ref());
         Ok(Some(event))
     }
+
+    /// Removes an id range of documents with a single range removal of the collection map.
+    ///
+    /// The stored documents are only read when something besides the map keeps track of
+    /// them: an index, the revision history, soft delete, a cap or quota, or a reference to
+    /// the collection. Like `clear`, the purge publishes no events.
+    fn remove_id_range(&self, start: &NitriteId, end: &NitriteId) -> NitriteResult<u64> {
+        if start > end {
+            return Ok(0);
+        }
+
+        let start_key = Key::NitriteId(*start);
+        let end_key = Key::NitriteId(end.next_id());
+        if self.tracks_removed_documents()? {
+            let store = self.nitrite_map.get_store()?;
+            let remove_at = get_current_time_or_zero();
+            let mut next = self.nitrite_map.ceiling_key(&start_key)?;
+            while let Some(key) = next.filter(|key| *key < end_key) {
+                next = self.nitrite_map.higher_key(&key)?;
+                let (Key::NitriteId(nitrite_id), Some(Value::Document(mut document))) =
+                    (&key, self.nitrite_map.get(&key)?)
+                else {
+                    continue;
+                };
+
+                self.references.check_remove(&self.collection_name, nitrite_id, &store)?;
+                self.document_index_writer.remove_index_entry(&mut document)?;
+                self.references.cascade_remove(&self.collection_name, nitrite_id, &store)?;
+                let revision = document.revision()? + 1;
+                self.history.record_removal(nitrite_id, &document, revision, remove_at)?;
+                self.options.record_removal(nitrite_id, &document)?;
+                self.options.track_removal(&document);
+            }
+        }
+        self.nitrite_map.remove_range(&start_key, &end_key)
+    }
+
+    /// Returns `true` if removing a document has to update more than the collection map.
+    fn tracks_removed_documents(&self) -> NitriteResult<bool> {
+        Ok(self.history.is_enabled()
+            || self.options.is_soft_delete()
+            || self.options.tracks_usage()
+            || self.references.is_referenced(&self.collection_name)
+            || !self.document_index_writer.index_operation().list_indexes()?.is_empty())
+    }
+
+    /// Removes the expired fields of one stored document. The sweep is not a user write,
+    /// so the revision and modification time stay as they are and no history version is
+    /// recorded.
+    fn sweep_document(&self, nitrite_id: &NitriteId, expiry_field: &str, now: i64) -> NitriteResult<bool> {
+        let mut old_doc = match self.nitrite_map.get(&Value::NitriteId(*nitrite_id))? {
+            Some(Value::Document(document)) => document,
+            _ => return Ok(false),
+        };
+        let expired = old_doc.expired_fields(expiry_field, now)?;
+        if expired.is_empty() {
+            return Ok(false);
+        }
+
+        let mut new_doc = old_doc.clone();
+        new_doc.remove_expired_fields(expiry_field, &expired)?;
+        let hash_field = content_hash_field();
+        if !new_doc.get(&hash_field)?.is_null() {
+            // the hash covers the document as written, before processing
+            let mut written = self.processor_chain.process_after_read(new_doc.clone())?;
+            self.options.stamp_content_hash(&mut written)?;
+            match written.get(&hash_field)? {
+                Value::Null => new_doc.remove(&hash_field)?,
+                hash => new_doc.put(hash_field, hash)?,
+            }
+        }
+
+        let previous = self.previous_stored(nitrite_id)?;
+        self.nitrite_map.put(
+            Value::NitriteId(*nitrite_id),
+            Value::Document(new_doc.clone()),
+        )?;
+        self.document_index_writer
+            .update_index_entry(&mut old_doc, &mut new_doc)?;
+        self.options.track_write(previous.as_ref(), &new_doc);
+
+        let source = new_doc.source()?;
+        let event = self.document_event(CollectionEvents::Update, new_doc, source, *nitrite_id, Some(&old_doc));
+        self.event_bus.publish(event)?;
+        Ok(true)
+    }
+
+    /// Creates the event of a write to the document `id`, whose item is `document`. The
+    /// images before and after the write are only kept if the collection asks for them;
+    /// the document is the image after inserts and updates.
+    fn document_event(
+        &self,
+        event_type: CollectionEvents,
+        document: Document,
+        source: String,
+        id: NitriteId,
+        before: Option<&Document>,
+    ) -> CollectionEventInfo {
+        let (before, after) = if self.options.has_event_images() {
+            let after = matches!(event_type, CollectionEvents::Insert | CollectionEvents::Update)
+                .then(|| document.clone());
+            (before.cloned(), after)
+        } else {
+            (None, None)
+        };
+        CollectionEventInfo::for_document(Some(Value::Document(document)), event_type, source, id, before, after)
+    }
+
+    /// Rejects writes that would take the collection over its quota, handing the first
+    /// document that does not fit to the quota hook.
+    fn check_quota(&self, writes: &[(Option<&Document>, &Document)]) -> NitriteResult<()> {
+        let (position, message) = match self.options.quota_breach(writes)? {
+            Some(breach) => breach,
+            None => return Ok(()),
+        };
+
+        self.options.quota_exceeded(writes[position].1);
+        log::error!("{}", message);
+        Err(NitriteError::new(&message, ErrorKind::QuotaExceeded))
+    }
+
+    /// Removes the oldest documents of a capped collection, in `NitriteId` order, until it
+    /// is back within its cap. Evicted documents are recorded in the revision history but
+    /// are not kept by soft delete.
+    fn evict_if_capped(&self) -> NitriteResult<()> {
+        while self.options.is_over_cap()? {
+            let key = match self.nitrite_map.first_key()? {
+                Some(key) => key,
+                None => break,
+            };
+            let mut document = match self.nitrite_map.remove(&key)? {
+                Some(Value::Document(document)) => document,
+                _ => {
+                    // the counted usage no longer matches the map, count it again next time
+                    self.options.reset_usage();
+                    break;
+                }
+            };
+
+            let nitrite_id = document.id()?;
+            let evict_at = get_current_time_or_zero();
+            self.document_index_writer.remove_index_entry(&mut document)?;
+            self.options.track_removal(&document);
+
+            let revision = document.revision()? + 1;
+            self.history.record_removal(&nitrite_id, &document, revision, evict_at)?;
+            let before = self.options.has_event_images().then(|| document.clone());
+            document.put(revision_field(), Value::I32(revision))?;
+            document.put(modified_field(), Value::U128(evict_at))?;
+
+            let source = document.source()?;
+            let event = CollectionEventInfo::for_eviction(Some(Value::Document(document)), source, nitrite_id, before);
+            if let Err(e) = self.event_bus.publish(event) {
+                log::warn!("Failed to publish evict event for {}: {}", nitrite_id, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds a number to the value of a counter field, keeping the type of the field. A missing
+/// field takes the type of `delta`.
+fn add_numbers(current: &Value, delta: &Value) -> NitriteResult<Value> {
+    if !delta.is_number() {
+        return Err(NitriteError::new(
+            &format!("Cannot increment by a non numeric value {}", delta),
+            ErrorKind::InvalidDataType,
+        ));
+    }
+
+    let overflow = || {
+        NitriteError::new(
+            &format!("Incrementing {} by {} overflows", current, delta),
+            ErrorKind::InvalidOperation,
+        )
+    };
+    match current {
+        Value::Null => Ok(delta.clone()),
+        Value::F32(value) => Ok(Value::F32(value + as_f64(delta) as f32)),
+        Value::F64(value) => Ok(Value::F64(value + as_f64(delta))),
+        current if current.is_integer() => {
+            let delta = match as_i128(delta) {
+                Some(delta) => delta,
+                None => {
+                    return Err(NitriteError::new(
+                        &format!("Cannot increment the integer {} by {}", current, delta),
+                        ErrorKind::InvalidDataType,
+                    ))
+                }
+            };
+            let sum = as_i128(current)
+                .and_then(|value| value.checked_add(delta))
+                .ok_or_else(overflow)?;
+            integer_like(current, sum).ok_or_else(overflow)
+        }
+        _ => Err(NitriteError::new(
+            &format!("Cannot increment the non numeric value {}", current),
+            ErrorKind::InvalidDataType,
+        )),
+    }
+}
+
+fn as_i128(value: &Value) -> Option<i128> {
+    match value {
+        Value::I8(v) => Some(*v as i128),
+        Value::U8(v) => Some(*v as i128),
+        Value::I16(v) => Some(*v as i128),
+        Value::U16(v) => Some(*v as i128),
+        Value::I32(v) => Some(*v as i128),
+        Value::U32(v) => Some(*v as i128),
+        Value::I64(v) => Some(*v as i128),
+        Value::U64(v) => Some(*v as i128),
+        Value::I128(v) => Some(*v),
+        Value::U128(v) => i128::try_from(*v).ok(),
+        Value::ISize(v) => Some(*v as i128),
+        Value::USize(v) => Some(*v as i128),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::F32(v) => *v as f64,
+        Value::F64(v) => *v,
+        other => as_i128(other).map_or(0.0, |v| v as f64),
+    }
+}
+
+/// Returns `value` as a value of the integer type of `like`, if it fits.
+fn integer_like(like: &Value, value: i128) -> Option<Value> {
+    match like {
+        Value::I8(_) => i8::try_from(value).ok().map(Value::I8),
+        Value::U8(_) => u8::try_from(value).ok().map(Value::U8),
+        Value::I16(_) => i16::try_from(value).ok().map(Value::I16),
+        Value::U16(_) => u16::try_from(value).ok().map(Value::U16),
+        Value::I32(_) => i32::try_from(value).ok().map(Value::I32),
+        Value::U32(_) => u32::try_from(value).ok().map(Value::U32),
+        Value::I64(_) => i64::try_from(value).ok().map(Value::I64),
+        Value::U64(_) => u64::try_from(value).ok().map(Value::U64),
+        Value::I128(_) => Some(Value::I128(value)),
+        Value::U128(_) => u128::try_from(value).ok().map(Value::U128),
+        Value::ISize(_) => isize::try_from(value).ok().map(Value::ISize),
+        Value::USize(_) => usize::try_from(value).ok().map(Value::USize),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -872,12 +1388,23 @@ mod tests {
             processor_chain.clone(),
         );
 
+        let history =
+            HistoryOperations::new("test_collection", nitrite_map.clone(), processor_chain.clone())
+                .unwrap();
+        let options =
+            OptionsOperations::new("test_collection", nitrite_map.clone(), processor_chain.clone())
+                .unwrap();
+
         WriteOperations::new(
             document_index_writer,
             read_operations,
             event_bus,
             nitrite_map,
             processor_chain,
+            history,
+            options,
+            "test_collection",
+            ReferenceRegistry::new(),
         )
     }
 
@@ -1013,8 +1540,8 @@ mod tests {
             docs.push(doc);
         }
         
-        let mut ids = Vec::new();
-        let result = inner.process_update_batch(&update_doc, &mut ids, docs);
+        let mut recorder = WriteRecorder::new(false);
+        let result = inner.process_update_batch(&update_doc, &mut recorder, docs);
         
         // Should complete without error
         assert!(result.is_ok());
@@ -1037,14 +1564,10 @@ mod tests {
             docs.push(doc);
         }
         
-        let mut ids = Vec::with_capacity(5);
-        let initial_capacity = ids.capacity();
-        
-        let result = inner.process_update_batch(&update_doc, &mut ids, docs);
+        let mut recorder = WriteRecorder::new(false);
+        let result = inner.process_update_batch(&update_doc, &mut recorder, docs);
         assert!(result.is_ok());
-        
-        // Capacity should remain efficient (no excessive reallocations)
-        assert!(ids.capacity() >= initial_capacity);
+        assert_eq!(recorder.finish().affected_nitrite_ids().len(), 5);
     }
 
     #[test]
@@ -1283,7 +1806,7 @@ mod tests {
         assert!(!id.to_string().is_empty());
         
         // Processed doc should have revision set (not Null)
-        let revision = processed.get(DOC_REVISION).unwrap();
+        let revision = processed.get(&revision_field()).unwrap();
         assert!(!matches!(revision, Value::Null));
         
         // Original should have the test field (not Null)
@@ -1503,11 +2026,11 @@ mod tests {
         let mut update_doc = Document::new();
         update_doc.put("batch_processed", Value::from(true)).unwrap();
         
-        let mut nitrite_ids = Vec::new();
-        let result = inner.process_update_batch_optimized(&update_doc, &mut nitrite_ids, docs);
+        let mut recorder = WriteRecorder::new(false);
+        let result = inner.process_update_batch_optimized(&update_doc, &mut recorder, docs);
         
         assert!(result.is_ok());
-        assert_eq!(nitrite_ids.len(), 15);
+        assert_eq!(recorder.finish().affected_nitrite_ids().len(), 15);
     }
     
     // =================== Comprehensive Batch Insert Tests ===================
@@ -2030,10 +2553,6 @@ mod tests {
             ids.extend(result.affected_nitrite_ids());
         }
         
-        // Manually prepare updated documents and track old state
-        let mut update_doc = Document::new();
-        update_doc.put("new_value", Value::from("changed")).unwrap();
-        
         // This tests the rollback mechanism itself
         let updated_indexes: Vec<(NitriteId, Document, Document)> = Vec::new();
         let failed_id = ids[0];
@@ -2046,7 +2565,60 @@ mod tests {
             .clone();
         
         // Call rollback (should not panic)
-        let result = inner.rollback_batch_update(&updated_indexes, &failed_id, &failed_old_doc, &update_doc);
+        let result = inner.rollback_batch_update(&updated_indexes, &failed_id, &failed_old_doc);
         assert!(result.is_ok());
     }
+
+    // =================== Increment Tests ===================
+
+    #[test]
+    fn test_increment_counter() {
+        let write_operations = setup_write_operations();
+        let mut doc = Document::new();
+        doc.put("views", Value::I64(41)).unwrap();
+        let id = write_operations.insert(doc).unwrap().affected_nitrite_ids()[0];
+
+        let counter = write_operations.increment(&id, "views", &Value::I32(1)).unwrap();
+        assert_eq!(counter, Value::I64(42));
+        let counter = write_operations.increment(&id, "likes", &Value::I32(3)).unwrap();
+        assert_eq!(counter, Value::I32(3));
+
+        let stored = write_operations.inner.nitrite_map.get(&Value::NitriteId(id)).unwrap().unwrap();
+        let stored = stored.as_document().unwrap();
+        assert_eq!(stored.get("views").unwrap(), Value::I64(42));
+        assert_eq!(stored.get("likes").unwrap(), Value::I32(3));
+        assert_eq!(stored.revision().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_increment_errors() {
+        let write_operations = setup_write_operations();
+        let mut doc = Document::new();
+        doc.put("name", Value::from("x")).unwrap();
+        doc.put("small", Value::U8(255)).unwrap();
+        let id = write_operations.insert(doc).unwrap().affected_nitrite_ids()[0];
+
+        let error = write_operations.increment(&NitriteId::new(), "views", &Value::I32(1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::NotFound);
+        let error = write_operations.increment(&id, "name", &Value::I32(1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidDataType);
+        let error = write_operations.increment(&id, "views", &Value::from("1")).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidDataType);
+        let error = write_operations.increment(&id, "small", &Value::I32(1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidOperation);
+        let error = write_operations.increment(&id, &revision_field(), &Value::I32(1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidFieldName);
+        let error = write_operations.increment(&id, DOC_ID, &Value::I32(1)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidFieldName);
+    }
+
+    #[test]
+    fn test_add_numbers() {
+        assert_eq!(add_numbers(&Value::Null, &Value::F64(0.5)).unwrap(), Value::F64(0.5));
+        assert_eq!(add_numbers(&Value::F32(1.0), &Value::I32(2)).unwrap(), Value::F32(3.0));
+        assert_eq!(add_numbers(&Value::U32(5), &Value::I64(-5)).unwrap(), Value::U32(0));
+        assert!(add_numbers(&Value::U32(5), &Value::I64(-6)).is_err());
+        assert!(add_numbers(&Value::I32(5), &Value::F64(0.5)).is_err());
+        assert!(add_numbers(&Value::I64(i64::MAX), &Value::I32(1)).is_err());
+    }
 }
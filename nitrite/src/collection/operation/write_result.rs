@@ -1,4 +1,6 @@
-use crate::collection::NitriteId;
+use crate::collection::{NitriteId, WriteToken, WriteTokenHolder};
+use crate::errors::NitriteError;
+use std::time::{Duration, Instant};
 
 /// The result of a write operation (insert, update, delete).
 ///
@@ -23,10 +25,15 @@ use crate::collection::NitriteId;
 /// for id in result.affected_nitrite_ids() {
 ///     println!("Inserted document with ID: {}", id);
 /// }
+///
+/// // Let a query on another thread wait for the insert
+/// let token = result.write_token();
 /// ```
 #[derive(Debug)]
 pub struct WriteResult {
     nitrite_ids: Vec<NitriteId>,
+    write_token: WriteToken,
+    details: Option<WriteDetails>,
 }
 
 impl WriteResult {
@@ -36,7 +43,11 @@ impl WriteResult {
     ///
     /// * `nitrite_ids` - A vector of NitriteIds that were affected by the write operation
     pub fn new(nitrite_ids: Vec<NitriteId>) -> Self {
-        Self { nitrite_ids }
+        Self {
+            nitrite_ids,
+            write_token: WriteToken::default(),
+            details: None,
+        }
     }
 
     /// Gets the list of NitriteIds affected by the write operation.
@@ -47,6 +58,180 @@ impl WriteResult {
     pub fn affected_nitrite_ids(&self) -> &Vec<NitriteId> {
         &self.nitrite_ids
     }
+
+    /// Gets the token of the write operation, to be passed to
+    /// [`FindOptions::after_write`](crate::collection::FindOptions::after_write).
+    pub fn write_token(&self) -> WriteToken {
+        self.write_token
+    }
+
+    /// Gets the per-document outcomes and timings of the write operation.
+    ///
+    /// They are only recorded for collections with
+    /// [`detailed_results`](crate::collection::CollectionOptions::detailed_results) set;
+    /// for the others this returns `None`.
+    pub fn details(&self) -> Option<&WriteDetails> {
+        self.details.as_ref()
+    }
+}
+
+impl WriteTokenHolder for WriteResult {
+    fn set_write_token(&mut self, token: WriteToken) {
+        self.write_token = token;
+    }
+}
+
+/// The per-document outcomes and timings of a write operation.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let result = collection.insert_many(documents)?;
+/// if let Some(details) = result.details() {
+///     println!("{:?} in total, {:?} on indexes", details.total_time(), details.index_time());
+///     for outcome in details.outcomes() {
+///         println!("{} inserted: {}", outcome.nitrite_id(), outcome.is_inserted());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WriteDetails {
+    outcomes: Vec<DocumentOutcome>,
+    index_time: Duration,
+    total_time: Duration,
+}
+
+impl WriteDetails {
+    /// Returns the outcome of each document written, in the order they were written.
+    pub fn outcomes(&self) -> &[DocumentOutcome] {
+        &self.outcomes
+    }
+
+    /// Returns the time spent writing the index entries of the documents.
+    pub fn index_time(&self) -> Duration {
+        self.index_time
+    }
+
+    /// Returns the time the whole write operation took.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+}
+
+/// What a write operation did to one document.
+///
+/// An update matches a document and modifies it unless the update is empty; a removal
+/// matches and modifies the documents it removes.
+#[derive(Debug, Clone)]
+pub struct DocumentOutcome {
+    nitrite_id: NitriteId,
+    inserted: bool,
+    matched: bool,
+    modified: bool,
+    error: Option<NitriteError>,
+}
+
+impl DocumentOutcome {
+    /// Returns the id of the document.
+    pub fn nitrite_id(&self) -> NitriteId {
+        self.nitrite_id
+    }
+
+    /// Returns `true` if the document was inserted.
+    pub fn is_inserted(&self) -> bool {
+        self.inserted
+    }
+
+    /// Returns `true` if the document was matched by an update or a removal.
+    pub fn is_matched(&self) -> bool {
+        self.matched
+    }
+
+    /// Returns `true` if the document was changed by an update or a removal.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Returns the error the write of the document survived, like an event listener that
+    /// failed. A failing write fails the whole operation and has no outcome.
+    pub fn error(&self) -> Option<&NitriteError> {
+        self.error.as_ref()
+    }
+}
+
+/// Collects the affected ids of a write operation and, for collections with detailed
+/// results, the outcome of each document and the time spent on indexes.
+pub(crate) struct WriteRecorder {
+    nitrite_ids: Vec<NitriteId>,
+    details: Option<(Instant, WriteDetails)>,
+}
+
+impl WriteRecorder {
+    pub fn new(detailed: bool) -> Self {
+        WriteRecorder {
+            nitrite_ids: Vec::new(),
+            details: detailed.then(|| (Instant::now(), WriteDetails::default())),
+        }
+    }
+
+    /// Runs an index write, adding its duration to the index time.
+    pub fn time_index<T>(&mut self, index_write: impl FnOnce() -> T) -> T {
+        match &mut self.details {
+            Some((_, details)) => {
+                let started = Instant::now();
+                let result = index_write();
+                details.index_time += started.elapsed();
+                result
+            }
+            None => index_write(),
+        }
+    }
+
+    pub fn inserted(&mut self, nitrite_id: NitriteId) {
+        self.nitrite_ids.push(nitrite_id);
+        self.record(nitrite_id, true, false, true);
+    }
+
+    /// Records a document matched by an update, affected if the update modified it.
+    pub fn updated(&mut self, nitrite_id: NitriteId, modified: bool) {
+        if modified {
+            self.nitrite_ids.push(nitrite_id);
+        }
+        self.record(nitrite_id, false, true, modified);
+    }
+
+    pub fn removed(&mut self, nitrite_id: NitriteId) {
+        self.nitrite_ids.push(nitrite_id);
+        self.record(nitrite_id, false, true, true);
+    }
+
+    /// Attaches an error the write of the last recorded document survived.
+    pub fn failed(&mut self, error: NitriteError) {
+        if let Some(outcome) = self.details.as_mut().and_then(|(_, details)| details.outcomes.last_mut()) {
+            outcome.error = Some(error);
+        }
+    }
+
+    pub fn finish(self) -> WriteResult {
+        let mut result = WriteResult::new(self.nitrite_ids);
+        result.details = self.details.map(|(started, mut details)| {
+            details.total_time = started.elapsed();
+            details
+        });
+        result
+    }
+
+    fn record(&mut self, nitrite_id: NitriteId, inserted: bool, matched: bool, modified: bool) {
+        if let Some((_, details)) = &mut self.details {
+            details.outcomes.push(DocumentOutcome {
+                nitrite_id,
+                inserted,
+                matched,
+                modified,
+                error: None,
+            });
+        }
+    }
 }
 
 impl Iterator for WriteResult {
@@ -85,4 +270,31 @@ mod tests {
         assert_eq!(write_result.next(), Some(nitrite_id1));
         assert_eq!(write_result.next(), None);
     }
+
+    #[test]
+    fn test_write_recorder_details() {
+        let inserted = NitriteId::new();
+        let unchanged = NitriteId::new();
+        let mut recorder = WriteRecorder::new(true);
+        recorder.inserted(inserted);
+        recorder.time_index(|| std::thread::sleep(Duration::from_millis(1)));
+        recorder.updated(unchanged, false);
+        recorder.failed(NitriteError::new("listener failed", crate::errors::ErrorKind::EventError));
+
+        let result = recorder.finish();
+        assert_eq!(result.affected_nitrite_ids(), &vec![inserted]);
+        let details = result.details().unwrap();
+        assert!(details.index_time() >= Duration::from_millis(1));
+        assert!(details.total_time() >= details.index_time());
+
+        let outcomes = details.outcomes();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes[0].is_inserted() && outcomes[0].error().is_none());
+        assert!(outcomes[1].is_matched() && !outcomes[1].is_modified());
+        assert!(outcomes[1].error().is_some());
+
+        let mut recorder = WriteRecorder::new(false);
+        recorder.inserted(inserted);
+        assert!(recorder.finish().details().is_none());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,425 @@
+use crate::collection::FindPlan;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::SortOrder;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+
+/// The version of the canonical text written by this release, see
+/// [`PlanSnapshot::to_canonical_string`].
+///
+/// It is raised whenever the text of an unchanged plan changes, so that a golden file
+/// written by another release fails with a clear cause.
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// Environment variable that makes [`PlanSnapshot::check_golden`] write the golden file
+/// instead of comparing against it.
+pub const UPDATE_PLANS_ENV: &str = "NITRITE_UPDATE_PLANS";
+
+/// A stable, plain-data description of a query plan, for golden tests.
+///
+/// Unlike [`FindPlan`], which the optimizer is free to change, a snapshot only holds what
+/// an application can rely on: how documents are reached, the filters, the sort and the
+/// paging. Filters are kept in their text form. The index scan order is sorted by field
+/// so two snapshots of the same plan are always equal.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let cursor = collection.find(field("age").gt(30))?;
+/// let snapshot = cursor.find_plan().unwrap().snapshot();
+/// snapshot.check_golden("tests/plans/adults.plan")?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSnapshot {
+    /// The filter on `_id` the documents are looked up by, if any.
+    pub id_lookup: Option<String>,
+    /// The index scanned, if any.
+    pub index_scan: Option<IndexScanSnapshot>,
+    /// The plans whose results are merged, for a query answered by several index scans.
+    pub branches: Vec<PlanSnapshot>,
+    /// The filter applied to each document read.
+    pub filter: Option<String>,
+    /// The sort by distance to a point, as `field (x, y)`.
+    pub distance_sort: Option<String>,
+    /// The fields sorted by after reading, with their order.
+    pub sort: Vec<(String, String)>,
+    /// Whether duplicate documents are left out.
+    pub distinct: bool,
+    /// The number of documents skipped.
+    pub skip: Option<u64>,
+    /// The most documents returned.
+    pub limit: Option<u64>,
+}
+
+/// The index scan of a [`PlanSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexScanSnapshot {
+    /// The index type, as given by [`crate::index::IndexOptions::index_type`].
+    pub index_type: String,
+    /// The indexed fields, in index order.
+    pub fields: Vec<String>,
+    /// The filters answered by the index.
+    pub filters: Vec<String>,
+    /// The fields the index is walked by, with their order, sorted by field.
+    pub order: Vec<(String, String)>,
+    /// Whether the query is answered from the index keys without reading documents.
+    pub covered: bool,
+}
+
+impl PlanSnapshot {
+    /// Takes a snapshot of a plan.
+    pub fn of(find_plan: &FindPlan) -> Self {
+        let index_scan = find_plan.index_descriptor().map(|descriptor| {
+            let mut order: Vec<(String, String)> = find_plan
+                .index_scan_order()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(field, reverse)| (field, order_name(reverse).to_string()))
+                .collect();
+            order.sort();
+            IndexScanSnapshot {
+                index_type: descriptor.index_type(),
+                fields: descriptor.index_fields().field_names(),
+                filters: find_plan
+                    .index_scan_filter()
+                    .map(|scan| scan.filters().iter().map(|filter| filter.to_string()).collect())
+                    .unwrap_or_default(),
+                order,
+                covered: find_plan.is_covered(),
+            }
+        });
+
+        PlanSnapshot {
+            id_lookup: find_plan.by_id_filter().map(|filter| filter.to_string()),
+            index_scan,
+            branches: find_plan
+                .sub_plans()
+                .unwrap_or_default()
+                .iter()
+                .map(PlanSnapshot::of)
+                .collect(),
+            filter: find_plan.full_scan_filter().map(|filter| filter.to_string()),
+            distance_sort: find_plan.distance_sort().map(|sort| {
+                format!("{} ({}, {})", sort.field_name(), sort.x(), sort.y())
+            }),
+            sort: find_plan
+                .blocking_sort_order()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(field, order)| {
+                    (field, order_name(order == SortOrder::Descending).to_string())
+                })
+                .collect(),
+            distinct: find_plan.distinct(),
+            skip: find_plan.skip(),
+            limit: find_plan.limit(),
+        }
+    }
+
+    /// Returns `true` if the plan, or one of its branches, scans an index on exactly
+    /// these fields, in this order.
+    pub fn uses_index(&self, fields: &[&str]) -> bool {
+        self.index_scan
+            .as_ref()
+            .is_some_and(|scan| scan.fields.iter().map(String::as_str).eq(fields.iter().copied()))
+            || self.branches.iter().any(|branch| branch.uses_index(fields))
+    }
+
+    /// Returns `true` if the plan, or one of its branches, scans an index.
+    pub fn uses_any_index(&self) -> bool {
+        self.index_scan.is_some() || self.branches.iter().any(PlanSnapshot::uses_any_index)
+    }
+
+    /// Renders the snapshot as text that stays the same for the same plan within a
+    /// [`PLAN_FORMAT_VERSION`], one step per line.
+    ///
+    /// # Examples
+    ///
+    /// ```text
+    /// plan format: 1
+    /// index scan: non-unique on [age]
+    ///   scan: (age > 30)
+    /// filter: (name == "Ada")
+    /// sort: name descending
+    /// limit: 10
+    /// ```
+    pub fn to_canonical_string(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "plan format: {}", PLAN_FORMAT_VERSION);
+        self.write_steps(&mut text, "");
+        text
+    }
+
+    /// Compares the snapshot with the golden file at `path`.
+    ///
+    /// When the [`UPDATE_PLANS_ENV`] environment variable is set, the file is written
+    /// with the snapshot instead, creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` showing both plans if they differ, and an I/O error if
+    /// the file cannot be read or written. A missing file is an error unless it is being
+    /// written, so a golden file left out of version control does not pass unnoticed.
+    pub fn check_golden<P: AsRef<Path>>(&self, path: P) -> NitriteResult<()> {
+        let path = path.as_ref();
+        let actual = self.to_canonical_string();
+
+        if std::env::var_os(UPDATE_PLANS_ENV).is_some() {
+            if let Some(directory) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(directory).map_err(|err| {
+                    log::error!("Failed to create directory {}: {}", directory.display(), err);
+                    NitriteError::from(err)
+                })?;
+            }
+            return std::fs::write(path, &actual).map_err(|err| {
+                log::error!("Failed to write golden plan {}: {}", path.display(), err);
+                NitriteError::from(err)
+            });
+        }
+
+        let expected = std::fs::read_to_string(path).map_err(|err| {
+            log::error!(
+                "Failed to read golden plan {} (set {} to write it): {}",
+                path.display(),
+                UPDATE_PLANS_ENV,
+                err
+            );
+            NitriteError::from(err)
+        })?;
+        if expected.replace("\r\n", "\n") != actual {
+            log::error!("Query plan differs from golden plan {}", path.display());
+            return Err(NitriteError::new(
+                &format!(
+                    "Query plan differs from golden plan {} (set {} to update it)\nexpected:\n{}actual:\n{}",
+                    path.display(),
+                    UPDATE_PLANS_ENV,
+                    expected,
+                    actual
+                ),
+                ErrorKind::ValidationError,
+            ));
+        }
+        Ok(())
+    }
+
+    fn write_steps(&self, text: &mut String, indent: &str) {
+        let mut scanned = false;
+
+        if let Some(id_lookup) = &self.id_lookup {
+            let _ = writeln!(text, "{}id lookup: {}", indent, id_lookup);
+            scanned = true;
+        }
+
+        if let Some(scan) = &self.index_scan {
+            let _ = writeln!(
+                text,
+                "{}index scan: {} on [{}]",
+                indent,
+                scan.index_type,
+                scan.fields.join(", ")
+            );
+            for filter in &scan.filters {
+                let _ = writeln!(text, "{}  scan: {}", indent, filter);
+            }
+            for (field, order) in &scan.order {
+                let _ = writeln!(text, "{}  order: {} {}", indent, field, order);
+            }
+            if scan.covered {
+                let _ = writeln!(text, "{}  covered", indent);
+            }
+            scanned = true;
+        }
+
+        if !self.branches.is_empty() {
+            let _ = writeln!(text, "{}union: {} branches", indent, self.branches.len());
+            let nested = format!("{}    ", indent);
+            for (number, branch) in self.branches.iter().enumerate() {
+                let _ = writeln!(text, "{}  branch {}:", indent, number + 1);
+                branch.write_steps(text, &nested);
+            }
+            scanned = true;
+        }
+
+        if !scanned {
+            let _ = writeln!(text, "{}collection scan", indent);
+        }
+        if let Some(filter) = &self.filter {
+            let _ = writeln!(text, "{}filter: {}", indent, filter);
+        }
+        if let Some(distance_sort) = &self.distance_sort {
+            let _ = writeln!(text, "{}distance sort: {}", indent, distance_sort);
+        }
+        if !self.sort.is_empty() {
+            let fields: Vec<String> = self
+                .sort
+                .iter()
+                .map(|(field, order)| format!("{} {}", field, order))
+                .collect();
+            let _ = writeln!(text, "{}sort: {}", indent, fields.join(", "));
+        }
+        if self.distinct {
+            let _ = writeln!(text, "{}distinct", indent);
+        }
+        if let Some(skip) = self.skip {
+            let _ = writeln!(text, "{}skip: {}", indent, skip);
+        }
+        if let Some(limit) = self.limit {
+            let _ = writeln!(text, "{}limit: {}", indent, limit);
+        }
+    }
+}
+
+fn order_name(descending: bool) -> &'static str {
+    if descending {
+        "descending"
+    } else {
+        "ascending"
+    }
+}
+
+/// Asserts that a query plan scans an index, panicking with the canonical text of the
+/// plan otherwise.
+///
+/// With only a plan, any index will do; with field names, the plan or one of its branches
+/// must scan an index on exactly those fields, in that order. The plan is anything that
+/// dereferences to a [`FindPlan`](crate::collection::FindPlan).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let cursor = collection.find(and(vec![field("last").eq("Lovelace"), field("first").eq("Ada")]))?;
+/// assert_plan_uses_index!(cursor.find_plan().unwrap(), "last", "first");
+/// ```
+#[macro_export]
+macro_rules! assert_plan_uses_index {
+    ($plan:expr $(,)?) => {{
+        let plan: &$crate::collection::FindPlan = &$plan;
+        let snapshot = plan.snapshot();
+        if !snapshot.uses_any_index() {
+            panic!(
+                "query plan does not scan an index:\n{}",
+                snapshot.to_canonical_string()
+            );
+        }
+    }};
+    ($plan:expr, $($field:expr),+ $(,)?) => {{
+        let plan: &$crate::collection::FindPlan = &$plan;
+        let snapshot = plan.snapshot();
+        let fields: &[&str] = &[$($field),+];
+        if !snapshot.uses_index(fields) {
+            panic!(
+                "query plan does not scan an index on {:?}:\n{}",
+                fields,
+                snapshot.to_canonical_string()
+            );
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Fields, NON_UNIQUE_INDEX, UNIQUE_INDEX};
+    use crate::filter::{field, IndexScanFilter};
+    use crate::index::IndexDescriptor;
+
+    fn index_plan(fields: Vec<&str>) -> FindPlan {
+        let mut find_plan = FindPlan::new();
+        find_plan.set_index_descriptor(IndexDescriptor::new(
+            NON_UNIQUE_INDEX,
+            Fields::with_names(fields).unwrap(),
+            "people",
+        ));
+        find_plan.set_index_scan_filter(IndexScanFilter::new(vec![field("age").gt(30)]));
+        find_plan
+    }
+
+    #[test]
+    fn test_canonical_string() {
+        let mut find_plan = index_plan(vec!["age"]);
+        find_plan.set_full_scan_filter(field("name").eq("Ada"));
+        find_plan.set_blocking_sort_order(vec![("name".to_string(), SortOrder::Descending)]);
+        find_plan.set_skip(5);
+        find_plan.set_limit(10);
+
+        assert_eq!(
+            find_plan.to_canonical_string(),
+            "plan format: 1\n\
+             index scan: non-unique on [age]\n  \
+               scan: (age > 30)\n\
+             filter: (name == \"Ada\")\n\
+             sort: name descending\n\
+             skip: 5\n\
+             limit: 10\n"
+        );
+        assert_eq!(FindPlan::new().to_canonical_string(), "plan format: 1\ncollection scan\n");
+    }
+
+    #[test]
+    fn test_branches_and_index_checks() {
+        let mut find_plan = FindPlan::new();
+        find_plan.add_sub_plan(index_plan(vec!["age", "name"]));
+        let mut second = FindPlan::new();
+        second.set_full_scan_filter(field("city").eq("Paris"));
+        find_plan.add_sub_plan(second);
+        find_plan.set_distinct(true);
+
+        let snapshot = find_plan.snapshot();
+        assert!(snapshot.uses_any_index());
+        assert!(snapshot.uses_index(&["age", "name"]));
+        assert!(!snapshot.uses_index(&["age"]));
+        assert!(!snapshot.uses_index(&["name", "age"]));
+        assert!(snapshot.to_canonical_string().contains(
+            "union: 2 branches\n  branch 1:\n    index scan: non-unique on [age, name]\n"
+        ));
+
+        assert_plan_uses_index!(find_plan);
+        assert_plan_uses_index!(find_plan, "age", "name");
+        let result = std::panic::catch_unwind(|| {
+            assert_plan_uses_index!(FindPlan::new(), "age");
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_covered_unique_scan() {
+        let mut find_plan = FindPlan::new();
+        find_plan.set_index_descriptor(IndexDescriptor::new(
+            UNIQUE_INDEX,
+            Fields::with_names(vec!["email"]).unwrap(),
+            "people",
+        ));
+        let snapshot = find_plan.to_covered().snapshot();
+        assert!(snapshot.index_scan.as_ref().unwrap().covered);
+        assert_eq!(
+            snapshot.to_canonical_string(),
+            "plan format: 1\nindex scan: unique on [email]\n  covered\n"
+        );
+        assert_ne!(snapshot, find_plan.snapshot());
+    }
+
+    #[test]
+    fn test_check_golden() {
+        let path = std::env::temp_dir()
+            .join(format!("nitrite-plan-{}", uuid::Uuid::new_v4()))
+            .join("adults.plan");
+        let snapshot = index_plan(vec!["age"]).snapshot();
+        if std::env::var_os(UPDATE_PLANS_ENV).is_some() {
+            return;
+        }
+
+        let missing = snapshot.check_golden(&path).unwrap_err();
+        assert_eq!(missing.kind(), &ErrorKind::FileNotFound);
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, snapshot.to_canonical_string()).unwrap();
+        snapshot.check_golden(&path).unwrap();
+
+        let other = index_plan(vec!["name"]).snapshot();
+        let error = other.check_golden(&path).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+        assert!(error.to_string().contains("index scan: non-unique on [name]"));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
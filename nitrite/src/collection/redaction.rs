@@ -0,0 +1,181 @@
+use crate::collection::Document;
+use crate::common::Value;
+use crate::errors::NitriteResult;
+use std::sync::Arc;
+
+type ValueTransform = Arc<dyn Fn(&Value) -> Value + Send + Sync>;
+
+#[derive(Clone)]
+enum Redaction {
+    Hide,
+    Mask { keep_last: usize },
+    Transform(ValueTransform),
+}
+
+/// Rules that redact the documents a collection returns to readers.
+///
+/// A policy is set on a collection with
+/// [`set_redaction_policy`](crate::collection::NitriteCollectionProvider::set_redaction_policy)
+/// and applied to every document leaving `find` and `get_by_id`, after the processors ran.
+/// Stored documents are not changed: filters, sorting and indexes still see the original
+/// values, and writes keep them.
+///
+/// A principal named in [`exempt`](RedactionPolicy::exempt) reads the documents as stored
+/// when it is passed with [`FindOptions::principal`](crate::collection::FindOptions::principal).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let policy = RedactionPolicy::new()
+///     .hide("cvv")
+///     .mask("credit_card_number", 4)
+///     .exempt("billing");
+/// payments.set_redaction_policy(Some(policy))?;
+///
+/// // "************4242", without a cvv
+/// let card = payments.find(all())?.first();
+/// // as stored
+/// let card = payments.find_with_options(all(), &FindOptions::new().principal("billing"))?.first();
+/// ```
+#[derive(Clone, Default)]
+pub struct RedactionPolicy {
+    rules: Vec<(String, Redaction)>,
+    exempt: Vec<String>,
+}
+
+impl RedactionPolicy {
+    /// Creates a policy without rules.
+    pub fn new() -> Self {
+        RedactionPolicy::default()
+    }
+
+    /// Removes a field from the returned documents.
+    pub fn hide(mut self, field: &str) -> Self {
+        self.rules.push((field.to_string(), Redaction::Hide));
+        self
+    }
+
+    /// Replaces every character of a field but the last `keep_last` ones with `*`.
+    ///
+    /// Values other than text are masked in their text form, so a masked number is
+    /// returned as a string; the elements of an array are masked one by one.
+    pub fn mask(mut self, field: &str, keep_last: usize) -> Self {
+        self.rules.push((field.to_string(), Redaction::Mask { keep_last }));
+        self
+    }
+
+    /// Replaces the value of a field with the result of `transform`.
+    pub fn transform<F>(mut self, field: &str, transform: F) -> Self
+    where
+        F: Fn(&Value) -> Value + Send + Sync + 'static,
+    {
+        self.rules
+            .push((field.to_string(), Redaction::Transform(Arc::new(transform))));
+        self
+    }
+
+    /// Lets a principal read the documents without redaction.
+    pub fn exempt(mut self, principal: &str) -> Self {
+        self.exempt.push(principal.to_string());
+        self
+    }
+
+    /// Checks whether the policy applies to a reader, `None` being an anonymous one.
+    pub fn applies_to(&self, principal: Option<&str>) -> bool {
+        match principal {
+            Some(principal) => !self.exempt.iter().any(|exempt| exempt == principal),
+            None => true,
+        }
+    }
+
+    pub(crate) fn redact(&self, mut document: Document) -> NitriteResult<Document> {
+        for (field, redaction) in &self.rules {
+            let value = document.get(field)?;
+            if value.is_null() {
+                continue;
+            }
+            match redaction {
+                Redaction::Hide => document.remove(field)?,
+                Redaction::Mask { keep_last } => document.put(field, mask(&value, *keep_last))?,
+                Redaction::Transform(transform) => document.put(field, transform(&value))?,
+            }
+        }
+        Ok(document)
+    }
+}
+
+fn mask(value: &Value, keep_last: usize) -> Value {
+    let text = match value {
+        Value::Array(values) => {
+            return Value::Array(values.iter().map(|value| mask(value, keep_last)).collect())
+        }
+        Value::String(text) => text.clone(),
+        Value::Null => return Value::Null,
+        other => other.to_string(),
+    };
+
+    let length = text.chars().count();
+    let hidden = length.saturating_sub(keep_last);
+    let masked: String = text
+        .chars()
+        .enumerate()
+        .map(|(position, c)| if position < hidden { '*' } else { c })
+        .collect();
+    Value::String(masked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    fn card() -> Document {
+        doc! {
+            holder: "Ada Lovelace",
+            card: { number: "4111111111114242", cvv: 123 },
+            phones: ["5550100", "5550199"],
+            pin: 1234,
+        }
+    }
+
+    #[test]
+    fn test_redact() {
+        let policy = RedactionPolicy::new()
+            .hide("card.cvv")
+            .mask("card.number", 4)
+            .mask("phones", 2)
+            .mask("pin", 0)
+            .transform("holder", |value| {
+                Value::from(value.as_string().map(|name| name[..1].to_string()).unwrap_or_default())
+            })
+            .hide("missing");
+
+        let document = policy.redact(card()).unwrap();
+        assert_eq!(document.get("card.number").unwrap(), Value::from("************4242"));
+        assert_eq!(document.get("card.cvv").unwrap(), Value::Null);
+        assert_eq!(
+            document.get("phones").unwrap(),
+            Value::Array(vec![Value::from("*****00"), Value::from("*****99")])
+        );
+        assert_eq!(document.get("pin").unwrap(), Value::from("****"));
+        assert_eq!(document.get("holder").unwrap(), Value::from("A"));
+        assert!(!document.contains_key("missing"));
+    }
+
+    #[test]
+    fn test_mask_shorter_than_kept() {
+        let document = RedactionPolicy::new()
+            .mask("pin", 8)
+            .redact(card())
+            .unwrap();
+        assert_eq!(document.get("pin").unwrap(), Value::from("1234"));
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let policy = RedactionPolicy::new().hide("card").exempt("billing");
+        assert!(policy.applies_to(None));
+        assert!(policy.applies_to(Some("support")));
+        assert!(!policy.applies_to(Some("billing")));
+    }
+}
@@ -0,0 +1,379 @@
+use crate::collection::{Document, NitriteCollection, NitriteId};
+use crate::common::{ReadExecutor, WriteExecutor};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::field;
+use crate::store::{NitriteMapProvider, NitriteStore, NitriteStoreProvider};
+use crate::{Atomic, Value};
+use std::sync::{Arc, OnceLock};
+
+/// What happens to the referencing documents when a referenced document is removed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OnDelete {
+    /// The removal fails while a document still references the document.
+    #[default]
+    Restrict,
+    /// The referencing documents are removed as well.
+    Cascade,
+    /// The reference field of the referencing documents is set to null.
+    SetNull,
+}
+
+/// A constraint that the documents of a collection reference existing documents of another
+/// collection.
+///
+/// The reference field holds the `_id` of a document of the target collection, as a
+/// [`NitriteId`]. Inserts and updates are rejected when the field names a document that
+/// does not exist; a missing or null field references nothing. What happens when a
+/// referenced document is removed is chosen with [`on_delete`](Reference::on_delete).
+///
+/// References are registered with [`Nitrite::add_reference`](crate::nitrite::Nitrite::add_reference)
+/// and last until the database is closed. They are checked by the collections of the
+/// database, so the writes of a transaction are checked when it commits. Removals look up
+/// the referencing documents by the reference field, which is best indexed.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// db.add_reference(Reference::new("orders", "customer_id", "customers").on_delete(OnDelete::Cascade))?;
+///
+/// let customer = customers.insert(doc!{ "name": "Ada" })?.affected_nitrite_ids()[0];
+/// orders.insert(doc!{ "customer_id": customer, "item": "anvil" })?;
+///
+/// // removes the order as well
+/// customers.remove(field("_id").eq(customer), false)?;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    collection: String,
+    field: String,
+    target: String,
+    on_delete: OnDelete,
+}
+
+impl Reference {
+    /// Creates a reference from a field of the documents of `collection` to the documents
+    /// of `target`, restricting the removal of referenced documents.
+    pub fn new(collection: &str, field: &str, target: &str) -> Self {
+        Reference {
+            collection: collection.to_string(),
+            field: field.to_string(),
+            target: target.to_string(),
+            on_delete: OnDelete::default(),
+        }
+    }
+
+    /// Sets what happens to the referencing documents when a referenced document is removed.
+    pub fn on_delete(mut self, on_delete: OnDelete) -> Self {
+        self.on_delete = on_delete;
+        self
+    }
+
+    /// Returns the name of the referencing collection.
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    /// Returns the reference field of the referencing documents.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the name of the referenced collection.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Returns what happens to the referencing documents when a referenced document is
+    /// removed.
+    pub fn get_on_delete(&self) -> OnDelete {
+        self.on_delete
+    }
+
+    /// Returns the document id the reference field of a document holds, if any.
+    fn referenced_id(&self, document: &Document) -> NitriteResult<Option<NitriteId>> {
+        match document.get(&self.field)? {
+            Value::Null => Ok(None),
+            Value::NitriteId(id) => Ok(Some(id)),
+            other => {
+                log::error!(
+                    "Reference field '{}' of collection '{}' holds {}, not a NitriteId",
+                    self.field, self.collection, other
+                );
+                Err(NitriteError::new(
+                    &format!(
+                        "Reference field '{}' of collection '{}' must hold the NitriteId of a document of '{}'",
+                        self.field, self.collection, self.target
+                    ),
+                    ErrorKind::ReferenceViolation,
+                ))
+            }
+        }
+    }
+}
+
+type CollectionResolver = Box<dyn Fn(&str) -> NitriteResult<NitriteCollection> + Send + Sync>;
+
+/// The references registered with a database, consulted by the write operations of its
+/// collections.
+#[derive(Clone, Default)]
+pub(crate) struct ReferenceRegistry {
+    inner: Arc<ReferenceRegistryInner>,
+}
+
+#[derive(Default)]
+struct ReferenceRegistryInner {
+    references: Atomic<Vec<Reference>>,
+    resolver: OnceLock<CollectionResolver>,
+}
+
+impl ReferenceRegistry {
+    pub fn new() -> Self {
+        ReferenceRegistry::default()
+    }
+
+    /// Sets how the registry opens the referencing collections to cascade a removal.
+    pub fn set_resolver<F>(&self, resolver: F)
+    where
+        F: Fn(&str) -> NitriteResult<NitriteCollection> + Send + Sync + 'static,
+    {
+        let _ = self.inner.resolver.set(Box::new(resolver));
+    }
+
+    /// Registers a reference, replacing the one on the same field of the same collection.
+    ///
+    /// References must not form a cycle: a removal cascades while the collection it started
+    /// in is locked, so a cycle could come back to it.
+    pub fn add(&self, reference: Reference) -> NitriteResult<()> {
+        if reference.collection.is_empty() || reference.field.is_empty() || reference.target.is_empty() {
+            log::error!("Reference needs a collection, a field and a target");
+            return Err(NitriteError::new(
+                "Reference needs a collection, a field and a target",
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        self.inner.references.write_with(|references| {
+            let mut kept: Vec<Reference> = references
+                .iter()
+                .filter(|existing| {
+                    existing.collection != reference.collection || existing.field != reference.field
+                })
+                .cloned()
+                .collect();
+            if reaches(&kept, &reference.collection, &reference.target) {
+                log::error!(
+                    "Reference from '{}' to '{}' would form a cycle",
+                    reference.collection, reference.target
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "Reference from '{}' to '{}' would form a cycle",
+                        reference.collection, reference.target
+                    ),
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+            kept.push(reference);
+            *references = kept;
+            Ok(())
+        })
+    }
+
+    /// Removes the reference on a field of a collection, returning whether there was one.
+    pub fn remove(&self, collection: &str, field: &str) -> bool {
+        self.inner.references.write_with(|references| {
+            let before = references.len();
+            references.retain(|existing| existing.collection != collection || existing.field != field);
+            references.len() != before
+        })
+    }
+
+    /// Returns the registered references.
+    pub fn references(&self) -> Vec<Reference> {
+        self.inner.references.read_with(|references| references.clone())
+    }
+
+    /// Checks that a document about to be written to `collection` references existing
+    /// documents.
+    pub fn check_write(&self, collection: &str, document: &Document, store: &NitriteStore) -> NitriteResult<()> {
+        for reference in self.outgoing(collection) {
+            let id = match reference.referenced_id(document)? {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let exists = store.has_map(&reference.target)?
+                && store
+                    .open_map(&reference.target)?
+                    .contains_key(&Value::NitriteId(id))?;
+            if !exists {
+                log::error!(
+                    "Document referenced by '{}' of collection '{}' does not exist in '{}'",
+                    reference.field, collection, reference.target
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "Field '{}' references document {} which does not exist in collection '{}'",
+                        reference.field, id, reference.target
+                    ),
+                    ErrorKind::ReferenceViolation,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if a document of `collection` about to be removed is referenced by a
+    /// restricting reference.
+    pub fn check_remove(&self, collection: &str, id: &NitriteId, store: &NitriteStore) -> NitriteResult<()> {
+        for reference in self.incoming(collection) {
+            if reference.on_delete != OnDelete::Restrict || !store.has_map(&reference.collection)? {
+                continue;
+            }
+
+            let referencing = self.resolve(&reference.collection)?;
+            let referenced = referencing
+                .find(field(&reference.field).eq(Value::NitriteId(*id)))?
+                .next()
+                .is_some();
+            if referenced {
+                log::error!(
+                    "Document {} of '{}' is still referenced by '{}'",
+                    id, collection, reference.collection
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "Document {} of collection '{}' is referenced by field '{}' of collection '{}'",
+                        id, collection, reference.field, reference.collection
+                    ),
+                    ErrorKind::ReferenceViolation,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cascades the removal of a document of `collection` to the documents referencing it.
+    pub fn cascade_remove(&self, collection: &str, id: &NitriteId, store: &NitriteStore) -> NitriteResult<()> {
+        for reference in self.incoming(collection) {
+            if reference.on_delete == OnDelete::Restrict || !store.has_map(&reference.collection)? {
+                continue;
+            }
+
+            let referencing = self.resolve(&reference.collection)?;
+            let filter = field(&reference.field).eq(Value::NitriteId(*id));
+            match reference.on_delete {
+                OnDelete::Cascade => {
+                    referencing.remove(filter, false)?;
+                }
+                OnDelete::SetNull => {
+                    let mut update = Document::new();
+                    update.put(&reference.field, Value::Null)?;
+                    referencing.update(filter, &update)?;
+                }
+                OnDelete::Restrict => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a registered reference points at `collection`, so removing its
+    /// documents must be checked and cascaded.
+    pub fn is_referenced(&self, collection: &str) -> bool {
+        self.inner
+            .references
+            .read_with(|references| references.iter().any(|reference| reference.target == collection))
+    }
+
+    fn outgoing(&self, collection: &str) -> Vec<Reference> {
+        self.inner.references.read_with(|references| {
+            references
+                .iter()
+                .filter(|reference| reference.collection == collection)
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn incoming(&self, collection: &str) -> Vec<Reference> {
+        self.inner.references.read_with(|references| {
+            references
+                .iter()
+                .filter(|reference| reference.target == collection)
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn resolve(&self, collection: &str) -> NitriteResult<NitriteCollection> {
+        match self.inner.resolver.get() {
+            Some(resolver) => resolver(collection),
+            None => {
+                log::error!("Reference registry cannot open collection '{}'", collection);
+                Err(NitriteError::new(
+                    &format!("Reference registry cannot open collection '{}'", collection),
+                    ErrorKind::InvalidOperation,
+                ))
+            }
+        }
+    }
+}
+
+/// Checks whether a reference from `from` to `to` would close a cycle, that is whether
+/// `from` is `to` or is already reachable by following the references back from `to`.
+fn reaches(references: &[Reference], from: &str, to: &str) -> bool {
+    let mut pending = vec![to.to_string()];
+    let mut seen = Vec::new();
+    while let Some(collection) = pending.pop() {
+        if collection == from {
+            return true;
+        }
+        if seen.contains(&collection) {
+            continue;
+        }
+        pending.extend(
+            references
+                .iter()
+                .filter(|reference| reference.collection == collection)
+                .map(|reference| reference.target.clone()),
+        );
+        seen.push(collection);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    #[test]
+    fn test_add_rejects_cycles() {
+        let registry = ReferenceRegistry::new();
+        registry.add(Reference::new("orders", "customer_id", "customers")).unwrap();
+        registry.add(Reference::new("lines", "order_id", "orders")).unwrap();
+
+        assert!(registry.add(Reference::new("tree", "parent", "tree")).is_err());
+        assert!(registry.add(Reference::new("customers", "last_line", "lines")).is_err());
+        assert!(registry.add(Reference::new("customers", "region", "regions")).is_ok());
+
+        // replaces the reference on the same field
+        registry
+            .add(Reference::new("orders", "customer_id", "customers").on_delete(OnDelete::Cascade))
+            .unwrap();
+        assert_eq!(registry.references().len(), 3);
+        assert!(registry.remove("orders", "customer_id"));
+        assert!(!registry.remove("orders", "customer_id"));
+    }
+
+    #[test]
+    fn test_referenced_id() {
+        let reference = Reference::new("orders", "customer_id", "customers");
+        let id = NitriteId::new();
+        assert_eq!(reference.referenced_id(&doc! { customer_id: id }).unwrap(), Some(id));
+        assert_eq!(reference.referenced_id(&doc! { item: "anvil" }).unwrap(), None);
+
+        let err = reference.referenced_id(&doc! { customer_id: "42" }).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ReferenceViolation);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt::Display;
+
+use crate::collection::NitriteId;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+
+/// Prefix and format version of the text form of a token.
+const TOKEN_PREFIX: &str = "scan1";
+
+/// The position of a collection scan, to resume the scan from later.
+///
+/// A scan reads the documents of a collection in the order of their ids, the order of
+/// the map that stores them. [`DocumentCursor::checkpoint`](crate::DocumentCursor::checkpoint)
+/// records the id of the last document the cursor returned, and
+/// [`NitriteCollectionProvider::resume_scan`](super::NitriteCollectionProvider::resume_scan)
+/// or [`FindOptions::resume_after`](super::FindOptions::resume_after) start a new scan
+/// right after it. Documents inserted behind the position since the checkpoint are not
+/// returned by the resumed scan, the others are.
+///
+/// Unlike a [`WriteToken`](super::WriteToken), a scan token survives a restart: its text
+/// form from [`to_string`](ToString::to_string) can be saved with the progress of a job
+/// and read back with [`ScanToken::parse`].
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut cursor = match saved_token {
+///     Some(text) => events.resume_scan(&ScanToken::parse(&text)?)?,
+///     None => events.find(all())?,
+/// };
+/// while let Some(document) = cursor.next() {
+///     export(document?)?;
+///     let token = cursor.checkpoint()?;
+///     if token.position() % 10_000 == 0 {
+///         save_progress(&token.to_string())?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScanToken {
+    collection_name: String,
+    last_id: Option<NitriteId>,
+    position: u64,
+}
+
+impl ScanToken {
+    pub(crate) fn new(collection_name: String, last_id: Option<NitriteId>, position: u64) -> Self {
+        ScanToken {
+            collection_name,
+            last_id,
+            position,
+        }
+    }
+
+    /// Returns the name of the scanned collection.
+    pub fn collection_name(&self) -> &str {
+        &self.collection_name
+    }
+
+    /// Returns the id of the last document returned before the checkpoint, or `None` if
+    /// the scan had not returned any document yet.
+    pub fn last_id(&self) -> Option<NitriteId> {
+        self.last_id
+    }
+
+    /// Returns the number of documents returned before the checkpoint, counting those
+    /// of the scans it resumed.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Reads a token from its text form.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ValidationError` if `text` is not the text form of a token.
+    pub fn parse(text: &str) -> NitriteResult<ScanToken> {
+        let mut parts = text.splitn(4, ':');
+        let (Some(TOKEN_PREFIX), Some(last_id), Some(position), Some(collection_name)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid_token(text));
+        };
+
+        let last_id = match last_id {
+            "-" => None,
+            id_value => {
+                let id_value = id_value.parse::<u64>().map_err(|_| invalid_token(text))?;
+                Some(NitriteId::create_id(id_value).map_err(|_| invalid_token(text))?)
+            }
+        };
+        let position = position.parse::<u64>().map_err(|_| invalid_token(text))?;
+        if collection_name.is_empty() {
+            return Err(invalid_token(text));
+        }
+        Ok(ScanToken::new(collection_name.to_string(), last_id, position))
+    }
+}
+
+impl Display for ScanToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.last_id {
+            Some(id) => write!(f, "{}:{}:", TOKEN_PREFIX, id.id_value())?,
+            None => write!(f, "{}:-:", TOKEN_PREFIX)?,
+        }
+        write!(f, "{}:{}", self.position, self.collection_name)
+    }
+}
+
+fn invalid_token(text: &str) -> NitriteError {
+    log::error!("Invalid scan token: {}", text);
+    NitriteError::new(
+        &format!("Invalid scan token: {}", text),
+        ErrorKind::ValidationError,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_text_round_trip() {
+        let token = ScanToken::new("orders:archive".to_string(), Some(NitriteId::new()), 42);
+        let text = token.to_string();
+        assert!(text.starts_with("scan1:"));
+        assert_eq!(ScanToken::parse(&text).unwrap(), token);
+
+        let token = ScanToken::new("orders".to_string(), None, 0);
+        assert_eq!(token.to_string(), "scan1:-:0:orders");
+        assert_eq!(ScanToken::parse("scan1:-:0:orders").unwrap(), token);
+    }
+
+    #[test]
+    fn test_invalid_tokens_are_rejected() {
+        for text in ["", "scan1:-:0", "scan2:-:0:orders", "scan1:x:0:orders", "scan1:5:0:orders", "scan1:-:-1:orders", "scan1:-:0:"] {
+            let error = ScanToken::parse(text).unwrap_err();
+            assert_eq!(error.kind(), &ErrorKind::ValidationError, "{}", text);
+        }
+    }
+}
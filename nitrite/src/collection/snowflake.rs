@@ -1,90 +1,342 @@
 use crate::common::get_current_time_or_zero;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use log::{info, warn};
 use rand::rngs::OsRng;
 use rand::Rng;
-use std::sync::atomic::AtomicU64;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Start of the snowflake clock, in milliseconds since the Unix epoch.
+pub(crate) const SNOWFLAKE_EPOCH: u64 = 1288834974657;
+/// Number of low bits of an id below its timestamp (node id and sequence).
+pub(crate) const TIMESTAMP_LEFT_SHIFT: u64 = 22;
+/// Largest node id that fits in the node bits of an id.
+pub const MAX_NODE_ID: u16 = 1023;
+
+/// What the id generator does when the system clock moves backwards.
+///
+/// Ids embed the time they were generated at, so a clock set back (by NTP or by hand)
+/// could produce ids another instance already issued. The policy is process-wide, like
+/// the generator itself. The default waits for the clock to catch up however long it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSkewPolicy {
+    /// Wait until the clock is back at the last timestamp seen. A jump larger than the
+    /// given duration fails id generation instead.
+    Wait(Duration),
+    /// Fail id generation until the clock is back at the last timestamp seen.
+    Error,
+    /// Keep issuing ids from the last timestamp seen, advancing the sequence. Ids stay
+    /// unique and ordered; their timestamps run ahead of the clock until it catches up.
+    Continue,
+}
+
+impl Default for ClockSkewPolicy {
+    fn default() -> Self {
+        ClockSkewPolicy::Wait(Duration::MAX)
+    }
+}
+
+/// How a generation request handles a backwards jump of the clock.
+enum SkewAction {
+    Proceed,
+    Wait(u64),
+    Refuse,
+}
+
+/// Counters of the id generator, see [`NitriteId::generator_metrics`](crate::collection::NitriteId::generator_metrics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdGeneratorMetrics {
+    node_id: u16,
+    ids_generated: u64,
+    clock_backward_jumps: u64,
+    max_clock_skew_ms: u64,
+    clock_skew_errors: u64,
+    sequence_exhaustions: u64,
+}
+
+impl IdGeneratorMetrics {
+    /// Returns the node id written in the generated ids.
+    pub fn node_id(&self) -> u16 {
+        self.node_id
+    }
+
+    /// Returns the number of ids generated by this process.
+    pub fn ids_generated(&self) -> u64 {
+        self.ids_generated
+    }
+
+    /// Returns how often the clock was seen moving backwards.
+    pub fn clock_backward_jumps(&self) -> u64 {
+        self.clock_backward_jumps
+    }
+
+    /// Returns the largest backwards jump of the clock seen, in milliseconds.
+    pub fn max_clock_skew_ms(&self) -> u64 {
+        self.max_clock_skew_ms
+    }
+
+    /// Returns the number of id requests failed by the [`ClockSkewPolicy`].
+    pub fn clock_skew_errors(&self) -> u64 {
+        self.clock_skew_errors
+    }
+
+    /// Returns how often all sequence numbers of a millisecond were used up, so that
+    /// generation moved on to the next millisecond.
+    pub fn sequence_exhaustions(&self) -> u64 {
+        self.sequence_exhaustions
+    }
+}
 
 pub struct SnowflakeIdGenerator {
-    node_id: u64,
+    node_id: AtomicU64,
+    node_id_configured: AtomicBool,
     sequence: AtomicU64,
     last_timestamp: AtomicU64,
     sequence_bits: u64,
     sequence_mask: u64,
     timestamp_left_shift: u64,
     epoch: u64,
-    mutex: Mutex<()>,
+    /// Serializes id generation; holds the clock skew policy.
+    policy: Mutex<ClockSkewPolicy>,
+    ids_generated: AtomicU64,
+    clock_backward_jumps: AtomicU64,
+    max_clock_skew_ms: AtomicU64,
+    clock_skew_errors: AtomicU64,
+    sequence_exhaustions: AtomicU64,
 }
 
 impl SnowflakeIdGenerator {
     pub fn new() -> Self {
         let node_id_bits = 10;
         let sequence_bits = 12;
-        let max_node_id = !0_u64 << node_id_bits;
-        let sequence_mask = !0_u64 << sequence_bits;
+        let max_node_id = (1_u64 << node_id_bits) - 1;
+        let sequence_mask = (1_u64 << sequence_bits) - 1;
         let timestamp_left_shift = sequence_bits + node_id_bits;
-        let epoch = 1288834974657;
+        let epoch = SNOWFLAKE_EPOCH;
+
+        let mut node_id = random_node_id();
+        if node_id > max_node_id {
+            warn!("Node id can't be greater than {}", max_node_id);
+            node_id = OsRng.gen_range(1..=max_node_id);
+        }
+        info!("Initialized with node id: {}", node_id);
 
-        let mut generator = SnowflakeIdGenerator {
-            node_id: 0,
+        SnowflakeIdGenerator {
+            node_id: AtomicU64::new(node_id),
+            node_id_configured: AtomicBool::new(false),
             sequence: AtomicU64::new(0),
             last_timestamp: AtomicU64::new(0),
             sequence_bits,
             sequence_mask,
             timestamp_left_shift,
             epoch,
-            mutex: Mutex::new(()),
-        };
+            policy: Mutex::new(ClockSkewPolicy::default()),
+            ids_generated: AtomicU64::new(0),
+            clock_backward_jumps: AtomicU64::new(0),
+            max_clock_skew_ms: AtomicU64::new(0),
+            clock_skew_errors: AtomicU64::new(0),
+            sequence_exhaustions: AtomicU64::new(0),
+        }
+    }
 
-        generator.node_id = generator.get_node_id();
-        if generator.node_id > max_node_id {
-            warn!("Node id can't be greater than {}", max_node_id);
-            generator.node_id = OsRng.gen_range(1..=max_node_id);
+    /// Returns the node id written in the generated ids.
+    pub fn node_id(&self) -> u16 {
+        self.node_id.load(Ordering::Relaxed) as u16
+    }
+
+    /// Replaces the random node id with `node_id`.
+    ///
+    /// Instances writing to the same database need distinct node ids for their ids to
+    /// never collide.
+    pub fn set_node_id(&self, node_id: u16) -> NitriteResult<()> {
+        if node_id > MAX_NODE_ID {
+            log::error!("Node id {} is greater than {}", node_id, MAX_NODE_ID);
+            return Err(NitriteError::new(
+                &format!("Node id {} is greater than {}", node_id, MAX_NODE_ID),
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        let _lock = self.lock();
+        let previous = self.node_id.swap(node_id as u64, Ordering::Relaxed);
+        if self.node_id_configured.swap(true, Ordering::Relaxed) && previous != node_id as u64 {
+            warn!(
+                "Node id changed from {} to {}; the id generator is shared by all databases of the process",
+                previous, node_id
+            );
         }
-        info!("Initialized with node id: {}", generator.node_id);
+        info!("Configured node id: {}", node_id);
+        Ok(())
+    }
+
+    /// Returns the policy applied when the clock moves backwards.
+    pub fn clock_skew_policy(&self) -> ClockSkewPolicy {
+        *self.lock()
+    }
 
-        generator
+    /// Sets the policy applied when the clock moves backwards.
+    pub fn set_clock_skew_policy(&self, policy: ClockSkewPolicy) {
+        *self.lock() = policy;
     }
-    
+
+    /// Returns the current counters of the generator.
+    pub fn metrics(&self) -> IdGeneratorMetrics {
+        IdGeneratorMetrics {
+            node_id: self.node_id(),
+            ids_generated: self.ids_generated.load(Ordering::Relaxed),
+            clock_backward_jumps: self.clock_backward_jumps.load(Ordering::Relaxed),
+            max_clock_skew_ms: self.max_clock_skew_ms.load(Ordering::Relaxed),
+            clock_skew_errors: self.clock_skew_errors.load(Ordering::Relaxed),
+            sequence_exhaustions: self.sequence_exhaustions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Generates the next id, applying the configured [`ClockSkewPolicy`] except that
+    /// a jump the policy refuses continues from the last timestamp instead of failing.
     pub fn get_id(&self) -> u64 {
+        self.id_at(current_time)
+    }
+
+    /// Generates the next id, applying the configured [`ClockSkewPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the clock moved backwards and the policy refuses the jump.
+    pub fn try_get_id(&self) -> NitriteResult<u64> {
+        self.try_id_at(current_time)
+    }
+
+    /// Generates the next id, reading the clock with `clock`, in milliseconds.
+    fn id_at(&self, clock: impl FnOnce() -> u64) -> u64 {
+        let policy = self.lock();
+        // read under the lock, so that a reading is never older than the last timestamp
+        let now = clock();
+        let last_timestamp = self.last_timestamp.load(Ordering::Relaxed);
+        let current_time = self.clock_reading(now, last_timestamp);
+        let skew = self.note_clock_skew(current_time, last_timestamp);
+        match self.skew_action(*policy, skew) {
+            SkewAction::Wait(millis) => std::thread::sleep(Duration::from_millis(millis)),
+            SkewAction::Refuse => {
+                warn!("Clock moved backwards by {} ms, continuing from the last timestamp", skew)
+            }
+            SkewAction::Proceed => {}
+        }
+        self.next_id(current_time.max(last_timestamp), last_timestamp)
+    }
+
+    /// Generates the next id like `id_at`, failing if the policy refuses a backwards
+    /// jump of the clock.
+    fn try_id_at(&self, clock: impl FnOnce() -> u64) -> NitriteResult<u64> {
+        let policy = self.lock();
+        let now = clock();
+        let last_timestamp = self.last_timestamp.load(Ordering::Relaxed);
+        let current_time = self.clock_reading(now, last_timestamp);
+        let skew = self.note_clock_skew(current_time, last_timestamp);
+        match self.skew_action(*policy, skew) {
+            SkewAction::Wait(millis) => std::thread::sleep(Duration::from_millis(millis)),
+            SkewAction::Refuse => {
+                self.clock_skew_errors.fetch_add(1, Ordering::Relaxed);
+                log::error!("Clock moved backwards by {} ms, refusing to generate an id", skew);
+                return Err(NitriteError::new(
+                    &format!("Clock moved backwards by {} ms, refusing to generate an id", skew),
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+            SkewAction::Proceed => {}
+        }
+        Ok(self.next_id(current_time.max(last_timestamp), last_timestamp))
+    }
+
+    fn skew_action(&self, policy: ClockSkewPolicy, skew: u64) -> SkewAction {
+        if skew == 0 {
+            return SkewAction::Proceed;
+        }
+        match policy {
+            ClockSkewPolicy::Wait(max_wait) if u128::from(skew) <= max_wait.as_millis() => {
+                warn!("Clock moved backwards by {} ms, waiting for it to catch up", skew);
+                SkewAction::Wait(skew)
+            }
+            ClockSkewPolicy::Wait(_) | ClockSkewPolicy::Error => SkewAction::Refuse,
+            ClockSkewPolicy::Continue => SkewAction::Proceed,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ClockSkewPolicy> {
         // Acquire the lock with poison recovery
-        let _lock = match self.mutex.lock() {
+        match self.policy.lock() {
             Ok(lock) => lock,
             Err(poisoned) => {
                 warn!("Snowflake lock was poisoned, recovering");
                 poisoned.into_inner()
             }
-        };
-        
-        let current_time = get_current_time_or_zero() as u64;
+        }
+    }
+
+    /// Returns the time to generate the id at: the clock reading, or the last timestamp
+    /// if the clock cannot be read.
+    fn clock_reading(&self, now: u64, last_timestamp: u64) -> u64 {
+        // A clock before the snowflake epoch, or one that cannot be read at all (zero), would
+        // underflow the id's timestamp and make the backwards-clock wait below last for years;
+        // continue from the last timestamp instead.
+        if now < self.epoch {
+            last_timestamp.max(self.epoch)
+        } else {
+            now
+        }
+    }
+
+    /// Records a backwards jump of the clock and returns its size in milliseconds.
+    fn note_clock_skew(&self, current_time: u64, last_timestamp: u64) -> u64 {
+        let skew = last_timestamp.saturating_sub(current_time);
+        if skew > 0 {
+            self.clock_backward_jumps.fetch_add(1, Ordering::Relaxed);
+            self.max_clock_skew_ms.fetch_max(skew, Ordering::Relaxed);
+        }
+        skew
+    }
+
+    /// Issues the id following `last_timestamp`; the caller holds the lock.
+    fn next_id(&self, current_time: u64, last_timestamp: u64) -> u64 {
         let mut timestamp = current_time;
-        let last_timestamp = self.last_timestamp.load(std::sync::atomic::Ordering::Relaxed);
-        let sequence = self.sequence.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        
-        // Handle clock moving backwards with optimized branching
+        let mut sequence = 0;
+
+        // Same millisecond as the last id: advance the sequence
         if timestamp <= last_timestamp {
             timestamp = last_timestamp;
+            // The sequence must stay within its bits so that the timestamp can be read
+            // back from the id; once a millisecond is used up, move on to the next one.
+            sequence = (self.sequence.load(Ordering::Relaxed) + 1) & self.sequence_mask;
+            if sequence == 0 {
+                timestamp += 1;
+                self.sequence_exhaustions.fetch_add(1, Ordering::Relaxed);
+            }
             let sleep_duration = timestamp.saturating_sub(current_time);
             if sleep_duration > 0 {
                 std::thread::sleep(std::time::Duration::from_millis(sleep_duration));
             }
         }
-        
-        self.last_timestamp.store(timestamp, std::sync::atomic::Ordering::Relaxed);
-        drop(_lock);
-        
+
+        self.sequence.store(sequence, Ordering::Relaxed);
+        self.last_timestamp.store(timestamp, Ordering::Relaxed);
+        self.ids_generated.fetch_add(1, Ordering::Relaxed);
+
         ((timestamp - self.epoch) << self.timestamp_left_shift)
-            | (self.node_id << self.sequence_bits)
+            | (self.node_id.load(Ordering::Relaxed) << self.sequence_bits)
             | sequence
     }
+}
 
-    fn get_node_id(&self) -> u64 {
-        let uuid = uuid::Uuid::new_v4();
-        let uid = uuid.as_bytes();
-        let rnd_byte = OsRng.gen::<u64>() & 0x000000FF;
+fn current_time() -> u64 {
+    get_current_time_or_zero() as u64
+}
 
-        ((0x000000FF & uid[uid.len() - 1] as u64) | (0x0000FF00 & ((rnd_byte) << 8))) >> 6
-    }
+fn random_node_id() -> u64 {
+    let uuid = uuid::Uuid::new_v4();
+    let uid = uuid.as_bytes();
+    let rnd_byte = OsRng.gen::<u64>() & 0x000000FF;
+
+    ((0x000000FF & uid[uid.len() - 1] as u64) | (0x0000FF00 & ((rnd_byte) << 8))) >> 6
 }
 
 #[cfg(test)]
@@ -113,12 +365,91 @@ mod tests {
         assert!(id > 0);
     }
 
+    #[test]
+    fn handles_clock_before_epoch() {
+        let generator = SnowflakeIdGenerator::new();
+        // an unreadable clock reads as zero
+        let first = generator.id_at(|| 0);
+        let second = generator.id_at(|| 0);
+        assert!(second > first);
+
+        // after a real reading, a zero reading continues from it without waiting
+        let now = get_current_time_or_zero() as u64;
+        let id = generator.id_at(|| now);
+        let start = std::time::Instant::now();
+        assert!(generator.id_at(|| 0) > id);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn clock_skew_policies() {
+        let generator = SnowflakeIdGenerator::new();
+        let now = get_current_time_or_zero() as u64;
+        let first = generator.try_id_at(|| now).unwrap();
+
+        // the default waits for the clock to catch up
+        let start = std::time::Instant::now();
+        assert!(generator.try_id_at(|| now - 50).unwrap() > first);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        generator.set_clock_skew_policy(ClockSkewPolicy::Wait(Duration::from_millis(10)));
+        let err = generator.try_id_at(|| now - 100).unwrap_err();
+        assert!(err.message().contains("Clock moved backwards by"));
+
+        generator.set_clock_skew_policy(ClockSkewPolicy::Error);
+        assert!(generator.try_id_at(|| now - 1).is_err());
+        // new() must not fail: it continues from the last timestamp
+        let start = std::time::Instant::now();
+        let continued = generator.id_at(|| now - 1000);
+        assert!(continued > first);
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        generator.set_clock_skew_policy(ClockSkewPolicy::Continue);
+        let start = std::time::Instant::now();
+        assert!(generator.try_id_at(|| now - 1000).unwrap() > continued);
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let metrics = generator.metrics();
+        assert_eq!(metrics.ids_generated(), 4);
+        assert_eq!(metrics.clock_backward_jumps(), 5);
+        assert!(metrics.max_clock_skew_ms() >= 1000);
+        assert_eq!(metrics.clock_skew_errors(), 2);
+    }
+
+    #[test]
+    fn configured_node_id() {
+        let generator = SnowflakeIdGenerator::new();
+        generator.set_node_id(MAX_NODE_ID).unwrap();
+        let id = generator.get_id();
+        assert_eq!((id >> generator.sequence_bits) & 1023, MAX_NODE_ID as u64);
+        assert_eq!(generator.metrics().node_id(), MAX_NODE_ID);
+
+        generator.set_node_id(0).unwrap();
+        let next = generator.get_id();
+        assert_eq!((next >> generator.sequence_bits) & 1023, 0);
+        assert_ne!(next, id);
+
+        let err = generator.set_node_id(MAX_NODE_ID + 1).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidOperation);
+        assert_eq!(generator.node_id(), 0);
+    }
+
+    #[test]
+    fn counts_sequence_exhaustions() {
+        let generator = SnowflakeIdGenerator::new();
+        let now = get_current_time_or_zero() as u64;
+        for _ in 0..=4096 {
+            generator.id_at(|| now);
+        }
+        assert!(generator.metrics().sequence_exhaustions() >= 1);
+    }
+
     #[test]
     fn generates_id_with_correct_node_id() {
         let generator = SnowflakeIdGenerator::new();
         let id = generator.get_id();
         let node_id = (id >> generator.sequence_bits) & ((1 << 10) - 1);
-        assert_eq!(node_id, generator.node_id);
+        assert_eq!(node_id, generator.node_id() as u64);
     }
 
     #[test]
@@ -129,6 +460,23 @@ mod tests {
         assert!(timestamp >= get_current_time_or_zero() as u64);
     }
 
+    #[test]
+    fn keeps_sequence_within_its_bits() {
+        let generator = SnowflakeIdGenerator::new();
+        let first = generator.get_id();
+        let mut last = first;
+        for _ in 0..10_000 {
+            let id = generator.get_id();
+            assert!(id > last);
+            last = id;
+        }
+
+        // 10 000 ids need at least three milliseconds of 4096 sequence numbers
+        let shift = generator.timestamp_left_shift;
+        assert!((last >> shift) - (first >> shift) >= 2);
+        assert_eq!((last >> generator.sequence_bits) & 1023, generator.node_id() as u64);
+    }
+
     #[test]
     fn handles_multiple_concurrent_id_generation() {
         use std::sync::Arc;
@@ -0,0 +1,72 @@
+use super::WriteToken;
+
+/// Default number of documents written back per batch by
+/// [`update_each`](super::NitriteCollection::update_each).
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Options of an [`update_each`](super::NitriteCollection::update_each).
+///
+/// The changed documents are written back in batches; each batch is applied atomically,
+/// like a [`bulk_write`](super::NitriteCollectionProvider::bulk_write).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateEachOptions {
+    batch_size: usize,
+}
+
+impl Default for UpdateEachOptions {
+    fn default() -> Self {
+        UpdateEachOptions {
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+impl UpdateEachOptions {
+    /// Creates the default options, writing back 500 documents per batch.
+    pub fn new() -> Self {
+        UpdateEachOptions::default()
+    }
+
+    /// Sets how many documents are read and written back per batch (at least 1).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Returns how many documents are read and written back per batch.
+    pub fn get_batch_size(&self) -> usize {
+        self.batch_size
+    }
+}
+
+/// The outcome of an [`update_each`](super::NitriteCollection::update_each).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateEachResult {
+    pub(crate) matched_count: usize,
+    pub(crate) modified_count: usize,
+    pub(crate) batch_count: usize,
+    pub(crate) write_token: WriteToken,
+}
+
+impl UpdateEachResult {
+    /// Returns the number of documents passed to the closure.
+    pub fn matched_count(&self) -> usize {
+        self.matched_count
+    }
+
+    /// Returns the number of documents the closure changed and that were written back.
+    pub fn modified_count(&self) -> usize {
+        self.modified_count
+    }
+
+    /// Returns the number of batches that were written.
+    pub fn batch_count(&self) -> usize {
+        self.batch_count
+    }
+
+    /// Returns the token of the last batch, to be passed to
+    /// [`FindOptions::after_write`](super::FindOptions::after_write).
+    pub fn write_token(&self) -> WriteToken {
+        self.write_token
+    }
+}
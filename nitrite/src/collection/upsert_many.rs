@@ -0,0 +1,373 @@
+use super::{BulkOperation, Document, NitriteCollectionProvider, NitriteId, WriteToken};
+use crate::common::{Value, DOC_ID, UNIQUE_INDEX};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::{and, by_id, field, Filter};
+use std::collections::HashMap;
+
+/// Default number of keys looked up per query by an
+/// [`upsert_many`](super::NitriteCollectionProvider::upsert_many).
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// What an [`upsert_many`](super::NitriteCollectionProvider::upsert_many) matches incoming
+/// documents on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictTarget {
+    /// The id: the `_id` of a collection document, the `#[entity(id)]` field of an entity.
+    Id,
+    /// The field of a unique index, such as an identifier assigned by another system.
+    Index(String),
+}
+
+impl ConflictTarget {
+    /// Creates a target matching documents on the unique index of `field_name`.
+    pub fn index(field_name: &str) -> Self {
+        ConflictTarget::Index(field_name.to_string())
+    }
+}
+
+/// Options of an [`upsert_many`](super::NitriteCollectionProvider::upsert_many).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpsertManyOptions {
+    batch_size: usize,
+}
+
+impl Default for UpsertManyOptions {
+    fn default() -> Self {
+        UpsertManyOptions {
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+impl UpsertManyOptions {
+    /// Creates the default options, looking up 500 keys per query.
+    pub fn new() -> Self {
+        UpsertManyOptions::default()
+    }
+
+    /// Sets how many keys are looked up per query (at least 1).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Returns how many keys are looked up per query.
+    pub fn get_batch_size(&self) -> usize {
+        self.batch_size
+    }
+}
+
+/// The outcome of an [`upsert_many`](super::NitriteCollectionProvider::upsert_many).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpsertManyResult {
+    pub(crate) inserted_ids: Vec<NitriteId>,
+    pub(crate) matched_count: usize,
+    pub(crate) modified_count: usize,
+    pub(crate) write_token: WriteToken,
+}
+
+impl UpsertManyResult {
+    /// Returns the ids of the documents inserted because no stored document had their key.
+    pub fn inserted_ids(&self) -> &[NitriteId] {
+        &self.inserted_ids
+    }
+
+    /// Returns the number of incoming documents that matched a stored document.
+    pub fn matched_count(&self) -> usize {
+        self.matched_count
+    }
+
+    /// Returns the number of matched documents that were changed by the update.
+    pub fn modified_count(&self) -> usize {
+        self.modified_count
+    }
+
+    /// Returns the token of the write, to be passed to
+    /// [`FindOptions::after_write`](super::FindOptions::after_write).
+    pub fn write_token(&self) -> WriteToken {
+        self.write_token
+    }
+}
+
+/// Returns the fields a conflict target matches on, checking that a unique index covers
+/// them unless they are the `_id`.
+pub(crate) fn conflict_fields<C: NitriteCollectionProvider + ?Sized>(
+    collection: &C,
+    target: &ConflictTarget,
+) -> NitriteResult<Vec<String>> {
+    match target {
+        ConflictTarget::Id => Ok(vec![DOC_ID.to_string()]),
+        ConflictTarget::Index(field_name) => {
+            let fields = vec![field_name.clone()];
+            check_unique_index(collection, &fields)?;
+            Ok(fields)
+        }
+    }
+}
+
+/// Checks that the collection has a unique index on exactly `fields`.
+pub(crate) fn check_unique_index<C: NitriteCollectionProvider + ?Sized>(
+    collection: &C,
+    fields: &[String],
+) -> NitriteResult<()> {
+    let indexed = collection.list_indexes()?.iter().any(|descriptor| {
+        descriptor.index_type() == UNIQUE_INDEX
+            && descriptor.index_fields().field_names() == fields
+    });
+    if indexed {
+        Ok(())
+    } else {
+        log::error!("No unique index on {:?} to upsert on", fields);
+        Err(NitriteError::new(
+            &format!(
+                "Cannot upsert on {:?}: the conflict target must be covered by a unique index",
+                fields
+            ),
+            ErrorKind::IndexNotFound,
+        ))
+    }
+}
+
+/// Inserts the documents whose key, the values of `key_fields`, no stored document has and
+/// updates the stored documents of the other keys, in one bulk write.
+///
+/// Documents of the batch sharing a key are merged in order, so later values win. The
+/// stored documents are looked up `batch_size` keys per query; a document without an `_id`
+/// is always inserted when the key is the `_id`.
+pub(crate) fn upsert_documents<C: NitriteCollectionProvider + ?Sized>(
+    collection: &C,
+    documents: Vec<Document>,
+    key_fields: &[String],
+    options: &UpsertManyOptions,
+) -> NitriteResult<UpsertManyResult> {
+    let mut pending: Vec<Document> = Vec::with_capacity(documents.len());
+    let mut keys: Vec<Option<Vec<Value>>> = Vec::with_capacity(documents.len());
+    let mut positions: HashMap<Vec<Value>, usize> = HashMap::with_capacity(documents.len());
+
+    for (index, document) in documents.into_iter().enumerate() {
+        let key = document_key(&document, key_fields, index)?;
+        if let Some(key) = &key {
+            if let Some(&position) = positions.get(key) {
+                pending[position].merge(&document)?;
+                continue;
+            }
+            positions.insert(key.clone(), pending.len());
+        }
+        pending.push(document);
+        keys.push(key);
+    }
+
+    let mut existing: Vec<Option<NitriteId>> = vec![None; pending.len()];
+    let keyed: Vec<usize> = (0..pending.len()).filter(|p| keys[*p].is_some()).collect();
+    for batch in keyed.chunks(options.get_batch_size()) {
+        if key_fields == [DOC_ID] {
+            for &position in batch {
+                if let Some([Value::NitriteId(id)]) = keys[position].as_deref() {
+                    if collection.get_by_id(id)?.is_some() {
+                        existing[position] = Some(*id);
+                    }
+                }
+            }
+        } else if let [field_name] = key_fields {
+            let values: Vec<Value> = batch
+                .iter()
+                .filter_map(|&position| keys[position].as_ref().map(|key| key[0].clone()))
+                .collect();
+            for stored in collection.find(field(field_name).in_array(values))? {
+                let mut stored = stored?;
+                let key = vec![stored.get(field_name)?];
+                if let Some(&position) = positions.get(&key) {
+                    existing[position] = Some(stored.id()?);
+                }
+            }
+        } else {
+            for &position in batch {
+                let Some(key) = &keys[position] else {
+                    continue;
+                };
+                let filters: Vec<Filter> = key_fields
+                    .iter()
+                    .zip(key)
+                    .map(|(field_name, value)| field(field_name).eq(value.clone()))
+                    .collect();
+                if let Some(stored) = collection.find(and(filters))?.next() {
+                    existing[position] = Some(stored?.id()?);
+                }
+            }
+        }
+    }
+
+    let mut result = UpsertManyResult::default();
+    let mut operations = Vec::with_capacity(pending.len());
+    for (mut document, stored_id) in pending.into_iter().zip(existing) {
+        match stored_id {
+            Some(id) => {
+                document.put(DOC_ID, Value::NitriteId(id))?;
+                operations.push(BulkOperation::UpdateOne {
+                    filter: by_id(id),
+                    update: document,
+                    upsert: false,
+                });
+                result.matched_count += 1;
+            }
+            None => operations.push(BulkOperation::InsertOne(document)),
+        }
+    }
+    if operations.is_empty() {
+        return Ok(result);
+    }
+
+    let written = collection.bulk_write(operations)?;
+    if let Some(failure) = written.errors().first() {
+        log::error!("Failed to write the documents of upsert_many: {}", failure.error());
+        return Err(failure.error().clone());
+    }
+    result.inserted_ids = written.inserted_ids().to_vec();
+    result.modified_count = written.modified_count();
+    result.write_token = written.write_token();
+    Ok(result)
+}
+
+/// Returns the key of the document at `index` of the batch, or `None` if the key is the
+/// `_id` and the document has none.
+fn document_key(
+    document: &Document,
+    key_fields: &[String],
+    index: usize,
+) -> NitriteResult<Option<Vec<Value>>> {
+    if key_fields == [DOC_ID] && !document.has_id() {
+        return Ok(None);
+    }
+
+    let mut key = Vec::with_capacity(key_fields.len());
+    for field_name in key_fields {
+        let value = document.get(field_name)?;
+        if value.is_null() {
+            log::error!("Document {} of upsert_many has no value for '{}'", index, field_name);
+            return Err(NitriteError::new(
+                &format!(
+                    "Document {} of the batch has no value for the conflict field '{}'",
+                    index, field_name
+                ),
+                ErrorKind::MissingRequiredField,
+            ));
+        }
+        if value.is_array() {
+            log::error!("Document {} of upsert_many has an array for '{}'", index, field_name);
+            return Err(NitriteError::new(
+                &format!(
+                    "Document {} of the batch has an array for the conflict field '{}'",
+                    index, field_name
+                ),
+                ErrorKind::InvalidDataType,
+            ));
+        }
+        key.push(value);
+    }
+    Ok(Some(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::NitriteCollection;
+    use crate::doc;
+    use crate::index::unique_index;
+    use crate::nitrite::Nitrite;
+
+    fn books() -> (Nitrite, NitriteCollection) {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let books = db.collection("books").unwrap();
+        books.create_index(vec!["isbn"], &unique_index()).unwrap();
+        (db, books)
+    }
+
+    #[test]
+    fn test_upsert_many_inserts_and_updates() {
+        let (_db, books) = books();
+        books
+            .insert(doc! { isbn: "1", title: "Old", stock: 3 })
+            .unwrap();
+
+        let result = books
+            .upsert_many(
+                vec![
+                    doc! { isbn: "1", title: "New" },
+                    doc! { isbn: "2", title: "Other" },
+                ],
+                ConflictTarget::index("isbn"),
+            )
+            .unwrap();
+        assert_eq!(result.matched_count(), 1);
+        assert_eq!(result.modified_count(), 1);
+        assert_eq!(result.inserted_ids().len(), 1);
+        assert_eq!(books.size().unwrap(), 2);
+
+        let first = books.find(field("isbn").eq("1")).unwrap().next().unwrap().unwrap();
+        assert_eq!(first.get("title").unwrap(), Value::from("New"));
+        assert_eq!(first.get("stock").unwrap(), Value::from(3));
+    }
+
+    #[test]
+    fn test_upsert_many_merges_duplicate_keys() {
+        let (_db, books) = books();
+        let result = books
+            .upsert_many_with_options(
+                vec![
+                    doc! { isbn: "1", title: "First", pages: 10 },
+                    doc! { isbn: "1", title: "Second" },
+                ],
+                ConflictTarget::index("isbn"),
+                &UpsertManyOptions::new().batch_size(1),
+            )
+            .unwrap();
+        assert_eq!(result.inserted_ids().len(), 1);
+
+        let book = books.find(field("isbn").eq("1")).unwrap().next().unwrap().unwrap();
+        assert_eq!(book.get("title").unwrap(), Value::from("Second"));
+        assert_eq!(book.get("pages").unwrap(), Value::from(10));
+    }
+
+    #[test]
+    fn test_upsert_many_on_id() {
+        let (_db, books) = books();
+        let mut stored = doc! { isbn: "1", title: "Old" };
+        let id = stored.id().unwrap();
+        books.insert(stored).unwrap();
+
+        let mut update = doc! { isbn: "1", title: "New" };
+        update.put(DOC_ID, id).unwrap();
+        let result = books
+            .upsert_many(vec![update, doc! { isbn: "2" }], ConflictTarget::Id)
+            .unwrap();
+        assert_eq!(result.matched_count(), 1);
+        assert_eq!(result.inserted_ids().len(), 1);
+        assert_eq!(
+            books.get_by_id(&id).unwrap().unwrap().get("title").unwrap(),
+            Value::from("New")
+        );
+    }
+
+    #[test]
+    fn test_upsert_many_needs_unique_index() {
+        let (_db, books) = books();
+        let error = books
+            .upsert_many(vec![doc! { title: "x" }], ConflictTarget::index("title"))
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::IndexNotFound);
+    }
+
+    #[test]
+    fn test_upsert_many_rejects_missing_key() {
+        let (_db, books) = books();
+        books.insert(doc! { isbn: "1" }).unwrap();
+        let error = books
+            .upsert_many(
+                vec![doc! { isbn: "2" }, doc! { title: "no isbn" }],
+                ConflictTarget::index("isbn"),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::MissingRequiredField);
+        assert_eq!(books.size().unwrap(), 1);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a query waits for a write to become visible before it gives up.
+const MAX_VISIBILITY_WAIT: Duration = Duration::from_secs(30);
+
+/// Identifies a write operation, so that a later query can wait until the write is visible.
+///
+/// Every write on a collection returns a token with its result. Passing the token to
+/// [`FindOptions::after_write`](super::FindOptions::after_write) makes the query wait until
+/// the write is visible to all threads before it runs. A write on a collection is visible
+/// as soon as it returns; a write made inside a transaction becomes visible when the
+/// transaction commits (or is discarded when it rolls back).
+///
+/// Tokens are cheap to copy and can be handed between threads, for example from the
+/// request that wrote a document to the follow-up request that reads it. They are only
+/// meaningful for the database instance that issued them and do not survive a restart.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let token = orders.insert(doc! { status: "paid" })?.write_token();
+///
+/// // on another thread
+/// let cursor = orders.find_with_options(
+///     field("status").eq("paid"),
+///     &FindOptions::new().after_write(token),
+/// )?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct WriteToken {
+    sequence: u64,
+}
+
+impl WriteToken {
+    /// Returns the sequence number of the write. Later writes have higher numbers; the
+    /// default token, with number 0, does not refer to any write.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// Issues write tokens and tracks the writes that are not yet visible.
+#[derive(Clone, Default)]
+pub(crate) struct WriteTracker {
+    inner: Arc<WriteTrackerInner>,
+}
+
+#[derive(Default)]
+struct WriteTrackerInner {
+    last_sequence: AtomicU64,
+    pending: Mutex<HashSet<u64>>,
+    released: Condvar,
+}
+
+impl WriteTracker {
+    pub fn new() -> Self {
+        WriteTracker::default()
+    }
+
+    /// Issues a token for a write that is already visible.
+    pub fn issue(&self) -> WriteToken {
+        WriteToken {
+            sequence: self.inner.last_sequence.fetch_add(1, Ordering::SeqCst) + 1,
+        }
+    }
+
+    /// Issues a token for writes that become visible only when the token is released.
+    pub fn reserve(&self) -> WriteToken {
+        let mut pending = self.inner.pending.lock();
+        let token = self.issue();
+        pending.insert(token.sequence);
+        token
+    }
+
+    /// Marks the writes of a reserved token as settled and wakes up the waiting queries.
+    pub fn release(&self, token: WriteToken) {
+        if self.inner.pending.lock().remove(&token.sequence) {
+            self.inner.released.notify_all();
+        }
+    }
+
+    /// Blocks until the writes of `token` are visible.
+    pub fn wait_for(&self, token: WriteToken) -> NitriteResult<()> {
+        self.wait_for_with_timeout(token, MAX_VISIBILITY_WAIT)
+    }
+
+    fn wait_for_with_timeout(&self, token: WriteToken, timeout: Duration) -> NitriteResult<()> {
+        let deadline = Instant::now() + timeout;
+        let mut pending = self.inner.pending.lock();
+        while pending.contains(&token.sequence) {
+            if self.inner.released.wait_until(&mut pending, deadline).timed_out()
+                && pending.contains(&token.sequence)
+            {
+                log::error!("Write {} is still not visible after {:?}", token.sequence, timeout);
+                return Err(NitriteError::new(
+                    &format!("Write {} is still not visible after {:?}", token.sequence, timeout),
+                    ErrorKind::Timeout,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of a write operation that carries the token of the write.
+pub(crate) trait WriteTokenHolder {
+    fn set_write_token(&mut self, token: WriteToken);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_issued_tokens_are_visible() {
+        let tracker = WriteTracker::new();
+        let first = tracker.issue();
+        let second = tracker.issue();
+        assert!(second > first);
+        assert!(tracker.wait_for(first).is_ok());
+        assert!(tracker.wait_for(WriteToken::default()).is_ok());
+    }
+
+    #[test]
+    fn test_wait_for_reserved_token() {
+        let tracker = WriteTracker::new();
+        let token = tracker.reserve();
+        let err = tracker
+            .wait_for_with_timeout(token, Duration::from_millis(20))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Timeout);
+
+        let releaser = tracker.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            releaser.release(token);
+        });
+        assert!(tracker.wait_for(token).is_ok());
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+use super::{columnar_error, ColumnType, SchemaMapping};
+use crate::collection::Document;
+use crate::common::{value_text, Value};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use arrow_array::builder::{
+    BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder, StringBuilder, UInt64Builder,
+};
+use arrow_array::{ArrayRef, ListArray, RecordBatch, RecordBatchOptions};
+use arrow_buffer::{NullBufferBuilder, OffsetBuffer};
+use std::sync::Arc;
+
+/// Converts documents into a record batch with the columns of `mapping`.
+///
+/// # Errors
+///
+/// Returns an `InvalidDataType` error if a value does not fit the type of its column.
+pub fn to_record_batch(
+    documents: &[Document],
+    mapping: &SchemaMapping,
+) -> NitriteResult<RecordBatch> {
+    let mut columns = Vec::with_capacity(mapping.columns().len());
+    for column in mapping.columns() {
+        let mut builder = ColumnBuilder::new(column.column_type(), documents.len());
+        for document in documents {
+            let value = document.get(column.field())?;
+            builder.append(&value).map_err(|err| {
+                let message = format!("Field {} {}", column.field(), err.message());
+                log::error!("{}", message);
+                NitriteError::new(&message, ErrorKind::InvalidDataType)
+            })?;
+        }
+        columns.push(builder.finish()?);
+    }
+
+    let options = RecordBatchOptions::new().with_row_count(Some(documents.len()));
+    RecordBatch::try_new_with_options(mapping.arrow_schema(), columns, &options)
+        .map_err(columnar_error)
+}
+
+/// Builds the Arrow array of one column.
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Int64(Int64Builder),
+    UInt64(UInt64Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    Binary(BinaryBuilder),
+    List {
+        item_type: ColumnType,
+        items: Box<ColumnBuilder>,
+        offsets: Vec<i32>,
+        validity: NullBufferBuilder,
+    },
+}
+
+impl ColumnBuilder {
+    fn new(column_type: &ColumnType, capacity: usize) -> Self {
+        match column_type {
+            ColumnType::Boolean => ColumnBuilder::Boolean(BooleanBuilder::with_capacity(capacity)),
+            ColumnType::Int64 => ColumnBuilder::Int64(Int64Builder::with_capacity(capacity)),
+            ColumnType::UInt64 => ColumnBuilder::UInt64(UInt64Builder::with_capacity(capacity)),
+            ColumnType::Float64 => ColumnBuilder::Float64(Float64Builder::with_capacity(capacity)),
+            ColumnType::Utf8 => ColumnBuilder::Utf8(StringBuilder::with_capacity(capacity, 0)),
+            ColumnType::Binary => ColumnBuilder::Binary(BinaryBuilder::with_capacity(capacity, 0)),
+            ColumnType::List(item_type) => {
+                let mut offsets = Vec::with_capacity(capacity + 1);
+                offsets.push(0);
+                ColumnBuilder::List {
+                    item_type: (**item_type).clone(),
+                    items: Box::new(ColumnBuilder::new(item_type, capacity)),
+                    offsets,
+                    validity: NullBufferBuilder::new(capacity),
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, value: &Value) -> NitriteResult<()> {
+        if matches!(value, Value::Null | Value::Unknown) {
+            self.append_null();
+            return Ok(());
+        }
+
+        match self {
+            ColumnBuilder::Boolean(builder) => match value {
+                Value::Bool(value) => builder.append_value(*value),
+                _ => return Err(mismatch(value, "Boolean")),
+            },
+            ColumnBuilder::Int64(builder) => {
+                let value = integer(value)
+                    .and_then(|integer| i64::try_from(integer).ok())
+                    .ok_or_else(|| mismatch(value, "Int64"))?;
+                builder.append_value(value);
+            }
+            ColumnBuilder::UInt64(builder) => {
+                let value = integer(value)
+                    .and_then(|integer| u64::try_from(integer).ok())
+                    .ok_or_else(|| mismatch(value, "UInt64"))?;
+                builder.append_value(value);
+            }
+            ColumnBuilder::Float64(builder) => match value {
+                Value::F32(value) => builder.append_value(*value as f64),
+                Value::F64(value) => builder.append_value(*value),
+                _ => {
+                    let value = integer(value).ok_or_else(|| mismatch(value, "Float64"))?;
+                    builder.append_value(value as f64);
+                }
+            },
+            ColumnBuilder::Utf8(builder) => match value {
+                Value::String(value) => builder.append_value(value),
+                Value::Char(value) => builder.append_value(value.to_string()),
+                _ => builder.append_value(value_text(value)),
+            },
+            ColumnBuilder::Binary(builder) => match value {
+                Value::Bytes(value) => builder.append_value(value),
+                _ => return Err(mismatch(value, "Binary")),
+            },
+            ColumnBuilder::List {
+                items,
+                offsets,
+                validity,
+                ..
+            } => match value {
+                Value::Array(values) => {
+                    for value in values {
+                        items.append(value)?;
+                    }
+                    let last = offsets.last().copied().unwrap_or_default();
+                    let length = i32::try_from(values.len())
+                        .ok()
+                        .and_then(|length| last.checked_add(length))
+                        .ok_or_else(|| {
+                            NitriteError::new(
+                                "holds too many list items",
+                                ErrorKind::InvalidDataType,
+                            )
+                        })?;
+                    offsets.push(length);
+                    validity.append_non_null();
+                }
+                _ => return Err(mismatch(value, "List")),
+            },
+        }
+        Ok(())
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            ColumnBuilder::Boolean(builder) => builder.append_null(),
+            ColumnBuilder::Int64(builder) => builder.append_null(),
+            ColumnBuilder::UInt64(builder) => builder.append_null(),
+            ColumnBuilder::Float64(builder) => builder.append_null(),
+            ColumnBuilder::Utf8(builder) => builder.append_null(),
+            ColumnBuilder::Binary(builder) => builder.append_null(),
+            ColumnBuilder::List {
+                offsets, validity, ..
+            } => {
+                offsets.push(offsets.last().copied().unwrap_or_default());
+                validity.append_null();
+            }
+        }
+    }
+
+    fn finish(self) -> NitriteResult<ArrayRef> {
+        Ok(match self {
+            ColumnBuilder::Boolean(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Int64(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::UInt64(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Float64(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Utf8(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Binary(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::List {
+                item_type,
+                items,
+                offsets,
+                mut validity,
+            } => {
+                let list = ListArray::try_new(
+                    Arc::new(item_type.item_field()),
+                    OffsetBuffer::new(offsets.into()),
+                    items.finish()?,
+                    validity.finish(),
+                )
+                .map_err(columnar_error)?;
+                Arc::new(list)
+            }
+        })
+    }
+}
+
+/// Returns the value of an integer of any width.
+fn integer(value: &Value) -> Option<i128> {
+    match value {
+        Value::I8(value) => Some(*value as i128),
+        Value::U8(value) => Some(*value as i128),
+        Value::I16(value) => Some(*value as i128),
+        Value::U16(value) => Some(*value as i128),
+        Value::I32(value) => Some(*value as i128),
+        Value::U32(value) => Some(*value as i128),
+        Value::I64(value) => Some(*value as i128),
+        Value::U64(value) => Some(*value as i128),
+        Value::ISize(value) => Some(*value as i128),
+        Value::USize(value) => Some(*value as i128),
+        Value::I128(value) => Some(*value),
+        Value::U128(value) => i128::try_from(*value).ok(),
+        _ => None,
+    }
+}
+
+fn mismatch(value: &Value, column_type: &str) -> NitriteError {
+    NitriteError::new(
+        &format!("cannot be stored as {}: {}", column_type, value),
+        ErrorKind::InvalidDataType,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use arrow_array::{Array, Float64Array, Int64Array, StringArray};
+
+    #[test]
+    fn test_to_record_batch() {
+        let documents = [
+            doc! { name: "Ada", age: 36, score: 1, tags: ["a", "b"], address: { city: "London" } },
+            doc! { name: "Grace", score: 2.5 },
+        ];
+        let mapping = SchemaMapping::new()
+            .column("name", ColumnType::Utf8)
+            .column("age", ColumnType::Int64)
+            .column("score", ColumnType::Float64)
+            .column("tags", ColumnType::List(Box::new(ColumnType::Utf8)))
+            .column_as("address.city", "city", ColumnType::Utf8);
+
+        let batch = to_record_batch(&documents, &mapping).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(4).name(), "city");
+
+        let age = batch.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(age.value(0), 36);
+        assert!(age.is_null(1));
+
+        let score = batch.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(score.values().to_vec(), vec![1.0, 2.5]);
+
+        let tags = batch.column(3).as_any().downcast_ref::<ListArray>().unwrap();
+        let first = tags.value(0);
+        let first = first.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(first.value(1), "b");
+        assert!(tags.is_null(1));
+
+        let city = batch.column(4).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(city.value(0), "London");
+
+        // nested values of a text column are written as compact JSON
+        let mapping = SchemaMapping::new().column("address", ColumnType::Utf8);
+        let batch = to_record_batch(&documents[..1], &mapping).unwrap();
+        let address = batch.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(address.value(0), r#"{"city":"London"}"#);
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let documents = [doc! { age: "old" }];
+        let mapping = SchemaMapping::new().column("age", ColumnType::Int64);
+        let err = to_record_batch(&documents, &mapping).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidDataType);
+        assert!(err.message().starts_with("Field age cannot be stored as Int64"));
+
+        let documents = [doc! { count: (u64::MAX) }];
+        let mapping = SchemaMapping::new().column("count", ColumnType::Int64);
+        assert!(to_record_batch(&documents, &mapping).is_err());
+        let mapping = SchemaMapping::new().column("count", ColumnType::UInt64);
+        assert!(to_record_batch(&documents, &mapping).is_ok());
+    }
+}
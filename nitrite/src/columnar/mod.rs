@@ -0,0 +1,34 @@
+//! Arrow record batches and Parquet export of query results.
+//!
+//! Documents are mapped to columns by a [`SchemaMapping`], which names the field (a
+//! top-level name or an embedded path like `address.city`) and the [`ColumnType`] of
+//! every column. A mapping is either written by hand or inferred from the documents.
+//!
+//! - [`DocumentCursor::to_arrow`](crate::common::DocumentCursor::to_arrow) converts the
+//!   documents of a cursor into one Arrow `RecordBatch`.
+//! - [`NitriteCollection::export_parquet`](crate::collection::NitriteCollection::export_parquet)
+//!   writes the documents matching a filter to a Parquet file, in batches.
+//! - [`to_record_batch`] converts documents read by other means, e.g. by language
+//!   bindings.
+//!
+//! This module requires the `arrow` feature.
+
+mod batch;
+mod parquet_export;
+mod schema;
+
+pub use batch::to_record_batch;
+pub use schema::*;
+
+pub(crate) use parquet_export::export_parquet;
+
+use crate::errors::{ErrorKind, NitriteError};
+use std::fmt::Display;
+
+pub(crate) fn columnar_error(err: impl Display) -> NitriteError {
+    log::error!("Columnar conversion failed: {}", err);
+    NitriteError::new(
+        &format!("Columnar conversion failed: {}", err),
+        ErrorKind::EncodingError,
+    )
+}
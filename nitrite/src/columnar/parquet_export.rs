@@ -0,0 +1,105 @@
+use super::schema::SchemaInference;
+use super::{columnar_error, to_record_batch, SchemaMapping};
+use crate::collection::{Document, NitriteCollection};
+use crate::errors::NitriteResult;
+use crate::filter::Filter;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+
+/// Number of documents converted into one record batch while exporting.
+const EXPORT_BATCH_SIZE: usize = 4096;
+
+/// Writes the documents matching `filter` to a zstd-compressed Parquet file and returns
+/// their number.
+///
+/// Without a mapping the schema is inferred from the `projection` fields, or from all the
+/// top-level fields, in a first pass that also collects the ids of the documents; the
+/// documents are then read again by id, so a document removed in between is skipped.
+pub(crate) fn export_parquet(
+    collection: &NitriteCollection,
+    path: &Path,
+    filter: Filter,
+    mapping: Option<&SchemaMapping>,
+    projection: Option<&[&str]>,
+) -> NitriteResult<u64> {
+    match mapping {
+        Some(mapping) => {
+            let mut writer = BatchWriter::create(path, mapping)?;
+            for document in collection.find(filter)? {
+                writer.push(document?)?;
+            }
+            writer.close()
+        }
+        None => {
+            let mut inference = SchemaInference::new(projection);
+            let mut ids = Vec::new();
+            for document in collection.find(filter)? {
+                let mut document = document?;
+                inference.add(&document)?;
+                ids.push(document.id()?);
+            }
+
+            let mapping = inference.finish();
+            let mut writer = BatchWriter::create(path, &mapping)?;
+            for id in &ids {
+                if let Some(document) = collection.get_by_id(id)? {
+                    writer.push(document)?;
+                }
+            }
+            writer.close()
+        }
+    }
+}
+
+/// Buffers documents and writes them as record batches.
+struct BatchWriter<'a> {
+    mapping: &'a SchemaMapping,
+    writer: ArrowWriter<File>,
+    buffer: Vec<Document>,
+    rows: u64,
+}
+
+impl<'a> BatchWriter<'a> {
+    fn create(path: &Path, mapping: &'a SchemaMapping) -> NitriteResult<Self> {
+        let file = File::create(path)?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let writer = ArrowWriter::try_new(file, mapping.arrow_schema(), Some(properties))
+            .map_err(columnar_error)?;
+
+        Ok(BatchWriter {
+            mapping,
+            writer,
+            buffer: Vec::with_capacity(EXPORT_BATCH_SIZE),
+            rows: 0,
+        })
+    }
+
+    fn push(&mut self, document: Document) -> NitriteResult<()> {
+        self.buffer.push(document);
+        if self.buffer.len() >= EXPORT_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> NitriteResult<()> {
+        if !self.buffer.is_empty() {
+            let batch = to_record_batch(&self.buffer, self.mapping)?;
+            self.writer.write(&batch).map_err(columnar_error)?;
+            self.rows += self.buffer.len() as u64;
+            self.buffer.clear();
+        }
+        Ok(())
+    }
+
+    fn close(mut self) -> NitriteResult<u64> {
+        self.flush()?;
+        self.writer.close().map_err(columnar_error)?;
+        Ok(self.rows)
+    }
+}
@@ -0,0 +1,313 @@
+use crate::collection::Document;
+use crate::common::{is_reserved_field, Value, DOC_ID};
+use crate::errors::NitriteResult;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use indexmap::IndexMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+/// The type of a column, and of the Arrow array holding it.
+///
+/// | Column type | Arrow type | Accepted values |
+/// |---|---|---|
+/// | `Boolean` | `Boolean` | booleans |
+/// | `Int64` | `Int64` | integers in the `i64` range |
+/// | `UInt64` | `UInt64` | integers in the `u64` range |
+/// | `Float64` | `Float64` | floats and integers |
+/// | `Utf8` | `Utf8` | any value; strings, chars and ids as text, documents and arrays as JSON |
+/// | `Binary` | `Binary` | byte arrays |
+/// | `List(item)` | `List` | arrays whose elements are accepted by `item` |
+///
+/// A null or missing field is a null in every column type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Boolean,
+    Int64,
+    UInt64,
+    Float64,
+    Utf8,
+    Binary,
+    List(Box<ColumnType>),
+}
+
+impl ColumnType {
+    /// Returns the Arrow type of the column.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::Boolean => DataType::Boolean,
+            ColumnType::Int64 => DataType::Int64,
+            ColumnType::UInt64 => DataType::UInt64,
+            ColumnType::Float64 => DataType::Float64,
+            ColumnType::Utf8 => DataType::Utf8,
+            ColumnType::Binary => DataType::Binary,
+            ColumnType::List(item) => DataType::List(Arc::new(item.item_field())),
+        }
+    }
+
+    pub(crate) fn item_field(&self) -> Field {
+        Field::new("item", self.data_type(), true)
+    }
+
+    fn of(value: &Value) -> Option<ColumnType> {
+        match value {
+            Value::Null | Value::Unknown => None,
+            Value::Bool(_) => Some(ColumnType::Boolean),
+            Value::I8(_)
+            | Value::U8(_)
+            | Value::I16(_)
+            | Value::U16(_)
+            | Value::I32(_)
+            | Value::U32(_)
+            | Value::I64(_)
+            | Value::ISize(_) => Some(ColumnType::Int64),
+            Value::U64(value) if i64::try_from(*value).is_ok() => Some(ColumnType::Int64),
+            Value::USize(value) if i64::try_from(*value).is_ok() => Some(ColumnType::Int64),
+            Value::U64(_) | Value::USize(_) => Some(ColumnType::UInt64),
+            Value::I128(value) if i64::try_from(*value).is_ok() => Some(ColumnType::Int64),
+            Value::U128(value) if i64::try_from(*value).is_ok() => Some(ColumnType::Int64),
+            Value::U128(value) if u64::try_from(*value).is_ok() => Some(ColumnType::UInt64),
+            Value::F32(_) | Value::F64(_) => Some(ColumnType::Float64),
+            Value::Bytes(_) => Some(ColumnType::Binary),
+            Value::Array(values) => {
+                let item = values
+                    .iter()
+                    .fold(None, |item, value| merge(item, ColumnType::of(value)));
+                Some(ColumnType::List(Box::new(item.unwrap_or(ColumnType::Utf8))))
+            }
+            _ => Some(ColumnType::Utf8),
+        }
+    }
+}
+
+impl Display for ColumnType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnType::List(item) => write!(f, "List({})", item),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Merges the types of two values of the same column; conflicting types become `Utf8`.
+fn merge(left: Option<ColumnType>, right: Option<ColumnType>) -> Option<ColumnType> {
+    use ColumnType::*;
+
+    match (left, right) {
+        (None, other) | (other, None) => other,
+        (Some(left), Some(right)) if left == right => Some(left),
+        (Some(Int64 | UInt64 | Float64), Some(Int64 | UInt64 | Float64)) => Some(Float64),
+        (Some(List(left)), Some(List(right))) => {
+            let item = merge(Some(*left), Some(*right)).unwrap_or(Utf8);
+            Some(List(Box::new(item)))
+        }
+        _ => Some(Utf8),
+    }
+}
+
+/// One column of a [`SchemaMapping`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnMapping {
+    field: String,
+    name: String,
+    column_type: ColumnType,
+}
+
+impl ColumnMapping {
+    /// Returns the field read from the documents.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the name of the column.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn column_type(&self) -> &ColumnType {
+        &self.column_type
+    }
+}
+
+/// Maps the fields of documents to the columns of an Arrow schema.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mapping = SchemaMapping::new()
+///     .column("_id", ColumnType::Utf8)
+///     .column("total", ColumnType::Float64)
+///     .column_as("customer.name", "customer", ColumnType::Utf8)
+///     .column("tags", ColumnType::List(Box::new(ColumnType::Utf8)));
+///
+/// let batch = orders.find(field("total").gt(100))?.to_arrow(Some(&mapping))?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaMapping {
+    columns: Vec<ColumnMapping>,
+}
+
+impl SchemaMapping {
+    /// Creates a mapping without columns.
+    pub fn new() -> Self {
+        SchemaMapping::default()
+    }
+
+    /// Adds a column named after the field.
+    pub fn column(self, field: &str, column_type: ColumnType) -> Self {
+        self.column_as(field, field, column_type)
+    }
+
+    /// Adds a column with a name of its own, e.g. for an embedded field.
+    pub fn column_as(mut self, field: &str, name: &str, column_type: ColumnType) -> Self {
+        self.columns.push(ColumnMapping {
+            field: field.to_string(),
+            name: name.to_string(),
+            column_type,
+        });
+        self
+    }
+
+    /// Infers a mapping from the documents.
+    ///
+    /// The columns are `_id` followed by the top-level fields in the order they are
+    /// first seen; the other metadata fields are left out. See [`ColumnType`] for the
+    /// types; a field holding values of conflicting types, or only nulls, becomes `Utf8`.
+    pub fn infer(documents: &[Document]) -> NitriteResult<Self> {
+        let mut inference = SchemaInference::new(None);
+        for document in documents {
+            inference.add(document)?;
+        }
+        Ok(inference.finish())
+    }
+
+    /// Infers a mapping with one column per field, which may be embedded fields.
+    pub fn infer_fields(documents: &[Document], fields: &[&str]) -> NitriteResult<Self> {
+        let mut inference = SchemaInference::new(Some(fields));
+        for document in documents {
+            inference.add(document)?;
+        }
+        Ok(inference.finish())
+    }
+
+    pub fn columns(&self) -> &[ColumnMapping] {
+        &self.columns
+    }
+
+    /// Returns the Arrow schema; every column is nullable.
+    pub fn arrow_schema(&self) -> SchemaRef {
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .map(|column| Field::new(&column.name, column.column_type.data_type(), true))
+            .collect();
+        Arc::new(Schema::new(fields))
+    }
+}
+
+/// Accumulates the column types of documents seen one at a time.
+pub(crate) struct SchemaInference {
+    projected: bool,
+    types: IndexMap<String, Option<ColumnType>>,
+}
+
+impl SchemaInference {
+    pub(crate) fn new(fields: Option<&[&str]>) -> Self {
+        let mut types = IndexMap::new();
+        match fields {
+            Some(fields) => {
+                for field in fields {
+                    types.insert(field.to_string(), None);
+                }
+            }
+            None => {
+                types.insert(DOC_ID.to_string(), None);
+            }
+        }
+
+        SchemaInference {
+            projected: fields.is_some(),
+            types,
+        }
+    }
+
+    pub(crate) fn add(&mut self, document: &Document) -> NitriteResult<()> {
+        if self.projected {
+            for (field, column_type) in self.types.iter_mut() {
+                let value = document.get(field)?;
+                *column_type = merge(column_type.take(), ColumnType::of(&value));
+            }
+        } else {
+            for (field, value) in document.iter() {
+                if field != DOC_ID && is_reserved_field(&field) {
+                    continue;
+                }
+                let column_type = self.types.entry(field).or_default();
+                *column_type = merge(column_type.take(), ColumnType::of(&value));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> SchemaMapping {
+        self.types
+            .into_iter()
+            .fold(SchemaMapping::new(), |mapping, (field, column_type)| {
+                mapping.column(&field, column_type.unwrap_or(ColumnType::Utf8))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    #[test]
+    fn test_infer() {
+        let mut first = doc! { name: "Ada", age: 36, score: 1, tags: ["a"], address: { city: "London" } };
+        first.id().unwrap();
+        let second = doc! { name: "Grace", score: 2.5, tags: [1, 2.0], active: true, misc: 1 };
+        let third = doc! { misc: "one" };
+
+        let mapping = SchemaMapping::infer(&[first, second, third]).unwrap();
+        let columns: Vec<(&str, &ColumnType)> = mapping
+            .columns()
+            .iter()
+            .map(|column| (column.name(), column.column_type()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("_id", &ColumnType::Utf8),
+                ("address", &ColumnType::Utf8),
+                ("age", &ColumnType::Int64),
+                ("name", &ColumnType::Utf8),
+                ("score", &ColumnType::Float64),
+                ("tags", &ColumnType::List(Box::new(ColumnType::Utf8))),
+                ("active", &ColumnType::Boolean),
+                ("misc", &ColumnType::Utf8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infer_fields() {
+        let documents = [
+            doc! { name: "Ada", address: { city: "London", zip: 1 } },
+            doc! { name: "Grace", values: [1, 2] },
+        ];
+        let mapping =
+            SchemaMapping::infer_fields(&documents, &["address.zip", "values", "missing"]).unwrap();
+        assert_eq!(
+            mapping,
+            SchemaMapping::new()
+                .column("address.zip", ColumnType::Int64)
+                .column("values", ColumnType::List(Box::new(ColumnType::Int64)))
+                .column("missing", ColumnType::Utf8)
+        );
+
+        let schema = mapping.arrow_schema();
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert!(schema.field(0).is_nullable());
+    }
+}
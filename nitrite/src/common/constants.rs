@@ -3,12 +3,31 @@ pub const DOC_REVISION: &str = "_revision";
 pub const DOC_MODIFIED: &str = "_modified";
 pub const DOC_SOURCE: &str = "_source";
 pub const DOC_ID: &str = "_id";
+pub const DOC_EXPIRY: &str = "_expiry";
+pub const DOC_CONTENT_HASH: &str = "_hash";
 pub const TYPE_NAME: &str = "_type";
-pub const RESERVED_FIELDS: [&str; 4] = [DOC_ID, DOC_REVISION, DOC_MODIFIED, DOC_SOURCE];
+pub const ENTITY_KEY: &str = "_key";
+pub const DOC_SCHEMA_VERSION: &str = "_schema_version";
+pub const RESERVED_FIELDS: [&str; 6] = [
+    DOC_ID,
+    DOC_REVISION,
+    DOC_MODIFIED,
+    DOC_SOURCE,
+    DOC_EXPIRY,
+    DOC_CONTENT_HASH,
+];
+
+// metadata field constants, `DOC_REVISION` etc. are the names under the default prefix
+pub const DEFAULT_METADATA_PREFIX: &str = "_";
+pub const REVISION_FIELD_NAME: &str = "revision";
+pub const MODIFIED_FIELD_NAME: &str = "modified";
+pub const SOURCE_FIELD_NAME: &str = "source";
+pub const EXPIRY_FIELD_NAME: &str = "expiry";
+pub const CONTENT_HASH_FIELD_NAME: &str = "hash";
 
 // Compile-time assertion for reserved fields count
 const _: () = {
-    const RESERVED_FIELDS_COUNT: usize = 4;
+    const RESERVED_FIELDS_COUNT: usize = 6;
     const ACTUAL_COUNT: usize = RESERVED_FIELDS.len();
     const _: [(); 1] = [(); (ACTUAL_COUNT == RESERVED_FIELDS_COUNT) as usize];
 };
@@ -42,6 +61,42 @@ pub const FULL_TEXT_INDEX: &str = "full-text";
 pub const INTERNAL_NAME_SEPARATOR: &str = "|";
 pub const INDEX_PREFIX: &str = "$nitrite_index";
 pub const INDEX_META_PREFIX: &str = "$nitrite_index_meta";
+pub const INDEX_EXACT_KEYS: &str = "index_exact_keys";
+pub const HISTORY_PREFIX: &str = "$nitrite_history";
+pub const HISTORY_MAX_VERSIONS: &str = "history_max_versions";
+pub const HISTORY_MAX_AGE: &str = "history_max_age_ms";
+pub const DELETED_PREFIX: &str = "$nitrite_deleted";
+pub const COLLECTION_DURABILITY: &str = "collection_durability";
+pub const COLLECTION_REQUIRED_FIELDS: &str = "collection_required_fields";
+pub const COLLECTION_SOFT_DELETE: &str = "collection_soft_delete";
+pub const COLLECTION_MAX_DOCUMENTS: &str = "collection_max_documents";
+pub const COLLECTION_MAX_BYTES: &str = "collection_max_bytes";
+pub const COLLECTION_DOCUMENT_QUOTA: &str = "collection_document_quota";
+pub const COLLECTION_BYTE_QUOTA: &str = "collection_byte_quota";
+pub const COLLECTION_DETAILED_RESULTS: &str = "collection_detailed_results";
+pub const COLLECTION_EVENT_IMAGES: &str = "collection_event_images";
+pub const COLLECTION_SPLIT_FIELDS: &str = "collection_split_fields";
+pub const COLLECTION_CONTENT_HASH: &str = "collection_content_hash";
+pub const TOPIC_PREFIX: &str = "$nitrite_topic";
+pub const TENANT_PREFIX: &str = "$nitrite_tenant";
+pub const TOPIC_GROUP_PREFIX: &str = "$nitrite_topic_group";
+pub const TOPIC_GROUP_CURSOR: &str = "topic_group_cursor";
+pub const VIEW_PREFIX: &str = "$nitrite_view";
+pub const SHARD_PREFIX: &str = "$nitrite_shard";
+pub const SHARD_KEY: &str = "shard_key";
+pub const SHARD_COUNT: &str = "shard_count";
+pub const SHARD_BOUNDARIES: &str = "shard_boundaries";
+pub const ENTITY_SCHEMA_FINGERPRINT: &str = "entity_schema_fingerprint";
+pub const ENTITY_SCHEMA_FIELDS: &str = "entity_schema_fields";
+pub const ENTITY_SCHEMA_VERSION: &str = "entity_schema_version";
+pub const SORT_PREFIX: &str = "$nitrite_sort";
+pub const AUDIT_COLLECTION: &str = "$nitrite_audit";
+pub const TIME_SERIES_PREFIX: &str = "$nitrite_time_series";
+pub const TIME_SERIES_BUCKET_WIDTH: &str = "time_series_bucket_width_ms";
+pub const TIME_SERIES_RETENTION: &str = "time_series_retention_ms";
+pub const GRAPH_PREFIX: &str = "$nitrite_graph";
+pub const GRAPH_VERTICES: &str = "graph_vertices";
+pub const DEFAULT_SORT_MEMORY_BUDGET: u64 = 64 * 1024 * 1024;
 pub const INITIAL_SCHEMA_VERSION: u32 = 1;
 pub const NO2: &str = "NO\u{2082}";
 pub const REPLICATOR: &str = "Replicator.NO\u{2082}";
@@ -1,10 +1,11 @@
 #![allow(non_snake_case)]
 
 use crate::collection::{Document, NitriteId};
-use crate::common::{ReadExecutor, Value};
+use crate::common::{ReadExecutor, Value, DOC_SCHEMA_VERSION};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::{atomic, document_from_map, Atomic};
 use std::any::{Any, TypeId};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::Hash;
 use std::str::FromStr;
@@ -522,14 +523,14 @@ where
     fn from_value(value: &Value) -> NitriteResult<Self::Output> {
         match value {
             Value::Bytes(arr) => {
-                let mut vec = Vec::new();
+                let mut vec = Vec::with_capacity(arr.len());
                 for item in arr {
                     vec.push(T::from_value(&Value::U8(*item))?);
                 }
                 Ok(vec)
             }
             Value::Array(arr) => {
-                let mut vec = Vec::new();
+                let mut vec = Vec::with_capacity(arr.len());
                 for item in arr {
                     vec.push(T::from_value(item)?);
                 }
@@ -763,6 +764,80 @@ where
 }
 
 
+/// Upgrade hook of a versioned type, bringing a document of the given older version
+/// up to the current version of the type.
+pub type UpgradeFn = fn(u32, Document) -> NitriteResult<Document>;
+
+/// Returns the schema version a document was written with.
+///
+/// Documents written before their type was versioned carry no version and are at version 1.
+pub fn document_version(doc: &Document) -> NitriteResult<u32> {
+    match doc.get(DOC_SCHEMA_VERSION)? {
+        Value::Null => Ok(1),
+        value => match value.as_integer().and_then(|v| u32::try_from(v).ok()) {
+            Some(version) => Ok(version),
+            None => {
+                log::error!("Invalid schema version {} in document", value);
+                Err(NitriteError::new(
+                    &format!("Invalid schema version {} in document", value),
+                    ErrorKind::ObjectMappingError,
+                ))
+            }
+        },
+    }
+}
+
+/// Brings a document written by an older version of a type up to the `current` version.
+///
+/// Called by the `Convertible` derive of types declared with `#[convertible(version = N)]`
+/// before the fields are read. A document of an older version is passed to `upgrade`
+/// together with its version. Without an upgrade hook it fails with
+/// `ErrorKind::DocumentTooOld`, and a document written by a newer version of the type
+/// fails with ObjectMappingError.
+pub fn upgrade_document(
+    doc: &Document,
+    current: u32,
+    upgrade: Option<UpgradeFn>,
+) -> NitriteResult<Cow<'_, Document>> {
+    let version = document_version(doc)?;
+    if version == current {
+        return Ok(Cow::Borrowed(doc));
+    }
+
+    if version > current {
+        log::error!(
+            "Document of version {} is newer than the current version {}",
+            version,
+            current
+        );
+        return Err(NitriteError::new(
+            &format!(
+                "Document of version {} is newer than the current version {}",
+                version, current
+            ),
+            ErrorKind::ObjectMappingError,
+        ));
+    }
+
+    match upgrade {
+        Some(upgrade) => upgrade(version, doc.clone()).map(Cow::Owned),
+        None => {
+            log::error!(
+                "Document of version {} is older than the current version {} and has no upgrade",
+                version,
+                current
+            );
+            Err(NitriteError::new(
+                &format!(
+                    "Document of version {} is older than the current version {} and has no upgrade",
+                    version, current
+                ),
+                ErrorKind::DocumentTooOld { version, current },
+            ))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1109,4 +1184,69 @@ mod tests {
         let error = result.unwrap_err();
         assert_eq!(error.to_string(), "Value is not a tuple");
     }
-}
\ No newline at end of file
+
+    fn rename_title(version: u32, mut doc: Document) -> NitriteResult<Document> {
+        if version < 2 {
+            let title = doc.get("title")?;
+            doc.put("name", title)?;
+            doc.remove("title")?;
+        }
+        Ok(doc)
+    }
+
+    #[test]
+    fn test_document_version() {
+        let mut doc = Document::new();
+        assert_eq!(document_version(&doc).unwrap(), 1);
+        doc.put(DOC_SCHEMA_VERSION, Value::U32(3)).unwrap();
+        assert_eq!(document_version(&doc).unwrap(), 3);
+        doc.put(DOC_SCHEMA_VERSION, Value::I64(4)).unwrap();
+        assert_eq!(document_version(&doc).unwrap(), 4);
+        doc.put(DOC_SCHEMA_VERSION, Value::String("x".to_string()))
+            .unwrap();
+        assert!(document_version(&doc).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_document() {
+        let mut doc = Document::new();
+        doc.put("title", Value::String("a".to_string())).unwrap();
+
+        let upgraded = upgrade_document(&doc, 2, Some(rename_title)).unwrap();
+        assert_eq!(
+            upgraded.get("name").unwrap(),
+            Value::String("a".to_string())
+        );
+        assert_eq!(upgraded.get("title").unwrap(), Value::Null);
+
+        // current documents are read as they are
+        doc.put(DOC_SCHEMA_VERSION, Value::U32(2)).unwrap();
+        let upgraded = upgrade_document(&doc, 2, Some(rename_title)).unwrap();
+        assert!(matches!(upgraded, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_upgrade_document_without_hook() {
+        let doc = Document::new();
+        let error = upgrade_document(&doc, 3, None).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::DocumentTooOld {
+                version: 1,
+                current: 3
+            }
+        );
+        assert_eq!(
+            error.kind().to_string(),
+            "Document too old (version 1, current 3)"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_document_newer_version() {
+        let mut doc = Document::new();
+        doc.put(DOC_SCHEMA_VERSION, Value::U32(5)).unwrap();
+        let error = upgrade_document(&doc, 2, Some(rename_title)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ObjectMappingError);
+    }
+}
@@ -1,4 +1,6 @@
 #[allow(clippy::module_inception)]
 mod convertible;
+mod value_pool;
 
 pub use convertible::*;
+pub use value_pool::*;
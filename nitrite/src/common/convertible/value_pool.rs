@@ -0,0 +1,173 @@
+use crate::collection::Document;
+use crate::common::Value;
+use crate::errors::NitriteResult;
+use std::cell::RefCell;
+
+/// The most array buffers a thread keeps for reuse.
+const MAX_POOLED_ARRAYS: usize = 64;
+
+/// Buffers that grew larger than this many values are freed rather than kept.
+const MAX_POOLED_CAPACITY: usize = 4096;
+
+thread_local! {
+    /// The array buffers released by the conversions run on the current thread.
+    static POOL: RefCell<ValuePool> = const { RefCell::new(ValuePool::new()) };
+}
+
+/// A per-thread pool of the array buffers used while objects are converted from documents.
+///
+/// Reading a field of a document copies its value, and copying an array allocates a new
+/// buffer for its elements (and for those of every nested array) that is freed as soon as
+/// the field is converted. The derived `Convertible` implementations read fields through
+/// [`ValuePool::field`] and hand the copies back with [`ValuePool::release`], so a cursor
+/// converting one document after another reuses the buffers of the previous document
+/// instead of going back to the allocator for each of them.
+///
+/// Embedded documents are not pooled: copying one only shares its nodes.
+#[derive(Debug)]
+pub struct ValuePool {
+    arrays: Vec<Vec<Value>>,
+    stats: PoolStats,
+}
+
+/// How often the pool of the current thread could hand out a released buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Arrays copied into a buffer taken from the pool.
+    pub reused: u64,
+    /// Arrays copied into a newly allocated buffer.
+    pub allocated: u64,
+}
+
+impl ValuePool {
+    const fn new() -> Self {
+        ValuePool {
+            arrays: Vec::new(),
+            stats: PoolStats {
+                reused: 0,
+                allocated: 0,
+            },
+        }
+    }
+
+    /// Returns a copy of a field of a document, like [`Document::get`], with its arrays
+    /// copied into pooled buffers. Pass the copy to [`ValuePool::release`] once it has been
+    /// converted.
+    pub fn field(document: &Document, field: &str) -> NitriteResult<Value> {
+        match document.field_ref(field) {
+            Some(value) => Ok(POOL.with(|pool| pool.borrow_mut().copy(value))),
+            None => document.get(field),
+        }
+    }
+
+    /// Gives the buffers of the arrays of a value back to the pool of the current thread.
+    pub fn release(value: Value) {
+        if let Value::Array(_) = value {
+            POOL.with(|pool| pool.borrow_mut().recycle(value));
+        }
+    }
+
+    /// Returns the statistics of the pool of the current thread.
+    pub fn stats() -> PoolStats {
+        POOL.with(|pool| pool.borrow().stats)
+    }
+
+    /// Frees the buffers held by the pool of the current thread and resets its statistics.
+    pub fn clear() {
+        POOL.with(|pool| *pool.borrow_mut() = ValuePool::new());
+    }
+
+    fn copy(&mut self, value: &Value) -> Value {
+        match value {
+            Value::Array(values) => {
+                let mut buffer = self.take(values.len());
+                for value in values {
+                    buffer.push(self.copy(value));
+                }
+                Value::Array(buffer)
+            }
+            value => value.clone(),
+        }
+    }
+
+    fn take(&mut self, capacity: usize) -> Vec<Value> {
+        match self.arrays.pop() {
+            Some(mut buffer) => {
+                self.stats.reused += 1;
+                buffer.reserve(capacity);
+                buffer
+            }
+            None => {
+                self.stats.allocated += 1;
+                Vec::with_capacity(capacity)
+            }
+        }
+    }
+
+    fn recycle(&mut self, value: Value) {
+        if let Value::Array(mut values) = value {
+            for value in values.drain(..) {
+                self.recycle(value);
+            }
+            if self.arrays.len() < MAX_POOLED_ARRAYS && values.capacity() <= MAX_POOLED_CAPACITY {
+                self.arrays.push(values);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    #[test]
+    fn test_field_copies_value() {
+        ValuePool::clear();
+        let document = doc! {
+            "name": "Alice",
+            "scores": [1, [2, 3]],
+            "address": { "city": "Paris" },
+        };
+
+        assert_eq!(ValuePool::field(&document, "name").unwrap(), Value::from("Alice"));
+        assert_eq!(
+            ValuePool::field(&document, "scores").unwrap(),
+            document.get("scores").unwrap()
+        );
+        assert_eq!(ValuePool::field(&document, "address.city").unwrap(), Value::from("Paris"));
+        assert_eq!(ValuePool::field(&document, "missing").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_released_buffers_are_reused() {
+        ValuePool::clear();
+        let document = doc! { "scores": [1, [2, 3], 4] };
+
+        let value = ValuePool::field(&document, "scores").unwrap();
+        assert_eq!(ValuePool::stats(), PoolStats { reused: 0, allocated: 2 });
+        ValuePool::release(value);
+
+        for _ in 0..10 {
+            let value = ValuePool::field(&document, "scores").unwrap();
+            assert_eq!(value, document.get("scores").unwrap());
+            ValuePool::release(value);
+        }
+        assert_eq!(ValuePool::stats(), PoolStats { reused: 20, allocated: 2 });
+    }
+
+    #[test]
+    fn test_pool_is_bounded() {
+        ValuePool::clear();
+        let large: Vec<Value> = (0..MAX_POOLED_CAPACITY + 1).map(|i| Value::from(i as i64)).collect();
+        ValuePool::release(Value::Array(large));
+        for _ in 0..MAX_POOLED_ARRAYS + 10 {
+            ValuePool::release(Value::Array(Vec::new()));
+        }
+        POOL.with(|pool| {
+            let pool = pool.borrow();
+            assert_eq!(pool.arrays.len(), MAX_POOLED_ARRAYS);
+            assert!(pool.arrays.iter().all(|buffer| buffer.capacity() <= MAX_POOLED_CAPACITY));
+        });
+    }
+}
@@ -42,6 +42,40 @@ pub trait AttributeAware {
     /// attributes to a `Document` format for storage. This operation is typically performed
     /// during collection creation to store ownership and creation metadata.
     fn set_attributes(&self, attributes: Attributes) -> NitriteResult<()>;
+
+    /// Retrieves the attributes for this object, or empty attributes if none are stored.
+    fn get_attributes(&self) -> NitriteResult<Attributes> {
+        Ok(self.attributes()?.unwrap_or_default())
+    }
+
+    /// Retrieves a single attribute value.
+    ///
+    /// # Returns
+    /// `Ok(Some(Value))` if the attribute is set, `Ok(None)` otherwise.
+    fn get_attribute(&self, key: &str) -> NitriteResult<Option<Value>> {
+        Ok(self.get_attributes()?.get(key).cloned())
+    }
+
+    /// Sets a single attribute, keeping all other attributes.
+    ///
+    /// # Behavior
+    /// Applications can use this to keep their own per-collection metadata, such as a
+    /// schema hash, last sync time or owner, in the database instead of a side file.
+    /// The attributes are persisted in the store's meta map.
+    fn set_attribute(&self, key: &str, value: Value) -> NitriteResult<()> {
+        let mut attributes = self.get_attributes()?;
+        attributes.put(key, value);
+        self.set_attributes(attributes)
+    }
+
+    /// Removes a single attribute, keeping all other attributes.
+    fn remove_attribute(&self, key: &str) -> NitriteResult<()> {
+        let mut attributes = self.get_attributes()?;
+        if attributes.remove(key).is_some() {
+            self.set_attributes(attributes)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -218,6 +252,27 @@ impl Attributes {
         self.attributes.contains_key(key)
     }
 
+    /// Removes an attribute key.
+    ///
+    /// # Arguments
+    /// * `key` - The attribute key to remove.
+    ///
+    /// # Returns
+    /// The removed `Value`, or `None` if the key did not exist.
+    ///
+    /// # Behavior
+    /// Preserves the insertion order of the remaining attributes.
+    #[inline]
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.attributes.shift_remove(key)
+    }
+
+    /// Returns an iterator over the attribute key-value pairs in insertion order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.attributes.iter()
+    }
+
     /// Converts this `Attributes` to a `Document`.
     ///
     /// # Returns
@@ -231,7 +286,7 @@ impl Attributes {
     pub fn to_document(&self) -> Document {
         let mut document = Document::new();
         for (key, value) in self.attributes.iter() {
-            document.put(key, value.clone()).unwrap();
+            document.put_literal(key.clone(), value.clone());
         }
         document
     }
@@ -357,6 +412,58 @@ mod tests {
         assert_eq!(test_struct.attributes().unwrap().unwrap(), new_attributes);
     }
 
+    #[test]
+    fn test_attributes_remove() {
+        let mut attributes = Attributes::default();
+        attributes.put("a", Value::I32(1));
+        attributes.put("b", Value::I32(2));
+        attributes.put("c", Value::I32(3));
+        assert_eq!(attributes.remove("b"), Some(Value::I32(2)));
+        assert!(attributes.remove("b").is_none());
+        let keys: Vec<&String> = attributes.iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_attribute_aware_single_attribute() {
+        struct TestStruct {
+            attributes: Atomic<Option<Attributes>>,
+        }
+
+        impl AttributeAware for TestStruct {
+            fn attributes(&self) -> NitriteResult<Option<Attributes>> {
+                self.attributes.read_with(|attributes| Ok(attributes.clone()))
+            }
+
+            fn set_attributes(&self, attributes: Attributes) -> NitriteResult<()> {
+                self.attributes.write_with(|current_attributes| {
+                    *current_attributes = Some(attributes);
+                    Ok(())
+                })
+            }
+        }
+
+        let test_struct = TestStruct {
+            attributes: atomic(None),
+        };
+
+        assert!(test_struct.get_attributes().unwrap().get("owner").is_none());
+        assert!(test_struct.get_attribute("schema_hash").unwrap().is_none());
+
+        test_struct
+            .set_attribute("schema_hash", Value::String("abc".to_string()))
+            .unwrap();
+        test_struct.set_attribute("owner", Value::String("sync".to_string())).unwrap();
+        assert_eq!(
+            test_struct.get_attribute("schema_hash").unwrap(),
+            Some(Value::String("abc".to_string()))
+        );
+
+        test_struct.remove_attribute("schema_hash").unwrap();
+        assert!(test_struct.get_attribute("schema_hash").unwrap().is_none());
+        assert!(test_struct.get_attributes().unwrap().has_key("owner"));
+    }
+
     #[test]
     fn bench_attributes_creation() {
         for _ in 0..1000 {
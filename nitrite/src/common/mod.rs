@@ -10,6 +10,7 @@ mod sort_order;
 mod stream;
 mod util;
 mod value;
+mod value_conversion;
 mod security;
 mod lock;
 
@@ -1,6 +1,8 @@
 #[allow(clippy::module_inception)]
 mod module;
+mod module_info;
 mod plugin_manager;
 
 pub use module::*;
+pub use module_info::*;
 pub use plugin_manager::*;
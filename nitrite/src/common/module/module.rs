@@ -1,4 +1,5 @@
 use super::plugin_manager::PluginRegistrar;
+use super::ModuleInfo;
 use crate::errors::NitriteResult;
 use crate::nitrite_config::NitriteConfig;
 use std::ops::Deref;
@@ -13,9 +14,19 @@ use std::sync::Arc;
 ///
 /// # Trait Methods
 /// - `initialize()`: Initializes the plugin with database configuration
+/// - `on_open()`: Notifies the plugin that the database is open
+/// - `on_flush()`: Persists the plugin's buffered state while the store is still open
+/// - `on_close()`: Notifies the plugin that the database is closing
 /// - `close()`: Cleanly closes the plugin and releases resources
 /// - `as_plugin()`: Returns a polymorphic wrapper of this plugin
 ///
+/// # Lifecycle
+/// Plugins run their phases in dependency order. Indexers depend on the store, so when
+/// the database opens the store runs `on_open()` before the indexers; when it closes, the
+/// indexers run `on_flush()`, `on_close()` and `close()` before the store does. All
+/// `on_flush()` and `on_close()` calls happen while the store is still open, so a plugin
+/// can write its pending state through it.
+///
 /// # Thread Safety
 /// Implementations must be `Send + Sync` for safe concurrent use.
 ///
@@ -44,6 +55,46 @@ pub trait NitritePluginProvider: Send + Sync {
     /// If this method returns an error, database startup fails.
     fn initialize(&self, config: NitriteConfig) -> NitriteResult<()>;
 
+    /// Called once the database is open.
+    ///
+    /// # Arguments
+    /// * `config` - Database configuration of the opened database.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if the plugin cannot serve the opened database.
+    ///
+    /// # Behavior
+    /// Called after the store is opened, the migrations ran and the user is authenticated.
+    /// If this method returns an error, opening the database fails. Does nothing by default.
+    fn on_open(&self, _config: &NitriteConfig) -> NitriteResult<()> {
+        Ok(())
+    }
+
+    /// Persists the plugin's buffered state.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if the pending state cannot be written.
+    ///
+    /// # Behavior
+    /// Called when the database closes, before the store commits and while it is still
+    /// open. Plugins that buffer writes (index writers, page caches) write them out here.
+    /// An error aborts the close. Does nothing by default.
+    fn on_flush(&self) -> NitriteResult<()> {
+        Ok(())
+    }
+
+    /// Called when the database starts closing.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error that aborts the close.
+    ///
+    /// # Behavior
+    /// Called after every plugin has been flushed and before any plugin is closed, while
+    /// the store is still open. Does nothing by default.
+    fn on_close(&self) -> NitriteResult<()> {
+        Ok(())
+    }
+
     /// Closes the plugin and releases all resources.
     ///
     /// # Returns
@@ -76,6 +127,7 @@ pub trait NitritePluginProvider: Send + Sync {
 /// # Trait Methods
 /// - `plugins()`: Returns the list of plugins provided by this module
 /// - `load()`: Registers plugins with the plugin registrar
+/// - `info()`: Describes the module, its version and its capabilities
 ///
 /// # Thread Safety
 /// Implementations must be `Send + Sync` for safe concurrent use.
@@ -113,6 +165,22 @@ pub trait NitriteModule: Send + Sync {
     /// to register all its plugins. The registrar manages plugin availability and
     /// dependency resolution.
     fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()>;
+
+    /// Describes the module: its name, version, minimum nitrite version and the indexer
+    /// types and filter operations it provides.
+    ///
+    /// # Returns
+    /// The `ModuleInfo` of this module.
+    ///
+    /// # Behavior
+    /// Checked when the database opens: the database refuses to open if it is older than
+    /// the module's minimum core version, if the module does not register the indexer
+    /// types or store it declares, or if another module provides the same indexer type
+    /// or a second store. By default the module is named after its type, its version is
+    /// `unknown` and it declares nothing.
+    fn info(&self) -> ModuleInfo {
+        ModuleInfo::new(std::any::type_name::<Self>(), "unknown")
+    }
 }
 
 /// Polymorphic wrapper around a Nitrite plugin implementation.
@@ -0,0 +1,199 @@
+use crate::common::NITRITE_VERSION;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+
+/// Describes a module: its name and version, the oldest nitrite version it works with and
+/// the capabilities it provides.
+///
+/// A module returns its description from `NitriteModule::info()`. When the database opens,
+/// the descriptions of all loaded modules are checked against each other and against the
+/// core version, so an incompatible module fails the open with a clear error instead of
+/// failing later inside an operation. `Nitrite::plugins()` lists them.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::common::ModuleInfo;
+///
+/// impl NitriteModule for GeoHashModule {
+///     fn info(&self) -> ModuleInfo {
+///         ModuleInfo::new("geohash", env!("CARGO_PKG_VERSION"))
+///             .min_core_version("0.4.3")
+///             .indexer_type("geohash")
+///             .filter_op("geohash_prefix")
+///     }
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInfo {
+    name: String,
+    version: String,
+    min_core_version: Option<String>,
+    indexer_types: Vec<String>,
+    filter_ops: Vec<String>,
+    store: bool,
+}
+
+impl ModuleInfo {
+    /// Creates the description of a module without any declared capability.
+    pub fn new(name: &str, version: &str) -> Self {
+        ModuleInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            min_core_version: None,
+            indexer_types: Vec::new(),
+            filter_ops: Vec::new(),
+            store: false,
+        }
+    }
+
+    /// Sets the oldest nitrite version the module works with, as `major.minor.patch`.
+    pub fn min_core_version(mut self, version: &str) -> Self {
+        self.min_core_version = Some(version.to_string());
+        self
+    }
+
+    /// Declares an index type the module registers an indexer for.
+    pub fn indexer_type(mut self, index_type: &str) -> Self {
+        self.indexer_types.push(index_type.to_string());
+        self
+    }
+
+    /// Declares a filter operation the module provides.
+    pub fn filter_op(mut self, filter_op: &str) -> Self {
+        self.filter_ops.push(filter_op.to_string());
+        self
+    }
+
+    /// Declares that the module provides the store.
+    pub fn store(mut self) -> Self {
+        self.store = true;
+        self
+    }
+
+    /// Returns the name of the module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version of the module.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the oldest nitrite version the module works with, if declared.
+    pub fn get_min_core_version(&self) -> Option<&str> {
+        self.min_core_version.as_deref()
+    }
+
+    /// Returns the index types the module provides.
+    pub fn indexer_types(&self) -> &[String] {
+        &self.indexer_types
+    }
+
+    /// Returns the filter operations the module provides.
+    pub fn filter_ops(&self) -> &[String] {
+        &self.filter_ops
+    }
+
+    /// Returns `true` if the module provides the store.
+    pub fn is_store(&self) -> bool {
+        self.store
+    }
+
+    /// Checks that this nitrite version is at least the module's minimum core version.
+    pub(crate) fn check_core_version(&self) -> NitriteResult<()> {
+        let min_version = match &self.min_core_version {
+            Some(min_version) => min_version,
+            None => return Ok(()),
+        };
+
+        let (required, current) = match (parse_version(min_version), parse_version(NITRITE_VERSION)) {
+            (Some(required), Some(current)) => (required, current),
+            _ => {
+                log::error!(
+                    "Module {} declares an invalid minimum nitrite version {}",
+                    self.name,
+                    min_version
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "Module {} declares an invalid minimum nitrite version {}",
+                        self.name, min_version
+                    ),
+                    ErrorKind::PluginLoadFailed,
+                ));
+            }
+        };
+
+        if required > current {
+            log::error!(
+                "Module {} {} requires nitrite {} or later, found {}",
+                self.name,
+                self.version,
+                min_version,
+                NITRITE_VERSION
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Module {} {} requires nitrite {} or later, found {}",
+                    self.name, self.version, min_version, NITRITE_VERSION
+                ),
+                ErrorKind::PluginLoadFailed,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `major.minor.patch` version, ignoring any pre-release or build suffix.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.trim().split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.4.3"), Some((0, 4, 3)));
+        assert_eq!(parse_version("1.2"), Some((1, 2, 0)));
+        assert_eq!(parse_version("1.0.0-beta.1"), Some((1, 0, 0)));
+        assert_eq!(parse_version("one.two"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn test_check_core_version() {
+        assert!(ModuleInfo::new("any", "1.0").check_core_version().is_ok());
+        assert!(ModuleInfo::new("old", "1.0")
+            .min_core_version("0.1.0")
+            .check_core_version()
+            .is_ok());
+        assert!(ModuleInfo::new("current", "1.0")
+            .min_core_version(NITRITE_VERSION)
+            .check_core_version()
+            .is_ok());
+
+        let err = ModuleInfo::new("future", "9.0.0")
+            .min_core_version("99.0.0")
+            .check_core_version()
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::PluginLoadFailed);
+        assert!(err.message().contains("requires nitrite 99.0.0 or later"));
+
+        assert!(ModuleInfo::new("broken", "1.0")
+            .min_core_version("latest")
+            .check_core_version()
+            .is_err());
+    }
+}
@@ -1,4 +1,5 @@
-use super::{NitriteModule, NitritePluginProvider};
+use super::{ModuleInfo, NitriteModule, NitritePluginProvider};
+use crate::common::catch_panics;
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::index::non_unique_indexer::NonUniqueIndexer;
 use crate::index::text::{EnglishTokenizer, Tokenizer};
@@ -7,9 +8,15 @@ use crate::index::NitriteIndexer;
 use crate::index::{text_indexer::TextIndexer, NitriteIndexerProvider};
 use crate::nitrite_config::NitriteConfig;
 use crate::store::memory::{InMemoryStore, InMemoryStoreConfig};
-use crate::store::NitriteStore;
+#[cfg(feature = "disk_watchdog")]
+use crate::store::disk::WriteSuspension;
+#[cfg(feature = "fault_injection")]
+use crate::store::fault::FaultInjector;
+use crate::store::{NitriteStore, SwitchableStore};
 use crate::{FULL_TEXT_INDEX, NON_UNIQUE_INDEX, UNIQUE_INDEX};
 use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::{Arc, OnceLock};
 
 pub trait PluginRegistrarProvider {
@@ -93,6 +100,16 @@ impl PluginManager {
         self.inner.load_module(module, self.clone())
     }
 
+    /// Returns the descriptions of the loaded modules, in load order.
+    pub fn modules(&self) -> Vec<ModuleInfo> {
+        self.inner.modules()
+    }
+
+    /// Checks the loaded modules against the core version and against each other.
+    pub fn validate_modules(&self) -> NitriteResult<()> {
+        self.inner.validate_modules()
+    }
+
     pub fn load_plugins(&self) -> NitriteResult<()> {
         self.inner.load_plugins()
     }
@@ -104,6 +121,34 @@ impl PluginManager {
     pub fn initialize_plugins(&self) -> NitriteResult<()> {
         self.inner.initialize_plugins()
     }
+
+    /// Runs `on_open()` on the store and then on the indexers.
+    pub fn open_plugins(&self) -> NitriteResult<()> {
+        self.inner.open_plugins()
+    }
+
+    /// Runs `on_flush()` and then `on_close()` on the indexers and then on the store.
+    pub fn prepare_close(&self) -> NitriteResult<()> {
+        self.inner.prepare_close()
+    }
+
+    /// Copies the maps of the registered store into `target` and switches the store to
+    /// it, returning the previous backend.
+    pub(crate) fn migrate_store(&self, target: NitriteStore) -> NitriteResult<NitriteStore> {
+        self.inner.migrate_store(target)
+    }
+
+    /// Returns the faults injected into the maps of the registered store.
+    #[cfg(feature = "fault_injection")]
+    pub(crate) fn fault_injector(&self) -> NitriteResult<FaultInjector> {
+        self.inner.fault_injector()
+    }
+
+    /// Returns the switch suspending the writes of the registered store.
+    #[cfg(feature = "disk_watchdog")]
+    pub(crate) fn write_suspension(&self) -> NitriteResult<WriteSuspension> {
+        self.inner.write_suspension()
+    }
 }
 
 impl Default for PluginManager {
@@ -122,11 +167,23 @@ impl PluginRegistrarProvider for PluginManager {
     }
 }
 
+/// A loaded module with the plugins it actually registered.
+#[derive(Default)]
+struct ModuleRegistrations {
+    indexer_types: Vec<String>,
+    store: bool,
+}
+
 /// Inner implementation of the plugin manager.
 struct PluginManagerInner {
     nitrite_config: OnceLock<NitriteConfig>,
     indexer_maps: DashMap<String, NitriteIndexer>,
     nitrite_store: OnceLock<NitriteStore>,
+    // the registered store, which nitrite_store wraps
+    switchable_store: OnceLock<SwitchableStore>,
+    modules: Mutex<Vec<(ModuleInfo, ModuleRegistrations)>>,
+    // registrations of the module being loaded
+    loading: Mutex<Option<ModuleRegistrations>>,
 }
 
 impl PluginManagerInner {
@@ -135,6 +192,9 @@ impl PluginManagerInner {
             nitrite_config: OnceLock::new(),
             indexer_maps: DashMap::new(),
             nitrite_store: OnceLock::new(),
+            switchable_store: OnceLock::new(),
+            modules: Mutex::new(Vec::new()),
+            loading: Mutex::new(None),
         }
     }
 
@@ -152,18 +212,126 @@ impl PluginManagerInner {
 
     pub fn register_indexer_plugin(&self, plugin: NitriteIndexer) -> NitriteResult<()> {
         let index_type = plugin.index_type();
+        if let Some(registrations) = self.loading.lock().as_mut() {
+            registrations.indexer_types.push(index_type.clone());
+        }
         self.indexer_maps.insert(index_type, plugin);
         Ok(())
     }
 
     pub fn register_store_plugin(&self, plugin: NitriteStore) -> NitriteResult<()> {
-        self.nitrite_store.get_or_init(|| plugin);
+        if let Some(registrations) = self.loading.lock().as_mut() {
+            registrations.store = true;
+        }
+        self.nitrite_store.get_or_init(|| {
+            let store = self.switchable_store.get_or_init(|| SwitchableStore::new(plugin));
+            NitriteStore::new(store.clone())
+        });
         Ok(())
     }
 
+    fn migrate_store(&self, target: NitriteStore) -> NitriteResult<NitriteStore> {
+        match self.switchable_store.get() {
+            Some(store) => store.migrate_to(target),
+            None => {
+                log::error!("No store plugin is configured");
+                Err(NitriteError::new(
+                    "No store plugin is configured",
+                    ErrorKind::PluginError,
+                ))
+            }
+        }
+    }
+
+    #[cfg(feature = "fault_injection")]
+    fn fault_injector(&self) -> NitriteResult<FaultInjector> {
+        match self.switchable_store.get() {
+            Some(store) => Ok(store.fault_injector()),
+            None => {
+                log::error!("No store plugin is configured");
+                Err(NitriteError::new(
+                    "No store plugin is configured",
+                    ErrorKind::PluginError,
+                ))
+            }
+        }
+    }
+
+    #[cfg(feature = "disk_watchdog")]
+    fn write_suspension(&self) -> NitriteResult<WriteSuspension> {
+        match self.switchable_store.get() {
+            Some(store) => Ok(store.write_suspension()),
+            None => {
+                log::error!("No store plugin is configured");
+                Err(NitriteError::new(
+                    "No store plugin is configured",
+                    ErrorKind::PluginError,
+                ))
+            }
+        }
+    }
+
     pub fn load_module(&self, module: Box<dyn NitriteModule>, registrar: PluginManager) -> NitriteResult<()> {
         let registrar = PluginRegistrar::new(registrar);
-        module.load(&registrar)?;
+        *self.loading.lock() = Some(ModuleRegistrations::default());
+        let result = module.load(&registrar);
+        let registrations = self.loading.lock().take().unwrap_or_default();
+        result?;
+
+        self.modules.lock().push((module.info(), registrations));
+        Ok(())
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        self.modules.lock().iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    fn validate_modules(&self) -> NitriteResult<()> {
+        let modules = self.modules.lock();
+        let mut indexer_owners: HashMap<&str, (usize, &str)> = HashMap::new();
+        let mut store_owner: Option<&str> = None;
+
+        for (position, (info, registrations)) in modules.iter().enumerate() {
+            info.check_core_version()?;
+
+            for index_type in info.indexer_types() {
+                if !registrations.indexer_types.contains(index_type) {
+                    return Err(module_conflict(&format!(
+                        "Module {} declares indexer type {} but does not register it",
+                        info.name(),
+                        index_type
+                    )));
+                }
+            }
+            if info.is_store() && !registrations.store {
+                return Err(module_conflict(&format!(
+                    "Module {} declares a store but does not register it",
+                    info.name()
+                )));
+            }
+
+            for index_type in &registrations.indexer_types {
+                if let Some((owner, name)) = indexer_owners.insert(index_type, (position, info.name())) {
+                    if owner != position {
+                        return Err(module_conflict(&format!(
+                            "Modules {} and {} both provide indexer type {}",
+                            name,
+                            info.name(),
+                            index_type
+                        )));
+                    }
+                }
+            }
+            if registrations.store {
+                if let Some(owner) = store_owner.replace(info.name()) {
+                    return Err(module_conflict(&format!(
+                        "Modules {} and {} both provide a store",
+                        owner,
+                        info.name()
+                    )));
+                }
+            }
+        }
         Ok(())
     }
 
@@ -190,12 +358,12 @@ impl PluginManagerInner {
 
     pub fn close(&self) -> NitriteResult<()> {
         // Optimize the closing process to avoid collecting errors vector
-        for plugin in self.indexer_maps.iter() {
+        for plugin in self.indexers() {
             plugin.close().ok();
         }
         
         if let Some(store) = self.nitrite_store.get() {
-            store.close()?;
+            catch_panics("Store close", || store.close())?;
         }
         
         Ok(())
@@ -206,7 +374,7 @@ impl PluginManagerInner {
         if let Some(config) = self.nitrite_config.get().cloned() {
             // Initialize store first - it sets db_path on the config which other plugins may need
             if let Some(store) = self.nitrite_store.get() {
-                store.initialize(config.clone())?;
+                catch_panics("Store initialize", || store.initialize(config.clone()))?;
             }
 
             // Then initialize indexers - they can now access db_path from config
@@ -225,6 +393,64 @@ impl PluginManagerInner {
     }
 }
 
+impl PluginManagerInner {
+    fn open_plugins(&self) -> NitriteResult<()> {
+        let config = match self.nitrite_config.get() {
+            Some(config) => config,
+            None => {
+                log::error!("NitriteConfig is not set");
+                return Err(NitriteError::new(
+                    "NitriteConfig is not set",
+                    ErrorKind::PluginError,
+                ));
+            }
+        };
+
+        if let Some(store) = self.nitrite_store.get() {
+            catch_panics("Store on_open", || store.on_open(config))?;
+        }
+        for plugin in self.indexers() {
+            plugin.on_open(config)?;
+        }
+        Ok(())
+    }
+
+    fn prepare_close(&self) -> NitriteResult<()> {
+        // indexers depend on the store, so they finish their writes first
+        let indexers = self.indexers();
+        for plugin in &indexers {
+            plugin.on_flush()?;
+        }
+        if let Some(store) = self.nitrite_store.get() {
+            catch_panics("Store on_flush", || store.on_flush())?;
+        }
+
+        for plugin in &indexers {
+            plugin.on_close()?;
+        }
+        if let Some(store) = self.nitrite_store.get() {
+            catch_panics("Store on_close", || store.on_close())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the indexers ordered by index type, so that the phases run in a stable order.
+    fn indexers(&self) -> Vec<NitriteIndexer> {
+        let mut indexers: Vec<(String, NitriteIndexer)> = self
+            .indexer_maps
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        indexers.sort_by(|a, b| a.0.cmp(&b.0));
+        indexers.into_iter().map(|(_, indexer)| indexer).collect()
+    }
+}
+
+fn module_conflict(message: &str) -> NitriteError {
+    log::error!("{}", message);
+    NitriteError::new(message, ErrorKind::PluginLoadFailed)
+}
+
 impl Drop for PluginManagerInner {
     fn drop(&mut self) {
         match self.close() {
@@ -558,4 +784,286 @@ mod tests {
         println!("100 plugin registrations: {:?}", elapsed);
         assert!(elapsed.as_millis() < 300);
     }
-}
\ No newline at end of file
+    struct RecordingIndexer {
+        index_type: &'static str,
+        log: Arc<parking_lot::Mutex<Vec<String>>>,
+    }
+
+    impl RecordingIndexer {
+        fn record(&self, phase: &str) {
+            self.log.lock().push(format!("{}:{}", self.index_type, phase));
+        }
+    }
+
+    impl NitritePluginProvider for RecordingIndexer {
+        fn initialize(&self, _config: NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn on_open(&self, _config: &NitriteConfig) -> NitriteResult<()> {
+            self.record("open");
+            Ok(())
+        }
+
+        fn on_flush(&self) -> NitriteResult<()> {
+            self.record("flush");
+            Ok(())
+        }
+
+        fn on_close(&self) -> NitriteResult<()> {
+            self.record("close");
+            Ok(())
+        }
+
+        fn close(&self) -> NitriteResult<()> {
+            self.record("release");
+            Ok(())
+        }
+
+        fn as_plugin(&self) -> NitritePlugin {
+            NitritePlugin::new(MockIndexer)
+        }
+    }
+
+    impl NitriteIndexerProvider for RecordingIndexer {
+        fn index_type(&self) -> String {
+            self.index_type.to_string()
+        }
+
+        fn is_unique(&self) -> bool {
+            false
+        }
+
+        fn validate_index(&self, _fields: &Fields) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn drop_index(&self, _index_descriptor: &IndexDescriptor, _nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn write_index_entry(&self, _field_values: &FieldValues, _index_descriptor: &IndexDescriptor, _nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn remove_index_entry(&self, _field_values: &FieldValues, _index_descriptor: &IndexDescriptor, _nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn find_by_filter(&self, _find_plan: &FindPlan, _nitrite_config: &NitriteConfig) -> NitriteResult<Vec<NitriteId>> {
+            Ok(vec![])
+        }
+    }
+
+    struct RecordingModule(Arc<parking_lot::Mutex<Vec<String>>>);
+
+    impl NitriteModule for RecordingModule {
+        fn plugins(&self) -> NitriteResult<Vec<NitritePlugin>> {
+            Ok(vec![])
+        }
+
+        fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()> {
+            for index_type in ["b_index", "a_index"] {
+                plugin_registrar.register_indexer_plugin(NitriteIndexer::new(RecordingIndexer {
+                    index_type,
+                    log: self.0.clone(),
+                }))?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_phases_run_in_order() {
+        let log = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let db = crate::nitrite::Nitrite::builder()
+            .load_module(RecordingModule(log.clone()))
+            .open_or_create(None, None)
+            .unwrap();
+        assert_eq!(*log.lock(), vec!["a_index:open", "b_index:open"]);
+
+        log.lock().clear();
+        db.close().unwrap();
+        // every plugin flushes before any of them is notified of the close
+        assert_eq!(
+            log.lock()[..6],
+            [
+                "a_index:flush",
+                "b_index:flush",
+                "a_index:close",
+                "b_index:close",
+                "a_index:release",
+                "b_index:release",
+            ][..]
+        );
+    }
+
+    struct DescribedModule {
+        info: ModuleInfo,
+        register_indexer: bool,
+    }
+
+    impl NitriteModule for DescribedModule {
+        fn plugins(&self) -> NitriteResult<Vec<NitritePlugin>> {
+            Ok(vec![])
+        }
+
+        fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()> {
+            if self.register_indexer {
+                plugin_registrar.register_indexer_plugin(NitriteIndexer::new(MockIndexer))?;
+            }
+            Ok(())
+        }
+
+        fn info(&self) -> ModuleInfo {
+            self.info.clone()
+        }
+    }
+
+    fn described(info: ModuleInfo, register_indexer: bool) -> DescribedModule {
+        DescribedModule { info, register_indexer }
+    }
+
+    #[test]
+    fn test_plugins_lists_loaded_modules() {
+        let db = crate::nitrite::Nitrite::builder()
+            .load_module(described(
+                ModuleInfo::new("mock", "1.2.0")
+                    .min_core_version("0.1.0")
+                    .indexer_type("mock_index")
+                    .filter_op("mock_match"),
+                true,
+            ))
+            .load_module(crate::store::memory::InMemoryStoreModule::new())
+            .open_or_create(None, None)
+            .unwrap();
+
+        let plugins = db.plugins();
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0].name(), "mock");
+        assert_eq!(plugins[0].version(), "1.2.0");
+        assert_eq!(plugins[0].indexer_types(), ["mock_index".to_string()]);
+        assert_eq!(plugins[0].filter_ops(), ["mock_match".to_string()]);
+        assert_eq!(plugins[1].name(), "in-memory-store");
+        assert!(plugins[1].is_store());
+        db.close().unwrap();
+    }
+
+    #[test]
+    fn test_open_fails_for_newer_core_version() {
+        let result = crate::nitrite::Nitrite::builder()
+            .load_module(described(
+                ModuleInfo::new("future", "9.0.0").min_core_version("99.0.0"),
+                false,
+            ))
+            .open_or_create(None, None);
+        let err = result.err().unwrap();
+        assert_eq!(err.kind(), &ErrorKind::PluginLoadFailed);
+        assert!(err.message().contains("requires nitrite 99.0.0 or later"));
+    }
+
+    #[test]
+    fn test_open_fails_for_conflicting_modules() {
+        let result = crate::nitrite::Nitrite::builder()
+            .load_module(described(ModuleInfo::new("first", "1.0"), true))
+            .load_module(described(ModuleInfo::new("second", "1.0"), true))
+            .open_or_create(None, None);
+        let err = result.err().unwrap();
+        assert_eq!(err.kind(), &ErrorKind::PluginLoadFailed);
+        assert!(err.message().contains("first and second both provide indexer type mock_index"));
+
+        let result = crate::nitrite::Nitrite::builder()
+            .load_module(crate::store::memory::InMemoryStoreModule::new())
+            .load_module(crate::store::memory::InMemoryStoreModule::new())
+            .open_or_create(None, None);
+        assert!(result.err().unwrap().message().contains("both provide a store"));
+    }
+
+    #[test]
+    fn test_open_fails_for_unregistered_indexer_type() {
+        let result = crate::nitrite::Nitrite::builder()
+            .load_module(described(ModuleInfo::new("lazy", "1.0").indexer_type("mock_index"), false))
+            .open_or_create(None, None);
+        let err = result.err().unwrap();
+        assert!(err.message().contains("declares indexer type mock_index but does not register it"));
+    }
+
+    struct PanickingIndexer;
+
+    impl NitritePluginProvider for PanickingIndexer {
+        fn initialize(&self, _config: NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn close(&self) -> NitriteResult<()> {
+            panic!("close failed")
+        }
+
+        fn as_plugin(&self) -> NitritePlugin {
+            NitritePlugin::new(MockIndexer)
+        }
+    }
+
+    impl NitriteIndexerProvider for PanickingIndexer {
+        fn index_type(&self) -> String {
+            "panic_index".to_string()
+        }
+
+        fn is_unique(&self) -> bool {
+            false
+        }
+
+        fn validate_index(&self, _fields: &Fields) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn drop_index(&self, _index_descriptor: &IndexDescriptor, _nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn write_index_entry(&self, _field_values: &FieldValues, _index_descriptor: &IndexDescriptor, _nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+            panic!("index entry could not be written")
+        }
+
+        fn remove_index_entry(&self, _field_values: &FieldValues, _index_descriptor: &IndexDescriptor, _nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+            Ok(())
+        }
+
+        fn find_by_filter(&self, _find_plan: &FindPlan, _nitrite_config: &NitriteConfig) -> NitriteResult<Vec<NitriteId>> {
+            Ok(vec![])
+        }
+    }
+
+    struct PanickingModule;
+
+    impl NitriteModule for PanickingModule {
+        fn plugins(&self) -> NitriteResult<Vec<NitritePlugin>> {
+            Ok(vec![])
+        }
+
+        fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()> {
+            plugin_registrar.register_indexer_plugin(NitriteIndexer::new(PanickingIndexer))
+        }
+    }
+
+    #[test]
+    fn test_panicking_indexer_returns_error() {
+        let db = crate::nitrite::Nitrite::builder()
+            .load_module(PanickingModule)
+            .open_or_create(None, None)
+            .unwrap();
+        let collection = db.collection("test").unwrap();
+        collection
+            .create_index(vec!["a"], &crate::index::IndexOptions::new("panic_index"))
+            .unwrap();
+
+        let err = collection.insert(crate::doc!{ a: 1 }).err().unwrap();
+        assert_eq!(err.kind(), &ErrorKind::PluginError);
+        assert!(err
+            .message()
+            .contains("Indexer 'panic_index' write_index_entry panicked: index entry could not be written"));
+
+        // a panic while closing the plugin does not escape either
+        assert!(db.close().is_ok());
+    }
+}
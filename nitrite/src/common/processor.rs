@@ -233,6 +233,11 @@ impl ProcessorChain {
         self.inner.remove_processor(processor_name);
     }
 
+    /// Returns `true` if the chain has no processor.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.inner.processors.is_empty()
+    }
+
     /// Applies all processors in the chain to a document before writing.
     ///
     /// # Arguments
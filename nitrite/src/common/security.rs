@@ -164,18 +164,16 @@ impl AuthServiceInner {
     #[inline]
     fn validate_user(&self, username: &str, password: &str) -> NitriteResult<()> {
         let user_map = self.store.open_map(USER_MAP)?;
-        let credential_doc = user_map.get(&Value::from(username))?;
-
-        if credential_doc.is_none() {
+        let Some(credential_doc) = user_map.get(&Value::from(username))? else {
             log::error!("Username or password is invalid");
             return Err(NitriteError::new(
                 "Username or password is invalid",
                 ErrorKind::SecurityError,
             ));
-        }
+        };
 
         // Validate credential is a Document before accessing
-        let credential_doc = match credential_doc.unwrap().as_document() {
+        let credential_doc = match credential_doc.as_document() {
             Some(doc) => doc.clone(),
             None => {
                 log::error!("User credential is not a valid document: {}", username);
@@ -224,18 +222,16 @@ impl AuthServiceInner {
         new_password: &str,
     ) -> NitriteResult<()> {
         let user_map = self.store.open_map(USER_MAP)?;
-        let credential_doc = user_map.get(&Value::from(username))?;
-
-        if credential_doc.is_none() {
+        let Some(credential_doc) = user_map.get(&Value::from(username))? else {
             log::error!("Username or password is invalid");
             return Err(NitriteError::new(
                 "Username or password is invalid",
                 ErrorKind::SecurityError,
             ));
-        }
+        };
 
         // Validate credential is a Document before accessing
-        let credential_doc = match credential_doc.unwrap().as_document() {
+        let credential_doc = match credential_doc.as_document() {
             Some(doc) => doc.clone(),
             None => {
                 log::error!("User credential is not a valid document: {}", username);
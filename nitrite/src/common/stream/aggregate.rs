@@ -0,0 +1,82 @@
+use crate::collection::Document;
+use crate::common::{SortOrder, Value};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+
+/// Answers the smallest (`Ascending`) or largest (`Descending`) numeric value of a field
+/// without a scan, or `None` when it cannot. A cursor over a whole collection gets one from
+/// its read context to serve `min`/`max` from an index.
+pub(crate) type FieldBound = Box<dyn Fn(&str, SortOrder) -> NitriteResult<Option<Value>>>;
+
+/// Returns the numeric values of a field: the value itself, or the numeric elements of an
+/// array, the way a multikey index sees them. Other values are skipped.
+pub(crate) fn field_numbers(document: &Document, field: &str) -> NitriteResult<Vec<Value>> {
+    Ok(match document.get(field)? {
+        Value::Array(values) => values.into_iter().filter(Value::is_number).collect(),
+        value if value.is_number() => vec![value],
+        _ => Vec::new(),
+    })
+}
+
+pub(crate) fn number_as_f64(value: &Value) -> f64 {
+    value
+        .as_signed_integer()
+        .map(|integer| integer as f64)
+        .or_else(|| value.as_decimal())
+        .unwrap_or(f64::NAN)
+}
+
+pub(crate) fn validate_percentile(percentile: f64) -> NitriteResult<()> {
+    if (0.0..=100.0).contains(&percentile) {
+        Ok(())
+    } else {
+        log::error!("Percentile {} is not between 0 and 100", percentile);
+        Err(NitriteError::new(
+            &format!("Percentile {} is not between 0 and 100", percentile),
+            ErrorKind::InvalidOperation,
+        ))
+    }
+}
+
+/// Returns the percentile of the values, interpolating linearly between the two closest
+/// ranks.
+pub(crate) fn percentile_of(mut values: Vec<f64>, percentile: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+
+    let rank = percentile / 100.0 * (values.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    let weight = rank - lower as f64;
+    Some(values[lower] + (values[upper] - values[lower]) * weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    #[test]
+    fn test_field_numbers() {
+        let document = doc! { price: 5, sizes: [1, "XL", 2.5], name: "shirt" };
+        assert_eq!(field_numbers(&document, "price").unwrap(), vec![Value::I32(5)]);
+        assert_eq!(
+            field_numbers(&document, "sizes").unwrap(),
+            vec![Value::I32(1), Value::F64(2.5)]
+        );
+        assert!(field_numbers(&document, "name").unwrap().is_empty());
+        assert!(field_numbers(&document, "missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_percentile_of() {
+        let values = vec![15.0, 20.0, 35.0, 40.0, 50.0];
+        assert_eq!(percentile_of(values.clone(), 0.0), Some(15.0));
+        assert_eq!(percentile_of(values.clone(), 50.0), Some(35.0));
+        assert_eq!(percentile_of(values.clone(), 100.0), Some(50.0));
+        assert_eq!(percentile_of(values, 40.0), Some(29.0));
+        assert_eq!(percentile_of(vec![7.0], 95.0), Some(7.0));
+        assert_eq!(percentile_of(Vec::new(), 50.0), None);
+        assert!(validate_percentile(100.5).is_err());
+    }
+}
@@ -1,10 +1,24 @@
-use crate::collection::{Document, FindPlan, NitriteId};
+use crate::collection::operation::estimated_size;
+use crate::collection::{Document, FindPlan, NitriteId, RedactionPolicy, ScanToken};
 use crate::common::processor::ProcessorChain;
+use crate::common::stream::aggregate::{
+    field_numbers, number_as_f64, percentile_of, validate_percentile, FieldBound,
+};
 use crate::common::stream::joined_cursor::{JoinedDocumentCursor, Lookup};
-use crate::common::stream::projected_cursor::ProjectedDocumentCursor;
-use crate::common::{ReadExecutor, WriteExecutor};
-use crate::errors::NitriteResult;
+use crate::common::stream::projected_cursor::{project, ProjectedDocumentCursor};
+use crate::common::{
+    expiry_field, get_current_time_or_zero, ReadExecutor, SortOrder, Value, WriteExecutor,
+    DOC_ID,
+};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::profiler::ActiveProfile;
+use crate::store::with_projection;
 use crate::ProcessorProvider;
+use std::sync::Arc;
+#[cfg(feature = "arrow")]
+use crate::columnar::{to_record_batch, SchemaMapping};
+#[cfg(feature = "arrow")]
+use arrow_array::RecordBatch;
 
 /// Rebuilds a cursor's underlying document stream. A streaming cursor uses this to replay on
 /// reset without retaining every yielded document in memory.
@@ -29,6 +43,37 @@ pub struct DocumentCursor {
     /// scan with no post-filter, skip, limit, or OR-union). When set, `count()`/`size()` return
     /// it directly instead of fetching and deserializing every matching document.
     covered_count: Option<usize>,
+    /// Metadata field holding field deadlines; when set, expired fields are removed from the
+    /// yielded documents.
+    expiry_field: Option<String>,
+    /// Answers `min`/`max` from an index; set only for cursors over a whole collection.
+    field_bound: Option<FieldBound>,
+    /// Redaction applied to the yielded documents, last.
+    redaction: Option<RedactionPolicy>,
+    /// Profile of the find that created the cursor, entered while it is iterated.
+    profile: Option<Arc<ActiveProfile>>,
+    /// Bytes of documents the cursor may hold, from `FindOptions::max_memory`.
+    memory_limit: Option<u64>,
+    /// Estimated size of the documents in `cache`.
+    cached_bytes: u64,
+    /// Name of the map the documents are read from, to push projections down to the store.
+    source_map: Option<String>,
+    /// Projection from `FindOptions::project`, applied to the yielded documents after
+    /// redaction.
+    projection: Option<Document>,
+    /// The map read by the cursor and the fields `projection` limits its reads to.
+    pushdown: Option<(String, Arc<[String]>)>,
+    /// Position of a cursor scanning its collection in the order of the map, for
+    /// `checkpoint()`.
+    scan: Option<ScanPosition>,
+}
+
+/// The documents a scanning cursor has returned so far, and where its scan started.
+struct ScanPosition {
+    collection_name: String,
+    start: (Option<NitriteId>, u64),
+    last_id: Option<NitriteId>,
+    position: u64,
 }
 
 impl DocumentCursor {
@@ -48,6 +93,16 @@ impl DocumentCursor {
             processor_chain,
             find_plan: None,
             covered_count: None,
+            expiry_field: None,
+            field_bound: None,
+            redaction: None,
+            profile: None,
+            memory_limit: None,
+            cached_bytes: 0,
+            source_map: None,
+            projection: None,
+            pushdown: None,
+            scan: None,
         }
     }
 
@@ -68,6 +123,16 @@ impl DocumentCursor {
             processor_chain,
             find_plan: None,
             covered_count: None,
+            expiry_field: None,
+            field_bound: None,
+            redaction: None,
+            profile: None,
+            memory_limit: None,
+            cached_bytes: 0,
+            source_map: None,
+            projection: None,
+            pushdown: None,
+            scan: None,
         }
     }
 
@@ -77,12 +142,121 @@ impl DocumentCursor {
         self.rewindable = true;
     }
 
+    /// Caps the bytes of documents the cursor holds. A streaming cursor made rewindable
+    /// stops caching at the cap and goes back to re-running its query on reset.
+    pub(crate) fn with_memory_limit(mut self, memory_limit: Option<u64>) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Lets projections of the cursor tell the store of `map_name` which fields they read.
+    pub(crate) fn with_source_map(mut self, map_name: String) -> Self {
+        self.source_map = Some(map_name);
+        self
+    }
+
+    /// Returns the map to read from and the fields to read for a projection, when the
+    /// store may leave the other fields out of the documents.
+    ///
+    /// Only a streaming cursor whose documents go from the store to the projection with
+    /// nothing else reading them qualifies: no filter, sort, distinct or processor needs
+    /// a field the projection drops, and no document is kept for later reads.
+    pub(crate) fn projection_pushdown(&self, projection: &Document) -> Option<(String, Arc<[String]>)> {
+        let map_name = self.source_map.as_ref()?;
+        let find_plan = self.find_plan.as_ref()?;
+        if self.rewindable
+            || !self.processor_chain.is_empty()
+            || find_plan.sub_plans().is_some_and(|sub_plans| !sub_plans.is_empty())
+            || find_plan.full_scan_filter().is_some()
+            || find_plan.blocking_sort_order().is_some()
+            || find_plan.distance_sort().is_some()
+            || find_plan.distinct()
+        {
+            return None;
+        }
+        Some((map_name.clone(), projection.fields().into_iter().collect()))
+    }
+
+    /// Projects the yielded documents to the fields of `projection`. Reads are pushed down to
+    /// the store when they can be, so the cursor must know its plan and source map first.
+    /// `min`/`max` stop asking an index, which knows fields the projection may drop.
+    pub(crate) fn projected_to(mut self, projection: Option<Document>) -> Self {
+        if let Some(projection) = &projection {
+            self.pushdown = self.projection_pushdown(projection);
+            self.field_bound = None;
+        }
+        self.projection = projection;
+        self
+    }
+
     /// Records the index-covered match count so `count()`/`size()` can answer without fetching.
     pub(crate) fn with_covered_count(mut self, covered_count: Option<usize>) -> Self {
         self.covered_count = covered_count;
         self
     }
 
+    /// Lets `min`/`max` ask an index before scanning.
+    pub(crate) fn with_field_bound(mut self, field_bound: FieldBound) -> Self {
+        self.field_bound = Some(field_bound);
+        self
+    }
+
+    /// Removes fields whose expiry deadline has passed from the yielded documents, for cursors
+    /// handed to users; writers keep seeing stored documents until a sweep.
+    pub(crate) fn hide_expired_fields(mut self) -> Self {
+        self.expiry_field = Some(expiry_field());
+        self
+    }
+
+    /// Redacts the yielded documents with `policy`. An index knows the stored values, so
+    /// `min`/`max` stop asking it.
+    pub(crate) fn redact_with(mut self, policy: RedactionPolicy) -> Self {
+        self.redaction = Some(policy);
+        self.field_bound = None;
+        self
+    }
+
+    /// Adds the time spent iterating the cursor to `profile`, which is recorded once the
+    /// cursor is dropped.
+    pub(crate) fn with_profile(mut self, profile: Arc<ActiveProfile>) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Lets the cursor be checkpointed. Its documents must come from a scan of the
+    /// collection in the order of the map, resumed from `resumed` if it is set.
+    pub(crate) fn checkpointed(mut self, collection_name: String, resumed: Option<&ScanToken>) -> Self {
+        let start = resumed.map_or((None, 0), |token| (token.last_id(), token.position()));
+        self.scan = Some(ScanPosition {
+            collection_name,
+            start,
+            last_id: start.0,
+            position: start.1,
+        });
+        self
+    }
+
+    /// Caches a yielded document for replay, unless the cache outgrows the memory limit of
+    /// the cursor and the stream can be rebuilt instead.
+    fn cache_document(&mut self, processed: &NitriteResult<Document>) {
+        if let (Some(limit), Some(_), Ok(document)) =
+            (self.memory_limit, &self.factory, processed)
+        {
+            self.cached_bytes += estimated_size(document);
+            if self.cached_bytes > limit {
+                log::debug!(
+                    "Cursor cache exceeds the memory limit of {} bytes, replaying by query",
+                    limit
+                );
+                self.cache = Vec::new();
+                self.cached_bytes = 0;
+                self.rewindable = false;
+                return;
+            }
+        }
+        self.cache.push(processed.clone());
+    }
+
     /// Resets the cursor so that it can be iterated from the beginning.
     ///
     /// A rewindable cursor replays from its cache; a streaming cursor that has advanced rebuilds
@@ -93,6 +267,51 @@ impl DocumentCursor {
             self.underlying = self.factory.as_ref().and_then(|factory| factory().ok());
         }
         self.current_index = 0;
+        if let Some(scan) = &mut self.scan {
+            (scan.last_id, scan.position) = scan.start;
+        }
+    }
+
+    /// Returns a token to resume the scan of the cursor after the last document it
+    /// returned, even from another process.
+    ///
+    /// Only a cursor that reads its collection in the order of the ids can be
+    /// checkpointed: one from a `find()` answered without an index, id lookup or sort,
+    /// like `find(all())` or one from
+    /// [`resume_scan`](crate::collection::NitriteCollectionProvider::resume_scan). The
+    /// token is passed to `resume_scan` or
+    /// [`FindOptions::resume_after`](crate::collection::FindOptions::resume_after), with
+    /// the filter of the original query if it had one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidOperation` if the cursor does not scan its collection in the order
+    /// of the ids.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let mut cursor = events.find(all())?;
+    /// for document in cursor.by_ref().take(1000) {
+    ///     export(document?)?;
+    /// }
+    /// save_progress(&cursor.checkpoint()?.to_string())?;
+    /// ```
+    pub fn checkpoint(&self) -> NitriteResult<ScanToken> {
+        match &self.scan {
+            Some(scan) if !self.rewindable => Ok(ScanToken::new(
+                scan.collection_name.clone(),
+                scan.last_id,
+                scan.position,
+            )),
+            _ => {
+                log::error!("Only a cursor scanning its collection by id can be checkpointed");
+                Err(NitriteError::new(
+                    "Only a cursor scanning its collection by id can be checkpointed",
+                    ErrorKind::InvalidOperation,
+                ))
+            }
+        }
     }
 
     /// Returns the number of matching documents.
@@ -128,11 +347,140 @@ impl DocumentCursor {
         count
     }
 
+    /// Returns the sum of the numeric values of a field over the remaining documents.
+    ///
+    /// Like the other aggregations, this consumes the cursor one document at a time without
+    /// collecting them. Missing and non-numeric values are skipped; an array field adds each
+    /// of its numeric elements.
+    pub fn sum(self, field: &str) -> NitriteResult<f64> {
+        let mut sum = 0.0;
+        self.for_each_number(field, |value| sum += number_as_f64(value))?;
+        Ok(sum)
+    }
+
+    /// Returns the average of the numeric values of a field, or `None` if there are none.
+    pub fn avg(self, field: &str) -> NitriteResult<Option<f64>> {
+        let (mut sum, mut count) = (0.0, 0u64);
+        self.for_each_number(field, |value| {
+            sum += number_as_f64(value);
+            count += 1;
+        })?;
+        Ok((count > 0).then(|| sum / count as f64))
+    }
+
+    /// Returns the smallest numeric value of a field, or `None` if there is none.
+    ///
+    /// A query over the whole collection is answered from a unique or non-unique index on
+    /// the field when there is one, without reading the documents.
+    pub fn min(self, field: &str) -> NitriteResult<Option<Value>> {
+        self.bound(field, SortOrder::Ascending)
+    }
+
+    /// Returns the largest numeric value of a field, or `None` if there is none.
+    ///
+    /// Like [`min`](DocumentCursor::min), this uses an index on the field when it can.
+    pub fn max(self, field: &str) -> NitriteResult<Option<Value>> {
+        self.bound(field, SortOrder::Descending)
+    }
+
+    /// Returns the `percentile` (from 0 to 100) of the numeric values of a field,
+    /// interpolating between the two closest values, or `None` if there are none.
+    ///
+    /// The numbers are kept in memory to rank them, the documents are not.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidOperation` error if `percentile` is outside 0 to 100.
+    pub fn percentile(self, field: &str, percentile: f64) -> NitriteResult<Option<f64>> {
+        validate_percentile(percentile)?;
+        let mut values = Vec::new();
+        self.for_each_number(field, |value| values.push(number_as_f64(value)))?;
+        Ok(percentile_of(values, percentile))
+    }
+
+    fn bound(self, field: &str, order: SortOrder) -> NitriteResult<Option<Value>> {
+        if let Some(field_bound) = &self.field_bound {
+            if let Some(value) = field_bound(field, order)? {
+                return Ok(Some(value));
+            }
+        }
+
+        let mut bound: Option<Value> = None;
+        self.for_each_number(field, |value| {
+            let better = match (&bound, order) {
+                (None, _) => true,
+                (Some(current), SortOrder::Ascending) => value < current,
+                (Some(current), SortOrder::Descending) => value > current,
+            };
+            if better {
+                bound = Some(value.clone());
+            }
+        })?;
+        Ok(bound)
+    }
+
+    fn for_each_number(mut self, field: &str, mut action: impl FnMut(&Value)) -> NitriteResult<()> {
+        for document in self.by_ref() {
+            for value in field_numbers(&document?, field)? {
+                action(&value);
+            }
+        }
+        Ok(())
+    }
+
     pub fn first(&mut self) -> Option<NitriteResult<Document>> {
         self.reset();
         self.next()
     }
 
+    /// Converts the matching documents into one Arrow record batch, with the columns of
+    /// `mapping` or, without one, the columns inferred from the documents as described on
+    /// [`SchemaMapping::infer`].
+    ///
+    /// Like [`size`](DocumentCursor::size), this reads the cursor from the beginning and
+    /// resets it afterwards. Requires the `arrow` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidDataType` error if a value does not fit the type of its column.
+    #[cfg(feature = "arrow")]
+    #[allow(clippy::wrong_self_convention)] // reads the cursor, which needs `&mut self`
+    pub fn to_arrow(&mut self, mapping: Option<&SchemaMapping>) -> NitriteResult<RecordBatch> {
+        self.reset();
+        let documents = self.collect_documents();
+        self.reset();
+
+        let documents = documents?;
+        match mapping {
+            Some(mapping) => to_record_batch(&documents, mapping),
+            None => to_record_batch(&documents, &SchemaMapping::infer(&documents)?),
+        }
+    }
+
+    /// Collects the remaining documents, failing with `MemoryLimitExceeded` once their size
+    /// passes the memory limit of the cursor.
+    #[cfg(feature = "arrow")]
+    fn collect_documents(&mut self) -> NitriteResult<Vec<Document>> {
+        let mut documents = Vec::new();
+        let mut bytes = 0u64;
+        let memory_limit = self.memory_limit;
+        for document in self.by_ref() {
+            let document = document?;
+            if let Some(limit) = memory_limit {
+                bytes += estimated_size(&document);
+                if bytes > limit {
+                    log::error!("Query results exceed the memory limit of {} bytes", limit);
+                    return Err(NitriteError::new(
+                        &format!("Query results exceed the memory limit of {} bytes", limit),
+                        ErrorKind::MemoryLimitExceeded,
+                    ));
+                }
+            }
+            documents.push(document);
+        }
+        Ok(documents)
+    }
+
     pub fn find_plan(&self) -> Option<&FindPlan> {
         self.find_plan.as_ref()
     }
@@ -199,17 +547,42 @@ impl Iterator for DocumentCursor {
         }
 
         // Otherwise, try to pull from the underlying iterator.
+        let _profile = self.profile.as_ref().map(ActiveProfile::enter);
         if let Some(ref mut iter) = self.underlying {
-            if let Some(item) = iter.next() {
+            let next = match &self.pushdown {
+                Some((map_name, fields)) => with_projection(map_name, fields, || iter.next()),
+                None => iter.next(),
+            };
+            if let Some(item) = next {
+                if let (Some(scan), Ok(doc)) = (&mut self.scan, &item) {
+                    if let Ok(Value::NitriteId(id)) = doc.get(DOC_ID) {
+                        scan.last_id = Some(id);
+                        scan.position += 1;
+                    }
+                }
                 // Process after read - combine Result<T, E> handling
                 let processed = item.and_then(|doc| {
-                    self.processor_chain.process_after_read(doc)
+                    let doc = self.processor_chain.process_after_read(doc)?;
+                    let doc = match &self.expiry_field {
+                        Some(expiry_field) => doc
+                            .without_expired_fields(expiry_field, get_current_time_or_zero() as i64)?
+                            .unwrap_or(doc),
+                        None => doc,
+                    };
+                    let doc = match &self.redaction {
+                        Some(policy) => policy.redact(doc)?,
+                        None => doc,
+                    };
+                    match &self.projection {
+                        Some(projection) => project(doc, projection),
+                        None => Ok(doc),
+                    }
                 });
 
                 // Only retain documents for cursors that replay from memory; a streaming cursor
                 // keeps nothing and rebuilds via its factory on reset.
                 if self.rewindable {
-                    self.cache.push(processed.clone());
+                    self.cache_document(&processed);
                 }
                 self.current_index += 1;
                 return Some(processed);
@@ -227,6 +600,7 @@ mod tests {
     use crate::collection::Document;
     use crate::doc;
     use crate::errors::{ErrorKind, NitriteError};
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     fn create_document(first: &str, last: &str) -> Document {
         let doc = doc!{
@@ -542,6 +916,129 @@ mod tests {
         assert_eq!(doc2.get("first").unwrap().as_string().unwrap(), "Bob");
     }
 
+    fn price_cursor() -> DocumentCursor {
+        let docs = vec![
+            Ok(doc! { item: "pen", price: 2 }),
+            Ok(doc! { item: "book", price: 12.5 }),
+            Ok(doc! { item: "bag", price: [30, "n/a"] }),
+            Ok(doc! { item: "gift" }),
+        ];
+        DocumentCursor::new(Box::new(docs.into_iter()), ProcessorChain::new())
+    }
+
+    #[test]
+    fn test_aggregations() {
+        assert_eq!(price_cursor().sum("price").unwrap(), 44.5);
+        assert_eq!(price_cursor().avg("price").unwrap(), Some(44.5 / 3.0));
+        assert_eq!(price_cursor().min("price").unwrap(), Some(Value::I32(2)));
+        assert_eq!(price_cursor().max("price").unwrap(), Some(Value::I32(30)));
+        assert_eq!(price_cursor().percentile("price", 50.0).unwrap(), Some(12.5));
+        assert_eq!(price_cursor().avg("weight").unwrap(), None);
+        assert_eq!(price_cursor().max("weight").unwrap(), None);
+        assert!(price_cursor().percentile("price", -1.0).is_err());
+    }
+
+    #[test]
+    fn test_aggregation_error() {
+        let docs = vec![Ok(doc! { price: 1 }), Err(NitriteError::new("boom", ErrorKind::IOError))];
+        let cursor = DocumentCursor::new(Box::new(docs.into_iter()), ProcessorChain::new());
+        assert!(cursor.sum("price").is_err());
+    }
+
+    #[test]
+    fn test_min_asks_field_bound_first() {
+        let cursor = price_cursor().with_field_bound(Box::new(|field, order| {
+            Ok((field == "price" && order == SortOrder::Ascending).then_some(Value::I32(-1)))
+        }));
+        assert_eq!(cursor.min("price").unwrap(), Some(Value::I32(-1)));
+        // a bound that cannot answer falls back to the scan
+        let cursor = price_cursor().with_field_bound(Box::new(|_, _| Ok(None)));
+        assert_eq!(cursor.max("price").unwrap(), Some(Value::I32(30)));
+    }
+
+    /// A streaming cursor over `count` documents that counts how often its query runs.
+    fn counted_cursor(count: usize, runs: Arc<AtomicUsize>) -> DocumentCursor {
+        let documents = move || -> Box<dyn Iterator<Item = NitriteResult<Document>>> {
+            Box::new((0..count).map(|i| Ok(create_document(&format!("John{}", i), "Doe"))))
+        };
+        let factory: StreamFactory = Box::new(move || {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Ok(documents())
+        });
+        DocumentCursor::streaming(documents(), factory, ProcessorChain::new())
+    }
+
+    #[test]
+    fn test_rewindable_cursor_replays_from_cache() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut cursor = counted_cursor(3, runs.clone()).with_memory_limit(Some(1024));
+        cursor.make_rewindable();
+        assert_eq!(cursor.size(), 3);
+        assert_eq!(cursor.size(), 3);
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_rewindable_cursor_over_memory_limit_replays_by_query() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        // each document is 17 bytes, the cache gives up at the third
+        let mut cursor = counted_cursor(5, runs.clone()).with_memory_limit(Some(50));
+        cursor.make_rewindable();
+        assert_eq!(cursor.size(), 5);
+        assert!(cursor.cache.is_empty());
+        assert_eq!(cursor.cached_bytes, 0);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let first = cursor.first().unwrap().unwrap();
+        assert_eq!(first.get("first").unwrap(), Value::from("John0"));
+        assert_eq!(cursor.size(), 5);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_to_arrow_over_memory_limit() {
+        let mut cursor = counted_cursor(5, Arc::new(AtomicUsize::new(0)));
+        assert_eq!(cursor.to_arrow(None).unwrap().num_rows(), 5);
+
+        let mut cursor =
+            counted_cursor(5, Arc::new(AtomicUsize::new(0))).with_memory_limit(Some(50));
+        let error = cursor.to_arrow(None).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::MemoryLimitExceeded);
+    }
+
+    #[test]
+    fn test_projection_pushdown() {
+        let projection = doc! { "first_name": (Value::Null) };
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        // nothing says which map the documents come from
+        let cursor = counted_cursor(1, runs.clone()).set_find_plan(FindPlan::new());
+        assert!(cursor.projection_pushdown(&projection).is_none());
+
+        let cursor = counted_cursor(1, runs.clone())
+            .set_find_plan(FindPlan::new())
+            .with_source_map("people".to_string());
+        let (map_name, fields) = cursor.projection_pushdown(&projection).unwrap();
+        assert_eq!(map_name, "people");
+        assert_eq!(&*fields, ["first_name".to_string()]);
+
+        // a sort reads fields the projection drops
+        let mut find_plan = FindPlan::new();
+        find_plan.set_blocking_sort_order(vec![("last_name".to_string(), SortOrder::Ascending)]);
+        let cursor = counted_cursor(1, runs.clone())
+            .set_find_plan(find_plan)
+            .with_source_map("people".to_string());
+        assert!(cursor.projection_pushdown(&projection).is_none());
+
+        // a rewindable cursor keeps the documents it read
+        let mut cursor = counted_cursor(1, runs)
+            .set_find_plan(FindPlan::new())
+            .with_source_map("people".to_string());
+        cursor.make_rewindable();
+        assert!(cursor.projection_pushdown(&projection).is_none());
+    }
+
     #[test]
     fn bench_iter_with_id() {
         let mut docs = Vec::new();
@@ -0,0 +1,96 @@
+use crate::{
+    collection::{Document, NitriteId},
+    errors::NitriteResult,
+    store::{NitriteMap, NitriteMapProvider},
+    Key, Value,
+};
+
+/// Iterates over the documents of a collection map whose ids lie between two ids, both
+/// inclusive, by walking the sorted keys from the start of the range.
+pub(crate) struct IdRangeStream {
+    entries: NitriteMap,
+    start: NitriteId,
+    end: NitriteId,
+    current: Option<Key>,
+    done: bool,
+}
+
+impl IdRangeStream {
+    pub fn new(map: NitriteMap, start: NitriteId, end: NitriteId) -> Self {
+        Self {
+            entries: map,
+            start,
+            end,
+            current: None,
+            done: false,
+        }
+    }
+
+    fn next_key(&self) -> NitriteResult<Option<Key>> {
+        match &self.current {
+            Some(current_key) => self.entries.higher_key(current_key),
+            None => self.entries.ceiling_key(&Value::NitriteId(self.start)),
+        }
+    }
+}
+
+impl Iterator for IdRangeStream {
+    type Item = NitriteResult<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let key = match self.next_key() {
+                Ok(Some(key)) => key,
+                Ok(None) => break,
+                Err(e) => return Some(Err(e)),
+            };
+
+            match key.as_nitrite_id() {
+                Some(id) if *id <= self.end => {}
+                _ => break,
+            }
+
+            self.current = Some(key.clone());
+            match self.entries.get(&key) {
+                Ok(Some(value)) => match value.as_document() {
+                    Some(doc) => return Some(Ok(doc.clone())),
+                    None => {
+                        log::warn!("Data corruption: Expected Document in map values, found {:?}", value);
+                    }
+                },
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        self.done = true;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{NitriteStore, NitriteStoreProvider};
+
+    #[test]
+    fn test_id_range_stream() {
+        let map = NitriteStore::default().open_map("range").unwrap();
+        let mut ids = Vec::new();
+        for i in 0..10 {
+            let id = NitriteId::new();
+            let mut doc = Document::new();
+            doc.put("n", Value::I32(i)).unwrap();
+            map.put(Value::NitriteId(id), Value::Document(doc)).unwrap();
+            ids.push(id);
+        }
+
+        let values: Vec<i32> = IdRangeStream::new(map.clone(), ids[2], ids[5])
+            .map(|doc| *doc.unwrap().get("n").unwrap().as_i32().unwrap())
+            .collect();
+        assert_eq!(values, vec![2, 3, 4, 5]);
+
+        let empty = IdRangeStream::new(map, ids[5], ids[2]);
+        assert_eq!(empty.count(), 0);
+    }
+}
@@ -0,0 +1,157 @@
+use roaring::RoaringTreemap;
+
+use crate::collection::NitriteId;
+
+/// A set of document ids backed by a compressed bitmap.
+///
+/// The query engine combines the ids found by several indexes — the branches of an `or`,
+/// the documents already returned by a union — with `IdSet`s instead of hashed id
+/// vectors. Generated ids embed a timestamp and a node id, so they are sparse: ids
+/// written in the same millisecond share a bitmap container and take about two bytes
+/// each, while ids written far apart get a container of their own and take about as much
+/// memory as a vector of ids. Unions merge whole containers at once.
+///
+/// Ids are iterated in ascending order, whatever order they were added in.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut ids: IdSet = open_ids.into_iter().collect();
+/// ids.union_with(&urgent_ids.into_iter().collect());
+/// for id in ids.iter() {
+///     // ascending ids of the open or urgent documents, each once
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdSet {
+    bitmap: RoaringTreemap,
+}
+
+impl IdSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        IdSet::default()
+    }
+
+    /// Adds an id, returning `false` if it was already in the set.
+    pub fn insert(&mut self, id: NitriteId) -> bool {
+        self.bitmap.insert(id.id_value())
+    }
+
+    /// Removes an id, returning `false` if it was not in the set.
+    pub fn remove(&mut self, id: &NitriteId) -> bool {
+        self.bitmap.remove(id.id_value())
+    }
+
+    /// Returns `true` if the set holds the id.
+    pub fn contains(&self, id: &NitriteId) -> bool {
+        self.bitmap.contains(id.id_value())
+    }
+
+    /// Returns the number of ids in the set.
+    pub fn len(&self) -> u64 {
+        self.bitmap.len()
+    }
+
+    /// Returns `true` if the set holds no id.
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.is_empty()
+    }
+
+    /// Adds every id of `other` to this set.
+    pub fn union_with(&mut self, other: &IdSet) {
+        self.bitmap |= &other.bitmap;
+    }
+
+    /// Iterates the ids in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = NitriteId> + '_ {
+        self.bitmap.iter().map(NitriteId::from_id_value)
+    }
+}
+
+impl FromIterator<NitriteId> for IdSet {
+    fn from_iter<T: IntoIterator<Item = NitriteId>>(iter: T) -> Self {
+        let mut set = IdSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<NitriteId> for IdSet {
+    fn extend<T: IntoIterator<Item = NitriteId>>(&mut self, iter: T) {
+        self.bitmap.extend(iter.into_iter().map(|id| id.id_value()));
+    }
+}
+
+impl IntoIterator for IdSet {
+    type Item = NitriteId;
+    type IntoIter = IdSetIntoIter;
+
+    fn into_iter(self) -> IdSetIntoIter {
+        IdSetIntoIter {
+            inner: self.bitmap.into_iter(),
+        }
+    }
+}
+
+/// Owning iterator over the ids of an [`IdSet`], in ascending order.
+pub struct IdSetIntoIter {
+    inner: roaring::treemap::IntoIter,
+}
+
+impl Iterator for IdSetIntoIter {
+    type Item = NitriteId;
+
+    fn next(&mut self) -> Option<NitriteId> {
+        self.inner.next().map(NitriteId::from_id_value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<NitriteId> {
+        (0..count).map(|_| NitriteId::new()).collect()
+    }
+
+    #[test]
+    fn test_id_set_iterates_ascending_without_duplicates() {
+        let ids = ids(100);
+        let mut shuffled: Vec<_> = ids.iter().rev().copied().collect();
+        shuffled.extend(ids.iter().take(10));
+
+        let set: IdSet = shuffled.into_iter().collect();
+        assert_eq!(set.len(), 100);
+        assert_eq!(set.iter().collect::<Vec<_>>(), ids);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    fn test_id_set_operations() {
+        let ids = ids(6);
+        let mut union: IdSet = ids[0..4].iter().copied().collect();
+        let right: IdSet = ids[2..6].iter().copied().collect();
+
+        union.union_with(&right);
+        assert_eq!(union.len(), 6);
+        assert_eq!(union.iter().collect::<Vec<_>>(), ids);
+        assert!(union.contains(&ids[5]));
+    }
+
+    #[test]
+    fn test_id_set_insert_and_remove() {
+        let id = NitriteId::new();
+        let mut set = IdSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(id));
+        assert!(!set.insert(id));
+        assert!(set.remove(&id));
+        assert!(!set.remove(&id));
+        assert!(set.is_empty());
+    }
+}
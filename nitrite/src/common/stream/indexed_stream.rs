@@ -1,3 +1,4 @@
+use super::id_set::IdSet;
 use crate::{
     collection::{Document, NitriteId},
     errors::NitriteResult,
@@ -7,16 +8,30 @@ use crate::{
 
 pub(crate) struct IndexedStream {
     nitrite_map: NitriteMap,
-    id_set: Vec<NitriteId>,
-    current: usize,
+    ids: Box<dyn Iterator<Item = NitriteId> + Send>,
 }
 
 impl IndexedStream {
+    /// Reads the documents of `id_set` in the order of the ids.
     pub fn new(nitrite_map: NitriteMap, id_set: Vec<NitriteId>) -> Self {
         IndexedStream {
             nitrite_map,
-            id_set,
-            current: 0,
+            ids: Box::new(id_set.into_iter()),
+        }
+    }
+
+    /// Reads the documents of `id_set` by ascending id, so an index scan returns the same
+    /// order whatever order its store keeps the ids of a key in.
+    pub fn ordered_by_id(nitrite_map: NitriteMap, id_set: Vec<NitriteId>) -> Self {
+        // the bitmap orders and deduplicates the ids without sorting them
+        Self::from_id_set(nitrite_map, id_set.into_iter().collect())
+    }
+
+    /// Reads the documents of `id_set`, by ascending id.
+    pub fn from_id_set(nitrite_map: NitriteMap, id_set: IdSet) -> Self {
+        IndexedStream {
+            nitrite_map,
+            ids: Box::new(id_set.into_iter()),
         }
     }
 }
@@ -25,21 +40,16 @@ impl Iterator for IndexedStream {
     type Item = NitriteResult<Document>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let id_set_len = self.id_set.len();
         loop {
-            // Bounds check with early exit
-            if self.current >= id_set_len {
-                log::debug!("IndexedStream::next - exhausted all {} ids", id_set_len);
+            let Some(id) = self.ids.next() else {
+                log::debug!("IndexedStream::next - exhausted all ids");
                 return None;
-            }
+            };
 
-            let id = &self.id_set[self.current];
-            self.current += 1;
-            
             log::debug!("IndexedStream::next - looking up id: {:?}", id);
             
             // Inline the match for better branch prediction
-            match self.nitrite_map.get(&Value::NitriteId(*id)) {
+            match self.nitrite_map.get(&Value::NitriteId(id)) {
                 Ok(Some(value)) => {
                     // Direct as_document() check without nested match
                     if let Some(doc) = value.as_document() {
@@ -62,6 +72,28 @@ impl Iterator for IndexedStream {
     }
 }
 
+/// Yields the documents of a covered find plan, which an index built from its keys
+/// without reading the collection. They only hold `_id` and the indexed fields.
+pub(crate) struct CoveredStream {
+    documents: std::vec::IntoIter<Document>,
+}
+
+impl CoveredStream {
+    pub fn new(documents: Vec<Document>) -> Self {
+        CoveredStream {
+            documents: documents.into_iter(),
+        }
+    }
+}
+
+impl Iterator for CoveredStream {
+    type Item = NitriteResult<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.documents.next().map(Ok)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +172,29 @@ mod tests {
         assert!(indexed_stream.next().is_none());
     }
 
+    #[test]
+    fn test_indexed_stream_ordered_by_id() {
+        let map = create_nitrite_map();
+        let mut ids = Vec::new();
+        for name in ["1", "2", "3"] {
+            let mut doc = create_document(name);
+            let id = doc.id().expect("Failed to get id");
+            map.put(Value::NitriteId(id), Value::from(doc)).unwrap();
+            ids.push(id);
+        }
+
+        let reversed: Vec<NitriteId> = ids.iter().rev().copied().collect();
+        let kept: Vec<NitriteId> = IndexedStream::new(map.clone(), reversed.clone())
+            .map(|doc| doc.unwrap().id().unwrap())
+            .collect();
+        assert_eq!(kept, reversed);
+
+        let ordered: Vec<NitriteId> = IndexedStream::ordered_by_id(map, reversed)
+            .map(|doc| doc.unwrap().id().unwrap())
+            .collect();
+        assert_eq!(ordered, ids);
+    }
+
     // as_document().unwrap() error handling tests
     #[test]
     fn test_indexed_stream_with_corrupted_document_type() {
@@ -0,0 +1,75 @@
+use crate::{
+    collection::{Document, QueryInterrupt},
+    errors::NitriteResult,
+};
+
+/// Ends a document stream with an error once its query times out or is cancelled.
+///
+/// Wraps the source of a query, so a filter skipping documents or a blocking sort reading
+/// its input stops at the next document it pulls.
+pub(crate) struct InterruptibleStream {
+    raw_stream: Box<dyn Iterator<Item = NitriteResult<Document>>>,
+    interrupt: QueryInterrupt,
+    interrupted: bool,
+}
+
+impl InterruptibleStream {
+    pub fn new(
+        raw_stream: Box<dyn Iterator<Item = NitriteResult<Document>>>,
+        interrupt: QueryInterrupt,
+    ) -> Self {
+        InterruptibleStream {
+            raw_stream,
+            interrupt,
+            interrupted: false,
+        }
+    }
+}
+
+impl Iterator for InterruptibleStream {
+    type Item = NitriteResult<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.interrupted {
+            return None;
+        }
+
+        if let Err(error) = self.interrupt.check() {
+            self.interrupted = true;
+            return Some(Err(error));
+        }
+        self.raw_stream.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::CancellationToken;
+    use crate::doc;
+    use crate::errors::ErrorKind;
+
+    fn documents(count: i32) -> Box<dyn Iterator<Item = NitriteResult<Document>>> {
+        Box::new((0..count).map(|i| Ok(doc! { "value": i })))
+    }
+
+    #[test]
+    fn test_interruptible_stream_cancelled() {
+        let token = CancellationToken::new();
+        let interrupt = QueryInterrupt::start(None, Some(&token)).unwrap();
+        let mut stream = InterruptibleStream::new(documents(3), interrupt);
+
+        assert!(stream.next().unwrap().is_ok());
+        token.cancel();
+        let error = stream.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::Cancelled);
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_interruptible_stream_without_interrupt() {
+        let interrupt = QueryInterrupt::start(None, Some(&CancellationToken::new())).unwrap();
+        let stream = InterruptibleStream::new(documents(3), interrupt);
+        assert_eq!(stream.filter(|document| document.is_ok()).count(), 3);
+    }
+}
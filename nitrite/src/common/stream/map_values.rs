@@ -15,6 +15,14 @@ impl MapValues {
         }
     }
 
+    /// Iterates the documents stored after `key`, in the order of the map.
+    pub fn after(map: NitriteMap, key: Key) -> Self {
+        Self {
+            entries: map,
+            current: Some(key),
+        }
+    }
+
     fn set_current(
         &mut self,
         next_key: NitriteResult<Option<Key>>,
@@ -138,6 +146,20 @@ mod tests {
         assert!(none_key.is_none());
     }
 
+    #[test]
+    fn test_map_values_after_key() {
+        let map = create_test_map();
+        let first_key = map.first_key().unwrap().unwrap();
+        let mut map_values = MapValues::after(map.clone(), first_key.clone());
+
+        let mut second = map_values.next().unwrap().unwrap();
+        assert_ne!(Key::from(second.id().unwrap()), first_key);
+        assert!(map_values.next().is_none());
+
+        let last_key = map.last_key().unwrap().unwrap();
+        assert!(MapValues::after(map, last_key).next().is_none());
+    }
+
     // as_document().unwrap() error handling tests
     #[test]
     fn test_map_values_with_corrupted_document_type() {
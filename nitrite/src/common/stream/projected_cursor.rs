@@ -1,15 +1,24 @@
 use crate::collection::Document;
 use crate::common::stream::document_cursor::DocumentCursor;
 use crate::errors::NitriteResult;
+use crate::store::with_projection;
+use std::sync::Arc;
 
 pub struct ProjectedDocumentCursor<'a> {
     cursor: &'a mut DocumentCursor,
     projection: Document,
+    /// The map read by the cursor and the fields it may limit its reads to.
+    pushdown: Option<(String, Arc<[String]>)>,
 }
 
 impl<'a> ProjectedDocumentCursor<'a> {
     pub(crate) fn new(cursor: &'a mut DocumentCursor, projection: Document) -> Self {
-        ProjectedDocumentCursor { cursor, projection }
+        let pushdown = cursor.projection_pushdown(&projection);
+        ProjectedDocumentCursor {
+            cursor,
+            projection,
+            pushdown,
+        }
     }
 
     /// Resets the projected cursor by resetting the underlying DocumentCursor.
@@ -32,13 +41,17 @@ impl<'a> Iterator for ProjectedDocumentCursor<'a> {
     type Item = NitriteResult<Document>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.cursor.next().map(|doc_result| {
+        let next = match &self.pushdown {
+            Some((map_name, fields)) => with_projection(map_name, fields, || self.cursor.next()),
+            None => self.cursor.next(),
+        };
+        next.map(|doc_result| {
             doc_result.and_then(|doc| project(doc, &self.projection))
         })
     }
 }
 
-fn project(doc: Document, projection: &Document) -> NitriteResult<Document> {
+pub(crate) fn project(doc: Document, projection: &Document) -> NitriteResult<Document> {
     let mut projected_doc = Document::new();
     let fields = projection.fields();
     for field in fields {
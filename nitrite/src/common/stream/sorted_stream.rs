@@ -1,96 +1,177 @@
 use crate::{
-    collection::Document,
-    errors::{NitriteError, NitriteResult},
-    SortOrder,
+    collection::{operation::estimated_size, Document},
+    common::Value,
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    profiler::{timed, Phase},
+    store::{NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider},
+    SortOrder, DOC_ID, INTERNAL_NAME_SEPARATOR, SORT_PREFIX,
 };
 use icu_collator::options::CollatorOptions;
 use icu_collator::{Collator, CollatorBorrowed, CollatorPreferences};
-
-// Make SortedStream generic over the input iterator type
+use std::cmp::Ordering;
+
+/// Sorts a document stream for a blocking sort.
+///
+/// Without a memory budget all documents are sorted in memory. With a budget, documents
+/// are buffered until their estimated size reaches it; the buffer is then sorted and
+/// spilled as a run to a temporary map of the store (`$nitrite_sort|<uuid>`). When the
+/// input is exhausted the runs and the last buffer are merged lazily, holding only the
+/// head document of each run in memory. The runs are removed from the store when the
+/// stream is dropped. Documents with equal sort keys are ordered by `_id` unless the
+/// tie-break is off; documents that still compare equal keep their input order.
 pub(crate) struct SortedStream {
-    sorted: Vec<NitriteResult<Document>>,
+    sorted: Vec<Document>,
     error: Option<NitriteError>,
     current_index: usize,
+    runs: Vec<SortRun>,
+    heads: Vec<Option<Document>>,
+    comparator: DocumentComparator,
 }
 
 impl SortedStream {
-    pub fn new<I: Iterator<Item = NitriteResult<Document>>> (
+    /// Creates a sorted stream in memory, breaking ties by `_id`.
+    pub fn new<I: Iterator<Item = NitriteResult<Document>>>(
+        raw_stream: I,
+        sort_order: Vec<(String, SortOrder)>,
+        collator: Option<CollatorBorrowed<'static>>,
+    ) -> Self {
+        Self::build(
+            raw_stream,
+            DocumentComparator {
+                sort_order,
+                collator,
+                tie_break_by_id: true,
+            },
+            None,
+        )
+    }
+
+    /// Creates a sorted stream that spills to `store` whenever the buffered documents
+    /// exceed `memory_budget` bytes.
+    pub fn with_memory_budget<I: Iterator<Item = NitriteResult<Document>>>(
         raw_stream: I,
         sort_order: Vec<(String, SortOrder)>,
-        collator: Option<CollatorBorrowed>,
+        collator: Option<CollatorBorrowed<'static>>,
+        tie_break_by_id: bool,
+        store: NitriteStore,
+        memory_budget: u64,
     ) -> Self {
-        let unsorted = raw_stream.collect::<Vec<NitriteResult<Document>>>();
-        let mut error = None;
-
-        let mut cleaned = Vec::with_capacity(unsorted.len());
-        for doc in unsorted.iter() {
-            if doc.is_err() {
-                error = doc.as_ref().err().cloned();
-                break;
+        Self::build(
+            raw_stream,
+            DocumentComparator {
+                sort_order,
+                collator,
+                tie_break_by_id,
+            },
+            Some((store, memory_budget)),
+        )
+    }
+
+    fn build<I: Iterator<Item = NitriteResult<Document>>>(
+        raw_stream: I,
+        comparator: DocumentComparator,
+        spill: Option<(NitriteStore, u64)>,
+    ) -> Self {
+        let mut stream = SortedStream {
+            sorted: Vec::new(),
+            error: None,
+            current_index: 0,
+            runs: Vec::new(),
+            heads: Vec::new(),
+            comparator,
+        };
+
+        let mut buffered = 0u64;
+        for item in raw_stream {
+            let document = match item {
+                Ok(document) => document,
+                Err(error) => {
+                    // fail fast, the documents read so far are never returned
+                    stream.error = Some(error);
+                    break;
+                }
+            };
+
+            if let Some((store, memory_budget)) = &spill {
+                buffered += estimated_size(&document);
+                stream.sorted.push(document);
+                if buffered >= *memory_budget {
+                    if let Err(error) = stream.spill(store) {
+                        stream.error = Some(error);
+                        break;
+                    }
+                    buffered = 0;
+                }
+            } else {
+                stream.sorted.push(document);
             }
-            cleaned.push(doc.clone());
         }
 
-        let has_collator = collator.is_some();
-        
-        cleaned.sort_by(|a, b| {
-            for (field, order) in sort_order.iter() {
-                // Safe extraction with proper error handling - avoid double unwrap
-                let a_value = match a.as_ref() {
-                    Ok(doc) => match doc.get(field) {
-                        Ok(val) => val,
-                        Err(_) => {
-                            // Field missing or error in document A
-                            return std::cmp::Ordering::Less;
-                        }
-                    },
-                    Err(_) => return std::cmp::Ordering::Less,
-                };
-
-                let b_value = match b.as_ref() {
-                    Ok(doc) => match doc.get(field) {
-                        Ok(val) => val,
-                        Err(_) => {
-                            // Field missing or error in document B
-                            return std::cmp::Ordering::Greater;
-                        }
-                    },
-                    Err(_) => return std::cmp::Ordering::Greater,
-                };
+        stream.comparator.sort(&mut stream.sorted);
+        if stream.error.is_none() {
+            for run in stream.runs.iter_mut() {
+                match run.next() {
+                    Ok(head) => stream.heads.push(head),
+                    Err(error) => {
+                        stream.error = Some(error);
+                        break;
+                    }
+                }
+            }
+        }
+        stream
+    }
 
-                // Handle null values
-                let cmp = if a_value.is_null() && !b_value.is_null() {
-                    std::cmp::Ordering::Less
-                } else if !a_value.is_null() && b_value.is_null() {
-                    std::cmp::Ordering::Greater
-                } else if a_value.is_null() && b_value.is_null() {
-                    std::cmp::Ordering::Equal
-                } else if a_value.is_string() && b_value.is_string() && has_collator {
-                    let a = a_value.as_string().unwrap();
-                    let b = b_value.as_string().unwrap();
-                    collator.as_ref().map(|cb| cb.compare(a, b)).unwrap_or_else(|| a.cmp(b))
-                } else if a_value.is_string() && b_value.is_string() {
-                    let a = a_value.as_string().unwrap();
-                    let b = b_value.as_string().unwrap();
-                    a.cmp(b)
-                } else {
-                    a_value.cmp(&b_value)
-                };
+    /// Sorts the buffered documents and moves them to a new run.
+    fn spill(&mut self, store: &NitriteStore) -> NitriteResult<()> {
+        let mut documents = std::mem::take(&mut self.sorted);
+        self.comparator.sort(&mut documents);
+        self.runs.push(SortRun::write(store, documents)?);
+        Ok(())
+    }
 
-                if cmp != std::cmp::Ordering::Equal {
-                    return match order {
-                        SortOrder::Ascending => cmp,
-                        SortOrder::Descending => cmp.reverse(),
-                    };
+    /// Takes the smallest head of the runs and the in-memory buffer.
+    fn merge_next(&mut self) -> Option<NitriteResult<Document>> {
+        // on ties the earlier run wins, the buffer holds the latest documents
+        let mut smallest: Option<(usize, &Document)> = None;
+        for (index, head) in self.heads.iter().enumerate() {
+            if let Some(document) = head {
+                if smallest.is_none_or(|(_, current)| {
+                    self.comparator.compare(document, current) == Ordering::Less
+                }) {
+                    smallest = Some((index, document));
                 }
             }
-            std::cmp::Ordering::Equal
-        });
+        }
 
-        Self {
-            sorted: cleaned,
-            error,
-            current_index: 0,
+        let buffered = self.sorted.get(self.current_index);
+        let run_index = match (smallest, buffered) {
+            (None, None) => return None,
+            (None, Some(_)) => None,
+            (Some((index, _)), None) => Some(index),
+            (Some((index, head)), Some(document)) => {
+                if self.comparator.compare(document, head) == Ordering::Less {
+                    None
+                } else {
+                    Some(index)
+                }
+            }
+        };
+
+        match run_index {
+            Some(index) => {
+                let document = self.heads[index].take()?;
+                match self.runs[index].next() {
+                    Ok(head) => self.heads[index] = head,
+                    Err(error) => self.error = Some(error),
+                }
+                Some(Ok(document))
+            }
+            None => {
+                let document = self.sorted[self.current_index].clone();
+                self.current_index += 1;
+                Some(Ok(document))
+            }
         }
     }
 }
@@ -104,22 +185,149 @@ impl Iterator for SortedStream {
             return Some(Err(error));
         }
 
+        if !self.runs.is_empty() {
+            return timed(Phase::Sort, || self.merge_next());
+        }
+
         if self.current_index < self.sorted.len() {
             let result = self.sorted[self.current_index].clone();
             self.current_index += 1;
-            Some(result)
+            Some(Ok(result))
         } else {
             None
         }
     }
 }
 
+/// Compares documents by a sort order. Nulls sort first and strings are compared with
+/// the collator, if there is one. With `tie_break_by_id`, documents equal on every sort
+/// field are compared by ascending `_id`, whatever the sort order.
+struct DocumentComparator {
+    sort_order: Vec<(String, SortOrder)>,
+    collator: Option<CollatorBorrowed<'static>>,
+    tie_break_by_id: bool,
+}
+
+impl DocumentComparator {
+    fn sort(&self, documents: &mut [Document]) {
+        timed(Phase::Sort, || documents.sort_by(|a, b| self.compare(a, b)));
+    }
+
+    fn compare(&self, a: &Document, b: &Document) -> Ordering {
+        for (field, order) in self.sort_order.iter() {
+            let a_value = match a.get(field) {
+                Ok(val) => val,
+                // Field missing or error in document A
+                Err(_) => return Ordering::Less,
+            };
+
+            let b_value = match b.get(field) {
+                Ok(val) => val,
+                // Field missing or error in document B
+                Err(_) => return Ordering::Greater,
+            };
+
+            // Handle null values
+            let cmp = if a_value.is_null() && !b_value.is_null() {
+                Ordering::Less
+            } else if !a_value.is_null() && b_value.is_null() {
+                Ordering::Greater
+            } else if a_value.is_null() && b_value.is_null() {
+                Ordering::Equal
+            } else if let (Some(a), Some(b)) = (a_value.as_string(), b_value.as_string()) {
+                self.collator
+                    .as_ref()
+                    .map(|cb| cb.compare(a, b))
+                    .unwrap_or_else(|| a.cmp(b))
+            } else {
+                a_value.cmp(&b_value)
+            };
+
+            if cmp != Ordering::Equal {
+                return match order {
+                    SortOrder::Ascending => cmp,
+                    SortOrder::Descending => cmp.reverse(),
+                };
+            }
+        }
+
+        if self.tie_break_by_id {
+            if let (Ok(a_id), Ok(b_id)) = (a.get(DOC_ID), b.get(DOC_ID)) {
+                return a_id.cmp(&b_id);
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// A sorted run spilled to a temporary map, keyed by position.
+struct SortRun {
+    map: NitriteMap,
+    len: u64,
+    position: u64,
+}
+
+impl SortRun {
+    fn write(store: &NitriteStore, documents: Vec<Document>) -> NitriteResult<SortRun> {
+        let name = format!(
+            "{}{}{}",
+            SORT_PREFIX,
+            INTERNAL_NAME_SEPARATOR,
+            uuid::Uuid::new_v4()
+        );
+        let run = SortRun {
+            map: store.open_map(&name)?,
+            len: documents.len() as u64,
+            position: 0,
+        };
+
+        let entries = documents
+            .into_iter()
+            .enumerate()
+            .map(|(position, document)| (Value::U64(position as u64), Value::Document(document)))
+            .collect();
+        run.map.put_all(entries)?;
+        Ok(run)
+    }
+
+    fn next(&mut self) -> NitriteResult<Option<Document>> {
+        if self.position >= self.len {
+            return Ok(None);
+        }
+
+        let value = self.map.get(&Value::U64(self.position))?;
+        self.position += 1;
+        match value {
+            Some(Value::Document(document)) => Ok(Some(document)),
+            _ => {
+                log::error!("Sorted run is missing the document at position {}", self.position - 1);
+                Err(NitriteError::new(
+                    &format!(
+                        "Sorted run is missing the document at position {}",
+                        self.position - 1
+                    ),
+                    ErrorKind::BackendError,
+                ))
+            }
+        }
+    }
+}
+
+impl Drop for SortRun {
+    fn drop(&mut self) {
+        if let Err(e) = self.map.dispose() {
+            log::warn!("Failed to remove a sorted run: {}", e);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::collection::Document;
+    use crate::collection::{Document, NitriteId};
     use crate::common::Value;
     use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+    use crate::store::memory::{InMemoryStore, InMemoryStoreConfig};
     use crate::SortOrder;
 
     fn create_document(fields: Vec<(&str, &str)>) -> Document {
@@ -499,4 +707,138 @@ mod tests {
             elapsed.as_micros() as f64 / 5.0
         );
     }
+
+    #[test]
+    fn test_sorted_stream_spills_to_store() {
+        let store = NitriteStore::new(InMemoryStore::new(InMemoryStoreConfig::new()));
+        store.open_or_create().unwrap();
+
+        let docs = (0..50i32)
+            .map(|seq| {
+                let mut doc = Document::new();
+                doc.put("group", Value::from((seq * 7) % 5)).unwrap();
+                doc.put("seq", Value::from(seq)).unwrap();
+                Ok(doc)
+            })
+            .collect::<Vec<_>>();
+        let sort_order = vec![("group".to_string(), SortOrder::Ascending)];
+
+        let sorted_stream =
+            SortedStream::with_memory_budget(docs.into_iter(), sort_order, None, true, store.clone(), 64);
+        assert!(sorted_stream.error.is_none());
+        assert!(sorted_stream.runs.len() > 1);
+        let run_names = sorted_stream
+            .runs
+            .iter()
+            .map(|run| run.map.get_name().unwrap())
+            .collect::<Vec<_>>();
+
+        let sorted = sorted_stream
+            .map(|doc| {
+                let doc = doc.unwrap();
+                let group = *doc.get("group").unwrap().as_i32().unwrap();
+                let seq = *doc.get("seq").unwrap().as_i32().unwrap();
+                (group, seq)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sorted.len(), 50);
+        // sorted by group, documents of a group keep their input order
+        assert!(sorted.windows(2).all(|pair| pair[0] < pair[1]));
+
+        for name in run_names {
+            assert!(!store.has_map(&name).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sorted_stream_within_memory_budget_does_not_spill() {
+        let store = NitriteStore::new(InMemoryStore::new(InMemoryStoreConfig::new()));
+        store.open_or_create().unwrap();
+
+        let docs = vec![
+            Ok(create_document(vec![("field1", "value3")])),
+            Ok(create_document(vec![("field1", "value1")])),
+        ];
+        let sort_order = vec![("field1".to_string(), SortOrder::Ascending)];
+
+        let mut sorted_stream =
+            SortedStream::with_memory_budget(docs.into_iter(), sort_order, None, true, store, 1024);
+        assert!(sorted_stream.runs.is_empty());
+        let result = sorted_stream.next().unwrap().unwrap();
+        assert_eq!(result.get("field1").unwrap(), "value1".into());
+    }
+
+    const ID_BASE: u64 = 1_000_000_000_000_000_000;
+
+    fn documents_with_ids(groups: &[i32]) -> Vec<NitriteResult<Document>> {
+        // ids descend, so the input order is the reverse of the id order
+        groups
+            .iter()
+            .enumerate()
+            .map(|(position, group)| {
+                let mut doc = Document::new();
+                let id = NitriteId::create_id(ID_BASE + 1000 - position as u64).unwrap();
+                doc.put("_id", id).unwrap();
+                doc.put("group", Value::from(*group)).unwrap();
+                Ok(doc)
+            })
+            .collect()
+    }
+
+    fn sorted_ids(sorted_stream: SortedStream) -> Vec<(i32, u64)> {
+        sorted_stream
+            .map(|doc| {
+                let mut doc = doc.unwrap();
+                let group = *doc.get("group").unwrap().as_i32().unwrap();
+                (group, doc.id().unwrap().id_value() - ID_BASE)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sorted_stream_breaks_ties_by_id() {
+        let groups = [2, 1, 2, 1, 2, 1];
+        let sort_order = vec![("group".to_string(), SortOrder::Descending)];
+        let sorted = sorted_ids(SortedStream::new(
+            documents_with_ids(&groups).into_iter(),
+            sort_order.clone(),
+            None,
+        ));
+        // ties are ordered by ascending id, also in a descending sort
+        assert_eq!(
+            sorted,
+            vec![(2, 996), (2, 998), (2, 1000), (1, 995), (1, 997), (1, 999)]
+        );
+
+        let store = NitriteStore::new(InMemoryStore::new(InMemoryStoreConfig::new()));
+        store.open_or_create().unwrap();
+        let spilled = SortedStream::with_memory_budget(
+            documents_with_ids(&groups).into_iter(),
+            sort_order,
+            None,
+            true,
+            store,
+            16,
+        );
+        assert!(spilled.runs.len() > 1);
+        assert_eq!(sorted_ids(spilled), sorted);
+    }
+
+    #[test]
+    fn test_sorted_stream_without_tie_break_keeps_input_order() {
+        let store = NitriteStore::new(InMemoryStore::new(InMemoryStoreConfig::new()));
+        store.open_or_create().unwrap();
+        let sorted_stream = SortedStream::with_memory_budget(
+            documents_with_ids(&[2, 1, 2, 1]).into_iter(),
+            vec![("group".to_string(), SortOrder::Ascending)],
+            None,
+            false,
+            store,
+            1024,
+        );
+        assert_eq!(
+            sorted_ids(sorted_stream),
+            vec![(1, 999), (1, 997), (2, 1000), (2, 998)]
+        );
+    }
 }
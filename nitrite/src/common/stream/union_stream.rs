@@ -1,4 +1,12 @@
-use crate::{collection::Document, errors::NitriteResult};
+use super::id_set::{IdSet, IdSetIntoIter};
+use crate::{
+    collection::{Document, NitriteId},
+    errors::NitriteResult,
+    filter::Filter,
+    profiler::{timed, Phase},
+    store::{NitriteMap, NitriteMapProvider},
+    Value,
+};
 
 // Make UnionStream generic over the iterator type
 pub(crate) struct UnionStream<I>
@@ -62,11 +70,163 @@ where
     }
 }
 
+/// Reads the documents of the union of the id sets found for the branches of an `or`.
+///
+/// Each document is read once, however many branches found it, and is returned if it
+/// passes the check of at least one of those branches. A branch without a check accepts
+/// every document its index found. The documents are read by ascending id.
+pub(crate) struct IdUnionStream {
+    nitrite_map: NitriteMap,
+    ids: IdSetIntoIter,
+    id_count: u64,
+    /// The id set and check of every branch with a check.
+    branches: Vec<(IdSet, Filter)>,
+    /// The ids found by the branches without a check, accepted as they are.
+    unchecked: IdSet,
+}
+
+impl IdUnionStream {
+    pub fn new(nitrite_map: NitriteMap, id_sets: Vec<Vec<NitriteId>>, checks: Vec<Option<Filter>>) -> Self {
+        let (union, branches, unchecked) = timed(Phase::Dedup, || {
+            let mut union = IdSet::new();
+            let mut branches = Vec::new();
+            let mut unchecked = IdSet::new();
+            for (id_set, check) in id_sets.into_iter().zip(checks) {
+                let id_set: IdSet = id_set.into_iter().collect();
+                union.union_with(&id_set);
+                match check {
+                    Some(check) => branches.push((id_set, check)),
+                    None => unchecked.union_with(&id_set),
+                }
+            }
+            (union, branches, unchecked)
+        });
+
+        IdUnionStream {
+            nitrite_map,
+            id_count: union.len(),
+            ids: union.into_iter(),
+            branches,
+            unchecked,
+        }
+    }
+
+    /// The number of distinct ids found by all branches.
+    pub fn id_count(&self) -> usize {
+        self.id_count as usize
+    }
+
+}
+
+fn passes(
+    branches: &[(IdSet, Filter)],
+    unchecked: &IdSet,
+    id: &NitriteId,
+    document: &Document,
+) -> NitriteResult<bool> {
+    if unchecked.contains(id) {
+        return Ok(true);
+    }
+    for (id_set, check) in branches {
+        if id_set.contains(id) && check.apply(document)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+impl Iterator for IdUnionStream {
+    type Item = NitriteResult<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let IdUnionStream {
+            nitrite_map,
+            ids,
+            branches,
+            unchecked,
+            ..
+        } = self;
+        for id in ids.by_ref() {
+            let document = match nitrite_map.get(&Value::NitriteId(id)) {
+                Ok(Some(value)) => match value.as_document() {
+                    Some(document) => document.clone(),
+                    None => {
+                        log::warn!("Data corruption: Expected Document in union stream, found {:?}", value);
+                        continue;
+                    }
+                },
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            };
+
+            match passes(branches, unchecked, &id, &document) {
+                Ok(true) => return Some(Ok(document)),
+                Ok(false) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::collection::Document;
     use crate::errors::{ErrorKind, NitriteError};
+    use crate::filter::field;
+    use crate::nitrite_config::NitriteConfig;
+    use crate::store::NitriteStoreProvider;
+
+    fn create_nitrite_map() -> NitriteMap {
+        let config = NitriteConfig::default();
+        config.auto_configure().expect("Failed to auto configure");
+        config.initialize().expect("Failed to initialize");
+        let store = config.nitrite_store().expect("Failed to get store");
+
+        store.open_map("test").expect("Failed to open map")
+    }
+
+    fn insert_document(map: &NitriteMap, n: i32) -> NitriteId {
+        let mut doc = Document::new();
+        doc.put("n", n).expect("Failed to put value");
+        let id = doc.id().expect("Failed to get id");
+        map.put(Value::NitriteId(id), Value::from(doc)).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_id_union_stream_reads_each_document_once() {
+        let map = create_nitrite_map();
+        let id1 = insert_document(&map, 1);
+        let id2 = insert_document(&map, 2);
+        let id3 = insert_document(&map, 3);
+
+        let id_sets = vec![vec![id1, id2], vec![id2, id3, NitriteId::new()]];
+        let stream = IdUnionStream::new(map, id_sets, vec![None, None]);
+        assert_eq!(stream.id_count(), 4);
+
+        let numbers: Vec<_> = stream
+            .map(|doc| doc.unwrap().get("n").unwrap())
+            .collect();
+        assert_eq!(numbers, vec![Value::I32(1), Value::I32(2), Value::I32(3)]);
+    }
+
+    #[test]
+    fn test_id_union_stream_checks_finding_branches() {
+        let map = create_nitrite_map();
+        let id1 = insert_document(&map, 1);
+        let id2 = insert_document(&map, 2);
+        let id3 = insert_document(&map, 3);
+
+        // id2 fails the check of the first branch but is also found by the second
+        let id_sets = vec![vec![id1, id2], vec![id2], vec![id3]];
+        let checks = vec![Some(field("n").eq(1)), None, Some(field("n").eq(4))];
+        let numbers: Vec<_> = IdUnionStream::new(map, id_sets, checks)
+            .map(|doc| doc.unwrap().get("n").unwrap())
+            .collect();
+        assert_eq!(numbers, vec![Value::I32(1), Value::I32(2)]);
+    }
 
     #[test]
     fn test_union_stream_empty() {
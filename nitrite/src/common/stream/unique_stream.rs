@@ -1,8 +1,8 @@
-use std::collections::HashSet;
-
+use super::id_set::IdSet;
 use crate::{
     collection::{Document, NitriteId},
     errors::NitriteResult,
+    profiler::{timed, Phase},
 };
 
 // Make UniqueStream generic over the iterator type
@@ -11,7 +11,7 @@ where
     I: Iterator<Item = NitriteResult<Document>>,
 {
     raw_stream: I,
-    unique_set: HashSet<NitriteId>,
+    unique_set: IdSet,
 }
 
 impl<I> UniqueStream<I>
@@ -19,10 +19,9 @@ where
     I: Iterator<Item = NitriteResult<Document>>,
 {
     pub fn new(raw_stream: I) -> Self {
-        // Preallocate with default capacity to reduce allocations during iteration
         UniqueStream {
             raw_stream,
-            unique_set: HashSet::with_capacity(128),
+            unique_set: IdSet::new(),
         }
     }
 }
@@ -43,7 +42,7 @@ where
                             match doc.id() {
                                 Ok(id) => {
                                     // Check if ID already seen; if not, add to set and return
-                                    if self.unique_set.insert(id) {
+                                    if timed(Phase::Dedup, || self.unique_set.insert(id)) {
                                         return Some(Ok(doc));
                                     }
                                     // Document is duplicate, continue to next
@@ -174,7 +173,7 @@ mod tests {
         let id1 = doc1.id().unwrap();
         let mut doc2 = Document::new();
         let _id2 = doc2.id().unwrap();
-        let mut unique_set = HashSet::new();
+        let mut unique_set = IdSet::new();
         unique_set.insert(id1);
         let raw_stream: Box<dyn Iterator<Item = NitriteResult<Document>>> =
             Box::new(vec![Ok(doc1.clone()), Ok(doc2.clone())].into_iter());
@@ -4,9 +4,19 @@ use crate::{
     collection::Document,
     errors::NitriteResult,
     filter::{by_id, Filter},
-    FieldValues, Fields, Value,
+    FieldValues, Fields, ReadExecutor, Value, DOC_ID, FIELD_SEPARATOR, METADATA_PREFIX,
+    CONTENT_HASH_FIELD_NAME, EXPIRY_FIELD_NAME, MODIFIED_FIELD_NAME, REVISION_FIELD_NAME,
+    SOURCE_FIELD_NAME,
 };
 
+const METADATA_FIELD_NAMES: [&str; 5] = [
+    REVISION_FIELD_NAME,
+    MODIFIED_FIELD_NAME,
+    SOURCE_FIELD_NAME,
+    EXPIRY_FIELD_NAME,
+    CONTENT_HASH_FIELD_NAME,
+];
+
 /// Creates an empty document.
 pub fn empty_document() -> Document {
     Document::new()
@@ -56,6 +66,82 @@ pub fn create_document(key: &str, value: Value) -> NitriteResult<Document> {
     Ok(doc)
 }
 
+/// Returns the name of the revision metadata field under the configured prefix.
+pub fn revision_field() -> String {
+    METADATA_PREFIX.read_with(|prefix| metadata_field(prefix, REVISION_FIELD_NAME))
+}
+
+/// Returns the name of the last modified metadata field under the configured prefix.
+pub fn modified_field() -> String {
+    METADATA_PREFIX.read_with(|prefix| metadata_field(prefix, MODIFIED_FIELD_NAME))
+}
+
+/// Returns the name of the source metadata field under the configured prefix.
+pub fn source_field() -> String {
+    METADATA_PREFIX.read_with(|prefix| metadata_field(prefix, SOURCE_FIELD_NAME))
+}
+
+/// Returns the name of the field expiry metadata field under the configured prefix.
+pub fn expiry_field() -> String {
+    METADATA_PREFIX.read_with(|prefix| metadata_field(prefix, EXPIRY_FIELD_NAME))
+}
+
+/// Returns the name of the content hash metadata field under the configured prefix.
+pub fn content_hash_field() -> String {
+    METADATA_PREFIX.read_with(|prefix| metadata_field(prefix, CONTENT_HASH_FIELD_NAME))
+}
+
+/// Builds a metadata field name from a prefix, e.g. `_revision` or `$nitrite.revision`.
+pub fn metadata_field(prefix: &str, name: &str) -> String {
+    format!("{}{}", prefix, name)
+}
+
+/// Checks if a top level key is reserved for `_id` or engine metadata.
+///
+/// When the metadata prefix contains the field separator (e.g. `$nitrite.`), the
+/// metadata lives in a nested document and its top level key is reserved as a whole.
+pub(crate) fn is_reserved_field(key: &str) -> bool {
+    if key == DOC_ID {
+        return true;
+    }
+
+    METADATA_PREFIX.read_with(|prefix| {
+        FIELD_SEPARATOR.read_with(|separator| is_metadata_key(key, prefix, separator))
+    })
+}
+
+pub(crate) fn is_metadata_key(key: &str, prefix: &str, separator: &str) -> bool {
+    match prefix.split_once(separator) {
+        Some((root, _)) => key == root,
+        None => key
+            .strip_prefix(prefix)
+            .is_some_and(|name| METADATA_FIELD_NAMES.contains(&name)),
+    }
+}
+
+/// Moves the metadata fields of a document from one prefix to another.
+///
+/// Returns `true` if the document was changed.
+pub(crate) fn rename_metadata_fields(
+    document: &mut Document,
+    old_prefix: &str,
+    new_prefix: &str,
+) -> NitriteResult<bool> {
+    let mut changed = false;
+    for name in METADATA_FIELD_NAMES {
+        let old_field = metadata_field(old_prefix, name);
+        let value = document.get(&old_field)?;
+        if value.is_null() {
+            continue;
+        }
+
+        document.remove(&old_field)?;
+        document.put(metadata_field(new_prefix, name), value)?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
 pub(crate) fn get_document_values(
     document: &mut Document,
     fields: &Fields,
@@ -66,19 +152,37 @@ pub(crate) fn get_document_values(
 
     for field in fields.field_names() {
         let value = document.get(&field)?;
-        values.push((field, value));
+        values.push((field, indexed_value(value)));
     }
 
     Ok(FieldValues::new(values, nitrite_id, fields.clone()))
 }
 
-pub(crate) fn is_affected_by_update(fields: &Fields, updated_fields: &Document) -> bool {
+/// Leaves raw JSON out of the values of an index: a raw JSON field is indexed as a
+/// missing value, and a raw JSON element of an array is not indexed.
+fn indexed_value(value: Value) -> Value {
+    match value {
+        Value::RawJson(_) => Value::Null,
+        Value::Array(values) if values.iter().any(Value::is_raw_json) => {
+            Value::Array(values.into_iter().filter(|value| !value.is_raw_json()).collect())
+        }
+        value => value,
+    }
+}
+
+/// Checks whether an update changed the value of any of the given fields, by
+/// comparing them in the documents before and after the update.
+pub(crate) fn is_affected_by_update(
+    fields: &Fields,
+    old_document: &Document,
+    new_document: &Document,
+) -> NitriteResult<bool> {
     for field in fields.field_names() {
-        if updated_fields.contains_key(&field) {
-            return true;
+        if old_document.get(&field)? != new_document.get(&field)? {
+            return Ok(true);
         }
     }
-    false
+    Ok(false)
 }
 
 pub(crate) fn create_unique_filter(document: &mut Document) -> NitriteResult<Filter> {
@@ -99,6 +203,37 @@ mod tests {
         assert!(doc.is_empty());
     }
 
+    #[test]
+    fn test_is_metadata_key() {
+        assert!(is_metadata_key("_revision", "_", "."));
+        assert!(is_metadata_key("_source", "_", "."));
+        assert!(!is_metadata_key("_other", "_", "."));
+        assert!(!is_metadata_key("revision", "_", "."));
+        // a namespaced prefix reserves its root document
+        assert!(is_metadata_key("$nitrite", "$nitrite.", "."));
+        assert!(!is_metadata_key("_revision", "$nitrite.", "."));
+    }
+
+    #[test]
+    fn test_rename_metadata_fields() {
+        let mut doc = Document::new();
+        doc.put("_revision", Value::I32(2)).unwrap();
+        doc.put("_source", Value::from("app")).unwrap();
+        doc.put("name", Value::from("a")).unwrap();
+
+        assert!(rename_metadata_fields(&mut doc, "_", "$nitrite.").unwrap());
+        assert_eq!(doc.get("$nitrite.revision").unwrap(), Value::I32(2));
+        assert_eq!(doc.get("$nitrite.source").unwrap(), Value::from("app"));
+        assert!(!doc.contains_key("_revision"));
+        assert!(!doc.contains_key("_source"));
+
+        assert!(rename_metadata_fields(&mut doc, "$nitrite.", "_").unwrap());
+        assert_eq!(doc.get("_revision").unwrap(), Value::I32(2));
+        assert!(!doc.contains_key("$nitrite"));
+
+        assert!(!rename_metadata_fields(&mut doc, "x_", "_").unwrap());
+    }
+
     #[test]
     fn test_document_from_map() {
         let mut map = BTreeMap::new();
@@ -124,12 +259,35 @@ mod tests {
         assert_eq!(values.get_value("field1").unwrap(), &Value::String("value1".to_string()));
     }
 
+    #[test]
+    fn test_raw_json_is_not_indexed() {
+        let mut doc = Document::new();
+        doc.put("payload", Value::raw_json(r#"{"a": 1}"#).unwrap()).unwrap();
+        doc.put("tags", Value::Array(vec![Value::from("x"), Value::raw_json("[1]").unwrap()]))
+            .unwrap();
+        let fields = Fields::with_names(vec!["payload", "tags"]).expect("Failed to create fields");
+        let values = get_document_values(&mut doc, &fields).unwrap();
+        assert_eq!(values.get_value("payload"), None);
+        assert_eq!(values.get_value("tags").unwrap(), &Value::Array(vec![Value::from("x")]));
+    }
+
     #[test]
     fn test_is_affected_by_update() {
-        let fields = Fields::with_names(vec!["field1"]).expect("Failed to create fields");
-        let mut updated_fields = Document::new();
-        updated_fields.put("field1", Value::String("value1".to_string())).unwrap();
-        assert!(is_affected_by_update(&fields, &updated_fields));
+        let fields = Fields::with_names(vec!["field1", "nested.field2"]).expect("Failed to create fields");
+        let mut old_document = Document::new();
+        old_document.put("field1", Value::String("value1".to_string())).unwrap();
+        old_document.put("other", Value::I32(1)).unwrap();
+
+        let mut new_document = old_document.clone();
+        new_document.put("other", Value::I32(2)).unwrap();
+        assert!(!is_affected_by_update(&fields, &old_document, &new_document).unwrap());
+
+        new_document.put("nested.field2", Value::I32(3)).unwrap();
+        assert!(is_affected_by_update(&fields, &old_document, &new_document).unwrap());
+
+        let mut new_document = old_document.clone();
+        new_document.put("field1", Value::String("value2".to_string())).unwrap();
+        assert!(is_affected_by_update(&fields, &old_document, &new_document).unwrap());
     }
 
     #[test]
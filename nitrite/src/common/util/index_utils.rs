@@ -1,6 +1,8 @@
 use crate::{
     errors::{ErrorKind, NitriteError, NitriteResult},
     index::IndexDescriptor,
+    nitrite_config::NitriteConfig,
+    store::{NitriteMapProvider, NitriteStoreProvider},
     Value, INDEX_META_PREFIX, INDEX_PREFIX, INTERNAL_NAME_SEPARATOR,
 };
 
@@ -18,6 +20,22 @@ pub(crate) fn derive_index_map_name(descriptor: &IndexDescriptor) -> String {
     name
 }
 
+/// Reads every entry of the map backing a simple, compound or text index so that its
+/// pages are loaded into the store caches.
+pub(crate) fn read_index_map(
+    descriptor: &IndexDescriptor,
+    nitrite_config: &NitriteConfig,
+) -> NitriteResult<()> {
+    let store = nitrite_config.nitrite_store()?;
+    let map_name = derive_index_map_name(descriptor);
+    if store.has_map(&map_name)? {
+        for entry in store.open_map(&map_name)?.entries()? {
+            entry?;
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn derive_index_meta_map_name(collection_name: &str) -> String {
     // Format: $nitrite_index_meta|collection (typical ~20-40 chars)
     let mut name = String::with_capacity(48);
@@ -0,0 +1,358 @@
+use crate::collection::Document;
+use crate::common::Value;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+
+/// Nesting of arrays and objects beyond which JSON text is rejected, so a hostile payload
+/// cannot exhaust the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Checks that `json` is a single valid JSON value, without building it.
+pub(crate) fn validate_json(json: &str) -> NitriteResult<()> {
+    JsonParser::new(json, false).parse().map(|_| ())
+}
+
+/// Parses JSON text into a value: objects become documents, whole numbers `I64` (or
+/// `U64` above `i64::MAX`) and other numbers `F64`.
+pub(crate) fn parse_json(json: &str) -> NitriteResult<Value> {
+    JsonParser::new(json, true).parse()
+}
+
+/// A recursive descent parser of RFC 8259 JSON text.
+///
+/// Without `build` the text is only checked: strings are not unescaped and every value is
+/// returned as `Null`.
+struct JsonParser<'a> {
+    json: &'a [u8],
+    position: usize,
+    depth: usize,
+    build: bool,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(json: &'a str, build: bool) -> Self {
+        JsonParser { json: json.as_bytes(), position: 0, depth: 0, build }
+    }
+
+    fn parse(mut self) -> NitriteResult<Value> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.position < self.json.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> NitriteResult<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::parse_object),
+            Some(b'[') => self.nested(Self::parse_array),
+            Some(b'"') => {
+                let text = self.parse_string()?;
+                Ok(text.map(Value::String).unwrap_or(Value::Null))
+            }
+            Some(b't') => self.parse_literal("true", Value::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Value::Bool(false)),
+            Some(b'n') => self.parse_literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> NitriteResult<Value>) -> NitriteResult<Value> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> NitriteResult<Value> {
+        self.position += 1;
+        let mut document = Document::new();
+        self.skip_whitespace();
+        if self.eat(b'}') {
+            return Ok(self.built(Value::Document(document)));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a field name"));
+            }
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if !self.eat(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            let value = self.parse_value()?;
+            // keys are taken literally, a dot in a JSON key does not nest the field
+            if let Some(key) = key {
+                document.put_literal(key, value);
+            }
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                return Ok(self.built(Value::Document(document)));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> NitriteResult<Value> {
+        self.position += 1;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.eat(b']') {
+            return Ok(self.built(Value::Array(values)));
+        }
+        loop {
+            let value = self.parse_value()?;
+            if self.build {
+                values.push(value);
+            }
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return Ok(self.built(Value::Array(values)));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+    }
+
+    /// Parses a string, returning its unescaped text if the parser builds values.
+    fn parse_string(&mut self) -> NitriteResult<Option<String>> {
+        self.position += 1;
+        let mut text = Vec::new();
+        loop {
+            let start = self.position;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.position += 1;
+            }
+            if self.build {
+                text.extend_from_slice(&self.json[start..self.position]);
+            }
+            match self.next() {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let unescaped = self.parse_escape()?;
+                    if self.build {
+                        let mut buffer = [0; 4];
+                        text.extend_from_slice(unescaped.encode_utf8(&mut buffer).as_bytes());
+                    }
+                }
+                Some(_) => {
+                    self.position -= 1;
+                    return Err(self.error("control character in string"));
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        if !self.build {
+            return Ok(None);
+        }
+        // the input is a str and escapes are encoded as UTF-8, so the text stays valid
+        String::from_utf8(text)
+            .map(Some)
+            .map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn parse_escape(&mut self) -> NitriteResult<char> {
+        let escaped = match self.next() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let code = self.parse_hex()?;
+                return match code {
+                    0xD800..=0xDBFF => {
+                        if !(self.eat(b'\\') && self.eat(b'u')) {
+                            return Err(self.error("unpaired surrogate in string"));
+                        }
+                        let low = self.parse_hex()?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(self.error("unpaired surrogate in string"));
+                        }
+                        let code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        char::from_u32(code).ok_or_else(|| self.error("invalid escape"))
+                    }
+                    0xDC00..=0xDFFF => Err(self.error("unpaired surrogate in string")),
+                    _ => char::from_u32(code).ok_or_else(|| self.error("invalid escape")),
+                };
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        Ok(escaped)
+    }
+
+    fn parse_hex(&mut self) -> NitriteResult<u32> {
+        let digits = self
+            .json
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))
+    }
+
+    fn parse_number(&mut self) -> NitriteResult<Value> {
+        let start = self.position;
+        self.eat(b'-');
+        match self.next() {
+            Some(b'0') => {}
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        let mut whole = true;
+        if self.eat(b'.') {
+            whole = false;
+            self.expect_digits()?;
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            whole = false;
+            if !self.eat(b'+') {
+                self.eat(b'-');
+            }
+            self.expect_digits()?;
+        }
+        if !self.build {
+            return Ok(Value::Null);
+        }
+
+        // the number is ASCII, checked above
+        let text = std::str::from_utf8(&self.json[start..self.position])
+            .map_err(|_| self.error("invalid number"))?;
+        if whole {
+            if let Ok(value) = text.parse::<i64>() {
+                return Ok(Value::I64(value));
+            }
+            if let Ok(value) = text.parse::<u64>() {
+                return Ok(Value::U64(value));
+            }
+        }
+        text.parse::<f64>()
+            .map(Value::F64)
+            .map_err(|_| self.error("invalid number"))
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Value) -> NitriteResult<Value> {
+        if self.json[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("expected a value"))
+        }
+    }
+
+    fn expect_digits(&mut self) -> NitriteResult<()> {
+        if !matches!(self.peek(), Some(b'0'..=b'9')) {
+            return Err(self.error("invalid number"));
+        }
+        self.skip_digits();
+        Ok(())
+    }
+
+    fn skip_digits(&mut self) {
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn built(&self, value: Value) -> Value {
+        if self.build {
+            value
+        } else {
+            Value::Null
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn error(&self, reason: &str) -> NitriteError {
+        let message = format!("Invalid JSON: {} at position {}", reason, self.position);
+        log::error!("{}", message);
+        NitriteError::new(&message, ErrorKind::ValidationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json() {
+        let value = parse_json(r#"{"a.b": [1, -2, 18446744073709551615, 1.5, null], "c": {"d": true}}"#)
+            .unwrap();
+        let document = value.as_document().unwrap();
+        assert_eq!(
+            document.get("a.b").unwrap(),
+            Value::Array(vec![
+                Value::I64(1),
+                Value::I64(-2),
+                Value::U64(u64::MAX),
+                Value::F64(1.5),
+                Value::Null,
+            ])
+        );
+        assert_eq!(document.get("c.d").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_parse_json_scalars() {
+        assert_eq!(parse_json(r#""a\"\\\/\n\u00e9\ud83d\ude00""#).unwrap(), Value::from("a\"\\/\né😀"));
+        assert_eq!(parse_json("-0.5e2").unwrap(), Value::F64(-50.0));
+        assert_eq!(parse_json("1E2").unwrap(), Value::F64(100.0));
+        assert_eq!(parse_json("99999999999999999999").unwrap(), Value::F64(1e20));
+        assert_eq!(parse_json(" false ").unwrap(), Value::Bool(false));
+        assert_eq!(parse_json("[]").unwrap(), Value::Array(vec![]));
+    }
+
+    #[test]
+    fn test_invalid_json_is_rejected() {
+        let nested = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        for json in [
+            "", "{", "[1,]", "{} {}", "nul", "01", "1.", "-", "{\"a\" 1}", "{1: 2}", "\"a",
+            "\"\t\"", "\"\\x\"", "\"\\ud800\"", "\"\\u12\"", nested.as_str(),
+        ] {
+            assert_eq!(validate_json(json).unwrap_err().kind(), &ErrorKind::ValidationError);
+            assert_eq!(parse_json(json).unwrap_err().kind(), &ErrorKind::ValidationError);
+        }
+        assert!(validate_json(" [1, {\"a\": \"b\"}] ").is_ok());
+        assert!(validate_json(&("[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH))).is_ok());
+    }
+}
@@ -2,16 +2,27 @@ mod date_utils;
 mod object_utils;
 mod navigable_map;
 mod index_utils;
+mod json_utils;
 mod tokenizer;
 mod type_utils;
 mod document_utils;
 mod task_util;
+mod panic_guard;
+mod stable_hash;
+#[cfg(any(feature = "arrow", feature = "csv"))]
+mod text_utils;
 
 pub use date_utils::*;
 pub use document_utils::*;
 pub(crate) use index_utils::*;
+pub(crate) use json_utils::*;
 pub(crate) use navigable_map::*;
 pub use object_utils::*;
+pub use panic_guard::catch_panics;
+pub(crate) use panic_guard::catch_panics_with;
+pub(crate) use stable_hash::*;
 pub use task_util::*;
+#[cfg(any(feature = "arrow", feature = "csv"))]
+pub(crate) use text_utils::*;
 pub use tokenizer::*;
 pub use type_utils::*;
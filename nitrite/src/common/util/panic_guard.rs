@@ -0,0 +1,81 @@
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Runs `f`, turning a panic raised inside it into a [`NitriteError`].
+///
+/// Nitrite calls plugin code (indexers, stores) through this boundary so that a
+/// panicking plugin fails the operation instead of unwinding into the application.
+/// `context` names the call in the error message, e.g. `"indexer 'Spatial' write_index_entry"`.
+///
+/// The closure is assumed to be unwind safe: after a panic the plugin may be left
+/// in an inconsistent state, which the returned `PluginError` reports to the caller.
+pub fn catch_panics<T, F>(context: &str, f: F) -> NitriteResult<T>
+where
+    F: FnOnce() -> NitriteResult<T>,
+{
+    catch_panics_with(|| context.to_string(), f)
+}
+
+/// Like [`catch_panics`], but builds the context only when `f` panics.
+pub(crate) fn catch_panics_with<T, C, F>(context: C, f: F) -> NitriteResult<T>
+where
+    C: FnOnce() -> String,
+    F: FnOnce() -> NitriteResult<T>,
+{
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = format!("{} panicked: {}", context(), panic_message(payload.as_ref()));
+            log::error!("{}", message);
+            Err(NitriteError::new(&message, ErrorKind::PluginError))
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown cause"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panics_passes_through_result() {
+        assert_eq!(catch_panics("test", || Ok(42)).unwrap(), 42);
+
+        let err = catch_panics::<(), _>("test", || {
+            Err(NitriteError::new("failed", ErrorKind::IndexingError))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::IndexingError);
+    }
+
+    #[test]
+    fn test_catch_panics_converts_str_panic() {
+        let err = catch_panics::<(), _>("indexer 'Test' find_by_filter", || panic!("boom"))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::PluginError);
+        assert_eq!(err.message(), "indexer 'Test' find_by_filter panicked: boom");
+    }
+
+    #[test]
+    fn test_catch_panics_converts_formatted_panic() {
+        let value = 7;
+        let err = catch_panics::<(), _>("store", || panic!("bad value {}", value)).unwrap_err();
+        assert_eq!(err.message(), "store panicked: bad value 7");
+    }
+
+    #[test]
+    fn test_catch_panics_unknown_payload() {
+        let err = catch_panics::<(), _>("store", || std::panic::panic_any(5u8)).unwrap_err();
+        assert_eq!(err.message(), "store panicked: unknown cause");
+    }
+}
@@ -0,0 +1,209 @@
+use crate::collection::{Document, NitriteId};
+use crate::common::{is_reserved_field, Value};
+
+const FNV_64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_64_PRIME: u64 = 0x0100_0000_01b3;
+const FNV_128_OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_128_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+const TAG_NULL: u8 = 0;
+const TAG_BOOL: u8 = 1;
+const TAG_INTEGER: u8 = 2;
+const TAG_END: u8 = 3;
+const TAG_FLOAT: u8 = 4;
+const TAG_STRING: u8 = 5;
+const TAG_NITRITE_ID: u8 = 6;
+const TAG_BYTES: u8 = 7;
+const TAG_DOCUMENT: u8 = 8;
+const TAG_OTHER: u8 = 9;
+const TAG_ARRAY: u8 = 10;
+const TAG_MAP: u8 = 11;
+
+/// Returns the 64-bit FNV-1a hash of the normalized encoding of a value, see
+/// [`encode_value`].
+pub(crate) fn stable_hash_64(value: &Value) -> u64 {
+    let mut bytes = Vec::new();
+    encode_value(value, &mut bytes);
+    bytes.iter().fold(FNV_64_OFFSET, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_64_PRIME)
+    })
+}
+
+/// Returns the 128-bit FNV-1a hash of the normalized content of a document: its fields
+/// but the id and the metadata fields, see [`encode_value`].
+pub(crate) fn content_hash_128(document: &Document) -> u128 {
+    let mut bytes = Vec::new();
+    encode_document(document, &mut bytes, true);
+    bytes.iter().fold(FNV_128_OFFSET, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(FNV_128_PRIME)
+    })
+}
+
+/// Returns the share of a stored document in the content hash of its collection: the
+/// 128-bit FNV-1a hash of its id and content hash. The shares of the documents are
+/// summed, so the hash of a collection does not depend on the order they are read in.
+pub(crate) fn collection_hash_entry(id: &NitriteId, document: &Document) -> u128 {
+    let mut bytes = Vec::with_capacity(24);
+    bytes.extend_from_slice(&id.id_value().to_le_bytes());
+    bytes.extend_from_slice(&content_hash_128(document).to_le_bytes());
+    bytes.iter().fold(FNV_128_OFFSET, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(FNV_128_PRIME)
+    })
+}
+
+/// Encodes a value so that equal values encode alike, whatever their integer type.
+///
+/// Integers are encoded by value, as are floats without a fraction, and a char is
+/// encoded as a one-character string. Null fields of documents are left out, as reading
+/// a missing field gives null. The encoding never changes for a given value, so hashes
+/// of it can be stored.
+pub(crate) fn encode_value(value: &Value, bytes: &mut Vec<u8>) {
+    let integer = match value {
+        Value::I8(v) => Some(i128::from(*v)),
+        Value::U8(v) => Some(i128::from(*v)),
+        Value::I16(v) => Some(i128::from(*v)),
+        Value::U16(v) => Some(i128::from(*v)),
+        Value::I32(v) => Some(i128::from(*v)),
+        Value::U32(v) => Some(i128::from(*v)),
+        Value::I64(v) => Some(i128::from(*v)),
+        Value::U64(v) => Some(i128::from(*v)),
+        Value::I128(v) => Some(*v),
+        Value::U128(v) => i128::try_from(*v).ok(),
+        Value::ISize(v) => i128::try_from(*v).ok(),
+        Value::USize(v) => i128::try_from(*v).ok(),
+        _ => None,
+    };
+    if let Some(integer) = integer {
+        bytes.push(TAG_INTEGER);
+        bytes.extend_from_slice(&integer.to_le_bytes());
+        return;
+    }
+
+    match value {
+        Value::Null => bytes.push(TAG_NULL),
+        Value::Bool(v) => bytes.extend_from_slice(&[TAG_BOOL, u8::from(*v)]),
+        Value::F32(v) => encode_float(f64::from(*v), bytes),
+        Value::F64(v) => encode_float(*v, bytes),
+        Value::Char(v) => encode_str(&v.to_string(), bytes),
+        Value::String(v) => encode_str(v, bytes),
+        Value::NitriteId(id) => {
+            bytes.push(TAG_NITRITE_ID);
+            bytes.extend_from_slice(&id.id_value().to_le_bytes());
+        }
+        Value::Bytes(v) => {
+            bytes.push(TAG_BYTES);
+            bytes.extend_from_slice(&(v.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(v);
+        }
+        Value::Document(document) => encode_document(document, bytes, false),
+        Value::Array(values) => {
+            bytes.push(TAG_ARRAY);
+            for value in values {
+                encode_value(value, bytes);
+            }
+            bytes.push(TAG_END);
+        }
+        Value::Map(entries) => {
+            bytes.push(TAG_MAP);
+            for (key, value) in entries {
+                encode_value(key, bytes);
+                encode_value(value, bytes);
+            }
+            bytes.push(TAG_END);
+        }
+        other => {
+            bytes.push(TAG_OTHER);
+            encode_str(&other.to_string(), bytes);
+        }
+    }
+}
+
+/// Encodes the fields of a document in field order; the id and the metadata fields of a
+/// top level document are left out.
+fn encode_document(document: &Document, bytes: &mut Vec<u8>, top_level: bool) {
+    bytes.push(TAG_DOCUMENT);
+    for (field, value) in document.iter() {
+        if value.is_null() || (top_level && is_reserved_field(&field)) {
+            continue;
+        }
+        encode_str(&field, bytes);
+        encode_value(&value, bytes);
+    }
+    bytes.push(TAG_END);
+}
+
+fn encode_float(value: f64, bytes: &mut Vec<u8>) {
+    if value.fract() == 0.0 && value.abs() < 1e18 {
+        bytes.push(TAG_INTEGER);
+        bytes.extend_from_slice(&(value as i128).to_le_bytes());
+    } else {
+        bytes.push(TAG_FLOAT);
+        bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+}
+
+fn encode_str(value: &str, bytes: &mut Vec<u8>) {
+    bytes.push(TAG_STRING);
+    bytes.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+
+    #[test]
+    fn test_encoding_ignores_integer_types() {
+        assert_eq!(stable_hash_64(&Value::I32(42)), stable_hash_64(&Value::U64(42)));
+        assert_eq!(stable_hash_64(&Value::F64(42.0)), stable_hash_64(&Value::I8(42)));
+        assert_ne!(stable_hash_64(&Value::F64(42.5)), stable_hash_64(&Value::I8(42)));
+        assert_eq!(stable_hash_64(&Value::Char('a')), stable_hash_64(&Value::from("a")));
+        // FNV-1a of the encoding of null, a single zero byte
+        assert_eq!(stable_hash_64(&Value::Null), 0xaf63_bd4c_8601_b7df);
+    }
+
+    #[test]
+    fn test_nested_values_are_delimited() {
+        let nested = doc! { a: { b: 1 }, c: 2 };
+        let flat = doc! { a: { b: 1, c: 2 } };
+        assert_ne!(content_hash_128(&nested), content_hash_128(&flat));
+
+        let arrays = Value::Array(vec![Value::Array(vec![Value::from(1)]), Value::from(2)]);
+        let array = Value::Array(vec![Value::Array(vec![Value::from(1), Value::from(2)])]);
+        assert_ne!(stable_hash_64(&arrays), stable_hash_64(&array));
+    }
+
+    #[test]
+    fn test_content_hash_skips_reserved_and_null_fields() {
+        let mut document = doc! { name: "Ada", age: 36 };
+        let hash = content_hash_128(&document);
+        document.put("_id", NitriteId::new()).unwrap();
+        document.put("_revision", 3).unwrap();
+        document.put("nickname", Value::Null).unwrap();
+        assert_eq!(content_hash_128(&document), hash);
+
+        // only the top level metadata is skipped
+        let embedded = doc! { name: "Ada", age: 36, meta: { _revision: 1 } };
+        assert_ne!(content_hash_128(&embedded), hash);
+    }
+
+    #[test]
+    fn test_collection_hash_entry_covers_the_id() {
+        let first = NitriteId::new();
+        let second = NitriteId::new();
+        let document = doc! { name: "Ada" };
+        assert_eq!(
+            collection_hash_entry(&first, &document),
+            collection_hash_entry(&first, &doc! { name: "Ada", _revision: 2 })
+        );
+        assert_ne!(
+            collection_hash_entry(&first, &document),
+            collection_hash_entry(&second, &document)
+        );
+        assert_ne!(
+            collection_hash_entry(&first, &document),
+            collection_hash_entry(&first, &doc! { name: "Grace" })
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use crate::SCHEDULER;
-use parking_lot::Mutex;
-use std::time::Duration;
-use timer::{Guard, Timer};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use parking_lot::{Condvar, Mutex};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt::{Debug, Formatter};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 /// Spawn an async task on a new thread.
 /// This avoids global thread pool contention that can occur in parallel test runs.
@@ -12,62 +17,459 @@ where
     std::thread::spawn(op);
 }
 
-#[inline]
-pub fn schedule_task<F>(duration: Duration, f: F)
-where
-    F: 'static + FnMut() + Send,
-{
-    SCHEDULER.schedule(duration, f);
+/// Runs the tasks of a [`Scheduler`] on threads owned by the application.
+///
+/// Set one with [`SchedulerConfig::executor`] to run background work on an existing thread
+/// pool. The scheduler then keeps a single timer thread that hands every due task to
+/// `execute`; the executor must eventually run each task it is given, or closing the
+/// database waits for it until the shutdown timeout.
+pub trait TaskExecutor: Send + Sync {
+    /// Runs `task` on any thread.
+    fn execute(&self, task: Box<dyn FnOnce() + Send>);
 }
 
-#[inline]
-pub fn stop_scheduled_tasks() {
-    SCHEDULER.stop();
+/// Configuration of the background task [`Scheduler`] of a database.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::common::SchedulerConfig;
+/// use std::time::Duration;
+///
+/// let db = Nitrite::builder()
+///     .scheduler(
+///         SchedulerConfig::new()
+///             .thread_count(2)
+///             .thread_name_prefix("orders-db")
+///             .shutdown_timeout(Duration::from_secs(10)),
+///     )
+///     .open_or_create(None, None)?;
+/// ```
+#[derive(Clone)]
+pub struct SchedulerConfig {
+    thread_count: usize,
+    thread_name_prefix: String,
+    shutdown_timeout: Duration,
+    executor: Option<Arc<dyn TaskExecutor>>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            thread_count: 1,
+            thread_name_prefix: "nitrite-scheduler".to_string(),
+            shutdown_timeout: Duration::from_secs(5),
+            executor: None,
+        }
+    }
 }
 
-pub(crate) struct Scheduler {
-    timer: Timer,
-    guards: Mutex<Vec<Guard>>,
+impl Debug for SchedulerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchedulerConfig")
+            .field("thread_count", &self.thread_count)
+            .field("thread_name_prefix", &self.thread_name_prefix)
+            .field("shutdown_timeout", &self.shutdown_timeout)
+            .field("executor", &self.executor.is_some())
+            .finish()
+    }
+}
+
+impl SchedulerConfig {
+    /// Creates the default configuration: one thread named `nitrite-scheduler-0` and a
+    /// shutdown timeout of 5s.
+    pub fn new() -> Self {
+        SchedulerConfig::default()
+    }
+
+    /// Sets the number of threads running the tasks (at least 1).
+    pub fn thread_count(mut self, thread_count: usize) -> Self {
+        self.thread_count = thread_count.max(1);
+        self
+    }
+
+    /// Sets the prefix of the thread names, which are suffixed with `-<n>`.
+    pub fn thread_name_prefix(mut self, prefix: &str) -> Self {
+        self.thread_name_prefix = prefix.to_string();
+        self
+    }
+
+    /// Sets how long closing the database waits for the running tasks to finish.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Runs the tasks on `executor` instead of the scheduler's own threads.
+    pub fn executor(mut self, executor: Arc<dyn TaskExecutor>) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// Returns the number of threads running the tasks.
+    pub fn get_thread_count(&self) -> usize {
+        self.thread_count
+    }
+
+    /// Returns the prefix of the thread names.
+    pub fn get_thread_name_prefix(&self) -> &str {
+        &self.thread_name_prefix
+    }
+
+    /// Returns how long closing the database waits for the running tasks.
+    pub fn get_shutdown_timeout(&self) -> Duration {
+        self.shutdown_timeout
+    }
+
+    /// Returns `true` if the tasks run on an external executor.
+    pub fn has_executor(&self) -> bool {
+        self.executor.is_some()
+    }
+}
+
+/// Runs background tasks, once or repeatedly, on a small pool of threads.
+///
+/// Each database owns a scheduler, configured with `NitriteBuilder::scheduler()` and
+/// reachable through `NitriteConfig::scheduler()`. Its threads start with the first task.
+/// Closing the database shuts the scheduler down: repeating tasks are cancelled, the
+/// pending one-off tasks still run, and the close waits for every running task to finish
+/// before the store is closed.
+#[derive(Clone)]
+pub struct Scheduler {
+    inner: Arc<SchedulerInner>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Scheduler {
+    /// Creates a scheduler with the default configuration.
     pub fn new() -> Scheduler {
+        Scheduler::with_config(SchedulerConfig::default())
+    }
+
+    /// Creates a scheduler with the given configuration.
+    pub fn with_config(config: SchedulerConfig) -> Scheduler {
         Scheduler {
-            timer: Timer::new(),
-            // Preallocate with typical capacity to reduce allocations during task scheduling
-            guards: Mutex::from(Vec::with_capacity(16)),
+            inner: Arc::new(SchedulerInner {
+                shared: Arc::new(SchedulerShared {
+                    config,
+                    state: Mutex::new(SchedulerState::default()),
+                    changed: Condvar::new(),
+                }),
+            }),
         }
     }
 
+    /// Returns the configuration of the scheduler.
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.inner.shared.config
+    }
+
+    /// Runs `f` every `duration`, starting one `duration` from now, until the scheduler
+    /// is stopped or shut down.
     #[inline]
     pub fn schedule<F>(&self, duration: Duration, f: F)
     where
         F: 'static + FnMut() + Send,
     {
-        match chrono::Duration::from_std(duration) {
-            Ok(chrono_duration) => {
-                let guard = self.timer.schedule_repeating(chrono_duration, f);
-                self.guards.lock().push(guard);
-            }
-            Err(e) => {
+        // a zero interval would keep a thread spinning
+        let interval = duration.max(Duration::from_millis(1));
+        match Instant::now().checked_add(interval) {
+            Some(due) => self.inner.shared.submit(due, Task::Repeating(interval, Box::new(f))),
+            None => {
                 log::error!(
-                    "Failed to convert duration to chrono::Duration: {}, skipping task scheduling",
-                    e
+                    "Task interval {:?} is out of range, skipping task scheduling",
+                    duration
                 );
             }
         }
     }
 
+    /// Runs `f` once, as soon as a thread is free.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: 'static + FnOnce() + Send,
+    {
+        self.inner.shared.submit(Instant::now(), Task::Once(Box::new(f)));
+    }
+
+    /// Cancels all repeating tasks. One-off tasks still run.
     #[inline]
     pub fn stop(&self) {
-        self.guards.lock().clear();
+        let mut state = self.inner.shared.state.lock();
+        state.cancel_repeating();
+    }
+
+    /// Shuts the scheduler down and waits for its tasks to drain.
+    ///
+    /// Repeating tasks are cancelled, pending one-off tasks run, and the call returns once
+    /// no task is running anymore and the scheduler's threads have exited. Tasks submitted
+    /// afterwards are dropped. Shutting down twice is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Timeout` error if the tasks are still running after the configured
+    /// shutdown timeout.
+    pub fn shutdown(&self) -> NitriteResult<()> {
+        let shared = &self.inner.shared;
+        let deadline = Instant::now() + shared.config.shutdown_timeout;
+
+        let mut state = shared.state.lock();
+        state.shutdown = true;
+        state.cancel_repeating();
+        shared.changed.notify_all();
+
+        while !state.is_idle() {
+            if shared.changed.wait_until(&mut state, deadline).timed_out() && !state.is_idle() {
+                log::error!(
+                    "Scheduler did not drain within {:?}: {} task(s) running, {} pending",
+                    shared.config.shutdown_timeout,
+                    state.running,
+                    state.queue.len()
+                );
+                return Err(NitriteError::new(
+                    "Background tasks did not finish within the shutdown timeout",
+                    ErrorKind::Timeout,
+                ));
+            }
+        }
+        let workers = std::mem::take(&mut state.workers);
+        drop(state);
+
+        let current = thread::current().id();
+        for worker in workers {
+            if worker.thread().id() != current && worker.join().is_err() {
+                log::warn!("Scheduler thread panicked");
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` once the scheduler has been shut down.
+    pub fn is_shutdown(&self) -> bool {
+        self.inner.shared.state.lock().shutdown
+    }
+
+    /// Returns the number of tasks waiting for their next run.
+    pub(crate) fn task_count(&self) -> usize {
+        self.inner.shared.state.lock().queue.len()
+    }
+}
+
+/// Last handle of a scheduler: dropping it lets the threads exit.
+struct SchedulerInner {
+    shared: Arc<SchedulerShared>,
+}
+
+impl Drop for SchedulerInner {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.shutdown = true;
+        state.cancel_repeating();
+        self.shared.changed.notify_all();
+    }
+}
+
+/// State shared between the scheduler handles and its threads.
+struct SchedulerShared {
+    config: SchedulerConfig,
+    state: Mutex<SchedulerState>,
+    changed: Condvar,
+}
+
+impl SchedulerShared {
+    fn submit(self: &Arc<Self>, due: Instant, task: Task) {
+        let mut state = self.state.lock();
+        if state.shutdown {
+            log::warn!("Scheduler is shut down, dropping a background task");
+            return;
+        }
+        if !state.started {
+            state.started = true;
+            self.start_threads(&mut state);
+        }
+
+        let generation = state.generation;
+        state.push(ScheduledTask {
+            due,
+            sequence: 0,
+            generation,
+            task,
+        });
+        self.changed.notify_all();
+    }
+
+    fn start_threads(self: &Arc<Self>, state: &mut SchedulerState) {
+        let thread_count = match self.config.executor {
+            // a single thread hands the due tasks over to the executor
+            Some(_) => 1,
+            None => self.config.thread_count,
+        };
+
+        for n in 0..thread_count {
+            let shared = self.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("{}-{}", self.config.thread_name_prefix, n))
+                .spawn(move || shared.run());
+            match spawned {
+                Ok(handle) => state.workers.push(handle),
+                Err(e) => log::error!("Failed to start scheduler thread: {}", e),
+            }
+        }
+    }
+
+    fn run(self: Arc<Self>) {
+        while let Some(task) = self.next_due() {
+            match &self.config.executor {
+                Some(executor) => {
+                    let shared = self.clone();
+                    executor.execute(Box::new(move || {
+                        let next = task.run();
+                        shared.finish(next);
+                    }));
+                }
+                None => {
+                    let next = task.run();
+                    self.finish(next);
+                }
+            }
+        }
+    }
+
+    /// Blocks until a task is due, or returns `None` once the scheduler is shut down and
+    /// nothing is left to run.
+    fn next_due(&self) -> Option<ScheduledTask> {
+        let mut state = self.state.lock();
+        loop {
+            let due = state.queue.peek().map(|Reverse(task)| task.due);
+            match due {
+                Some(due) if due <= Instant::now() => {
+                    let Reverse(task) = state.queue.pop()?;
+                    state.running += 1;
+                    return Some(task);
+                }
+                Some(due) => {
+                    self.changed.wait_until(&mut state, due);
+                }
+                None if state.shutdown => return None,
+                None => self.changed.wait(&mut state),
+            }
+        }
+    }
+
+    fn finish(&self, next: Option<ScheduledTask>) {
+        let mut state = self.state.lock();
+        state.running -= 1;
+        if let Some(task) = next {
+            // a repeating task is dropped if it was cancelled while it ran
+            if !state.shutdown && task.generation == state.generation {
+                state.push(task);
+            }
+        }
+        self.changed.notify_all();
+    }
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    queue: BinaryHeap<Reverse<ScheduledTask>>,
+    next_sequence: u64,
+    /// Bumped to cancel the repeating tasks that are running.
+    generation: u64,
+    running: usize,
+    workers: Vec<JoinHandle<()>>,
+    started: bool,
+    shutdown: bool,
+}
+
+impl SchedulerState {
+    fn push(&mut self, mut task: ScheduledTask) {
+        task.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.queue.push(Reverse(task));
+    }
+
+    fn cancel_repeating(&mut self) {
+        self.generation += 1;
+        self.queue
+            .retain(|Reverse(task)| matches!(task.task, Task::Once(_)));
+    }
+
+    fn is_idle(&self) -> bool {
+        self.queue.is_empty() && self.running == 0
+    }
+}
+
+enum Task {
+    Once(Box<dyn FnOnce() + Send>),
+    Repeating(Duration, Box<dyn FnMut() + Send>),
+}
+
+/// A task with the time of its next run, ordered by that time and then by submission.
+struct ScheduledTask {
+    due: Instant,
+    sequence: u64,
+    generation: u64,
+    task: Task,
+}
+
+impl ScheduledTask {
+    /// Runs the task and returns its next run, if it repeats.
+    fn run(self) -> Option<ScheduledTask> {
+        match self.task {
+            Task::Once(f) => {
+                guard_panic(f);
+                None
+            }
+            Task::Repeating(interval, mut f) => {
+                guard_panic(&mut f);
+                Some(ScheduledTask {
+                    due: Instant::now() + interval,
+                    sequence: 0,
+                    generation: self.generation,
+                    task: Task::Repeating(interval, f),
+                })
+            }
+        }
+    }
+}
+
+fn guard_panic<F: FnOnce()>(f: F) {
+    if catch_unwind(AssertUnwindSafe(f)).is_err() {
+        log::error!("Background task panicked");
+    }
+}
+
+impl PartialEq for ScheduledTask {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledTask {}
+
+impl PartialOrd for ScheduledTask {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledTask {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.due
+            .cmp(&other.due)
+            .then(self.sequence.cmp(&other.sequence))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::Arc;
     use std::thread;
     use std::time::Duration;
@@ -88,8 +490,6 @@ mod tests {
     #[test]
     #[retry]
     fn test_schedule_task() {
-        // Use local scheduler to avoid interference from other tests
-        // that may call stop_scheduled_tasks() on the global SCHEDULER
         let scheduler = Scheduler::new();
         let flag = Arc::new(AtomicBool::new(false));
         let flag_clone = Arc::clone(&flag);
@@ -103,14 +503,15 @@ mod tests {
     #[test]
     #[retry]
     fn test_stop_scheduled_tasks() {
+        let scheduler = Scheduler::new();
         let flag = Arc::new(AtomicBool::new(false));
         let flag_clone = Arc::clone(&flag);
 
-        schedule_task(Duration::from_millis(100), move || {
+        scheduler.schedule(Duration::from_millis(100), move || {
             flag_clone.store(true, Ordering::Relaxed);
         });
 
-        stop_scheduled_tasks();
+        scheduler.stop();
         thread::sleep(Duration::from_millis(200));
         assert!(!flag.load(Ordering::Relaxed));
     }
@@ -118,7 +519,7 @@ mod tests {
     #[test]
     fn test_scheduler_new() {
         let scheduler = Scheduler::new();
-        assert!(scheduler.guards.lock().is_empty());
+        assert_eq!(scheduler.task_count(), 0);
     }
 
     #[test]
@@ -163,8 +564,8 @@ mod tests {
             flag_clone.store(true, Ordering::Relaxed);
         });
 
-        // Verify guard was added (indicating successful scheduling)
-        assert_eq!(scheduler.guards.lock().len(), 1);
+        // Verify the task was queued (indicating successful scheduling)
+        assert_eq!(scheduler.task_count(), 1);
     }
 
     #[test]
@@ -181,7 +582,7 @@ mod tests {
         });
 
         // Should handle large duration without panicking
-        assert_eq!(scheduler.guards.lock().len(), 1);
+        assert_eq!(scheduler.task_count(), 1);
     }
 
     #[test]
@@ -199,16 +600,122 @@ mod tests {
             flag_clone.store(true, Ordering::Relaxed);
         });
 
-        // Guard should not be added due to conversion failure
-        assert_eq!(scheduler.guards.lock().len(), 0);
+        // The task should not be queued since its first run is out of range
+        assert_eq!(scheduler.task_count(), 0);
+    }
+
+    #[test]
+    fn test_scheduler_thread_config() {
+        let config = SchedulerConfig::new()
+            .thread_count(0)
+            .thread_name_prefix("custom-scheduler");
+        assert_eq!(config.get_thread_count(), 1);
+
+        let scheduler = Scheduler::with_config(config);
+        let name = Arc::new(Mutex::new(None));
+        let name_clone = Arc::clone(&name);
+        scheduler.execute(move || {
+            *name_clone.lock() = thread::current().name().map(String::from);
+        });
+
+        scheduler.shutdown().unwrap();
+        assert_eq!(name.lock().as_deref(), Some("custom-scheduler-0"));
+    }
+
+    #[test]
+    fn test_shutdown_drains_tasks() {
+        let scheduler = Scheduler::with_config(SchedulerConfig::new().thread_count(2));
+        let count = Arc::new(AtomicUsize::new(0));
+        for _ in 0..10 {
+            let count = Arc::clone(&count);
+            scheduler.execute(move || {
+                thread::sleep(Duration::from_millis(10));
+                count.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticks_clone = Arc::clone(&ticks);
+        scheduler.schedule(Duration::from_secs(60), move || {
+            ticks_clone.fetch_add(1, Ordering::SeqCst);
+        });
+
+        scheduler.shutdown().unwrap();
+        // every one-off task ran, the repeating one was cancelled before its first run
+        assert_eq!(count.load(Ordering::SeqCst), 10);
+        assert_eq!(ticks.load(Ordering::SeqCst), 0);
+        assert!(scheduler.is_shutdown());
+
+        // tasks submitted after the shutdown are dropped
+        let flag = Arc::new(AtomicBool::new(false));
+        let flag_clone = Arc::clone(&flag);
+        scheduler.execute(move || flag_clone.store(true, Ordering::SeqCst));
+        assert_eq!(scheduler.task_count(), 0);
+        assert!(scheduler.shutdown().is_ok());
+        assert!(!flag.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_shutdown_timeout() {
+        let config = SchedulerConfig::new().shutdown_timeout(Duration::from_millis(50));
+        let scheduler = Scheduler::with_config(config);
+        scheduler.execute(|| thread::sleep(Duration::from_millis(500)));
+        thread::sleep(Duration::from_millis(20));
+
+        let result = scheduler.shutdown();
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::Timeout);
+    }
+
+    #[test]
+    fn test_panicking_task_does_not_stop_scheduler() {
+        let scheduler = Scheduler::new();
+        scheduler.execute(|| panic!("task failure"));
+        let flag = Arc::new(AtomicBool::new(false));
+        let flag_clone = Arc::clone(&flag);
+        scheduler.execute(move || flag_clone.store(true, Ordering::SeqCst));
+
+        scheduler.shutdown().unwrap();
+        assert!(flag.load(Ordering::SeqCst));
+    }
+
+    struct CountingExecutor {
+        executed: AtomicUsize,
+    }
+
+    impl TaskExecutor for CountingExecutor {
+        fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+            self.executed.fetch_add(1, Ordering::SeqCst);
+            thread::spawn(task);
+        }
+    }
+
+    #[test]
+    fn test_external_executor() {
+        let executor = Arc::new(CountingExecutor {
+            executed: AtomicUsize::new(0),
+        });
+        let scheduler = Scheduler::with_config(SchedulerConfig::new().executor(executor.clone()));
+        assert!(scheduler.config().has_executor());
+
+        let count = Arc::new(AtomicUsize::new(0));
+        for _ in 0..3 {
+            let count = Arc::clone(&count);
+            scheduler.execute(move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        scheduler.shutdown().unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+        assert_eq!(executor.executed.load(Ordering::SeqCst), 3);
     }
 
     #[test]
     fn test_schedule_task_with_zero_duration() {
+        let scheduler = Scheduler::new();
         let flag = Arc::new(AtomicBool::new(false));
         let flag_clone = Arc::clone(&flag);
 
-        schedule_task(Duration::from_millis(0), move || {
+        scheduler.schedule(Duration::from_millis(0), move || {
             flag_clone.store(true, Ordering::Relaxed);
         });
 
@@ -0,0 +1,88 @@
+use crate::common::Value;
+
+/// Formats a value as text: strings and chars as they are, ids as their digits,
+/// documents, maps and arrays as compact JSON.
+pub(crate) fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Char(value) => value.to_string(),
+        Value::NitriteId(id) => id.id_value().to_string(),
+        Value::RawJson(json) => json.to_string(),
+        Value::Document(_) | Value::Map(_) | Value::Array(_) => {
+            let mut json = String::new();
+            write_json(value, &mut json);
+            json
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn write_json(value: &Value, json: &mut String) {
+    match value {
+        Value::Null | Value::Unknown => json.push_str("null"),
+        Value::F32(value) if !value.is_finite() => json.push_str("null"),
+        Value::F64(value) if !value.is_finite() => json.push_str("null"),
+        Value::Char(value) => write_json_string(&value.to_string(), json),
+        Value::String(value) => write_json_string(value, json),
+        Value::NitriteId(id) => write_json_string(&id.id_value().to_string(), json),
+        Value::RawJson(raw) => json.push_str(raw),
+        Value::Bytes(bytes) => {
+            let values = bytes.iter().map(|byte| Value::U8(*byte)).collect();
+            write_json(&Value::Array(values), json)
+        }
+        Value::Array(values) => {
+            json.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_json(value, json);
+            }
+            json.push(']');
+        }
+        Value::Document(document) => {
+            json.push('{');
+            for (index, (key, value)) in document.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_json_string(&key, json);
+                json.push(':');
+                write_json(&value, json);
+            }
+            json.push('}');
+        }
+        Value::Map(entries) => {
+            json.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                match key {
+                    Value::String(key) => write_json_string(key, json),
+                    _ => write_json_string(&value_text(key), json),
+                }
+                json.push(':');
+                write_json(value, json);
+            }
+            json.push('}');
+        }
+        _ => json.push_str(&value.to_string()),
+    }
+}
+
+fn write_json_string(value: &str, json: &mut String) {
+    json.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
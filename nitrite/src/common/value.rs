@@ -1,11 +1,13 @@
 use crate::collection::Document;
 use crate::collection::NitriteId;
+use crate::common::util::{parse_json, validate_json};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use argon2::password_hash::Decimal;
 use std::any::{Any, TypeId};
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
+use std::sync::Arc;
 
 /// Compare two integers represented as u128 for equality.
 /// This handles cross-type comparison by converting to a common type.
@@ -64,6 +66,7 @@ fn num_cmp_float(a: f64, b: f64) -> std::cmp::Ordering {
 /// - NitriteId(NitriteId): Database-generated unique identifier
 /// - Bytes(Vec<u8>): Binary data (not indexable/queryable)
 /// - Unknown: Unrecognized value type
+/// - RawJson(Arc<str>): JSON text stored verbatim (not indexable/queryable)
 ///
 /// # Characteristics
 /// - **Flexible**: Supports any JSON-compatible type plus Nitrite-specific types
@@ -139,6 +142,30 @@ pub enum Value {
     Bytes(Vec<u8>),
     /// Represents an unknown value.
     Unknown,
+    /// Represents a JSON payload stored as its text, without converting it into values.
+    ///
+    /// The text is kept as it is, so storing and reading a large schemaless payload costs
+    /// no more than a string of the same size. It is parsed only on demand, with
+    /// [`Value::materialize`]. Filters do not look into it and indexes see it as a
+    /// missing value; [`Document::materialize`] converts the field into documents and
+    /// arrays that can be queried and indexed. Create it with [`Value::raw_json`], which
+    /// checks that the text is valid JSON.
+    #[serde(with = "raw_json_text")]
+    RawJson(Arc<str>),
+}
+
+/// Serializes the text of a [`Value::RawJson`] as a plain string.
+mod raw_json_text {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::sync::Arc;
+
+    pub(super) fn serialize<S: Serializer>(json: &Arc<str>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(json)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<str>, D::Error> {
+        String::deserialize(deserializer).map(Arc::from)
+    }
 }
 
 /// Type alias for map and document keys.
@@ -205,6 +232,7 @@ impl PartialEq for Value {
             (Value::NitriteId(a), Value::NitriteId(b)) => *a == *b,
             (Value::Bytes(a), Value::Bytes(b)) => *a == *b,
             (Value::Unknown, Value::Unknown) => true,
+            (Value::RawJson(a), Value::RawJson(b)) => *a == *b,
             _ => false,
         }
     }
@@ -271,6 +299,7 @@ impl Ord for Value {
             (Value::NitriteId(a), Value::NitriteId(b)) => a.cmp(b),
             (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
             (Value::Unknown, Value::Unknown) => std::cmp::Ordering::Equal,
+            (Value::RawJson(a), Value::RawJson(b)) => a.cmp(b),
             _ => self.to_string().cmp(&other.to_string()), // fallback to string comparison
         }
     }
@@ -303,6 +332,7 @@ impl Hash for Value {
             Value::NitriteId(v) => v.hash(state),
             Value::Bytes(v) => v.hash(state),
             Value::Unknown => (&"unknown_value").hash(state),
+            Value::RawJson(v) => v.hash(state),
         }
     }
 }
@@ -765,6 +795,49 @@ impl Value {
         }
     }
 
+    /// Returns the JSON text if the [Value] is [Value::RawJson], without parsing it.
+    #[inline]
+    pub fn as_raw_json(&self) -> Option<&str> {
+        match self {
+            Value::RawJson(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Creates a [Value::RawJson] holding `json` verbatim.
+    ///
+    /// The text is checked to be a single valid JSON value, without building any value
+    /// from it.
+    ///
+    /// # Errors
+    /// Returns `ValidationError` if `json` is not valid JSON.
+    ///
+    /// # Examples
+    /// ```text
+    /// let payload = Value::raw_json(r#"{"sensor": "t1", "readings": [21.5, 21.7]}"#)?;
+    /// doc.put("payload", payload)?;
+    /// ```
+    pub fn raw_json(json: impl Into<Arc<str>>) -> NitriteResult<Value> {
+        let json = json.into();
+        validate_json(&json)?;
+        Ok(Value::RawJson(json))
+    }
+
+    /// Parses a [Value::RawJson] into documents, arrays and scalar values. Any other
+    /// value is returned as it is.
+    ///
+    /// JSON objects become documents, whole numbers [Value::I64] (or [Value::U64] above
+    /// `i64::MAX`) and other numbers [Value::F64].
+    ///
+    /// # Errors
+    /// Returns `ValidationError` if the JSON text is not valid.
+    pub fn materialize(&self) -> NitriteResult<Value> {
+        match self {
+            Value::RawJson(json) => parse_json(json),
+            other => Ok(other.clone()),
+        }
+    }
+
     /// Checks if the [Value] is [Value::Null].
     #[inline]
     pub fn is_null(&self) -> bool {
@@ -903,6 +976,12 @@ impl Value {
         matches!(self, Value::Unknown)
     }
 
+    /// Checks if the [Value] is [Value::RawJson].
+    #[inline]
+    pub fn is_raw_json(&self) -> bool {
+        matches!(self, Value::RawJson(_))
+    }
+
     /// Checks if the [Value] is a number type.
     #[inline]
     pub fn is_number(&self) -> bool {
@@ -977,6 +1056,37 @@ impl Value {
         matches!(self, Value::F32(_) | Value::F64(_))
     }
 
+    /// Returns the name of the [Value] variant, e.g. `"I32"` or `"String"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "Null",
+            Value::Bool(_) => "Bool",
+            Value::I8(_) => "I8",
+            Value::U8(_) => "U8",
+            Value::I16(_) => "I16",
+            Value::U16(_) => "U16",
+            Value::I32(_) => "I32",
+            Value::U32(_) => "U32",
+            Value::I64(_) => "I64",
+            Value::U64(_) => "U64",
+            Value::I128(_) => "I128",
+            Value::U128(_) => "U128",
+            Value::ISize(_) => "ISize",
+            Value::USize(_) => "USize",
+            Value::F32(_) => "F32",
+            Value::F64(_) => "F64",
+            Value::Char(_) => "Char",
+            Value::String(_) => "String",
+            Value::Document(_) => "Document",
+            Value::Array(_) => "Array",
+            Value::Map(_) => "Map",
+            Value::NitriteId(_) => "NitriteId",
+            Value::Bytes(_) => "Bytes",
+            Value::Unknown => "Unknown",
+            Value::RawJson(_) => "RawJson",
+        }
+    }
+
     /// Takes the value, replacing it with [Value::Null].
     ///
     /// # Returns
@@ -1073,6 +1183,7 @@ impl Value {
                 json_str
             }
             Value::Unknown => "unknown".to_string(),
+            Value::RawJson(v) => v.to_string(),
         }
     }
 
@@ -1159,6 +1270,7 @@ impl Value {
                 debug_str
             }
             Value::Unknown => "unknown".to_string(),
+            Value::RawJson(v) => format!("raw_json({})", v),
         }
     }
 }
@@ -2462,6 +2574,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_raw_json_value() {
+        let json = r#"{"sensor": "t1", "readings": [21.5, 22]}"#;
+        let value = Value::raw_json(json).unwrap();
+        assert!(value.is_raw_json());
+        assert_eq!(value.as_raw_json(), Some(json));
+        assert_eq!(value.type_name(), "RawJson");
+        assert_eq!(value, Value::raw_json(json.to_string()).unwrap());
+
+        let materialized = value.materialize().unwrap();
+        let document = materialized.as_document().unwrap();
+        assert_eq!(document.get("sensor").unwrap(), Value::from("t1"));
+        assert_eq!(
+            document.get("readings").unwrap(),
+            Value::Array(vec![Value::F64(21.5), Value::I64(22)])
+        );
+        assert_eq!(Value::I32(1).materialize().unwrap(), Value::I32(1));
+
+        let error = Value::raw_json("{\"sensor\":").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+    }
+
     #[test]
     fn bench_value_conversions() {
         for i in 0..500 {
@@ -0,0 +1,547 @@
+//! `TryFrom` conversions from [Value] to Rust types.
+//!
+//! Integers convert from any integer variant whose value fits the target type,
+//! so a field stored as `I64` can be read as `i32` or `u8`. Decimals convert
+//! without loss only: `f64` accepts `F32` and `F64`, `f32` accepts `F32`.
+//! Every other type requires its own variant. Failed conversions return an
+//! `InvalidDataType` error naming the expected type and the variant found.
+
+use crate::collection::{Document, NitriteId};
+use crate::common::Value;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+impl Value {
+    /// Converts the value to `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidDataType` error if the value is of another type or
+    /// does not fit `T`, e.g. `"Expected i32, found String"`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use nitrite::common::Value;
+    ///
+    /// let age = Value::I64(42);
+    /// assert_eq!(age.get_as::<u8>().unwrap(), 42);
+    ///
+    /// let error = Value::from("42").get_as::<i32>().unwrap_err();
+    /// assert_eq!(error.message(), "Expected i32, found String");
+    /// ```
+    pub fn get_as<T>(&self) -> NitriteResult<T>
+    where
+        T: for<'a> TryFrom<&'a Value, Error = NitriteError>,
+    {
+        T::try_from(self)
+    }
+}
+
+fn type_mismatch(expected: &str, value: &Value) -> NitriteError {
+    let message = format!("Expected {}, found {}", expected, value.type_name());
+    log::error!("{}", message);
+    NitriteError::new(&message, ErrorKind::InvalidDataType)
+}
+
+fn out_of_range(expected: &str, value: &Value) -> NitriteError {
+    let message = format!(
+        "Value {} of type {} is out of range for {}",
+        value,
+        value.type_name(),
+        expected
+    );
+    log::error!("{}", message);
+    NitriteError::new(&message, ErrorKind::InvalidDataType)
+}
+
+/// Prefixes the error of a nested value with its position in the container.
+fn nested_error(position: String, error: NitriteError) -> NitriteError {
+    NitriteError::new(
+        &format!("{}: {}", position, error.message()),
+        error.kind().clone(),
+    )
+}
+
+/// Returns `None` if the value is not an integer, `Some(None)` if it does not fit `T`.
+fn integer_as<T>(value: &Value) -> Option<Option<T>>
+where
+    T: TryFrom<i128> + TryFrom<u128>,
+{
+    match value {
+        Value::U128(v) => Some(T::try_from(*v).ok()),
+        _ => value
+            .as_signed_integer()
+            .map(|v| T::try_from(v).ok()),
+    }
+}
+
+macro_rules! impl_try_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<&Value> for $t {
+                type Error = NitriteError;
+
+                fn try_from(value: &Value) -> NitriteResult<Self> {
+                    match integer_as::<$t>(value) {
+                        Some(Some(v)) => Ok(v),
+                        Some(None) => Err(out_of_range(stringify!($t), value)),
+                        None => Err(type_mismatch(stringify!($t), value)),
+                    }
+                }
+            }
+
+            impl TryFrom<Value> for $t {
+                type Error = NitriteError;
+
+                fn try_from(value: Value) -> NitriteResult<Self> {
+                    <$t>::try_from(&value)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_integer!(i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, isize, usize);
+
+macro_rules! impl_try_from_variant {
+    ($($t:ty => $variant:ident),*) => {
+        $(
+            impl TryFrom<&Value> for $t {
+                type Error = NitriteError;
+
+                fn try_from(value: &Value) -> NitriteResult<Self> {
+                    match value {
+                        Value::$variant(v) => Ok(v.clone()),
+                        _ => Err(type_mismatch(stringify!($t), value)),
+                    }
+                }
+            }
+
+            impl TryFrom<Value> for $t {
+                type Error = NitriteError;
+
+                fn try_from(value: Value) -> NitriteResult<Self> {
+                    match value {
+                        Value::$variant(v) => Ok(v),
+                        _ => Err(type_mismatch(stringify!($t), &value)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_variant!(
+    bool => Bool,
+    f32 => F32,
+    char => Char,
+    String => String,
+    Document => Document,
+    NitriteId => NitriteId
+);
+
+impl TryFrom<&Value> for f64 {
+    type Error = NitriteError;
+
+    fn try_from(value: &Value) -> NitriteResult<Self> {
+        value
+            .as_decimal()
+            .ok_or_else(|| type_mismatch("f64", value))
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = NitriteError;
+
+    fn try_from(value: Value) -> NitriteResult<Self> {
+        f64::try_from(&value)
+    }
+}
+
+/// Converts an `Array`, or `Bytes` as an array of `U8` values.
+impl<T> TryFrom<&Value> for Vec<T>
+where
+    T: for<'a> TryFrom<&'a Value, Error = NitriteError>,
+{
+    type Error = NitriteError;
+
+    fn try_from(value: &Value) -> NitriteResult<Self> {
+        match value {
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| T::try_from(v).map_err(|e| nested_error(format!("Element {}", i), e)))
+                .collect(),
+            Value::Bytes(bytes) => bytes
+                .iter()
+                .enumerate()
+                .map(|(i, b)| {
+                    T::try_from(&Value::U8(*b)).map_err(|e| nested_error(format!("Element {}", i), e))
+                })
+                .collect(),
+            _ => Err(type_mismatch("Array", value)),
+        }
+    }
+}
+
+/// Converts an `Array`, or `Bytes` as an array of `U8` values.
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value, Error = NitriteError>,
+{
+    type Error = NitriteError;
+
+    fn try_from(value: Value) -> NitriteResult<Self> {
+        match value {
+            Value::Array(values) => values
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| T::try_from(v).map_err(|e| nested_error(format!("Element {}", i), e)))
+                .collect(),
+            Value::Bytes(bytes) => bytes
+                .into_iter()
+                .enumerate()
+                .map(|(i, b)| {
+                    T::try_from(Value::U8(b)).map_err(|e| nested_error(format!("Element {}", i), e))
+                })
+                .collect(),
+            _ => Err(type_mismatch("Array", &value)),
+        }
+    }
+}
+
+/// Converts a `Map`, or a `Document` whose keys convert from `String` values.
+impl<K, V> TryFrom<&Value> for HashMap<K, V>
+where
+    K: for<'a> TryFrom<&'a Value, Error = NitriteError> + Eq + Hash,
+    V: for<'a> TryFrom<&'a Value, Error = NitriteError>,
+{
+    type Error = NitriteError;
+
+    fn try_from(value: &Value) -> NitriteResult<Self> {
+        match value {
+            Value::Map(entries) => entries
+                .iter()
+                .map(|(k, v)| convert_entry(k, v))
+                .collect(),
+            Value::Document(document) => document
+                .iter()
+                .map(|(k, v)| convert_entry(&Value::String(k), &v))
+                .collect(),
+            _ => Err(type_mismatch("Map or Document", value)),
+        }
+    }
+}
+
+/// Converts a `Map`, or a `Document` whose keys convert from `String` values.
+impl<K, V> TryFrom<Value> for HashMap<K, V>
+where
+    K: for<'a> TryFrom<&'a Value, Error = NitriteError> + Eq + Hash,
+    V: for<'a> TryFrom<&'a Value, Error = NitriteError>,
+{
+    type Error = NitriteError;
+
+    fn try_from(value: Value) -> NitriteResult<Self> {
+        HashMap::try_from(&value)
+    }
+}
+
+fn convert_entry<K, V>(key: &Value, value: &Value) -> NitriteResult<(K, V)>
+where
+    K: for<'a> TryFrom<&'a Value, Error = NitriteError>,
+    V: for<'a> TryFrom<&'a Value, Error = NitriteError>,
+{
+    let k = K::try_from(key).map_err(|e| nested_error(format!("Key {}", key), e))?;
+    let v = V::try_from(value).map_err(|e| nested_error(format!("Entry {}", key), e))?;
+    Ok((k, v))
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversion {
+    //! Date/time values are stored as ISO 8601 strings; `DateTime<Utc>` also
+    //! converts from an integer number of milliseconds since the Unix epoch.
+
+    use super::type_mismatch;
+    use crate::common::Value;
+    use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+    const NAIVE_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+    fn parse_error(expected: &str, text: &str, error: chrono::ParseError) -> NitriteError {
+        let message = format!("Cannot parse '{}' as {}: {}", text, expected, error);
+        log::error!("{}", message);
+        NitriteError::new(&message, ErrorKind::InvalidDataType)
+    }
+
+    fn parse_string<T>(
+        expected: &str,
+        value: &Value,
+        parse: impl FnOnce(&str) -> Result<T, chrono::ParseError>,
+    ) -> NitriteResult<T> {
+        match value {
+            Value::String(text) => parse(text).map_err(|e| parse_error(expected, text, e)),
+            _ => Err(type_mismatch(expected, value)),
+        }
+    }
+
+    impl From<DateTime<Utc>> for Value {
+        fn from(value: DateTime<Utc>) -> Self {
+            Value::String(value.to_rfc3339())
+        }
+    }
+
+    impl From<DateTime<FixedOffset>> for Value {
+        fn from(value: DateTime<FixedOffset>) -> Self {
+            Value::String(value.to_rfc3339())
+        }
+    }
+
+    impl From<NaiveDateTime> for Value {
+        fn from(value: NaiveDateTime) -> Self {
+            Value::String(value.format(NAIVE_DATE_TIME_FORMAT).to_string())
+        }
+    }
+
+    impl From<NaiveDate> for Value {
+        fn from(value: NaiveDate) -> Self {
+            Value::String(value.to_string())
+        }
+    }
+
+    impl From<NaiveTime> for Value {
+        fn from(value: NaiveTime) -> Self {
+            Value::String(value.to_string())
+        }
+    }
+
+    impl TryFrom<&Value> for DateTime<Utc> {
+        type Error = NitriteError;
+
+        fn try_from(value: &Value) -> NitriteResult<Self> {
+            if value.is_integer() {
+                let millis = i64::try_from(value)?;
+                return DateTime::from_timestamp_millis(millis).ok_or_else(|| {
+                    let message = format!("Timestamp {} is out of range for DateTime<Utc>", millis);
+                    log::error!("{}", message);
+                    NitriteError::new(&message, ErrorKind::InvalidDataType)
+                });
+            }
+            parse_string("DateTime<Utc>", value, |text| {
+                DateTime::parse_from_rfc3339(text).map(|dt| dt.with_timezone(&Utc))
+            })
+        }
+    }
+
+    impl TryFrom<&Value> for DateTime<FixedOffset> {
+        type Error = NitriteError;
+
+        fn try_from(value: &Value) -> NitriteResult<Self> {
+            parse_string("DateTime<FixedOffset>", value, DateTime::parse_from_rfc3339)
+        }
+    }
+
+    impl TryFrom<&Value> for NaiveDateTime {
+        type Error = NitriteError;
+
+        fn try_from(value: &Value) -> NitriteResult<Self> {
+            parse_string("NaiveDateTime", value, |text| text.parse())
+        }
+    }
+
+    impl TryFrom<&Value> for NaiveDate {
+        type Error = NitriteError;
+
+        fn try_from(value: &Value) -> NitriteResult<Self> {
+            parse_string("NaiveDate", value, |text| text.parse())
+        }
+    }
+
+    impl TryFrom<&Value> for NaiveTime {
+        type Error = NitriteError;
+
+        fn try_from(value: &Value) -> NitriteResult<Self> {
+            parse_string("NaiveTime", value, |text| text.parse())
+        }
+    }
+
+    macro_rules! impl_try_from_owned {
+        ($($t:ty),*) => {
+            $(
+                impl TryFrom<Value> for $t {
+                    type Error = NitriteError;
+
+                    fn try_from(value: Value) -> NitriteResult<Self> {
+                        <$t>::try_from(&value)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_try_from_owned!(
+        DateTime<Utc>,
+        DateTime<FixedOffset>,
+        NaiveDateTime,
+        NaiveDate,
+        NaiveTime
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_integer_conversion_across_variants() {
+        assert_eq!(i32::try_from(Value::I64(42)).unwrap(), 42);
+        assert_eq!(u8::try_from(&Value::I32(255)).unwrap(), 255);
+        assert_eq!(i64::try_from(Value::U8(7)).unwrap(), 7);
+        assert_eq!(u128::try_from(Value::U128(u128::MAX)).unwrap(), u128::MAX);
+        assert_eq!(i128::try_from(Value::I8(-1)).unwrap(), -1);
+        assert_eq!(usize::try_from(Value::USize(3)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_integer_out_of_range() {
+        let error = u8::try_from(Value::I32(300)).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidDataType);
+        assert_eq!(error.message(), "Value 300 of type I32 is out of range for u8");
+
+        let error = u32::try_from(Value::I64(-1)).unwrap_err();
+        assert_eq!(error.message(), "Value -1 of type I64 is out of range for u32");
+
+        assert!(i128::try_from(Value::U128(u128::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_type_mismatch_names_variant() {
+        let error = i32::try_from(Value::from("42")).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidDataType);
+        assert_eq!(error.message(), "Expected i32, found String");
+
+        let error = String::try_from(&Value::I32(1)).unwrap_err();
+        assert_eq!(error.message(), "Expected String, found I32");
+
+        let error = bool::try_from(Value::Null).unwrap_err();
+        assert_eq!(error.message(), "Expected bool, found Null");
+
+        let error = i32::try_from(Value::F64(1.0)).unwrap_err();
+        assert_eq!(error.message(), "Expected i32, found F64");
+    }
+
+    #[test]
+    fn test_decimal_conversion() {
+        assert_eq!(f64::try_from(Value::F64(1.5)).unwrap(), 1.5);
+        assert_eq!(f64::try_from(Value::F32(0.5)).unwrap(), 0.5);
+        assert_eq!(f32::try_from(Value::F32(0.5)).unwrap(), 0.5);
+
+        let error = f32::try_from(Value::F64(0.5)).unwrap_err();
+        assert_eq!(error.message(), "Expected f32, found F64");
+        let error = f64::try_from(Value::I32(1)).unwrap_err();
+        assert_eq!(error.message(), "Expected f64, found I32");
+    }
+
+    #[test]
+    fn test_variant_conversion() {
+        assert!(bool::try_from(Value::Bool(true)).unwrap());
+        assert_eq!(char::try_from(&Value::Char('x')).unwrap(), 'x');
+        assert_eq!(String::try_from(Value::from("abc")).unwrap(), "abc");
+
+        let id = NitriteId::new();
+        assert_eq!(NitriteId::try_from(Value::from(id)).unwrap(), id);
+
+        let document = doc! { "a": 1 };
+        assert_eq!(Document::try_from(&Value::from(document.clone())).unwrap(), document);
+    }
+
+    #[test]
+    fn test_vec_conversion() {
+        let value = Value::from(vec![1, 2, 3]);
+        assert_eq!(Vec::<i64>::try_from(&value).unwrap(), vec![1, 2, 3]);
+        assert_eq!(Vec::<i32>::try_from(value).unwrap(), vec![1, 2, 3]);
+
+        let bytes = Value::Bytes(vec![1, 2]);
+        assert_eq!(Vec::<u8>::try_from(&bytes).unwrap(), vec![1, 2]);
+        assert_eq!(Vec::<u16>::try_from(bytes).unwrap(), vec![1, 2]);
+
+        let nested = Value::Array(vec![Value::from(vec!["a"]), Value::from(vec!["b", "c"])]);
+        assert_eq!(
+            nested.get_as::<Vec<Vec<String>>>().unwrap(),
+            vec![vec!["a".to_string()], vec!["b".to_string(), "c".to_string()]]
+        );
+
+        let mixed = Value::Array(vec![Value::I32(1), Value::from("two")]);
+        let error = Vec::<i32>::try_from(mixed).unwrap_err();
+        assert_eq!(error.message(), "Element 1: Expected i32, found String");
+
+        let error = Vec::<i32>::try_from(Value::I32(1)).unwrap_err();
+        assert_eq!(error.message(), "Expected Array, found I32");
+    }
+
+    #[test]
+    fn test_hash_map_conversion() {
+        let mut entries = BTreeMap::new();
+        entries.insert(Value::I32(1), Value::from("one"));
+        entries.insert(Value::I32(2), Value::from("two"));
+        let map: HashMap<i64, String> = Value::Map(entries).try_into().unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&2], "two");
+
+        let document = Value::from(doc! { "a": 1, "b": 2 });
+        let map = document.get_as::<HashMap<String, u8>>().unwrap();
+        assert_eq!(map["a"], 1);
+        assert_eq!(map["b"], 2);
+
+        let document = Value::from(doc! { "a": 1, "b": "x" });
+        let error = HashMap::<String, i32>::try_from(document).unwrap_err();
+        assert_eq!(error.message(), "Entry \"b\": Expected i32, found String");
+
+        let error = HashMap::<String, i32>::try_from(Value::Null).unwrap_err();
+        assert_eq!(error.message(), "Expected Map or Document, found Null");
+    }
+
+    #[test]
+    fn test_get_as() {
+        let value = Value::I64(42);
+        assert_eq!(value.get_as::<i32>().unwrap(), 42);
+        assert_eq!(value.get_as::<u64>().unwrap(), 42);
+        assert_eq!(value.get_as::<String>().unwrap_err().message(), "Expected String, found I64");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversion() {
+        use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+        let instant = Utc.with_ymd_and_hms(2024, 5, 17, 10, 30, 0).unwrap();
+        let value = Value::from(instant);
+        assert_eq!(value.get_as::<DateTime<Utc>>().unwrap(), instant);
+        assert_eq!(
+            value.get_as::<DateTime<FixedOffset>>().unwrap(),
+            instant.fixed_offset()
+        );
+        let millis = Value::I64(instant.timestamp_millis());
+        assert_eq!(DateTime::<Utc>::try_from(millis).unwrap(), instant);
+
+        let date = NaiveDate::from_ymd_opt(2024, 5, 17).unwrap();
+        assert_eq!(NaiveDate::try_from(Value::from(date)).unwrap(), date);
+
+        let time = NaiveTime::from_hms_milli_opt(10, 30, 0, 250).unwrap();
+        assert_eq!(NaiveTime::try_from(Value::from(time)).unwrap(), time);
+
+        let date_time = date.and_time(time);
+        assert_eq!(NaiveDateTime::try_from(Value::from(date_time)).unwrap(), date_time);
+
+        let error = NaiveDate::try_from(Value::from("17/05/2024")).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::InvalidDataType);
+        assert!(error.message().starts_with("Cannot parse '17/05/2024' as NaiveDate"));
+
+        let error = NaiveDate::try_from(Value::I32(1)).unwrap_err();
+        assert_eq!(error.message(), "Expected NaiveDate, found I32");
+    }
+}
@@ -0,0 +1,428 @@
+//! Database configuration loaded from a TOML or YAML file and the environment.
+//!
+//! A [`ConfigFile`] holds the settings a deployment may want to tune without recompiling:
+//! the field separator, schema version, sort memory budget and node id, the store backend
+//! with its path, cache size and durability, and which optional modules (spatial,
+//! full-text, ...) are enabled. It is applied with
+//! [`NitriteBuilder::from_config_file`](crate::nitrite_builder::NitriteBuilder::from_config_file).
+//!
+//! ```toml
+//! field_separator = "."
+//! schema_version = 2
+//!
+//! [store]
+//! backend = "fjall"
+//! path = "/var/lib/orders/db"
+//! cache_size = 134217728
+//! durability = "on_commit"
+//! fsync_frequency = 500
+//!
+//! [modules]
+//! spatial = true
+//! fts = false
+//! ```
+//!
+//! The store backend and the modules are not known to this crate. Each name used in the
+//! file must be registered on the builder with
+//! [`module_factory`](crate::nitrite_builder::NitriteBuilder::module_factory), which
+//! creates the module from the loaded configuration. The `memory` backend is built in.
+//!
+//! Environment variables override the file:
+//!
+//! - `NITRITE_FIELD_SEPARATOR`, `NITRITE_METADATA_PREFIX`, `NITRITE_SCHEMA_VERSION`,
+//!   `NITRITE_SORT_MEMORY_BUDGET` and `NITRITE_NODE_ID` set the top-level settings
+//! - `NITRITE_STORE_<SETTING>` sets a store setting, e.g. `NITRITE_STORE_PATH`
+//! - `NITRITE_MODULES_<NAME>` enables (`true`) or disables (`false`) a module
+//!
+//! This module requires the `config` feature.
+
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Name of the built-in in-memory store backend.
+pub const MEMORY_BACKEND: &str = "memory";
+
+/// Prefix of the environment variables that override the configuration.
+const ENV_PREFIX: &str = "NITRITE_";
+
+/// Settings of a database, read from a configuration file and the environment.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::config_file::ConfigFile;
+///
+/// let config = ConfigFile::load("nitrite.yaml")?;
+/// println!("opening {:?}", config.store().path());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    field_separator: Option<String>,
+    metadata_prefix: Option<String>,
+    schema_version: Option<u32>,
+    sort_memory_budget: Option<u64>,
+    node_id: Option<u16>,
+    store: StoreSettings,
+    modules: BTreeMap<String, bool>,
+}
+
+impl ConfigFile {
+    /// Reads a configuration file and applies the environment overrides.
+    ///
+    /// The format is chosen by the extension: `.toml`, or `.yaml` / `.yml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, has another extension, is not valid
+    /// for its format, or an environment override has an invalid value.
+    pub fn load<P: AsRef<Path>>(path: P) -> NitriteResult<ConfigFile> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            log::error!("Failed to read configuration file {}: {}", path.display(), err);
+            NitriteError::from(err)
+        })?;
+
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let mut config = match extension.as_deref() {
+            Some("toml") => ConfigFile::from_toml(&content)?,
+            Some("yaml") | Some("yml") => ConfigFile::from_yaml(&content)?,
+            _ => {
+                log::error!("Unsupported configuration file {}, expected .toml, .yaml or .yml", path.display());
+                return Err(NitriteError::new(
+                    &format!("Unsupported configuration file {}, expected .toml, .yaml or .yml", path.display()),
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+        };
+        config.apply_env(std::env::vars())?;
+        Ok(config)
+    }
+
+    /// Creates a configuration from the environment variables only.
+    pub fn from_env() -> NitriteResult<ConfigFile> {
+        let mut config = ConfigFile::default();
+        config.apply_env(std::env::vars())?;
+        Ok(config)
+    }
+
+    /// Parses a configuration in TOML format, without environment overrides.
+    pub fn from_toml(content: &str) -> NitriteResult<ConfigFile> {
+        toml::from_str(content).map_err(|err| invalid_config(err.to_string()))
+    }
+
+    /// Parses a configuration in YAML format, without environment overrides.
+    pub fn from_yaml(content: &str) -> NitriteResult<ConfigFile> {
+        serde_norway::from_str::<Option<ConfigFile>>(content)
+            .map(|config| config.unwrap_or_default())
+            .map_err(|err| invalid_config(err.to_string()))
+    }
+
+    /// Returns the separator of nested field names, if configured.
+    pub fn field_separator(&self) -> Option<&str> {
+        self.field_separator.as_deref()
+    }
+
+    /// Returns the prefix of the document metadata fields, if configured.
+    pub fn metadata_prefix(&self) -> Option<&str> {
+        self.metadata_prefix.as_deref()
+    }
+
+    /// Returns the schema version of the database, if configured.
+    pub fn schema_version(&self) -> Option<u32> {
+        self.schema_version
+    }
+
+    /// Returns the memory budget of blocking sorts in bytes, if configured.
+    pub fn sort_memory_budget(&self) -> Option<u64> {
+        self.sort_memory_budget
+    }
+
+    /// Returns the node id of the generated ids, if configured.
+    pub fn node_id(&self) -> Option<u16> {
+        self.node_id
+    }
+
+    /// Returns the store settings.
+    pub fn store(&self) -> &StoreSettings {
+        &self.store
+    }
+
+    /// Returns the names of the enabled modules, in name order.
+    pub fn enabled_modules(&self) -> Vec<&str> {
+        self.modules
+            .iter()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    fn apply_env<I: IntoIterator<Item = (String, String)>>(&mut self, vars: I) -> NitriteResult<()> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            if let Some(setting) = key.strip_prefix("STORE_") {
+                self.store.set(&setting.to_ascii_lowercase(), &value, &name)?;
+            } else if let Some(module) = key.strip_prefix("MODULES_") {
+                let enabled = value.parse().map_err(|_| invalid_env(&name, &value))?;
+                self.modules.insert(module.to_ascii_lowercase(), enabled);
+            } else {
+                match key {
+                    "FIELD_SEPARATOR" => self.field_separator = Some(value),
+                    "METADATA_PREFIX" => self.metadata_prefix = Some(value),
+                    "SCHEMA_VERSION" => {
+                        self.schema_version = Some(value.parse().map_err(|_| invalid_env(&name, &value))?)
+                    }
+                    "SORT_MEMORY_BUDGET" => {
+                        self.sort_memory_budget = Some(value.parse().map_err(|_| invalid_env(&name, &value))?)
+                    }
+                    "NODE_ID" => {
+                        self.node_id = Some(value.parse().map_err(|_| invalid_env(&name, &value))?)
+                    }
+                    // other tools may share the prefix
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Settings of the store backend.
+///
+/// The common settings have their own accessors; backend specific settings, such as
+/// `fsync_frequency` of the fjall backend, are read with [`option`](StoreSettings::option).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StoreSettings {
+    backend: Option<String>,
+    path: Option<String>,
+    cache_size: Option<u64>,
+    durability: Option<String>,
+    #[serde(flatten)]
+    options: BTreeMap<String, SettingValue>,
+}
+
+impl StoreSettings {
+    /// Returns the name of the store backend, `memory` by default.
+    pub fn backend(&self) -> &str {
+        self.backend.as_deref().unwrap_or(MEMORY_BACKEND)
+    }
+
+    /// Returns the path of the database files, if configured.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Returns the size of the store cache in bytes, if configured.
+    pub fn cache_size(&self) -> Option<u64> {
+        self.cache_size
+    }
+
+    /// Returns the durability of the commits, if configured. The accepted values depend
+    /// on the backend.
+    pub fn durability(&self) -> Option<&str> {
+        self.durability.as_deref()
+    }
+
+    /// Returns a backend specific setting.
+    pub fn option(&self, name: &str) -> Option<&SettingValue> {
+        self.options.get(name)
+    }
+
+    /// Returns all backend specific settings, in name order.
+    pub fn options(&self) -> &BTreeMap<String, SettingValue> {
+        &self.options
+    }
+
+    fn set(&mut self, setting: &str, value: &str, variable: &str) -> NitriteResult<()> {
+        match setting {
+            "backend" => self.backend = Some(value.to_string()),
+            "path" => self.path = Some(value.to_string()),
+            "cache_size" => {
+                self.cache_size = Some(value.parse().map_err(|_| invalid_env(variable, value))?)
+            }
+            "durability" => self.durability = Some(value.to_string()),
+            _ => {
+                self.options.insert(setting.to_string(), SettingValue::parse(value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value of a backend specific store setting.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum SettingValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl SettingValue {
+    /// Returns the value if it is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            SettingValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a non-negative integer.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            SettingValue::Integer(value) => u64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            SettingValue::Integer(value) => Some(*value as f64),
+            SettingValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SettingValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Reads an environment value as a boolean or number if it looks like one.
+    fn parse(value: &str) -> SettingValue {
+        if let Ok(value) = value.parse() {
+            SettingValue::Bool(value)
+        } else if let Ok(value) = value.parse() {
+            SettingValue::Integer(value)
+        } else if let Ok(value) = value.parse() {
+            SettingValue::Float(value)
+        } else {
+            SettingValue::String(value.to_string())
+        }
+    }
+}
+
+fn invalid_config(message: String) -> NitriteError {
+    log::error!("Invalid configuration: {}", message);
+    NitriteError::new(
+        &format!("Invalid configuration: {}", message),
+        ErrorKind::ValidationError,
+    )
+}
+
+fn invalid_env(variable: &str, value: &str) -> NitriteError {
+    log::error!("Invalid value '{}' of environment variable {}", value, variable);
+    NitriteError::new(
+        &format!("Invalid value '{}' of environment variable {}", value, variable),
+        ErrorKind::ValidationError,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = ConfigFile::from_toml(
+            r#"
+            field_separator = ":"
+            schema_version = 3
+            node_id = 7
+
+            [store]
+            backend = "fjall"
+            path = "/tmp/db"
+            cache_size = 1024
+            durability = "on_commit"
+            fsync_frequency = 500
+
+            [modules]
+            spatial = true
+            fts = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.field_separator(), Some(":"));
+        assert_eq!(config.schema_version(), Some(3));
+        assert_eq!(config.sort_memory_budget(), None);
+        assert_eq!(config.node_id(), Some(7));
+        assert_eq!(config.store().backend(), "fjall");
+        assert_eq!(config.store().path(), Some("/tmp/db"));
+        assert_eq!(config.store().cache_size(), Some(1024));
+        assert_eq!(config.store().durability(), Some("on_commit"));
+        assert_eq!(config.store().option("fsync_frequency"), Some(&SettingValue::Integer(500)));
+        assert_eq!(config.enabled_modules(), vec!["spatial"]);
+    }
+
+    #[test]
+    fn test_from_yaml() {
+        let config = ConfigFile::from_yaml(
+            "store:\n  backend: fjall\n  path: /tmp/db\n  kv_separated: true\nmodules:\n  fts: true\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.store().backend(), "fjall");
+        assert_eq!(config.store().option("kv_separated"), Some(&SettingValue::Bool(true)));
+        assert_eq!(config.enabled_modules(), vec!["fts"]);
+        assert_eq!(ConfigFile::from_yaml("").unwrap(), ConfigFile::default());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let err = ConfigFile::from_toml("unknown_setting = 1").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ValidationError);
+
+        let err = ConfigFile::from_yaml("schema_version: two").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ValidationError);
+    }
+
+    #[test]
+    fn test_apply_env() {
+        let mut config = ConfigFile::from_toml("[store]\npath = \"/tmp/db\"\n[modules]\nfts = true").unwrap();
+        config
+            .apply_env(vars(&[
+                ("NITRITE_STORE_PATH", "/data/db"),
+                ("NITRITE_STORE_BLOCK_SIZE", "4096"),
+                ("NITRITE_MODULES_FTS", "false"),
+                ("NITRITE_MODULES_SPATIAL", "true"),
+                ("NITRITE_SCHEMA_VERSION", "2"),
+                ("NITRITE_NODE_ID", "12"),
+                ("NITRITE_UNRELATED", "x"),
+                ("PATH", "/usr/bin"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.store().path(), Some("/data/db"));
+        assert_eq!(config.store().option("block_size"), Some(&SettingValue::Integer(4096)));
+        assert_eq!(config.enabled_modules(), vec!["spatial"]);
+        assert_eq!(config.schema_version(), Some(2));
+        assert_eq!(config.node_id(), Some(12));
+
+        let err = config
+            .apply_env(vars(&[("NITRITE_SCHEMA_VERSION", "two")]))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ValidationError);
+    }
+}
@@ -0,0 +1,158 @@
+use super::csv_error;
+use crate::collection::{Document, NitriteCollection};
+use crate::common::{value_text, Value};
+use crate::errors::NitriteResult;
+use crate::filter::{all, Filter};
+use csv::{Writer, WriterBuilder};
+use indexmap::IndexSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Writes the documents of a collection as CSV rows.
+///
+/// Each column is a field, which may be an embedded field such as `address.city`. Without
+/// explicit columns they are the leaf fields of the exported documents in the order they
+/// are first seen, found in a first pass over the documents; the documents are then read
+/// again by id, so a document removed in between is skipped.
+///
+/// Missing and null fields are written as empty cells, strings as they are, ids as their
+/// digits, and arrays, maps and documents stored whole as compact JSON.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::csv::CsvExporter;
+///
+/// let rows = CsvExporter::new(&orders)
+///     .filter(field("total").gt(100))
+///     .columns(["customer.name", "total"])
+///     .export_to_file("orders.csv")?;
+/// ```
+pub struct CsvExporter {
+    collection: NitriteCollection,
+    filter: Option<Filter>,
+    columns: Option<Vec<String>>,
+    delimiter: u8,
+    headers: bool,
+}
+
+impl CsvExporter {
+    /// Creates an exporter for all the documents of `collection`.
+    pub fn new(collection: &NitriteCollection) -> Self {
+        CsvExporter {
+            collection: collection.clone(),
+            filter: None,
+            columns: None,
+            delimiter: b',',
+            headers: true,
+        }
+    }
+
+    /// Restricts the export to the documents matching `filter`.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Sets the exported fields, in column order.
+    pub fn columns<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the field delimiter (default `,`).
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether a header row with the field names is written (default `true`).
+    pub fn headers(mut self, headers: bool) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Exports to a new file at `path`, replacing any existing file.
+    pub fn export_to_file(&self, path: impl AsRef<Path>) -> NitriteResult<u64> {
+        let file = File::create(path)?;
+        self.export_to(BufWriter::new(file))
+    }
+
+    /// Exports to `writer` and returns the number of rows written, without the header.
+    pub fn export_to<W: Write>(&self, writer: W) -> NitriteResult<u64> {
+        let mut writer = WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer);
+
+        let rows = match &self.columns {
+            Some(columns) => {
+                self.write_header(&mut writer, columns)?;
+                let mut rows = 0;
+                for document in self.collection.find(self.find_filter())? {
+                    write_row(&mut writer, &document?, columns)?;
+                    rows += 1;
+                }
+                rows
+            }
+            None => {
+                let mut columns = IndexSet::new();
+                let mut ids = Vec::new();
+                for document in self.collection.find(self.find_filter())? {
+                    let mut document = document?;
+                    columns.extend(document.fields());
+                    ids.push(document.id()?);
+                }
+
+                let columns: Vec<String> = columns.into_iter().collect();
+                self.write_header(&mut writer, &columns)?;
+                let mut rows = 0;
+                for id in &ids {
+                    if let Some(document) = self.collection.get_by_id(id)? {
+                        write_row(&mut writer, &document, &columns)?;
+                        rows += 1;
+                    }
+                }
+                rows
+            }
+        };
+
+        writer.flush()?;
+        log::info!(
+            "Exported {} document(s) of {} to CSV",
+            rows,
+            self.collection.name()
+        );
+        Ok(rows)
+    }
+
+    fn find_filter(&self) -> Filter {
+        self.filter.clone().unwrap_or_else(all)
+    }
+
+    fn write_header<W: Write>(&self, writer: &mut Writer<W>, columns: &[String]) -> NitriteResult<()> {
+        if self.headers {
+            writer.write_record(columns).map_err(csv_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_row<W: Write>(
+    writer: &mut Writer<W>,
+    document: &Document,
+    columns: &[String],
+) -> NitriteResult<()> {
+    for column in columns {
+        let cell = match document.get(column)? {
+            Value::Null | Value::Unknown => String::new(),
+            value => value_text(&value),
+        };
+        writer.write_field(cell).map_err(csv_error)?;
+    }
+    writer.write_record(None::<&[u8]>).map_err(csv_error)
+}
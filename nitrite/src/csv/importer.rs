@@ -0,0 +1,276 @@
+use super::csv_error;
+use crate::collection::{Document, NitriteCollection};
+use crate::common::{is_reserved_field, Value};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use csv::{ReaderBuilder, StringRecord};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The number of documents inserted at once when none is configured.
+pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 1000;
+
+/// The type a CSV cell is stored as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvType {
+    /// A 64-bit signed integer.
+    I64,
+    /// A 64-bit float; integers are accepted too.
+    F64,
+    /// `true`/`false` in any case, or `1`/`0`.
+    Bool,
+    /// The cell as it is.
+    String,
+}
+
+impl CsvType {
+    /// Infers the type of a cell: a boolean, an integer, a finite float, or else a string.
+    /// Numbers with a leading zero, such as `02134`, stay strings so that codes keep
+    /// their digits.
+    pub fn infer(cell: &str) -> CsvType {
+        if cell.eq_ignore_ascii_case("true") || cell.eq_ignore_ascii_case("false") {
+            return CsvType::Bool;
+        }
+
+        let digits = cell.strip_prefix(['-', '+']).unwrap_or(cell);
+        let leading_zero =
+            digits.len() > 1 && digits.starts_with('0') && digits.as_bytes()[1].is_ascii_digit();
+        if leading_zero {
+            CsvType::String
+        } else if cell.parse::<i64>().is_ok() {
+            CsvType::I64
+        } else if cell.parse::<f64>().is_ok_and(f64::is_finite) {
+            CsvType::F64
+        } else {
+            CsvType::String
+        }
+    }
+
+    fn parse(&self, cell: &str) -> Option<Value> {
+        match self {
+            CsvType::I64 => cell.trim().parse::<i64>().ok().map(Value::I64),
+            CsvType::F64 => cell.trim().parse::<f64>().ok().map(Value::F64),
+            CsvType::Bool => match cell.trim() {
+                "1" => Some(Value::Bool(true)),
+                "0" => Some(Value::Bool(false)),
+                cell if cell.eq_ignore_ascii_case("true") => Some(Value::Bool(true)),
+                cell if cell.eq_ignore_ascii_case("false") => Some(Value::Bool(false)),
+                _ => None,
+            },
+            CsvType::String => Some(Value::String(cell.to_string())),
+        }
+    }
+}
+
+/// Inserts the rows of a CSV file as documents of a collection.
+///
+/// Every row becomes one document, with a field per column named after the header, or
+/// after the name it is mapped to. A dotted field such as `address.city` is stored in an
+/// embedded document. Empty cells leave the field out, and columns of reserved fields such
+/// as `_id` are ignored, as the documents get new ids.
+///
+/// A cell is stored with the [`CsvType`] hint of its field if there is one, otherwise
+/// with the type inferred from the cell alone, or as a string when inference is turned
+/// off. Rows are read one at a time and inserted in batches; an error stops the import,
+/// leaving the batches inserted before it in place.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::csv::{CsvImporter, CsvType};
+///
+/// let imported = CsvImporter::new(&customers)
+///     .delimiter(b';')
+///     .map_header("Customer Name", "name")
+///     .map_header("City", "address.city")
+///     .column_type("zip", CsvType::String)
+///     .import_from_file("customers.csv")?;
+/// ```
+pub struct CsvImporter {
+    collection: NitriteCollection,
+    delimiter: u8,
+    has_header_row: bool,
+    headers: Option<Vec<String>>,
+    header_mapping: HashMap<String, String>,
+    column_types: HashMap<String, CsvType>,
+    infer_types: bool,
+    batch_size: usize,
+}
+
+impl CsvImporter {
+    /// Creates an importer that inserts into `collection`.
+    pub fn new(collection: &NitriteCollection) -> Self {
+        CsvImporter {
+            collection: collection.clone(),
+            delimiter: b',',
+            has_header_row: true,
+            headers: None,
+            header_mapping: HashMap::new(),
+            column_types: HashMap::new(),
+            infer_types: true,
+            batch_size: DEFAULT_IMPORT_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the field delimiter (default `,`).
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets whether the first row holds the headers (default `true`).
+    pub fn has_header_row(mut self, has_header_row: bool) -> Self {
+        self.has_header_row = has_header_row;
+        self
+    }
+
+    /// Sets the headers of the columns, replacing the header row if there is one.
+    pub fn headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.headers = Some(headers.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Stores the column with this header in `field` instead of a field named after it.
+    pub fn map_header(mut self, header: &str, field: &str) -> Self {
+        self.header_mapping
+            .insert(header.to_string(), field.to_string());
+        self
+    }
+
+    /// Stores the cells of `field` as `column_type`.
+    pub fn column_type(mut self, field: &str, column_type: CsvType) -> Self {
+        self.column_types.insert(field.to_string(), column_type);
+        self
+    }
+
+    /// Sets whether the type of cells without a hint is inferred (default `true`);
+    /// otherwise they are stored as strings.
+    pub fn infer_types(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+
+    /// Sets the number of documents inserted at once
+    /// (default [`DEFAULT_IMPORT_BATCH_SIZE`]).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Imports from the CSV file at `path`.
+    pub fn import_from_file(&self, path: impl AsRef<Path>) -> NitriteResult<u64> {
+        let file = File::open(path)?;
+        self.import_from(file)
+    }
+
+    /// Imports the rows read from `reader` and returns the number of documents inserted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EncodingError` if the data is not valid CSV or a row does not have as
+    /// many cells as there are headers, and an `InvalidDataType` error if a cell does not
+    /// match the type hint of its field.
+    pub fn import_from<R: Read>(&self, reader: R) -> NitriteResult<u64> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.has_header_row)
+            .flexible(true)
+            .from_reader(reader);
+
+        let headers = match &self.headers {
+            Some(headers) => headers.clone(),
+            None if self.has_header_row => reader
+                .headers()
+                .map_err(csv_error)?
+                .iter()
+                .map(String::from)
+                .collect(),
+            None => {
+                log::error!("CSV headers are required when there is no header row");
+                return Err(NitriteError::new(
+                    "CSV headers are required when there is no header row",
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+        };
+        let fields: Vec<String> = headers
+            .into_iter()
+            .map(|header| self.header_mapping.get(&header).cloned().unwrap_or(header))
+            .collect();
+
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut imported = 0;
+        let mut record = StringRecord::new();
+        while reader.read_record(&mut record).map_err(csv_error)? {
+            if record.len() != fields.len() {
+                let line = record.position().map_or(0, |position| position.line());
+                log::error!("Invalid CSV data: line {} has {} cells", line, record.len());
+                return Err(NitriteError::new(
+                    &format!(
+                        "Invalid CSV data: line {} has {} cells, expected {}",
+                        line,
+                        record.len(),
+                        fields.len()
+                    ),
+                    ErrorKind::EncodingError,
+                ));
+            }
+
+            batch.push(self.to_document(&record, &fields)?);
+            if batch.len() >= self.batch_size {
+                imported += self.insert(&mut batch)?;
+            }
+        }
+        imported += self.insert(&mut batch)?;
+
+        log::info!(
+            "Imported {} document(s) into {} from CSV",
+            imported,
+            self.collection.name()
+        );
+        Ok(imported)
+    }
+
+    fn to_document(&self, record: &StringRecord, fields: &[String]) -> NitriteResult<Document> {
+        let mut document = Document::new();
+        for (field, cell) in fields.iter().zip(record.iter()) {
+            if cell.is_empty() || field.is_empty() || is_reserved_field(field) {
+                continue;
+            }
+
+            let column_type = match self.column_types.get(field) {
+                Some(column_type) => *column_type,
+                None if self.infer_types => CsvType::infer(cell),
+                None => CsvType::String,
+            };
+            let value = column_type.parse(cell).ok_or_else(|| {
+                let line = record.position().map_or(0, |position| position.line());
+                log::error!("Invalid {:?} value in field {} on line {}", column_type, field, line);
+                NitriteError::new(
+                    &format!(
+                        "Invalid {:?} value {:?} in field {} on line {}",
+                        column_type, cell, field, line
+                    ),
+                    ErrorKind::InvalidDataType,
+                )
+            })?;
+            document.put(field.as_str(), value)?;
+        }
+        Ok(document)
+    }
+
+    fn insert(&self, batch: &mut Vec<Document>) -> NitriteResult<u64> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        let documents = std::mem::replace(batch, Vec::with_capacity(self.batch_size));
+        let count = documents.len() as u64;
+        self.collection.insert_many(documents)?;
+        Ok(count)
+    }
+}
@@ -0,0 +1,181 @@
+//! CSV import and export of collections.
+//!
+//! - [`CsvExporter`] writes the documents matching a filter as rows; embedded fields
+//!   become dotted column headers such as `address.city`.
+//! - [`CsvImporter`] inserts one document per row, building embedded documents from
+//!   dotted headers and typing every cell from a [`CsvType`] hint or by inference.
+//!
+//! Both read and write one row at a time and insert in batches, so files larger than
+//! memory can be processed.
+//!
+//! This module requires the `csv` feature.
+
+mod exporter;
+mod importer;
+
+pub use exporter::*;
+pub use importer::*;
+
+use crate::errors::{ErrorKind, NitriteError};
+
+pub(crate) fn csv_error(err: csv::Error) -> NitriteError {
+    if err.is_io_error() {
+        if let csv::ErrorKind::Io(err) = err.into_kind() {
+            return NitriteError::from(err);
+        }
+        unreachable!("is_io_error checked the kind");
+    }
+
+    log::error!("Invalid CSV data: {}", err);
+    NitriteError::new(
+        &format!("Invalid CSV data: {}", err),
+        ErrorKind::EncodingError,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Value;
+    use crate::doc;
+    use crate::filter::{all, field};
+    use crate::nitrite::Nitrite;
+    use crate::PersistentCollection;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    fn export(exporter: CsvExporter) -> String {
+        let mut buffer = Vec::new();
+        exporter.export_to(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_export_infers_dotted_columns() {
+        let db = setup_nitrite();
+        let users = db.collection("users").unwrap();
+        users
+            .insert(doc! { name: "Ada", age: 36, address: { city: "London" }, tags: ["a", "b"] })
+            .unwrap();
+        users.insert(doc! { name: "Grace, B.", active: true }).unwrap();
+
+        let csv = export(CsvExporter::new(&users));
+        assert_eq!(
+            csv,
+            "address.city,age,name,tags,active\n\
+             London,36,Ada,\"[\"\"a\"\",\"\"b\"\"]\",\n\
+             ,,\"Grace, B.\",,true\n"
+        );
+    }
+
+    #[test]
+    fn test_export_selected_columns() {
+        let db = setup_nitrite();
+        let users = db.collection("users").unwrap();
+        users.insert(doc! { name: "Ada", age: 36 }).unwrap();
+        users.insert(doc! { name: "Linus", age: 17 }).unwrap();
+
+        let csv = export(
+            CsvExporter::new(&users)
+                .filter(field("age").gt(18))
+                .columns(["age", "name"])
+                .delimiter(b';')
+                .headers(false),
+        );
+        assert_eq!(csv, "36;Ada\n");
+    }
+
+    #[test]
+    fn test_import_infers_types() {
+        let db = setup_nitrite();
+        let users = db.collection("users").unwrap();
+        let data = "name,age,score,active,zip,address.city\n\
+                    Ada,36,1.5,true,02134,London\n\
+                    Grace,,2,FALSE,,\n";
+        let imported = CsvImporter::new(&users).import_from(data.as_bytes()).unwrap();
+        assert_eq!(imported, 2);
+
+        let ada = users.find(field("name").eq("Ada")).unwrap().next().unwrap().unwrap();
+        assert_eq!(ada.get("age").unwrap(), Value::I64(36));
+        assert_eq!(ada.get("score").unwrap(), Value::F64(1.5));
+        assert_eq!(ada.get("active").unwrap(), Value::Bool(true));
+        assert_eq!(ada.get("zip").unwrap(), Value::from("02134"));
+        assert_eq!(ada.get("address.city").unwrap(), Value::from("London"));
+
+        let grace = users.find(field("name").eq("Grace")).unwrap().next().unwrap().unwrap();
+        assert!(!grace.contains_key("age"));
+        assert_eq!(grace.get("score").unwrap(), Value::I64(2));
+        assert_eq!(grace.get("active").unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_import_with_hints_and_header_mapping() {
+        let db = setup_nitrite();
+        let users = db.collection("users").unwrap();
+        let data = "Full Name|Age|Zip\nAda|36|2134\n";
+        let imported = CsvImporter::new(&users)
+            .delimiter(b'|')
+            .map_header("Full Name", "name.full")
+            .map_header("Age", "age")
+            .map_header("Zip", "zip")
+            .column_type("age", CsvType::F64)
+            .column_type("zip", CsvType::String)
+            .import_from(data.as_bytes())
+            .unwrap();
+        assert_eq!(imported, 1);
+
+        let ada = users.find(all()).unwrap().next().unwrap().unwrap();
+        assert_eq!(ada.get("name.full").unwrap(), Value::from("Ada"));
+        assert_eq!(ada.get("age").unwrap(), Value::F64(36.0));
+        assert_eq!(ada.get("zip").unwrap(), Value::from("2134"));
+    }
+
+    #[test]
+    fn test_import_without_header_row() {
+        let db = setup_nitrite();
+        let users = db.collection("users").unwrap();
+        let imported = CsvImporter::new(&users)
+            .headers(["name", "age"])
+            .has_header_row(false)
+            .batch_size(1)
+            .import_from("Ada,36\nGrace,45\n".as_bytes())
+            .unwrap();
+        assert_eq!(imported, 2);
+        assert_eq!(users.size().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_cells() {
+        let db = setup_nitrite();
+        let users = db.collection("users").unwrap();
+        let result = CsvImporter::new(&users)
+            .column_type("age", CsvType::I64)
+            .import_from("name,age\nAda,36\nGrace,old\n".as_bytes());
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidDataType);
+        assert!(err.message().contains("line 3"), "{}", err.message());
+
+        let result = CsvImporter::new(&users).import_from("name,age\nAda\n".as_bytes());
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::EncodingError);
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let db = setup_nitrite();
+        let source = db.collection("source").unwrap();
+        source
+            .insert(doc! { name: "Ada", age: 36, score: 1.5, address: { city: "London", zip: "02134" } })
+            .unwrap();
+
+        let csv = export(CsvExporter::new(&source));
+        let target = db.collection("target").unwrap();
+        CsvImporter::new(&target).import_from(csv.as_bytes()).unwrap();
+
+        let ada = target.find(all()).unwrap().next().unwrap().unwrap();
+        assert_eq!(ada.get("age").unwrap(), Value::I64(36));
+        assert_eq!(ada.get("score").unwrap(), Value::F64(1.5));
+        assert_eq!(ada.get("address.zip").unwrap(), Value::from("02134"));
+    }
+}
@@ -0,0 +1,21 @@
+//! A summary of the state of an open database, see
+//! [`Nitrite::describe`](crate::nitrite::Nitrite::describe).
+
+use crate::collection::ListenerHealth;
+
+/// A summary of the state of an open database, from
+/// [`Nitrite::describe`](crate::nitrite::Nitrite::describe).
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseDescription {
+    /// The names of the collections, sorted.
+    pub collections: Vec<String>,
+    /// The listeners subscribed to the open collections, by collection and name.
+    pub listeners: Vec<ListenerHealth>,
+}
+
+impl DatabaseDescription {
+    /// Returns the listeners that failed to handle an event or lost one to a full queue.
+    pub fn unhealthy_listeners(&self) -> impl Iterator<Item = &ListenerHealth> {
+        self.listeners.iter().filter(|listener| !listener.is_healthy())
+    }
+}
@@ -81,6 +81,10 @@ pub enum ErrorKind {
     // Constraint Violation Errors - actively used in index uniqueness checks
     /// A unique constraint was violated
     UniqueConstraintViolation,
+    /// A write would take a collection over its quota
+    QuotaExceeded,
+    /// A document references a missing document, or a removed document is still referenced
+    ReferenceViolation,
     
     // Validation Errors - actively used in field/data validation
     /// Generic validation error
@@ -119,10 +123,26 @@ pub enum ErrorKind {
     /// transaction conflicted with it (serializable-snapshot-isolation conflict).
     /// The operation may be retried.
     TransactionConflict,
+    /// An operation gave up waiting for a lock or resource held by a concurrent
+    /// operation. The operation may be retried.
+    Timeout,
+    /// A query ran past the timeout set in its find options. Unlike [`ErrorKind::Timeout`]
+    /// it is not retryable: running the same query again takes as long.
+    QueryTimedOut,
+    /// A query was cancelled through its cancellation token.
+    Cancelled,
+    /// A query needed more memory for its documents than the limit set in its find options.
+    MemoryLimitExceeded,
 
     // Migration Errors - actively used in migration operations
     /// Error during schema migration
     MigrationError,
+    /// The fields of an entity changed since its repository was created; holds the names
+    /// of the added, removed and retyped fields. A migration is required.
+    SchemaChanged(Vec<String>),
+    /// A document was written by an older version of a versioned type that has no
+    /// upgrade hook; holds the version of the document and the current version of the type.
+    DocumentTooOld { version: u32, current: u32 },
     
     // Extension Errors - allows external crates to plug in their own error types
     // The String contains the extension name/category (e.g., "spatial", "fulltext")
@@ -159,6 +179,8 @@ impl Display for ErrorKind {
             ErrorKind::ObjectMappingError => write!(f, "Object mapping error"),
             ErrorKind::SecurityError => write!(f, "Security error"),
             ErrorKind::UniqueConstraintViolation => write!(f, "Unique constraint violation"),
+            ErrorKind::QuotaExceeded => write!(f, "Quota exceeded"),
+            ErrorKind::ReferenceViolation => write!(f, "Reference violation"),
             ErrorKind::ValidationError => write!(f, "Validation error"),
             ErrorKind::InvalidDataType => write!(f, "Invalid data type"),
             ErrorKind::InvalidFieldName => write!(f, "Invalid field name"),
@@ -172,7 +194,19 @@ impl Display for ErrorKind {
             ErrorKind::StoreNotInitialized => write!(f, "Store not initialized"),
             ErrorKind::StoreAlreadyClosed => write!(f, "Store already closed"),
             ErrorKind::TransactionConflict => write!(f, "Transaction conflict"),
+            ErrorKind::Timeout => write!(f, "Timeout"),
+            ErrorKind::QueryTimedOut => write!(f, "Query timed out"),
+            ErrorKind::Cancelled => write!(f, "Cancelled"),
+            ErrorKind::MemoryLimitExceeded => write!(f, "Memory limit exceeded"),
             ErrorKind::MigrationError => write!(f, "Migration error"),
+            ErrorKind::SchemaChanged(fields) => write!(f, "Schema changed ({})", fields.join(", ")),
+            ErrorKind::DocumentTooOld { version, current } => {
+                write!(
+                    f,
+                    "Document too old (version {}, current {})",
+                    version, current
+                )
+            }
             ErrorKind::Extension(name) => write!(f, "{} error", name),
             ErrorKind::InternalError => write!(f, "Internal error"),
         }
@@ -262,6 +296,16 @@ impl NitriteError {
     pub fn cause(&self) -> Option<&NitriteError> {
         self.cause.as_deref()
     }
+
+    /// Returns `true` if this error, or any error in its cause chain, is a
+    /// [`ErrorKind::TransactionConflict`] or [`ErrorKind::Timeout`], i.e. the failed
+    /// operation may succeed if it is retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.error_kind,
+            ErrorKind::TransactionConflict | ErrorKind::Timeout
+        ) || self.cause().is_some_and(NitriteError::is_retryable)
+    }
 }
 
 impl Display for NitriteError {
@@ -624,6 +668,21 @@ mod tests {
         assert_eq!(closed.kind(), &ErrorKind::StoreAlreadyClosed);
     }
 
+    #[test]
+    fn test_is_retryable() {
+        assert!(NitriteError::new("Conflict", ErrorKind::TransactionConflict).is_retryable());
+        assert!(NitriteError::new("Timed out", ErrorKind::Timeout).is_retryable());
+        assert!(!NitriteError::new("Invalid", ErrorKind::InvalidOperation).is_retryable());
+        assert!(!NitriteError::new("Query timed out", ErrorKind::QueryTimedOut).is_retryable());
+
+        let wrapped = NitriteError::new_with_cause(
+            "Commit failed",
+            ErrorKind::InvalidOperation,
+            NitriteError::new("Conflict", ErrorKind::TransactionConflict),
+        );
+        assert!(wrapped.is_retryable());
+    }
+
     // Test Internal and Unknown Errors
     #[test]
     fn test_internal_errors() {
@@ -3,7 +3,7 @@ use crate::collection::NitriteId;
 use crate::errors::ErrorKind;
 use crate::errors::NitriteError;
 use crate::errors::NitriteResult;
-use crate::index::IndexMap;
+use crate::index::{IndexHint, IndexMap};
 use crate::Value;
 use crate::DOC_ID;
 use std::any::Any;
@@ -16,7 +16,10 @@ use super::AndFilter;
 use super::BetweenFilter;
 use super::ElementMatchFilter;
 use super::EqualsFilter;
+use super::IdRangeFilter;
+use super::InFilter;
 use super::NotFilter;
+use super::TextAnyFilter;
 use super::OrFilter;
 use super::TextFilter;
 
@@ -240,6 +243,8 @@ pub trait FilterProvider: Any + Send + Sync + Display {
 #[derive(Clone)]
 pub struct Filter {
     inner: Arc<dyn FilterProvider>,
+    hint: Option<IndexHint>,
+    plan_shape: Option<u64>,
 }
 
 impl Filter {
@@ -253,7 +258,43 @@ impl Filter {
     ///
     /// A new `Filter` instance wrapping the provider
     pub fn new<T: FilterProvider + 'static>(inner: T) -> Self {
-        Filter { inner: Arc::new(inner) }
+        Filter { inner: Arc::new(inner), hint: None, plan_shape: None }
+    }
+
+    /// Forces the query planner to use a specific index, or none, for this filter.
+    ///
+    /// The hint applies to the query this filter is part of. It must name an existing
+    /// index that can serve the filter, or be [`IndexHint::CollectionScan`]; otherwise
+    /// `find()` fails. A hint on a filter combined with `and()` still applies, but all
+    /// hints of a query must agree.
+    ///
+    /// # Arguments
+    ///
+    /// * `hint` - The field name(s) of the index to use, or an [`IndexHint`]
+    ///
+    /// # Returns
+    ///
+    /// This filter carrying the hint
+    pub fn with_hint(mut self, hint: impl Into<IndexHint>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Returns the index hint set with [`Filter::with_hint`], if any.
+    pub fn hint(&self) -> Option<&IndexHint> {
+        self.hint.as_ref()
+    }
+
+    /// Marks this filter as bound from a [`PreparedFilter`](super::PreparedFilter) of the
+    /// given shape, so the query planner can reuse its index choice for that shape.
+    pub(crate) fn with_plan_shape(mut self, shape: u64) -> Self {
+        self.plan_shape = Some(shape);
+        self
+    }
+
+    /// Returns the shape of the prepared filter this filter was bound from, if any.
+    pub(crate) fn plan_shape(&self) -> Option<u64> {
+        self.plan_shape
     }
 
     /// Combines this filter with another using logical AND.
@@ -337,6 +378,25 @@ pub fn by_id(id: NitriteId) -> Filter {
     ))
 }
 
+/// Creates a filter that matches the documents whose ID lies between `start` and `end`,
+/// both inclusive.
+///
+/// IDs grow with their creation time, so together with `NitriteId::from_time_range()` this
+/// selects the documents created in a time range. The query is answered by a range scan of
+/// the collection, without any index.
+///
+/// # Arguments
+///
+/// * `start` - The smallest matching `NitriteId`
+/// * `end` - The largest matching `NitriteId`
+///
+/// # Returns
+///
+/// A `Filter` that matches the documents with an ID in the range
+pub fn by_id_range(start: NitriteId, end: NitriteId) -> Filter {
+    Filter::new(IdRangeFilter::new(start, end))
+}
+
 /// Combines multiple filters using logical AND.
 ///
 /// Creates a filter that matches documents satisfying all of the provided filters.
@@ -382,6 +442,40 @@ pub fn not(filter: Filter) -> Filter {
     Filter::new(NotFilter::new(filter))
 }
 
+/// Creates a filter that matches documents containing every word of `terms` in any of
+/// their string fields.
+///
+/// The text is split into words like the text of a full-text index, and words are compared
+/// case-insensitively, so `text_any("ada london")` matches a document with the name
+/// `"Ada Lovelace"` and the city `"London"`. It is meant for "search everything" boxes;
+/// the documents are always scanned, so for large collections prefer `field(..).text(..)`
+/// on a full-text index.
+///
+/// # Arguments
+///
+/// * `terms` - The words to search for
+///
+/// # Returns
+///
+/// A `Filter` matching documents containing all the words
+pub fn text_any(terms: &str) -> Filter {
+    Filter::new(TextAnyFilter::new(terms, false))
+}
+
+/// Creates a filter like [`text_any`] that only searches the fields the collection has
+/// an index on.
+///
+/// # Arguments
+///
+/// * `terms` - The words to search for
+///
+/// # Returns
+///
+/// A `Filter` matching documents containing all the words in their indexed fields
+pub fn text_any_indexed(terms: &str) -> Filter {
+    Filter::new(TextAnyFilter::new(terms, true))
+}
+
 /// Internal filter for optimized index scans.
 ///
 /// This struct groups multiple filters for coordinated index-accelerated query execution.
@@ -427,6 +521,10 @@ pub(crate) fn is_or_filter(filter: &Filter) -> bool {
     filter.as_any().is::<OrFilter>()
 }
 
+pub(crate) fn is_not_filter(filter: &Filter) -> bool {
+    filter.as_any().is::<NotFilter>()
+}
+
 pub(crate) fn is_text_filter(filter: &Filter) -> bool {
     filter.as_any().is::<TextFilter>()
 }
@@ -438,10 +536,18 @@ pub(crate) fn is_between_filter(filter: &Filter) -> bool {
     filter.as_any().is::<BetweenFilter>()
 }
 
+pub(crate) fn is_id_range_filter(filter: &Filter) -> bool {
+    filter.as_any().is::<IdRangeFilter>()
+}
+
 pub(crate) fn is_equals_filter(filter: &Filter) -> bool {
     filter.as_any().is::<EqualsFilter>()
 }
 
+pub(crate) fn is_in_filter(filter: &Filter) -> bool {
+    filter.as_any().is::<InFilter>()
+}
+
 pub(crate) fn is_element_match_filter(filter: &Filter) -> bool {
     filter.as_any().is::<ElementMatchFilter>()
 }
@@ -1,3 +1,4 @@
+use crate::index::IndexHint;
 use crate::Value;
 
 use super::{
@@ -24,6 +25,15 @@ use super::{
 pub fn field(field_name: &str) -> FluentFilter {
     FluentFilter {
         field_name: field_name.to_string(),
+        hint: None,
+    }
+}
+
+#[inline]
+fn hinted(hint: Option<IndexHint>, filter: Filter) -> Filter {
+    match hint {
+        Some(hint) => filter.with_hint(hint),
+        None => filter,
     }
 }
 
@@ -43,9 +53,23 @@ pub fn field(field_name: &str) -> FluentFilter {
 /// * **Range Filtering**: Provides between filtering for value ranges
 pub struct FluentFilter {
     field_name: String,
+    hint: Option<IndexHint>,
 }
 
 impl FluentFilter {
+    /// Sets an index hint carried by the filter built next.
+    ///
+    /// `field("age").with_hint("age").gt(30)` is the same as
+    /// `field("age").gt(30).with_hint("age")`; see [`Filter::with_hint`].
+    ///
+    /// # Arguments
+    ///
+    /// * `hint` - The field name(s) of the index to use, or an [`IndexHint`]
+    pub fn with_hint(mut self, hint: impl Into<IndexHint>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
     /// Creates a filter that matches documents where the field equals the specified value.
     ///
     /// # Arguments
@@ -57,7 +81,7 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field equals the value
     #[inline]
     pub fn eq<T: Into<Value>>(self, value: T) -> Filter {
-        Filter::new(EqualsFilter::new(self.field_name, value.into()))
+        hinted(self.hint, Filter::new(EqualsFilter::new(self.field_name, value.into())))
     }
 
     /// Creates a filter that matches documents where the field does not equal the specified value.
@@ -71,7 +95,7 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field differs from the value
     #[inline]
     pub fn ne<T: Into<Value>>(self, value: T) -> Filter {
-        Filter::new(NotEqualsFilter::new(self.field_name, value.into()))
+        hinted(self.hint, Filter::new(NotEqualsFilter::new(self.field_name, value.into())))
     }
 
     /// Creates a filter that matches documents where the field is greater than the specified value.
@@ -85,11 +109,11 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field is greater than the value
     #[inline]
     pub fn gt<T: Into<Value>>(self, value: T) -> Filter {
-        Filter::new(SortingAwareFilter::new(
+        hinted(self.hint, Filter::new(SortingAwareFilter::new(
             self.field_name,
             value.into(),
             ComparisonMode::Greater,
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field is greater than or equal to the specified value.
@@ -103,11 +127,11 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field is greater than or equal to the value
     #[inline]
     pub fn gte<T: Into<Value>>(self, value: T) -> Filter {
-        Filter::new(SortingAwareFilter::new(
+        hinted(self.hint, Filter::new(SortingAwareFilter::new(
             self.field_name,
             value.into(),
             ComparisonMode::GreaterEqual,
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field is less than the specified value.
@@ -121,11 +145,11 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field is less than the value
     #[inline]
     pub fn lt<T: Into<Value>>(self, value: T) -> Filter {
-        Filter::new(SortingAwareFilter::new(
+        hinted(self.hint, Filter::new(SortingAwareFilter::new(
             self.field_name,
             value.into(),
             ComparisonMode::Lesser,
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field is less than or equal to the specified value.
@@ -139,11 +163,11 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field is less than or equal to the value
     #[inline]
     pub fn lte<T: Into<Value>>(self, value: T) -> Filter {
-        Filter::new(SortingAwareFilter::new(
+        hinted(self.hint, Filter::new(SortingAwareFilter::new(
             self.field_name,
             value.into(),
             ComparisonMode::LesserEqual,
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field value is within a range (both bounds inclusive).
@@ -161,10 +185,10 @@ impl FluentFilter {
         lower_bound: T,
         upper_bound: T,
     ) -> Filter {
-        Filter::new(BetweenFilter::new(
+        hinted(self.hint, Filter::new(BetweenFilter::new(
             self.field_name,
             Bound::inclusive(lower_bound.into(), upper_bound.into()),
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field value is within a range with configurable inclusivity.
@@ -184,10 +208,10 @@ impl FluentFilter {
         upper_bound: T,
         inclusive: bool,
     ) -> Filter {
-        Filter::new(BetweenFilter::new(
+        hinted(self.hint, Filter::new(BetweenFilter::new(
             self.field_name,
             Bound::optional_inclusive(lower_bound.into(), upper_bound.into(), inclusive),
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field value is within a range with independent bound inclusivity.
@@ -209,7 +233,7 @@ impl FluentFilter {
         lower_inclusive: bool,
         upper_inclusive: bool,
     ) -> Filter {
-        Filter::new(BetweenFilter::new(
+        hinted(self.hint, Filter::new(BetweenFilter::new(
             self.field_name,
             Bound::new(
                 lower_bound.into(),
@@ -217,7 +241,7 @@ impl FluentFilter {
                 lower_inclusive,
                 upper_inclusive,
             ),
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents containing the specified text in the field (case-sensitive).
@@ -233,7 +257,7 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field contains the specified text
     #[inline]
     pub fn text(self, value: &str) -> Filter {
-        Filter::new(TextFilter::new(self.field_name, value.to_string(), true))
+        hinted(self.hint, Filter::new(TextFilter::new(self.field_name, value.to_string(), true)))
     }
 
     /// Creates a filter that matches documents containing the specified text in the field (case-insensitive).
@@ -250,7 +274,7 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field contains the specified text (case-insensitive)
     #[inline]
     pub fn text_case_insensitive(self, value: &str) -> Filter {
-        Filter::new(TextFilter::new(self.field_name, value.to_string(), false))
+        hinted(self.hint, Filter::new(TextFilter::new(self.field_name, value.to_string(), false)))
     }
 
     /// Creates a filter that matches documents where the field matches the specified regex pattern.
@@ -264,7 +288,7 @@ impl FluentFilter {
     /// A `Filter` matching documents where the field matches the regex pattern
     #[inline]
     pub fn text_regex(self, value: &str) -> Filter {
-        Filter::new(RegexFilter::new(self.field_name, value.to_string()))
+        hinted(self.hint, Filter::new(RegexFilter::new(self.field_name, value.to_string())))
     }
 
     /// Creates a filter that matches documents where the field value is in the specified array.
@@ -279,10 +303,10 @@ impl FluentFilter {
     ///
     /// A `Filter` matching documents where the field is in the specified values
     pub fn in_array<T: Into<Value>>(self, values: Vec<T>) -> Filter {
-        Filter::new(InFilter::new(
+        hinted(self.hint, Filter::new(InFilter::new(
             self.field_name,
             values.into_iter().map(|v| v.into()).collect(),
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where the field value is not in the specified array.
@@ -297,10 +321,10 @@ impl FluentFilter {
     ///
     /// A `Filter` matching documents where the field is not in the specified values
     pub fn not_in_array<T: Into<Value>>(self, values: Vec<T>) -> Filter {
-        Filter::new(NotInFilter::new(
+        hinted(self.hint, Filter::new(NotInFilter::new(
             self.field_name,
             values.into_iter().map(|v| v.into()).collect(),
-        ))
+        )))
     }
 
     /// Creates a filter that matches documents where at least one array element matches the specified filter.
@@ -317,7 +341,7 @@ impl FluentFilter {
     /// A `Filter` matching documents where at least one element in the array satisfies the filter
     #[inline]
     pub fn elem_match(self, filter: Filter) -> Filter {
-        Filter::new(ElementMatchFilter::new(self.field_name, filter))
+        hinted(self.hint, Filter::new(ElementMatchFilter::new(self.field_name, filter)))
     }
 }
 
@@ -11,6 +11,7 @@
 //! - `field("name").eq("Alice")` - equality checks
 //! - `all()` - match all documents
 //! - `by_id(id)` - match by document ID
+//! - `by_id_range(start, end)` - match document IDs in a range, e.g. a creation time window
 //! - `field("name").and(field("age").gt(30))` - logical AND
 //!
 //! # Examples
@@ -39,10 +40,23 @@
 //!
 //! - **Equality**: `eq`, `ne`
 //! - **Comparison**: `gt`, `gte`, `lt`, `lte`
-//! - **Pattern**: `regex`, `text`
+//! - **Pattern**: `regex`, `text`, `text_any` (all string fields)
 //! - **Array**: `in`, `nin`, `elemMatch`
 //! - **Logical**: `and`, `or`, `not`
 //! - **Special**: `all` (match all), `by_id` (match by ID)
+//!
+//! # Prepared Filters
+//!
+//! A filter run many times with different values can be prepared once with `param`
+//! placeholders and bound per execution; the query planner then reuses its index choice
+//! for every binding:
+//!
+//! ```rust,ignore
+//! use nitrite::filter::{field, param, Params, PreparedFilter};
+//!
+//! let older_than = PreparedFilter::new(field("age").gt(param("min")))?;
+//! let results = collection.find(older_than.bind(&Params::new().set("min", 30))?)?;
+//! ```
 
 #[allow(clippy::module_inception)]
 mod filter;
@@ -51,12 +65,16 @@ mod fluent;
 // New modular filter implementations
 mod basic_filters;
 mod logical_filters;
+mod normalizer;
 mod range_filters;
 mod pattern_filters;
+mod prepared;
 
 pub use basic_filters::*;
 pub use filter::*;
 pub use fluent::*;
 pub use logical_filters::*;
+pub(crate) use normalizer::normalize;
 pub use pattern_filters::*;
+pub use prepared::*;
 pub use range_filters::*;
\ No newline at end of file
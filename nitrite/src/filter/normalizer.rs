@@ -0,0 +1,396 @@
+//! Filter normalization run by the query planner before it picks indexes.
+//!
+//! The rewrites keep the documents a filter matches unchanged:
+//!
+//! - nested `and`s and `or`s are flattened into one operand list,
+//! - `not(not(a))` becomes `a`,
+//! - `not` is pushed through `and` / `or` by De Morgan's laws, so `not(a || b)` is
+//!   planned as `not(a) && not(b)`,
+//! - `not(x != v)` and `not(x not in [..])` become `x == v` and `x in [..]`, which an
+//!   index can answer; the negation is kept as a check on the documents found, because
+//!   `==` and `in` also match array fields containing the value,
+//! - bounds on the same field of an `and` are merged into the tightest range, so
+//!   `x > 3 && x > 5 && x < 10` becomes `x between 5 and 10` (exclusive).
+//!
+//! Other negations, e.g. `not(x == v)` or `not(x > v)`, are left as they are: the
+//! complementary filters also fail on documents the index does not hold (missing or
+//! null fields, empty arrays), so they are only safe on a full scan.
+
+use std::cmp::Ordering;
+
+use crate::{errors::NitriteResult, Value};
+
+use super::{
+    is_and_filter, is_between_filter, is_not_filter, is_or_filter, AndFilter, BetweenFilter,
+    Bound, ComparisonMode, EqualsFilter, Filter, InFilter, NotEqualsFilter, NotFilter,
+    NotInFilter, OrFilter, SortingAwareFilter,
+};
+
+/// Returns a filter matching the same documents as `filter`, in the form that lets the
+/// query planner use the most indexes.
+pub(crate) fn normalize(filter: &Filter) -> NitriteResult<Filter> {
+    if is_and_filter(filter) {
+        let mut operands = Vec::new();
+        for operand in filter.logical_filters()? {
+            operands.push(normalize(&operand)?);
+        }
+        conjunction(operands)
+    } else if is_or_filter(filter) {
+        let mut operands = Vec::new();
+        for operand in filter.logical_filters()? {
+            operands.push(normalize(&operand)?);
+        }
+        disjunction(operands)
+    } else if is_not_filter(filter) {
+        match filter.logical_filters()?.into_iter().next() {
+            Some(negated) => negate(&negated, filter),
+            None => Ok(filter.clone()),
+        }
+    } else {
+        Ok(filter.clone())
+    }
+}
+
+/// Normalizes `not(filter)`, where `original` is that negation as written.
+fn negate(filter: &Filter, original: &Filter) -> NitriteResult<Filter> {
+    if is_not_filter(filter) {
+        // not(not(a)) == a
+        return match filter.logical_filters()?.into_iter().next() {
+            Some(inner) => normalize(&inner),
+            None => Ok(original.clone()),
+        };
+    }
+
+    if is_and_filter(filter) || is_or_filter(filter) {
+        let mut operands = Vec::new();
+        for operand in filter.logical_filters()? {
+            operands.push(negate(&operand, &operand.not())?);
+        }
+        return if is_and_filter(filter) {
+            disjunction(operands)
+        } else {
+            conjunction(operands)
+        };
+    }
+
+    let complement = if filter.as_any().is::<NotEqualsFilter>() {
+        let value = filter.get_field_value()?.unwrap_or(Value::Null);
+        if is_index_key(&value) {
+            Some(Filter::new(EqualsFilter::new(filter.get_field_name()?, value)))
+        } else {
+            None
+        }
+    } else if filter.as_any().is::<NotInFilter>() {
+        match filter.get_field_value()? {
+            Some(Value::Array(values)) if values.iter().all(is_index_key) => {
+                Some(Filter::new(InFilter::new(filter.get_field_name()?, values)))
+            }
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let negation = Filter::new(NotFilter::new(filter.clone()));
+    match complement {
+        Some(complement) => {
+            if let Ok(collection_name) = filter.get_collection_name() {
+                complement.set_collection_name(collection_name.clone())?;
+                negation.set_collection_name(collection_name)?;
+            }
+            Ok(Filter::new(AndFilter::new(vec![complement, negation])))
+        }
+        None if is_not_filter(original) => Ok(original.clone()),
+        None => Ok(negation),
+    }
+}
+
+/// Returns `true` if an index holds the documents whose field is exactly `value`.
+fn is_index_key(value: &Value) -> bool {
+    value.is_null() || (value.is_comparable() && !matches!(value, Value::Array(_)))
+}
+
+/// Builds the `and` of normalized operands, flattening nested `and`s and merging bounds.
+fn conjunction(operands: Vec<Filter>) -> NitriteResult<Filter> {
+    let mut flattened = Vec::with_capacity(operands.len());
+    for operand in operands {
+        if is_and_filter(&operand) || is_between_filter(&operand) {
+            flattened.extend(operand.logical_filters()?);
+        } else {
+            flattened.push(operand);
+        }
+    }
+
+    let mut merged = merge_ranges(flattened)?;
+    if merged.len() == 1 {
+        return Ok(merged.remove(0));
+    }
+    Ok(Filter::new(AndFilter::new(merged)))
+}
+
+/// Builds the `or` of normalized operands, flattening nested `or`s.
+fn disjunction(operands: Vec<Filter>) -> NitriteResult<Filter> {
+    let mut flattened = Vec::with_capacity(operands.len());
+    for operand in operands {
+        if is_or_filter(&operand) {
+            flattened.extend(operand.logical_filters()?);
+        } else {
+            flattened.push(operand);
+        }
+    }
+
+    if flattened.len() == 1 {
+        return Ok(flattened.remove(0));
+    }
+    Ok(Filter::new(OrFilter::new(flattened)))
+}
+
+/// The tightest lower and upper bound found for a field of an `and`.
+struct FieldRange {
+    field_name: String,
+    position: usize,
+    lower: Option<(Value, bool, Filter)>,
+    upper: Option<(Value, bool, Filter)>,
+}
+
+/// Replaces the `>`, `>=`, `<` and `<=` filters on each field of an `and` with the
+/// tightest lower and upper bound, as a `between` if there are both.
+///
+/// Only bounds whose values are all numbers, or all strings, are merged; other values
+/// compare by their text and do not order consistently with each other.
+fn merge_ranges(filters: Vec<Filter>) -> NitriteResult<Vec<Filter>> {
+    let mut ranges: Vec<FieldRange> = Vec::new();
+    let mut others = Vec::with_capacity(filters.len());
+
+    for (position, filter) in filters.into_iter().enumerate() {
+        let comparison = match filter.as_any().downcast_ref::<SortingAwareFilter>() {
+            Some(comparison) if filter.hint().is_none() => comparison,
+            _ => {
+                others.push((position, filter));
+                continue;
+            }
+        };
+        let value = match comparison.field_value() {
+            Some(value) if is_number(value) || matches!(value, Value::String(_)) => value.clone(),
+            _ => {
+                others.push((position, filter));
+                continue;
+            }
+        };
+
+        let field_name = filter.get_field_name()?;
+        let index = match ranges.iter().position(|range| {
+            range.field_name == field_name && same_kind(&range.lower, &range.upper, &value)
+        }) {
+            Some(index) => index,
+            None => {
+                ranges.push(FieldRange {
+                    field_name,
+                    position,
+                    lower: None,
+                    upper: None,
+                });
+                ranges.len() - 1
+            }
+        };
+        let range = &mut ranges[index];
+
+        match comparison.comparison_mode() {
+            ComparisonMode::Greater | ComparisonMode::GreaterEqual => {
+                let inclusive = comparison.comparison_mode() == ComparisonMode::GreaterEqual;
+                let tighter = match &range.lower {
+                    None => true,
+                    Some((current, current_inclusive, _)) => match value.cmp(current) {
+                        Ordering::Greater => true,
+                        Ordering::Equal => *current_inclusive && !inclusive,
+                        Ordering::Less => false,
+                    },
+                };
+                if tighter {
+                    range.lower = Some((value, inclusive, filter));
+                }
+            }
+            ComparisonMode::Lesser | ComparisonMode::LesserEqual => {
+                let inclusive = comparison.comparison_mode() == ComparisonMode::LesserEqual;
+                let tighter = match &range.upper {
+                    None => true,
+                    Some((current, current_inclusive, _)) => match value.cmp(current) {
+                        Ordering::Less => true,
+                        Ordering::Equal => *current_inclusive && !inclusive,
+                        Ordering::Greater => false,
+                    },
+                };
+                if tighter {
+                    range.upper = Some((value, inclusive, filter));
+                }
+            }
+        }
+    }
+
+    let mut merged: Vec<(usize, Filter)> = others;
+    for range in ranges {
+        let filter = match (range.lower, range.upper) {
+            (Some((lower, lower_inclusive, _)), Some((upper, upper_inclusive, _))) => {
+                Filter::new(BetweenFilter::new(
+                    range.field_name,
+                    Bound::new(lower, upper, lower_inclusive, upper_inclusive),
+                ))
+            }
+            (Some((_, _, filter)), None) | (None, Some((_, _, filter))) => filter,
+            (None, None) => continue,
+        };
+        merged.push((range.position, filter));
+    }
+    merged.sort_by_key(|(position, _)| *position);
+    Ok(merged.into_iter().map(|(_, filter)| filter).collect())
+}
+
+/// Returns `true` if `value` orders consistently with the bounds already collected.
+fn same_kind(
+    lower: &Option<(Value, bool, Filter)>,
+    upper: &Option<(Value, bool, Filter)>,
+    value: &Value,
+) -> bool {
+    let existing = lower
+        .as_ref()
+        .or(upper.as_ref())
+        .map(|(existing, _, _)| existing);
+    match existing {
+        Some(existing) => is_number(existing) == is_number(value),
+        None => true,
+    }
+}
+
+fn is_number(value: &Value) -> bool {
+    value.is_integer() || value.is_decimal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Document;
+    use crate::doc;
+    use crate::filter::{and, field, not, or};
+
+    fn matches(filter: &Filter, documents: &[Document]) -> Vec<bool> {
+        documents
+            .iter()
+            .map(|document| filter.apply(document).unwrap())
+            .collect()
+    }
+
+    fn documents() -> Vec<Document> {
+        vec![
+            doc! { "a": 1, "b": 2 },
+            doc! { "a": 5, "b": "x" },
+            doc! { "a": [1, 2], "b": 7 },
+            doc! { "b": 3 },
+            doc! { "a": 12, "b": (Value::Null) },
+            doc! { "a": [], "b": 2 },
+        ]
+    }
+
+    #[test]
+    fn test_double_negation() {
+        let filter = not(not(field("a").eq(1)));
+        let normalized = normalize(&filter).unwrap();
+        assert!(normalized.as_any().is::<EqualsFilter>());
+    }
+
+    #[test]
+    fn test_de_morgan_or() {
+        let filter = not(or(vec![field("a").eq(1), field("b").eq(2)]));
+        let normalized = normalize(&filter).unwrap();
+        assert!(is_and_filter(&normalized));
+        let operands = normalized.logical_filters().unwrap();
+        assert_eq!(operands.len(), 2);
+        assert!(operands.iter().all(is_not_filter));
+        assert_eq!(matches(&normalized, &documents()), matches(&filter, &documents()));
+    }
+
+    #[test]
+    fn test_de_morgan_and() {
+        let filter = not(and(vec![field("a").ne(1), not(field("b").eq(2))]));
+        let normalized = normalize(&filter).unwrap();
+        assert!(is_or_filter(&normalized));
+        assert_eq!(matches(&normalized, &documents()), matches(&filter, &documents()));
+    }
+
+    #[test]
+    fn test_not_not_equals_becomes_indexable() {
+        let filter = not(field("a").ne(1));
+        let normalized = normalize(&filter).unwrap();
+        assert!(is_and_filter(&normalized));
+        let operands = normalized.logical_filters().unwrap();
+        assert!(operands[0].as_any().is::<EqualsFilter>());
+        assert!(is_not_filter(&operands[1]));
+        assert_eq!(matches(&normalized, &documents()), matches(&filter, &documents()));
+
+        let filter = not(field("a").not_in_array(vec![1, 5]));
+        let normalized = normalize(&filter).unwrap();
+        let operands = normalized.logical_filters().unwrap();
+        assert!(operands[0].as_any().is::<InFilter>());
+        assert_eq!(matches(&normalized, &documents()), matches(&filter, &documents()));
+    }
+
+    #[test]
+    fn test_unsafe_negation_is_kept() {
+        let filter = not(field("a").gt(3));
+        let normalized = normalize(&filter).unwrap();
+        assert!(is_not_filter(&normalized));
+        assert_eq!(normalized.to_string(), filter.to_string());
+    }
+
+    #[test]
+    fn test_nested_logical_filters_are_flattened() {
+        let filter = and(vec![
+            and(vec![field("a").eq(1), field("b").eq(2)]),
+            not(not(and(vec![field("c").eq(3)]))),
+        ]);
+        let normalized = normalize(&filter).unwrap();
+        assert_eq!(normalized.logical_filters().unwrap().len(), 3);
+
+        let filter = or(vec![or(vec![field("a").eq(1), field("b").eq(2)]), field("c").eq(3)]);
+        let normalized = normalize(&filter).unwrap();
+        assert_eq!(normalized.logical_filters().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_range_merging() {
+        let filter = field("a").gt(3).and(field("a").lt(10));
+        let normalized = normalize(&filter).unwrap();
+        assert!(is_between_filter(&normalized));
+        assert_eq!(normalized.to_string(), "((a < 10) && (a > 3))");
+
+        let filter = and(vec![
+            field("a").gt(3),
+            field("a").gte(5),
+            field("b").eq(1),
+            field("a").lte(10),
+            field("a").lt(10),
+        ]);
+        let normalized = normalize(&filter).unwrap();
+        let operands = normalized.logical_filters().unwrap();
+        assert_eq!(operands.len(), 2);
+        assert_eq!(operands[0].to_string(), "((a < 10) && (a >= 5))");
+        assert_eq!(operands[1].to_string(), "(b == 1)");
+        assert_eq!(matches(&normalized, &documents()), matches(&filter, &documents()));
+    }
+
+    #[test]
+    fn test_range_merging_keeps_mixed_kinds() {
+        let filter = and(vec![field("a").gt(3), field("a").gt("x"), field("a").gt(5)]);
+        let normalized = normalize(&filter).unwrap();
+        let operands = normalized.logical_filters().unwrap();
+        assert_eq!(operands.len(), 2);
+        assert_eq!(operands[0].to_string(), "(a > 5)");
+        assert_eq!(operands[1].to_string(), "(a > \"x\")");
+    }
+
+    #[test]
+    fn test_hinted_bounds_are_not_merged() {
+        let filter = and(vec![field("a").gt(3).with_hint("a"), field("a").gt(5)]);
+        let normalized = normalize(&filter).unwrap();
+        assert_eq!(normalized.logical_filters().unwrap().len(), 2);
+    }
+}
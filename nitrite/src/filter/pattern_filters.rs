@@ -1,17 +1,27 @@
 use regex::Regex;
-use std::{any::Any, collections::HashMap, fmt::Display, sync::OnceLock};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    sync::OnceLock,
+};
 
 use crate::{
     collection::Document,
     errors::{ErrorKind, NitriteError, NitriteResult},
     index::{
-        text::{Tokenizer, TokenizerProvider},
+        text::{EnglishTokenizer, Tokenizer, TokenizerProvider},
         IndexMap,
     },
-    DefaultFilter, StringTokenizer, Value,
+    common::ReadExecutor,
+    DefaultFilter, StringTokenizer, Value, FIELD_SEPARATOR,
 };
 
-use super::{is_element_match_filter, is_text_filter, Filter, FilterProvider};
+use super::{
+    field, is_and_filter, is_between_filter, is_element_match_filter, is_equals_filter,
+    is_in_filter, is_not_filter, is_or_filter, is_text_filter, Filter,
+    FilterProvider, SortingAwareFilter,
+};
 
 /// A filter that matches documents using regular expressions.
 ///
@@ -521,20 +531,144 @@ impl FilterProvider for TextFilter {
     }
 }
 
+/// A filter that matches documents containing all the words of a search text in any of
+/// their string fields.
+///
+/// The search text and the string values, including those inside arrays and embedded
+/// documents, are split into words by the tokenizer of full-text indexes and compared
+/// case-insensitively; stop words are ignored. The words may be spread over several
+/// fields. The filter always scans the documents and does not use text indexes.
+///
+/// When restricted to indexed fields, only the fields of the indexes of the collection
+/// are searched; the read operations hand them over before the query runs.
+pub(crate) struct TextAnyFilter {
+    terms: String,
+    words: HashSet<String>,
+    indexed_only: bool,
+    indexed_fields: OnceLock<Vec<String>>,
+    tokenizer: Tokenizer,
+    collection_name: OnceLock<String>,
+}
+
+impl TextAnyFilter {
+    pub(crate) fn new(terms: &str, indexed_only: bool) -> Self {
+        let tokenizer = Tokenizer::new(EnglishTokenizer);
+        let words = tokenizer
+            .tokenize(terms)
+            .into_iter()
+            .map(|word| word.to_lowercase())
+            .collect();
+        TextAnyFilter {
+            terms: terms.to_string(),
+            words,
+            indexed_only,
+            indexed_fields: OnceLock::new(),
+            tokenizer,
+            collection_name: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn is_indexed_only(&self) -> bool {
+        self.indexed_only
+    }
+
+    pub(crate) fn set_indexed_fields(&self, fields: Vec<String>) {
+        self.indexed_fields.get_or_init(|| fields);
+    }
+
+    /// Removes the words found in `value` from `missing`.
+    fn find_words(&self, value: &Value, missing: &mut HashSet<String>) {
+        match value {
+            Value::String(text) => {
+                for word in self.tokenizer.tokenize(text) {
+                    missing.remove(&word.to_lowercase());
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.find_words(value, missing);
+                }
+            }
+            Value::Document(document) => {
+                for (_, value) in document.iter() {
+                    self.find_words(&value, missing);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Display for TextAnyFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.indexed_only {
+            write!(f, "(text_any_indexed {})", self.terms)
+        } else {
+            write!(f, "(text_any {})", self.terms)
+        }
+    }
+}
+
+impl FilterProvider for TextAnyFilter {
+    fn apply(&self, entry: &Document) -> NitriteResult<bool> {
+        if self.words.is_empty() {
+            return Ok(false);
+        }
+
+        let fields = if self.indexed_only {
+            self.indexed_fields.get().cloned().unwrap_or_default()
+        } else {
+            entry.fields().into_iter().collect()
+        };
+
+        let mut missing = self.words.clone();
+        for field in fields {
+            self.find_words(&entry.get(&field)?, &mut missing);
+            if missing.is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_collection_name(&self) -> NitriteResult<String> {
+        self.collection_name.get().cloned().ok_or_else(|| {
+            log::error!("Collection name is not set for filter");
+            NitriteError::new("Collection name is not set", ErrorKind::InvalidOperation)
+        })
+    }
+
+    fn set_collection_name(&self, collection_name: String) -> NitriteResult<()> {
+        self.collection_name.get_or_init(|| collection_name);
+        Ok(())
+    }
+
+    fn get_field_value(&self) -> NitriteResult<Option<Value>> {
+        Ok(Some(Value::String(self.terms.clone())))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 /// A filter that matches elements within arrays.
 ///
 /// This filter evaluates a condition against each element in an array field and matches
 /// documents where at least one element satisfies the condition. Elements can be documents
-/// or scalar values (matched using a special `$` field name). The filter validates that
-/// the wrapped filter is not itself an ElementMatchFilter or TextFilter to prevent invalid nesting.
+/// or scalar values (matched using a special `$` field name). The condition can be any
+/// combination of filters, including another ElementMatchFilter for arrays nested inside the
+/// elements, but it must not contain a TextFilter or an index-only filter, as those can only
+/// be answered by an index and not on a single element.
 ///
 /// # Responsibilities
 ///
 /// * **Array Element Matching**: Evaluates filters against array elements
 /// * **Document Handling**: Applies filters to document elements
 /// * **Scalar Matching**: Matches scalar values using synthetic documents with `$` field
-/// * **Filter Validation**: Prevents invalid filter nesting
+/// * **Filter Validation**: Rejects conditions that need an index
 /// * **Short-Circuit Evaluation**: Returns true on first matching element
+/// * **Index Assist**: Lifts element conditions onto the array path for multikey indexes
 pub(crate) struct ElementMatchFilter {
     field_name: OnceLock<String>,
     filter: Filter,
@@ -568,18 +702,22 @@ impl ElementMatchFilter {
         }
     }
 
-    fn has_element_match_filter(&self) -> bool {
-        if is_element_match_filter(&self.filter) {
-            return true;
-        }
-        false
+    /// Returns the filters an index on the array path can answer for this condition.
+    ///
+    /// Every comparison in the top-level conjunction of the condition is lifted onto the
+    /// array path, so `score > 0.9` under `product_scores` becomes `product_scores.score > 0.9`
+    /// and `$ == "a"` under `tags` becomes `tags == "a"`. An element satisfying the condition
+    /// satisfies each lifted filter, so an index scan with them finds every matching document;
+    /// the element match itself still has to be checked on the documents found.
+    pub(crate) fn index_filters(&self) -> NitriteResult<Vec<Filter>> {
+        let field_name = self.get_field_name()?;
+        let mut filters = Vec::new();
+        lift_conjuncts(&field_name, &self.filter, &mut filters)?;
+        Ok(filters)
     }
 
     fn has_text_filter(&self) -> bool {
-        if is_text_filter(&self.filter) {
-            return true;
-        }
-        false
+        requires_index(&self.filter)
     }
 
     fn matches(&self, value: Vec<Value>) -> NitriteResult<bool> {
@@ -603,6 +741,77 @@ impl ElementMatchFilter {
     }
 }
 
+/// Returns `true` if `filter` has a part that can only be answered by an index.
+fn requires_index(filter: &Filter) -> bool {
+    if is_text_filter(filter) || filter.is_index_only_filter() {
+        return true;
+    }
+    if is_and_filter(filter) || is_or_filter(filter) || is_not_filter(filter) {
+        return filter
+            .logical_filters()
+            .map(|filters| filters.iter().any(requires_index))
+            .unwrap_or(false);
+    }
+    false
+}
+
+fn lift_conjuncts(array_field: &str, filter: &Filter, lifted: &mut Vec<Filter>) -> NitriteResult<()> {
+    if is_and_filter(filter) || is_between_filter(filter) {
+        for filter in filter.logical_filters()? {
+            lift_conjuncts(array_field, &filter, lifted)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(element_match) = filter.as_any().downcast_ref::<ElementMatchFilter>() {
+        // an array nested in the elements decomposes onto the same path
+        let nested_field = lifted_path(array_field, &element_match.get_field_name()?);
+        return lift_conjuncts(&nested_field, &element_match.filter, lifted);
+    }
+
+    if !filter.has_field() {
+        return Ok(());
+    }
+    let field_name = lifted_path(array_field, &filter.get_field_name()?);
+    let value = match filter.get_field_value()? {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+
+    if is_equals_filter(filter) && is_lookup_key(&value) {
+        lifted.push(field(&field_name).eq(value));
+    } else if is_in_filter(filter) {
+        if let Value::Array(values) = value {
+            if values.iter().all(is_lookup_key) {
+                lifted.push(field(&field_name).in_array(values));
+            }
+        }
+    } else if let Some(comparison) = filter.as_any().downcast_ref::<SortingAwareFilter>() {
+        if is_lookup_key(&value) {
+            lifted.push(Filter::new(SortingAwareFilter::new(
+                field_name,
+                value,
+                comparison.comparison_mode(),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The path of an element field inside the array, the array itself for scalar elements.
+fn lifted_path(array_field: &str, element_field: &str) -> String {
+    if element_field == "$" {
+        array_field.to_string()
+    } else {
+        FIELD_SEPARATOR.read_with(|separator| format!("{array_field}{separator}{element_field}"))
+    }
+}
+
+/// Null, arrays and documents are not stored as keys of a multikey index.
+fn is_lookup_key(value: &Value) -> bool {
+    !value.is_null() && value.is_comparable() && !matches!(value, Value::Array(_))
+}
+
 impl Display for ElementMatchFilter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "(elemMatch {})", self.filter)
@@ -612,14 +821,6 @@ impl Display for ElementMatchFilter {
 impl FilterProvider for ElementMatchFilter {
     #[inline]
     fn apply(&self, entry: &Document) -> NitriteResult<bool> {
-        if self.has_element_match_filter() {
-            log::error!("ElementMatchFilter {} cannot have another ElementMatchFilter {}", self, self.filter);
-            return Err(NitriteError::new(
-                "ElementMatchFilter cannot have another ElementMatchFilter",
-                ErrorKind::FilterError,
-            ));
-        }
-
         if self.has_text_filter() {
             log::error!("ElementMatchFilter {} cannot have TextFilter {}", self, self.filter);
             return Err(NitriteError::new(
@@ -754,6 +955,80 @@ mod tests {
         assert!(!filter.apply(&doc).unwrap());
     }
 
+    #[test]
+    fn test_element_match_filter_with_nested_element_match() {
+        let filter = field("orders").elem_match(
+            field("qty")
+                .gt(1)
+                .and(field("items").elem_match(field("sku").eq("a"))),
+        );
+        let doc = |qty: i32, sku: &str| {
+            let mut item = Document::new();
+            item.put("sku", sku).unwrap();
+            let mut order = Document::new();
+            order.put("qty", qty).unwrap();
+            order.put("items", vec![Value::Document(item)]).unwrap();
+            let mut doc = Document::new();
+            doc.put("orders", vec![Value::Document(order)]).unwrap();
+            doc
+        };
+        assert!(filter.apply(&doc(2, "a")).unwrap());
+        assert!(!filter.apply(&doc(1, "a")).unwrap());
+        assert!(!filter.apply(&doc(2, "b")).unwrap());
+    }
+
+    #[test]
+    fn test_element_match_filter_rejects_nested_text_filter() {
+        let filter = field("items")
+            .elem_match(field("score").gt(1).and(field("name").text("value")));
+        let mut doc = Document::new();
+        doc.put("items", vec![Value::I32(1)]).unwrap();
+        assert!(filter.apply(&doc).is_err());
+    }
+
+    #[test]
+    fn test_element_match_filter_index_filters() {
+        let filter = ElementMatchFilter::new(
+            "scores".to_string(),
+            field("score")
+                .between_optional_inclusive(1, 5)
+                .and(field("product").eq("x"))
+                .and(field("tags").elem_match(field("$").in_array(vec!["a", "b"]))),
+        );
+        let mut lifted: Vec<String> = filter
+            .index_filters()
+            .unwrap()
+            .iter()
+            .map(|filter| filter.to_string())
+            .collect();
+        lifted.sort();
+        assert_eq!(
+            lifted,
+            vec![
+                "(scores.product == \"x\")",
+                "(scores.score <= 5)",
+                "(scores.score >= 1)",
+                "(scores.tags in [\"a\", \"b\"])",
+            ]
+        );
+
+        // disjunctions, negations and null lookups have no index equivalent
+        let filter = ElementMatchFilter::new(
+            "scores".to_string(),
+            field("score")
+                .gt(1)
+                .or(field("score").lt(0))
+                .and(field("product").ne("x"))
+                .and(field("product").eq(Value::Null)),
+        );
+        assert!(filter.index_filters().unwrap().is_empty());
+
+        let filter = ElementMatchFilter::new("tags".to_string(), field("$").lt("m"));
+        let lifted = filter.index_filters().unwrap();
+        assert_eq!(lifted.len(), 1);
+        assert_eq!(lifted[0].get_field_name().unwrap(), "tags");
+    }
+
     // Invalid regex pattern handling tests
     #[test]
     fn test_regex_filter_handles_invalid_pattern_in_constructor() {
@@ -1017,4 +1292,34 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap());
     }
+
+    #[test]
+    fn test_text_any_filter_apply() {
+        let doc = crate::doc! {
+            name: "Ada Lovelace",
+            address: { city: "London" },
+            tags: ["Mathematician", { note: "first programmer" }],
+            age: 36,
+        };
+        assert!(TextAnyFilter::new("ada LONDON", false).apply(&doc).unwrap());
+        assert!(TextAnyFilter::new("programmer", false).apply(&doc).unwrap());
+        assert!(TextAnyFilter::new("mathematician lovelace", false).apply(&doc).unwrap());
+        assert!(!TextAnyFilter::new("ada paris", false).apply(&doc).unwrap());
+        assert!(!TextAnyFilter::new("lond", false).apply(&doc).unwrap());
+        // nothing left to search for after the stop words
+        assert!(!TextAnyFilter::new("the", false).apply(&doc).unwrap());
+    }
+
+    #[test]
+    fn test_text_any_filter_indexed_fields() {
+        let doc = crate::doc! { name: "Ada Lovelace", address: { city: "London" } };
+        let filter = TextAnyFilter::new("london", true);
+        filter.set_indexed_fields(vec!["name".to_string()]);
+        assert!(!filter.apply(&doc).unwrap());
+
+        let filter = TextAnyFilter::new("london", true);
+        filter.set_indexed_fields(vec!["address.city".to_string()]);
+        assert!(filter.apply(&doc).unwrap());
+        assert_eq!(filter.to_string(), "(text_any_indexed london)");
+    }
 }
\ No newline at end of file
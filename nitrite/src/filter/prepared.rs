@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::common::Value;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+
+use super::{
+    AndFilter, Bound, BetweenFilter, ComparisonMode, EqualsFilter, Filter, InFilter,
+    NotEqualsFilter, NotFilter, NotInFilter, OrFilter, SortingAwareFilter,
+};
+
+// Placeholders are string values with a prefix no real data is expected to carry.
+const PARAM_PREFIX: &str = "$nitrite.param:";
+
+/// Creates a placeholder for a value bound later through a [`PreparedFilter`].
+///
+/// Placeholders can stand for the value of `eq`, `ne`, `gt`, `gte`, `lt` and `lte`, a
+/// bound of `between`, or an element of `in_array` and `not_in_array`, at any depth of
+/// `and`, `or` and `not`.
+///
+/// # Arguments
+///
+/// * `name` - The name the value is bound by
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let adults = PreparedFilter::new(field("age").gte(param("min")))?;
+/// ```
+pub fn param(name: &str) -> Value {
+    Value::String(format!("{}{}", PARAM_PREFIX, name))
+}
+
+fn param_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(text) => text.strip_prefix(PARAM_PREFIX),
+        _ => None,
+    }
+}
+
+/// The values bound to the placeholders of a [`PreparedFilter`].
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let params = Params::new().set("min", 18).set("status", "active");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Params {
+    values: HashMap<String, Value>,
+}
+
+impl Params {
+    /// Creates an empty set of values.
+    pub fn new() -> Self {
+        Params::default()
+    }
+
+    /// Binds a value to the placeholder `name`, replacing any value bound before.
+    pub fn set<T: Into<Value>>(mut self, name: &str, value: T) -> Self {
+        self.values.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Returns the value bound to `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+}
+
+/// A filter built once with [`param`] placeholders and executed many times with
+/// different values.
+///
+/// Every filter returned by [`PreparedFilter::bind`] carries the shape of the prepared
+/// filter, so the query planner ranks the indexes for a shape once and reuses that choice
+/// for any bound values, instead of planning and caching each combination of values
+/// separately.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let by_age = PreparedFilter::new(field("age").gt(param("min")).and(field("city").eq(param("city"))))?;
+///
+/// for (min, city) in [(18, "Oslo"), (30, "Lima")] {
+///     let cursor = collection.find(by_age.bind(&Params::new().set("min", min).set("city", city))?)?;
+///     // ...
+/// }
+/// ```
+#[derive(Clone)]
+pub struct PreparedFilter {
+    template: Filter,
+    parameters: Vec<String>,
+    shape: u64,
+}
+
+impl PreparedFilter {
+    /// Prepares a filter holding [`param`] placeholders.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FilterError` if a placeholder is used in a filter that cannot bind
+    /// it, such as `text` or `elem_match`.
+    pub fn new(filter: Filter) -> NitriteResult<PreparedFilter> {
+        let mut parameters = Vec::new();
+        let probe = bind_filter(&filter, &mut |name| {
+            if !parameters.iter().any(|parameter| parameter == name) {
+                parameters.push(name.to_string());
+            }
+            Ok(Value::Null)
+        })?
+        .unwrap_or_else(|| filter.clone());
+
+        if probe.to_string().contains(PARAM_PREFIX) {
+            log::error!("Filter {} uses a parameter where it cannot be bound", filter);
+            return Err(NitriteError::new(
+                &format!("Filter {} uses a parameter where it cannot be bound", filter),
+                ErrorKind::FilterError,
+            ));
+        }
+
+        let mut hasher = DefaultHasher::new();
+        filter.to_string().hash(&mut hasher);
+        Ok(PreparedFilter {
+            shape: hasher.finish(),
+            template: filter,
+            parameters,
+        })
+    }
+
+    /// Returns the names of the placeholders, in the order they appear in the filter.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Returns a filter with every placeholder replaced by its value in `params`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FilterError` if a placeholder has no value, or a value is bound to a
+    /// name the filter does not use.
+    pub fn bind(&self, params: &Params) -> NitriteResult<Filter> {
+        if let Some(name) = params
+            .values
+            .keys()
+            .find(|name| !self.parameters.contains(name))
+        {
+            log::error!("Filter {} has no parameter {}", self.template, name);
+            return Err(NitriteError::new(
+                &format!("Filter has no parameter {}", name),
+                ErrorKind::FilterError,
+            ));
+        }
+
+        let filter = bind_filter(&self.template, &mut |name| match params.get(name) {
+            Some(value) => Ok(value.clone()),
+            None => {
+                log::error!("No value bound to filter parameter {}", name);
+                Err(NitriteError::new(
+                    &format!("No value bound to filter parameter {}", name),
+                    ErrorKind::FilterError,
+                ))
+            }
+        })?
+        .unwrap_or_else(|| self.template.clone());
+        Ok(filter.with_plan_shape(self.shape))
+    }
+}
+
+type Resolver<'a> = dyn FnMut(&str) -> NitriteResult<Value> + 'a;
+
+fn resolve(value: &Value, resolver: &mut Resolver) -> NitriteResult<Option<Value>> {
+    match param_name(value) {
+        Some(name) => resolver(name).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Rebuilds `filter` with its placeholders resolved, or returns `None` if it has none.
+/// Filters are immutable once built, so every filter on the path to a placeholder is
+/// rebuilt; the others are shared with the template.
+fn bind_filter(filter: &Filter, resolver: &mut Resolver) -> NitriteResult<Option<Filter>> {
+    let any = filter.as_any();
+    let bound = if any.is::<EqualsFilter>() || any.is::<NotEqualsFilter>() {
+        let value = filter.get_field_value()?.unwrap_or(Value::Null);
+        match resolve(&value, resolver)? {
+            Some(value) if any.is::<EqualsFilter>() => {
+                Some(Filter::new(EqualsFilter::new(filter.get_field_name()?, value)))
+            }
+            Some(value) => Some(Filter::new(NotEqualsFilter::new(filter.get_field_name()?, value))),
+            None => None,
+        }
+    } else if let Some(sorting_aware) = any.downcast_ref::<SortingAwareFilter>() {
+        let value = sorting_aware.field_value().cloned().unwrap_or(Value::Null);
+        resolve(&value, resolver)?
+            .map(|value| -> NitriteResult<Filter> {
+                Ok(Filter::new(SortingAwareFilter::new(
+                    filter.get_field_name()?,
+                    value,
+                    sorting_aware.comparison_mode(),
+                )))
+            })
+            .transpose()?
+    } else if any.is::<InFilter>() || any.is::<NotInFilter>() {
+        let values = match filter.get_field_value()? {
+            Some(Value::Array(values)) => values,
+            _ => Vec::new(),
+        };
+        if values.iter().any(|value| param_name(value).is_some()) {
+            let mut bound_values = Vec::with_capacity(values.len());
+            for value in &values {
+                bound_values.push(resolve(value, resolver)?.unwrap_or_else(|| value.clone()));
+            }
+            let field_name = filter.get_field_name()?;
+            Some(if any.is::<InFilter>() {
+                Filter::new(InFilter::new(field_name, bound_values))
+            } else {
+                Filter::new(NotInFilter::new(field_name, bound_values))
+            })
+        } else {
+            None
+        }
+    } else if any.is::<BetweenFilter>() {
+        bind_between(filter, resolver)?
+    } else if any.is::<AndFilter>() || any.is::<OrFilter>() {
+        bind_all(&filter.logical_filters()?, resolver)?.map(|filters| {
+            if any.is::<AndFilter>() {
+                Filter::new(AndFilter::new(filters))
+            } else {
+                Filter::new(OrFilter::new(filters))
+            }
+        })
+    } else if any.is::<NotFilter>() {
+        bind_all(&filter.logical_filters()?, resolver)?
+            .map(|mut filters| Filter::new(NotFilter::new(filters.remove(0))))
+    } else {
+        None
+    };
+
+    Ok(bound.map(|bound| match filter.hint() {
+        Some(hint) => bound.with_hint(hint.clone()),
+        None => bound,
+    }))
+}
+
+fn bind_all(filters: &[Filter], resolver: &mut Resolver) -> NitriteResult<Option<Vec<Filter>>> {
+    let mut bound_filters = Vec::with_capacity(filters.len());
+    let mut changed = false;
+    for filter in filters {
+        match bind_filter(filter, resolver)? {
+            Some(bound) => {
+                changed = true;
+                bound_filters.push(bound);
+            }
+            None => bound_filters.push(filter.clone()),
+        }
+    }
+    Ok(changed.then_some(bound_filters))
+}
+
+fn bind_between(filter: &Filter, resolver: &mut Resolver) -> NitriteResult<Option<Filter>> {
+    let mut field_name = None;
+    let mut lower = None;
+    let mut upper = None;
+    let mut changed = false;
+    for bound in filter.logical_filters()? {
+        let Some(sorting_aware) = bound.as_any().downcast_ref::<SortingAwareFilter>() else {
+            return Ok(None);
+        };
+        field_name = Some(bound.get_field_name()?);
+        let value = sorting_aware.field_value().cloned().unwrap_or(Value::Null);
+        let value = match resolve(&value, resolver)? {
+            Some(resolved) => {
+                changed = true;
+                resolved
+            }
+            None => value,
+        };
+        match sorting_aware.comparison_mode() {
+            mode @ (ComparisonMode::Greater | ComparisonMode::GreaterEqual) => {
+                lower = Some((value, mode == ComparisonMode::GreaterEqual))
+            }
+            mode => upper = Some((value, mode == ComparisonMode::LesserEqual)),
+        }
+    }
+
+    match (changed, field_name, lower, upper) {
+        (true, Some(field_name), Some((lower, lower_inclusive)), Some((upper, upper_inclusive))) => {
+            Ok(Some(Filter::new(BetweenFilter::new(
+                field_name,
+                Bound::new(lower, upper, lower_inclusive, upper_inclusive),
+            ))))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use crate::filter::{and, field, not};
+
+    #[test]
+    fn test_parameters() {
+        let prepared = PreparedFilter::new(and(vec![
+            field("age").gt(param("min")),
+            not(field("city").in_array(vec![param("city"), Value::from("Oslo")])),
+            field("score").between_inclusive(param("low"), param("min"), false),
+        ]))
+        .unwrap();
+        assert_eq!(prepared.parameters(), &["min", "city", "low"]);
+
+        let prepared = PreparedFilter::new(field("age").gt(18)).unwrap();
+        assert!(prepared.parameters().is_empty());
+    }
+
+    #[test]
+    fn test_bind() {
+        let prepared = PreparedFilter::new(
+            field("age")
+                .gte(param("min"))
+                .and(field("city").ne(param("city")))
+                .or(field("score").between_inclusive(param("low"), Value::from(100), true)),
+        )
+        .unwrap();
+
+        let filter = prepared
+            .bind(&Params::new().set("min", 18).set("city", "Oslo").set("low", 90))
+            .unwrap();
+        assert!(filter.apply(&doc! { age: 20, city: "Lima", score: 10 }).unwrap());
+        assert!(!filter.apply(&doc! { age: 20, city: "Oslo", score: 10 }).unwrap());
+        assert!(!filter.apply(&doc! { age: 17, city: "Lima", score: 10 }).unwrap());
+        assert!(filter.apply(&doc! { age: 17, city: "Oslo", score: 90 }).unwrap());
+        assert_eq!(filter.plan_shape(), Some(prepared.shape));
+
+        let other = prepared
+            .bind(&Params::new().set("min", 65).set("city", "Lima").set("low", 50))
+            .unwrap();
+        assert!(!other.apply(&doc! { age: 20, city: "Oslo", score: 10 }).unwrap());
+        assert_eq!(other.plan_shape(), filter.plan_shape());
+        assert_ne!(other.to_string(), filter.to_string());
+    }
+
+    #[test]
+    fn test_bind_keeps_hint() {
+        let prepared =
+            PreparedFilter::new(field("age").with_hint("age").eq(param("age"))).unwrap();
+        let filter = prepared.bind(&Params::new().set("age", 30)).unwrap();
+        assert!(filter.hint().is_some());
+        assert!(filter.apply(&doc! { age: 30 }).unwrap());
+    }
+
+    #[test]
+    fn test_bind_errors() {
+        let prepared = PreparedFilter::new(field("age").lt(param("max"))).unwrap();
+
+        let err = prepared.bind(&Params::new()).err().unwrap();
+        assert_eq!(err.kind(), &ErrorKind::FilterError);
+        assert!(err.message().contains("max"));
+
+        let err = prepared
+            .bind(&Params::new().set("max", 1).set("min", 0))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), &ErrorKind::FilterError);
+        assert!(err.message().contains("min"));
+    }
+
+    #[test]
+    fn test_unsupported_placeholder() {
+        let err = PreparedFilter::new(field("tags").elem_match(field("$").eq(param("tag"))))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), &ErrorKind::FilterError);
+    }
+}
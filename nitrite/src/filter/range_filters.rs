@@ -1,10 +1,10 @@
 use std::{any::Any, fmt::Display, sync::{atomic::AtomicBool, OnceLock}};
 
 use crate::{
-    collection::Document,
+    collection::{Document, NitriteId},
     errors::{ErrorKind, NitriteError, NitriteResult},
     index::IndexMap,
-    Value,
+    Value, DOC_ID,
 };
 
 use super::{Filter, FilterProvider};
@@ -344,21 +344,20 @@ impl Display for SortingAwareFilter {
             ComparisonMode::Lesser => "<",
             ComparisonMode::LesserEqual => "<=",
         };
-        write!(
-            f,
-            "({} {} {})",
-            self.field_name.get().expect("field_name not initialized"),
-            operator,
-            self.field_value.get().expect("field_value not initialized")
-        )
+        match (self.field_name.get(), self.field_value.get()) {
+            (Some(name), Some(value)) => write!(f, "({} {} {})", name, operator, value),
+            (Some(name), None) => write!(f, "({} {} unknown)", name, operator),
+            (None, Some(value)) => write!(f, "(unknown {} {})", operator, value),
+            (None, None) => write!(f, "(unknown {} unknown)", operator),
+        }
     }
 }
 
 impl FilterProvider for SortingAwareFilter {
     #[inline]
     fn apply(&self, entry: &Document) -> NitriteResult<bool> {
-        let value = entry.get(self.field_name.get().expect("field_name not initialized"))?;
-        let field_value = self.field_value.get().expect("field_value not initialized");
+        let value = entry.get(initialized(&self.field_name, "field name")?)?;
+        let field_value = initialized(&self.field_value, "field value")?;
 
         // a null (or missing) field value is never lesser or greater than the
         // search term; without this check the mixed-type Value ordering would
@@ -394,14 +393,15 @@ impl FilterProvider for SortingAwareFilter {
     }
 
     fn get_collection_name(&self) -> NitriteResult<String> {
-        if self.collection_name.get().is_none() {
-            log::error!("Collection name is not set for filter {}", self);
-            Err(NitriteError::new(
-                "Collection name is not set",
-                ErrorKind::CollectionNotFound,
-            ))
-        } else {
-            Ok(self.collection_name.get().unwrap().clone())
+        match self.collection_name.get() {
+            Some(collection_name) => Ok(collection_name.clone()),
+            None => {
+                log::error!("Collection name is not set for filter {}", self);
+                Err(NitriteError::new(
+                    "Collection name is not set",
+                    ErrorKind::CollectionNotFound,
+                ))
+            }
         }
     }
 
@@ -415,7 +415,7 @@ impl FilterProvider for SortingAwareFilter {
     }
 
     fn get_field_name(&self) -> NitriteResult<String> {
-        Ok(self.field_name.get().expect("field_name not initialized").clone())
+        Ok(initialized(&self.field_name, "field name")?.clone())
     }
 
     fn set_field_name(&self, field_name: String) -> NitriteResult<()> {
@@ -602,17 +602,18 @@ impl InFilter {
 impl Display for InFilter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut values = String::new();
-        for value in self.field_values.get().expect("field_values not initialized") {
+        for value in self.field_values.get().into_iter().flatten() {
             values.push_str(&format!("{}, ", value));
         }
-        write!(f, "({} in [{}])", self.field_name.get().expect("field_name not initialized"), values.trim_end_matches(", "))
+        let name = self.field_name.get().map_or("unknown", String::as_str);
+        write!(f, "({} in [{}])", name, values.trim_end_matches(", "))
     }
 }
 
 impl FilterProvider for InFilter {
     #[inline]
     fn apply(&self, entry: &Document) -> NitriteResult<bool> {
-        let value = entry.get(self.field_name.get().expect("field_name not initialized"))?;
+        let value = entry.get(initialized(&self.field_name, "field name")?)?;
         // `in` is multi-value `eq`, so it matches array fields by element
         // containment for the same index-independence reason as EqualsFilter
         // (see basic_filters.rs): the index path already treats arrays
@@ -621,7 +622,7 @@ impl FilterProvider for InFilter {
             Value::Array(elements) => Some(elements),
             _ => None,
         };
-        for field_value in self.field_values.get().expect("field_values not initialized") {
+        for field_value in initialized(&self.field_values, "field values")? {
             if &value == field_value {
                 return Ok(true);
             }
@@ -638,7 +639,7 @@ impl FilterProvider for InFilter {
         let mut sub_map = Vec::new();
         let mut nitrite_ids = Vec::new();
 
-        for field_value in self.field_values.get().expect("field_values not initialized") {
+        for field_value in initialized(&self.field_values, "field values")? {
             let value = index_map.get(field_value)?;
             self.process_index_value(value, &mut sub_map, &mut nitrite_ids);
         }
@@ -654,14 +655,15 @@ impl FilterProvider for InFilter {
     }
 
     fn get_collection_name(&self) -> NitriteResult<String> {
-        if self.collection_name.get().is_none() {
-            log::error!("Collection name is not set for filter {}", self);
-            Err(NitriteError::new(
-                "Collection name is not set",
-                ErrorKind::CollectionNotFound,
-            ))
-        } else {
-            Ok(self.collection_name.get().expect("collection_name not initialized").clone())
+        match self.collection_name.get() {
+            Some(collection_name) => Ok(collection_name.clone()),
+            None => {
+                log::error!("Collection name is not set for filter {}", self);
+                Err(NitriteError::new(
+                    "Collection name is not set",
+                    ErrorKind::CollectionNotFound,
+                ))
+            }
         }
     }
 
@@ -675,7 +677,7 @@ impl FilterProvider for InFilter {
     }
 
     fn get_field_name(&self) -> NitriteResult<String> {
-        Ok(self.field_name.get().expect("field_name not initialized").clone())
+        Ok(initialized(&self.field_name, "field name")?.clone())
     }
 
     fn set_field_name(&self, field_name: String) -> NitriteResult<()> {
@@ -684,7 +686,7 @@ impl FilterProvider for InFilter {
     }
 
     fn get_field_value(&self) -> NitriteResult<Option<Value>> {
-        Ok(Some(Value::Array(self.field_values.get().expect("field_values not initialized").clone())))
+        Ok(Some(Value::Array(initialized(&self.field_values, "field values")?.clone())))
     }
 
     fn set_field_value(&self, field_values: Value) -> NitriteResult<()> {
@@ -753,18 +755,19 @@ impl NotInFilter {
 impl Display for NotInFilter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut values = String::new();
-        for value in self.field_values.get().expect("field_values not initialized") {
+        for value in self.field_values.get().into_iter().flatten() {
             values.push_str(&format!("{}, ", value));
         }
-        write!(f, "({} not in [{}])", self.field_name.get().expect("field_name not initialized"), values.trim_end_matches(", "))
+        let name = self.field_name.get().map_or("unknown", String::as_str);
+        write!(f, "({} not in [{}])", name, values.trim_end_matches(", "))
     }
 }
 
 impl FilterProvider for NotInFilter {
     #[inline]
     fn apply(&self, entry: &Document) -> NitriteResult<bool> {
-        let value = entry.get(self.field_name.get().expect("field_name not initialized"))?;
-        for field_value in self.field_values.get().expect("field_values not initialized") {
+        let value = entry.get(initialized(&self.field_name, "field name")?)?;
+        for field_value in initialized(&self.field_values, "field values")? {
             if &value == field_value {
                 return Ok(false);
             }
@@ -779,7 +782,7 @@ impl FilterProvider for NotInFilter {
         let entries = index_map.entries()?;
         for result in entries {
             let (key, value) = result?;
-            if !self.field_values.get().expect("field_values not initialized").contains(&key) {
+            if !initialized(&self.field_values, "field values")?.contains(&key) {
                 self.process_index_value(Some(value), &mut sub_map, &mut nitrite_ids);
             }
         }
@@ -795,14 +798,15 @@ impl FilterProvider for NotInFilter {
     }
 
     fn get_collection_name(&self) -> NitriteResult<String> {
-        if self.collection_name.get().is_none() {
-            log::error!("Collection name is not set for filter {}", self);
-            Err(NitriteError::new(
-                "Collection name is not set",
-                ErrorKind::CollectionNotFound,
-            ))
-        } else {
-            Ok(self.collection_name.get().expect("collection_name not initialized").clone())
+        match self.collection_name.get() {
+            Some(collection_name) => Ok(collection_name.clone()),
+            None => {
+                log::error!("Collection name is not set for filter {}", self);
+                Err(NitriteError::new(
+                    "Collection name is not set",
+                    ErrorKind::CollectionNotFound,
+                ))
+            }
         }
     }
 
@@ -816,7 +820,7 @@ impl FilterProvider for NotInFilter {
     }
 
     fn get_field_name(&self) -> NitriteResult<String> {
-        Ok(self.field_name.get().expect("field_name not initialized").clone())
+        Ok(initialized(&self.field_name, "field name")?.clone())
     }
 
     fn set_field_name(&self, field_name: String) -> NitriteResult<()> {
@@ -825,7 +829,7 @@ impl FilterProvider for NotInFilter {
     }
 
     fn get_field_value(&self) -> NitriteResult<Option<Value>> {
-        Ok(Some(Value::Array(self.field_values.get().unwrap().clone())))
+        Ok(Some(Value::Array(initialized(&self.field_values, "field values")?.clone())))
     }
 
     fn set_field_value(&self, field_values: Value) -> NitriteResult<()> {
@@ -846,6 +850,98 @@ impl FilterProvider for NotInFilter {
     }
 }
 
+/// Matches documents whose id lies between two ids, both inclusive.
+///
+/// Ids are ordered by creation time, so with bounds from `NitriteId::from_time_range()` this
+/// selects the documents created in a time range. The planner answers it with a range scan
+/// of the collection map instead of a full scan.
+///
+/// Created internally by: `by_id_range(start, end)`
+pub(crate) struct IdRangeFilter {
+    start: NitriteId,
+    end: NitriteId,
+    collection_name: OnceLock<String>,
+}
+
+impl IdRangeFilter {
+    #[inline]
+    pub(crate) fn new(start: NitriteId, end: NitriteId) -> Self {
+        IdRangeFilter {
+            start,
+            end,
+            collection_name: OnceLock::new(),
+        }
+    }
+
+    /// Returns the inclusive bounds of the range.
+    pub(crate) fn bounds(&self) -> (NitriteId, NitriteId) {
+        (self.start, self.end)
+    }
+}
+
+impl Display for IdRangeFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({} between {} and {})", DOC_ID, self.start, self.end)
+    }
+}
+
+impl FilterProvider for IdRangeFilter {
+    fn apply(&self, entry: &Document) -> NitriteResult<bool> {
+        match entry.get(DOC_ID)? {
+            Value::NitriteId(id) => Ok(self.start <= id && id <= self.end),
+            _ => Ok(false),
+        }
+    }
+
+    fn get_collection_name(&self) -> NitriteResult<String> {
+        match self.collection_name.get() {
+            Some(collection_name) => Ok(collection_name.clone()),
+            None => {
+                log::error!("Collection name is not set for filter {}", self);
+                Err(NitriteError::new(
+                    "Collection name is not set",
+                    ErrorKind::CollectionNotFound,
+                ))
+            }
+        }
+    }
+
+    fn set_collection_name(&self, collection_name: String) -> NitriteResult<()> {
+        self.collection_name.get_or_init(|| collection_name);
+        Ok(())
+    }
+
+    fn has_field(&self) -> bool {
+        true
+    }
+
+    fn get_field_name(&self) -> NitriteResult<String> {
+        Ok(DOC_ID.to_string())
+    }
+
+    fn get_field_value(&self) -> NitriteResult<Option<Value>> {
+        Ok(Some(Value::Array(vec![
+            Value::NitriteId(self.start),
+            Value::NitriteId(self.end),
+        ])))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Returns the value of a filter field set at construction, or an error if it is missing.
+fn initialized<'a, T>(cell: &'a OnceLock<T>, what: &str) -> NitriteResult<&'a T> {
+    cell.get().ok_or_else(|| {
+        log::error!("Filter {} is not initialized", what);
+        NitriteError::new(
+            &format!("Filter {} is not initialized", what),
+            ErrorKind::InvalidOperation,
+        )
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,508 @@
+//! Plain-text rendering of documents and query plans.
+//!
+//! [`Table`] lays documents out as an aligned ASCII table, with a chosen set of columns,
+//! a maximum cell width and a maximum number of rows. [`render_plan`] describes how a
+//! query runs: the index it scans, the filter applied to each document, the sort and the
+//! paging. Both are meant for reading, in a REPL, a test failure or an example; the
+//! format may change between versions. Tests that compare plans should use
+//! [`FindPlan::to_canonical_string`] instead, whose format is versioned.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use nitrite::fmt::{render_plan, Table};
+//!
+//! let mut cursor = collection.find(field("age").gt(30))?;
+//! println!("{}", render_plan(cursor.find_plan().unwrap()));
+//! println!("{}", Table::new().columns(&["name", "age"]).render_cursor(&mut cursor)?);
+//! ```
+//!
+//! ```text
+//! +-------+-----+
+//! | name  | age |
+//! +-------+-----+
+//! | Ada   |  36 |
+//! | Grace |  45 |
+//! +-------+-----+
+//! 2 rows
+//! ```
+
+use crate::collection::{Document, FindPlan};
+use crate::common::{is_reserved_field, DocumentCursor, SortOrder, Value, DOC_ID};
+use crate::errors::NitriteResult;
+use std::fmt::Write;
+
+/// The widest a cell is by default, in characters.
+pub const DEFAULT_MAX_CELL_WIDTH: usize = 32;
+
+/// Renders documents as an aligned ASCII table.
+///
+/// Without a column selection the columns are `_id` followed by the fields of the
+/// documents in the order they are first seen; the other reserved fields are left out.
+/// A column may name an embedded field, like `address.city`. Missing and null values are
+/// empty cells, numbers are right-aligned and values wider than the maximum cell width
+/// are cut short with `...`.
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Option<Vec<String>>,
+    max_width: usize,
+    max_rows: Option<usize>,
+}
+
+impl Table {
+    /// Creates a table with inferred columns, cells of up to
+    /// [`DEFAULT_MAX_CELL_WIDTH`] characters and no row limit.
+    pub fn new() -> Self {
+        Table {
+            columns: None,
+            max_width: DEFAULT_MAX_CELL_WIDTH,
+            max_rows: None,
+        }
+    }
+
+    /// Shows only these fields, in this order.
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = Some(columns.iter().map(|column| column.to_string()).collect());
+        self
+    }
+
+    /// Sets the widest a cell can be, in characters; at least 4 so that a cut value keeps
+    /// one character.
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width.max(4);
+        self
+    }
+
+    /// Shows at most this many rows.
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Renders documents, followed by the number of rows.
+    pub fn render(&self, documents: &[Document]) -> String {
+        let shown = self.max_rows.map_or(documents.len(), |max| max.min(documents.len()));
+        let footer = if shown < documents.len() {
+            format!("{} of {} rows", shown, documents.len())
+        } else {
+            row_count(shown)
+        };
+        self.render_rows(&documents[..shown], &footer)
+    }
+
+    /// Renders the remaining documents of a cursor, up to the row limit.
+    ///
+    /// The cursor is read one row past the limit, to tell whether rows were left out,
+    /// and is not reset.
+    pub fn render_cursor(&self, cursor: &mut DocumentCursor) -> NitriteResult<String> {
+        let mut documents = Vec::new();
+        let mut more = false;
+        for document in cursor.by_ref() {
+            let document = document?;
+            if self.max_rows.is_some_and(|max| documents.len() >= max) {
+                more = true;
+                break;
+            }
+            documents.push(document);
+        }
+
+        let footer = if more {
+            format!("first {}", row_count(documents.len()))
+        } else {
+            row_count(documents.len())
+        };
+        Ok(self.render_rows(&documents, &footer))
+    }
+
+    fn render_rows(&self, documents: &[Document], footer: &str) -> String {
+        let columns = match &self.columns {
+            Some(columns) => columns.clone(),
+            None => infer_columns(documents),
+        };
+        if columns.is_empty() {
+            return footer.to_string();
+        }
+
+        let rows: Vec<Vec<(String, bool)>> = documents
+            .iter()
+            .map(|document| {
+                columns
+                    .iter()
+                    .map(|column| {
+                        let value = document.get(column).unwrap_or(Value::Null);
+                        (truncate(&cell(&value), self.max_width), value.is_number())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut widths: Vec<usize> = columns
+            .iter()
+            .map(|column| truncate(column, self.max_width).chars().count())
+            .collect();
+        for row in &rows {
+            for (width, (text, _)) in widths.iter_mut().zip(row) {
+                *width = (*width).max(text.chars().count());
+            }
+        }
+
+        let mut separator = String::from("+");
+        for width in &widths {
+            separator.push_str(&"-".repeat(width + 2));
+            separator.push('+');
+        }
+
+        let mut table = String::new();
+        table.push_str(&separator);
+        table.push('\n');
+        let header: Vec<(String, bool)> = columns
+            .iter()
+            .map(|column| (truncate(column, self.max_width), false))
+            .collect();
+        push_row(&mut table, &header, &widths);
+        table.push_str(&separator);
+        table.push('\n');
+        if !rows.is_empty() {
+            for row in &rows {
+                push_row(&mut table, row, &widths);
+            }
+            table.push_str(&separator);
+            table.push('\n');
+        }
+        table.push_str(footer);
+        table
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+/// Renders documents as a table with the default settings of [`Table`].
+pub fn render_table(documents: &[Document]) -> String {
+    Table::new().render(documents)
+}
+
+/// Describes a query plan, one step per line, in the order the steps run.
+///
+/// # Examples
+///
+/// ```text
+/// index scan: non-unique index on [age]
+///   (age > 30)
+/// filter: (name == Ada)
+/// sort: name ascending
+/// limit: 10
+/// ```
+pub fn render_plan(find_plan: &FindPlan) -> String {
+    let mut text = String::new();
+    write_plan(&mut text, find_plan, 0);
+    text.truncate(text.trim_end().len());
+    text
+}
+
+fn write_plan(text: &mut String, find_plan: &FindPlan, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut scanned = false;
+
+    if let Some(by_id_filter) = find_plan.by_id_filter() {
+        let _ = writeln!(text, "{}id lookup: {}", indent, by_id_filter);
+        scanned = true;
+    }
+
+    if let Some(index_descriptor) = find_plan.index_descriptor() {
+        let _ = writeln!(
+            text,
+            "{}index scan: {} index on [{}]",
+            indent,
+            index_descriptor.index_type(),
+            index_descriptor.index_fields().field_names().join(", ")
+        );
+        if let Some(index_scan_filter) = find_plan.index_scan_filter() {
+            for filter in index_scan_filter.filters() {
+                let _ = writeln!(text, "{}  {}", indent, filter);
+            }
+        }
+        if let Some(scan_order) = find_plan.index_scan_order() {
+            let mut fields: Vec<_> = scan_order.into_iter().collect();
+            fields.sort();
+            for (field, reverse) in fields {
+                let order = if reverse { "descending" } else { "ascending" };
+                let _ = writeln!(text, "{}  order: {} {}", indent, field, order);
+            }
+        }
+        if find_plan.is_covered() {
+            let _ = writeln!(text, "{}  covered: true", indent);
+        }
+        scanned = true;
+    }
+
+    if let Some(sub_plans) = find_plan.sub_plans().filter(|plans| !plans.is_empty()) {
+        let _ = writeln!(text, "{}union of {} branches:", indent, sub_plans.len());
+        for (number, sub_plan) in sub_plans.iter().enumerate() {
+            let _ = writeln!(text, "{}  branch {}:", indent, number + 1);
+            write_plan(text, sub_plan, depth + 2);
+        }
+        scanned = true;
+    }
+
+    if !scanned {
+        let _ = writeln!(text, "{}collection scan", indent);
+    }
+
+    if let Some(full_scan_filter) = find_plan.full_scan_filter() {
+        let _ = writeln!(text, "{}filter: {}", indent, full_scan_filter);
+    }
+
+    if let Some(distance_sort) = find_plan.distance_sort() {
+        let _ = writeln!(
+            text,
+            "{}sort: {} by distance to ({}, {})",
+            indent,
+            distance_sort.field_name(),
+            distance_sort.x(),
+            distance_sort.y()
+        );
+    }
+
+    if let Some(sort_order) = find_plan.blocking_sort_order().filter(|order| !order.is_empty()) {
+        let fields: Vec<String> = sort_order
+            .iter()
+            .map(|(field, order)| match order {
+                SortOrder::Ascending => format!("{} ascending", field),
+                SortOrder::Descending => format!("{} descending", field),
+            })
+            .collect();
+        let _ = writeln!(text, "{}sort: {}", indent, fields.join(", "));
+        if !find_plan.tie_break_by_id() {
+            let _ = writeln!(text, "{}ties: unordered", indent);
+        }
+    }
+
+    if find_plan.distinct() {
+        let _ = writeln!(text, "{}distinct", indent);
+    }
+    if let Some(skip) = find_plan.skip() {
+        let _ = writeln!(text, "{}skip: {}", indent, skip);
+    }
+    if let Some(limit) = find_plan.limit() {
+        let _ = writeln!(text, "{}limit: {}", indent, limit);
+    }
+}
+
+/// Lists `_id` and the fields of the documents in the order they are first seen,
+/// without the other reserved fields.
+fn infer_columns(documents: &[Document]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    if documents.iter().any(|document| document.contains_key(DOC_ID)) {
+        columns.push(DOC_ID.to_string());
+    }
+    for document in documents {
+        for (field, _) in document.iter() {
+            if !is_reserved_field(&field) && !columns.contains(&field) {
+                columns.push(field);
+            }
+        }
+    }
+    columns
+}
+
+/// Renders a value on one line: strings as they are, embedded documents and arrays in a
+/// compact JSON-like form.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        _ => inline(value, true),
+    }
+}
+
+fn inline(value: &Value, top_level: bool) -> String {
+    match value {
+        Value::String(text) if top_level => escape(text),
+        Value::String(text) => format!("\"{}\"", escape(text)),
+        Value::Char(c) if top_level => escape(&c.to_string()),
+        Value::Char(c) => format!("\"{}\"", escape(&c.to_string())),
+        Value::NitriteId(id) => id.id_value().to_string(),
+        Value::Bytes(bytes) => format!("<{} bytes>", bytes.len()),
+        Value::RawJson(json) => escape(json),
+        Value::Document(document) => {
+            let fields: Vec<String> = document
+                .iter()
+                .map(|(field, value)| format!("{}: {}", field, inline(&value, false)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        Value::Array(values) => {
+            let values: Vec<String> = values.iter().map(|value| inline(value, false)).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Map(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", inline(key, false), inline(value, false)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Keeps line breaks and tabs from breaking the layout of a row.
+fn escape(text: &str) -> String {
+    text.replace('\n', "\\n").replace('\r', "\\r").replace('\t', "\\t")
+}
+
+fn truncate(text: &str, max_width: usize) -> String {
+    if text.chars().count() <= max_width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_width - 3).collect();
+    cut.push_str("...");
+    cut
+}
+
+fn push_row(table: &mut String, row: &[(String, bool)], widths: &[usize]) {
+    table.push('|');
+    for ((text, right_aligned), width) in row.iter().zip(widths) {
+        if *right_aligned {
+            let _ = write!(table, " {:>width$} |", text, width = width);
+        } else {
+            let _ = write!(table, " {:<width$} |", text, width = width);
+        }
+    }
+    table.push('\n');
+}
+
+fn row_count(count: usize) -> String {
+    if count == 1 {
+        "1 row".to_string()
+    } else {
+        format!("{} rows", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::NitriteId;
+    use crate::common::{Fields, NON_UNIQUE_INDEX};
+    use crate::doc;
+    use crate::filter::{field, IndexScanFilter};
+    use crate::index::IndexDescriptor;
+
+    #[test]
+    fn test_render_table() {
+        let documents = vec![
+            doc! { "name": "Ada", "age": 36 },
+            doc! { "name": "Grace", "age": 145, "city": "Arlington" },
+        ];
+        let table = Table::new().columns(&["name", "age", "city"]).render(&documents);
+        assert_eq!(
+            table,
+            "+-------+-----+-----------+\n\
+             | name  | age | city      |\n\
+             +-------+-----+-----------+\n\
+             | Ada   |  36 |           |\n\
+             | Grace | 145 | Arlington |\n\
+             +-------+-----+-----------+\n\
+             2 rows"
+        );
+    }
+
+    #[test]
+    fn test_render_table_infers_columns() {
+        let mut document = doc! { "b": 1, "a": { "x": [1, "two"] } };
+        document.put(DOC_ID, NitriteId::create_id(1_000_000_000_000_000_042).unwrap()).unwrap();
+        document.put("_revision", 3).unwrap();
+
+        let table = render_table(&[document]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[1], "| _id                 | a               | b |");
+        assert_eq!(lines[3], "| 1000000000000000042 | {x: [1, \"two\"]} | 1 |");
+        assert_eq!(lines[5], "1 row");
+    }
+
+    #[test]
+    fn test_render_table_truncates_and_escapes() {
+        let documents = vec![doc! { "text": "first line\nsecond line", "blob": (vec![1u8, 2, 3]) }];
+        let table = Table::new().max_width(10).render(&documents);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[1], "| blob      | text       |");
+        assert_eq!(lines[3], "| <3 bytes> | first l... |");
+    }
+
+    #[test]
+    fn test_render_table_max_rows() {
+        let documents: Vec<Document> = (0..5).map(|i| doc! { "n": i }).collect();
+        let table = Table::new().max_rows(2).render(&documents);
+        assert_eq!(table.lines().filter(|line| line.starts_with("| ")).count(), 3);
+        assert!(table.ends_with("2 of 5 rows"));
+
+        let table = render_table(&[]);
+        assert_eq!(table, "0 rows");
+    }
+
+    #[test]
+    fn test_render_plan() {
+        let mut find_plan = FindPlan::new();
+        find_plan.set_index_descriptor(IndexDescriptor::new(
+            NON_UNIQUE_INDEX,
+            Fields::with_names(vec!["age"]).unwrap(),
+            "people",
+        ));
+        find_plan.set_index_scan_filter(IndexScanFilter::new(vec![field("age").gt(30)]));
+        find_plan.set_full_scan_filter(field("name").eq("Ada"));
+        find_plan.set_blocking_sort_order(vec![("name".to_string(), SortOrder::Descending)]);
+        find_plan.set_skip(5);
+        find_plan.set_limit(10);
+
+        let plan = render_plan(&find_plan);
+        let lines: Vec<&str> = plan.lines().collect();
+        assert_eq!(lines[0], "index scan: non-unique index on [age]");
+        assert!(lines[1].starts_with("  ") && lines[1].contains("age"));
+        assert!(lines[2].starts_with("filter: ") && lines[2].contains("Ada"));
+        assert_eq!(&lines[3..], ["sort: name descending", "skip: 5", "limit: 10"]);
+
+        find_plan.set_tie_break_by_id(false);
+        let plan = render_plan(&find_plan);
+        assert!(plan.contains("sort: name descending\nties: unordered\nskip: 5"));
+    }
+
+    #[test]
+    fn test_render_covered_plan() {
+        let mut find_plan = FindPlan::new();
+        find_plan.set_index_descriptor(IndexDescriptor::new(
+            NON_UNIQUE_INDEX,
+            Fields::with_names(vec!["age", "name"]).unwrap(),
+            "people",
+        ));
+        find_plan.set_index_scan_filter(IndexScanFilter::new(vec![field("age").gt(30)]));
+        assert!(!render_plan(&find_plan).contains("covered"));
+
+        let plan = render_plan(&find_plan.to_covered());
+        let lines: Vec<&str> = plan.lines().collect();
+        assert_eq!(lines[0], "index scan: non-unique index on [age, name]");
+        assert_eq!(lines[2], "  covered: true");
+    }
+
+    #[test]
+    fn test_render_plan_with_branches() {
+        let mut find_plan = FindPlan::new();
+        let mut first = FindPlan::new();
+        first.set_full_scan_filter(field("a").eq(1));
+        find_plan.add_sub_plan(first);
+        find_plan.add_sub_plan(FindPlan::new());
+        find_plan.set_distinct(true);
+
+        let plan = render_plan(&find_plan);
+        let lines: Vec<&str> = plan.lines().collect();
+        assert_eq!(lines[0], "union of 2 branches:");
+        assert_eq!(lines[1], "  branch 1:");
+        assert_eq!(lines[2], "    collection scan");
+        assert!(lines[3].starts_with("    filter: "));
+        assert_eq!(&lines[4..], ["  branch 2:", "    collection scan", "distinct"]);
+        assert_eq!(render_plan(&FindPlan::new()), "collection scan");
+    }
+}
@@ -0,0 +1,553 @@
+use crate::{
+    collection::{Document, NitriteCollection, NitriteId},
+    common::{AttributeAware, PersistentCollection},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::{and, field, or, Filter},
+    index::{non_unique_index, unique_index},
+    Value, GRAPH_PREFIX, GRAPH_VERTICES, INTERNAL_NAME_SEPARATOR,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+const FROM: &str = "from";
+const TO: &str = "to";
+const LABEL: &str = "label";
+
+/// The direction in which edges are followed from a vertex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// From the vertex to the other end of the edges starting at it.
+    #[default]
+    Outgoing,
+    /// From the vertex to the other end of the edges ending at it.
+    Incoming,
+    /// Along every edge of the vertex, whatever its direction.
+    Both,
+}
+
+/// A labelled edge of a [`Graph`], from one vertex to another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edge {
+    /// Id of the vertex the edge starts at.
+    pub from: NitriteId,
+    /// Id of the vertex the edge ends at.
+    pub to: NitriteId,
+    /// Label of the edge, such as `"friend"`.
+    pub label: String,
+}
+
+/// Which edges a traversal follows and how far it goes.
+///
+/// By default a traversal follows outgoing edges of any label, without a depth limit.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let traversal = Traversal::new()
+///     .direction(Direction::Both)
+///     .label("friend")
+///     .max_depth(2);
+/// let friends_of_friends = people.bfs(&alice, &traversal)?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Traversal {
+    direction: Direction,
+    label: Option<String>,
+    max_depth: Option<usize>,
+}
+
+impl Traversal {
+    /// Creates a traversal following outgoing edges of any label, without a depth limit.
+    pub fn new() -> Self {
+        Traversal::default()
+    }
+
+    /// Sets the direction in which edges are followed.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Follows only the edges with `label`.
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Stops at vertices `max_depth` edges away from the start. A depth of 0 visits only
+    /// the start.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Returns the direction in which edges are followed.
+    pub fn get_direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the label of the edges followed, `None` if any.
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the depth limit, `None` if there is none.
+    pub fn get_max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    fn can_expand(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth < max_depth)
+    }
+}
+
+/// A vertex reached by a traversal, with the number of edges from the start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Visit {
+    /// The document of the vertex.
+    pub document: Document,
+    /// Number of edges between the start and the vertex, 0 for the start.
+    pub depth: usize,
+}
+
+/// Relationships between the documents of a collection.
+///
+/// The documents of the vertex collection are the vertices; the graph stores labelled,
+/// directed edges between their ids in a collection of its own, indexed on both ends.
+/// Neighbors, breadth-first and depth-first traversals and shortest paths are answered
+/// from those indexes and return the vertex documents, so relationships such as
+/// "friend of" need no id fields in the documents and no recursive lookups.
+///
+/// Edges ending at a vertex removed from the vertex collection directly are skipped by
+/// the traversals; [`remove_vertex`](Graph::remove_vertex) removes a vertex with its
+/// edges. A graph is opened with [`Nitrite::graph`](crate::nitrite::Nitrite::graph) and
+/// is cheap to clone.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let people = db.graph("social", "people")?;
+/// people.add_edge(&alice, &bob, "friend")?;
+/// people.add_edge(&bob, &carol, "friend")?;
+///
+/// let friends = people.neighbors(&alice, Direction::Outgoing, Some("friend"))?;
+/// let path = people.shortest_path(&alice, &carol, &Traversal::new().label("friend"))?;
+/// ```
+#[derive(Clone)]
+pub struct Graph {
+    inner: Arc<GraphInner>,
+}
+
+struct GraphInner {
+    name: String,
+    vertices: NitriteCollection,
+    edges: NitriteCollection,
+}
+
+pub(crate) fn graph_collection_name(name: &str) -> String {
+    format!("{}{}{}", GRAPH_PREFIX, INTERNAL_NAME_SEPARATOR, name)
+}
+
+/// Removes the vertex collection from the attributes of a graph about to be dropped.
+pub(crate) fn forget_graph_vertices(edges: &NitriteCollection) -> NitriteResult<()> {
+    let mut attributes = edges.get_attributes()?;
+    if attributes.remove(GRAPH_VERTICES).is_some() {
+        edges.set_attributes(attributes)?;
+    }
+    Ok(())
+}
+
+impl Graph {
+    pub(crate) fn open(
+        name: &str,
+        vertices: NitriteCollection,
+        edges: NitriteCollection,
+    ) -> NitriteResult<Graph> {
+        let vertex_collection = vertices.name();
+        let mut attributes = edges.get_attributes()?;
+        match attributes.get(GRAPH_VERTICES).and_then(Value::as_string) {
+            Some(existing) if *existing != vertex_collection => {
+                log::error!(
+                    "Graph {} is over collection {}, not {}",
+                    name,
+                    existing,
+                    vertex_collection
+                );
+                return Err(NitriteError::new(
+                    &format!("Graph {} is over collection {}", name, existing),
+                    ErrorKind::ValidationError,
+                ));
+            }
+            Some(_) => {}
+            None => {
+                attributes.put(GRAPH_VERTICES, Value::from(vertex_collection.as_str()));
+                edges.set_attributes(attributes)?;
+            }
+        }
+
+        // the unique index also answers the lookups by the start of the edges
+        if !edges.has_index(vec![FROM, TO, LABEL])? {
+            edges.create_index(vec![FROM, TO, LABEL], &unique_index())?;
+        }
+        if !edges.has_index(vec![TO])? {
+            edges.create_index(vec![TO], &non_unique_index())?;
+        }
+
+        Ok(Graph {
+            inner: Arc::new(GraphInner {
+                name: name.to_string(),
+                vertices,
+                edges,
+            }),
+        })
+    }
+
+    /// Returns the name of the graph.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the collection of the vertices.
+    pub fn vertices(&self) -> &NitriteCollection {
+        &self.inner.vertices
+    }
+
+    /// Adds an edge with `label` from one vertex to another. Returns `false` if the
+    /// graph already has it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if either vertex is not in the vertex collection.
+    pub fn add_edge(&self, from: &NitriteId, to: &NitriteId, label: &str) -> NitriteResult<bool> {
+        for id in [from, to] {
+            if self.inner.vertices.get_by_id(id)?.is_none() {
+                log::error!("Vertex {} not found in graph {}", id, self.inner.name);
+                return Err(NitriteError::new(
+                    &format!("Vertex {} not found in graph {}", id, self.inner.name),
+                    ErrorKind::NotFound,
+                ));
+            }
+        }
+
+        if self.inner.edges.find(edge_filter(from, to, label))?.next().is_some() {
+            return Ok(false);
+        }
+
+        let mut edge = Document::new();
+        edge.put(FROM, *from)?;
+        edge.put(TO, *to)?;
+        edge.put(LABEL, label)?;
+        match self.inner.edges.insert(edge) {
+            Ok(_) => Ok(true),
+            // added concurrently since the lookup
+            Err(error) if error.kind() == &ErrorKind::UniqueConstraintViolation => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Removes the edge with `label` from one vertex to another. Returns `false` if the
+    /// graph doesn't have it.
+    pub fn remove_edge(&self, from: &NitriteId, to: &NitriteId, label: &str) -> NitriteResult<bool> {
+        let filter = edge_filter(from, to, label);
+        Ok(!self.inner.edges.remove(filter, true)?.affected_nitrite_ids().is_empty())
+    }
+
+    /// Removes a vertex from the vertex collection, with every edge from or to it.
+    /// Returns `false` if there was no such vertex.
+    pub fn remove_vertex(&self, id: &NitriteId) -> NitriteResult<bool> {
+        self.inner.edges.remove(edges_of(id, Direction::Both), false)?;
+        let Some(vertex) = self.inner.vertices.get_by_id(id)? else {
+            return Ok(false);
+        };
+        self.inner.vertices.remove_one(&vertex)?;
+        Ok(true)
+    }
+
+    /// Returns the edges of a vertex in `direction`.
+    pub fn edges(&self, id: &NitriteId, direction: Direction) -> NitriteResult<Vec<Edge>> {
+        let mut edges = Vec::new();
+        for document in self.inner.edges.find(edges_of(id, direction))? {
+            edges.push(read_edge(&document?)?);
+        }
+        Ok(edges)
+    }
+
+    /// Returns the vertices one edge away from a vertex in `direction`, following only
+    /// the edges with `label` if one is given. Each neighbor is returned once.
+    pub fn neighbors(
+        &self,
+        id: &NitriteId,
+        direction: Direction,
+        label: Option<&str>,
+    ) -> NitriteResult<Vec<Document>> {
+        let mut neighbors = Vec::new();
+        for neighbor in self.neighbor_ids(id, direction, label)? {
+            if let Some(document) = self.inner.vertices.get_by_id(&neighbor)? {
+                neighbors.push(document);
+            }
+        }
+        Ok(neighbors)
+    }
+
+    /// Visits the vertices reachable from `start` breadth-first: nearest first, each
+    /// with its smallest depth. The start is visited first, at depth 0.
+    pub fn bfs(&self, start: &NitriteId, traversal: &Traversal) -> NitriteResult<Vec<Visit>> {
+        let mut visits = Vec::new();
+        let mut seen = HashSet::from([*start]);
+        let mut queue = VecDeque::from([(*start, 0)]);
+        while let Some((id, depth)) = queue.pop_front() {
+            let Some(document) = self.inner.vertices.get_by_id(&id)? else {
+                continue;
+            };
+            visits.push(Visit { document, depth });
+            if !traversal.can_expand(depth) {
+                continue;
+            }
+            for neighbor in self.neighbor_ids(&id, traversal.direction, traversal.get_label())? {
+                if seen.insert(neighbor) {
+                    queue.push_back((neighbor, depth + 1));
+                }
+            }
+        }
+        Ok(visits)
+    }
+
+    /// Visits the vertices reachable from `start` depth-first, following each branch
+    /// before the next. The start is visited first, at depth 0; a vertex is visited
+    /// once, at the depth it was first reached.
+    pub fn dfs(&self, start: &NitriteId, traversal: &Traversal) -> NitriteResult<Vec<Visit>> {
+        let mut visits = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![(*start, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            let Some(document) = self.inner.vertices.get_by_id(&id)? else {
+                continue;
+            };
+            visits.push(Visit { document, depth });
+            if !traversal.can_expand(depth) {
+                continue;
+            }
+            let neighbors = self.neighbor_ids(&id, traversal.direction, traversal.get_label())?;
+            // pushed in reverse so the first neighbor is explored first
+            for neighbor in neighbors.into_iter().rev() {
+                if !seen.contains(&neighbor) {
+                    stack.push((neighbor, depth + 1));
+                }
+            }
+        }
+        Ok(visits)
+    }
+
+    /// Returns the vertices of a path with the fewest edges from `from` to `to`, both
+    /// included, or `None` if `to` can't be reached within the depth limit.
+    pub fn shortest_path(
+        &self,
+        from: &NitriteId,
+        to: &NitriteId,
+        traversal: &Traversal,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        if self.inner.vertices.get_by_id(from)?.is_none() {
+            return Ok(None);
+        }
+
+        let mut parents: HashMap<NitriteId, NitriteId> = HashMap::new();
+        let mut seen = HashSet::from([*from]);
+        let mut queue = VecDeque::from([(*from, 0)]);
+        let mut found = from == to;
+        while let Some((id, depth)) = queue.pop_front() {
+            if found || !traversal.can_expand(depth) {
+                break;
+            }
+            for neighbor in self.neighbor_ids(&id, traversal.direction, traversal.get_label())? {
+                // an edge to a removed vertex leads nowhere
+                if !seen.insert(neighbor) || self.inner.vertices.get_by_id(&neighbor)?.is_none() {
+                    continue;
+                }
+                parents.insert(neighbor, id);
+                if neighbor == *to {
+                    found = true;
+                    break;
+                }
+                queue.push_back((neighbor, depth + 1));
+            }
+        }
+        if !found {
+            return Ok(None);
+        }
+
+        let mut ids = vec![*to];
+        while let Some(parent) = parents.get(ids.last().unwrap_or(to)) {
+            ids.push(*parent);
+        }
+        let mut path = Vec::with_capacity(ids.len());
+        for id in ids.into_iter().rev() {
+            match self.inner.vertices.get_by_id(&id)? {
+                Some(document) => path.push(document),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(path))
+    }
+
+    /// Returns the ids at the other end of the edges of a vertex, sorted, each once.
+    fn neighbor_ids(
+        &self,
+        id: &NitriteId,
+        direction: Direction,
+        label: Option<&str>,
+    ) -> NitriteResult<Vec<NitriteId>> {
+        let mut filter = edges_of(id, direction);
+        if let Some(label) = label {
+            filter = and(vec![filter, field(LABEL).eq(label)]);
+        }
+
+        let mut neighbors = Vec::new();
+        for document in self.inner.edges.find(filter)? {
+            let edge = read_edge(&document?)?;
+            // a self loop reached in both directions leads back to the vertex
+            neighbors.push(if edge.from == *id { edge.to } else { edge.from });
+        }
+        neighbors.sort();
+        neighbors.dedup();
+        Ok(neighbors)
+    }
+}
+
+fn edge_filter(from: &NitriteId, to: &NitriteId, label: &str) -> Filter {
+    and(vec![
+        field(FROM).eq(*from),
+        field(TO).eq(*to),
+        field(LABEL).eq(label),
+    ])
+}
+
+fn edges_of(id: &NitriteId, direction: Direction) -> Filter {
+    match direction {
+        Direction::Outgoing => field(FROM).eq(*id),
+        Direction::Incoming => field(TO).eq(*id),
+        Direction::Both => or(vec![field(FROM).eq(*id), field(TO).eq(*id)]),
+    }
+}
+
+fn read_edge(document: &Document) -> NitriteResult<Edge> {
+    let from = document.get(FROM)?.as_nitrite_id().copied();
+    let to = document.get(TO)?.as_nitrite_id().copied();
+    let label = document.get(LABEL)?.as_string().cloned();
+    match (from, to, label) {
+        (Some(from), Some(to), Some(label)) => Ok(Edge { from, to, label }),
+        _ => {
+            log::error!("Invalid graph edge {}", document);
+            Err(NitriteError::new("Invalid graph edge", ErrorKind::InternalError))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use crate::nitrite::Nitrite;
+
+    fn setup_graph() -> (Nitrite, Graph, Vec<NitriteId>) {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let people = db.collection("people").unwrap();
+        let ids = ["alice", "bob", "carol", "dave", "eve"]
+            .into_iter()
+            .map(|name| {
+                let mut person = doc! { name: name };
+                let id = person.id().unwrap();
+                people.insert(person).unwrap();
+                id
+            })
+            .collect();
+        let graph = db.graph("social", "people").unwrap();
+        (db, graph, ids)
+    }
+
+    fn names(documents: &[Document]) -> Vec<String> {
+        documents
+            .iter()
+            .map(|document| document.get("name").unwrap().as_string().unwrap().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_edges_and_neighbors() -> NitriteResult<()> {
+        let (_db, graph, ids) = setup_graph();
+        assert!(graph.add_edge(&ids[0], &ids[1], "friend")?);
+        assert!(!graph.add_edge(&ids[0], &ids[1], "friend")?);
+        assert!(graph.add_edge(&ids[0], &ids[2], "manager")?);
+        assert!(graph.add_edge(&ids[3], &ids[0], "friend")?);
+
+        let outgoing = graph.neighbors(&ids[0], Direction::Outgoing, None)?;
+        assert_eq!(names(&outgoing), vec!["bob", "carol"]);
+        let friends = graph.neighbors(&ids[0], Direction::Both, Some("friend"))?;
+        assert_eq!(names(&friends), vec!["bob", "dave"]);
+        assert_eq!(graph.edges(&ids[0], Direction::Incoming)?.len(), 1);
+
+        assert!(graph.remove_edge(&ids[0], &ids[1], "friend")?);
+        assert!(!graph.remove_edge(&ids[0], &ids[1], "friend")?);
+        assert_eq!(graph.edges(&ids[0], Direction::Both)?.len(), 2);
+
+        let missing = NitriteId::create_id(1_000_000_000_000_000_001).unwrap();
+        let error = graph.add_edge(&ids[0], &missing, "friend").err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversals() -> NitriteResult<()> {
+        let (_db, graph, ids) = setup_graph();
+        // alice -> bob -> carol -> dave, alice -> carol, eve -> alice
+        graph.add_edge(&ids[0], &ids[1], "friend")?;
+        graph.add_edge(&ids[1], &ids[2], "friend")?;
+        graph.add_edge(&ids[2], &ids[3], "friend")?;
+        graph.add_edge(&ids[0], &ids[2], "colleague")?;
+        graph.add_edge(&ids[4], &ids[0], "friend")?;
+
+        let visits = graph.bfs(&ids[0], &Traversal::new())?;
+        let depths: Vec<usize> = visits.iter().map(|visit| visit.depth).collect();
+        assert_eq!(depths, vec![0, 1, 1, 2]);
+
+        let friends = Traversal::new().label("friend").max_depth(2);
+        let visits = graph.bfs(&ids[0], &friends)?;
+        let documents: Vec<Document> = visits.into_iter().map(|visit| visit.document).collect();
+        assert_eq!(names(&documents), vec!["alice", "bob", "carol"]);
+
+        let visits = graph.dfs(&ids[0], &Traversal::new().label("friend"))?;
+        let documents: Vec<Document> = visits.into_iter().map(|visit| visit.document).collect();
+        assert_eq!(names(&documents), vec!["alice", "bob", "carol", "dave"]);
+
+        let path = graph.shortest_path(&ids[0], &ids[3], &Traversal::new())?.unwrap();
+        assert_eq!(names(&path), vec!["alice", "carol", "dave"]);
+        let path = graph
+            .shortest_path(&ids[3], &ids[4], &Traversal::new().direction(Direction::Incoming))?
+            .unwrap();
+        assert_eq!(names(&path), vec!["dave", "carol", "alice", "eve"]);
+        assert!(graph.shortest_path(&ids[3], &ids[0], &Traversal::new())?.is_none());
+        assert!(graph
+            .shortest_path(&ids[0], &ids[3], &Traversal::new().max_depth(1))?
+            .is_none());
+
+        // removing a vertex removes its edges
+        assert!(graph.remove_vertex(&ids[2])?);
+        assert!(graph.shortest_path(&ids[0], &ids[3], &Traversal::new())?.is_none());
+        assert!(graph.edges(&ids[3], Direction::Incoming)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_is_bound_to_its_vertices() {
+        let (db, _graph, _ids) = setup_graph();
+        db.collection("companies").unwrap();
+        let error = db.graph("social", "companies").err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+        assert!(!db.list_collection_names().unwrap().iter().any(|name| name.contains("social")));
+
+        db.drop_graph("social").unwrap();
+        assert!(db.graph("social", "companies").is_ok());
+    }
+}
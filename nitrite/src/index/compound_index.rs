@@ -3,18 +3,27 @@ use super::{
     nitrite_index::NitriteIndexProvider, IndexDescriptor, IndexMap,
 };
 use crate::{
-    collection::{FindPlan, NitriteId},
+    collection::{Document, FindPlan, NitriteId},
+    common::{AttributeAware, Key, INDEX_EXACT_KEYS},
     derive_index_map_name,
     errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::{is_equals_filter, is_in_filter, ComparisonMode, Filter, SortingAwareFilter},
     store::{NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider},
-    validate_index_field, common::Key, FieldValues, Value, UNIQUE_INDEX,
+    validate_index_field, FieldValues, Value, DOC_ID, UNIQUE_INDEX,
 };
 use itertools::Itertools;
 use once_cell::sync::Lazy;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 
+/// States of the exactness of the keys of a compound index, see
+/// [`CompoundIndexInner::has_exact_keys`].
+const KEYS_UNKNOWN: u8 = 0;
+const KEYS_EXACT: u8 = 1;
+const KEYS_INEXACT: u8 = 2;
+
 static UNIQUE_CONSTRAINT_ERROR: Lazy<NitriteError> = Lazy::new(|| {
     NitriteError::new(
         "Unique constraint violated",
@@ -153,6 +162,29 @@ impl NitriteIndexProvider for CompoundIndex {
         self.inner.find_nitrite_ids(find_plan)
     }
 
+    /// Returns whether every composite key holds the values of its document as they are.
+    ///
+    /// # Behavior
+    /// Keys stop being exact once a document with a multikey first field or an unsigned
+    /// value is indexed, and stay so until the index is dropped. Indexes holding entries
+    /// written before this was tracked are not exact either.
+    fn has_exact_keys(&self) -> NitriteResult<bool> {
+        self.inner.has_exact_keys(&self.inner.find_index_map()?)
+    }
+
+    /// Builds the documents matching a covered find plan from the composite keys.
+    ///
+    /// # Returns
+    /// A document per matching id holding `_id` and the indexed fields, or `None` if the
+    /// keys are not exact.
+    ///
+    /// # Behavior
+    /// The ids are found as by `find_nitrite_ids`, then their keys are read from the rows
+    /// of the first-field values the first filter selects.
+    fn find_covered(&self, find_plan: &FindPlan) -> NitriteResult<Option<Vec<Document>>> {
+        self.inner.find_covered(find_plan)
+    }
+
     /// Returns whether this compound index has a unique constraint.
     ///
     /// # Returns
@@ -169,6 +201,8 @@ impl NitriteIndexProvider for CompoundIndex {
 pub struct CompoundIndexInner {
     index_descriptor: IndexDescriptor,
     nitrite_store: NitriteStore,
+    /// Whether the keys are exact, loaded from the attributes of the index map.
+    exact_keys: AtomicU8,
 }
 
 impl CompoundIndexInner {
@@ -176,6 +210,7 @@ impl CompoundIndexInner {
         Self {
             index_descriptor,
             nitrite_store,
+            exact_keys: AtomicU8::new(KEYS_UNKNOWN),
         }
     }
 
@@ -324,6 +359,11 @@ impl CompoundIndexInner {
         validate_index_field(first_value, first_field)?;
 
         let index_map: NitriteMap = self.find_index_map()?;
+        // read before the first entry lands, while an untracked map is known to be new
+        if self.has_exact_keys(&index_map)? && !is_exact(field_values) {
+            self.set_exact_keys(&index_map, false)?;
+        }
+
         match first_value {
             None | Some(Value::Null) => {
                 self.add_index_element(&index_map, field_values, &Value::Null)?;
@@ -374,7 +414,145 @@ impl CompoundIndexInner {
     fn drop_index(&self) -> NitriteResult<()> {
         let index_map = self.find_index_map()?;
         index_map.clear()?;
+        // the attributes outlive the map, a recreated index starts with exact keys
+        self.set_exact_keys(&index_map, true)?;
         index_map.dispose()?;
+        self.exact_keys.store(KEYS_UNKNOWN, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns `true` if every key holds the values of its document as they are, so the
+    /// document can be rebuilt from it.
+    ///
+    /// The answer is kept in the attributes of the index map. An index map without it
+    /// is exact if it is empty, like a new one; otherwise its entries may predate the
+    /// tracking and are taken as inexact.
+    fn has_exact_keys(&self, index_map: &NitriteMap) -> NitriteResult<bool> {
+        match self.exact_keys.load(Ordering::Acquire) {
+            KEYS_EXACT => return Ok(true),
+            KEYS_INEXACT => return Ok(false),
+            _ => {}
+        }
+
+        let stored = index_map
+            .attributes()?
+            .and_then(|attributes| attributes.get(INDEX_EXACT_KEYS).cloned());
+        match stored {
+            Some(Value::Bool(exact)) => {
+                self.remember_exact_keys(exact);
+                Ok(exact)
+            }
+            _ => {
+                let exact = index_map.is_empty()?;
+                self.set_exact_keys(index_map, exact)?;
+                Ok(exact)
+            }
+        }
+    }
+
+    fn set_exact_keys(&self, index_map: &NitriteMap, exact: bool) -> NitriteResult<()> {
+        let mut attributes = index_map.attributes()?.unwrap_or_default();
+        attributes.put(INDEX_EXACT_KEYS, Value::Bool(exact));
+        index_map.set_attributes(attributes)?;
+        self.remember_exact_keys(exact);
+        Ok(())
+    }
+
+    fn remember_exact_keys(&self, exact: bool) {
+        let state = if exact { KEYS_EXACT } else { KEYS_INEXACT };
+        self.exact_keys.store(state, Ordering::Release);
+    }
+
+    fn find_covered(&self, find_plan: &FindPlan) -> NitriteResult<Option<Vec<Document>>> {
+        let index_map = self.find_index_map()?;
+        if !self.has_exact_keys(&index_map)? {
+            return Ok(None);
+        }
+        let Some(first_filter) = find_plan
+            .index_scan_filter()
+            .and_then(|index_scan_filter| index_scan_filter.filters().into_iter().next())
+        else {
+            return Ok(None);
+        };
+
+        let nitrite_ids = self.scan_index(find_plan, index_map.clone())?;
+        let mut pending: HashSet<NitriteId> = nitrite_ids.iter().copied().collect();
+        let mut keys = HashMap::with_capacity(pending.len());
+        for (lower, upper) in first_field_ranges(&first_filter)? {
+            if pending.is_empty() {
+                break;
+            }
+            self.read_keys(&index_map, lower.as_ref(), upper.as_ref(), &mut pending, &mut keys)?;
+        }
+        if !pending.is_empty() {
+            // the values of a key did not order as the filter expects, read the documents
+            return Ok(None);
+        }
+
+        let field_names = self.index_descriptor.index_fields().field_names();
+        let mut documents = Vec::with_capacity(nitrite_ids.len());
+        for nitrite_id in nitrite_ids {
+            let Some(values) = keys.remove(&nitrite_id) else {
+                continue;
+            };
+            let mut document = Document::new();
+            document.put(DOC_ID, Value::NitriteId(nitrite_id))?;
+            for (field_name, value) in field_names.iter().zip(values) {
+                document.put(field_name, value)?;
+            }
+            documents.push(document);
+        }
+        Ok(Some(documents))
+    }
+
+    /// Reads the field values of the `pending` ids from the keys whose first value is
+    /// between `lower` and `upper`, both included, moving the ids found to `keys`.
+    fn read_keys(
+        &self,
+        index_map: &NitriteMap,
+        lower: Option<&Value>,
+        upper: Option<&Value>,
+        pending: &mut HashSet<NitriteId>,
+        keys: &mut HashMap<NitriteId, Vec<Value>>,
+    ) -> NitriteResult<()> {
+        let field_count = self.field_count();
+        // returns `false` once past the upper bound or when every id is found
+        let mut visit = |key: &Key| -> bool {
+            let Value::Array(parts) = key else {
+                return true;
+            };
+            if parts.len() != field_count + 1 {
+                return true;
+            }
+            if upper.is_some_and(|upper| !parts[0].is_null() && parts[0] > *upper) {
+                return false;
+            }
+            if let Value::NitriteId(nitrite_id) = &parts[field_count] {
+                if pending.remove(nitrite_id) {
+                    keys.insert(*nitrite_id, parts[..field_count].to_vec());
+                }
+            }
+            !pending.is_empty()
+        };
+
+        match lower {
+            Some(lower) => {
+                let mut key = index_map.ceiling_key(&Value::Array(vec![lower.clone()]))?;
+                while let Some(current) = key {
+                    if !visit(&current) {
+                        break;
+                    }
+                    key = index_map.higher_key(&current)?;
+                }
+            }
+            None => {
+                for key in index_map.keys()? {
+                    if !visit(&key?) {
+                        break;
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
@@ -394,6 +572,53 @@ impl CompoundIndexInner {
     }
 }
 
+/// Returns `true` if the composite keys of a document keep its values as they are: its
+/// first field is not multikey and no value is unsigned.
+fn is_exact(field_values: &FieldValues) -> bool {
+    field_values.values().iter().all(|(_, value)| {
+        !matches!(
+            value,
+            Value::Array(_)
+                | Value::U8(_)
+                | Value::U16(_)
+                | Value::U32(_)
+                | Value::U64(_)
+                | Value::U128(_)
+                | Value::USize(_)
+        )
+    })
+}
+
+/// Returns the ranges of first-field values, bounds included, holding the keys a filter
+/// on the first field selects. Filters without bounds select the whole index.
+fn first_field_ranges(filter: &Filter) -> NitriteResult<Vec<(Option<Value>, Option<Value>)>> {
+    if is_equals_filter(filter) {
+        if let Some(value) = filter.get_field_value()? {
+            let value = normalize_index_value(&value);
+            return Ok(vec![(Some(value.clone()), Some(value))]);
+        }
+    } else if is_in_filter(filter) {
+        if let Some(Value::Array(values)) = filter.get_field_value()? {
+            let mut values: Vec<Value> = values.iter().map(normalize_index_value).collect();
+            values.sort();
+            values.dedup();
+            return Ok(values
+                .into_iter()
+                .map(|value| (Some(value.clone()), Some(value)))
+                .collect());
+        }
+    } else if let Some(comparison) = filter.as_any().downcast_ref::<SortingAwareFilter>() {
+        if let Some(value) = comparison.field_value() {
+            let value = normalize_index_value(value);
+            return Ok(match comparison.comparison_mode() {
+                ComparisonMode::Greater | ComparisonMode::GreaterEqual => vec![(Some(value), None)],
+                ComparisonMode::Lesser | ComparisonMode::LesserEqual => vec![(None, Some(value))],
+            });
+        }
+    }
+    Ok(vec![(None, None)])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -678,4 +903,114 @@ mod tests {
         // Should return empty vec with no filter
         assert_eq!(ids.len(), 0);
     }
+
+    fn field_values_of(first: Value, second: Value) -> FieldValues {
+        FieldValues::new(
+            vec![("field1".to_string(), first), ("field2".to_string(), second)],
+            NitriteId::new(),
+            Fields::with_names(vec!["field1", "field2"]).unwrap(),
+        )
+    }
+
+    fn covered_plan(filters: Vec<Filter>) -> FindPlan {
+        let mut find_plan = FindPlan::new();
+        find_plan.set_index_descriptor(create_test_index_descriptor());
+        find_plan.set_index_scan_filter(crate::filter::IndexScanFilter::new(filters));
+        find_plan
+    }
+
+    #[test]
+    fn test_compound_index_keys_exact_until_inexact_write() {
+        let compound_index =
+            CompoundIndex::new(create_test_index_descriptor(), NitriteStore::default());
+        assert!(compound_index.has_exact_keys().unwrap());
+
+        compound_index
+            .write(&field_values_of(Value::from("a"), Value::I32(1)))
+            .unwrap();
+        assert!(compound_index.has_exact_keys().unwrap());
+
+        compound_index
+            .write(&field_values_of(Value::from("b"), Value::U32(2)))
+            .unwrap();
+        assert!(!compound_index.has_exact_keys().unwrap());
+
+        // a fresh handle reads the state back from the index map
+        let reopened = CompoundIndex::new(
+            create_test_index_descriptor(),
+            compound_index.inner.nitrite_store.clone(),
+        );
+        assert!(!reopened.has_exact_keys().unwrap());
+
+        compound_index.drop_index().unwrap();
+        assert!(compound_index.has_exact_keys().unwrap());
+    }
+
+    #[test]
+    fn test_compound_index_multikey_keys_are_inexact() {
+        let compound_index =
+            CompoundIndex::new(create_test_index_descriptor(), NitriteStore::default());
+        compound_index
+            .write(&field_values_of(
+                Value::Array(vec![Value::from("a"), Value::from("b")]),
+                Value::I32(1),
+            ))
+            .unwrap();
+        assert!(!compound_index.has_exact_keys().unwrap());
+
+        let find_plan = covered_plan(vec![crate::filter::field("field1").eq("a")]);
+        assert!(compound_index.find_covered(&find_plan).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_compound_index_untracked_entries_are_inexact() {
+        let compound_index =
+            CompoundIndex::new(create_test_index_descriptor(), NitriteStore::default());
+        let index_map = compound_index.find_index_map().unwrap();
+        let field_values = create_test_field_values();
+        compound_index
+            .add_index_element(&index_map, &field_values, &Value::from("value1"))
+            .unwrap();
+        assert!(!compound_index.has_exact_keys().unwrap());
+    }
+
+    #[test]
+    fn test_compound_index_find_covered_builds_documents_from_keys() {
+        use crate::filter::field;
+
+        let compound_index =
+            CompoundIndex::new(create_test_index_descriptor(), NitriteStore::default());
+        let rows = [("a", 1), ("a", 2), ("b", 3), ("c", 4)];
+        let mut ids = Vec::new();
+        for (first, second) in rows {
+            let field_values = field_values_of(Value::from(first), Value::I32(second));
+            ids.push(*field_values.nitrite_id());
+            compound_index.write(&field_values).unwrap();
+        }
+
+        let find_plan = covered_plan(vec![field("field1").eq("a"), field("field2").gt(1)]);
+        let documents = compound_index.find_covered(&find_plan).unwrap().unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].get(DOC_ID).unwrap(), Value::NitriteId(ids[1]));
+        assert_eq!(documents[0].get("field1").unwrap(), Value::from("a"));
+        assert_eq!(documents[0].get("field2").unwrap(), Value::I32(2));
+        assert_eq!(documents[0].size(), 3);
+
+        let find_plan = covered_plan(vec![field("field1").gte("b")]);
+        let documents = compound_index.find_covered(&find_plan).unwrap().unwrap();
+        let mut seconds: Vec<Value> = documents
+            .iter()
+            .map(|document| document.get("field2").unwrap())
+            .collect();
+        seconds.sort();
+        assert_eq!(seconds, vec![Value::I32(3), Value::I32(4)]);
+
+        let find_plan = covered_plan(vec![field("field1").in_array(vec!["c", "a"])]);
+        let documents = compound_index.find_covered(&find_plan).unwrap().unwrap();
+        assert_eq!(documents.len(), 3);
+
+        let find_plan = covered_plan(vec![field("field1").lt("b")]);
+        let documents = compound_index.find_covered(&find_plan).unwrap().unwrap();
+        assert_eq!(documents.len(), 2);
+    }
 }
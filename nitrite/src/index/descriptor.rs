@@ -6,6 +6,8 @@ use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::repository::{EntityId, EntityIndex, NitriteEntity};
 use crate::{Convertible, Value};
 
+use super::IndexOptions;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 /// Describes the configuration of an index on a collection.
 ///
@@ -58,6 +60,51 @@ impl IndexDescriptor {
                 index_type: index_type.to_string(),
                 index_fields,
                 collection_name: collection_name.to_string(),
+                case_insensitive: false,
+                sparse: false,
+                compressed: false,
+            }),
+        }
+    }
+
+    /// Returns a copy of this descriptor with string keys compared case-insensitively.
+    pub(crate) fn with_case_insensitive(&self, case_insensitive: bool) -> Self {
+        Self {
+            inner: Arc::new(IndexDescriptorInner {
+                index_type: self.inner.index_type.clone(),
+                index_fields: self.inner.index_fields.clone(),
+                collection_name: self.inner.collection_name.clone(),
+                case_insensitive,
+                sparse: self.inner.sparse,
+                compressed: self.inner.compressed,
+            }),
+        }
+    }
+
+    /// Returns a copy of this descriptor that skips null or missing values.
+    pub(crate) fn with_sparse(&self, sparse: bool) -> Self {
+        Self {
+            inner: Arc::new(IndexDescriptorInner {
+                index_type: self.inner.index_type.clone(),
+                index_fields: self.inner.index_fields.clone(),
+                collection_name: self.inner.collection_name.clone(),
+                case_insensitive: self.inner.case_insensitive,
+                sparse,
+                compressed: self.inner.compressed,
+            }),
+        }
+    }
+
+    /// Returns a copy of this descriptor that stores compressed posting lists.
+    pub(crate) fn with_compressed(&self, compressed: bool) -> Self {
+        Self {
+            inner: Arc::new(IndexDescriptorInner {
+                index_type: self.inner.index_type.clone(),
+                index_fields: self.inner.index_fields.clone(),
+                collection_name: self.inner.collection_name.clone(),
+                case_insensitive: self.inner.case_insensitive,
+                sparse: self.inner.sparse,
+                compressed,
             }),
         }
     }
@@ -93,6 +140,38 @@ impl IndexDescriptor {
     pub fn is_compound_index(&self) -> bool {
         self.inner.index_fields.field_names().len() > 1
     }
+
+    /// Returns the options this index was created with.
+    pub(crate) fn index_options(&self) -> IndexOptions {
+        IndexOptions::new(&self.inner.index_type)
+            .case_insensitive(self.inner.case_insensitive)
+            .sparse(self.inner.sparse)
+            .compressed(self.inner.compressed)
+    }
+
+    /// Determines whether the index compares string keys case-insensitively.
+    ///
+    /// # Returns
+    /// `true` if the index stores lower-cased string keys.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.inner.case_insensitive
+    }
+
+    /// Determines whether the index skips documents with null or missing values.
+    ///
+    /// # Returns
+    /// `true` if documents whose indexed fields are all null are not indexed.
+    pub fn is_sparse(&self) -> bool {
+        self.inner.sparse
+    }
+
+    /// Determines whether the index stores its ids as compressed posting lists.
+    ///
+    /// # Returns
+    /// `true` if the ids of each value are kept in delta-encoded blocks.
+    pub fn is_compressed(&self) -> bool {
+        self.inner.compressed
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -104,6 +183,9 @@ pub struct IndexDescriptorInner {
     index_type: String,
     index_fields: Fields,
     collection_name: String,
+    case_insensitive: bool,
+    sparse: bool,
+    compressed: bool,
 }
 
 impl IndexDescriptorInner {
@@ -116,6 +198,9 @@ impl IndexDescriptorInner {
             index_type,
             index_fields,
             collection_name,
+            case_insensitive: false,
+            sparse: false,
+            compressed: false,
         }
     }
 }
@@ -136,6 +221,15 @@ impl Convertible for IndexDescriptor {
         doc.put("index_type", Value::String(self.index_type()))?;
         doc.put("index_fields", self.index_fields().to_value()?)?;
         doc.put("collection_name", Value::String(self.collection_name()))?;
+        if self.is_case_insensitive() {
+            doc.put("case_insensitive", Value::Bool(true))?;
+        }
+        if self.is_sparse() {
+            doc.put("sparse", Value::Bool(true))?;
+        }
+        if self.is_compressed() {
+            doc.put("compressed", Value::Bool(true))?;
+        }
         Ok(Value::Document(doc))
     }
 
@@ -175,7 +269,14 @@ impl Convertible for IndexDescriptor {
                         ErrorKind::ObjectMappingError
                     ))?
                     .clone();
-                Ok(IndexDescriptor::new(&index_type, index_fields, &collection_name))
+                // Options are only stored when set, so older descriptors lack them
+                let case_insensitive = matches!(doc.get("case_insensitive")?, Value::Bool(true));
+                let sparse = matches!(doc.get("sparse")?, Value::Bool(true));
+                let compressed = matches!(doc.get("compressed")?, Value::Bool(true));
+                Ok(IndexDescriptor::new(&index_type, index_fields, &collection_name)
+                    .with_case_insensitive(case_insensitive)
+                    .with_sparse(sparse)
+                    .with_compressed(compressed))
             }
             _ => {
                 log::error!("Failed to create IndexDescriptor from Value {:?}", value);
@@ -246,6 +347,38 @@ mod tests {
         assert_eq!(descriptor.collection_name(), "collection1");
     }
 
+    #[test]
+    fn test_case_insensitive_round_trip() {
+        let fields = Fields::with_names(vec!["email"]).unwrap();
+        let descriptor = IndexDescriptor::new("type1", fields, "collection1");
+        assert!(!descriptor.is_case_insensitive());
+
+        let descriptor = descriptor.with_case_insensitive(true);
+        let restored = IndexDescriptor::from_value(&descriptor.to_value().unwrap()).unwrap();
+        assert!(restored.is_case_insensitive());
+        assert_eq!(restored, descriptor);
+    }
+
+    #[test]
+    fn test_sparse_round_trip() {
+        let fields = Fields::with_names(vec!["referral_code"]).unwrap();
+        let descriptor = IndexDescriptor::new("type1", fields, "collection1").with_sparse(true);
+        let restored = IndexDescriptor::from_value(&descriptor.to_value().unwrap()).unwrap();
+        assert!(restored.is_sparse());
+        assert!(!restored.is_case_insensitive());
+        assert!(restored.index_options().is_sparse());
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let fields = Fields::with_names(vec!["status"]).unwrap();
+        let descriptor = IndexDescriptor::new("type1", fields, "collection1").with_compressed(true);
+        let restored = IndexDescriptor::from_value(&descriptor.to_value().unwrap()).unwrap();
+        assert!(restored.is_compressed());
+        assert!(!restored.is_sparse());
+        assert!(restored.index_options().is_compressed());
+    }
+
     #[test]
     fn test_from_value_invalid() {
         let value = Value::String("invalid".to_string());
@@ -0,0 +1,85 @@
+use std::fmt::Display;
+
+use super::IndexDescriptor;
+
+/// Tells the query planner which index to use for a query.
+///
+/// The planner normally chooses the index itself. A hint overrides that choice, either
+/// forcing the index on a given set of fields or forcing a scan of the whole collection.
+/// Hints are set with `FindOptions::hint()` or `Filter::with_hint()`; a hint naming an
+/// index that does not exist, or one that cannot serve the query, fails the `find()`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::collection::FindOptions;
+/// use nitrite::filter::field;
+/// use nitrite::index::IndexHint;
+///
+/// // use the index on `region`
+/// let options = FindOptions::new().hint("region");
+/// let cursor = collection.find_with_options(filter, &options)?;
+///
+/// // use the compound index on `last_name, first_name`
+/// let filter = field("last_name").eq("Doe").with_hint(vec!["last_name", "first_name"]);
+///
+/// // ignore all indexes
+/// let options = FindOptions::new().hint(IndexHint::CollectionScan);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IndexHint {
+    /// Use the index on these fields.
+    Index(Vec<String>),
+    /// Do not use any index and scan the whole collection.
+    CollectionScan,
+}
+
+impl IndexHint {
+    /// Returns `true` if this hint names the index described by `index_descriptor`.
+    pub(crate) fn is_index(&self, index_descriptor: &IndexDescriptor) -> bool {
+        match self {
+            IndexHint::Index(field_names) => {
+                index_descriptor.index_fields().field_names() == *field_names
+            }
+            IndexHint::CollectionScan => false,
+        }
+    }
+}
+
+impl Display for IndexHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexHint::Index(field_names) => write!(f, "index on {:?}", field_names),
+            IndexHint::CollectionScan => write!(f, "collection scan"),
+        }
+    }
+}
+
+impl From<&str> for IndexHint {
+    fn from(field_name: &str) -> Self {
+        IndexHint::Index(vec![field_name.to_string()])
+    }
+}
+
+impl From<Vec<&str>> for IndexHint {
+    fn from(field_names: Vec<&str>) -> Self {
+        IndexHint::Index(field_names.into_iter().map(String::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Fields, NON_UNIQUE_INDEX};
+
+    #[test]
+    fn test_hint_matches_index_fields() {
+        let fields = Fields::with_names(vec!["a", "b"]).unwrap();
+        let descriptor = IndexDescriptor::new(NON_UNIQUE_INDEX, fields, "test");
+
+        assert!(IndexHint::from(vec!["a", "b"]).is_index(&descriptor));
+        assert!(!IndexHint::from("a").is_index(&descriptor));
+        assert!(!IndexHint::CollectionScan.is_index(&descriptor));
+        assert_eq!(IndexHint::from("a").to_string(), "index on [\"a\"]");
+    }
+}
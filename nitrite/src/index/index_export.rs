@@ -0,0 +1,304 @@
+//! Portable index data, moved between copies of a collection instead of rebuilding an index.
+
+use crate::{
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    index::IndexOptions,
+};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The version of the index export format written by this release.
+pub const INDEX_EXPORT_VERSION: u32 = 2;
+
+const INDEX_EXPORT_MAGIC: &[u8; 8] = b"NITRIDX\0";
+
+/// The data of an index, exported by
+/// [`NitriteCollection::export_index`](crate::collection::NitriteCollection) to be
+/// imported into another copy of the collection, on another machine for example, instead
+/// of rebuilding the index from the documents.
+///
+/// The export holds the data of the indexer as it is, with the definition of the index
+/// and the content hash of the collection it was taken from. An import checks that hash
+/// against the collection it goes into, so the index always matches the documents it
+/// answers for. Only indexers that store their entries outside of the collection, like
+/// the full-text indexes of `nitrite_tantivy_fts`, can export them.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let export = collection.export_index(vec!["body"])?;
+/// export.write_to_file("body.idx")?;
+///
+/// // on the other machine, once the documents are restored
+/// let export = IndexExport::read_from_file("body.idx")?;
+/// restored.import_index(&export)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexExport {
+    collection_name: String,
+    field_names: Vec<String>,
+    index_type: String,
+    case_insensitive: bool,
+    sparse: bool,
+    compressed: bool,
+    content_hash: u128,
+    data: Vec<u8>,
+}
+
+impl IndexExport {
+    pub(crate) fn new(
+        collection_name: String,
+        field_names: Vec<String>,
+        index_options: &IndexOptions,
+        content_hash: u128,
+        data: Vec<u8>,
+    ) -> Self {
+        IndexExport {
+            collection_name,
+            field_names,
+            index_type: index_options.index_type(),
+            case_insensitive: index_options.is_case_insensitive(),
+            sparse: index_options.is_sparse(),
+            compressed: index_options.is_compressed(),
+            content_hash,
+            data,
+        }
+    }
+
+    /// Returns the name of the collection the index was exported from.
+    pub fn collection_name(&self) -> &str {
+        &self.collection_name
+    }
+
+    /// Returns the indexed fields, in index order.
+    pub fn field_names(&self) -> &[String] {
+        &self.field_names
+    }
+
+    /// Returns the index type, as given by [`IndexOptions::index_type`].
+    pub fn index_type(&self) -> &str {
+        &self.index_type
+    }
+
+    /// Returns the options of the exported index.
+    pub fn index_options(&self) -> IndexOptions {
+        IndexOptions::new(&self.index_type)
+            .case_insensitive(self.case_insensitive)
+            .sparse(self.sparse)
+            .compressed(self.compressed)
+    }
+
+    /// Returns the content hash of the collection when the index was exported, see
+    /// [`NitriteCollection::content_hash`](crate::collection::NitriteCollection).
+    pub fn content_hash(&self) -> u128 {
+        self.content_hash
+    }
+
+    /// Returns the data of the indexer.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Writes the export to a new file at `path`, replacing any existing file.
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> NitriteResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads an export from the file at `path`.
+    pub fn read_from_file(path: impl AsRef<Path>) -> NitriteResult<IndexExport> {
+        IndexExport::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the export to `writer`.
+    ///
+    /// The format is binary and the same on every platform: a header naming the format
+    /// and its version, the definition of the index, the content hash and the data.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> NitriteResult<()> {
+        writer.write_all(INDEX_EXPORT_MAGIC)?;
+        writer.write_all(&INDEX_EXPORT_VERSION.to_le_bytes())?;
+        write_str(&mut writer, &self.collection_name)?;
+        writer.write_all(&(self.field_names.len() as u32).to_le_bytes())?;
+        for field_name in &self.field_names {
+            write_str(&mut writer, field_name)?;
+        }
+        write_str(&mut writer, &self.index_type)?;
+        writer.write_all(&[
+            u8::from(self.case_insensitive),
+            u8::from(self.sparse),
+            u8::from(self.compressed),
+        ])?;
+        writer.write_all(&self.content_hash.to_le_bytes())?;
+        writer.write_all(&(self.data.len() as u64).to_le_bytes())?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+
+    /// Reads an export written by [`IndexExport::write_to`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EncodingError` if the data is not an index export, is truncated or was
+    /// written by a newer format version. Exports of version 1 have no `compressed` flag
+    /// and are read as uncompressed.
+    pub fn read_from<R: Read>(mut reader: R) -> NitriteResult<IndexExport> {
+        let mut magic = [0u8; 8];
+        read_exact(&mut reader, &mut magic)?;
+        if &magic != INDEX_EXPORT_MAGIC {
+            return Err(encoding_error("not an index export"));
+        }
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        if version > INDEX_EXPORT_VERSION {
+            return Err(encoding_error(&format!(
+                "unsupported version {} (supported up to {})",
+                version, INDEX_EXPORT_VERSION
+            )));
+        }
+
+        let collection_name = read_str(&mut reader)?;
+        let field_count = u32::from_le_bytes(read_array(&mut reader)?);
+        let mut field_names = Vec::new();
+        for _ in 0..field_count {
+            field_names.push(read_str(&mut reader)?);
+        }
+        let index_type = read_str(&mut reader)?;
+        let [case_insensitive, sparse] = read_array(&mut reader)?;
+        let [compressed] = if version >= 2 { read_array(&mut reader)? } else { [0] };
+        let content_hash = u128::from_le_bytes(read_array(&mut reader)?);
+        let data_len = u64::from_le_bytes(read_array(&mut reader)?);
+
+        // read through `take` so a corrupt length cannot allocate more than is there
+        let mut data = Vec::new();
+        reader.take(data_len).read_to_end(&mut data)?;
+        if data.len() as u64 != data_len {
+            return Err(encoding_error("truncated data"));
+        }
+
+        Ok(IndexExport {
+            collection_name,
+            field_names,
+            index_type,
+            case_insensitive: case_insensitive != 0,
+            sparse: sparse != 0,
+            compressed: compressed != 0,
+            content_hash,
+            data,
+        })
+    }
+}
+
+fn write_str<W: Write>(writer: &mut W, value: &str) -> NitriteResult<()> {
+    writer.write_all(&(value.len() as u32).to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_str<R: Read>(reader: &mut R) -> NitriteResult<String> {
+    let len = u32::from_le_bytes(read_array(reader)?);
+    let mut bytes = Vec::new();
+    reader.take(u64::from(len)).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(encoding_error("truncated header"));
+    }
+    String::from_utf8(bytes).map_err(|_| encoding_error("invalid text in header"))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> NitriteResult<[u8; N]> {
+    let mut bytes = [0u8; N];
+    read_exact(reader, &mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_exact<R: Read>(reader: &mut R, bytes: &mut [u8]) -> NitriteResult<()> {
+    reader.read_exact(bytes).map_err(|err| match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => encoding_error("truncated header"),
+        _ => NitriteError::from(err),
+    })
+}
+
+fn encoding_error(message: &str) -> NitriteError {
+    log::error!("Invalid index export: {}", message);
+    NitriteError::new(
+        &format!("Invalid index export: {}", message),
+        ErrorKind::EncodingError,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{full_text_index, non_unique_index};
+
+    fn export() -> IndexExport {
+        IndexExport::new(
+            "articles".to_string(),
+            vec!["body".to_string()],
+            &full_text_index().sparse(true),
+            0x0123_4567_89ab_cdef_0011_2233_4455_6677,
+            vec![1, 2, 3, 4, 5],
+        )
+    }
+
+    #[test]
+    fn test_index_export_roundtrip() {
+        let mut bytes = Vec::new();
+        export().write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], INDEX_EXPORT_MAGIC);
+
+        let read = IndexExport::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read, export());
+        assert_eq!(read.field_names(), ["body".to_string()]);
+        assert!(read.index_options().is_sparse());
+        assert!(!read.index_options().is_case_insensitive());
+        assert!(!read.index_options().is_compressed());
+    }
+
+    #[test]
+    fn test_index_export_keeps_compressed_flag() {
+        let compressed = IndexExport::new(
+            "orders".to_string(),
+            vec!["status".to_string()],
+            &non_unique_index().compressed(true),
+            7,
+            vec![9],
+        );
+        let mut bytes = Vec::new();
+        compressed.write_to(&mut bytes).unwrap();
+        let read = IndexExport::read_from(bytes.as_slice()).unwrap();
+        assert!(read.index_options().is_compressed());
+        assert_eq!(read, compressed);
+    }
+
+    #[test]
+    fn test_index_export_reads_version_1() {
+        // version 1 wrote two flag bytes, without `compressed`
+        let mut bytes = Vec::new();
+        export().write_to(&mut bytes).unwrap();
+        bytes[8..12].copy_from_slice(&1u32.to_le_bytes());
+        let flags = 8 + 4 + (4 + 8) + 4 + (4 + 4) + (4 + export().index_type().len());
+        bytes.remove(flags + 2);
+
+        let read = IndexExport::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(read, export());
+    }
+
+    #[test]
+    fn test_index_export_rejects_invalid_data() {
+        let mut bytes = Vec::new();
+        export().write_to(&mut bytes).unwrap();
+
+        let err = IndexExport::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::EncodingError);
+        let err = IndexExport::read_from(&bytes[..20]).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::EncodingError);
+        let err = IndexExport::read_from(&b"not an export"[..]).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::EncodingError);
+
+        bytes[8..12].copy_from_slice(&(INDEX_EXPORT_VERSION + 1).to_le_bytes());
+        let err = IndexExport::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::EncodingError);
+    }
+}
@@ -1,9 +1,10 @@
 use once_cell::sync::Lazy;
 use smallvec::SmallVec;
 
+use super::posting_list::decode_block;
 use crate::collection::NitriteId;
 use crate::common::NavigableMap;
-use crate::common::{Key, Value};
+use crate::common::{FieldValues, Key, Value};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::store::{EntryIterator, NitriteMap, NitriteMapProvider};
 use std::collections::BTreeMap;
@@ -31,10 +32,16 @@ static INDEX_CORRUPT_ERROR: Lazy<NitriteError> = Lazy::new(|| {
 /// [`IndexLayout::Array`] is the classic `value -> Array[ids]` layout, still used for
 /// unique simple indexes (array length ≤ 1, where the uniqueness check depends on the
 /// single-array shape), compound-index sub-maps, and in-memory maps.
+///
+/// [`IndexLayout::Compressed`] is the composite layout of a single-field index created
+/// with [`IndexOptions::compressed`](super::IndexOptions::compressed): its rows are
+/// `[value, first_id] -> Bytes` blocks of delta-encoded ids (see `posting_list`), so a
+/// value matching millions of documents takes a few thousand rows instead of millions.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub(crate) enum IndexLayout {
     Array,
     Composite,
+    Compressed,
 }
 
 /// Canonicalizes a value used as the first component of a composite key.
@@ -57,6 +64,32 @@ pub(crate) fn normalize_index_value(value: &Value) -> Value {
     }
 }
 
+/// Lower-cases the strings of a value (including the elements of a multikey array) for a
+/// case-insensitive index. Other values are returned unchanged.
+pub(crate) fn fold_case(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.to_lowercase()),
+        Value::Array(values) => Value::Array(values.iter().map(fold_case).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Returns the field values a case-insensitive index stores for a document.
+pub(crate) fn fold_field_values(field_values: &FieldValues) -> FieldValues {
+    let values = field_values
+        .values()
+        .iter()
+        .map(|(name, value)| (name.clone(), fold_case(value)))
+        .collect();
+    FieldValues::new(values, *field_values.nitrite_id(), field_values.fields().clone())
+}
+
+/// Returns `true` if a sparse index leaves these field values out, i.e. every indexed
+/// field is null or missing.
+pub(crate) fn is_sparse_skipped(field_values: &FieldValues) -> bool {
+    field_values.values().iter().all(|(_, value)| value.is_null())
+}
+
 /// Builds the composite key `[value, id]` for the [`IndexLayout::Composite`] layout.
 pub(crate) fn composite_key(value: &Value, id: &NitriteId) -> Key {
     Value::Array(vec![normalize_index_value(value), Value::NitriteId(*id)])
@@ -125,6 +158,20 @@ fn reconstruct_group(rows: &[&[Value]], value_fields: usize) -> Value {
     Value::Map(map)
 }
 
+/// Appends the rows of one underlying composite entry to a group: the key tail itself
+/// for a `[.., id] -> null` row, or one `[id]` row per id of a compressed block.
+fn push_rows(rows: &mut Vec<Vec<Value>>, tail: &[Value], value: &Value) -> NitriteResult<()> {
+    match value {
+        Value::Bytes(block) => {
+            for id in decode_block(block)? {
+                rows.push(vec![Value::NitriteId(id)]);
+            }
+        }
+        _ => rows.push(tail.to_vec()),
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 /// Provides efficient key-value access for index data with navigable operations.
 ///
@@ -200,6 +247,16 @@ impl IndexMap {
         }
     }
 
+    /// Creates a new IndexMap over a persisted map of a compressed single-field index,
+    /// whose `[value, first_id]` rows hold blocks of ids. Navigation is the one of the
+    /// composite layout; `get` and `entries` decode the blocks into `Array[ids]`.
+    pub(crate) fn compressed(nitrite_map: NitriteMap) -> Self {
+        let inner_map = IndexMapInner::new(Some(nitrite_map), None, IndexLayout::Compressed, 1);
+        IndexMap {
+            inner: Arc::new(inner_map),
+        }
+    }
+
     /// Retrieves the value associated with a key.
     ///
     /// # Arguments
@@ -421,8 +478,14 @@ impl IndexMapInner {
                 Value::Array(parts)
                     if parts.first().map(|p| *p == target).unwrap_or(false) =>
                 {
-                    // Tail after the leading value: [v1, …, id].
-                    rows.push(parts[1..].to_vec());
+                    if self.layout == IndexLayout::Compressed {
+                        // A block of ids keyed by its first id.
+                        let block = map.get(&k)?.unwrap_or(Value::Null);
+                        push_rows(&mut rows, &parts[1..], &block)?;
+                    } else {
+                        // Tail after the leading value: [v1, …, id].
+                        rows.push(parts[1..].to_vec());
+                    }
                     key = map.higher_key(&k)?;
                 }
                 _ => break,
@@ -462,7 +525,7 @@ impl IndexMapInner {
     }
 
     pub fn get(&self, key: &Key) -> NitriteResult<Option<Value>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             return self.composite_get(key);
         }
         if let Some(ref nitrite_map) = self.nitrite_map {
@@ -479,7 +542,7 @@ impl IndexMapInner {
     }
 
     pub fn first_key(&self) -> NitriteResult<Option<Key>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             return Ok(Self::composite_value_of(self.composite_map()?.first_key()?));
         }
         if let Some(ref nitrite_map) = &self.nitrite_map {
@@ -495,7 +558,7 @@ impl IndexMapInner {
     }
 
     pub fn last_key(&self) -> NitriteResult<Option<Key>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             return Ok(Self::composite_value_of(self.composite_map()?.last_key()?));
         }
         if let Some(ref nitrite_map) = &self.nitrite_map {
@@ -511,7 +574,7 @@ impl IndexMapInner {
     }
 
     pub fn higher_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             // Next distinct leading value strictly greater than `key`.
             if self.arity == 1 {
                 // Single field: everything `[key, *]` is `<= [key, MAX_id]`, so the first
@@ -538,7 +601,7 @@ impl IndexMapInner {
     }
 
     pub fn ceiling_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             // First distinct leading value `>= key`: `[key]` sorts before every `[key, *]`, so
             // the ceiling of `[key]` is the first entry whose leading value is `>= key`.
             return Ok(Self::composite_value_of(
@@ -558,7 +621,7 @@ impl IndexMapInner {
     }
 
     pub fn lower_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             // Largest distinct leading value strictly less than `key`: `[key]` sorts before
             // every `[key, *]`, so the largest underlying key below `[key]` belongs to the
             // previous value.
@@ -579,7 +642,7 @@ impl IndexMapInner {
     }
 
     pub fn floor_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             // Largest distinct leading value `<= key`.
             if self.arity == 1 {
                 // Single field: every `[key, *]` is `<= [key, MAX_id]`, so the floor of
@@ -617,7 +680,7 @@ impl IndexMapInner {
     }
 
     pub fn entries(&self) -> NitriteResult<IndexMapIterator> {
-        if self.layout == IndexLayout::Composite {
+        if self.layout != IndexLayout::Array {
             // Group the sorted flat `[v0, …, id] -> ()` rows back into one entry per distinct
             // leading value — `(v0, Array[ids])` for a simple index, `(v0, Map{…})` for a
             // compound index — so callers (full scans, not-equals/not-in) see the same shape as
@@ -855,9 +918,13 @@ impl IndexMapIterator {
             },
         };
 
-        let (group_value, first_tail) = match &first.0 {
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+        let group_value = match &first.0 {
             Value::Array(parts) if !parts.is_empty() => {
-                (parts[0].clone(), parts[1..].to_vec())
+                if let Err(e) = push_rows(&mut rows, &parts[1..], &first.1) {
+                    return Some(Err(e));
+                }
+                parts[0].clone()
             }
             _ => {
                 log::error!("Composite index is in corrupt state: malformed key {:?}", first.0);
@@ -865,14 +932,15 @@ impl IndexMapIterator {
             }
         };
 
-        let mut rows: Vec<Vec<Value>> = vec![first_tail];
         loop {
             match iterator.next() {
                 Some(Ok(kv)) => match &kv.0 {
                     Value::Array(parts)
                         if parts.first().map(|p| *p == group_value).unwrap_or(false) =>
                     {
-                        rows.push(parts[1..].to_vec());
+                        if let Err(e) = push_rows(&mut rows, &parts[1..], &kv.1) {
+                            return Some(Err(e));
+                        }
                     }
                     Value::Array(_) => {
                         // Reached the next distinct leading value — buffer it for the next call.
@@ -1377,4 +1445,34 @@ mod tests {
         let count = entries.by_ref().filter(|e| e.is_ok()).count();
         assert_eq!(count, 3);
     }
+
+    #[test]
+    fn test_compressed_index_map_reads_blocks() {
+        use crate::index::posting_list::add_posting;
+
+        let map = NitriteStore::default().open_map("compressed").unwrap();
+        let mut open_ids = Vec::new();
+        for i in 0..1200 {
+            let id = NitriteId::new();
+            let value = Value::from(if i % 3 == 0 { "closed" } else { "open" });
+            if i % 3 != 0 {
+                open_ids.push(Value::NitriteId(id));
+            }
+            add_posting(&map, &value, &id).unwrap();
+        }
+
+        let index_map = IndexMap::compressed(map);
+        assert_eq!(index_map.first_key().unwrap(), Some(Value::from("closed")));
+        assert_eq!(
+            index_map.higher_key(&Value::from("closed")).unwrap(),
+            Some(Value::from("open"))
+        );
+        assert_eq!(index_map.higher_key(&Value::from("open")).unwrap(), None);
+        assert_eq!(index_map.get(&Value::from("open")).unwrap(), Some(Value::Array(open_ids)));
+
+        let groups: Vec<_> = index_map.entries().unwrap().map(|e| e.unwrap()).collect();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].1.as_array().unwrap().len(), 400);
+        assert_eq!(index_map.terminal_nitrite_ids().unwrap().len(), 1200);
+    }
 }
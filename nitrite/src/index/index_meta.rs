@@ -1,11 +1,13 @@
 use crate::{collection::Document, errors::{ErrorKind, NitriteError, NitriteResult}, Convertible, Value};
 
-use super::IndexDescriptor;
+use super::{IndexDescriptor, IndexStatistics};
 
 pub struct IndexMeta {
     index_descriptor: IndexDescriptor,
     index_map: String,
     is_dirty: bool,
+    is_disabled: bool,
+    statistics: Option<IndexStatistics>,
 }
 
 impl IndexMeta {
@@ -14,6 +16,8 @@ impl IndexMeta {
             index_descriptor,
             index_map,
             is_dirty: false,
+            is_disabled: false,
+            statistics: None,
         }
     }
 
@@ -32,6 +36,24 @@ impl IndexMeta {
     pub fn set_dirty(&mut self, dirty: bool) {
         self.is_dirty = dirty;
     }
+
+    /// Whether the index was disabled because it was found inconsistent, and is
+    /// neither used nor maintained until it is rebuilt.
+    pub fn is_disabled(&self) -> bool {
+        self.is_disabled
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.is_disabled = disabled;
+    }
+
+    pub fn statistics(&self) -> Option<IndexStatistics> {
+        self.statistics.clone()
+    }
+
+    pub fn set_statistics(&mut self, statistics: Option<IndexStatistics>) {
+        self.statistics = statistics;
+    }
 }
 
 impl Convertible for IndexMeta {
@@ -42,6 +64,12 @@ impl Convertible for IndexMeta {
         doc.put("index_descriptor", self.index_descriptor.to_value()?)?;
         doc.put("index_map", Value::String(self.index_map.clone()))?;
         doc.put("is_dirty", Value::Bool(self.is_dirty))?;
+        if self.is_disabled {
+            doc.put("is_disabled", Value::Bool(true))?;
+        }
+        if let Some(statistics) = &self.statistics {
+            doc.put("statistics", statistics.to_value()?)?;
+        }
         Ok(Value::Document(doc))
     }
 
@@ -72,11 +100,22 @@ impl Convertible for IndexMeta {
                             ErrorKind::ObjectMappingError,
                         )
                     })?;
-                    
+
+                // only written for a disabled index
+                let is_disabled = matches!(doc.get("is_disabled")?, Value::Bool(true));
+
+                // Statistics are only present once the index has been analyzed
+                let statistics = match doc.get("statistics")? {
+                    Value::Null => None,
+                    value => Some(IndexStatistics::from_value(&value)?),
+                };
+
                 Ok(IndexMeta {
                     index_descriptor,
                     index_map,
                     is_dirty,
+                    is_disabled,
+                    statistics,
                 })
             }
             _ => {
@@ -97,6 +136,7 @@ mod tests {
     use crate::common::{Fields, Value};
     use crate::errors::ErrorKind;
     use crate::index::IndexDescriptor;
+    use crate::store::{NitriteMapProvider, NitriteStoreProvider};
 
     fn create_fields() -> Fields {
         Fields::with_names(vec!["test_field"]).unwrap()
@@ -159,6 +199,32 @@ mod tests {
         assert!(!index_meta.is_dirty());
     }
 
+    #[test]
+    fn test_index_meta_statistics_round_trip() {
+        let map = crate::store::NitriteStore::default().open_map("stats").unwrap();
+        map.put(Value::from("a"), Value::Array(vec![])).unwrap();
+        let statistics = IndexStatistics::analyze(&map).unwrap();
+
+        let mut index_meta = IndexMeta::new(create_index_descriptor(), "test_map".to_string());
+        assert!(IndexMeta::from_value(&index_meta.to_value().unwrap()).unwrap().statistics().is_none());
+
+        index_meta.set_statistics(Some(statistics.clone()));
+        let restored = IndexMeta::from_value(&index_meta.to_value().unwrap()).unwrap();
+        assert_eq!(restored.statistics(), Some(statistics));
+    }
+
+    #[test]
+    fn test_index_meta_disabled_round_trip() {
+        let mut index_meta = IndexMeta::new(create_index_descriptor(), "test_map".to_string());
+        let value = index_meta.to_value().unwrap();
+        assert!(!IndexMeta::from_value(&value).unwrap().is_disabled());
+
+        index_meta.set_disabled(true);
+        let restored = IndexMeta::from_value(&index_meta.to_value().unwrap()).unwrap();
+        assert!(restored.is_disabled());
+        assert!(!restored.is_dirty());
+    }
+
     #[test]
     fn test_index_meta_from_value_invalid() {
         let value = Value::String("invalid".to_string());
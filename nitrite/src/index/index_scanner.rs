@@ -1,3 +1,4 @@
+use super::posting_list::{intersect_sorted, union_sorted};
 use super::IndexMap;
 use crate::{
     collection::NitriteId,
@@ -6,7 +7,7 @@ use crate::{
     filter::{ComparisonMode, Filter, FilterProvider, SortingAwareFilter},
 };
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Scanner for evaluating filter expressions against index maps.
@@ -268,7 +269,8 @@ impl IndexScannerInner {
             self.index_map.higher_key(&lower_val)?
         };
 
-        let mut nitrite_ids: Vec<NitriteId> = Vec::new();
+        // the ids of each key are ascending, so the range is a streaming merge of them
+        let mut postings: Vec<Vec<NitriteId>> = Vec::new();
         while let Some(k) = key {
             // the null key is never part of a range result; skip it instead of
             // comparing, since the mixed-type Value ordering is unreliable for Null
@@ -283,11 +285,12 @@ impl IndexScannerInner {
 
             match self.index_map.get(&k)? {
                 Some(Value::Array(array)) => {
-                    for value in array {
-                        if let Some(id) = value.as_nitrite_id() {
-                            nitrite_ids.push(*id);
-                        }
-                    }
+                    postings.push(
+                        array
+                            .iter()
+                            .filter_map(|value| value.as_nitrite_id().copied())
+                            .collect(),
+                    );
                 }
                 // A sub-map means this is a compound-index level, not a single-field scan.
                 Some(Value::Map(_)) => return Ok(None),
@@ -297,9 +300,7 @@ impl IndexScannerInner {
             key = self.index_map.higher_key(&k)?;
         }
 
-        nitrite_ids.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-        nitrite_ids.dedup();
-        Ok(Some(nitrite_ids))
+        Ok(Some(union_sorted(postings).collect()))
     }
 
     /// Evaluates several filters that all target the same single field and intersects their id
@@ -313,7 +314,7 @@ impl IndexScannerInner {
         filters: &[Filter],
         scan_order: &HashMap<String, bool>,
     ) -> NitriteResult<Option<Vec<NitriteId>>> {
-        let mut acc: Option<HashSet<NitriteId>> = None;
+        let mut acc: Option<Vec<NitriteId>> = None;
 
         for filter in filters {
             let field = filter.get_field_name()?;
@@ -329,25 +330,25 @@ impl IndexScannerInner {
                 return Ok(None);
             }
 
-            let ids: HashSet<NitriteId> = result
+            let mut ids: Vec<NitriteId> = result
                 .iter()
                 .filter_map(|value| value.as_nitrite_id().copied())
                 .collect();
+            ids.sort_unstable();
+            ids.dedup();
 
             acc = Some(match acc {
                 None => ids,
-                Some(existing) => existing.intersection(&ids).copied().collect(),
+                Some(existing) => intersect_sorted(existing, ids).collect(),
             });
 
             // Nothing can survive further intersection once the set is empty.
-            if acc.as_ref().is_some_and(|set| set.is_empty()) {
+            if acc.as_ref().is_some_and(|ids| ids.is_empty()) {
                 break;
             }
         }
 
-        let mut nitrite_ids: Vec<NitriteId> = acc.unwrap_or_default().into_iter().collect();
-        nitrite_ids.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-        Ok(Some(nitrite_ids))
+        Ok(Some(acc.unwrap_or_default()))
     }
 }
 
@@ -0,0 +1,274 @@
+//! Cardinality statistics used by the query planner to choose between indexes.
+
+use crate::{
+    collection::Document,
+    common::get_current_time_or_zero,
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    store::{NitriteMap, NitriteMapProvider},
+    Convertible, Value,
+};
+
+use super::normalize_index_value;
+use super::posting_list::block_len;
+
+/// Number of most common keys kept per index.
+const MOST_COMMON_LIMIT: usize = 16;
+
+/// Lightweight statistics of an index, gathered by `analyze()`.
+///
+/// The statistics describe the first field of the index: how many entries it holds, how many
+/// distinct keys those entries have, and the keys that occur most often. The find optimizer
+/// uses them to estimate how many documents an index scan returns and picks the cheapest
+/// index when several indexes match a query.
+///
+/// Statistics are a snapshot: they are persisted with the index metadata and reflect the
+/// index as of the last `analyze()`. Rebuilding or dropping the index discards them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexStatistics {
+    total_entries: u64,
+    distinct_keys: u64,
+    most_common: Vec<(Value, u64)>,
+    analyzed_at: u128,
+}
+
+impl IndexStatistics {
+    /// Returns the number of index entries, one per indexed value of each document.
+    pub fn total_entries(&self) -> u64 {
+        self.total_entries
+    }
+
+    /// Returns the number of distinct keys of the first indexed field.
+    pub fn distinct_keys(&self) -> u64 {
+        self.distinct_keys
+    }
+
+    /// Returns the most common keys with their entry counts, most frequent first.
+    pub fn most_common(&self) -> &[(Value, u64)] {
+        &self.most_common
+    }
+
+    /// Returns when the statistics were gathered, in milliseconds since the Unix epoch.
+    pub fn analyzed_at(&self) -> u128 {
+        self.analyzed_at
+    }
+
+    /// Estimates the number of entries whose first field equals `value`.
+    pub(crate) fn estimate_equals(&self, value: &Value) -> f64 {
+        let value = normalize_index_value(value);
+        if let Some((_, count)) = self.most_common.iter().find(|(key, _)| *key == value) {
+            return *count as f64;
+        }
+
+        // the remaining entries are assumed to be spread evenly over the remaining keys
+        let common_entries: u64 = self.most_common.iter().map(|(_, count)| count).sum();
+        let other_entries = self.total_entries.saturating_sub(common_entries);
+        let other_keys = self.distinct_keys.saturating_sub(self.most_common.len() as u64);
+        if other_keys == 0 {
+            0.0
+        } else {
+            other_entries as f64 / other_keys as f64
+        }
+    }
+
+    /// Estimates the number of entries matched by a range on the first field.
+    pub(crate) fn estimate_range(&self) -> f64 {
+        self.total_entries as f64 / 3.0
+    }
+
+    /// Gathers the statistics of an index by scanning its map.
+    ///
+    /// All index layouts are understood: `value -> [ids]` rows of unique single-field
+    /// indexes, `[value, .., id] -> null` composite rows of the other indexes and
+    /// `[value, first_id] -> bytes` blocks of compressed indexes. Rows of any other shape
+    /// (for example those of plugin indexes) are ignored.
+    pub(crate) fn analyze(index_map: &NitriteMap) -> NitriteResult<IndexStatistics> {
+        let mut statistics = IndexStatistics {
+            total_entries: 0,
+            distinct_keys: 0,
+            most_common: Vec::with_capacity(MOST_COMMON_LIMIT + 1),
+            analyzed_at: get_current_time_or_zero(),
+        };
+
+        // keys are sorted, so all rows of a first-field value are adjacent
+        let mut current: Option<(Value, u64)> = None;
+        for entry in index_map.entries()? {
+            let (key, value) = entry?;
+            let (first, count) = match (&key, &value) {
+                (Value::Array(parts), Value::Null)
+                    if parts.len() >= 2 && matches!(parts.last(), Some(Value::NitriteId(_))) =>
+                {
+                    (normalize_index_value(&parts[0]), 1)
+                }
+                (Value::Array(parts), Value::Bytes(block)) if parts.len() == 2 => {
+                    (normalize_index_value(&parts[0]), block_len(block) as u64)
+                }
+                (_, Value::Array(ids)) => (normalize_index_value(&key), ids.len() as u64),
+                _ => continue,
+            };
+
+            statistics.total_entries += count;
+            match current.as_mut() {
+                Some((key, total)) if *key == first => *total += count,
+                _ => {
+                    if let Some(done) = current.replace((first, count)) {
+                        statistics.add_key(done);
+                    }
+                }
+            }
+        }
+        if let Some(done) = current {
+            statistics.add_key(done);
+        }
+
+        Ok(statistics)
+    }
+
+    fn add_key(&mut self, key: (Value, u64)) {
+        self.distinct_keys += 1;
+        let position = self
+            .most_common
+            .iter()
+            .position(|(_, count)| *count < key.1)
+            .unwrap_or(self.most_common.len());
+        if position < MOST_COMMON_LIMIT {
+            self.most_common.insert(position, key);
+            self.most_common.truncate(MOST_COMMON_LIMIT);
+        }
+    }
+}
+
+impl Convertible for IndexStatistics {
+    type Output = Self;
+
+    fn to_value(&self) -> NitriteResult<Value> {
+        let most_common = self
+            .most_common
+            .iter()
+            .map(|(key, count)| Value::Array(vec![key.clone(), Value::U64(*count)]))
+            .collect();
+
+        let mut doc = Document::new();
+        doc.put("total_entries", Value::U64(self.total_entries))?;
+        doc.put("distinct_keys", Value::U64(self.distinct_keys))?;
+        doc.put("most_common", Value::Array(most_common))?;
+        doc.put("analyzed_at", Value::U128(self.analyzed_at))?;
+        Ok(Value::Document(doc))
+    }
+
+    fn from_value(value: &Value) -> NitriteResult<Self> {
+        let invalid = || {
+            log::error!("Failed to convert Value {:?} to IndexStatistics", value);
+            NitriteError::new(
+                "Invalid index statistics in index metadata",
+                ErrorKind::ObjectMappingError,
+            )
+        };
+
+        let doc = value.as_document().ok_or_else(invalid)?;
+        let total_entries = *doc.get("total_entries")?.as_u64().ok_or_else(invalid)?;
+        let distinct_keys = *doc.get("distinct_keys")?.as_u64().ok_or_else(invalid)?;
+        let analyzed_at = *doc.get("analyzed_at")?.as_u128().ok_or_else(invalid)?;
+
+        let mut most_common = Vec::new();
+        for pair in doc.get("most_common")?.as_array().ok_or_else(invalid)? {
+            match pair.as_array().map(|pair| pair.as_slice()) {
+                Some([key, Value::U64(count)]) => most_common.push((key.clone(), *count)),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(IndexStatistics {
+            total_entries,
+            distinct_keys,
+            most_common,
+            analyzed_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::NitriteId;
+    use crate::index::composite_key;
+    use crate::index::posting_list::add_posting;
+    use crate::store::{NitriteStore, NitriteStoreProvider};
+
+    fn open_map(name: &str) -> NitriteMap {
+        NitriteStore::default().open_map(name).unwrap()
+    }
+
+    #[test]
+    fn test_analyze_composite_layout() {
+        let map = open_map("composite");
+        for i in 0..30 {
+            let value = Value::I32(if i < 20 { 0 } else { i });
+            map.put(composite_key(&value, &NitriteId::new()), Value::Null)
+                .unwrap();
+        }
+
+        let statistics = IndexStatistics::analyze(&map).unwrap();
+        assert_eq!(statistics.total_entries(), 30);
+        assert_eq!(statistics.distinct_keys(), 11);
+        assert_eq!(statistics.most_common()[0], (Value::I32(0), 20));
+        assert_eq!(statistics.estimate_equals(&Value::I32(0)), 20.0);
+        assert_eq!(statistics.estimate_equals(&Value::I32(25)), 1.0);
+        assert_eq!(statistics.estimate_range(), 10.0);
+    }
+
+    #[test]
+    fn test_analyze_array_layout() {
+        let map = open_map("array");
+        for i in 0..5 {
+            let ids = vec![Value::NitriteId(NitriteId::new())];
+            map.put(Value::String(format!("k{}", i)), Value::Array(ids))
+                .unwrap();
+        }
+
+        let statistics = IndexStatistics::analyze(&map).unwrap();
+        assert_eq!(statistics.total_entries(), 5);
+        assert_eq!(statistics.distinct_keys(), 5);
+        assert_eq!(statistics.estimate_equals(&Value::from("k1")), 1.0);
+        // every key is a most common one, so an unknown key matches nothing
+        assert_eq!(statistics.estimate_equals(&Value::from("k9")), 0.0);
+    }
+
+    #[test]
+    fn test_analyze_compressed_layout() {
+        let map = open_map("compressed");
+        for i in 0..40 {
+            let value = Value::from(if i < 30 { "open" } else { "closed" });
+            add_posting(&map, &value, &NitriteId::new()).unwrap();
+        }
+
+        let statistics = IndexStatistics::analyze(&map).unwrap();
+        assert_eq!(statistics.total_entries(), 40);
+        assert_eq!(statistics.distinct_keys(), 2);
+        assert_eq!(statistics.most_common()[0], (Value::from("open"), 30));
+    }
+
+    #[test]
+    fn test_most_common_is_bounded() {
+        let map = open_map("bounded");
+        for i in 0..100 {
+            map.put(composite_key(&Value::I32(i), &NitriteId::new()), Value::Null)
+                .unwrap();
+        }
+
+        let statistics = IndexStatistics::analyze(&map).unwrap();
+        assert_eq!(statistics.distinct_keys(), 100);
+        assert_eq!(statistics.most_common().len(), MOST_COMMON_LIMIT);
+    }
+
+    #[test]
+    fn test_statistics_round_trip() {
+        let map = open_map("round_trip");
+        map.put(composite_key(&Value::from("a"), &NitriteId::new()), Value::Null)
+            .unwrap();
+
+        let statistics = IndexStatistics::analyze(&map).unwrap();
+        let restored = IndexStatistics::from_value(&statistics.to_value().unwrap()).unwrap();
+        assert_eq!(restored, statistics);
+        assert!(IndexStatistics::from_value(&Value::I32(1)).is_err());
+    }
+}
@@ -26,6 +26,8 @@
 //! - **Index hints**: Filters support specifying preferred indexes
 //! - **Index rebuilding**: Rebuild corrupted or fragmented indexes
 //! - **Index dropping**: Remove indexes to save space
+//! - **Index statistics**: `analyze()` gathers cardinality statistics the planner uses to
+//!   choose between several matching indexes
 //!
 //! # Performance Considerations
 //!
@@ -35,14 +37,18 @@
 //! - Unique indexes prevent duplicate values automatically
 
 mod descriptor;
+mod hint;
+mod index_export;
 mod nitrite_indexer;
 mod index_map;
 pub mod index_meta;
+mod index_statistics;
 mod nitrite_index;
 mod compound_index;
 pub mod text;
 pub mod index_scanner;
 mod options;
+pub(crate) mod posting_list;
 mod text_index;
 mod simple_index;
 pub mod text_indexer;
@@ -50,6 +56,9 @@ pub mod unique_indexer;
 pub mod non_unique_indexer;
 
 pub use descriptor::*;
+pub use hint::*;
+pub use index_export::*;
 pub use index_map::*;
+pub use index_statistics::*;
 pub use nitrite_indexer::*;
 pub use options::*;
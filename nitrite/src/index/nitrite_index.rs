@@ -1,6 +1,6 @@
 use super::IndexDescriptor;
 use crate::{
-    collection::{FindPlan, NitriteId},
+    collection::{Document, FindPlan, NitriteId},
     errors::{ErrorKind, NitriteError, NitriteResult},
     FieldValues,
 };
@@ -86,6 +86,27 @@ pub trait NitriteIndexProvider: Send + Sync {
     /// Returns IndexingError if query execution fails.
     fn find_nitrite_ids(&self, find_plan: &FindPlan) -> NitriteResult<Vec<NitriteId>>;
 
+    /// Returns whether the index entries hold the indexed values of the documents as
+    /// they are, so that a covered query can be answered from them.
+    ///
+    /// # Behavior
+    /// The default implementation returns `false`.
+    fn has_exact_keys(&self) -> NitriteResult<bool> {
+        Ok(false)
+    }
+
+    /// Builds the documents matching a covered find plan from the index entries.
+    ///
+    /// # Returns
+    /// The `_id` and indexed fields of each match, in the order of `find_nitrite_ids`,
+    /// or `None` if the entries cannot answer the plan.
+    ///
+    /// # Behavior
+    /// The default implementation returns `None`.
+    fn find_covered(&self, _find_plan: &FindPlan) -> NitriteResult<Option<Vec<Document>>> {
+        Ok(None)
+    }
+
     /// Returns whether this index enforces uniqueness on indexed field values.
     ///
     /// # Returns
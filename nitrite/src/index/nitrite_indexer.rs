@@ -1,9 +1,11 @@
-use crate::collection::{FindPlan, NitriteId};
+use crate::collection::{Document, FindPlan, NitriteId};
 use crate::common::{Fields, NitritePlugin};
-use crate::errors::NitriteResult;
+use crate::common::catch_panics_with;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::index::IndexDescriptor;
 use crate::nitrite_config::NitriteConfig;
-use crate::{FieldValues, NitritePluginProvider};
+use crate::store::NitriteStoreProvider;
+use crate::{derive_index_map_name, FieldValues, NitritePluginProvider};
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -110,6 +112,55 @@ pub trait NitriteIndexerProvider: NitritePluginProvider + Send + Sync {
         nitrite_config: &NitriteConfig,
     ) -> NitriteResult<()>;
 
+    /// Removes all entries from this index while keeping the index itself.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index being cleared
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Behavior
+    /// Called by `NitriteCollection::clear`, which truncates the documents of a
+    /// collection but keeps its indexes. The default implementation clears the
+    /// store map of the index, which suits indexes kept in a store map. Indexes
+    /// kept elsewhere override it.
+    ///
+    /// # Errors
+    /// Returns IndexingError if underlying storage operations fail.
+    fn clear_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        let store = nitrite_config.nitrite_store()?;
+        let index_map_name = derive_index_map_name(index_descriptor);
+        if store.has_map(&index_map_name)? {
+            store.open_map(&index_map_name)?.clear()?;
+        }
+        Ok(())
+    }
+
+    /// Destroys this index along with every resource it holds outside the store.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index being destroyed
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Behavior
+    /// Called when the collection of the index is dropped. Unlike `drop_index`, the
+    /// index may not have been used since the database was opened, so implementations
+    /// keeping files or directories of their own must remove them from disk either way.
+    /// The default implementation calls `drop_index`.
+    ///
+    /// # Errors
+    /// Returns IndexingError if underlying storage operations fail.
+    fn destroy_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.drop_index(index_descriptor, nitrite_config)
+    }
+
     /// Records a new or updated field value mapping to a NitriteId in the index.
     ///
     /// # Arguments
@@ -185,6 +236,154 @@ pub trait NitriteIndexerProvider: NitritePluginProvider + Send + Sync {
         find_plan: &FindPlan,
         nitrite_config: &NitriteConfig,
     ) -> NitriteResult<Vec<NitriteId>>;
+
+    /// Returns whether the indexer can order its results by distance.
+    ///
+    /// # Returns
+    /// `true` if `find_by_filter` returns the matching ids nearest first when the
+    /// plan has a [`FindPlan::distance_sort`]. Queries sorted by distance fail with
+    /// InvalidOperation on indexers returning `false`, the default.
+    fn supports_distance_sort(&self) -> bool {
+        false
+    }
+
+    /// Returns whether the order of the ids found by the indexer is part of the result.
+    ///
+    /// # Returns
+    /// `true` if `find_by_filter` ranks the matching ids, like the nearest first of a
+    /// vector search or the best match first of a full-text search. The ids of other
+    /// indexers are read by ascending NitriteId, see
+    /// [`FindOptions::tie_break_by_id`](crate::collection::FindOptions::tie_break_by_id).
+    /// The default implementation returns `false`.
+    fn ranks_results(&self) -> bool {
+        false
+    }
+
+    /// Returns whether the entries of an index hold the indexed values of its documents
+    /// as they are.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Behavior
+    /// A query needing no field but those of such an index is planned as covered and
+    /// answered by `find_covered`. Entries folding, converting or splitting the values
+    /// they index do not qualify. The default implementation returns `false`.
+    fn can_cover(
+        &self,
+        _index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        Ok(false)
+    }
+
+    /// Builds the documents matching a covered find plan from the index entries.
+    ///
+    /// # Arguments
+    /// * `find_plan` - Covered query plan, see [`FindPlan::is_covered`]
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Returns
+    /// A document per match holding its `_id` and indexed fields, in the order
+    /// `find_by_filter` returns the ids, or `None` if the index cannot answer the plan
+    /// anymore, in which case the documents are read as usual. The default
+    /// implementation returns `None`.
+    fn find_covered(
+        &self,
+        _find_plan: &FindPlan,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        Ok(None)
+    }
+
+    /// Exports the data of an index, to be imported into another copy of its collection.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index to export
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Returns
+    /// The data of the index in a form `import_index` of the same indexer reads back on
+    /// any machine, or `None` if the index cannot be exported. Called with the
+    /// collection locked against writes, so the data matches the documents. The default
+    /// implementation returns `None`, which suits indexes kept in store maps: they are
+    /// copied with the store and rebuilt quickly from it.
+    fn export_index(
+        &self,
+        _index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Replaces the data of an index with data written by `export_index`.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index to import into
+    /// * `nitrite_config` - Database configuration for resource access
+    /// * `data` - The exported data
+    ///
+    /// # Behavior
+    /// Called instead of a build, once the collection holds the same documents as the one
+    /// the data was exported from. Any data the index already holds is discarded. The
+    /// default implementation fails with InvalidOperation.
+    ///
+    /// # Errors
+    /// Returns IndexingError if the data cannot be read or written.
+    fn import_index(
+        &self,
+        _index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+        _data: &[u8],
+    ) -> NitriteResult<()> {
+        log::error!("Index type {} cannot import index data", self.index_type());
+        Err(NitriteError::new(
+            &format!("Index type {} cannot import index data", self.index_type()),
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    /// Loads an index ahead of its first query.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index to load
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Behavior
+    /// Called by `Nitrite::warm_up` from a background thread. Implementations open the
+    /// index structures and read through their data so that later queries find them in
+    /// memory and in the store caches. The default implementation does nothing.
+    ///
+    /// # Errors
+    /// Returns IndexingError if the index cannot be opened or read.
+    fn warm_up(
+        &self,
+        _index_descriptor: &IndexDescriptor,
+        _nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        Ok(())
+    }
+
+    /// Checks if an index has been opened by a query, a write or a warm-up.
+    ///
+    /// # Arguments
+    /// * `index_descriptor` - Metadata describing the index
+    /// * `nitrite_config` - Database configuration for resource access
+    ///
+    /// # Behavior
+    /// Used by `Nitrite::index_load_status` to report the indexes still to be loaded.
+    /// The default implementation checks if the store has opened the map of the index,
+    /// which suits indexes kept in a store map. Indexes kept elsewhere override it.
+    fn is_loaded(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        nitrite_config
+            .nitrite_store()?
+            .is_map_opened(&derive_index_map_name(index_descriptor))
+    }
 }
 
 
@@ -205,6 +404,157 @@ impl NitriteIndexer {
     pub fn new<T: NitriteIndexerProvider + 'static>(inner: T) -> Self {
         NitriteIndexer { inner: Arc::new(inner) }
     }
+
+    // The methods below shadow the provider methods reached through Deref, so every
+    // call into the indexer plugin goes through a panic boundary.
+
+    pub fn validate_index(&self, fields: &Fields) -> NitriteResult<()> {
+        self.guard("validate_index", || self.inner.validate_index(fields))
+    }
+
+    pub fn drop_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.guard("drop_index", || {
+            self.inner.drop_index(index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn clear_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.guard("clear_index", || {
+            self.inner.clear_index(index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn destroy_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.guard("destroy_index", || {
+            self.inner.destroy_index(index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn write_index_entry(
+        &self,
+        field_values: &FieldValues,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.guard("write_index_entry", || {
+            self.inner
+                .write_index_entry(field_values, index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn remove_index_entry(
+        &self,
+        field_values: &FieldValues,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.guard("remove_index_entry", || {
+            self.inner
+                .remove_index_entry(field_values, index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn find_by_filter(
+        &self,
+        find_plan: &FindPlan,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Vec<NitriteId>> {
+        self.guard("find_by_filter", || {
+            self.inner.find_by_filter(find_plan, nitrite_config)
+        })
+    }
+
+    pub fn can_cover(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        self.guard("can_cover", || {
+            self.inner.can_cover(index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn find_covered(
+        &self,
+        find_plan: &FindPlan,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        self.guard("find_covered", || {
+            self.inner.find_covered(find_plan, nitrite_config)
+        })
+    }
+
+    pub fn export_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<u8>>> {
+        self.guard("export_index", || {
+            self.inner.export_index(index_descriptor, nitrite_config)
+        })
+    }
+
+    pub fn import_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+        data: &[u8],
+    ) -> NitriteResult<()> {
+        self.guard("import_index", || {
+            self.inner.import_index(index_descriptor, nitrite_config, data)
+        })
+    }
+
+    pub fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        self.guard("warm_up", || self.inner.warm_up(index_descriptor, nitrite_config))
+    }
+
+    pub fn initialize(&self, nitrite_config: NitriteConfig) -> NitriteResult<()> {
+        self.guard("initialize", || self.inner.initialize(nitrite_config))
+    }
+
+    pub fn on_open(&self, nitrite_config: &NitriteConfig) -> NitriteResult<()> {
+        self.guard("on_open", || self.inner.on_open(nitrite_config))
+    }
+
+    pub fn on_flush(&self) -> NitriteResult<()> {
+        self.guard("on_flush", || self.inner.on_flush())
+    }
+
+    pub fn on_close(&self) -> NitriteResult<()> {
+        self.guard("on_close", || self.inner.on_close())
+    }
+
+    pub fn close(&self) -> NitriteResult<()> {
+        self.guard("close", || self.inner.close())
+    }
+
+    fn guard<T>(
+        &self,
+        operation: &str,
+        f: impl FnOnce() -> NitriteResult<T>,
+    ) -> NitriteResult<T> {
+        catch_panics_with(
+            || format!("Indexer '{}' {}", self.inner.index_type(), operation),
+            f,
+        )
+    }
 }
 
 impl Deref for NitriteIndexer {
@@ -320,6 +670,44 @@ mod tests {
         assert!(indexer.drop_index(&index_descriptor, &config).is_ok());
     }
 
+    #[test]
+    fn test_default_clear_index_clears_index_map() {
+        let indexer = NitriteIndexer::new(MockNitriteIndexer);
+        let index_descriptor = IndexDescriptor::new(
+            "test_index",
+            Fields::with_names(vec!["test_field"]).unwrap(),
+            "test",
+        );
+        let config = NitriteConfig::default();
+        config.auto_configure().unwrap();
+        config.initialize().unwrap();
+
+        let store = config.nitrite_store().unwrap();
+        let index_map = store
+            .open_map(&derive_index_map_name(&index_descriptor))
+            .unwrap();
+        index_map.put(1.to_value().unwrap(), 2.to_value().unwrap()).unwrap();
+
+        indexer.clear_index(&index_descriptor, &config).unwrap();
+        assert_eq!(index_map.size().unwrap(), 0);
+        // the map is kept for further writes
+        assert!(store
+            .has_map(&derive_index_map_name(&index_descriptor))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_default_destroy_index_drops_index() {
+        let indexer = NitriteIndexer::new(MockNitriteIndexer);
+        let index_descriptor = IndexDescriptor::new(
+            "test_index",
+            Fields::with_names(vec!["test_field"]).unwrap(),
+            "test",
+        );
+        let config = NitriteConfig::default();
+        assert!(indexer.destroy_index(&index_descriptor, &config).is_ok());
+    }
+
     #[test]
     fn test_write_index_entry() {
         let indexer = NitriteIndexer::new(MockNitriteIndexer);
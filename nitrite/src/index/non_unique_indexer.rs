@@ -1,14 +1,14 @@
 use super::{
     compound_index::CompoundIndex, nitrite_index::NitriteIndex,
     nitrite_index::NitriteIndexProvider,
-    simple_index::SimpleIndex, IndexDescriptor, NitriteIndexerProvider,
+    simple_index::SimpleIndex, fold_field_values, is_sparse_skipped, IndexDescriptor, NitriteIndexerProvider,
 };
 use crate::{
-    collection::{FindPlan, NitriteId},
+    collection::{Document, FindPlan, NitriteId},
     errors::{ErrorKind, NitriteError, NitriteResult}
     ,
     nitrite_config::NitriteConfig,
-    FieldValues, Fields, NitritePlugin, NitritePluginProvider, NON_UNIQUE_INDEX,
+    read_index_map, FieldValues, Fields, NitritePlugin, NitritePluginProvider, NON_UNIQUE_INDEX,
 };
 use dashmap::DashMap;
 use std::sync::Arc;
@@ -75,10 +75,10 @@ impl NitriteIndexerProvider for NonUniqueIndexer {
     fn drop_index(
         &self,
         index_descriptor: &IndexDescriptor,
-        _nitrite_config: &NitriteConfig,
+        nitrite_config: &NitriteConfig,
     ) -> NitriteResult<()> {
         self.inner
-            .drop_index(index_descriptor)
+            .drop_index(index_descriptor, nitrite_config)
     }
 
     fn write_index_entry(
@@ -108,6 +108,33 @@ impl NitriteIndexerProvider for NonUniqueIndexer {
     ) -> NitriteResult<Vec<NitriteId>> {
         self.inner.find_by_filter(find_plan, nitrite_config)
     }
+
+    fn can_cover(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        self.inner.can_cover(index_descriptor, nitrite_config)
+    }
+
+    fn find_covered(
+        &self,
+        find_plan: &FindPlan,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        self.inner.find_covered(find_plan, nitrite_config)
+    }
+
+    fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        if self.inner.find_nitrite_index(index_descriptor).is_none() {
+            self.inner.create_nitrite_index(index_descriptor, nitrite_config)?;
+        }
+        read_index_map(index_descriptor, nitrite_config)
+    }
 }
 
 struct NonUniqueIndexerInner {
@@ -149,12 +176,15 @@ impl NonUniqueIndexerInner {
     fn drop_index(
         &self,
         index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
     ) -> NitriteResult<()> {
-        let nitrite_index = self.find_nitrite_index(index_descriptor);
-        if let Some(nitrite_index) = nitrite_index {
-            nitrite_index.drop_index()?;
-            self.index_registry.remove(index_descriptor);
-        }
+        let nitrite_index = match self.find_nitrite_index(index_descriptor) {
+            Some(nitrite_index) => nitrite_index,
+            // not used since the database was opened, but its map holds entries
+            None => self.create_nitrite_index(index_descriptor, nitrite_config)?,
+        };
+        nitrite_index.drop_index()?;
+        self.index_registry.remove(index_descriptor);
         Ok(())
     }
 
@@ -169,8 +199,16 @@ impl NonUniqueIndexerInner {
             nitrite_index = Some(self.create_nitrite_index(index_descriptor, nitrite_config)?);
         }
 
+        if index_descriptor.is_sparse() && is_sparse_skipped(field_values) {
+            return Ok(());
+        }
+
         if let Some(nitrite_index) = nitrite_index {
-            nitrite_index.write(field_values)?;
+            if index_descriptor.is_case_insensitive() {
+                nitrite_index.write(&fold_field_values(field_values))?;
+            } else {
+                nitrite_index.write(field_values)?;
+            }
         }
         Ok(())
     }
@@ -186,8 +224,16 @@ impl NonUniqueIndexerInner {
             nitrite_index = Some(self.create_nitrite_index(index_descriptor, nitrite_config)?);
         }
 
+        if index_descriptor.is_sparse() && is_sparse_skipped(field_values) {
+            return Ok(());
+        }
+
         if let Some(nitrite_index) = nitrite_index {
-            nitrite_index.remove(field_values)?;
+            if index_descriptor.is_case_insensitive() {
+                nitrite_index.remove(&fold_field_values(field_values))?;
+            } else {
+                nitrite_index.remove(field_values)?;
+            }
         }
         Ok(())
     }
@@ -220,6 +266,39 @@ impl NonUniqueIndexerInner {
             },
         }
     }
+
+    fn can_cover(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        // a case-insensitive index holds folded values
+        if index_descriptor.is_case_insensitive() {
+            return Ok(false);
+        }
+        let nitrite_index = match self.find_nitrite_index(index_descriptor) {
+            Some(nitrite_index) => nitrite_index,
+            None => self.create_nitrite_index(index_descriptor, nitrite_config)?,
+        };
+        nitrite_index.has_exact_keys()
+    }
+
+    fn find_covered(
+        &self,
+        find_plan: &FindPlan,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        let Some(index_descriptor) = find_plan.index_descriptor() else {
+            return Ok(None);
+        };
+        if !self.can_cover(&index_descriptor, nitrite_config)? {
+            return Ok(None);
+        }
+        match self.find_nitrite_index(&index_descriptor) {
+            Some(nitrite_index) => nitrite_index.find_covered(find_plan),
+            None => Ok(None),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -291,6 +370,8 @@ mod tests {
         let indexer = NonUniqueIndexer::new();
         let index_descriptor = create_test_index_descriptor();
         let config = NitriteConfig::default();
+        config.auto_configure().unwrap();
+        config.initialize().unwrap();
         assert!(indexer.drop_index(&index_descriptor, &config).is_ok());
     }
 
@@ -363,7 +444,10 @@ mod tests {
     fn test_drop_index_not_found() {
         let indexer = NonUniqueIndexer::new();
         let index_descriptor = create_test_index_descriptor();
-        assert!(indexer.inner.drop_index(&index_descriptor).is_ok());
+        let config = NitriteConfig::default();
+        config.auto_configure().unwrap();
+        config.initialize().unwrap();
+        assert!(indexer.inner.drop_index(&index_descriptor, &config).is_ok());
     }
 
     #[test]
@@ -519,9 +603,12 @@ mod tests {
         // Test that drop_index gracefully handles missing index in registry
         let indexer = NonUniqueIndexer::new();
         let index_descriptor = create_test_index_descriptor();
-        
+        let config = NitriteConfig::default();
+        config.auto_configure().unwrap();
+        config.initialize().unwrap();
+
         // Dropping non-existent index should not error
-        let result = indexer.inner.drop_index(&index_descriptor);
+        let result = indexer.inner.drop_index(&index_descriptor, &config);
         assert!(result.is_ok());
     }
 
@@ -43,6 +43,9 @@ use crate::{FULL_TEXT_INDEX, NON_UNIQUE_INDEX, UNIQUE_INDEX};
 #[derive(Clone)]
 pub struct IndexOptions {
     index_type: String,
+    case_insensitive: bool,
+    sparse: bool,
+    compressed: bool,
 }
 
 impl IndexOptions {
@@ -64,7 +67,98 @@ impl IndexOptions {
     /// let opts = IndexOptions::new(NON_UNIQUE_INDEX);
     /// ```
     pub fn new(index_type: &str) -> IndexOptions {
-        IndexOptions { index_type: index_type.to_string() }
+        IndexOptions {
+            index_type: index_type.to_string(),
+            case_insensitive: false,
+            sparse: false,
+            compressed: false,
+        }
+    }
+
+    /// Makes the index compare string keys case-insensitively.
+    ///
+    /// # Arguments
+    /// * `case_insensitive` - Whether string keys are compared ignoring case
+    ///
+    /// # Returns
+    /// The IndexOptions with the case sensitivity set.
+    ///
+    /// # Behavior
+    /// The index stores the lower-cased form of every string key, so a unique index
+    /// rejects `"Foo@x.com"` when `"foo@x.com"` is already indexed. Documents keep their
+    /// original values, and equality queries still match the exact value. Only unique
+    /// and non-unique indexes support this option.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// collection.create_index(vec!["email"], &unique_index().case_insensitive(true))?;
+    /// ```
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> IndexOptions {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Returns `true` if the index compares string keys case-insensitively.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Makes the index skip documents whose indexed fields are null or missing.
+    ///
+    /// # Arguments
+    /// * `sparse` - Whether documents without a value for the indexed fields are left out
+    ///
+    /// # Returns
+    /// The IndexOptions with the sparse flag set.
+    ///
+    /// # Behavior
+    /// A sparse index only stores entries for documents that have a non-null value in at
+    /// least one of the indexed fields, which keeps indexes on optional fields small. A
+    /// sparse unique index also lets any number of documents omit the field. Because such
+    /// documents are not in the index, queries for null values never use a sparse index.
+    /// Only unique and non-unique indexes support this option.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// collection.create_index(vec!["referral_code"], &non_unique_index().sparse(true))?;
+    /// ```
+    pub fn sparse(mut self, sparse: bool) -> IndexOptions {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Returns `true` if the index skips documents with null or missing values.
+    pub fn is_sparse(&self) -> bool {
+        self.sparse
+    }
+
+    /// Stores the ids of each indexed value as compressed posting lists.
+    ///
+    /// # Arguments
+    /// * `compressed` - Whether the ids of a value are stored in compressed blocks
+    ///
+    /// # Returns
+    /// The IndexOptions with the compressed flag set.
+    ///
+    /// # Behavior
+    /// A compressed index keeps the ids of a value in blocks of up to 512 delta-encoded
+    /// ids rather than one entry per document, which makes an index over a field with a
+    /// handful of distinct values across millions of documents many times smaller.
+    /// Each write re-encodes one block, so it suits read-heavy, low-cardinality fields.
+    /// Only single-field non-unique indexes support this option.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// collection.create_index(vec!["status"], &non_unique_index().compressed(true))?;
+    /// ```
+    pub fn compressed(mut self, compressed: bool) -> IndexOptions {
+        self.compressed = compressed;
+        self
+    }
+
+    /// Returns `true` if the index stores its ids as compressed posting lists.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
     }
 
     /// Retrieves the index type identifier.
@@ -204,6 +298,18 @@ mod tests {
         assert_eq!(index_options.index_type(), UNIQUE_INDEX);
     }
 
+    #[test]
+    fn test_index_options_case_insensitive() {
+        assert!(!unique_index().is_case_insensitive());
+        assert!(unique_index().case_insensitive(true).is_case_insensitive());
+    }
+
+    #[test]
+    fn test_index_options_sparse() {
+        assert!(!non_unique_index().is_sparse());
+        assert!(non_unique_index().sparse(true).is_sparse());
+    }
+
     #[test]
     fn test_unique_index() {
         let index_options = unique_index();
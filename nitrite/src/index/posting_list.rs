@@ -0,0 +1,314 @@
+//! Compressed posting lists of non-unique indexes.
+//!
+//! A compressed index stores the ids of an indexed value in blocks of up to
+//! [`POSTING_BLOCK_SIZE`] ascending ids instead of one composite row per id. Each block is
+//! keyed like a composite row, `[value, first_id]`, and holds the ids as LEB128 varints —
+//! the first id in full, every following id as the delta to its predecessor. Dense ids of
+//! a low-cardinality field mostly differ by small amounts, so an id takes one or two bytes
+//! instead of a full key with its value.
+//!
+//! Because the block keys keep the composite shape, the navigation of the composite
+//! layout works unchanged; only the reads of a group decode the blocks, see
+//! [`IndexLayout::Compressed`](super::IndexLayout).
+
+use itertools::Itertools;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+use super::index_map::{composite_key, normalize_index_value};
+use crate::collection::NitriteId;
+use crate::common::{Key, Value};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::store::{NitriteMap, NitriteMapProvider};
+
+/// Maximum number of ids in a block. A block growing past it is split in two halves.
+pub(crate) const POSTING_BLOCK_SIZE: usize = 512;
+
+/// Encodes ascending, distinct ids into a block.
+pub(crate) fn encode_block(ids: &[NitriteId]) -> Value {
+    let mut bytes = Vec::with_capacity(ids.len() * 2);
+    let mut previous = 0u64;
+    for id in ids {
+        write_varint(&mut bytes, id.id_value() - previous);
+        previous = id.id_value();
+    }
+    Value::Bytes(bytes)
+}
+
+/// Decodes the ids of a block, in ascending order.
+pub(crate) fn decode_block(bytes: &[u8]) -> NitriteResult<Vec<NitriteId>> {
+    let mut ids = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    let mut previous = 0u64;
+    while position < bytes.len() {
+        let delta = read_varint(bytes, &mut position)?;
+        previous = previous.checked_add(delta).ok_or_else(corrupt_block)?;
+        ids.push(NitriteId::create_id(previous)?);
+    }
+    Ok(ids)
+}
+
+/// Counts the ids of a block without decoding them: every varint ends with the one
+/// byte that has its high bit clear.
+pub(crate) fn block_len(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|byte| *byte & 0x80 == 0).count()
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> NitriteResult<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*position).ok_or_else(corrupt_block)?;
+        *position += 1;
+        if shift > 63 {
+            return Err(corrupt_block());
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+fn corrupt_block() -> NitriteError {
+    log::error!("Compressed index is in corrupt state: malformed posting block");
+    NitriteError::new(
+        "Compressed index is in corrupt state",
+        ErrorKind::IndexingError,
+    )
+}
+
+/// Finds the block of `value` that `id` belongs to: the block whose first id is the
+/// largest one not above `id`, or the first block of the value when `id` is smaller
+/// than all of them.
+fn find_block(
+    index_map: &NitriteMap,
+    value: &Value,
+    id: &NitriteId,
+) -> NitriteResult<Option<(Key, Vec<NitriteId>)>> {
+    let lead = normalize_index_value(value);
+    let target = composite_key(value, id);
+    let is_block_of_value =
+        |key: &Key| matches!(key, Value::Array(parts) if parts.len() == 2 && parts[0] == lead);
+
+    let key = match index_map.floor_key(&target)? {
+        Some(key) if is_block_of_value(&key) => Some(key),
+        _ => index_map
+            .ceiling_key(&target)?
+            .filter(|key| is_block_of_value(key)),
+    };
+
+    match key {
+        Some(key) => match index_map.get(&key)? {
+            Some(Value::Bytes(bytes)) => Ok(Some((key, decode_block(&bytes)?))),
+            _ => Err(corrupt_block()),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Stores a block under the key of its first id, removing the previous key of the
+/// block if the first id changed.
+fn put_block(
+    index_map: &NitriteMap,
+    value: &Value,
+    old_key: &Key,
+    ids: &[NitriteId],
+) -> NitriteResult<()> {
+    let key = composite_key(value, &ids[0]);
+    if key != *old_key {
+        index_map.remove(old_key)?;
+    }
+    index_map.put(key, encode_block(ids))
+}
+
+/// Adds `id` to the posting list of `value`.
+pub(crate) fn add_posting(index_map: &NitriteMap, value: &Value, id: &NitriteId) -> NitriteResult<()> {
+    let Some((key, mut ids)) = find_block(index_map, value, id)? else {
+        return index_map.put(composite_key(value, id), encode_block(&[*id]));
+    };
+
+    let Err(position) = ids.binary_search(id) else {
+        // already indexed, e.g. an array holding the same value twice
+        return Ok(());
+    };
+    ids.insert(position, *id);
+
+    if ids.len() > POSTING_BLOCK_SIZE {
+        let upper = ids.split_off(ids.len() / 2);
+        put_block(index_map, value, &key, &ids)?;
+        index_map.put(composite_key(value, &upper[0]), encode_block(&upper))
+    } else {
+        put_block(index_map, value, &key, &ids)
+    }
+}
+
+/// Removes `id` from the posting list of `value`.
+pub(crate) fn remove_posting(
+    index_map: &NitriteMap,
+    value: &Value,
+    id: &NitriteId,
+) -> NitriteResult<()> {
+    let Some((key, mut ids)) = find_block(index_map, value, id)? else {
+        return Ok(());
+    };
+
+    let Ok(position) = ids.binary_search(id) else {
+        return Ok(());
+    };
+    ids.remove(position);
+
+    if ids.is_empty() {
+        index_map.remove(&key)?;
+        Ok(())
+    } else {
+        put_block(index_map, value, &key, &ids)
+    }
+}
+
+/// Streaming intersection of two ascending id sequences.
+pub(crate) struct Intersection<A: Iterator, B: Iterator> {
+    left: Peekable<A>,
+    right: Peekable<B>,
+}
+
+impl<A, B> Iterator for Intersection<A, B>
+where
+    A: Iterator<Item = NitriteId>,
+    B: Iterator<Item = NitriteId>,
+{
+    type Item = NitriteId;
+
+    fn next(&mut self) -> Option<NitriteId> {
+        loop {
+            let left = *self.left.peek()?;
+            let right = *self.right.peek()?;
+            match left.cmp(&right) {
+                Ordering::Less => {
+                    self.left.next();
+                }
+                Ordering::Greater => {
+                    self.right.next();
+                }
+                Ordering::Equal => {
+                    self.left.next();
+                    self.right.next();
+                    return Some(left);
+                }
+            }
+        }
+    }
+}
+
+/// Intersects two ascending id sequences without materializing either of them.
+pub(crate) fn intersect_sorted<A, B>(left: A, right: B) -> Intersection<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator<Item = NitriteId>,
+    B: IntoIterator<Item = NitriteId>,
+{
+    Intersection {
+        left: left.into_iter().peekable(),
+        right: right.into_iter().peekable(),
+    }
+}
+
+/// Merges ascending id sequences into one ascending sequence without duplicates.
+pub(crate) fn union_sorted<I>(lists: I) -> impl Iterator<Item = NitriteId>
+where
+    I: IntoIterator,
+    I::Item: IntoIterator<Item = NitriteId>,
+{
+    lists.into_iter().kmerge().dedup()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{NitriteStore, NitriteStoreProvider};
+
+    fn id(value: u64) -> NitriteId {
+        NitriteId::create_id(1_000_000_000_000_000_000 + value).unwrap()
+    }
+
+    fn stored_ids(index_map: &NitriteMap) -> Vec<NitriteId> {
+        let mut ids = Vec::new();
+        for entry in index_map.entries().unwrap() {
+            match entry.unwrap().1 {
+                Value::Bytes(bytes) => ids.extend(decode_block(&bytes).unwrap()),
+                other => panic!("unexpected row {:?}", other),
+            }
+        }
+        ids
+    }
+
+    #[test]
+    fn test_block_round_trip() {
+        let ids = vec![id(1), id(2), id(130), id(1 << 40), id(1 << 62)];
+        let Value::Bytes(bytes) = encode_block(&ids) else {
+            panic!("expected bytes");
+        };
+        assert_eq!(decode_block(&bytes).unwrap(), ids);
+        assert_eq!(block_len(&bytes), ids.len());
+        // the first id takes nine bytes, the dense ids after it a byte each
+        let Value::Bytes(dense) = encode_block(&(1..=100).map(id).collect::<Vec<_>>()) else {
+            panic!("expected bytes");
+        };
+        assert_eq!(dense.len(), 9 + 99);
+    }
+
+    #[test]
+    fn test_truncated_block_is_rejected() {
+        let error = decode_block(&[0x81]).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::IndexingError);
+    }
+
+    #[test]
+    fn test_add_and_remove_postings() {
+        let index_map = NitriteStore::default().open_map("postings").unwrap();
+        let value = Value::from("active");
+        for i in (1..=2000).rev() {
+            add_posting(&index_map, &value, &id(i)).unwrap();
+        }
+        add_posting(&index_map, &Value::from("closed"), &id(5)).unwrap();
+        add_posting(&index_map, &value, &id(7)).unwrap();
+
+        // blocks are split, never larger than the block size
+        assert!(index_map.size().unwrap() > 2000 / POSTING_BLOCK_SIZE as u64);
+        let ids = stored_ids(&index_map);
+        assert_eq!(ids.len(), 2001);
+
+        for i in 1..=1000 {
+            remove_posting(&index_map, &value, &id(i)).unwrap();
+        }
+        remove_posting(&index_map, &value, &id(1)).unwrap();
+        let ids = stored_ids(&index_map);
+        assert_eq!(ids.len(), 1001);
+        assert!(ids.contains(&id(5)));
+        assert!(!ids.contains(&id(7)));
+
+        for i in 1001..=2000 {
+            remove_posting(&index_map, &value, &id(i)).unwrap();
+        }
+        assert_eq!(index_map.size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_intersect_and_union() {
+        let left = vec![id(1), id(3), id(5), id(7)];
+        let right = vec![id(2), id(3), id(7), id(9)];
+        let common: Vec<_> = intersect_sorted(left.clone(), right.clone()).collect();
+        assert_eq!(common, vec![id(3), id(7)]);
+
+        let all: Vec<_> = union_sorted(vec![left, right, vec![id(4)]]).collect();
+        assert_eq!(all, vec![id(1), id(2), id(3), id(4), id(5), id(7), id(9)]);
+    }
+}
@@ -1,5 +1,8 @@
 use super::{
-    index_map::composite_key, index_scanner::IndexScanner, nitrite_index::NitriteIndexProvider,
+    index_map::composite_key,
+    index_scanner::IndexScanner,
+    nitrite_index::NitriteIndexProvider,
+    posting_list::{add_posting, remove_posting},
     IndexDescriptor, IndexMap,
 };
 use crate::{
@@ -93,6 +96,9 @@ impl SimpleIndexInner {
         field_values: &FieldValues,
         value: &Value,
     ) -> NitriteResult<()> {
+        if self.index_descriptor.is_compressed() {
+            return add_posting(index_map, value, field_values.nitrite_id());
+        }
         if !self.is_unique() {
             // Non-unique indexes use the composite-key layout: one O(1) point write per
             // `(value, id)` pair, instead of an O(k) read-modify-write of a shared array.
@@ -118,6 +124,9 @@ impl SimpleIndexInner {
         field_values: &FieldValues,
         value: &Value,
     ) -> NitriteResult<()> {
+        if self.index_descriptor.is_compressed() {
+            return remove_posting(&index_map, value, field_values.nitrite_id());
+        }
         if !self.is_unique() {
             // Composite-key layout: remove the single `(value, id)` row in O(1).
             index_map.remove(&composite_key(value, field_values.nitrite_id()))?;
@@ -219,6 +228,8 @@ impl SimpleIndexInner {
 
         let i_map = if self.is_unique() {
             IndexMap::new(Some(index_map), None)
+        } else if self.index_descriptor.is_compressed() {
+            IndexMap::compressed(index_map)
         } else {
             IndexMap::composite(index_map, 1)
         };
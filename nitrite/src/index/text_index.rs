@@ -305,8 +305,8 @@ impl TextIndexInner {
             None | Some(Value::Null) => {
                 self.add_index_element(&index_map, field_values, &Value::Null)?;
             }
-            Some(Value::String(_)) => {
-                self.add_index_element(&index_map, field_values, first_value.unwrap())?;
+            Some(value @ Value::String(_)) => {
+                self.add_index_element(&index_map, field_values, value)?;
             }
             Some(Value::Array(values)) => {
                 validate_string_array_index_field(values, first_field)?;
@@ -340,8 +340,8 @@ impl TextIndexInner {
             None | Some(Value::Null) => {
                 self.remove_index_element(&index_map, field_values, &Value::Null)?;
             }
-            Some(Value::String(_)) => {
-                self.remove_index_element(&index_map, field_values, first_value.unwrap())?;
+            Some(value @ Value::String(_)) => {
+                self.remove_index_element(&index_map, field_values, value)?;
             }
             Some(Value::Array(values)) => {
                 validate_string_array_index_field(values, first_field)?;
@@ -366,12 +366,11 @@ impl TextIndexInner {
     }
 
     fn find_nitrite_ids(&self, find_plan: &FindPlan) -> NitriteResult<Vec<NitriteId>> {
-        let index_scan_filter = find_plan.index_scan_filter();
-        if index_scan_filter.is_none() {
+        let Some(index_scan_filter) = find_plan.index_scan_filter() else {
             return Ok(Vec::new());
-        }
+        };
 
-        let filters = index_scan_filter.unwrap().filters();
+        let filters = index_scan_filter.filters();
         if filters.len() != 1 {
             log::error!("Invalid filter count {} for text index", filters.len());
             return Err(INVALID_FILTER_COUNT_ERROR.clone());
@@ -6,7 +6,7 @@ use crate::{
     collection::{FindPlan, NitriteId},
     errors::{ErrorKind, NitriteError, NitriteResult},
     nitrite_config::NitriteConfig,
-    FieldValues, Fields, NitritePlugin, NitritePluginProvider, FULL_TEXT_INDEX,
+    read_index_map, FieldValues, Fields, NitritePlugin, NitritePluginProvider, FULL_TEXT_INDEX,
 };
 use dashmap::DashMap;
 use std::sync::Arc;
@@ -69,9 +69,9 @@ impl NitriteIndexerProvider for TextIndexer {
     fn drop_index(
         &self,
         index_descriptor: &IndexDescriptor,
-        _nitrite_config: &NitriteConfig,
+        nitrite_config: &NitriteConfig,
     ) -> NitriteResult<()> {
-        self.inner.drop_index(index_descriptor)
+        self.inner.drop_index(index_descriptor, nitrite_config)
     }
 
     fn write_index_entry(
@@ -101,6 +101,17 @@ impl NitriteIndexerProvider for TextIndexer {
     ) -> NitriteResult<Vec<NitriteId>> {
         self.inner.find_by_filter(find_plan, nitrite_config)
     }
+
+    fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        if self.inner.find_text_index(index_descriptor).is_none() {
+            self.inner.create_nitrite_index(index_descriptor, nitrite_config)?;
+        }
+        read_index_map(index_descriptor, nitrite_config)
+    }
 }
 
 struct TextIndexerInner {
@@ -153,12 +164,18 @@ impl TextIndexerInner {
         Ok(())
     }
 
-    fn drop_index(&self, index_descriptor: &IndexDescriptor) -> NitriteResult<()> {
-        let text_index = self.find_text_index(index_descriptor);
-        if let Some(text_index) = text_index {
-            text_index.drop_index()?;
-            self.index_registry.remove(index_descriptor);
-        }
+    fn drop_index(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        let text_index = match self.find_text_index(index_descriptor) {
+            Some(text_index) => text_index,
+            // not used since the database was opened, but its map holds entries
+            None => self.create_nitrite_index(index_descriptor, nitrite_config)?,
+        };
+        text_index.drop_index()?;
+        self.index_registry.remove(index_descriptor);
         Ok(())
     }
 
@@ -1,8 +1,8 @@
 use super::{
-    compound_index::CompoundIndex, nitrite_index::{NitriteIndex, NitriteIndexProvider}, simple_index::SimpleIndex, IndexDescriptor, NitriteIndexerProvider,
+    compound_index::CompoundIndex, nitrite_index::{NitriteIndex, NitriteIndexProvider}, simple_index::SimpleIndex, fold_field_values, is_sparse_skipped, IndexDescriptor, NitriteIndexerProvider,
 };
 use crate::{
-    collection::{FindPlan, NitriteId}, errors::{ErrorKind, NitriteError, NitriteResult}, nitrite_config::NitriteConfig, FieldValues, Fields, NitritePlugin, NitritePluginProvider, UNIQUE_INDEX,
+    collection::{Document, FindPlan, NitriteId}, errors::{ErrorKind, NitriteError, NitriteResult}, nitrite_config::NitriteConfig, read_index_map, FieldValues, Fields, NitritePlugin, NitritePluginProvider, UNIQUE_INDEX,
 };
 use dashmap::DashMap;
 use log::log;
@@ -70,9 +70,9 @@ impl NitriteIndexerProvider for UniqueIndexer {
     fn drop_index(
         &self,
         index_descriptor: &IndexDescriptor,
-        _nitrite_config: &NitriteConfig,
+        nitrite_config: &NitriteConfig,
     ) -> NitriteResult<()> {
-        self.inner.drop_index(index_descriptor)
+        self.inner.drop_index(index_descriptor, nitrite_config)
     }
 
     fn write_index_entry(
@@ -102,6 +102,33 @@ impl NitriteIndexerProvider for UniqueIndexer {
     ) -> NitriteResult<Vec<NitriteId>> {
         self.inner.find_by_filter(find_plan, nitrite_config)
     }
+
+    fn can_cover(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        self.inner.can_cover(index_descriptor, nitrite_config)
+    }
+
+    fn find_covered(
+        &self,
+        find_plan: &FindPlan,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        self.inner.find_covered(find_plan, nitrite_config)
+    }
+
+    fn warm_up(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<()> {
+        if self.inner.find_nitrite_index(index_descriptor).is_none() {
+            self.inner.create_nitrite_index(index_descriptor, nitrite_config)?;
+        }
+        read_index_map(index_descriptor, nitrite_config)
+    }
 }
 
 struct UniqueIndexerInner {
@@ -143,12 +170,15 @@ impl UniqueIndexerInner {
     fn drop_index(
         &self,
         index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
     ) -> NitriteResult<()> {
-        let nitrite_index = self.find_nitrite_index(index_descriptor);
-        if let Some(nitrite_index) = nitrite_index {
-            nitrite_index.drop_index()?;
-            self.index_registry.remove(index_descriptor);
-        }
+        let nitrite_index = match self.find_nitrite_index(index_descriptor) {
+            Some(nitrite_index) => nitrite_index,
+            // not used since the database was opened, but its map holds entries
+            None => self.create_nitrite_index(index_descriptor, nitrite_config)?,
+        };
+        nitrite_index.drop_index()?;
+        self.index_registry.remove(index_descriptor);
         Ok(())
     }
 
@@ -163,8 +193,16 @@ impl UniqueIndexerInner {
             nitrite_index = Some(self.create_nitrite_index(index_descriptor, nitrite_config)?);
         }
 
+        if index_descriptor.is_sparse() && is_sparse_skipped(field_values) {
+            return Ok(());
+        }
+
         if let Some(nitrite_index) = nitrite_index {
-            nitrite_index.write(field_values)?;
+            if index_descriptor.is_case_insensitive() {
+                nitrite_index.write(&fold_field_values(field_values))?;
+            } else {
+                nitrite_index.write(field_values)?;
+            }
         }
         Ok(())
     }
@@ -180,8 +218,16 @@ impl UniqueIndexerInner {
             nitrite_index = Some(self.create_nitrite_index(index_descriptor, nitrite_config)?);
         }
 
+        if index_descriptor.is_sparse() && is_sparse_skipped(field_values) {
+            return Ok(());
+        }
+
         if let Some(nitrite_index) = nitrite_index {
-            nitrite_index.remove(field_values)?;
+            if index_descriptor.is_case_insensitive() {
+                nitrite_index.remove(&fold_field_values(field_values))?;
+            } else {
+                nitrite_index.remove(field_values)?;
+            }
         }
         Ok(())
     }
@@ -214,6 +260,39 @@ impl UniqueIndexerInner {
             },
         }
     }
+
+    fn can_cover(
+        &self,
+        index_descriptor: &IndexDescriptor,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<bool> {
+        // a case-insensitive index holds folded values
+        if index_descriptor.is_case_insensitive() {
+            return Ok(false);
+        }
+        let nitrite_index = match self.find_nitrite_index(index_descriptor) {
+            Some(nitrite_index) => nitrite_index,
+            None => self.create_nitrite_index(index_descriptor, nitrite_config)?,
+        };
+        nitrite_index.has_exact_keys()
+    }
+
+    fn find_covered(
+        &self,
+        find_plan: &FindPlan,
+        nitrite_config: &NitriteConfig,
+    ) -> NitriteResult<Option<Vec<Document>>> {
+        let Some(index_descriptor) = find_plan.index_descriptor() else {
+            return Ok(None);
+        };
+        if !self.can_cover(&index_descriptor, nitrite_config)? {
+            return Ok(None);
+        }
+        match self.find_nitrite_index(&index_descriptor) {
+            Some(nitrite_index) => nitrite_index.find_covered(find_plan),
+            None => Ok(None),
+        }
+    }
 }
 
 
@@ -0,0 +1,488 @@
+use crate::{
+    derive_index_meta_map_name,
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    get_document_values,
+    index::{index_meta::IndexMeta, is_sparse_skipped, posting_list::decode_block, IndexDescriptor},
+    nitrite::Nitrite,
+    repository_name,
+    store::{NitriteMapProvider, NitriteStore, NitriteStoreProvider},
+    Convertible, FieldValues, PersistentCollection, Value, NON_UNIQUE_INDEX, UNIQUE_INDEX,
+};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// What opening a database does with the indexes an index check finds inconsistent,
+/// for example after a crash between a document write and its index writes.
+///
+/// Set with [`NitriteBuilder::index_recovery_policy`](crate::nitrite_builder::NitriteBuilder::index_recovery_policy).
+/// Without a policy no check runs at open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexRecoveryPolicy {
+    /// Fail to open the database, reporting the inconsistent indexes.
+    Fail,
+    /// Rebuild the inconsistent indexes from the documents before the database is returned.
+    Rebuild,
+    /// Disable the inconsistent indexes. Queries do not use a disabled index and writes
+    /// do not maintain it, so a unique index no longer rejects duplicates, until the
+    /// index is rebuilt with `rebuild_index()`.
+    Disable,
+}
+
+/// Why an index is inconsistent with its collection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexIssue {
+    /// The index is marked dirty: its build or rebuild did not complete.
+    Interrupted,
+    /// Documents that should have index entries have none.
+    MissingEntries {
+        /// Number of documents without their entries.
+        documents: u64,
+    },
+    /// Index entries point to documents that do not exist or should not be indexed.
+    DanglingEntries {
+        /// Number of documents the stray entries point to.
+        documents: u64,
+    },
+}
+
+impl Display for IndexIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexIssue::Interrupted => write!(f, "interrupted build"),
+            IndexIssue::MissingEntries { documents } => {
+                write!(f, "{} document(s) missing from the index", documents)
+            }
+            IndexIssue::DanglingEntries { documents } => {
+                write!(f, "entries for {} unknown document(s)", documents)
+            }
+        }
+    }
+}
+
+/// An index found inconsistent by an index check.
+#[derive(Debug, Clone)]
+pub struct InconsistentIndex {
+    /// The index, including the name of its collection or repository.
+    pub index_descriptor: IndexDescriptor,
+    /// What is wrong with it.
+    pub issues: Vec<IndexIssue>,
+}
+
+/// Result of an index check, see [`Nitrite::check_indexes`].
+///
+/// The entries of unique and non-unique indexes are compared with the documents of
+/// their collection. Other indexes, such as spatial and full-text ones, are only
+/// checked for an interrupted build.
+#[derive(Debug, Clone, Default)]
+pub struct IndexCheckReport {
+    /// Number of indexes checked.
+    pub checked: usize,
+    /// The indexes found inconsistent.
+    pub inconsistent: Vec<InconsistentIndex>,
+    /// The indexes disabled by an earlier check and not rebuilt since. They are not checked.
+    pub disabled: Vec<IndexDescriptor>,
+}
+
+impl IndexCheckReport {
+    /// Returns `true` if no index was found inconsistent.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistent.is_empty()
+    }
+}
+
+/// A collection or repository whose indexes are checked.
+struct IndexOwner {
+    name: String,
+    /// Entity name and key of a repository
+    repository: Option<(String, Option<String>)>,
+}
+
+fn index_owners(db: &Nitrite) -> NitriteResult<Vec<IndexOwner>> {
+    let mut owners: Vec<IndexOwner> = db
+        .list_collection_names()?
+        .into_iter()
+        .map(|name| IndexOwner {
+            name,
+            repository: None,
+        })
+        .collect();
+    for entity_name in db.list_repositories()? {
+        owners.push(IndexOwner {
+            name: entity_name.clone(),
+            repository: Some((entity_name, None)),
+        });
+    }
+    for (key, entity_names) in db.list_keyed_repositories()? {
+        for entity_name in entity_names {
+            owners.push(IndexOwner {
+                name: repository_name(&entity_name, Some(&key))?,
+                repository: Some((entity_name, Some(key.clone()))),
+            });
+        }
+    }
+    owners.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(owners)
+}
+
+pub(crate) fn check_indexes(db: &Nitrite) -> NitriteResult<IndexCheckReport> {
+    let store = db.store();
+    let mut report = IndexCheckReport::default();
+    for owner in index_owners(db)? {
+        check_owner(&store, &owner.name, &mut report)?;
+    }
+    Ok(report)
+}
+
+/// Checks the indexes of every collection and repository and handles the inconsistent
+/// ones as the policy says. Runs while the database is opened.
+pub(crate) fn recover_indexes(db: &Nitrite, policy: IndexRecoveryPolicy) -> NitriteResult<()> {
+    let store = db.store();
+    for owner in index_owners(db)? {
+        let mut report = IndexCheckReport::default();
+        check_owner(&store, &owner.name, &mut report)?;
+
+        let mut rebuild = Vec::new();
+        for inconsistent in report.inconsistent {
+            let issues: Vec<String> = inconsistent
+                .issues
+                .iter()
+                .map(|it| it.to_string())
+                .collect();
+            let message = format!(
+                "Index on {} of {} is inconsistent: {}",
+                inconsistent.index_descriptor.index_fields(),
+                owner.name,
+                issues.join(", ")
+            );
+            match policy {
+                IndexRecoveryPolicy::Fail => {
+                    log::error!("{}", message);
+                    return Err(NitriteError::new(&message, ErrorKind::IndexingError));
+                }
+                IndexRecoveryPolicy::Rebuild => {
+                    log::warn!("{}, rebuilding it", message);
+                    rebuild.push(inconsistent.index_descriptor);
+                }
+                IndexRecoveryPolicy::Disable => {
+                    log::warn!("{}, disabling it", message);
+                    disable_index(&store, &inconsistent.index_descriptor)?;
+                }
+            }
+        }
+        if policy == IndexRecoveryPolicy::Rebuild {
+            rebuild.extend(report.disabled);
+        }
+
+        if !rebuild.is_empty() {
+            let collection = match &owner.repository {
+                Some((entity_name, key)) => {
+                    db.repository_collection(entity_name, key.as_deref())?
+                }
+                None => db.collection(&owner.name)?,
+            };
+            for index_descriptor in rebuild {
+                let field_names = index_descriptor.index_fields().field_names();
+                collection.rebuild_index(field_names.iter().map(String::as_str).collect())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_owner(
+    store: &NitriteStore,
+    name: &str,
+    report: &mut IndexCheckReport,
+) -> NitriteResult<()> {
+    let index_meta_map_name = derive_index_meta_map_name(name);
+    if !store.has_map(&index_meta_map_name)? {
+        return Ok(());
+    }
+
+    for entry in store.open_map(&index_meta_map_name)?.entries()? {
+        let (_, value) = entry?;
+        let index_meta = IndexMeta::from_value(&value)?;
+        let index_descriptor = index_meta.index_descriptor();
+        if index_meta.is_disabled() {
+            report.disabled.push(index_descriptor);
+            continue;
+        }
+
+        report.checked += 1;
+        let issues = if index_meta.is_dirty() {
+            vec![IndexIssue::Interrupted]
+        } else {
+            let index_type = index_descriptor.index_type();
+            if index_type == UNIQUE_INDEX || index_type == NON_UNIQUE_INDEX {
+                compare_entries(store, name, &index_meta)?
+            } else {
+                // plugin indexes keep their own layout
+                Vec::new()
+            }
+        };
+
+        if !issues.is_empty() {
+            report.inconsistent.push(InconsistentIndex {
+                index_descriptor,
+                issues,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Compares the ids an index holds with the documents that should have entries in it.
+fn compare_entries(
+    store: &NitriteStore,
+    collection_name: &str,
+    index_meta: &IndexMeta,
+) -> NitriteResult<Vec<IndexIssue>> {
+    let index_descriptor = index_meta.index_descriptor();
+    let mut indexed = indexed_ids(store, &index_meta.index_map_name())?;
+
+    let mut missing = 0;
+    if store.has_map(collection_name)? {
+        let fields = index_descriptor.index_fields();
+        for entry in store.open_map(collection_name)?.entries()? {
+            let (_, value) = entry?;
+            if let Value::Document(mut doc) = value {
+                let field_values = get_document_values(&mut doc, &fields)?;
+                let id = field_values.nitrite_id().id_value();
+                let present = indexed.remove(&id);
+                if !present && has_entries(&field_values, index_descriptor.is_sparse()) {
+                    missing += 1;
+                }
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    if missing > 0 {
+        issues.push(IndexIssue::MissingEntries { documents: missing });
+    }
+    if !indexed.is_empty() {
+        issues.push(IndexIssue::DanglingEntries {
+            documents: indexed.len() as u64,
+        });
+    }
+    Ok(issues)
+}
+
+/// Reads the ids of an index map, in all index layouts: `value -> [ids]` rows of unique
+/// single-field indexes, `[value, .., id] -> null` composite rows and the
+/// `[value, first_id] -> bytes` blocks of compressed indexes.
+fn indexed_ids(store: &NitriteStore, index_map_name: &str) -> NitriteResult<HashSet<u64>> {
+    let mut ids = HashSet::new();
+    if !store.has_map(index_map_name)? {
+        return Ok(ids);
+    }
+
+    for entry in store.open_map(index_map_name)?.entries()? {
+        let (key, value) = entry?;
+        match (&key, &value) {
+            (Value::Array(parts), Value::Null) => {
+                if let Some(Value::NitriteId(id)) = parts.last() {
+                    ids.insert(id.id_value());
+                }
+            }
+            (Value::Array(_), Value::Bytes(block)) => {
+                ids.extend(decode_block(block)?.iter().map(|id| id.id_value()));
+            }
+            (_, Value::Array(values)) => {
+                ids.extend(
+                    values
+                        .iter()
+                        .filter_map(|it| it.as_nitrite_id())
+                        .map(|id| id.id_value()),
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(ids)
+}
+
+/// Whether writing the document into the index leaves at least one entry. Mirrors the
+/// writes of the simple and compound indexes, which skip non-comparable values and
+/// write one entry per element of an array.
+fn has_entries(field_values: &FieldValues, sparse: bool) -> bool {
+    if sparse && is_sparse_skipped(field_values) {
+        return false;
+    }
+    match field_values.values().first().map(|(_, value)| value) {
+        None | Some(Value::Null) => true,
+        Some(Value::Array(values)) => !values.is_empty(),
+        Some(value) => value.is_comparable(),
+    }
+}
+
+/// Marks an index disabled in its metadata, without opening its collection.
+fn disable_index(store: &NitriteStore, index_descriptor: &IndexDescriptor) -> NitriteResult<()> {
+    let index_meta_map = store.open_map(&derive_index_meta_map_name(
+        &index_descriptor.collection_name(),
+    ))?;
+    let key = index_descriptor.index_fields().to_value()?;
+    if let Some(value) = index_meta_map.get(&key)? {
+        let mut index_meta = IndexMeta::from_value(&value)?;
+        index_meta.set_disabled(true);
+        index_meta_map.put(key, index_meta.to_value()?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::{NitriteCollection, NitriteId};
+    use crate::derive_index_map_name;
+    use crate::doc;
+    use crate::index::{non_unique_index, unique_index, IndexOptions};
+
+    fn index_map_of(
+        db: &Nitrite,
+        collection: &NitriteCollection,
+        fields: Vec<&str>,
+    ) -> crate::store::NitriteMap {
+        let index_descriptor = collection
+            .list_indexes()
+            .unwrap()
+            .into_iter()
+            .find(|it| it.index_fields().field_names() == fields)
+            .unwrap();
+        db.store()
+            .open_map(&derive_index_map_name(&index_descriptor))
+            .unwrap()
+    }
+
+    fn populated_db() -> (Nitrite, NitriteCollection) {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let users = db.collection("users").unwrap();
+        users.create_index(vec!["email"], &unique_index()).unwrap();
+        users
+            .create_index(vec!["age"], &non_unique_index())
+            .unwrap();
+        users
+            .create_index(vec!["city", "age"], &non_unique_index())
+            .unwrap();
+        users
+            .create_index(
+                vec!["nick"],
+                &IndexOptions::new(NON_UNIQUE_INDEX).sparse(true),
+            )
+            .unwrap();
+        users
+            .create_index(vec!["tags"], &non_unique_index())
+            .unwrap();
+        for i in 0..10 {
+            users
+                .insert(doc! { email: (format!("u{}@x.io", i)), age: (i % 3), city: "Oslo", tags: ["a", "b"] })
+                .unwrap();
+        }
+        // no entries in the sparse and the multikey index
+        users
+            .insert(doc! { email: "x@x.io", nick: "x", tags: [] })
+            .unwrap();
+        (db, users)
+    }
+
+    #[test]
+    fn test_check_consistent_indexes() {
+        let (db, _) = populated_db();
+        let report = db.check_indexes().unwrap();
+        assert_eq!(report.checked, 5);
+        assert!(report.is_consistent(), "{:?}", report.inconsistent);
+        assert!(report.disabled.is_empty());
+    }
+
+    #[test]
+    fn test_check_missing_and_dangling_entries() {
+        let (db, users) = populated_db();
+        index_map_of(&db, &users, vec!["email"]).clear().unwrap();
+        let ages = index_map_of(&db, &users, vec!["age"]);
+        let stray = NitriteId::new();
+        ages.put(
+            Value::Array(vec![Value::I32(1), Value::NitriteId(stray)]),
+            Value::Null,
+        )
+        .unwrap();
+
+        let report = db.check_indexes().unwrap();
+        assert_eq!(report.inconsistent.len(), 2);
+        let issues_of = |field: &str| {
+            report
+                .inconsistent
+                .iter()
+                .find(|it| it.index_descriptor.index_fields().field_names() == vec![field])
+                .map(|it| it.issues.clone())
+                .unwrap()
+        };
+        assert_eq!(
+            issues_of("email"),
+            vec![IndexIssue::MissingEntries { documents: 11 }]
+        );
+        assert_eq!(
+            issues_of("age"),
+            vec![IndexIssue::DanglingEntries { documents: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_check_interrupted_build() {
+        let (db, users) = populated_db();
+        let index_descriptor = users
+            .list_indexes()
+            .unwrap()
+            .into_iter()
+            .find(|it| it.index_fields().field_names() == vec!["age"])
+            .unwrap();
+        let meta_map = db
+            .store()
+            .open_map(&derive_index_meta_map_name("users"))
+            .unwrap();
+        let key = index_descriptor.index_fields().to_value().unwrap();
+        let mut index_meta = IndexMeta::from_value(&meta_map.get(&key).unwrap().unwrap()).unwrap();
+        index_meta.set_dirty(true);
+        meta_map.put(key, index_meta.to_value().unwrap()).unwrap();
+
+        let report = db.check_indexes().unwrap();
+        assert_eq!(report.inconsistent.len(), 1);
+        assert_eq!(report.inconsistent[0].issues, vec![IndexIssue::Interrupted]);
+    }
+
+    #[test]
+    fn test_recover_fail_and_rebuild() {
+        let (db, users) = populated_db();
+        index_map_of(&db, &users, vec!["city", "age"])
+            .clear()
+            .unwrap();
+
+        let err = recover_indexes(&db, IndexRecoveryPolicy::Fail).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::IndexingError);
+
+        recover_indexes(&db, IndexRecoveryPolicy::Rebuild).unwrap();
+        assert!(db.check_indexes().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_disable_index_until_rebuild() {
+        let (db, users) = populated_db();
+        index_map_of(&db, &users, vec!["email"]).clear().unwrap();
+        let index_descriptor = users
+            .list_indexes()
+            .unwrap()
+            .into_iter()
+            .find(|it| it.index_fields().field_names() == vec!["email"])
+            .unwrap();
+        disable_index(&db.store(), &index_descriptor).unwrap();
+
+        let report = db.check_indexes().unwrap();
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.disabled.len(), 1);
+
+        // a rebuild clears the flag
+        users.rebuild_index(vec!["email"]).unwrap();
+        let report = db.check_indexes().unwrap();
+        assert!(report.disabled.is_empty());
+        assert!(report.is_consistent());
+    }
+}
@@ -4,6 +4,9 @@
     clippy::invisible_characters,
     clippy::approx_constant,
 )]
+// A panic inside the library takes the host application down with it, so library code
+// reports failures as `NitriteResult` errors instead. Tests may still unwrap.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used, clippy::panic))]
 //! # Nitrite - Embedded NoSQL Database
 //!
 //! Nitrite is a lightweight, feature-rich, embedded NoSQL database written in Rust.
@@ -20,6 +23,7 @@
 //! - **Transactions**: ACID transaction support
 //! - **Migration**: Schema migration management
 //! - **Events**: Event listeners for database, collection, and store events
+//! - **Topics**: In-process publish/subscribe with at-least-once delivery
 //! - **Multiple Storage Backends**: In-memory storage and pluggable store providers
 //! - **Clean API**: PIMPL pattern provides stable, encapsulated interface
 //!
@@ -73,42 +77,74 @@
 //! - [`common`] - Common types, traits, and utilities
 //! - [`errors`] - Error types and result definitions
 //! - [`filter`] - Query filters and filter providers
+//! - [`fmt`] - Plain-text tables of documents and descriptions of query plans
 //! - [`index`] - Indexing support (unique, non-unique, full-text)
 //! - [`metadata`] - Database metadata management
 //! - [`migration`] - Schema migration support
 //! - [`nitrite`] - Core database interface
 //! - [`nitrite_builder`] - Database builder for initialization
 //! - [`nitrite_config`] - Database configuration
+//! - [`profiler`] - Opt-in phase timings of the collection operations
 //! - [`repository`] - Type-safe object repositories
+//! - [`spi`] - Stable extension points for third-party indexers and stores
 //! - [`store`] - Storage backend abstractions
+//! - [`topic`] - Publish/subscribe topics backed by capped collections
 //! - [`transaction`] - Transaction support
+//! - [`view`] - Read-only views derived from a collection and kept up to date
 
 use crate::collection::snowflake::SnowflakeIdGenerator;
 use crate::common::*;
 use std::sync::LazyLock;
 use std::thread::available_parallelism;
 
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod audit;
 pub mod collection;
+#[cfg(feature = "arrow")]
+pub mod columnar;
 pub mod common;
+#[cfg(feature = "config")]
+pub mod config_file;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod describe;
 pub mod errors;
 pub mod filter;
+pub mod fmt;
+pub mod graph;
 pub mod index;
+pub mod index_check;
 pub mod metadata;
 pub mod migration;
 pub mod nitrite;
 pub mod nitrite_builder;
 pub mod nitrite_config;
+pub mod profiler;
+pub mod read_view;
 pub mod repository;
+pub mod shard;
+pub mod snapshot;
+pub mod spi;
+#[cfg(feature = "sql")]
+pub mod sql;
 pub mod store;
+pub mod tenant;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod time_series;
+pub mod topic;
 pub mod transaction;
+pub mod view;
+pub mod warm_up;
 
 pub(crate) static FIELD_SEPARATOR: LazyLock<Atomic<String>> =
     LazyLock::new(|| atomic(".".to_string()));
+pub(crate) static METADATA_PREFIX: LazyLock<Atomic<String>> =
+    LazyLock::new(|| atomic(DEFAULT_METADATA_PREFIX.to_string()));
 pub(crate) static ID_GENERATOR: LazyLock<SnowflakeIdGenerator> =
     LazyLock::new(SnowflakeIdGenerator::new);
 
-pub(crate) static SCHEDULER: LazyLock<Scheduler> = LazyLock::new(Scheduler::new);
-
 /// Returns the number of available CPU cores.
 ///
 /// This function attempts to detect the number of available processors on the system.
@@ -150,12 +186,4 @@ mod tests {
         let count = get_cpu_count();
         assert!(count > 0);
     }
-
-    #[test]
-    fn test_scheduler_initialization() {
-        // Test that SCHEDULER initializes successfully
-        let _scheduler = &*SCHEDULER;
-        // If we can access it, initialization was successful
-        assert!(true);
-    }
 }
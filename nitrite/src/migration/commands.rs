@@ -10,6 +10,7 @@ use crate::{
     },
     common::{Fields, NitriteEventBus, Value, DOC_ID, UNIQUE_INDEX},
     errors::{ErrorKind, NitriteError, NitriteResult},
+    index::IndexOptions,
     nitrite::Nitrite,
     store::{NitriteMap, NitriteStore},
 };
@@ -142,7 +143,7 @@ impl Command for MigrationCommand {
                     if let Some(index) = &index_descriptor {
                         ops.create_index(
                             &Fields::with_names(vec![field_name])?,
-                            &index.index_type(),
+                            &index.index_options(),
                         )?;
                     }
                 }
@@ -203,7 +204,7 @@ impl Command for MigrationCommand {
                     ops.drop_index(old_id_field)?;
                 }
 
-                ops.create_index(new_id_field, UNIQUE_INDEX)?;
+                ops.create_index(new_id_field, &IndexOptions::new(UNIQUE_INDEX))?;
 
                 Ok(())
             }
@@ -218,7 +219,7 @@ impl Command for MigrationCommand {
                     NitriteError::new("Operations not initialized", ErrorKind::MigrationError)
                 })?;
 
-                ops.create_index(fields, index_type)?;
+                ops.create_index(fields, &IndexOptions::new(index_type))?;
 
                 Ok(())
             }
@@ -314,7 +315,7 @@ impl Command for MigrationCommand {
                     IndexManager::new(collection_name.clone(), nitrite.config().clone())?;
                 let index_entries = index_manager.get_index_descriptors()?;
                 for index in index_entries {
-                    new_ops.create_index(&index.index_fields(), &index.index_type())?;
+                    new_ops.create_index(&index.index_fields(), &index.index_options())?;
                 }
 
                 if let Some(ops) = ops {
@@ -381,7 +382,7 @@ impl Command for MigrationCommand {
                     }
                     let new_fields =
                         Fields::with_names(new_field_names.iter().map(String::as_str).collect())?;
-                    ops.create_index(&new_fields, &descriptor.index_type())?;
+                    ops.create_index(&new_fields, &descriptor.index_options())?;
                     ops.drop_index(&descriptor.index_fields())?;
                 }
 
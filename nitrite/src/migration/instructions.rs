@@ -8,7 +8,7 @@ use crate::{
 use std::{
     any::Any
     ,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, PoisonError},
 };
 
 /// Unified wrapper enum for all migration function closures that can be type-erased and downcasted.
@@ -234,7 +234,10 @@ impl InstructionSet {
     /// # Errors
     /// Returns error if mutex is poisoned
     pub fn get_steps(&self) -> NitriteResult<Vec<MigrationStep>> {
-        let steps = self.migration_steps.lock().unwrap();
+        let steps = self.migration_steps.lock().map_err(|_| {
+            log::error!("Migration steps lock is poisoned");
+            NitriteError::new("Migration steps lock is poisoned", ErrorKind::InvalidOperation)
+        })?;
         Ok(steps.clone())
     }
 
@@ -487,7 +490,8 @@ impl DatabaseInstructionBuilder {
     }
 
     fn add_step(&mut self, step: MigrationStep) {
-        let mut steps = self.steps.lock().unwrap();
+        // a push cannot leave the list half-updated, so a poisoned lock is still usable
+        let mut steps = self.steps.lock().unwrap_or_else(PoisonError::into_inner);
         steps.push(step);
     }
 
@@ -668,7 +672,8 @@ impl CollectionInstructionBuilder {
     }
 
     fn add_step(&mut self, step: MigrationStep) {
-        let mut steps = self.steps.lock().unwrap();
+        // a push cannot leave the list half-updated, so a poisoned lock is still usable
+        let mut steps = self.steps.lock().unwrap_or_else(PoisonError::into_inner);
         steps.push(step);
     }
 
@@ -941,7 +946,8 @@ impl RepositoryInstructionBuilder {
     }
 
     fn add_step(&mut self, step: MigrationStep) {
-        let mut steps = self.steps.lock().unwrap();
+        // a push cannot leave the list half-updated, so a poisoned lock is still usable
+        let mut steps = self.steps.lock().unwrap_or_else(PoisonError::into_inner);
         steps.push(step);
     }
 
@@ -1,7 +1,10 @@
 use super::instructions::MigrationFn;
 use super::migration::{Migration, MigrationStep};
 use crate::collection::Document;
-use crate::common::{repository_name, AuthService, Fields};
+use crate::common::{
+    repository_name, AttributeAware, AuthService, Fields, ENTITY_SCHEMA_FIELDS,
+    ENTITY_SCHEMA_FINGERPRINT, ENTITY_SCHEMA_VERSION,
+};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::migration::commands::{Command, MigrationCommand};
 use crate::migration::InstructionType;
@@ -554,6 +557,49 @@ impl MigrationManager {
         };
 
         command.execute(self.nitrite.clone())?;
+
+        // the entity changed, so its new schema is recorded when the repository is next opened
+        let migrated_repository = match step.instruction_type {
+            InstructionType::RepositoryRename => {
+                let (new_entity_name, new_key) =
+                    step.arguments.as_double::<String, Option<String>>()?;
+                Some(repository_name(&new_entity_name, new_key.as_deref())?)
+            }
+            InstructionType::RepositoryAddField
+            | InstructionType::RepositoryRenameField
+            | InstructionType::RepositoryDeleteField
+            | InstructionType::RepositoryChangeDataType
+            | InstructionType::RepositoryChangeIdField
+            | InstructionType::RepositoryDropIndex
+            | InstructionType::RepositoryDropAllIndices
+            | InstructionType::RepositoryCreateIndex => step
+                .entity_name
+                .as_ref()
+                .map(|entity_name| repository_name(entity_name, step.key.as_deref()))
+                .transpose()?,
+            _ => None,
+        };
+        if let Some(collection_name) = migrated_repository {
+            self.reset_entity_schema(&collection_name)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the schema fingerprint stored for a repository
+    fn reset_entity_schema(&self, collection_name: &str) -> NitriteResult<()> {
+        let store = self.nitrite.store();
+        if !store.has_map(collection_name)? {
+            return Ok(());
+        }
+
+        let map = store.open_map(collection_name)?;
+        if let Some(mut attributes) = map.attributes()? {
+            if attributes.remove(ENTITY_SCHEMA_FINGERPRINT).is_some() {
+                attributes.remove(ENTITY_SCHEMA_FIELDS);
+                attributes.remove(ENTITY_SCHEMA_VERSION);
+                map.set_attributes(attributes)?;
+            }
+        }
         Ok(())
     }
 }
@@ -0,0 +1,108 @@
+use crate::{
+    common::{rename_metadata_fields, repository_name, Value},
+    errors::NitriteResult,
+    nitrite::Nitrite,
+};
+
+/// Moves the document metadata of an existing database to the configured prefix.
+///
+/// Use this after changing [`crate::nitrite_builder::NitriteBuilder::metadata_prefix`]
+/// on a database whose documents were written with `old_prefix` (`"_"` for databases
+/// created with the default configuration). Every collection, repository and keyed
+/// repository is rewritten in place; revisions and modification times are preserved.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let db = Nitrite::builder()
+///     .metadata_prefix("$nitrite.")
+///     .open_or_create(None, None)?;
+///
+/// migrate_metadata_prefix(&db, "_")?;
+/// ```
+pub fn migrate_metadata_prefix(nitrite: &Nitrite, old_prefix: &str) -> NitriteResult<()> {
+    let new_prefix = nitrite.config().metadata_prefix();
+    if old_prefix == new_prefix {
+        return Ok(());
+    }
+
+    let mut map_names: Vec<String> = nitrite.list_collection_names()?.into_iter().collect();
+    map_names.extend(nitrite.list_repositories()?);
+    for (key, types) in nitrite.list_keyed_repositories()? {
+        for entity_name in types {
+            map_names.push(repository_name(&entity_name, Some(&key))?);
+        }
+    }
+
+    let store = nitrite.store();
+    for map_name in map_names {
+        let map = store.open_map(&map_name)?;
+        for entry in map.entries()? {
+            let (key, value) = entry?;
+            if let Value::Document(mut doc) = value {
+                if rename_metadata_fields(&mut doc, old_prefix, &new_prefix)? {
+                    map.put(key, Value::Document(doc))?;
+                }
+            }
+        }
+    }
+
+    log::info!(
+        "Migrated document metadata prefix from '{}' to '{}'",
+        old_prefix,
+        new_prefix
+    );
+    nitrite.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{DOC_MODIFIED, DOC_REVISION};
+    use crate::doc;
+    use crate::filter::all;
+    use crate::nitrite_config::NitriteConfig;
+
+    fn setup_nitrite() -> NitriteResult<Nitrite> {
+        let config = NitriteConfig::default();
+        config.auto_configure()?;
+        let nitrite = Nitrite::new(config);
+        nitrite.initialize(None, None)?;
+        Ok(nitrite)
+    }
+
+    #[test]
+    fn test_migrate_metadata_prefix() -> NitriteResult<()> {
+        let nitrite = setup_nitrite()?;
+        let col = nitrite.collection("test_migrate_metadata_prefix")?;
+        col.insert(doc! { name: "a" })?;
+
+        // simulate documents written by a database using a legacy prefix
+        let map = nitrite.store().open_map("test_migrate_metadata_prefix")?;
+        for entry in map.entries()? {
+            let (key, value) = entry?;
+            let mut doc = value.as_document().unwrap().clone();
+            rename_metadata_fields(&mut doc, "_", "legacy_")?;
+            map.put(key, Value::Document(doc))?;
+        }
+
+        let doc = col.find(all())?.next().unwrap()?;
+        assert!(doc.get(DOC_REVISION)?.is_null());
+        assert_eq!(doc.get("legacy_revision")?, Value::I32(1));
+
+        migrate_metadata_prefix(&nitrite, "legacy_")?;
+
+        let doc = col.find(all())?.next().unwrap()?;
+        assert_eq!(doc.revision()?, 1);
+        assert!(!doc.get(DOC_MODIFIED)?.is_null());
+        assert!(doc.get("legacy_revision")?.is_null());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_metadata_prefix_same_prefix() -> NitriteResult<()> {
+        let nitrite = setup_nitrite()?;
+        assert!(migrate_metadata_prefix(&nitrite, "_").is_ok());
+        Ok(())
+    }
+}
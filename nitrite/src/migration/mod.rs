@@ -42,9 +42,11 @@ mod manager;
 mod migration;
 mod instructions;
 mod commands;
+mod metadata_prefix;
 
 pub use instructions::*;
 pub use manager::MigrationManager;
+pub use metadata_prefix::migrate_metadata_prefix;
 pub use migration::{
     Migration, MigrationArguments, MigrationStep,
 };
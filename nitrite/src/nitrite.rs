@@ -1,18 +1,42 @@
 use crate::collection;
-use crate::common::{get_key_name, get_keyed_repo_type, repository_name_by_type, Convertible, LockRegistry, NitritePluginProvider};
-use crate::repository::{NitriteEntity, ObjectRepository, RepositoryFactory};
-use crate::transaction::Session;
+use crate::common::{get_key_name, get_keyed_repo_type, repository_name, repository_name_by_type, Convertible, LockRegistry, ModuleInfo, NitritePluginProvider};
+use crate::repository::{
+    DynamicRepository, EntityDescriptor, IdFnRepository, NitriteEntity, ObjectRepository,
+    RepositoryFactory,
+};
+use crate::snapshot::NitriteSnapshot;
+use crate::read_view::{ReadView, ReadViewOptions};
+use crate::audit::{AuditLog, AuditOptions};
+use crate::profiler::Profiler;
+use crate::tenant::{tenant_of, tenant_prefix, Tenant};
+#[cfg(feature = "sql")]
+use crate::sql::SqlQuery;
+use crate::topic::{Topic, TopicOptions};
+use crate::graph::{forget_graph_vertices, graph_collection_name, Graph};
+#[cfg(feature = "disk_watchdog")]
+use crate::store::disk::DiskMonitor;
+#[cfg(feature = "fault_injection")]
+use crate::store::fault::FaultInjector;
+use crate::time_series::{forget_time_series_options, time_series_collection_name, TimeSeries, TimeSeriesOptions};
+use crate::view::{view_collection_name, View, ViewOptions};
+use crate::shard::{forget_layout, shard_collection_name, ShardOptions, ShardedCollection};
+use crate::describe::DatabaseDescription;
+use crate::index_check::{check_indexes, recover_indexes, IndexCheckReport};
+use crate::warm_up::{index_load_status, start_warm_up, IndexLoadStatus, WarmUpHandle};
+use crate::transaction::{retry, NitriteTransaction, RetryPolicy, Session};
 use crate::{
-    collection::{CollectionFactory, Document, NitriteCollection},
+    collection::{CollectionFactory, CollectionOptions, Document, NitriteCollection, Reference},
     errors::{ErrorKind, NitriteError, NitriteResult},
     get_current_time_or_zero,
     metadata::NitriteMetadata,
     migration::MigrationManager,
     nitrite_builder::NitriteBuilder,
     nitrite_config::NitriteConfig,
-    store::{Metadata, NitriteMapProvider, NitriteStore, NitriteStoreProvider},
-    AuthService, Value, NITRITE_VERSION, RESERVED_NAMES, STORE_INFO,
+    store::{Metadata, NitriteMapProvider, NitriteStore, NitriteStoreProvider, StoreModule},
+    AuthService, Value, AUDIT_COLLECTION, INTERNAL_NAME_SEPARATOR, NITRITE_VERSION, RESERVED_NAMES, SORT_PREFIX,
+    STORE_INFO, TOPIC_PREFIX,
 };
+use parking_lot::Mutex;
 use std::collections::{HashMap, HashSet};
 use std::marker;
 use std::ops::Deref;
@@ -88,9 +112,20 @@ impl Nitrite {
     }
 
     pub(crate) fn new(nitrite_config: NitriteConfig) -> Self {
-        Nitrite {
-            inner: Arc::new(NitriteInner::new(nitrite_config.clone())),
-        }
+        let inner = Arc::new(NitriteInner::new(nitrite_config.clone()));
+
+        // the registry lives in the config the database holds, so it must not hold the
+        // database in turn
+        let database = Arc::downgrade(&inner);
+        nitrite_config.references().set_resolver(move |name| match database.upgrade() {
+            Some(inner) => inner.collection_factory.get_collection(name, inner.nitrite_config.clone(), false),
+            None => {
+                log::error!("Database is closed");
+                Err(NitriteError::new("Database is closed", ErrorKind::InvalidOperation))
+            }
+        });
+
+        Nitrite { inner }
     }
 
     /// Gets a collection by name, creating it if it doesn't exist.
@@ -113,6 +148,209 @@ impl Nitrite {
         self.inner.collection(name)
     }
 
+    /// Gets a collection by name, creating it if it doesn't exist, and applies `options`.
+    ///
+    /// The options replace the ones the collection had and are persisted with it, so
+    /// `collection()` restores them after the database is reopened.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The collection name
+    /// * `options` - The settings of the collection
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `collection()`, or an error if the options cannot be stored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use nitrite::collection::{CollectionOptions, WriteDurability};
+    ///
+    /// let logs = db.collection_with_options(
+    ///     "logs",
+    ///     CollectionOptions::new().durability(WriteDurability::Sync).required_field("level"),
+    /// )?;
+    /// ```
+    pub fn collection_with_options(
+        &self,
+        name: &str,
+        options: CollectionOptions,
+    ) -> NitriteResult<NitriteCollection> {
+        let collection = self.inner.collection(name)?;
+        collection.set_options(options)?;
+        Ok(collection)
+    }
+
+    /// Gets a topic by name, creating it if it doesn't exist, with the default options.
+    ///
+    /// See [`Topic`] for the delivery guarantees.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the name is not a valid collection
+    /// name.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let jobs = db.topic("jobs")?;
+    /// jobs.publish(doc! { kind: "resize" })?;
+    /// ```
+    pub fn topic(&self, name: &str) -> NitriteResult<Topic> {
+        self.inner.topic(name, TopicOptions::default())
+    }
+
+    /// Gets a topic by name, creating it if it doesn't exist, with the given options.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `topic()`.
+    pub fn topic_with_options(&self, name: &str, options: TopicOptions) -> NitriteResult<Topic> {
+        self.inner.topic(name, options)
+    }
+
+    /// Opens a time series, creating it if it doesn't exist.
+    ///
+    /// A time series stores numeric points keyed by series and timestamp in time
+    /// buckets, far more compactly than one document per point. See [`TimeSeries`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, the name is not a valid collection name
+    /// or contains the internal name separator, or the time series exists with another
+    /// bucket width.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let readings = db.time_series(
+    ///     "readings",
+    ///     TimeSeriesOptions::new().retention(Duration::from_secs(7 * 24 * 3600)),
+    /// )?;
+    /// readings.insert("sensor-1", DataPoint::new(now).with("temperature", 21.5))?;
+    /// ```
+    pub fn time_series(&self, name: &str, options: TimeSeriesOptions) -> NitriteResult<TimeSeries> {
+        self.inner.time_series(name, options)
+    }
+
+    /// Drops a time series and all its points. Dropping a time series that doesn't exist
+    /// does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the points cannot be removed.
+    pub fn drop_time_series(&self, name: &str) -> NitriteResult<()> {
+        self.inner.drop_time_series(name)
+    }
+
+    /// Opens a graph over the documents of the `vertices` collection, creating it if it
+    /// doesn't exist.
+    ///
+    /// The graph stores labelled edges between the ids of the vertices and answers
+    /// neighbor, traversal and shortest path queries with the vertex documents. See
+    /// [`Graph`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, a name is not a valid collection name,
+    /// the graph name contains the internal name separator, or the graph exists over
+    /// another collection.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let social = db.graph("social", "people")?;
+    /// social.add_edge(&alice, &bob, "friend")?;
+    /// let friends = social.neighbors(&alice, Direction::Outgoing, Some("friend"))?;
+    /// ```
+    pub fn graph(&self, name: &str, vertices: &str) -> NitriteResult<Graph> {
+        self.inner.graph(name, vertices)
+    }
+
+    /// Drops a graph and all its edges; the vertex collection is left as it is. Dropping
+    /// a graph that doesn't exist does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the edges cannot be removed.
+    pub fn drop_graph(&self, name: &str) -> NitriteResult<()> {
+        self.inner.drop_graph(name)
+    }
+
+    /// Opens a view over a collection, creating it if it doesn't exist.
+    ///
+    /// The documents of the view are brought in line with the source before it is
+    /// returned, then kept up to date by every write to the source. Opening a view that is
+    /// already open replaces its definition. See [`View`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, the name is not a valid collection name
+    /// or contains the internal name separator, or the source documents cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let open_orders = db.view(
+    ///     "open_orders",
+    ///     ViewOptions::new("orders").filter(field("status").eq("open")),
+    /// )?;
+    /// let count = open_orders.size()?;
+    /// ```
+    pub fn view(&self, name: &str, options: ViewOptions) -> NitriteResult<View> {
+        self.inner.view(name, options)
+    }
+
+    /// Drops a view: stops maintaining it and removes its documents and indexes. Dropping
+    /// a view that doesn't exist does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the documents cannot be removed.
+    pub fn drop_view(&self, name: &str) -> NitriteResult<()> {
+        self.inner.drop_view(name)
+    }
+
+    /// Opens a collection partitioned over several shards, creating it if it doesn't
+    /// exist.
+    ///
+    /// Each shard is a collection of its own, kept out of the catalog, and documents are
+    /// routed to a shard by their shard key. The layout is stored with the shards and
+    /// cannot change once the collection exists. See [`ShardedCollection`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, the name is not a valid collection name
+    /// or contains the internal name separator, the layout is invalid or differs from the
+    /// stored one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use nitrite::shard::ShardOptions;
+    ///
+    /// let events = db.sharded_collection("events", ShardOptions::hash("device", 4))?;
+    /// events.insert(doc! { device: "sensor-7", reading: 21.5 })?;
+    /// ```
+    pub fn sharded_collection(
+        &self,
+        name: &str,
+        options: ShardOptions,
+    ) -> NitriteResult<ShardedCollection> {
+        self.inner.sharded_collection(name, options)
+    }
+
+    /// Drops a sharded collection: removes the documents and indexes of all its shards.
+    /// Dropping a sharded collection that doesn't exist does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or a shard cannot be removed.
+    pub fn drop_sharded_collection(&self, name: &str) -> NitriteResult<()> {
+        self.inner.drop_sharded_collection(name)
+    }
+
     /// Gets or creates a typed object repository for entities of type `T`.
     ///
     /// A repository provides type-safe access to stored objects, handling serialization
@@ -146,6 +384,49 @@ impl Nitrite {
         self.inner.repository(None)
     }
 
+    /// Gets or creates a typed object repository whose entities are identified by a
+    /// closure.
+    ///
+    /// Use it for ids `#[entity(id)]` cannot express, like an id computed from several
+    /// fields. The id returned by `id_fn` is stored with each entity under a unique index
+    /// and used by `update_one` upserts, `remove_one` and `get_by_id` lookups. See
+    /// [`IdFnRepository`].
+    ///
+    /// # Type Parameters
+    ///
+    /// * `T` - The entity type implementing `NitriteEntity` and `Convertible`
+    /// * `K` - The id type returned by the closure
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, or if entities already stored in the
+    /// repository have the same id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let repo = db.repository_with_id_fn::<Line, (String, i64)>(|line| {
+    ///     (line.order.clone(), line.number)
+    /// })?;
+    /// repo.update_one(line, true)?;
+    /// let line = repo.get_by_id(&("A-100".to_string(), 2))?;
+    /// ```
+    pub fn repository_with_id_fn<T, K>(
+        &self,
+        id_fn: impl Fn(&T) -> K + Send + Sync + 'static,
+    ) -> NitriteResult<IdFnRepository<T, K>>
+    where
+        T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
+        K: Convertible + Send + Sync + 'static,
+    {
+        self.inner.check_opened()?;
+        self.inner.repository_factory.get_repository_with_id_fn(
+            None,
+            self.inner.nitrite_config.clone(),
+            Arc::new(id_fn),
+        )
+    }
+
     /// Gets or creates a keyed typed object repository for entities of type `T`.
     ///
     /// Similar to `repository()`, but allows multiple repositories of the same type
@@ -175,7 +456,73 @@ impl Nitrite {
         self.inner.repository(Some(key))
     }
 
-    /// Destroys an object repository, removing all data associated with it.
+    /// Gets or creates a repository of the entities described by `descriptor`.
+    ///
+    /// The entities are read and written as documents, with the id and indexes of the
+    /// descriptor, so a plugin can persist entities without a Rust type for them. The
+    /// repository shares its collection with a typed repository of an entity with the same
+    /// name. See [`DynamicRepository`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, the entity name is not valid, or the
+    /// repository is already open with a different id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let books = db.dynamic_repository(
+    ///     EntityDescriptor::new("Book").id(EntityId::new("isbn", None, None)),
+    /// )?;
+    /// books.update_one(doc! { isbn: "978-0", title: "Dune" }, true)?;
+    /// ```
+    pub fn dynamic_repository(&self, descriptor: EntityDescriptor) -> NitriteResult<DynamicRepository> {
+        self.inner.dynamic_repository(descriptor, None)
+    }
+
+    /// Gets or creates a keyed repository of the entities described by `descriptor`.
+    ///
+    /// Like `dynamic_repository()`, for the repository of `keyed_repository()` with the
+    /// same entity name and key.
+    pub fn keyed_dynamic_repository(
+        &self,
+        descriptor: EntityDescriptor,
+        key: &str,
+    ) -> NitriteResult<DynamicRepository> {
+        self.inner.dynamic_repository(descriptor, Some(key))
+    }
+
+    /// Gets the documents of an existing repository as a document collection.
+    ///
+    /// This is meant for tools that read a repository without its entity type, such as
+    /// language bindings and exporters. The entities are returned in their stored form;
+    /// documents written through the collection bypass the entity conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_name` - The entity name of the repository
+    /// * `key` - The key of a keyed repository, or `None`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or no such repository exists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let users = db.repository_collection("User", None)?;
+    /// let adults = users.find(field("age").gte(18))?;
+    /// ```
+    pub fn repository_collection(
+        &self,
+        entity_name: &str,
+        key: Option<&str>,
+    ) -> NitriteResult<NitriteCollection> {
+        self.inner.repository_collection(entity_name, key)
+    }
+
+    /// Destroys an object repository, removing all data associated with it,
+    /// including its indexes and the files of module indexes.
     ///
     /// # Type Parameters
     ///
@@ -278,6 +625,10 @@ impl Nitrite {
 
     /// Destroys a collection, removing all documents in it.
     ///
+    /// The indexes, history and options of the collection are removed too, along with
+    /// the files and directories of module indexes such as full-text and spatial ones.
+    /// Use `collection.clear()` to remove the documents but keep the indexes.
+    ///
     /// # Arguments
     ///
     /// * `name` - The collection name
@@ -332,6 +683,78 @@ impl Nitrite {
         self.inner.list_keyed_repositories()
     }
 
+    /// Returns a view of the database scoped to a tenant.
+    ///
+    /// The collections and repositories of the tenant are isolated from the ones of the
+    /// database and of the other tenants. See [`Tenant`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, or the name is empty or contains a
+    /// space or `|`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let orders = db.tenant("acme")?.collection("orders")?;
+    /// ```
+    pub fn tenant(&self, name: &str) -> NitriteResult<Tenant> {
+        self.inner.check_opened()?;
+        Tenant::new(name, self.clone())
+    }
+
+    /// Lists the tenants holding at least one collection or repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed.
+    pub fn list_tenants(&self) -> NitriteResult<HashSet<String>> {
+        self.inner.list_tenants()
+    }
+
+    /// Drops a tenant: destroys all its collections and repositories, with their indexes
+    /// and metadata. Dropping a tenant without data does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or a collection cannot be removed.
+    pub fn drop_tenant(&self, name: &str) -> NitriteResult<()> {
+        self.inner.drop_tenant(name)
+    }
+
+    /// Registers a reference between two collections: the documents of the referencing
+    /// collection must hold the id of an existing document of the target collection in
+    /// the reference field. A reference on the same field replaces the previous one.
+    ///
+    /// References are checked from then on, by the writes and removals of the database;
+    /// the documents already stored are not checked. They are not persisted and must be
+    /// registered again after the database is reopened. See [`Reference`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, or the reference would form a cycle,
+    /// a collection referencing itself included.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// db.add_reference(Reference::new("orders", "customer_id", "customers"))?;
+    /// ```
+    pub fn add_reference(&self, reference: Reference) -> NitriteResult<()> {
+        self.inner.check_opened()?;
+        self.inner.nitrite_config.references().add(reference)
+    }
+
+    /// Removes the reference on a field of a collection, returning whether there was one.
+    pub fn remove_reference(&self, collection: &str, field: &str) -> bool {
+        self.inner.nitrite_config.references().remove(collection, field)
+    }
+
+    /// Lists the references registered with the database.
+    pub fn references(&self) -> Vec<Reference> {
+        self.inner.nitrite_config.references().references()
+    }
+
     /// Checks if the database has unsaved changes.
     ///
     /// # Returns
@@ -359,6 +782,21 @@ impl Nitrite {
         self.inner.config()
     }
 
+    /// Gets the profiler of the collection operations.
+    ///
+    /// The profiler is disabled until [`Profiler::enable()`] is called. See [`Profiler`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// db.profiler().enable();
+    /// collection.find(field("status").eq("open"))?.count();
+    /// let slowest = db.profiler().recent(100).into_iter().max_by_key(|p| p.total());
+    /// ```
+    pub fn profiler(&self) -> Profiler {
+        self.inner.config().profiler()
+    }
+
     /// Gets the underlying storage backend.
     ///
     /// # Returns
@@ -368,6 +806,113 @@ impl Nitrite {
         self.inner.store()
     }
 
+    /// Moves the database to the store of another module without closing it.
+    ///
+    /// Every map of the current store (the collections and repositories, their indexes,
+    /// the catalog and the attributes of the maps) is copied into the store of `module`,
+    /// then the database switches to it and closes the current store. Reads are served by
+    /// the current store while the maps are copied; writes and the opening of maps wait
+    /// for the switch. Collections, repositories and other handles taken before the
+    /// migration keep working and use the new store afterwards, but cursors opened before
+    /// it should be consumed first, as they read the closed store.
+    ///
+    /// Listeners subscribed to the events of the current store are not carried over.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed, if the current store cannot list its
+    /// maps, if the new store cannot be opened or already holds one of the maps, or if the
+    /// copy fails. The database stays on the current store unless the switch is done.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let db = Nitrite::builder().open_or_create(None, None)?;
+    /// // ... serve from memory, then persist without stopping
+    /// db.migrate_store(FjallModule::with_config().db_path("/var/lib/app").build())?;
+    /// ```
+    pub fn migrate_store<M: StoreModule>(&self, module: M) -> NitriteResult<()> {
+        self.inner.migrate_store(module.get_store()?)
+    }
+
+    /// Returns the injector of faults into the maps of the store, to make chosen map
+    /// operations fail or wait in tests. See [`FaultInjector`].
+    ///
+    /// Requires the `fault_injection` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let faults = db.fault_injector()?;
+    /// faults.inject(Fault::fail().on_map("orders").on(MapOperation::Put).nth(2));
+    /// ```
+    #[cfg(feature = "fault_injection")]
+    pub fn fault_injector(&self) -> NitriteResult<FaultInjector> {
+        self.inner.check_opened()?;
+        self.inner.nitrite_config.fault_injector()
+    }
+
+    /// Returns the disk watchdog of the database, or `None` if it was opened without one.
+    /// See [`DiskMonitor`].
+    ///
+    /// Requires the `disk_watchdog` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// if let Some(monitor) = db.disk_monitor()? {
+    ///     let usage = monitor.check()?;
+    ///     println!("{} bytes free, writes suspended: {}", usage.available(), monitor.writes_suspended());
+    /// }
+    /// ```
+    #[cfg(feature = "disk_watchdog")]
+    pub fn disk_monitor(&self) -> NitriteResult<Option<DiskMonitor>> {
+        self.inner.check_opened()?;
+        Ok(self.inner.nitrite_config.disk_monitor())
+    }
+
+    /// Describes the state of the database: its collections and the health of the
+    /// listeners subscribed to its open collections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// for listener in db.describe()?.unhealthy_listeners() {
+    ///     log::warn!("{:?} on {} dropped {} events", listener.name, listener.collection, listener.dropped);
+    /// }
+    /// ```
+    pub fn describe(&self) -> NitriteResult<DatabaseDescription> {
+        let mut collections: Vec<String> = self.list_collection_names()?.into_iter().collect();
+        collections.sort();
+        Ok(DatabaseDescription {
+            collections,
+            listeners: self.config().listeners().health(),
+        })
+    }
+
+    /// Lists the modules loaded into this database.
+    ///
+    /// # Returns
+    ///
+    /// The `ModuleInfo` of each module passed to `load_module()`, in load order, with its
+    /// version and the capabilities it provides. The built-in indexers and the default
+    /// in-memory store are not listed.
+    pub fn plugins(&self) -> Vec<ModuleInfo> {
+        self.inner.config().plugins()
+    }
+
     /// Commits any pending changes to persistent storage.
     ///
     /// # Returns
@@ -409,6 +954,187 @@ impl Nitrite {
         self.inner.database_metadata()
     }
 
+    /// Runs a read-only SQL query and returns the matching documents.
+    ///
+    /// See [`SqlQuery`] for the supported subset. This method requires the `sql` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FilterError` if the query is invalid, and a `CollectionNotFound` error
+    /// if it reads a collection or repository that does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let admins = db.sql("SELECT name, email FROM users WHERE role = 'admin' ORDER BY name")?;
+    /// ```
+    #[cfg(feature = "sql")]
+    pub fn sql(&self, query: &str) -> NitriteResult<Vec<Document>> {
+        SqlQuery::parse(query)?.execute(self)
+    }
+
+    /// Opens a read-only view of the whole database at the current point in time.
+    ///
+    /// The snapshot can be iterated while other threads keep writing to the database;
+    /// those writes are not visible through it. See [`NitriteSnapshot`] for the
+    /// consistency guarantees of each store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the store cannot open a snapshot.
+    pub fn snapshot(&self) -> NitriteResult<NitriteSnapshot> {
+        self.inner.snapshot()
+    }
+
+    /// Starts recording the operations of every collection and repository in the audit
+    /// log, or replaces the options of a running audit.
+    ///
+    /// Each completed write is logged with its time, operation, collection, the session
+    /// and transaction that committed it, a summary of its filter and the ids of the
+    /// documents it wrote; reads are logged too if
+    /// [`include_reads`](AuditOptions::include_reads) is set. Failed operations are not
+    /// logged. The log is a capped collection kept in the store; a failure to write an
+    /// entry is logged as an error and does not fail the operation.
+    ///
+    /// Auditing is not remembered by the database: enable it again after it is reopened.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the log cannot be opened.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// db.enable_audit(AuditOptions::new().include_reads(true).redact("ssn"))?;
+    /// patients.remove(field("ssn").eq("078-05-1120"), false)?;
+    ///
+    /// let removals = db.audit_log()?.entries(&AuditQuery::new().operation("remove"))?;
+    /// assert_eq!(removals[0].filter.as_deref(), Some("(ssn == <redacted>)"));
+    /// ```
+    pub fn enable_audit(&self, options: AuditOptions) -> NitriteResult<()> {
+        let entries = self.inner.audit_collection()?;
+        self.inner.nitrite_config.auditor().enable(options, entries)
+    }
+
+    /// Stops recording operations in the audit log. The entries already recorded are
+    /// kept.
+    pub fn disable_audit(&self) {
+        self.inner.nitrite_config.auditor().disable();
+    }
+
+    /// Returns the options of the audit, or `None` if auditing is disabled.
+    pub fn audit_options(&self) -> Option<AuditOptions> {
+        self.inner.nitrite_config.auditor().options()
+    }
+
+    /// Opens the audit log to query its entries, whether auditing is enabled or not.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the log cannot be opened.
+    pub fn audit_log(&self) -> NitriteResult<AuditLog> {
+        Ok(AuditLog::new(self.inner.audit_collection()?))
+    }
+
+    /// Opens a read-only view of the database for analytics and reporting threads,
+    /// refreshed at most once a second.
+    ///
+    /// Reads through the view are served from a snapshot and never wait on the locks
+    /// taken by writers; in exchange they may lag behind the database. See [`ReadView`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the store cannot open a snapshot.
+    pub fn read_view(&self) -> NitriteResult<ReadView> {
+        self.read_view_with_options(ReadViewOptions::default())
+    }
+
+    /// Opens a read-only view of the database refreshed as set by `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the store cannot open a snapshot.
+    pub fn read_view_with_options(&self, options: ReadViewOptions) -> NitriteResult<ReadView> {
+        ReadView::new(self.clone(), options)
+    }
+
+    /// Starts loading collections and their indexes into memory in the background.
+    ///
+    /// After a large persistent database is opened, the first queries are slow while the
+    /// store caches are cold. A warm-up reads every document of the given collections and
+    /// asks the indexer of each of their indexes (including spatial, full-text and other
+    /// plugin indexes) to load it, so that services can delay readiness until the caches
+    /// are warm. The database stays fully usable while the warm-up runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if one of the collections does not exist. Errors that
+    /// happen during the warm-up itself are returned by [`WarmUpHandle::wait`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let report = db.warm_up(["orders", "customers"])?.wait()?;
+    /// ```
+    pub fn warm_up<I, S>(&self, collections: I) -> NitriteResult<WarmUpHandle>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.inner.check_opened()?;
+        start_warm_up(self, collections.into_iter().map(Into::into).collect())
+    }
+
+    /// Reports which indexes of the database are loaded.
+    ///
+    /// Opening a database or a collection does not load its indexes: their metadata is
+    /// read on the first use of an index of the collection, and each index is opened by the
+    /// first query or write that needs it, or by a [`warm_up`](Nitrite::warm_up). The
+    /// status lists every index of every collection and repository, read from the index
+    /// metadata without opening the collections.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the indexer of an index is not loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let status = db.index_load_status()?;
+    /// for index in status.pending() {
+    ///     log::info!("{} on {} is not loaded yet", index.index_type(), index.collection_name());
+    /// }
+    /// ```
+    pub fn index_load_status(&self) -> NitriteResult<IndexLoadStatus> {
+        self.inner.check_opened()?;
+        index_load_status(self)
+    }
+
+    /// Checks that the unique and non-unique indexes hold an entry for every document
+    /// of their collection and none for other documents, and which indexes were left
+    /// dirty by an interrupted build.
+    ///
+    /// The check only reports: rebuild an inconsistent index with `rebuild_index()`.
+    /// To check the indexes every time the database is opened, set an
+    /// [`IndexRecoveryPolicy`](crate::index_check::IndexRecoveryPolicy) on the builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the index metadata cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let report = db.check_indexes()?;
+    /// for index in &report.inconsistent {
+    ///     log::warn!("{:?}: {:?}", index.index_descriptor, index.issues);
+    /// }
+    /// ```
+    pub fn check_indexes(&self) -> NitriteResult<IndexCheckReport> {
+        self.inner.check_opened()?;
+        check_indexes(self)
+    }
+
     /// Executes a closure within a transactional session context.
     ///
     /// This method creates a new session that provides transactional semantics for
@@ -498,30 +1224,83 @@ impl Nitrite {
         Ok(result)
     }
 
+    /// Runs a closure in a transaction, retrying it on write conflicts.
+    ///
+    /// A new transaction is started for every attempt and passed to `func`. If `func`
+    /// returns `Ok` the transaction is committed (unless `func` already committed it);
+    /// if it returns an error the transaction is rolled back. When the attempt fails with
+    /// a retryable error (see [`NitriteError::is_retryable`]) the closure is run again in a
+    /// fresh transaction after an exponential backoff, up to the attempts allowed by the
+    /// default [`RetryPolicy`]. Because it may run several times, `func` should not have
+    /// side effects outside the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt, or the first non-retryable error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let balance = db.run_in_transaction(|tx| {
+    ///     let accounts = tx.collection("accounts")?;
+    ///     accounts.update(field("id").eq(1), &doc! { balance: 90 })?;
+    ///     accounts.update(field("id").eq(2), &doc! { balance: 110 })?;
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn run_in_transaction<F, R>(&self, func: F) -> NitriteResult<R>
+    where
+        F: FnMut(&NitriteTransaction) -> NitriteResult<R>,
+    {
+        self.run_in_transaction_with(&RetryPolicy::default(), func)
+    }
+
+    /// Runs a closure in a transaction like [`Nitrite::run_in_transaction`], retrying as
+    /// configured by `policy`.
+    pub fn run_in_transaction_with<F, R>(&self, policy: &RetryPolicy, func: F) -> NitriteResult<R>
+    where
+        F: FnMut(&NitriteTransaction) -> NitriteResult<R>,
+    {
+        retry::run_in_transaction(self, policy, func)
+    }
+
     pub(crate) fn initialize(
         &self,
         username: Option<&str>,
         password: Option<&str>,
     ) -> NitriteResult<()> {
         {
-            let result = self.inner.initialize();
-
-            if result.is_err() {
+            if let Err(err) = self.inner.initialize() {
                 self.inner.close()?;
 
-                log::error!("Failed to initialize Nitrite: {:?}", result.clone().err().unwrap());
+                log::error!("Failed to initialize Nitrite: {:?}", err);
                 return Err(NitriteError::new_with_cause(
                     "Failed to initialize Nitrite",
                     ErrorKind::IOError,
-                    result.err().unwrap(),
+                    err,
                 ));
             }
         }
 
+        if let Some(policy) = self.inner.nitrite_config.index_recovery_policy() {
+            if let Err(err) = recover_indexes(self, policy) {
+                self.inner.close()?;
+                return Err(err);
+            }
+        }
+
         self.migrate()?;
 
         self.inner.validate_credentials(username, password)?;
-        self.inner.authenticate(username, password)
+        self.inner.authenticate(username, password)?;
+        self.inner.nitrite_config.open_plugins()?;
+
+        #[cfg(feature = "disk_watchdog")]
+        if let Err(err) = self.inner.nitrite_config.start_disk_watchdog() {
+            self.inner.close()?;
+            return Err(err);
+        }
+        Ok(())
     }
 
     fn migrate(&self) -> NitriteResult<()> {
@@ -552,6 +1331,7 @@ struct NitriteInner {
     store: OnceLock<NitriteStore>,
     metadata: OnceLock<NitriteMetadata>,
     lock_registry: LockRegistry,
+    views: Mutex<HashMap<String, View>>,
 }
 
 impl NitriteInner {
@@ -567,6 +1347,7 @@ impl NitriteInner {
             store: OnceLock::new(),
             metadata: OnceLock::new(),
             lock_registry,
+            views: Mutex::new(HashMap::new()),
         }
     }
 
@@ -586,7 +1367,200 @@ impl NitriteInner {
 
         self.collection_factory.get_collection(name, self.nitrite_config.clone(), true)
     }
+
+    fn topic(&self, name: &str, options: TopicOptions) -> NitriteResult<Topic> {
+        self.validate_collection_name(name)?;
+        if name.contains(INTERNAL_NAME_SEPARATOR) {
+            log::error!("Topic name cannot contain '{}'", INTERNAL_NAME_SEPARATOR);
+            return Err(NitriteError::new(
+                &format!("Topic name cannot contain '{}'", INTERNAL_NAME_SEPARATOR),
+                ErrorKind::ValidationError,
+            ));
+        }
+        self.check_opened()?;
+
+        // topics are kept out of the catalog so they are not listed as collections
+        let collection_name = format!("{}{}{}", TOPIC_PREFIX, INTERNAL_NAME_SEPARATOR, name);
+        let messages =
+            self.collection_factory
+                .get_collection(&collection_name, self.nitrite_config.clone(), false)?;
+        Topic::new(name, messages, self.store(), self.lock_registry.clone(), options)
+    }
+
+    fn time_series(&self, name: &str, options: TimeSeriesOptions) -> NitriteResult<TimeSeries> {
+        self.validate_collection_name(name)?;
+        if name.contains(INTERNAL_NAME_SEPARATOR) {
+            log::error!("Time series name cannot contain '{}'", INTERNAL_NAME_SEPARATOR);
+            return Err(NitriteError::new(
+                &format!("Time series name cannot contain '{}'", INTERNAL_NAME_SEPARATOR),
+                ErrorKind::ValidationError,
+            ));
+        }
+        self.check_opened()?;
+
+        // time series are kept out of the catalog so they are not listed as collections
+        let collection_name = time_series_collection_name(name);
+        let buckets =
+            self.collection_factory
+                .get_collection(&collection_name, self.nitrite_config.clone(), false)?;
+        let writer = self
+            .lock_registry
+            .get_lock(&format!("{}{}writer", collection_name, INTERNAL_NAME_SEPARATOR));
+        TimeSeries::open(name, buckets, writer, options)
+    }
+
+    fn drop_time_series(&self, name: &str) -> NitriteResult<()> {
+        self.check_opened()?;
+        let collection_name = time_series_collection_name(name);
+        if self.opened_store()?.has_map(&collection_name)? {
+            let buckets = self.collection_factory.get_collection(
+                &collection_name,
+                self.nitrite_config.clone(),
+                false,
+            )?;
+            // the attributes of a map outlive it, a new time series must not inherit them
+            forget_time_series_options(&buckets)?;
+            buckets.dispose()?;
+            self.collection_factory.destroy_collection(&collection_name)?;
+        }
+        Ok(())
+    }
+
+    fn graph(&self, name: &str, vertices: &str) -> NitriteResult<Graph> {
+        self.validate_collection_name(name)?;
+        if name.contains(INTERNAL_NAME_SEPARATOR) {
+            log::error!("Graph name cannot contain '{}'", INTERNAL_NAME_SEPARATOR);
+            return Err(NitriteError::new(
+                &format!("Graph name cannot contain '{}'", INTERNAL_NAME_SEPARATOR),
+                ErrorKind::ValidationError,
+            ));
+        }
+        self.check_opened()?;
+
+        let vertices = self.collection(vertices)?;
+        // graphs are kept out of the catalog so they are not listed as collections
+        let edges = self.collection_factory.get_collection(
+            &graph_collection_name(name),
+            self.nitrite_config.clone(),
+            false,
+        )?;
+        Graph::open(name, vertices, edges)
+    }
+
+    fn drop_graph(&self, name: &str) -> NitriteResult<()> {
+        self.check_opened()?;
+        let collection_name = graph_collection_name(name);
+        if self.opened_store()?.has_map(&collection_name)? {
+            let edges = self.collection_factory.get_collection(
+                &collection_name,
+                self.nitrite_config.clone(),
+                false,
+            )?;
+            // the attributes of a map outlive it, a new graph must not inherit them
+            forget_graph_vertices(&edges)?;
+            edges.dispose()?;
+            self.collection_factory.destroy_collection(&collection_name)?;
+        }
+        Ok(())
+    }
+
+    fn audit_collection(&self) -> NitriteResult<NitriteCollection> {
+        self.check_opened()?;
+        // the log is kept out of the catalog so it is not listed as a collection
+        self.collection_factory
+            .get_collection(AUDIT_COLLECTION, self.nitrite_config.clone(), false)
+    }
+
+    fn view(&self, name: &str, options: ViewOptions) -> NitriteResult<View> {
+        self.validate_collection_name(name)?;
+        if name.contains(INTERNAL_NAME_SEPARATOR) {
+            log::error!("View name cannot contain '{}'", INTERNAL_NAME_SEPARATOR);
+            return Err(NitriteError::new(
+                &format!("View name cannot contain '{}'", INTERNAL_NAME_SEPARATOR),
+                ErrorKind::ValidationError,
+            ));
+        }
+        self.check_opened()?;
+
+        let mut views = self.views.lock();
+        if let Some(previous) = views.remove(name) {
+            previous.detach()?;
+        }
+
+        let source = self.collection(options.get_source())?;
+        // views are kept out of the catalog so they are not listed as collections
+        let data = self.collection_factory.get_collection(
+            &view_collection_name(name),
+            self.nitrite_config.clone(),
+            false,
+        )?;
+        let view = View::open(name, source, data, options)?;
+        views.insert(name.to_string(), view.clone());
+        Ok(view)
+    }
+
+    fn drop_view(&self, name: &str) -> NitriteResult<()> {
+        self.check_opened()?;
+        if let Some(view) = self.views.lock().remove(name) {
+            view.detach()?;
+        }
+
+        let collection_name = view_collection_name(name);
+        if self.opened_store()?.has_map(&collection_name)? {
+            self.dispose_collection(&collection_name)?;
+            self.collection_factory.destroy_collection(&collection_name)?;
+        }
+        Ok(())
+    }
     
+    fn sharded_collection(
+        &self,
+        name: &str,
+        options: ShardOptions,
+    ) -> NitriteResult<ShardedCollection> {
+        self.validate_collection_name(name)?;
+        if name.contains(INTERNAL_NAME_SEPARATOR) {
+            log::error!("Sharded collection name cannot contain '{}'", INTERNAL_NAME_SEPARATOR);
+            return Err(NitriteError::new(
+                &format!("Sharded collection name cannot contain '{}'", INTERNAL_NAME_SEPARATOR),
+                ErrorKind::ValidationError,
+            ));
+        }
+        self.check_opened()?;
+
+        // shards are kept out of the catalog so they are not listed as collections
+        let mut shards = Vec::with_capacity(options.shard_count());
+        for index in 0..options.shard_count() {
+            shards.push(self.collection_factory.get_collection(
+                &shard_collection_name(name, index),
+                self.nitrite_config.clone(),
+                false,
+            )?);
+        }
+        ShardedCollection::open(name, options, shards, self.nitrite_config.clone())
+    }
+
+    fn drop_sharded_collection(&self, name: &str) -> NitriteResult<()> {
+        self.check_opened()?;
+        let mut index = 0;
+        loop {
+            let collection_name = shard_collection_name(name, index);
+            if !self.opened_store()?.has_map(&collection_name)? {
+                return Ok(());
+            }
+            let shard = self.collection_factory.get_collection(
+                &collection_name,
+                self.nitrite_config.clone(),
+                false,
+            )?;
+            // the attributes of a map outlive it, a new shard must not inherit the layout
+            forget_layout(&shard)?;
+            shard.dispose()?;
+            self.collection_factory.destroy_collection(&collection_name)?;
+            index += 1;
+        }
+    }
+
     fn repository<T>(&self, key: Option<&str>) -> NitriteResult<ObjectRepository<T>>
     where
         T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
@@ -595,39 +1569,143 @@ impl NitriteInner {
         self.repository_factory.get_repository::<T>(key, self.nitrite_config.clone())
     }
 
+    fn dynamic_repository(
+        &self,
+        descriptor: EntityDescriptor,
+        key: Option<&str>,
+    ) -> NitriteResult<DynamicRepository> {
+        self.check_opened()?;
+        self.repository_factory
+            .get_dynamic_repository(descriptor, key, self.nitrite_config.clone())
+    }
+
+    fn repository_collection(
+        &self,
+        entity_name: &str,
+        key: Option<&str>,
+    ) -> NitriteResult<NitriteCollection> {
+        self.check_opened()?;
+        let exists = match key {
+            Some(key) => self
+                .list_keyed_repositories()?
+                .get(key)
+                .is_some_and(|types| types.contains(entity_name)),
+            None => self.list_repositories()?.contains(entity_name),
+        };
+        if !exists {
+            log::error!("Repository {} does not exist", entity_name);
+            return Err(NitriteError::new(
+                &format!("Repository {} does not exist", entity_name),
+                ErrorKind::RepositoryNotFound,
+            ));
+        }
+
+        let name = repository_name(entity_name, key)?;
+        self.collection_factory
+            .get_collection(&name, self.nitrite_config.clone(), false)
+    }
+
     fn destroy_collection(&self, name: &str) -> NitriteResult<()> {
         self.check_opened()?;
+        if self.opened_store()?.has_map(name)? {
+            self.dispose_collection(name)?;
+        }
         self.collection_factory.destroy_collection(name)?;
-        self.store.get().unwrap().remove_map(name)
+        self.opened_store()?.remove_map(name)
     }
     
     fn destroy_repository<T: NitriteEntity>(&self, key: Option<&str>) -> NitriteResult<()> {
         self.check_opened()?;
+        let name = repository_name_by_type::<T>(key)?;
+        if self.opened_store()?.has_map(&name)? {
+            self.dispose_collection(&name)?;
+        }
         self.repository_factory.destroy_repository::<T>(key)
     }
 
     fn list_collection_names(&self) -> NitriteResult<HashSet<String>> {
         self.check_opened()?;
-        self.store.get().unwrap().get_collection_names()
+        self.opened_store()?.get_collection_names()
     }
     
     fn list_repositories(&self) -> NitriteResult<HashSet<String>> {
         self.check_opened()?;
-        self.store.get().unwrap().get_repository_registry()
+        self.opened_store()?.get_repository_registry()
     }
     
     fn list_keyed_repositories(&self) -> NitriteResult<HashMap<String, HashSet<String>>> {
         self.check_opened()?;
-        self.store.get().unwrap().get_keyed_repository_registry()
+        self.opened_store()?.get_keyed_repository_registry()
+    }
+
+    fn list_tenants(&self) -> NitriteResult<HashSet<String>> {
+        let collections = self.list_collection_names()?;
+        let keyed_repositories = self.list_keyed_repositories()?;
+        Ok(collections
+            .iter()
+            .chain(keyed_repositories.keys())
+            .filter_map(|name| tenant_of(name))
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn drop_tenant(&self, tenant: &str) -> NitriteResult<()> {
+        self.check_opened()?;
+        let prefix = tenant_prefix(tenant);
+        for name in self.list_collection_names()? {
+            if name.starts_with(&prefix) {
+                self.dispose_collection(&name)?;
+                self.collection_factory.destroy_collection(&name)?;
+            }
+        }
+        for (key, entity_names) in self.list_keyed_repositories()? {
+            if tenant_of(&key) == Some(tenant) {
+                for entity_name in entity_names {
+                    let name = repository_name(&entity_name, Some(&key))?;
+                    self.dispose_collection(&name)?;
+                    self.repository_factory.destroy_repository_by_name(&name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a collection with its indexes, history and options from the store.
+    fn dispose_collection(&self, name: &str) -> NitriteResult<()> {
+        self.collection_factory
+            .get_collection(name, self.nitrite_config.clone(), false)?
+            .dispose()
+    }
+
+    fn snapshot(&self) -> NitriteResult<NitriteSnapshot> {
+        let collection_names = self.list_collection_names()?;
+        let repository_names = self.list_repositories()?;
+        let keyed_repository_names = self.list_keyed_repositories()?;
+
+        let mut map_names = collection_names.clone();
+        map_names.extend(repository_names.iter().cloned());
+        for (key, entity_names) in &keyed_repository_names {
+            for entity_name in entity_names {
+                map_names.insert(repository_name(entity_name, Some(key))?);
+            }
+        }
+
+        let store_snapshot = self.opened_store()?.open_snapshot(&map_names)?;
+        Ok(NitriteSnapshot::new(
+            store_snapshot,
+            collection_names,
+            repository_names,
+            keyed_repository_names,
+        ))
     }
 
     fn has_unsaved_changes(&self) -> NitriteResult<bool> {
         self.check_opened()?;
-        self.store.get().unwrap().has_unsaved_changes()
+        self.opened_store()?.has_unsaved_changes()
     }
 
     fn is_closed(&self) -> NitriteResult<bool> {
-        self.store.get().unwrap().is_closed()
+        self.opened_store()?.is_closed()
     }
 
     fn config(&self) -> NitriteConfig {
@@ -635,23 +1713,42 @@ impl NitriteInner {
     }
 
     fn store(&self) -> NitriteStore {
-        self.store.get().unwrap().clone()
+        // a Nitrite handle is only handed out after initialize() has set the store
+        #[allow(clippy::expect_used)]
+        self.store.get().expect("Nitrite store is not initialized").clone()
+    }
+
+    fn opened_store(&self) -> NitriteResult<&NitriteStore> {
+        self.store.get().ok_or_else(|| {
+            log::error!("Nitrite store is not initialized");
+            NitriteError::new("Nitrite store is not initialized", ErrorKind::StoreNotInitialized)
+        })
     }
 
     fn commit(&self) -> NitriteResult<()> {
         self.check_opened()?;
         self.save_metadata()?;
-        self.store.get().unwrap().commit()
+        self.opened_store()?.commit()
     }
     
     fn compact(&self) -> NitriteResult<()> {
         self.check_opened()?;
-        self.store.get().unwrap().compact()
+        self.opened_store()?.compact()
     }
 
     fn close(&self) -> NitriteResult<()> {
-        let store = self.store.get().unwrap();
+        // background tasks may still use the store, let them finish first; a task that
+        // does not finish in time must not keep the store open
+        let shutdown = self.nitrite_config.shutdown_scheduler();
+        if let Err(err) = &shutdown {
+            log::error!("Closing the database while background tasks are still running: {}", err);
+        }
+        // plugins write out their buffers while the store can still take them
+        self.nitrite_config.prepare_close()?;
+        let store = self.opened_store()?;
         store.before_close()?;
+        self.views.lock().clear();
+        self.nitrite_config.auditor().disable();
         if store.has_unsaved_changes()? {
             store.commit()?;
         }
@@ -659,7 +1756,32 @@ impl NitriteInner {
         self.nitrite_config.close()?;
         // Close the store to release all resources including background threads
         store.close()?;
-        Ok(())
+        shutdown
+    }
+
+    fn migrate_store(&self, target: NitriteStore) -> NitriteResult<()> {
+        self.check_opened()?;
+        target.initialize(self.nitrite_config.clone())?;
+        target.open_or_create()?;
+
+        let source = match self.nitrite_config.migrate_store(target.clone()) {
+            Ok(source) => source,
+            Err(err) => {
+                let _ = target.close();
+                return Err(err);
+            }
+        };
+        target.on_open(&self.nitrite_config)?;
+
+        // the copied store info still names the version of the old store
+        let store_info = self.opened_store()?.open_map(STORE_INFO)?;
+        if let Some(Value::Document(mut info)) = store_info.get(&Value::from(STORE_INFO))? {
+            info.put("store_version", Value::from(target.store_version()?))?;
+            store_info.put(Value::from(STORE_INFO), Value::Document(info))?;
+        }
+
+        source.before_close()?;
+        source.close()
     }
 
     fn database_metadata(&self) -> NitriteResult<NitriteMetadata> {
@@ -676,7 +1798,7 @@ impl NitriteInner {
     }
 
     fn create_database_metadata(&self) -> NitriteResult<()> {
-        let meta_map = self.store.get().unwrap().open_map(STORE_INFO)?;
+        let meta_map = self.opened_store()?.open_map(STORE_INFO)?;
         let store_info = meta_map.get(&Value::from(STORE_INFO))?;
 
         if let Some(store_info_value) = store_info {
@@ -697,7 +1819,7 @@ impl NitriteInner {
             meta_doc.put("create_time", Value::from(get_current_time_or_zero()))?;
             meta_doc.put(
                 "store_version",
-                Value::from(self.store.get().unwrap().store_version()?),
+                Value::from(self.opened_store()?.store_version()?),
             )?;
             meta_doc.put("nitrite_version", Value::from(NITRITE_VERSION))?;
             meta_doc.put(
@@ -713,7 +1835,11 @@ impl NitriteInner {
 
     fn save_metadata(&self) -> NitriteResult<()> {
         if let Some(metadata) = self.metadata.get() {
-            let store = self.store.get().unwrap();
+            let store = self.opened_store()?;
+            // a read-only store, or one whose writes are suspended, keeps the metadata it has
+            if store.is_read_only()? {
+                return Ok(());
+            }
             let store_info = store.open_map(STORE_INFO)?;
             store_info.put(
                 Value::from(STORE_INFO),
@@ -767,9 +1893,29 @@ impl NitriteInner {
     fn initialize(&self) -> NitriteResult<()> {
         self.nitrite_config.initialize()?;
         let store = self.nitrite_config.nitrite_store()?;
-        self.store.get_or_init(|| store);
-        self.store.get().unwrap().open_or_create()?;
+        self.store.get_or_init(|| store).open_or_create()?;
         self.create_database_metadata()?;
+        self.remove_sort_runs()?;
+        Ok(())
+    }
+
+    /// Removes the sorted runs left in the store by a crash or a leaked cursor. A run is
+    /// only read by the cursor that wrote it, so none of them is in use after opening.
+    fn remove_sort_runs(&self) -> NitriteResult<()> {
+        let store = self.opened_store()?;
+        if store.is_read_only()? {
+            return Ok(());
+        }
+        // a store that cannot list its maps has no way to find them
+        let Ok(names) = store.map_names() else {
+            return Ok(());
+        };
+
+        let prefix = format!("{}{}", SORT_PREFIX, INTERNAL_NAME_SEPARATOR);
+        for name in names.iter().filter(|name| name.starts_with(&prefix)) {
+            log::warn!("Removing the orphaned sorted run {}", name);
+            store.remove_map(name)?;
+        }
         Ok(())
     }
 
@@ -1,6 +1,21 @@
+use crate::collection::ClockSkewPolicy;
+use crate::common::SchedulerConfig;
+#[cfg(feature = "config")]
+use crate::config_file::{ConfigFile, MEMORY_BACKEND};
+#[cfg(feature = "config")]
+use crate::errors::ErrorKind;
 use crate::errors::NitriteError;
+use crate::index_check::IndexRecoveryPolicy;
 use crate::migration::Migration;
+#[cfg(feature = "disk_watchdog")]
+use crate::store::disk::DiskWatchdog;
 use crate::{errors::NitriteResult, nitrite::Nitrite, nitrite_config::NitriteConfig, NitriteModule};
+#[cfg(feature = "config")]
+use std::collections::HashMap;
+
+/// Creates a module named in a configuration file and loads it into the database.
+#[cfg(feature = "config")]
+type ModuleFactory = Box<dyn Fn(&ConfigFile, &NitriteConfig) -> NitriteResult<()>>;
 
 /// Builder for creating and configuring a Nitrite database instance.
 ///
@@ -30,6 +45,10 @@ use crate::{errors::NitriteResult, nitrite::Nitrite, nitrite_config::NitriteConf
 pub struct NitriteBuilder {
     error: Option<NitriteError>,
     nitrite_config: NitriteConfig,
+    #[cfg(feature = "config")]
+    config_file: Option<ConfigFile>,
+    #[cfg(feature = "config")]
+    module_factories: HashMap<String, ModuleFactory>,
 }
 
 impl NitriteBuilder {
@@ -44,10 +63,140 @@ impl NitriteBuilder {
     pub fn new() -> Self {
         NitriteBuilder {
             error: None,
-            nitrite_config: NitriteConfig::new() 
+            nitrite_config: NitriteConfig::new(),
+            #[cfg(feature = "config")]
+            config_file: None,
+            #[cfg(feature = "config")]
+            module_factories: HashMap::new(),
         }
     }
 
+    /// Creates a `NitriteBuilder` configured from a TOML or YAML file.
+    ///
+    /// The file sets the field separator, schema version and sort memory budget, and
+    /// names the store backend and the enabled modules with their settings. Environment
+    /// variables override the file, see [`config_file`](crate::config_file). The store
+    /// backend and every enabled module must be registered with
+    /// [`module_factory`](NitriteBuilder::module_factory); the `memory` backend is built in.
+    ///
+    /// Requires the `config` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of a `.toml`, `.yaml` or `.yml` file
+    ///
+    /// # Returns
+    ///
+    /// A new `NitriteBuilder`. If the file cannot be loaded, the error is captured and
+    /// will be returned when calling `open_or_create()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use nitrite::nitrite_builder::NitriteBuilder;
+    /// use nitrite_fjall_adapter::FjallModule;
+    /// use nitrite_spatial::SpatialModule;
+    ///
+    /// let db = NitriteBuilder::from_config_file("nitrite.toml")
+    ///     .module_factory("fjall", |config| FjallModule::from_settings(config.store()))
+    ///     .module_factory("spatial", |_| Ok(SpatialModule))
+    ///     .open_or_create(None, None)?;
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_config_file<P: AsRef<std::path::Path>>(path: P) -> Self {
+        match ConfigFile::load(path) {
+            Ok(config_file) => NitriteBuilder::from_config(config_file),
+            Err(e) => NitriteBuilder {
+                error: Some(e),
+                ..NitriteBuilder::new()
+            },
+        }
+    }
+
+    /// Creates a `NitriteBuilder` from an already loaded configuration, e.g.
+    /// [`ConfigFile::from_env`].
+    ///
+    /// Requires the `config` feature.
+    #[cfg(feature = "config")]
+    pub fn from_config(config_file: ConfigFile) -> Self {
+        let mut builder = NitriteBuilder::new();
+        if let Some(field_separator) = config_file.field_separator() {
+            builder = builder.field_separator(field_separator);
+        }
+        if let Some(prefix) = config_file.metadata_prefix() {
+            builder = builder.metadata_prefix(prefix);
+        }
+        if let Some(schema_version) = config_file.schema_version() {
+            builder = builder.schema_version(schema_version);
+        }
+        if let Some(bytes) = config_file.sort_memory_budget() {
+            builder = builder.sort_memory_budget(bytes);
+        }
+        if let Some(node_id) = config_file.node_id() {
+            builder = builder.node_id(node_id);
+        }
+        builder.config_file = Some(config_file);
+        builder
+    }
+
+    /// Registers how to create a store backend or module named in the configuration file.
+    ///
+    /// The factory is only called if the configuration selects the backend or enables the
+    /// module, when the database is opened.
+    ///
+    /// Requires the `config` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The backend or module name used in the configuration file
+    /// * `factory` - Creates the module from the loaded configuration
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    #[cfg(feature = "config")]
+    pub fn module_factory<T, F>(mut self, name: &str, factory: F) -> Self
+    where
+        T: NitriteModule + 'static,
+        F: Fn(&ConfigFile) -> NitriteResult<T> + 'static,
+    {
+        self.module_factories.insert(
+            name.to_string(),
+            Box::new(move |config_file, nitrite_config| {
+                nitrite_config.load_module(factory(config_file)?)
+            }),
+        );
+        self
+    }
+
+    /// Loads the store backend and the modules selected by the configuration file.
+    #[cfg(feature = "config")]
+    fn load_configured_modules(&self) -> NitriteResult<()> {
+        let Some(config_file) = &self.config_file else {
+            return Ok(());
+        };
+
+        let backend = config_file.store().backend();
+        let mut names = config_file.enabled_modules();
+        if backend != MEMORY_BACKEND {
+            names.insert(0, backend);
+        }
+
+        for name in names {
+            match self.module_factories.get(name) {
+                Some(factory) => factory(config_file, &self.nitrite_config)?,
+                None => {
+                    log::error!("No module factory registered for '{}' of the configuration", name);
+                    return Err(NitriteError::new(
+                        &format!("No module factory registered for '{}' of the configuration", name),
+                        ErrorKind::PluginLoadFailed,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Sets the field separator for nested document fields.
     ///
     /// The field separator is used to access nested fields in documents using dot-notation
@@ -75,6 +224,33 @@ impl NitriteBuilder {
         self
     }
 
+    /// Sets the prefix of the document metadata fields.
+    ///
+    /// By default Nitrite stores its per-document metadata as `_revision`, `_modified`
+    /// and `_source`. Use a different prefix if those names collide with application
+    /// fields, e.g. `"$nitrite."` stores them in a nested `$nitrite` document.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - A non-empty prefix for the metadata field names
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    ///
+    /// # Panics
+    ///
+    /// If `prefix` is empty, the error is captured and will be returned
+    /// when calling `open_or_create()`.
+    pub fn metadata_prefix(mut self, prefix: &str) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_metadata_prefix(prefix) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
     /// Loads a plugin module into the database.
     ///
     /// Modules can provide additional functionality such as storage backends, indexing
@@ -108,6 +284,38 @@ impl NitriteBuilder {
         self
     }
 
+    /// Configures the scheduler that runs the background tasks of the database.
+    ///
+    /// The scheduler runs on its own threads unless the configuration supplies an external
+    /// executor. Closing the database waits for its tasks to finish, up to the configured
+    /// shutdown timeout.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The thread count, thread name prefix, shutdown timeout and executor
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use nitrite::common::SchedulerConfig;
+    ///
+    /// let db = Nitrite::builder()
+    ///     .scheduler(SchedulerConfig::new().thread_count(2).thread_name_prefix("orders-db"))
+    ///     .open_or_create(None, None)?;
+    /// ```
+    pub fn scheduler(mut self, config: SchedulerConfig) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_scheduler_config(config) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
     /// Sets the schema version for the database.
     ///
     /// The schema version helps track database structure and enables migrations.
@@ -129,6 +337,153 @@ impl NitriteBuilder {
         self
     }
 
+    /// Sets how much memory a sort that cannot use an index may use, in bytes.
+    ///
+    /// Sorting on a field without a suitable index buffers the matching documents. Once
+    /// their estimated size reaches the budget, the buffered documents are sorted and
+    /// spilled to a temporary map of the store, and the spilled runs are merged as the
+    /// cursor is read. The default budget is 64 MiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The memory budget in bytes (must be > 0)
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    pub fn sort_memory_budget(mut self, bytes: u64) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_sort_memory_budget(bytes) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    /// Sets the node id written in the generated `NitriteId`s.
+    ///
+    /// Ids are made of a timestamp, a node id and a sequence number. Without a configured
+    /// node id each process picks a random one, so application instances writing to the
+    /// same (e.g. replicated) database could pick the same node id and generate colliding
+    /// ids. Give each instance its own node id to rule that out.
+    ///
+    /// The id generator is shared by all databases of the process, so the node id
+    /// identifies the process rather than the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - The node id, at most [`MAX_NODE_ID`](crate::collection::MAX_NODE_ID) (1023)
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let db = Nitrite::builder()
+    ///     .node_id(7)
+    ///     .open_or_create(None, None)?;
+    /// ```
+    pub fn node_id(mut self, node_id: u16) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_node_id(node_id) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    /// Sets what the id generator does when the system clock moves backwards.
+    ///
+    /// By default it waits for the clock to catch up. With
+    /// [`ClockSkewPolicy::Error`] inserting documents fails until then, and with
+    /// [`ClockSkewPolicy::Wait`] it waits only up to the given duration. Like the node id,
+    /// the policy applies to all databases of the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy for backwards jumps of the clock
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    pub fn clock_skew_policy(mut self, policy: ClockSkewPolicy) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_clock_skew_policy(policy) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    /// Checks the indexes when opening the database and handles the inconsistent ones,
+    /// for example those left behind by a crash, as the policy says.
+    ///
+    /// The check compares the entries of every unique and non-unique index with the
+    /// documents of its collection, so opening a large database takes longer. Without a
+    /// policy no check runs; [`Nitrite::check_indexes`] runs one on demand.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Fail to open, rebuild the inconsistent indexes, or disable them
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let db = Nitrite::builder()
+    ///     .load_module(storage_module)
+    ///     .index_recovery_policy(IndexRecoveryPolicy::Rebuild)
+    ///     .open_or_create(None, None)?;
+    /// ```
+    pub fn index_recovery_policy(mut self, policy: IndexRecoveryPolicy) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_index_recovery_policy(policy) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
+    /// Checks the free disk space under the store path while the database is open.
+    ///
+    /// The watchdog runs on the scheduler of the database. It publishes store events
+    /// when the free space crosses its thresholds and suspends the writes, which then
+    /// fail with `DiskFull`, before the volume fills up. Opening fails if the store keeps
+    /// no files and the watchdog has no path of its own.
+    ///
+    /// Requires the `disk_watchdog` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `watchdog` - The check interval, the thresholds and the path to check
+    ///
+    /// # Returns
+    ///
+    /// This `NitriteBuilder` for method chaining.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let db = Nitrite::builder()
+    ///     .load_module(storage_module)
+    ///     .disk_watchdog(DiskWatchdog::new().warn_below(1 << 30).read_only_below(256 << 20))
+    ///     .open_or_create(None, None)?;
+    /// ```
+    #[cfg(feature = "disk_watchdog")]
+    pub fn disk_watchdog(mut self, watchdog: DiskWatchdog) -> Self {
+        if self.error.is_none() {
+            if let Err(e) = self.nitrite_config.set_disk_watchdog(watchdog) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+
     /// Adds a migration to be executed when opening the database.
     ///
     /// Migrations are executed in order when the database schema version changes.
@@ -176,6 +531,8 @@ impl NitriteBuilder {
         if let Some(error) = self.error {
             return Err(error);
         }
+        #[cfg(feature = "config")]
+        self.load_configured_modules()?;
         self.nitrite_config.auto_configure()?;
         let nitrite = Nitrite::new(self.nitrite_config);
         nitrite.initialize(username, password)?;
@@ -241,6 +598,16 @@ mod tests {
         assert!(result.is_err(), "Should capture the error from second field_separator call");
     }
 
+    #[test]
+    fn test_metadata_prefix_error_propagation() {
+        let builder = NitriteBuilder::new().metadata_prefix("");
+        let result = builder.open_or_create(None, None);
+        assert!(result.is_err(), "Should propagate metadata prefix error");
+        if let Err(e) = result {
+            assert!(e.to_string().to_lowercase().contains("metadata prefix"));
+        }
+    }
+
     #[test]
     fn test_load_module_error_propagation() {
         let builder = NitriteBuilder::new();
@@ -327,6 +694,27 @@ mod tests {
         NitriteConfig::default().set_field_separator(".").unwrap();
     }
 
+    #[test]
+    fn test_node_id() {
+        let err = NitriteBuilder::new()
+            .node_id(1024)
+            .open_or_create(None, None)
+            .err()
+            .unwrap();
+        assert!(err.message().contains("Node id 1024 is greater than 1023"));
+
+        let db = NitriteBuilder::new()
+            .node_id(1023)
+            .clock_skew_policy(ClockSkewPolicy::default())
+            .open_or_create(None, None)
+            .unwrap();
+        assert_eq!(db.config().node_id(), 1023);
+        assert_eq!(crate::collection::NitriteId::generator_metrics().node_id(), 1023);
+        assert!(db.config().set_node_id(1).is_err());
+        assert!(db.config().set_clock_skew_policy(ClockSkewPolicy::Error).is_err());
+        db.close().unwrap();
+    }
+
     #[test]
     fn test_open_or_create() {
         let builder = NitriteBuilder::new();
@@ -420,4 +808,53 @@ mod tests {
         
         assert!(builder.error.is_some(), "Error should remain set");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_scheduler_config_and_close_drains_tasks() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let db = NitriteBuilder::new()
+            .scheduler(SchedulerConfig::new().thread_count(2).thread_name_prefix("test-db"))
+            .open_or_create(None, None)
+            .unwrap();
+        let scheduler = db.config().scheduler();
+        assert_eq!(scheduler.config().get_thread_count(), 2);
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let flag_clone = Arc::clone(&flag);
+        scheduler.execute(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            flag_clone.store(true, Ordering::SeqCst);
+        });
+
+        // close waits for the task instead of letting it outlive the store
+        db.close().unwrap();
+        assert!(flag.load(Ordering::SeqCst));
+        assert!(scheduler.is_shutdown());
+
+        // the configuration cannot change once the database is open
+        let config = NitriteConfig::new();
+        config.initialize().unwrap();
+        assert!(config.set_scheduler_config(SchedulerConfig::new()).is_err());
+    }
+
+    #[test]
+    fn test_close_finishes_when_scheduler_times_out() {
+        use crate::errors::ErrorKind;
+        use std::time::Duration;
+
+        let db = NitriteBuilder::new()
+            .scheduler(SchedulerConfig::new().shutdown_timeout(Duration::from_millis(20)))
+            .open_or_create(None, None)
+            .unwrap();
+        db.config().scheduler().execute(|| std::thread::sleep(Duration::from_millis(500)));
+        std::thread::sleep(Duration::from_millis(20));
+
+        // the store is closed even though the task is still running
+        let error = db.close().err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::Timeout);
+        assert!(db.is_closed().unwrap());
+    }
+}
@@ -4,15 +4,25 @@ use dashmap::DashMap;
 use std::collections::BTreeMap;
 use std::ops::Deref;
 
-use crate::common::{ReadExecutor, WriteExecutor, PluginManager};
+use crate::common::{ModuleInfo, ReadExecutor, WriteExecutor, PluginManager, Scheduler, SchedulerConfig};
+use crate::collection::{ClockSkewPolicy, ListenerRegistry, ReferenceRegistry, WriteTracker};
+use crate::index_check::IndexRecoveryPolicy;
 use crate::migration::Migration;
+use crate::profiler::Profiler;
+use crate::audit::Auditor;
+#[cfg(feature = "disk_watchdog")]
+use crate::store::disk::{DiskMonitor, DiskWatchdog};
+#[cfg(feature = "fault_injection")]
+use crate::store::fault::FaultInjector;
 use crate::{
     errors::{ErrorKind, NitriteError, NitriteResult},
     index::NitriteIndexer,
     store::NitriteStore,
-    NitriteModule, FIELD_SEPARATOR, INITIAL_SCHEMA_VERSION,
+    NitriteModule, DEFAULT_SORT_MEMORY_BUDGET, FIELD_SEPARATOR, ID_GENERATOR,
+    INITIAL_SCHEMA_VERSION, METADATA_PREFIX,
 };
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use parking_lot::Mutex;
 use std::sync::{Arc, OnceLock};
 
 /// Public interface for Nitrite database configuration.
@@ -60,6 +70,24 @@ impl NitriteConfig {
         self.inner.set_field_separator(separator)
     }
 
+    /// Returns the prefix of the document metadata fields (`_` by default).
+    pub fn metadata_prefix(&self) -> String {
+        self.inner.metadata_prefix()
+    }
+
+    /// Sets the prefix of the document metadata fields (`revision`, `modified`, `source`).
+    ///
+    /// A prefix containing the field separator, like `$nitrite.`, stores the metadata
+    /// in a nested document. Existing databases can be converted with
+    /// [`crate::migration::migrate_metadata_prefix`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already configured or if prefix is empty.
+    pub fn set_metadata_prefix(&self, prefix: &str) -> NitriteResult<()> {
+        self.inner.set_metadata_prefix(prefix)
+    }
+
     /// Gets the configured store plugin.
     ///
     /// # Errors
@@ -69,6 +97,46 @@ impl NitriteConfig {
         self.inner.nitrite_store()
     }
 
+    /// Switches the configured store to `target` once its maps are copied into it, and
+    /// returns the store used until then.
+    pub(crate) fn migrate_store(&self, target: NitriteStore) -> NitriteResult<NitriteStore> {
+        self.inner.plugin_manager.migrate_store(target)
+    }
+
+    /// Returns the faults injected into the maps of the store.
+    #[cfg(feature = "fault_injection")]
+    pub(crate) fn fault_injector(&self) -> NitriteResult<FaultInjector> {
+        self.inner.plugin_manager.fault_injector()
+    }
+
+    /// Returns the disk watchdog started when the database opens, if one is set.
+    #[cfg(feature = "disk_watchdog")]
+    pub fn disk_watchdog(&self) -> Option<DiskWatchdog> {
+        self.inner.disk_watchdog.lock().clone()
+    }
+
+    /// Sets the disk watchdog started when the database opens.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already initialized or if the watchdog configuration is invalid.
+    #[cfg(feature = "disk_watchdog")]
+    pub fn set_disk_watchdog(&self, watchdog: DiskWatchdog) -> NitriteResult<()> {
+        self.inner.set_disk_watchdog(watchdog)
+    }
+
+    /// Returns the running disk watchdog.
+    #[cfg(feature = "disk_watchdog")]
+    pub(crate) fn disk_monitor(&self) -> Option<DiskMonitor> {
+        self.inner.disk_monitor.get().cloned()
+    }
+
+    /// Starts the disk watchdog, if one is set, on the store and the scheduler.
+    #[cfg(feature = "disk_watchdog")]
+    pub(crate) fn start_disk_watchdog(&self) -> NitriteResult<()> {
+        self.inner.start_disk_watchdog()
+    }
+
     /// Finds an indexer plugin by type.
     ///
     /// # Errors
@@ -96,6 +164,65 @@ impl NitriteConfig {
         self.inner.auto_configure()
     }
 
+    /// Returns the descriptions of the loaded modules, in load order.
+    pub fn plugins(&self) -> Vec<ModuleInfo> {
+        self.inner.plugins()
+    }
+
+    /// Shuts down the scheduler and waits for its tasks to drain.
+    pub(crate) fn shutdown_scheduler(&self) -> NitriteResult<()> {
+        self.inner.shutdown_scheduler()
+    }
+
+    /// Notifies all plugins that the database is open.
+    pub(crate) fn open_plugins(&self) -> NitriteResult<()> {
+        self.inner.open_plugins()
+    }
+
+    /// Flushes all plugins and notifies them that the database is closing.
+    pub(crate) fn prepare_close(&self) -> NitriteResult<()> {
+        self.inner.prepare_close()
+    }
+
+    /// Returns the scheduler running the background tasks of the database.
+    pub fn scheduler(&self) -> Scheduler {
+        self.inner.scheduler()
+    }
+
+    /// Returns the tracker of the write tokens of the database.
+    pub(crate) fn write_tracker(&self) -> WriteTracker {
+        self.inner.write_tracker.clone()
+    }
+
+    /// Returns the registry of the references between the collections of the database.
+    pub(crate) fn references(&self) -> ReferenceRegistry {
+        self.inner.references.clone()
+    }
+
+    /// Returns the registry of the listeners subscribed to the collections of the database.
+    pub(crate) fn listeners(&self) -> ListenerRegistry {
+        self.inner.listeners.clone()
+    }
+
+    /// Returns the profiler of the operations of the database.
+    pub fn profiler(&self) -> Profiler {
+        self.inner.profiler.clone()
+    }
+
+    /// Returns the recorder of the audit log of the database.
+    pub(crate) fn auditor(&self) -> Auditor {
+        self.inner.auditor.clone()
+    }
+
+    /// Sets the configuration of the background task scheduler.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already initialized or if the scheduler is already in use.
+    pub fn set_scheduler_config(&self, config: SchedulerConfig) -> NitriteResult<()> {
+        self.inner.set_scheduler_config(config)
+    }
+
     /// Closes the configuration and all plugins.
     ///
     /// # Errors
@@ -119,6 +246,69 @@ impl NitriteConfig {
         self.inner.set_schema_version(version)
     }
 
+    /// Returns the memory budget of a blocking sort, in bytes.
+    pub fn sort_memory_budget(&self) -> u64 {
+        self.inner.sort_memory_budget()
+    }
+
+    /// Sets the memory budget of a blocking sort, in bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already initialized or if the budget is zero.
+    pub fn set_sort_memory_budget(&self, bytes: u64) -> NitriteResult<()> {
+        self.inner.set_sort_memory_budget(bytes)
+    }
+
+    /// Returns the node id written in the generated `NitriteId`s.
+    pub fn node_id(&self) -> u16 {
+        ID_GENERATOR.node_id()
+    }
+
+    /// Sets the node id written in the generated `NitriteId`s.
+    ///
+    /// The id generator is shared by all databases of the process, so the node id
+    /// identifies the process rather than this database.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already initialized or if the node id is greater than 1023.
+    pub fn set_node_id(&self, node_id: u16) -> NitriteResult<()> {
+        self.inner.set_node_id(node_id)
+    }
+
+    /// Returns what the id generator does when the clock moves backwards.
+    pub fn clock_skew_policy(&self) -> ClockSkewPolicy {
+        ID_GENERATOR.clock_skew_policy()
+    }
+
+    /// Sets what the id generator does when the clock moves backwards.
+    ///
+    /// Like the node id, the policy applies to all databases of the process.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already initialized.
+    pub fn set_clock_skew_policy(&self, policy: ClockSkewPolicy) -> NitriteResult<()> {
+        self.inner.set_clock_skew_policy(policy)
+    }
+
+    /// Returns what opening the database does with inconsistent indexes, if an index
+    /// check runs at open.
+    pub fn index_recovery_policy(&self) -> Option<IndexRecoveryPolicy> {
+        self.inner.index_recovery_policy()
+    }
+
+    /// Sets what opening the database does with inconsistent indexes. An index check
+    /// runs at open only when a policy is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if already initialized.
+    pub fn set_index_recovery_policy(&self, policy: IndexRecoveryPolicy) -> NitriteResult<()> {
+        self.inner.set_index_recovery_policy(policy)
+    }
+
     /// Adds a migration to the configuration.
     ///
     /// # Errors
@@ -161,10 +351,34 @@ struct NitriteConfigInner {
     plugin_manager: PluginManager,
     /// Current database schema version
     schema_version: AtomicU32,
+    /// Estimated size of the documents a blocking sort keeps in memory before it spills
+    sort_memory_budget: AtomicU64,
     /// Path to the database file (set only once)
     db_path: OnceLock<String>,
     /// Map of migrations indexed by from_version -> to_version -> Migration
     migrations: DashMap<u32, BTreeMap<u32, Migration>>,
+    /// Configuration of the scheduler, used when it is first needed
+    scheduler_config: Mutex<SchedulerConfig>,
+    /// Scheduler of the background tasks (created on first use)
+    scheduler: OnceLock<Scheduler>,
+    /// Issues the write tokens and tracks the writes that are not visible yet
+    write_tracker: WriteTracker,
+    /// References between the collections, checked by their writes
+    references: ReferenceRegistry,
+    /// Listeners subscribed to the collections, reported by `Nitrite::describe`
+    listeners: ListenerRegistry,
+    /// Records the phase timings of the operations when enabled
+    profiler: Profiler,
+    /// Writes the audit log when auditing is enabled
+    auditor: Auditor,
+    /// Handling of the inconsistent indexes found at open, no check runs without one
+    index_recovery_policy: Mutex<Option<IndexRecoveryPolicy>>,
+    /// Disk space checks started when the database opens
+    #[cfg(feature = "disk_watchdog")]
+    disk_watchdog: Mutex<Option<DiskWatchdog>>,
+    /// The disk watchdog once started
+    #[cfg(feature = "disk_watchdog")]
+    disk_monitor: OnceLock<DiskMonitor>,
 }
 
 impl NitriteConfigInner {
@@ -174,8 +388,21 @@ impl NitriteConfigInner {
             configured: AtomicBool::from(false),
             plugin_manager: PluginManager::new(),
             schema_version: AtomicU32::from(INITIAL_SCHEMA_VERSION),
+            sort_memory_budget: AtomicU64::from(DEFAULT_SORT_MEMORY_BUDGET),
             db_path: OnceLock::new(),
             migrations: DashMap::new(),
+            scheduler_config: Mutex::new(SchedulerConfig::default()),
+            scheduler: OnceLock::new(),
+            write_tracker: WriteTracker::new(),
+            references: ReferenceRegistry::new(),
+            listeners: ListenerRegistry::new(),
+            profiler: Profiler::new(),
+            auditor: Auditor::new(),
+            index_recovery_policy: Mutex::new(None),
+            #[cfg(feature = "disk_watchdog")]
+            disk_watchdog: Mutex::new(None),
+            #[cfg(feature = "disk_watchdog")]
+            disk_monitor: OnceLock::new(),
         }
     }
 
@@ -207,6 +434,33 @@ impl NitriteConfigInner {
         Ok(())
     }
 
+    /// Returns the prefix of the document metadata fields.
+    pub(crate) fn metadata_prefix(&self) -> String {
+        METADATA_PREFIX.read_with(|it| it.clone())
+    }
+
+    /// Sets the prefix of the document metadata fields.
+    pub(crate) fn set_metadata_prefix(&self, prefix: &str) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) {
+            log::error!("Metadata prefix cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Metadata prefix cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        if prefix.is_empty() {
+            log::error!("Metadata prefix cannot be empty");
+            return Err(NitriteError::new(
+                "Metadata prefix cannot be empty",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        METADATA_PREFIX.write_with(|it| *it = prefix.to_string());
+        Ok(())
+    }
+
     /// Gets the configured store plugin.
     pub(crate) fn nitrite_store(&self) -> NitriteResult<NitriteStore> {
         match self.plugin_manager.get_store() {
@@ -257,11 +511,18 @@ impl NitriteConfigInner {
             ));
         }
 
+        // an incompatible module fails here rather than inside the first operation using it
+        self.plugin_manager.validate_modules()?;
         self.plugin_manager.load_plugins()
             .map_err(|e| NitriteError::new(&format!("Failed to auto-configure plugins: {}", e), e.kind().clone()))?;
         Ok(())
     }
 
+    /// Returns the descriptions of the loaded modules.
+    pub(crate) fn plugins(&self) -> Vec<ModuleInfo> {
+        self.plugin_manager.modules()
+    }
+
     /// Closes the configuration and all plugins.
     pub(crate) fn close(&self) -> NitriteResult<()> {
         self.plugin_manager.close()
@@ -286,6 +547,110 @@ impl NitriteConfigInner {
         Ok(())
     }
 
+    /// Returns the memory budget of a blocking sort, in bytes.
+    pub(crate) fn sort_memory_budget(&self) -> u64 {
+        self.sort_memory_budget.load(Ordering::Relaxed)
+    }
+
+    /// Sets the memory budget of a blocking sort, in bytes.
+    pub(crate) fn set_sort_memory_budget(&self, bytes: u64) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) {
+            log::error!("Sort memory budget cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Sort memory budget cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        if bytes == 0 {
+            log::error!("Sort memory budget must be greater than zero");
+            return Err(NitriteError::new(
+                "Sort memory budget must be greater than zero",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        self.sort_memory_budget.store(bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Sets the node id of the id generator.
+    pub(crate) fn set_node_id(&self, node_id: u16) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) {
+            log::error!("Node id cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Node id cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        ID_GENERATOR.set_node_id(node_id)
+    }
+
+    /// Sets the clock skew policy of the id generator.
+    pub(crate) fn set_clock_skew_policy(&self, policy: ClockSkewPolicy) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) {
+            log::error!("Clock skew policy cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Clock skew policy cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        ID_GENERATOR.set_clock_skew_policy(policy);
+        Ok(())
+    }
+
+    /// Returns the index recovery policy.
+    pub(crate) fn index_recovery_policy(&self) -> Option<IndexRecoveryPolicy> {
+        *self.index_recovery_policy.lock()
+    }
+
+    /// Sets the index recovery policy.
+    pub(crate) fn set_index_recovery_policy(&self, policy: IndexRecoveryPolicy) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) {
+            log::error!("Index recovery policy cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Index recovery policy cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        *self.index_recovery_policy.lock() = Some(policy);
+        Ok(())
+    }
+
+    /// Sets the disk watchdog.
+    #[cfg(feature = "disk_watchdog")]
+    pub(crate) fn set_disk_watchdog(&self, watchdog: DiskWatchdog) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) {
+            log::error!("Disk watchdog cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Disk watchdog cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        watchdog.validate()?;
+        *self.disk_watchdog.lock() = Some(watchdog);
+        Ok(())
+    }
+
+    /// Starts the disk watchdog, if one is set and it is not running yet.
+    #[cfg(feature = "disk_watchdog")]
+    pub(crate) fn start_disk_watchdog(&self) -> NitriteResult<()> {
+        let watchdog = match self.disk_watchdog.lock().clone() {
+            Some(watchdog) => watchdog,
+            None => return Ok(()),
+        };
+        if self.disk_monitor.get().is_some() {
+            return Ok(());
+        }
+        let monitor = watchdog.start(
+            self.nitrite_store()?,
+            self.plugin_manager.write_suspension()?,
+            &self.scheduler(),
+        )?;
+        let _ = self.disk_monitor.set(monitor);
+        Ok(())
+    }
+
     /// Adds a migration to be executed during initialization.
     pub(crate) fn add_migration(&self, migration: Migration) -> NitriteResult<()> {
         if self.configured.load(Ordering::Relaxed) {
@@ -334,6 +699,44 @@ impl NitriteConfigInner {
             .map_err(|e| NitriteError::new(&format!("Failed to initialize nitrite configuration plugins: {}", e), e.kind().clone()))
     }
 
+    /// Returns the scheduler, creating it from its configuration on first use.
+    pub(crate) fn scheduler(&self) -> Scheduler {
+        self.scheduler
+            .get_or_init(|| Scheduler::with_config(self.scheduler_config.lock().clone()))
+            .clone()
+    }
+
+    /// Sets the scheduler configuration.
+    pub(crate) fn set_scheduler_config(&self, config: SchedulerConfig) -> NitriteResult<()> {
+        if self.configured.load(Ordering::Relaxed) || self.scheduler.get().is_some() {
+            log::error!("Scheduler configuration cannot be changed after initialization");
+            return Err(NitriteError::new(
+                "Scheduler configuration cannot be changed after initialization",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        *self.scheduler_config.lock() = config;
+        Ok(())
+    }
+
+    /// Shuts down the scheduler, if it was ever used, and waits for its tasks to drain.
+    pub(crate) fn shutdown_scheduler(&self) -> NitriteResult<()> {
+        match self.scheduler.get() {
+            Some(scheduler) => scheduler.shutdown(),
+            None => Ok(()),
+        }
+    }
+
+    /// Notifies all plugins that the database is open.
+    pub(crate) fn open_plugins(&self) -> NitriteResult<()> {
+        self.plugin_manager.open_plugins()
+    }
+
+    /// Flushes all plugins and notifies them that the database is closing.
+    pub(crate) fn prepare_close(&self) -> NitriteResult<()> {
+        self.plugin_manager.prepare_close()
+    }
+
     /// Sets the parent Nitrite instance reference in the plugin manager.
     pub(crate) fn set_nitrite_config(&self, nitrite_config: NitriteConfig) {
         self.plugin_manager.set_nitrite_config(nitrite_config);
@@ -411,6 +814,29 @@ mod tests {
         NitriteConfig::default().set_field_separator(".").unwrap();
     }
 
+    #[test]
+    fn test_default_metadata_prefix() {
+        let config = NitriteConfig::new();
+        assert_eq!(config.metadata_prefix(), "_");
+    }
+
+    #[test]
+    fn test_set_metadata_prefix_after_initialization() {
+        let config = NitriteConfig::new();
+        config.inner.configured.store(true, Ordering::Relaxed);
+        let result = config.set_metadata_prefix("$nitrite.");
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::InvalidOperation);
+    }
+
+    #[test]
+    fn test_set_metadata_prefix_empty() {
+        let config = NitriteConfig::new();
+        let result = config.set_metadata_prefix("");
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::InvalidOperation);
+    }
+
     #[test]
     fn test_load_module() {
         let config = NitriteConfig::new();
@@ -465,6 +891,19 @@ mod tests {
         assert_eq!(result.unwrap_err().kind(), &ErrorKind::InvalidOperation);
     }
 
+    #[test]
+    fn test_set_sort_memory_budget() {
+        let config = NitriteConfig::new();
+        assert_eq!(config.sort_memory_budget(), DEFAULT_SORT_MEMORY_BUDGET);
+        assert!(config.set_sort_memory_budget(1024).is_ok());
+        assert_eq!(config.sort_memory_budget(), 1024);
+        assert!(config.set_sort_memory_budget(0).is_err());
+
+        config.inner.configured.store(true, Ordering::Relaxed);
+        let result = config.set_sort_memory_budget(2048);
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::InvalidOperation);
+    }
+
     #[test]
     fn test_set_db_path() {
         let config = NitriteConfig::new();
@@ -0,0 +1,505 @@
+//! Opt-in profiling of the collection operations.
+//!
+//! When the [`Profiler`] of a database is enabled, every `find`, `count`, `get_by_id` and
+//! write of a collection records how long it spent in each [`Phase`]. The last profiles are
+//! kept in a ring buffer and can be read back with [`Profiler::recent`], or rendered in the
+//! folded stack format understood by flamegraph tools with
+//! [`OperationProfile::to_folded`].
+//!
+//! The time of a `find` includes the iteration of its cursor, and the profile is recorded
+//! when the cursor is dropped. Phases are timed exclusively: the deserialization done
+//! during an index scan counts as deserialization only.
+
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::common::get_current_time_or_zero;
+use parking_lot::Mutex;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The number of profiles kept by default.
+pub const DEFAULT_PROFILER_CAPACITY: usize = 1000;
+
+thread_local! {
+    /// The profile of the operation the current thread is running, if any.
+    static CURRENT_PROFILE: RefCell<Option<Arc<ActiveProfile>>> = const { RefCell::new(None) };
+}
+
+/// A phase of an operation timed by the profiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Preparing the filter and choosing the find plan.
+    Planning,
+    /// Looking up the matching ids in an index.
+    IndexScan,
+    /// Removing the documents found more than once.
+    Dedup,
+    /// Sorting the documents, including the merge of spilled runs.
+    Sort,
+    /// Decoding the values read from the store.
+    Deserialization,
+    /// Writing, updating and removing index entries.
+    IndexMaintenance,
+}
+
+impl Phase {
+    /// All the phases, in the order they are reported.
+    pub const ALL: [Phase; 6] = [
+        Phase::Planning,
+        Phase::IndexScan,
+        Phase::Dedup,
+        Phase::Sort,
+        Phase::Deserialization,
+        Phase::IndexMaintenance,
+    ];
+
+    /// Returns the name of the phase used in the folded stacks.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Planning => "planning",
+            Phase::IndexScan => "index_scan",
+            Phase::Dedup => "dedup",
+            Phase::Sort => "sort",
+            Phase::Deserialization => "deserialization",
+            Phase::IndexMaintenance => "index_maintenance",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// The phase timings of one operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationProfile {
+    operation: String,
+    collection: String,
+    started_at: u128,
+    total: Duration,
+    phases: [Duration; Phase::ALL.len()],
+}
+
+impl OperationProfile {
+    /// Returns the name of the operation, like `find` or `insert`.
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+
+    /// Returns the name of the collection the operation ran on.
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    /// Returns when the operation started, in milliseconds since the epoch.
+    pub fn started_at(&self) -> u128 {
+        self.started_at
+    }
+
+    /// Returns the time spent in the operation, including the iteration of its cursor.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the time spent in `phase`.
+    pub fn phase(&self, phase: Phase) -> Duration {
+        self.phases[phase.index()]
+    }
+
+    /// Returns the time not spent in any phase, like filtering or reading the store.
+    pub fn unattributed(&self) -> Duration {
+        self.total.saturating_sub(self.phases.iter().sum())
+    }
+
+    /// Renders the profile as folded stacks, one `collection;operation;phase micros` line
+    /// per phase that took any time, and a `collection;operation micros` line for the
+    /// unattributed time.
+    pub fn to_folded(&self) -> String {
+        let mut folded = String::new();
+        for phase in Phase::ALL {
+            let micros = self.phase(phase).as_micros();
+            if micros > 0 {
+                folded.push_str(&format!(
+                    "{};{};{} {}\n",
+                    self.collection, self.operation, phase.name(), micros
+                ));
+            }
+        }
+        let micros = self.unattributed().as_micros();
+        if micros > 0 {
+            folded.push_str(&format!("{};{} {}\n", self.collection, self.operation, micros));
+        }
+        folded
+    }
+}
+
+/// Records the phase timings of the operations of a database.
+///
+/// The profiler is disabled by default. It is shared by all the collections of the
+/// database and obtained with [`Nitrite::profiler()`](crate::nitrite::Nitrite::profiler).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// db.profiler().enable();
+/// let orders: Vec<_> = collection.find(field("status").eq("open"))?.collect();
+/// for profile in db.profiler().recent(10) {
+///     print!("{}", profile.to_folded());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Profiler {
+    inner: Arc<ProfilerInner>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub(crate) fn new() -> Self {
+        Profiler {
+            inner: Arc::new(ProfilerInner {
+                enabled: AtomicBool::new(false),
+                capacity: AtomicUsize::new(DEFAULT_PROFILER_CAPACITY),
+                profiles: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// Starts profiling the operations.
+    pub fn enable(&self) {
+        self.inner.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Stops profiling the operations. The profiles already recorded are kept.
+    pub fn disable(&self) {
+        self.inner.enabled.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns whether the operations are profiled.
+    pub fn is_enabled(&self) -> bool {
+        self.inner.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of profiles kept.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity.load(Ordering::Relaxed)
+    }
+
+    /// Sets the number of profiles kept, dropping the oldest ones beyond it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capacity is zero.
+    pub fn set_capacity(&self, capacity: usize) -> NitriteResult<()> {
+        if capacity == 0 {
+            log::error!("Profiler capacity must be greater than zero");
+            return Err(NitriteError::new(
+                "Profiler capacity must be greater than zero",
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        self.inner.capacity.store(capacity, Ordering::Relaxed);
+        let mut profiles = self.inner.profiles.lock();
+        while profiles.len() > capacity {
+            profiles.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Returns the last `n` profiles, newest first.
+    pub fn recent(&self, n: usize) -> Vec<OperationProfile> {
+        self.inner.profiles.lock().iter().rev().take(n).cloned().collect()
+    }
+
+    /// Removes the recorded profiles.
+    pub fn clear(&self) {
+        self.inner.profiles.lock().clear();
+    }
+
+    /// Starts profiling an operation on the current thread until the returned scope is
+    /// dropped. Returns `None` when the profiler is disabled or the thread is already
+    /// running a profiled operation, whose profile then includes this one.
+    pub(crate) fn start(&self, operation: &str, collection: &str) -> Option<ProfileScope> {
+        if !self.is_enabled() || CURRENT_PROFILE.with(|current| current.borrow().is_some()) {
+            return None;
+        }
+
+        let profile = Arc::new(ActiveProfile {
+            profiler: self.inner.clone(),
+            operation: operation.to_string(),
+            collection: collection.to_string(),
+            started_at: get_current_time_or_zero(),
+            timings: Mutex::new(Timings::default()),
+        });
+        Some(ActiveProfile::enter(&profile))
+    }
+}
+
+struct ProfilerInner {
+    enabled: AtomicBool,
+    capacity: AtomicUsize,
+    profiles: Mutex<VecDeque<OperationProfile>>,
+}
+
+impl ProfilerInner {
+    fn record(&self, profile: OperationProfile) {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let mut profiles = self.profiles.lock();
+        while profiles.len() >= capacity {
+            profiles.pop_front();
+        }
+        profiles.push_back(profile);
+    }
+}
+
+/// Times `f` as `phase` of the operation the current thread is profiling, if any.
+///
+/// The time spent in the phases nested in `f` is not counted as `phase`.
+pub fn timed<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    match CURRENT_PROFILE.with(|current| current.borrow().clone()) {
+        Some(profile) => profile.time(phase, f),
+        None => f(),
+    }
+}
+
+#[derive(Default)]
+struct Timings {
+    total: Duration,
+    phases: [Duration; Phase::ALL.len()],
+    /// Time of the phases nested in the phase being timed.
+    nested: Duration,
+}
+
+/// The profile of a running operation, recorded when the last reference is dropped.
+pub(crate) struct ActiveProfile {
+    profiler: Arc<ProfilerInner>,
+    operation: String,
+    collection: String,
+    started_at: u128,
+    timings: Mutex<Timings>,
+}
+
+impl ActiveProfile {
+    /// Makes `profile` the profile of the current thread until the returned scope is
+    /// dropped. The time in between is added to the total of the operation.
+    pub(crate) fn enter(profile: &Arc<ActiveProfile>) -> ProfileScope {
+        let previous =
+            CURRENT_PROFILE.with(|current| current.borrow_mut().replace(profile.clone()));
+        ProfileScope {
+            profile: profile.clone(),
+            previous,
+            started: Instant::now(),
+        }
+    }
+
+    fn time<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let outer = std::mem::take(&mut self.timings.lock().nested);
+        let started = Instant::now();
+        let result = f();
+        let elapsed = started.elapsed();
+
+        let mut timings = self.timings.lock();
+        let nested = std::mem::replace(&mut timings.nested, outer + elapsed);
+        timings.phases[phase.index()] += elapsed.saturating_sub(nested);
+        result
+    }
+}
+
+impl Drop for ActiveProfile {
+    fn drop(&mut self) {
+        let timings = self.timings.get_mut();
+        let (total, phases) = (timings.total, timings.phases);
+        self.profiler.record(OperationProfile {
+            operation: std::mem::take(&mut self.operation),
+            collection: std::mem::take(&mut self.collection),
+            started_at: self.started_at,
+            total,
+            phases,
+        });
+    }
+}
+
+/// Restores the previous profile of the thread when dropped.
+pub(crate) struct ProfileScope {
+    profile: Arc<ActiveProfile>,
+    previous: Option<Arc<ActiveProfile>>,
+    started: Instant,
+}
+
+impl ProfileScope {
+    /// Returns the profile, to be entered again by a cursor.
+    pub(crate) fn profile(&self) -> Arc<ActiveProfile> {
+        self.profile.clone()
+    }
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        self.profile.timings.lock().total += self.started.elapsed();
+        let previous = self.previous.take();
+        CURRENT_PROFILE.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::{order_by, Document};
+    use crate::common::SortOrder;
+    use crate::doc;
+    use crate::filter::{field, or};
+    use crate::index::non_unique_index;
+    use crate::nitrite::Nitrite;
+    use std::thread::sleep;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    fn insert_numbers(db: &Nitrite, count: i32) {
+        let collection = db.collection("numbers").unwrap();
+        let documents: Vec<Document> =
+            (0..count).map(|n| doc!{ "n": n, "even": (n % 2 == 0) }).collect();
+        collection.insert_many(documents).unwrap();
+    }
+
+    #[test]
+    fn test_disabled_profiler_records_nothing() {
+        let db = setup_nitrite();
+        insert_numbers(&db, 10);
+        let collection = db.collection("numbers").unwrap();
+        assert_eq!(collection.find(field("n").gt(5)).unwrap().count(), 4);
+
+        assert!(!db.profiler().is_enabled());
+        assert!(db.profiler().recent(10).is_empty());
+    }
+
+    #[test]
+    fn test_find_profile_includes_cursor_iteration() {
+        let db = setup_nitrite();
+        insert_numbers(&db, 100);
+        let collection = db.collection("numbers").unwrap();
+        collection.create_index(vec!["n"], &non_unique_index()).unwrap();
+        db.profiler().enable();
+
+        let cursor = collection
+            .find_with_options(
+                or(vec![field("n").lt(10), field("n").gt(90)]),
+                &order_by("n", SortOrder::Descending),
+            )
+            .unwrap();
+        // not recorded until the cursor is dropped
+        assert!(db.profiler().recent(10).is_empty());
+        assert_eq!(cursor.count(), 19);
+
+        let profiles = db.profiler().recent(10);
+        assert_eq!(profiles.len(), 1);
+        let profile = &profiles[0];
+        assert_eq!(profile.operation(), "find");
+        assert_eq!(profile.collection(), "numbers");
+        assert!(profile.started_at() > 0);
+        assert!(profile.phase(Phase::Planning) > Duration::ZERO);
+        assert!(profile.phase(Phase::IndexScan) > Duration::ZERO);
+        assert!(profile.phase(Phase::Dedup) > Duration::ZERO);
+        assert!(profile.phase(Phase::Sort) > Duration::ZERO);
+        let phases: Duration = Phase::ALL.iter().map(|phase| profile.phase(*phase)).sum();
+        assert_eq!(phases + profile.unattributed(), profile.total());
+    }
+
+    #[test]
+    fn test_writes_record_index_maintenance() {
+        let db = setup_nitrite();
+        let collection = db.collection("numbers").unwrap();
+        collection.create_index(vec!["n"], &non_unique_index()).unwrap();
+        db.profiler().enable();
+
+        collection.insert(doc!{ "n": 1 }).unwrap();
+        collection.update(field("n").eq(1), &doc!{ "n": 2 }).unwrap();
+        collection.update(field("n").eq(2), &doc!{ "other": 3 }).unwrap();
+
+        let profiles = db.profiler().recent(10);
+        let operations: Vec<&str> = profiles.iter().map(|p| p.operation()).collect();
+        assert_eq!(operations, vec!["update", "update", "insert"]);
+        assert!(profiles[2].phase(Phase::IndexMaintenance) > Duration::ZERO);
+        assert!(profiles[1].phase(Phase::IndexMaintenance) > Duration::ZERO);
+        // the update of the internal find is part of the update
+        assert!(profiles[1].phase(Phase::Planning) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_the_newest_profiles() {
+        let db = setup_nitrite();
+        let collection = db.collection("numbers").unwrap();
+        let profiler = db.profiler();
+        profiler.set_capacity(3).unwrap();
+        profiler.enable();
+
+        for n in 0..5 {
+            collection.insert(doc!{ "n": n }).unwrap();
+        }
+        collection.count(field("n").gt(1)).unwrap();
+
+        let profiles = profiler.recent(10);
+        let operations: Vec<&str> = profiles.iter().map(|p| p.operation()).collect();
+        assert_eq!(operations, vec!["count", "insert", "insert"]);
+        assert_eq!(profiler.recent(1).len(), 1);
+
+        profiler.set_capacity(1).unwrap();
+        assert_eq!(profiler.recent(10)[0].operation(), "count");
+        assert!(profiler.set_capacity(0).is_err());
+
+        profiler.disable();
+        collection.insert(doc!{ "n": 5 }).unwrap();
+        assert_eq!(profiler.recent(10)[0].operation(), "count");
+        profiler.clear();
+        assert!(profiler.recent(10).is_empty());
+    }
+
+    #[test]
+    fn test_nested_phases_are_timed_exclusively() {
+        let profiler = Profiler::new();
+        profiler.enable();
+        {
+            let _scope = profiler.start("find", "test").unwrap();
+            // a nested operation is part of the outer one
+            assert!(profiler.start("count", "test").is_none());
+            timed(Phase::IndexScan, || {
+                sleep(Duration::from_millis(5));
+                timed(Phase::Deserialization, || sleep(Duration::from_millis(20)));
+            });
+        }
+        assert!(timed(Phase::Sort, || true));
+
+        let profile = &profiler.recent(1)[0];
+        assert!(profile.phase(Phase::Deserialization) >= Duration::from_millis(20));
+        assert!(profile.phase(Phase::IndexScan) >= Duration::from_millis(5));
+        assert!(profile.phase(Phase::IndexScan) < Duration::from_millis(20));
+        assert_eq!(profile.phase(Phase::Sort), Duration::ZERO);
+        assert!(profile.total() >= Duration::from_millis(25));
+    }
+
+    #[test]
+    fn test_to_folded() {
+        let mut phases = [Duration::ZERO; Phase::ALL.len()];
+        phases[Phase::IndexScan.index()] = Duration::from_micros(40);
+        phases[Phase::Deserialization.index()] = Duration::from_micros(25);
+        let profile = OperationProfile {
+            operation: "find".to_string(),
+            collection: "orders".to_string(),
+            started_at: 1,
+            total: Duration::from_micros(100),
+            phases,
+        };
+
+        assert_eq!(
+            profile.to_folded(),
+            "orders;find;index_scan 40\norders;find;deserialization 25\norders;find 35\n"
+        );
+    }
+}
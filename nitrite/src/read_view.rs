@@ -0,0 +1,281 @@
+use crate::{
+    collection::Document,
+    errors::NitriteResult,
+    filter::Filter,
+    nitrite::Nitrite,
+    snapshot::{NitriteSnapshot, SnapshotCursor},
+};
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Options of a [`ReadView`].
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let view = db.read_view_with_options(
+///     ReadViewOptions::new().refresh_interval(Duration::from_secs(10)),
+/// )?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadViewOptions {
+    refresh_interval: Duration,
+}
+
+impl Default for ReadViewOptions {
+    fn default() -> Self {
+        ReadViewOptions {
+            refresh_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ReadViewOptions {
+    /// Creates the default options: a refresh interval of one second.
+    pub fn new() -> Self {
+        ReadViewOptions::default()
+    }
+
+    /// Sets how old the snapshot of the view gets before a read replaces it with a new
+    /// one. A zero interval takes a snapshot on every read.
+    pub fn refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// Returns how old the snapshot of the view gets before it is replaced.
+    pub fn get_refresh_interval(&self) -> Duration {
+        self.refresh_interval
+    }
+}
+
+/// A read-only handle on the database for analytics and reporting threads, served from a
+/// periodically refreshed [`NitriteSnapshot`].
+///
+/// Reads through the view never take the locks of the collections: they scan the
+/// snapshot, so a long report does not hold up writers. In exchange the view is
+/// eventually consistent. It shows the database as it was when its snapshot was taken,
+/// and the first read after the [refresh interval](ReadViewOptions::refresh_interval)
+/// has passed takes a new snapshot, on the reading thread. While one thread refreshes
+/// the view, the others keep reading the previous snapshot. [`ReadView::lag`] reports
+/// how far behind the database the view is.
+///
+/// `ReadView` is cheap to clone; clones share the same snapshot.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let view = db.read_view()?;
+/// thread::spawn(move || loop {
+///     let open = view.find("orders", field("status").eq("open"))?.count();
+///     metrics.record(open, view.lag());
+///     thread::sleep(Duration::from_secs(5));
+/// });
+/// ```
+#[derive(Clone)]
+pub struct ReadView {
+    inner: Arc<ReadViewInner>,
+}
+
+impl ReadView {
+    pub(crate) fn new(db: Nitrite, options: ReadViewOptions) -> NitriteResult<Self> {
+        let current = Generation::take(&db)?;
+        Ok(ReadView {
+            inner: Arc::new(ReadViewInner {
+                db,
+                options,
+                current: RwLock::new(Arc::new(current)),
+                refreshing: Mutex::new(()),
+            }),
+        })
+    }
+
+    /// Returns the options of the view.
+    pub fn options(&self) -> &ReadViewOptions {
+        &self.inner.options
+    }
+
+    /// Returns the current snapshot of the view, refreshing it first if it is older than
+    /// the refresh interval.
+    ///
+    /// Holding on to the returned snapshot keeps reading the same point in time, for
+    /// example to read several collections consistently.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot is due for a refresh and a new one cannot be
+    /// taken, for example because the database is closed.
+    pub fn snapshot(&self) -> NitriteResult<NitriteSnapshot> {
+        self.inner.current()
+    }
+
+    /// Replaces the snapshot of the view with a new one now, whatever its age.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database is closed or the store cannot open a snapshot.
+    pub fn refresh(&self) -> NitriteResult<()> {
+        let _refreshing = self.inner.refreshing.lock();
+        self.inner.refresh()
+    }
+
+    /// Returns how long ago the snapshot of the view was taken: writes made since then
+    /// are not visible through the view yet.
+    pub fn lag(&self) -> Duration {
+        self.inner.current.read().taken_at.elapsed()
+    }
+
+    /// Returns the names of the collections in the view.
+    pub fn collection_names(&self) -> NitriteResult<HashSet<String>> {
+        Ok(self.snapshot()?.collection_names().clone())
+    }
+
+    /// Iterates over all documents of a collection as of the snapshot of the view.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the collection is not in the snapshot.
+    pub fn documents(&self, collection_name: &str) -> NitriteResult<SnapshotCursor> {
+        self.snapshot()?.documents(collection_name)
+    }
+
+    /// Iterates over the documents of a collection that match `filter`, as of the
+    /// snapshot of the view. See [`NitriteSnapshot::find`].
+    pub fn find(
+        &self,
+        collection_name: &str,
+        filter: Filter,
+    ) -> NitriteResult<impl Iterator<Item = NitriteResult<Document>>> {
+        self.snapshot()?.find(collection_name, filter)
+    }
+}
+
+struct ReadViewInner {
+    db: Nitrite,
+    options: ReadViewOptions,
+    current: RwLock<Arc<Generation>>,
+    // held by the thread taking a new snapshot
+    refreshing: Mutex<()>,
+}
+
+impl ReadViewInner {
+    fn current(&self) -> NitriteResult<NitriteSnapshot> {
+        let current = self.current.read().clone();
+        if current.taken_at.elapsed() < self.options.refresh_interval {
+            return Ok(current.snapshot.clone());
+        }
+
+        match self.refreshing.try_lock() {
+            Some(_refreshing) => {
+                // another thread may have refreshed the view since it was read
+                if self.current.read().taken_at == current.taken_at {
+                    self.refresh()?;
+                }
+                Ok(self.current.read().snapshot.clone())
+            }
+            // a refresh is under way, keep reading the previous snapshot meanwhile
+            None => Ok(current.snapshot.clone()),
+        }
+    }
+
+    fn refresh(&self) -> NitriteResult<()> {
+        let generation = Generation::take(&self.db)?;
+        *self.current.write() = Arc::new(generation);
+        Ok(())
+    }
+}
+
+struct Generation {
+    snapshot: NitriteSnapshot,
+    taken_at: Instant,
+}
+
+impl Generation {
+    fn take(db: &Nitrite) -> NitriteResult<Self> {
+        let taken_at = Instant::now();
+        let snapshot = db.snapshot()?;
+        Ok(Generation { snapshot, taken_at })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use crate::filter::field;
+    use std::thread;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    #[test]
+    fn test_read_view_lags_until_refreshed() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let col = db.collection("read_view_orders")?;
+        col.insert(doc! { order: 1 })?;
+
+        let view = db.read_view_with_options(
+            ReadViewOptions::new().refresh_interval(Duration::from_secs(3600)),
+        )?;
+        col.insert(doc! { order: 2 })?;
+        assert_eq!(view.documents("read_view_orders")?.count(), 1);
+
+        view.refresh()?;
+        assert_eq!(view.documents("read_view_orders")?.count(), 2);
+        assert!(view.lag() < Duration::from_secs(3600));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_view_refreshes_after_interval() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let col = db.collection("read_view_events")?;
+        let view = db.read_view_with_options(
+            ReadViewOptions::new().refresh_interval(Duration::from_millis(20)),
+        )?;
+        col.insert(doc! { kind: "a" })?;
+        db.collection("read_view_created_later")?;
+
+        thread::sleep(Duration::from_millis(30));
+        assert!(view.lag() >= Duration::from_millis(30));
+        assert_eq!(view.find("read_view_events", field("kind").eq("a"))?.count(), 1);
+        assert!(view.collection_names()?.contains("read_view_created_later"));
+        assert!(view.lag() < Duration::from_millis(30));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_view_shared_between_threads() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let col = db.collection("read_view_shared")?;
+        col.insert(doc! { n: 1 })?;
+
+        let view = db.read_view_with_options(ReadViewOptions::new().refresh_interval(Duration::ZERO))?;
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let view = view.clone();
+                thread::spawn(move || view.documents("read_view_shared").map(|cursor| cursor.count()))
+            })
+            .collect();
+        col.insert(doc! { n: 2 })?;
+        for reader in readers {
+            let count = reader.join().unwrap()?;
+            assert!(count == 1 || count == 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_view_on_closed_database() {
+        let db = setup_nitrite();
+        let view = db
+            .read_view_with_options(ReadViewOptions::new().refresh_interval(Duration::ZERO))
+            .unwrap();
+        db.close().unwrap();
+        assert!(view.snapshot().is_err());
+        assert!(db.read_view().is_err());
+    }
+}
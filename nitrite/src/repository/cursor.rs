@@ -35,6 +35,33 @@ where
         count
     }
 
+    /// Returns the sum of the numeric values of a field of the remaining entities; see
+    /// [`DocumentCursor::sum`].
+    pub fn sum(self, field: &str) -> NitriteResult<f64> {
+        self.cursor.sum(field)
+    }
+
+    /// Returns the average of the numeric values of a field; see [`DocumentCursor::avg`].
+    pub fn avg(self, field: &str) -> NitriteResult<Option<f64>> {
+        self.cursor.avg(field)
+    }
+
+    /// Returns the smallest numeric value of a field; see [`DocumentCursor::min`].
+    pub fn min(self, field: &str) -> NitriteResult<Option<Value>> {
+        self.cursor.min(field)
+    }
+
+    /// Returns the largest numeric value of a field; see [`DocumentCursor::max`].
+    pub fn max(self, field: &str) -> NitriteResult<Option<Value>> {
+        self.cursor.max(field)
+    }
+
+    /// Returns a percentile of the numeric values of a field; see
+    /// [`DocumentCursor::percentile`].
+    pub fn percentile(self, field: &str, percentile: f64) -> NitriteResult<Option<f64>> {
+        self.cursor.percentile(field, percentile)
+    }
+
     pub fn first(&mut self) -> Option<NitriteResult<T>> {
         let doc_result = self.cursor.first();
         match doc_result {
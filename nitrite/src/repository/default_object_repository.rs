@@ -36,6 +36,14 @@ impl<T> DefaultObjectRepository<T> {
             _phantom: PhantomData,
         }
     }
+
+    pub(crate) fn collection(&self) -> &NitriteCollection {
+        &self.nitrite_collection
+    }
+
+    pub(crate) fn operations(&self) -> &RepositoryOperations {
+        &self.repository_operations
+    }
 }
 
 impl<T> PersistentCollection for DefaultObjectRepository<T>
@@ -0,0 +1,421 @@
+use crate::collection::operation::WriteResult;
+use crate::collection::{
+    CollectionEventListener, Document, FindOptions, NitriteCollection, NitriteCollectionProvider,
+    UpdateOptions,
+};
+use crate::common::{
+    AttributeAware, Attributes, DocumentCursor, EventAware, PersistentCollection, Processor,
+    SubscriberRef, Value,
+};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::Filter;
+use crate::index::{IndexDescriptor, IndexOptions};
+use crate::repository::repository_operations::RepositoryOperations;
+use crate::repository::{EntityId, EntityIndex};
+use crate::store::NitriteStore;
+use std::sync::Arc;
+
+/// Describes an entity as data: its name, id field and indexes.
+///
+/// It carries what `#[derive(NitriteEntity)]` generates for a Rust type, for entities
+/// whose shape is only known at runtime. A [`DynamicRepository`] opened with a
+/// descriptor uses the same collection as a typed repository of an entity with the same
+/// name.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let descriptor = EntityDescriptor::new("Book")
+///     .id(EntityId::new("isbn", None, None))
+///     .index(EntityIndex::new(vec!["author"], Some(NON_UNIQUE_INDEX)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityDescriptor {
+    name: String,
+    id: Option<EntityId>,
+    indexes: Vec<EntityIndex>,
+}
+
+impl EntityDescriptor {
+    /// Creates a descriptor of an entity without id or indexes.
+    pub fn new(name: &str) -> Self {
+        EntityDescriptor {
+            name: name.to_string(),
+            id: None,
+            indexes: Vec::new(),
+        }
+    }
+
+    /// Sets the id field of the entity, indexed by a unique index.
+    pub fn id(mut self, id: EntityId) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Adds an index of the entity.
+    pub fn index(mut self, index: EntityIndex) -> Self {
+        self.indexes.push(index);
+        self
+    }
+
+    /// Returns the entity name, which names the repository.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the id field of the entity, if it has one.
+    pub fn entity_id(&self) -> Option<&EntityId> {
+        self.id.as_ref()
+    }
+
+    /// Returns the indexes of the entity.
+    pub fn indexes(&self) -> &[EntityIndex] {
+        &self.indexes
+    }
+}
+
+/// A repository of entities described at runtime, read and written as documents.
+///
+/// It gives documents the semantics of a typed [`ObjectRepository`](crate::repository::ObjectRepository):
+/// the id field of the descriptor is required on insert and filled with a new id when it
+/// is a `NitriteId`, `update_one` upserts and `remove_one` removes by that id, and
+/// [`get_by_id`](DynamicRepository::get_by_id) looks it up. Plugins can persist their
+/// entities without compiling a derive into the host.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let books = db.dynamic_repository(
+///     EntityDescriptor::new("Book").id(EntityId::new("isbn", None, None)),
+/// )?;
+/// books.insert(doc! { isbn: "978-0", title: "Dune" })?;
+/// let book = books.get_by_id(&Value::from("978-0"))?;
+/// ```
+#[derive(Clone)]
+pub struct DynamicRepository {
+    inner: Arc<DynamicRepositoryInner>,
+}
+
+struct DynamicRepositoryInner {
+    descriptor: EntityDescriptor,
+    collection: NitriteCollection,
+    operations: RepositoryOperations,
+}
+
+impl DynamicRepository {
+    pub(crate) fn new(
+        descriptor: EntityDescriptor,
+        collection: NitriteCollection,
+        operations: RepositoryOperations,
+    ) -> Self {
+        DynamicRepository {
+            inner: Arc::new(DynamicRepositoryInner {
+                descriptor,
+                collection,
+                operations,
+            }),
+        }
+    }
+
+    /// Returns the descriptor of the entities.
+    pub fn descriptor(&self) -> &EntityDescriptor {
+        &self.inner.descriptor
+    }
+
+    /// Inserts an entity.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the id of the entity is missing, if a `NitriteId` id is set by
+    /// the caller, or if an entity with the same id exists.
+    pub fn insert(&self, document: Document) -> NitriteResult<WriteResult> {
+        let document = self.inner.operations.prepare_document(document, false)?;
+        self.inner.collection.insert(document)
+    }
+
+    /// Inserts several entities at once, or none of them if one is rejected.
+    pub fn insert_many(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
+        let documents = documents
+            .into_iter()
+            .map(|document| self.inner.operations.prepare_document(document, false))
+            .collect::<NitriteResult<Vec<_>>>()?;
+        self.inner.collection.insert_many(documents)
+    }
+
+    /// Replaces the fields of the entities matching `filter` with those of `document`.
+    pub fn update(&self, filter: Filter, document: Document) -> NitriteResult<WriteResult> {
+        self.update_with_options(filter, document, &UpdateOptions::default())
+    }
+
+    /// Replaces the fields of the entities matching `filter` with those of `document`,
+    /// inserting it if nothing matches and `insert_if_absent` is set.
+    pub fn update_with_options(
+        &self,
+        filter: Filter,
+        document: Document,
+        update_options: &UpdateOptions,
+    ) -> NitriteResult<WriteResult> {
+        let mut document = self.inner.operations.prepare_document(document, true)?;
+        if !update_options.is_insert_if_absent() {
+            self.inner.operations.remove_nitrite_id(&mut document)?;
+        }
+        self.inner
+            .collection
+            .update_with_options(filter, &document, update_options)
+    }
+
+    /// Updates the entity with the id of `document`, inserting it if it doesn't exist and
+    /// `insert_if_absent` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the descriptor has no id.
+    pub fn update_one(&self, document: Document, insert_if_absent: bool) -> NitriteResult<WriteResult> {
+        let filter = self.inner.operations.create_document_filter(&document)?;
+        let update_options = UpdateOptions::new(insert_if_absent, true);
+        self.update_with_options(filter, document, &update_options)
+    }
+
+    /// Sets the fields of `update` on the entities matching `filter`, leaving their other
+    /// fields alone.
+    pub fn update_document(
+        &self,
+        filter: Filter,
+        update: &Document,
+        just_once: bool,
+    ) -> NitriteResult<WriteResult> {
+        let mut update = update.clone();
+        self.inner.operations.remove_nitrite_id(&mut update)?;
+        self.inner
+            .collection
+            .update_with_options(filter, &update, &UpdateOptions::new(false, just_once))
+    }
+
+    /// Removes the entity with the id of `document`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the descriptor has no id.
+    pub fn remove_one(&self, document: &Document) -> NitriteResult<WriteResult> {
+        let filter = self.inner.operations.create_document_filter(document)?;
+        self.remove(filter, true)
+    }
+
+    /// Removes the entities matching `filter`, or only the first one if `just_once` is set.
+    pub fn remove(&self, filter: Filter, just_once: bool) -> NitriteResult<WriteResult> {
+        self.inner.collection.remove(filter, just_once)
+    }
+
+    /// Gets the entity with the id `id`. An embedded id is given as a document of its
+    /// fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the descriptor has no id.
+    pub fn get_by_id(&self, id: &Value) -> NitriteResult<Option<Document>> {
+        let entity_id = match self.inner.descriptor.entity_id() {
+            Some(entity_id) => entity_id,
+            None => {
+                log::error!("Entity {} does not have an id field", self.inner.descriptor.name());
+                return Err(NitriteError::new(
+                    "Entity does not have an id field",
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+        };
+        let filter = entity_id.create_id_filter(id.clone())?;
+        let mut cursor = self.inner.collection.find(filter)?;
+        cursor.next().transpose()
+    }
+
+    /// Finds the entities matching `filter`.
+    pub fn find(&self, filter: Filter) -> NitriteResult<DocumentCursor> {
+        self.inner.collection.find(filter)
+    }
+
+    /// Finds the entities matching `filter`, sorted and paged by `find_options`.
+    pub fn find_with_options(
+        &self,
+        filter: Filter,
+        find_options: &FindOptions,
+    ) -> NitriteResult<DocumentCursor> {
+        self.inner.collection.find_with_options(filter, find_options)
+    }
+
+    /// Returns the collection storing the entities. Documents written through it bypass
+    /// the checks of the repository.
+    pub fn document_collection(&self) -> NitriteCollection {
+        self.inner.collection.clone()
+    }
+}
+
+impl PersistentCollection for DynamicRepository {
+    fn add_processor(&self, processor: Processor) -> NitriteResult<()> {
+        self.inner.collection.add_processor(processor)
+    }
+
+    fn create_index(&self, field_names: Vec<&str>, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.inner.collection.create_index(field_names, index_options)
+    }
+
+    fn rebuild_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.inner.collection.rebuild_index(field_names)
+    }
+
+    fn list_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.inner.collection.list_indexes()
+    }
+
+    fn has_index(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        self.inner.collection.has_index(field_names)
+    }
+
+    fn is_indexing(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        self.inner.collection.is_indexing(field_names)
+    }
+
+    fn drop_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.inner.collection.drop_index(field_names)
+    }
+
+    fn drop_all_indexes(&self) -> NitriteResult<()> {
+        self.inner.collection.drop_all_indexes()
+    }
+
+    fn clear(&self) -> NitriteResult<()> {
+        self.inner.collection.clear()
+    }
+
+    fn dispose(&self) -> NitriteResult<()> {
+        self.inner.collection.dispose()
+    }
+
+    fn is_dropped(&self) -> NitriteResult<bool> {
+        self.inner.collection.is_dropped()
+    }
+
+    fn is_open(&self) -> NitriteResult<bool> {
+        self.inner.collection.is_open()
+    }
+
+    fn size(&self) -> NitriteResult<u64> {
+        self.inner.collection.size()
+    }
+
+    fn close(&self) -> NitriteResult<()> {
+        self.inner.collection.close()
+    }
+
+    fn store(&self) -> NitriteResult<NitriteStore> {
+        self.inner.collection.store()
+    }
+}
+
+impl EventAware for DynamicRepository {
+    fn subscribe(&self, handler: CollectionEventListener) -> NitriteResult<Option<SubscriberRef>> {
+        self.inner.collection.subscribe(handler)
+    }
+
+    fn unsubscribe(&self, subscriber: SubscriberRef) -> NitriteResult<()> {
+        self.inner.collection.unsubscribe(subscriber)
+    }
+}
+
+impl AttributeAware for DynamicRepository {
+    fn attributes(&self) -> NitriteResult<Option<Attributes>> {
+        self.inner.collection.attributes()
+    }
+
+    fn set_attributes(&self, attributes: Attributes) -> NitriteResult<()> {
+        self.inner.collection.set_attributes(attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{NON_UNIQUE_INDEX, UNIQUE_INDEX};
+    use crate::doc;
+    use crate::filter::field;
+    use crate::nitrite::Nitrite;
+
+    fn books(db: &Nitrite) -> DynamicRepository {
+        db.dynamic_repository(
+            EntityDescriptor::new("Book")
+                .id(EntityId::new("isbn", None, None))
+                .index(EntityIndex::new(vec!["author"], Some(NON_UNIQUE_INDEX))),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_descriptor_indexes_are_created() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let repo = books(&db);
+
+        assert_eq!(repo.descriptor().name(), "Book");
+        assert!(repo.has_index(vec!["isbn"]).unwrap());
+        assert!(repo.has_index(vec!["author"]).unwrap());
+        let indexes = repo.list_indexes().unwrap();
+        let isbn = indexes.iter().find(|index| index.index_fields().field_names() == vec!["isbn"]);
+        assert_eq!(isbn.unwrap().index_type(), UNIQUE_INDEX);
+        assert!(db.list_repositories().unwrap().contains("Book"));
+    }
+
+    #[test]
+    fn test_crud_by_entity_id() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let repo = books(&db);
+
+        repo.insert(doc!{ isbn: "1", title: "Dune", author: "Herbert" }).unwrap();
+        assert!(repo.insert(doc!{ isbn: "1", title: "Other" }).is_err());
+        assert_eq!(
+            repo.insert(doc!{ title: "No id" }).unwrap_err().kind(),
+            &ErrorKind::InvalidId
+        );
+
+        repo.update_one(doc!{ isbn: "2", title: "Emma", author: "Austen" }, true).unwrap();
+        repo.update_one(doc!{ isbn: "1", title: "Dune Messiah", author: "Herbert" }, false)
+            .unwrap();
+        assert_eq!(repo.size().unwrap(), 2);
+
+        let book = repo.get_by_id(&Value::from("1")).unwrap().unwrap();
+        assert_eq!(book.get("title").unwrap(), Value::from("Dune Messiah"));
+        assert!(repo.get_by_id(&Value::from("3")).unwrap().is_none());
+
+        repo.update_document(field("author").eq("Austen"), &doc!{ year: 1815 }, false).unwrap();
+        let book = repo.get_by_id(&Value::from("2")).unwrap().unwrap();
+        assert_eq!(book.get("title").unwrap(), Value::from("Emma"));
+        assert_eq!(book.get("year").unwrap(), Value::from(1815));
+
+        repo.remove_one(&doc!{ isbn: "2" }).unwrap();
+        assert_eq!(repo.find(field("author").eq("Austen")).unwrap().count(), 0);
+        assert_eq!(repo.size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_entity_without_id() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let repo = db.dynamic_repository(EntityDescriptor::new("Note")).unwrap();
+
+        repo.insert(doc!{ text: "a" }).unwrap();
+        repo.insert(doc!{ text: "a" }).unwrap();
+        assert_eq!(repo.size().unwrap(), 2);
+        assert!(repo.get_by_id(&Value::from("a")).is_err());
+        assert_eq!(
+            repo.update_one(doc!{ text: "b" }, true).unwrap_err().kind(),
+            &ErrorKind::NotIdentifiable
+        );
+    }
+
+    #[test]
+    fn test_reopen_with_another_id_fails() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        books(&db);
+
+        assert!(db.dynamic_repository(EntityDescriptor::new("Book")).is_err());
+        let other_id = EntityDescriptor::new("Book").id(EntityId::new("title", None, None));
+        assert!(db.dynamic_repository(other_id).is_err());
+        assert!(db.dynamic_repository(EntityDescriptor::new("a+b")).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use crate::common::{Convertible, Value, DOC_ID, UNIQUE_INDEX};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::filter::{and, field, Filter};
 use crate::FIELD_SEPARATOR;
+use std::collections::BTreeMap;
 
 /// Trait that defines the schema and metadata for a database entity (repository type).
 ///
@@ -19,6 +20,7 @@ use crate::FIELD_SEPARATOR;
 /// * `entity_name()` - Returns the entity type name (e.g., "Book")
 /// * `entity_indexes()` - Returns index definitions if any
 /// * `entity_id()` - Returns ID field configuration if specified
+/// * `entity_schema()` - Returns the persisted fields, to detect schema changes
 ///
 /// # Usage
 /// ```ignore
@@ -62,6 +64,15 @@ pub trait NitriteEntity: Default {
     /// - Some(EntityId) if an ID field is defined via #[entity(id(field = "..."))]
     /// - None if no explicit ID field is configured
     fn entity_id(&self) -> Option<EntityId>;
+
+    /// Returns the persisted fields of this entity, used to detect schema changes.
+    ///
+    /// # Returns
+    /// - Some(EntitySchema) when derived, listing the fields of the struct
+    /// - None to skip the schema check (default)
+    fn entity_schema(&self) -> Option<EntitySchema> {
+        None
+    }
 }
 
 /// Defines a database index on one or more fields of an entity.
@@ -126,6 +137,137 @@ impl EntityIndex {
     }
 }
 
+/// Describes the persisted fields of an entity and their types.
+///
+/// # Purpose
+/// The fingerprint of the schema is stored with the repository when it is first opened.
+/// Opening the repository later with an entity whose fingerprint differs fails with
+/// `ErrorKind::SchemaChanged`, naming the added, removed and retyped fields, so that the
+/// change is not silently mapped onto documents of the old shape. A migration with a
+/// repository instruction for the entity discards the stored fingerprint, and the new
+/// schema is recorded on the next open. An entity versioned with
+/// `#[convertible(version = N)]` upgrades its old documents on read, so a changed
+/// fingerprint is accepted and recorded when the version is above the stored one.
+///
+/// # Characteristics
+/// - Generated by the entity derive macro from the struct fields
+/// - Field order does not affect the fingerprint
+/// - Types are compared as written in the struct, ignoring whitespace
+///
+/// # Usage
+/// ```ignore
+/// let schema = EntitySchema::new(vec![("id", "i32"), ("name", "String")]);
+/// let fingerprint = schema.fingerprint();
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct EntitySchema {
+    fields: BTreeMap<String, String>,
+    version: u32,
+}
+
+impl Default for EntitySchema {
+    fn default() -> Self {
+        EntitySchema {
+            fields: BTreeMap::new(),
+            version: 1,
+        }
+    }
+}
+
+impl EntitySchema {
+    /// Creates a schema from field names and type names.
+    pub fn new(fields: Vec<(&str, &str)>) -> Self {
+        EntitySchema {
+            fields: fields
+                .into_iter()
+                .map(|(name, type_name)| (name.to_string(), type_name.replace(' ', "")))
+                .collect(),
+            version: 1,
+        }
+    }
+
+    /// Sets the version of the entity, from `#[convertible(version = N)]`.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the version of the entity, 1 for unversioned entities.
+    ///
+    /// The version is not part of the fingerprint.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the type name of each field, ordered by field name.
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    /// Returns a stable 64-bit FNV-1a hash of the field names and types.
+    ///
+    /// # Behavior
+    /// - Identical across builds and platforms, so it can be persisted
+    pub fn fingerprint(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET_BASIS;
+        for (name, type_name) in &self.fields {
+            for byte in name.bytes().chain([b':']).chain(type_name.bytes()).chain([b';']) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(PRIME);
+            }
+        }
+        hash
+    }
+
+    /// Returns the fields that were added, removed or changed type since `previous`,
+    /// ordered by name.
+    pub fn changed_fields(&self, previous: &EntitySchema) -> Vec<String> {
+        let mut changed: Vec<String> = self
+            .fields
+            .iter()
+            .filter(|(name, type_name)| previous.fields.get(*name) != Some(type_name))
+            .map(|(name, _)| name.clone())
+            .collect();
+        changed.extend(
+            previous
+                .fields
+                .keys()
+                .filter(|name| !self.fields.contains_key(*name))
+                .cloned(),
+        );
+        changed.sort();
+        changed
+    }
+
+    /// Encodes the fields as `name: type` strings, for the collection attributes.
+    pub(crate) fn to_value(&self) -> Value {
+        Value::Array(
+            self.fields
+                .iter()
+                .map(|(name, type_name)| Value::String(format!("{}: {}", name, type_name)))
+                .collect(),
+        )
+    }
+
+    /// Decodes the fields written by `to_value()`; anything else gives an empty schema.
+    pub(crate) fn from_value(value: Option<&Value>) -> Self {
+        let mut fields = BTreeMap::new();
+        if let Some(Value::Array(values)) = value {
+            for value in values {
+                if let Some((name, type_name)) =
+                    value.as_string().and_then(|field| field.split_once(": "))
+                {
+                    fields.insert(name.to_string(), type_name.to_string());
+                }
+            }
+        }
+        EntitySchema { fields, version: 1 }
+    }
+}
+
 /// Defines the ID field configuration for an entity.
 ///
 /// # Purpose
@@ -563,4 +705,36 @@ mod tests {
         let encoded = id.encoded_field_names();
         assert_eq!(encoded.len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_entity_schema_fingerprint_ignores_field_order() {
+        let schema = EntitySchema::new(vec![("id", "i32"), ("tags", "Vec< String >")]);
+        let reordered = EntitySchema::new(vec![("tags", "Vec<String>"), ("id", "i32")]);
+        assert_eq!(schema.fingerprint(), reordered.fingerprint());
+
+        let retyped = EntitySchema::new(vec![("id", "i64"), ("tags", "Vec<String>")]);
+        assert_ne!(schema.fingerprint(), retyped.fingerprint());
+    }
+
+    #[test]
+    fn test_entity_schema_version() {
+        let schema = EntitySchema::new(vec![("id", "i32")]);
+        assert_eq!(schema.version(), 1);
+        assert_eq!(EntitySchema::default().version(), 1);
+
+        let versioned = schema.clone().with_version(3);
+        assert_eq!(versioned.version(), 3);
+        assert_eq!(versioned.fingerprint(), schema.fingerprint());
+    }
+
+    #[test]
+    fn test_entity_schema_changed_fields() {
+        let previous = EntitySchema::new(vec![("id", "i32"), ("name", "String"), ("age", "u8")]);
+        let current = EntitySchema::new(vec![("id", "i32"), ("name", "Option<String>"), ("email", "String")]);
+        assert_eq!(current.changed_fields(&previous), vec!["age", "email", "name"]);
+
+        let decoded = EntitySchema::from_value(Some(&previous.to_value()));
+        assert_eq!(decoded, previous);
+        assert!(decoded.changed_fields(&previous).is_empty());
+    }
+}
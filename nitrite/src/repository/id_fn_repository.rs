@@ -0,0 +1,513 @@
+use crate::collection::operation::WriteResult;
+use crate::collection::{
+    conflict_fields, upsert_documents, CollectionEventListener, ConflictTarget, Document,
+    FindOptions, NitriteCollection, NitriteCollectionProvider, NitriteId, UpdateOptions,
+    UpsertManyOptions, UpsertManyResult,
+};
+use crate::common::{
+    AttributeAware, Attributes, Convertible, EventAware, PersistentCollection, Processor,
+    SubscriberRef, Value, ENTITY_KEY, UNIQUE_INDEX,
+};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::{all, field, Filter};
+use crate::index::{IndexDescriptor, IndexOptions};
+use crate::repository::cursor::ObjectCursor;
+use crate::repository::default_object_repository::DefaultObjectRepository;
+use crate::repository::repository::{ObjectRepository, ObjectRepositoryProvider};
+use crate::repository::NitriteEntity;
+use crate::store::NitriteStore;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Computes the id of an entity for an [`IdFnRepository`].
+pub(crate) type IdFn<T, K> = Arc<dyn Fn(&T) -> K + Send + Sync>;
+
+/// A repository whose entities are identified by a closure instead of `#[entity(id)]`.
+///
+/// The id computed by the closure is stored with every entity in the `_key` field, under a
+/// unique index. It identifies the entity for [`get_by_id`](IdFnRepository::get_by_id),
+/// [`remove_by_id`](IdFnRepository::remove_by_id) and for the upserts and removals of
+/// `update_one` and `remove_one`. All the other operations of
+/// [`ObjectRepository`] are available through `Deref`.
+///
+/// Ids that convert to an array or a document, like tuples, are stored in their JSON form,
+/// so a composite id is indexed as a single value.
+///
+/// Entities must be written through this repository for their id to be stored. When it is
+/// opened, the ids of the entities stored without one, for example through
+/// `db.repository::<T>()`, are computed and stored.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let repo = db.repository_with_id_fn::<Line, (String, i64)>(|line| {
+///     (line.order.clone(), line.number)
+/// })?;
+///
+/// repo.update_one(line, true)?;
+/// let line = repo.get_by_id(&("A-100".to_string(), 2))?;
+/// ```
+pub struct IdFnRepository<T, K>
+where
+    T: Convertible + NitriteEntity,
+{
+    repository: ObjectRepository<T>,
+    id_fn: IdFn<T, K>,
+}
+
+impl<T, K> IdFnRepository<T, K>
+where
+    T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
+    K: Convertible + Send + Sync + 'static,
+{
+    /// Wraps `repository` so its entities are identified by `id_fn`, storing the ids of
+    /// the entities that do not have one yet.
+    pub(crate) fn new(
+        repository: DefaultObjectRepository<T>,
+        id_fn: IdFn<T, K>,
+    ) -> NitriteResult<Self> {
+        let provider = IdFnObjectRepository {
+            repository,
+            id_fn: id_fn.clone(),
+        };
+        provider.initialize()?;
+        Ok(IdFnRepository {
+            repository: ObjectRepository::new(provider),
+            id_fn,
+        })
+    }
+
+    /// Computes the id of `object`.
+    pub fn id_of(&self, object: &T) -> K {
+        (self.id_fn)(object)
+    }
+
+    /// Gets the entity with the id `id`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if no entity has this id.
+    pub fn get_by_id(&self, id: &K) -> NitriteResult<Option<T>> {
+        let filter = field(ENTITY_KEY).eq(key_value(id)?);
+        let mut cursor = self.repository.find(filter)?;
+        cursor.first().transpose()
+    }
+
+    /// Removes the entity with the id `id`, if any.
+    pub fn remove_by_id(&self, id: &K) -> NitriteResult<WriteResult> {
+        let filter = field(ENTITY_KEY).eq(key_value(id)?);
+        self.repository.remove(filter, true)
+    }
+
+    /// Returns the repository, to pass where an `ObjectRepository` is expected.
+    pub fn repository(&self) -> &ObjectRepository<T> {
+        &self.repository
+    }
+}
+
+impl<T, K> Clone for IdFnRepository<T, K>
+where
+    T: Convertible + NitriteEntity,
+{
+    fn clone(&self) -> Self {
+        IdFnRepository {
+            repository: self.repository.clone(),
+            id_fn: self.id_fn.clone(),
+        }
+    }
+}
+
+impl<T, K> Deref for IdFnRepository<T, K>
+where
+    T: Convertible + NitriteEntity,
+{
+    type Target = ObjectRepository<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.repository
+    }
+}
+
+/// Converts an id to the value stored in the `_key` field.
+fn key_value<K: Convertible>(id: &K) -> NitriteResult<Value> {
+    match id.to_value()? {
+        Value::Null => {
+            log::error!("Entity id computed by the id function cannot be null");
+            Err(NitriteError::new(
+                "Entity id computed by the id function cannot be null",
+                ErrorKind::InvalidId,
+            ))
+        }
+        value @ (Value::Array(_) | Value::Document(_)) => Ok(Value::String(value.to_string())),
+        value => Ok(value),
+    }
+}
+
+/// The provider behind an [`IdFnRepository`]: a default repository that stores the id of
+/// each entity it writes and matches entities by that id.
+struct IdFnObjectRepository<T, K> {
+    repository: DefaultObjectRepository<T>,
+    id_fn: IdFn<T, K>,
+}
+
+impl<T, K> IdFnObjectRepository<T, K>
+where
+    T: Convertible<Output = T> + NitriteEntity + Send + Sync,
+    K: Convertible + Send + Sync,
+{
+    /// Stores the ids missing from the stored entities and indexes the ids.
+    fn initialize(&self) -> NitriteResult<()> {
+        let collection = self.repository.collection();
+        if collection.has_index(vec![ENTITY_KEY])? {
+            return Ok(());
+        }
+
+        let documents = collection.find(all())?.collect::<NitriteResult<Vec<_>>>()?;
+        for mut document in documents {
+            if !document.get(ENTITY_KEY)?.is_null() {
+                continue;
+            }
+            let object = T::from_value(&Value::Document(document.clone()))?;
+            let mut update = Document::new();
+            update.put(ENTITY_KEY, self.key_of(&object)?)?;
+            collection.update_by_id(&document.id()?, &update, false)?;
+        }
+        collection.create_index(vec![ENTITY_KEY], &IndexOptions::new(UNIQUE_INDEX))
+    }
+
+    fn key_of(&self, object: &T) -> NitriteResult<Value> {
+        key_value(&(self.id_fn)(object))
+    }
+
+    fn key_filter(&self, object: &T) -> NitriteResult<Filter> {
+        Ok(field(ENTITY_KEY).eq(self.key_of(object)?))
+    }
+
+    fn to_document(&self, object: &T, update: bool) -> NitriteResult<Document> {
+        let mut document = self.repository.operations().to_document(object, update)?;
+        document.put(ENTITY_KEY, self.key_of(object)?)?;
+        Ok(document)
+    }
+}
+
+impl<T, K> PersistentCollection for IdFnObjectRepository<T, K>
+where
+    T: Convertible + NitriteEntity + Send + Sync,
+    K: Send + Sync,
+{
+    fn add_processor(&self, processor: Processor) -> NitriteResult<()> {
+        self.repository.add_processor(processor)
+    }
+
+    fn create_index(&self, field_names: Vec<&str>, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.repository.create_index(field_names, index_options)
+    }
+
+    fn rebuild_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.repository.rebuild_index(field_names)
+    }
+
+    fn list_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.repository.list_indexes()
+    }
+
+    fn has_index(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        self.repository.has_index(field_names)
+    }
+
+    fn is_indexing(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        self.repository.is_indexing(field_names)
+    }
+
+    fn drop_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.repository.drop_index(field_names)
+    }
+
+    fn drop_all_indexes(&self) -> NitriteResult<()> {
+        self.repository.drop_all_indexes()
+    }
+
+    fn clear(&self) -> NitriteResult<()> {
+        self.repository.clear()
+    }
+
+    fn dispose(&self) -> NitriteResult<()> {
+        self.repository.dispose()
+    }
+
+    fn is_dropped(&self) -> NitriteResult<bool> {
+        self.repository.is_dropped()
+    }
+
+    fn is_open(&self) -> NitriteResult<bool> {
+        self.repository.is_open()
+    }
+
+    fn size(&self) -> NitriteResult<u64> {
+        self.repository.size()
+    }
+
+    fn close(&self) -> NitriteResult<()> {
+        self.repository.close()
+    }
+
+    fn store(&self) -> NitriteResult<NitriteStore> {
+        self.repository.store()
+    }
+}
+
+impl<T, K> EventAware for IdFnObjectRepository<T, K> {
+    fn subscribe(&self, handler: CollectionEventListener) -> NitriteResult<Option<SubscriberRef>> {
+        self.repository.subscribe(handler)
+    }
+
+    fn unsubscribe(&self, subscriber: SubscriberRef) -> NitriteResult<()> {
+        self.repository.unsubscribe(subscriber)
+    }
+}
+
+impl<T, K> AttributeAware for IdFnObjectRepository<T, K> {
+    fn attributes(&self) -> NitriteResult<Option<Attributes>> {
+        self.repository.attributes()
+    }
+
+    fn set_attributes(&self, attributes: Attributes) -> NitriteResult<()> {
+        self.repository.set_attributes(attributes)
+    }
+}
+
+impl<T, K> ObjectRepositoryProvider<T> for IdFnObjectRepository<T, K>
+where
+    T: Convertible<Output = T> + NitriteEntity + Send + Sync,
+    K: Convertible + Send + Sync,
+{
+    fn insert(&self, object: T) -> NitriteResult<WriteResult> {
+        let document = self.to_document(&object, false)?;
+        self.repository.collection().insert(document)
+    }
+
+    fn insert_many(&self, objects: Vec<T>) -> NitriteResult<WriteResult> {
+        let documents = objects
+            .iter()
+            .map(|object| self.to_document(object, false))
+            .collect::<NitriteResult<Vec<_>>>()?;
+        self.repository.collection().insert_many(documents)
+    }
+
+    fn update_with_options(
+        &self,
+        filter: Filter,
+        object: T,
+        update_options: &UpdateOptions,
+    ) -> NitriteResult<WriteResult> {
+        let mut document = self.to_document(&object, true)?;
+        if !update_options.is_insert_if_absent() {
+            self.repository.operations().remove_nitrite_id(&mut document)?;
+        }
+
+        self.repository
+            .collection()
+            .update_with_options(filter, &document, update_options)
+    }
+
+    fn update_one(&self, object: T, insert_if_absent: bool) -> NitriteResult<WriteResult> {
+        let update_options = UpdateOptions::new(insert_if_absent, true);
+        let filter = self.key_filter(&object)?;
+        self.update_with_options(filter, object, &update_options)
+    }
+
+    fn update_document(
+        &self,
+        filter: Filter,
+        document: &Document,
+        just_once: bool,
+    ) -> NitriteResult<WriteResult> {
+        self.repository.update_document(filter, document, just_once)
+    }
+
+    fn upsert_many_with_options(
+        &self,
+        objects: Vec<T>,
+        target: ConflictTarget,
+        options: &UpsertManyOptions,
+    ) -> NitriteResult<UpsertManyResult> {
+        let collection = self.repository.document_collection();
+        let key_fields = match &target {
+            // the id computed by the id function identifies the entity
+            ConflictTarget::Id => vec![ENTITY_KEY.to_string()],
+            ConflictTarget::Index(_) => conflict_fields(&**collection, &target)?,
+        };
+        let documents = objects
+            .iter()
+            .map(|object| self.to_document(object, true))
+            .collect::<NitriteResult<Vec<_>>>()?;
+        upsert_documents(&**collection, documents, &key_fields, options)
+    }
+
+    fn update_by_nitrite_id(
+        &self,
+        id: &NitriteId,
+        object: T,
+        insert_if_absent: bool,
+    ) -> NitriteResult<WriteResult> {
+        let mut document = self.to_document(&object, true)?;
+        self.repository.operations().remove_nitrite_id(&mut document)?;
+        self.repository
+            .collection()
+            .update_by_id(id, &document, insert_if_absent)
+    }
+
+    fn remove_one(&self, object: T) -> NitriteResult<WriteResult> {
+        let filter = self.key_filter(&object)?;
+        self.remove(filter, true)
+    }
+
+    fn remove(&self, filter: Filter, just_once: bool) -> NitriteResult<WriteResult> {
+        self.repository.remove(filter, just_once)
+    }
+
+    fn get_by_id(&self, id: &T::Id) -> NitriteResult<Option<T>> {
+        self.repository.get_by_id(id)
+    }
+
+    fn find(&self, filter: Filter) -> NitriteResult<ObjectCursor<T>> {
+        self.repository.find(filter)
+    }
+
+    fn find_with_options(
+        &self,
+        filter: Filter,
+        find_options: &FindOptions,
+    ) -> NitriteResult<ObjectCursor<T>> {
+        self.repository.find_with_options(filter, find_options)
+    }
+
+    fn document_collection(&self) -> NitriteCollection {
+        self.repository.document_collection()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nitrite::Nitrite;
+    use crate::repository::{EntityId, EntityIndex};
+
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Line {
+        order: String,
+        number: i64,
+        quantity: i64,
+    }
+
+    impl NitriteEntity for Line {
+        type Id = ();
+
+        fn entity_name(&self) -> String {
+            "Line".to_string()
+        }
+
+        fn entity_indexes(&self) -> Option<Vec<EntityIndex>> {
+            None
+        }
+
+        fn entity_id(&self) -> Option<EntityId> {
+            None
+        }
+    }
+
+    impl Convertible for Line {
+        type Output = Line;
+
+        fn to_value(&self) -> NitriteResult<Value> {
+            let mut document = Document::new();
+            document.put("order", self.order.as_str())?;
+            document.put("number", self.number)?;
+            document.put("quantity", self.quantity)?;
+            Ok(Value::Document(document))
+        }
+
+        fn from_value(value: &Value) -> NitriteResult<Self::Output> {
+            let document = value.as_document().ok_or_else(|| {
+                NitriteError::new("Line must be a document", ErrorKind::ObjectMappingError)
+            })?;
+            Ok(Line {
+                order: document.get("order")?.as_string().cloned().unwrap_or_default(),
+                number: document.get("number")?.as_i64().copied().unwrap_or_default(),
+                quantity: document.get("quantity")?.as_i64().copied().unwrap_or_default(),
+            })
+        }
+    }
+
+    fn line(order: &str, number: i64, quantity: i64) -> Line {
+        Line { order: order.to_string(), number, quantity }
+    }
+
+    fn open(db: &Nitrite) -> IdFnRepository<Line, (String, i64)> {
+        db.repository_with_id_fn::<Line, (String, i64)>(|line| (line.order.clone(), line.number))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_upsert_and_get_by_composite_id() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let repo = open(&db);
+
+        repo.update_one(line("A", 1, 5), true).unwrap();
+        repo.update_one(line("A", 2, 1), true).unwrap();
+        repo.update_one(line("A", 1, 7), true).unwrap();
+        assert_eq!(repo.size().unwrap(), 2);
+
+        let id = ("A".to_string(), 1);
+        assert_eq!(repo.get_by_id(&id).unwrap(), Some(line("A", 1, 7)));
+        assert_eq!(repo.get_by_id(&("B".to_string(), 1)).unwrap(), None);
+        assert_eq!(repo.id_of(&line("A", 1, 0)), id);
+
+        repo.remove_one(line("A", 2, 0)).unwrap();
+        repo.remove_by_id(&id).unwrap();
+        assert_eq!(repo.size().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_insert_rejects_duplicate_id() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let repo = open(&db);
+
+        repo.insert(line("A", 1, 5)).unwrap();
+        assert!(repo.insert(line("A", 1, 6)).is_err());
+        assert!(repo.insert_many(vec![line("B", 1, 1), line("B", 1, 2)]).is_err());
+        assert!(repo.has_index(vec![ENTITY_KEY]).unwrap());
+    }
+
+    #[test]
+    fn test_backfills_ids_of_existing_entities() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let plain = db.repository::<Line>().unwrap();
+        plain.insert(line("A", 1, 5)).unwrap();
+        plain.insert(line("A", 2, 3)).unwrap();
+
+        let repo = open(&db);
+        assert_eq!(repo.get_by_id(&("A".to_string(), 2)).unwrap(), Some(line("A", 2, 3)));
+
+        // duplicate ids cannot be indexed
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let plain = db.repository::<Line>().unwrap();
+        plain.insert(line("A", 1, 5)).unwrap();
+        plain.insert(line("A", 1, 6)).unwrap();
+        assert!(db
+            .repository_with_id_fn::<Line, (String, i64)>(|line| (line.order.clone(), line.number))
+            .is_err());
+    }
+
+    #[test]
+    fn test_key_value() {
+        assert_eq!(key_value(&5i64).unwrap(), Value::I64(5));
+        assert_eq!(key_value(&"a".to_string()).unwrap(), Value::String("a".to_string()));
+        assert!(matches!(key_value(&("a".to_string(), 1)).unwrap(), Value::String(_)));
+        assert_ne!(
+            key_value(&("a".to_string(), 1)).unwrap(),
+            key_value(&("a".to_string(), 2)).unwrap()
+        );
+        assert_eq!(key_value(&()).unwrap_err().kind(), &ErrorKind::InvalidId);
+    }
+}
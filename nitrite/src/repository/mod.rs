@@ -26,6 +26,12 @@
 //! // Or create a keyed repository for multiple instances
 //! let repo_prod = db.keyed_repository::<User>("prod")?;
 //! let repo_test = db.keyed_repository::<User>("test")?;
+//!
+//! // Or identify the entities by a closure, for ids `#[entity(id)]` cannot express
+//! let repo = db.repository_with_id_fn::<User, (String, u32)>(|user| (user.name.clone(), user.age))?;
+//!
+//! // Or describe the entity at runtime and work with documents
+//! let repo = db.dynamic_repository(EntityDescriptor::new("User").id(EntityId::new("name", None, None)))?;
 //! ```
 //!
 //! # Operations
@@ -43,9 +49,13 @@ mod cursor;
 mod repository_factory;
 mod repository_operations;
 mod default_object_repository;
+mod id_fn_repository;
+mod dynamic_repository;
 
 pub use cursor::*;
+pub use dynamic_repository::{DynamicRepository, EntityDescriptor};
 pub use entity::*;
+pub use id_fn_repository::IdFnRepository;
 pub use repository::*;
 pub(crate) use repository_factory::*;
 pub(crate) use repository_operations::*;
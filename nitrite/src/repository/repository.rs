@@ -1,14 +1,17 @@
 use crate::collection::operation::WriteResult;
-use crate::collection::{CollectionEventListener, Document, FindOptions, NitriteCollection, NitriteId, UpdateOptions};
+use crate::collection::{
+    check_unique_index, conflict_fields, upsert_documents, CollectionEventListener, ConflictTarget, Document,
+    FindOptions, NitriteCollection, NitriteId, UpdateOptions, UpsertManyOptions, UpsertManyResult,
+};
 use crate::common::{
     AttributeAware, Attributes, Convertible, EventAware, PersistentCollection, Processor,
-    SubscriberRef,
+    SubscriberRef, Value,
 };
-use crate::errors::NitriteResult;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::filter::Filter;
 use crate::index::{IndexDescriptor, IndexOptions};
 use crate::repository::cursor::ObjectCursor;
-use crate::repository::NitriteEntity;
+use crate::repository::{EntityId, NitriteEntity};
 use crate::store::NitriteStore;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -195,6 +198,70 @@ where
     /// ```
     fn update_one(&self, object: T, insert_if_absent: bool) -> NitriteResult<WriteResult>;
 
+    /// Inserts the entities no stored entity conflicts with and updates the others, in one
+    /// atomic write (convenience method).
+    ///
+    /// This calls `upsert_many_with_options` with the default `UpsertManyOptions`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let repository: ObjectRepository<Book> = db.repository()?;
+    /// let result = repository.upsert_many(feed, ConflictTarget::index("isbn"))?;
+    /// ```
+    fn upsert_many(&self, objects: Vec<T>, target: ConflictTarget) -> NitriteResult<UpsertManyResult> {
+        self.upsert_many_with_options(objects, target, &UpsertManyOptions::default())
+    }
+
+    /// Inserts the entities no stored entity conflicts with and updates the others, with the
+    /// specified options.
+    ///
+    /// # Behavior
+    ///
+    /// - `ConflictTarget::Id` matches on the entity id, `ConflictTarget::Index` on a field
+    ///   with a unique index, such as an identifier of another system
+    /// - Stored entities are looked up a batch of keys per query instead of once per entity
+    /// - Matching entities are updated like `update_one`, the others are inserted
+    /// - All writes are atomic; either all succeed or all fail
+    ///
+    /// See [`NitriteCollectionProvider::upsert_many_with_options`] for the details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the target has no unique index, if an entity has no value for
+    /// it, or for `ConflictTarget::Id` if the entity has no id or a `NitriteId` id.
+    fn upsert_many_with_options(
+        &self,
+        objects: Vec<T>,
+        target: ConflictTarget,
+        options: &UpsertManyOptions,
+    ) -> NitriteResult<UpsertManyResult> {
+        let collection = self.document_collection();
+        let key_fields = match &target {
+            ConflictTarget::Id => {
+                let key_fields = entity_id_fields(T::default().entity_id())?;
+                check_unique_index(&**collection, &key_fields)?;
+                key_fields
+            }
+            ConflictTarget::Index(_) => conflict_fields(&**collection, &target)?,
+        };
+
+        let mut documents = Vec::with_capacity(objects.len());
+        for object in &objects {
+            match object.to_value()? {
+                Value::Document(document) => documents.push(document),
+                other => {
+                    log::error!("Expected Document from entity Convertible, got {:?}", other);
+                    return Err(NitriteError::new(
+                        "Cannot upsert: Expected Document from Convertible",
+                        ErrorKind::ObjectMappingError,
+                    ));
+                }
+            }
+        }
+        upsert_documents(&**collection, documents, &key_fields, options)
+    }
+
     /// Updates documents at the raw document level matching a filter.
     ///
     /// # Arguments
@@ -442,6 +509,31 @@ where
     fn document_collection(&self) -> NitriteCollection;
 }
 
+/// Returns the fields an upsert on the id of an entity matches on.
+pub(crate) fn entity_id_fields(entity_id: Option<EntityId>) -> NitriteResult<Vec<String>> {
+    match entity_id {
+        Some(entity_id) if entity_id.is_embedded() => Ok(entity_id.encoded_field_names()),
+        Some(entity_id) if !entity_id.is_nitrite_id() => Ok(vec![entity_id.field_name().to_string()]),
+        Some(entity_id) => {
+            log::error!("Cannot upsert on the NitriteId field '{}'", entity_id.field_name());
+            Err(NitriteError::new(
+                &format!(
+                    "Cannot upsert on the NitriteId field '{}': generated ids do not identify incoming entities",
+                    entity_id.field_name()
+                ),
+                ErrorKind::InvalidOperation,
+            ))
+        }
+        None => {
+            log::error!("Cannot upsert on the id: entity id is not defined");
+            Err(NitriteError::new(
+                "Cannot upsert on the id: Entity ID is not defined. Use ConflictTarget::Index or define an id field",
+                ErrorKind::NotIdentifiable,
+            ))
+        }
+    }
+}
+
 /// A typed facade for repository operations on a specific entity type.
 ///
 /// # Purpose
@@ -486,7 +578,6 @@ where
 ///     // Process employee
 /// }
 /// ```
-#[derive(Clone)]
 pub struct ObjectRepository<T>
 where
     T: Convertible + NitriteEntity
@@ -494,6 +585,16 @@ where
     inner: Arc<dyn ObjectRepositoryProvider<T>>,
 }
 
+// not derived, the entity type itself does not have to be Clone
+impl<T> Clone for ObjectRepository<T>
+where
+    T: Convertible + NitriteEntity
+{
+    fn clone(&self) -> Self {
+        ObjectRepository { inner: self.inner.clone() }
+    }
+}
+
 impl<T> ObjectRepository<T>
 where
     T: Convertible<Output = T> + NitriteEntity + Send + Sync
@@ -1,9 +1,11 @@
 use crate::collection::{self, CollectionFactory, NitriteCollection};
-use crate::common::{atomic, repository_name_by_type, Atomic, Convertible};
+use crate::common::{atomic, repository_name, repository_name_by_type, Atomic, Convertible};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::nitrite::Nitrite;
 use crate::nitrite_config::NitriteConfig;
 use crate::repository::default_object_repository::DefaultObjectRepository;
+use crate::repository::dynamic_repository::{DynamicRepository, EntityDescriptor};
+use crate::repository::id_fn_repository::{IdFn, IdFnRepository};
 use crate::repository::repository::ObjectRepository;
 use crate::repository::repository_operations::RepositoryOperations;
 use crate::repository::NitriteEntity;
@@ -33,7 +35,35 @@ impl RepositoryFactory {
     where
         T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
     {
-        self.inner.get_repository(key, nitrite_config)
+        let repository = self.inner.get_repository(key, nitrite_config)?;
+        Ok(ObjectRepository::new(repository))
+    }
+
+    /// Gets a repository whose entities are identified by `id_fn`.
+    pub(crate) fn get_repository_with_id_fn<T, K>(
+        &self,
+        key: Option<&str>,
+        nitrite_config: NitriteConfig,
+        id_fn: IdFn<T, K>,
+    ) -> NitriteResult<IdFnRepository<T, K>>
+    where
+        T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
+        K: Convertible + Send + Sync + 'static,
+    {
+        let repository = self.inner.get_repository(key, nitrite_config)?;
+        IdFnRepository::new(repository, id_fn)
+    }
+
+    /// Gets a repository of the entities described by `descriptor`.
+    pub(crate) fn get_dynamic_repository(
+        &self,
+        descriptor: EntityDescriptor,
+        key: Option<&str>,
+        nitrite_config: NitriteConfig,
+    ) -> NitriteResult<DynamicRepository> {
+        let (collection, operations) =
+            self.inner.get_dynamic_repository(&descriptor, key, nitrite_config)?;
+        Ok(DynamicRepository::new(descriptor, collection, operations))
     }
 
     pub(crate) fn create_repository<T>(
@@ -44,13 +74,19 @@ impl RepositoryFactory {
     where
         T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
     {
-        self.inner.create_repository(key, nitrite_config)
+        let repository = self.inner.create_repository(key, nitrite_config)?;
+        Ok(ObjectRepository::new(repository))
     }
 
     pub(crate) fn destroy_repository<T: NitriteEntity>(&self, key: Option<&str>) -> NitriteResult<()> {
         self.inner.destroy_repository::<T>(key)
     }
 
+    /// Destroys a repository by its stored name, for callers without its entity type.
+    pub(crate) fn destroy_repository_by_name(&self, name: &str) -> NitriteResult<()> {
+        self.inner.destroy_repository_by_name(name)
+    }
+
     pub(crate) fn clear(&self) -> NitriteResult<()> {
         self.inner.clear()
     }
@@ -78,7 +114,7 @@ impl RepositoryFactoryInner {
         Ok(self.repository_operations.read().contains_key(&*name))
     }
 
-    fn get_repository<T>(&self, key: Option<&str>, nitrite_config: NitriteConfig) -> NitriteResult<ObjectRepository<T>>
+    fn get_repository<T>(&self, key: Option<&str>, nitrite_config: NitriteConfig) -> NitriteResult<DefaultObjectRepository<T>>
     where
         T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
     {
@@ -97,8 +133,9 @@ impl RepositoryFactoryInner {
             
             let operations_opt = self.repository_operations.read().get(&*name).cloned();
             if let Some(operations) = operations_opt {
-                let repository = DefaultObjectRepository::new(collection, operations);
-                Ok(ObjectRepository::new(repository))
+                // another entity type may share the name of the cached repository
+                operations.check_schema::<T>(&collection)?;
+                Ok(DefaultObjectRepository::new(collection, operations))
             } else {
                 log::error!("No repository operation found for name {}. Reinitialize the database", name);
                 Err(NitriteError::new(
@@ -115,7 +152,7 @@ impl RepositoryFactoryInner {
         &self,
         key: Option<&str>,
         nitrite_config: NitriteConfig
-    ) -> NitriteResult<ObjectRepository<T>>
+    ) -> NitriteResult<DefaultObjectRepository<T>>
     where
         T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
     {
@@ -138,14 +175,72 @@ impl RepositoryFactoryInner {
 
         self.repository_operations.write().insert(name.clone(), operations);
         self.collection_registry.write().insert(name.clone(), collection);
-        Ok(ObjectRepository::new(repository))
+        Ok(repository)
+    }
+
+    fn get_dynamic_repository(
+        &self,
+        descriptor: &EntityDescriptor,
+        key: Option<&str>,
+        nitrite_config: NitriteConfig,
+    ) -> NitriteResult<(NitriteCollection, RepositoryOperations)> {
+        let name = repository_name(descriptor.name(), key)?;
+
+        let _guard = self.lock.lock();
+        let collection_opt = self.collection_registry.read().get(&*name).cloned();
+        if let Some(collection) = collection_opt {
+            if !collection.is_dropped()? && collection.is_open()? {
+                let operations_opt = self.repository_operations.read().get(&*name).cloned();
+                return match operations_opt {
+                    // a typed repository may have opened the collection with another id
+                    Some(operations) if operations.entity_id().as_ref() != descriptor.entity_id() => {
+                        log::error!("Repository {} is open with a different entity id", name);
+                        Err(NitriteError::new(
+                            &format!("Repository {} is open with a different entity id", name),
+                            ErrorKind::InvalidOperation,
+                        ))
+                    }
+                    Some(operations) => Ok((collection, operations)),
+                    None => {
+                        log::error!("No repository operation found for name {}. Reinitialize the database", name);
+                        Err(NitriteError::new(
+                            "Database is in invalid state. Reinitialize the database",
+                            ErrorKind::InvalidOperation,
+                        ))
+                    }
+                };
+            }
+            self.collection_registry.write().remove(&*name);
+            self.repository_operations.write().remove(&*name);
+        }
+
+        let store = nitrite_config.nitrite_store()?;
+        if store.get_collection_names()?.contains(&name) {
+            return Err(NitriteError::new(
+                &format!("A collection with same name '{}' already exists", name),
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        let collection = self.collection_factory.get_collection(&name, nitrite_config, false)?;
+        let operations = RepositoryOperations::new();
+        operations.initialize_descriptor(&collection, descriptor)?;
+        self.write_catalog(store, name.clone(), key)?;
+
+        self.repository_operations.write().insert(name.clone(), operations.clone());
+        self.collection_registry.write().insert(name, collection.clone());
+        Ok((collection, operations))
     }
 
     fn destroy_repository<T: NitriteEntity>(&self, key: Option<&str>) -> NitriteResult<()> {
         let name = repository_name_by_type::<T>(key)?;
-        self.collection_factory.destroy_collection(&name)?;
-        self.repository_operations.write().remove(&*name);
-        self.collection_registry.write().remove(&*name);
+        self.destroy_repository_by_name(&name)
+    }
+
+    fn destroy_repository_by_name(&self, name: &str) -> NitriteResult<()> {
+        self.collection_factory.destroy_collection(name)?;
+        self.repository_operations.write().remove(name);
+        self.collection_registry.write().remove(name);
         Ok(())
     }
 
@@ -1,9 +1,12 @@
 use crate::collection::{Document, NitriteCollection};
-use crate::common::{Convertible, PersistentCollection, Value, DOC_ID, UNIQUE_INDEX};
+use crate::common::{
+    AttributeAware, Attributes, Convertible, PersistentCollection, Value, DOC_ID,
+    ENTITY_SCHEMA_FIELDS, ENTITY_SCHEMA_FINGERPRINT, ENTITY_SCHEMA_VERSION, UNIQUE_INDEX,
+};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::filter::Filter;
 use crate::index::IndexOptions;
-use crate::repository::{EntityId, NitriteEntity};
+use crate::repository::{EntityDescriptor, EntityId, EntityIndex, EntitySchema, NitriteEntity};
 use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::sync::{Arc, OnceLock};
@@ -29,7 +32,29 @@ impl RepositoryOperations {
     {
         self.inner.initialize::<T>(collection)
     }
+
+    /// Creates the id index and the indexes of an entity described at runtime.
+    pub(crate) fn initialize_descriptor(
+        &self,
+        collection: &NitriteCollection,
+        descriptor: &EntityDescriptor,
+    ) -> NitriteResult<()> {
+        self.inner.create_id_index(collection, descriptor.entity_id().cloned())?;
+        self.inner.create_indexes(collection, Some(descriptor.indexes().to_vec()))
+    }
+
+    /// Returns the id of the entities, once the repository is initialized.
+    pub(crate) fn entity_id(&self) -> Option<EntityId> {
+        self.inner.entity_id.get().cloned()
+    }
     
+    pub(crate) fn check_schema<T>(&self, collection: &NitriteCollection) -> NitriteResult<()>
+    where
+        T: Convertible<Output = T> + NitriteEntity,
+    {
+        self.inner.check_schema::<T>(collection)
+    }
+
     pub(crate) fn to_documents<T>(&self, entities: Vec<&T>) -> NitriteResult<Vec<Document>>
     where
         T: Convertible<Output = T> + NitriteEntity,
@@ -44,6 +69,11 @@ impl RepositoryOperations {
         self.inner.to_document(entity, update)
     }
     
+    /// Checks the id of an entity document and fills a `NitriteId` id, like `to_document`.
+    pub(crate) fn prepare_document(&self, document: Document, update: bool) -> NitriteResult<Document> {
+        self.inner.prepare_document(document, self.inner.entity_id.get(), update)
+    }
+
     pub(crate) fn remove_nitrite_id(&self, document: &mut Document) -> NitriteResult<()> {
         self.inner.remove_nitrite_id(document)
     }
@@ -54,6 +84,11 @@ impl RepositoryOperations {
     {
         self.inner.create_unique_filter(entity)
     }
+
+    /// Creates the filter matching the entity with the id of `document`.
+    pub(crate) fn create_document_filter(&self, document: &Document) -> NitriteResult<Filter> {
+        self.inner.create_document_filter(document)
+    }
     
     pub(crate) fn create_id_filter<Id>(&self, id: Id) -> NitriteResult<Filter>
     where
@@ -72,11 +107,68 @@ impl RepositoryOperationsInner {
     where
         T: Convertible<Output = T> + NitriteEntity,
     {
-        self.create_id_index::<T>(&collection)?;
-        self.create_indexes::<T>(&collection)?;
+        self.check_schema::<T>(&collection)?;
+        let default_entity = T::default();
+        self.create_id_index(&collection, default_entity.entity_id())?;
+        self.create_indexes(&collection, default_entity.entity_indexes())?;
         Ok(())
     }
-    
+
+    fn check_schema<T>(&self, collection: &NitriteCollection) -> NitriteResult<()>
+    where
+        T: Convertible<Output = T> + NitriteEntity,
+    {
+        let default_entity = T::default();
+        let schema = match default_entity.entity_schema() {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+
+        let attributes = collection.get_attributes()?;
+        // a schema recorded before versioning existed belongs to version 1
+        let stored_version = match attributes.get(ENTITY_SCHEMA_VERSION) {
+            Some(Value::U32(version)) => *version,
+            _ => 1,
+        };
+        match attributes.get(ENTITY_SCHEMA_FINGERPRINT) {
+            Some(Value::U64(fingerprint)) if *fingerprint == schema.fingerprint() => Ok(()),
+            // a newer version upgrades documents of the old shape when they are read
+            Some(Value::U64(_)) if schema.version() > stored_version => {
+                self.record_schema(collection, attributes, &schema)
+            }
+            Some(Value::U64(_)) => {
+                let previous = EntitySchema::from_value(attributes.get(ENTITY_SCHEMA_FIELDS));
+                let changed = schema.changed_fields(&previous);
+                log::error!(
+                    "Schema of entity {} changed since its repository was created (fields: {}), a migration is required",
+                    default_entity.entity_name(),
+                    changed.join(", ")
+                );
+                Err(NitriteError::new(
+                    &format!(
+                        "Schema of entity {} changed since its repository was created (fields: {}), a migration is required",
+                        default_entity.entity_name(),
+                        changed.join(", ")
+                    ),
+                    ErrorKind::SchemaChanged(changed),
+                ))
+            }
+            _ => self.record_schema(collection, attributes, &schema),
+        }
+    }
+
+    fn record_schema(
+        &self,
+        collection: &NitriteCollection,
+        mut attributes: Attributes,
+        schema: &EntitySchema,
+    ) -> NitriteResult<()> {
+        attributes.put(ENTITY_SCHEMA_FINGERPRINT, Value::U64(schema.fingerprint()));
+        attributes.put(ENTITY_SCHEMA_FIELDS, schema.to_value());
+        attributes.put(ENTITY_SCHEMA_VERSION, Value::U32(schema.version()));
+        collection.set_attributes(attributes)
+    }
+
     fn to_documents<T>(&self, entities: Vec<&T>) -> NitriteResult<Vec<Document>>
     where
         T: Convertible<Output = T> + NitriteEntity,
@@ -98,7 +190,7 @@ impl RepositoryOperationsInner {
         
         // Validate that entity.to_value() returns a Document type
         // This protects against malformed Convertible implementations
-        let mut document = match value {
+        let document = match value {
             Value::Document(doc) => doc,
             other => {
                 log::error!("Expected Document from entity Convertible, got {:?}", other);
@@ -108,7 +200,15 @@ impl RepositoryOperationsInner {
                 ));
             }
         };
-        
+        self.prepare_document(document, entity_id.as_ref(), update)
+    }
+
+    fn prepare_document(
+        &self,
+        mut document: Document,
+        entity_id: Option<&EntityId>,
+        update: bool,
+    ) -> NitriteResult<Document> {
         if let Some(entity_id) = entity_id {
             let id_value = document.get(entity_id.field_name())?;
             if entity_id.is_nitrite_id() {
@@ -153,7 +253,7 @@ impl RepositoryOperationsInner {
     where
         T: Convertible<Output = T> + NitriteEntity,
     {
-        if let Some(entity_id) = self.entity_id.get() {
+        if self.entity_id.get().is_some() {
             let value = entity.to_value()?;
             
             // Validate that entity.to_value() returns a Document type
@@ -168,6 +268,18 @@ impl RepositoryOperationsInner {
                 }
             };
             
+            self.create_document_filter(&document)
+        } else {
+            log::error!("Failed to create unique filter: entity id is not defined");
+            Err(NitriteError::new(
+                "Cannot create unique filter: Entity ID is not defined. Ensure the entity class has a @Id field or use @Embedded ID",
+                ErrorKind::NotIdentifiable
+            ))
+        }
+    }
+    
+    fn create_document_filter(&self, document: &Document) -> NitriteResult<Filter> {
+        if let Some(entity_id) = self.entity_id.get() {
             let id_value = document.get(entity_id.field_name())?;
             entity_id.create_unique_filter(id_value)
         } else {
@@ -178,7 +290,7 @@ impl RepositoryOperationsInner {
             ))
         }
     }
-    
+
     fn create_id_filter<Id>(&self, id: Id) -> NitriteResult<Filter>
     where
         Id: Convertible,
@@ -194,12 +306,11 @@ impl RepositoryOperationsInner {
         }
     }
     
-    fn create_id_index<T>(&self, collection: &NitriteCollection) -> NitriteResult<()>
-    where
-        T: Convertible<Output = T> + NitriteEntity,
-    {
-        let default_entity = T::default();
-        let entity_id = default_entity.entity_id();
+    fn create_id_index(
+        &self,
+        collection: &NitriteCollection,
+        entity_id: Option<EntityId>,
+    ) -> NitriteResult<()> {
         if let Some(entity_id) = entity_id {
             self.entity_id.get_or_init(|| entity_id.clone());
             
@@ -215,12 +326,12 @@ impl RepositoryOperationsInner {
         Ok(())
     }
     
-    fn create_indexes<T>(&self, collection: &NitriteCollection) -> NitriteResult<()>
-    where
-        T: Convertible<Output = T> + NitriteEntity,
-    {
-        let default_entity = T::default();
-        if let Some(entity_indexes) = default_entity.entity_indexes() {
+    fn create_indexes(
+        &self,
+        collection: &NitriteCollection,
+        entity_indexes: Option<Vec<EntityIndex>>,
+    ) -> NitriteResult<()> {
+        if let Some(entity_indexes) = entity_indexes {
             for entity_index in entity_indexes {
                 let field_names = entity_index.field_names();
                 let field_names: Vec<&str> = field_names.iter().map(|s| s.as_str()).collect();
@@ -0,0 +1,1111 @@
+use crate::{
+    collection::{
+        operation::WriteResult, BulkOperation, BulkWriteOptions, BulkWriteResult,
+        CollectionEventListener, CollectionOptions, Document, DocumentVersion, FindOptions,
+        HistoryOptions, NitriteCollection, NitriteCollectionProvider, NitriteId, RedactionPolicy,
+        UpdateEachOptions, UpdateEachResult, UpdateOptions, WriteTokenHolder,
+    },
+    common::{
+        AttributeAware, Attributes, EventAware, PersistentCollection, Processor, ProcessorChain,
+        stable_hash_64, SortableFields, SubscriberRef, Value,
+    },
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::{is_equals_filter, Filter},
+    index::{IndexDescriptor, IndexOptions, IndexStatistics},
+    nitrite_config::NitriteConfig,
+    sorted_stream::SortedStream,
+    store::NitriteStore,
+    DocumentCursor, DOC_ID, INTERNAL_NAME_SEPARATOR, SHARD_BOUNDARIES, SHARD_COUNT, SHARD_KEY,
+    SHARD_PREFIX,
+};
+use basu::HandlerId;
+use icu_collator::options::CollatorOptions;
+use icu_collator::Collator;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// How the documents of a sharded collection are spread over its shards.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShardStrategy {
+    /// Documents go to the shard picked by a stable hash of their shard key.
+    Hash {
+        /// The number of shards.
+        shards: usize,
+    },
+    /// Documents go to the shard of the range their shard key falls in. Shard `0` holds
+    /// the keys below the first boundary, shard `i` the keys from boundary `i - 1` up to
+    /// boundary `i`, and the last shard the keys from the last boundary on.
+    Range {
+        /// The ascending boundaries between the shards.
+        boundaries: Vec<Value>,
+    },
+}
+
+/// The layout of a sharded collection: the field its documents are routed by and how.
+///
+/// The layout is stored with the shards; opening the collection again with another
+/// layout is an error, as documents are never moved between shards.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::shard::ShardOptions;
+///
+/// // eight shards picked by the hash of the customer
+/// let by_customer = ShardOptions::hash("customer", 8);
+///
+/// // three shards: before 2020, 2020 to 2023, from 2024 on
+/// let by_year = ShardOptions::range("year", vec![Value::from(2020), Value::from(2024)]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardOptions {
+    key: String,
+    strategy: ShardStrategy,
+}
+
+impl ShardOptions {
+    /// Spreads the documents over `shards` shards by the hash of the field `key`.
+    pub fn hash(key: &str, shards: usize) -> Self {
+        ShardOptions {
+            key: key.to_string(),
+            strategy: ShardStrategy::Hash { shards },
+        }
+    }
+
+    /// Spreads the documents over `boundaries.len() + 1` shards by the range the field
+    /// `key` falls in. The boundaries must be ascending.
+    pub fn range(key: &str, boundaries: Vec<Value>) -> Self {
+        ShardOptions {
+            key: key.to_string(),
+            strategy: ShardStrategy::Range { boundaries },
+        }
+    }
+
+    /// Returns the field the documents are routed by.
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns how the documents are spread over the shards.
+    pub fn get_strategy(&self) -> &ShardStrategy {
+        &self.strategy
+    }
+
+    /// Returns the number of shards.
+    pub fn shard_count(&self) -> usize {
+        match &self.strategy {
+            ShardStrategy::Hash { shards } => *shards,
+            ShardStrategy::Range { boundaries } => boundaries.len() + 1,
+        }
+    }
+
+    /// Returns the shard holding the documents whose shard key is `value`. A document
+    /// without the shard key is routed as if it were `null`, which falls in the first
+    /// range.
+    pub fn shard_of(&self, value: &Value) -> usize {
+        match &self.strategy {
+            ShardStrategy::Hash { shards } => (stable_hash_64(value) % (*shards).max(1) as u64) as usize,
+            // nulls come first, as they do in sorted results
+            ShardStrategy::Range { .. } if value.is_null() => 0,
+            ShardStrategy::Range { boundaries } => {
+                boundaries.partition_point(|boundary| boundary <= value)
+            }
+        }
+    }
+
+    fn validate(&self) -> NitriteResult<()> {
+        if self.key.is_empty() || self.key == DOC_ID {
+            log::error!("Invalid shard key '{}'", self.key);
+            return Err(NitriteError::new(
+                &format!("Invalid shard key '{}'", self.key),
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        match &self.strategy {
+            ShardStrategy::Hash { shards } if *shards == 0 => {
+                log::error!("A sharded collection needs at least one shard");
+                Err(NitriteError::new(
+                    "A sharded collection needs at least one shard",
+                    ErrorKind::ValidationError,
+                ))
+            }
+            ShardStrategy::Range { boundaries }
+                if boundaries.windows(2).any(|pair| pair[0] >= pair[1]) =>
+            {
+                log::error!("Shard boundaries must be strictly ascending");
+                Err(NitriteError::new(
+                    "Shard boundaries must be strictly ascending",
+                    ErrorKind::ValidationError,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the layout stored in the attributes of a shard, if any.
+    fn from_attributes(attributes: &Attributes) -> Option<ShardOptions> {
+        let key = attributes.get(SHARD_KEY)?.as_string()?.clone();
+        let strategy = match attributes.get(SHARD_BOUNDARIES) {
+            Some(Value::Array(boundaries)) => ShardStrategy::Range {
+                boundaries: boundaries.clone(),
+            },
+            _ => ShardStrategy::Hash {
+                shards: usize::try_from(*attributes.get(SHARD_COUNT)?.as_i64()?).ok()?,
+            },
+        };
+        Some(ShardOptions { key, strategy })
+    }
+
+    fn write_to(&self, attributes: &mut Attributes) {
+        attributes.put(SHARD_KEY, Value::from(self.key.clone()));
+        attributes.put(SHARD_COUNT, Value::I64(self.shard_count() as i64));
+        match &self.strategy {
+            ShardStrategy::Hash { .. } => {
+                attributes.remove(SHARD_BOUNDARIES);
+            }
+            ShardStrategy::Range { boundaries } => {
+                attributes.put(SHARD_BOUNDARIES, Value::Array(boundaries.clone()));
+            }
+        }
+    }
+}
+
+/// Returns the name of the collection holding the shard `index` of the sharded
+/// collection `name`.
+pub(crate) fn shard_collection_name(name: &str, index: usize) -> String {
+    format!(
+        "{}{}{}{}{}",
+        SHARD_PREFIX, INTERNAL_NAME_SEPARATOR, name, INTERNAL_NAME_SEPARATOR, index
+    )
+}
+
+/// Removes the layout from the attributes of a shard about to be dropped.
+pub(crate) fn forget_layout(shard: &NitriteCollection) -> NitriteResult<()> {
+    let mut attributes = shard.get_attributes()?;
+    let mut changed = false;
+    for key in [SHARD_KEY, SHARD_COUNT, SHARD_BOUNDARIES] {
+        changed |= attributes.remove(key).is_some();
+    }
+    if changed {
+        shard.set_attributes(attributes)?;
+    }
+    Ok(())
+}
+
+/// A collection partitioned over several shards, each an ordinary collection of its own.
+///
+/// A sharded collection is opened with
+/// [`Nitrite::sharded_collection`](crate::nitrite::Nitrite::sharded_collection) and used
+/// like any [`NitriteCollection`], which it dereferences to:
+///
+/// * inserts are routed to one shard by the shard key of the document;
+/// * a filter `field(key).eq(value)` on the shard key reads or writes only the shard of
+///   `value`; other filters run on every shard, and the results of a find are merged,
+///   sorted and paged as one;
+/// * an update may not move a document to another shard by changing its shard key;
+/// * a bulk write must stay within one shard, which keeps it atomic.
+///
+/// Indexes are created on every shard, so a unique index is only unique within a shard
+/// unless it includes the shard key. The shards themselves are available through
+/// [`shard`](Self::shard), to rebuild the indexes, purge the deleted documents or sweep the
+/// expired fields of one shard at a time.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let events = db.sharded_collection("events", ShardOptions::hash("device", 4))?;
+/// events.insert(doc! { device: "sensor-7", reading: 21.5 })?;
+///
+/// // only the shard of "sensor-7" is read
+/// let readings = events.find(field("device").eq("sensor-7"))?;
+///
+/// // maintenance, one shard at a time
+/// for shard in events.shards() {
+///     shard.rebuild_index(vec!["reading"])?;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ShardedCollection {
+    inner: Arc<ShardedCollectionInner>,
+    collection: NitriteCollection,
+}
+
+impl ShardedCollection {
+    pub(crate) fn open(
+        name: &str,
+        options: ShardOptions,
+        shards: Vec<NitriteCollection>,
+        config: NitriteConfig,
+    ) -> NitriteResult<ShardedCollection> {
+        options.validate()?;
+        for shard in &shards {
+            let mut attributes = shard.get_attributes()?;
+            match ShardOptions::from_attributes(&attributes) {
+                Some(stored) if stored != options => {
+                    log::error!(
+                        "Sharded collection {} is laid out as {:?}, not {:?}",
+                        name,
+                        stored,
+                        options
+                    );
+                    return Err(NitriteError::new(
+                        &format!("Sharded collection {} is laid out as {:?}", name, stored),
+                        ErrorKind::ValidationError,
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    options.write_to(&mut attributes);
+                    shard.set_attributes(attributes)?;
+                }
+            }
+        }
+
+        let inner = Arc::new(ShardedCollectionInner {
+            name: name.to_string(),
+            options,
+            shards,
+            config,
+            subscriptions: Mutex::new(HashMap::new()),
+        });
+        Ok(ShardedCollection {
+            collection: NitriteCollection::new(ShardedProvider {
+                inner: inner.clone(),
+            }),
+            inner,
+        })
+    }
+
+    /// Returns the name of the collection.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the layout of the collection.
+    pub fn shard_options(&self) -> &ShardOptions {
+        &self.inner.options
+    }
+
+    /// Returns the shard `index`, or `None` if there is no such shard.
+    pub fn shard(&self, index: usize) -> Option<&NitriteCollection> {
+        self.inner.shards.get(index)
+    }
+
+    /// Returns the shards, in order.
+    pub fn shards(&self) -> &[NitriteCollection] {
+        &self.inner.shards
+    }
+
+    /// Returns the index of the shard `document` is routed to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shard key of the document is an array.
+    pub fn shard_of(&self, document: &Document) -> NitriteResult<usize> {
+        self.inner.route(document)
+    }
+
+    /// Returns the facade of the collection.
+    pub fn collection(&self) -> &NitriteCollection {
+        &self.collection
+    }
+}
+
+impl Deref for ShardedCollection {
+    type Target = NitriteCollection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.collection
+    }
+}
+
+struct ShardedCollectionInner {
+    name: String,
+    options: ShardOptions,
+    shards: Vec<NitriteCollection>,
+    config: NitriteConfig,
+    /// The subscriptions to the shards of each listener, by the subscription returned for it.
+    subscriptions: Mutex<HashMap<HandlerId, Vec<(usize, SubscriberRef)>>>,
+}
+
+impl ShardedCollectionInner {
+    fn route(&self, document: &Document) -> NitriteResult<usize> {
+        let key = document.get(&self.options.key)?;
+        if matches!(key, Value::Array(_)) {
+            log::error!("Shard key {} of a document cannot be an array", self.options.key);
+            return Err(NitriteError::new(
+                &format!("Shard key {} of a document cannot be an array", self.options.key),
+                ErrorKind::ValidationError,
+            ));
+        }
+        Ok(self.options.shard_of(&key))
+    }
+
+    /// Returns the shards that may hold the documents matching `filter`.
+    fn targets(&self, filter: &Filter) -> NitriteResult<Vec<usize>> {
+        if is_equals_filter(filter) && filter.get_field_name()? == self.options.key {
+            if let Some(value) = filter.get_field_value()? {
+                return Ok(vec![self.options.shard_of(&value)]);
+            }
+        }
+        Ok((0..self.shards.len()).collect())
+    }
+
+    /// Returns the shard holding the document `id`.
+    fn locate(&self, id: &NitriteId) -> NitriteResult<Option<usize>> {
+        for (index, shard) in self.shards.iter().enumerate() {
+            if shard.get_by_id(id)?.is_some() {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fails if `update` sets a shard key that belongs to another shard than `shard`.
+    fn check_stays(&self, shard: usize, update: &Document) -> NitriteResult<()> {
+        if update.contains_field(&self.options.key) && self.route(update)? != shard {
+            log::error!(
+                "Cannot move a document of {} to another shard by changing its shard key {}",
+                self.name,
+                self.options.key
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Cannot move a document of {} to another shard by changing its shard key {}",
+                    self.name, self.options.key
+                ),
+                ErrorKind::ValidationError,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails if a shard other than `shard` already holds the id of `document`.
+    fn check_id_free(&self, shard: usize, document: &Document) -> NitriteResult<()> {
+        if let Value::NitriteId(id) = document.get(DOC_ID)? {
+            let taken = self.shards.iter().enumerate().try_fold(false, |taken, (index, other)| {
+                Ok::<_, NitriteError>(taken || (index != shard && other.get_by_id(&id)?.is_some()))
+            })?;
+            if taken {
+                log::error!("Document already exists with id {}", id);
+                return Err(NitriteError::new(
+                    &format!("Document already exists with id {}", id),
+                    ErrorKind::UniqueConstraintViolation,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn bulk_target(&self, operation: &BulkOperation) -> NitriteResult<usize> {
+        let (filter, update) = match operation {
+            BulkOperation::InsertOne(document) => return self.route(document),
+            BulkOperation::UpdateOne { filter, update, .. }
+            | BulkOperation::UpdateMany { filter, update, .. } => (filter, Some(update)),
+            BulkOperation::ReplaceOne {
+                filter,
+                replacement,
+                ..
+            } => (filter, Some(replacement)),
+            BulkOperation::DeleteOne(filter) | BulkOperation::DeleteMany(filter) => (filter, None),
+        };
+
+        match self.targets(filter)?.as_slice() {
+            [shard] => {
+                if let Some(update) = update {
+                    self.check_stays(*shard, update)?;
+                }
+                Ok(*shard)
+            }
+            _ => Err(single_shard_error(&self.name, operation.name())),
+        }
+    }
+}
+
+fn single_shard_error(name: &str, operation: &str) -> NitriteError {
+    log::error!(
+        "{} of a bulk write on {} must filter on the shard key",
+        operation,
+        name
+    );
+    NitriteError::new(
+        &format!("{} of a bulk write on {} must filter on the shard key", operation, name),
+        ErrorKind::InvalidOperation,
+    )
+}
+
+/// Merges the results of a write on several shards.
+fn merge_results(results: Vec<WriteResult>) -> WriteResult {
+    let token = results.iter().map(WriteResult::write_token).max().unwrap_or_default();
+    let ids = results
+        .into_iter()
+        .flat_map(|result| result.affected_nitrite_ids().clone())
+        .collect();
+    let mut merged = WriteResult::new(ids);
+    merged.set_write_token(token);
+    merged
+}
+
+/// Options for the find run on each shard of a find on several shards: the shards return
+/// enough documents for the merged page, which is sorted, paged and projected afterwards.
+fn shard_find_options(options: &FindOptions) -> NitriteResult<FindOptions> {
+    let limit = options
+        .limit
+        .map(|limit| limit.saturating_add(options.skip.unwrap_or(0)));
+    // sorting each shard only pays off when it trims what the shard returns
+    let sort_by = match (&options.sort_by, limit) {
+        (Some(sort_by), Some(_)) => Some(SortableFields::with_names_and_order(
+            sort_by.field_names(),
+            sort_by.sorting_order(),
+        )?),
+        _ => None,
+    };
+
+    Ok(FindOptions {
+        sort_by,
+        distance_sort: None,
+        skip: None,
+        limit,
+        distinct: options.distinct,
+        collator_options: options.collator_options,
+        collator_preferences: options.collator_preferences,
+        hint: options.hint.clone(),
+        after_write: options.after_write,
+        principal: options.principal.clone(),
+        timeout: options.timeout,
+        cancellation_token: options.cancellation_token.clone(),
+        max_memory: options.max_memory,
+        projection: None,
+        tie_break_by_id: options.tie_break_by_id,
+        resume_after: None,
+    })
+}
+
+fn defer_on_shards(
+    shards: &[NitriteCollection],
+    bulk_load: &mut dyn FnMut() -> NitriteResult<()>,
+) -> NitriteResult<()> {
+    match shards.split_first() {
+        Some((shard, rest)) => {
+            shard.defer_index_maintenance(&mut || defer_on_shards(rest, &mut *bulk_load))
+        }
+        None => bulk_load(),
+    }
+}
+
+/// The provider behind the [`NitriteCollection`] facade of a sharded collection.
+struct ShardedProvider {
+    inner: Arc<ShardedCollectionInner>,
+}
+
+impl ShardedProvider {
+    fn shards(&self) -> &[NitriteCollection] {
+        &self.inner.shards
+    }
+
+    fn on_all(&self, f: impl Fn(&NitriteCollection) -> NitriteResult<()>) -> NitriteResult<()> {
+        self.shards().iter().try_for_each(f)
+    }
+
+    fn sum(&self, f: impl Fn(&NitriteCollection) -> NitriteResult<u64>) -> NitriteResult<u64> {
+        self.shards().iter().try_fold(0, |total, shard| Ok(total + f(shard)?))
+    }
+
+    fn first_shard(&self) -> &NitriteCollection {
+        // validated layouts have at least one shard
+        &self.inner.shards[0]
+    }
+}
+
+impl PersistentCollection for ShardedProvider {
+    fn add_processor(&self, processor: Processor) -> NitriteResult<()> {
+        self.on_all(|shard| shard.add_processor(processor.clone()))
+    }
+
+    fn create_index(&self, field_names: Vec<&str>, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.on_all(|shard| shard.create_index(field_names.clone(), index_options))
+    }
+
+    fn rebuild_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.on_all(|shard| shard.rebuild_index(field_names.clone()))
+    }
+
+    fn list_indexes(&self) -> NitriteResult<Vec<IndexDescriptor>> {
+        self.first_shard().list_indexes()
+    }
+
+    fn has_index(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        self.first_shard().has_index(field_names)
+    }
+
+    fn is_indexing(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        for shard in self.shards() {
+            if shard.is_indexing(field_names.clone())? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn drop_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.on_all(|shard| shard.drop_index(field_names.clone()))
+    }
+
+    fn drop_all_indexes(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.drop_all_indexes())
+    }
+
+    fn clear(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.clear())
+    }
+
+    fn dispose(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.dispose())
+    }
+
+    fn is_dropped(&self) -> NitriteResult<bool> {
+        self.first_shard().is_dropped()
+    }
+
+    fn is_open(&self) -> NitriteResult<bool> {
+        for shard in self.shards() {
+            if !shard.is_open()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn size(&self) -> NitriteResult<u64> {
+        self.sum(|shard| shard.size())
+    }
+
+    fn close(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.close())
+    }
+
+    fn store(&self) -> NitriteResult<NitriteStore> {
+        self.first_shard().store()
+    }
+}
+
+impl EventAware for ShardedProvider {
+    fn subscribe(&self, handler: CollectionEventListener) -> NitriteResult<Option<SubscriberRef>> {
+        let mut subscribed = Vec::with_capacity(self.shards().len());
+        for (index, shard) in self.shards().iter().enumerate() {
+            if let Some(subscriber) = shard.subscribe(handler.clone())? {
+                subscribed.push((index, subscriber));
+            }
+        }
+
+        let Some(id) = subscribed.first().map(|(_, subscriber)| subscriber.inner.clone()) else {
+            return Ok(None);
+        };
+        self.inner.subscriptions.lock().insert(id.clone(), subscribed);
+        Ok(Some(SubscriberRef::new(id)))
+    }
+
+    fn unsubscribe(&self, subscriber: SubscriberRef) -> NitriteResult<()> {
+        let subscribed = self.inner.subscriptions.lock().remove(&subscriber.inner);
+        for (index, subscriber) in subscribed.unwrap_or_default() {
+            self.inner.shards[index].unsubscribe(subscriber)?;
+        }
+        Ok(())
+    }
+}
+
+impl AttributeAware for ShardedProvider {
+    fn attributes(&self) -> NitriteResult<Option<Attributes>> {
+        self.first_shard().attributes()
+    }
+
+    fn set_attributes(&self, attributes: Attributes) -> NitriteResult<()> {
+        // the layout is kept whatever the attributes say
+        let mut attributes = attributes;
+        self.inner.options.write_to(&mut attributes);
+        self.on_all(|shard| shard.set_attributes(attributes.clone()))
+    }
+}
+
+impl NitriteCollectionProvider for ShardedProvider {
+    fn insert(&self, document: Document) -> NitriteResult<WriteResult> {
+        let shard = self.inner.route(&document)?;
+        self.inner.check_id_free(shard, &document)?;
+        self.inner.shards[shard].insert(document)
+    }
+
+    fn insert_many(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
+        let mut batches: Vec<Vec<Document>> = vec![Vec::new(); self.shards().len()];
+        for document in documents {
+            let shard = self.inner.route(&document)?;
+            self.inner.check_id_free(shard, &document)?;
+            batches[shard].push(document);
+        }
+
+        let mut results = Vec::new();
+        for (shard, batch) in batches.into_iter().enumerate() {
+            if !batch.is_empty() {
+                results.push(self.inner.shards[shard].insert_many(batch)?);
+            }
+        }
+        Ok(merge_results(results))
+    }
+
+    fn update_with_options(
+        &self,
+        filter: Filter,
+        update: &Document,
+        update_options: &UpdateOptions,
+    ) -> NitriteResult<WriteResult> {
+        let targets = self.inner.targets(&filter)?;
+        if update.contains_field(&self.inner.options.key) {
+            let destination = self.inner.route(update)?;
+            for &shard in &targets {
+                if shard != destination && self.inner.shards[shard].count(filter.clone())? > 0 {
+                    self.inner.check_stays(shard, update)?;
+                }
+            }
+        }
+
+        let just_once = update_options.is_just_once();
+        let no_insert = UpdateOptions::new(false, just_once);
+        let mut results = Vec::new();
+        for shard in targets {
+            let result = self.inner.shards[shard].update_with_options(filter.clone(), update, &no_insert)?;
+            let done = just_once && !result.affected_nitrite_ids().is_empty();
+            results.push(result);
+            if done {
+                break;
+            }
+        }
+
+        let merged = merge_results(results);
+        if merged.affected_nitrite_ids().is_empty() && update_options.is_insert_if_absent() {
+            let shard = self.inner.route(update)?;
+            return self.inner.shards[shard].update_with_options(filter, update, update_options);
+        }
+        Ok(merged)
+    }
+
+    fn update_one(&self, document: &Document, insert_if_absent: bool) -> NitriteResult<WriteResult> {
+        let shard = match document.get(DOC_ID)? {
+            Value::NitriteId(id) => self.inner.locate(&id)?,
+            _ => None,
+        };
+        match shard {
+            Some(shard) => {
+                self.inner.check_stays(shard, document)?;
+                self.inner.shards[shard].update_one(document, insert_if_absent)
+            }
+            None => {
+                let shard = self.inner.route(document)?;
+                self.inner.shards[shard].update_one(document, insert_if_absent)
+            }
+        }
+    }
+
+    fn update_by_id(
+        &self,
+        id: &NitriteId,
+        update: &Document,
+        insert_if_absent: bool,
+    ) -> NitriteResult<WriteResult> {
+        match self.inner.locate(id)? {
+            Some(shard) => {
+                self.inner.check_stays(shard, update)?;
+                self.inner.shards[shard].update_by_id(id, update, insert_if_absent)
+            }
+            None => {
+                let shard = self.inner.route(update)?;
+                self.inner.shards[shard].update_by_id(id, update, insert_if_absent)
+            }
+        }
+    }
+
+    fn increment(&self, id: &NitriteId, field: &str, delta: Value) -> NitriteResult<Value> {
+        if field == self.inner.options.key {
+            log::error!("Cannot increment the shard key {} of {}", field, self.inner.name);
+            return Err(NitriteError::new(
+                &format!("Cannot increment the shard key {} of {}", field, self.inner.name),
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        match self.inner.locate(id)? {
+            Some(shard) => self.inner.shards[shard].increment(id, field, delta),
+            None => {
+                log::error!("Document {} not found in collection {}", id, self.inner.name);
+                Err(NitriteError::new(
+                    &format!("Document {} not found in collection {}", id, self.inner.name),
+                    ErrorKind::NotFound,
+                ))
+            }
+        }
+    }
+
+    fn remove(&self, filter: Filter, just_once: bool) -> NitriteResult<WriteResult> {
+        let mut results = Vec::new();
+        for shard in self.inner.targets(&filter)? {
+            let result = self.inner.shards[shard].remove(filter.clone(), just_once)?;
+            let done = just_once && !result.affected_nitrite_ids().is_empty();
+            results.push(result);
+            if done {
+                break;
+            }
+        }
+        Ok(merge_results(results))
+    }
+
+    fn remove_id_range(&self, start: &NitriteId, end: &NitriteId) -> NitriteResult<u64> {
+        self.sum(|shard| shard.remove_id_range(start, end))
+    }
+
+    fn remove_one(&self, document: &Document) -> NitriteResult<WriteResult> {
+        let shard = match document.get(DOC_ID)? {
+            Value::NitriteId(id) => self.inner.locate(&id)?,
+            _ => None,
+        };
+        match shard {
+            Some(shard) => self.inner.shards[shard].remove_one(document),
+            None => Ok(WriteResult::new(vec![])),
+        }
+    }
+
+    fn bulk_write_with_options(
+        &self,
+        operations: Vec<BulkOperation>,
+        options: &BulkWriteOptions,
+    ) -> NitriteResult<BulkWriteResult> {
+        let mut target = None;
+        for operation in &operations {
+            let shard = self.inner.bulk_target(operation)?;
+            if target.is_some_and(|target| target != shard) {
+                log::error!("A bulk write on {} must stay within one shard", self.inner.name);
+                return Err(NitriteError::new(
+                    &format!("A bulk write on {} must stay within one shard", self.inner.name),
+                    ErrorKind::InvalidOperation,
+                ));
+            }
+            target = Some(shard);
+        }
+
+        match target {
+            Some(shard) => self.inner.shards[shard].bulk_write_with_options(operations, options),
+            None => Ok(BulkWriteResult::default()),
+        }
+    }
+
+    fn update_each_with_options(
+        &self,
+        filter: Filter,
+        transform: &mut dyn FnMut(Document) -> NitriteResult<Option<Document>>,
+        options: &UpdateEachOptions,
+    ) -> NitriteResult<UpdateEachResult> {
+        let mut result = UpdateEachResult::default();
+        for shard in self.inner.targets(&filter)? {
+            let mut checked = |document| {
+                let update = transform(document)?;
+                if let Some(update) = &update {
+                    self.inner.check_stays(shard, update)?;
+                }
+                Ok(update)
+            };
+            let updated = self.inner.shards[shard].update_each_with_options(
+                filter.clone(),
+                &mut checked,
+                options,
+            )?;
+            result.matched_count += updated.matched_count;
+            result.modified_count += updated.modified_count;
+            result.batch_count += updated.batch_count;
+            result.write_token = result.write_token.max(updated.write_token);
+        }
+        Ok(result)
+    }
+
+    fn find(&self, filter: Filter) -> NitriteResult<DocumentCursor> {
+        self.find_with_options(filter, &FindOptions::new())
+    }
+
+    fn find_with_options(
+        &self,
+        filter: Filter,
+        find_options: &FindOptions,
+    ) -> NitriteResult<DocumentCursor> {
+        let targets = self.inner.targets(&filter)?;
+        if let [shard] = targets.as_slice() {
+            return self.inner.shards[*shard].find_with_options(filter, find_options);
+        }
+        if find_options.distance_sort.is_some() {
+            log::error!("Sorting by distance needs a filter on the shard key of {}", self.inner.name);
+            return Err(NitriteError::new(
+                &format!(
+                    "Sorting by distance needs a filter on the shard key of {}",
+                    self.inner.name
+                ),
+                ErrorKind::InvalidOperation,
+            ));
+        }
+        if find_options.resume_after.is_some() {
+            log::error!("A scan of several shards of {} cannot be resumed", self.inner.name);
+            return Err(NitriteError::new(
+                &format!("A scan of several shards of {} cannot be resumed", self.inner.name),
+                ErrorKind::InvalidOperation,
+            ));
+        }
+
+        let shard_options = shard_find_options(find_options)?;
+        let mut cursors = Vec::with_capacity(targets.len());
+        for shard in targets {
+            cursors.push(self.inner.shards[shard].find_with_options(filter.clone(), &shard_options)?);
+        }
+        let mut stream: Box<dyn Iterator<Item = NitriteResult<Document>>> =
+            Box::new(cursors.into_iter().flatten());
+
+        if let Some(sort_by) = &find_options.sort_by {
+            let collator = Collator::try_new(
+                find_options.collator_preferences.unwrap_or_default(),
+                find_options.collator_options.unwrap_or(CollatorOptions::default()),
+            )
+            .map_err(|_| {
+                NitriteError::new(
+                    "Failed to create collator for sorting - check collator preferences and options",
+                    ErrorKind::BackendError,
+                )
+            })?;
+            let memory_budget = self.inner.config.sort_memory_budget();
+            let memory_budget = find_options
+                .max_memory
+                .map_or(memory_budget, |max| max.min(memory_budget));
+            stream = Box::new(SortedStream::with_memory_budget(
+                stream,
+                sort_by.sorting_order(),
+                Some(collator),
+                find_options.tie_break_by_id,
+                self.first_shard().store()?,
+                memory_budget,
+            ));
+        }
+
+        if find_options.skip.is_some() || find_options.limit.is_some() {
+            let skip = find_options.skip.unwrap_or(0);
+            let limit = find_options.limit.unwrap_or(u64::MAX);
+            stream = Box::new(stream.skip(skip as usize).take(limit as usize));
+        }
+
+        Ok(DocumentCursor::new(stream, ProcessorChain::new())
+            .projected_to(find_options.projection.clone()))
+    }
+
+    fn count(&self, filter: Filter) -> NitriteResult<u64> {
+        let mut total = 0;
+        for shard in self.inner.targets(&filter)? {
+            total += self.inner.shards[shard].count(filter.clone())?;
+        }
+        Ok(total)
+    }
+
+    fn get_by_id(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
+        for shard in self.shards() {
+            if let Some(document) = shard.get_by_id(id)? {
+                return Ok(Some(document));
+            }
+        }
+        Ok(None)
+    }
+
+    fn enable_history(&self, options: HistoryOptions) -> NitriteResult<()> {
+        self.on_all(|shard| shard.enable_history(options))
+    }
+
+    fn disable_history(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.disable_history())
+    }
+
+    fn history_options(&self) -> NitriteResult<Option<HistoryOptions>> {
+        self.first_shard().history_options()
+    }
+
+    fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        for shard in self.shards() {
+            let history = shard.document_history(id)?;
+            if !history.is_empty() {
+                return Ok(history);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    fn find_as_of(&self, timestamp: u128, filter: Filter) -> NitriteResult<DocumentCursor> {
+        let mut cursors = Vec::with_capacity(self.shards().len());
+        for shard in self.shards() {
+            cursors.push(shard.find_as_of(timestamp, filter.clone())?);
+        }
+        Ok(DocumentCursor::new(
+            Box::new(cursors.into_iter().flatten()),
+            ProcessorChain::new(),
+        ))
+    }
+
+    fn analyze(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.analyze())
+    }
+
+    fn index_statistics(&self, _field_names: Vec<&str>) -> NitriteResult<Option<IndexStatistics>> {
+        log::error!("Index statistics of {} are kept per shard", self.inner.name);
+        Err(NitriteError::new(
+            &format!("Index statistics of {} are kept per shard", self.inner.name),
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    fn defer_index_maintenance(
+        &self,
+        bulk_load: &mut dyn FnMut() -> NitriteResult<()>,
+    ) -> NitriteResult<()> {
+        defer_on_shards(self.shards(), bulk_load)
+    }
+
+    fn options(&self) -> NitriteResult<CollectionOptions> {
+        self.first_shard().options()
+    }
+
+    fn set_options(&self, options: CollectionOptions) -> NitriteResult<()> {
+        self.on_all(|shard| shard.set_options(options.clone()))
+    }
+
+    fn deleted_documents(&self) -> NitriteResult<Vec<Document>> {
+        let mut deleted = Vec::new();
+        for shard in self.shards() {
+            deleted.extend(shard.deleted_documents()?);
+        }
+        Ok(deleted)
+    }
+
+    fn restore(&self, id: &NitriteId) -> NitriteResult<WriteResult> {
+        for shard in self.shards() {
+            let result = shard.restore(id)?;
+            if !result.affected_nitrite_ids().is_empty() {
+                return Ok(result);
+            }
+        }
+        Ok(WriteResult::new(vec![]))
+    }
+
+    fn purge_deleted(&self) -> NitriteResult<()> {
+        self.on_all(|shard| shard.purge_deleted())
+    }
+
+    fn sweep_expired_fields(&self) -> NitriteResult<u64> {
+        self.sum(|shard| shard.sweep_expired_fields())
+    }
+
+    fn set_redaction_policy(&self, policy: Option<RedactionPolicy>) -> NitriteResult<()> {
+        self.on_all(|shard| shard.set_redaction_policy(policy.clone()))
+    }
+
+    fn redaction_policy(&self) -> NitriteResult<Option<RedactionPolicy>> {
+        self.first_shard().redaction_policy()
+    }
+
+    fn name(&self) -> String {
+        self.inner.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::order_by;
+    use crate::filter::{all, field};
+    use crate::nitrite::Nitrite;
+    use crate::{doc, SortOrder};
+
+    fn open_db() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    #[test]
+    fn test_hash_routing_is_stable_across_integer_types() {
+        let options = ShardOptions::hash("customer", 8);
+        assert_eq!(options.shard_of(&Value::I32(42)), options.shard_of(&Value::I64(42)));
+        assert_eq!(options.shard_of(&Value::U8(42)), options.shard_of(&Value::F64(42.0)));
+        assert_eq!(options.shard_of(&Value::Char('a')), options.shard_of(&Value::from("a")));
+        // FNV-1a of the encoding of null, a single zero byte
+        assert_eq!(stable_hash_64(&Value::Null), 0xaf63_bd4c_8601_b7df);
+    }
+
+    #[test]
+    fn test_range_routing() {
+        let options = ShardOptions::range("year", vec![Value::from(2020), Value::from(2024)]);
+        assert_eq!(options.shard_count(), 3);
+        assert_eq!(options.shard_of(&Value::Null), 0);
+        assert_eq!(options.shard_of(&Value::from(2019)), 0);
+        assert_eq!(options.shard_of(&Value::from(2020)), 1);
+        assert_eq!(options.shard_of(&Value::from(2023)), 1);
+        assert_eq!(options.shard_of(&Value::from(2024)), 2);
+    }
+
+    #[test]
+    fn test_invalid_layouts() {
+        assert!(ShardOptions::hash("customer", 0).validate().is_err());
+        assert!(ShardOptions::hash("", 2).validate().is_err());
+        assert!(ShardOptions::hash(DOC_ID, 2).validate().is_err());
+        assert!(ShardOptions::range("year", vec![Value::from(2024), Value::from(2020)])
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_writes_route_and_finds_merge() {
+        let db = open_db();
+        let orders = db
+            .sharded_collection("orders", ShardOptions::hash("customer", 4))
+            .unwrap();
+        for (customer, total) in [("a", 5), ("b", 3), ("c", 9), ("d", 1), ("e", 7), ("a", 2)] {
+            orders.insert(doc! { customer: customer, total: total }).unwrap();
+        }
+
+        assert_eq!(orders.size().unwrap(), 6);
+        let spread: u64 = orders.shards().iter().map(|shard| shard.size().unwrap()).sum();
+        assert_eq!(spread, 6);
+        let shard = orders.shard_of(&doc! { customer: "a" }).unwrap();
+        assert_eq!(orders.shard(shard).unwrap().count(field("customer").eq("a")).unwrap(), 2);
+        assert_eq!(orders.count(field("customer").eq("a")).unwrap(), 2);
+
+        let totals: Vec<Value> = orders
+            .find_with_options(all(), &order_by("total", SortOrder::Descending).skip(1).limit(3))
+            .unwrap()
+            .map(|document| document.unwrap().get("total").unwrap())
+            .collect();
+        assert_eq!(totals, vec![Value::from(7), Value::from(5), Value::from(3)]);
+        assert!(!db.list_collection_names().unwrap().contains("orders"));
+    }
+
+    #[test]
+    fn test_update_cannot_move_a_document() {
+        let db = open_db();
+        let orders = db
+            .sharded_collection("orders", ShardOptions::range("total", vec![Value::from(5)]))
+            .unwrap();
+        let id = orders.insert(doc! { customer: "a", total: 2 }).unwrap().affected_nitrite_ids()[0];
+
+        let moved = orders.update_by_id(&id, &doc! { total: 8 }, false);
+        assert!(matches!(moved.err().map(|e| e.kind().clone()), Some(ErrorKind::ValidationError)));
+        orders.update_by_id(&id, &doc! { total: 4 }, false).unwrap();
+        assert_eq!(orders.get_by_id(&id).unwrap().unwrap().get("total").unwrap(), Value::from(4));
+        assert!(orders.update(all(), &doc! { total: 9 }).is_err());
+    }
+
+    #[test]
+    fn test_reopen_with_another_layout() {
+        let db = open_db();
+        db.sharded_collection("orders", ShardOptions::hash("customer", 2)).unwrap();
+        assert!(db.sharded_collection("orders", ShardOptions::hash("customer", 2)).is_ok());
+        let other = db.sharded_collection("orders", ShardOptions::hash("region", 2));
+        assert!(matches!(other.err().map(|e| e.kind().clone()), Some(ErrorKind::ValidationError)));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    collection::Document,
+    common::{repository_name, repository_name_by_type},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::Filter,
+    repository::NitriteEntity,
+    store::{EntryIterator, StoreSnapshot},
+    Value,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// A read-only view of the whole database at a single point in time.
+///
+/// A snapshot is opened with [`crate::nitrite::Nitrite::snapshot`] and exposes every
+/// collection and repository as it was at that moment. The application can keep writing
+/// while the snapshot is being read; those writes are never visible through it. This makes
+/// it suitable for long running readers such as nightly exports to a warehouse.
+///
+/// `NitriteSnapshot` is cheap to clone and can be shared between threads, so several
+/// collections can be dumped concurrently.
+///
+/// # Consistency
+///
+/// How consistent the view is depends on the store:
+/// - Stores with multi-version reads (such as the Fjall adapter) provide a view that is
+///   consistent across all collections.
+/// - Other stores (such as the in-memory store) copy each collection when the snapshot is
+///   opened; every collection is consistent on its own, but a write spanning several
+///   collections that races with the snapshot may be partially visible.
+///
+/// # Notes
+///
+/// Documents are returned exactly as stored. Collection processors are not applied, and
+/// queries are evaluated by scanning the snapshot rather than through indexes.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let snapshot = db.snapshot()?;
+/// for name in snapshot.collection_names() {
+///     for doc in snapshot.documents(name)? {
+///         warehouse.write(name, doc?)?;
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct NitriteSnapshot {
+    inner: Arc<NitriteSnapshotInner>,
+}
+
+impl NitriteSnapshot {
+    pub(crate) fn new(
+        store_snapshot: StoreSnapshot,
+        collection_names: HashSet<String>,
+        repository_names: HashSet<String>,
+        keyed_repository_names: HashMap<String, HashSet<String>>,
+    ) -> Self {
+        NitriteSnapshot {
+            inner: Arc::new(NitriteSnapshotInner {
+                store_snapshot,
+                collection_names,
+                repository_names,
+                keyed_repository_names,
+            }),
+        }
+    }
+
+    /// Returns the names of the collections captured by the snapshot.
+    pub fn collection_names(&self) -> &HashSet<String> {
+        &self.inner.collection_names
+    }
+
+    /// Returns the names of the repositories captured by the snapshot.
+    pub fn repository_names(&self) -> &HashSet<String> {
+        &self.inner.repository_names
+    }
+
+    /// Returns the keyed repositories captured by the snapshot, grouped by key.
+    pub fn keyed_repository_names(&self) -> &HashMap<String, HashSet<String>> {
+        &self.inner.keyed_repository_names
+    }
+
+    /// Iterates over all documents of a collection as of the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the collection did not exist when the snapshot was
+    /// opened.
+    pub fn documents(&self, collection_name: &str) -> NitriteResult<SnapshotCursor> {
+        if !self.inner.collection_names.contains(collection_name) {
+            log::error!("Collection {} does not exist in the snapshot", collection_name);
+            return Err(NitriteError::new(
+                &format!("Collection {} does not exist in the snapshot", collection_name),
+                ErrorKind::NotFound,
+            ));
+        }
+        self.inner.cursor(collection_name)
+    }
+
+    /// Iterates over the documents of a collection that match `filter`.
+    ///
+    /// The filter is evaluated against every document of the snapshot; indexes are not
+    /// used, so index-only filters (such as full-text filters) are not supported.
+    pub fn find(
+        &self,
+        collection_name: &str,
+        filter: Filter,
+    ) -> NitriteResult<impl Iterator<Item = NitriteResult<Document>>> {
+        let cursor = self.documents(collection_name)?;
+        Ok(cursor.filter_map(move |result| match result {
+            Ok(doc) => match filter.apply(&doc) {
+                Ok(true) => Some(Ok(doc)),
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            },
+            Err(e) => Some(Err(e)),
+        }))
+    }
+
+    /// Iterates over all documents of the repository of `T` as of the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the repository did not exist when the snapshot was
+    /// opened.
+    pub fn repository_documents<T: NitriteEntity>(&self) -> NitriteResult<SnapshotCursor> {
+        let name = repository_name_by_type::<T>(None)?;
+        if !self.inner.repository_names.contains(&name) {
+            log::error!("Repository {} does not exist in the snapshot", name);
+            return Err(NitriteError::new(
+                &format!("Repository {} does not exist in the snapshot", name),
+                ErrorKind::NotFound,
+            ));
+        }
+        self.inner.cursor(&name)
+    }
+
+    /// Iterates over all documents of the keyed repository of `T` as of the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the keyed repository did not exist when the snapshot
+    /// was opened.
+    pub fn keyed_repository_documents<T: NitriteEntity>(
+        &self,
+        key: &str,
+    ) -> NitriteResult<SnapshotCursor> {
+        let entity_name = T::default().entity_name();
+        let exists = self
+            .inner
+            .keyed_repository_names
+            .get(key)
+            .is_some_and(|names| names.contains(&entity_name));
+        if !exists {
+            log::error!(
+                "Keyed repository {} with key {} does not exist in the snapshot",
+                entity_name,
+                key
+            );
+            return Err(NitriteError::new(
+                &format!(
+                    "Keyed repository {} with key {} does not exist in the snapshot",
+                    entity_name, key
+                ),
+                ErrorKind::NotFound,
+            ));
+        }
+        self.inner.cursor(&repository_name(&entity_name, Some(key))?)
+    }
+}
+
+struct NitriteSnapshotInner {
+    store_snapshot: StoreSnapshot,
+    collection_names: HashSet<String>,
+    repository_names: HashSet<String>,
+    keyed_repository_names: HashMap<String, HashSet<String>>,
+}
+
+impl NitriteSnapshotInner {
+    fn cursor(&self, map_name: &str) -> NitriteResult<SnapshotCursor> {
+        Ok(SnapshotCursor {
+            entries: self.store_snapshot.entries(map_name)?,
+        })
+    }
+}
+
+/// An iterator over the documents of a collection or repository in a [`NitriteSnapshot`].
+///
+/// Documents are returned in `_id` order.
+pub struct SnapshotCursor {
+    entries: EntryIterator,
+}
+
+impl Iterator for SnapshotCursor {
+    type Item = NitriteResult<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        Some(entry.and_then(|(_, value)| match value {
+            Value::Document(doc) => Ok(doc),
+            _ => {
+                log::error!("Snapshot entry is not a document");
+                Err(NitriteError::new(
+                    "Snapshot entry is not a document",
+                    ErrorKind::InvalidOperation,
+                ))
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::doc;
+    use crate::errors::{ErrorKind, NitriteResult};
+    use crate::filter::field;
+    use crate::nitrite::Nitrite;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_is_isolated_from_later_writes() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let col = db.collection("snapshot_orders")?;
+        col.insert(doc! { order: 1 })?;
+        col.insert(doc! { order: 2 })?;
+
+        let snapshot = db.snapshot()?;
+        col.insert(doc! { order: 3 })?;
+        db.collection("snapshot_created_later")?;
+
+        assert_eq!(snapshot.documents("snapshot_orders")?.count(), 2);
+        assert_eq!(col.size()?, 3);
+        assert!(snapshot.collection_names().contains("snapshot_orders"));
+        assert!(!snapshot.collection_names().contains("snapshot_created_later"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_find() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let col = db.collection("snapshot_find")?;
+        col.insert(doc! { name: "a", qty: 1 })?;
+        col.insert(doc! { name: "b", qty: 5 })?;
+
+        let snapshot = db.snapshot()?;
+        let matches = snapshot
+            .find("snapshot_find", field("qty").gt(2))?
+            .collect::<NitriteResult<Vec<_>>>()?;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].get("name")?, "b".into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_unknown_collection() {
+        let db = setup_nitrite();
+        let snapshot = db.snapshot().unwrap();
+        let result = snapshot.documents("snapshot_missing");
+        assert!(matches!(result.err().map(|e| e.kind().clone()), Some(ErrorKind::NotFound)));
+    }
+
+    #[test]
+    fn test_snapshot_on_closed_database() {
+        let db = setup_nitrite();
+        db.close().unwrap();
+        assert!(db.snapshot().is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Service provider interface for extension authors.
+//!
+//! This module gathers everything a third-party crate needs to plug a new index type
+//! (like `nitrite-spatial` or `nitrite-tantivy-fts`) or a new storage backend into
+//! Nitrite, without reaching into internal modules.
+//!
+//! # Stability
+//!
+//! The items re-exported here follow semantic versioning: a breaking change to any of
+//! them, including a new required trait method, only ships in a new major version.
+//! New provided trait methods may be added in minor versions. Types reachable only
+//! through other modules carry no such promise for extension authors.
+//!
+//! # Building an Indexer
+//!
+//! An indexer implements [`NitriteIndexerProvider`] and is registered through a
+//! [`NitriteModule`]:
+//!
+//! - Wrap the provider in a [`NitriteIndexer`] and return it as a [`NitritePlugin`]
+//!   from `NitritePluginProvider::as_plugin`.
+//! - Keep index entries in a store map opened with [`open_index_map`]. The default
+//!   `clear_index` and `is_loaded` of [`NitriteIndexerProvider`] find the map by
+//!   [`index_map_name`], so an indexer using that name gets them for free.
+//! - Keep per-index settings in the attributes of its map through [`AttributeAware`];
+//!   they are persisted in the store's meta map alongside the data.
+//!
+//! # Building a Store
+//!
+//! A store implements [`NitriteStoreProvider`] with maps implementing
+//! [`NitriteMapProvider`], and is registered through a [`StoreModule`]. The store keeps
+//! a [`StoreCatalog`] of the collections and repositories it holds, which Nitrite writes
+//! through `write_collection_entry` and its siblings whenever a collection or repository
+//! is created.
+//!
+//! # Example
+//!
+//! ```rust
+//! use nitrite::errors::NitriteResult;
+//! use nitrite::nitrite::Nitrite;
+//! use nitrite::spi::{
+//!     index_map_name, open_index_map, AttributeAware, Fields, IndexDescriptor, NitriteMapProvider,
+//!     Value,
+//! };
+//!
+//! # fn main() -> NitriteResult<()> {
+//! let db = Nitrite::builder().open_or_create(None, None)?;
+//! let fields = Fields::with_names(vec!["location"])?;
+//! let descriptor = IndexDescriptor::new("grid", fields, "places");
+//!
+//! let map = open_index_map(&descriptor, &db.config())?;
+//! map.put(Value::from("cell-7"), Value::from(3i64))?;
+//! map.set_attribute("grid_size", Value::from(16i64))?;
+//!
+//! assert_eq!(map.get_name()?, index_map_name(&descriptor));
+//! assert_eq!(map.get_attribute("grid_size")?, Some(Value::from(16i64)));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errors::NitriteResult;
+use crate::nitrite_config::NitriteConfig;
+
+pub use crate::collection::{Document, FindPlan, NitriteId};
+pub use crate::common::{
+    AttributeAware, Attributes, FieldValues, Fields, Key, ModuleInfo, NitriteModule,
+    NitritePlugin, NitritePluginProvider, PluginRegistrar, Value,
+};
+pub use crate::filter::{Filter, FilterProvider};
+pub use crate::index::{IndexDescriptor, IndexExport, NitriteIndexer, NitriteIndexerProvider};
+pub use crate::store::{
+    EntryIterator, EntryIteratorProvider, KeyIterator, KeyIteratorProvider, NitriteMap,
+    NitriteMapProvider, NitriteStore, NitriteStoreProvider, StoreCatalog, StoreConfig,
+    StoreConfigProvider, StoreModule, ValueIterator, ValueIteratorProvider,
+};
+
+/// Returns the name of the store map that holds the entries of an index.
+///
+/// The name is `$nitrite_index|<collection>|<fields>|<index type>`, the same one the
+/// built-in indexes use. It is part of the stable on-disk format.
+pub fn index_map_name(descriptor: &IndexDescriptor) -> String {
+    crate::derive_index_map_name(descriptor)
+}
+
+/// Opens the store map that holds the entries of an index, creating it if needed.
+///
+/// # Errors
+/// Returns an error if the store is not open or the map cannot be opened.
+pub fn open_index_map(
+    descriptor: &IndexDescriptor,
+    nitrite_config: &NitriteConfig,
+) -> NitriteResult<NitriteMap> {
+    nitrite_config
+        .nitrite_store()?
+        .open_map(&index_map_name(descriptor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::INDEX_PREFIX;
+    use crate::nitrite::Nitrite;
+
+    fn descriptor() -> IndexDescriptor {
+        let fields = Fields::with_names(vec!["a", "b"]).unwrap();
+        IndexDescriptor::new("custom", fields, "items")
+    }
+
+    #[test]
+    fn test_index_map_name_matches_core_indexes() {
+        let name = index_map_name(&descriptor());
+        assert!(name.starts_with(INDEX_PREFIX));
+        assert_eq!(name, crate::derive_index_map_name(&descriptor()));
+        assert!(name.ends_with("|custom"));
+    }
+
+    #[test]
+    fn test_open_index_map_keeps_attributes() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let map = open_index_map(&descriptor(), &db.config()).unwrap();
+        map.put(Value::from(1i64), Value::from("one")).unwrap();
+        map.set_attribute("version", Value::from(2i64)).unwrap();
+
+        let reopened = open_index_map(&descriptor(), &db.config()).unwrap();
+        assert_eq!(reopened.get(&Value::from(1i64)).unwrap(), Some(Value::from("one")));
+        assert_eq!(reopened.get_attribute("version").unwrap(), Some(Value::from(2i64)));
+    }
+}
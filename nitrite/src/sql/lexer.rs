@@ -0,0 +1,179 @@
+use super::syntax_error;
+use crate::errors::NitriteResult;
+
+/// A token of a query, with the byte offset where it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    pub(crate) position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    /// A keyword or a name; dots are part of the name, as in `address.city`.
+    Word(String),
+    /// A name between double quotes or backticks, never read as a keyword.
+    QuotedName(String),
+    /// A literal between single quotes; `''` stands for one quote.
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Comparison(Comparison),
+    Comma,
+    Star,
+    OpenParen,
+    CloseParen,
+    Semicolon,
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+/// Splits a query into tokens, ending with [`TokenKind::End`].
+pub(crate) fn tokenize(sql: &str) -> NitriteResult<Vec<Token>> {
+    let chars: Vec<(usize, char)> = sql.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let (position, char) = chars[index];
+        let next = chars.get(index + 1).map(|(_, char)| *char);
+
+        let kind = match char {
+            char if char.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            ',' => TokenKind::Comma,
+            '*' => TokenKind::Star,
+            '(' => TokenKind::OpenParen,
+            ')' => TokenKind::CloseParen,
+            ';' => TokenKind::Semicolon,
+            '=' => {
+                if next == Some('=') {
+                    index += 1;
+                }
+                TokenKind::Comparison(Comparison::Eq)
+            }
+            '!' if next == Some('=') => {
+                index += 1;
+                TokenKind::Comparison(Comparison::Ne)
+            }
+            '<' => match next {
+                Some('=') => {
+                    index += 1;
+                    TokenKind::Comparison(Comparison::Lte)
+                }
+                Some('>') => {
+                    index += 1;
+                    TokenKind::Comparison(Comparison::Ne)
+                }
+                _ => TokenKind::Comparison(Comparison::Lt),
+            },
+            '>' => {
+                if next == Some('=') {
+                    index += 1;
+                    TokenKind::Comparison(Comparison::Gte)
+                } else {
+                    TokenKind::Comparison(Comparison::Gt)
+                }
+            }
+            '\'' | '"' | '`' => {
+                let (text, end) = read_quoted(&chars, index)?;
+                index = end;
+                tokens.push(Token {
+                    kind: if char == '\'' {
+                        TokenKind::Text(text)
+                    } else {
+                        TokenKind::QuotedName(text)
+                    },
+                    position,
+                });
+                continue;
+            }
+            char if char.is_ascii_digit()
+                || (char == '-' && next.is_some_and(|next| next.is_ascii_digit())) =>
+            {
+                let start = index;
+                index += 1;
+                while index < chars.len()
+                    && (chars[index].1.is_ascii_digit() || matches!(chars[index].1, '.' | 'e' | 'E')
+                        || (matches!(chars[index].1, '+' | '-')
+                            && matches!(chars[index - 1].1, 'e' | 'E')))
+                {
+                    index += 1;
+                }
+                let end = chars.get(index).map_or(sql.len(), |(position, _)| *position);
+                tokens.push(Token {
+                    kind: number(&sql[chars[start].0..end], position)?,
+                    position,
+                });
+                continue;
+            }
+            char if char.is_alphabetic() || char == '_' || char == '$' => {
+                let start = index;
+                while index < chars.len()
+                    && (chars[index].1.is_alphanumeric() || matches!(chars[index].1, '_' | '$' | '.'))
+                {
+                    index += 1;
+                }
+                let end = chars.get(index).map_or(sql.len(), |(position, _)| *position);
+                tokens.push(Token {
+                    kind: TokenKind::Word(sql[chars[start].0..end].to_string()),
+                    position,
+                });
+                continue;
+            }
+            char => return Err(syntax_error(position, &format!("unexpected character '{}'", char))),
+        };
+
+        tokens.push(Token { kind, position });
+        index += 1;
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        position: sql.len(),
+    });
+    Ok(tokens)
+}
+
+/// Reads the quoted text starting at `start`; a doubled quote stands for one quote.
+/// Returns the text and the index after the closing quote.
+fn read_quoted(chars: &[(usize, char)], start: usize) -> NitriteResult<(String, usize)> {
+    let (position, quote) = chars[start];
+    let mut text = String::new();
+    let mut index = start + 1;
+    while index < chars.len() {
+        let char = chars[index].1;
+        if char == quote {
+            if chars.get(index + 1).map(|(_, char)| *char) == Some(quote) {
+                text.push(quote);
+                index += 2;
+                continue;
+            }
+            return Ok((text, index + 1));
+        }
+        text.push(char);
+        index += 1;
+    }
+    Err(syntax_error(position, "unterminated quote"))
+}
+
+fn number(text: &str, position: usize) -> NitriteResult<TokenKind> {
+    if let Ok(value) = text.parse::<i64>() {
+        return Ok(TokenKind::Integer(value));
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(TokenKind::Float(value)),
+        _ => Err(syntax_error(position, &format!("invalid number {}", text))),
+    }
+}
@@ -0,0 +1,176 @@
+//! A read-only SQL layer over collections.
+//!
+//! [`SqlQuery`] parses a small `SELECT` subset into the [`Filter`](crate::filter::Filter)
+//! and [`FindOptions`](crate::collection::FindOptions) the fluent API would build, for
+//! ad-hoc inspection and reporting:
+//!
+//! ```rust,ignore
+//! let rows = db.sql("SELECT name, total FROM orders WHERE status IN ('new', 'paid') ORDER BY total DESC LIMIT 20")?;
+//! ```
+//!
+//! Queries only read; there is no `INSERT`, `UPDATE` or `DELETE`, and no joins or
+//! aggregates.
+//!
+//! This module requires the `sql` feature.
+
+mod lexer;
+mod parser;
+mod query;
+
+pub use query::*;
+
+use crate::errors::{ErrorKind, NitriteError};
+
+pub(crate) fn syntax_error(position: usize, message: &str) -> NitriteError {
+    log::error!("Invalid SQL query at position {}: {}", position, message);
+    NitriteError::new(
+        &format!("Invalid SQL query at position {}: {}", position, message),
+        ErrorKind::FilterError,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Document;
+    use crate::common::{SortOrder, Value};
+    use crate::doc;
+    use crate::nitrite::Nitrite;
+
+    fn setup_nitrite() -> Nitrite {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let users = db.collection("users").unwrap();
+        users
+            .insert_many(vec![
+                doc! { name: "Ada", age: 36, role: "admin", address: { city: "London" } },
+                doc! { name: "Alan", age: 41, role: "user", address: { city: "Wilmslow" } },
+                doc! { name: "Grace", age: 45, role: "admin" },
+                doc! { name: "Linus", age: 17, role: "user", address: { city: "Helsinki" } },
+            ])
+            .unwrap();
+        db
+    }
+
+    fn names(documents: &[Document]) -> Vec<String> {
+        documents
+            .iter()
+            .map(|document| document.get("name").unwrap().as_string().unwrap().clone())
+            .collect()
+    }
+
+    fn query(db: &Nitrite, sql: &str) -> Vec<String> {
+        names(&SqlQuery::parse(sql).unwrap().execute(db).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        let query = SqlQuery::parse(
+            "select name, \"order\", `address.city` from users \
+             where age >= 18 and not (role = 'guest' or name like 'X%') \
+             order by age desc, name limit 10 offset 5;",
+        )
+        .unwrap();
+        assert_eq!(query.collection(), "users");
+        assert_eq!(
+            query.fields(),
+            Some(&["name".to_string(), "order".to_string(), "address.city".to_string()][..])
+        );
+        assert_eq!(
+            query.sort,
+            vec![
+                ("age".to_string(), SortOrder::Descending),
+                ("name".to_string(), SortOrder::Ascending)
+            ]
+        );
+        assert_eq!((query.skip, query.limit), (Some(5), Some(10)));
+
+        let query = SqlQuery::parse("SELECT * FROM users").unwrap();
+        assert!(query.fields().is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (sql, position) in [
+            ("SELECT FROM users", 7),
+            ("SELECT * users", 9),
+            ("SELECT * FROM users WHERE age >", 31),
+            ("SELECT * FROM users WHERE name = 'Ada", 33),
+            ("SELECT * FROM users LIMIT -1", 26),
+            ("SELECT * FROM users WHERE age ~ 1", 30),
+            ("SELECT * FROM users ORDER age", 26),
+        ] {
+            let err = SqlQuery::parse(sql).err().unwrap();
+            assert_eq!(err.kind(), &ErrorKind::FilterError);
+            assert!(
+                err.message().contains(&format!("position {}", position)),
+                "{}: {}",
+                sql,
+                err.message()
+            );
+        }
+    }
+
+    #[test]
+    fn test_execute_predicates() {
+        let db = setup_nitrite();
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE age > 18 AND role = 'admin' ORDER BY name"),
+            vec!["Ada", "Grace"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE role <> 'admin' OR age >= 45 ORDER BY age"),
+            vec!["Linus", "Alan", "Grace"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE name IN ('Ada', 'Linus') ORDER BY name"),
+            vec!["Ada", "Linus"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE name NOT LIKE 'A%' ORDER BY name"),
+            vec!["Grace", "Linus"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE name LIKE '_da'"),
+            vec!["Ada"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE age BETWEEN 36 AND 41 ORDER BY age"),
+            vec!["Ada", "Alan"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE address.city IS NULL"),
+            vec!["Grace"]
+        );
+        assert_eq!(
+            query(&db, "SELECT * FROM users WHERE NOT address.city IS NOT NULL"),
+            vec!["Grace"]
+        );
+    }
+
+    #[test]
+    fn test_execute_projection_and_paging() {
+        let db = setup_nitrite();
+        let documents = SqlQuery::parse(
+            "SELECT name, address.city FROM users ORDER BY age DESC LIMIT 2 OFFSET 1",
+        )
+        .unwrap()
+        .execute(&db)
+        .unwrap();
+        assert_eq!(names(&documents), vec!["Alan", "Ada"]);
+        assert_eq!(documents[0].get("address.city").unwrap(), Value::from("Wilmslow"));
+        assert!(!documents[0].contains_key("age"));
+        assert!(!documents[0].has_id());
+    }
+
+    #[test]
+    fn test_execute_unknown_collection() {
+        let db = setup_nitrite();
+        let err = SqlQuery::parse("SELECT * FROM missing")
+            .unwrap()
+            .execute(&db)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), &ErrorKind::CollectionNotFound);
+        assert!(!db.has_collection("missing").unwrap());
+    }
+}
@@ -0,0 +1,310 @@
+use super::lexer::{tokenize, Comparison, Token, TokenKind};
+use super::query::SqlQuery;
+use super::syntax_error;
+use crate::common::{SortOrder, Value};
+use crate::errors::{NitriteError, NitriteResult};
+use crate::filter::{all, and, field, not, or, Filter};
+
+const KEYWORDS: [&str; 19] = [
+    "SELECT", "FROM", "WHERE", "ORDER", "BY", "ASC", "DESC", "LIMIT", "OFFSET", "AND", "OR",
+    "NOT", "IN", "LIKE", "IS", "NULL", "BETWEEN", "TRUE", "FALSE",
+];
+
+/// Parses a query into its parts; see [`SqlQuery`] for the grammar.
+pub(crate) fn parse(sql: &str) -> NitriteResult<SqlQuery> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        index: 0,
+    };
+    parser.select()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn select(&mut self) -> NitriteResult<SqlQuery> {
+        self.expect_keyword("SELECT")?;
+        let fields = if self.accept(&TokenKind::Star) {
+            None
+        } else {
+            let mut fields = vec![self.name("a field name or *")?];
+            while self.accept(&TokenKind::Comma) {
+                fields.push(self.name("a field name")?);
+            }
+            Some(fields)
+        };
+
+        self.expect_keyword("FROM")?;
+        let collection = self.name("a collection name")?;
+
+        let filter = if self.accept_keyword("WHERE") {
+            self.or_expression()?
+        } else {
+            all()
+        };
+
+        let mut sort = Vec::new();
+        if self.accept_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let field = self.name("a field name")?;
+                let order = if self.accept_keyword("DESC") {
+                    SortOrder::Descending
+                } else {
+                    self.accept_keyword("ASC");
+                    SortOrder::Ascending
+                };
+                sort.push((field, order));
+                if !self.accept(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let mut limit = None;
+        let mut skip = None;
+        if self.accept_keyword("LIMIT") {
+            limit = Some(self.count()?);
+            if self.accept_keyword("OFFSET") {
+                skip = Some(self.count()?);
+            }
+        }
+
+        self.accept(&TokenKind::Semicolon);
+        if self.peek().kind != TokenKind::End {
+            return Err(self.unexpected("the end of the query"));
+        }
+
+        Ok(SqlQuery {
+            collection,
+            fields,
+            filter,
+            sort,
+            skip,
+            limit,
+        })
+    }
+
+    fn or_expression(&mut self) -> NitriteResult<Filter> {
+        let mut filters = vec![self.and_expression()?];
+        while self.accept_keyword("OR") {
+            filters.push(self.and_expression()?);
+        }
+        Ok(combine(filters, or))
+    }
+
+    fn and_expression(&mut self) -> NitriteResult<Filter> {
+        let mut filters = vec![self.not_expression()?];
+        while self.accept_keyword("AND") {
+            filters.push(self.not_expression()?);
+        }
+        Ok(combine(filters, and))
+    }
+
+    fn not_expression(&mut self) -> NitriteResult<Filter> {
+        if self.accept_keyword("NOT") {
+            return Ok(not(self.not_expression()?));
+        }
+        if self.accept(&TokenKind::OpenParen) {
+            let filter = self.or_expression()?;
+            self.expect(&TokenKind::CloseParen, ")")?;
+            return Ok(filter);
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> NitriteResult<Filter> {
+        let name = self.name("a field name")?;
+
+        if let TokenKind::Comparison(comparison) = self.peek().kind {
+            self.index += 1;
+            let value = self.literal()?;
+            let field = field(&name);
+            return Ok(match comparison {
+                Comparison::Eq => field.eq(value),
+                Comparison::Ne => field.ne(value),
+                Comparison::Lt => field.lt(value),
+                Comparison::Lte => field.lte(value),
+                Comparison::Gt => field.gt(value),
+                Comparison::Gte => field.gte(value),
+            });
+        }
+
+        if self.accept_keyword("IS") {
+            let negated = self.accept_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(if negated {
+                field(&name).ne(Value::Null)
+            } else {
+                field(&name).eq(Value::Null)
+            });
+        }
+
+        let negated = self.accept_keyword("NOT");
+        let filter = if self.accept_keyword("IN") {
+            self.expect(&TokenKind::OpenParen, "(")?;
+            let mut values = vec![self.literal()?];
+            while self.accept(&TokenKind::Comma) {
+                values.push(self.literal()?);
+            }
+            self.expect(&TokenKind::CloseParen, ")")?;
+            field(&name).in_array(values)
+        } else if self.accept_keyword("LIKE") {
+            let token = self.next();
+            match token.kind {
+                TokenKind::Text(pattern) => field(&name).text_regex(&like_to_regex(&pattern)),
+                _ => return Err(unexpected(&token, "a quoted pattern")),
+            }
+        } else if self.accept_keyword("BETWEEN") {
+            let lower = self.literal()?;
+            self.expect_keyword("AND")?;
+            let upper = self.literal()?;
+            field(&name).between_inclusive(lower, upper, true)
+        } else {
+            return Err(self.unexpected("a comparison, IN, LIKE, IS or BETWEEN"));
+        };
+
+        Ok(if negated { not(filter) } else { filter })
+    }
+
+    fn literal(&mut self) -> NitriteResult<Value> {
+        let token = self.next();
+        match &token.kind {
+            TokenKind::Text(text) => Ok(Value::String(text.clone())),
+            TokenKind::Integer(value) => Ok(Value::I64(*value)),
+            TokenKind::Float(value) => Ok(Value::F64(*value)),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("TRUE") => Ok(Value::Bool(true)),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("FALSE") => Ok(Value::Bool(false)),
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("NULL") => Ok(Value::Null),
+            _ => Err(unexpected(&token, "a literal")),
+        }
+    }
+
+    fn count(&mut self) -> NitriteResult<u64> {
+        let token = self.next();
+        match token.kind {
+            TokenKind::Integer(value) if value >= 0 => Ok(value as u64),
+            _ => Err(unexpected(&token, "a non-negative integer")),
+        }
+    }
+
+    fn name(&mut self, expected: &str) -> NitriteResult<String> {
+        let token = self.next();
+        match &token.kind {
+            TokenKind::QuotedName(name) => Ok(name.clone()),
+            TokenKind::Word(word) if !is_keyword(word) => Ok(word.clone()),
+            _ => Err(unexpected(&token, expected)),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.index].clone();
+        if token.kind != TokenKind::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn accept(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        match &self.peek().kind {
+            TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword) => {
+                self.index += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind, expected: &str) -> NitriteResult<()> {
+        if self.accept(kind) {
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> NitriteResult<()> {
+        if self.accept_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> NitriteError {
+        unexpected(self.peek(), expected)
+    }
+}
+
+fn unexpected(token: &Token, expected: &str) -> NitriteError {
+    syntax_error(
+        token.position,
+        &format!("expected {}, found {}", expected, describe(&token.kind)),
+    )
+}
+
+fn combine(mut filters: Vec<Filter>, combinator: fn(Vec<Filter>) -> Filter) -> Filter {
+    if filters.len() == 1 {
+        filters.remove(0)
+    } else {
+        combinator(filters)
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+fn describe(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Word(word) => format!("'{}'", word),
+        TokenKind::QuotedName(name) => format!("\"{}\"", name),
+        TokenKind::Text(text) => format!("'{}'", text.replace('\'', "''")),
+        TokenKind::Integer(value) => value.to_string(),
+        TokenKind::Float(value) => value.to_string(),
+        TokenKind::Comparison(_) => "a comparison".to_string(),
+        TokenKind::Comma => "','".to_string(),
+        TokenKind::Star => "'*'".to_string(),
+        TokenKind::OpenParen => "'('".to_string(),
+        TokenKind::CloseParen => "')'".to_string(),
+        TokenKind::Semicolon => "';'".to_string(),
+        TokenKind::End => "the end of the query".to_string(),
+    }
+}
+
+/// Converts a `LIKE` pattern, where `%` matches any text and `_` one character, into an
+/// anchored regular expression.
+fn like_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^(?s)");
+    for char in pattern.chars() {
+        match char {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' => {
+                regex.push('\\');
+                regex.push(char);
+            }
+            char => regex.push(char),
+        }
+    }
+    regex.push('$');
+    regex
+}
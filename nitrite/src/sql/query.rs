@@ -0,0 +1,142 @@
+use super::parser::parse;
+use crate::collection::{Document, FindOptions, NitriteCollection};
+use crate::common::SortOrder;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::filter::Filter;
+use crate::nitrite::Nitrite;
+
+/// A parsed `SELECT` query.
+///
+/// The supported subset is:
+///
+/// ```text
+/// SELECT * | field [, field]...
+/// FROM collection
+/// [WHERE condition]
+/// [ORDER BY field [ASC | DESC] [, field [ASC | DESC]]...]
+/// [LIMIT count [OFFSET count]]
+/// ```
+///
+/// A condition combines predicates with `AND`, `OR`, `NOT` and parentheses:
+///
+/// | Predicate | Filter |
+/// |---|---|
+/// | `field = value`, `!=` or `<>`, `<`, `<=`, `>`, `>=` | `field(..).eq(value)` and so on |
+/// | `field [NOT] IN (value, ...)` | `in_array` |
+/// | `field [NOT] LIKE 'pattern'` | `text_regex`; `%` matches any text, `_` one character |
+/// | `field [NOT] BETWEEN low AND high` | `between_inclusive` |
+/// | `field IS [NOT] NULL` | `eq(Value::Null)`, which matches missing fields too |
+///
+/// Values are `'text'` (with `''` for a quote), integers, decimals, `TRUE`, `FALSE` and
+/// `NULL`. Keywords are case-insensitive. Fields may be embedded fields such as
+/// `address.city`; names that clash with keywords or contain other characters are
+/// written between double quotes or backticks.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let query = SqlQuery::parse(
+///     "SELECT name, address.city FROM users WHERE age >= 18 AND name LIKE 'A%' ORDER BY age DESC LIMIT 10",
+/// )?;
+/// let adults = query.execute(&db)?;
+/// ```
+pub struct SqlQuery {
+    pub(crate) collection: String,
+    pub(crate) fields: Option<Vec<String>>,
+    pub(crate) filter: Filter,
+    pub(crate) sort: Vec<(String, SortOrder)>,
+    pub(crate) skip: Option<u64>,
+    pub(crate) limit: Option<u64>,
+}
+
+impl SqlQuery {
+    /// Parses a query.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FilterError` pointing at the position of the first syntax error.
+    pub fn parse(sql: &str) -> NitriteResult<SqlQuery> {
+        parse(sql)
+    }
+
+    /// Returns the name of the queried collection or repository.
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    /// Returns the selected fields, or `None` for `SELECT *`.
+    pub fn fields(&self) -> Option<&[String]> {
+        self.fields.as_deref()
+    }
+
+    /// Returns the filter of the `WHERE` clause, or `all()` without one.
+    pub fn filter(&self) -> Filter {
+        self.filter.clone()
+    }
+
+    /// Returns the options holding the `ORDER BY`, `LIMIT` and `OFFSET` clauses.
+    pub fn find_options(&self) -> FindOptions {
+        let mut options = FindOptions::new();
+        for (field, order) in &self.sort {
+            options = options.sort_by(field.clone(), *order);
+        }
+        if let Some(skip) = self.skip {
+            options = options.skip(skip);
+        }
+        if let Some(limit) = self.limit {
+            options = options.limit(limit);
+        }
+        options
+    }
+
+    /// Runs the query and returns the matching documents.
+    ///
+    /// The `FROM` name is looked up among the collections, then among the entity names of
+    /// the repositories. With selected fields, every document holds only those of them
+    /// that are set.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CollectionNotFound` error if there is no such collection or repository;
+    /// the query never creates one.
+    pub fn execute(&self, nitrite: &Nitrite) -> NitriteResult<Vec<Document>> {
+        let collection = self.resolve_collection(nitrite)?;
+        let cursor = collection.find_with_options(self.filter(), &self.find_options())?;
+
+        let mut documents = Vec::new();
+        for document in cursor {
+            let document = document?;
+            match &self.fields {
+                Some(fields) => documents.push(project(&document, fields)?),
+                None => documents.push(document),
+            }
+        }
+        Ok(documents)
+    }
+
+    fn resolve_collection(&self, nitrite: &Nitrite) -> NitriteResult<NitriteCollection> {
+        if nitrite.has_collection(&self.collection)? {
+            return nitrite.collection(&self.collection);
+        }
+        if nitrite.list_repositories()?.contains(&self.collection) {
+            return nitrite.repository_collection(&self.collection, None);
+        }
+
+        log::error!("Collection {} does not exist", self.collection);
+        Err(NitriteError::new(
+            &format!("Collection {} does not exist", self.collection),
+            ErrorKind::CollectionNotFound,
+        ))
+    }
+}
+
+fn project(document: &Document, fields: &[String]) -> NitriteResult<Document> {
+    let mut projected = Document::new();
+    for field in fields {
+        let value = document.get(field)?;
+        if !value.is_null() {
+            projected.put(field.as_str(), value)?;
+        }
+    }
+    Ok(projected)
+}
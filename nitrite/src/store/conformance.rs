@@ -0,0 +1,661 @@
+//! A conformance suite for store implementations.
+//!
+//! [`StoreConformance`] runs a battery of checks against any [`NitriteStoreProvider`]
+//! through a database opened with it: map semantics, value round trips, key order and
+//! navigation, map lifecycle, concurrent access and, for persistent stores, recovery after
+//! the database is closed and opened again. Third-party adapters run it from their own
+//! tests to validate that they behave like the stores shipped with Nitrite.
+//!
+//! Every check works on maps of its own, named `conformance_<check>`, which it removes
+//! before it starts, so a suite can run against a database holding other data.
+//!
+//! This module requires the `conformance` feature.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn my_store_conforms() {
+//!     let path = temp_path();
+//!     let report = StoreConformance::new(move || {
+//!         Nitrite::builder()
+//!             .load_module(MyStoreModule::with_config().db_path(&path).build())
+//!             .open_or_create(None, None)
+//!     })
+//!     .persistent(true)
+//!     .run();
+//!     assert!(report.is_success(), "{}", report);
+//! }
+//! ```
+//!
+//! [`NitriteStoreProvider`]: crate::store::NitriteStoreProvider
+
+use crate::collection::{Document, NitriteId};
+use crate::common::Value;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::nitrite::Nitrite;
+use crate::store::NitriteMap;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+
+type OpenFn = dyn Fn() -> NitriteResult<Nitrite> + Send + Sync;
+type CheckFn = fn(&StoreConformance, &Nitrite) -> NitriteResult<()>;
+
+struct Check {
+    name: &'static str,
+    persistent_only: bool,
+    run: CheckFn,
+}
+
+const CHECKS: &[Check] = &[
+    Check { name: "put_and_get", persistent_only: false, run: check_put_and_get },
+    Check { name: "remove", persistent_only: false, run: check_remove },
+    Check { name: "put_if_absent", persistent_only: false, run: check_put_if_absent },
+    Check { name: "put_all_and_clear", persistent_only: false, run: check_put_all_and_clear },
+    Check { name: "value_round_trip", persistent_only: false, run: check_value_round_trip },
+    Check { name: "key_order", persistent_only: false, run: check_key_order },
+    Check { name: "key_navigation", persistent_only: false, run: check_key_navigation },
+    Check { name: "map_lifecycle", persistent_only: false, run: check_map_lifecycle },
+    Check { name: "concurrent_writes", persistent_only: false, run: check_concurrent_writes },
+    Check { name: "concurrent_reads", persistent_only: false, run: check_concurrent_reads },
+    Check { name: "reopen_keeps_data", persistent_only: true, run: check_reopen_keeps_data },
+    Check { name: "reopen_keeps_removals", persistent_only: true, run: check_reopen_keeps_removals },
+];
+
+/// Runs the conformance checks against the store of a database.
+///
+/// The suite is given a function opening the database; it opens a fresh handle for every
+/// check and closes it afterwards. For a persistent store the function must open the same
+/// location each time, so that the recovery checks see what the previous handle wrote.
+pub struct StoreConformance {
+    open: Arc<OpenFn>,
+    persistent: bool,
+    threads: usize,
+}
+
+impl StoreConformance {
+    /// Creates a suite opening databases with `open`.
+    pub fn new<F>(open: F) -> Self
+    where
+        F: Fn() -> NitriteResult<Nitrite> + Send + Sync + 'static,
+    {
+        StoreConformance {
+            open: Arc::new(open),
+            persistent: false,
+            threads: 4,
+        }
+    }
+
+    /// Runs the recovery checks too, which close the database and open it again. Off by
+    /// default, since an in-memory store starts empty every time.
+    pub fn persistent(mut self, persistent: bool) -> Self {
+        self.persistent = persistent;
+        self
+    }
+
+    /// Sets the number of threads of the concurrency checks; 4 by default.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(2);
+        self
+    }
+
+    /// Returns the names of all checks, in the order they run.
+    pub fn check_names() -> Vec<&'static str> {
+        CHECKS.iter().map(|check| check.name).collect()
+    }
+
+    /// Runs every check and reports the outcome of each.
+    pub fn run(&self) -> ConformanceReport {
+        let outcomes = CHECKS
+            .iter()
+            .map(|check| (check.name, self.run_check(check)))
+            .collect();
+        ConformanceReport { outcomes }
+    }
+
+    /// Runs one check by name. A skipped check succeeds.
+    pub fn run_check_named(&self, name: &str) -> NitriteResult<()> {
+        let Some(check) = CHECKS.iter().find(|check| check.name == name) else {
+            log::error!("No conformance check named {}", name);
+            return Err(NitriteError::new(
+                &format!("No conformance check named {}", name),
+                ErrorKind::NotFound,
+            ));
+        };
+
+        match self.run_check(check) {
+            CheckOutcome::Failed(error) => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    fn run_check(&self, check: &Check) -> CheckOutcome {
+        if check.persistent_only && !self.persistent {
+            return CheckOutcome::Skipped;
+        }
+
+        let db = match self.open() {
+            Ok(db) => db,
+            Err(error) => return CheckOutcome::Failed(error),
+        };
+        let result = remove_map(&db, &map_name(check.name)).and_then(|_| (check.run)(self, &db));
+        let closed = match db.is_closed() {
+            Ok(true) => Ok(()),
+            _ => db.close(),
+        };
+
+        match result.and(closed) {
+            Ok(()) => CheckOutcome::Passed,
+            Err(error) => CheckOutcome::Failed(error),
+        }
+    }
+
+    fn open(&self) -> NitriteResult<Nitrite> {
+        (self.open)()
+    }
+}
+
+/// The outcome of one conformance check.
+#[derive(Debug)]
+pub enum CheckOutcome {
+    /// The store behaved as expected.
+    Passed,
+    /// The store did not behave as expected, or failed with an error.
+    Failed(NitriteError),
+    /// The check does not apply to this store.
+    Skipped,
+}
+
+/// The outcomes of a conformance run, in the order the checks ran.
+///
+/// Its `Display` lists one check per line, which makes a readable assertion message.
+#[derive(Debug)]
+pub struct ConformanceReport {
+    outcomes: Vec<(&'static str, CheckOutcome)>,
+}
+
+impl ConformanceReport {
+    /// Returns `true` if no check failed.
+    pub fn is_success(&self) -> bool {
+        self.failures().is_empty()
+    }
+
+    /// Returns the outcome of every check, by name.
+    pub fn outcomes(&self) -> &[(&'static str, CheckOutcome)] {
+        &self.outcomes
+    }
+
+    /// Returns the failed checks with their errors.
+    pub fn failures(&self) -> Vec<(&'static str, &NitriteError)> {
+        self.outcomes
+            .iter()
+            .filter_map(|(name, outcome)| match outcome {
+                CheckOutcome::Failed(error) => Some((*name, error)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn count(&self, matches: fn(&CheckOutcome) -> bool) -> usize {
+        self.outcomes.iter().filter(|(_, outcome)| matches(outcome)).count()
+    }
+}
+
+impl Display for ConformanceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "store conformance: {} passed, {} failed, {} skipped",
+            self.count(|outcome| matches!(outcome, CheckOutcome::Passed)),
+            self.count(|outcome| matches!(outcome, CheckOutcome::Failed(_))),
+            self.count(|outcome| matches!(outcome, CheckOutcome::Skipped)),
+        )?;
+        for (name, outcome) in &self.outcomes {
+            match outcome {
+                CheckOutcome::Passed => writeln!(f, "  ok      {}", name)?,
+                CheckOutcome::Failed(error) => writeln!(f, "  FAILED  {}: {}", name, error)?,
+                CheckOutcome::Skipped => writeln!(f, "  skipped {}", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn map_name(check: &str) -> String {
+    format!("conformance_{}", check)
+}
+
+fn remove_map(db: &Nitrite, name: &str) -> NitriteResult<()> {
+    let store = db.store();
+    if store.has_map(name)? {
+        store.remove_map(name)?;
+    }
+    Ok(())
+}
+
+fn open_map(db: &Nitrite, check: &str) -> NitriteResult<NitriteMap> {
+    db.store().open_map(&map_name(check))
+}
+
+fn ensure(condition: bool, message: impl FnOnce() -> String) -> NitriteResult<()> {
+    if condition {
+        return Ok(());
+    }
+    let message = message();
+    log::error!("{}", message);
+    Err(NitriteError::new(&message, ErrorKind::ValidationError))
+}
+
+fn ensure_eq<T: PartialEq + Debug>(actual: T, expected: T, what: &str) -> NitriteResult<()> {
+    ensure(actual == expected, || {
+        format!("{}: expected {:?}, got {:?}", what, expected, actual)
+    })
+}
+
+fn keys_of(map: &NitriteMap) -> NitriteResult<Vec<Value>> {
+    map.keys()?.collect()
+}
+
+/// Keys of several types, in the order a store must keep them.
+fn ordered_keys() -> NitriteResult<Vec<Vec<Value>>> {
+    let ids = [1_000_000_000_000_000_001u64, 1_000_000_000_000_000_002, 2_000_000_000_000_000_000]
+        .iter()
+        .map(|id| NitriteId::create_id(*id).map(Value::NitriteId))
+        .collect::<NitriteResult<Vec<_>>>()?;
+
+    Ok(vec![
+        [i64::MIN, -1000, -1, 0, 1, 255, 256, 70000, i64::MAX]
+            .iter()
+            .map(|i| Value::I64(*i))
+            .collect(),
+        [-1.5e10f64, -2.25, -0.5, 0.0, 0.5, 3.75, 1e12]
+            .iter()
+            .map(|f| Value::F64(*f))
+            .collect(),
+        ["", "A", "Z", "a", "aa", "ab", "b", "é"]
+            .iter()
+            .map(|s| Value::String(s.to_string()))
+            .collect(),
+        ids,
+    ])
+}
+
+fn check_put_and_get(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "put_and_get")?;
+    ensure_eq(map.is_empty()?, true, "a new map is empty")?;
+    ensure_eq(map.get(&Value::from("missing"))?, None, "get of a missing key")?;
+
+    map.put(Value::from("a"), Value::from(1))?;
+    map.put(Value::from("b"), Value::from(2))?;
+    ensure_eq(map.get(&Value::from("a"))?, Some(Value::from(1)), "get after put")?;
+    ensure_eq(map.contains_key(&Value::from("b"))?, true, "contains_key after put")?;
+    ensure_eq(map.contains_key(&Value::from("c"))?, false, "contains_key of a missing key")?;
+
+    map.put(Value::from("a"), Value::from(10))?;
+    ensure_eq(map.get(&Value::from("a"))?, Some(Value::from(10)), "get after overwrite")?;
+    ensure_eq(map.size()?, 2, "size after an overwrite")?;
+    ensure_eq(map.is_empty()?, false, "is_empty of a filled map")
+}
+
+fn check_remove(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "remove")?;
+    map.put(Value::from(1), Value::from("one"))?;
+    map.put(Value::from(2), Value::from("two"))?;
+
+    ensure_eq(map.remove(&Value::from(1))?, Some(Value::from("one")), "remove of a key")?;
+    ensure_eq(map.remove(&Value::from(1))?, None, "remove of a removed key")?;
+    ensure_eq(map.remove(&Value::from(3))?, None, "remove of a missing key")?;
+    ensure_eq(map.get(&Value::from(1))?, None, "get after remove")?;
+    ensure_eq(map.size()?, 1, "size after remove")?;
+
+    map.remove(&Value::from(2))?;
+    ensure_eq(map.is_empty()?, true, "is_empty after removing every key")?;
+    ensure_eq(map.first_key()?, None, "first_key of an emptied map")
+}
+
+fn check_put_if_absent(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "put_if_absent")?;
+    ensure_eq(
+        map.put_if_absent(Value::from("k"), Value::from("first"))?,
+        None,
+        "put_if_absent of a new key",
+    )?;
+    ensure_eq(
+        map.put_if_absent(Value::from("k"), Value::from("second"))?,
+        Some(Value::from("first")),
+        "put_if_absent of an existing key",
+    )?;
+    ensure_eq(map.get(&Value::from("k"))?, Some(Value::from("first")), "value kept by put_if_absent")?;
+    ensure_eq(map.size()?, 1, "size after put_if_absent")
+}
+
+fn check_put_all_and_clear(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "put_all_and_clear")?;
+    map.put(Value::from(0), Value::from("old"))?;
+    let entries: Vec<(Value, Value)> = (0..100)
+        .map(|i| (Value::from(i), Value::from(format!("value{}", i))))
+        .collect();
+    map.put_all(entries)?;
+
+    ensure_eq(map.size()?, 100, "size after put_all")?;
+    ensure_eq(map.get(&Value::from(0))?, Some(Value::from("value0")), "put_all overwrites")?;
+    ensure_eq(map.get(&Value::from(99))?, Some(Value::from("value99")), "get after put_all")?;
+
+    map.clear()?;
+    ensure_eq(map.size()?, 0, "size after clear")?;
+    ensure_eq(map.entries()?.count(), 0, "entries after clear")?;
+    map.put(Value::from(1), Value::from("again"))?;
+    ensure_eq(map.size()?, 1, "put after clear")
+}
+
+fn check_value_round_trip(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let mut document = Document::new();
+    document.put("name", "nested")?;
+    document.put("tags", Value::Array(vec![Value::from("a"), Value::Null]))?;
+    let mut map_value = BTreeMap::new();
+    map_value.insert(Value::from("key"), Value::from(1));
+
+    let values = vec![
+        Value::Null,
+        Value::Bool(true),
+        Value::I8(-8),
+        Value::U8(8),
+        Value::I16(-16),
+        Value::U16(16),
+        Value::I32(-32),
+        Value::U32(32),
+        Value::I64(i64::MIN),
+        Value::U64(u64::MAX),
+        Value::I128(i128::MIN),
+        Value::U128(u128::MAX),
+        Value::F32(1.5),
+        Value::F64(-2.25),
+        Value::Char('é'),
+        Value::String("text with \"quotes\" and\nlines".to_string()),
+        Value::String(String::new()),
+        Value::Document(document),
+        Value::Array(vec![Value::from(1), Value::from("two"), Value::Array(vec![])]),
+        Value::Map(map_value),
+        Value::NitriteId(NitriteId::create_id(1_000_000_000_000_000_007)?),
+        Value::Bytes(vec![0, 1, 254, 255]),
+        Value::Bytes(Vec::new()),
+    ];
+
+    let map = open_map(db, "value_round_trip")?;
+    for (i, value) in values.iter().enumerate() {
+        map.put(Value::from(i as i64), value.clone())?;
+    }
+    for (i, value) in values.into_iter().enumerate() {
+        let stored = map.get(&Value::from(i as i64))?;
+        ensure_eq(stored, Some(value), "value read back")?;
+    }
+    Ok(())
+}
+
+fn check_key_order(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "key_order")?;
+    for keys in ordered_keys()? {
+        map.clear()?;
+        // insert out of order: odd positions first, then even ones backwards
+        let shuffled = keys
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .chain(keys.iter().step_by(2).rev());
+        for key in shuffled {
+            map.put(key.clone(), Value::from(format!("{}", key)))?;
+        }
+
+        ensure_eq(keys_of(&map)?, keys.clone(), "keys in ascending order")?;
+        let entries: Vec<Value> = map
+            .entries()?
+            .map(|entry| entry.map(|(key, _)| key))
+            .collect::<NitriteResult<_>>()?;
+        ensure_eq(entries, keys.clone(), "entries in ascending order")?;
+        let values: Vec<Value> = map.values()?.collect::<NitriteResult<_>>()?;
+        let expected: Vec<Value> = keys.iter().map(|key| Value::from(format!("{}", key))).collect();
+        ensure_eq(values, expected, "values in key order")?;
+
+        let reversed: Vec<Value> = map
+            .reverse_entries()?
+            .map(|entry| entry.map(|(key, _)| key))
+            .collect::<NitriteResult<_>>()?;
+        let expected: Vec<Value> = keys.iter().rev().cloned().collect();
+        ensure_eq(reversed, expected, "reverse entries in descending order")?;
+    }
+    Ok(())
+}
+
+fn check_key_navigation(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "key_navigation")?;
+    ensure_eq(map.first_key()?, None, "first_key of an empty map")?;
+    ensure_eq(map.last_key()?, None, "last_key of an empty map")?;
+    ensure_eq(map.higher_key(&Value::from(0))?, None, "higher_key in an empty map")?;
+
+    for i in [10i64, 20, 30] {
+        map.put(Value::from(i), Value::from(i))?;
+    }
+    let key = |i: i64| Some(Value::from(i));
+    ensure_eq(map.first_key()?, key(10), "first_key")?;
+    ensure_eq(map.last_key()?, key(30), "last_key")?;
+
+    ensure_eq(map.higher_key(&Value::from(20))?, key(30), "higher_key of a present key")?;
+    ensure_eq(map.higher_key(&Value::from(25))?, key(30), "higher_key of a missing key")?;
+    ensure_eq(map.higher_key(&Value::from(30))?, None, "higher_key of the last key")?;
+    ensure_eq(map.ceiling_key(&Value::from(20))?, key(20), "ceiling_key of a present key")?;
+    ensure_eq(map.ceiling_key(&Value::from(5))?, key(10), "ceiling_key below the first key")?;
+    ensure_eq(map.ceiling_key(&Value::from(31))?, None, "ceiling_key above the last key")?;
+
+    ensure_eq(map.lower_key(&Value::from(20))?, key(10), "lower_key of a present key")?;
+    ensure_eq(map.lower_key(&Value::from(25))?, key(20), "lower_key of a missing key")?;
+    ensure_eq(map.lower_key(&Value::from(10))?, None, "lower_key of the first key")?;
+    ensure_eq(map.floor_key(&Value::from(20))?, key(20), "floor_key of a present key")?;
+    ensure_eq(map.floor_key(&Value::from(35))?, key(30), "floor_key above the last key")?;
+    ensure_eq(map.floor_key(&Value::from(9))?, None, "floor_key below the first key")
+}
+
+fn check_map_lifecycle(_: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let store = db.store();
+    let name = map_name("map_lifecycle");
+    ensure_eq(store.has_map(&name)?, false, "has_map before the map is opened")?;
+
+    {
+        let map = store.open_map(&name)?;
+        map.put(Value::from("k"), Value::from("v"))?;
+        ensure_eq(store.has_map(&name)?, true, "has_map of an opened map")?;
+        ensure_eq(map.get_name()?, name.clone(), "map name")?;
+
+        let again = store.open_map(&name)?;
+        ensure_eq(again.get(&Value::from("k"))?, Some(Value::from("v")), "a map opened twice shares its data")?;
+    }
+
+    // like a dropped collection, the map is removed once its handles are gone
+    store.remove_map(&name)?;
+    ensure_eq(store.has_map(&name)?, false, "has_map of a removed map")?;
+    let reopened = store.open_map(&name)?;
+    ensure_eq(reopened.size()?, 0, "a removed map opens empty")
+}
+
+fn check_concurrent_writes(suite: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "concurrent_writes")?;
+    let per_thread = 250i64;
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..suite.threads as i64)
+            .map(|thread| {
+                let map = map.clone();
+                scope.spawn(move || -> NitriteResult<()> {
+                    for i in 0..per_thread {
+                        let key = thread * per_thread + i;
+                        map.put(Value::from(key), Value::from(thread))?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        join_all(handles)
+    })?;
+
+    let total = suite.threads as i64 * per_thread;
+    ensure_eq(map.size()?, total as u64, "size after concurrent writes")?;
+    let expected: Vec<Value> = (0..total).map(Value::from).collect();
+    ensure_eq(keys_of(&map)?, expected, "keys after concurrent writes")
+}
+
+fn check_concurrent_reads(suite: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let map = open_map(db, "concurrent_reads")?;
+    for i in 0..500i64 {
+        map.put(Value::from(i), Value::from(i))?;
+    }
+
+    std::thread::scope(|scope| {
+        let writer = {
+            let map = map.clone();
+            scope.spawn(move || -> NitriteResult<()> {
+                for i in 500..1000i64 {
+                    map.put(Value::from(i), Value::from(i))?;
+                    map.remove(&Value::from(i - 500))?;
+                }
+                Ok(())
+            })
+        };
+        let readers: Vec<_> = (1..suite.threads)
+            .map(|_| {
+                let map = map.clone();
+                scope.spawn(move || -> NitriteResult<()> {
+                    for _ in 0..10 {
+                        let keys = keys_of(&map)?;
+                        ensure(keys.windows(2).all(|pair| pair[0] < pair[1]), || {
+                            "keys read during writes are not in ascending order".to_string()
+                        })?;
+                        for key in keys.iter().take(20) {
+                            // a key may be removed between listing and reading it
+                            if let Some(value) = map.get(key)? {
+                                ensure_eq(&value, key, "value read during writes")?;
+                            }
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        let mut handles = vec![writer];
+        handles.extend(readers);
+        join_all(handles)
+    })?;
+
+    let expected: Vec<Value> = (500..1000i64).map(Value::from).collect();
+    ensure_eq(keys_of(&map)?, expected, "keys after concurrent reads and writes")
+}
+
+fn check_reopen_keeps_data(suite: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    // one map per key type, as a store only orders keys of the same type
+    let groups = ordered_keys()?;
+    let names: Vec<String> = (0..groups.len())
+        .map(|i| format!("{}_{}", map_name("reopen_keeps_data"), i))
+        .collect();
+    for (name, keys) in names.iter().zip(&groups) {
+        remove_map(db, name)?;
+        let map = db.store().open_map(name)?;
+        for (i, key) in keys.iter().enumerate().rev() {
+            map.put(key.clone(), Value::from(i as i64))?;
+        }
+    }
+    db.commit()?;
+    db.close()?;
+
+    let db = suite.open()?;
+    let result = (|| {
+        for (name, keys) in names.iter().zip(&groups) {
+            let map = db.store().open_map(name)?;
+            ensure_eq(map.size()?, keys.len() as u64, "size after reopening")?;
+            for (i, key) in keys.iter().enumerate() {
+                ensure_eq(map.get(key)?, Some(Value::from(i as i64)), "value after reopening")?;
+            }
+            ensure_eq(keys_of(&map)?, keys.clone(), "key order after reopening")?;
+        }
+        names.iter().try_for_each(|name| remove_map(&db, name))
+    })();
+    result.and(db.close())
+}
+
+fn check_reopen_keeps_removals(suite: &StoreConformance, db: &Nitrite) -> NitriteResult<()> {
+    let name = map_name("reopen_keeps_removals");
+    let removed = map_name("reopen_keeps_removals_dropped");
+    remove_map(db, &removed)?;
+    {
+        let map = db.store().open_map(&name)?;
+        for i in 0..10i64 {
+            map.put(Value::from(i), Value::from(i))?;
+        }
+        for i in (0..10i64).step_by(2) {
+            map.remove(&Value::from(i))?;
+        }
+        map.put(Value::from(1), Value::from("updated"))?;
+
+        let dropped = db.store().open_map(&removed)?;
+        dropped.put(Value::from("k"), Value::from("v"))?;
+        db.store().remove_map(&removed)?;
+        db.commit()?;
+    }
+    db.close()?;
+
+    let db = suite.open()?;
+    let result = (|| {
+        let map = db.store().open_map(&name)?;
+        let expected: Vec<Value> = [1i64, 3, 5, 7, 9].into_iter().map(Value::from).collect();
+        ensure_eq(keys_of(&map)?, expected, "keys after removals and reopening")?;
+        ensure_eq(map.get(&Value::from(1))?, Some(Value::from("updated")), "overwritten value after reopening")?;
+        ensure_eq(db.store().has_map(&removed)?, false, "a removed map after reopening")?;
+        remove_map(&db, &name)
+    })();
+    result.and(db.close())
+}
+
+fn join_all(
+    handles: Vec<std::thread::ScopedJoinHandle<'_, NitriteResult<()>>>,
+) -> NitriteResult<()> {
+    let mut result = Ok(());
+    for handle in handles {
+        let outcome = handle.join().unwrap_or_else(|_| {
+            log::error!("A conformance thread panicked");
+            Err(NitriteError::new("A conformance thread panicked", ErrorKind::InternalError))
+        });
+        result = result.and(outcome);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_store_conforms() {
+        let report = StoreConformance::new(|| Nitrite::builder().open_or_create(None, None)).run();
+        assert!(report.is_success(), "{}", report);
+        assert_eq!(report.outcomes().len(), StoreConformance::check_names().len());
+        assert!(report.to_string().contains("skipped reopen_keeps_data"));
+    }
+
+    #[test]
+    fn test_run_check_named() {
+        let suite = StoreConformance::new(|| Nitrite::builder().open_or_create(None, None));
+        assert!(suite.run_check_named("key_order").is_ok());
+        let error = suite.run_check_named("no_such_check").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_report_lists_failures() {
+        let suite = StoreConformance::new(|| {
+            Err(NitriteError::new("cannot open", ErrorKind::IOError))
+        });
+        let report = suite.run();
+        assert!(!report.is_success());
+        assert_eq!(report.failures().len(), CHECKS.len() - 2);
+        assert!(report.to_string().starts_with("store conformance: 0 passed, 10 failed, 2 skipped"));
+    }
+}
@@ -0,0 +1,524 @@
+//! Disk space monitoring for the stores keeping their data in files.
+//!
+//! A [`DiskWatchdog`] checks the free space of the volume holding the store at a fixed
+//! interval, on the scheduler of the database. When the free space falls below one of
+//! its warning thresholds it publishes [`StoreEvents::DiskSpaceLow`] to the store
+//! listeners, and [`StoreEvents::DiskSpaceRecovered`] once it rises back above it. Below
+//! the read-only threshold it suspends the writes of the database, which then fail with
+//! [`ErrorKind::DiskFull`] while reads keep working, so that a volume filling up does not
+//! leave the store half written. Writes resume once the free space is back above the
+//! resume threshold.
+//!
+//! The watchdog is configured with `NitriteBuilder::disk_watchdog` and checks once when
+//! the database opens; [`Nitrite::disk_monitor`](crate::nitrite::Nitrite::disk_monitor)
+//! returns the [`DiskMonitor`] running it.
+//!
+//! This module requires the `disk_watchdog` feature.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let db = Nitrite::builder()
+//!     .load_module(storage_module)
+//!     .disk_watchdog(
+//!         DiskWatchdog::new()
+//!             .interval(Duration::from_secs(5))
+//!             .warn_below(2 << 30)
+//!             .warn_below(1 << 30)
+//!             .read_only_below(256 << 20),
+//!     )
+//!     .open_or_create(None, None)?;
+//! ```
+
+use crate::common::Scheduler;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::store::{NitriteStore, StoreEvents};
+use parking_lot::Mutex;
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Interval between two checks of the free space, unless configured otherwise.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+type DiskProbe = Arc<dyn Fn(&Path) -> NitriteResult<DiskUsage> + Send + Sync>;
+
+/// The space of the volume holding a path, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    available: u64,
+    total: u64,
+}
+
+impl DiskUsage {
+    /// Creates a usage with `available` bytes free out of `total`.
+    pub fn new(available: u64, total: u64) -> Self {
+        DiskUsage { available, total }
+    }
+
+    /// Reads the usage of the volume holding `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IOError` if the file system cannot be queried, for example because
+    /// `path` does not exist.
+    pub fn of(path: &Path) -> NitriteResult<DiskUsage> {
+        match fs4::statvfs(path) {
+            Ok(stats) => Ok(DiskUsage::new(stats.available_space(), stats.total_space())),
+            Err(e) => {
+                log::error!("Failed to read the disk usage of {}: {}", path.display(), e);
+                Err(NitriteError::new(
+                    &format!("Failed to read the disk usage of {}: {}", path.display(), e),
+                    ErrorKind::IOError,
+                ))
+            }
+        }
+    }
+
+    /// Returns the bytes available to the process.
+    pub fn available(&self) -> u64 {
+        self.available
+    }
+
+    /// Returns the size of the volume.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the bytes not available, including those reserved for other users.
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+}
+
+/// Configuration of the disk space checks of a database.
+///
+/// Without thresholds the watchdog only records the usage, see
+/// [`DiskMonitor::last_usage`]. The path checked is the file path of the store unless
+/// set with [`path`](DiskWatchdog::path).
+#[derive(Clone)]
+pub struct DiskWatchdog {
+    interval: Duration,
+    warn_below: Vec<u64>,
+    read_only_below: Option<u64>,
+    resume_above: Option<u64>,
+    path: Option<PathBuf>,
+    probe: Option<DiskProbe>,
+}
+
+impl Default for DiskWatchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for DiskWatchdog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiskWatchdog")
+            .field("interval", &self.interval)
+            .field("warn_below", &self.warn_below)
+            .field("read_only_below", &self.read_only_below)
+            .field("resume_above", &self.resume_above)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl DiskWatchdog {
+    /// Creates a watchdog checking every 10 seconds, without thresholds.
+    pub fn new() -> Self {
+        DiskWatchdog {
+            interval: DEFAULT_CHECK_INTERVAL,
+            warn_below: Vec::new(),
+            read_only_below: None,
+            resume_above: None,
+            path: None,
+            probe: None,
+        }
+    }
+
+    /// Sets the time between two checks.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Adds a warning threshold: an event is published when the free space falls below
+    /// `bytes`, and another when it rises back above. Can be called once per threshold.
+    pub fn warn_below(mut self, bytes: u64) -> Self {
+        if let Err(index) = self.warn_below.binary_search_by(|it| bytes.cmp(it)) {
+            self.warn_below.insert(index, bytes);
+        }
+        self
+    }
+
+    /// Suspends the writes of the database when the free space falls below `bytes`.
+    pub fn read_only_below(mut self, bytes: u64) -> Self {
+        self.read_only_below = Some(bytes);
+        self
+    }
+
+    /// Resumes the suspended writes once the free space is `bytes` or more. Defaults to
+    /// twice the read-only threshold, so the writes do not flap around it.
+    pub fn resume_above(mut self, bytes: u64) -> Self {
+        self.resume_above = Some(bytes);
+        self
+    }
+
+    /// Checks the volume holding `path` instead of the one holding the store.
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Reads the usage with `probe` instead of querying the file system, for example to
+    /// simulate a volume filling up in tests.
+    pub fn probe<F>(mut self, probe: F) -> Self
+    where
+        F: Fn(&Path) -> NitriteResult<DiskUsage> + Send + Sync + 'static,
+    {
+        self.probe = Some(Arc::new(probe));
+        self
+    }
+
+    /// Returns the time between two checks.
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the warning thresholds, largest first.
+    pub fn get_warn_below(&self) -> &[u64] {
+        &self.warn_below
+    }
+
+    /// Returns the threshold below which writes are suspended.
+    pub fn get_read_only_below(&self) -> Option<u64> {
+        self.read_only_below
+    }
+
+    /// Returns the free space at which suspended writes resume.
+    pub fn get_resume_above(&self) -> Option<u64> {
+        self.resume_above
+            .or_else(|| self.read_only_below.map(|bytes| bytes.saturating_mul(2)))
+    }
+
+    /// Checks that the configuration can run.
+    pub(crate) fn validate(&self) -> NitriteResult<()> {
+        if self.interval.is_zero() {
+            log::error!("Disk watchdog interval must be greater than zero");
+            return Err(NitriteError::new(
+                "Disk watchdog interval must be greater than zero",
+                ErrorKind::ValidationError,
+            ));
+        }
+        if let (Some(read_only), Some(resume)) = (self.read_only_below, self.resume_above) {
+            if resume < read_only {
+                log::error!(
+                    "Disk watchdog resume threshold {} is below its read-only threshold {}",
+                    resume,
+                    read_only
+                );
+                return Err(NitriteError::new(
+                    &format!(
+                        "Disk watchdog resume threshold {} is below its read-only threshold {}",
+                        resume, read_only
+                    ),
+                    ErrorKind::ValidationError,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the store once, then every interval on `scheduler`.
+    pub(crate) fn start(
+        &self,
+        store: NitriteStore,
+        suspension: WriteSuspension,
+        scheduler: &Scheduler,
+    ) -> NitriteResult<DiskMonitor> {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => PathBuf::from(store.store_config()?.file_path()),
+        };
+        if path.as_os_str().is_empty() {
+            log::error!("Disk watchdog needs a path, the store keeps no files");
+            return Err(NitriteError::new(
+                "Disk watchdog needs a path, the store keeps no files",
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        let monitor = DiskMonitor {
+            inner: Arc::new(DiskMonitorInner {
+                watchdog: self.clone(),
+                path,
+                store,
+                suspension,
+                state: Mutex::new(MonitorState {
+                    low: vec![false; self.warn_below.len()],
+                    last: None,
+                }),
+            }),
+        };
+        monitor.check()?;
+
+        let scheduled = monitor.clone();
+        scheduler.schedule(self.interval, move || {
+            if let Err(e) = scheduled.check() {
+                log::error!("Disk watchdog check failed: {}", e);
+            }
+        });
+        Ok(monitor)
+    }
+}
+
+/// The running disk watchdog of a database.
+#[derive(Clone)]
+pub struct DiskMonitor {
+    inner: Arc<DiskMonitorInner>,
+}
+
+struct DiskMonitorInner {
+    watchdog: DiskWatchdog,
+    path: PathBuf,
+    store: NitriteStore,
+    suspension: WriteSuspension,
+    state: Mutex<MonitorState>,
+}
+
+struct MonitorState {
+    /// Whether the free space is below each warning threshold
+    low: Vec<bool>,
+    last: Option<DiskUsage>,
+}
+
+impl DiskMonitor {
+    /// Reads the usage now, instead of waiting for the next check, and publishes the
+    /// events of the thresholds it crossed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IOError` if the usage cannot be read.
+    pub fn check(&self) -> NitriteResult<DiskUsage> {
+        let inner = &self.inner;
+        let watchdog = &inner.watchdog;
+        let usage = match &watchdog.probe {
+            Some(probe) => probe(&inner.path)?,
+            None => DiskUsage::of(&inner.path)?,
+        };
+        let available = usage.available();
+
+        let mut events = Vec::new();
+        {
+            let mut state = inner.state.lock();
+            for (threshold, low) in watchdog.warn_below.iter().zip(state.low.iter_mut()) {
+                let threshold = *threshold;
+                if available < threshold && !*low {
+                    *low = true;
+                    events.push(StoreEvents::DiskSpaceLow { available, threshold });
+                } else if available >= threshold && *low {
+                    *low = false;
+                    events.push(StoreEvents::DiskSpaceRecovered { available, threshold });
+                }
+            }
+
+            if let Some(read_only) = watchdog.read_only_below {
+                let resume = watchdog.get_resume_above().unwrap_or(read_only);
+                if available < read_only && inner.suspension.suspend() {
+                    log::warn!(
+                        "Suspending writes, {} bytes left under {}",
+                        available,
+                        inner.path.display()
+                    );
+                    events.push(StoreEvents::WritesSuspended { available });
+                } else if available >= resume && inner.suspension.resume() {
+                    log::info!(
+                        "Resuming writes, {} bytes free under {}",
+                        available,
+                        inner.path.display()
+                    );
+                    events.push(StoreEvents::WritesResumed { available });
+                }
+            }
+            state.last = Some(usage);
+        }
+
+        for event in events {
+            if let Err(e) = inner.store.alert(event) {
+                log::warn!("Failed to publish a disk watchdog event: {}", e);
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Returns the usage read by the last check.
+    pub fn last_usage(&self) -> Option<DiskUsage> {
+        self.inner.state.lock().last
+    }
+
+    /// Returns `true` while the writes of the database are suspended.
+    pub fn writes_suspended(&self) -> bool {
+        self.inner.suspension.is_suspended()
+    }
+
+    /// Returns the path whose volume is checked.
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// Returns the configuration of the watchdog.
+    pub fn watchdog(&self) -> &DiskWatchdog {
+        &self.inner.watchdog
+    }
+}
+
+/// Switch turning the writes of a store off, shared by the store and its watchdog.
+#[derive(Clone, Default)]
+pub(crate) struct WriteSuspension {
+    suspended: Arc<AtomicBool>,
+}
+
+impl WriteSuspension {
+    /// Suspends the writes, returns `false` if they were suspended already.
+    pub(crate) fn suspend(&self) -> bool {
+        !self.suspended.swap(true, Ordering::AcqRel)
+    }
+
+    /// Resumes the writes, returns `false` if they were not suspended.
+    pub(crate) fn resume(&self) -> bool {
+        self.suspended.swap(false, Ordering::AcqRel)
+    }
+
+    pub(crate) fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::Acquire)
+    }
+
+    /// Fails while the writes are suspended.
+    pub(crate) fn check(&self) -> NitriteResult<()> {
+        if self.is_suspended() {
+            return Err(NitriteError::new(
+                "Writes are suspended, the volume of the store is nearly full",
+                ErrorKind::DiskFull,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use crate::nitrite::Nitrite;
+    use crate::store::{NitriteStoreProvider, StoreEventListener};
+    use std::sync::atomic::AtomicU64;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn open(watchdog: DiskWatchdog) -> NitriteResult<Nitrite> {
+        Nitrite::builder().disk_watchdog(watchdog).open_or_create(None, None)
+    }
+
+    fn simulated(free: &Arc<AtomicU64>) -> DiskWatchdog {
+        let free = free.clone();
+        DiskWatchdog::new()
+            .interval(Duration::from_secs(3600))
+            .path(std::env::temp_dir())
+            .probe(move |_| Ok(DiskUsage::new(free.load(Ordering::SeqCst), 1024 * MIB)))
+    }
+
+    fn record_events(db: &Nitrite) -> Arc<Mutex<Vec<StoreEvents>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        db.store()
+            .subscribe(StoreEventListener::new(move |info| {
+                recorded.lock().push(info.event());
+                Ok(())
+            }))
+            .unwrap();
+        events
+    }
+
+    #[test]
+    fn test_thresholds_publish_events_once() {
+        let free = Arc::new(AtomicU64::new(500 * MIB));
+        let db = open(simulated(&free).warn_below(100 * MIB).warn_below(200 * MIB)).unwrap();
+        let monitor = db.disk_monitor().unwrap().unwrap();
+        assert_eq!(monitor.watchdog().get_warn_below(), &[200 * MIB, 100 * MIB]);
+        let events = record_events(&db);
+
+        free.store(150 * MIB, Ordering::SeqCst);
+        monitor.check().unwrap();
+        monitor.check().unwrap();
+        free.store(50 * MIB, Ordering::SeqCst);
+        monitor.check().unwrap();
+        free.store(300 * MIB, Ordering::SeqCst);
+        assert_eq!(monitor.check().unwrap().available(), 300 * MIB);
+
+        assert_eq!(
+            *events.lock(),
+            vec![
+                StoreEvents::DiskSpaceLow { available: 150 * MIB, threshold: 200 * MIB },
+                StoreEvents::DiskSpaceLow { available: 50 * MIB, threshold: 100 * MIB },
+                StoreEvents::DiskSpaceRecovered { available: 300 * MIB, threshold: 200 * MIB },
+                StoreEvents::DiskSpaceRecovered { available: 300 * MIB, threshold: 100 * MIB },
+            ]
+        );
+        assert!(!monitor.writes_suspended());
+        db.close().unwrap();
+    }
+
+    #[test]
+    fn test_low_space_suspends_writes() {
+        let free = Arc::new(AtomicU64::new(500 * MIB));
+        let db = open(simulated(&free).read_only_below(64 * MIB)).unwrap();
+        let monitor = db.disk_monitor().unwrap().unwrap();
+        assert_eq!(monitor.watchdog().get_resume_above(), Some(128 * MIB));
+        let events = record_events(&db);
+        let collection = db.collection("test").unwrap();
+        collection.insert(doc! { a: 1 }).unwrap();
+
+        free.store(10 * MIB, Ordering::SeqCst);
+        monitor.check().unwrap();
+        assert!(monitor.writes_suspended());
+        assert!(db.store().is_read_only().unwrap());
+        let error = collection.insert(doc! { a: 2 }).err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::DiskFull);
+        assert_eq!(collection.size().unwrap(), 1);
+
+        // above the read-only threshold but below the resume one, writes stay suspended
+        free.store(100 * MIB, Ordering::SeqCst);
+        monitor.check().unwrap();
+        assert!(monitor.writes_suspended());
+        free.store(200 * MIB, Ordering::SeqCst);
+        monitor.check().unwrap();
+        assert!(!monitor.writes_suspended());
+        collection.insert(doc! { a: 3 }).unwrap();
+
+        assert_eq!(
+            *events.lock(),
+            vec![
+                StoreEvents::WritesSuspended { available: 10 * MIB },
+                StoreEvents::WritesResumed { available: 200 * MIB },
+            ]
+        );
+        db.close().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_watchdog_fails_open() {
+        let error = open(DiskWatchdog::new().read_only_below(10).resume_above(5)).err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+
+        // the in-memory store keeps no files to check
+        let error = open(DiskWatchdog::new().warn_below(10)).err().unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        assert!(db.disk_monitor().unwrap().is_none());
+        db.close().unwrap();
+    }
+}
@@ -22,6 +22,10 @@ use std::sync::Arc;
 /// - **Commit**: Fired when a transaction is committed to persistent storage
 /// - **Closing**: Fired when the store is about to be closed (before close completes)
 /// - **Closed**: Fired after the store has been fully closed
+/// - **DiskSpaceLow** / **DiskSpaceRecovered**: Fired by the disk watchdog when the free
+///   space under the store path falls below, or rises back above, one of its thresholds
+/// - **WritesSuspended** / **WritesResumed**: Fired by the disk watchdog when it switches
+///   the database to read-only because the volume is nearly full, and back
 ///
 /// # Characteristics
 ///
@@ -41,6 +45,7 @@ use std::sync::Arc;
 ///         StoreEvents::Commit => println!("Data committed"),
 ///         StoreEvents::Closing => println!("Database closing"),
 ///         StoreEvents::Closed => println!("Database closed"),
+///         other => println!("Disk watchdog: {:?}", other),
 ///     }
 ///     Ok(())
 /// });
@@ -52,6 +57,14 @@ pub enum StoreEvents {
     Commit,
     Closing,
     Closed,
+    /// Free space under the store path fell below `threshold` bytes.
+    DiskSpaceLow { available: u64, threshold: u64 },
+    /// Free space under the store path rose back above `threshold` bytes.
+    DiskSpaceRecovered { available: u64, threshold: u64 },
+    /// Writes were suspended, with `available` bytes left under the store path.
+    WritesSuspended { available: u64 },
+    /// Writes were resumed, with `available` bytes free under the store path.
+    WritesResumed { available: u64 },
 }
 
 /// Context information provided with each store event.
@@ -0,0 +1,419 @@
+//! Fault injection for the maps of a store, to test failure handling.
+//!
+//! A [`FaultInjector`] makes chosen [`NitriteMap`](crate::store::NitriteMap) operations
+//! fail or slow down on demand: the nth matching operation, the operations on a key, or
+//! operations picked at random from a seed, so that retries in an application and the
+//! recovery paths of the engine can be tested deterministically. Every map of the
+//! database goes through the injector, including the maps of the indexes and of the
+//! internal collections, whatever the store backend.
+//!
+//! The injector of a database is returned by
+//! [`Nitrite::fault_injector`](crate::nitrite::Nitrite::fault_injector). It does nothing
+//! until a [`Fault`] is injected, and a fault stays in effect until it is removed.
+//!
+//! This module requires the `fault_injection` feature, which is meant for tests only.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let faults = db.fault_injector();
+//! // the third write to the collection fails
+//! let id = faults.inject(Fault::fail().on_map("orders").on_writes().nth(3));
+//! ...
+//! assert_eq!(faults.hits(id), 1);
+//! faults.clear();
+//! ```
+
+use crate::common::Key;
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use parking_lot::{Mutex, RwLock};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An operation of a [`NitriteMap`](crate::store::NitriteMap) a fault can target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapOperation {
+    /// `get`.
+    Get,
+    /// `contains_key`.
+    ContainsKey,
+    /// `put`.
+    Put,
+    /// `put_all`.
+    PutAll,
+    /// `put_if_absent`.
+    PutIfAbsent,
+    /// `remove`.
+    Remove,
+    /// `remove_range`.
+    RemoveRange,
+    /// `clear`.
+    Clear,
+    /// `set_attributes`.
+    SetAttributes,
+    /// `keys`, `values`, `entries` and `reverse_entries`.
+    Iterate,
+    /// `first_key`, `last_key`, `higher_key`, `ceiling_key`, `lower_key` and `floor_key`.
+    Navigate,
+    /// `size` and `is_empty`.
+    Size,
+}
+
+impl MapOperation {
+    /// The operations that change a map.
+    pub const WRITES: [MapOperation; 7] = [
+        MapOperation::Put,
+        MapOperation::PutAll,
+        MapOperation::PutIfAbsent,
+        MapOperation::Remove,
+        MapOperation::RemoveRange,
+        MapOperation::Clear,
+        MapOperation::SetAttributes,
+    ];
+
+    /// Returns `true` if the operation changes the map.
+    pub fn is_write(&self) -> bool {
+        MapOperation::WRITES.contains(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FaultAction {
+    Fail(ErrorKind),
+    Delay(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum MapMatcher {
+    Any,
+    Name(String),
+    Prefix(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Trigger {
+    Always,
+    Nth(u64),
+    Times(u64),
+    Probability(f64),
+}
+
+/// A fault to inject: what happens, to which operations and when.
+///
+/// A fault matches every operation of every map unless narrowed down with the `on_*`
+/// and [`matching_key`](Fault::matching_key) methods, and fires on every operation it
+/// matches unless given a trigger with [`nth`](Fault::nth), [`times`](Fault::times) or
+/// [`probability`](Fault::probability).
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // every read of one document takes 200 ms
+/// Fault::delay(Duration::from_millis(200)).on(MapOperation::Get).matching_key(id);
+/// // a tenth of the writes to the indexes of a collection fail, the same ones on every run
+/// Fault::fail().on_map_prefix("$nitrite_index|orders|").on_writes().probability(0.1).seed(7);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fault {
+    action: FaultAction,
+    map: MapMatcher,
+    operations: HashSet<MapOperation>,
+    key: Option<Key>,
+    trigger: Trigger,
+    seed: u64,
+}
+
+impl Fault {
+    /// Creates a fault failing the operations with an `IOError`.
+    pub fn fail() -> Self {
+        Fault::new(FaultAction::Fail(ErrorKind::IOError))
+    }
+
+    /// Creates a fault making the operations wait for `delay` before they run.
+    pub fn delay(delay: Duration) -> Self {
+        Fault::new(FaultAction::Delay(delay))
+    }
+
+    fn new(action: FaultAction) -> Self {
+        Fault {
+            action,
+            map: MapMatcher::Any,
+            operations: HashSet::new(),
+            key: None,
+            trigger: Trigger::Always,
+            seed: 0,
+        }
+    }
+
+    /// Fails the operations with an error of `kind` instead of an `IOError`. Does nothing
+    /// for a delay.
+    pub fn with_error(mut self, kind: ErrorKind) -> Self {
+        if let FaultAction::Fail(_) = self.action {
+            self.action = FaultAction::Fail(kind);
+        }
+        self
+    }
+
+    /// Matches only the operations on the map named `name`. The map of a collection has
+    /// the name of the collection.
+    pub fn on_map(mut self, name: &str) -> Self {
+        self.map = MapMatcher::Name(name.to_string());
+        self
+    }
+
+    /// Matches only the operations on the maps whose name starts with `prefix`.
+    pub fn on_map_prefix(mut self, prefix: &str) -> Self {
+        self.map = MapMatcher::Prefix(prefix.to_string());
+        self
+    }
+
+    /// Matches `operation`, in addition to the operations already given.
+    pub fn on(mut self, operation: MapOperation) -> Self {
+        self.operations.insert(operation);
+        self
+    }
+
+    /// Matches the operations that change a map, in addition to the operations already
+    /// given.
+    pub fn on_writes(mut self) -> Self {
+        self.operations.extend(MapOperation::WRITES);
+        self
+    }
+
+    /// Matches only the operations on `key`: reads, writes and removals of the key, and
+    /// `put_all` with an entry for it.
+    pub fn matching_key(mut self, key: impl Into<Key>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Fires only on the nth matching operation, counting from 1.
+    pub fn nth(mut self, n: u64) -> Self {
+        self.trigger = Trigger::Nth(n.max(1));
+        self
+    }
+
+    /// Fires on the first `n` matching operations.
+    pub fn times(mut self, n: u64) -> Self {
+        self.trigger = Trigger::Times(n);
+        self
+    }
+
+    /// Fires on each matching operation with the probability `probability`, between 0
+    /// and 1. The operations are picked by a generator started from the
+    /// [`seed`](Fault::seed), so a test fails the same operations on every run.
+    pub fn probability(mut self, probability: f64) -> Self {
+        self.trigger = Trigger::Probability(probability.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Sets the seed of the generator picking the operations of a probabilistic fault,
+    /// 0 by default.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn matches(&self, map_name: &str, operation: MapOperation, keys: &[&Key]) -> bool {
+        let map_matches = match &self.map {
+            MapMatcher::Any => true,
+            MapMatcher::Name(name) => name == map_name,
+            MapMatcher::Prefix(prefix) => map_name.starts_with(prefix.as_str()),
+        };
+        map_matches
+            && (self.operations.is_empty() || self.operations.contains(&operation))
+            && self.key.as_ref().is_none_or(|key| keys.contains(&key))
+    }
+}
+
+/// Identifies a fault injected with [`FaultInjector::inject`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaultId(u64);
+
+struct ActiveFault {
+    id: FaultId,
+    fault: Fault,
+    matched: AtomicU64,
+    hits: AtomicU64,
+    rng: Mutex<StdRng>,
+}
+
+impl ActiveFault {
+    /// Counts a matching operation and returns `true` if the fault fires on it.
+    fn fires(&self) -> bool {
+        let matched = self.matched.fetch_add(1, Ordering::SeqCst) + 1;
+        let fires = match self.fault.trigger {
+            Trigger::Always => true,
+            Trigger::Nth(n) => matched == n,
+            Trigger::Times(n) => matched <= n,
+            Trigger::Probability(probability) => self.rng.lock().gen_bool(probability),
+        };
+        if fires {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+        }
+        fires
+    }
+}
+
+/// The faults injected into the maps of a database. See the [module](self) docs.
+///
+/// The injector is cheap to clone; clones share the faults.
+#[derive(Clone, Default)]
+pub struct FaultInjector {
+    inner: Arc<FaultInjectorInner>,
+}
+
+#[derive(Default)]
+struct FaultInjectorInner {
+    faults: RwLock<Vec<Arc<ActiveFault>>>,
+    next_id: AtomicU64,
+}
+
+impl FaultInjector {
+    /// Injects `fault`, in effect until it is removed.
+    pub fn inject(&self, fault: Fault) -> FaultId {
+        let id = FaultId(self.inner.next_id.fetch_add(1, Ordering::SeqCst));
+        let rng = Mutex::new(StdRng::seed_from_u64(fault.seed));
+        self.inner.faults.write().push(Arc::new(ActiveFault {
+            id,
+            fault,
+            matched: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            rng,
+        }));
+        id
+    }
+
+    /// Removes a fault. Returns `false` if it was already removed.
+    pub fn remove(&self, id: FaultId) -> bool {
+        let mut faults = self.inner.faults.write();
+        let before = faults.len();
+        faults.retain(|active| active.id != id);
+        faults.len() != before
+    }
+
+    /// Removes every fault.
+    pub fn clear(&self) {
+        self.inner.faults.write().clear();
+    }
+
+    /// Returns the number of operations a fault fired on, 0 once it is removed.
+    pub fn hits(&self, id: FaultId) -> u64 {
+        self.find(id).map_or(0, |active| active.hits.load(Ordering::SeqCst))
+    }
+
+    /// Returns the number of operations a fault matched, whether it fired or not, 0 once
+    /// it is removed.
+    pub fn matched(&self, id: FaultId) -> u64 {
+        self.find(id).map_or(0, |active| active.matched.load(Ordering::SeqCst))
+    }
+
+    /// Returns `true` if a fault is injected.
+    pub(crate) fn is_active(&self) -> bool {
+        !self.inner.faults.read().is_empty()
+    }
+
+    /// Applies the faults matching an operation on `keys` of a map: waits for the delays
+    /// and returns the error of the first failure.
+    pub(crate) fn check(&self, map_name: &str, operation: MapOperation, keys: &[&Key]) -> NitriteResult<()> {
+        let fired: Vec<Arc<ActiveFault>> = {
+            let faults = self.inner.faults.read();
+            if faults.is_empty() {
+                return Ok(());
+            }
+            faults
+                .iter()
+                .filter(|active| active.fault.matches(map_name, operation, keys) && active.fires())
+                .cloned()
+                .collect()
+        };
+
+        // the delays are waited for outside the lock, so faults can be removed meanwhile
+        for active in fired {
+            match &active.fault.action {
+                FaultAction::Delay(delay) => std::thread::sleep(*delay),
+                FaultAction::Fail(kind) => {
+                    log::error!("Injected fault on {:?} of map {}", operation, map_name);
+                    return Err(NitriteError::new(
+                        &format!("Injected fault on {:?} of map {}", operation, map_name),
+                        kind.clone(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find(&self, id: FaultId) -> Option<Arc<ActiveFault>> {
+        self.inner.faults.read().iter().find(|active| active.id == id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Value;
+
+    fn check(faults: &FaultInjector, map_name: &str, operation: MapOperation, key: i64) -> bool {
+        faults.check(map_name, operation, &[&Value::I64(key)]).is_err()
+    }
+
+    #[test]
+    fn test_nth_and_times() {
+        let faults = FaultInjector::default();
+        let third = faults.inject(Fault::fail().on_map("orders").on_writes().nth(3));
+        let outcomes: Vec<bool> = (0..5).map(|n| check(&faults, "orders", MapOperation::Put, n)).collect();
+        assert_eq!(outcomes, vec![false, false, true, false, false]);
+        // other maps and reads are not counted
+        assert!(!check(&faults, "users", MapOperation::Put, 1));
+        assert!(!check(&faults, "orders", MapOperation::Get, 1));
+        assert_eq!((faults.matched(third), faults.hits(third)), (5, 1));
+
+        assert!(faults.remove(third));
+        assert!(!faults.remove(third));
+        let twice = faults.inject(Fault::fail().times(2).with_error(ErrorKind::DiskFull));
+        let error = faults.check("any", MapOperation::Size, &[]).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::DiskFull);
+        assert!(check(&faults, "any", MapOperation::Get, 1));
+        assert!(!check(&faults, "any", MapOperation::Get, 1));
+        assert_eq!(faults.hits(twice), 2);
+    }
+
+    #[test]
+    fn test_matching_key_and_prefix() {
+        let faults = FaultInjector::default();
+        faults.inject(Fault::fail().on_map_prefix("$nitrite").matching_key(7i64));
+        assert!(check(&faults, "$nitrite_index|orders", MapOperation::Remove, 7));
+        assert!(!check(&faults, "$nitrite_index|orders", MapOperation::Remove, 8));
+        assert!(!check(&faults, "orders", MapOperation::Remove, 7));
+        // an operation without a key never matches a fault on a key
+        assert!(faults.check("$nitrite_meta", MapOperation::Clear, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_probability_is_seeded() {
+        let run = |seed: u64| {
+            let faults = FaultInjector::default();
+            faults.inject(Fault::fail().probability(0.5).seed(seed));
+            (0..64).map(|n| check(&faults, "m", MapOperation::Put, n)).collect::<Vec<_>>()
+        };
+        let first = run(42);
+        assert_eq!(first, run(42));
+        let failures = first.iter().filter(|failed| **failed).count();
+        assert!(failures > 0 && failures < 64);
+    }
+
+    #[test]
+    fn test_delay() {
+        let faults = FaultInjector::default();
+        let id = faults.inject(Fault::delay(Duration::from_millis(50)).on(MapOperation::Get));
+        let start = std::time::Instant::now();
+        assert!(!check(&faults, "m", MapOperation::Get, 1));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(faults.hits(id), 1);
+    }
+}
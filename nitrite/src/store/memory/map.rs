@@ -107,6 +107,10 @@ impl NitriteMapProvider for InMemoryMap {
         self.inner.put(key, value)
     }
 
+    fn remove_range(&self, start: &Key, end: &Key) -> NitriteResult<u64> {
+        self.inner.remove_range(start, end)
+    }
+
     fn size(&self) -> NitriteResult<u64> {
         self.inner.size()
     }
@@ -292,6 +296,22 @@ impl InMemoryMapInner {
         }
     }
 
+    pub(crate) fn remove_range(&self, start: &Key, end: &Key) -> NitriteResult<u64> {
+        self.check_opened()?;
+        if start >= end {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        for entry in self.backing_map.range((Included(start), Excluded(end))) {
+            // an entry removed concurrently is not counted twice
+            if entry.remove() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
     pub(crate) fn put(&self, key: Key, value: Value) -> NitriteResult<()> {
         self.check_opened()?;
         self.backing_map.insert(key, value);
@@ -474,6 +494,22 @@ mod tests {
         assert!(map.get(&key).unwrap().is_none());
     }
 
+    #[test]
+    fn test_remove_range() {
+        let map = create_test_map();
+        for i in 0..10 {
+            map.put(Key::from(i), Value::from(i)).unwrap();
+        }
+        assert_eq!(map.remove_range(&Key::from(2), &Key::from(5)).unwrap(), 3);
+        assert_eq!(map.size().unwrap(), 7);
+        assert!(map.get(&Key::from(2)).unwrap().is_none());
+        assert!(map.get(&Key::from(4)).unwrap().is_none());
+        assert_eq!(map.get(&Key::from(5)).unwrap(), Some(Value::from(5)));
+        assert_eq!(map.remove_range(&Key::from(2), &Key::from(5)).unwrap(), 0);
+        assert_eq!(map.remove_range(&Key::from(8), &Key::from(1)).unwrap(), 0);
+        assert_eq!(map.size().unwrap(), 7);
+    }
+
     #[test]
     fn test_put() {
         let map = create_test_map();
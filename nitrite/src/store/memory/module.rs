@@ -1,4 +1,4 @@
-use crate::common::{NitriteModule, NitritePlugin, PluginRegistrar, PluginRegistrarProvider};
+use crate::common::{ModuleInfo, NitriteModule, NitritePlugin, PluginRegistrar, PluginRegistrarProvider};
 use crate::errors::NitriteResult;
 use crate::store::memory::{InMemoryStore, InMemoryStoreConfig};
 use crate::store::{NitriteStore, StoreConfigProvider, StoreEventListener, StoreModule};
@@ -7,12 +7,25 @@ use crate::NitritePluginProvider;
 #[derive(Default)]
 pub struct InMemoryStoreModule {
     store_config: InMemoryStoreConfig,
+    store: Option<InMemoryStore>,
 }
 
 impl InMemoryStoreModule {
     pub fn new() -> InMemoryStoreModule {
         InMemoryStoreModule {
             store_config: InMemoryStoreConfig::new(),
+            store: None,
+        }
+    }
+
+    /// Creates a module that opens the database on an existing store.
+    ///
+    /// The caller keeps a handle to the store, e.g. to load it from a dump before the
+    /// database is opened and to dump it again before the database is closed.
+    pub fn from_store(store: InMemoryStore) -> InMemoryStoreModule {
+        InMemoryStoreModule {
+            store_config: InMemoryStoreConfig::new(),
+            store: Some(store),
         }
     }
 
@@ -32,11 +45,18 @@ impl NitriteModule for InMemoryStoreModule {
         let store = self.get_store()?;
         plugin_registrar.register_store_plugin(store)
     }
+
+    fn info(&self) -> ModuleInfo {
+        ModuleInfo::new("in-memory-store", env!("CARGO_PKG_VERSION")).store()
+    }
 }
 
 impl StoreModule for InMemoryStoreModule {
     fn get_store(&self) -> NitriteResult<NitriteStore> {
-        let store = InMemoryStore::new(self.store_config.clone());
+        let store = match &self.store {
+            Some(store) => store.clone(),
+            None => InMemoryStore::new(self.store_config.clone()),
+        };
         Ok(NitriteStore::new(store))
     }
 }
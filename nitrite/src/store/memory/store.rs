@@ -1,5 +1,9 @@
 use super::InMemoryMap;
 use crate::common::{NitritePlugin, SubscriberRef, COLLECTION_CATALOG};
+#[cfg(feature = "memory_dump")]
+use crate::common::Value;
+#[cfg(feature = "memory_dump")]
+use crate::errors::{ErrorKind, NitriteError};
 use crate::errors::NitriteResult;
 use crate::nitrite_config::NitriteConfig;
 use crate::store::memory::config::InMemoryStoreConfig;
@@ -12,6 +16,12 @@ use dashmap::DashMap;
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, OnceLock};
+#[cfg(feature = "memory_dump")]
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
 
 /// In-memory implementation of a Nitrite database store.
 ///
@@ -56,6 +66,33 @@ impl InMemoryStore {
     }
 }
 
+#[cfg(feature = "memory_dump")]
+impl InMemoryStore {
+    /// Writes the entries of every open map of this store to a file.
+    ///
+    /// Commit and finish pending writes first, the maps are read one after another and
+    /// not as of a single point in time. The dump must be taken before the database is
+    /// closed, as closing the store discards its maps. The file is replaced only once the
+    /// complete dump has been written.
+    ///
+    /// # Arguments
+    /// * `path` - The file to write the dump to
+    pub fn dump_to<P: AsRef<Path>>(&self, path: P) -> NitriteResult<()> {
+        self.inner.dump_to(path.as_ref())
+    }
+
+    /// Loads a dump written by [`InMemoryStore::dump_to`] into this store.
+    ///
+    /// Call it on a new store, before a database is opened on it. A map of the dump
+    /// replaces the entries of the store's map with the same name.
+    ///
+    /// # Arguments
+    /// * `path` - The file to read the dump from
+    pub fn load_from<P: AsRef<Path>>(&self, path: P) -> NitriteResult<()> {
+        self.inner.load_from(path.as_ref(), self.clone())
+    }
+}
+
 impl NitritePluginProvider for InMemoryStore {
     fn initialize(&self, config: NitriteConfig) -> NitriteResult<()> {
         self.inner.initialize(config)
@@ -141,6 +178,10 @@ impl NitriteStoreProvider for InMemoryStore {
         self.close_map(name)
     }
 
+    fn map_names(&self) -> NitriteResult<HashSet<String>> {
+        self.inner.map_names()
+    }
+
     fn subscribe(&self, listener: StoreEventListener) -> NitriteResult<Option<SubscriberRef>> {
         self.inner.subscribe(listener)
     }
@@ -160,6 +201,10 @@ impl NitriteStoreProvider for InMemoryStore {
     fn store_catalog(&self) -> NitriteResult<StoreCatalog> {
         self.inner.store_catalog(self.clone())
     }
+
+    fn alert(&self, event: StoreEvents) -> NitriteResult<()> {
+        self.inner.alert(event)
+    }
 }
 
 struct InMemoryStoreInner {
@@ -288,6 +333,16 @@ impl InMemoryStoreInner {
         }
     }
 
+    pub(crate) fn map_names(&self) -> NitriteResult<HashSet<String>> {
+        let mut names = HashSet::with_capacity(self.map_registry.len());
+        for entry in self.map_registry.iter() {
+            if !entry.value().is_closed()? {
+                names.insert(entry.key().clone());
+            }
+        }
+        Ok(names)
+    }
+
     pub(crate) fn close_map(&self, name: &str) -> NitriteResult<()> {
         // Use idiomatic Rust pattern matching
         // DashMap::remove() returns Option<(K, V)>
@@ -313,6 +368,106 @@ impl InMemoryStoreInner {
     pub(crate) fn store_config(&self) -> NitriteResult<StoreConfig> {
         Ok(StoreConfig::new(self.store_config.clone()))
     }
+
+    #[cfg(feature = "memory_dump")]
+    fn dump_to(&self, path: &Path) -> NitriteResult<()> {
+        if self.closed.load(Ordering::Relaxed) {
+            log::error!("Cannot dump a closed in-memory store");
+            return Err(NitriteError::new(
+                "Cannot dump a closed in-memory store",
+                ErrorKind::StoreAlreadyClosed,
+            ));
+        }
+
+        let mut maps = Vec::with_capacity(self.map_registry.len());
+        let open_maps: Vec<InMemoryMap> = self
+            .map_registry
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        for map in open_maps {
+            if map.is_closed()? {
+                continue;
+            }
+            let entries = map.entries()?.collect::<NitriteResult<Vec<_>>>()?;
+            maps.push(MapDump {
+                name: map.get_name()?,
+                entries,
+            });
+        }
+        let dump = StoreDump {
+            version: DUMP_VERSION,
+            maps,
+        };
+
+        let temp_path = path.with_extension("dump.tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            writer.write_all(DUMP_MAGIC)?;
+            bincode::serde::encode_into_std_write(&dump, &mut writer, bincode::config::legacy())
+                .map_err(|err| dump_error(&err.to_string()))?;
+            writer.flush()?;
+        }
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "memory_dump")]
+    fn load_from(&self, path: &Path, store: InMemoryStore) -> NitriteResult<()> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; DUMP_MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| dump_error("missing header"))?;
+        if &magic != DUMP_MAGIC {
+            return Err(dump_error("not an in-memory store dump"));
+        }
+
+        let dump: StoreDump =
+            bincode::serde::decode_from_std_read(&mut reader, bincode::config::legacy())
+                .map_err(|err| dump_error(&err.to_string()))?;
+        if dump.version != DUMP_VERSION {
+            return Err(dump_error(&format!("unsupported version {}", dump.version)));
+        }
+
+        for map_dump in dump.maps {
+            let map = self.open_map(&map_dump.name, store.clone())?;
+            map.clear()?;
+            for (key, value) in map_dump.entries {
+                map.put(key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "memory_dump")]
+const DUMP_MAGIC: &[u8; 8] = b"NO2MEMDB";
+
+#[cfg(feature = "memory_dump")]
+const DUMP_VERSION: u32 = 1;
+
+#[cfg(feature = "memory_dump")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoreDump {
+    version: u32,
+    maps: Vec<MapDump>,
+}
+
+#[cfg(feature = "memory_dump")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MapDump {
+    name: String,
+    entries: Vec<(Value, Value)>,
+}
+
+#[cfg(feature = "memory_dump")]
+fn dump_error(reason: &str) -> NitriteError {
+    log::error!("Invalid in-memory store dump: {}", reason);
+    NitriteError::new(
+        &format!("Invalid in-memory store dump: {}", reason),
+        ErrorKind::FileCorrupted,
+    )
 }
 
 #[cfg(test)]
@@ -605,4 +760,59 @@ mod tests {
         assert!(store.close().is_ok());
         assert_eq!(store.inner.map_registry.len(), 0);
     }
+
+    #[cfg(feature = "memory_dump")]
+    #[test]
+    fn test_dump_and_load_round_trip() {
+        let path = std::env::temp_dir().join(format!("nitrite_dump_{}", uuid::Uuid::new_v4()));
+        let store = create_store();
+        let map = store.open_map("dumped").unwrap();
+        map.put(Value::from("key"), Value::from(vec![Value::I32(1), Value::Null]))
+            .unwrap();
+        map.put(Value::I64(2), Value::from("value")).unwrap();
+        map.put(Value::I64(3), Value::raw_json(r#"{"a": [1]}"#).unwrap()).unwrap();
+        store.open_map("empty").unwrap();
+        store.dump_to(&path).unwrap();
+
+        let loaded = create_store();
+        loaded.open_map("dumped").unwrap().put(Value::I32(9), Value::Null).unwrap();
+        loaded.load_from(&path).unwrap();
+        let map = loaded.open_map("dumped").unwrap();
+        assert_eq!(map.size().unwrap(), 3);
+        assert_eq!(
+            map.get(&Value::from("key")).unwrap(),
+            Some(Value::from(vec![Value::I32(1), Value::Null]))
+        );
+        assert_eq!(map.get(&Value::I64(2)).unwrap(), Some(Value::from("value")));
+        assert_eq!(map.get(&Value::I64(3)).unwrap(), Some(Value::raw_json(r#"{"a": [1]}"#).unwrap()));
+        assert!(loaded.has_map("empty").unwrap());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "memory_dump")]
+    #[test]
+    fn test_load_rejects_foreign_file() {
+        let path = std::env::temp_dir().join(format!("nitrite_dump_{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"not a dump").unwrap();
+
+        let store = create_store();
+        let err = store.load_from(&path).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::FileCorrupted);
+
+        let err = store.load_from(path.with_extension("missing")).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::FileNotFound);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(feature = "memory_dump")]
+    #[test]
+    fn test_dump_closed_store_fails() {
+        let path = std::env::temp_dir().join(format!("nitrite_dump_{}", uuid::Uuid::new_v4()));
+        let store = create_store();
+        store.close().unwrap();
+        assert!(store.dump_to(&path).is_err());
+        assert!(!path.exists());
+    }
 }
@@ -40,7 +40,7 @@ impl Metadata for MapMeta {
             names_vec.push(Value::String(name.clone()));
         }
         let names = Value::Array(names_vec);
-        doc.put(TAG_MAP_METADATA, names).unwrap();
+        doc.put_literal(TAG_MAP_METADATA.to_string(), names);
         doc
     }
 }
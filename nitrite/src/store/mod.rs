@@ -30,22 +30,56 @@
 //!
 //! The store layer supports event listeners for monitoring store state changes,
 //! useful for debugging and metrics collection.
+//!
+//! # Conformance
+//!
+//! With the `conformance` feature, `conformance::StoreConformance` runs a battery of
+//! checks that any store implementation can use to validate itself.
+//!
+//! # Disk space
+//!
+//! With the `disk_watchdog` feature, `disk::DiskWatchdog` checks the free space under the
+//! store path, publishes store events when it runs low and suspends the writes before
+//! the volume fills up.
+//!
+//! # Fault injection
+//!
+//! With the `fault_injection` feature, `fault::FaultInjector` makes chosen map operations
+//! fail or wait, to test how applications and the engine handle store failures.
+//!
+//! # Projections
+//!
+//! While a projected query reads its documents, `is_field_requested` tells a store which
+//! fields the query needs, so a store keeping large fields apart from their documents can
+//! leave the others unread.
 
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "disk_watchdog")]
+pub mod disk;
 mod event;
+#[cfg(feature = "fault_injection")]
+pub mod fault;
 mod iters;
 pub mod memory;
 mod meta;
 mod nitrite_map;
 mod nitrite_store;
+mod projection;
+mod snapshot;
 mod store_catalog;
 mod store_config;
 mod store_module;
+mod switchable;
 
 pub use event::*;
 pub use iters::*;
 pub use meta::*;
 pub use nitrite_map::*;
 pub use nitrite_store::*;
+pub use projection::{is_field_requested, with_projection};
+pub use snapshot::*;
 pub use store_catalog::*;
 pub use store_config::*;
-pub use store_module::*;
\ No newline at end of file
+pub use store_module::*;
+pub(crate) use switchable::SwitchableStore;
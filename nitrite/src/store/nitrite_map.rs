@@ -18,7 +18,8 @@ use std::sync::Arc;
 ///
 /// # Key Methods
 /// - **Basic Operations**: `put()`, `get()`, `remove()`, `contains_key()`
-/// - **Bulk Operations**: `put_all()` for atomic batch inserts
+/// - **Bulk Operations**: `put_all()` for atomic batch inserts, `remove_range()` for key
+///   range deletion
 /// - **Iteration**: `keys()`, `values()`, `entries()` with bidirectional traversal
 /// - **Navigation**: `first_key()`, `last_key()`, `higher_key()`, `ceiling_key()`, `lower_key()`, `floor_key()`
 /// - **Lifecycle**: `open()`, `close()`, `clear()`, `dispose()`
@@ -151,6 +152,38 @@ pub trait NitriteMapProvider: AttributeAware + Send + Sync {
         Ok(())
     }
 
+    /// Removes every entry whose key lies in `start..end`, the start included and the end
+    /// excluded, and returns the number of entries removed.
+    ///
+    /// # Arguments
+    /// * `start` - The lowest key to remove
+    /// * `end` - The key the removal stops at
+    ///
+    /// # Returns
+    /// * `Ok(count)` with the number of entries removed
+    /// * `Err(NitriteError)` if the operation fails
+    ///
+    /// # Default Implementation
+    /// The default implementation walks the keys of the range with `ceiling_key()` and
+    /// `higher_key()` and calls `remove()` for each of them. Backends that can drop a key
+    /// range directly should override it.
+    fn remove_range(&self, start: &Key, end: &Key) -> NitriteResult<u64> {
+        let mut keys = Vec::new();
+        let mut next = self.ceiling_key(start)?;
+        while let Some(key) = next.filter(|key| key < end) {
+            next = self.higher_key(&key)?;
+            keys.push(key);
+        }
+
+        let mut removed = 0;
+        for key in keys {
+            if self.remove(&key)?.is_some() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
     /// Returns the number of entries in the map.
     ///
     /// # Returns
@@ -508,6 +541,14 @@ mod tests {
         assert_eq!(map.remove(&Key::from("key2")).unwrap(), None);
     }
 
+    #[test]
+    fn test_remove_range_walks_the_keys() {
+        let map = NitriteMap::new(MockNitriteMap);
+        assert_eq!(map.remove_range(&Key::from("key1"), &Key::from("key2")).unwrap(), 1);
+        assert_eq!(map.remove_range(&Key::from("key0"), &Key::from("key1")).unwrap(), 0);
+        assert_eq!(map.remove_range(&Key::from("key1"), &Key::from("key1")).unwrap(), 0);
+    }
+
     #[test]
     fn test_put() {
         let map = NitriteMap::new(MockNitriteMap);
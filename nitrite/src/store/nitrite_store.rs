@@ -1,7 +1,10 @@
 use crate::common::{NitritePlugin, SubscriberRef};
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::nitrite_config::NitriteConfig;
-use crate::store::{NitriteMap, StoreCatalog, StoreConfig, StoreEventListener};
+use crate::store::{
+    CopiedStoreSnapshot, NitriteMap, StoreCatalog, StoreConfig, StoreEventListener, StoreEvents,
+    StoreSnapshot,
+};
 use crate::NitritePluginProvider;
 use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
@@ -219,6 +222,24 @@ pub trait NitriteStoreProvider: NitritePluginProvider + Send + Sync {
     /// * `Err(NitriteError)` if the operation fails
     fn remove_map(&self, name: &str) -> NitriteResult<()>;
 
+    /// Lists the names of every map of the store.
+    ///
+    /// The list includes the internal maps such as the catalog, the metadata and the index
+    /// maps, but not the maps a store keeps for itself. Migrating a database to another
+    /// store copies these maps. The default implementation fails, so a store that does
+    /// not override it cannot be migrated from.
+    ///
+    /// # Returns
+    /// * `Ok(HashSet<String>)` with the names of the maps
+    /// * `Err(NitriteError)` if the store cannot list its maps
+    fn map_names(&self) -> NitriteResult<HashSet<String>> {
+        log::error!("The store cannot list its maps");
+        Err(NitriteError::new(
+            "The store cannot list its maps",
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
     /// Subscribes to store events.
     ///
     /// The listener will be called whenever store state changes occur.
@@ -265,6 +286,38 @@ pub trait NitriteStoreProvider: NitritePluginProvider + Send + Sync {
     /// * `Ok(StoreCatalog)` with store catalog information
     /// * `Err(NitriteError)` if the operation fails
     fn store_catalog(&self) -> NitriteResult<StoreCatalog>;
+
+    /// Opens a read-only, point-in-time view over the given maps.
+    ///
+    /// Writes made after the snapshot is opened are not visible through it. The default
+    /// implementation copies each map into a [`CopiedStoreSnapshot`], which is consistent
+    /// per map only; stores with multi-version reads should override this to provide a
+    /// view that is consistent across all maps.
+    ///
+    /// # Arguments
+    /// * `map_names` - The maps to capture; names of maps that do not exist are ignored
+    ///
+    /// # Returns
+    /// * `Ok(StoreSnapshot)` over the captured maps
+    /// * `Err(NitriteError)` if the snapshot could not be opened
+    fn open_snapshot(&self, map_names: &HashSet<String>) -> NitriteResult<StoreSnapshot> {
+        Ok(StoreSnapshot::new(CopiedStoreSnapshot::capture(self, map_names)?))
+    }
+
+    /// Publishes an event to the listeners of the store.
+    ///
+    /// Used for the events raised outside the store, like those of the disk watchdog.
+    /// The default implementation drops the event; stores with listeners should override it.
+    ///
+    /// # Arguments
+    /// * `event` - The event to publish
+    ///
+    /// # Returns
+    /// * `Ok(())` once the listeners have been called
+    /// * `Err(NitriteError)` if publishing failed
+    fn alert(&self, _event: StoreEvents) -> NitriteResult<()> {
+        Ok(())
+    }
 }
 
 
@@ -361,9 +414,13 @@ impl NitriteStore {
         // failure to `run_atomic` by returning `Err`, so a failing `op` discards the scope.
         let mut captured: Option<NitriteResult<T>> = None;
         let run_result = self.inner.run_atomic(&mut || {
-            let op = op
-                .take()
-                .expect("run_atomic invoked the atomic operation more than once");
+            let Some(op) = op.take() else {
+                log::error!("Store invoked the atomic operation more than once");
+                return Err(NitriteError::new(
+                    "Store invoked the atomic operation more than once",
+                    ErrorKind::InvalidOperation,
+                ));
+            };
             let result = op();
             let signal = match &result {
                 Ok(_) => Ok(()),
@@ -381,9 +438,14 @@ impl NitriteStore {
             // `op` succeeded but the commit itself failed: surface the commit error.
             (Err(e), Some(Ok(_))) => Err(e),
             // `op` never ran (should not happen with the default/Fjall providers).
-            (run_result, None) => run_result.map(|()| {
-                unreachable!("run_atomic returned without invoking the operation")
-            }),
+            (run_result, None) => {
+                run_result?;
+                log::error!("Store returned without invoking the atomic operation");
+                Err(NitriteError::new(
+                    "Store returned without invoking the atomic operation",
+                    ErrorKind::InvalidOperation,
+                ))
+            }
         }
     }
 }
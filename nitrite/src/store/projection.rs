@@ -0,0 +1,104 @@
+use crate::common::ReadExecutor;
+use crate::FIELD_SEPARATOR;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+thread_local! {
+    /// The fields needed by the projected query reading on the current thread.
+    static CURRENT_PROJECTION: RefCell<Option<ReadProjection>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone)]
+struct ReadProjection {
+    map_name: String,
+    fields: Arc<[String]>,
+}
+
+/// Runs `f` with the reads of the map named `map_name` limited to `fields`.
+///
+/// Projected queries read their documents this way. A store that keeps some fields of its
+/// documents apart can skip reading those that are not asked for, see
+/// [`is_field_requested`].
+pub fn with_projection<T>(map_name: &str, fields: &Arc<[String]>, f: impl FnOnce() -> T) -> T {
+    let projection = ReadProjection {
+        map_name: map_name.to_string(),
+        fields: fields.clone(),
+    };
+    let previous = CURRENT_PROJECTION.with(|current| current.borrow_mut().replace(projection));
+    let result = f();
+    CURRENT_PROJECTION.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Returns `true` if a document read from the map named `map_name` on the current
+/// thread needs its `field`.
+///
+/// Every field is needed, unless the read is made for a projection of the documents:
+/// then only the projected fields, their embedded fields and the documents embedding them
+/// are. Stores may use this to leave out the fields they keep apart from their documents.
+pub fn is_field_requested(map_name: &str, field: &str) -> bool {
+    CURRENT_PROJECTION.with(|current| match &*current.borrow() {
+        Some(projection) if projection.map_name == map_name => {
+            FIELD_SEPARATOR.read_with(|separator| {
+                projection
+                    .fields
+                    .iter()
+                    .any(|requested| overlaps(requested, field, separator))
+            })
+        }
+        _ => true,
+    })
+}
+
+/// Returns `true` if one of the field paths is the other or embedded in it.
+fn overlaps(first: &str, second: &str, separator: &str) -> bool {
+    let (shorter, longer) = if first.len() <= second.len() {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    match longer.strip_prefix(shorter) {
+        Some("") => true,
+        Some(rest) => rest.starts_with(separator),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_field_requested_without_projection() {
+        assert!(is_field_requested("books", "content"));
+    }
+
+    #[test]
+    fn test_projection_limits_fields_of_its_map() {
+        let fields: Arc<[String]> = Arc::from(vec!["title".to_string(), "meta.author".to_string()]);
+        with_projection("books", &fields, || {
+            assert!(is_field_requested("books", "title"));
+            assert!(is_field_requested("books", "meta"));
+            assert!(is_field_requested("books", "meta.author.name"));
+            assert!(!is_field_requested("books", "content"));
+            assert!(!is_field_requested("books", "titles"));
+            assert!(!is_field_requested("books", "meta.pages"));
+            assert!(is_field_requested("authors", "content"));
+        });
+        assert!(is_field_requested("books", "content"));
+    }
+
+    #[test]
+    fn test_nested_projection_restores_outer() {
+        let outer: Arc<[String]> = Arc::from(vec!["title".to_string()]);
+        let inner: Arc<[String]> = Arc::from(vec!["content".to_string()]);
+        with_projection("books", &outer, || {
+            with_projection("books", &inner, || {
+                assert!(is_field_requested("books", "content"));
+                assert!(!is_field_requested("books", "title"));
+            });
+            assert!(is_field_requested("books", "title"));
+            assert!(!is_field_requested("books", "content"));
+        });
+    }
+}
@@ -0,0 +1,177 @@
+use crate::common::{Key, Value};
+use crate::errors::NitriteResult;
+use crate::store::{EntryIterator, EntryIteratorProvider, NitriteStoreProvider};
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Trait for read-only, point-in-time views over the maps of a store.
+///
+/// # Purpose
+/// A snapshot exposes the contents of a set of maps as they were when the snapshot was
+/// opened. Writes performed on the store afterwards are not visible through the snapshot,
+/// which lets long running readers (exports, ETL jobs) iterate a consistent view while the
+/// application keeps writing.
+///
+/// # Implementations
+/// - Stores with native multi-version reads (e.g. Fjall) implement this on top of a
+///   read transaction, so the view is consistent across all maps.
+/// - [`CopiedStoreSnapshot`] is the fallback used by
+///   [`NitriteStoreProvider::open_snapshot`]; it copies each map when the snapshot is
+///   opened.
+pub trait StoreSnapshotProvider: Send + Sync {
+    /// Iterates over the entries of a map as of the snapshot.
+    ///
+    /// # Arguments
+    /// * `map_name` - The name of the map to iterate
+    ///
+    /// # Returns
+    /// * `Ok(EntryIterator)` over the entries in key order; empty if the map was not
+    ///   captured by the snapshot
+    /// * `Err(NitriteError)` if the snapshot could not be read
+    fn entries(&self, map_name: &str) -> NitriteResult<EntryIterator>;
+}
+
+/// A read-only, point-in-time view over the maps of a store.
+///
+/// # Purpose
+/// Wraps a [`StoreSnapshotProvider`] implementation. Cloning is cheap and clones share the
+/// same underlying view, so a snapshot can be handed to several reader threads.
+#[derive(Clone)]
+pub struct StoreSnapshot {
+    inner: Arc<dyn StoreSnapshotProvider>,
+}
+
+impl StoreSnapshot {
+    /// Creates a new `StoreSnapshot` from a provider implementation.
+    pub fn new<T: StoreSnapshotProvider + 'static>(inner: T) -> Self {
+        StoreSnapshot { inner: Arc::new(inner) }
+    }
+}
+
+impl Deref for StoreSnapshot {
+    type Target = Arc<dyn StoreSnapshotProvider>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A snapshot that holds a copy of each captured map.
+///
+/// # Behavior
+/// Each map is copied in a single pass when the snapshot is opened. The copy of a single
+/// map reflects the map at the time it was read, but maps are read one after another, so
+/// concurrent writes spanning several maps may be partially visible. Stores that can offer
+/// a consistent cross-map view should implement [`StoreSnapshotProvider`] natively.
+pub struct CopiedStoreSnapshot {
+    maps: HashMap<String, Arc<Vec<(Key, Value)>>>,
+}
+
+impl CopiedStoreSnapshot {
+    /// Copies the given maps of `store` into a new snapshot.
+    ///
+    /// Maps that do not exist in the store are skipped.
+    pub fn capture<S: NitriteStoreProvider + ?Sized>(
+        store: &S,
+        map_names: &HashSet<String>,
+    ) -> NitriteResult<CopiedStoreSnapshot> {
+        let mut maps = HashMap::with_capacity(map_names.len());
+        for map_name in map_names {
+            if !store.has_map(map_name)? {
+                continue;
+            }
+
+            let map = store.open_map(map_name)?;
+            let entries = map.entries()?.collect::<NitriteResult<Vec<_>>>()?;
+            maps.insert(map_name.clone(), Arc::new(entries));
+        }
+        Ok(CopiedStoreSnapshot { maps })
+    }
+}
+
+impl StoreSnapshotProvider for CopiedStoreSnapshot {
+    fn entries(&self, map_name: &str) -> NitriteResult<EntryIterator> {
+        let entries = self.maps.get(map_name).cloned().unwrap_or_default();
+        Ok(EntryIterator::new(CopiedEntryProvider {
+            entries,
+            position: None,
+        }))
+    }
+}
+
+struct CopiedEntryProvider {
+    entries: Arc<Vec<(Key, Value)>>,
+    position: Option<usize>,
+}
+
+impl EntryIteratorProvider for CopiedEntryProvider {
+    fn next_entry(&mut self) -> Option<NitriteResult<(Key, Value)>> {
+        let next = self.position.map_or(0, |position| position + 1);
+        let entry = self.entries.get(next)?.clone();
+        self.position = Some(next);
+        Some(Ok(entry))
+    }
+
+    fn prev_entry(&mut self) -> Option<NitriteResult<(Key, Value)>> {
+        let prev = match self.position {
+            None => self.entries.len().checked_sub(1)?,
+            Some(position) => position.checked_sub(1)?,
+        };
+        let entry = self.entries[prev].clone();
+        self.position = Some(prev);
+        Some(Ok(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory::{InMemoryStore, InMemoryStoreConfig};
+
+    fn setup_store() -> InMemoryStore {
+        let store = InMemoryStore::new(InMemoryStoreConfig::new());
+        store.open_or_create().unwrap();
+        store
+    }
+
+    #[test]
+    fn test_copied_snapshot_is_isolated_from_writes() {
+        let store = setup_store();
+        let map = store.open_map("snapshot_map").unwrap();
+        map.put(Key::from("a"), Value::from(1)).unwrap();
+        map.put(Key::from("b"), Value::from(2)).unwrap();
+
+        let names = HashSet::from(["snapshot_map".to_string(), "missing".to_string()]);
+        let snapshot = StoreSnapshot::new(CopiedStoreSnapshot::capture(&store, &names).unwrap());
+
+        map.put(Key::from("c"), Value::from(3)).unwrap();
+        map.remove(&Key::from("a")).unwrap();
+
+        let keys: Vec<Key> = snapshot
+            .entries("snapshot_map")
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec![Key::from("a"), Key::from("b")]);
+        assert_eq!(snapshot.entries("missing").unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_copied_snapshot_reverse_iteration() {
+        let store = setup_store();
+        let map = store.open_map("snapshot_rev").unwrap();
+        map.put(Key::from(1), Value::from("x")).unwrap();
+        map.put(Key::from(2), Value::from("y")).unwrap();
+
+        let names = HashSet::from(["snapshot_rev".to_string()]);
+        let snapshot = CopiedStoreSnapshot::capture(&store, &names).unwrap();
+        let values: Vec<Value> = snapshot
+            .entries("snapshot_rev")
+            .unwrap()
+            .rev()
+            .map(|entry| entry.unwrap().1)
+            .collect();
+        assert_eq!(values, vec![Value::from("y"), Value::from("x")]);
+    }
+}
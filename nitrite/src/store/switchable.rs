@@ -0,0 +1,583 @@
+use crate::common::{
+    AttributeAware, Attributes, Key, NitritePlugin, SubscriberRef, Value, COLLECTION_CATALOG,
+    META_MAP_NAME,
+};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::nitrite_config::NitriteConfig;
+#[cfg(feature = "disk_watchdog")]
+use crate::store::disk::WriteSuspension;
+#[cfg(feature = "fault_injection")]
+use crate::store::fault::{FaultInjector, MapOperation};
+use crate::store::{
+    EntryIterator, KeyIterator, NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider,
+    StoreCatalog, StoreConfig, StoreEventListener, StoreEvents, StoreSnapshot, ValueIterator,
+};
+use crate::NitritePluginProvider;
+use dashmap::DashMap;
+use parking_lot::{RwLock, RwLockReadGuard};
+use std::collections::{HashMap, HashSet};
+use std::iter::Rev;
+use std::sync::Arc;
+
+/// Number of entries copied into the target store at once.
+const COPY_BATCH_SIZE: usize = 1024;
+
+/// A store whose backend can be replaced while the database is open.
+///
+/// Every store registered with the plugin manager is wrapped in a `SwitchableStore`. The
+/// maps it opens forward to the map with the same name in the current backend, so
+/// collections, indexes and every other holder of a map keep working when
+/// [`SwitchableStore::migrate_to`] moves the database to another backend.
+///
+/// Writes share a gate that a migration takes exclusively: while the maps are copied,
+/// reads are served by the current backend and writes wait for the switch. With the
+/// `disk_watchdog` feature, the writes fail instead while the watchdog suspends them.
+#[derive(Clone)]
+pub(crate) struct SwitchableStore {
+    inner: Arc<SwitchableStoreInner>,
+}
+
+struct SwitchableStoreInner {
+    current: RwLock<NitriteStore>,
+    maps: DashMap<String, SwitchableMap>,
+    gate: RwLock<()>,
+    #[cfg(feature = "fault_injection")]
+    faults: FaultInjector,
+    #[cfg(feature = "disk_watchdog")]
+    suspension: WriteSuspension,
+}
+
+impl SwitchableStore {
+    pub(crate) fn new(store: NitriteStore) -> Self {
+        SwitchableStore {
+            inner: Arc::new(SwitchableStoreInner {
+                current: RwLock::new(store),
+                maps: DashMap::new(),
+                gate: RwLock::new(()),
+                #[cfg(feature = "fault_injection")]
+                faults: FaultInjector::default(),
+                #[cfg(feature = "disk_watchdog")]
+                suspension: WriteSuspension::default(),
+            }),
+        }
+    }
+
+    /// Returns the backend the store currently uses.
+    pub(crate) fn current(&self) -> NitriteStore {
+        self.inner.current.read().clone()
+    }
+
+    /// Returns the faults injected into the maps of the store.
+    #[cfg(feature = "fault_injection")]
+    pub(crate) fn fault_injector(&self) -> FaultInjector {
+        self.inner.faults.clone()
+    }
+
+    /// Returns the switch suspending the writes of the store.
+    #[cfg(feature = "disk_watchdog")]
+    pub(crate) fn write_suspension(&self) -> WriteSuspension {
+        self.inner.suspension.clone()
+    }
+
+    /// Copies every map of the current backend into `target` and switches to it.
+    ///
+    /// `target` must be open and must not hold any of the maps. On success the previous
+    /// backend is returned, for the caller to close; on error the store keeps using it.
+    pub(crate) fn migrate_to(&self, target: NitriteStore) -> NitriteResult<NitriteStore> {
+        // in-flight writes finish first, later ones wait until the maps are switched
+        let _gate = self.inner.gate.write();
+        let source = self.current();
+
+        let mut names: Vec<String> = source.map_names()?.into_iter().collect();
+        // the attributes of the maps come first, so the target applies them to the entries
+        names.sort_by_key(|name| name != META_MAP_NAME);
+
+        for name in &names {
+            if target.has_map(name)? && !target.open_map(name)?.is_empty()? {
+                log::error!("Target store already holds the map {}", name);
+                return Err(NitriteError::new(
+                    &format!("Target store already holds the map {}", name),
+                    ErrorKind::ValidationError,
+                ));
+            }
+        }
+        for name in &names {
+            copy_map(&source, &target, name)?;
+        }
+        target.commit()?;
+
+        // open every target map before switching any, so a failure leaves all on the source
+        let mut switched = Vec::with_capacity(self.inner.maps.len());
+        for entry in self.inner.maps.iter() {
+            if !entry.value().is_closed()? {
+                switched.push((entry.value().clone(), target.open_map(entry.key())?));
+            }
+        }
+        *self.inner.current.write() = target;
+        for (map, target_map) in switched {
+            *map.inner.current.write() = target_map;
+        }
+        Ok(source)
+    }
+
+    /// Stops tracking a map closed or disposed through its own handle, so the registry
+    /// does not keep the backend map open.
+    fn forget(&self, map: &SwitchableMap) -> NitriteResult<()> {
+        let name = map.get_name()?;
+        self.inner
+            .maps
+            .remove_if(&name, |_, opened| Arc::ptr_eq(&opened.inner, &map.inner));
+        Ok(())
+    }
+
+    fn write_gate(&self) -> RwLockReadGuard<'_, ()> {
+        // writes nest inside atomic scopes, which hold the gate already
+        self.inner.gate.read_recursive()
+    }
+
+    /// Passes the write gate, unless the writes are suspended.
+    fn writable(&self) -> NitriteResult<RwLockReadGuard<'_, ()>> {
+        #[cfg(feature = "disk_watchdog")]
+        self.inner.suspension.check()?;
+        Ok(self.write_gate())
+    }
+}
+
+fn copy_map(source: &NitriteStore, target: &NitriteStore, name: &str) -> NitriteResult<()> {
+    let from = source.open_map(name)?;
+    let to = target.open_map(name)?;
+    let mut batch = Vec::with_capacity(COPY_BATCH_SIZE);
+    for entry in from.entries()? {
+        batch.push(entry?);
+        if batch.len() == COPY_BATCH_SIZE {
+            to.put_all(std::mem::replace(&mut batch, Vec::with_capacity(COPY_BATCH_SIZE)))?;
+        }
+    }
+    if !batch.is_empty() {
+        to.put_all(batch)?;
+    }
+    Ok(())
+}
+
+impl NitritePluginProvider for SwitchableStore {
+    fn initialize(&self, config: NitriteConfig) -> NitriteResult<()> {
+        self.current().initialize(config)
+    }
+
+    fn on_open(&self, config: &NitriteConfig) -> NitriteResult<()> {
+        self.current().on_open(config)
+    }
+
+    fn on_flush(&self) -> NitriteResult<()> {
+        self.current().on_flush()
+    }
+
+    fn on_close(&self) -> NitriteResult<()> {
+        self.current().on_close()
+    }
+
+    fn close(&self) -> NitriteResult<()> {
+        self.inner.maps.clear();
+        self.current().close()
+    }
+
+    fn as_plugin(&self) -> NitritePlugin {
+        NitritePlugin::new(self.clone())
+    }
+}
+
+impl NitriteStoreProvider for SwitchableStore {
+    fn open_or_create(&self) -> NitriteResult<()> {
+        self.current().open_or_create()
+    }
+
+    fn is_closed(&self) -> NitriteResult<bool> {
+        self.current().is_closed()
+    }
+
+    fn get_collection_names(&self) -> NitriteResult<HashSet<String>> {
+        self.current().get_collection_names()
+    }
+
+    fn get_repository_registry(&self) -> NitriteResult<HashSet<String>> {
+        self.current().get_repository_registry()
+    }
+
+    fn get_keyed_repository_registry(&self) -> NitriteResult<HashMap<String, HashSet<String>>> {
+        self.current().get_keyed_repository_registry()
+    }
+
+    fn has_unsaved_changes(&self) -> NitriteResult<bool> {
+        self.current().has_unsaved_changes()
+    }
+
+    fn is_read_only(&self) -> NitriteResult<bool> {
+        #[cfg(feature = "disk_watchdog")]
+        if self.inner.suspension.is_suspended() {
+            return Ok(true);
+        }
+        self.current().is_read_only()
+    }
+
+    fn is_map_opened(&self, name: &str) -> NitriteResult<bool> {
+        self.current().is_map_opened(name)
+    }
+
+    fn commit(&self) -> NitriteResult<()> {
+        self.current().commit()
+    }
+
+    fn compact(&self) -> NitriteResult<()> {
+        self.current().compact()
+    }
+
+    fn supports_atomic(&self) -> bool {
+        self.current().supports_atomic()
+    }
+
+    fn run_atomic(&self, op: &mut dyn FnMut() -> NitriteResult<()>) -> NitriteResult<()> {
+        // a migration must not split the writes of a scope between two backends
+        let _gate = self.write_gate();
+        self.current().run_atomic(op)
+    }
+
+    fn before_close(&self) -> NitriteResult<()> {
+        self.current().before_close()
+    }
+
+    fn has_map(&self, name: &str) -> NitriteResult<bool> {
+        self.current().has_map(name)
+    }
+
+    fn open_map(&self, name: &str) -> NitriteResult<NitriteMap> {
+        // a migration must see every opened map to switch it
+        let _gate = self.write_gate();
+        let opened = self.inner.maps.get(name).map(|entry| entry.value().clone());
+        if let Some(map) = opened {
+            if !map.is_closed()? {
+                return Ok(NitriteMap::new(map));
+            }
+        }
+
+        let map = SwitchableMap::new(self.clone(), self.current().open_map(name)?);
+        self.inner.maps.insert(name.to_string(), map.clone());
+        Ok(NitriteMap::new(map))
+    }
+
+    fn close_map(&self, name: &str) -> NitriteResult<()> {
+        let _gate = self.write_gate();
+        self.inner.maps.remove(name);
+        self.current().close_map(name)
+    }
+
+    fn remove_map(&self, name: &str) -> NitriteResult<()> {
+        let _gate = self.writable()?;
+        self.inner.maps.remove(name);
+        self.current().remove_map(name)
+    }
+
+    fn map_names(&self) -> NitriteResult<HashSet<String>> {
+        self.current().map_names()
+    }
+
+    fn subscribe(&self, listener: StoreEventListener) -> NitriteResult<Option<SubscriberRef>> {
+        self.current().subscribe(listener)
+    }
+
+    fn unsubscribe(&self, subscriber_ref: SubscriberRef) -> NitriteResult<()> {
+        self.current().unsubscribe(subscriber_ref)
+    }
+
+    fn store_version(&self) -> NitriteResult<String> {
+        self.current().store_version()
+    }
+
+    fn store_config(&self) -> NitriteResult<StoreConfig> {
+        self.current().store_config()
+    }
+
+    fn store_catalog(&self) -> NitriteResult<StoreCatalog> {
+        StoreCatalog::new(self.open_map(COLLECTION_CATALOG)?)
+    }
+
+    fn open_snapshot(&self, map_names: &HashSet<String>) -> NitriteResult<StoreSnapshot> {
+        self.current().open_snapshot(map_names)
+    }
+
+    fn alert(&self, event: StoreEvents) -> NitriteResult<()> {
+        self.current().alert(event)
+    }
+}
+
+/// A map of a [`SwitchableStore`], forwarding to the map of its current backend.
+#[derive(Clone)]
+struct SwitchableMap {
+    inner: Arc<SwitchableMapInner>,
+}
+
+struct SwitchableMapInner {
+    store: SwitchableStore,
+    current: RwLock<NitriteMap>,
+}
+
+impl SwitchableMap {
+    fn new(store: SwitchableStore, map: NitriteMap) -> Self {
+        SwitchableMap {
+            inner: Arc::new(SwitchableMapInner {
+                store,
+                current: RwLock::new(map),
+            }),
+        }
+    }
+
+    fn current(&self) -> NitriteMap {
+        self.inner.current.read().clone()
+    }
+
+    #[cfg(feature = "fault_injection")]
+    fn inject(&self, operation: MapOperation, keys: &[&Key]) -> NitriteResult<()> {
+        let faults = &self.inner.store.inner.faults;
+        if !faults.is_active() {
+            return Ok(());
+        }
+        faults.check(&self.current().get_name()?, operation, keys)
+    }
+}
+
+impl AttributeAware for SwitchableMap {
+    fn attributes(&self) -> NitriteResult<Option<Attributes>> {
+        self.current().attributes()
+    }
+
+    fn set_attributes(&self, attributes: Attributes) -> NitriteResult<()> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::SetAttributes, &[])?;
+        let _gate = self.inner.store.writable()?;
+        self.current().set_attributes(attributes)
+    }
+}
+
+impl NitriteMapProvider for SwitchableMap {
+    fn contains_key(&self, key: &Key) -> NitriteResult<bool> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::ContainsKey, &[key])?;
+        self.current().contains_key(key)
+    }
+
+    fn get(&self, key: &Key) -> NitriteResult<Option<Value>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Get, &[key])?;
+        self.current().get(key)
+    }
+
+    fn clear(&self) -> NitriteResult<()> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Clear, &[])?;
+        let _gate = self.inner.store.writable()?;
+        self.current().clear()
+    }
+
+    fn is_closed(&self) -> NitriteResult<bool> {
+        self.current().is_closed()
+    }
+
+    fn close(&self) -> NitriteResult<()> {
+        self.inner.store.forget(self)?;
+        self.current().close()
+    }
+
+    fn values(&self) -> NitriteResult<ValueIterator> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Iterate, &[])?;
+        self.current().values()
+    }
+
+    fn keys(&self) -> NitriteResult<KeyIterator> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Iterate, &[])?;
+        self.current().keys()
+    }
+
+    fn remove(&self, key: &Key) -> NitriteResult<Option<Value>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Remove, &[key])?;
+        let _gate = self.inner.store.writable()?;
+        self.current().remove(key)
+    }
+
+    fn put(&self, key: Key, value: Value) -> NitriteResult<()> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Put, &[&key])?;
+        let _gate = self.inner.store.writable()?;
+        self.current().put(key, value)
+    }
+
+    fn put_all(&self, entries: Vec<(Key, Value)>) -> NitriteResult<()> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::PutAll, &entries.iter().map(|(key, _)| key).collect::<Vec<_>>())?;
+        let _gate = self.inner.store.writable()?;
+        self.current().put_all(entries)
+    }
+
+    fn remove_range(&self, start: &Key, end: &Key) -> NitriteResult<u64> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::RemoveRange, &[])?;
+        let _gate = self.inner.store.writable()?;
+        self.current().remove_range(start, end)
+    }
+
+    fn size(&self) -> NitriteResult<u64> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Size, &[])?;
+        self.current().size()
+    }
+
+    fn put_if_absent(&self, key: Key, value: Value) -> NitriteResult<Option<Value>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::PutIfAbsent, &[&key])?;
+        let _gate = self.inner.store.writable()?;
+        self.current().put_if_absent(key, value)
+    }
+
+    fn first_key(&self) -> NitriteResult<Option<Key>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Navigate, &[])?;
+        self.current().first_key()
+    }
+
+    fn last_key(&self) -> NitriteResult<Option<Key>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Navigate, &[])?;
+        self.current().last_key()
+    }
+
+    fn higher_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Navigate, &[key])?;
+        self.current().higher_key(key)
+    }
+
+    fn ceiling_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Navigate, &[key])?;
+        self.current().ceiling_key(key)
+    }
+
+    fn lower_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Navigate, &[key])?;
+        self.current().lower_key(key)
+    }
+
+    fn floor_key(&self, key: &Key) -> NitriteResult<Option<Key>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Navigate, &[key])?;
+        self.current().floor_key(key)
+    }
+
+    fn is_empty(&self) -> NitriteResult<bool> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Size, &[])?;
+        self.current().is_empty()
+    }
+
+    fn get_store(&self) -> NitriteResult<NitriteStore> {
+        Ok(NitriteStore::new(self.inner.store.clone()))
+    }
+
+    fn get_name(&self) -> NitriteResult<String> {
+        self.current().get_name()
+    }
+
+    fn entries(&self) -> NitriteResult<EntryIterator> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Iterate, &[])?;
+        self.current().entries()
+    }
+
+    fn reverse_entries(&self) -> NitriteResult<Rev<EntryIterator>> {
+        #[cfg(feature = "fault_injection")]
+        self.inject(MapOperation::Iterate, &[])?;
+        self.current().reverse_entries()
+    }
+
+    fn dispose(&self) -> NitriteResult<()> {
+        let _gate = self.inner.store.writable()?;
+        self.inner.store.forget(self)?;
+        self.current().dispose()
+    }
+
+    fn is_dropped(&self) -> NitriteResult<bool> {
+        self.current().is_dropped()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory::{InMemoryStore, InMemoryStoreConfig};
+
+    fn memory_store() -> NitriteStore {
+        let store = NitriteStore::new(InMemoryStore::new(InMemoryStoreConfig::new()));
+        store.open_or_create().unwrap();
+        store
+    }
+
+    #[test]
+    fn test_migrate_moves_open_maps() {
+        let source = memory_store();
+        let store = SwitchableStore::new(source.clone());
+        let map = store.open_map("books").unwrap();
+        map.put(Value::from(1), Value::from("dune")).unwrap();
+        let mut attributes = Attributes::new();
+        attributes.put("owner", Value::from("library"));
+        map.set_attributes(attributes).unwrap();
+
+        let target = memory_store();
+        let previous = store.migrate_to(target.clone()).unwrap();
+        assert!(Arc::ptr_eq(&previous, &source));
+
+        map.put(Value::from(2), Value::from("emma")).unwrap();
+        assert_eq!(map.size().unwrap(), 2);
+        let moved = target.open_map("books").unwrap();
+        assert_eq!(moved.get(&Value::from(1)).unwrap(), Some(Value::from("dune")));
+        assert_eq!(moved.get(&Value::from(2)).unwrap(), Some(Value::from("emma")));
+        assert_eq!(
+            moved.attributes().unwrap().and_then(|attributes| attributes.get("owner").cloned()),
+            Some(Value::from("library"))
+        );
+        assert_eq!(source.open_map("books").unwrap().size().unwrap(), 1);
+        assert!(Arc::ptr_eq(&store.current(), &target));
+    }
+
+    #[test]
+    fn test_migrate_refuses_target_holding_a_map() {
+        let source = memory_store();
+        let store = SwitchableStore::new(source.clone());
+        let map = store.open_map("books").unwrap();
+        map.put(Value::from(1), Value::from("dune")).unwrap();
+
+        let target = memory_store();
+        target.open_map("books").unwrap().put(Value::from(9), Value::from("old")).unwrap();
+        let result = store.migrate_to(target);
+        assert!(matches!(result.err().map(|e| e.kind().clone()), Some(ErrorKind::ValidationError)));
+
+        // still on the source
+        map.put(Value::from(2), Value::from("emma")).unwrap();
+        assert_eq!(source.open_map("books").unwrap().size().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_disposed_map_is_reopened_from_the_backend() {
+        let store = SwitchableStore::new(memory_store());
+        let map = store.open_map("books").unwrap();
+        map.put(Value::from(1), Value::from("dune")).unwrap();
+        map.dispose().unwrap();
+        assert!(store.inner.maps.is_empty());
+
+        let reopened = store.open_map("books").unwrap();
+        assert!(reopened.is_empty().unwrap());
+        reopened.put(Value::from(2), Value::from("emma")).unwrap();
+        assert_eq!(reopened.size().unwrap(), 1);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    collection::{CollectionOptions, NitriteCollection},
+    common::Convertible,
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    nitrite::Nitrite,
+    repository::{NitriteEntity, ObjectRepository},
+    INTERNAL_NAME_SEPARATOR, TENANT_PREFIX,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A view of a database scoped to one tenant.
+///
+/// The collections and repositories of a tenant are stored under names prefixed with the
+/// tenant, so each tenant sees only its own data, including its indexes, options and
+/// metadata, and two tenants can use the same collection or entity names. A tenant exists
+/// as long as it holds a collection or a repository; it is dropped with
+/// [`Nitrite::drop_tenant`].
+///
+/// Through the database itself, the collections of a tenant are listed with their stored
+/// names (`$nitrite_tenant|<tenant>|<name>`) and its repositories as keyed repositories.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let acme = db.tenant("acme")?;
+/// let orders = acme.collection("orders")?;
+/// orders.insert(doc!{ "item": "anvil" })?;
+///
+/// // another tenant's "orders" is a different collection
+/// assert_eq!(db.tenant("globex")?.collection("orders")?.size()?, 0);
+/// ```
+#[derive(Clone)]
+pub struct Tenant {
+    name: String,
+    db: Nitrite,
+}
+
+impl Tenant {
+    pub(crate) fn new(name: &str, db: Nitrite) -> NitriteResult<Tenant> {
+        validate_tenant_name(name)?;
+        Ok(Tenant {
+            name: name.to_string(),
+            db,
+        })
+    }
+
+    /// Returns the name of the tenant.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets a collection of the tenant by name, creating it if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `Nitrite::collection()`.
+    pub fn collection(&self, name: &str) -> NitriteResult<NitriteCollection> {
+        self.db.collection(&self.collection_name(name)?)
+    }
+
+    /// Gets a collection of the tenant by name, creating it if it doesn't exist, and
+    /// applies `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `Nitrite::collection_with_options()`.
+    pub fn collection_with_options(
+        &self,
+        name: &str,
+        options: CollectionOptions,
+    ) -> NitriteResult<NitriteCollection> {
+        self.db
+            .collection_with_options(&self.collection_name(name)?, options)
+    }
+
+    /// Checks if the tenant has a collection with the specified name.
+    pub fn has_collection(&self, name: &str) -> NitriteResult<bool> {
+        Ok(self.list_collection_names()?.contains(name))
+    }
+
+    /// Destroys a collection of the tenant, removing all documents in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `Nitrite::destroy_collection()`.
+    pub fn destroy_collection(&self, name: &str) -> NitriteResult<()> {
+        self.db.destroy_collection(&self.collection_name(name)?)
+    }
+
+    /// Lists the names of the collections of the tenant, without the tenant prefix.
+    pub fn list_collection_names(&self) -> NitriteResult<HashSet<String>> {
+        let prefix = self.prefix();
+        Ok(self
+            .db
+            .list_collection_names()?
+            .into_iter()
+            .filter_map(|name| name.strip_prefix(&prefix).map(str::to_string))
+            .collect())
+    }
+
+    /// Gets or creates the repository of the tenant for entities of type `T`.
+    pub fn repository<T>(&self) -> NitriteResult<ObjectRepository<T>>
+    where
+        T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
+    {
+        self.db.keyed_repository::<T>(&self.repository_key(None))
+    }
+
+    /// Gets or creates a keyed repository of the tenant for entities of type `T`.
+    pub fn keyed_repository<T>(&self, key: &str) -> NitriteResult<ObjectRepository<T>>
+    where
+        T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
+    {
+        self.db.keyed_repository::<T>(&self.repository_key(Some(key)))
+    }
+
+    /// Checks if the tenant has a repository for entities of type `T`.
+    pub fn has_repository<T: NitriteEntity>(&self) -> NitriteResult<bool> {
+        self.db.has_keyed_repository::<T>(&self.repository_key(None))
+    }
+
+    /// Checks if the tenant has a keyed repository for entities of type `T`.
+    pub fn has_keyed_repository<T: NitriteEntity>(&self, key: &str) -> NitriteResult<bool> {
+        self.db
+            .has_keyed_repository::<T>(&self.repository_key(Some(key)))
+    }
+
+    /// Destroys the repository of the tenant for entities of type `T`.
+    pub fn destroy_repository<T: NitriteEntity>(&self) -> NitriteResult<()> {
+        self.db
+            .destroy_keyed_repository::<T>(&self.repository_key(None))
+    }
+
+    /// Destroys a keyed repository of the tenant for entities of type `T`.
+    pub fn destroy_keyed_repository<T: NitriteEntity>(&self, key: &str) -> NitriteResult<()> {
+        self.db
+            .destroy_keyed_repository::<T>(&self.repository_key(Some(key)))
+    }
+
+    /// Lists the entity names of the repositories of the tenant.
+    pub fn list_repositories(&self) -> NitriteResult<HashSet<String>> {
+        Ok(self
+            .db
+            .list_keyed_repositories()?
+            .remove(&self.repository_key(None))
+            .unwrap_or_default())
+    }
+
+    /// Lists the keyed repositories of the tenant, grouped by key.
+    pub fn list_keyed_repositories(&self) -> NitriteResult<HashMap<String, HashSet<String>>> {
+        let prefix = self.prefix();
+        Ok(self
+            .db
+            .list_keyed_repositories()?
+            .into_iter()
+            .filter_map(|(key, types)| key.strip_prefix(&prefix).map(|key| (key.to_string(), types)))
+            .collect())
+    }
+
+    fn prefix(&self) -> String {
+        tenant_prefix(&self.name)
+    }
+
+    fn collection_name(&self, name: &str) -> NitriteResult<String> {
+        if name.is_empty() {
+            log::error!("Collection name cannot be empty");
+            return Err(NitriteError::new(
+                "Collection name cannot be empty",
+                ErrorKind::ValidationError,
+            ));
+        }
+        Ok(format!("{}{}", self.prefix(), name))
+    }
+
+    /// The repositories of a tenant are keyed repositories whose key carries the tenant.
+    fn repository_key(&self, key: Option<&str>) -> String {
+        match key {
+            Some(key) => format!("{}{}", self.prefix(), key),
+            None => format!("{}{}{}", TENANT_PREFIX, INTERNAL_NAME_SEPARATOR, self.name),
+        }
+    }
+}
+
+/// Returns the prefix of the collection names and repository keys of a tenant.
+pub(crate) fn tenant_prefix(tenant: &str) -> String {
+    format!(
+        "{}{}{}{}",
+        TENANT_PREFIX, INTERNAL_NAME_SEPARATOR, tenant, INTERNAL_NAME_SEPARATOR
+    )
+}
+
+/// Returns the tenant a collection name or repository key belongs to, if any.
+pub(crate) fn tenant_of(name: &str) -> Option<&str> {
+    let rest = name
+        .strip_prefix(TENANT_PREFIX)?
+        .strip_prefix(INTERNAL_NAME_SEPARATOR)?;
+    match rest.split_once(INTERNAL_NAME_SEPARATOR) {
+        Some((tenant, _)) => Some(tenant),
+        None => Some(rest),
+    }
+}
+
+fn validate_tenant_name(name: &str) -> NitriteResult<()> {
+    if name.is_empty() || name.contains(' ') || name.contains(INTERNAL_NAME_SEPARATOR) {
+        log::error!("Invalid tenant name '{}'", name);
+        return Err(NitriteError::new(
+            &format!(
+                "Invalid tenant name '{}', it must not be empty or contain a space or '{}'",
+                name, INTERNAL_NAME_SEPARATOR
+            ),
+            ErrorKind::ValidationError,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tenant_of() {
+        assert_eq!(tenant_of(&format!("{}orders", tenant_prefix("acme"))), Some("acme"));
+        assert_eq!(tenant_of("$nitrite_tenant|acme"), Some("acme"));
+        assert_eq!(tenant_of("orders"), None);
+        assert_eq!(tenant_of("$nitrite_tenants|acme|orders"), None);
+    }
+
+    #[test]
+    fn test_validate_tenant_name() {
+        assert!(validate_tenant_name("acme").is_ok());
+        assert!(validate_tenant_name("").is_err());
+        assert!(validate_tenant_name("ac me").is_err());
+        assert!(validate_tenant_name("ac|me").is_err());
+    }
+}
@@ -0,0 +1,536 @@
+//! Declarative fixtures to seed a database in tests.
+//!
+//! [`Fixtures`] are documents to insert into collections and repositories, written in
+//! JSON or TOML, usually embedded in the test with `include_str!`. A fixture document can
+//! be given a label with `$label`; other documents refer to it with `{"$ref": "label"}`,
+//! replaced by the id of the labelled document, or `{"$ref": "label.field"}`, replaced by
+//! the value of one of its fields. References can point to documents of any collection,
+//! declared before or after them.
+//!
+//! ```json
+//! {
+//!   "collections": {
+//!     "orders": [
+//!       { "customer": { "$ref": "alice" }, "email": { "$ref": "alice.email" }, "total": 42.5 }
+//!     ]
+//!   },
+//!   "repositories": {
+//!     "Customer": [
+//!       { "$label": "alice", "name": "Alice", "email": "alice@example.com" }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! Repository fixtures are given in the stored form of the entities. The repository of a
+//! type registered with [`Fixtures::repository`] is opened before it is seeded, so its
+//! indexes exist; any other entity name must be the name of an existing repository.
+//!
+//! [`Fixtures::reset`] empties the collections and repositories of the fixtures and
+//! seeds them again, so tests sharing a database start from the same state.
+//!
+//! This module requires the `testing` feature.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let fixtures = Fixtures::from_json(include_str!("fixtures/shop.json"))?
+//!     .repository::<Customer>();
+//! let seeded = fixtures.load(&db)?;
+//!
+//! let alice = seeded.id("alice").unwrap();
+//! let orders = db.collection("orders")?.find(field("customer").eq(alice))?;
+//!
+//! // before the next test
+//! fixtures.reset(&db)?;
+//! ```
+
+use crate::collection::{Document, NitriteCollection, NitriteId};
+use crate::common::{parse_json, Convertible, Value, DOC_ID};
+use crate::errors::{ErrorKind, NitriteError, NitriteResult};
+use crate::nitrite::Nitrite;
+use crate::repository::NitriteEntity;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// The field giving a fixture document a label.
+pub const FIXTURE_LABEL: &str = "$label";
+/// The field of a reference to a labelled fixture document.
+pub const FIXTURE_REF: &str = "$ref";
+
+const COLLECTIONS: &str = "collections";
+const REPOSITORIES: &str = "repositories";
+
+type RepositoryOpener = dyn Fn(&Nitrite) -> NitriteResult<NitriteCollection> + Send + Sync;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Target {
+    Collection(String),
+    Repository(String),
+}
+
+#[derive(Debug, Clone)]
+struct Fixture {
+    target: Target,
+    label: Option<String>,
+    document: Document,
+}
+
+/// A set of fixture documents to seed a database with. See the [module](self) docs for
+/// the format.
+#[derive(Clone, Default)]
+pub struct Fixtures {
+    fixtures: Vec<Fixture>,
+    repositories: HashMap<String, Arc<RepositoryOpener>>,
+}
+
+impl Fixtures {
+    /// Creates an empty set of fixtures.
+    pub fn new() -> Self {
+        Fixtures::default()
+    }
+
+    /// Reads fixtures from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if the JSON is invalid or not in the fixture format.
+    pub fn from_json(json: &str) -> NitriteResult<Self> {
+        Fixtures::new().and_json(json)
+    }
+
+    /// Reads fixtures from TOML, with the same layout as JSON: arrays of tables under
+    /// `[[collections.<name>]]` and `[[repositories.<entity>]]`. Dates are read as
+    /// strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if the TOML is invalid or not in the fixture format.
+    pub fn from_toml(toml: &str) -> NitriteResult<Self> {
+        Fixtures::new().and_toml(toml)
+    }
+
+    /// Adds the fixtures read from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if the JSON is invalid or not in the fixture format.
+    pub fn and_json(self, json: &str) -> NitriteResult<Self> {
+        self.and_value(parse_json(json)?)
+    }
+
+    /// Adds the fixtures read from TOML.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if the TOML is invalid or not in the fixture format.
+    pub fn and_toml(self, toml: &str) -> NitriteResult<Self> {
+        let table = toml.parse::<toml::Table>().map_err(|err| {
+            log::error!("Invalid TOML fixtures: {}", err);
+            NitriteError::new(&format!("Invalid TOML fixtures: {}", err), ErrorKind::ValidationError)
+        })?;
+        self.and_value(toml_to_value(toml::Value::Table(table)))
+    }
+
+    /// Opens the repository of `T` with its indexes before its fixtures are seeded.
+    pub fn repository<T>(mut self) -> Self
+    where
+        T: Convertible<Output = T> + NitriteEntity + Send + Sync + 'static,
+    {
+        let entity_name = T::default().entity_name();
+        self.repositories.insert(
+            entity_name,
+            Arc::new(|db: &Nitrite| Ok(db.repository::<T>()?.document_collection())),
+        );
+        self
+    }
+
+    /// Inserts the fixture documents, references resolved, and returns the seeded
+    /// documents by label. A labelled document gets the same id on every load; use
+    /// [`reset`](Fixtures::reset) to seed a database the fixtures were loaded into.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if a reference is to an unknown label or field, or
+    /// references are circular, and any error inserting the documents. The documents
+    /// are resolved and their collections opened before any is inserted.
+    pub fn load(&self, db: &Nitrite) -> NitriteResult<SeededFixtures> {
+        let documents = self.resolve()?;
+        let mut collections = HashMap::new();
+        for fixture in &self.fixtures {
+            if !collections.contains_key(&fixture.target) {
+                collections.insert(&fixture.target, self.open(db, &fixture.target)?);
+            }
+        }
+        for (fixture, document) in self.fixtures.iter().zip(documents.iter()) {
+            if let Some(collection) = collections.get(&fixture.target) {
+                collection.insert(document.clone())?;
+            }
+        }
+
+        let documents = self
+            .fixtures
+            .iter()
+            .zip(documents)
+            .filter_map(|(fixture, document)| fixture.label.clone().map(|label| (label, document)))
+            .collect();
+        Ok(SeededFixtures { documents })
+    }
+
+    /// Removes every document from the collections and repositories of the fixtures,
+    /// then seeds them again.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`load`](Fixtures::load), and any error clearing the
+    /// collections.
+    pub fn reset(&self, db: &Nitrite) -> NitriteResult<SeededFixtures> {
+        let mut cleared = HashSet::new();
+        for fixture in &self.fixtures {
+            if cleared.insert(&fixture.target) {
+                self.open(db, &fixture.target)?.clear()?;
+            }
+        }
+        self.load(db)
+    }
+
+    /// Returns the number of fixture documents.
+    pub fn len(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    /// Returns `true` if there are no fixture documents.
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+    }
+
+    fn and_value(mut self, value: Value) -> NitriteResult<Self> {
+        let Value::Document(root) = value else {
+            return Err(invalid_fixtures("the fixtures must be an object"));
+        };
+
+        let mut labels: HashSet<String> = self.fixtures.iter().filter_map(|f| f.label.clone()).collect();
+        for (section, targets) in root.iter() {
+            let Value::Document(targets) = targets else {
+                return Err(invalid_fixtures(&format!("'{}' must be an object", section)));
+            };
+            for (name, documents) in targets.iter() {
+                let target = match section.as_str() {
+                    COLLECTIONS => Target::Collection(name.clone()),
+                    REPOSITORIES => Target::Repository(name.clone()),
+                    _ => {
+                        return Err(invalid_fixtures(&format!(
+                            "unknown section '{}', expected '{}' or '{}'",
+                            section, COLLECTIONS, REPOSITORIES
+                        )))
+                    }
+                };
+                let Value::Array(documents) = documents else {
+                    return Err(invalid_fixtures(&format!("'{}' must be an array", name)));
+                };
+
+                for document in documents {
+                    let Value::Document(mut document) = document else {
+                        return Err(invalid_fixtures(&format!(
+                            "the fixtures of '{}' must be objects",
+                            name
+                        )));
+                    };
+                    let label = match document.get(FIXTURE_LABEL)? {
+                        Value::Null => None,
+                        Value::String(label) if !label.contains('.') => Some(label),
+                        other => {
+                            return Err(invalid_fixtures(&format!(
+                                "invalid label {}, expected a string without '.'",
+                                other
+                            )))
+                        }
+                    };
+                    if let Some(label) = &label {
+                        if !labels.insert(label.clone()) {
+                            return Err(invalid_fixtures(&format!("duplicate label '{}'", label)));
+                        }
+                        document.remove(FIXTURE_LABEL)?;
+                        // the id is known before insertion so that references can use it
+                        document.id()?;
+                    }
+                    self.fixtures.push(Fixture {
+                        target: target.clone(),
+                        label,
+                        document,
+                    });
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    fn open(&self, db: &Nitrite, target: &Target) -> NitriteResult<NitriteCollection> {
+        match target {
+            Target::Collection(name) => db.collection(name),
+            Target::Repository(entity_name) => match self.repositories.get(entity_name) {
+                Some(open) => open(db),
+                None => db.repository_collection(entity_name, None),
+            },
+        }
+    }
+
+    fn resolve(&self) -> NitriteResult<Vec<Document>> {
+        let mut resolver = Resolver {
+            labelled: self
+                .fixtures
+                .iter()
+                .filter_map(|fixture| fixture.label.as_deref().map(|label| (label, fixture)))
+                .collect(),
+            resolved: HashMap::new(),
+            resolving: HashSet::new(),
+        };
+        self.fixtures
+            .iter()
+            .map(|fixture| match &fixture.label {
+                Some(label) => resolver.resolve_label(label),
+                None => resolver.resolve_document(&fixture.document),
+            })
+            .collect()
+    }
+}
+
+/// The documents seeded by [`Fixtures::load`], by label.
+#[derive(Debug, Clone)]
+pub struct SeededFixtures {
+    documents: BTreeMap<String, Document>,
+}
+
+impl SeededFixtures {
+    /// Returns the id of the document labelled `label`.
+    pub fn id(&self, label: &str) -> Option<NitriteId> {
+        self.documents
+            .get(label)
+            .and_then(|document| document.get(DOC_ID).ok())
+            .and_then(|id| id.as_nitrite_id().copied())
+    }
+
+    /// Returns the document labelled `label`, as inserted.
+    pub fn document(&self, label: &str) -> Option<&Document> {
+        self.documents.get(label)
+    }
+
+    /// Returns the labels of the seeded documents.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.documents.keys().map(String::as_str)
+    }
+}
+
+struct Resolver<'a> {
+    labelled: HashMap<&'a str, &'a Fixture>,
+    resolved: HashMap<String, Document>,
+    // labels being resolved, to detect circular references
+    resolving: HashSet<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve_label(&mut self, label: &str) -> NitriteResult<Document> {
+        if let Some(document) = self.resolved.get(label) {
+            return Ok(document.clone());
+        }
+        let Some(fixture) = self.labelled.get(label).copied() else {
+            return Err(invalid_fixtures(&format!("reference to unknown label '{}'", label)));
+        };
+        if !self.resolving.insert(label.to_string()) {
+            return Err(invalid_fixtures(&format!("circular reference to '{}'", label)));
+        }
+        let document = self.resolve_document(&fixture.document)?;
+        self.resolving.remove(label);
+        self.resolved.insert(label.to_string(), document.clone());
+        Ok(document)
+    }
+
+    fn resolve_document(&mut self, document: &Document) -> NitriteResult<Document> {
+        let mut resolved = Document::new();
+        for (key, value) in document.iter() {
+            resolved.put_literal(key, self.resolve_value(value)?);
+        }
+        Ok(resolved)
+    }
+
+    fn resolve_value(&mut self, value: Value) -> NitriteResult<Value> {
+        match value {
+            Value::Document(document) => match reference_of(&document) {
+                Some(reference) => self.resolve_reference(&reference),
+                None => Ok(Value::Document(self.resolve_document(&document)?)),
+            },
+            Value::Array(values) => Ok(Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| self.resolve_value(value))
+                    .collect::<NitriteResult<_>>()?,
+            )),
+            value => Ok(value),
+        }
+    }
+
+    fn resolve_reference(&mut self, reference: &str) -> NitriteResult<Value> {
+        match reference.split_once('.') {
+            None => {
+                // the id was assigned when the fixtures were read
+                let document = self.labelled.get(reference).map(|fixture| &fixture.document);
+                match document.map(|document| document.get(DOC_ID)).transpose()? {
+                    Some(id) => Ok(id),
+                    None => Err(invalid_fixtures(&format!(
+                        "reference to unknown label '{}'",
+                        reference
+                    ))),
+                }
+            }
+            Some((label, field)) => match self.resolve_label(label)?.get(field)? {
+                Value::Null => Err(invalid_fixtures(&format!(
+                    "reference to missing field '{}' of '{}'",
+                    field, label
+                ))),
+                value => Ok(value),
+            },
+        }
+    }
+}
+
+/// Returns the target of `{"$ref": "..."}`, the only field of a reference.
+fn reference_of(document: &Document) -> Option<String> {
+    if document.size() != 1 {
+        return None;
+    }
+    document.get(FIXTURE_REF).ok()?.as_string().cloned()
+}
+
+fn toml_to_value(toml: toml::Value) -> Value {
+    match toml {
+        toml::Value::String(value) => Value::String(value),
+        toml::Value::Integer(value) => Value::I64(value),
+        toml::Value::Float(value) => Value::F64(value),
+        toml::Value::Boolean(value) => Value::Bool(value),
+        toml::Value::Datetime(value) => Value::String(value.to_string()),
+        toml::Value::Array(values) => Value::Array(values.into_iter().map(toml_to_value).collect()),
+        toml::Value::Table(table) => {
+            let mut document = Document::new();
+            for (key, value) in table {
+                document.put_literal(key, toml_to_value(value));
+            }
+            Value::Document(document)
+        }
+    }
+}
+
+fn invalid_fixtures(message: &str) -> NitriteError {
+    log::error!("Invalid fixtures: {}", message);
+    NitriteError::new(&format!("Invalid fixtures: {}", message), ErrorKind::ValidationError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::field;
+
+    const SHOP: &str = r#"{
+        "collections": {
+            "orders": [
+                { "customer": { "$ref": "alice" }, "email": { "$ref": "alice.email" }, "total": 42.5 },
+                { "customer": { "$ref": "bob" }, "lines": [{ "sku": { "$ref": "pen.sku" } }] }
+            ],
+            "customers": [
+                { "$label": "alice", "name": "Alice", "email": "alice@example.com" },
+                { "$label": "bob", "name": "Bob", "friend": { "$ref": "alice" } }
+            ],
+            "products": [
+                { "$label": "pen", "sku": "P-1" }
+            ]
+        }
+    }"#;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    #[test]
+    fn test_load_resolves_references() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let fixtures = Fixtures::from_json(SHOP)?;
+        assert_eq!(fixtures.len(), 5);
+        let seeded = fixtures.load(&db)?;
+
+        let alice = seeded.id("alice").unwrap();
+        let customers = db.collection("customers")?;
+        let stored = customers.get_by_id(&alice)?.unwrap();
+        assert_eq!(stored.get("name")?, Value::from("Alice"));
+        assert!(!stored.contains_key(FIXTURE_LABEL));
+        assert_eq!(seeded.document("bob").unwrap().get("friend")?, Value::NitriteId(alice));
+
+        let orders = db.collection("orders")?;
+        let order = orders.find(field("customer").eq(alice))?.next().unwrap()?;
+        assert_eq!(order.get("email")?, Value::from("alice@example.com"));
+        let order = orders.find(field("customer").eq(seeded.id("bob").unwrap()))?.next().unwrap()?;
+        assert_eq!(order.get("lines.0.sku")?, Value::from("P-1"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_seeds_again() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let fixtures = Fixtures::from_json(SHOP)?;
+        fixtures.load(&db)?;
+        let customers = db.collection("customers")?;
+        customers.insert(crate::doc! { name: "Carol" })?;
+
+        let seeded = fixtures.reset(&db)?;
+        assert_eq!(customers.size()?, 2);
+        assert_eq!(db.collection("orders")?.size()?, 2);
+        assert!(customers.get_by_id(&seeded.id("bob").unwrap())?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_toml_fixtures() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let fixtures = Fixtures::from_toml(
+            r#"
+            [[collections.customers]]
+            "$label" = "alice"
+            name = "Alice"
+            since = 2020-01-01
+
+            [[collections.orders]]
+            customer = { "$ref" = "alice" }
+            quantity = 3
+            "#,
+        )?;
+        let seeded = fixtures.load(&db)?;
+        let alice = seeded.document("alice").unwrap();
+        assert_eq!(alice.get("since")?, Value::from("2020-01-01"));
+        let order = db.collection("orders")?.find(field("quantity").eq(3i64))?.next().unwrap()?;
+        assert_eq!(order.get("customer")?, Value::NitriteId(seeded.id("alice").unwrap()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_fixtures() {
+        for json in [
+            r#"[]"#,
+            r#"{"tables": {"a": []}}"#,
+            r#"{"collections": {"a": [1]}}"#,
+            r#"{"collections": {"a": [{"$label": "x"}, {"$label": "x"}]}}"#,
+            r#"{"collections": {"a": [{"$label": "x.y"}]}}"#,
+        ] {
+            let error = Fixtures::from_json(json).err().unwrap();
+            assert_eq!(error.kind(), &ErrorKind::ValidationError, "{}", json);
+        }
+
+        let db = setup_nitrite();
+        for json in [
+            r#"{"collections": {"a": [{"b": {"$ref": "missing"}}]}}"#,
+            r#"{"collections": {"a": [{"$label": "x", "y": {"$ref": "z.v"}}, {"$label": "z", "v": {"$ref": "x.y"}}]}}"#,
+            r#"{"collections": {"a": [{"$label": "x"}, {"b": {"$ref": "x.missing"}}]}}"#,
+        ] {
+            let error = Fixtures::from_json(json).unwrap().load(&db).err().unwrap();
+            assert_eq!(error.kind(), &ErrorKind::ValidationError, "{}", json);
+        }
+        assert!(!db.has_collection("a").unwrap());
+    }
+}
@@ -0,0 +1,650 @@
+use crate::{
+    collection::{Document, NitriteCollection},
+    common::{get_current_time_or_zero, AttributeAware, Attributes, LockHandle, PersistentCollection},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::{all, and, field, Filter},
+    index::unique_index,
+    Value, DOC_ID, INTERNAL_NAME_SEPARATOR, TIME_SERIES_BUCKET_WIDTH, TIME_SERIES_PREFIX,
+    TIME_SERIES_RETENTION,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+const SERIES: &str = "series";
+const START: &str = "start";
+const COUNT: &str = "count";
+const TIMESTAMPS: &str = "timestamps";
+const FIELDS: &str = "fields";
+const COLUMNS: &str = "columns";
+
+/// Options of a [`TimeSeries`].
+///
+/// The bucket width is fixed when the time series is created; opening it again with
+/// another width fails. The retention can be changed on every open.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let readings = db.time_series(
+///     "readings",
+///     TimeSeriesOptions::new()
+///         .bucket_width(Duration::from_secs(15 * 60))
+///         .retention(Duration::from_secs(30 * 24 * 3600)),
+/// )?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSeriesOptions {
+    bucket_width: Duration,
+    retention: Option<Duration>,
+}
+
+impl Default for TimeSeriesOptions {
+    fn default() -> Self {
+        TimeSeriesOptions {
+            bucket_width: Duration::from_secs(3600),
+            retention: None,
+        }
+    }
+}
+
+impl TimeSeriesOptions {
+    /// Creates the default options: one-hour buckets, kept forever.
+    pub fn new() -> Self {
+        TimeSeriesOptions::default()
+    }
+
+    /// Sets the time span of the points stored together in one bucket, at least one
+    /// millisecond. Wider buckets store dense series more compactly, narrower ones make
+    /// short range queries and writes to sparse series cheaper.
+    pub fn bucket_width(mut self, bucket_width: Duration) -> Self {
+        self.bucket_width = bucket_width.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Sets how long points are kept. A bucket is removed once all its points are older.
+    pub fn retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Returns the time span of a bucket.
+    pub fn get_bucket_width(&self) -> Duration {
+        self.bucket_width
+    }
+
+    /// Returns how long points are kept, `None` if forever.
+    pub fn get_retention(&self) -> Option<Duration> {
+        self.retention
+    }
+
+    fn width_millis(&self) -> i64 {
+        i64::try_from(self.bucket_width.as_millis()).unwrap_or(i64::MAX)
+    }
+}
+
+/// One point of a series: a timestamp, in milliseconds, and the values measured then.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let point = DataPoint::new(1_700_000_000_000).with("temperature", 21.5).with("humidity", 40.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPoint {
+    timestamp: i64,
+    values: BTreeMap<String, f64>,
+}
+
+impl DataPoint {
+    /// Creates a point without values at `timestamp`, in milliseconds.
+    pub fn new(timestamp: i64) -> Self {
+        DataPoint {
+            timestamp,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the value of `field`. A NaN value is not stored.
+    pub fn with(mut self, field: &str, value: f64) -> Self {
+        self.values.insert(field.to_string(), value);
+        self
+    }
+
+    /// Returns the timestamp of the point, in milliseconds.
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Returns the value of `field`, if the point has one.
+    pub fn value(&self, field: &str) -> Option<f64> {
+        self.values.get(field).copied()
+    }
+
+    /// Returns the values of the point by field.
+    pub fn values(&self) -> &BTreeMap<String, f64> {
+        &self.values
+    }
+}
+
+/// The values of a field within one interval, as computed by [`TimeSeries::downsample`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntervalStats {
+    /// Start of the interval, in milliseconds.
+    pub start: i64,
+    /// Number of values in the interval.
+    pub count: u64,
+    /// Smallest value.
+    pub min: f64,
+    /// Largest value.
+    pub max: f64,
+    /// Sum of the values.
+    pub sum: f64,
+}
+
+impl IntervalStats {
+    /// Returns the average of the values.
+    pub fn avg(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+    }
+}
+
+/// A collection of series of numeric measurements, such as sensor readings, stored in
+/// time buckets.
+///
+/// The points of a series are keyed by their timestamp: writing a point at a timestamp
+/// the series already has replaces it. Instead of one document per point, the points of
+/// a series falling in the same [bucket](TimeSeriesOptions::bucket_width) are stored in
+/// one document, column by column: the timestamps delta-encoded, then the values of each
+/// field. This takes a fraction of the space of one document per point, and a range
+/// query reads only the buckets it overlaps.
+///
+/// Writes to a time series are serialized. A time series is opened with
+/// [`Nitrite::time_series`](crate::nitrite::Nitrite::time_series) and is cheap to clone.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// readings.insert("sensor-1", DataPoint::new(now).with("temperature", 21.5))?;
+///
+/// let last_hour = readings.range("sensor-1", now - 3_600_000, now + 1)?;
+/// for minute in readings.downsample("sensor-1", "temperature", now - 3_600_000, now + 1, Duration::from_secs(60))? {
+///     println!("{} avg {} max {}", minute.start, minute.avg(), minute.max);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TimeSeries {
+    inner: Arc<TimeSeriesInner>,
+}
+
+struct TimeSeriesInner {
+    name: String,
+    buckets: NitriteCollection,
+    options: TimeSeriesOptions,
+    // serializes the read-modify-write of the buckets
+    writer: LockHandle,
+}
+
+pub(crate) fn time_series_collection_name(name: &str) -> String {
+    format!("{}{}{}", TIME_SERIES_PREFIX, INTERNAL_NAME_SEPARATOR, name)
+}
+
+/// Removes the options from the attributes of a time series about to be dropped.
+pub(crate) fn forget_time_series_options(buckets: &NitriteCollection) -> NitriteResult<()> {
+    let mut attributes = buckets.get_attributes()?;
+    let mut changed = false;
+    for key in [TIME_SERIES_BUCKET_WIDTH, TIME_SERIES_RETENTION] {
+        changed |= attributes.remove(key).is_some();
+    }
+    if changed {
+        buckets.set_attributes(attributes)?;
+    }
+    Ok(())
+}
+
+impl TimeSeries {
+    pub(crate) fn open(
+        name: &str,
+        buckets: NitriteCollection,
+        writer: LockHandle,
+        options: TimeSeriesOptions,
+    ) -> NitriteResult<TimeSeries> {
+        let mut attributes = buckets.get_attributes()?;
+        match attributes.get(TIME_SERIES_BUCKET_WIDTH).and_then(Value::as_i64) {
+            Some(width) if *width != options.width_millis() => {
+                log::error!(
+                    "Time series {} has buckets of {} ms, not {} ms",
+                    name,
+                    width,
+                    options.width_millis()
+                );
+                return Err(NitriteError::new(
+                    &format!("Time series {} has buckets of {} ms", name, width),
+                    ErrorKind::ValidationError,
+                ));
+            }
+            _ => {}
+        }
+        write_options(&options, &mut attributes);
+        buckets.set_attributes(attributes)?;
+
+        if !buckets.has_index(vec![SERIES, START])? {
+            buckets.create_index(vec![SERIES, START], &unique_index())?;
+        }
+
+        Ok(TimeSeries {
+            inner: Arc::new(TimeSeriesInner {
+                name: name.to_string(),
+                buckets,
+                options,
+                writer,
+            }),
+        })
+    }
+
+    /// Returns the name of the time series.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the options of the time series.
+    pub fn options(&self) -> &TimeSeriesOptions {
+        &self.inner.options
+    }
+
+    /// Writes a point to `series`, replacing the point it has at the same timestamp.
+    pub fn insert(&self, series: &str, point: DataPoint) -> NitriteResult<()> {
+        self.insert_many(series, vec![point])
+    }
+
+    /// Writes points to `series`, replacing the points it has at the same timestamps.
+    /// The points of a bucket are written together.
+    pub fn insert_many(&self, series: &str, points: Vec<DataPoint>) -> NitriteResult<()> {
+        let width = self.inner.options.width_millis();
+        let mut by_bucket: BTreeMap<i64, Vec<DataPoint>> = BTreeMap::new();
+        for point in points {
+            by_bucket
+                .entry(point.timestamp.div_euclid(width) * width)
+                .or_default()
+                .push(point);
+        }
+
+        let _guard = self.inner.writer.write();
+        let mut created = false;
+        for (start, points) in by_bucket {
+            created |= self.write_bucket(series, start, points)?;
+        }
+        // a new bucket means time moved on, older buckets may have expired meanwhile
+        if created {
+            self.remove_expired()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the points of `series` with a timestamp in `from..to`, in milliseconds,
+    /// oldest first.
+    pub fn range(&self, series: &str, from: i64, to: i64) -> NitriteResult<Vec<DataPoint>> {
+        let mut points = Vec::new();
+        for bucket in self.buckets_overlapping(series, from, to)? {
+            points.extend(
+                decode_bucket(&bucket)?
+                    .into_iter()
+                    .filter(|point| point.timestamp >= from && point.timestamp < to),
+            );
+        }
+        Ok(points)
+    }
+
+    /// Returns the count, minimum, maximum and sum of the values of `field` in `series`
+    /// over each `interval` of `from..to`. Intervals are aligned on multiples of
+    /// `interval` since the epoch; those without a value are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if `interval` is shorter than a millisecond.
+    pub fn downsample(
+        &self,
+        series: &str,
+        field_name: &str,
+        from: i64,
+        to: i64,
+        interval: Duration,
+    ) -> NitriteResult<Vec<IntervalStats>> {
+        let interval = i64::try_from(interval.as_millis()).unwrap_or(i64::MAX);
+        if interval <= 0 {
+            log::error!("Downsampling interval must be at least one millisecond");
+            return Err(NitriteError::new(
+                "Downsampling interval must be at least one millisecond",
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        let mut intervals: BTreeMap<i64, IntervalStats> = BTreeMap::new();
+        for point in self.range(series, from, to)? {
+            let Some(value) = point.value(field_name) else {
+                continue;
+            };
+            let start = point.timestamp.div_euclid(interval) * interval;
+            intervals
+                .entry(start)
+                .or_insert(IntervalStats {
+                    start,
+                    count: 0,
+                    min: f64::INFINITY,
+                    max: f64::NEG_INFINITY,
+                    sum: 0.0,
+                })
+                .add(value);
+        }
+        Ok(intervals.into_values().collect())
+    }
+
+    /// Returns the names of the series that have points.
+    pub fn series(&self) -> NitriteResult<BTreeSet<String>> {
+        let mut names = BTreeSet::new();
+        for bucket in self.inner.buckets.find(all())? {
+            if let Some(name) = bucket?.get(SERIES)?.as_string() {
+                names.insert(name.clone());
+            }
+        }
+        Ok(names)
+    }
+
+    /// Returns the number of points of `series`.
+    pub fn size(&self, series: &str) -> NitriteResult<u64> {
+        let mut size = 0;
+        for bucket in self.inner.buckets.find(field(SERIES).eq(series))? {
+            size += bucket?.get(COUNT)?.as_i64().copied().unwrap_or_default() as u64;
+        }
+        Ok(size)
+    }
+
+    /// Removes every point of `series`.
+    pub fn remove_series(&self, series: &str) -> NitriteResult<()> {
+        let _guard = self.inner.writer.write();
+        self.inner.buckets.remove(field(SERIES).eq(series), false)?;
+        Ok(())
+    }
+
+    /// Removes the buckets whose points are all older than the retention and returns
+    /// the number of points removed. Does nothing if points are kept forever.
+    ///
+    /// Expired buckets are also removed whenever a write starts a new bucket; call this
+    /// to reclaim space from a time series that is not written to anymore.
+    pub fn enforce_retention(&self) -> NitriteResult<u64> {
+        let _guard = self.inner.writer.write();
+        self.remove_expired()
+    }
+
+    fn remove_expired(&self) -> NitriteResult<u64> {
+        let Some(retention) = self.inner.options.retention else {
+            return Ok(0);
+        };
+        let now = i64::try_from(get_current_time_or_zero()).unwrap_or(i64::MAX);
+        let retention = i64::try_from(retention.as_millis()).unwrap_or(i64::MAX);
+        // the last bucket to remove ends at or before the cutoff
+        let cutoff = now.saturating_sub(retention);
+        let expired = field(START).lte(cutoff.saturating_sub(self.inner.options.width_millis()));
+
+        let mut removed = 0;
+        for bucket in self.inner.buckets.find(expired.clone())? {
+            removed += bucket?.get(COUNT)?.as_i64().copied().unwrap_or_default() as u64;
+        }
+        if removed > 0 {
+            self.inner.buckets.remove(expired, false)?;
+        }
+        Ok(removed)
+    }
+
+    /// Merges `points` into a bucket, returns `true` if the bucket was created.
+    fn write_bucket(&self, series: &str, start: i64, points: Vec<DataPoint>) -> NitriteResult<bool> {
+        let existing = self.inner.buckets.find(bucket_filter(series, start))?.next().transpose()?;
+        let mut merged: BTreeMap<i64, DataPoint> = BTreeMap::new();
+        if let Some(bucket) = &existing {
+            for point in decode_bucket(bucket)? {
+                merged.insert(point.timestamp, point);
+            }
+        }
+        for point in points {
+            merged.insert(point.timestamp, point);
+        }
+
+        let mut bucket = encode_bucket(start, merged.into_values().collect())?;
+        bucket.put(SERIES, series)?;
+        match existing {
+            Some(existing) => {
+                bucket.put(DOC_ID, existing.get(DOC_ID)?)?;
+                self.inner.buckets.update_one(&bucket, false)?;
+                Ok(false)
+            }
+            None => {
+                self.inner.buckets.insert(bucket)?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn buckets_overlapping(&self, series: &str, from: i64, to: i64) -> NitriteResult<Vec<Document>> {
+        let width = self.inner.options.width_millis();
+        let filter = and(vec![
+            field(SERIES).eq(series),
+            field(START).gt(from.saturating_sub(width)),
+            field(START).lt(to),
+        ]);
+        let mut buckets = self.inner.buckets.find(filter)?.collect::<NitriteResult<Vec<_>>>()?;
+        buckets.sort_by_key(bucket_start);
+        Ok(buckets)
+    }
+}
+
+fn write_options(options: &TimeSeriesOptions, attributes: &mut Attributes) {
+    attributes.put(TIME_SERIES_BUCKET_WIDTH, Value::I64(options.width_millis()));
+    match options.retention {
+        Some(retention) => attributes.put(
+            TIME_SERIES_RETENTION,
+            Value::I64(i64::try_from(retention.as_millis()).unwrap_or(i64::MAX)),
+        ),
+        None => {
+            attributes.remove(TIME_SERIES_RETENTION);
+        }
+    }
+}
+
+fn bucket_filter(series: &str, start: i64) -> Filter {
+    and(vec![field(SERIES).eq(series), field(START).eq(start)])
+}
+
+fn bucket_start(bucket: &Document) -> i64 {
+    bucket
+        .get(START)
+        .ok()
+        .and_then(|start| start.as_i64().copied())
+        .unwrap_or_default()
+}
+
+/// Writes the points of a bucket, sorted by timestamp, as columns: the timestamps as
+/// varint deltas from the start of the bucket, then for each field its values as
+/// little-endian `f64`, NaN where a point has no value.
+fn encode_bucket(start: i64, points: Vec<DataPoint>) -> NitriteResult<Document> {
+    let fields: BTreeSet<&String> = points.iter().flat_map(|point| point.values.keys()).collect();
+
+    let mut timestamps = Vec::with_capacity(points.len() * 2);
+    let mut previous = start;
+    for point in &points {
+        write_varint(&mut timestamps, (point.timestamp - previous) as u64);
+        previous = point.timestamp;
+    }
+
+    let mut columns = Vec::with_capacity(fields.len());
+    for field_name in &fields {
+        let mut column = Vec::with_capacity(points.len() * 8);
+        for point in &points {
+            let value = point.values.get(*field_name).copied().unwrap_or(f64::NAN);
+            column.extend_from_slice(&value.to_le_bytes());
+        }
+        columns.push(Value::Bytes(column));
+    }
+
+    let mut bucket = Document::new();
+    bucket.put(START, start)?;
+    bucket.put(COUNT, points.len() as i64)?;
+    bucket.put(TIMESTAMPS, Value::Bytes(timestamps))?;
+    bucket.put(
+        FIELDS,
+        Value::Array(fields.into_iter().map(|name| Value::from(name.as_str())).collect()),
+    )?;
+    bucket.put(COLUMNS, Value::Array(columns))?;
+    Ok(bucket)
+}
+
+fn decode_bucket(bucket: &Document) -> NitriteResult<Vec<DataPoint>> {
+    let start = bucket_start(bucket);
+    let count = bucket.get(COUNT)?.as_i64().copied().unwrap_or_default() as usize;
+    let timestamps = bucket.get(TIMESTAMPS)?;
+    let timestamps = timestamps.as_bytes().ok_or_else(corrupt_bucket)?;
+
+    let mut points = Vec::with_capacity(count);
+    let mut position = 0;
+    let mut previous = start;
+    for _ in 0..count {
+        let delta = read_varint(timestamps, &mut position).ok_or_else(corrupt_bucket)?;
+        previous += delta as i64;
+        points.push(DataPoint::new(previous));
+    }
+
+    let (Value::Array(fields), Value::Array(columns)) = (bucket.get(FIELDS)?, bucket.get(COLUMNS)?)
+    else {
+        return Err(corrupt_bucket());
+    };
+    for (field_name, column) in fields.iter().zip(columns.iter()) {
+        let (Some(field_name), Some(column)) = (field_name.as_string(), column.as_bytes()) else {
+            return Err(corrupt_bucket());
+        };
+        if column.len() != count * 8 {
+            return Err(corrupt_bucket());
+        }
+        for (point, bytes) in points.iter_mut().zip(column.chunks_exact(8)) {
+            let value = f64::from_le_bytes(bytes.try_into().unwrap_or_default());
+            if !value.is_nan() {
+                point.values.insert(field_name.clone(), value);
+            }
+        }
+    }
+    Ok(points)
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(buffer: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *buffer.get(*position)?;
+        *position += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
+
+fn corrupt_bucket() -> NitriteError {
+    log::error!("Time series bucket is corrupted");
+    NitriteError::new("Time series bucket is corrupted", ErrorKind::InternalError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nitrite::Nitrite;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    #[test]
+    fn test_bucket_round_trip() {
+        let points = vec![
+            DataPoint::new(1_000).with("t", 20.5).with("h", 40.0),
+            DataPoint::new(1_250).with("t", 21.0),
+            DataPoint::new(1_999).with("h", 41.5),
+        ];
+        let bucket = encode_bucket(1_000, points.clone()).unwrap();
+        assert_eq!(decode_bucket(&bucket).unwrap(), points);
+    }
+
+    #[test]
+    fn test_range_and_downsample() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let readings = db.time_series(
+            "readings",
+            TimeSeriesOptions::new().bucket_width(Duration::from_millis(100)),
+        )?;
+        let points = (0..50).map(|n| DataPoint::new(n * 10).with("t", n as f64)).collect();
+        readings.insert_many("s1", points)?;
+        readings.insert("s2", DataPoint::new(5).with("t", -1.0))?;
+        // the same timestamp replaces the point
+        readings.insert("s1", DataPoint::new(20).with("t", 100.0))?;
+
+        assert_eq!(readings.size("s1")?, 50);
+        let range = readings.range("s1", 95, 205)?;
+        let timestamps: Vec<i64> = range.iter().map(DataPoint::timestamp).collect();
+        assert_eq!(timestamps, (10..=20).map(|n| n * 10).collect::<Vec<_>>());
+
+        let stats = readings.downsample("s1", "t", 0, 100, Duration::from_millis(50))?;
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].start, stats[0].count, stats[0].min, stats[0].max), (0, 5, 0.0, 100.0));
+        assert_eq!(stats[1].avg(), 7.0);
+        assert_eq!(readings.series()?, BTreeSet::from(["s1".to_string(), "s2".to_string()]));
+
+        readings.remove_series("s2")?;
+        assert_eq!(readings.size("s2")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention_removes_old_buckets() -> NitriteResult<()> {
+        let db = setup_nitrite();
+        let readings = db.time_series(
+            "retained",
+            TimeSeriesOptions::new()
+                .bucket_width(Duration::from_secs(60))
+                .retention(Duration::from_secs(3600)),
+        )?;
+        let now = get_current_time_or_zero() as i64;
+        readings.insert("s", DataPoint::new(now - 7_200_000).with("v", 1.0))?;
+        readings.insert("s", DataPoint::new(now).with("v", 2.0))?;
+        assert_eq!(readings.size("s")?, 1);
+        assert_eq!(readings.enforce_retention()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_width_is_fixed() {
+        let db = setup_nitrite();
+        db.time_series("fixed", TimeSeriesOptions::new()).unwrap();
+        let error = db
+            .time_series("fixed", TimeSeriesOptions::new().bucket_width(Duration::from_secs(60)))
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), &ErrorKind::ValidationError);
+        assert!(!db.list_collection_names().unwrap().iter().any(|name| name.contains("fixed")));
+    }
+}
@@ -0,0 +1,550 @@
+use crate::{
+    collection::{Document, NitriteCollection, NitriteId},
+    common::{LockHandle, LockRegistry},
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    filter::by_id_range,
+    get_current_time_or_zero,
+    store::{NitriteMap, NitriteMapProvider, NitriteStore, NitriteStoreProvider},
+    AttributeAware, DocumentCursor, Value, INTERNAL_NAME_SEPARATOR, TOPIC_GROUP_CURSOR,
+    TOPIC_GROUP_PREFIX,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+const PAYLOAD: &str = "payload";
+const DELIVERIES: &str = "deliveries";
+const VISIBLE_AT: &str = "visible_at";
+const ACKED: &str = "acked";
+
+/// Settings of a topic.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::topic::TopicOptions;
+/// use std::time::Duration;
+///
+/// let jobs = db.topic_with_options(
+///     "jobs",
+///     TopicOptions::new()
+///         .max_messages(1_000)
+///         .visibility_timeout(Duration::from_secs(60)),
+/// )?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicOptions {
+    max_messages: u64,
+    visibility_timeout: Duration,
+}
+
+impl Default for TopicOptions {
+    fn default() -> Self {
+        TopicOptions {
+            max_messages: 10_000,
+            visibility_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl TopicOptions {
+    /// Creates the default options: 10 000 retained messages and a visibility timeout of
+    /// 30 seconds.
+    pub fn new() -> Self {
+        TopicOptions::default()
+    }
+
+    /// Sets how many messages the topic retains. Once the topic is full, publishing a
+    /// message evicts the oldest one, whether it was delivered or not.
+    pub fn max_messages(mut self, max_messages: u64) -> Self {
+        self.max_messages = max_messages;
+        self
+    }
+
+    /// Sets how long a received message stays hidden from the other subscribers of the
+    /// group before it is delivered again, unless it is acknowledged.
+    pub fn visibility_timeout(mut self, visibility_timeout: Duration) -> Self {
+        self.visibility_timeout = visibility_timeout;
+        self
+    }
+
+    /// Returns how many messages the topic retains.
+    pub fn get_max_messages(&self) -> u64 {
+        self.max_messages
+    }
+
+    /// Returns how long a received message stays hidden.
+    pub fn get_visibility_timeout(&self) -> Duration {
+        self.visibility_timeout
+    }
+}
+
+/// A named channel of documents with at-least-once delivery to consumer groups.
+///
+/// A topic is opened with [`Nitrite::topic`](crate::nitrite::Nitrite::topic). Published
+/// documents are stored, in publish order, in an internal capped collection. Every
+/// consumer group sees every message; within a group each message is handed to one
+/// subscriber at a time:
+///
+/// - [`Subscription::receive`] returns the oldest message that is neither acknowledged
+///   nor held by another subscriber of the group, and hides it for the visibility timeout.
+/// - [`Subscription::ack`] marks the message as processed for the group.
+/// - [`Subscription::nack`] makes the message visible again right away.
+///
+/// A message that is not acknowledged before its visibility timeout expires is delivered
+/// again, so consumers must tolerate duplicates. Messages and the progress of every group
+/// are persisted with the database; the visibility timeout is not, it is taken from the
+/// options the topic is opened with.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let jobs = db.topic("jobs")?;
+/// jobs.publish(doc! { kind: "resize", image: "cat.png" })?;
+///
+/// let workers = jobs.subscribe("workers")?;
+/// while let Some(message) = workers.receive()? {
+///     match process(message.payload()) {
+///         Ok(_) => workers.ack(&message)?,
+///         Err(_) => workers.nack(&message)?,
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Topic {
+    inner: Arc<TopicInner>,
+}
+
+impl Topic {
+    pub(crate) fn new(
+        name: &str,
+        messages: NitriteCollection,
+        store: NitriteStore,
+        lock_registry: LockRegistry,
+        options: TopicOptions,
+    ) -> NitriteResult<Self> {
+        let collection_options = messages
+            .options()?
+            .max_documents(options.get_max_messages());
+        messages.set_options(collection_options)?;
+
+        Ok(Topic {
+            inner: Arc::new(TopicInner {
+                name: name.to_string(),
+                messages,
+                store,
+                lock_registry,
+                options,
+            }),
+        })
+    }
+
+    /// Returns the name of the topic.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the options the topic was opened with.
+    pub fn options(&self) -> &TopicOptions {
+        &self.inner.options
+    }
+
+    /// Publishes a document and returns the id of the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be stored.
+    pub fn publish(&self, payload: Document) -> NitriteResult<NitriteId> {
+        let mut message = Document::new();
+        message.put(PAYLOAD, Value::Document(payload))?;
+        let result = self.inner.messages.insert(message)?;
+        match result.affected_nitrite_ids().first() {
+            Some(id) => Ok(*id),
+            None => {
+                log::error!("Failed to publish a message to topic {}", self.inner.name);
+                Err(NitriteError::new(
+                    &format!("Failed to publish a message to topic {}", self.inner.name),
+                    ErrorKind::InternalError,
+                ))
+            }
+        }
+    }
+
+    /// Returns the number of messages the topic currently retains.
+    pub fn size(&self) -> NitriteResult<u64> {
+        self.inner.messages.size()
+    }
+
+    /// Joins the consumer group `group`, creating it if needed.
+    ///
+    /// A new group starts with the oldest message the topic retains.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ValidationError` if the group name is empty or contains the internal
+    /// name separator.
+    pub fn subscribe(&self, group: &str) -> NitriteResult<Subscription> {
+        if group.is_empty() || group.contains(INTERNAL_NAME_SEPARATOR) {
+            log::error!("Invalid consumer group name '{}'", group);
+            return Err(NitriteError::new(
+                &format!("Invalid consumer group name '{}'", group),
+                ErrorKind::ValidationError,
+            ));
+        }
+
+        let state_name = format!(
+            "{}{}{}{}{}",
+            TOPIC_GROUP_PREFIX,
+            INTERNAL_NAME_SEPARATOR,
+            self.inner.name,
+            INTERNAL_NAME_SEPARATOR,
+            group
+        );
+        let state = self.inner.store.open_map(&state_name)?;
+        let lock = self.inner.lock_registry.get_lock(&state_name);
+
+        Ok(Subscription {
+            inner: Arc::new(SubscriptionInner {
+                topic: self.clone(),
+                group: group.to_string(),
+                state,
+                lock,
+            }),
+        })
+    }
+}
+
+struct TopicInner {
+    name: String,
+    messages: NitriteCollection,
+    store: NitriteStore,
+    lock_registry: LockRegistry,
+    options: TopicOptions,
+}
+
+/// A message received from a topic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicMessage {
+    id: NitriteId,
+    payload: Document,
+    delivery_count: u32,
+}
+
+impl TopicMessage {
+    /// Returns the id of the message, which orders messages by publish time.
+    pub fn id(&self) -> NitriteId {
+        self.id
+    }
+
+    /// Returns the published document.
+    pub fn payload(&self) -> &Document {
+        &self.payload
+    }
+
+    /// Returns how many times the message has been handed to the group, this delivery
+    /// included. A count above 1 means an earlier delivery was not acknowledged in time
+    /// or was rejected.
+    pub fn delivery_count(&self) -> u32 {
+        self.delivery_count
+    }
+}
+
+/// Membership of a consumer group of a [`Topic`].
+///
+/// Subscriptions of the same group, in any thread, share the messages of the group:
+/// each message is received by one of them at a time. `Subscription` is cheap to clone.
+#[derive(Clone)]
+pub struct Subscription {
+    inner: Arc<SubscriptionInner>,
+}
+
+impl Subscription {
+    /// Returns the name of the consumer group.
+    pub fn group(&self) -> &str {
+        &self.inner.group
+    }
+
+    /// Returns the oldest message available to the group, or `None` if there is none.
+    ///
+    /// The message is hidden from the group for the visibility timeout of the topic. It
+    /// must be acknowledged with [`ack`](Self::ack) before the timeout expires, otherwise it
+    /// is delivered again.
+    pub fn receive(&self) -> NitriteResult<Option<TopicMessage>> {
+        self.inner.receive()
+    }
+
+    /// Acknowledges a message so the group never receives it again. Acknowledging a
+    /// message twice, or a message evicted from the topic, does nothing.
+    pub fn ack(&self, message: &TopicMessage) -> NitriteResult<()> {
+        self.inner.ack(&message.id)
+    }
+
+    /// Rejects a message so the group can receive it again right away.
+    pub fn nack(&self, message: &TopicMessage) -> NitriteResult<()> {
+        self.inner.nack(&message.id)
+    }
+}
+
+struct SubscriptionInner {
+    topic: Topic,
+    group: String,
+    state: NitriteMap,
+    lock: LockHandle,
+}
+
+/// Progress of the group for a message it received but has not yet passed by.
+struct DeliveryState {
+    deliveries: u32,
+    visible_at: u128,
+    acked: bool,
+}
+
+impl DeliveryState {
+    fn from_value(value: Value) -> Option<Self> {
+        let document = match value {
+            Value::Document(document) => document,
+            _ => return None,
+        };
+        let deliveries = match document.get(DELIVERIES).ok()? {
+            Value::U32(deliveries) => deliveries,
+            _ => 0,
+        };
+        let visible_at = match document.get(VISIBLE_AT).ok()? {
+            Value::U128(visible_at) => visible_at,
+            _ => 0,
+        };
+        let acked = matches!(document.get(ACKED).ok()?, Value::Bool(true));
+        Some(DeliveryState {
+            deliveries,
+            visible_at,
+            acked,
+        })
+    }
+
+    fn to_value(&self) -> NitriteResult<Value> {
+        let mut document = Document::new();
+        document.put(DELIVERIES, Value::U32(self.deliveries))?;
+        document.put(VISIBLE_AT, Value::U128(self.visible_at))?;
+        document.put(ACKED, Value::Bool(self.acked))?;
+        Ok(Value::Document(document))
+    }
+}
+
+impl SubscriptionInner {
+    fn receive(&self) -> NitriteResult<Option<TopicMessage>> {
+        let _guard = self.lock.write();
+        let now = get_current_time_or_zero();
+        let cursor = self.cursor()?;
+
+        for message in self.pending_messages(cursor)? {
+            let mut message = message?;
+            let id = message.id()?;
+            if cursor.is_some_and(|cursor| id.id_value() <= cursor.id_value()) {
+                continue;
+            }
+
+            let state = self.delivery_state(&id)?;
+            if state
+                .as_ref()
+                .is_some_and(|state| state.acked || state.visible_at > now)
+            {
+                continue;
+            }
+
+            let timeout = self.topic.inner.options.get_visibility_timeout().as_millis();
+            let state = DeliveryState {
+                deliveries: state.map_or(0, |state| state.deliveries) + 1,
+                visible_at: get_current_time_or_zero().saturating_add(timeout),
+                acked: false,
+            };
+            self.state.put(Value::NitriteId(id), state.to_value()?)?;
+
+            let payload = match message.get(PAYLOAD)? {
+                Value::Document(payload) => payload,
+                _ => Document::new(),
+            };
+            return Ok(Some(TopicMessage {
+                id,
+                payload,
+                delivery_count: state.deliveries,
+            }));
+        }
+        Ok(None)
+    }
+
+    fn ack(&self, id: &NitriteId) -> NitriteResult<()> {
+        let _guard = self.lock.write();
+        let mut state = match self.delivery_state(id)? {
+            Some(state) if !state.acked => state,
+            _ => return Ok(()),
+        };
+        state.acked = true;
+        self.state.put(Value::NitriteId(*id), state.to_value()?)?;
+        self.advance_cursor()
+    }
+
+    fn nack(&self, id: &NitriteId) -> NitriteResult<()> {
+        let _guard = self.lock.write();
+        let mut state = match self.delivery_state(id)? {
+            Some(state) if !state.acked => state,
+            _ => return Ok(()),
+        };
+        state.visible_at = 0;
+        self.state.put(Value::NitriteId(*id), state.to_value()?)
+    }
+
+    /// Moves the cursor of the group past the acknowledged messages at the head of the
+    /// topic and drops their delivery state, together with the state of messages that
+    /// were evicted before being acknowledged.
+    fn advance_cursor(&self) -> NitriteResult<()> {
+        let initial = self.cursor()?;
+        let mut advanced = None;
+
+        for message in self.pending_messages(initial)? {
+            let id = message?.id()?;
+            if initial.is_some_and(|initial| id.id_value() <= initial.id_value()) {
+                continue;
+            }
+            match self.delivery_state(&id)? {
+                Some(state) if state.acked => advanced = Some(id),
+                _ => break,
+            }
+        }
+
+        let cursor = match advanced {
+            Some(cursor) => cursor,
+            None => return Ok(()),
+        };
+
+        while let Some(key) = self.state.first_key()? {
+            match key {
+                Value::NitriteId(id) if id.id_value() <= cursor.id_value() => {
+                    self.state.remove(&key)?;
+                }
+                _ => break,
+            }
+        }
+
+        let mut attributes = self.state.attributes()?.unwrap_or_default();
+        attributes.put(TOPIC_GROUP_CURSOR, Value::NitriteId(cursor));
+        self.state.set_attributes(attributes)
+    }
+
+    /// Returns the id of the last message the group is done with, if any.
+    fn cursor(&self) -> NitriteResult<Option<NitriteId>> {
+        Ok(self.state.attributes()?.and_then(|attributes| {
+            match attributes.get(TOPIC_GROUP_CURSOR) {
+                Some(Value::NitriteId(id)) => Some(*id),
+                _ => None,
+            }
+        }))
+    }
+
+    /// Returns the messages from the cursor on, in publish order.
+    fn pending_messages(&self, cursor: Option<NitriteId>) -> NitriteResult<DocumentCursor> {
+        let (lowest, highest) = NitriteId::from_time_range(0, u128::MAX)?;
+        let start = cursor.unwrap_or(lowest);
+        self.topic.inner.messages.find(by_id_range(start, highest))
+    }
+
+    fn delivery_state(&self, id: &NitriteId) -> NitriteResult<Option<DeliveryState>> {
+        Ok(self
+            .state
+            .get(&Value::NitriteId(*id))?
+            .and_then(DeliveryState::from_value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc;
+    use crate::nitrite::Nitrite;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    fn seq(message: &TopicMessage) -> Value {
+        message.payload().get("seq").unwrap()
+    }
+
+    #[test]
+    fn test_publish_receive_ack() {
+        let db = setup_nitrite();
+        let jobs = db.topic("jobs").unwrap();
+        for seq in 0..3 {
+            jobs.publish(doc! { seq: seq }).unwrap();
+        }
+        assert_eq!(jobs.size().unwrap(), 3);
+        assert!(!db.list_collection_names().unwrap().iter().any(|name| name.contains("jobs")));
+
+        let workers = jobs.subscribe("workers").unwrap();
+        let first = workers.receive().unwrap().unwrap();
+        let second = workers.receive().unwrap().unwrap();
+        assert_eq!(seq(&first), Value::I32(0));
+        assert_eq!(seq(&second), Value::I32(1));
+        assert_eq!(first.delivery_count(), 1);
+
+        workers.ack(&second).unwrap();
+        workers.ack(&first).unwrap();
+        workers.ack(&first).unwrap();
+        let third = workers.receive().unwrap().unwrap();
+        assert_eq!(seq(&third), Value::I32(2));
+        workers.ack(&third).unwrap();
+        assert!(workers.receive().unwrap().is_none());
+
+        // every group sees every message
+        let audit = jobs.subscribe("audit").unwrap();
+        assert_eq!(seq(&audit.receive().unwrap().unwrap()), Value::I32(0));
+    }
+
+    #[test]
+    fn test_nack_and_visibility_timeout() {
+        let db = setup_nitrite();
+        let jobs = db
+            .topic_with_options(
+                "jobs",
+                TopicOptions::new().visibility_timeout(Duration::from_millis(200)),
+            )
+            .unwrap();
+        jobs.publish(doc! { seq: 0 }).unwrap();
+
+        let workers = jobs.subscribe("workers").unwrap();
+        let other = jobs.subscribe("workers").unwrap();
+        let message = workers.receive().unwrap().unwrap();
+        assert!(other.receive().unwrap().is_none());
+
+        workers.nack(&message).unwrap();
+        let message = other.receive().unwrap().unwrap();
+        assert_eq!(message.delivery_count(), 2);
+
+        std::thread::sleep(Duration::from_millis(300));
+        let message = workers.receive().unwrap().unwrap();
+        assert_eq!(message.delivery_count(), 3);
+        workers.ack(&message).unwrap();
+        assert!(other.receive().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_topic_is_capped() {
+        let db = setup_nitrite();
+        let jobs = db
+            .topic_with_options("jobs", TopicOptions::new().max_messages(2))
+            .unwrap();
+        let workers = jobs.subscribe("workers").unwrap();
+        for seq in 0..4 {
+            jobs.publish(doc! { seq: seq }).unwrap();
+        }
+        assert_eq!(jobs.size().unwrap(), 2);
+        assert_eq!(seq(&workers.receive().unwrap().unwrap()), Value::I32(2));
+    }
+
+    #[test]
+    fn test_invalid_names() {
+        let db = setup_nitrite();
+        assert!(db.topic("a|b").is_err());
+        let jobs = db.topic("jobs").unwrap();
+        assert!(jobs.subscribe("").is_err());
+        assert!(jobs.subscribe("a|b").is_err());
+    }
+}
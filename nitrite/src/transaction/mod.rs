@@ -6,6 +6,7 @@
 pub mod core;
 pub mod iters;
 pub mod nitrite_transaction;
+pub mod retry;
 pub mod session;
 pub mod transaction_store;
 pub mod transactional_collection;
@@ -18,6 +19,7 @@ pub use core::{
 };
 pub use iters::{TransactionEntryProvider, TransactionKeyProvider, TransactionValueProvider};
 pub use nitrite_transaction::NitriteTransaction;
+pub use retry::RetryPolicy;
 pub use session::Session;
 pub use transaction_store::TransactionStore;
 pub use transactional_map::TransactionalMap;
@@ -1,9 +1,9 @@
 use super::core::{JournalEntry, TransactionContext, TransactionState, UndoEntry};
 use super::transaction_store::TransactionStore;
 use crate::collection::operation::CollectionOperations;
-use crate::collection::{NitriteCollection, NitriteCollectionProvider};
+use crate::collection::{EventOrigin, NitriteCollection, NitriteCollectionProvider, WriteToken, WriteTracker};
 use crate::common::{
-    repository_name_by_type, Convertible, LockRegistry, NitriteEventBus, NitriteModule,
+    repository_name_by_type, Convertible, LockRegistry, ModuleInfo, NitriteEventBus, NitriteModule,
     NitritePlugin, PluginRegistrar,
 };
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
@@ -34,6 +34,10 @@ impl NitriteModule for TransactionStoreModule {
     fn load(&self, plugin_registrar: &PluginRegistrar) -> NitriteResult<()> {
         plugin_registrar.register_store_plugin(self.store.clone())
     }
+
+    fn info(&self) -> ModuleInfo {
+        ModuleInfo::new("transaction-store", env!("CARGO_PKG_VERSION")).store()
+    }
 }
 /// Nitrite transaction implementation
 ///
@@ -76,6 +80,8 @@ use uuid::Uuid;
 /// ```
 pub struct NitriteTransaction {
     id: String,
+    /// Id of the session the transaction was begun from, if any
+    session_id: Option<String>,
     state: Arc<Mutex<TransactionState>>,
     contexts: Arc<Mutex<HashMap<String, TransactionContext>>>,
     undo_registry: Arc<Mutex<HashMap<String, Vec<UndoEntry>>>>,
@@ -85,6 +91,9 @@ pub struct NitriteTransaction {
     lock_registry: LockRegistry,
     store: TransactionStore,
     tx_config: NitriteConfig,
+    write_tracker: WriteTracker,
+    /// Token of all the writes of the transaction, pending until it is closed
+    write_token: WriteToken,
 }
 
 impl NitriteTransaction {
@@ -113,8 +122,12 @@ impl NitriteTransaction {
         tx_config.auto_configure()?;
         tx_config.initialize()?;
 
+        let write_tracker = db.config().write_tracker();
+        let write_token = write_tracker.reserve();
+
         Ok(NitriteTransaction {
             id: Uuid::new_v4().to_string(),
+            session_id: None,
             state: Arc::new(Mutex::new(TransactionState::Active)),
             contexts: Arc::new(Mutex::new(HashMap::new())),
             undo_registry: Arc::new(Mutex::new(HashMap::new())),
@@ -124,6 +137,8 @@ impl NitriteTransaction {
             lock_registry,
             store: tx_store,
             tx_config,
+            write_tracker,
+            write_token,
         })
     }
 
@@ -135,6 +150,20 @@ impl NitriteTransaction {
         &self.id
     }
 
+    /// Gets the id of the session the transaction was begun from.
+    ///
+    /// # Returns
+    /// The session id, or `None` for a transaction begun from the database directly
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Records the session the transaction was begun from.
+    pub(crate) fn with_session_id(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
     /// Gets the current transaction state.
     ///
     /// # Returns
@@ -172,7 +201,16 @@ impl NitriteTransaction {
             self.tx_config.clone(), // Use transaction config for isolated index operations
             event_bus.clone(),
         )?;
-        let tc = TransactionalCollection::new(primary, context, db_store, operations, event_bus);
+        operations.set_redaction_policy(primary.redaction_policy()?);
+        let tc = TransactionalCollection::new(
+            primary,
+            context,
+            db_store,
+            operations,
+            event_bus,
+            self.write_tracker.clone(),
+            self.write_token,
+        );
         registry.insert(name.to_string(), tc.clone());
         Ok(NitriteCollection::new(tc))
     }
@@ -243,12 +281,15 @@ impl NitriteTransaction {
             self.tx_config.clone(), // Use transaction config for isolated index operations
             event_bus.clone(),
         )?;
+        operations.set_redaction_policy(primary_repo.document_collection().redaction_policy()?);
         let tc = TransactionalCollection::new(
             primary_repo.document_collection(),
             context,
             db_store,
             operations,
             event_bus,
+            self.write_tracker.clone(),
+            self.write_token,
         );
         registry.insert(name.clone(), tc.clone());
         self.create_repository_from_collection::<T>(tc, key)
@@ -347,9 +388,10 @@ impl NitriteTransaction {
                 // Try to rollback on failure
                 let _ = self.perform_rollback();
                 self.close();
-                Err(NitriteError::new(
+                Err(NitriteError::new_with_cause(
                     &format!("Commit failed: {}", e.message()),
                     ErrorKind::InvalidOperation,
+                    e,
                 ))
             }
         }
@@ -394,6 +436,8 @@ impl NitriteTransaction {
         // `undo_cell` so it is still available on the failure path (where it drives the
         // logical rollback for non-atomic backends).
         let supports_atomic = self.db.store().supports_atomic();
+        // the collection events raised by the commit name this transaction
+        let _origin = EventOrigin::enter(self.session_id.clone(), self.id.clone());
         let undo_cell: Mutex<HashMap<String, Vec<UndoEntry>>> = Mutex::new(HashMap::new());
 
         let outcome = self.db.store().with_atomic(|| {
@@ -407,9 +451,10 @@ impl NitriteTransaction {
                             // non-atomic backend can still undo what was applied.
                             undo_registry.insert(collection_name.clone(), undo_stack);
                             *undo_cell.lock() = undo_registry;
-                            return Err(NitriteError::new(
+                            return Err(NitriteError::new_with_cause(
                                 &format!("Failed to execute commit: {}", e.message()),
                                 ErrorKind::InvalidOperation,
+                                e,
                             ));
                         }
 
@@ -514,6 +559,8 @@ impl NitriteTransaction {
 
         *self.state.lock() = TransactionState::Closed;
         let _ = self.store.close_all();
+        // the writes are now committed or rolled back, stop holding back the readers
+        self.write_tracker.release(self.write_token);
     }
 
     /// Checks if transaction is active
@@ -551,6 +598,7 @@ impl Clone for NitriteTransaction {
     fn clone(&self) -> Self {
         NitriteTransaction {
             id: self.id.clone(),
+            session_id: self.session_id.clone(),
             state: Arc::clone(&self.state),
             contexts: Arc::clone(&self.contexts),
             undo_registry: Arc::clone(&self.undo_registry),
@@ -560,6 +608,8 @@ impl Clone for NitriteTransaction {
             lock_registry: self.lock_registry.clone(),
             store: self.store.clone(),
             tx_config: self.tx_config.clone(),
+            write_tracker: self.write_tracker.clone(),
+            write_token: self.write_token,
         }
     }
 }
@@ -591,11 +641,12 @@ impl Drop for NitriteTransaction {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::collection::Document;
+    use crate::collection::{Document, FindOptions};
     use crate::common::Convertible;
     use crate::common::LockRegistry;
     use crate::common::Value;
     use crate::errors::ErrorKind;
+    use crate::filter::all;
     use crate::repository::{EntityId, EntityIndex, NitriteEntity};
     use crate::transaction::core::ChangeType;
 
@@ -1411,4 +1462,34 @@ mod tests {
             assert!(err.message().contains("not active"));
         }
     }
+
+    /// Tests that a query after a transactional write waits for the commit
+    #[test]
+    fn test_after_write_waits_for_commit() {
+        let db = create_test_db();
+        let tx = NitriteTransaction::new(db.clone(), LockRegistry::new()).unwrap();
+        let tx_collection = tx.collection("orders").unwrap();
+
+        let mut doc = Document::new();
+        doc.put("status", "paid").unwrap();
+        let token = tx_collection.insert(doc).unwrap().write_token();
+
+        // the transaction sees its own write without waiting
+        let options = FindOptions::new().after_write(token);
+        assert_eq!(tx_collection.find_with_options(all(), &options).unwrap().count(), 1);
+
+        let reader_db = db.clone();
+        let reader = std::thread::spawn(move || {
+            let options = FindOptions::new().after_write(token);
+            let orders = reader_db.collection("orders").unwrap();
+            orders.find_with_options(all(), &options).unwrap().count()
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        tx.commit().unwrap();
+        assert_eq!(reader.join().unwrap(), 1);
+
+        let token = db.collection("orders").unwrap().remove(all(), false).unwrap().write_token();
+        assert!(token.sequence() > 0);
+    }
 }
@@ -0,0 +1,247 @@
+//! Retry policy for transactions that fail with a retryable error.
+
+use super::{NitriteTransaction, TransactionState};
+use crate::errors::NitriteResult;
+use crate::nitrite::Nitrite;
+use rand::Rng;
+use std::thread;
+use std::time::Duration;
+
+/// Controls how [`Nitrite::run_in_transaction_with`] retries a transaction.
+///
+/// A transaction is retried when it fails with an error for which
+/// [`NitriteError::is_retryable`](crate::errors::NitriteError::is_retryable) returns `true`
+/// (a write conflict or a lock timeout). Before each retry the caller sleeps for a backoff
+/// that starts at `initial_backoff`, is multiplied by `multiplier` after every attempt and is
+/// capped at `max_backoff`. Up to half of each backoff is randomized so that conflicting
+/// callers do not retry in lockstep.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::transaction::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new()
+///     .max_attempts(10)
+///     .initial_backoff(Duration::from_millis(5));
+///
+/// db.run_in_transaction_with(&policy, |tx| {
+///     let accounts = tx.collection("accounts")?;
+///     accounts.update(field("id").eq(1), &doc! { balance: 90 })?;
+///     Ok(())
+/// })?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates the default policy: 5 attempts, backing off from 10ms up to 1s.
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Sets the total number of attempts, including the first one (at least 1).
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the backoff before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the upper bound of the backoff between two attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the factor the backoff grows by after each attempt (at least 1.0).
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Returns the total number of attempts.
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the backoff before the first retry.
+    pub fn get_initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// Returns the upper bound of the backoff.
+    pub fn get_max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// Returns the backoff growth factor.
+    pub fn get_multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Returns the backoff before retry number `retry` (starting at 1), without jitter.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.saturating_sub(1) as i32);
+        let backoff = self.initial_backoff.as_secs_f64() * factor;
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    fn sleep(&self, retry: u32) {
+        let backoff = self.backoff(retry);
+        let jitter = rand::thread_rng().gen_range(0.0..=0.5);
+        thread::sleep(backoff.mul_f64(1.0 - jitter));
+    }
+}
+
+/// Runs `func` in a new transaction, committing it on success and rolling it back on
+/// failure, and retries the whole transaction as configured by `policy`.
+pub(crate) fn run_in_transaction<F, R>(
+    db: &Nitrite,
+    policy: &RetryPolicy,
+    mut func: F,
+) -> NitriteResult<R>
+where
+    F: FnMut(&NitriteTransaction) -> NitriteResult<R>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = db.with_session(|session| {
+            let transaction = session.begin_transaction()?;
+            match func(&transaction) {
+                Ok(value) => {
+                    if transaction.state() == TransactionState::Active {
+                        transaction.commit()?;
+                    }
+                    Ok(value)
+                }
+                Err(e) => {
+                    if let Err(rollback_err) = transaction.rollback() {
+                        log::warn!("Failed to roll back transaction: {}", rollback_err);
+                    }
+                    Err(e)
+                }
+            }
+        });
+
+        match result {
+            Err(e) if e.is_retryable() && attempt < policy.max_attempts => {
+                log::debug!(
+                    "Transaction attempt {} of {} failed, retrying: {}",
+                    attempt,
+                    policy.max_attempts,
+                    e
+                );
+                policy.sleep(attempt);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::NitriteCollectionProvider;
+    use crate::common::PersistentCollection;
+    use crate::doc;
+    use crate::errors::{ErrorKind, NitriteError};
+    use crate::filter::all;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(1))
+            .max_backoff(Duration::from_millis(2))
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(10))
+            .max_backoff(Duration::from_millis(50));
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(3), Duration::from_millis(40));
+        assert_eq!(policy.backoff(4), Duration::from_millis(50));
+        assert_eq!(RetryPolicy::new().max_attempts(0).get_max_attempts(), 1);
+    }
+
+    #[test]
+    fn test_retries_conflicts_until_success() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let mut attempts = 0;
+        let result = run_in_transaction(&db, &fast_policy(), |tx| {
+            attempts += 1;
+            tx.collection("items")?.insert(doc! { attempt: attempts })?;
+            if attempts < 3 {
+                return Err(NitriteError::new("Conflict", ErrorKind::TransactionConflict));
+            }
+            Ok(attempts)
+        });
+
+        assert_eq!(result.unwrap(), 3);
+        // the failed attempts were rolled back
+        let items = db.collection("items").unwrap();
+        assert_eq!(items.find(all()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let mut attempts = 0;
+        let result: NitriteResult<()> =
+            run_in_transaction(&db, &fast_policy().max_attempts(2), |_| {
+                attempts += 1;
+                Err(NitriteError::new("Timed out", ErrorKind::Timeout))
+            });
+
+        assert_eq!(result.unwrap_err().kind(), &ErrorKind::Timeout);
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn test_does_not_retry_other_errors() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let mut attempts = 0;
+        let result: NitriteResult<()> = run_in_transaction(&db, &fast_policy(), |_| {
+            attempts += 1;
+            Err(NitriteError::new("Invalid", ErrorKind::ValidationError))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_commits_on_success() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        run_in_transaction(&db, &RetryPolicy::new(), |tx| {
+            tx.collection("items")?.insert(doc! { n: 1 })?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(db.collection("items").unwrap().size().unwrap(), 1);
+    }
+}
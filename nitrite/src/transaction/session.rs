@@ -183,7 +183,8 @@ impl SessionInner {
     pub fn begin_transaction(&self) -> NitriteResult<NitriteTransaction> {
         self.check_active()?;
 
-        let tx = NitriteTransaction::new(self.db.clone(), self.lock_registry.clone())?;
+        let tx = NitriteTransaction::new(self.db.clone(), self.lock_registry.clone())?
+            .with_session_id(&self.id);
         let tx_id = tx.id().to_string();
 
         self.transactions.lock().insert(tx_id, tx.clone());
@@ -1,18 +1,19 @@
+use parking_lot::Mutex;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 use super::core::{ChangeType, Command, JournalEntry, TransactionContext};
 use crate::collection::operation::{CollectionOperations, WriteResult};
 use crate::collection::{
-    CollectionEventInfo, CollectionEventListener, Document, FindOptions, NitriteCollection, NitriteCollectionProvider, NitriteId, UpdateOptions
+    BulkOperation, BulkWriteOptions, BulkWriteResult, CollectionEventInfo, CollectionOptions, CollectionEventListener, Document, DocumentVersion, FindOptions, HistoryOptions, NitriteCollection, NitriteCollectionProvider, NitriteId, RedactionPolicy, UpdateEachOptions, UpdateEachResult, UpdateOptions, WriteToken, WriteTokenHolder, WriteTracker
 };
 use crate::common::{
     create_unique_filter, AttributeAware, Attributes, EventAware,
-    NitriteEventBus, PersistentCollection, Processor, DOC_ID,
+    NitriteEventBus, PersistentCollection, Processor, Value, DOC_ID,
 };
 use crate::errors::{ErrorKind, NitriteError, NitriteResult};
 use crate::filter::{all, field, is_all_filter};
-use crate::index::{IndexDescriptor, IndexOptions};
+use crate::index::{IndexDescriptor, IndexOptions, IndexStatistics};
 use crate::store::NitriteStore;
 
 #[derive(Clone)]
@@ -27,6 +28,8 @@ impl TransactionalCollection {
         store: NitriteStore,
         operations: CollectionOperations,
         event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
+        write_tracker: WriteTracker,
+        write_token: WriteToken,
     ) -> Self {
         let inner = TransactionalCollectionInner::new(
             primary,
@@ -34,6 +37,8 @@ impl TransactionalCollection {
             store,
             operations,
             event_bus,
+            write_tracker,
+            write_token,
         );
         TransactionalCollection {
             inner: Arc::new(inner),
@@ -132,11 +137,11 @@ impl AttributeAware for TransactionalCollection {
 
 impl NitriteCollectionProvider for TransactionalCollection {
     fn insert(&self, document: Document) -> NitriteResult<WriteResult> {
-        self.inner.insert(document)
+        self.inner.stamped(self.inner.insert(document))
     }
 
     fn insert_many(&self, documents: Vec<Document>) -> NitriteResult<WriteResult> {
-        self.inner.insert_batch(documents)
+        self.inner.stamped(self.inner.insert_batch(documents))
     }
 
     fn update_with_options(
@@ -146,7 +151,7 @@ impl NitriteCollectionProvider for TransactionalCollection {
         update_options: &crate::collection::UpdateOptions,
     ) -> NitriteResult<WriteResult> {
         self.inner
-            .update_with_options(filter, update, update_options)
+            .stamped(self.inner.update_with_options(filter, update, update_options))
     }
 
     fn update_one(
@@ -154,7 +159,7 @@ impl NitriteCollectionProvider for TransactionalCollection {
         document: &Document,
         insert_if_absent: bool,
     ) -> NitriteResult<WriteResult> {
-        self.inner.update_one(document, insert_if_absent)
+        self.inner.stamped(self.inner.update_one(document, insert_if_absent))
     }
 
     fn update_by_id(
@@ -163,15 +168,46 @@ impl NitriteCollectionProvider for TransactionalCollection {
         update: &Document,
         insert_if_absent: bool,
     ) -> NitriteResult<WriteResult> {
-        self.inner.update_by_id(id, update, insert_if_absent)
+        self.inner.stamped(self.inner.update_by_id(id, update, insert_if_absent))
+    }
+
+    fn increment(&self, id: &NitriteId, field: &str, delta: Value) -> NitriteResult<Value> {
+        self.inner.increment(id, field, delta)
     }
 
     fn remove(&self, filter: crate::filter::Filter, just_once: bool) -> NitriteResult<WriteResult> {
-        self.inner.remove(filter, just_once)
+        self.inner.stamped(self.inner.remove(filter, just_once))
     }
 
     fn remove_one(&self, document: &Document) -> NitriteResult<WriteResult> {
-        self.inner.remove_one(document)
+        self.inner.stamped(self.inner.remove_one(document))
+    }
+
+    fn bulk_write_with_options(
+        &self,
+        _operations: Vec<BulkOperation>,
+        _options: &BulkWriteOptions,
+    ) -> NitriteResult<BulkWriteResult> {
+        // a bulk write is its own transaction and transactions do not nest
+        log::error!("Bulk write is not supported inside a transaction");
+        Err(NitriteError::new(
+            "Bulk write is not supported inside a transaction, write the documents directly",
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    fn update_each_with_options(
+        &self,
+        _filter: crate::filter::Filter,
+        _transform: &mut dyn FnMut(Document) -> NitriteResult<Option<Document>>,
+        _options: &UpdateEachOptions,
+    ) -> NitriteResult<UpdateEachResult> {
+        // every batch of update_each is its own transaction
+        log::error!("update_each is not supported inside a transaction");
+        Err(NitriteError::new(
+            "update_each is not supported inside a transaction, update the documents directly",
+            ErrorKind::InvalidOperation,
+        ))
     }
 
     fn find(&self, filter: crate::filter::Filter) -> NitriteResult<crate::common::DocumentCursor> {
@@ -186,10 +222,95 @@ impl NitriteCollectionProvider for TransactionalCollection {
         self.inner.find_with_options(_filter, find_options)
     }
 
+    fn count(&self, filter: crate::filter::Filter) -> NitriteResult<u64> {
+        self.inner.count(filter)
+    }
+
     fn get_by_id(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
         self.inner.get_by_id(id)
     }
 
+    fn enable_history(&self, options: HistoryOptions) -> NitriteResult<()> {
+        self.inner.enable_history(options)
+    }
+
+    fn disable_history(&self) -> NitriteResult<()> {
+        self.inner.disable_history()
+    }
+
+    fn history_options(&self) -> NitriteResult<Option<HistoryOptions>> {
+        self.inner.history_options()
+    }
+
+    fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        self.inner.document_history(id)
+    }
+
+    fn find_as_of(
+        &self,
+        timestamp: u128,
+        filter: crate::filter::Filter,
+    ) -> NitriteResult<crate::common::DocumentCursor> {
+        self.inner.find_as_of(timestamp, filter)
+    }
+
+    fn analyze(&self) -> NitriteResult<()> {
+        self.inner.analyze()
+    }
+
+    fn index_statistics(&self, field_names: Vec<&str>) -> NitriteResult<Option<IndexStatistics>> {
+        self.inner.index_statistics(field_names)
+    }
+
+    fn defer_index_maintenance(
+        &self,
+        _bulk_load: &mut dyn FnMut() -> NitriteResult<()>,
+    ) -> NitriteResult<()> {
+        // the rebuild after the bulk load is not part of the transaction
+        log::error!("Deferring index maintenance is not supported inside a transaction");
+        Err(NitriteError::new(
+            "Deferring index maintenance is not supported inside a transaction",
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    fn options(&self) -> NitriteResult<CollectionOptions> {
+        self.inner.options()
+    }
+
+    fn set_options(&self, options: CollectionOptions) -> NitriteResult<()> {
+        self.inner.set_options(options)
+    }
+
+    fn deleted_documents(&self) -> NitriteResult<Vec<Document>> {
+        self.inner.deleted_documents()
+    }
+
+    fn restore(&self, _id: &NitriteId) -> NitriteResult<WriteResult> {
+        // the soft-deleted documents are not part of the transaction's snapshot
+        log::error!("Restore is not supported inside a transaction");
+        Err(NitriteError::new(
+            "Restore is not supported inside a transaction, restore the document directly",
+            ErrorKind::InvalidOperation,
+        ))
+    }
+
+    fn purge_deleted(&self) -> NitriteResult<()> {
+        self.inner.purge_deleted()
+    }
+
+    fn sweep_expired_fields(&self) -> NitriteResult<u64> {
+        self.inner.sweep_expired_fields()
+    }
+
+    fn set_redaction_policy(&self, policy: Option<RedactionPolicy>) -> NitriteResult<()> {
+        self.inner.set_redaction_policy(policy)
+    }
+
+    fn redaction_policy(&self) -> NitriteResult<Option<RedactionPolicy>> {
+        self.inner.redaction_policy()
+    }
+
     fn name(&self) -> String {
         self.inner.name()
     }
@@ -203,6 +324,9 @@ struct TransactionalCollectionInner {
     closed: Arc<AtomicBool>,
     event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
     operations: CollectionOperations,
+    write_tracker: WriteTracker,
+    /// Token of all the writes of the transaction, released when it commits or rolls back
+    write_token: WriteToken,
 }
 
 impl TransactionalCollectionInner {
@@ -212,6 +336,8 @@ impl TransactionalCollectionInner {
         store: NitriteStore,
         operations: CollectionOperations,
         event_bus: NitriteEventBus<CollectionEventInfo, CollectionEventListener>,
+        write_tracker: WriteTracker,
+        write_token: WriteToken,
     ) -> Self {
         TransactionalCollectionInner {
             primary,
@@ -221,9 +347,18 @@ impl TransactionalCollectionInner {
             closed: Arc::new(AtomicBool::new(false)),
             event_bus,
             operations,
+            write_tracker,
+            write_token,
         }
     }
 
+    /// Stamps the result of a write with the token of the transaction.
+    fn stamped(&self, result: NitriteResult<WriteResult>) -> NitriteResult<WriteResult> {
+        let mut result = result?;
+        result.set_write_token(self.write_token);
+        Ok(result)
+    }
+
     fn check_open(&self) -> NitriteResult<()> {
         let is_closed = self.closed.load(std::sync::atomic::Ordering::Acquire);
         if is_closed {
@@ -588,6 +723,42 @@ impl TransactionalCollectionInner {
         Ok(result)
     }
 
+    fn increment(&self, id: &NitriteId, field: &str, delta: Value) -> NitriteResult<Value> {
+        self.check_open()?;
+        let counter = self.operations.increment(id, field, &delta)?;
+
+        // the commit adds the delta to the primary's current value rather than writing
+        // the counter seen here, so increments of concurrent transactions all count
+        let primary = self.primary.clone();
+        let primary_for_rollback = self.primary.clone();
+        let id = *id;
+        let field = field.to_string();
+        let field_for_rollback = field.clone();
+        let original: Arc<Mutex<Option<Value>>> = Arc::new(Mutex::new(None));
+        let original_for_rollback = original.clone();
+
+        let commit: Command = Arc::new(move || {
+            if let Some(document) = primary.get_by_id(&id)? {
+                *original.lock() = Some(document.get(&field)?);
+            }
+            primary.increment(&id, &field, delta.clone())?;
+            Ok(())
+        });
+
+        let rollback: Command = Arc::new(move || {
+            if let Some(value) = original_for_rollback.lock().take() {
+                let mut update = Document::new();
+                update.put(&field_for_rollback, value)?;
+                primary_for_rollback.update_by_id(&id, &update, false)?;
+            }
+            Ok(())
+        });
+
+        let entry = JournalEntry::new(ChangeType::Update, Some(commit), Some(rollback));
+        self.context.add_entry(entry)?;
+        Ok(counter)
+    }
+
     fn remove(&self, filter: crate::filter::Filter, just_once: bool) -> NitriteResult<WriteResult> {
         if is_all_filter(&filter) && just_once {
             log::error!("Cannot remove all documents with just once as true");
@@ -684,12 +855,23 @@ impl TransactionalCollectionInner {
         self.operations.find(filter, &FindOptions::new())
     }
 
+    fn count(&self, filter: crate::filter::Filter) -> NitriteResult<u64> {
+        self.check_open()?;
+        self.operations.count(filter)
+    }
+
     fn find_with_options(
         &self,
         _filter: crate::filter::Filter,
         find_options: &crate::collection::FindOptions,
     ) -> NitriteResult<crate::common::DocumentCursor> {
         self.check_open()?;
+        // the writes of this transaction are already visible to it
+        if let Some(token) = find_options.after_write {
+            if token != self.write_token {
+                self.write_tracker.wait_for(token)?;
+            }
+        }
         self.operations.find(_filter, find_options)
     }
 
@@ -698,6 +880,86 @@ impl TransactionalCollectionInner {
         self.operations.get_by_id(id)
     }
 
+    // Revision history belongs to the primary collection: committed writes are replayed
+    // there and recorded, so history is read and configured on the primary directly.
+    fn enable_history(&self, options: HistoryOptions) -> NitriteResult<()> {
+        self.check_open()?;
+        self.primary.enable_history(options)
+    }
+
+    fn disable_history(&self) -> NitriteResult<()> {
+        self.check_open()?;
+        self.primary.disable_history()
+    }
+
+    fn history_options(&self) -> NitriteResult<Option<HistoryOptions>> {
+        self.check_open()?;
+        self.primary.history_options()
+    }
+
+    fn document_history(&self, id: &NitriteId) -> NitriteResult<Vec<DocumentVersion>> {
+        self.check_open()?;
+        self.primary.document_history(id)
+    }
+
+    fn find_as_of(
+        &self,
+        timestamp: u128,
+        filter: crate::filter::Filter,
+    ) -> NitriteResult<crate::common::DocumentCursor> {
+        self.check_open()?;
+        self.primary.find_as_of(timestamp, filter)
+    }
+
+    // Statistics describe the committed data, so they are gathered on the primary.
+    fn analyze(&self) -> NitriteResult<()> {
+        self.check_open()?;
+        self.primary.analyze()
+    }
+
+    fn index_statistics(&self, field_names: Vec<&str>) -> NitriteResult<Option<IndexStatistics>> {
+        self.check_open()?;
+        self.primary.index_statistics(field_names)
+    }
+
+    fn options(&self) -> NitriteResult<CollectionOptions> {
+        self.check_open()?;
+        self.primary.options()
+    }
+
+    fn set_options(&self, options: CollectionOptions) -> NitriteResult<()> {
+        self.check_open()?;
+        self.primary.set_options(options)
+    }
+
+    fn deleted_documents(&self) -> NitriteResult<Vec<Document>> {
+        self.check_open()?;
+        self.primary.deleted_documents()
+    }
+
+    fn purge_deleted(&self) -> NitriteResult<()> {
+        self.check_open()?;
+        self.primary.purge_deleted()
+    }
+
+    fn sweep_expired_fields(&self) -> NitriteResult<u64> {
+        self.check_open()?;
+        self.primary.sweep_expired_fields()
+    }
+
+    /// Redaction applies to the reads of this transaction only; the primary collection
+    /// keeps its own policy.
+    fn set_redaction_policy(&self, policy: Option<RedactionPolicy>) -> NitriteResult<()> {
+        self.check_open()?;
+        self.operations.set_redaction_policy(policy);
+        Ok(())
+    }
+
+    fn redaction_policy(&self) -> NitriteResult<Option<RedactionPolicy>> {
+        self.check_open()?;
+        Ok(self.operations.redaction_policy())
+    }
+
     fn name(&self) -> String {
         self.primary.name()
     }
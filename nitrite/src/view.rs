@@ -0,0 +1,573 @@
+use crate::{
+    collection::{
+        BulkOperation, CollectionEventInfo, CollectionEventListener, CollectionEvents, Document,
+        FindOptions, NitriteCollection, NitriteId,
+    },
+    common::{
+        content_hash_field, modified_field, revision_field, source_field, EventAware,
+        PersistentCollection, SubscriberRef,
+    },
+    errors::NitriteResult,
+    filter::{all, by_id, Filter},
+    index::IndexOptions,
+    DocumentCursor, Value, DOC_ID, INTERNAL_NAME_SEPARATOR, VIEW_PREFIX,
+};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Weak};
+
+/// Closure deriving the document of a view from a matching source document.
+pub type ViewTransform = Arc<dyn Fn(Document) -> NitriteResult<Option<Document>> + Send + Sync>;
+
+/// Definition of a view: the source collection, which of its documents the view holds and
+/// how each one is derived.
+///
+/// A source document is derived in three steps:
+///
+/// 1. it is skipped unless it matches the [`filter`](Self::filter);
+/// 2. it is reduced to the [`projection`](Self::projection) fields, if any;
+/// 3. the [`transform`](Self::transform) closure, if any, rewrites it or skips it by
+///    returning `None`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use nitrite::filter::field;
+/// use nitrite::view::ViewOptions;
+///
+/// let open_orders = db.view(
+///     "open_orders",
+///     ViewOptions::new("orders")
+///         .filter(field("status").eq("open"))
+///         .projection(vec!["customer", "total"]),
+/// )?;
+/// ```
+#[derive(Clone)]
+pub struct ViewOptions {
+    source: String,
+    filter: Filter,
+    projection: Option<Vec<String>>,
+    transform: Option<ViewTransform>,
+}
+
+impl ViewOptions {
+    /// Creates the definition of a view holding every document of the collection
+    /// `source`, as it is.
+    pub fn new(source: &str) -> Self {
+        ViewOptions {
+            source: source.to_string(),
+            filter: all(),
+            projection: None,
+            transform: None,
+        }
+    }
+
+    /// Sets the filter the source documents must match to be in the view.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the fields kept from the source documents. Embedded fields keep their
+    /// nesting; fields a document does not have are left out.
+    pub fn projection(mut self, fields: Vec<&str>) -> Self {
+        self.projection = Some(fields.into_iter().map(String::from).collect());
+        self
+    }
+
+    /// Sets a closure rewriting each projected document. Returning `None` leaves the
+    /// document out of the view.
+    ///
+    /// The closure runs on the thread writing the source document, so it should be cheap
+    /// and must not write to the source collection.
+    pub fn transform(
+        mut self,
+        transform: impl Fn(Document) -> NitriteResult<Option<Document>> + Send + Sync + 'static,
+    ) -> Self {
+        self.transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Returns the name of the source collection.
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the filter of the view.
+    pub fn get_filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// Returns the projected fields, or `None` if whole documents are kept.
+    pub fn get_projection(&self) -> Option<&[String]> {
+        self.projection.as_deref()
+    }
+}
+
+impl Debug for ViewOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ViewOptions")
+            .field("source", &self.source)
+            .field("filter", &self.filter.to_string())
+            .field("projection", &self.projection)
+            .field("transform", &self.transform.is_some())
+            .finish()
+    }
+}
+
+/// A read-only collection derived from a source collection and kept up to date as the
+/// source changes.
+///
+/// A view is opened with [`Nitrite::view`](crate::nitrite::Nitrite::view). Its documents
+/// are stored in an internal collection, under the ids of the source documents they are
+/// derived from, so they survive restarts and can be indexed and queried like any
+/// collection. Every write to the source updates the view right away, on the writing
+/// thread; the writes of a transaction update it when the transaction commits.
+///
+/// The definition of a view holds a filter and a closure, which cannot be stored, so it
+/// is not persisted: a view must be opened again after the database is reopened. Opening
+/// a view brings its documents in line with the source, which covers the writes made
+/// while it was not open and changes to its definition.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let big_orders = db.view(
+///     "big_orders",
+///     ViewOptions::new("orders")
+///         .filter(field("total").gt(1000))
+///         .transform(|mut order| {
+///             order.put("flagged", true)?;
+///             Ok(Some(order))
+///         }),
+/// )?;
+///
+/// db.collection("orders")?.insert(doc! { customer: "acme", total: 5000 })?;
+/// assert_eq!(big_orders.size()?, 1);
+/// ```
+#[derive(Clone)]
+pub struct View {
+    inner: Arc<ViewInner>,
+}
+
+impl View {
+    /// Opens the view over `source`, storing its documents in `data`, and brings them in
+    /// line with the source.
+    pub(crate) fn open(
+        name: &str,
+        source: NitriteCollection,
+        data: NitriteCollection,
+        options: ViewOptions,
+    ) -> NitriteResult<Self> {
+        let inner = Arc::new(ViewInner {
+            name: name.to_string(),
+            options,
+            source,
+            data,
+            subscriber: Mutex::new(None),
+            refresh_lock: Mutex::new(()),
+            touched: Mutex::new(None),
+        });
+
+        let weak: Weak<ViewInner> = Arc::downgrade(&inner);
+        let listener = CollectionEventListener::new(move |event: CollectionEventInfo| {
+            if let Some(inner) = weak.upgrade() {
+                inner.on_event(event);
+            }
+            Ok(())
+        });
+        *inner.subscriber.lock() = inner.source.subscribe(listener)?;
+
+        let view = View { inner };
+        view.refresh()?;
+        Ok(view)
+    }
+
+    /// Returns the name of the view.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the definition of the view.
+    pub fn options(&self) -> &ViewOptions {
+        &self.inner.options
+    }
+
+    /// Finds the documents of the view matching a filter.
+    pub fn find(&self, filter: Filter) -> NitriteResult<DocumentCursor> {
+        self.inner.data.find(filter)
+    }
+
+    /// Finds the documents of the view matching a filter, with sorting and pagination.
+    pub fn find_with_options(
+        &self,
+        filter: Filter,
+        find_options: &FindOptions,
+    ) -> NitriteResult<DocumentCursor> {
+        self.inner.data.find_with_options(filter, find_options)
+    }
+
+    /// Returns the document derived from the source document `id`, if it is in the view.
+    pub fn get_by_id(&self, id: &NitriteId) -> NitriteResult<Option<Document>> {
+        self.inner.data.get_by_id(id)
+    }
+
+    /// Returns the number of documents matching a filter.
+    pub fn count(&self, filter: Filter) -> NitriteResult<u64> {
+        self.inner.data.count(filter)
+    }
+
+    /// Returns the number of documents in the view.
+    pub fn size(&self) -> NitriteResult<u64> {
+        self.inner.data.size()
+    }
+
+    /// Creates an index on the documents of the view. Indexes are persisted with the view.
+    pub fn create_index(&self, field_names: Vec<&str>, index_options: &IndexOptions) -> NitriteResult<()> {
+        self.inner.data.create_index(field_names, index_options)
+    }
+
+    /// Returns `true` if the view has an index on the fields.
+    pub fn has_index(&self, field_names: Vec<&str>) -> NitriteResult<bool> {
+        self.inner.data.has_index(field_names)
+    }
+
+    /// Drops an index of the view.
+    pub fn drop_index(&self, field_names: Vec<&str>) -> NitriteResult<()> {
+        self.inner.data.drop_index(field_names)
+    }
+
+    /// Brings the documents of the view in line with the source: derives every source
+    /// document again and rewrites the view documents that differ.
+    ///
+    /// Views are kept up to date by the writes to the source, so this is only needed if
+    /// the definition depends on state outside the source documents, or after an error
+    /// was logged while updating the view.
+    pub fn refresh(&self) -> NitriteResult<()> {
+        self.inner.refresh()
+    }
+
+    /// Stops following the source. The documents of the view are kept.
+    pub(crate) fn detach(&self) -> NitriteResult<()> {
+        match self.inner.subscriber.lock().take() {
+            Some(subscriber) => self.inner.source.unsubscribe(subscriber),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Debug for View {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("View")
+            .field("name", &self.inner.name)
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
+
+struct ViewInner {
+    name: String,
+    options: ViewOptions,
+    source: NitriteCollection,
+    data: NitriteCollection,
+    subscriber: Mutex<Option<SubscriberRef>>,
+    refresh_lock: Mutex<()>,
+    /// Ids written to by the source while a refresh runs. The events of these documents
+    /// are newer than what the refresh read, so the refresh leaves them alone.
+    touched: Mutex<Option<HashSet<NitriteId>>>,
+}
+
+impl ViewInner {
+    fn on_event(&self, event: CollectionEventInfo) {
+        let id = match event.document_id() {
+            Some(id) => id,
+            None => return,
+        };
+
+        let mut touched = self.touched.lock();
+        if let Some(touched) = touched.as_mut() {
+            touched.insert(id);
+        }
+
+        let result = match (event.event_type(), event.item()) {
+            (CollectionEvents::Insert | CollectionEvents::Update, Some(Value::Document(document))) => {
+                self.derive(&document).and_then(|derived| self.apply(id, derived))
+            }
+            (CollectionEvents::Remove, _) => self.apply(id, None),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            // the source write is done, failing it would not undo it
+            log::error!("Failed to update view {} for document {}: {}", self.name, id, e);
+        }
+    }
+
+    fn refresh(&self) -> NitriteResult<()> {
+        let _guard = self.refresh_lock.lock();
+        *self.touched.lock() = Some(HashSet::new());
+        let result = self.reconcile();
+        *self.touched.lock() = None;
+        result
+    }
+
+    fn reconcile(&self) -> NitriteResult<()> {
+        let mut derived = HashMap::new();
+        for document in self.source.find(self.options.filter.clone())? {
+            let mut document = document?;
+            let id = document.id()?;
+            if let Some(view_document) = self.derive(&document)? {
+                derived.insert(id, view_document);
+            }
+        }
+
+        // the lock keeps the events of the source out until the view is in line
+        let touched = self.touched.lock();
+        let touched = touched.as_ref();
+        let is_touched = |id: &NitriteId| touched.is_some_and(|touched| touched.contains(id));
+
+        let mut stale = Vec::new();
+        for document in self.data.find(all())? {
+            let id = document?.id()?;
+            if !derived.contains_key(&id) && !is_touched(&id) {
+                stale.push(id);
+            }
+        }
+        for id in stale {
+            self.apply(id, None)?;
+        }
+        for (id, document) in derived {
+            if !is_touched(&id) {
+                self.apply(id, Some(document))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Derives the document of the view from a source document, or returns `None` if the
+    /// source document is not in the view.
+    fn derive(&self, document: &Document) -> NitriteResult<Option<Document>> {
+        if !self.options.filter.apply(document)? {
+            return Ok(None);
+        }
+
+        let projected = match &self.options.projection {
+            Some(fields) => {
+                let mut projected = Document::new();
+                for field in fields {
+                    let value = document.get(field)?;
+                    if !value.is_null() {
+                        projected.put(field.as_str(), value)?;
+                    }
+                }
+                projected
+            }
+            None => without_metadata(document.clone())?,
+        };
+
+        match &self.options.transform {
+            Some(transform) => transform(projected),
+            None => Ok(Some(projected)),
+        }
+    }
+
+    /// Stores the document of the view derived from the source document `id`, or removes
+    /// it if there is none. A document equal to the stored one is not written again.
+    fn apply(&self, id: NitriteId, document: Option<Document>) -> NitriteResult<()> {
+        let existing = self.data.get_by_id(&id)?;
+        let mut document = match document {
+            Some(document) => document,
+            None => {
+                if existing.is_some() {
+                    self.data.remove(by_id(id), true)?;
+                }
+                return Ok(());
+            }
+        };
+
+        document.put(DOC_ID, Value::NitriteId(id))?;
+        match existing {
+            Some(existing) => {
+                if without_metadata(existing)? == document {
+                    return Ok(());
+                }
+                self.data.bulk_write(vec![BulkOperation::ReplaceOne {
+                    filter: by_id(id),
+                    replacement: document,
+                    upsert: true,
+                }])?;
+            }
+            None => {
+                self.data.insert(document)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the name of the internal collection holding the documents of the view `name`.
+pub(crate) fn view_collection_name(name: &str) -> String {
+    format!("{}{}{}", VIEW_PREFIX, INTERNAL_NAME_SEPARATOR, name)
+}
+
+/// Removes the fields the collection maintains, keeping the id.
+fn without_metadata(mut document: Document) -> NitriteResult<Document> {
+    for field in [revision_field(), modified_field(), source_field(), content_hash_field()] {
+        document.remove(&field)?;
+    }
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::CollectionOptions;
+    use crate::doc;
+    use crate::filter::field;
+    use crate::nitrite::Nitrite;
+
+    fn setup_nitrite() -> Nitrite {
+        Nitrite::builder().open_or_create(None, None).unwrap()
+    }
+
+    #[test]
+    fn test_view_follows_source_writes() {
+        let db = setup_nitrite();
+        let orders = db.collection("orders").unwrap();
+        let view = db
+            .view(
+                "open_orders",
+                ViewOptions::new("orders")
+                    .filter(field("status").eq("open"))
+                    .projection(vec!["customer", "total"]),
+            )
+            .unwrap();
+        assert_eq!(view.size().unwrap(), 0);
+
+        let result = orders
+            .insert_many(vec![
+                doc! { customer: "acme", total: 10, status: "open" },
+                doc! { customer: "globex", total: 20, status: "closed" },
+            ])
+            .unwrap();
+        let acme = result.affected_nitrite_ids()[0];
+        assert_eq!(view.size().unwrap(), 1);
+
+        let document = view.get_by_id(&acme).unwrap().unwrap();
+        assert_eq!(document.get("customer").unwrap(), Value::from("acme"));
+        assert!(document.get("status").unwrap().is_null());
+
+        orders.update(field("customer").eq("globex"), &doc! { status: "open" }).unwrap();
+        assert_eq!(view.size().unwrap(), 2);
+
+        orders.update(field("customer").eq("acme"), &doc! { status: "closed" }).unwrap();
+        assert_eq!(view.size().unwrap(), 1);
+        assert!(view.get_by_id(&acme).unwrap().is_none());
+
+        orders.remove(field("customer").eq("globex"), false).unwrap();
+        assert_eq!(view.size().unwrap(), 0);
+        assert!(!db.list_collection_names().unwrap().iter().any(|name| name.contains("open_orders")));
+    }
+
+    #[test]
+    fn test_view_transform() {
+        let db = setup_nitrite();
+        let orders = db.collection("orders").unwrap();
+        orders.insert(doc! { customer: "acme", total: 10 }).unwrap();
+        orders.insert(doc! { customer: "globex", total: 5000 }).unwrap();
+
+        let view = db
+            .view(
+                "big_orders",
+                ViewOptions::new("orders").transform(|mut order| {
+                    match order.get("total")? {
+                        Value::I32(total) if total > 1000 => {
+                            order.put("flagged", true)?;
+                            Ok(Some(order))
+                        }
+                        _ => Ok(None),
+                    }
+                }),
+            )
+            .unwrap();
+
+        let documents: Vec<Document> = view.find(all()).unwrap().map(|d| d.unwrap()).collect();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].get("customer").unwrap(), Value::from("globex"));
+        assert_eq!(documents[0].get("flagged").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_view_drops_source_metadata() {
+        let db = setup_nitrite();
+        let orders = db
+            .collection_with_options("orders", CollectionOptions::new().content_hash(true))
+            .unwrap();
+        let view = db.view("all_orders", ViewOptions::new("orders")).unwrap();
+
+        let result = orders.insert(doc! { customer: "acme" }).unwrap();
+        let id = result.affected_nitrite_ids()[0];
+        assert!(orders.get_by_id(&id).unwrap().unwrap().contains_field(&content_hash_field()));
+
+        let document = view.get_by_id(&id).unwrap().unwrap();
+        assert!(!document.contains_field(&content_hash_field()));
+        assert!(!document.contains_field(&source_field()));
+        assert_eq!(document.get("customer").unwrap(), Value::from("acme"));
+    }
+
+    #[test]
+    fn test_reopening_view_reconciles_definition() {
+        let db = setup_nitrite();
+        let orders = db.collection("orders").unwrap();
+        orders.insert(doc! { customer: "acme", status: "open" }).unwrap();
+        orders.insert(doc! { customer: "globex", status: "closed" }).unwrap();
+
+        let view = db.view("orders_view", ViewOptions::new("orders")).unwrap();
+        assert_eq!(view.size().unwrap(), 2);
+
+        let view = db
+            .view("orders_view", ViewOptions::new("orders").filter(field("status").eq("open")))
+            .unwrap();
+        assert_eq!(view.size().unwrap(), 1);
+
+        // only the latest definition follows the source
+        orders.insert(doc! { customer: "initech", status: "closed" }).unwrap();
+        assert_eq!(view.size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_view_index_and_query() {
+        let db = setup_nitrite();
+        let orders = db.collection("orders").unwrap();
+        let view = db.view("by_customer", ViewOptions::new("orders")).unwrap();
+        view.create_index(vec!["customer"], &crate::index::non_unique_index()).unwrap();
+        orders.insert(doc! { customer: "acme" }).unwrap();
+        orders.insert(doc! { customer: "globex" }).unwrap();
+
+        assert!(view.has_index(vec!["customer"]).unwrap());
+        assert_eq!(view.count(field("customer").eq("acme")).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_drop_view() {
+        let db = setup_nitrite();
+        let orders = db.collection("orders").unwrap();
+        orders.insert(doc! { customer: "acme" }).unwrap();
+        let view = db.view("all_orders", ViewOptions::new("orders")).unwrap();
+        assert_eq!(view.size().unwrap(), 1);
+
+        db.drop_view("all_orders").unwrap();
+        db.drop_view("all_orders").unwrap();
+        orders.insert(doc! { customer: "globex" }).unwrap();
+
+        let view = db.view("all_orders", ViewOptions::new("orders").filter(field("customer").eq("globex"))).unwrap();
+        assert_eq!(view.size().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_invalid_view_name() {
+        let db = setup_nitrite();
+        assert!(db.view("", ViewOptions::new("orders")).is_err());
+        assert!(db.view("a|b", ViewOptions::new("orders")).is_err());
+    }
+}
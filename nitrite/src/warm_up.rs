@@ -0,0 +1,257 @@
+use crate::{
+    collection::operation::read_index_descriptors,
+    errors::{ErrorKind, NitriteError, NitriteResult},
+    index::{IndexDescriptor, NitriteIndexerProvider},
+    nitrite::Nitrite,
+    repository_name,
+    store::{NitriteMapProvider, NitriteStoreProvider},
+    PersistentCollection,
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// What a finished warm-up loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WarmUpReport {
+    /// Number of collections that were warmed up.
+    pub collections: usize,
+    /// Number of documents read.
+    pub documents: u64,
+    /// Number of indexes loaded, including spatial, full-text and other plugin indexes.
+    pub indexes: usize,
+    /// Time the warm-up took.
+    pub elapsed: Duration,
+}
+
+/// Completion handle of a warm-up started with [`Nitrite::warm_up`].
+///
+/// The warm-up runs on a background thread. Dropping the handle does not stop it; call
+/// [`WarmUpHandle::wait`] to block until it has finished, for example before reporting a
+/// service as ready.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let handle = db.warm_up(["orders", "customers"])?;
+/// // ... start accepting connections on a best-effort basis ...
+/// let report = handle.wait()?;
+/// log::info!("warmed up {} documents in {:?}", report.documents, report.elapsed);
+/// ```
+pub struct WarmUpHandle {
+    handle: JoinHandle<NitriteResult<WarmUpReport>>,
+}
+
+impl WarmUpHandle {
+    /// Returns `true` once the warm-up has finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Blocks until the warm-up has finished and returns what it loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error that stopped the warm-up, for example when a collection is
+    /// dropped or the database is closed while it runs.
+    pub fn wait(self) -> NitriteResult<WarmUpReport> {
+        match self.handle.join() {
+            Ok(result) => result,
+            Err(_) => {
+                log::error!("Warm-up thread panicked");
+                Err(NitriteError::new(
+                    "Warm-up thread panicked",
+                    ErrorKind::InternalError,
+                ))
+            }
+        }
+    }
+}
+
+/// Load state of one index, as reported by [`Nitrite::index_load_status`].
+#[derive(Debug, Clone)]
+pub struct IndexLoadState {
+    /// The index, including the name of its collection or repository.
+    pub index_descriptor: IndexDescriptor,
+    /// Whether the index has been opened, by a query, a write or a warm-up.
+    pub loaded: bool,
+}
+
+/// Which indexes of a database are loaded and which are still to be loaded.
+///
+/// Indexes are opened on their first use, so right after a large database is opened
+/// most of them are pending. A warm-up loads them ahead of the first queries.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let handle = db.warm_up(db.list_collection_names()?)?;
+/// while !handle.is_finished() {
+///     let status = db.index_load_status()?;
+///     log::info!("{} of {} indexes loaded", status.loaded_count(), status.indexes.len());
+///     std::thread::sleep(Duration::from_secs(1));
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IndexLoadStatus {
+    /// Every index of the database, ordered by collection.
+    pub indexes: Vec<IndexLoadState>,
+}
+
+impl IndexLoadStatus {
+    /// Returns the number of loaded indexes.
+    pub fn loaded_count(&self) -> usize {
+        self.indexes.iter().filter(|state| state.loaded).count()
+    }
+
+    /// Returns the indexes still to be loaded.
+    pub fn pending(&self) -> impl Iterator<Item = &IndexDescriptor> {
+        self.indexes
+            .iter()
+            .filter(|state| !state.loaded)
+            .map(|state| &state.index_descriptor)
+    }
+
+    /// Returns `true` if every index is loaded.
+    pub fn is_complete(&self) -> bool {
+        self.indexes.iter().all(|state| state.loaded)
+    }
+}
+
+pub(crate) fn index_load_status(db: &Nitrite) -> NitriteResult<IndexLoadStatus> {
+    let store = db.store();
+    let config = db.config();
+
+    let mut names: Vec<String> = db.list_collection_names()?.into_iter().collect();
+    names.extend(db.list_repositories()?);
+    for (key, entity_names) in db.list_keyed_repositories()? {
+        for entity_name in entity_names {
+            names.push(repository_name(&entity_name, Some(&key))?);
+        }
+    }
+    names.sort();
+
+    let mut status = IndexLoadStatus::default();
+    for name in names {
+        // read from the index metadata, so that pending collections stay unopened
+        for index_descriptor in read_index_descriptors(&name, &store)? {
+            let indexer = config.find_indexer(&index_descriptor.index_type())?;
+            let loaded = indexer.is_loaded(&index_descriptor, &config)?;
+            status.indexes.push(IndexLoadState {
+                index_descriptor,
+                loaded,
+            });
+        }
+    }
+    Ok(status)
+}
+
+pub(crate) fn start_warm_up(db: &Nitrite, collections: Vec<String>) -> NitriteResult<WarmUpHandle> {
+    for name in &collections {
+        if !db.has_collection(name)? {
+            log::error!("Collection {} does not exist", name);
+            return Err(NitriteError::new(
+                &format!("Collection {} does not exist", name),
+                ErrorKind::NotFound,
+            ));
+        }
+    }
+
+    let db = db.clone();
+    let handle = thread::Builder::new()
+        .name("nitrite-warm-up".to_string())
+        .spawn(move || warm_up_collections(&db, &collections))?;
+    Ok(WarmUpHandle { handle })
+}
+
+fn warm_up_collections(db: &Nitrite, collections: &[String]) -> NitriteResult<WarmUpReport> {
+    let started = Instant::now();
+    let store = db.store();
+    let config = db.config();
+    let mut report = WarmUpReport::default();
+
+    for name in collections {
+        // reading every document pulls the collection's pages into the store caches
+        for entry in store.open_map(name)?.entries()? {
+            entry?;
+            report.documents += 1;
+        }
+
+        for descriptor in db.collection(name)?.list_indexes()? {
+            let indexer = config.find_indexer(&descriptor.index_type())?;
+            indexer.warm_up(&descriptor, &config)?;
+            report.indexes += 1;
+        }
+        report.collections += 1;
+    }
+
+    report.elapsed = started.elapsed();
+    log::info!(
+        "Warmed up {} collection(s): {} document(s), {} index(es) in {:?}",
+        report.collections,
+        report.documents,
+        report.indexes,
+        report.elapsed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::NitriteCollectionProvider;
+    use crate::doc;
+    use crate::filter::field;
+    use crate::index::{non_unique_index, unique_index};
+
+    #[test]
+    fn test_warm_up_collections() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let users = db.collection("users").unwrap();
+        users.create_index(vec!["email"], &unique_index()).unwrap();
+        users.create_index(vec!["age"], &non_unique_index()).unwrap();
+        for i in 0..20 {
+            users
+                .insert(doc! { email: (format!("u{}@x.io", i)), age: (i % 5) })
+                .unwrap();
+        }
+        db.collection("events").unwrap().insert(doc! { n: 1 }).unwrap();
+
+        let report = db.warm_up(["users", "events"]).unwrap().wait().unwrap();
+        assert_eq!(report.collections, 2);
+        assert_eq!(report.documents, 21);
+        assert_eq!(report.indexes, 2);
+        assert_eq!(users.find(field("age").eq(3)).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn test_index_load_status() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        assert!(db.index_load_status().unwrap().indexes.is_empty());
+
+        let users = db.collection("users").unwrap();
+        users.create_index(vec!["email"], &unique_index()).unwrap();
+        users.create_index(vec!["age"], &non_unique_index()).unwrap();
+        users.insert(doc! { email: "a@x.io", age: 1 }).unwrap();
+        db.collection("events").unwrap().insert(doc! { n: 1 }).unwrap();
+
+        let status = db.index_load_status().unwrap();
+        assert_eq!(status.indexes.len(), 2);
+        assert!(status
+            .indexes
+            .iter()
+            .all(|state| state.index_descriptor.collection_name() == "users"));
+        assert_eq!(status.loaded_count(), 2);
+        assert!(status.is_complete());
+        assert_eq!(status.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_warm_up_unknown_collection() {
+        let db = Nitrite::builder().open_or_create(None, None).unwrap();
+        let result = db.warm_up(["missing"]);
+        assert!(matches!(
+            result.err().map(|e| e.kind().clone()),
+            Some(ErrorKind::NotFound)
+        ));
+    }
+}